@@ -0,0 +1,3345 @@
+//! Matching Engine
+//!
+//! High-performance order matching engine with concurrent access support.
+//! Manages multiple orderbooks for different trading pairs.
+//!
+//! # Prediction Market Matching
+//!
+//! Supports three types of matching:
+//! - **Normal**: Same share type, opposite sides (Yes buy vs Yes sell)
+//! - **Mint**: Two buys for complementary shares (Yes buy + No buy → new shares)
+//! - **Merge**: Two sells for complementary shares (Yes sell + No sell → collateral)
+//!
+//! In a categorical market (see [`MatchingEngine::register_outcome_set`])
+//! each outcome trades as its own Yes book, and Mint/Merge extend to
+//! complete sets: a buy mints with buys of every other outcome when their
+//! prices sum to at least 1, and a sell merges with sells of every other
+//! outcome when they sum to at most 1.
+//!
+//! Every match type prices the same way: makers trade at their resting
+//! price and the taker keeps any price improvement. In Normal matching the
+//! taker trades at the maker's price; in Mint/Merge at one less the
+//! complement maker's price; in a set fill at one less the sum of the leg
+//! makers' prices. A taker never trades worse than its limit.
+
+use crate::history::HistoryManager;
+use crate::metrics;
+use crate::orderbook::Orderbook;
+use crate::precision;
+use crate::price_guard::{MarketHalt, PriceGuard, PriceGuardConfig};
+use crate::types::*;
+use crate::ShareType;
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Collateral notional from which a trade is flagged as a block trade
+const DEFAULT_BLOCK_TRADE_NOTIONAL: Decimal = Decimal::from_parts(10_000, 0, 0, false, 0);
+
+/// The main matching engine
+pub struct MatchingEngine {
+    /// Map of symbol to orderbook (concurrent access)
+    orderbooks: DashMap<String, Arc<Orderbook>>,
+
+    /// Trade event broadcaster
+    trade_sender: broadcast::Sender<TradeEvent>,
+
+    /// Orderbook update broadcaster
+    orderbook_sender: broadcast::Sender<OrderbookUpdate>,
+
+    /// Orderbook delta broadcaster
+    delta_sender: broadcast::Sender<OrderbookDelta>,
+
+    /// Levels and delta sequence last published per book. Kept after a book
+    /// is dropped so its sequence never starts over.
+    published_depth: DashMap<String, SequencedDepth>,
+
+    /// Trade and orderbook update sequence numbers per market key. Numbered
+    /// and sent under the key's entry, so receivers see them in order.
+    market_sequences: DashMap<String, MarketSequence>,
+
+    /// Order lifecycle event broadcaster
+    order_sender: broadcast::Sender<OrderEvent>,
+
+    /// History manager for trade/order records
+    history: Arc<HistoryManager>,
+
+    /// Fee configuration
+    fee_config: FeeConfig,
+
+    /// Supported symbols
+    symbols: Vec<String>,
+
+    /// Whether Mint/Merge matching against complement books is enabled
+    complement_matching: AtomicBool,
+
+    /// Outcomes of each categorical market, for set matching across them
+    outcome_sets: DashMap<Uuid, Arc<Vec<Uuid>>>,
+
+    /// Last trade sequence number handed out
+    trade_sequence: AtomicU64,
+
+    /// Trades with at least this notional are flagged as block trades
+    block_trade_notional: Decimal,
+
+    /// Cancel-all-after deadlines (milliseconds) per lowercased user
+    cancel_all_deadlines: DashMap<String, i64>,
+
+    /// Good-til-date expiries (milliseconds) of resting orders, with their
+    /// book. Orders that leave the book earlier are dropped when due.
+    order_expiries: DashMap<Uuid, (String, i64)>,
+
+    /// Changes to resting orders for the order journal, once attached
+    journal: OnceLock<mpsc::UnboundedSender<OrderEvent>>,
+
+    /// Changes sent to the journal so far
+    journaled: AtomicU64,
+
+    /// Price bands, last prices and circuit breaker halts
+    price_guard: PriceGuard,
+
+    /// Circuit breaker halt broadcaster
+    halt_sender: broadcast::Sender<MarketHalt>,
+}
+
+impl MatchingEngine {
+    /// Create a new matching engine
+    pub fn new() -> Self {
+        // Start with empty orderbooks for prediction markets
+        // Orderbooks are created dynamically when orders are submitted
+        Self::with_symbols(vec![])
+    }
+
+    /// Create with specific symbols
+    pub fn with_symbols(symbols: Vec<String>) -> Self {
+        let (trade_sender, _) = broadcast::channel(10000);
+        let (orderbook_sender, _) = broadcast::channel(10000);
+        let (delta_sender, _) = broadcast::channel(10000);
+        let (order_sender, _) = broadcast::channel(10000);
+        let (halt_sender, _) = broadcast::channel(100);
+        let orderbooks = DashMap::new();
+
+        // Initialize orderbooks for all symbols
+        for symbol in &symbols {
+            orderbooks.insert(symbol.clone(), Arc::new(Orderbook::new(symbol.clone())));
+        }
+
+        info!("MatchingEngine initialized with {} symbols", symbols.len());
+
+        Self {
+            orderbooks,
+            trade_sender,
+            orderbook_sender,
+            delta_sender,
+            published_depth: DashMap::new(),
+            market_sequences: DashMap::new(),
+            order_sender,
+            history: Arc::new(HistoryManager::new()),
+            fee_config: FeeConfig::default(),
+            symbols,
+            complement_matching: AtomicBool::new(true),
+            outcome_sets: DashMap::new(),
+            trade_sequence: AtomicU64::new(0),
+            block_trade_notional: DEFAULT_BLOCK_TRADE_NOTIONAL,
+            cancel_all_deadlines: DashMap::new(),
+            order_expiries: DashMap::new(),
+            journal: OnceLock::new(),
+            journaled: AtomicU64::new(0),
+            price_guard: PriceGuard::default(),
+            halt_sender,
+        }
+    }
+
+    /// Create with custom fee config
+    pub fn with_fee_config(mut self, fee_config: FeeConfig) -> Self {
+        self.fee_config = fee_config;
+        self
+    }
+
+    /// Set the notional from which trades are flagged as block trades
+    pub fn with_block_trade_threshold(mut self, notional: Decimal) -> Self {
+        self.block_trade_notional = notional;
+        self
+    }
+
+    /// Reject limit orders outside the price band and halt markets whose
+    /// price moves too fast (see [`PriceGuardConfig`])
+    pub fn with_price_guard(mut self, config: PriceGuardConfig) -> Self {
+        self.price_guard = PriceGuard::new(config);
+        self
+    }
+
+    /// Continue trade sequence numbers after `last` (e.g. the highest
+    /// persisted sequence), so numbering stays monotonic across restarts
+    pub fn seed_trade_sequence(&self, last: u64) {
+        self.trade_sequence.fetch_max(last, Ordering::SeqCst);
+    }
+
+    /// Highest trade sequence number handed out so far
+    pub fn last_trade_sequence(&self) -> u64 {
+        self.trade_sequence.load(Ordering::SeqCst)
+    }
+
+    /// Enable or disable Mint/Merge matching (enabled by default).
+    /// Orders already resting in the books are unaffected.
+    pub fn set_complement_matching(&self, enabled: bool) {
+        if self.complement_matching.swap(enabled, Ordering::Relaxed) != enabled {
+            info!("Complement (Mint/Merge) matching {}", if enabled { "enabled" } else { "disabled" });
+        }
+    }
+
+    /// Whether Mint/Merge matching is enabled
+    pub fn complement_matching_enabled(&self) -> bool {
+        self.complement_matching.load(Ordering::Relaxed)
+    }
+
+    /// Register the outcomes of a categorical market. Each outcome trades
+    /// as its own Yes book, and one Yes share of every outcome is a
+    /// complete set worth 1: with complement matching enabled, a buy of
+    /// one outcome also mints sets with buys of all the others, and a sell
+    /// merges sets with sells of all the others (see [`SetFill`]).
+    pub fn register_outcome_set(&self, market_id: Uuid, outcome_ids: Vec<Uuid>) -> Result<(), MatchingError> {
+        let mut distinct = outcome_ids.clone();
+        distinct.sort();
+        distinct.dedup();
+        if distinct.len() < 2 || distinct.len() != outcome_ids.len() {
+            return Err(MatchingError::OutcomeNotFound(format!(
+                "market {} needs at least two distinct outcomes",
+                market_id
+            )));
+        }
+        info!("Registered categorical market {} with {} outcomes", market_id, outcome_ids.len());
+        self.outcome_sets.insert(market_id, Arc::new(outcome_ids));
+        Ok(())
+    }
+
+    /// Outcomes registered for a categorical market
+    pub fn outcome_set(&self, market_id: Uuid) -> Option<Arc<Vec<Uuid>>> {
+        self.outcome_sets.get(&market_id).map(|set| Arc::clone(set.value()))
+    }
+
+    /// Get supported symbols
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    /// Check if a symbol is supported
+    pub fn is_valid_symbol(&self, symbol: &str) -> bool {
+        self.orderbooks.contains_key(symbol)
+    }
+
+    /// Add a new symbol/market
+    pub fn add_symbol(&mut self, symbol: String) {
+        if !self.orderbooks.contains_key(&symbol) {
+            self.orderbooks.insert(symbol.clone(), Arc::new(Orderbook::new(symbol.clone())));
+            self.symbols.push(symbol.clone());
+            info!("Added new symbol: {}", symbol);
+        }
+    }
+
+    /// Get trade event receiver
+    pub fn subscribe_trades(&self) -> broadcast::Receiver<TradeEvent> {
+        self.trade_sender.subscribe()
+    }
+
+    /// Get orderbook update receiver
+    pub fn subscribe_orderbook(&self) -> broadcast::Receiver<OrderbookUpdate> {
+        self.orderbook_sender.subscribe()
+    }
+
+    /// Get orderbook delta receiver
+    pub fn subscribe_orderbook_deltas(&self) -> broadcast::Receiver<OrderbookDelta> {
+        self.delta_sender.subscribe()
+    }
+
+    /// Get circuit breaker halt receiver
+    pub fn subscribe_halts(&self) -> broadcast::Receiver<MarketHalt> {
+        self.halt_sender.subscribe()
+    }
+
+    /// Every level of a book as of its latest delta, to apply later deltas
+    /// to. A book without deltas yet starts its sequence here at 0.
+    pub fn sequenced_depth(&self, symbol: &str) -> Result<SequencedDepth, MatchingError> {
+        if let Some(depth) = self.published_depth.get(symbol) {
+            return Ok(depth.clone());
+        }
+        let orderbook = self.get_orderbook_ref(symbol)
+            .ok_or_else(|| MatchingError::SymbolNotFound(symbol.to_string()))?;
+        let depth = self.published_depth.entry(symbol.to_string()).or_insert_with(|| SequencedDepth {
+            sequence: 0,
+            bids: orderbook.bid_levels(),
+            asks: orderbook.ask_levels(),
+        });
+        Ok(depth.clone())
+    }
+
+    /// Get order lifecycle event receiver. Every state change of every
+    /// order is published here, including makers filled by other users.
+    pub fn subscribe_orders(&self) -> broadcast::Receiver<OrderEvent> {
+        self.order_sender.subscribe()
+    }
+
+    /// Attach the order journal. From now on every change to a resting
+    /// order (rested, filled, reduced, cancelled, amended, expired) is sent
+    /// on the returned channel in the order it was made, after it was made:
+    /// the journal trails the book. The channel is unbounded so matching
+    /// never waits on the journal and no change is dropped. Only the first
+    /// call gets a receiver.
+    pub fn attach_journal(&self) -> Option<mpsc::UnboundedReceiver<OrderEvent>> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.journal.set(sender).ok()?;
+        Some(receiver)
+    }
+
+    /// Number of changes sent to the journal so far
+    pub fn journaled(&self) -> u64 {
+        self.journaled.load(Ordering::Acquire)
+    }
+
+    fn journal(&self, event: &OrderEvent) {
+        if let Some(journal) = self.journal.get() {
+            if journal.send(event.clone()).is_ok() {
+                self.journaled.fetch_add(1, Ordering::AcqRel);
+            }
+        }
+    }
+
+    fn emit_order_event(&self, event: OrderEvent) {
+        debug!(
+            "Order event: id={}, kind={:?}, status={}, remaining={}",
+            event.order_id, event.kind, event.status, event.remaining_amount
+        );
+        if event.changes_book() {
+            self.journal(&event);
+        }
+        let _ = self.order_sender.send(event);
+    }
+
+    /// Broadcast orderbook update for a symbol
+    fn broadcast_orderbook_update(&self, symbol: &str) {
+        if let Some(orderbook) = self.orderbooks.get(symbol) {
+            let mut sequence = self.market_sequences.entry(symbol.to_string()).or_default();
+            sequence.orderbook += 1;
+            // Top 20 levels; receivers clone the update, which only bumps refcounts
+            let depth = orderbook.cached_depth();
+            let update = OrderbookUpdate {
+                symbol: symbol.to_string(),
+                bids: depth.bids.clone(),
+                asks: depth.asks.clone(),
+                sequence: sequence.orderbook,
+                timestamp: chrono::Utc::now().timestamp_millis(),
+            };
+            let _ = self.orderbook_sender.send(update);
+            drop(sequence);
+            self.publish_delta(symbol, Some(&orderbook));
+        }
+    }
+
+    /// Number `event` within its market key and broadcast it, returning the
+    /// number of receivers (`None` without any)
+    fn send_trade(&self, event: &mut TradeEvent) -> Option<usize> {
+        let mut sequence = self.market_sequences.entry(event.symbol.clone()).or_default();
+        sequence.trade += 1;
+        event.market_sequence = sequence.trade;
+        self.trade_sender.send(event.clone()).ok()
+    }
+
+    /// Last trade and orderbook update sequence numbers broadcast for
+    /// `symbol`. A book snapshot taken afterwards is at least as new as
+    /// the update numbered here, so later updates continue it.
+    pub fn market_sequence(&self, symbol: &str) -> MarketSequence {
+        self.market_sequences.get(symbol).map(|s| *s).unwrap_or_default()
+    }
+
+    /// Broadcast the levels of `book` (none once dropped) that differ from
+    /// the last published ones as the book's next delta. Levels are read
+    /// under the entry lock, so concurrent publishers can't sequence an
+    /// older read after a newer one.
+    fn publish_delta(&self, symbol: &str, book: Option<&Orderbook>) {
+        let mut published = self.published_depth.entry(symbol.to_string()).or_default();
+        let (bids, asks) = book.map(|b| (b.bid_levels(), b.ask_levels())).unwrap_or_default();
+        let changes = published.diff(&bids, &asks);
+        if changes.is_empty() {
+            return;
+        }
+        published.sequence += 1;
+        published.bids = bids;
+        published.asks = asks;
+        let _ = self.delta_sender.send(OrderbookDelta {
+            symbol: symbol.to_string(),
+            sequence: published.sequence,
+            changes: changes.into(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        });
+    }
+
+    /// Get history manager
+    pub fn history(&self) -> Arc<HistoryManager> {
+        Arc::clone(&self.history)
+    }
+
+    /// Get orderbook for a symbol
+    pub fn get_orderbook_ref(&self, symbol: &str) -> Option<Arc<Orderbook>> {
+        self.orderbooks.get(symbol).map(|ob| Arc::clone(ob.value()))
+    }
+
+    // ========================================================================
+    // Complement Orderbook (for Mint/Merge matching)
+    // ========================================================================
+
+    /// Parse market key into components
+    fn parse_market_key(market_key: &str) -> Option<(Uuid, Uuid, ShareType)> {
+        OrderbookSnapshot::parse_market_key(market_key)
+    }
+
+    /// Get the complement market key (Yes ↔ No), in the same namespace
+    fn get_complement_market_key(market_key: &str) -> Option<String> {
+        let (market_id, outcome_id, share_type) = Self::parse_market_key(market_key)?;
+        let (namespace, _) = OrderbookSnapshot::split_namespace(market_key);
+        Some(OrderbookSnapshot::market_key(namespace, market_id, outcome_id, share_type.complement()))
+    }
+
+    /// Get or create the complement orderbook
+    fn get_or_create_complement_orderbook(&self, market_key: &str) -> Option<Arc<Orderbook>> {
+        let complement_key = Self::get_complement_market_key(market_key)?;
+        let orderbook = self.orderbooks
+            .entry(complement_key.clone())
+            .or_insert_with(|| Arc::new(Orderbook::new(complement_key)))
+            .clone();
+        Some(orderbook)
+    }
+
+    /// Try Mint matching: match taker buy order against complement orderbook's buy orders
+    ///
+    /// Mint matching occurs when:
+    /// - Taker wants to buy Yes shares at price P_yes
+    /// - Maker wants to buy No shares at price P_no
+    /// - P_yes + P_no >= 1.0 (combined willingness to pay covers minting cost)
+    ///
+    /// Example: Taker buys 100 Yes @ 0.65, Maker buys 100 No @ 0.40
+    /// Combined: 0.65 + 0.40 = 1.05 >= 1.0 ✓
+    /// Result: Mint 100 (Yes, No) pairs, taker gets Yes @ 0.60, maker gets No @ 0.40
+    #[allow(clippy::too_many_arguments)]
+    fn try_mint_match(
+        &self,
+        taker_order_id: Uuid,
+        taker_address: &str,
+        taker_share_type: ShareType,
+        taker_market_key: &str,
+        complement_orderbook: &Orderbook,
+        taker_price: Decimal,
+        remaining_amount: Decimal,
+        stp: SelfTradePrevention,
+    ) -> BookMatch {
+        let mut result = BookMatch::new(remaining_amount);
+        let complement_price = Decimal::ONE - taker_price;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        // Parse market_key for trade records
+        let (market_id, outcome_id, _) = Self::parse_market_key(taker_market_key)
+            .unwrap_or((Uuid::nil(), Uuid::nil(), ShareType::Yes));
+
+        // Look for buy orders in complement orderbook that can mint with us
+        // We need: taker_price + maker_price >= 1.0
+        // So maker_price >= 1.0 - taker_price = complement_price
+        //
+        // In the complement orderbook, buy orders are stored in bids
+        // We look for bids at price >= complement_price
+        let matching_orders = complement_orderbook.get_matching_buy_orders(complement_price);
+
+        for maker_order in matching_orders {
+            if !result.open() {
+                break;
+            }
+            if maker_order.user_address.eq_ignore_ascii_case(taker_address) {
+                complement_orderbook.prevent_self_trade(&maker_order, stp, &mut result);
+                continue;
+            }
+
+            // Calculate trade amount
+            let trade_amount = result.remaining.min(maker_order.remaining_amount);
+
+            // The maker trades the complement at its own price, the taker
+            // at the rest of the pair's 1
+            let trade_price = Decimal::ONE - maker_order.price;
+            let taker_fee = self.fee_config.calculate_taker_fee(trade_price, trade_amount);
+            let maker_fee = self.fee_config.calculate_maker_fee(maker_order.price, trade_amount);
+
+            let trade = TradeExecution {
+                trade_id: Uuid::new_v4(),
+                market_id,
+                outcome_id,
+                share_type: taker_share_type,
+                match_type: MatchType::Mint,
+                maker_order_id: maker_order.id,
+                taker_order_id,
+                maker_address: maker_order.user_address.clone(),
+                price: trade_price,
+                amount: trade_amount,
+                maker_fee,
+                taker_fee,
+                timestamp: now,
+                sequence: 0,
+                is_block_trade: false,
+            };
+
+            result.trades.push(trade);
+            result.remaining -= trade_amount;
+
+            // Update maker order in complement orderbook
+            complement_orderbook.fill_order(maker_order.id, trade_amount);
+            let mut maker = maker_order.clone();
+            maker.remaining_amount -= trade_amount;
+            result.makers.push(maker);
+
+            debug!(
+                "🔨 MINT match: {} {} @ {:.4} + {} No @ {:.4} = {} pairs",
+                trade_amount, taker_share_type, trade_price,
+                trade_amount, maker_order.price, trade_amount
+            );
+        }
+
+        result
+    }
+
+    /// Try Merge matching: match taker sell order against complement orderbook's sell orders
+    ///
+    /// Merge matching occurs when:
+    /// - Taker wants to sell Yes shares at price P_yes
+    /// - Maker wants to sell No shares at price P_no
+    /// - P_yes + P_no <= 1.0 (combined receive <= redemption value)
+    ///
+    /// Example: Taker sells 100 Yes @ 0.55, Maker sells 100 No @ 0.40
+    /// Combined: 0.55 + 0.40 = 0.95 <= 1.0 ✓
+    /// Result: Merge 100 (Yes, No) pairs → redeem 100 USDC, taker receives
+    /// 0.60 per share, maker 0.40
+    #[allow(clippy::too_many_arguments)]
+    fn try_merge_match(
+        &self,
+        taker_order_id: Uuid,
+        taker_address: &str,
+        taker_share_type: ShareType,
+        taker_market_key: &str,
+        complement_orderbook: &Orderbook,
+        taker_price: Decimal,
+        remaining_amount: Decimal,
+        stp: SelfTradePrevention,
+    ) -> BookMatch {
+        let mut result = BookMatch::new(remaining_amount);
+        let complement_price = Decimal::ONE - taker_price;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        // Parse market_key for trade records
+        let (market_id, outcome_id, _) = Self::parse_market_key(taker_market_key)
+            .unwrap_or((Uuid::nil(), Uuid::nil(), ShareType::Yes));
+
+        // Look for sell orders in complement orderbook that can merge with us
+        // We need: taker_price + maker_price <= 1.0
+        // So maker_price <= 1.0 - taker_price = complement_price
+        //
+        // In the complement orderbook, sell orders are stored in asks
+        // We look for asks at price <= complement_price
+        let matching_orders = complement_orderbook.get_matching_sell_orders(complement_price);
+
+        for maker_order in matching_orders {
+            if !result.open() {
+                break;
+            }
+            if maker_order.user_address.eq_ignore_ascii_case(taker_address) {
+                complement_orderbook.prevent_self_trade(&maker_order, stp, &mut result);
+                continue;
+            }
+
+            // Calculate trade amount
+            let trade_amount = result.remaining.min(maker_order.remaining_amount);
+
+            // The maker trades the complement at its own price, the taker
+            // at the rest of the pair's 1
+            let trade_price = Decimal::ONE - maker_order.price;
+            let taker_fee = self.fee_config.calculate_taker_fee(trade_price, trade_amount);
+            let maker_fee = self.fee_config.calculate_maker_fee(maker_order.price, trade_amount);
+
+            let trade = TradeExecution {
+                trade_id: Uuid::new_v4(),
+                market_id,
+                outcome_id,
+                share_type: taker_share_type,
+                match_type: MatchType::Merge,
+                maker_order_id: maker_order.id,
+                taker_order_id,
+                maker_address: maker_order.user_address.clone(),
+                price: trade_price,
+                amount: trade_amount,
+                maker_fee,
+                taker_fee,
+                timestamp: now,
+                sequence: 0,
+                is_block_trade: false,
+            };
+
+            result.trades.push(trade);
+            result.remaining -= trade_amount;
+
+            // Update maker order in complement orderbook
+            complement_orderbook.fill_order(maker_order.id, trade_amount);
+            let mut maker = maker_order.clone();
+            maker.remaining_amount -= trade_amount;
+            result.makers.push(maker);
+
+            debug!(
+                "🔄 MERGE match: {} {} @ {:.4} + {} No @ {:.4} → {} USDC",
+                trade_amount, taker_share_type, trade_price,
+                trade_amount, maker_order.price, trade_amount
+            );
+        }
+
+        result
+    }
+
+    /// Yes books of the other outcomes of `market_key`'s categorical
+    /// market, in `market_key`'s namespace. `None` unless the key is a Yes
+    /// book of a registered market and every other outcome has a book.
+    fn set_legs(&self, market_key: &str) -> Option<Vec<(Uuid, String, Arc<Orderbook>)>> {
+        let (market_id, outcome_id, share_type) = Self::parse_market_key(market_key)?;
+        if share_type != ShareType::Yes {
+            return None;
+        }
+        let outcomes = self.outcome_set(market_id)?;
+        if !outcomes.contains(&outcome_id) {
+            return None;
+        }
+        let (namespace, _) = OrderbookSnapshot::split_namespace(market_key);
+        outcomes
+            .iter()
+            .filter(|&&other| other != outcome_id)
+            .map(|&other| {
+                let key = OrderbookSnapshot::market_key(namespace, market_id, other, ShareType::Yes);
+                let book = self.get_orderbook_ref(&key)?;
+                Some((other, key, book))
+            })
+            .collect()
+    }
+
+    /// Try set matching in a categorical market: fill the taker together
+    /// with the order first in line on every other outcome's Yes book,
+    /// one complete set at a time
+    ///
+    /// A buy mints sets against the best bids `p_j` of the other outcomes
+    /// while `taker_price + Σ p_j >= 1`; a sell merges sets against their
+    /// best asks `a_j` while `taker_price + Σ a_j <= 1`. Makers trade at
+    /// their own prices and the taker at the rest of the set's value of 1,
+    /// which is never worse than its limit.
+    ///
+    /// Example: 3 outcomes, taker buys A @ 0.40, makers bid B @ 0.35 and
+    /// C @ 0.30. Combined: 0.40 + 0.35 + 0.30 = 1.05 >= 1.0 ✓
+    /// Result: mint 1 (A, B, C) set per share, taker pays 0.35 for A
+    #[allow(clippy::too_many_arguments)]
+    fn try_set_match(
+        &self,
+        taker_order_id: Uuid,
+        taker_address: &str,
+        taker_market_key: &str,
+        side: Side,
+        taker_price: Decimal,
+        remaining_amount: Decimal,
+        stp: SelfTradePrevention,
+    ) -> SetMatch {
+        let mut result = SetMatch {
+            remaining: remaining_amount,
+            ..SetMatch::default()
+        };
+        let Some(legs) = self.set_legs(taker_market_key) else {
+            return result;
+        };
+        let Some((market_id, taker_outcome_id, _)) = Self::parse_market_key(taker_market_key) else {
+            return result;
+        };
+        // Buys mint against the other outcomes' bids, sells merge against their asks
+        let (maker_side, match_type) = match side {
+            Side::Buy => (Side::Buy, MatchType::Mint),
+            Side::Sell => (Side::Sell, MatchType::Merge),
+        };
+        let now = chrono::Utc::now().timestamp_millis();
+
+        while !result.stopped && result.remaining > Decimal::ZERO {
+            let Some(makers) = legs
+                .iter()
+                .map(|(_, _, book)| book.best_order(maker_side))
+                .collect::<Option<Vec<OrderEntry>>>()
+            else {
+                break;
+            };
+            let leg_total: Decimal = makers.iter().map(|m| m.price).sum();
+            let crosses = match side {
+                Side::Buy => taker_price + leg_total >= Decimal::ONE,
+                Side::Sell => taker_price + leg_total <= Decimal::ONE,
+            };
+            if !crosses {
+                break;
+            }
+
+            // Own orders are cleared out of the way (or stop us) first
+            if let Some(index) = makers.iter().position(|m| m.user_address.eq_ignore_ascii_case(taker_address)) {
+                let (_, key, book) = &legs[index];
+                let mut own = BookMatch::new(result.remaining);
+                book.prevent_self_trade(&makers[index], stp, &mut own);
+                if own.self_trades.is_empty() && !own.stopped {
+                    // Gone from the book meanwhile; look again
+                    continue;
+                }
+                result.self_trades.extend(own.self_trades.into_iter().map(|(order, amount)| SelfTradeReduction {
+                    symbol: key.clone(),
+                    order,
+                    amount,
+                }));
+                result.remaining = own.remaining;
+                result.decremented += own.decremented;
+                result.stopped |= own.stopped;
+                continue;
+            }
+
+            let set_amount = makers.iter().map(|m| m.remaining_amount).fold(result.remaining, Decimal::min);
+            let price = (Decimal::ONE - leg_total).max(Decimal::ZERO);
+            let set_id = Uuid::new_v4();
+
+            let mut set_legs = Vec::with_capacity(makers.len());
+            for ((outcome_id, key, book), maker_order) in legs.iter().zip(makers) {
+                book.fill_order(maker_order.id, set_amount);
+                set_legs.push(SetLeg {
+                    symbol: key.clone(),
+                    outcome_id: *outcome_id,
+                    maker_order_id: maker_order.id,
+                    maker_address: maker_order.user_address.clone(),
+                    price: maker_order.price,
+                    maker_fee: self.fee_config.calculate_maker_fee(maker_order.price, set_amount),
+                });
+                let mut maker = maker_order;
+                maker.remaining_amount -= set_amount;
+                result.makers.push((key.clone(), maker));
+            }
+
+            debug!(
+                "🧩 SET {:?}: {} shares of outcome {} @ {:.4} with {} legs summing to {:.4}",
+                match_type, set_amount, taker_outcome_id, price, set_legs.len(), leg_total
+            );
+            result.fills.push(SetFill {
+                set_id,
+                market_id,
+                match_type,
+                taker_order_id,
+                taker_address: taker_address.to_string(),
+                taker_outcome_id,
+                price,
+                amount: set_amount,
+                taker_fee: self.fee_config.calculate_taker_fee(price, set_amount),
+                legs: set_legs,
+                timestamp: now,
+            });
+            result.remaining -= set_amount;
+        }
+
+        result
+    }
+
+    // ========================================================================
+    // Order Operations
+    // ========================================================================
+
+    /// Submit an order for matching
+    ///
+    /// This method implements the full prediction market matching logic:
+    /// 1. **Normal matching**: Match against opposite side in same orderbook
+    /// 2. **Mint matching** (for buy orders): Match against buy orders in complement orderbook
+    /// 3. **Merge matching** (for sell orders): Match against sell orders in complement orderbook
+    ///
+    /// `time_in_force` decides what happens to an unfilled limit order: GTC
+    /// rests it in the book, IOC cancels it, and FOK is rejected up front
+    /// unless the book can fill all of it. PostOnly rests like GTC but is
+    /// rejected up front if it would trade at all. Market orders never rest.
+    ///
+    /// Orders of one account never trade with each other; an order meeting
+    /// its own resting orders cancels the rest of itself, as
+    /// [`SelfTradePrevention::CancelNewest`] does. See
+    /// [`submit_order_with_stp`](Self::submit_order_with_stp) to choose.
+    // Mirrors the order request fields one to one
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_order(
+        &self,
+        order_id: Uuid,
+        symbol: &str,
+        user_address: &str,
+        side: Side,
+        order_type: OrderType,
+        amount: Decimal,
+        price: Option<Decimal>,
+        leverage: u32,
+        time_in_force: TimeInForce,
+    ) -> Result<MatchResult, MatchingError> {
+        self.submit_order_with_stp(
+            order_id,
+            symbol,
+            user_address,
+            side,
+            order_type,
+            amount,
+            price,
+            leverage,
+            time_in_force,
+            SelfTradePrevention::default(),
+        )
+    }
+
+    /// [`submit_order`](Self::submit_order) with the order's self-trade
+    /// prevention mode. Own resting orders cancelled or shrunk come back in
+    /// [`MatchResult::self_trades`]. A FOK order is rejected up front if
+    /// one of its own orders would stop or shrink it before it filled
+    /// completely; own orders it would never reach don't count.
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_order_with_stp(
+        &self,
+        order_id: Uuid,
+        symbol: &str,
+        user_address: &str,
+        side: Side,
+        order_type: OrderType,
+        amount: Decimal,
+        price: Option<Decimal>,
+        _leverage: u32,
+        time_in_force: TimeInForce,
+        stp: SelfTradePrevention,
+    ) -> Result<MatchResult, MatchingError> {
+        // Validate inputs
+        let invalid = if amount <= Decimal::ZERO {
+            Some(MatchingError::InvalidAmount("Amount must be positive".to_string()))
+        } else if order_type == OrderType::Limit && price.is_none() {
+            Some(MatchingError::InvalidPrice("Limit order requires price".to_string()))
+        } else if let Some(halt) = self.price_guard.halt(symbol) {
+            Some(MatchingError::TradingHalted(halt.market))
+        } else if let Some(Err(err)) = price.filter(|_| order_type == OrderType::Limit).map(|price| {
+            self.price_guard.check_band(price, || {
+                self.price_guard.last_price(symbol).or_else(|| self.quoted_mid(symbol))
+            })
+        }) {
+            Some(err)
+        } else if time_in_force == TimeInForce::FOK
+            && !self.fills_completely(symbol, side, price, amount, user_address, stp)
+        {
+            Some(MatchingError::InsufficientLiquidity)
+        } else if time_in_force == TimeInForce::PostOnly
+            && (order_type == OrderType::Market || price.is_some_and(|price| self.would_cross(symbol, side, price)))
+        {
+            // would_cross checks the complement book too: a mint or merge
+            // against it takes liquidity as well
+            Some(MatchingError::WouldTakeLiquidity)
+        } else {
+            None
+        };
+        if let Some(err) = invalid {
+            let now = chrono::Utc::now().timestamp_millis();
+            self.reject_order(OrderEvent {
+                kind: OrderEventKind::Rejected,
+                symbol: symbol.to_string(),
+                order_id,
+                user_address: user_address.to_string(),
+                side,
+                order_type,
+                price,
+                original_amount: amount,
+                filled_amount: Decimal::ZERO,
+                remaining_amount: amount,
+                status: OrderStatus::Rejected,
+                trade_id: None,
+                reject_reason: Some(err.reject_reason()),
+                created_at: now,
+                timestamp: now,
+            });
+            return Err(err);
+        }
+
+        // Get or create orderbook for this symbol/market_key
+        // For prediction markets, orderbooks are created dynamically
+        let orderbook = self.orderbooks
+            .entry(symbol.to_string())
+            .or_insert_with(|| Arc::new(Orderbook::new(symbol.to_string())))
+            .clone();
+
+        // Record order submission metric
+        let started = Instant::now();
+        let side_str = match side {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        };
+        let order_type_str = match order_type {
+            OrderType::Limit => "limit",
+            OrderType::Market => "market",
+        };
+        metrics::record_order_submitted(side_str, order_type_str);
+
+        let now = chrono::Utc::now().timestamp_millis();
+
+        debug!(
+            "Processing order: id={}, symbol={}, side={:?}, type={:?}, amount={}, price={:?}",
+            order_id, symbol, side, order_type, amount, price
+        );
+
+        // Parse share type from symbol for Mint/Merge matching
+        let share_type = Self::parse_market_key(symbol)
+            .map(|(_, _, st)| st)
+            .unwrap_or(ShareType::Yes);
+
+        // ========================================================================
+        // Step 1: Normal matching (same share type, opposite sides)
+        // ========================================================================
+        let mut matched = orderbook.match_order_with_makers(
+            &Taker::new(order_id, user_address).with_stp(stp),
+            side,
+            amount,
+            price,
+            &self.fee_config,
+        );
+        let complement_key = Self::get_complement_market_key(symbol);
+        let mut self_trades: Vec<SelfTradeReduction> = matched
+            .self_trades
+            .drain(..)
+            .map(|(order, amount)| SelfTradeReduction { symbol: symbol.to_string(), order, amount })
+            .collect();
+
+        // ========================================================================
+        // Step 2: Mint/Merge matching (complement orderbook)
+        // ========================================================================
+        // Only try Mint/Merge if:
+        // - There's remaining amount and self-trade prevention did not stop us
+        // - Order has a price (limit order)
+        // - Complement matching is enabled
+        // - We can find/create the complement orderbook
+        if let Some(taker_price) = price.filter(|_| matched.open() && self.complement_matching_enabled()) {
+            if let Some(complement_orderbook) = self.get_or_create_complement_orderbook(symbol) {
+                let before = matched.remaining;
+
+                let mut complement = match side {
+                    // Try Mint matching: match our buy against complement's buy orders
+                    Side::Buy => self.try_mint_match(
+                        order_id,
+                        user_address,
+                        share_type,
+                        symbol,
+                        &complement_orderbook,
+                        taker_price,
+                        before,
+                        stp,
+                    ),
+                    // Try Merge matching: match our sell against complement's sell orders
+                    Side::Sell => self.try_merge_match(
+                        order_id,
+                        user_address,
+                        share_type,
+                        symbol,
+                        &complement_orderbook,
+                        taker_price,
+                        before,
+                        stp,
+                    ),
+                };
+
+                if !complement.trades.is_empty() {
+                    let filled: Decimal = complement.trades.iter().map(|t| t.amount).sum();
+                    match side {
+                        Side::Buy => info!("🔨 MINT matched {} trades, filled {} shares", complement.trades.len(), filled),
+                        Side::Sell => info!("🔄 MERGE matched {} trades, redeemed {} shares", complement.trades.len(), filled),
+                    }
+                }
+                if let Some(complement_key) = &complement_key {
+                    self_trades.extend(complement.self_trades.drain(..).map(|(order, amount)| SelfTradeReduction {
+                        symbol: complement_key.clone(),
+                        order,
+                        amount,
+                    }));
+                    // Broadcast complement orderbook update
+                    if !complement.trades.is_empty() || before != complement.remaining {
+                        self.broadcast_orderbook_update(complement_key);
+                    }
+                }
+                matched.extend(complement);
+            }
+        }
+
+        // ========================================================================
+        // Step 3: Set matching (categorical markets)
+        // ========================================================================
+        // Mint/Merge complete sets with the other outcomes' Yes books
+        let mut set_match = SetMatch::default();
+        if matched.open() && share_type == ShareType::Yes && self.complement_matching_enabled() {
+            if let Some(taker_price) = price {
+                set_match = self.try_set_match(order_id, user_address, symbol, side, taker_price, matched.remaining, stp);
+                if !set_match.fills.is_empty() {
+                    let filled: Decimal = set_match.fills.iter().map(|f| f.amount).sum();
+                    info!("🧩 SET matched {} complete sets, filled {} shares", set_match.fills.len(), filled);
+                }
+                matched.remaining = set_match.remaining;
+                matched.decremented += set_match.decremented;
+                matched.stopped |= set_match.stopped;
+                self_trades.append(&mut set_match.self_trades);
+            }
+        }
+        let SetMatch { fills: set_fills, makers: set_makers, .. } = set_match;
+
+        let BookMatch { mut trades, makers, remaining, decremented, stopped, .. } = matched;
+        // Decrement shrinks the order itself
+        let size = amount - decremented;
+        let filled_amount = size - remaining;
+
+        // Sequence and flag the trades in execution order
+        for trade in trades.iter_mut() {
+            trade.sequence = self.trade_sequence.fetch_add(1, Ordering::SeqCst) + 1;
+            trade.is_block_trade = trade.price * trade.amount >= self.block_trade_notional;
+        }
+
+        // Broadcast trade events and record metrics
+        for trade in &trades {
+            // Use from_execution to preserve match_type (Normal/Mint/Merge)
+            let mut event = TradeEvent::from_execution(
+                trade,
+                symbol.to_string(),
+                user_address.to_string(),
+                side,
+            );
+
+            // Record trade metrics
+            let match_type_str = match trade.match_type {
+                MatchType::Normal => "normal",
+                MatchType::Mint => "mint",
+                MatchType::Merge => "merge",
+            };
+            metrics::record_order_matched(match_type_str);
+
+            // Record trade volume (convert Decimal to f64 for metrics)
+            let volume_usdc = (trade.price * trade.amount).to_string().parse::<f64>().unwrap_or(0.0);
+            metrics::record_trade_executed(match_type_str, volume_usdc);
+
+            // Record mint/merge specific metrics
+            match trade.match_type {
+                MatchType::Mint => metrics::record_mint_operation(),
+                MatchType::Merge => metrics::record_merge_operation(),
+                MatchType::Normal => {}
+            }
+
+            // Broadcast with detailed logging
+            let match_type_log = match trade.match_type {
+                MatchType::Normal => "NORMAL",
+                MatchType::Mint => "🔨 MINT",
+                MatchType::Merge => "🔄 MERGE",
+            };
+            match self.send_trade(&mut event) {
+                Some(n) => {
+                    info!(
+                        "📊 {} Trade broadcast to {} subscribers: symbol={}, price={}, amount={}, side={}",
+                        match_type_log, n, event.symbol, event.price, event.amount, event.side
+                    );
+                }
+                None => {
+                    warn!(
+                        "⚠️  Failed to broadcast {} trade (no subscribers?) - symbol={}, price={}",
+                        match_type_log, event.symbol, event.price
+                    );
+                }
+            }
+
+            // Store in history
+            self.history.store_trade(TradeRecord::from(&event));
+        }
+
+        // A complete set is worth 1 per share, whatever its legs' prices
+        for fill in &set_fills {
+            let match_type_str = match fill.match_type {
+                MatchType::Mint => "mint",
+                MatchType::Merge => "merge",
+                MatchType::Normal => "normal",
+            };
+            metrics::record_order_matched(match_type_str);
+            metrics::record_trade_executed(match_type_str, fill.amount.to_string().parse::<f64>().unwrap_or(0.0));
+            match fill.match_type {
+                MatchType::Mint => metrics::record_mint_operation(),
+                MatchType::Merge => metrics::record_merge_operation(),
+                MatchType::Normal => {}
+            }
+        }
+
+        // Trip the circuit breaker once the move is complete
+        if let Some(halt) = self.price_guard.record(
+            symbol,
+            complement_key.as_deref(),
+            now,
+            trades.iter().map(|t| t.price).chain(set_fills.iter().map(|f| f.price)),
+        ) {
+            warn!(
+                "Circuit breaker halted market {}: {} moved {} -> {} within {}ms",
+                halt.market, halt.symbol, halt.from_price, halt.to_price, halt.window_ms
+            );
+            metrics::record_market_halted();
+            let _ = self.halt_sender.send(halt);
+        }
+
+        // Determine order status
+        let status = match order_type {
+            OrderType::Market => {
+                // Market orders are IOC - an order that found nothing expires
+                if filled_amount == size && size > Decimal::ZERO {
+                    OrderStatus::Filled
+                } else if filled_amount > Decimal::ZERO {
+                    OrderStatus::PartiallyFilled
+                } else {
+                    OrderStatus::Expired
+                }
+            }
+            OrderType::Limit if !time_in_force.rests() || stopped => {
+                // IOC (and a FOK the pre-check let through) never rests, nor
+                // does an order self-trade prevention stopped; the unfilled
+                // remainder is cancelled
+                if filled_amount == size && size > Decimal::ZERO {
+                    OrderStatus::Filled
+                } else {
+                    OrderStatus::Cancelled
+                }
+            }
+            OrderType::Limit if remaining == Decimal::ZERO => {
+                // Filled, or decremented down to nothing
+                if filled_amount == size && size > Decimal::ZERO {
+                    OrderStatus::Filled
+                } else {
+                    OrderStatus::Cancelled
+                }
+            }
+            OrderType::Limit => {
+                // Add remaining to orderbook
+                let entry = OrderEntry {
+                    id: order_id,
+                    user_address: user_address.to_string(),
+                    price: price.unwrap(),
+                    original_amount: size,
+                    remaining_amount: remaining,
+                    side,
+                    time_in_force,
+                    timestamp: now,
+                };
+                let _ = orderbook.add_order(entry);
+                if filled_amount > Decimal::ZERO {
+                    OrderStatus::PartiallyFilled
+                } else {
+                    OrderStatus::Open
+                }
+            }
+        };
+
+        // Calculate average price
+        let average_price = precision::average_price(
+            trades.iter().map(|t| t.price * t.amount).chain(set_fills.iter().map(|f| f.price * f.amount)).sum(),
+            filled_amount,
+        );
+
+        // Order events: the makers this order filled, then the order itself
+        self.emit_maker_fills(symbol, &trades, &makers, now);
+        self.emit_set_fills(&set_fills, &set_makers, now);
+        self.emit_self_trades(&self_trades, now);
+        self.emit_order_event(OrderEvent {
+            kind: OrderEventKind::Accepted,
+            symbol: symbol.to_string(),
+            order_id,
+            user_address: user_address.to_string(),
+            side,
+            order_type,
+            price,
+            original_amount: size,
+            filled_amount,
+            remaining_amount: remaining,
+            status,
+            trade_id: None,
+            reject_reason: None,
+            created_at: now,
+            timestamp: now,
+        });
+
+        // Store order in history
+        let order_record = OrderHistoryRecord {
+            order_id: order_id.to_string(),
+            user_address: user_address.to_string(),
+            symbol: symbol.to_string(),
+            side: side.to_string(),
+            order_type: format!("{:?}", order_type).to_lowercase(),
+            price: price.map(|p| p.to_string()).unwrap_or_default(),
+            original_amount: size.to_string(),
+            filled_amount: filled_amount.to_string(),
+            remaining_amount: remaining.to_string(),
+            status: status.to_string(),
+            leverage: 1, // No leverage in prediction markets
+            created_at: now,
+            updated_at: now,
+            avg_fill_price: average_price.map(|p| p.to_string()),
+            trade_ids: trades
+                .iter()
+                .map(|t| t.trade_id)
+                .chain(set_fills.iter().map(|f| f.set_id))
+                .map(|id| id.to_string())
+                .collect(),
+            reject_reason: None,
+        };
+        self.history.store_order(order_record);
+
+        info!(
+            "Order completed: id={}, status={:?}, filled={}, remaining={}",
+            order_id, status, filled_amount, remaining
+        );
+
+        // Broadcast orderbook update after order processing
+        self.broadcast_orderbook_update(symbol);
+        let mut leg_symbols: Vec<&str> = set_makers.iter().map(|(key, _)| key.as_str()).collect();
+        leg_symbols.sort_unstable();
+        leg_symbols.dedup();
+        for leg_symbol in leg_symbols {
+            self.broadcast_orderbook_update(leg_symbol);
+        }
+
+        // Record order matching duration
+        metrics::record_order_match_duration(started.elapsed().as_secs_f64());
+
+        Ok(MatchResult {
+            order_id,
+            status,
+            filled_amount,
+            remaining_amount: remaining,
+            average_price,
+            trades,
+            queue_position: orderbook.queue_position(&order_id),
+            self_trades,
+            decremented,
+            set_fills,
+        })
+    }
+
+    /// Update history and emit a fill event for each maker order filled by
+    /// a taker. `makers` holds each maker as it stands after its trade.
+    fn emit_maker_fills(&self, symbol: &str, trades: &[TradeExecution], makers: &[OrderEntry], now: i64) {
+        let complement_key = Self::get_complement_market_key(symbol);
+        for (trade, maker) in trades.iter().zip(makers) {
+            // Mint/Merge makers rest in the complement book
+            let maker_symbol = match trade.match_type {
+                MatchType::Normal => symbol,
+                MatchType::Mint | MatchType::Merge => complement_key.as_deref().unwrap_or(symbol),
+            };
+            self.emit_maker_fill(maker_symbol, maker, trade.trade_id, now);
+        }
+    }
+
+    /// [`emit_maker_fills`](Self::emit_maker_fills) for the legs of set
+    /// fills; `makers` holds each leg maker with its book, in leg order
+    fn emit_set_fills(&self, fills: &[SetFill], makers: &[(String, OrderEntry)], now: i64) {
+        let set_ids = fills.iter().flat_map(|fill| fill.legs.iter().map(|_| fill.set_id));
+        for (set_id, (maker_symbol, maker)) in set_ids.zip(makers) {
+            self.emit_maker_fill(maker_symbol, maker, set_id, now);
+        }
+    }
+
+    /// Update history and emit a fill event for one maker order
+    fn emit_maker_fill(&self, maker_symbol: &str, maker: &OrderEntry, trade_id: Uuid, now: i64) {
+        let status = if maker.remaining_amount > Decimal::ZERO {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Filled
+        };
+
+        self.history.update_order(&maker.user_address, &maker.id.to_string(), |order| {
+            if !order.transition(status) {
+                warn!("Ignoring fill of order {} in state {}", maker.id, order.status);
+                return;
+            }
+            order.filled_amount = (maker.original_amount - maker.remaining_amount).to_string();
+            order.remaining_amount = maker.remaining_amount.to_string();
+            order.updated_at = now;
+            order.trade_ids.push(trade_id.to_string());
+        });
+
+        let mut event = OrderEvent::for_resting(OrderEventKind::Fill, maker_symbol, maker, status);
+        event.trade_id = Some(trade_id);
+        self.emit_order_event(event);
+    }
+
+    /// Update history and tell the owner of each resting order self-trade
+    /// prevention cancelled or shrank
+    fn emit_self_trades(&self, self_trades: &[SelfTradeReduction], now: i64) {
+        for reduction in self_trades {
+            let order = &reduction.order;
+            let cancelled = reduction.cancelled();
+            self.history.update_order(&order.user_address, &order.id.to_string(), |record| {
+                if cancelled {
+                    if !record.transition(OrderStatus::Cancelled) {
+                        return;
+                    }
+                } else {
+                    record.original_amount = (order.original_amount - reduction.amount).to_string();
+                    record.remaining_amount = (order.remaining_amount - reduction.amount).to_string();
+                }
+                record.updated_at = now;
+            });
+
+            if cancelled {
+                metrics::record_order_cancelled();
+                info!("Order cancelled by self-trade prevention: id={}, symbol={}", order.id, reduction.symbol);
+                self.emit_order_event(OrderEvent::for_resting(
+                    OrderEventKind::Cancelled,
+                    &reduction.symbol,
+                    order,
+                    OrderStatus::Cancelled,
+                ));
+            } else {
+                // Owners are not told, but the journal must see the book shrink
+                let mut shrunk = order.clone();
+                shrunk.original_amount -= reduction.amount;
+                shrunk.remaining_amount -= reduction.amount;
+                let status = if shrunk.remaining_amount < shrunk.original_amount {
+                    OrderStatus::PartiallyFilled
+                } else {
+                    OrderStatus::Open
+                };
+                self.journal(&OrderEvent::for_resting(OrderEventKind::Amended, &reduction.symbol, &shrunk, status));
+            }
+        }
+    }
+
+    /// Record a rejected order in history and tell its owner why
+    fn reject_order(&self, event: OrderEvent) {
+        metrics::record_order_rejected(event.reject_reason.map(|r| r.code()).unwrap_or("UNKNOWN"));
+        self.history.store_order(OrderHistoryRecord {
+            order_id: event.order_id.to_string(),
+            user_address: event.user_address.clone(),
+            symbol: event.symbol.clone(),
+            side: event.side.to_string(),
+            order_type: event.order_type.to_string(),
+            price: event.price.map(|p| p.to_string()).unwrap_or_default(),
+            original_amount: event.original_amount.to_string(),
+            filled_amount: Decimal::ZERO.to_string(),
+            remaining_amount: event.remaining_amount.to_string(),
+            status: OrderStatus::Rejected.to_string(),
+            leverage: 1,
+            created_at: event.created_at,
+            updated_at: event.timestamp,
+            avg_fill_price: None,
+            trade_ids: Vec::new(),
+            reject_reason: event.reject_reason.map(|r| r.code().to_string()),
+        });
+        debug!("Order rejected: id={}, reason={:?}", event.order_id, event.reject_reason);
+        self.emit_order_event(event);
+    }
+
+    /// Cancel an order
+    pub fn cancel_order(&self, symbol: &str, order_id: Uuid, user_address: &str) -> Result<bool, MatchingError> {
+        let orderbook = self.orderbooks.get(symbol)
+            .ok_or_else(|| MatchingError::SymbolNotFound(symbol.to_string()))?;
+
+        // Try to cancel
+        let cancelled = orderbook.cancel_order(order_id);
+
+        if let Some(entry) = cancelled {
+            // Record cancellation metric
+            metrics::record_order_cancelled();
+
+            // Update order history
+            self.history.update_order(user_address, &order_id.to_string(), |order| {
+                if order.transition(OrderStatus::Cancelled) {
+                    order.updated_at = chrono::Utc::now().timestamp_millis();
+                }
+            });
+
+            info!("Order cancelled: id={}, symbol={}", order_id, symbol);
+
+            // Broadcast orderbook update after cancellation
+            self.broadcast_orderbook_update(symbol);
+            self.emit_order_event(OrderEvent::for_resting(
+                OrderEventKind::Cancelled,
+                symbol,
+                &entry,
+                OrderStatus::Cancelled,
+            ));
+
+            Ok(true)
+        } else {
+            warn!("Order not found for cancellation: id={}", order_id);
+            Ok(false)
+        }
+    }
+
+    /// Change the price and total size of a resting order. The amended
+    /// order never trades on arrival: a new price that would cross the book
+    /// (directly or through the complement book) is refused with
+    /// [`MatchingError::WouldTakeLiquidity`]. `amount` is the new total size
+    /// and must exceed what has already filled. Returns `None` if the order
+    /// is no longer resting.
+    pub fn amend_order(
+        &self,
+        symbol: &str,
+        order_id: Uuid,
+        user_address: &str,
+        price: Decimal,
+        amount: Decimal,
+    ) -> Result<Option<Amendment>, MatchingError> {
+        let orderbook = self.orderbooks.get(symbol)
+            .ok_or_else(|| MatchingError::SymbolNotFound(symbol.to_string()))?
+            .clone();
+        let Some(current) = orderbook.get_order(&order_id) else {
+            return Ok(None);
+        };
+
+        let filled = current.original_amount - current.remaining_amount;
+        if amount <= filled {
+            return Err(MatchingError::InvalidAmount(format!(
+                "Amount {} must exceed the {} already filled",
+                amount, filled
+            )));
+        }
+        if price != current.price && self.fillable_amount(symbol, current.side, Some(price)) > Decimal::ZERO {
+            return Err(MatchingError::WouldTakeLiquidity);
+        }
+
+        let Some(amendment) = orderbook.amend_order(order_id, price, amount - filled)? else {
+            return Ok(None);
+        };
+        metrics::record_order_amended();
+
+        let after = &amendment.after;
+        self.history.update_order(user_address, &order_id.to_string(), |order| {
+            order.price = after.price.to_string();
+            order.original_amount = after.original_amount.to_string();
+            order.remaining_amount = after.remaining_amount.to_string();
+        });
+        info!(
+            "Order amended: id={}, symbol={}, {} @ {} -> {} @ {}",
+            order_id, symbol, amendment.before.remaining_amount, amendment.before.price, after.remaining_amount, after.price
+        );
+
+        self.broadcast_orderbook_update(symbol);
+        let status = if filled > Decimal::ZERO { OrderStatus::PartiallyFilled } else { OrderStatus::Open };
+        self.emit_order_event(OrderEvent::for_resting(OrderEventKind::Amended, symbol, after, status));
+
+        Ok(Some(amendment))
+    }
+
+    /// Drop every orderbook of a closed (resolved or cancelled) market,
+    /// returning the orders that were still resting. Owners receive a
+    /// cancellation event and book subscribers an empty book.
+    pub fn close_market(&self, market_id: Uuid) -> Vec<OrderEntry> {
+        let prefix = format!("{}:", market_id);
+        let keys: Vec<String> = self
+            .orderbooks
+            .iter()
+            .filter(|entry| OrderbookSnapshot::split_namespace(entry.key()).1.starts_with(&prefix))
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        self.outcome_sets.remove(&market_id);
+        let mut cancelled = Vec::new();
+        for key in keys {
+            let Some((_, book)) = self.orderbooks.remove(&key) else {
+                continue;
+            };
+            for entry in book.orders() {
+                self.emit_order_event(OrderEvent::for_resting(
+                    OrderEventKind::Cancelled,
+                    &key,
+                    &entry,
+                    OrderStatus::Cancelled,
+                ));
+                cancelled.push(entry);
+            }
+            let mut sequence = self.market_sequences.entry(key.clone()).or_default();
+            sequence.orderbook += 1;
+            let _ = self.orderbook_sender.send(OrderbookUpdate {
+                symbol: key.clone(),
+                bids: Arc::from([]),
+                asks: Arc::from([]),
+                sequence: sequence.orderbook,
+                timestamp: chrono::Utc::now().timestamp_millis(),
+            });
+            drop(sequence);
+            self.publish_delta(&key, None);
+        }
+
+        if !cancelled.is_empty() {
+            info!("Closed market {}: {} resting orders cancelled", market_id, cancelled.len());
+        }
+        cancelled
+    }
+
+    // ========================================================================
+    // Cancel-all-after (dead man's switch)
+    // ========================================================================
+
+    /// Arm (or refresh) the user's dead man's switch: unless called again
+    /// within `timeout_ms`, all their resting orders are cancelled. A
+    /// timeout of 0 disarms it. Returns the new deadline.
+    pub fn cancel_all_after(&self, user_address: &str, timeout_ms: u64, now_ms: i64) -> Option<i64> {
+        let user = user_address.to_lowercase();
+        if timeout_ms == 0 {
+            self.cancel_all_deadlines.remove(&user);
+            return None;
+        }
+        let deadline = now_ms.saturating_add(timeout_ms as i64);
+        self.cancel_all_deadlines.insert(user, deadline);
+        Some(deadline)
+    }
+
+    /// The user's armed cancel-all-after deadline
+    pub fn cancel_all_deadline(&self, user_address: &str) -> Option<i64> {
+        self.cancel_all_deadlines.get(&user_address.to_lowercase()).map(|d| *d)
+    }
+
+    /// Cancel every resting order of the user across all books, returning
+    /// each with its book key
+    pub fn cancel_all_for_user(&self, user_address: &str) -> Vec<(String, OrderEntry)> {
+        self.cancel_user_orders(user_address, |_| true)
+    }
+
+    /// Cancel every resting order of the user in the books of `market_id`,
+    /// returning each with its book key
+    pub fn cancel_market_orders_for_user(&self, user_address: &str, market_id: Uuid) -> Vec<(String, OrderEntry)> {
+        let prefix = format!("{}:", market_id);
+        self.cancel_user_orders(user_address, |symbol| symbol.starts_with(&prefix))
+    }
+
+    /// Cancel every resting order of the user in the book `symbol`,
+    /// returning each with its book key
+    pub fn cancel_book_orders_for_user(&self, symbol: &str, user_address: &str) -> Vec<(String, OrderEntry)> {
+        self.cancel_user_orders(user_address, |key| key == symbol)
+    }
+
+    /// Books holding a resting order of the user
+    pub fn user_books(&self, user_address: &str) -> Vec<String> {
+        self.orderbooks
+            .iter()
+            .filter(|entry| {
+                entry
+                    .value()
+                    .orders()
+                    .iter()
+                    .any(|o| o.user_address.eq_ignore_ascii_case(user_address))
+            })
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    fn cancel_user_orders(&self, user_address: &str, in_book: impl Fn(&str) -> bool) -> Vec<(String, OrderEntry)> {
+        let books: Vec<(String, Arc<Orderbook>)> = self
+            .orderbooks
+            .iter()
+            .filter(|entry| in_book(entry.key()))
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+
+        let mut cancelled = Vec::new();
+        for (symbol, book) in books {
+            let owned: Vec<Uuid> = book
+                .orders()
+                .into_iter()
+                .filter(|o| o.user_address.eq_ignore_ascii_case(user_address))
+                .map(|o| o.id)
+                .collect();
+            if owned.is_empty() {
+                continue;
+            }
+
+            for order_id in owned {
+                let Some(entry) = book.cancel_order(order_id) else {
+                    continue;
+                };
+                metrics::record_order_cancelled();
+                self.history.update_order(&entry.user_address, &order_id.to_string(), |order| {
+                    if order.transition(OrderStatus::Cancelled) {
+                        order.updated_at = chrono::Utc::now().timestamp_millis();
+                    }
+                });
+                self.emit_order_event(OrderEvent::for_resting(
+                    OrderEventKind::Cancelled,
+                    &symbol,
+                    &entry,
+                    OrderStatus::Cancelled,
+                ));
+                cancelled.push((symbol.clone(), entry));
+            }
+            self.broadcast_orderbook_update(&symbol);
+        }
+        cancelled
+    }
+
+    /// Fire every cancel-all-after switch whose deadline has passed. Returns
+    /// the affected users with the orders cancelled for each; a fired switch
+    /// is disarmed.
+    pub fn fire_cancel_all_after(&self, now_ms: i64) -> Vec<(String, Vec<(String, OrderEntry)>)> {
+        let mut fired = Vec::new();
+        for user in self.take_fired_cancel_all_after(now_ms) {
+            let cancelled = self.cancel_all_for_user(&user);
+            info!("Cancel-all-after fired for {}: {} orders cancelled", user, cancelled.len());
+            fired.push((user, cancelled));
+        }
+        fired
+    }
+
+    /// Disarm every cancel-all-after switch whose deadline has passed and
+    /// return its user, leaving the cancelling to the caller (book by book,
+    /// see [`user_books`](Self::user_books))
+    pub fn take_fired_cancel_all_after(&self, now_ms: i64) -> Vec<String> {
+        let expired: Vec<String> = self
+            .cancel_all_deadlines
+            .iter()
+            .filter(|entry| *entry.value() <= now_ms)
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        // Refreshed since it was collected: leave it armed
+        expired
+            .into_iter()
+            .filter(|user| self.cancel_all_deadlines.remove_if(user, |_, deadline| *deadline <= now_ms).is_some())
+            .collect()
+    }
+
+    // ========================================================================
+    // Good-til-date expiry
+    // ========================================================================
+
+    /// Expire the resting order `order_id` of `symbol` at `expires_at_ms`
+    pub fn set_order_expiry(&self, symbol: &str, order_id: Uuid, expires_at_ms: i64) {
+        self.order_expiries.insert(order_id, (symbol.to_string(), expires_at_ms));
+    }
+
+    /// When a resting order expires, if it does
+    pub fn order_expiry(&self, order_id: &Uuid) -> Option<i64> {
+        self.order_expiries.get(order_id).map(|entry| entry.1)
+    }
+
+    /// Books holding an order whose expiry has passed
+    pub fn expiring_books(&self, now_ms: i64) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .order_expiries
+            .iter()
+            .filter(|entry| entry.value().1 <= now_ms)
+            .map(|entry| entry.value().0.clone())
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Take every order of `symbol` whose expiry has passed off the book,
+    /// returning each with its book key
+    pub fn expire_orders(&self, symbol: &str, now_ms: i64) -> Vec<(String, OrderEntry)> {
+        let due: Vec<Uuid> = self
+            .order_expiries
+            .iter()
+            .filter(|entry| entry.value().0 == symbol && entry.value().1 <= now_ms)
+            .map(|entry| *entry.key())
+            .collect();
+
+        let mut expired = Vec::new();
+        for order_id in due {
+            let Some((_, (symbol, _))) = self.order_expiries.remove_if(&order_id, |_, (_, at)| *at <= now_ms) else {
+                continue;
+            };
+            let book = self.orderbooks.get(&symbol).map(|book| book.clone());
+            if let Some(entry) = book.and_then(|book| book.cancel_order(order_id)) {
+                expired.push((symbol, entry));
+            }
+        }
+        self.record_expired(&expired);
+        expired
+    }
+
+    /// Expire every resting order of a market that stopped trading, leaving
+    /// its (now empty) books in place
+    pub fn expire_market(&self, market_id: Uuid) -> Vec<(String, OrderEntry)> {
+        let prefix = format!("{}:", market_id);
+        let books: Vec<(String, Arc<Orderbook>)> = self
+            .orderbooks
+            .iter()
+            .filter(|entry| OrderbookSnapshot::split_namespace(entry.key()).1.starts_with(&prefix))
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+
+        let mut expired = Vec::new();
+        for (symbol, book) in books {
+            for order in book.orders() {
+                if let Some(entry) = book.cancel_order(order.id) {
+                    self.order_expiries.remove(&entry.id);
+                    expired.push((symbol.clone(), entry));
+                }
+            }
+        }
+        if !expired.is_empty() {
+            info!("Market {} stopped trading: {} resting orders expired", market_id, expired.len());
+        }
+        self.record_expired(&expired);
+        expired
+    }
+
+    /// History, owner events and book updates for orders taken off their
+    /// books by expiry
+    fn record_expired(&self, expired: &[(String, OrderEntry)]) {
+        let mut symbols: Vec<&str> = Vec::new();
+        for (symbol, entry) in expired {
+            metrics::record_order_expired();
+            self.history.update_order(&entry.user_address, &entry.id.to_string(), |order| {
+                order.transition(OrderStatus::Expired);
+            });
+            self.emit_order_event(OrderEvent::for_resting(OrderEventKind::Expired, symbol, entry, OrderStatus::Expired));
+            if !symbols.contains(&symbol.as_str()) {
+                symbols.push(symbol);
+            }
+        }
+        for symbol in symbols {
+            self.broadcast_orderbook_update(symbol);
+        }
+    }
+
+    // ========================================================================
+    // Recovery
+    // ========================================================================
+
+    /// Put a persisted resting order back on its book as it was: original
+    /// timestamp (so time priority is kept), no matching and no order events
+    pub fn restore_order(&self, symbol: &str, entry: OrderEntry) -> Result<(), MatchingError> {
+        if entry.remaining_amount <= Decimal::ZERO {
+            return Err(MatchingError::InvalidAmount("Remaining amount must be positive".to_string()));
+        }
+        let orderbook = self.orderbooks
+            .entry(symbol.to_string())
+            .or_insert_with(|| Arc::new(Orderbook::new(symbol.to_string())))
+            .clone();
+        orderbook.add_order(entry)
+    }
+
+    /// Whether a limit order at `price` would trade against the current books
+    fn would_cross(&self, symbol: &str, side: Side, price: Decimal) -> bool {
+        let book = self.get_orderbook_ref(symbol);
+        let complement = Self::get_complement_market_key(symbol)
+            .filter(|_| self.complement_matching_enabled())
+            .and_then(|key| self.get_orderbook_ref(&key));
+
+        // A set leg needs a quote on every other outcome
+        let legs = self.set_legs(symbol).filter(|_| self.complement_matching_enabled());
+        let leg_total = |best: fn(&Orderbook) -> Option<Decimal>| {
+            legs.as_ref()?.iter().map(|(_, _, book)| best(book)).sum::<Option<Decimal>>()
+        };
+
+        match side {
+            Side::Buy => {
+                book.and_then(|b| b.best_ask()).is_some_and(|ask| ask <= price)
+                    || complement.and_then(|c| c.best_bid()).is_some_and(|bid| bid + price >= Decimal::ONE)
+                    || leg_total(Orderbook::best_bid).is_some_and(|bids| bids + price >= Decimal::ONE)
+            }
+            Side::Sell => {
+                book.and_then(|b| b.best_bid()).is_some_and(|bid| bid >= price)
+                    || complement.and_then(|c| c.best_ask()).is_some_and(|ask| ask + price <= Decimal::ONE)
+                    || leg_total(Orderbook::best_ask).is_some_and(|asks| asks + price <= Decimal::ONE)
+            }
+        }
+    }
+
+    /// Books holding orders that could trade against the book itself or,
+    /// with complement matching, against its complement book. Only one key
+    /// of each crossed Yes/No pair is returned.
+    pub fn crossed_books(&self) -> Vec<String> {
+        let mut keys = self.orderbook_keys();
+        keys.sort();
+
+        let mut crossed: Vec<String> = Vec::new();
+        for key in keys {
+            let complement = Self::get_complement_market_key(&key);
+            if complement.as_ref().is_some_and(|c| crossed.contains(c)) {
+                continue;
+            }
+            let Some(book) = self.get_orderbook_ref(&key) else {
+                continue;
+            };
+            let bid_crosses = book.best_bid().is_some_and(|bid| self.would_cross(&key, Side::Sell, bid));
+            let ask_crosses = book.best_ask().is_some_and(|ask| self.would_cross(&key, Side::Buy, ask));
+            if bid_crosses || ask_crosses {
+                crossed.push(key);
+            }
+        }
+        crossed
+    }
+
+    /// Uncross `symbol` and its complement book: their orders are taken off
+    /// and re-inserted in time order, restoring those that no longer cross
+    /// and submitting the rest as takers. Returns each order that traded as
+    /// a taker with its result; trades are broadcast like any other.
+    pub fn resolve_crossed(&self, symbol: &str) -> Vec<(String, OrderEntry, MatchResult)> {
+        let mut keys = vec![symbol.to_string()];
+        keys.extend(Self::get_complement_market_key(symbol));
+
+        let mut orders: Vec<(String, OrderEntry)> = Vec::new();
+        for key in &keys {
+            if let Some(book) = self.get_orderbook_ref(key) {
+                for entry in book.orders() {
+                    book.cancel_order(entry.id);
+                    orders.push((key.clone(), entry));
+                }
+            }
+        }
+        orders.sort_by_key(|(_, entry)| entry.timestamp);
+
+        let mut results = Vec::new();
+        for (key, entry) in orders {
+            if !self.would_cross(&key, entry.side, entry.price) {
+                let _ = self.restore_order(&key, entry);
+                continue;
+            }
+            match self.submit_order(
+                entry.id,
+                &key,
+                &entry.user_address,
+                entry.side,
+                OrderType::Limit,
+                entry.remaining_amount,
+                Some(entry.price),
+                1,
+                // Resting post-only orders were makers when placed; the
+                // uncrossing trades them like any other
+                match entry.time_in_force {
+                    TimeInForce::PostOnly => TimeInForce::GTC,
+                    time_in_force => time_in_force,
+                },
+            ) {
+                Ok(result) => results.push((key, entry, result)),
+                Err(e) => warn!("Failed to resubmit crossed order {}: {}", entry.id, e),
+            }
+        }
+
+        for key in &keys {
+            self.broadcast_orderbook_update(key);
+        }
+        results
+    }
+
+    // ========================================================================
+    // Circuit breaker
+    // ========================================================================
+
+    /// Markets the circuit breaker has halted
+    pub fn halts(&self) -> Vec<MarketHalt> {
+        self.price_guard.halts()
+    }
+
+    /// The halt of a market, if the circuit breaker has halted it
+    pub fn market_halt(&self, market_id: Uuid) -> Option<MarketHalt> {
+        self.price_guard.halt(&market_id.to_string())
+    }
+
+    /// Lift a market's halt; returns it, or `None` if the market wasn't
+    /// halted. Resting orders stayed on the book throughout.
+    pub fn resume_market(&self, market_id: Uuid) -> Option<MarketHalt> {
+        let halt = self.price_guard.resume(&market_id.to_string())?;
+        info!("Market {} resumed after circuit breaker halt", market_id);
+        Some(halt)
+    }
+
+    /// Price band and circuit breaker settings
+    pub fn price_guard_config(&self) -> &PriceGuardConfig {
+        self.price_guard.config()
+    }
+
+    // ========================================================================
+    // Query Operations
+    // ========================================================================
+
+    /// Keys of every orderbook the engine holds
+    pub fn orderbook_keys(&self) -> Vec<String> {
+        self.orderbooks.iter().map(|entry| entry.key().clone()).collect()
+    }
+
+    /// Get orderbook snapshot
+    pub fn get_orderbook(&self, symbol: &str, depth: usize) -> Result<OrderbookSnapshot, MatchingError> {
+        let orderbook = self.orderbooks.get(symbol)
+            .ok_or_else(|| MatchingError::SymbolNotFound(symbol.to_string()))?;
+
+        Ok(orderbook.snapshot(depth))
+    }
+
+    /// Cached top-of-book depth ([`CACHED_DEPTH`](crate::CACHED_DEPTH) levels) with its
+    /// preserialized snapshot JSON
+    pub fn get_orderbook_depth(&self, symbol: &str) -> Result<Arc<CachedDepth>, MatchingError> {
+        let orderbook = self.orderbooks.get(symbol)
+            .ok_or_else(|| MatchingError::SymbolNotFound(symbol.to_string()))?;
+
+        Ok(orderbook.cached_depth())
+    }
+
+    /// Get best bid/ask
+    pub fn get_best_prices(&self, symbol: &str) -> Result<(Option<Decimal>, Option<Decimal>), MatchingError> {
+        let orderbook = self.orderbooks.get(symbol)
+            .ok_or_else(|| MatchingError::SymbolNotFound(symbol.to_string()))?;
+
+        Ok((orderbook.best_bid(), orderbook.best_ask()))
+    }
+
+    /// Quote a taker order of `size` across the direct book and, when
+    /// complement matching is enabled, the synthetic mint/merge route
+    pub fn quote(&self, symbol: &str, side: Side, size: Decimal) -> Quote {
+        let direct = self.orderbooks.get(symbol).map(|book| match side {
+            Side::Buy => book.ask_levels(),
+            Side::Sell => book.bid_levels(),
+        });
+
+        let synthetic = Self::get_complement_market_key(symbol)
+            .filter(|_| self.complement_matching_enabled())
+            .and_then(|key| self.orderbooks.get(&key).map(|book| book.clone()))
+            .map(|book| {
+                let levels = match side {
+                    Side::Buy => book.bid_levels(),
+                    Side::Sell => book.ask_levels(),
+                };
+                levels.into_iter().map(|(price, amount)| (Decimal::ONE - price, amount)).collect::<Vec<_>>()
+            });
+
+        Quote::build(
+            symbol,
+            side,
+            size,
+            direct.as_deref().unwrap_or_default(),
+            synthetic.as_deref().unwrap_or_default(),
+        )
+    }
+
+    /// Shares a taker order could fill right now: the direct book within
+    /// `price` (any price for a market order), plus the mint/merge route and
+    /// complete sets of a categorical market for limit orders when
+    /// complement matching is enabled. Mirrors what
+    /// [`submit_order`](Self::submit_order) would match, so a FOK order can
+    /// be checked before anything is filled.
+    pub fn fillable_amount(&self, symbol: &str, side: Side, price: Option<Decimal>) -> Decimal {
+        let routes: Decimal = self
+            .fillable_orders(symbol, side, price)
+            .iter()
+            .map(|order| order.remaining_amount)
+            .sum();
+        routes + self.set_fillable(symbol, side, price, Decimal::MAX, None)
+    }
+
+    /// Whether `user_address` could fill all of `amount` right now. Walks
+    /// the resting orders in the order [`submit_order`](Self::submit_order)
+    /// meets them; only [`SelfTradePrevention::CancelOldest`] gets past an
+    /// own order, every other mode stops or shrinks the taker there.
+    fn fills_completely(
+        &self,
+        symbol: &str,
+        side: Side,
+        price: Option<Decimal>,
+        amount: Decimal,
+        user_address: &str,
+        stp: SelfTradePrevention,
+    ) -> bool {
+        let mut filled = Decimal::ZERO;
+        for order in self.fillable_orders(symbol, side, price) {
+            if filled >= amount {
+                break;
+            }
+            if order.user_address.eq_ignore_ascii_case(user_address) {
+                if stp != SelfTradePrevention::CancelOldest {
+                    return false;
+                }
+                continue;
+            }
+            filled += order.remaining_amount;
+        }
+        if filled < amount {
+            filled += self.set_fillable(symbol, side, price, amount - filled, Some((user_address, stp)));
+        }
+        filled >= amount
+    }
+
+    /// Shares set matching would fill, up to `limit`, after the other
+    /// routes: walks the other outcomes' books one complete set at a time
+    /// as [`try_set_match`](Self::try_set_match) does. With a `taker`, an
+    /// own leg is skipped under [`SelfTradePrevention::CancelOldest`] and
+    /// ends the walk otherwise.
+    fn set_fillable(
+        &self,
+        symbol: &str,
+        side: Side,
+        price: Option<Decimal>,
+        limit: Decimal,
+        taker: Option<(&str, SelfTradePrevention)>,
+    ) -> Decimal {
+        let (Some(price), Some(legs)) = (price, self.set_legs(symbol).filter(|_| self.complement_matching_enabled()))
+        else {
+            return Decimal::ZERO;
+        };
+        let mut queues: Vec<VecDeque<OrderEntry>> = legs
+            .iter()
+            .map(|(_, _, book)| match side {
+                Side::Buy => book.get_matching_buy_orders(Decimal::ZERO).into(),
+                Side::Sell => book.get_matching_sell_orders(Decimal::ONE).into(),
+            })
+            .collect();
+
+        let mut filled = Decimal::ZERO;
+        while filled < limit {
+            let Some(heads) = queues.iter().map(|queue| queue.front()).collect::<Option<Vec<_>>>() else {
+                break;
+            };
+            let leg_total: Decimal = heads.iter().map(|order| order.price).sum();
+            let crosses = match side {
+                Side::Buy => price + leg_total >= Decimal::ONE,
+                Side::Sell => price + leg_total <= Decimal::ONE,
+            };
+            if !crosses {
+                break;
+            }
+            if let Some((address, stp)) = taker {
+                if let Some(index) = heads.iter().position(|order| order.user_address.eq_ignore_ascii_case(address)) {
+                    if stp != SelfTradePrevention::CancelOldest {
+                        break;
+                    }
+                    queues[index].pop_front();
+                    continue;
+                }
+            }
+
+            let set_amount = heads.iter().map(|order| order.remaining_amount).fold(limit - filled, Decimal::min);
+            for queue in &mut queues {
+                if let Some(order) = queue.front_mut() {
+                    order.remaining_amount -= set_amount;
+                    if order.remaining_amount <= Decimal::ZERO {
+                        queue.pop_front();
+                    }
+                }
+            }
+            filled += set_amount;
+        }
+        filled
+    }
+
+    /// Resting orders a taker would match, in matching order: the direct
+    /// book best price first, then the mint/merge route
+    fn fillable_orders(&self, symbol: &str, side: Side, price: Option<Decimal>) -> Vec<OrderEntry> {
+        let direct = self
+            .orderbooks
+            .get(symbol)
+            .map(|book| match (side, price) {
+                (Side::Buy, limit) => book.get_matching_sell_orders(limit.unwrap_or(Decimal::ONE)),
+                (Side::Sell, limit) => book.get_matching_buy_orders(limit.unwrap_or(Decimal::ZERO)),
+            })
+            .unwrap_or_default();
+
+        // Mint against complement bids, merge against complement asks, at
+        // the complement of our limit
+        let synthetic = match price {
+            Some(limit) if self.complement_matching_enabled() => Self::get_complement_market_key(symbol)
+                .and_then(|key| self.orderbooks.get(&key).map(|book| book.clone()))
+                .map(|book| match side {
+                    Side::Buy => book.get_matching_buy_orders(Decimal::ONE - limit),
+                    Side::Sell => book.get_matching_sell_orders(Decimal::ONE - limit),
+                })
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        direct.into_iter().chain(synthetic).collect()
+    }
+
+    /// Midpoint of the best bid and ask a taker would trade against,
+    /// including the synthetic mint/merge levels when complement matching
+    /// is enabled; `None` unless both sides are quoted
+    pub fn quoted_mid(&self, symbol: &str) -> Option<Decimal> {
+        let (mut bid, mut ask) = self
+            .orderbooks
+            .get(symbol)
+            .map(|book| (book.best_bid(), book.best_ask()))
+            .unwrap_or_default();
+
+        let complement = Self::get_complement_market_key(symbol)
+            .filter(|_| self.complement_matching_enabled())
+            .and_then(|key| self.orderbooks.get(&key).map(|book| book.clone()));
+        if let Some(book) = complement {
+            // A complement bid at p mints this share at 1 - p; a complement
+            // ask at p merges against a bid at 1 - p
+            let synthetic_ask = book.best_bid().map(|p| Decimal::ONE - p);
+            let synthetic_bid = book.best_ask().map(|p| Decimal::ONE - p);
+            ask = ask.into_iter().chain(synthetic_ask).min();
+            bid = bid.into_iter().chain(synthetic_bid).max();
+        }
+
+        Some((bid? + ask?) / Decimal::TWO)
+    }
+
+    /// Get trade history for a symbol
+    pub fn get_trades(&self, symbol: &str, query: &TradeHistoryQuery) -> TradeHistoryResponse {
+        self.history.get_trades(symbol, query)
+    }
+
+    /// Get order history for a user
+    pub fn get_orders(&self, user_address: &str, query: &OrderHistoryQuery) -> OrderHistoryResponse {
+        self.history.get_orders(user_address, query)
+    }
+
+    /// Broadcast a trade event (for internal/market maker use). With no
+    /// subscribers the event comes back boxed.
+    pub fn broadcast_trade(&self, mut event: TradeEvent) -> Result<usize, Box<broadcast::error::SendError<TradeEvent>>> {
+        // Also store in history
+        self.history.store_trade(TradeRecord::from(&event));
+        self.send_trade(&mut event).ok_or_else(|| Box::new(broadcast::error::SendError(event)))
+    }
+
+    // ========================================================================
+    // Statistics
+    // ========================================================================
+
+    /// Get engine statistics
+    pub fn stats(&self) -> EngineStats {
+        let mut total_orders = 0i64;
+        let mut total_bid_depth = Decimal::ZERO;
+        let mut total_ask_depth = Decimal::ZERO;
+
+        for entry in self.orderbooks.iter() {
+            let ob = entry.value();
+            total_orders += ob.order_count();
+            total_bid_depth += ob.bid_depth();
+            total_ask_depth += ob.ask_depth();
+        }
+
+        let history_stats = self.history.stats();
+
+        EngineStats {
+            symbols_count: self.orderbooks.len(),
+            total_orders_in_book: total_orders,
+            total_bid_depth,
+            total_ask_depth,
+            total_trades_recorded: history_stats.total_trades,
+            total_orders_recorded: history_stats.total_orders,
+        }
+    }
+}
+
+impl Default for MatchingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Engine statistics
+#[derive(Debug, Clone)]
+pub struct EngineStats {
+    pub symbols_count: usize,
+    pub total_orders_in_book: i64,
+    pub total_bid_depth: Decimal,
+    pub total_ask_depth: Decimal,
+    pub total_trades_recorded: usize,
+    pub total_orders_recorded: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::collections::BTreeMap;
+
+    fn create_market_key() -> String {
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        format!("{}:{}:yes", market_id, outcome_id)
+    }
+
+    #[test]
+    fn test_engine_creation() {
+        let engine = MatchingEngine::new();
+        // New engine starts empty (no default symbols for prediction markets)
+        assert!(engine.symbols().is_empty());
+    }
+
+    #[test]
+    fn test_submit_limit_order_no_match() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+
+        let result = engine.submit_order(
+            Uuid::new_v4(),
+            &market_key,
+            "0x1234",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.55)), // probability price 0-1
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        assert_eq!(result.status, OrderStatus::Open);
+        assert_eq!(result.filled_amount, dec!(0));
+        assert_eq!(result.remaining_amount, dec!(100.0));
+        assert!(result.trades.is_empty());
+    }
+
+    #[test]
+    fn test_submit_and_match_orders() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+
+        // Submit sell order at 0.60
+        let sell_result = engine.submit_order(
+            Uuid::new_v4(),
+            &market_key,
+            "0x1111",
+            Side::Sell,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.60)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+        assert_eq!(sell_result.status, OrderStatus::Open);
+
+        // Submit matching buy order at 0.60
+        let buy_result = engine.submit_order(
+            Uuid::new_v4(),
+            &market_key,
+            "0x2222",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(50.0),
+            Some(dec!(0.60)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        assert_eq!(buy_result.status, OrderStatus::Filled);
+        assert_eq!(buy_result.filled_amount, dec!(50.0));
+        assert_eq!(buy_result.trades.len(), 1);
+        assert_eq!(buy_result.trades[0].price, dec!(0.60));
+        assert_eq!(buy_result.trades[0].amount, dec!(50.0));
+    }
+
+    #[test]
+    fn test_market_order() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+
+        // Add liquidity (sell order)
+        engine.submit_order(
+            Uuid::new_v4(),
+            &market_key,
+            "0x1111",
+            Side::Sell,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.65)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        // Market buy
+        let result = engine.submit_order(
+            Uuid::new_v4(),
+            &market_key,
+            "0x2222",
+            Side::Buy,
+            OrderType::Market,
+            dec!(50.0),
+            None,
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        assert_eq!(result.status, OrderStatus::Filled);
+        assert_eq!(result.filled_amount, dec!(50.0));
+    }
+
+    #[test]
+    fn test_cancel_order() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+
+        let result = engine.submit_order(
+            Uuid::new_v4(),
+            &market_key,
+            "0x1234",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.55)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        let cancelled = engine.cancel_order(&market_key, result.order_id, "0x1234").unwrap();
+        assert!(cancelled);
+
+        // Try to cancel again
+        let cancelled_again = engine.cancel_order(&market_key, result.order_id, "0x1234").unwrap();
+        assert!(!cancelled_again);
+    }
+
+    #[test]
+    fn test_amend_order() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+        let submit = |user: &str, side, amount, price| {
+            engine
+                .submit_order(Uuid::new_v4(), &market_key, user, side, OrderType::Limit, amount, Some(price), 1, TimeInForce::GTC)
+                .unwrap()
+                .order_id
+        };
+        let first = submit("0x1", Side::Buy, dec!(100), dec!(0.55));
+        let second = submit("0x2", Side::Buy, dec!(100), dec!(0.55));
+        submit("0x3", Side::Sell, dec!(100), dec!(0.65));
+        let position = |id| engine.get_orderbook_ref(&market_key).unwrap().queue_position(&id).unwrap().position;
+
+        // Shrinking keeps the order's place, growing sends it to the back
+        let shrunk = engine.amend_order(&market_key, first, "0x1", dec!(0.55), dec!(60)).unwrap().unwrap();
+        assert!(shrunk.kept_priority);
+        assert_eq!((shrunk.before.remaining_amount, shrunk.after.remaining_amount), (dec!(100), dec!(60)));
+        assert_eq!(position(first), 1);
+        let grown = engine.amend_order(&market_key, first, "0x1", dec!(0.55), dec!(80)).unwrap().unwrap();
+        assert!(!grown.kept_priority);
+        assert_eq!(position(first), 2);
+
+        // A price that would trade is refused
+        assert!(matches!(
+            engine.amend_order(&market_key, second, "0x2", dec!(0.65), dec!(100)),
+            Err(MatchingError::WouldTakeLiquidity)
+        ));
+        engine.amend_order(&market_key, second, "0x2", dec!(0.60), dec!(100)).unwrap().unwrap();
+        assert_eq!(engine.get_best_prices(&market_key).unwrap().0, Some(dec!(0.60)));
+
+        // Filled size stays filled
+        engine
+            .submit_order(Uuid::new_v4(), &market_key, "0x4", Side::Sell, OrderType::Market, dec!(30), None, 1, TimeInForce::GTC)
+            .unwrap();
+        assert!(matches!(
+            engine.amend_order(&market_key, second, "0x2", dec!(0.60), dec!(30)),
+            Err(MatchingError::InvalidAmount(_))
+        ));
+        let amended = engine.amend_order(&market_key, second, "0x2", dec!(0.60), dec!(50)).unwrap().unwrap();
+        assert_eq!((amended.after.original_amount, amended.after.remaining_amount), (dec!(50), dec!(20)));
+
+        engine.cancel_order(&market_key, first, "0x1").unwrap();
+        assert!(engine.amend_order(&market_key, first, "0x1", dec!(0.55), dec!(10)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_order_expiry() {
+        let engine = MatchingEngine::new();
+        let market_id = Uuid::new_v4();
+        let (yes, no) = (format!("{}:{}:yes", market_id, Uuid::new_v4()), format!("{}:{}:no", market_id, Uuid::new_v4()));
+        let submit = |symbol: &str, price| {
+            engine
+                .submit_order(Uuid::new_v4(), symbol, "0x1", Side::Buy, OrderType::Limit, dec!(10), Some(price), 1, TimeInForce::GTC)
+                .unwrap()
+                .order_id
+        };
+        let expiring = submit(&yes, dec!(0.4));
+        let filled = submit(&yes, dec!(0.5));
+        let resting = submit(&no, dec!(0.3));
+        engine.set_order_expiry(&yes, expiring, 1_000);
+        engine.set_order_expiry(&yes, filled, 1_000);
+        engine
+            .submit_order(Uuid::new_v4(), &yes, "0x2", Side::Sell, OrderType::Market, dec!(10), None, 1, TimeInForce::GTC)
+            .unwrap();
+
+        assert!(engine.expiring_books(999).is_empty());
+        assert!(engine.expire_orders(&yes, 999).is_empty());
+        assert_eq!(engine.order_expiry(&expiring), Some(1_000));
+        assert_eq!(engine.expiring_books(1_000), std::slice::from_ref(&yes));
+        assert!(engine.expire_orders(&no, 1_000).is_empty());
+        let expired = engine.expire_orders(&yes, 1_000);
+        assert_eq!(expired.len(), 1);
+        assert_eq!((expired[0].0.as_str(), expired[0].1.id), (yes.as_str(), expiring));
+        assert_eq!(engine.order_expiry(&filled), None);
+        assert!(engine.expiring_books(2_000).is_empty());
+
+        // Market close takes everything off, books stay
+        let closed = engine.expire_market(market_id);
+        assert_eq!(closed.iter().map(|(_, entry)| entry.id).collect::<Vec<_>>(), [resting]);
+        assert!(engine.get_orderbook(&no, 10).unwrap().bids.is_empty());
+    }
+
+    #[test]
+    fn test_journal_sees_every_book_change() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+        let mut journal = engine.attach_journal().unwrap();
+        assert!(engine.attach_journal().is_none());
+        let submit = |user: &str, side, amount, stp| {
+            engine
+                .submit_order_with_stp(Uuid::new_v4(), &market_key, user, side, OrderType::Limit, amount, Some(dec!(0.5)), 1, TimeInForce::GTC, stp)
+                .unwrap()
+                .order_id
+        };
+
+        let maker = submit("0x1", Side::Sell, dec!(100), SelfTradePrevention::default());
+        submit("0x2", Side::Buy, dec!(30), SelfTradePrevention::default());
+        submit("0x1", Side::Buy, dec!(20), SelfTradePrevention::Decrement);
+        engine.cancel_order(&market_key, maker, "0x1").unwrap();
+        // Never rested, so never journaled
+        engine
+            .submit_order(Uuid::new_v4(), &market_key, "0x3", Side::Buy, OrderType::Market, dec!(10), None, 1, TimeInForce::GTC)
+            .unwrap();
+
+        let mut changes = Vec::new();
+        while let Ok(event) = journal.try_recv() {
+            changes.push((event.order_id, event.kind, event.remaining_amount, event.original_amount));
+        }
+        assert_eq!(
+            changes,
+            [
+                (maker, OrderEventKind::Accepted, dec!(100), dec!(100)),
+                (maker, OrderEventKind::Fill, dec!(70), dec!(100)),
+                (maker, OrderEventKind::Amended, dec!(50), dec!(80)),
+                (maker, OrderEventKind::Cancelled, dec!(50), dec!(80)),
+            ]
+        );
+        assert_eq!(engine.journaled(), 4);
+    }
+
+    #[test]
+    fn test_deltas_rebuild_book() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+        let mut deltas = engine.subscribe_orderbook_deltas();
+        let submit = |user: &str, side, amount, price| {
+            engine.submit_order(Uuid::new_v4(), &market_key, user, side, OrderType::Limit, amount, Some(price), 1, TimeInForce::GTC).unwrap();
+        };
+
+        submit("0x1", Side::Buy, dec!(100), dec!(0.5));
+        let snapshot = engine.sequenced_depth(&market_key).unwrap();
+        assert_eq!(snapshot.sequence, 1);
+        submit("0x2", Side::Buy, dec!(50), dec!(0.4));
+        submit("0x3", Side::Sell, dec!(100), dec!(0.5));
+        submit("0x3", Side::Sell, dec!(30), dec!(0.7));
+
+        let mut bids: BTreeMap<Decimal, Decimal> = snapshot.bids.iter().copied().collect();
+        let mut asks: BTreeMap<Decimal, Decimal> = snapshot.asks.iter().copied().collect();
+        let mut actions = Vec::new();
+        while let Ok(delta) = deltas.try_recv() {
+            if delta.symbol != market_key || delta.sequence <= snapshot.sequence {
+                continue;
+            }
+            assert_eq!(delta.sequence, snapshot.sequence + actions.len() as u64 + 1);
+            for change in delta.changes.iter() {
+                let side = if change.side == Side::Buy { &mut bids } else { &mut asks };
+                match change.action {
+                    LevelAction::Remove => side.remove(&change.price),
+                    _ => side.insert(change.price, change.size),
+                };
+            }
+            actions.push(delta.changes.iter().map(|c| (c.price, c.action)).collect::<Vec<_>>());
+        }
+        assert_eq!(
+            actions,
+            [
+                vec![(dec!(0.4), LevelAction::Add)],
+                vec![(dec!(0.5), LevelAction::Remove)],
+                vec![(dec!(0.7), LevelAction::Add)],
+            ]
+        );
+        let book = engine.get_orderbook_ref(&market_key).unwrap();
+        assert_eq!(bids.into_iter().rev().collect::<Vec<_>>(), book.bid_levels());
+        assert_eq!(asks.into_iter().collect::<Vec<_>>(), book.ask_levels());
+
+        // Closing empties the book as the next delta of the same sequence
+        engine.close_market(OrderbookSnapshot::parse_market_key(&market_key).unwrap().0);
+        let closed = engine.sequenced_depth(&market_key).unwrap();
+        assert_eq!((closed.sequence, closed.bids.len(), closed.asks.len()), (5, 0, 0));
+    }
+
+    #[test]
+    fn test_orderbook_snapshot() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+
+        // Add orders with probability prices
+        engine.submit_order(Uuid::new_v4(), &market_key, "0x1", Side::Buy, OrderType::Limit, dec!(100.0), Some(dec!(0.55)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &market_key, "0x2", Side::Buy, OrderType::Limit, dec!(200.0), Some(dec!(0.50)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &market_key, "0x3", Side::Sell, OrderType::Limit, dec!(150.0), Some(dec!(0.65)), 1, TimeInForce::GTC).unwrap();
+
+        let snapshot = engine.get_orderbook(&market_key, 10).unwrap();
+
+        assert!(snapshot.symbol.contains("yes")); // market_key contains share type
+        assert_eq!(snapshot.bids.len(), 2);
+        assert_eq!(snapshot.asks.len(), 1);
+    }
+
+    #[test]
+    fn test_trade_history() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+
+        // Create trades
+        engine.submit_order(Uuid::new_v4(), &market_key, "0x1", Side::Sell, OrderType::Limit, dec!(100.0), Some(dec!(0.60)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &market_key, "0x2", Side::Buy, OrderType::Limit, dec!(100.0), Some(dec!(0.60)), 1, TimeInForce::GTC).unwrap();
+
+        let trades = engine.get_trades(&market_key, &TradeHistoryQuery::default());
+        assert_eq!(trades.total_count, 1);
+    }
+
+    #[test]
+    fn test_order_history() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+
+        engine.submit_order(Uuid::new_v4(), &market_key, "0x1234", Side::Buy, OrderType::Limit, dec!(100.0), Some(dec!(0.55)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &market_key, "0x1234", Side::Sell, OrderType::Limit, dec!(50.0), Some(dec!(0.65)), 1, TimeInForce::GTC).unwrap();
+
+        let orders = engine.get_orders("0x1234", &OrderHistoryQuery::default());
+        assert_eq!(orders.total_count, 2);
+    }
+
+    #[test]
+    fn test_stats() {
+        let engine = MatchingEngine::new();
+        let market_key1 = create_market_key();
+        let market_key2 = create_market_key();
+
+        engine.submit_order(Uuid::new_v4(), &market_key1, "0x1", Side::Buy, OrderType::Limit, dec!(100.0), Some(dec!(0.55)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &market_key2, "0x2", Side::Sell, OrderType::Limit, dec!(200.0), Some(dec!(0.65)), 1, TimeInForce::GTC).unwrap();
+
+        let stats = engine.stats();
+        // With Mint/Merge support, complement orderbooks are also created
+        // So we have 4 orderbooks: 2 original + 2 complements (Yes <-> No)
+        assert_eq!(stats.symbols_count, 4);
+        assert_eq!(stats.total_orders_in_book, 2);
+    }
+
+    #[test]
+    fn test_mint_matching() {
+        let engine = MatchingEngine::new();
+
+        // Create market keys for Yes and No
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_market_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_market_key = format!("{}:{}:no", market_id, outcome_id);
+
+        // User A submits buy order for No shares at 0.40
+        let order_a = engine.submit_order(
+            Uuid::new_v4(),
+            &no_market_key,
+            "0xUserA",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.40)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        // Order should be open (no match yet)
+        assert_eq!(order_a.status, OrderStatus::Open);
+        assert_eq!(order_a.filled_amount, dec!(0));
+
+        // User B submits buy order for Yes shares at 0.65
+        // Combined: 0.65 + 0.40 = 1.05 >= 1.0, should trigger MINT
+        let order_b = engine.submit_order(
+            Uuid::new_v4(),
+            &yes_market_key,
+            "0xUserB",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.65)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        // Should be filled via MINT matching
+        assert_eq!(order_b.status, OrderStatus::Filled);
+        assert_eq!(order_b.filled_amount, dec!(100.0));
+        assert_eq!(order_b.trades.len(), 1);
+        assert_eq!(order_b.trades[0].match_type, MatchType::Mint);
+    }
+
+    #[test]
+    fn test_complement_fills_leave_the_improvement_to_the_taker() {
+        let engine = MatchingEngine::new();
+        let yes_key = create_market_key();
+        let no_key = MatchingEngine::get_complement_market_key(&yes_key).unwrap();
+        engine.submit_order(Uuid::new_v4(), &no_key, "0xB", Side::Buy, OrderType::Limit, dec!(30), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &no_key, "0xC", Side::Buy, OrderType::Limit, dec!(30), Some(dec!(0.38)), 1, TimeInForce::GTC).unwrap();
+
+        // Each No bid keeps its price; the Yes buyer pays the rest of 1
+        let mint = engine.submit_order(Uuid::new_v4(), &yes_key, "0xA", Side::Buy, OrderType::Limit, dec!(60), Some(dec!(0.65)), 1, TimeInForce::GTC).unwrap();
+        let prices: Vec<_> = mint.trades.iter().map(|t| (t.match_type, t.price, t.amount)).collect();
+        assert_eq!(prices, vec![(MatchType::Mint, dec!(0.60), dec!(30)), (MatchType::Mint, dec!(0.62), dec!(30))]);
+        assert_eq!(mint.average_price, Some(dec!(0.61)));
+
+        // Likewise a Yes seller receives 1 less the No ask
+        engine.submit_order(Uuid::new_v4(), &no_key, "0xB", Side::Sell, OrderType::Limit, dec!(30), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        let merge = engine.submit_order(Uuid::new_v4(), &yes_key, "0xA", Side::Sell, OrderType::Limit, dec!(30), Some(dec!(0.55)), 1, TimeInForce::GTC).unwrap();
+        assert_eq!(merge.trades.len(), 1);
+        assert_eq!((merge.trades[0].match_type, merge.trades[0].price), (MatchType::Merge, dec!(0.60)));
+    }
+
+    #[test]
+    fn test_self_trade_prevention_through_mint() {
+        let engine = MatchingEngine::new();
+        let yes_key = create_market_key();
+        let no_key = MatchingEngine::get_complement_market_key(&yes_key).unwrap();
+        let own_no = Uuid::new_v4();
+        engine.submit_order(own_no, &no_key, "0xA", Side::Buy, OrderType::Limit, dec!(40), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &no_key, "0xB", Side::Buy, OrderType::Limit, dec!(50), Some(dec!(0.38)), 1, TimeInForce::GTC).unwrap();
+
+        // A FOK would have to skip its own No bid
+        let fok = engine.submit_order(Uuid::new_v4(), &yes_key, "0xa", Side::Buy, OrderType::Limit, dec!(50), Some(dec!(0.65)), 1, TimeInForce::FOK);
+        assert!(matches!(fok, Err(MatchingError::InsufficientLiquidity)));
+
+        // Decrement: the own No bid (40) is cancelled and the order shrinks to
+        // 60, of which 50 mint with 0xB and 10 rest
+        let result = engine
+            .submit_order_with_stp(Uuid::new_v4(), &yes_key, "0xa", Side::Buy, OrderType::Limit, dec!(100), Some(dec!(0.65)), 1, TimeInForce::GTC, SelfTradePrevention::Decrement)
+            .unwrap();
+        assert_eq!(result.decremented, dec!(40));
+        assert_eq!((result.filled_amount, result.remaining_amount), (dec!(50), dec!(10)));
+        assert_eq!(result.status, OrderStatus::PartiallyFilled);
+        assert!(result.trades.iter().all(|t| t.match_type == MatchType::Mint && t.maker_address == "0xB"));
+        assert_eq!(result.self_trades.len(), 1);
+        assert_eq!((result.self_trades[0].symbol.as_str(), result.self_trades[0].order.id), (no_key.as_str(), own_no));
+        assert!(result.self_trades[0].cancelled());
+        assert!(engine.get_orderbook_ref(&no_key).unwrap().get_order(&own_no).is_none());
+
+        // Default: meeting its own bid cancels the rest of the new order
+        let yes_key = create_market_key();
+        let no_key = MatchingEngine::get_complement_market_key(&yes_key).unwrap();
+        engine.submit_order(own_no, &no_key, "0xA", Side::Buy, OrderType::Limit, dec!(40), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        let result = engine.submit_order(Uuid::new_v4(), &yes_key, "0xA", Side::Buy, OrderType::Limit, dec!(20), Some(dec!(0.70)), 1, TimeInForce::GTC).unwrap();
+        assert_eq!(result.status, OrderStatus::Cancelled);
+        assert!(result.trades.is_empty() && result.self_trades.is_empty());
+    }
+
+    #[test]
+    fn test_fok_ignores_own_orders_it_would_not_reach() {
+        let engine = MatchingEngine::new();
+        let key = create_market_key();
+        // 0xB offers 6 at 0.55 and 4 at 0.58; the caller's own ask sits
+        // behind 0xB at 0.58 and alone at 0.59
+        engine.submit_order(Uuid::new_v4(), &key, "0xB", Side::Sell, OrderType::Limit, dec!(6), Some(dec!(0.55)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &key, "0xB", Side::Sell, OrderType::Limit, dec!(4), Some(dec!(0.58)), 1, TimeInForce::GTC).unwrap();
+        let own_ask = Uuid::new_v4();
+        engine.submit_order(own_ask, &key, "0xA", Side::Sell, OrderType::Limit, dec!(5), Some(dec!(0.58)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &key, "0xA", Side::Sell, OrderType::Limit, dec!(5), Some(dec!(0.59)), 1, TimeInForce::GTC).unwrap();
+
+        // Reaching the own ask would stop the order first
+        let fok = engine.submit_order(Uuid::new_v4(), &key, "0xa", Side::Buy, OrderType::Limit, dec!(11), Some(dec!(0.60)), 1, TimeInForce::FOK);
+        assert!(matches!(fok, Err(MatchingError::InsufficientLiquidity)));
+
+        // 10 from 0xB fill it before the own asks come up
+        let fok = engine
+            .submit_order(Uuid::new_v4(), &key, "0xa", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.60)), 1, TimeInForce::FOK)
+            .unwrap();
+        assert_eq!((fok.status, fok.filled_amount), (OrderStatus::Filled, dec!(10)));
+        assert!(fok.trades.iter().all(|t| t.maker_address == "0xB"));
+        assert!(fok.self_trades.is_empty());
+        assert!(engine.get_orderbook_ref(&key).unwrap().get_order(&own_ask).is_some());
+    }
+
+    #[test]
+    fn test_cancel_all_after() {
+        let engine = MatchingEngine::new();
+        let symbol = format!("{}:{}:yes", Uuid::new_v4(), Uuid::new_v4());
+        engine.submit_order(Uuid::new_v4(), &symbol, "0xMM", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.4)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &symbol, "0xmm", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.6)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &symbol, "0xother", Side::Sell, OrderType::Limit, dec!(5), Some(dec!(0.7)), 1, TimeInForce::GTC).unwrap();
+
+        assert_eq!(engine.cancel_all_after("0xMM", 5_000, 1_000), Some(6_000));
+        assert!(engine.fire_cancel_all_after(5_999).is_empty());
+
+        // Refreshing pushes the deadline out
+        engine.cancel_all_after("0xmm", 5_000, 4_000);
+        assert!(engine.fire_cancel_all_after(6_000).is_empty());
+
+        let fired = engine.fire_cancel_all_after(9_000);
+        assert_eq!(fired.len(), 1);
+        assert_eq!((fired[0].0.as_str(), fired[0].1.len()), ("0xmm", 2));
+        assert_eq!(engine.cancel_all_deadline("0xmm"), None);
+
+        let book = engine.get_orderbook(&symbol, 10).unwrap();
+        assert!(book.bids.is_empty());
+        assert_eq!(book.asks.len(), 1);
+
+        // Book by book: the switch is disarmed, the caller cancels
+        engine.submit_order(Uuid::new_v4(), &symbol, "0xMM", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.4)), 1, TimeInForce::GTC).unwrap();
+        engine.cancel_all_after("0xmm", 1_000, 9_000);
+        assert_eq!(engine.take_fired_cancel_all_after(10_000), ["0xmm"]);
+        assert_eq!(engine.user_books("0xmm"), std::slice::from_ref(&symbol));
+        assert_eq!(engine.cancel_book_orders_for_user(&symbol, "0xmm").len(), 1);
+        assert!(engine.user_books("0xmm").is_empty());
+
+        // Disarming prevents the cancel
+        engine.submit_order(Uuid::new_v4(), &symbol, "0xother", Side::Buy, OrderType::Limit, dec!(1), Some(dec!(0.3)), 1, TimeInForce::GTC).unwrap();
+        engine.cancel_all_after("0xother", 1_000, 0);
+        assert_eq!(engine.cancel_all_after("0xother", 0, 500), None);
+        assert!(engine.fire_cancel_all_after(10_000).is_empty());
+    }
+
+    #[test]
+    fn test_rejected_and_expired_orders() {
+        let engine = MatchingEngine::new();
+        let mut events = engine.subscribe_orders();
+        let symbol = format!("{}:{}:yes", Uuid::new_v4(), Uuid::new_v4());
+
+        let bad_id = Uuid::new_v4();
+        let err = engine
+            .submit_order(bad_id, &symbol, "0xuser", Side::Buy, OrderType::Limit, dec!(0), Some(dec!(0.5)), 1, TimeInForce::GTC)
+            .unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::InvalidAmount);
+        let rejected = events.try_recv().unwrap();
+        assert_eq!((rejected.order_id, rejected.kind, rejected.status), (bad_id, OrderEventKind::Rejected, OrderStatus::Rejected));
+        assert_eq!(rejected.reject_reason, Some(RejectReason::InvalidAmount));
+        let history = engine.get_orders("0xuser", &OrderHistoryQuery::default());
+        assert_eq!(history.orders[0].reject_reason.as_deref(), Some("INVALID_AMOUNT"));
+
+        // A market order with nothing to match expires rather than resting
+        let result = engine
+            .submit_order(Uuid::new_v4(), &symbol, "0xuser", Side::Buy, OrderType::Market, dec!(5), None, 1, TimeInForce::GTC)
+            .unwrap();
+        assert_eq!(result.status, OrderStatus::Expired);
+    }
+
+    #[test]
+    fn test_fok_and_ioc_never_rest() {
+        let engine = MatchingEngine::new();
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_key = format!("{}:{}:no", market_id, outcome_id);
+        // 6 Yes offered at 0.60, and 4 No bid at 0.42 (mints Yes at 0.58)
+        engine.submit_order(Uuid::new_v4(), &yes_key, "0xmaker", Side::Sell, OrderType::Limit, dec!(6), Some(dec!(0.60)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &no_key, "0xmaker", Side::Buy, OrderType::Limit, dec!(4), Some(dec!(0.42)), 1, TimeInForce::GTC).unwrap();
+        assert_eq!(engine.fillable_amount(&yes_key, Side::Buy, Some(dec!(0.60))), dec!(10));
+        assert_eq!(engine.fillable_amount(&yes_key, Side::Buy, Some(dec!(0.59))), dec!(4));
+
+        // FOK for more than is there is rejected and fills nothing
+        let err = engine
+            .submit_order(Uuid::new_v4(), &yes_key, "0xtaker", Side::Buy, OrderType::Limit, dec!(11), Some(dec!(0.60)), 1, TimeInForce::FOK)
+            .unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::NoLiquidity);
+        assert_eq!(engine.fillable_amount(&yes_key, Side::Buy, Some(dec!(0.60))), dec!(10));
+
+        // FOK that fits fills across both routes
+        let fok = engine
+            .submit_order(Uuid::new_v4(), &yes_key, "0xtaker", Side::Buy, OrderType::Limit, dec!(7), Some(dec!(0.60)), 1, TimeInForce::FOK)
+            .unwrap();
+        assert_eq!((fok.status, fok.filled_amount), (OrderStatus::Filled, dec!(7)));
+
+        // IOC fills the remaining 3 and cancels the rest instead of resting
+        let ioc_id = Uuid::new_v4();
+        let ioc = engine
+            .submit_order(ioc_id, &yes_key, "0xtaker", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.60)), 1, TimeInForce::IOC)
+            .unwrap();
+        assert_eq!((ioc.status, ioc.filled_amount, ioc.remaining_amount), (OrderStatus::Cancelled, dec!(3), dec!(2)));
+        let book = engine.get_orderbook(&yes_key, 10).unwrap();
+        assert!(book.bids.is_empty() && book.asks.is_empty());
+        assert!(!engine.cancel_order(&yes_key, ioc_id, "0xtaker").unwrap_or(false));
+    }
+
+    #[test]
+    fn test_post_only_never_takes() {
+        let engine = MatchingEngine::new();
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_key = format!("{}:{}:no", market_id, outcome_id);
+        // Yes offered at 0.60, No bid at 0.35 (mints Yes at 0.65)
+        engine.submit_order(Uuid::new_v4(), &yes_key, "0xmaker", Side::Sell, OrderType::Limit, dec!(5), Some(dec!(0.60)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &no_key, "0xmaker", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.35)), 1, TimeInForce::GTC).unwrap();
+
+        // Crossing the Yes ask, or the No bid through a mint, is refused
+        for price in [dec!(0.60), dec!(0.65)] {
+            let err = engine
+                .submit_order(Uuid::new_v4(), &yes_key, "0xtaker", Side::Buy, OrderType::Limit, dec!(1), Some(price), 1, TimeInForce::PostOnly)
+                .unwrap_err();
+            assert_eq!(err.reject_reason(), RejectReason::PostOnlyWouldTake);
+        }
+        let err = engine
+            .submit_order(Uuid::new_v4(), &yes_key, "0xtaker", Side::Buy, OrderType::Market, dec!(1), None, 1, TimeInForce::PostOnly)
+            .unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::PostOnlyWouldTake);
+
+        // Inside the spread it rests
+        let maker = engine
+            .submit_order(Uuid::new_v4(), &yes_key, "0xtaker", Side::Buy, OrderType::Limit, dec!(1), Some(dec!(0.59)), 1, TimeInForce::PostOnly)
+            .unwrap();
+        assert_eq!((maker.status, maker.filled_amount), (OrderStatus::Open, dec!(0)));
+        assert_eq!(engine.get_orderbook(&yes_key, 10).unwrap().bids, vec![["0.59".to_string(), "1".to_string()]]);
+    }
+
+    #[test]
+    fn test_resting_orders_report_queue_position() {
+        let engine = MatchingEngine::new();
+        let key = format!("{}:{}:yes", Uuid::new_v4(), Uuid::new_v4());
+        let first = engine.submit_order(Uuid::new_v4(), &key, "0xa", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        let second = engine.submit_order(Uuid::new_v4(), &key, "0xb", Side::Buy, OrderType::Limit, dec!(3), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        assert_eq!(
+            first.queue_position,
+            Some(QueuePosition { price: dec!(0.40), position: 1, ahead_amount: dec!(0), level_amount: dec!(5) })
+        );
+        assert_eq!(
+            second.queue_position,
+            Some(QueuePosition { price: dec!(0.40), position: 2, ahead_amount: dec!(5), level_amount: dec!(8) })
+        );
+
+        // A partial fill rests with the rest of its level ahead of it
+        let sell = engine.submit_order(Uuid::new_v4(), &key, "0xc", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        assert_eq!(sell.filled_amount, dec!(8));
+        assert_eq!(sell.queue_position.map(|q| (q.price, q.position, q.level_amount)), Some((dec!(0.40), 1, dec!(2))));
+
+        // Fully filled orders do not rest
+        let taker = engine.submit_order(Uuid::new_v4(), &key, "0xd", Side::Buy, OrderType::Limit, dec!(2), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        assert_eq!(taker.queue_position, None);
+    }
+
+    #[test]
+    fn test_order_events_reach_makers() {
+        let engine = MatchingEngine::new();
+        let mut events = engine.subscribe_orders();
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_key = format!("{}:{}:no", market_id, outcome_id);
+
+        let ask_id = Uuid::new_v4();
+        engine.submit_order(ask_id, &yes_key, "0xmaker", Side::Sell, OrderType::Limit, dec!(4), Some(dec!(0.6)), 1, TimeInForce::GTC).unwrap();
+        let no_bid_id = Uuid::new_v4();
+        engine.submit_order(no_bid_id, &no_key, "0xminter", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.45)), 1, TimeInForce::GTC).unwrap();
+        for id in [ask_id, no_bid_id] {
+            let accepted = events.try_recv().unwrap();
+            assert_eq!((accepted.order_id, accepted.kind, accepted.status), (id, OrderEventKind::Accepted, OrderStatus::Open));
+        }
+
+        // Fills the 4 ask (normal), then mints 5 against the No bid
+        let taker_id = Uuid::new_v4();
+        let result = engine
+            .submit_order(taker_id, &yes_key, "0xtaker", Side::Buy, OrderType::Limit, dec!(9), Some(dec!(0.6)), 1, TimeInForce::GTC)
+            .unwrap();
+        assert_eq!(result.filled_amount, dec!(9));
+
+        let ask_fill = events.try_recv().unwrap();
+        assert_eq!((ask_fill.order_id, ask_fill.kind), (ask_id, OrderEventKind::Fill));
+        assert_eq!(ask_fill.user_address, "0xmaker");
+        assert_eq!((ask_fill.status, ask_fill.filled_amount, ask_fill.remaining_amount), (OrderStatus::Filled, dec!(4), dec!(0)));
+        assert_eq!(ask_fill.trade_id, Some(result.trades[0].trade_id));
+
+        let mint_fill = events.try_recv().unwrap();
+        assert_eq!((mint_fill.order_id, mint_fill.status, mint_fill.symbol.as_str()), (no_bid_id, OrderStatus::PartiallyFilled, no_key.as_str()));
+        assert_eq!((mint_fill.user_address.as_str(), mint_fill.remaining_amount), ("0xminter", dec!(5)));
+
+        let taker = events.try_recv().unwrap();
+        assert_eq!((taker.order_id, taker.kind, taker.status), (taker_id, OrderEventKind::Accepted, OrderStatus::Filled));
+
+        // Maker history follows the fill
+        let history = engine.get_orders("0xminter", &OrderHistoryQuery::default());
+        assert_eq!((history.orders[0].remaining_amount.as_str(), history.orders[0].status.as_str()), ("5", "partially_filled"));
+
+        engine.cancel_order(&no_key, no_bid_id, "0xminter").unwrap();
+        let cancelled = events.try_recv().unwrap();
+        assert_eq!((cancelled.kind, cancelled.status, cancelled.remaining_amount), (OrderEventKind::Cancelled, OrderStatus::Cancelled, dec!(5)));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_merge_matching() {
+        let engine = MatchingEngine::new();
+
+        // Create market keys for Yes and No
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_market_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_market_key = format!("{}:{}:no", market_id, outcome_id);
+
+        // User A submits sell order for No shares at 0.35
+        let order_a = engine.submit_order(
+            Uuid::new_v4(),
+            &no_market_key,
+            "0xUserA",
+            Side::Sell,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.35)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        // Order should be open (no match yet)
+        assert_eq!(order_a.status, OrderStatus::Open);
+        assert_eq!(order_a.filled_amount, dec!(0));
+
+        // User B submits sell order for Yes shares at 0.60
+        // Combined: 0.60 + 0.35 = 0.95 <= 1.0, should trigger MERGE
+        let order_b = engine.submit_order(
+            Uuid::new_v4(),
+            &yes_market_key,
+            "0xUserB",
+            Side::Sell,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.60)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        // Should be filled via MERGE matching
+        assert_eq!(order_b.status, OrderStatus::Filled);
+        assert_eq!(order_b.filled_amount, dec!(100.0));
+        assert_eq!(order_b.trades.len(), 1);
+        assert_eq!(order_b.trades[0].match_type, MatchType::Merge);
+    }
+
+    #[test]
+    fn test_mint_skipped_when_complement_matching_disabled() {
+        let engine = MatchingEngine::new();
+        engine.set_complement_matching(false);
+
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_market_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_market_key = format!("{}:{}:no", market_id, outcome_id);
+
+        engine.submit_order(
+            Uuid::new_v4(),
+            &no_market_key,
+            "0xUserA",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.40)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        // Would MINT (0.65 + 0.40 >= 1.0) but complement matching is off
+        let order_b = engine.submit_order(
+            Uuid::new_v4(),
+            &yes_market_key,
+            "0xUserB",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.65)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        assert_eq!(order_b.status, OrderStatus::Open);
+        assert!(order_b.trades.is_empty());
+    }
+
+    #[test]
+    fn test_mint_not_triggered_when_prices_too_low() {
+        let engine = MatchingEngine::new();
+
+        // Create market keys for Yes and No
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_market_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_market_key = format!("{}:{}:no", market_id, outcome_id);
+
+        // User A submits buy order for No shares at 0.30
+        engine.submit_order(
+            Uuid::new_v4(),
+            &no_market_key,
+            "0xUserA",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.30)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        // User B submits buy order for Yes shares at 0.60
+        // Combined: 0.60 + 0.30 = 0.90 < 1.0, should NOT trigger MINT
+        let order_b = engine.submit_order(
+            Uuid::new_v4(),
+            &yes_market_key,
+            "0xUserB",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0),
+            Some(dec!(0.60)),
+            1,
+            TimeInForce::GTC,
+        ).unwrap();
+
+        // Should be open (no MINT match because prices sum to < 1.0)
+        assert_eq!(order_b.status, OrderStatus::Open);
+        assert_eq!(order_b.filled_amount, dec!(0));
+        assert!(order_b.trades.is_empty());
+    }
+
+    #[test]
+    fn test_quote_combines_direct_and_mint_routes() {
+        let engine = MatchingEngine::new();
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_key = format!("{}:{}:no", market_id, outcome_id);
+
+        let rest = |key: &str, side, amount, price| {
+            engine
+                .submit_order(Uuid::new_v4(), key, "0xMaker", side, OrderType::Limit, amount, Some(price), 1, TimeInForce::GTC)
+                .unwrap();
+        };
+        rest(&yes_key, Side::Sell, dec!(10), dec!(0.60));
+        rest(&yes_key, Side::Sell, dec!(10), dec!(0.70));
+        // No bid at 0.35 mints Yes at 0.65, between the two direct asks
+        rest(&no_key, Side::Buy, dec!(5), dec!(0.35));
+
+        let quote = engine.quote(&yes_key, Side::Buy, dec!(20));
+        assert_eq!((quote.filled, quote.unfilled), (dec!(20), dec!(0)));
+        assert_eq!(quote.notional, dec!(6.00) + dec!(3.25) + dec!(3.50));
+        assert_eq!(quote.limit_price, Some(dec!(0.70)));
+        assert_eq!(quote.routes.len(), 2);
+        assert_eq!((quote.routes[0].route, quote.routes[0].amount), (MatchType::Normal, dec!(15)));
+        assert_eq!((quote.routes[1].route, quote.routes[1].average_price), (MatchType::Mint, Some(dec!(0.65))));
+
+        // Without complement matching only the direct book counts, and the
+        // quote reports what it cannot fill
+        engine.set_complement_matching(false);
+        let quote = engine.quote(&yes_key, Side::Buy, dec!(30));
+        assert_eq!((quote.filled, quote.unfilled), (dec!(20), dec!(10)));
+        assert_eq!(quote.routes.len(), 1);
+    }
+
+    #[test]
+    fn test_quoted_mid_includes_synthetic_levels() {
+        let engine = MatchingEngine::new();
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let yes_key = format!("{}:{}:yes", market_id, outcome_id);
+        let no_key = format!("{}:{}:no", market_id, outcome_id);
+
+        let rest = |key: &str, side, price| {
+            engine
+                .submit_order(Uuid::new_v4(), key, "0xMaker", side, OrderType::Limit, dec!(10), Some(price), 1, TimeInForce::GTC)
+                .unwrap();
+        };
+        rest(&yes_key, Side::Buy, dec!(0.40));
+        assert_eq!(engine.quoted_mid(&yes_key), None);
+        rest(&yes_key, Side::Sell, dec!(0.60));
+        assert_eq!(engine.quoted_mid(&yes_key), Some(dec!(0.50)));
+
+        // No bid at 0.45 offers Yes at 0.55 through minting
+        rest(&no_key, Side::Buy, dec!(0.45));
+        assert_eq!(engine.quoted_mid(&yes_key), Some(dec!(0.475)));
+
+        engine.set_complement_matching(false);
+        assert_eq!(engine.quoted_mid(&yes_key), Some(dec!(0.50)));
+    }
+
+    #[test]
+    fn test_close_market_frees_books() {
+        let engine = MatchingEngine::new();
+        let market_id = Uuid::new_v4();
+        let yes_key = format!("{}:{}:yes", market_id, Uuid::new_v4());
+        let other_key = create_market_key();
+        let mut events = engine.subscribe_orders();
+
+        let order_id = Uuid::new_v4();
+        engine.submit_order(order_id, &yes_key, "0xA", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.4)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &other_key, "0xB", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.4)), 1, TimeInForce::GTC).unwrap();
+        while events.try_recv().is_ok() {}
+
+        let cancelled = engine.close_market(market_id);
+        assert_eq!(cancelled.iter().map(|o| o.id).collect::<Vec<_>>(), vec![order_id]);
+        assert!(engine.get_orderbook(&yes_key, 10).is_err());
+        assert!(engine.get_orderbook(&other_key, 10).is_ok());
+
+        let event = events.try_recv().unwrap();
+        assert_eq!((event.order_id, event.kind), (order_id, OrderEventKind::Cancelled));
+    }
+
+    #[test]
+    fn test_namespaced_books_are_separate() {
+        let engine = MatchingEngine::new();
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let no_key = format!("{}:{}:no", market_id, outcome_id);
+        let paper_yes = OrderbookSnapshot::namespaced_key("paper", &format!("{}:{}:yes", market_id, outcome_id));
+        let paper_no = OrderbookSnapshot::namespaced_key("paper", &no_key);
+
+        // A real No bid does not mint against a paper Yes bid...
+        engine.submit_order(Uuid::new_v4(), &no_key, "0xA", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.4)), 1, TimeInForce::GTC).unwrap();
+        let taker = engine
+            .submit_order(Uuid::new_v4(), &paper_yes, "0xB", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.65)), 1, TimeInForce::GTC)
+            .unwrap();
+        assert_eq!(taker.status, OrderStatus::Open);
+
+        // ...but a paper No bid does
+        let taker = engine
+            .submit_order(Uuid::new_v4(), &paper_no, "0xC", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.4)), 1, TimeInForce::GTC)
+            .unwrap();
+        assert_eq!(taker.status, OrderStatus::Filled);
+        assert_eq!(taker.trades[0].match_type, MatchType::Mint);
+        assert_eq!(taker.trades[0].market_id, market_id);
+
+        // Closing the market clears its books in every namespace
+        let cancelled = engine.close_market(market_id);
+        assert_eq!(cancelled.len(), 1);
+        assert!(engine.orderbook_keys().is_empty());
+    }
+
+    #[test]
+    fn test_restore_does_not_match_until_resolved() {
+        let engine = MatchingEngine::new();
+        let yes_key = create_market_key();
+        let entry = |id, user: &str, side, price, timestamp| OrderEntry {
+            id,
+            user_address: user.to_string(),
+            price,
+            original_amount: dec!(10),
+            remaining_amount: dec!(10),
+            side,
+            time_in_force: TimeInForce::GTC,
+            timestamp,
+        };
+
+        // A crossed pair is restored as-is
+        let (ask_id, bid_id) = (Uuid::new_v4(), Uuid::new_v4());
+        engine.restore_order(&yes_key, entry(ask_id, "0xA", Side::Sell, dec!(0.5), 1)).unwrap();
+        engine.restore_order(&yes_key, entry(bid_id, "0xB", Side::Buy, dec!(0.6), 2)).unwrap();
+        assert_eq!(engine.get_best_prices(&yes_key).unwrap(), (Some(dec!(0.6)), Some(dec!(0.5))));
+        assert_eq!(engine.crossed_books(), vec![yes_key.clone()]);
+
+        // The later order takes the earlier one at the resting price
+        let results = engine.resolve_crossed(&yes_key);
+        assert_eq!(results.len(), 1);
+        let (key, taker, result) = &results[0];
+        assert_eq!((key, taker.id, result.status), (&yes_key, bid_id, OrderStatus::Filled));
+        assert_eq!(result.trades[0].maker_order_id, ask_id);
+        assert_eq!(result.trades[0].price, dec!(0.5));
+        assert!(engine.crossed_books().is_empty());
+    }
+
+    #[test]
+    fn test_trades_sequenced_and_block_flagged() {
+        let engine = MatchingEngine::new().with_block_trade_threshold(dec!(100));
+        engine.seed_trade_sequence(41);
+        let market_key = create_market_key();
+
+        engine.submit_order(Uuid::new_v4(), &market_key, "0xA", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.5)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &market_key, "0xA", Side::Sell, OrderType::Limit, dec!(400), Some(dec!(0.6)), 1, TimeInForce::GTC).unwrap();
+        let result = engine
+            .submit_order(Uuid::new_v4(), &market_key, "0xB", Side::Buy, OrderType::Limit, dec!(410), Some(dec!(0.6)), 1, TimeInForce::GTC)
+            .unwrap();
+
+        let flags: Vec<(u64, bool)> = result.trades.iter().map(|t| (t.sequence, t.is_block_trade)).collect();
+        assert_eq!(flags, vec![(42, false), (43, true)]);
+        assert_eq!(engine.last_trade_sequence(), 43);
+    }
+
+    #[test]
+    fn test_market_sequences_per_key() {
+        let engine = MatchingEngine::new();
+        let market_key = create_market_key();
+        let other_key = create_market_key();
+        let mut trades = engine.subscribe_trades();
+        let mut updates = engine.subscribe_orderbook();
+
+        engine.submit_order(Uuid::new_v4(), &market_key, "0xA", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.5)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &other_key, "0xA", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.5)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &market_key, "0xA", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.6)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &other_key, "0xB", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.5)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &market_key, "0xB", Side::Buy, OrderType::Limit, dec!(15), Some(dec!(0.6)), 1, TimeInForce::GTC).unwrap();
+
+        let mut traded = Vec::new();
+        while let Ok(trade) = trades.try_recv() {
+            traded.push((trade.symbol == market_key, trade.market_sequence));
+        }
+        assert_eq!(traded, vec![(false, 1), (true, 1), (true, 2)]);
+
+        // Each key's updates count up from 1 without gaps
+        let mut last = std::collections::HashMap::new();
+        while let Ok(update) = updates.try_recv() {
+            let previous = last.insert(update.symbol.clone(), update.sequence).unwrap_or(0);
+            assert_eq!(update.sequence, previous + 1);
+        }
+        let sequence = engine.market_sequence(&market_key);
+        assert_eq!((sequence.trade, sequence.orderbook), (2, last[&market_key]));
+        assert_eq!(engine.market_sequence("unknown"), MarketSequence::default());
+    }
+
+    #[test]
+    fn test_price_band_and_circuit_breaker() {
+        let engine = MatchingEngine::new().with_price_guard(PriceGuardConfig {
+            band: Some(dec!(0.1)),
+            halt_move: Some(dec!(0.15)),
+            halt_window_ms: 60_000,
+        });
+        let market_id = Uuid::new_v4();
+        let market_key = format!("{}:{}:yes", market_id, Uuid::new_v4());
+        let mut halts = engine.subscribe_halts();
+        let trade = |price| {
+            engine.submit_order(Uuid::new_v4(), &market_key, "0xA", Side::Sell, OrderType::Limit, dec!(10), Some(price), 1, TimeInForce::GTC)?;
+            engine.submit_order(Uuid::new_v4(), &market_key, "0xB", Side::Buy, OrderType::Limit, dec!(10), Some(price), 1, TimeInForce::GTC)
+        };
+
+        trade(dec!(0.5)).unwrap();
+        // Banded around the last trade
+        let err = trade(dec!(0.65)).unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::OutsidePriceBand);
+        trade(dec!(0.6)).unwrap();
+        assert!(halts.try_recv().is_err());
+
+        // 0.5 -> 0.7 within the window halts the market
+        trade(dec!(0.7)).unwrap();
+        let halt = halts.try_recv().unwrap();
+        assert_eq!((halt.from_price, halt.to_price), (dec!(0.5), dec!(0.7)));
+        assert_eq!(engine.market_halt(market_id), Some(halt));
+        let err = trade(dec!(0.7)).unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::TradingHalted);
+
+        assert!(engine.resume_market(market_id).is_some());
+        assert!(engine.halts().is_empty());
+        trade(dec!(0.75)).unwrap();
+        assert!(halts.try_recv().is_err());
+    }
+
+    /// A registered categorical market with `n` outcomes; returns the Yes
+    /// key of each outcome
+    fn categorical_market(engine: &MatchingEngine, n: usize) -> Vec<String> {
+        let market_id = Uuid::new_v4();
+        let outcomes: Vec<Uuid> = (0..n).map(|_| Uuid::new_v4()).collect();
+        engine.register_outcome_set(market_id, outcomes.clone()).unwrap();
+        outcomes.iter().map(|outcome| format!("{}:{}:yes", market_id, outcome)).collect()
+    }
+
+    #[test]
+    fn test_set_mint_across_three_outcomes() {
+        let engine = MatchingEngine::new();
+        let keys = categorical_market(&engine, 3);
+        let bid_b = Uuid::new_v4();
+        engine.submit_order(bid_b, &keys[1], "0xB", Side::Buy, OrderType::Limit, dec!(100), Some(dec!(0.35)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &keys[2], "0xC", Side::Buy, OrderType::Limit, dec!(60), Some(dec!(0.30)), 1, TimeInForce::GTC).unwrap();
+
+        // 0.40 + 0.35 + 0.30 = 1.05 >= 1: 60 sets mint, the taker paying 0.35
+        let result = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Buy, OrderType::Limit, dec!(100), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        assert!(result.trades.is_empty());
+        assert_eq!(result.set_fills.len(), 1);
+        let fill = &result.set_fills[0];
+        assert_eq!((fill.match_type, fill.price, fill.amount), (MatchType::Mint, dec!(0.35), dec!(60)));
+        assert_eq!(fill.legs.iter().map(|l| (l.symbol.as_str(), l.price)).collect::<Vec<_>>(), vec![(keys[1].as_str(), dec!(0.35)), (keys[2].as_str(), dec!(0.30))]);
+        assert_eq!((result.filled_amount, result.remaining_amount), (dec!(60), dec!(40)));
+        assert_eq!((result.status, result.average_price), (OrderStatus::PartiallyFilled, Some(dec!(0.35))));
+
+        // Outcome C ran out, so the rest of the order rests
+        assert_eq!(engine.get_orderbook_ref(&keys[1]).unwrap().get_order(&bid_b).unwrap().remaining_amount, dec!(40));
+        assert_eq!(engine.get_best_prices(&keys[2]).unwrap(), (None, None));
+        assert_eq!(engine.get_best_prices(&keys[0]).unwrap().0, Some(dec!(0.40)));
+        let history = engine.get_orders("0xC", &OrderHistoryQuery::default());
+        assert_eq!(history.orders[0].status, OrderStatus::Filled.to_string());
+        assert_eq!(history.orders[0].trade_ids, vec![fill.set_id.to_string()]);
+    }
+
+    #[test]
+    fn test_set_merge_across_four_outcomes() {
+        let engine = MatchingEngine::new();
+        let keys = categorical_market(&engine, 4);
+        for (key, price) in keys[1..].iter().zip([dec!(0.20), dec!(0.25), dec!(0.30)]) {
+            engine.submit_order(Uuid::new_v4(), key, "0xM", Side::Sell, OrderType::Limit, dec!(50), Some(price), 1, TimeInForce::GTC).unwrap();
+        }
+        // A cheaper ask on B fills first, in a set of its own
+        engine.submit_order(Uuid::new_v4(), &keys[1], "0xN", Side::Sell, OrderType::Limit, dec!(20), Some(dec!(0.15)), 1, TimeInForce::GTC).unwrap();
+
+        // Legs sum to 0.70 then 0.75, both within 1 - 0.20
+        let result = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Sell, OrderType::Limit, dec!(50), Some(dec!(0.20)), 1, TimeInForce::IOC).unwrap();
+        assert_eq!(result.status, OrderStatus::Filled);
+        let fills: Vec<_> = result.set_fills.iter().map(|f| (f.match_type, f.price, f.amount, f.legs.len())).collect();
+        assert_eq!(fills, vec![(MatchType::Merge, dec!(0.30), dec!(20), 3), (MatchType::Merge, dec!(0.25), dec!(30), 3)]);
+        assert_eq!(result.average_price, Some(dec!(0.27)));
+        assert_eq!(result.set_fills[0].legs[0].maker_address, "0xN");
+        // C and D are sold out; B keeps 30 of 0xM's 50
+        assert!(keys[2..].iter().all(|key| engine.get_orderbook_ref(key).unwrap().ask_levels().is_empty()));
+        assert_eq!(engine.get_orderbook_ref(&keys[1]).unwrap().ask_levels(), vec![(dec!(0.20), dec!(20))]);
+    }
+
+    #[test]
+    fn test_set_match_needs_crossing_prices() {
+        let engine = MatchingEngine::new();
+        let keys = categorical_market(&engine, 3);
+        engine.submit_order(Uuid::new_v4(), &keys[1], "0xB", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.30)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &keys[2], "0xC", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.30)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &keys[1], "0xB", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.45)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &keys[2], "0xC", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.45)), 1, TimeInForce::GTC).unwrap();
+
+        // 0.35 + 0.60 < 1 mints nothing; 0.15 + 0.90 > 1 merges nothing
+        let buy = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.35)), 1, TimeInForce::GTC).unwrap();
+        let sell = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Sell, OrderType::Limit, dec!(10), Some(dec!(0.15)), 1, TimeInForce::IOC).unwrap();
+        assert!(buy.set_fills.is_empty() && sell.set_fills.is_empty());
+        assert_eq!((buy.status, sell.status), (OrderStatus::Open, OrderStatus::Cancelled));
+
+        // A post-only order that would mint a set takes liquidity
+        let err = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.40)), 1, TimeInForce::PostOnly).unwrap_err();
+        assert!(matches!(err, MatchingError::WouldTakeLiquidity));
+
+        // No set matching with complement matching off, nor in No books
+        engine.set_complement_matching(false);
+        let off = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.40)), 1, TimeInForce::IOC).unwrap();
+        assert!(off.set_fills.is_empty());
+        engine.set_complement_matching(true);
+        let no_key = MatchingEngine::get_complement_market_key(&keys[0]).unwrap();
+        let no = engine.submit_order(Uuid::new_v4(), &no_key, "0xA", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.90)), 1, TimeInForce::IOC).unwrap();
+        assert!(no.set_fills.is_empty());
+
+        assert!(engine.register_outcome_set(Uuid::new_v4(), vec![Uuid::new_v4()]).is_err());
+    }
+
+    #[test]
+    fn test_self_trade_prevention_through_set() {
+        let engine = MatchingEngine::new();
+        let keys = categorical_market(&engine, 3);
+        let own = Uuid::new_v4();
+        engine.submit_order(Uuid::new_v4(), &keys[1], "0xB", Side::Buy, OrderType::Limit, dec!(30), Some(dec!(0.35)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(own, &keys[2], "0xA", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.35)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &keys[2], "0xC", Side::Buy, OrderType::Limit, dec!(30), Some(dec!(0.30)), 1, TimeInForce::GTC).unwrap();
+
+        // Cancel oldest: the own C bid goes and the set mints with 0xC's
+        let result = engine
+            .submit_order_with_stp(Uuid::new_v4(), &keys[0], "0xa", Side::Buy, OrderType::Limit, dec!(20), Some(dec!(0.40)), 1, TimeInForce::GTC, SelfTradePrevention::CancelOldest)
+            .unwrap();
+        assert_eq!(result.status, OrderStatus::Filled);
+        assert_eq!(result.set_fills[0].legs[1].maker_address, "0xC");
+        assert_eq!((result.self_trades[0].symbol.as_str(), result.self_trades[0].order.id), (keys[2].as_str(), own));
+        assert!(engine.get_orderbook_ref(&keys[2]).unwrap().get_order(&own).is_none());
+
+        // Default: meeting its own leg stops the order
+        engine.submit_order(own, &keys[2], "0xA", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        let result = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.40)), 1, TimeInForce::GTC).unwrap();
+        assert_eq!(result.status, OrderStatus::Cancelled);
+        assert!(result.set_fills.is_empty());
+    }
+
+    #[test]
+    fn test_fok_counts_complete_sets() {
+        let engine = MatchingEngine::new();
+        let keys = categorical_market(&engine, 3);
+        engine.submit_order(Uuid::new_v4(), &keys[1], "0xB", Side::Buy, OrderType::Limit, dec!(20), Some(dec!(0.35)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &keys[2], "0xC", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.30)), 1, TimeInForce::GTC).unwrap();
+        engine.submit_order(Uuid::new_v4(), &keys[2], "0xD", Side::Buy, OrderType::Limit, dec!(10), Some(dec!(0.25)), 1, TimeInForce::GTC).unwrap();
+
+        // 0.35 + 0.30 crosses at 0.35; the 0.25 bid on C also needs 0.40
+        assert_eq!(engine.fillable_amount(&keys[0], Side::Buy, Some(dec!(0.35))), dec!(10));
+        assert_eq!(engine.fillable_amount(&keys[0], Side::Buy, Some(dec!(0.40))), dec!(20));
+        let fok = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Buy, OrderType::Limit, dec!(15), Some(dec!(0.35)), 1, TimeInForce::FOK);
+        assert!(matches!(fok, Err(MatchingError::InsufficientLiquidity)));
+
+        let fok = engine
+            .submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Buy, OrderType::Limit, dec!(15), Some(dec!(0.40)), 1, TimeInForce::FOK)
+            .unwrap();
+        assert_eq!((fok.status, fok.filled_amount), (OrderStatus::Filled, dec!(15)));
+        assert_eq!(fok.set_fills.iter().map(|f| (f.price, f.amount)).collect::<Vec<_>>(), vec![(dec!(0.35), dec!(10)), (dec!(0.40), dec!(5))]);
+
+        // An own leg stops a FOK unless prevention cancels it
+        engine.submit_order(Uuid::new_v4(), &keys[1], "0xA", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.50)), 1, TimeInForce::GTC).unwrap();
+        let fok = engine.submit_order(Uuid::new_v4(), &keys[0], "0xA", Side::Buy, OrderType::Limit, dec!(5), Some(dec!(0.40)), 1, TimeInForce::FOK);
+        assert!(matches!(fok, Err(MatchingError::InsufficientLiquidity)));
+    }
+}
@@ -1,11 +1,17 @@
 //! Trade and Order History
 //!
 //! In-memory storage for recent trades and orders with efficient lookup.
+//!
+//! Memory is bounded per key (symbol, market, user) by count and, via
+//! [`HistoryManager::evict_expired`], by age. Each key exposes the start of
+//! its in-memory window: records older than that may only exist in the
+//! database, so callers fall through to it for older queries.
 
-use super::types::*;
+use crate::types::*;
 use dashmap::DashMap;
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use tracing::debug;
 
 /// Trade and order history manager
@@ -13,12 +19,18 @@ pub struct HistoryManager {
     /// Trade history per symbol (most recent first)
     trade_history: DashMap<String, VecDeque<TradeRecord>>,
 
+    /// Trade tape per market across all its books (highest sequence first)
+    market_tapes: DashMap<String, VecDeque<TradeRecord>>,
+
     /// Order history per user address (most recent first)
     order_history: DashMap<String, VecDeque<OrderHistoryRecord>>,
 
     /// Maximum trades to keep per symbol
     max_trades_per_symbol: usize,
 
+    /// Maximum trades to keep per market tape
+    max_tape_per_market: usize,
+
     /// Maximum orders to keep per user
     max_orders_per_user: usize,
 
@@ -27,8 +39,27 @@ pub struct HistoryManager {
 
     /// Total order count
     total_orders: AtomicUsize,
+
+    /// Maximum record age kept by `evict_expired` (milliseconds)
+    retention_ms: i64,
+
+    /// When this manager started recording (milliseconds); nothing older
+    /// was ever held in memory
+    started_at: i64,
+
+    /// Newest evicted trade timestamp per symbol
+    trades_evicted_through: DashMap<String, i64>,
+
+    /// Newest evicted tape timestamp per market
+    tape_evicted_through: DashMap<String, i64>,
+
+    /// Newest evicted order update timestamp per user
+    orders_evicted_through: DashMap<String, i64>,
 }
 
+/// Default age after which records are evicted
+const DEFAULT_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
 impl HistoryManager {
     /// Create a new history manager
     pub fn new() -> Self {
@@ -39,14 +70,27 @@ impl HistoryManager {
     pub fn with_limits(max_trades_per_symbol: usize, max_orders_per_user: usize) -> Self {
         Self {
             trade_history: DashMap::new(),
+            market_tapes: DashMap::new(),
             order_history: DashMap::new(),
             max_trades_per_symbol,
+            max_tape_per_market: max_trades_per_symbol,
             max_orders_per_user,
             total_trades: AtomicUsize::new(0),
             total_orders: AtomicUsize::new(0),
+            retention_ms: DEFAULT_RETENTION.as_millis() as i64,
+            started_at: chrono::Utc::now().timestamp_millis(),
+            trades_evicted_through: DashMap::new(),
+            tape_evicted_through: DashMap::new(),
+            orders_evicted_through: DashMap::new(),
         }
     }
 
+    /// Set the maximum age of records kept in memory
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention_ms = retention.as_millis() as i64;
+        self
+    }
+
     // ========================================================================
     // Trade History
     // ========================================================================
@@ -58,21 +102,55 @@ impl HistoryManager {
 
         let mut entry = self.trade_history
             .entry(symbol.clone())
-            .or_insert_with(VecDeque::new);
+            .or_default();
 
         // Add at front (most recent)
         entry.push_front(trade.clone());
 
         // Trim if exceeding limit
         if entry.len() > self.max_trades_per_symbol {
-            entry.pop_back();
+            if let Some(evicted) = entry.pop_back() {
+                Self::mark_evicted(&self.trades_evicted_through, &symbol, evicted.timestamp);
+            }
         } else {
             self.total_trades.fetch_add(1, Ordering::Relaxed);
         }
 
+        drop(entry);
+        self.append_to_tape(trade.clone());
+
         debug!("Stored trade {} for {}", trade.trade_id, symbol);
     }
 
+    /// Insert into the market's tape by sequence number; trades usually
+    /// arrive in order, but concurrent books can store them out of order
+    fn append_to_tape(&self, trade: TradeRecord) {
+        let mut tape = self.market_tapes.entry(trade.market_id.clone()).or_default();
+
+        let position = tape.iter().position(|t| t.sequence < trade.sequence).unwrap_or(tape.len());
+        tape.insert(position, trade);
+        while tape.len() > self.max_tape_per_market {
+            if let Some(evicted) = tape.pop_back() {
+                Self::mark_evicted(&self.tape_evicted_through, &evicted.market_id, evicted.timestamp);
+            }
+        }
+    }
+
+    /// Recent trades of a market across all of its books, highest sequence
+    /// first, optionally only those before sequence `before`
+    pub fn get_market_tape(&self, market_id: &str, before: Option<u64>, limit: usize) -> Vec<TradeRecord> {
+        self.market_tapes
+            .get(market_id)
+            .map(|tape| {
+                tape.iter()
+                    .filter(|t| before.is_none_or(|seq| t.sequence < seq))
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Store multiple trades
     pub fn store_trades(&self, trades: Vec<TradeRecord>) {
         for trade in trades {
@@ -88,8 +166,8 @@ impl HistoryManager {
             let mut filtered: Vec<TradeRecord> = all_trades
                 .iter()
                 .filter(|t| {
-                    let matches_before = query.before.map_or(true, |ts| t.timestamp < ts);
-                    let matches_after = query.after.map_or(true, |ts| t.timestamp > ts);
+                    let matches_before = query.before.is_none_or(|ts| t.timestamp < ts);
+                    let matches_after = query.after.is_none_or(|ts| t.timestamp > ts);
                     matches_before && matches_after
                 })
                 .cloned()
@@ -124,7 +202,7 @@ impl HistoryManager {
         }
 
         // Sort by timestamp descending
-        all_trades.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        all_trades.sort_by_key(|t| std::cmp::Reverse(t.timestamp));
         all_trades.truncate(limit);
         all_trades
     }
@@ -151,7 +229,7 @@ impl HistoryManager {
 
         let mut entry = self.order_history
             .entry(user.clone())
-            .or_insert_with(VecDeque::new);
+            .or_default();
 
         // Check if order already exists (update case)
         let existing_pos = entry.iter().position(|o| o.order_id == order.order_id);
@@ -165,7 +243,9 @@ impl HistoryManager {
             entry.push_front(order.clone());
 
             if entry.len() > self.max_orders_per_user {
-                entry.pop_back();
+                if let Some(evicted) = entry.pop_back() {
+                    Self::mark_evicted(&self.orders_evicted_through, &user, evicted.updated_at);
+                }
             } else {
                 self.total_orders.fetch_add(1, Ordering::Relaxed);
             }
@@ -241,6 +321,94 @@ impl HistoryManager {
         }
     }
 
+    // ========================================================================
+    // Eviction
+    // ========================================================================
+
+    fn mark_evicted(watermarks: &DashMap<String, i64>, key: &str, timestamp: i64) {
+        let mut mark = watermarks.entry(key.to_string()).or_insert(timestamp);
+        *mark = (*mark).max(timestamp);
+    }
+
+    /// Remove records older than `cutoff` from one key's records
+    fn evict_from<T>(
+        records: &mut VecDeque<T>,
+        timestamp: impl Fn(&T) -> i64,
+        cutoff: i64,
+        watermarks: &DashMap<String, i64>,
+        key: &str,
+    ) -> usize {
+        let before = records.len();
+        let mut newest_evicted = None;
+        records.retain(|r| {
+            let ts = timestamp(r);
+            if ts < cutoff {
+                newest_evicted = newest_evicted.max(Some(ts));
+                false
+            } else {
+                true
+            }
+        });
+        if let Some(ts) = newest_evicted {
+            Self::mark_evicted(watermarks, key, ts);
+        }
+        before - records.len()
+    }
+
+    /// Drop records older than the retention window. Returns how many were
+    /// dropped.
+    pub fn evict_expired(&self, now_ms: i64) -> usize {
+        let cutoff = now_ms - self.retention_ms;
+
+        let mut evicted = 0;
+        for mut entry in self.trade_history.iter_mut() {
+            let (symbol, trades) = entry.pair_mut();
+            evicted += Self::evict_from(trades, |t| t.timestamp, cutoff, &self.trades_evicted_through, symbol);
+        }
+        self.total_trades.fetch_sub(evicted, Ordering::Relaxed);
+        self.trade_history.retain(|_, trades| !trades.is_empty());
+
+        for mut entry in self.market_tapes.iter_mut() {
+            let (market_id, tape) = entry.pair_mut();
+            Self::evict_from(tape, |t| t.timestamp, cutoff, &self.tape_evicted_through, market_id);
+        }
+        self.market_tapes.retain(|_, tape| !tape.is_empty());
+
+        let mut evicted_orders = 0;
+        for mut entry in self.order_history.iter_mut() {
+            let (user, orders) = entry.pair_mut();
+            evicted_orders += Self::evict_from(orders, |o| o.updated_at, cutoff, &self.orders_evicted_through, user);
+        }
+        self.total_orders.fetch_sub(evicted_orders, Ordering::Relaxed);
+        self.order_history.retain(|_, orders| !orders.is_empty());
+
+        if evicted + evicted_orders > 0 {
+            debug!("Evicted {} trades and {} orders from history", evicted, evicted_orders);
+        }
+        evicted + evicted_orders
+    }
+
+    fn window_start(&self, watermarks: &DashMap<String, i64>, key: &str) -> i64 {
+        watermarks
+            .get(key)
+            .map_or(self.started_at, |mark| self.started_at.max(*mark + 1))
+    }
+
+    /// Oldest timestamp from which the symbol's trades are all in memory
+    pub fn trade_window_start(&self, symbol: &str) -> i64 {
+        self.window_start(&self.trades_evicted_through, symbol)
+    }
+
+    /// Oldest timestamp from which the market's tape is complete in memory
+    pub fn tape_window_start(&self, market_id: &str) -> i64 {
+        self.window_start(&self.tape_evicted_through, market_id)
+    }
+
+    /// Oldest update time from which the user's orders are all in memory
+    pub fn order_window_start(&self, user_address: &str) -> i64 {
+        self.window_start(&self.orders_evicted_through, user_address)
+    }
+
     // ========================================================================
     // Statistics
     // ========================================================================
@@ -305,6 +473,9 @@ mod tests {
             maker_fee: "0.01".to_string(),
             taker_fee: "0.02".to_string(),
             timestamp: chrono::Utc::now().timestamp_millis(),
+            sequence: 0,
+            is_block_trade: false,
+            is_rfq: false,
         }
     }
 
@@ -325,6 +496,7 @@ mod tests {
             updated_at: chrono::Utc::now().timestamp_millis(),
             avg_fill_price: None,
             trade_ids: vec![],
+            reject_reason: None,
         }
     }
 
@@ -418,4 +590,56 @@ mod tests {
         let recent = manager.get_recent_trades(10);
         assert_eq!(recent.len(), 2);
     }
+
+    #[test]
+    fn test_market_tape_ordered_by_sequence() {
+        let manager = HistoryManager::with_limits(100, 100);
+        let market_id = uuid::Uuid::new_v4();
+        let yes_key = format!("{}:{}:yes", market_id, uuid::Uuid::new_v4());
+        let no_key = format!("{}:{}:no", market_id, uuid::Uuid::new_v4());
+
+        // Stored out of order across two books of the same market
+        for (id, key, sequence) in [("t1", &yes_key, 1), ("t3", &no_key, 3), ("t2", &yes_key, 2)] {
+            let mut trade = create_test_trade(id, key, "0.5");
+            trade.sequence = sequence;
+            manager.store_trade(trade);
+        }
+        let other_key = format!("{}:{}:yes", uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        manager.store_trade(create_test_trade("other", &other_key, "0.5"));
+
+        let tape = manager.get_market_tape(&market_id.to_string(), None, 10);
+        let ids: Vec<&str> = tape.iter().map(|t| t.trade_id.as_str()).collect();
+        assert_eq!(ids, vec!["t3", "t2", "t1"]);
+
+        let older = manager.get_market_tape(&market_id.to_string(), Some(3), 1);
+        assert_eq!(older[0].trade_id, "t2");
+    }
+
+    #[test]
+    fn test_evict_expired_moves_window() {
+        let manager = HistoryManager::new().with_retention(Duration::from_secs(60));
+        let market_id = uuid::Uuid::new_v4();
+        let market_key = format!("{}:{}:yes", market_id, uuid::Uuid::new_v4());
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let mut old = create_test_trade("old", &market_key, "0.5");
+        old.timestamp = now - 120_000;
+        manager.store_trade(old);
+        manager.store_trade(create_test_trade("new", &market_key, "0.5"));
+        let mut order = create_test_order("o1", "0x1234", "filled");
+        order.updated_at = now - 120_000;
+        manager.store_order(order);
+
+        assert_eq!(manager.evict_expired(now), 2);
+
+        let trades = manager.get_trades(&market_key, &TradeHistoryQuery::default());
+        assert_eq!(trades.trades.iter().map(|t| t.trade_id.as_str()).collect::<Vec<_>>(), vec!["new"]);
+        assert_eq!(manager.total_trade_count(), 1);
+        assert!(manager.get_order("0x1234", "o1").is_none());
+        assert!(manager.get_market_tape(&market_id.to_string(), None, 10).len() == 1);
+
+        // Nothing older than the manager's start was ever in memory
+        assert!(manager.trade_window_start(&market_key) >= now - 1000);
+        assert!(manager.trade_window_start("unknown") <= now);
+    }
 }
@@ -0,0 +1,110 @@
+//! Polymarket Matching Engine
+//!
+//! High-performance in-memory order matching for prediction markets with
+//! price-time priority. This crate has no HTTP or database dependencies so it
+//! can be embedded in simulators and tested in isolation; the backend wires
+//! persistence, APIs and WebSocket fan-out around it.
+//!
+//! # Architecture
+//!
+//! ```text
+//! MatchingEngine (in-memory matching)
+//!   ├→ Orderbook (per market:outcome:share_type)
+//!   ├→ HistoryManager (in-memory history)
+//!   ├→ PriceGuard (price bands, circuit breaker halts)
+//!   └→ broadcast channels (trades, orderbook updates, order events)
+//!
+//! EngineShards (optional)
+//!   └→ one thread + bounded queue per shard; a market always runs on the same shard
+//! ```
+//!
+//! # Features
+//!
+//! - **Concurrent Access**: Uses DashMap for lock-free orderbook access
+//! - **Price-Time Priority**: Orders are matched by best price, then oldest first
+//! - **History Tracking**: Keeps recent trades and orders in memory
+//! - **Event Streams**: Broadcasts trade, orderbook and order lifecycle events to subscribers
+//!
+//! # Prediction Market Keys
+//!
+//! For prediction markets, we use market keys in the format:
+//! `{market_id}:{outcome_id}:{share_type}`
+//!
+//! For example: `550e8400-e29b-41d4-a716-446655440000:660e8400-e29b-41d4-a716-446655440001:Yes`
+//!
+//! A key may carry a namespace (`paper/{market_id}:{outcome_id}:{share_type}`)
+//! to run separate books for the same market; complement matching stays
+//! within the namespace.
+//!
+//! A categorical market with N outcomes keeps one key per outcome, each
+//! trading its Yes shares (`{market_id}:{outcome_j}:yes`). Once its outcomes
+//! are registered with [`MatchingEngine::register_outcome_set`], buys and
+//! sells across all N books mint and merge complete sets ([`SetFill`]).
+//!
+//! # Features flags
+//!
+//! - `proptest`: build the property-based invariant tests
+
+mod engine;
+mod history;
+mod orderbook;
+mod price_guard;
+mod shard;
+mod share_type;
+pub mod metrics;
+pub mod precision;
+pub mod types;
+
+pub use engine::{EngineStats, MatchingEngine};
+pub use history::{HistoryManager, HistoryStats};
+pub use orderbook::{Orderbook, CACHED_DEPTH};
+pub use price_guard::{MarketHalt, PriceGuardConfig};
+pub use shard::{EngineShards, ShardConfig, ShardStats};
+pub use share_type::ShareType;
+pub use types::*;
+
+#[cfg(all(test, feature = "proptest"))]
+mod proptests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_price_level() {
+        let price = dec!(0.55);
+        let level = PriceLevel::from_decimal(price);
+        let back = level.to_decimal();
+
+        // Should preserve 8 decimal places
+        assert_eq!(price, back);
+    }
+
+    #[test]
+    fn test_engine_basic() {
+        let engine = MatchingEngine::new();
+
+        // For prediction markets, use market_key format with valid UUIDs
+        let market_id = uuid::Uuid::new_v4();
+        let outcome_id = uuid::Uuid::new_v4();
+        let market_key = format!("{}:{}:yes", market_id, outcome_id);
+
+        // Submit a buy order (buy Yes shares at 0.55)
+        let result = engine.submit_order(
+            uuid::Uuid::new_v4(),
+            &market_key,
+            "0x1234",
+            Side::Buy,
+            OrderType::Limit,
+            dec!(100.0), // 100 shares
+            Some(dec!(0.55)), // at 0.55 probability
+            1, // leverage not used in prediction markets
+            TimeInForce::GTC,
+        );
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+        assert_eq!(result.status, OrderStatus::Open);
+    }
+}
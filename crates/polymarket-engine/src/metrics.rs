@@ -0,0 +1,123 @@
+//! Matching engine metrics
+//!
+//! Recorded through the `metrics` facade; the host application installs the
+//! exporter (Prometheus in the backend). Without a recorder these are no-ops.
+
+use metrics::{counter, gauge, histogram};
+
+/// Metric names
+pub mod names {
+    pub const ORDERS_SUBMITTED_TOTAL: &str = "orders_submitted_total";
+    pub const ORDERS_MATCHED_TOTAL: &str = "orders_matched_total";
+    pub const ORDERS_CANCELLED_TOTAL: &str = "orders_cancelled_total";
+    pub const ORDERS_AMENDED_TOTAL: &str = "orders_amended_total";
+    pub const ORDERS_EXPIRED_TOTAL: &str = "orders_expired_total";
+    pub const ORDERS_REJECTED_TOTAL: &str = "orders_rejected_total";
+    pub const ORDER_MATCH_DURATION_SECONDS: &str = "order_match_duration_seconds";
+    pub const TRADES_EXECUTED_TOTAL: &str = "trades_executed_total";
+    pub const TRADE_VOLUME_USDC: &str = "trade_volume_usdc";
+    pub const MINT_OPERATIONS_TOTAL: &str = "mint_operations_total";
+    pub const MERGE_OPERATIONS_TOTAL: &str = "merge_operations_total";
+    pub const ENGINE_SHARD_QUEUE_DEPTH: &str = "engine_shard_queue_depth";
+    pub const ENGINE_SHARD_REJECTED_TOTAL: &str = "engine_shard_rejected_total";
+    pub const ENGINE_SHARD_ADMISSION_REFUSED_TOTAL: &str = "engine_shard_admission_refused_total";
+    pub const MARKETS_HALTED_TOTAL: &str = "markets_halted_total";
+}
+
+/// Label keys
+pub mod labels {
+    pub const ORDER_SIDE: &str = "side";
+    pub const ORDER_TYPE: &str = "order_type";
+    pub const MATCH_TYPE: &str = "match_type";
+    pub const REJECT_REASON: &str = "reason";
+    pub const SHARD: &str = "shard";
+}
+
+/// Record order submission
+pub fn record_order_submitted(side: &str, order_type: &str) {
+    counter!(
+        names::ORDERS_SUBMITTED_TOTAL,
+        labels::ORDER_SIDE => side.to_string(),
+        labels::ORDER_TYPE => order_type.to_string()
+    )
+    .increment(1);
+}
+
+/// Record order matched
+pub fn record_order_matched(match_type: &str) {
+    counter!(
+        names::ORDERS_MATCHED_TOTAL,
+        labels::MATCH_TYPE => match_type.to_string()
+    )
+    .increment(1);
+}
+
+/// Record order cancelled
+pub fn record_order_cancelled() {
+    counter!(names::ORDERS_CANCELLED_TOTAL).increment(1);
+}
+
+/// Record order amended
+pub fn record_order_amended() {
+    counter!(names::ORDERS_AMENDED_TOTAL).increment(1);
+}
+
+/// Record resting order expired
+pub fn record_order_expired() {
+    counter!(names::ORDERS_EXPIRED_TOTAL).increment(1);
+}
+
+/// Record order rejected
+pub fn record_order_rejected(reason: &str) {
+    counter!(
+        names::ORDERS_REJECTED_TOTAL,
+        labels::REJECT_REASON => reason.to_string()
+    )
+    .increment(1);
+}
+
+/// Record order matching duration
+pub fn record_order_match_duration(duration_secs: f64) {
+    histogram!(names::ORDER_MATCH_DURATION_SECONDS).record(duration_secs);
+}
+
+/// Record trade execution
+pub fn record_trade_executed(match_type: &str, volume_usdc: f64) {
+    counter!(
+        names::TRADES_EXECUTED_TOTAL,
+        labels::MATCH_TYPE => match_type.to_string()
+    )
+    .increment(1);
+
+    counter!(names::TRADE_VOLUME_USDC).increment(volume_usdc as u64);
+}
+
+/// Record mint operation
+pub fn record_mint_operation() {
+    counter!(names::MINT_OPERATIONS_TOTAL).increment(1);
+}
+
+/// Record merge operation
+pub fn record_merge_operation() {
+    counter!(names::MERGE_OPERATIONS_TOTAL).increment(1);
+}
+
+/// Record a market halted by the circuit breaker
+pub fn record_market_halted() {
+    counter!(names::MARKETS_HALTED_TOTAL).increment(1);
+}
+
+/// Record the number of jobs waiting in a shard's queue
+pub fn set_shard_queue_depth(shard: usize, depth: usize) {
+    gauge!(names::ENGINE_SHARD_QUEUE_DEPTH, labels::SHARD => shard.to_string()).set(depth as f64);
+}
+
+/// Record a job refused because its shard's queue was full
+pub fn record_shard_rejected(shard: usize) {
+    counter!(names::ENGINE_SHARD_REJECTED_TOTAL, labels::SHARD => shard.to_string()).increment(1);
+}
+
+/// Record a new order refused by a shard's admission control
+pub fn record_shard_admission_refused(shard: usize) {
+    counter!(names::ENGINE_SHARD_ADMISSION_REFUSED_TOTAL, labels::SHARD => shard.to_string()).increment(1);
+}
@@ -2,15 +2,19 @@
 //!
 //! High-performance orderbook for prediction markets with lock-free concurrent access.
 
-use super::types::*;
-use crate::models::market::ShareType;
+use crate::types::*;
+use crate::ShareType;
 use dashmap::DashMap;
 use parking_lot::RwLock;
 use rust_decimal::Decimal;
 use std::collections::{BTreeMap, VecDeque};
-use std::sync::atomic::{AtomicI64, Ordering as AtomicOrdering};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::Arc;
 use uuid::Uuid;
 
+/// Depth kept in the per-book depth cache (the broadcast depth)
+pub const CACHED_DEPTH: usize = 20;
+
 /// A single orderbook for a specific market outcome (Yes or No shares)
 pub struct Orderbook {
     /// Market ID
@@ -37,6 +41,12 @@ pub struct Orderbook {
 
     /// Order count
     order_count: AtomicI64,
+
+    /// Bumped after every change to the levels or last price
+    version: AtomicU64,
+
+    /// Top-of-book levels as of some version, rebuilt lazily on read
+    depth_cache: RwLock<Option<Arc<CachedDepth>>>,
 }
 
 impl Orderbook {
@@ -57,6 +67,8 @@ impl Orderbook {
             order_index: DashMap::new(),
             last_trade_price: AtomicI64::new(0),
             order_count: AtomicI64::new(0),
+            version: AtomicU64::new(0),
+            depth_cache: RwLock::new(None),
         }
     }
 
@@ -107,6 +119,19 @@ impl Orderbook {
     pub fn set_last_trade_price(&self, price: Decimal) {
         let level = PriceLevel::from_decimal(price);
         self.last_trade_price.store(level.raw(), AtomicOrdering::Relaxed);
+        self.touch();
+    }
+
+    /// Book version; changes whenever the depth or last price may have
+    pub fn version(&self) -> u64 {
+        self.version.load(AtomicOrdering::Acquire)
+    }
+
+    /// Mark the book changed. Called after the mutation so a reader that
+    /// sees the new version also sees the new levels. Matching bumps it
+    /// through `set_last_trade_price` on every fill.
+    fn touch(&self) {
+        self.version.fetch_add(1, AtomicOrdering::Release);
     }
 
     /// Get best bid price
@@ -157,13 +182,13 @@ impl Orderbook {
             Side::Buy => {
                 let mut bids = self.bids.write();
                 bids.entry(price_level)
-                    .or_insert_with(VecDeque::new)
+                    .or_default()
                     .push_back(entry);
             }
             Side::Sell => {
                 let mut asks = self.asks.write();
                 asks.entry(price_level)
-                    .or_insert_with(VecDeque::new)
+                    .or_default()
                     .push_back(entry);
             }
         }
@@ -171,6 +196,7 @@ impl Orderbook {
         // Add to index
         self.order_index.insert(order_id, (side, price_level));
         self.order_count.fetch_add(1, AtomicOrdering::Relaxed);
+        self.touch();
 
         Ok(())
     }
@@ -220,207 +246,346 @@ impl Orderbook {
 
         if entry.is_some() {
             self.order_count.fetch_sub(1, AtomicOrdering::Relaxed);
+            self.touch();
         }
 
         entry
     }
 
+    /// Give a resting order a new price and remaining size. Shrinking it
+    /// at its price keeps its place in the queue; any other change sends it
+    /// to the back of its (new) level, as a new order would be. Filled size
+    /// is kept, so the original size moves with the remaining one. Returns
+    /// the order before and after, or `None` if it is not in the book.
+    pub fn amend_order(
+        &self,
+        order_id: Uuid,
+        price: Decimal,
+        remaining: Decimal,
+    ) -> Result<Option<Amendment>, MatchingError> {
+        self.validate_price(price)?;
+        if remaining <= Decimal::ZERO {
+            return Err(MatchingError::InvalidAmount(format!(
+                "Remaining amount {} must be positive",
+                remaining
+            )));
+        }
+        let Some(before) = self.get_order(&order_id) else {
+            return Ok(None);
+        };
+
+        let kept_priority = PriceLevel::from_decimal(price) == PriceLevel::from_decimal(before.price)
+            && remaining <= before.remaining_amount;
+        if kept_priority {
+            if remaining < before.remaining_amount
+                && !self.reduce_order(order_id, before.remaining_amount - remaining, true)
+            {
+                return Ok(None);
+            }
+        } else {
+            let Some(mut entry) = self.cancel_order(order_id) else {
+                return Ok(None);
+            };
+            entry.original_amount += remaining - entry.remaining_amount;
+            entry.remaining_amount = remaining;
+            entry.price = price;
+            entry.timestamp = chrono::Utc::now().timestamp_millis();
+            self.add_order(entry)?;
+        }
+
+        let after = self.get_order(&order_id).ok_or_else(|| {
+            MatchingError::InternalError(format!("Amended order {} left the book", order_id))
+        })?;
+        Ok(Some(Amendment { before, after, kept_priority }))
+    }
+
     /// Match an incoming order against the orderbook (Normal matching)
     /// Returns (trades, remaining_amount)
     ///
     /// Normal matching: Same share type, opposite sides
     /// - Buy order matches against Sell orders
     /// - Sell order matches against Buy orders
+    ///
+    /// Resting orders of the taker's own address are never traded with;
+    /// its self-trade prevention decides what happens instead.
     pub fn match_order(
         &self,
-        taker_order_id: Uuid,
-        _taker_address: &str,
+        taker: &Taker,
         side: Side,
-        mut amount: Decimal,
+        amount: Decimal,
         limit_price: Option<Decimal>,
         fee_config: &FeeConfig,
     ) -> (Vec<TradeExecution>, Decimal) {
-        let mut trades = Vec::new();
+        let matched = self.match_order_with_makers(taker, side, amount, limit_price, fee_config);
+        (matched.trades, matched.remaining)
+    }
+
+    /// Like [`match_order`](Self::match_order), also returning each filled
+    /// maker order as it stands after its fill and the orders self-trade
+    /// prevention cancelled or shrank
+    pub fn match_order_with_makers(
+        &self,
+        taker: &Taker,
+        side: Side,
+        amount: Decimal,
+        limit_price: Option<Decimal>,
+        fee_config: &FeeConfig,
+    ) -> BookMatch {
+        let mut result = BookMatch::new(amount);
         let now = chrono::Utc::now().timestamp_millis();
 
-        match side {
-            Side::Buy => {
-                // Match against asks (lowest first)
-                let mut asks = self.asks.write();
-                let price_levels: Vec<PriceLevel> = asks.keys().cloned().collect();
+        // Buys match against asks (lowest first), sells against bids
+        // (highest first)
+        let mut book = match side {
+            Side::Buy => self.asks.write(),
+            Side::Sell => self.bids.write(),
+        };
+        let price_levels: Vec<PriceLevel> = match side {
+            Side::Buy => book.keys().cloned().collect(),
+            Side::Sell => book.keys().rev().cloned().collect(),
+        };
 
-                for price_level in price_levels {
-                    if amount <= Decimal::ZERO {
-                        break;
-                    }
+        for price_level in price_levels {
+            if !result.open() {
+                break;
+            }
 
-                    let level_price = price_level.to_decimal();
+            // Check price limit for limit orders
+            let level_price = price_level.to_decimal();
+            let beyond_limit = limit_price.is_some_and(|limit| match side {
+                Side::Buy => level_price > limit,
+                Side::Sell => level_price < limit,
+            });
+            if beyond_limit {
+                break;
+            }
 
-                    // Check price limit for limit orders
-                    if let Some(limit) = limit_price {
-                        if level_price > limit {
-                            break;
-                        }
-                    }
+            if let Some(queue) = book.get_mut(&price_level) {
+                self.match_level(queue, taker, fee_config, now, &mut result);
+                if queue.is_empty() {
+                    book.remove(&price_level);
+                }
+            }
+        }
 
-                    if let Some(queue) = asks.get_mut(&price_level) {
-                        while let Some(maker) = queue.front_mut() {
-                            if amount <= Decimal::ZERO {
-                                break;
-                            }
-
-                            let trade_amount = amount.min(maker.remaining_amount);
-                            let trade_price = maker.price;
-
-                            // Calculate symmetric fees for prediction market
-                            let maker_fee = fee_config.calculate_maker_fee(trade_price, trade_amount);
-                            let taker_fee = fee_config.calculate_taker_fee(trade_price, trade_amount);
-
-                            let trade = TradeExecution {
-                                trade_id: Uuid::new_v4(),
-                                market_id: self.market_id,
-                                outcome_id: self.outcome_id,
-                                share_type: self.share_type,
-                                match_type: MatchType::Normal,
-                                maker_order_id: maker.id,
-                                taker_order_id,
-                                maker_address: maker.user_address.clone(),
-                                price: trade_price,
-                                amount: trade_amount,
-                                maker_fee,
-                                taker_fee,
-                                timestamp: now,
-                            };
-
-                            trades.push(trade);
-                            amount -= trade_amount;
-                            maker.remaining_amount -= trade_amount;
-
-                            // Update last trade price
-                            self.set_last_trade_price(trade_price);
-
-                            // Remove fully filled maker order
-                            if maker.remaining_amount <= Decimal::ZERO {
-                                let maker_id = maker.id;
-                                queue.pop_front();
-                                self.order_index.remove(&maker_id);
-                                self.order_count.fetch_sub(1, AtomicOrdering::Relaxed);
-                            }
-                        }
+        result
+    }
 
-                        if queue.is_empty() {
-                            asks.remove(&price_level);
-                        }
-                    }
-                }
+    /// Match the taker against one price level, oldest order first
+    fn match_level(
+        &self,
+        queue: &mut VecDeque<OrderEntry>,
+        taker: &Taker,
+        fee_config: &FeeConfig,
+        now: i64,
+        result: &mut BookMatch,
+    ) {
+        let stp = taker.stp;
+        while let Some(maker) = queue.front_mut() {
+            if !result.open() {
+                break;
             }
-            Side::Sell => {
-                // Match against bids (highest first)
-                let mut bids = self.bids.write();
-                let price_levels: Vec<PriceLevel> = bids.keys().rev().cloned().collect();
 
-                for price_level in price_levels {
-                    if amount <= Decimal::ZERO {
+            if maker.user_address.eq_ignore_ascii_case(taker.address) {
+                let removed = match stp {
+                    SelfTradePrevention::CancelNewest => {
+                        result.stopped = true;
                         break;
                     }
-
-                    let level_price = price_level.to_decimal();
-
-                    // Check price limit for limit orders
-                    if let Some(limit) = limit_price {
-                        if level_price < limit {
-                            break;
-                        }
+                    SelfTradePrevention::CancelOldest | SelfTradePrevention::CancelBoth => {
+                        result.self_trades.push((maker.clone(), maker.remaining_amount));
+                        result.stopped = stp == SelfTradePrevention::CancelBoth;
+                        true
                     }
-
-                    if let Some(queue) = bids.get_mut(&price_level) {
-                        while let Some(maker) = queue.front_mut() {
-                            if amount <= Decimal::ZERO {
-                                break;
-                            }
-
-                            let trade_amount = amount.min(maker.remaining_amount);
-                            let trade_price = maker.price;
-
-                            // Calculate symmetric fees for prediction market
-                            let maker_fee = fee_config.calculate_maker_fee(trade_price, trade_amount);
-                            let taker_fee = fee_config.calculate_taker_fee(trade_price, trade_amount);
-
-                            let trade = TradeExecution {
-                                trade_id: Uuid::new_v4(),
-                                market_id: self.market_id,
-                                outcome_id: self.outcome_id,
-                                share_type: self.share_type,
-                                match_type: MatchType::Normal,
-                                maker_order_id: maker.id,
-                                taker_order_id,
-                                maker_address: maker.user_address.clone(),
-                                price: trade_price,
-                                amount: trade_amount,
-                                maker_fee,
-                                taker_fee,
-                                timestamp: now,
-                            };
-
-                            trades.push(trade);
-                            amount -= trade_amount;
-                            maker.remaining_amount -= trade_amount;
-
-                            // Update last trade price
-                            self.set_last_trade_price(trade_price);
-
-                            // Remove fully filled maker order
-                            if maker.remaining_amount <= Decimal::ZERO {
-                                let maker_id = maker.id;
-                                queue.pop_front();
-                                self.order_index.remove(&maker_id);
-                                self.order_count.fetch_sub(1, AtomicOrdering::Relaxed);
-                            }
-                        }
-
-                        if queue.is_empty() {
-                            bids.remove(&price_level);
-                        }
+                    SelfTradePrevention::Decrement => {
+                        let decrement = result.remaining.min(maker.remaining_amount);
+                        result.self_trades.push((maker.clone(), decrement));
+                        result.remaining -= decrement;
+                        result.decremented += decrement;
+                        maker.remaining_amount -= decrement;
+                        maker.original_amount -= decrement;
+                        maker.remaining_amount <= Decimal::ZERO
                     }
+                };
+                if removed {
+                    let maker_id = maker.id;
+                    queue.pop_front();
+                    self.order_index.remove(&maker_id);
+                    self.order_count.fetch_sub(1, AtomicOrdering::Relaxed);
                 }
+                self.touch();
+                continue;
+            }
+
+            let trade_amount = result.remaining.min(maker.remaining_amount);
+            let trade_price = maker.price;
+
+            // Calculate symmetric fees for prediction market
+            let maker_fee = fee_config.calculate_maker_fee(trade_price, trade_amount);
+            let taker_fee = fee_config.calculate_taker_fee(trade_price, trade_amount);
+
+            let trade = TradeExecution {
+                trade_id: Uuid::new_v4(),
+                market_id: self.market_id,
+                outcome_id: self.outcome_id,
+                share_type: self.share_type,
+                match_type: MatchType::Normal,
+                maker_order_id: maker.id,
+                taker_order_id: taker.order_id,
+                maker_address: maker.user_address.clone(),
+                price: trade_price,
+                amount: trade_amount,
+                maker_fee,
+                taker_fee,
+                timestamp: now,
+                sequence: 0,
+                is_block_trade: false,
+            };
+
+            result.trades.push(trade);
+            result.remaining -= trade_amount;
+            maker.remaining_amount -= trade_amount;
+            result.makers.push(maker.clone());
+
+            // Update last trade price
+            self.set_last_trade_price(trade_price);
+
+            // Remove fully filled maker order
+            if maker.remaining_amount <= Decimal::ZERO {
+                let maker_id = maker.id;
+                queue.pop_front();
+                self.order_index.remove(&maker_id);
+                self.order_count.fetch_sub(1, AtomicOrdering::Relaxed);
             }
         }
+    }
 
-        (trades, amount)
+    /// Apply self-trade prevention to `maker`, a resting order of this book
+    /// owned by the taker of a Mint/Merge match
+    pub fn prevent_self_trade(&self, maker: &OrderEntry, stp: SelfTradePrevention, result: &mut BookMatch) {
+        match stp {
+            SelfTradePrevention::CancelNewest => result.stopped = true,
+            SelfTradePrevention::CancelOldest | SelfTradePrevention::CancelBoth => {
+                if self.cancel_order(maker.id).is_some() {
+                    result.self_trades.push((maker.clone(), maker.remaining_amount));
+                }
+                result.stopped = stp == SelfTradePrevention::CancelBoth;
+            }
+            SelfTradePrevention::Decrement => {
+                let decrement = result.remaining.min(maker.remaining_amount);
+                if self.reduce_order(maker.id, decrement, true) {
+                    result.self_trades.push((maker.clone(), decrement));
+                    result.remaining -= decrement;
+                    result.decremented += decrement;
+                }
+            }
+        }
     }
 
-    /// Get orderbook snapshot
-    pub fn snapshot(&self, depth: usize) -> OrderbookSnapshot {
-        let mut bids_vec: Vec<[String; 2]> = Vec::new();
-        let mut asks_vec: Vec<[String; 2]> = Vec::new();
+    /// Top [`CACHED_DEPTH`] levels, preserialized. Rebuilt only when the
+    /// book changed since the cached copy, so repeated broadcasts and
+    /// snapshot requests between two changes share one set of strings.
+    pub fn cached_depth(&self) -> Arc<CachedDepth> {
+        let version = self.version();
+        if let Some(cached) = self.depth_cache.read().as_ref().filter(|c| c.version == version) {
+            return cached.clone();
+        }
 
-        // Get bids (highest first)
-        {
-            let bids = self.bids.read();
-            for (price_level, orders) in bids.iter().rev().take(depth) {
-                let total: Decimal = orders.iter().map(|o| o.remaining_amount).sum();
-                bids_vec.push([price_level.to_decimal().to_string(), total.to_string()]);
+        // Stamped with the version read before building: a change racing
+        // the build leaves the cache one version behind, never ahead
+        let (bids, asks) = self.levels(CACHED_DEPTH);
+        let snapshot = OrderbookSnapshot {
+            symbol: format!("{}:{}:{}", self.market_id, self.outcome_id, self.share_type),
+            bids,
+            asks,
+            last_price: self.last_trade_price(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        };
+        let built = Arc::new(CachedDepth::new(version, snapshot));
+
+        let mut cache = self.depth_cache.write();
+        match cache.as_ref() {
+            Some(current) if current.version >= version => current.clone(),
+            _ => {
+                *cache = Some(built.clone());
+                built
             }
         }
+    }
 
-        // Get asks (lowest first)
-        {
-            let asks = self.asks.read();
-            for (price_level, orders) in asks.iter().take(depth) {
+    /// Top `depth` bid (highest first) and ask (lowest first) levels as
+    /// `[price, amount]` strings
+    fn levels(&self, depth: usize) -> (Vec<[String; 2]>, Vec<[String; 2]>) {
+        let bids = self
+            .bids
+            .read()
+            .iter()
+            .rev()
+            .take(depth)
+            .map(|(price_level, orders)| {
                 let total: Decimal = orders.iter().map(|o| o.remaining_amount).sum();
-                asks_vec.push([price_level.to_decimal().to_string(), total.to_string()]);
-            }
-        }
+                [price_level.to_decimal().to_string(), total.to_string()]
+            })
+            .collect();
+        let asks = self
+            .asks
+            .read()
+            .iter()
+            .take(depth)
+            .map(|(price_level, orders)| {
+                let total: Decimal = orders.iter().map(|o| o.remaining_amount).sum();
+                [price_level.to_decimal().to_string(), total.to_string()]
+            })
+            .collect();
+        (bids, asks)
+    }
+
+    /// Get orderbook snapshot. Depths up to [`CACHED_DEPTH`] are served
+    /// from the depth cache.
+    pub fn snapshot(&self, depth: usize) -> OrderbookSnapshot {
+        let (bids, asks) = if depth <= CACHED_DEPTH {
+            let cached = self.cached_depth();
+            (
+                cached.bids[..depth.min(cached.bids.len())].to_vec(),
+                cached.asks[..depth.min(cached.asks.len())].to_vec(),
+            )
+        } else {
+            self.levels(depth)
+        };
 
         OrderbookSnapshot {
             symbol: format!("{}:{}:{}", self.market_id, self.outcome_id, self.share_type),
-            bids: bids_vec,
-            asks: asks_vec,
+            bids,
+            asks,
             last_price: self.last_trade_price(),
             timestamp: chrono::Utc::now().timestamp_millis(),
         }
     }
 
+    /// Every resting order, bids then asks
+    pub fn orders(&self) -> Vec<OrderEntry> {
+        let bids = self.bids.read();
+        let asks = self.asks.read();
+        bids.values().chain(asks.values()).flat_map(|q| q.iter().cloned()).collect()
+    }
+
+    /// Aggregated bid levels `(price, amount)`, best (highest) first
+    pub fn bid_levels(&self) -> Vec<(Decimal, Decimal)> {
+        let bids = self.bids.read();
+        bids.iter()
+            .rev()
+            .map(|(level, orders)| (level.to_decimal(), orders.iter().map(|o| o.remaining_amount).sum()))
+            .collect()
+    }
+
+    /// Aggregated ask levels `(price, amount)`, best (lowest) first
+    pub fn ask_levels(&self) -> Vec<(Decimal, Decimal)> {
+        let asks = self.asks.read();
+        asks.iter()
+            .map(|(level, orders)| (level.to_decimal(), orders.iter().map(|o| o.remaining_amount).sum()))
+            .collect()
+    }
+
     /// Get bid depth (total bids volume)
     pub fn bid_depth(&self) -> Decimal {
         let bids = self.bids.read();
@@ -446,7 +611,7 @@ impl Orderbook {
 
     /// Get order by ID
     pub fn get_order(&self, order_id: &Uuid) -> Option<OrderEntry> {
-        let (side, price_level) = self.order_index.get(order_id)?.clone();
+        let (side, price_level) = *self.order_index.get(order_id)?;
 
         match side {
             Side::Buy => {
@@ -466,6 +631,24 @@ impl Orderbook {
         }
     }
 
+    /// Where a resting order stands in its price level's time-priority
+    /// queue; `None` unless the order is in the book
+    pub fn queue_position(&self, order_id: &Uuid) -> Option<QueuePosition> {
+        let (side, price_level) = *self.order_index.get(order_id)?;
+        let levels = match side {
+            Side::Buy => self.bids.read(),
+            Side::Sell => self.asks.read(),
+        };
+        let queue = levels.get(&price_level)?;
+        let index = queue.iter().position(|o| o.id == *order_id)?;
+        Some(QueuePosition {
+            price: price_level.to_decimal(),
+            position: index + 1,
+            ahead_amount: queue.iter().take(index).map(|o| o.remaining_amount).sum(),
+            level_amount: queue.iter().map(|o| o.remaining_amount).sum(),
+        })
+    }
+
     /// Get all buy orders at a specific price level
     pub fn get_bids_at_price(&self, price: Decimal) -> Vec<OrderEntry> {
         let price_level = PriceLevel::from_decimal(price);
@@ -536,72 +719,62 @@ impl Orderbook {
         orders
     }
 
+    /// The order first in line on `side`: the oldest at the best price
+    ///
+    /// Used by set matching, which fills one order per outcome book at a time
+    pub fn best_order(&self, side: Side) -> Option<OrderEntry> {
+        match side {
+            Side::Buy => self.bids.read().values().next_back().and_then(|q| q.front().cloned()),
+            Side::Sell => self.asks.read().values().next().and_then(|q| q.front().cloned()),
+        }
+    }
+
     /// Fill an order by a specific amount
     ///
     /// Used by Mint/Merge matching to update maker orders in the complement orderbook
     pub fn fill_order(&self, order_id: Uuid, fill_amount: Decimal) -> bool {
+        self.reduce_order(order_id, fill_amount, false)
+    }
+
+    /// Take `amount` off an order's remaining size, and off its original
+    /// size too when `shrink` (the size is cancelled rather than filled).
+    /// An order left with nothing is removed.
+    fn reduce_order(&self, order_id: Uuid, amount: Decimal, shrink: bool) -> bool {
         // Find the order in index
-        let entry = match self.order_index.get(&order_id) {
-            Some(e) => e.clone(),
+        let (side, price_level) = match self.order_index.get(&order_id) {
+            Some(e) => *e,
             None => return false,
         };
 
-        let (side, price_level) = entry;
+        let mut book = match side {
+            Side::Buy => self.bids.write(),
+            Side::Sell => self.asks.write(),
+        };
+        let Some(queue) = book.get_mut(&price_level) else {
+            return false;
+        };
+        let Some(pos) = queue.iter().position(|o| o.id == order_id) else {
+            return false;
+        };
 
-        match side {
-            Side::Buy => {
-                let mut bids = self.bids.write();
-                if let Some(queue) = bids.get_mut(&price_level) {
-                    for order in queue.iter_mut() {
-                        if order.id == order_id {
-                            order.remaining_amount -= fill_amount;
-
-                            // Remove if fully filled
-                            if order.remaining_amount <= Decimal::ZERO {
-                                let pos = queue.iter().position(|o| o.id == order_id);
-                                if let Some(pos) = pos {
-                                    queue.remove(pos);
-                                }
-                                self.order_index.remove(&order_id);
-                                self.order_count.fetch_sub(1, AtomicOrdering::Relaxed);
-
-                                if queue.is_empty() {
-                                    bids.remove(&price_level);
-                                }
-                            }
-                            return true;
-                        }
-                    }
-                }
-            }
-            Side::Sell => {
-                let mut asks = self.asks.write();
-                if let Some(queue) = asks.get_mut(&price_level) {
-                    for order in queue.iter_mut() {
-                        if order.id == order_id {
-                            order.remaining_amount -= fill_amount;
-
-                            // Remove if fully filled
-                            if order.remaining_amount <= Decimal::ZERO {
-                                let pos = queue.iter().position(|o| o.id == order_id);
-                                if let Some(pos) = pos {
-                                    queue.remove(pos);
-                                }
-                                self.order_index.remove(&order_id);
-                                self.order_count.fetch_sub(1, AtomicOrdering::Relaxed);
-
-                                if queue.is_empty() {
-                                    asks.remove(&price_level);
-                                }
-                            }
-                            return true;
-                        }
-                    }
-                }
-            }
+        let order = &mut queue[pos];
+        order.remaining_amount -= amount;
+        if shrink {
+            order.original_amount -= amount;
         }
 
-        false
+        // Remove if nothing is left
+        if order.remaining_amount <= Decimal::ZERO {
+            queue.remove(pos);
+            self.order_index.remove(&order_id);
+            self.order_count.fetch_sub(1, AtomicOrdering::Relaxed);
+
+            if queue.is_empty() {
+                book.remove(&price_level);
+            }
+        }
+        self.touch();
+        true
     }
 }
 
@@ -768,14 +941,8 @@ mod tests {
 
         // Match a buy order
         let taker_id = Uuid::new_v4();
-        let (trades, remaining) = book.match_order(
-            taker_id,
-            "0x5678",
-            Side::Buy,
-            dec!(150),
-            Some(dec!(0.65)),
-            &fee_config,
-        );
+        let (trades, remaining) =
+            book.match_order(&Taker::new(taker_id, "0x5678"), Side::Buy, dec!(150), Some(dec!(0.65)), &fee_config);
 
         assert_eq!(trades.len(), 2);
         assert_eq!(remaining, dec!(0));
@@ -812,8 +979,7 @@ mod tests {
 
         // Match with a buy order
         let (trades, _) = book.match_order(
-            Uuid::new_v4(),
-            "0x5678",
+            &Taker::new(Uuid::new_v4(), "0x5678"),
             Side::Buy,
             dec!(100),
             Some(dec!(0.95)),
@@ -865,4 +1031,79 @@ mod tests {
         assert_eq!(snapshot.bids[0][1], "300"); // Total bid at 0.60 (100 + 200)
         assert_eq!(snapshot.asks[0][1], "150");
     }
+
+    #[test]
+    fn test_cached_depth_reused_until_book_changes() {
+        let (market_key, _, _) = create_market_key();
+        let book = Orderbook::new(market_key);
+        let order_id = Uuid::new_v4();
+        book.add_order(create_test_order(order_id, dec!(0.40), dec!(100), Side::Buy))
+            .unwrap();
+
+        let first = book.cached_depth();
+        assert!(Arc::ptr_eq(&first, &book.cached_depth()));
+        assert_eq!(first.bids[0], ["0.40".to_string(), "100".to_string()]);
+        let json: serde_json::Value = serde_json::from_str(&first.json).unwrap();
+        assert_eq!(json["bids"][0][1], "100");
+
+        book.fill_order(order_id, dec!(40));
+        let second = book.cached_depth();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(second.bids[0][1], "60");
+        assert_eq!(book.snapshot(5).bids, second.bids.to_vec());
+
+        book.cancel_order(order_id);
+        assert!(book.cached_depth().bids.is_empty());
+    }
+
+    #[test]
+    fn test_self_trade_prevention_modes() {
+        // Our own ask at 0.60 ahead of someone else's at 0.61
+        let book_with_own_ask = || {
+            let (market_key, _, _) = create_market_key();
+            let book = Orderbook::new(market_key);
+            let own_id = Uuid::new_v4();
+            book.add_order(create_test_order(own_id, dec!(0.60), dec!(100), Side::Sell)).unwrap();
+            let mut other = create_test_order(Uuid::new_v4(), dec!(0.61), dec!(100), Side::Sell);
+            other.user_address = "0xother".to_string();
+            book.add_order(other).unwrap();
+            (book, own_id)
+        };
+        let buy = |book: &Orderbook, stp| {
+            book.match_order_with_makers(
+                &Taker::new(Uuid::new_v4(), "0x1234").with_stp(stp),
+                Side::Buy,
+                dec!(150),
+                Some(dec!(0.65)),
+                &FeeConfig::default(),
+            )
+        };
+
+        let (book, own_id) = book_with_own_ask();
+        let matched = buy(&book, SelfTradePrevention::CancelNewest);
+        assert!(matched.stopped && matched.trades.is_empty() && matched.self_trades.is_empty());
+        assert_eq!(matched.remaining, dec!(150));
+        assert!(book.has_order(&own_id));
+
+        let (book, own_id) = book_with_own_ask();
+        let matched = buy(&book, SelfTradePrevention::CancelOldest);
+        assert_eq!(matched.self_trades[0].1, dec!(100));
+        assert_eq!((matched.trades.len(), matched.trades[0].price), (1, dec!(0.61)));
+        assert_eq!(matched.remaining, dec!(50));
+        assert!(!book.has_order(&own_id));
+
+        let (book, own_id) = book_with_own_ask();
+        let matched = buy(&book, SelfTradePrevention::CancelBoth);
+        assert!(matched.stopped && matched.trades.is_empty());
+        assert_eq!(matched.self_trades.len(), 1);
+        assert!(!book.has_order(&own_id));
+        assert_eq!(book.order_count(), 1);
+
+        let (book, own_id) = book_with_own_ask();
+        let matched = buy(&book, SelfTradePrevention::Decrement);
+        assert_eq!((matched.decremented, matched.self_trades[0].1), (dec!(100), dec!(100)));
+        assert_eq!(matched.trades[0].amount, dec!(50));
+        assert_eq!(matched.remaining, dec!(0));
+        assert!(!book.has_order(&own_id));
+    }
 }
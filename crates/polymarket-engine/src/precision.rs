@@ -0,0 +1,202 @@
+//! Decimal Precision Policy
+//!
+//! Every amount that moves money has a defined precision:
+//!
+//! - **Collateral** (balances, fees, notionals, payouts): [`COLLATERAL_DP`]
+//!   decimals, matching USDC on-chain
+//! - **Shares** (order and position quantities): [`DEFAULT_SHARE_DP`]
+//!   decimals unless the market configures its own [`SharePrecision`]
+//! - **Average prices**: [`PRICE_DP`] decimals
+//!
+//! Derived amounts are rounded exactly once, with banker's rounding
+//! (midpoint to even), through the constructors below. Rounding each
+//! component the same way is what lets independently computed totals (fee
+//! ledger vs. trade fees, payouts vs. balance credits) reconcile exactly.
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+/// Collateral decimals
+pub const COLLATERAL_DP: u32 = 6;
+
+/// Share quantity decimals unless a market overrides it
+pub const DEFAULT_SHARE_DP: u32 = 2;
+
+/// Average/derived price decimals
+pub const PRICE_DP: u32 = 6;
+
+/// Largest share precision a market may configure
+pub const MAX_SHARE_DP: u32 = COLLATERAL_DP;
+
+/// Round `value` to `dp` decimals, midpoint to even
+pub fn round_bankers(value: Decimal, dp: u32) -> Decimal {
+    value.round_dp_with_strategy(dp, RoundingStrategy::MidpointNearestEven)
+}
+
+/// Whether `value` has no more than `dp` significant decimals
+pub fn fits_dp(value: Decimal, dp: u32) -> bool {
+    value.normalize().scale() <= dp
+}
+
+/// Volume-weighted average price of fills totalling `notional` over `amount`
+pub fn average_price(notional: Decimal, amount: Decimal) -> Option<Decimal> {
+    (!amount.is_zero()).then(|| round_bankers(notional / amount, PRICE_DP))
+}
+
+/// Collateral amount, always at [`COLLATERAL_DP`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Collateral(Decimal);
+
+impl Collateral {
+    pub const ZERO: Collateral = Collateral(Decimal::ZERO);
+
+    /// Round `value` to collateral precision
+    pub fn new(value: Decimal) -> Self {
+        Self(round_bankers(value, COLLATERAL_DP))
+    }
+
+    /// `value` if it is already at collateral precision (e.g. user input)
+    pub fn exact(value: Decimal) -> Option<Self> {
+        fits_dp(value, COLLATERAL_DP).then_some(Self(value))
+    }
+
+    /// `price * shares`, rounded once
+    pub fn notional(price: Decimal, shares: Decimal) -> Self {
+        Self::new(price * shares)
+    }
+
+    pub fn value(self) -> Decimal {
+        self.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl From<Collateral> for Decimal {
+    fn from(c: Collateral) -> Self {
+        c.0
+    }
+}
+
+impl fmt::Display for Collateral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// Sums and differences of values at the same precision stay exact
+impl Add for Collateral {
+    type Output = Collateral;
+    fn add(self, rhs: Collateral) -> Collateral {
+        Collateral(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Collateral {
+    type Output = Collateral;
+    fn sub(self, rhs: Collateral) -> Collateral {
+        Collateral(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Collateral {
+    type Output = Collateral;
+    fn neg(self) -> Collateral {
+        Collateral(-self.0)
+    }
+}
+
+impl AddAssign for Collateral {
+    fn add_assign(&mut self, rhs: Collateral) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Collateral {
+    fn sub_assign(&mut self, rhs: Collateral) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Collateral {
+    fn sum<I: Iterator<Item = Collateral>>(iter: I) -> Self {
+        iter.fold(Collateral::ZERO, Add::add)
+    }
+}
+
+/// Share quantity precision of a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SharePrecision(u32);
+
+impl SharePrecision {
+    /// `None` if `dp` exceeds [`MAX_SHARE_DP`]
+    pub fn new(dp: u32) -> Option<Self> {
+        (dp <= MAX_SHARE_DP).then_some(Self(dp))
+    }
+
+    pub fn dp(self) -> u32 {
+        self.0
+    }
+
+    /// Round a share quantity to this precision
+    pub fn round(self, value: Decimal) -> Decimal {
+        round_bankers(value, self.0)
+    }
+
+    /// Whether `value` is a valid quantity at this precision
+    pub fn accepts(self, value: Decimal) -> bool {
+        fits_dp(value, self.0)
+    }
+}
+
+impl Default for SharePrecision {
+    fn default() -> Self {
+        Self(DEFAULT_SHARE_DP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_bankers_rounding() {
+        assert_eq!(Collateral::new(dec!(0.0000005)).value(), dec!(0.000000));
+        assert_eq!(Collateral::new(dec!(0.0000015)).value(), dec!(0.000002));
+        assert_eq!(Collateral::new(dec!(-0.0000025)).value(), dec!(-0.000002));
+        assert_eq!(SharePrecision::default().round(dec!(1.005)), dec!(1.00));
+        assert_eq!(SharePrecision::default().round(dec!(1.015)), dec!(1.02));
+    }
+
+    #[test]
+    fn test_exact_and_precision_checks() {
+        assert!(Collateral::exact(dec!(1.500000000)).is_some());
+        assert!(Collateral::exact(dec!(0.0000001)).is_none());
+        assert!(SharePrecision::default().accepts(dec!(10.50)));
+        assert!(!SharePrecision::default().accepts(dec!(10.505)));
+        assert!(SharePrecision::new(0).unwrap().accepts(dec!(3.0)));
+        assert!(SharePrecision::new(7).is_none());
+    }
+
+    #[test]
+    fn test_rounded_parts_reconcile() {
+        // Each fill's notional is rounded once; the sum of the parts stays at
+        // collateral precision, so it matches the ledger total to the unit
+        let fills = [dec!(0.33), dec!(0.33), dec!(0.34)];
+        let total: Collateral = fills.iter().map(|s| Collateral::notional(dec!(0.3333335), *s)).sum();
+        assert_eq!(total.value(), dec!(0.110000) + dec!(0.110000) + dec!(0.113333));
+        assert!(Collateral::exact(total.value()).is_some());
+
+        assert_eq!(average_price(dec!(5.5), dec!(3)), Some(dec!(1.833333)));
+        assert_eq!(average_price(dec!(1), Decimal::ZERO), None);
+    }
+}
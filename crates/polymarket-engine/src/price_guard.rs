@@ -0,0 +1,230 @@
+//! Price Bands and Circuit Breaker
+//!
+//! Two guards against a book trading far from where its market was:
+//!
+//! - **Price band**: a limit order priced more than `band` away from the
+//!   book's reference price - its last trade, or the midpoint before it has
+//!   traded - is rejected with [`MatchingError::OutsidePriceBand`].
+//! - **Circuit breaker**: once a book's trades move more than `halt_move`
+//!   within `halt_window_ms`, its market is halted. Orders for every book of
+//!   a halted market are rejected with [`MatchingError::TradingHalted`]
+//!   until an operator resumes it; cancels still go through.
+//!
+//! A binary market's books mirror each other (Yes at `p` is No at `1 - p`),
+//! so a trade moves the reference price of both. Both guards are off by
+//! default. Last prices and halts live in memory: a restart clears them.
+
+use std::collections::VecDeque;
+
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::types::MatchingError;
+
+/// Price band and circuit breaker settings; `None` turns a guard off
+#[derive(Debug, Clone, Default)]
+pub struct PriceGuardConfig {
+    /// Furthest a limit order's price may be from the reference price
+    pub band: Option<Decimal>,
+    /// Price move within `halt_window_ms` that halts the market
+    pub halt_move: Option<Decimal>,
+    pub halt_window_ms: i64,
+}
+
+/// A market halted by the circuit breaker
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketHalt {
+    /// Market part of the book keys (`{market_id}`, namespaced if the
+    /// books are)
+    pub market: String,
+    /// Book whose trade tripped the breaker
+    pub symbol: String,
+    /// Price of the window's trade furthest from `to_price`
+    pub from_price: Decimal,
+    /// Price of the trade that tripped the breaker
+    pub to_price: Decimal,
+    pub window_ms: i64,
+    pub halted_at: i64,
+}
+
+/// Market part of a book key
+pub(crate) fn market_of(symbol: &str) -> &str {
+    symbol.split(':').next().unwrap_or(symbol)
+}
+
+#[derive(Debug, Default)]
+struct BookPrices {
+    last: Option<Decimal>,
+    /// Trades within the breaker window: (timestamp ms, price)
+    recent: VecDeque<(i64, Decimal)>,
+}
+
+/// Per-book price state and halted markets
+#[derive(Debug, Default)]
+pub(crate) struct PriceGuard {
+    config: PriceGuardConfig,
+    prices: DashMap<String, BookPrices>,
+    halts: DashMap<String, MarketHalt>,
+}
+
+impl PriceGuard {
+    pub(crate) fn new(config: PriceGuardConfig) -> Self {
+        Self {
+            config,
+            prices: DashMap::new(),
+            halts: DashMap::new(),
+        }
+    }
+
+    pub(crate) fn config(&self) -> &PriceGuardConfig {
+        &self.config
+    }
+
+    /// The halt of `symbol`'s market, if halted
+    pub(crate) fn halt(&self, symbol: &str) -> Option<MarketHalt> {
+        self.halts.get(market_of(symbol)).map(|halt| halt.clone())
+    }
+
+    pub(crate) fn halts(&self) -> Vec<MarketHalt> {
+        self.halts.iter().map(|halt| halt.clone()).collect()
+    }
+
+    /// Last trade price of `symbol`, mirrored trades included
+    pub(crate) fn last_price(&self, symbol: &str) -> Option<Decimal> {
+        self.prices.get(symbol).and_then(|prices| prices.last)
+    }
+
+    /// Refuse `price` if it is more than the band away from `reference`,
+    /// which is only looked up with a band configured
+    pub(crate) fn check_band(
+        &self,
+        price: Decimal,
+        reference: impl FnOnce() -> Option<Decimal>,
+    ) -> Result<(), MatchingError> {
+        let Some(band) = self.config.band else {
+            return Ok(());
+        };
+        match reference() {
+            Some(reference) if (price - reference).abs() > band => {
+                Err(MatchingError::OutsidePriceBand { price, reference, band })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Record trades of `symbol` at `prices` (and the mirrored prices in its
+    /// complement book). Returns the halt if they tripped the breaker.
+    pub(crate) fn record(
+        &self,
+        symbol: &str,
+        complement: Option<&str>,
+        now: i64,
+        prices: impl IntoIterator<Item = Decimal>,
+    ) -> Option<MarketHalt> {
+        let mut tripped = None;
+        for price in prices {
+            if let Some(complement) = complement {
+                self.push(complement, now, Decimal::ONE - price);
+            }
+            if let Some(from_price) = self.push(symbol, now, price) {
+                tripped.get_or_insert((from_price, price));
+            }
+        }
+
+        let (from_price, to_price) = tripped?;
+        let market = market_of(symbol).to_string();
+        if self.halts.contains_key(&market) {
+            return None;
+        }
+        let halt = MarketHalt {
+            market: market.clone(),
+            symbol: symbol.to_string(),
+            from_price,
+            to_price,
+            window_ms: self.config.halt_window_ms,
+            halted_at: now,
+        };
+        self.halts.insert(market, halt.clone());
+        Some(halt)
+    }
+
+    /// Record one trade; returns the window's price furthest from it if
+    /// the move breaches the breaker
+    fn push(&self, symbol: &str, now: i64, price: Decimal) -> Option<Decimal> {
+        let mut prices = self.prices.entry(symbol.to_string()).or_default();
+        prices.last = Some(price);
+        let halt_move = self.config.halt_move?;
+
+        let since = now - self.config.halt_window_ms;
+        while prices.recent.front().is_some_and(|(at, _)| *at < since) {
+            prices.recent.pop_front();
+        }
+        let furthest = prices
+            .recent
+            .iter()
+            .map(|(_, p)| *p)
+            .max_by_key(|p| (*p - price).abs());
+        prices.recent.push_back((now, price));
+        furthest.filter(|p| (*p - price).abs() > halt_move)
+    }
+
+    /// Lift the halt of `market`. Its books' windows start over, so the
+    /// move that halted it doesn't trip the breaker again.
+    pub(crate) fn resume(&self, market: &str) -> Option<MarketHalt> {
+        let (_, halt) = self.halts.remove(market)?;
+        for mut prices in self.prices.iter_mut().filter(|p| market_of(p.key()) == market) {
+            prices.recent.clear();
+        }
+        Some(halt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn guard() -> PriceGuard {
+        PriceGuard::new(PriceGuardConfig {
+            band: Some(dec!(0.1)),
+            halt_move: Some(dec!(0.2)),
+            halt_window_ms: 10_000,
+        })
+    }
+
+    #[test]
+    fn test_breaker_trips_within_window_only() {
+        let guard = guard();
+        let (yes, no) = ("m:o:Yes", "m:o:No");
+
+        // Moves spread over more than the window don't trip it
+        assert_eq!(guard.record(yes, Some(no), 0, [dec!(0.5)]), None);
+        assert_eq!(guard.record(yes, Some(no), 8_000, [dec!(0.65)]), None);
+        assert_eq!(guard.record(yes, Some(no), 16_000, [dec!(0.8)]), None);
+        assert_eq!(guard.last_price(no), Some(dec!(0.2)));
+
+        let halt = guard.record(no, Some(yes), 17_000, [dec!(0.3), dec!(0.45)]).unwrap();
+        assert_eq!((halt.market.as_str(), halt.from_price, halt.to_price), ("m", dec!(0.2), dec!(0.45)));
+        assert!(guard.halt(yes).is_some());
+        assert_eq!(guard.halts().len(), 1);
+
+        // Resuming starts the windows over
+        assert_eq!(guard.resume("m"), Some(halt));
+        assert_eq!(guard.halt(yes), None);
+        assert_eq!(guard.record(yes, Some(no), 18_000, [dec!(0.55)]), None);
+    }
+
+    #[test]
+    fn test_band_against_reference() {
+        let guard = guard();
+        assert!(guard.check_band(dec!(0.6), || Some(dec!(0.5))).is_ok());
+        assert!(matches!(
+            guard.check_band(dec!(0.61), || Some(dec!(0.5))),
+            Err(MatchingError::OutsidePriceBand { band, .. }) if band == dec!(0.1)
+        ));
+        // Without a reference there is nothing to band against
+        assert!(guard.check_band(dec!(0.99), || None).is_ok());
+        assert!(PriceGuard::default().check_band(dec!(0.99), || Some(dec!(0.01))).is_ok());
+    }
+}
@@ -0,0 +1,513 @@
+//! Engine Invariant Properties
+//!
+//! Random streams of orders and cancellations over the Yes and No books of
+//! one outcome, checked against a model of every order after each step:
+//!
+//! - no book is left crossed, and with complement matching on no Yes/No
+//!   pair is left resting that could mint or merge
+//! - every fill respects both parties' limits, and shares and collateral
+//!   are conserved across Normal, Mint and Merge fills
+//! - fees follow the schedule at each party's execution price and are the
+//!   same for an outcome and its complement
+//! - cancellation removes exactly the orders still resting
+//! - an order that reaches its own account's resting order stops there
+//!   (self-trade prevention, cancel newest) instead of trading with it
+//!
+//! Built with the `proptest` feature:
+//! `cargo test -p polymarket-engine --features proptest`
+
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{FeeConfig, MatchType, MatchingEngine, OrderStatus, OrderType, ShareType, Side, TimeInForce, TradeExecution};
+
+const USERS: [&str; 3] = ["0xa1", "0xb2", "0xc3"];
+
+#[derive(Debug, Clone)]
+enum Op {
+    Place {
+        user: usize,
+        share_type: ShareType,
+        side: Side,
+        order_type: OrderType,
+        /// Limit price in cents (ignored by market orders)
+        price: i64,
+        /// Shares in hundredths
+        amount: i64,
+    },
+    Cancel(Index),
+    CancelAll(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let place = (
+        0..USERS.len(),
+        prop_oneof![Just(ShareType::Yes), Just(ShareType::No)],
+        prop_oneof![Just(Side::Buy), Just(Side::Sell)],
+        prop_oneof![4 => Just(OrderType::Limit), 1 => Just(OrderType::Market)],
+        1i64..=99,
+        1i64..=5_000,
+    )
+        .prop_map(|(user, share_type, side, order_type, price, amount)| Op::Place {
+            user,
+            share_type,
+            side,
+            order_type,
+            price,
+            amount,
+        });
+    prop_oneof![
+        6 => place,
+        2 => any::<Index>().prop_map(Op::Cancel),
+        1 => (0..USERS.len()).prop_map(Op::CancelAll),
+    ]
+}
+
+/// What the model knows about a submitted order
+#[derive(Debug)]
+struct ModelOrder {
+    user: &'static str,
+    share_type: ShareType,
+    side: Side,
+    limit: Option<Decimal>,
+    amount: Decimal,
+    filled: Decimal,
+    cancelled: Decimal,
+    resting: bool,
+    /// Submission sequence: time priority within a price level
+    seq: usize,
+}
+
+impl ModelOrder {
+    fn open(&self) -> Decimal {
+        self.amount - self.filled - self.cancelled
+    }
+
+    /// Whether this resting order is one `taker` would match next at
+    /// `price`, in its own book or through Mint/Merge
+    fn crosses(&self, taker: &ModelOrder, complement: bool) -> bool {
+        let Some(price) = self.limit.filter(|_| self.resting) else {
+            return false;
+        };
+        if self.share_type == taker.share_type {
+            self.side != taker.side
+                && match (taker.side, taker.limit) {
+                    (_, None) => true,
+                    (Side::Buy, Some(limit)) => price <= limit,
+                    (Side::Sell, Some(limit)) => price >= limit,
+                }
+        } else {
+            // Mint/Merge needs a taker price
+            complement
+                && self.side == taker.side
+                && match (taker.side, taker.limit) {
+                    (_, None) => false,
+                    (Side::Buy, Some(limit)) => limit + price >= Decimal::ONE,
+                    (Side::Sell, Some(limit)) => limit + price <= Decimal::ONE,
+                }
+        }
+    }
+}
+
+/// Collateral and shares a user gained (negative: paid or delivered)
+#[derive(Debug, Default)]
+struct Position {
+    collateral: Decimal,
+    yes: Decimal,
+    no: Decimal,
+}
+
+impl Position {
+    fn shares(&mut self, share_type: ShareType) -> &mut Decimal {
+        match share_type {
+            ShareType::Yes => &mut self.yes,
+            ShareType::No => &mut self.no,
+        }
+    }
+}
+
+struct Harness {
+    engine: MatchingEngine,
+    fees: FeeConfig,
+    complement: bool,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    orders: HashMap<Uuid, ModelOrder>,
+    /// Submission order, for picking cancellation targets
+    ids: Vec<Uuid>,
+    positions: HashMap<&'static str, Position>,
+    /// Pairs minted less pairs merged
+    pairs: Decimal,
+}
+
+impl Harness {
+    fn new(complement: bool) -> Self {
+        let engine = MatchingEngine::new();
+        engine.set_complement_matching(complement);
+        Self {
+            engine,
+            fees: FeeConfig::default(),
+            complement,
+            market_id: Uuid::new_v4(),
+            outcome_id: Uuid::new_v4(),
+            orders: HashMap::new(),
+            ids: Vec::new(),
+            positions: HashMap::new(),
+            pairs: Decimal::ZERO,
+        }
+    }
+
+    fn key(&self, share_type: ShareType) -> String {
+        format!("{}:{}:{}", self.market_id, self.outcome_id, share_type)
+    }
+
+    fn apply(&mut self, op: &Op) -> Result<(), TestCaseError> {
+        match *op {
+            Op::Place {
+                user,
+                share_type,
+                side,
+                order_type,
+                price,
+                amount,
+            } => self.place(USERS[user], share_type, side, order_type, Decimal::new(price, 2), Decimal::new(amount, 2)),
+            Op::Cancel(index) => {
+                if self.ids.is_empty() {
+                    return Ok(());
+                }
+                let id = self.ids[index.index(self.ids.len())];
+                self.cancel(id)
+            }
+            Op::CancelAll(user) => self.cancel_all(USERS[user]),
+        }
+    }
+
+    fn place(
+        &mut self,
+        user: &'static str,
+        share_type: ShareType,
+        side: Side,
+        order_type: OrderType,
+        price: Decimal,
+        amount: Decimal,
+    ) -> Result<(), TestCaseError> {
+        let id = Uuid::new_v4();
+        let limit = (order_type == OrderType::Limit).then_some(price);
+        let result = self
+            .engine
+            .submit_order(id, &self.key(share_type), user, side, order_type, amount, limit, 1, TimeInForce::GTC)
+            .map_err(|e| TestCaseError::fail(format!("order rejected: {}", e)))?;
+
+        let filled: Decimal = result.trades.iter().map(|t| t.amount).sum();
+        prop_assert_eq!(result.filled_amount, filled);
+        prop_assert_eq!(result.filled_amount + result.remaining_amount, amount);
+        // Cancel newest never touches the resting order or shrinks the taker
+        prop_assert!(result.self_trades.is_empty());
+        prop_assert_eq!(result.decremented, Decimal::ZERO);
+
+        self.orders.insert(
+            id,
+            ModelOrder {
+                user,
+                share_type,
+                side,
+                limit,
+                amount,
+                filled: Decimal::ZERO,
+                cancelled: Decimal::ZERO,
+                resting: false,
+                seq: self.ids.len(),
+            },
+        );
+        self.ids.push(id);
+        for trade in &result.trades {
+            self.check_trade(id, trade)?;
+        }
+
+        // Whatever is left unfilled either met nothing it crosses, or
+        // stopped at the first maker in line because it was its own
+        let next = self.next_maker(id);
+        let stopped = result.remaining_amount > Decimal::ZERO && next.is_some();
+        if let Some(maker) = next.filter(|_| stopped) {
+            prop_assert_eq!(self.orders[&maker].user, user, "crossing maker {} left untraded", maker);
+        }
+        let expected_status = if filled == amount {
+            OrderStatus::Filled
+        } else if order_type == OrderType::Market {
+            if filled > Decimal::ZERO {
+                OrderStatus::PartiallyFilled
+            } else {
+                OrderStatus::Expired
+            }
+        } else if stopped {
+            OrderStatus::Cancelled
+        } else if filled > Decimal::ZERO {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Open
+        };
+        prop_assert_eq!(result.status, expected_status);
+
+        // Market orders never rest: their unfilled part expires, as does a
+        // stopped limit order's
+        let order = self.orders.get_mut(&id).unwrap();
+        if limit.is_none() || stopped {
+            order.cancelled = result.remaining_amount;
+        } else {
+            order.resting = result.remaining_amount > Decimal::ZERO;
+        }
+        Ok(())
+    }
+
+    /// The resting order `taker_id` would match next: the best price in its
+    /// own book, then (its own book crossed no further) the best in the
+    /// complement book, oldest first within a price
+    fn next_maker(&self, taker_id: Uuid) -> Option<Uuid> {
+        let taker = &self.orders[&taker_id];
+        let best = |same_book: bool| {
+            self.orders
+                .iter()
+                .filter(|(_, o)| (o.share_type == taker.share_type) == same_book && o.crosses(taker, self.complement))
+                .min_by_key(|(_, o)| {
+                    let price = o.limit.unwrap();
+                    // Best first: lowest ask, highest bid
+                    let rank = if o.side == Side::Sell { price } else { -price };
+                    (rank, o.seq)
+                })
+                .map(|(id, _)| *id)
+        };
+        best(true).or_else(|| best(false))
+    }
+
+    /// Check one fill against both orders' limits and the fee schedule,
+    /// then book it in the model
+    fn check_trade(&mut self, taker_id: Uuid, trade: &TradeExecution) -> Result<(), TestCaseError> {
+        let complement_price = Decimal::ONE - trade.price;
+        prop_assert!(trade.amount > Decimal::ZERO);
+        prop_assert!(trade.price > Decimal::ZERO && trade.price < Decimal::ONE);
+        prop_assert_eq!(trade.taker_order_id, taker_id);
+
+        let taker = &self.orders[&taker_id];
+        let maker = self
+            .orders
+            .get(&trade.maker_order_id)
+            .ok_or_else(|| TestCaseError::fail("fill against an unknown maker"))?;
+        prop_assert!(maker.resting, "maker {} was not resting", trade.maker_order_id);
+        prop_assert!(trade.amount <= maker.open() && trade.amount <= taker.open());
+        prop_assert_eq!(trade.maker_address.as_str(), maker.user);
+        prop_assert_ne!(maker.user, taker.user, "self-trade");
+        prop_assert_eq!(trade.share_type, taker.share_type);
+
+        // The price each party trades its own share type at
+        let maker_price = match trade.match_type {
+            MatchType::Normal => {
+                prop_assert_eq!(maker.share_type, taker.share_type);
+                prop_assert!(maker.side != taker.side);
+                prop_assert_eq!(Some(trade.price), maker.limit, "normal fills at the maker's price");
+                trade.price
+            }
+            MatchType::Mint | MatchType::Merge => {
+                prop_assert!(self.complement, "complement fill with complement matching off");
+                prop_assert_eq!(maker.share_type, taker.share_type.complement());
+                prop_assert_eq!(maker.side, taker.side);
+                prop_assert_eq!(taker.side == Side::Buy, trade.match_type == MatchType::Mint);
+                prop_assert_eq!(Some(trade.price), taker.limit, "complement fills at the taker's price");
+                complement_price
+            }
+        };
+        let within = |side: Side, price: Decimal, limit: Option<Decimal>| match (side, limit) {
+            (_, None) => true,
+            (Side::Buy, Some(limit)) => price <= limit,
+            (Side::Sell, Some(limit)) => price >= limit,
+        };
+        prop_assert!(within(taker.side, trade.price, taker.limit), "taker limit breached");
+        prop_assert!(within(maker.side, maker_price, maker.limit), "maker limit breached");
+
+        // Fees: schedule at the execution price, symmetric in the complement
+        prop_assert_eq!(trade.taker_fee, self.fees.calculate_taker_fee(trade.price, trade.amount));
+        prop_assert_eq!(trade.maker_fee, self.fees.calculate_maker_fee(maker_price, trade.amount));
+        prop_assert_eq!(
+            self.fees.calculate_taker_fee(trade.price, trade.amount),
+            self.fees.calculate_taker_fee(complement_price, trade.amount)
+        );
+        prop_assert_eq!(
+            self.fees.calculate_maker_fee(trade.price, trade.amount),
+            self.fees.calculate_maker_fee(complement_price, trade.amount)
+        );
+        prop_assert!(trade.maker_fee <= trade.taker_fee);
+
+        // Book the fill: buyers pay their execution price, sellers receive it
+        let (taker_user, taker_side, taker_type) = (taker.user, taker.side, taker.share_type);
+        let (maker_user, maker_side, maker_type) = (maker.user, maker.side, maker.share_type);
+        for (user, side, share_type, price) in [
+            (taker_user, taker_side, taker_type, trade.price),
+            (maker_user, maker_side, maker_type, maker_price),
+        ] {
+            let position = self.positions.entry(user).or_default();
+            let (shares, collateral) = match side {
+                Side::Buy => (trade.amount, -price * trade.amount),
+                Side::Sell => (-trade.amount, price * trade.amount),
+            };
+            *position.shares(share_type) += shares;
+            position.collateral += collateral;
+        }
+        match trade.match_type {
+            MatchType::Normal => {}
+            MatchType::Mint => self.pairs += trade.amount,
+            MatchType::Merge => self.pairs -= trade.amount,
+        }
+
+        let maker = self.orders.get_mut(&trade.maker_order_id).unwrap();
+        maker.filled += trade.amount;
+        maker.resting = maker.open() > Decimal::ZERO;
+        self.orders.get_mut(&taker_id).unwrap().filled += trade.amount;
+        Ok(())
+    }
+
+    fn cancel(&mut self, id: Uuid) -> Result<(), TestCaseError> {
+        let order = &self.orders[&id];
+        let cancelled = self
+            .engine
+            .cancel_order(&self.key(order.share_type), id, order.user)
+            .map_err(|e| TestCaseError::fail(format!("cancel failed: {}", e)))?;
+        prop_assert_eq!(cancelled, order.resting, "cancel of {} disagrees with the model", id);
+
+        let order = self.orders.get_mut(&id).unwrap();
+        if cancelled {
+            order.cancelled = order.open();
+            order.resting = false;
+        }
+        Ok(())
+    }
+
+    fn cancel_all(&mut self, user: &'static str) -> Result<(), TestCaseError> {
+        let mut cancelled: Vec<Uuid> = self
+            .engine
+            .cancel_all_for_user(user)
+            .into_iter()
+            .map(|(_, entry)| entry.id)
+            .collect();
+        let mut expected: Vec<Uuid> = self
+            .orders
+            .iter()
+            .filter(|(_, o)| o.user == user && o.resting)
+            .map(|(id, _)| *id)
+            .collect();
+        cancelled.sort();
+        expected.sort();
+        prop_assert_eq!(&cancelled, &expected);
+
+        for id in cancelled {
+            let order = self.orders.get_mut(&id).unwrap();
+            order.cancelled = order.open();
+            order.resting = false;
+        }
+        Ok(())
+    }
+
+    /// Invariants that hold between any two operations
+    fn check_books(&self) -> Result<(), TestCaseError> {
+        let mut best = HashMap::new();
+        for share_type in [ShareType::Yes, ShareType::No] {
+            let Some(book) = self.engine.get_orderbook_ref(&self.key(share_type)) else {
+                best.insert(share_type, (None, None));
+                continue;
+            };
+            let (bid, ask) = (book.best_bid(), book.best_ask());
+            if let (Some(bid), Some(ask)) = (bid, ask) {
+                prop_assert!(bid < ask, "{} book crossed: bid {} >= ask {}", share_type, bid, ask);
+            }
+            best.insert(share_type, (bid, ask));
+
+            // The book holds exactly the orders the model has resting
+            for entry in book.orders() {
+                let order = self
+                    .orders
+                    .get(&entry.id)
+                    .ok_or_else(|| TestCaseError::fail("unknown order resting"))?;
+                prop_assert!(order.resting);
+                prop_assert_eq!(order.share_type, share_type);
+                prop_assert_eq!(entry.remaining_amount, order.open());
+            }
+            let resting = self.orders.values().filter(|o| o.resting && o.share_type == share_type).count();
+            prop_assert_eq!(book.order_count(), resting as i64);
+        }
+
+        if self.complement {
+            let (yes_bid, yes_ask) = best[&ShareType::Yes];
+            let (no_bid, no_ask) = best[&ShareType::No];
+            if let (Some(yes), Some(no)) = (yes_bid, no_bid) {
+                prop_assert!(yes + no < Decimal::ONE, "mintable bids left resting: {} + {}", yes, no);
+            }
+            if let (Some(yes), Some(no)) = (yes_ask, no_ask) {
+                prop_assert!(yes + no > Decimal::ONE, "mergeable asks left resting: {} + {}", yes, no);
+            }
+        }
+
+        // Every share of every order is filled, resting, cancelled or expired
+        for order in self.orders.values() {
+            prop_assert!(order.open() >= Decimal::ZERO);
+            prop_assert_eq!(order.resting, order.open() > Decimal::ZERO);
+        }
+
+        // Fills only move shares and collateral between users, except that
+        // each minted pair costs 1 and each merged pair pays out 1
+        let (mut collateral, mut yes, mut no) = (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+        for position in self.positions.values() {
+            collateral += position.collateral;
+            yes += position.yes;
+            no += position.no;
+        }
+        prop_assert_eq!(yes, self.pairs);
+        prop_assert_eq!(no, self.pairs);
+        prop_assert_eq!(collateral, -self.pairs);
+        Ok(())
+    }
+
+    /// Cancel everything still resting and check the books end up empty
+    fn drain(&mut self) -> Result<(), TestCaseError> {
+        for id in self.ids.clone() {
+            self.cancel(id)?;
+        }
+        for share_type in [ShareType::Yes, ShareType::No] {
+            if let Some(book) = self.engine.get_orderbook_ref(&self.key(share_type)) {
+                prop_assert_eq!(book.order_count(), 0);
+                prop_assert!(book.bid_levels().is_empty() && book.ask_levels().is_empty());
+            }
+        }
+        Ok(())
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    #[test]
+    fn prop_engine_invariants(complement in any::<bool>(), ops in prop::collection::vec(op(), 1..80)) {
+        let mut harness = Harness::new(complement);
+        for op in &ops {
+            harness.apply(op)?;
+            harness.check_books()?;
+        }
+        harness.drain()?;
+        harness.check_books()?;
+    }
+
+    #[test]
+    fn prop_fee_symmetric(price in 1i64..=99, amount in 1i64..=1_000_000) {
+        let fees = FeeConfig::default();
+        let (price, amount) = (Decimal::new(price, 2), Decimal::new(amount, 2));
+        prop_assert_eq!(
+            fees.calculate_taker_fee(price, amount),
+            fees.calculate_taker_fee(Decimal::ONE - price, amount)
+        );
+        prop_assert_eq!(
+            fees.calculate_maker_fee(price, amount),
+            fees.calculate_maker_fee(Decimal::ONE - price, amount)
+        );
+    }
+}
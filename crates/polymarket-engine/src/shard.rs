@@ -0,0 +1,378 @@
+//! Market Sharding
+//!
+//! Without sharding every order runs on whichever tokio worker picked up the
+//! request, so a burst in one market competes with all others for the same
+//! runtime and the same orderbook map shards. [`EngineShards`] assigns each
+//! market to one of N dedicated OS threads (optionally pinned to a CPU core)
+//! with its own bounded queue:
+//!
+//! - all books of a market (`market:outcome:share_type`, including the
+//!   complement books used for mint/merge) are touched by a single thread,
+//!   which keeps them hot in that core's cache and serializes their matching;
+//! - a market that floods its shard fills that shard's queue only; further
+//!   jobs for it are refused with [`MatchingError::Overloaded`] instead of
+//!   delaying every other market.
+//!
+//! Admission control sits in front of the queue: once a shard's backlog
+//! reaches `admission_limit`, [`EngineShards::admit`] refuses new orders
+//! before the caller does any work for them, with a retry hint derived
+//! from the backlog and the shard's recent job time. Cancels skip admission
+//! and may use the headroom up to `queue_capacity`, so users can always
+//! pull liquidity from a hot market.
+//!
+//! With zero shards jobs run inline on the caller, matching the unsharded
+//! engine exactly.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::engine::MatchingEngine;
+use crate::metrics;
+use crate::types::MatchingError;
+
+/// Sharding configuration
+#[derive(Debug, Clone)]
+pub struct ShardConfig {
+    /// Number of shard threads; 0 runs jobs inline on the caller
+    pub shards: usize,
+    /// Jobs a shard queues before refusing more
+    pub queue_capacity: usize,
+    /// Backlog at which [`EngineShards::admit`] starts refusing new orders
+    pub admission_limit: usize,
+    /// Pin shard `i` to core `i % cores`
+    pub pin_threads: bool,
+}
+
+impl Default for ShardConfig {
+    fn default() -> Self {
+        Self {
+            shards: 0,
+            queue_capacity: 10_000,
+            admission_limit: 8_000,
+            pin_threads: false,
+        }
+    }
+}
+
+/// Point-in-time view of one shard
+#[derive(Debug, Clone, Serialize)]
+pub struct ShardStats {
+    pub shard: usize,
+    pub queue_depth: usize,
+    pub processed: u64,
+    /// Jobs refused because the queue was full
+    pub rejected: u64,
+    /// Orders refused by admission control
+    pub refused: u64,
+    /// Smoothed job execution time
+    pub avg_job_micros: u64,
+    /// Core the shard thread is pinned to
+    pub core: Option<usize>,
+}
+
+type Job = Box<dyn FnOnce(&MatchingEngine) + Send>;
+
+struct Shard {
+    sender: SyncSender<Job>,
+    depth: Arc<AtomicUsize>,
+    processed: Arc<AtomicU64>,
+    avg_job_micros: Arc<AtomicU64>,
+    rejected: AtomicU64,
+    refused: AtomicU64,
+    core: Option<usize>,
+}
+
+impl Shard {
+    /// How long a job queued now would wait, in ms
+    fn retry_after_ms(&self) -> u64 {
+        let backlog = self.depth.load(Ordering::Relaxed) as u64;
+        // Before the first job completes assume 100µs per job
+        let per_job = match self.avg_job_micros.load(Ordering::Relaxed) {
+            0 => 100,
+            micros => micros,
+        };
+        (backlog * per_job / 1000).clamp(MIN_RETRY_AFTER_MS, MAX_RETRY_AFTER_MS)
+    }
+}
+
+/// Bounds of the retry hint handed to refused callers
+const MIN_RETRY_AFTER_MS: u64 = 10;
+const MAX_RETRY_AFTER_MS: u64 = 5_000;
+
+/// Runs engine operations on the shard owning the market
+pub struct EngineShards {
+    engine: Arc<MatchingEngine>,
+    shards: Vec<Shard>,
+    admission_limit: usize,
+}
+
+impl EngineShards {
+    /// Spawn `config.shards` shard threads around `engine`
+    pub fn new(engine: Arc<MatchingEngine>, config: &ShardConfig) -> Self {
+        let cores = if config.pin_threads {
+            core_affinity::get_core_ids().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        let shards = (0..config.shards)
+            .map(|index| {
+                let (sender, receiver) = mpsc::sync_channel::<Job>(config.queue_capacity.max(1));
+                let depth = Arc::new(AtomicUsize::new(0));
+                let processed = Arc::new(AtomicU64::new(0));
+                let avg_job_micros = Arc::new(AtomicU64::new(0));
+                let core = (!cores.is_empty()).then(|| cores[index % cores.len()]);
+
+                let engine = engine.clone();
+                let (thread_depth, thread_processed, thread_avg) =
+                    (depth.clone(), processed.clone(), avg_job_micros.clone());
+                std::thread::Builder::new()
+                    .name(format!("engine-shard-{}", index))
+                    .spawn(move || {
+                        if let Some(core) = core {
+                            if !core_affinity::set_for_current(core) {
+                                tracing::warn!("Failed to pin engine shard {} to core {}", index, core.id);
+                            }
+                        }
+                        while let Ok(job) = receiver.recv() {
+                            let depth = thread_depth.fetch_sub(1, Ordering::Relaxed) - 1;
+                            metrics::set_shard_queue_depth(index, depth);
+                            let started = Instant::now();
+                            // A panicking job drops its reply channel; the shard keeps serving
+                            if panic::catch_unwind(AssertUnwindSafe(|| job(&engine))).is_err() {
+                                tracing::error!("Engine shard {} job panicked", index);
+                            }
+                            let micros = started.elapsed().as_micros() as u64;
+                            let avg = thread_avg.load(Ordering::Relaxed);
+                            // EWMA with weight 1/8; only this thread writes it
+                            let avg = if avg == 0 { micros.max(1) } else { (avg * 7 + micros) / 8 };
+                            thread_avg.store(avg, Ordering::Relaxed);
+                            thread_processed.fetch_add(1, Ordering::Relaxed);
+                        }
+                        tracing::info!("Engine shard {} stopped", index);
+                    })
+                    .expect("failed to spawn engine shard thread");
+
+                Shard {
+                    sender,
+                    depth,
+                    processed,
+                    avg_job_micros,
+                    rejected: AtomicU64::new(0),
+                    refused: AtomicU64::new(0),
+                    core: core.map(|c| c.id),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if !shards.is_empty() {
+            tracing::info!(
+                "Matching engine sharded across {} threads (queue capacity {}, admission limit {}, pinned: {})",
+                shards.len(),
+                config.queue_capacity,
+                config.admission_limit,
+                !cores.is_empty()
+            );
+        }
+        Self {
+            engine,
+            shards,
+            admission_limit: config.admission_limit.min(config.queue_capacity),
+        }
+    }
+
+    pub fn engine(&self) -> &Arc<MatchingEngine> {
+        &self.engine
+    }
+
+    /// Number of shard threads (0 = inline)
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Shard owning the market of `symbol` (`market:outcome:share_type` or a
+    /// bare market id). Stable across restarts for a given shard count.
+    pub fn shard_for(&self, symbol: &str) -> usize {
+        shard_index(symbol, self.shards.len())
+    }
+
+    /// Admission check for a new order on `symbol`'s market. Refuses with
+    /// [`MatchingError::Overloaded`] once the shard's backlog reaches the
+    /// admission limit; always admits when unsharded.
+    pub fn admit(&self, symbol: &str) -> Result<(), MatchingError> {
+        if self.shards.is_empty() {
+            return Ok(());
+        }
+        let index = self.shard_for(symbol);
+        let shard = &self.shards[index];
+        if shard.depth.load(Ordering::Relaxed) < self.admission_limit {
+            return Ok(());
+        }
+        shard.refused.fetch_add(1, Ordering::Relaxed);
+        metrics::record_shard_admission_refused(index);
+        Err(MatchingError::Overloaded {
+            shard: index,
+            retry_after_ms: shard.retry_after_ms(),
+        })
+    }
+
+    /// Run `job` on the shard owning `symbol`'s market and wait for its result
+    pub async fn execute<R, F>(&self, symbol: &str, job: F) -> Result<R, MatchingError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&MatchingEngine) -> R + Send + 'static,
+    {
+        if self.shards.is_empty() {
+            return Ok(job(&self.engine));
+        }
+
+        let index = self.shard_for(symbol);
+        let shard = &self.shards[index];
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let boxed: Job = Box::new(move |engine| {
+            let _ = reply_tx.send(job(engine));
+        });
+
+        shard.depth.fetch_add(1, Ordering::Relaxed);
+        match shard.sender.try_send(boxed) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                shard.depth.fetch_sub(1, Ordering::Relaxed);
+                shard.rejected.fetch_add(1, Ordering::Relaxed);
+                metrics::record_shard_rejected(index);
+                return Err(MatchingError::Overloaded {
+                    shard: index,
+                    retry_after_ms: shard.retry_after_ms(),
+                });
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                shard.depth.fetch_sub(1, Ordering::Relaxed);
+                return Err(MatchingError::InternalError(format!("engine shard {} stopped", index)));
+            }
+        }
+
+        reply_rx
+            .await
+            .map_err(|_| MatchingError::InternalError(format!("engine shard {} dropped the job", index)))
+    }
+
+    pub fn stats(&self) -> Vec<ShardStats> {
+        self.shards
+            .iter()
+            .enumerate()
+            .map(|(shard, s)| ShardStats {
+                shard,
+                queue_depth: s.depth.load(Ordering::Relaxed),
+                processed: s.processed.load(Ordering::Relaxed),
+                rejected: s.rejected.load(Ordering::Relaxed),
+                refused: s.refused.load(Ordering::Relaxed),
+                avg_job_micros: s.avg_job_micros.load(Ordering::Relaxed),
+                core: s.core,
+            })
+            .collect()
+    }
+}
+
+/// FNV-1a of the market id part of `symbol`, modulo `shards`
+fn shard_index(symbol: &str, shards: usize) -> usize {
+    if shards == 0 {
+        return 0;
+    }
+    let market = symbol.split(':').next().unwrap_or(symbol);
+    let hash = market
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    (hash % shards as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OrderType, Side, TimeInForce};
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_books_of_a_market_share_a_shard() {
+        let market = Uuid::new_v4();
+        let yes = format!("{}:{}:yes", market, Uuid::new_v4());
+        let no = format!("{}:{}:no", market, Uuid::new_v4());
+        assert_eq!(shard_index(&yes, 8), shard_index(&no, 8));
+        assert_eq!(shard_index(&yes, 8), shard_index(&market.to_string(), 8));
+        assert_eq!(shard_index(&yes, 0), 0);
+    }
+
+    #[tokio::test]
+    async fn test_execute_on_shard_and_inline() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = format!("{}:{}:yes", Uuid::new_v4(), Uuid::new_v4());
+
+        for shards in [0, 2] {
+            let sharded = EngineShards::new(
+                engine.clone(),
+                &ShardConfig {
+                    shards,
+                    ..Default::default()
+                },
+            );
+            let key = symbol.clone();
+            let result = sharded
+                .execute(&symbol, move |engine| {
+                    engine.submit_order(Uuid::new_v4(), &key, "0xa", Side::Buy, OrderType::Limit, dec!(1), Some(dec!(0.5)), 1, TimeInForce::GTC)
+                })
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(result.filled_amount, dec!(0));
+            assert_eq!(sharded.stats().len(), shards);
+            assert!(sharded.stats().iter().all(|s| s.queue_depth == 0));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admission_refuses_new_orders_over_limit() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = format!("{}:{}:yes", Uuid::new_v4(), Uuid::new_v4());
+        let sharded = EngineShards::new(
+            engine,
+            &ShardConfig {
+                shards: 1,
+                queue_capacity: 4,
+                admission_limit: 1,
+                pin_threads: false,
+            },
+        );
+        assert!(sharded.admit(&symbol).is_ok());
+
+        // Park the shard thread so the next job stays queued
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let (started_tx, started_rx) = std::sync::mpsc::channel::<()>();
+        let blocker = sharded.execute(&symbol, move |_| {
+            started_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        });
+        let queued = sharded.execute(&symbol, |_| ());
+        let check = async {
+            tokio::task::yield_now().await;
+            started_rx.recv().unwrap();
+            match sharded.admit(&symbol) {
+                Err(MatchingError::Overloaded { shard, retry_after_ms }) => {
+                    assert_eq!(shard, 0);
+                    assert!((MIN_RETRY_AFTER_MS..=MAX_RETRY_AFTER_MS).contains(&retry_after_ms));
+                }
+                other => panic!("expected overload, got {:?}", other),
+            }
+            release_tx.send(()).unwrap();
+        };
+        let (blocked, queued, ()) = tokio::join!(blocker, queued, check);
+        assert!(blocked.is_ok() && queued.is_ok());
+        assert_eq!(sharded.stats()[0].refused, 1);
+        assert!(sharded.admit(&symbol).is_ok());
+    }
+}
@@ -0,0 +1,74 @@
+//! Outcome share type
+
+use serde::{Deserialize, Serialize};
+
+/// 份额类型
+///
+/// 预测市场中的两种结果份额：Yes 和 No
+/// Yes + No 的价格总和始终等于 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareType {
+    /// Yes 份额 - 预测事件会发生
+    Yes,
+    /// No 份额 - 预测事件不会发生
+    No,
+}
+
+impl ShareType {
+    /// 获取互补份额类型
+    ///
+    /// Yes 的互补是 No，No 的互补是 Yes
+    pub fn complement(&self) -> ShareType {
+        match self {
+            ShareType::Yes => ShareType::No,
+            ShareType::No => ShareType::Yes,
+        }
+    }
+
+    /// 转换为字符串
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShareType::Yes => "yes",
+            ShareType::No => "no",
+        }
+    }
+}
+
+impl std::fmt::Display for ShareType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for ShareType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "yes" => Ok(ShareType::Yes),
+            "no" => Ok(ShareType::No),
+            _ => Err(format!("Invalid share type: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_type_complement() {
+        assert_eq!(ShareType::Yes.complement(), ShareType::No);
+        assert_eq!(ShareType::No.complement(), ShareType::Yes);
+    }
+
+    #[test]
+    fn test_share_type_from_str() {
+        assert_eq!("yes".parse::<ShareType>().unwrap(), ShareType::Yes);
+        assert_eq!("YES".parse::<ShareType>().unwrap(), ShareType::Yes);
+        assert_eq!("no".parse::<ShareType>().unwrap(), ShareType::No);
+        assert_eq!("No".parse::<ShareType>().unwrap(), ShareType::No);
+        assert!("invalid".parse::<ShareType>().is_err());
+    }
+}
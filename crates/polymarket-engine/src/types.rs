@@ -0,0 +1,1762 @@
+//! Matching Engine Types
+//!
+//! Shared types and DTOs for the prediction market matching engine.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::precision::{average_price, Collateral};
+use crate::ShareType;
+
+// ============================================================================
+// Price Level
+// ============================================================================
+
+/// Price level with 8 decimal precision for exact comparison
+/// For prediction markets, price is always between 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PriceLevel(i64);
+
+impl PriceLevel {
+    /// Create a PriceLevel from a Decimal price
+    pub fn from_decimal(price: Decimal) -> Self {
+        let scaled = price * Decimal::from(100_000_000);
+        let truncated = scaled.trunc();
+        let value = truncated.mantissa() / 10i128.pow(truncated.scale());
+        PriceLevel(value as i64)
+    }
+
+    /// Convert back to Decimal
+    pub fn to_decimal(&self) -> Decimal {
+        Decimal::from(self.0) / Decimal::from(100_000_000)
+    }
+
+    /// Get raw value
+    pub fn raw(&self) -> i64 {
+        self.0
+    }
+
+    /// Get the complement price level (1 - price)
+    /// For prediction markets: Yes_price + No_price = 1
+    #[allow(dead_code)]
+    pub fn complement(&self) -> Self {
+        let one = 100_000_000i64; // 1.0 in scaled form
+        PriceLevel(one - self.0)
+    }
+
+    /// Check if price is valid for prediction markets (0 < price < 1)
+    #[allow(dead_code)]
+    pub fn is_valid_probability(&self) -> bool {
+        self.0 > 0 && self.0 < 100_000_000
+    }
+}
+
+impl Ord for PriceLevel {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialOrd for PriceLevel {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// ============================================================================
+// Order Types
+// ============================================================================
+
+/// Order side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    /// Get the opposite side
+    pub fn opposite(&self) -> Self {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Buy => write!(f, "buy"),
+            Side::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+/// Order type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderType {
+    Limit,
+    Market,
+}
+
+impl std::fmt::Display for OrderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderType::Limit => write!(f, "limit"),
+            OrderType::Market => write!(f, "market"),
+        }
+    }
+}
+
+/// Time in force
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TimeInForce {
+    /// Good Till Cancel
+    #[default]
+    GTC,
+    /// Immediate or Cancel
+    IOC,
+    /// Fill or Kill
+    FOK,
+    /// Good Till Cancel, rejected instead of taking liquidity on arrival
+    #[serde(rename = "POST_ONLY")]
+    PostOnly,
+}
+
+impl TimeInForce {
+    /// Whether the unfilled part of a limit order rests in the book
+    pub fn rests(self) -> bool {
+        matches!(self, TimeInForce::GTC | TimeInForce::PostOnly)
+    }
+}
+
+/// Self-trade prevention: what happens when an order would trade with a
+/// resting order of the same account, in its own book or through Mint/Merge
+/// against the complement book. Orders of one account never trade with
+/// each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTradePrevention {
+    /// Cancel the rest of the incoming order
+    #[default]
+    CancelNewest,
+    /// Cancel the resting order and keep matching
+    CancelOldest,
+    /// Cancel the rest of the incoming order and the resting order
+    CancelBoth,
+    /// Shrink both by the smaller remaining size without trading; an order
+    /// left with nothing is cancelled
+    Decrement,
+}
+
+/// Order status
+///
+/// Orders start `accepted`, then rest (`open`, `partially_filled`) or end
+/// `filled`, `cancelled`, `expired` or `rejected`. The allowed moves are
+/// [`OrderStatus::can_transition_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    /// Order passed validation and is being matched
+    Accepted,
+    /// Order is active in the orderbook
+    Open,
+    /// Order is partially filled
+    PartiallyFilled,
+    /// Order is completely filled
+    Filled,
+    /// Order was cancelled
+    Cancelled,
+    /// Unfilled remainder of an immediate (market) order lapsed
+    Expired,
+    /// Order was rejected; see [`RejectReason`]
+    Rejected,
+}
+
+impl OrderStatus {
+    /// Terminal states never change again
+    pub fn is_final(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Expired | OrderStatus::Rejected
+        )
+    }
+
+    /// Whether an order may move from `self` to `next`
+    pub fn can_transition_to(self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+        match self {
+            Accepted => next != Accepted,
+            Open => matches!(next, PartiallyFilled | Filled | Cancelled | Expired),
+            PartiallyFilled => matches!(next, PartiallyFilled | Filled | Cancelled | Expired),
+            Filled | Cancelled | Expired | Rejected => false,
+        }
+    }
+}
+
+impl std::fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderStatus::Accepted => write!(f, "accepted"),
+            OrderStatus::Open => write!(f, "open"),
+            OrderStatus::PartiallyFilled => write!(f, "partially_filled"),
+            OrderStatus::Filled => write!(f, "filled"),
+            OrderStatus::Cancelled => write!(f, "cancelled"),
+            OrderStatus::Expired => write!(f, "expired"),
+            OrderStatus::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+impl std::str::FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "accepted" => Ok(OrderStatus::Accepted),
+            "open" => Ok(OrderStatus::Open),
+            "partially_filled" => Ok(OrderStatus::PartiallyFilled),
+            "filled" => Ok(OrderStatus::Filled),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            "expired" => Ok(OrderStatus::Expired),
+            "rejected" => Ok(OrderStatus::Rejected),
+            _ => Err(format!("Invalid order status: {}", s)),
+        }
+    }
+}
+
+// ============================================================================
+// Reject Reasons
+// ============================================================================
+
+/// Why an order was rejected. The code is what REST error responses,
+/// WebSocket order events and `orders.reject_reason` carry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RejectReason {
+    InvalidPrice,
+    InvalidAmount,
+    InvalidSide,
+    TimestampExpired,
+    SignatureInvalid,
+    FeatureDisabled,
+    SettlementMode,
+    InsufficientBalance,
+    MarketNotFound,
+    MarketNotActive,
+    OutsideTradingHours,
+    OutsidePriceBand,
+    TradingHalted,
+    NoLiquidity,
+    TooManyOpenOrders,
+    PostOnlyWouldTake,
+    InvalidExpiry,
+    InvalidBatch,
+    Overloaded,
+    InternalError,
+}
+
+impl RejectReason {
+    /// Every reason, in catalog order
+    pub const ALL: [RejectReason; 20] = [
+        RejectReason::InvalidPrice,
+        RejectReason::InvalidAmount,
+        RejectReason::InvalidSide,
+        RejectReason::TimestampExpired,
+        RejectReason::SignatureInvalid,
+        RejectReason::FeatureDisabled,
+        RejectReason::SettlementMode,
+        RejectReason::InsufficientBalance,
+        RejectReason::MarketNotFound,
+        RejectReason::MarketNotActive,
+        RejectReason::OutsideTradingHours,
+        RejectReason::OutsidePriceBand,
+        RejectReason::TradingHalted,
+        RejectReason::NoLiquidity,
+        RejectReason::TooManyOpenOrders,
+        RejectReason::PostOnlyWouldTake,
+        RejectReason::InvalidExpiry,
+        RejectReason::InvalidBatch,
+        RejectReason::Overloaded,
+        RejectReason::InternalError,
+    ];
+
+    /// Stable machine-readable code
+    pub fn code(self) -> &'static str {
+        match self {
+            RejectReason::InvalidPrice => "INVALID_PRICE",
+            RejectReason::InvalidAmount => "INVALID_AMOUNT",
+            RejectReason::InvalidSide => "INVALID_SIDE",
+            RejectReason::TimestampExpired => "TIMESTAMP_EXPIRED",
+            RejectReason::SignatureInvalid => "SIGNATURE_INVALID",
+            RejectReason::FeatureDisabled => "FEATURE_DISABLED",
+            RejectReason::SettlementMode => "SETTLEMENT_MODE",
+            RejectReason::InsufficientBalance => "INSUFFICIENT_BALANCE",
+            RejectReason::MarketNotFound => "MARKET_NOT_FOUND",
+            RejectReason::MarketNotActive => "MARKET_NOT_ACTIVE",
+            RejectReason::OutsideTradingHours => "OUTSIDE_TRADING_HOURS",
+            RejectReason::OutsidePriceBand => "OUTSIDE_PRICE_BAND",
+            RejectReason::TradingHalted => "TRADING_HALTED",
+            RejectReason::NoLiquidity => "NO_LIQUIDITY",
+            RejectReason::TooManyOpenOrders => "TOO_MANY_OPEN_ORDERS",
+            RejectReason::PostOnlyWouldTake => "POST_ONLY_WOULD_TAKE",
+            RejectReason::InvalidExpiry => "INVALID_EXPIRY",
+            RejectReason::InvalidBatch => "INVALID_BATCH",
+            RejectReason::Overloaded => "OVERLOADED",
+            RejectReason::InternalError => "INTERNAL_ERROR",
+        }
+    }
+
+    /// What the reason means, for the public catalog
+    pub fn description(self) -> &'static str {
+        match self {
+            RejectReason::InvalidPrice => "Price missing for a limit order or outside 0.01-0.99",
+            RejectReason::InvalidAmount => "Amount not positive or finer than the market's share precision",
+            RejectReason::InvalidSide => "Side is not buy or sell",
+            RejectReason::TimestampExpired => "Signed timestamp is more than 5 minutes from server time",
+            RejectReason::SignatureInvalid => "EIP-712 signature does not match the order or signer",
+            RejectReason::FeatureDisabled => "Order type is not enabled for this account",
+            RejectReason::SettlementMode => "Self-custody accounts place signed CTF orders through /orders/ctf",
+            RejectReason::InsufficientBalance => "Available collateral does not cover the order",
+            RejectReason::MarketNotFound => "Market, outcome or orderbook does not exist",
+            RejectReason::MarketNotActive => "Market is not open for trading",
+            RejectReason::OutsideTradingHours => "Market is outside its trading hours or in a scheduled halt",
+            RejectReason::OutsidePriceBand => {
+                "Limit price is further from the last trade (or the midpoint) than the price band allows"
+            }
+            RejectReason::TradingHalted => {
+                "The circuit breaker halted the market after a sharp price move; it trades again once resumed"
+            }
+            RejectReason::NoLiquidity => "Nothing to match against, or not enough to fill a fill-or-kill order",
+            RejectReason::TooManyOpenOrders => "The account or the market has reached its cap on resting orders",
+            RejectReason::PostOnlyWouldTake => {
+                "A post-only order would have traded on arrival, or was not a GTC limit order"
+            }
+            RejectReason::InvalidExpiry => "An expiry was set on an order other than a GTC limit order, or is not in the future",
+            RejectReason::InvalidBatch => {
+                "An atomic batch held orders of more than one market, or one other than a GTC limit order"
+            }
+            RejectReason::Overloaded => "The market's matching queue is full; retry after the hinted delay",
+            RejectReason::InternalError => "The order could not be processed; retry later",
+        }
+    }
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl std::str::FromStr for RejectReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RejectReason::ALL
+            .into_iter()
+            .find(|reason| reason.code() == s)
+            .ok_or_else(|| format!("Unknown reject reason: {}", s))
+    }
+}
+
+// ============================================================================
+// Match Type (Prediction Market Specific)
+// ============================================================================
+
+/// Match type for prediction markets
+///
+/// In prediction markets, there are three types of matches:
+/// - Normal: Same share type, opposite sides (Yes buy vs Yes sell)
+/// - Mint: Two buy orders for complementary shares (Yes buy + No buy)
+/// - Merge: Two sell orders for complementary shares (Yes sell + No sell)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchType {
+    /// Normal match: Same share type, opposite sides
+    /// Example: Yes buy matches Yes sell
+    Normal,
+
+    /// Mint match: Two buys for complementary shares create new shares
+    /// Example: Yes buy + No buy = mint 1 Yes + 1 No (costs 1 USDC)
+    Mint,
+
+    /// Merge match: Two sells for complementary shares redeem for collateral
+    /// Example: Yes sell + No sell = merge to 1 USDC
+    Merge,
+}
+
+impl MatchType {
+    /// Derive the match type from order characteristics
+    pub fn derive(
+        taker_share_type: ShareType,
+        taker_side: Side,
+        maker_share_type: ShareType,
+        maker_side: Side,
+    ) -> Self {
+        // Same share type = Normal matching
+        if taker_share_type == maker_share_type {
+            return MatchType::Normal;
+        }
+
+        // Different share types (complementary)
+        match (taker_side, maker_side) {
+            // Two buys for complementary shares = Mint
+            (Side::Buy, Side::Buy) => MatchType::Mint,
+            // Two sells for complementary shares = Merge
+            (Side::Sell, Side::Sell) => MatchType::Merge,
+            // Buy vs Sell for complementary shares = Normal
+            _ => MatchType::Normal,
+        }
+    }
+
+    /// Check if this match type requires minting new shares
+    pub fn requires_mint(&self) -> bool {
+        matches!(self, MatchType::Mint)
+    }
+
+    /// Check if this match type results in collateral redemption
+    pub fn redeems_collateral(&self) -> bool {
+        matches!(self, MatchType::Merge)
+    }
+}
+
+impl std::fmt::Display for MatchType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchType::Normal => write!(f, "normal"),
+            MatchType::Mint => write!(f, "mint"),
+            MatchType::Merge => write!(f, "merge"),
+        }
+    }
+}
+
+// ============================================================================
+// Order Entry (in orderbook)
+// ============================================================================
+
+/// An order entry in the orderbook
+#[derive(Debug, Clone)]
+pub struct OrderEntry {
+    /// Order ID
+    pub id: Uuid,
+
+    /// User wallet address
+    pub user_address: String,
+
+    /// Probability price (0-1)
+    pub price: Decimal,
+
+    /// Original order amount
+    pub original_amount: Decimal,
+
+    /// Remaining unfilled amount
+    pub remaining_amount: Decimal,
+
+    /// Order side (Buy/Sell)
+    pub side: Side,
+
+    /// Time in force
+    pub time_in_force: TimeInForce,
+
+    /// Order timestamp (milliseconds)
+    pub timestamp: i64,
+}
+
+impl OrderEntry {
+    /// Get the complement price (1 - price)
+    pub fn complement_price(&self) -> Decimal {
+        Decimal::ONE - self.price
+    }
+}
+
+// ============================================================================
+// Trade Execution
+// ============================================================================
+
+/// A trade execution result
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeExecution {
+    /// Trade ID
+    pub trade_id: Uuid,
+
+    /// Market ID
+    pub market_id: Uuid,
+
+    /// Outcome ID
+    pub outcome_id: Uuid,
+
+    /// Share type
+    pub share_type: ShareType,
+
+    /// Match type
+    pub match_type: MatchType,
+
+    /// Maker order ID
+    pub maker_order_id: Uuid,
+
+    /// Taker order ID
+    pub taker_order_id: Uuid,
+
+    /// Maker address
+    pub maker_address: String,
+
+    /// Trade price
+    pub price: Decimal,
+
+    /// Trade amount (shares)
+    pub amount: Decimal,
+
+    /// Maker fee
+    pub maker_fee: Decimal,
+
+    /// Taker fee
+    pub taker_fee: Decimal,
+
+    /// Trade timestamp
+    pub timestamp: i64,
+
+    /// Engine-wide trade sequence number (assigned by the engine once the
+    /// order has finished matching)
+    pub sequence: u64,
+
+    /// Notional at or above the engine's block trade threshold
+    pub is_block_trade: bool,
+}
+
+/// Trade event for broadcasting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeEvent {
+    /// Market key (format: market_id:outcome_id:share_type)
+    pub symbol: String,
+
+    /// Market ID (parsed from symbol)
+    pub market_id: Uuid,
+
+    /// Outcome ID (parsed from symbol)
+    pub outcome_id: Uuid,
+
+    /// Share type (parsed from symbol)
+    pub share_type: ShareType,
+
+    /// Match type (Normal, Mint, or Merge)
+    pub match_type: MatchType,
+
+    /// Trade ID
+    pub trade_id: Uuid,
+
+    /// Maker order ID
+    pub maker_order_id: Uuid,
+
+    /// Taker order ID
+    pub taker_order_id: Uuid,
+
+    /// Maker address
+    pub maker_address: String,
+
+    /// Taker address
+    pub taker_address: String,
+
+    /// Taker side
+    pub side: String,
+
+    /// Trade price
+    pub price: Decimal,
+
+    /// Trade amount
+    pub amount: Decimal,
+
+    /// Maker fee
+    pub maker_fee: Decimal,
+
+    /// Taker fee
+    pub taker_fee: Decimal,
+
+    /// Trade timestamp
+    pub timestamp: i64,
+
+    /// Engine sequence number; orders trades consistently across books
+    #[serde(default)]
+    pub sequence: u64,
+
+    /// Notional at or above the engine's block trade threshold
+    #[serde(default)]
+    pub is_block_trade: bool,
+
+    /// Executed from a negotiated RFQ quote rather than by book matching
+    #[serde(default)]
+    pub is_rfq: bool,
+
+    /// Position among the trades of its market key, one past the previous
+    /// one; numbering starts over when the engine restarts
+    #[serde(default)]
+    pub market_sequence: u64,
+}
+
+impl TradeEvent {
+    /// Create a TradeEvent from symbol and other fields
+    // One argument per trade field
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        symbol: String,
+        trade_id: Uuid,
+        maker_order_id: Uuid,
+        taker_order_id: Uuid,
+        maker_address: String,
+        taker_address: String,
+        side: Side,
+        price: Decimal,
+        amount: Decimal,
+        maker_fee: Decimal,
+        taker_fee: Decimal,
+    ) -> Self {
+        let (market_id, outcome_id, share_type) = OrderbookSnapshot::parse_market_key(&symbol)
+            .unwrap_or((Uuid::nil(), Uuid::nil(), ShareType::Yes));
+
+        Self {
+            symbol,
+            market_id,
+            outcome_id,
+            share_type,
+            match_type: MatchType::Normal,
+            trade_id,
+            maker_order_id,
+            taker_order_id,
+            maker_address,
+            taker_address,
+            side: side.to_string(),
+            price,
+            amount,
+            maker_fee,
+            taker_fee,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            sequence: 0,
+            is_block_trade: false,
+            is_rfq: false,
+            market_sequence: 0,
+        }
+    }
+
+    /// Create a TradeEvent from a TradeExecution
+    pub fn from_execution(execution: &TradeExecution, symbol: String, taker_address: String, side: Side) -> Self {
+        Self {
+            symbol,
+            market_id: execution.market_id,
+            outcome_id: execution.outcome_id,
+            share_type: execution.share_type,
+            match_type: execution.match_type,
+            trade_id: execution.trade_id,
+            maker_order_id: execution.maker_order_id,
+            taker_order_id: execution.taker_order_id,
+            maker_address: execution.maker_address.clone(),
+            taker_address,
+            side: side.to_string(),
+            price: execution.price,
+            amount: execution.amount,
+            maker_fee: execution.maker_fee,
+            taker_fee: execution.taker_fee,
+            timestamp: execution.timestamp,
+            sequence: execution.sequence,
+            is_block_trade: execution.is_block_trade,
+            is_rfq: false,
+            market_sequence: 0,
+        }
+    }
+
+    /// Set the match type
+    pub fn with_match_type(mut self, match_type: MatchType) -> Self {
+        self.match_type = match_type;
+        self
+    }
+}
+
+// ============================================================================
+// Match Result
+// ============================================================================
+
+/// Result of order matching
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+    pub order_id: Uuid,
+    pub status: OrderStatus,
+    pub filled_amount: Decimal,
+    pub remaining_amount: Decimal,
+    pub average_price: Option<Decimal>,
+    pub trades: Vec<TradeExecution>,
+    /// Where the unfilled remainder rests, if it does
+    pub queue_position: Option<QueuePosition>,
+    /// Own resting orders cancelled or shrunk by self-trade prevention
+    pub self_trades: Vec<SelfTradeReduction>,
+    /// Size of this order removed by [`SelfTradePrevention::Decrement`];
+    /// the order's size is reduced by it
+    pub decremented: Decimal,
+    /// Complete sets minted or merged across a categorical market's
+    /// outcomes. Not in `trades`: each fills the taker once but trades with
+    /// one maker per other outcome.
+    pub set_fills: Vec<SetFill>,
+}
+
+/// A complete set minted (buys) or merged (sells) across the outcomes of a
+/// categorical market: the taker's shares of one outcome plus a leg of the
+/// same size on every other outcome's Yes book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetFill {
+    pub set_id: Uuid,
+    pub market_id: Uuid,
+    /// Mint for a buy, Merge for a sell
+    pub match_type: MatchType,
+    pub taker_order_id: Uuid,
+    pub taker_address: String,
+    pub taker_outcome_id: Uuid,
+    /// Taker's price per share: one less the leg prices (never below zero)
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub taker_fee: Decimal,
+    pub legs: Vec<SetLeg>,
+    pub timestamp: i64,
+}
+
+/// One maker of a [`SetFill`], trading at its own resting price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetLeg {
+    /// Book the maker rests in
+    pub symbol: String,
+    pub outcome_id: Uuid,
+    pub maker_order_id: Uuid,
+    pub maker_address: String,
+    pub price: Decimal,
+    pub maker_fee: Decimal,
+}
+
+/// What set matching a taker across a categorical market did
+#[derive(Debug, Clone, Default)]
+pub struct SetMatch {
+    pub fills: Vec<SetFill>,
+    /// Each filled leg maker as it stands after its fill, with its book
+    /// (in the order of the legs of `fills`)
+    pub makers: Vec<(String, OrderEntry)>,
+    /// Taker size still unmatched
+    pub remaining: Decimal,
+    pub self_trades: Vec<SelfTradeReduction>,
+    /// Taker size removed by [`SelfTradePrevention::Decrement`]
+    pub decremented: Decimal,
+    /// Self-trade prevention cancelled the rest of the taker
+    pub stopped: bool,
+}
+
+/// The incoming order book matching runs for: whose it is, so its own
+/// resting orders are spotted, and what self-trade prevention does then
+#[derive(Debug, Clone, Copy)]
+pub struct Taker<'a> {
+    pub order_id: Uuid,
+    pub address: &'a str,
+    pub stp: SelfTradePrevention,
+}
+
+impl<'a> Taker<'a> {
+    /// A taker under the default self-trade prevention
+    pub fn new(order_id: Uuid, address: &'a str) -> Self {
+        Self { order_id, address, stp: SelfTradePrevention::default() }
+    }
+
+    pub fn with_stp(mut self, stp: SelfTradePrevention) -> Self {
+        self.stp = stp;
+        self
+    }
+}
+
+/// What matching a taker against one book did
+#[derive(Debug, Clone, Default)]
+pub struct BookMatch {
+    pub trades: Vec<TradeExecution>,
+    /// Each filled maker as it stands after its fill (parallel to `trades`)
+    pub makers: Vec<OrderEntry>,
+    /// Taker size still unmatched
+    pub remaining: Decimal,
+    /// Own resting orders hit by self-trade prevention, as they stood,
+    /// with the size taken off them
+    pub self_trades: Vec<(OrderEntry, Decimal)>,
+    /// Taker size removed by [`SelfTradePrevention::Decrement`]
+    pub decremented: Decimal,
+    /// Self-trade prevention cancelled the rest of the taker
+    pub stopped: bool,
+}
+
+impl BookMatch {
+    pub fn new(amount: Decimal) -> Self {
+        Self {
+            remaining: amount,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the taker can keep matching
+    pub fn open(&self) -> bool {
+        !self.stopped && self.remaining > Decimal::ZERO
+    }
+
+    /// Append what matching against another book did
+    pub fn extend(&mut self, other: BookMatch) {
+        self.trades.extend(other.trades);
+        self.makers.extend(other.makers);
+        self.self_trades.extend(other.self_trades);
+        self.remaining = other.remaining;
+        self.decremented += other.decremented;
+        self.stopped |= other.stopped;
+    }
+}
+
+/// A resting order of the taker's own account that self-trade prevention
+/// cancelled or shrank instead of trading with it
+#[derive(Debug, Clone)]
+pub struct SelfTradeReduction {
+    /// Book the order rests in (the complement book for Mint/Merge)
+    pub symbol: String,
+    /// The order as it stood before
+    pub order: OrderEntry,
+    /// Size taken off it
+    pub amount: Decimal,
+}
+
+impl SelfTradeReduction {
+    /// Whether the order lost all it had left and is off the book
+    pub fn cancelled(&self) -> bool {
+        self.amount >= self.order.remaining_amount
+    }
+}
+
+/// A resting order's place in its price level, which fills first come
+/// first served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QueuePosition {
+    pub price: Decimal,
+    /// 1 for the front of the queue
+    pub position: usize,
+    /// Size resting ahead of the order at this price
+    pub ahead_amount: Decimal,
+    /// Total size resting at this price, the order included
+    pub level_amount: Decimal,
+}
+
+/// A resting order changed in place by
+/// [`MatchingEngine::amend_order`](crate::MatchingEngine::amend_order)
+#[derive(Debug, Clone)]
+pub struct Amendment {
+    /// The order as it rested before
+    pub before: OrderEntry,
+    /// The order as it rests now
+    pub after: OrderEntry,
+    /// Whether it kept its place in its price level's queue
+    pub kept_priority: bool,
+}
+
+// ============================================================================
+// Orderbook Snapshot
+// ============================================================================
+
+/// Orderbook snapshot for API response
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderbookSnapshot {
+    /// Market key (format: market_id:outcome_id:share_type)
+    pub symbol: String,
+
+    /// Bid levels [price, amount]
+    pub bids: Vec<[String; 2]>,
+
+    /// Ask levels [price, amount]
+    pub asks: Vec<[String; 2]>,
+
+    /// Last trade price
+    pub last_price: Option<Decimal>,
+
+    /// Snapshot timestamp
+    pub timestamp: i64,
+}
+
+/// Separates an optional namespace from the market key
+/// (`{namespace}/{market_id}:{outcome_id}:{share_type}`)
+pub const KEY_NAMESPACE_SEPARATOR: char = '/';
+
+impl OrderbookSnapshot {
+    /// Split a market key into its namespace (if any) and the bare key
+    pub fn split_namespace(market_key: &str) -> (Option<&str>, &str) {
+        match market_key.split_once(KEY_NAMESPACE_SEPARATOR) {
+            Some((namespace, key)) => (Some(namespace), key),
+            None => (None, market_key),
+        }
+    }
+
+    /// Market key of `key` inside `namespace`
+    pub fn namespaced_key(namespace: &str, market_key: &str) -> String {
+        format!("{}{}{}", namespace, KEY_NAMESPACE_SEPARATOR, market_key)
+    }
+
+    /// Market key of an outcome's shares, inside `namespace` if given
+    pub fn market_key(namespace: Option<&str>, market_id: Uuid, outcome_id: Uuid, share_type: ShareType) -> String {
+        let key = format!("{}:{}:{}", market_id, outcome_id, share_type);
+        match namespace {
+            Some(namespace) => Self::namespaced_key(namespace, &key),
+            None => key,
+        }
+    }
+
+    /// Parse market key into components, ignoring any namespace
+    pub fn parse_market_key(market_key: &str) -> Option<(Uuid, Uuid, ShareType)> {
+        let (_, market_key) = Self::split_namespace(market_key);
+        let parts: Vec<&str> = market_key.split(':').collect();
+        if parts.len() != 3 {
+            return None;
+        }
+        let market_id = Uuid::parse_str(parts[0]).ok()?;
+        let outcome_id = Uuid::parse_str(parts[1]).ok()?;
+        let share_type: ShareType = parts[2].parse().ok()?;
+        Some((market_id, outcome_id, share_type))
+    }
+}
+
+/// Top-of-book depth cached on an orderbook, shared by every reader until
+/// the book next changes
+#[derive(Debug)]
+pub struct CachedDepth {
+    /// Book version the levels were built at
+    pub version: u64,
+
+    /// Bid levels [price, amount], best first
+    pub bids: Arc<[[String; 2]]>,
+
+    /// Ask levels [price, amount], best first
+    pub asks: Arc<[[String; 2]]>,
+
+    /// The same depth as a serialized [`OrderbookSnapshot`] (timestamped at build)
+    pub json: Arc<str>,
+}
+
+impl CachedDepth {
+    pub fn new(version: u64, snapshot: OrderbookSnapshot) -> Self {
+        let json = serde_json::to_string(&snapshot).unwrap_or_default();
+        Self {
+            version,
+            bids: snapshot.bids.into(),
+            asks: snapshot.asks.into(),
+            json: json.into(),
+        }
+    }
+}
+
+/// Orderbook update event for broadcasting
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderbookUpdate {
+    /// Market key (format: market_id:outcome_id:share_type)
+    pub symbol: String,
+
+    /// Updated bid levels (shared with the book's depth cache)
+    pub bids: Arc<[[String; 2]]>,
+
+    /// Updated ask levels
+    pub asks: Arc<[[String; 2]]>,
+
+    /// Position among the updates of its market key, one past the previous
+    /// one; numbering starts over when the engine restarts
+    pub sequence: u64,
+
+    /// Update timestamp
+    pub timestamp: i64,
+}
+
+/// Last sequence numbers broadcast for a market key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MarketSequence {
+    /// Of its trades
+    pub trade: u64,
+    /// Of its orderbook updates
+    pub orderbook: u64,
+}
+
+/// How a price level changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LevelAction {
+    /// The price has its first resting order
+    Add,
+    /// The amount resting at the price changed
+    Change,
+    /// The last order at the price left the book
+    Remove,
+}
+
+/// One changed price level of a book; `size` is the new total (zero when
+/// removed)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LevelChange {
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub action: LevelAction,
+}
+
+/// Levels of a book that changed since its previous delta, over the whole
+/// depth. Each book numbers its deltas 1, 2, ... without gaps, so a receiver
+/// that applies them to a [`SequencedDepth`] keeps an exact copy of the book.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderbookDelta {
+    /// Market key (format: market_id:outcome_id:share_type)
+    pub symbol: String,
+
+    /// Position of this delta in the book's sequence
+    pub sequence: u64,
+
+    /// Changed levels, bids then asks
+    pub changes: Arc<[LevelChange]>,
+
+    /// Update timestamp
+    pub timestamp: i64,
+}
+
+/// Every level of a book as of delta `sequence` (0 before the first)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SequencedDepth {
+    pub sequence: u64,
+
+    /// Bid levels (price, amount), best (highest) first
+    pub bids: Vec<(Decimal, Decimal)>,
+
+    /// Ask levels (price, amount), best (lowest) first
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+impl SequencedDepth {
+    /// Changes turning `self` into `bids`/`asks`
+    pub fn diff(&self, bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]) -> Vec<LevelChange> {
+        let mut changes = diff_levels(Side::Buy, &self.bids, bids);
+        changes.extend(diff_levels(Side::Sell, &self.asks, asks));
+        changes
+    }
+}
+
+fn diff_levels(side: Side, before: &[(Decimal, Decimal)], after: &[(Decimal, Decimal)]) -> Vec<LevelChange> {
+    let previous: HashMap<Decimal, Decimal> = before.iter().copied().collect();
+    let current: HashMap<Decimal, Decimal> = after.iter().copied().collect();
+
+    let changed = after.iter().filter_map(|&(price, size)| {
+        let action = match previous.get(&price) {
+            None => LevelAction::Add,
+            Some(old) if *old != size => LevelAction::Change,
+            Some(_) => return None,
+        };
+        Some(LevelChange { side, price, size, action })
+    });
+    let removed = before
+        .iter()
+        .filter(|(price, _)| !current.contains_key(price))
+        .map(|&(price, _)| LevelChange {
+            side,
+            price,
+            size: Decimal::ZERO,
+            action: LevelAction::Remove,
+        });
+    changed.chain(removed).collect()
+}
+
+/// What changed an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderEventKind {
+    /// Order was submitted (as taker); status reflects its immediate fills
+    Accepted,
+    /// Order failed validation and never reached the book
+    Rejected,
+    /// Resting order was filled by another user's order
+    Fill,
+    /// Order was removed from the book
+    Cancelled,
+    /// Resting order's price or size was changed by its owner
+    Amended,
+    /// Resting order reached its expiry, or its market stopped trading
+    Expired,
+}
+
+/// Order lifecycle event, broadcast by the engine for every state change
+/// of every order it touches (takers and the makers they fill)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderEvent {
+    pub kind: OrderEventKind,
+
+    /// Market key (format: market_id:outcome_id:share_type)
+    pub symbol: String,
+
+    pub order_id: Uuid,
+
+    /// Order owner
+    pub user_address: String,
+
+    pub side: Side,
+
+    pub order_type: OrderType,
+
+    /// Limit price (None for market orders)
+    pub price: Option<Decimal>,
+
+    pub original_amount: Decimal,
+
+    pub filled_amount: Decimal,
+
+    pub remaining_amount: Decimal,
+
+    /// Status after the change
+    pub status: OrderStatus,
+
+    /// Trade that filled a maker order
+    pub trade_id: Option<Uuid>,
+
+    /// Set when `status` is `rejected`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<RejectReason>,
+
+    /// Order creation timestamp (milliseconds)
+    pub created_at: i64,
+
+    /// Event timestamp (milliseconds)
+    pub timestamp: i64,
+}
+
+impl OrderEvent {
+    /// Event for a resting (limit) order in its current state
+    pub fn for_resting(kind: OrderEventKind, symbol: &str, entry: &OrderEntry, status: OrderStatus) -> Self {
+        Self {
+            kind,
+            symbol: symbol.to_string(),
+            order_id: entry.id,
+            user_address: entry.user_address.clone(),
+            side: entry.side,
+            order_type: OrderType::Limit,
+            price: Some(entry.price),
+            original_amount: entry.original_amount,
+            filled_amount: entry.original_amount - entry.remaining_amount,
+            remaining_amount: entry.remaining_amount,
+            status,
+            trade_id: None,
+            reject_reason: None,
+            created_at: entry.timestamp,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// Whether the event rests, changes or removes a book order. Rejections
+    /// and takers that never rested leave the books as they were.
+    pub fn changes_book(&self) -> bool {
+        match self.kind {
+            OrderEventKind::Rejected => false,
+            OrderEventKind::Accepted => !self.status.is_final(),
+            _ => true,
+        }
+    }
+}
+
+// ============================================================================
+// Quote
+// ============================================================================
+
+/// Fills along one route of a quote
+#[derive(Debug, Clone, Serialize)]
+pub struct QuoteRoute {
+    /// `normal` for the direct book, `mint`/`merge` for the synthetic route
+    /// through the complement book
+    pub route: MatchType,
+
+    pub amount: Decimal,
+
+    /// Collateral paid (buy) or received (sell)
+    pub notional: Decimal,
+
+    pub average_price: Option<Decimal>,
+}
+
+/// Best achievable execution of a taker order across the direct book and
+/// the complement book. A resting complement bid at `p` fills a buy at
+/// `1 - p` (mint); a resting complement ask at `p` fills a sell at `1 - p`
+/// (merge).
+#[derive(Debug, Clone, Serialize)]
+pub struct Quote {
+    /// Market key (format: market_id:outcome_id:share_type)
+    pub symbol: String,
+
+    pub side: Side,
+
+    pub requested: Decimal,
+
+    pub filled: Decimal,
+
+    /// Size the books cannot absorb
+    pub unfilled: Decimal,
+
+    /// Collateral paid (buy) or received (sell)
+    pub notional: Decimal,
+
+    pub average_price: Option<Decimal>,
+
+    /// Least favourable price touched, i.e. the limit price that takes the
+    /// whole quoted size
+    pub limit_price: Option<Decimal>,
+
+    /// Non-empty routes, direct first
+    pub routes: Vec<QuoteRoute>,
+
+    pub timestamp: i64,
+}
+
+impl Quote {
+    /// Walk `direct` and `synthetic` levels (`(price, amount)`, synthetic
+    /// prices already converted to this book) best price first. Direct
+    /// levels win ties, as the engine matches the direct book first.
+    pub fn build(
+        symbol: &str,
+        side: Side,
+        size: Decimal,
+        direct: &[(Decimal, Decimal)],
+        synthetic: &[(Decimal, Decimal)],
+    ) -> Self {
+        let synthetic_route = match side {
+            Side::Buy => MatchType::Mint,
+            Side::Sell => MatchType::Merge,
+        };
+        let better = |a: Decimal, b: Decimal| match side {
+            Side::Buy => a <= b,
+            Side::Sell => a >= b,
+        };
+
+        let mut legs = [(MatchType::Normal, Decimal::ZERO, Collateral::ZERO), (synthetic_route, Decimal::ZERO, Collateral::ZERO)];
+        let (mut d, mut s) = (direct.iter().peekable(), synthetic.iter().peekable());
+        let mut remaining = size;
+        let mut limit_price = None;
+
+        while remaining > Decimal::ZERO {
+            let (leg, &(price, amount)) = match (d.peek(), s.peek()) {
+                (Some(&&(dp, _)), Some(&&(sp, _))) if !better(dp, sp) => (1, s.next().unwrap()),
+                (Some(_), _) => (0, d.next().unwrap()),
+                (None, Some(_)) => (1, s.next().unwrap()),
+                (None, None) => break,
+            };
+            let take = remaining.min(amount);
+            legs[leg].1 += take;
+            legs[leg].2 += Collateral::notional(price, take);
+            remaining -= take;
+            limit_price = Some(price);
+        }
+
+        let routes: Vec<QuoteRoute> = legs
+            .iter()
+            .filter(|(_, amount, _)| !amount.is_zero())
+            .map(|&(route, amount, notional)| QuoteRoute {
+                route,
+                amount,
+                notional: notional.value(),
+                average_price: average_price(notional.value(), amount),
+            })
+            .collect();
+        let filled = size - remaining;
+        let notional: Collateral = legs.iter().map(|(_, _, n)| *n).sum();
+
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            requested: size,
+            filled,
+            unfilled: remaining,
+            notional: notional.value(),
+            average_price: average_price(notional.value(), filled),
+            limit_price,
+            routes,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+// ============================================================================
+// Trade Record (for history)
+// ============================================================================
+
+/// Trade record for history storage
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeRecord {
+    pub trade_id: String,
+    pub market_id: String,
+    pub outcome_id: String,
+    pub share_type: String,
+    pub match_type: String,
+    pub side: String,
+    pub price: String,
+    pub amount: String,
+    pub maker_order_id: String,
+    pub taker_order_id: String,
+    pub maker_address: String,
+    pub taker_address: String,
+    pub maker_fee: String,
+    pub taker_fee: String,
+    pub timestamp: i64,
+    pub sequence: u64,
+    pub is_block_trade: bool,
+    pub is_rfq: bool,
+}
+
+impl From<&TradeEvent> for TradeRecord {
+    fn from(event: &TradeEvent) -> Self {
+        TradeRecord {
+            trade_id: event.trade_id.to_string(),
+            market_id: event.market_id.to_string(),
+            outcome_id: event.outcome_id.to_string(),
+            share_type: event.share_type.to_string(),
+            match_type: event.match_type.to_string(),
+            side: event.side.clone(),
+            price: event.price.to_string(),
+            amount: event.amount.to_string(),
+            maker_order_id: event.maker_order_id.to_string(),
+            taker_order_id: event.taker_order_id.to_string(),
+            maker_address: event.maker_address.clone(),
+            taker_address: event.taker_address.clone(),
+            maker_fee: event.maker_fee.to_string(),
+            taker_fee: event.taker_fee.to_string(),
+            timestamp: event.timestamp,
+            sequence: event.sequence,
+            is_block_trade: event.is_block_trade,
+            is_rfq: event.is_rfq,
+        }
+    }
+}
+
+// ============================================================================
+// Order History Record
+// ============================================================================
+
+/// Order history record for storage
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderHistoryRecord {
+    pub order_id: String,
+    pub user_address: String,
+    /// Market key (format: market_id:outcome_id:share_type or just symbol for legacy)
+    pub symbol: String,
+    pub side: String,
+    pub order_type: String,
+    pub price: String,
+    pub original_amount: String,
+    pub filled_amount: String,
+    pub remaining_amount: String,
+    pub status: String,
+    pub leverage: u32,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub avg_fill_price: Option<String>,
+    pub trade_ids: Vec<String>,
+    /// Reject reason code, for rejected orders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<String>,
+}
+
+impl OrderHistoryRecord {
+    /// Move to `next` if the state machine allows it. Records with an
+    /// unrecognised status are left alone.
+    pub fn transition(&mut self, next: OrderStatus) -> bool {
+        let allowed = self
+            .status
+            .parse::<OrderStatus>()
+            .is_ok_and(|current| current.can_transition_to(next));
+        if allowed {
+            self.status = next.to_string();
+        }
+        allowed
+    }
+}
+
+// ============================================================================
+// Query Types
+// ============================================================================
+
+/// Trade history query parameters
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct TradeHistoryQuery {
+    pub market_id: Option<Uuid>,
+    pub share_type: Option<String>,
+    pub limit: Option<usize>,
+    pub before: Option<i64>,
+    pub after: Option<i64>,
+}
+
+impl TradeHistoryQuery {
+    pub fn get_limit(&self) -> usize {
+        self.limit.unwrap_or(50).clamp(1, 100)
+    }
+}
+
+/// Trade history response
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeHistoryResponse {
+    pub trades: Vec<TradeRecord>,
+    pub total_count: usize,
+    pub has_more: bool,
+}
+
+/// Order history query parameters
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct OrderHistoryQuery {
+    pub status: Option<String>,
+    pub market_id: Option<Uuid>,
+    pub share_type: Option<String>,
+    pub limit: Option<usize>,
+    pub before: Option<i64>,
+    pub after: Option<i64>,
+}
+
+impl OrderHistoryQuery {
+    pub fn get_limit(&self) -> usize {
+        self.limit.unwrap_or(50).clamp(1, 100)
+    }
+
+    pub fn matches_status(&self, status: &str) -> bool {
+        match &self.status {
+            None => true,
+            Some(filter) => filter == "all" || status == filter,
+        }
+    }
+
+    pub fn matches_market(&self, market_id: &Uuid) -> bool {
+        match &self.market_id {
+            None => true,
+            Some(filter) => market_id == filter,
+        }
+    }
+
+    pub fn matches_share_type(&self, share_type: &str) -> bool {
+        match &self.share_type {
+            None => true,
+            Some(filter) => share_type == filter,
+        }
+    }
+
+    pub fn matches_time(&self, timestamp: i64) -> bool {
+        let matches_before = self.before.is_none_or(|ts| timestamp < ts);
+        let matches_after = self.after.is_none_or(|ts| timestamp > ts);
+        matches_before && matches_after
+    }
+
+    /// Match symbol/market_key against query
+    /// Supports both legacy symbol format and market_key format
+    pub fn matches_symbol(&self, symbol: &str) -> bool {
+        // If no market_id filter, match everything
+        let market_id = match &self.market_id {
+            None => return true,
+            Some(id) => id,
+        };
+
+        // Check if symbol contains the market_id
+        if symbol.contains(&market_id.to_string()) {
+            // Also check share_type if specified
+            if let Some(st) = &self.share_type {
+                return symbol.to_lowercase().contains(&st.to_lowercase());
+            }
+            return true;
+        }
+
+        false
+    }
+}
+
+/// Order history response
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderHistoryResponse {
+    pub orders: Vec<OrderHistoryRecord>,
+    pub total_count: usize,
+    pub has_more: bool,
+}
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Matching engine errors
+#[derive(Debug, thiserror::Error)]
+pub enum MatchingError {
+    #[error("Symbol/Market not found: {0}")]
+    SymbolNotFound(String),
+
+    #[error("Market not found: {0}")]
+    MarketNotFound(String),
+
+    #[error("Outcome not found: {0}")]
+    OutcomeNotFound(String),
+
+    #[error("Order not found: {0}")]
+    OrderNotFound(String),
+
+    #[error("Invalid price: {0}")]
+    InvalidPrice(String),
+
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("Invalid side: {0}")]
+    InvalidSide(String),
+
+    #[error("Market not active: {0}")]
+    MarketNotActive(String),
+
+    #[error("Insufficient liquidity")]
+    InsufficientLiquidity,
+
+    #[error("Post-only order would take liquidity")]
+    WouldTakeLiquidity,
+
+    #[error("Price {price} is more than {band} from the reference price {reference}")]
+    OutsidePriceBand { price: Decimal, reference: Decimal, band: Decimal },
+
+    #[error("Trading is halted in market {0}")]
+    TradingHalted(String),
+
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Internal error: {0}")]
+    InternalError(String),
+
+    #[error("Engine shard {shard} is overloaded; retry after {retry_after_ms}ms")]
+    Overloaded { shard: usize, retry_after_ms: u64 },
+}
+
+impl MatchingError {
+    /// The reject reason reported for an order that failed with this error
+    pub fn reject_reason(&self) -> RejectReason {
+        match self {
+            MatchingError::InvalidPrice(_) => RejectReason::InvalidPrice,
+            MatchingError::InvalidAmount(_) => RejectReason::InvalidAmount,
+            MatchingError::InvalidSide(_) => RejectReason::InvalidSide,
+            MatchingError::SymbolNotFound(_) | MatchingError::MarketNotFound(_) | MatchingError::OutcomeNotFound(_) => {
+                RejectReason::MarketNotFound
+            }
+            MatchingError::MarketNotActive(_) => RejectReason::MarketNotActive,
+            MatchingError::InsufficientLiquidity => RejectReason::NoLiquidity,
+            MatchingError::WouldTakeLiquidity => RejectReason::PostOnlyWouldTake,
+            MatchingError::OutsidePriceBand { .. } => RejectReason::OutsidePriceBand,
+            MatchingError::TradingHalted(_) => RejectReason::TradingHalted,
+            MatchingError::OrderNotFound(_)
+            | MatchingError::DatabaseError(_)
+            | MatchingError::InternalError(_) => RejectReason::InternalError,
+            MatchingError::Overloaded { .. } => RejectReason::Overloaded,
+        }
+    }
+
+    /// Suggested wait before retrying, for errors that are worth retrying
+    pub fn retry_after_ms(&self) -> Option<u64> {
+        match self {
+            MatchingError::Overloaded { retry_after_ms, .. } => Some(*retry_after_ms),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Fee Configuration (Prediction Market Symmetric Fee)
+// ============================================================================
+
+/// Prediction market fee configuration
+///
+/// Uses symmetric fee formula: fee = base_rate * min(price, 1-price) * amount
+/// This ensures that buying 100 shares of Yes @ 0.99 costs the same fee as
+/// buying 100 shares of No @ 0.01
+#[derive(Debug, Clone)]
+pub struct FeeConfig {
+    /// Base fee rate in basis points (1 bp = 0.01%)
+    pub base_fee_bps: u32,
+
+    /// Maximum fee rate in basis points
+    pub max_fee_bps: u32,
+
+    /// Maker fee discount rate (0-100, percentage)
+    pub maker_discount_pct: u32,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            base_fee_bps: 200,       // 2%
+            max_fee_bps: 1000,       // 10%
+            maker_discount_pct: 50,  // 50% discount for makers
+        }
+    }
+}
+
+impl FeeConfig {
+    /// Calculate symmetric fee for prediction market orders
+    ///
+    /// Formula: fee = base_rate * min(price, 1-price) * amount
+    ///
+    /// This ensures that:
+    /// - Buying Yes @ 0.90 has same fee as buying No @ 0.10
+    /// - Fee is highest at price = 0.50
+    /// - Fee approaches zero as price approaches 0 or 1
+    ///
+    /// The result is rounded to collateral precision (banker's rounding).
+    pub fn calculate_fee(&self, price: Decimal, amount: Decimal, is_maker: bool) -> Decimal {
+        // Convert basis points to decimal (200 bps = 0.02)
+        let base_rate = Decimal::new(self.base_fee_bps as i64, 4);
+
+        // Calculate complement price
+        let complement_price = Decimal::ONE - price;
+
+        // Use minimum of price and complement price for symmetric fee
+        let min_price = price.min(complement_price);
+
+        // Calculate base fee
+        let mut fee = base_rate * min_price * amount;
+
+        // Apply maker discount
+        if is_maker {
+            let discount = Decimal::new(self.maker_discount_pct as i64, 2);
+            fee *= Decimal::ONE - discount;
+        }
+
+        // Apply maximum fee cap
+        let max_rate = Decimal::new(self.max_fee_bps as i64, 4);
+        let max_fee = max_rate * amount;
+
+        // Fees are collateral: round once so fee ledgers reconcile exactly
+        Collateral::new(fee.min(max_fee)).value()
+    }
+
+    /// Calculate taker fee
+    pub fn calculate_taker_fee(&self, price: Decimal, amount: Decimal) -> Decimal {
+        self.calculate_fee(price, amount, false)
+    }
+
+    /// Calculate maker fee
+    pub fn calculate_maker_fee(&self, price: Decimal, amount: Decimal) -> Decimal {
+        self.calculate_fee(price, amount, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_price_level_conversion() {
+        let price = dec!(0.65);
+        let level = PriceLevel::from_decimal(price);
+        let back = level.to_decimal();
+        assert_eq!(price, back);
+    }
+
+    #[test]
+    fn test_price_level_complement() {
+        let price = dec!(0.65);
+        let level = PriceLevel::from_decimal(price);
+        let complement = level.complement();
+        assert_eq!(complement.to_decimal(), dec!(0.35));
+    }
+
+    #[test]
+    fn test_price_level_valid_probability() {
+        assert!(PriceLevel::from_decimal(dec!(0.50)).is_valid_probability());
+        assert!(PriceLevel::from_decimal(dec!(0.01)).is_valid_probability());
+        assert!(PriceLevel::from_decimal(dec!(0.99)).is_valid_probability());
+        assert!(!PriceLevel::from_decimal(dec!(0)).is_valid_probability());
+        assert!(!PriceLevel::from_decimal(dec!(1)).is_valid_probability());
+    }
+
+    #[test]
+    fn test_order_status_transitions() {
+        use OrderStatus::*;
+        assert!(Accepted.can_transition_to(Open));
+        assert!(Accepted.can_transition_to(Rejected));
+        assert!(Open.can_transition_to(PartiallyFilled));
+        assert!(PartiallyFilled.can_transition_to(PartiallyFilled));
+        assert!(PartiallyFilled.can_transition_to(Cancelled));
+        assert!(!Open.can_transition_to(Rejected));
+        assert!(!Open.can_transition_to(Accepted));
+        for terminal in [Filled, Cancelled, Expired, Rejected] {
+            assert!(terminal.is_final());
+            assert!(!terminal.can_transition_to(Open));
+            assert!(!terminal.can_transition_to(Cancelled));
+        }
+        for status in [Accepted, Open, PartiallyFilled, Filled, Cancelled, Expired, Rejected] {
+            assert_eq!(status.to_string().parse::<OrderStatus>(), Ok(status));
+        }
+    }
+
+    #[test]
+    fn test_reject_reason_codes_round_trip() {
+        for reason in RejectReason::ALL {
+            assert_eq!(reason.code().parse::<RejectReason>(), Ok(reason));
+        }
+        assert_eq!(
+            MatchingError::InvalidAmount("zero".to_string()).reject_reason(),
+            RejectReason::InvalidAmount
+        );
+    }
+
+    #[test]
+    fn test_match_type_derive() {
+        // Normal: Yes buy vs Yes sell
+        assert_eq!(
+            MatchType::derive(ShareType::Yes, Side::Buy, ShareType::Yes, Side::Sell),
+            MatchType::Normal
+        );
+
+        // Mint: Yes buy + No buy
+        assert_eq!(
+            MatchType::derive(ShareType::Yes, Side::Buy, ShareType::No, Side::Buy),
+            MatchType::Mint
+        );
+
+        // Merge: Yes sell + No sell
+        assert_eq!(
+            MatchType::derive(ShareType::Yes, Side::Sell, ShareType::No, Side::Sell),
+            MatchType::Merge
+        );
+    }
+
+    #[test]
+    fn test_symmetric_fee() {
+        let config = FeeConfig::default();
+
+        // Fee for buying at 0.90 should equal fee for buying at 0.10
+        let fee_high = config.calculate_taker_fee(dec!(0.90), dec!(100));
+        let fee_low = config.calculate_taker_fee(dec!(0.10), dec!(100));
+        assert_eq!(fee_high, fee_low);
+
+        // Fee is highest at 0.50
+        let fee_mid = config.calculate_taker_fee(dec!(0.50), dec!(100));
+        assert!(fee_mid > fee_high);
+    }
+
+    #[test]
+    fn test_maker_discount() {
+        let config = FeeConfig::default();
+        let taker_fee = config.calculate_taker_fee(dec!(0.50), dec!(100));
+        let maker_fee = config.calculate_maker_fee(dec!(0.50), dec!(100));
+
+        // Maker fee should be less than taker fee
+        assert!(maker_fee < taker_fee);
+    }
+
+    #[test]
+    fn test_order_history_query() {
+        let query = OrderHistoryQuery {
+            status: Some("filled".to_string()),
+            market_id: Some(Uuid::new_v4()),
+            share_type: Some("yes".to_string()),
+            limit: Some(10),
+            before: None,
+            after: None,
+        };
+
+        assert_eq!(query.get_limit(), 10);
+        assert!(query.matches_status("filled"));
+        assert!(!query.matches_status("open"));
+    }
+}
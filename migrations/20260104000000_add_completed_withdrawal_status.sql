@@ -0,0 +1,4 @@
+-- Add 'completed' status to withdrawal_status enum: the withdrawal handlers
+-- set it, but the initial enum lacks it. Versioned ahead of the migration
+-- that places 'processing' before it, so fresh databases apply it first.
+ALTER TYPE withdrawal_status ADD VALUE IF NOT EXISTS 'completed' AFTER 'confirmed';
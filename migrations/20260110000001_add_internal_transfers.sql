@@ -0,0 +1,39 @@
+-- Internal (off-chain) collateral transfers between platform users
+-- plus a per-user balance ledger recording every balance movement
+
+CREATE TABLE IF NOT EXISTS internal_transfers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    from_address VARCHAR(42) NOT NULL,
+    to_address VARCHAR(42) NOT NULL,
+    token VARCHAR(42) NOT NULL,
+    amount DECIMAL(36, 18) NOT NULL CHECK (amount > 0),
+    memo VARCHAR(140),
+    status VARCHAR(20) NOT NULL DEFAULT 'completed',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (from_address <> to_address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_internal_transfers_from ON internal_transfers(from_address, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_internal_transfers_to ON internal_transfers(to_address, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS balance_ledger (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_address VARCHAR(42) NOT NULL,
+    token VARCHAR(42) NOT NULL,
+    entry_type VARCHAR(32) NOT NULL,
+    amount DECIMAL(36, 18) NOT NULL,
+    balance_after DECIMAL(36, 18) NOT NULL,
+    reference_id UUID,
+    counterparty VARCHAR(42),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_balance_ledger_user ON balance_ledger(user_address, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_balance_ledger_reference ON balance_ledger(reference_id);
+
+COMMENT ON TABLE internal_transfers IS 'Instant off-chain collateral transfers between platform addresses';
+COMMENT ON COLUMN internal_transfers.memo IS 'Optional free-form note (e.g. OTC reference)';
+COMMENT ON TABLE balance_ledger IS 'Append-only ledger of available balance movements';
+COMMENT ON COLUMN balance_ledger.entry_type IS 'transfer_in, transfer_out, ...';
+COMMENT ON COLUMN balance_ledger.amount IS 'Signed amount: positive = credit, negative = debit';
+COMMENT ON COLUMN balance_ledger.balance_after IS 'Available balance after this entry was applied';
@@ -0,0 +1,24 @@
+-- Withdrawal fee policy: record fee breakdown and dust sweep on each withdrawal
+-- `amount` remains the gross amount debited from the user's balance;
+-- `net_amount` is what is actually paid out on-chain.
+
+ALTER TABLE withdrawals
+ADD COLUMN IF NOT EXISTS fee DECIMAL(36, 18) NOT NULL DEFAULT 0;
+
+ALTER TABLE withdrawals
+ADD COLUMN IF NOT EXISTS gas_fee DECIMAL(36, 18) NOT NULL DEFAULT 0;
+
+ALTER TABLE withdrawals
+ADD COLUMN IF NOT EXISTS dust_swept DECIMAL(36, 18) NOT NULL DEFAULT 0;
+
+ALTER TABLE withdrawals
+ADD COLUMN IF NOT EXISTS net_amount DECIMAL(36, 18);
+
+-- Backfill existing rows: no fee was charged
+UPDATE withdrawals SET net_amount = amount WHERE net_amount IS NULL;
+
+COMMENT ON COLUMN withdrawals.amount IS 'Gross amount debited from balance (requested + dust swept)';
+COMMENT ON COLUMN withdrawals.fee IS 'Total withdrawal fee (flat + gas-linked)';
+COMMENT ON COLUMN withdrawals.gas_fee IS 'Gas-linked portion of the fee';
+COMMENT ON COLUMN withdrawals.dust_swept IS 'Leftover dust balance included in this withdrawal';
+COMMENT ON COLUMN withdrawals.net_amount IS 'Amount paid out on-chain (amount - fee)';
@@ -0,0 +1,45 @@
+-- Email notifications: user email, per-user preferences and the outbound notification queue
+
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS email VARCHAR(255);
+
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_address VARCHAR(42) PRIMARY KEY,
+    email_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    notify_fills BOOLEAN NOT NULL DEFAULT TRUE,
+    fill_min_notional DECIMAL(36, 18) NOT NULL DEFAULT 100,
+    notify_settlements BOOLEAN NOT NULL DEFAULT TRUE,
+    notify_withdrawals BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_notification_preferences_updated_at ON notification_preferences;
+CREATE TRIGGER update_notification_preferences_updated_at
+    BEFORE UPDATE ON notification_preferences
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS notification_queue (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_address VARCHAR(42) NOT NULL,
+    channel VARCHAR(20) NOT NULL DEFAULT 'email',
+    kind VARCHAR(64) NOT NULL,
+    recipient VARCHAR(255) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_queue_due ON notification_queue(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_notification_queue_user ON notification_queue(user_address, created_at DESC);
+
+COMMENT ON COLUMN users.email IS 'Optional contact email for notifications';
+COMMENT ON TABLE notification_preferences IS 'Per-user notification opt-ins; missing row = defaults (email off)';
+COMMENT ON COLUMN notification_preferences.fill_min_notional IS 'Only fills with price * amount >= this value generate an email';
+COMMENT ON TABLE notification_queue IS 'Outbound notifications rendered and sent by the notification worker';
+COMMENT ON COLUMN notification_queue.kind IS 'order_filled, settlement_payout, withdrawal_status';
+COMMENT ON COLUMN notification_queue.status IS 'pending, sent, failed (retries exhausted)';
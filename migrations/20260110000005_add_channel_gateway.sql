@@ -0,0 +1,53 @@
+-- Telegram/Discord channel gateway: featured markets, channel integrations and outbound messages
+
+ALTER TABLE markets
+ADD COLUMN IF NOT EXISTS is_featured BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE markets
+ADD COLUMN IF NOT EXISTS featured_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_markets_featured ON markets(featured_at DESC) WHERE is_featured;
+
+CREATE TABLE IF NOT EXISTS channel_integrations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    platform VARCHAR(20) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    -- Telegram chat id (e.g. -100123 or @channel) / Discord channel id
+    chat_id VARCHAR(100) NOT NULL,
+    event_types TEXT[] NOT NULL DEFAULT '{}',
+    categories TEXT[] NOT NULL DEFAULT '{}',
+    min_trade_notional DECIMAL(36, 18) NOT NULL DEFAULT 1000,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT channel_integrations_platform_check CHECK (platform IN ('telegram', 'discord'))
+);
+
+DROP TRIGGER IF EXISTS update_channel_integrations_updated_at ON channel_integrations;
+CREATE TRIGGER update_channel_integrations_updated_at
+    BEFORE UPDATE ON channel_integrations
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS channel_messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    integration_id UUID NOT NULL REFERENCES channel_integrations(id) ON DELETE CASCADE,
+    event_type VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_channel_messages_due ON channel_messages(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_channel_messages_integration ON channel_messages(integration_id, created_at DESC);
+
+COMMENT ON COLUMN markets.is_featured IS 'Promoted on the home page and announced to channel integrations';
+COMMENT ON TABLE channel_integrations IS 'Telegram/Discord channels receiving platform announcements';
+COMMENT ON COLUMN channel_integrations.event_types IS 'market_resolved, large_trade, featured_market; empty = all';
+COMMENT ON COLUMN channel_integrations.categories IS 'Market categories to announce; empty = all';
+COMMENT ON COLUMN channel_integrations.min_trade_notional IS 'Trades below this notional are not announced';
+COMMENT ON TABLE channel_messages IS 'Per-channel outbound messages sent by the gateway worker';
+COMMENT ON COLUMN channel_messages.status IS 'pending, sent, failed (retries exhausted)';
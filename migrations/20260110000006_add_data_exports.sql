@@ -0,0 +1,23 @@
+-- Public data snapshot exports (daily trades, orderbook snapshots, resolutions)
+
+CREATE TABLE IF NOT EXISTS data_exports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    export_date DATE NOT NULL,
+    dataset VARCHAR(50) NOT NULL,
+    format VARCHAR(10) NOT NULL,
+    object_key TEXT NOT NULL,
+    row_count BIGINT NOT NULL DEFAULT 0,
+    size_bytes BIGINT NOT NULL DEFAULT 0,
+    sha256 VARCHAR(64),
+    status VARCHAR(20) NOT NULL DEFAULT 'completed',
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (export_date, dataset, format)
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_exports_date ON data_exports(export_date DESC);
+
+COMMENT ON TABLE data_exports IS 'Manifest of exported public datasets in object storage';
+COMMENT ON COLUMN data_exports.dataset IS 'trades, orderbook_snapshots, resolutions';
+COMMENT ON COLUMN data_exports.format IS 'parquet or csv';
+COMMENT ON COLUMN data_exports.status IS 'completed or failed';
@@ -0,0 +1,23 @@
+-- Per-market analytics buckets (volume, trader counts, probability history)
+
+CREATE TABLE IF NOT EXISTS market_analytics (
+    market_id UUID NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    bucket_interval VARCHAR(4) NOT NULL,
+    bucket_start TIMESTAMPTZ NOT NULL,
+    volume NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    shares_traded NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    trade_count BIGINT NOT NULL DEFAULT 0,
+    unique_traders BIGINT NOT NULL DEFAULT 0,
+    avg_trade_size NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    probability NUMERIC(36, 18),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (market_id, bucket_interval, bucket_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_analytics_bucket ON market_analytics(bucket_interval, bucket_start DESC);
+
+COMMENT ON TABLE market_analytics IS 'Time-bucketed market statistics maintained by the analytics aggregation job';
+COMMENT ON COLUMN market_analytics.bucket_interval IS '1h or 1d';
+COMMENT ON COLUMN market_analytics.volume IS 'Collateral notional traded (price * amount)';
+COMMENT ON COLUMN market_analytics.unique_traders IS 'Distinct maker/taker addresses in the bucket';
+COMMENT ON COLUMN market_analytics.probability IS 'Last YES trade price in the bucket';
@@ -0,0 +1,31 @@
+-- Feature flags gating risky features per environment or user cohort
+
+CREATE TABLE IF NOT EXISTS feature_flags (
+    key VARCHAR(64) PRIMARY KEY,
+    description TEXT NOT NULL DEFAULT '',
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Environments the flag applies in; empty = all
+    environments TEXT[] NOT NULL DEFAULT '{}',
+    -- Percentage of users (by stable address hash) the flag is on for
+    rollout_percentage SMALLINT NOT NULL DEFAULT 0,
+    -- Addresses the flag is always on for while enabled
+    allowed_users TEXT[] NOT NULL DEFAULT '{}',
+    updated_by VARCHAR(42),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT feature_flags_rollout_check CHECK (rollout_percentage BETWEEN 0 AND 100)
+);
+
+DROP TRIGGER IF EXISTS update_feature_flags_updated_at ON feature_flags;
+CREATE TRIGGER update_feature_flags_updated_at
+    BEFORE UPDATE ON feature_flags
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO feature_flags (key, description, enabled, rollout_percentage) VALUES
+    ('complement_matching', 'Mint/Merge matching against the complement orderbook', TRUE, 100),
+    ('market_orders', 'Market (non-limit) order type', TRUE, 100),
+    ('auto_mm', 'Automated market making', FALSE, 0)
+ON CONFLICT (key) DO NOTHING;
+
+COMMENT ON TABLE feature_flags IS 'Runtime feature flags; cached in Redis and evaluated in-process';
+COMMENT ON COLUMN feature_flags.enabled IS 'Kill switch: when false the flag is off for everyone';
@@ -0,0 +1,88 @@
+-- Per-user, per-market open order aggregate maintained by trigger, so the
+-- order summary endpoint reads a handful of rows instead of scanning orders
+
+CREATE TABLE IF NOT EXISTS user_open_order_stats (
+    user_address VARCHAR(42) NOT NULL,
+    market_id UUID NOT NULL,
+    open_orders INTEGER NOT NULL DEFAULT 0,
+    buy_orders INTEGER NOT NULL DEFAULT 0,
+    sell_orders INTEGER NOT NULL DEFAULT 0,
+    buy_notional NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    sell_shares NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_address, market_id)
+);
+
+COMMENT ON TABLE user_open_order_stats IS 'Open (pending/open/partially_filled) prediction market orders per user and market';
+COMMENT ON COLUMN user_open_order_stats.buy_notional IS 'Collateral reserved by open buys: price * (amount - filled_amount)';
+COMMENT ON COLUMN user_open_order_stats.sell_shares IS 'Shares reserved by open sells: amount - filled_amount';
+
+CREATE OR REPLACE FUNCTION apply_open_order_stats(o orders, sign INTEGER)
+RETURNS VOID AS $$
+BEGIN
+    IF o.market_id IS NULL OR o.status NOT IN ('pending', 'open', 'partially_filled') THEN
+        RETURN;
+    END IF;
+
+    INSERT INTO user_open_order_stats AS s (
+        user_address, market_id, open_orders, buy_orders, sell_orders, buy_notional, sell_shares, updated_at
+    )
+    VALUES (
+        LOWER(o.user_address),
+        o.market_id,
+        sign,
+        CASE WHEN o.side = 'buy' THEN sign ELSE 0 END,
+        CASE WHEN o.side = 'sell' THEN sign ELSE 0 END,
+        CASE WHEN o.side = 'buy' THEN sign * COALESCE(o.price, 0) * (o.amount - o.filled_amount) ELSE 0 END,
+        CASE WHEN o.side = 'sell' THEN sign * (o.amount - o.filled_amount) ELSE 0 END,
+        NOW()
+    )
+    ON CONFLICT (user_address, market_id) DO UPDATE SET
+        open_orders = s.open_orders + EXCLUDED.open_orders,
+        buy_orders = s.buy_orders + EXCLUDED.buy_orders,
+        sell_orders = s.sell_orders + EXCLUDED.sell_orders,
+        buy_notional = s.buy_notional + EXCLUDED.buy_notional,
+        sell_shares = s.sell_shares + EXCLUDED.sell_shares,
+        updated_at = NOW();
+
+    DELETE FROM user_open_order_stats
+    WHERE user_address = LOWER(o.user_address) AND market_id = o.market_id AND open_orders <= 0;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION maintain_open_order_stats()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM apply_open_order_stats(OLD, -1);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM apply_open_order_stats(NEW, 1);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS orders_open_stats_insert_delete ON orders;
+CREATE TRIGGER orders_open_stats_insert_delete
+    AFTER INSERT OR DELETE ON orders
+    FOR EACH ROW EXECUTE FUNCTION maintain_open_order_stats();
+
+DROP TRIGGER IF EXISTS orders_open_stats_update ON orders;
+CREATE TRIGGER orders_open_stats_update
+    AFTER UPDATE OF status, side, price, amount, filled_amount, market_id, user_address ON orders
+    FOR EACH ROW EXECUTE FUNCTION maintain_open_order_stats();
+
+-- Backfill from existing open orders
+INSERT INTO user_open_order_stats (user_address, market_id, open_orders, buy_orders, sell_orders, buy_notional, sell_shares)
+SELECT LOWER(user_address),
+       market_id,
+       COUNT(*),
+       COUNT(*) FILTER (WHERE side = 'buy'),
+       COUNT(*) FILTER (WHERE side = 'sell'),
+       COALESCE(SUM(COALESCE(price, 0) * (amount - filled_amount)) FILTER (WHERE side = 'buy'), 0),
+       COALESCE(SUM(amount - filled_amount) FILTER (WHERE side = 'sell'), 0)
+FROM orders
+WHERE market_id IS NOT NULL AND status IN ('pending', 'open', 'partially_filled')
+GROUP BY LOWER(user_address), market_id
+ON CONFLICT (user_address, market_id) DO NOTHING;
@@ -0,0 +1,21 @@
+-- Retry queue for trades whose persistence failed after matching. The
+-- trade already happened in the engine, so it is retried with backoff and
+-- parked as 'dead' for an operator once retries are exhausted.
+
+CREATE TABLE IF NOT EXISTS trade_persist_queue (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    trade_id UUID NOT NULL UNIQUE,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN trade_persist_queue.payload IS 'Serialized TradeEvent as produced by the matching engine';
+COMMENT ON COLUMN trade_persist_queue.status IS 'pending (retried by the worker) or dead (retries exhausted)';
+
+CREATE INDEX IF NOT EXISTS idx_trade_persist_queue_due ON trade_persist_queue(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_trade_persist_queue_status ON trade_persist_queue(status, created_at DESC);
@@ -0,0 +1,7 @@
+-- Per-market share quantity precision. Collateral is always 6 decimals;
+-- order amounts must fit the market's share precision.
+
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS share_decimals SMALLINT NOT NULL DEFAULT 2
+    CHECK (share_decimals BETWEEN 0 AND 6);
+
+COMMENT ON COLUMN markets.share_decimals IS 'Decimals allowed in order and position share quantities';
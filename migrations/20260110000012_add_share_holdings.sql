@@ -0,0 +1,56 @@
+-- Authoritative share holdings maintained by the order flow orchestrator
+--
+-- A user can hold both Yes and No shares of the same outcome (mint gives the
+-- maker the complement side), so holdings are keyed by share type as well.
+
+ALTER TABLE shares DROP CONSTRAINT IF EXISTS shares_user_address_outcome_id_key;
+ALTER TABLE shares DROP CONSTRAINT IF EXISTS shares_user_outcome_share_type_key;
+ALTER TABLE shares ADD CONSTRAINT shares_user_outcome_share_type_key
+    UNIQUE (user_address, outcome_id, share_type);
+
+-- Per-outcome pair supply. For every outcome:
+--   SUM(yes shares) = minted - merged - yes_redeemed
+--   SUM(no shares)  = minted - merged - no_redeemed
+CREATE TABLE IF NOT EXISTS outcome_share_supply (
+    outcome_id UUID PRIMARY KEY REFERENCES outcomes(id),
+    market_id UUID NOT NULL REFERENCES markets(id),
+    minted DECIMAL(30, 8) NOT NULL DEFAULT 0,
+    merged DECIMAL(30, 8) NOT NULL DEFAULT 0,
+    yes_redeemed DECIMAL(30, 8) NOT NULL DEFAULT 0,
+    no_redeemed DECIMAL(30, 8) NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_outcome_share_supply_market ON outcome_share_supply(market_id);
+
+COMMENT ON TABLE outcome_share_supply IS 'Yes/No pairs minted, merged and redeemed per outcome';
+
+-- Seed supply so existing holdings satisfy the invariant
+INSERT INTO outcome_share_supply (outcome_id, market_id, minted, yes_redeemed, no_redeemed)
+SELECT outcome_id,
+       market_id,
+       GREATEST(yes, no, 0),
+       GREATEST(yes, no, 0) - yes,
+       GREATEST(yes, no, 0) - no
+FROM (
+    SELECT outcome_id,
+           MIN(market_id::text)::uuid AS market_id,
+           COALESCE(SUM(amount) FILTER (WHERE share_type = 'yes'), 0) AS yes,
+           COALESCE(SUM(amount) FILTER (WHERE share_type = 'no'), 0) AS no
+    FROM shares
+    GROUP BY outcome_id
+) s
+ON CONFLICT (outcome_id) DO NOTHING;
+
+-- Read model for WebSocket position snapshots
+CREATE OR REPLACE VIEW share_holdings AS
+SELECT id,
+       user_address,
+       market_id,
+       outcome_id,
+       share_type::text AS share_type,
+       amount AS shares,
+       avg_cost AS avg_price,
+       created_at,
+       updated_at
+FROM shares;
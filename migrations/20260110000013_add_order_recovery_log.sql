@@ -0,0 +1,28 @@
+-- Audit trail of startup order recovery. One row per open order re-inserted
+-- into the matching engine, so support can explain fills that happened while
+-- the books were rebuilt. Rows whose order changed are pushed to the owner
+-- over WebSocket and stamped notified_at.
+
+CREATE TABLE IF NOT EXISTS order_recovery_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL,
+    user_address VARCHAR(42) NOT NULL,
+    symbol VARCHAR(200) NOT NULL,
+    side VARCHAR(10) NOT NULL,
+    price DECIMAL(20, 8) NOT NULL,
+    amount DECIMAL(30, 8) NOT NULL,
+    remaining_before DECIMAL(30, 8) NOT NULL,
+    remaining_after DECIMAL(30, 8) NOT NULL,
+    outcome VARCHAR(20) NOT NULL,
+    trade_ids UUID[] NOT NULL DEFAULT '{}',
+    error TEXT,
+    recovered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    notified_at TIMESTAMPTZ
+);
+
+COMMENT ON COLUMN order_recovery_log.outcome IS 'restored, partially_filled, filled or failed';
+COMMENT ON COLUMN order_recovery_log.trade_ids IS 'Trades the order took part in during recovery';
+
+CREATE INDEX IF NOT EXISTS idx_order_recovery_log_order ON order_recovery_log(order_id, recovered_at DESC);
+CREATE INDEX IF NOT EXISTS idx_order_recovery_log_pending ON order_recovery_log(user_address)
+    WHERE notified_at IS NULL AND outcome <> 'restored';
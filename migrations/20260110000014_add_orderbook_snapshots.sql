@@ -0,0 +1,21 @@
+-- Periodic orderbook snapshots, so past liquidity can be queried for
+-- disputes ("the book showed 0.62 when I placed my order") and analytics.
+-- A book is only written when it changed since its previous snapshot; the
+-- state at any time is the latest snapshot at or before it.
+
+CREATE TABLE IF NOT EXISTS orderbook_snapshots (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    market_id UUID NOT NULL,
+    outcome_id UUID NOT NULL,
+    share_type VARCHAR(10) NOT NULL,
+    bids JSONB NOT NULL,
+    asks JSONB NOT NULL,
+    last_price DECIMAL(20, 8),
+    captured_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN orderbook_snapshots.bids IS 'Aggregated [price, amount] levels, best first';
+
+CREATE INDEX IF NOT EXISTS idx_orderbook_snapshots_book
+    ON orderbook_snapshots(market_id, outcome_id, share_type, captured_at DESC);
+CREATE INDEX IF NOT EXISTS idx_orderbook_snapshots_captured ON orderbook_snapshots(captured_at);
@@ -0,0 +1,10 @@
+-- Trade tape fields: engine sequence number (orders trades consistently
+-- across a market's books) and block/RFQ flags
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS sequence BIGINT;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS is_block_trade BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS is_rfq BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN trades.sequence IS 'Matching engine trade sequence number; NULL for trades persisted before it existed';
+
+CREATE INDEX IF NOT EXISTS idx_trades_sequence ON trades(sequence DESC) WHERE sequence IS NOT NULL;
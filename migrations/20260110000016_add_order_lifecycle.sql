@@ -0,0 +1,42 @@
+-- Canonical order lifecycle. Orders start 'accepted', rest as 'open' /
+-- 'partially_filled', and end 'filled', 'cancelled', 'expired' or
+-- 'rejected'. Rejected orders carry a reason code from the public catalog
+-- (GET /orders/reject-reasons). The trigger refuses moves the state machine
+-- does not allow, so no writer can reopen a finished order.
+
+ALTER TYPE order_status ADD VALUE IF NOT EXISTS 'accepted' AFTER 'pending';
+ALTER TYPE order_status ADD VALUE IF NOT EXISTS 'expired' AFTER 'cancelled';
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS reject_reason VARCHAR(40);
+
+COMMENT ON COLUMN orders.reject_reason IS 'Reject reason code (e.g. INSUFFICIENT_BALANCE) when status is rejected';
+
+CREATE INDEX IF NOT EXISTS idx_orders_rejected ON orders(user_address, created_at DESC)
+    WHERE reject_reason IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION enforce_order_status_transition() RETURNS trigger AS $$
+DECLARE
+    old_status TEXT := OLD.status::text;
+    new_status TEXT := NEW.status::text;
+BEGIN
+    IF new_status = old_status THEN
+        RETURN NEW;
+    END IF;
+
+    IF old_status IN ('filled', 'cancelled', 'expired', 'rejected')
+        OR (old_status IN ('open', 'partially_filled')
+            AND new_status NOT IN ('partially_filled', 'filled', 'cancelled', 'expired'))
+        OR new_status IN ('pending', 'accepted')
+    THEN
+        RAISE EXCEPTION 'illegal order status transition % -> % for order %', old_status, new_status, OLD.id
+            USING ERRCODE = 'check_violation';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_orders_status_transition ON orders;
+CREATE TRIGGER trg_orders_status_transition
+    BEFORE UPDATE OF status ON orders
+    FOR EACH ROW EXECUTE FUNCTION enforce_order_status_transition();
@@ -0,0 +1,32 @@
+-- Market groups: sibling binary markets of one event (e.g. one market per
+-- candidate in an election). In a negative-risk group at most one market
+-- resolves Yes, so holding No in every market pays out N-1 for sure and can
+-- be converted straight into collateral.
+
+CREATE TABLE IF NOT EXISTS market_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    title TEXT NOT NULL,
+    neg_risk BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by VARCHAR(42),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS group_id UUID REFERENCES market_groups(id);
+CREATE INDEX IF NOT EXISTS idx_markets_group ON markets(group_id) WHERE group_id IS NOT NULL;
+
+COMMENT ON COLUMN market_groups.neg_risk IS 'Outcomes are mutually exclusive across the group: at most one market resolves Yes';
+
+-- No sets converted into collateral
+CREATE TABLE IF NOT EXISTS neg_risk_conversions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    group_id UUID NOT NULL REFERENCES market_groups(id),
+    user_address VARCHAR(42) NOT NULL,
+    -- No shares burned in each market of the group
+    amount DECIMAL(30, 8) NOT NULL,
+    market_count INTEGER NOT NULL,
+    -- Collateral credited: amount * (market_count - 1)
+    payout DECIMAL(30, 8) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_neg_risk_conversions_user ON neg_risk_conversions(user_address, created_at DESC);
@@ -0,0 +1,53 @@
+-- Trade busts and price adjustments
+--
+-- Erroneous trades are corrected with compensating holdings and ledger
+-- entries, never by rewriting the trade. Each correction is recorded in
+-- trade_adjustments; the trade row only carries an annotation of its current
+-- state, and a trigger keeps its economic fields immutable.
+
+CREATE TABLE IF NOT EXISTS trade_adjustments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    trade_id UUID NOT NULL REFERENCES trades(id),
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('bust', 'price_adjust')),
+    -- Price the trade was effective at before this adjustment
+    previous_price DECIMAL(36, 18) NOT NULL,
+    -- New effective price; NULL for busts
+    adjusted_price DECIMAL(36, 18),
+    amount DECIMAL(36, 18) NOT NULL,
+    reason TEXT NOT NULL,
+    admin_address VARCHAR(42) NOT NULL,
+    compensations JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_trade_adjustments_trade ON trade_adjustments(trade_id, created_at);
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS adjustment_status VARCHAR(20);
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS adjusted_price DECIMAL(36, 18);
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS adjusted_at TIMESTAMPTZ;
+
+COMMENT ON TABLE trade_adjustments IS 'Admin busts and price adjustments of trades, with the compensations applied';
+COMMENT ON COLUMN trades.adjustment_status IS 'NULL, busted or price_adjusted';
+COMMENT ON COLUMN trades.adjusted_price IS 'Effective price after the latest price adjustment; price keeps the executed price';
+
+CREATE OR REPLACE FUNCTION protect_trade_economics() RETURNS trigger AS $$
+BEGIN
+    IF (NEW.price, NEW.amount, NEW.side, NEW.share_type, NEW.match_type,
+        NEW.maker_address, NEW.taker_address, NEW.maker_order_id, NEW.taker_order_id,
+        NEW.maker_fee, NEW.taker_fee, NEW.created_at)
+       IS DISTINCT FROM
+       (OLD.price, OLD.amount, OLD.side, OLD.share_type, OLD.match_type,
+        OLD.maker_address, OLD.taker_address, OLD.maker_order_id, OLD.taker_order_id,
+        OLD.maker_fee, OLD.taker_fee, OLD.created_at)
+    THEN
+        RAISE EXCEPTION 'trade % is immutable; record a trade adjustment instead', OLD.id
+            USING ERRCODE = 'check_violation';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_trades_immutable ON trades;
+CREATE TRIGGER trg_trades_immutable
+    BEFORE UPDATE ON trades
+    FOR EACH ROW EXECUTE FUNCTION protect_trade_economics();
@@ -0,0 +1,26 @@
+-- System event timeline for incident forensics
+--
+-- Significant operational events (engine start/stop, order recovery,
+-- market closes, settlement failures, trade corrections, config changes)
+-- with the instance that recorded them, so an incident timeline can be read
+-- back with one query instead of being reconstructed from logs.
+
+CREATE TABLE IF NOT EXISTS system_events (
+    id BIGSERIAL PRIMARY KEY,
+    kind VARCHAR(64) NOT NULL,
+    category VARCHAR(32) NOT NULL,
+    severity VARCHAR(16) NOT NULL CHECK (severity IN ('info', 'warning', 'critical')),
+    instance VARCHAR(128) NOT NULL,
+    -- What the event is about (market id, trade id, flag key, user address)
+    subject VARCHAR(128),
+    message TEXT NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_system_events_time ON system_events(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_system_events_category ON system_events(category, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_system_events_subject ON system_events(subject, created_at DESC) WHERE subject IS NOT NULL;
+
+COMMENT ON TABLE system_events IS 'Append-only operational event timeline (GET /admin/system-events)';
+COMMENT ON COLUMN system_events.instance IS 'host:pid of the process that recorded the event';
@@ -0,0 +1,172 @@
+-- Per-market listing aggregates maintained by trigger, so GET /markets reads
+-- one row per market instead of aggregating trades and querying outcomes
+-- for every market on the page.
+--
+-- Volume counts trades at their effective price (adjusted price if
+-- re-priced, nothing once busted). volume_24h is kept current on every
+-- trade and decayed by a periodic refresh.
+
+CREATE TABLE IF NOT EXISTS market_summaries (
+    market_id UUID PRIMARY KEY REFERENCES markets(id) ON DELETE CASCADE,
+    trade_count BIGINT NOT NULL DEFAULT 0,
+    total_volume NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    volume_24h NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    -- Yes price of the latest trade (No trades count as 1 - price)
+    last_price NUMERIC(36, 18),
+    last_trade_at TIMESTAMPTZ,
+    -- [{id, name, probability}] ordered by name
+    outcomes JSONB NOT NULL DEFAULT '[]',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_summaries_volume_24h ON market_summaries(volume_24h DESC);
+CREATE INDEX IF NOT EXISTS idx_market_summaries_recent ON market_summaries(last_trade_at DESC) WHERE last_trade_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_trades_market_time ON trades(market_id, created_at DESC);
+
+COMMENT ON TABLE market_summaries IS 'Trigger-maintained listing aggregates per market (GET /markets)';
+
+CREATE OR REPLACE FUNCTION trade_effective_notional(t trades)
+RETURNS NUMERIC AS $$
+    SELECT CASE
+        WHEN t.adjustment_status = 'busted' THEN 0
+        ELSE COALESCE(t.adjusted_price, t.price) * t.amount
+    END;
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION market_outcomes_json(p_market_id UUID)
+RETURNS JSONB AS $$
+    SELECT COALESCE(
+        jsonb_agg(jsonb_build_object('id', id, 'name', name, 'probability', probability::text) ORDER BY name),
+        '[]'::jsonb
+    )
+    FROM outcomes
+    WHERE market_id = p_market_id;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION maintain_market_summary_trades()
+RETURNS TRIGGER AS $$
+DECLARE
+    delta NUMERIC;
+    count_delta INTEGER := 0;
+    recent BOOLEAN;
+    yes_price NUMERIC;
+BEGIN
+    IF NEW.market_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'INSERT' THEN
+        delta := trade_effective_notional(NEW);
+        count_delta := CASE WHEN NEW.adjustment_status = 'busted' THEN 0 ELSE 1 END;
+    ELSE
+        delta := trade_effective_notional(NEW) - trade_effective_notional(OLD);
+        IF NEW.adjustment_status = 'busted' AND OLD.adjustment_status IS DISTINCT FROM 'busted' THEN
+            count_delta := -1;
+        END IF;
+    END IF;
+
+    recent := NEW.created_at >= NOW() - INTERVAL '24 hours';
+    yes_price := CASE
+        WHEN NEW.share_type = 'no' THEN 1 - COALESCE(NEW.adjusted_price, NEW.price)
+        ELSE COALESCE(NEW.adjusted_price, NEW.price)
+    END;
+
+    -- Every market has a row (created with the market, backfilled below);
+    -- trades of unknown markets are ignored rather than failing the insert
+    UPDATE market_summaries SET
+        trade_count = trade_count + count_delta,
+        total_volume = total_volume + delta,
+        volume_24h = volume_24h + CASE WHEN recent THEN delta ELSE 0 END,
+        last_price = CASE
+            WHEN TG_OP = 'INSERT' AND (last_trade_at IS NULL OR NEW.created_at >= last_trade_at) THEN yes_price
+            ELSE last_price
+        END,
+        last_trade_at = CASE
+            WHEN TG_OP = 'INSERT' THEN GREATEST(last_trade_at, NEW.created_at)
+            ELSE last_trade_at
+        END,
+        updated_at = NOW()
+    WHERE market_id = NEW.market_id;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trades_market_summary_insert ON trades;
+CREATE TRIGGER trades_market_summary_insert
+    AFTER INSERT ON trades
+    FOR EACH ROW EXECUTE FUNCTION maintain_market_summary_trades();
+
+DROP TRIGGER IF EXISTS trades_market_summary_adjust ON trades;
+CREATE TRIGGER trades_market_summary_adjust
+    AFTER UPDATE OF adjustment_status, adjusted_price ON trades
+    FOR EACH ROW EXECUTE FUNCTION maintain_market_summary_trades();
+
+CREATE OR REPLACE FUNCTION maintain_market_summary_outcomes()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        -- Also fires while a market delete cascades; never re-create the row
+        UPDATE market_summaries
+        SET outcomes = market_outcomes_json(OLD.market_id), updated_at = NOW()
+        WHERE market_id = OLD.market_id;
+    ELSE
+        INSERT INTO market_summaries AS s (market_id, outcomes)
+        VALUES (NEW.market_id, market_outcomes_json(NEW.market_id))
+        ON CONFLICT (market_id) DO UPDATE SET
+            outcomes = EXCLUDED.outcomes,
+            updated_at = NOW();
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS outcomes_market_summary ON outcomes;
+CREATE TRIGGER outcomes_market_summary
+    AFTER INSERT OR DELETE OR UPDATE OF name, probability ON outcomes
+    FOR EACH ROW EXECUTE FUNCTION maintain_market_summary_outcomes();
+
+CREATE OR REPLACE FUNCTION create_market_summary()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO market_summaries (market_id) VALUES (NEW.id) ON CONFLICT (market_id) DO NOTHING;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS markets_create_summary ON markets;
+CREATE TRIGGER markets_create_summary
+    AFTER INSERT ON markets
+    FOR EACH ROW EXECUTE FUNCTION create_market_summary();
+
+-- Backfill existing markets
+INSERT INTO market_summaries (market_id, trade_count, total_volume, volume_24h, last_price, last_trade_at, outcomes)
+SELECT m.id,
+       COALESCE(t.trade_count, 0),
+       COALESCE(t.total_volume, 0),
+       COALESCE(t.volume_24h, 0),
+       l.yes_price,
+       t.last_trade_at,
+       market_outcomes_json(m.id)
+FROM markets m
+LEFT JOIN (
+    SELECT market_id,
+           COUNT(*) FILTER (WHERE adjustment_status IS DISTINCT FROM 'busted') AS trade_count,
+           SUM(trade_effective_notional(trades)) AS total_volume,
+           SUM(trade_effective_notional(trades)) FILTER (WHERE created_at >= NOW() - INTERVAL '24 hours') AS volume_24h,
+           MAX(created_at) AS last_trade_at
+    FROM trades
+    WHERE market_id IS NOT NULL
+    GROUP BY market_id
+) t ON t.market_id = m.id
+LEFT JOIN LATERAL (
+    SELECT CASE
+        WHEN share_type = 'no' THEN 1 - COALESCE(adjusted_price, price)
+        ELSE COALESCE(adjusted_price, price)
+    END AS yes_price
+    FROM trades
+    WHERE market_id = m.id
+    ORDER BY created_at DESC
+    LIMIT 1
+) l ON TRUE
+ON CONFLICT (market_id) DO NOTHING;
@@ -0,0 +1,13 @@
+-- Attribute orders to whoever placed them. Orders placed in-process by the
+-- built-in market maker or the settlement service go through the internal
+-- order gateway and are tagged, so they can be told apart from user orders
+-- in history, fills and fees.
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'api';
+
+ALTER TABLE orders DROP CONSTRAINT IF EXISTS orders_source_check;
+ALTER TABLE orders ADD CONSTRAINT orders_source_check CHECK (source IN ('api', 'auto_mm', 'settlement'));
+
+CREATE INDEX IF NOT EXISTS idx_orders_internal_source ON orders(source, created_at DESC) WHERE source <> 'api';
+
+COMMENT ON COLUMN orders.source IS 'Who placed the order: api (users), auto_mm or settlement (internal order gateway)';
@@ -0,0 +1,21 @@
+-- One row per issued JWT. Tokens carry the session id (`sid` claim) and
+-- are only accepted while their session is neither revoked nor expired,
+-- so users can see where they are logged in and sign out other devices.
+-- Tokens issued before sessions were tracked carry no `sid` and are no
+-- longer accepted; their holders log in again.
+
+CREATE TABLE IF NOT EXISTS user_sessions (
+    id UUID PRIMARY KEY,
+    user_address VARCHAR(42) NOT NULL,
+    ip_address TEXT,
+    user_agent TEXT,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    last_used_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_sessions_active ON user_sessions(user_address, issued_at DESC) WHERE revoked_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_user_sessions_expires ON user_sessions(expires_at);
+
+COMMENT ON TABLE user_sessions IS 'Issued JWT sessions per address; revoked sessions are rejected by auth';
@@ -0,0 +1,40 @@
+-- Admin user-management console
+--
+-- Accounts can be frozen by support (a frozen account can still sign in and
+-- read its data, but every state-changing request is refused), annotated
+-- with support notes, and every admin access to an account - including
+-- lookups - is written to an append-only audit log.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS frozen_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS frozen_reason TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS frozen_by VARCHAR(42);
+
+CREATE INDEX IF NOT EXISTS idx_users_frozen ON users(frozen_at) WHERE frozen_at IS NOT NULL;
+
+COMMENT ON COLUMN users.frozen_at IS 'Set while the account is frozen; state-changing requests are refused';
+
+CREATE TABLE IF NOT EXISTS user_support_notes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_address VARCHAR(42) NOT NULL,
+    admin_address VARCHAR(42) NOT NULL,
+    note TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_support_notes_user ON user_support_notes(user_address, created_at DESC);
+
+COMMENT ON TABLE user_support_notes IS 'Support annotations on user accounts (admin only)';
+
+CREATE TABLE IF NOT EXISTS admin_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    admin_address VARCHAR(42) NOT NULL,
+    action VARCHAR(32) NOT NULL,
+    target_address VARCHAR(42) NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_target ON admin_audit_log(target_address, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_admin ON admin_audit_log(admin_address, created_at DESC);
+
+COMMENT ON TABLE admin_audit_log IS 'Append-only record of admin access to user accounts';
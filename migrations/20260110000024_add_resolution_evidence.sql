@@ -0,0 +1,21 @@
+-- Resolution evidence bundles
+--
+-- Every market resolution stores how it was reached: the oracle data it was
+-- based on (UMA assertion, on-chain condition payouts) or the admin's
+-- justification, who resolved it, when, and the transaction hashes to check
+-- it against on-chain. Served by GET /markets/:id/resolution.
+
+CREATE TABLE IF NOT EXISTS market_resolution_evidence (
+    market_id UUID PRIMARY KEY REFERENCES markets(id) ON DELETE CASCADE,
+    method VARCHAR(20) NOT NULL CHECK (method IN ('admin', 'uma', 'onchain')),
+    winning_outcome_id UUID REFERENCES outcomes(id),
+    -- Admin address, 'cli' for operator resolutions, NULL for oracles
+    resolved_by VARCHAR(42),
+    justification TEXT,
+    -- Method-specific oracle data (assertion, claim, payouts, ...)
+    oracle_data JSONB NOT NULL DEFAULT '{}',
+    tx_hashes TEXT[] NOT NULL DEFAULT '{}',
+    resolved_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE market_resolution_evidence IS 'Provenance of each market resolution (GET /markets/:id/resolution)';
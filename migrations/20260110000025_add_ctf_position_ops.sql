@@ -0,0 +1,29 @@
+-- User-directed on-chain split / merge operations
+--
+-- Users split collateral into Yes + No positions (or merge them back) on
+-- the ConditionalTokens contract with calldata prepared by the API. Each
+-- prepared operation is tracked here until the event listener sees the
+-- matching PositionSplit / PositionsMerge event; operations the listener
+-- sees without a prepared counterpart are recorded as well.
+
+CREATE TABLE IF NOT EXISTS ctf_position_ops (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_address VARCHAR(42) NOT NULL,
+    market_id UUID REFERENCES markets(id),
+    condition_id VARCHAR(66) NOT NULL,
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('split', 'merge')),
+    amount NUMERIC(36, 18) NOT NULL CHECK (amount > 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'prepared' CHECK (status IN ('prepared', 'confirmed')),
+    tx_hash VARCHAR(66),
+    block_number BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    confirmed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ctf_position_ops_user ON ctf_position_ops(user_address, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_ctf_position_ops_pending
+    ON ctf_position_ops(user_address, condition_id, kind, amount, created_at)
+    WHERE status = 'prepared';
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ctf_position_ops_tx ON ctf_position_ops(tx_hash, kind) WHERE tx_hash IS NOT NULL;
+
+COMMENT ON TABLE ctf_position_ops IS 'On-chain splitPosition / mergePositions prepared for and detected from users';
@@ -0,0 +1,20 @@
+-- Per-user settlement mode
+--
+-- custodial:    fills settle into the user's internal balances and shares
+--               (orders placed through POST /orders).
+-- self_custody: fills settle on-chain to the user's wallet through
+--               CTFExchange.matchOrders (signed orders placed through
+--               POST /orders/ctf); positions live in the wallet.
+--
+-- Users who already trade with signed CTF orders keep on-chain settlement.
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS settlement_mode VARCHAR(16) NOT NULL DEFAULT 'custodial'
+        CHECK (settlement_mode IN ('custodial', 'self_custody'));
+
+UPDATE users u
+SET settlement_mode = 'self_custody'
+WHERE EXISTS (
+    SELECT 1 FROM orders o
+    WHERE o.user_address = u.address AND o.maker_amount IS NOT NULL
+);
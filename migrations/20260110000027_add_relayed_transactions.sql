@@ -0,0 +1,32 @@
+-- Gasless meta-transaction relay
+--
+-- Users sign ERC-2771 forward requests for redeemPositions and token
+-- approvals; the operator submits them through the trusted forwarder and
+-- pays the gas. Every relayed request is recorded here, which is also what
+-- the per-user quota counts.
+
+CREATE TABLE IF NOT EXISTS relayed_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_address VARCHAR(42) NOT NULL,
+    kind VARCHAR(32) NOT NULL
+        CHECK (kind IN ('redeem_positions', 'approve_collateral', 'approve_outcome_tokens')),
+    target VARCHAR(42) NOT NULL,
+    forwarder_nonce NUMERIC(78, 0) NOT NULL,
+    call_data TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'submitted'
+        CHECK (status IN ('submitted', 'confirmed', 'failed')),
+    tx_hash VARCHAR(66),
+    block_number BIGINT,
+    gas_used NUMERIC(78, 0),
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_relayed_transactions_user
+    ON relayed_transactions (user_address, created_at DESC);
+
+-- One live relay per signed request; a failed one may be retried
+CREATE UNIQUE INDEX IF NOT EXISTS idx_relayed_transactions_nonce
+    ON relayed_transactions (user_address, forwarder_nonce)
+    WHERE status <> 'failed';
@@ -0,0 +1,69 @@
+-- Paper trading
+--
+-- Virtual balances, positions, orders and fills of the practice mode.
+-- Paper orders match in separate in-memory orderbooks (namespaced
+-- `paper/...` market keys) seeded with house liquidity around the real
+-- market's prices; nothing here touches real balances or shares.
+
+CREATE TABLE IF NOT EXISTS paper_accounts (
+    user_address VARCHAR(42) PRIMARY KEY,
+    available NUMERIC(30, 8) NOT NULL CHECK (available >= 0),
+    frozen NUMERIC(30, 8) NOT NULL DEFAULT 0 CHECK (frozen >= 0),
+    starting_balance NUMERIC(30, 8) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    reset_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS paper_positions (
+    user_address VARCHAR(42) NOT NULL,
+    market_id UUID NOT NULL REFERENCES markets(id),
+    outcome_id UUID NOT NULL REFERENCES outcomes(id),
+    share_type share_type NOT NULL,
+    amount NUMERIC(30, 8) NOT NULL DEFAULT 0,
+    avg_cost NUMERIC(30, 8) NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_address, outcome_id, share_type)
+);
+
+CREATE TABLE IF NOT EXISTS paper_orders (
+    id UUID PRIMARY KEY,
+    user_address VARCHAR(42) NOT NULL,
+    market_id UUID NOT NULL REFERENCES markets(id),
+    outcome_id UUID NOT NULL REFERENCES outcomes(id),
+    share_type share_type NOT NULL,
+    side order_side NOT NULL,
+    order_type VARCHAR(10) NOT NULL CHECK (order_type IN ('limit', 'market')),
+    -- NULL for market orders
+    price NUMERIC(10, 4),
+    amount NUMERIC(30, 8) NOT NULL CHECK (amount > 0),
+    filled_amount NUMERIC(30, 8) NOT NULL DEFAULT 0,
+    -- Collateral still reserved for the unfilled part of a buy
+    reserved NUMERIC(30, 8) NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'partially_filled', 'filled', 'cancelled')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_orders_user ON paper_orders (user_address, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_paper_orders_open ON paper_orders (status)
+    WHERE status IN ('open', 'partially_filled');
+
+-- One row per party per fill
+CREATE TABLE IF NOT EXISTS paper_fills (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    trade_id UUID NOT NULL,
+    order_id UUID NOT NULL REFERENCES paper_orders(id) ON DELETE CASCADE,
+    user_address VARCHAR(42) NOT NULL,
+    market_id UUID NOT NULL,
+    outcome_id UUID NOT NULL,
+    share_type share_type NOT NULL,
+    -- buy / sell / mint / merge
+    change_type VARCHAR(10) NOT NULL,
+    amount NUMERIC(30, 8) NOT NULL,
+    price NUMERIC(10, 4) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_fills_user ON paper_fills (user_address, created_at DESC);
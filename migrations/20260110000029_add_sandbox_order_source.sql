@@ -0,0 +1,8 @@
+-- Sandbox markets for staging/demo environments are seeded with synthetic
+-- resting liquidity placed through the internal order gateway; tag those
+-- orders so they stay distinguishable from user and market maker flow.
+
+ALTER TABLE orders DROP CONSTRAINT IF EXISTS orders_source_check;
+ALTER TABLE orders ADD CONSTRAINT orders_source_check CHECK (source IN ('api', 'auto_mm', 'settlement', 'sandbox'));
+
+COMMENT ON COLUMN orders.source IS 'Who placed the order: api (users), auto_mm, settlement or sandbox (internal order gateway)';
@@ -0,0 +1,5 @@
+-- Order and trade symbols are `market_id:outcome_id:share_type` keys (up to
+-- 78 characters); the 20-character columns from the perpetuals schema
+-- rejected every order placed through the order gateway
+ALTER TABLE orders ALTER COLUMN symbol TYPE VARCHAR(100);
+ALTER TABLE trades ALTER COLUMN symbol TYPE VARCHAR(100);
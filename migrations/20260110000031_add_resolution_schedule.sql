@@ -0,0 +1,40 @@
+-- Market resolution scheduling
+--
+-- A market's `resolution_time` is the deadline after which its outcome is
+-- due. Watchers and open-order holders are reminded 24 hours and 1 hour
+-- ahead; once the deadline passes an active market moves to
+-- `awaiting_resolution`, which refuses new orders but still allows cancels.
+
+DO $$ BEGIN
+    ALTER TYPE market_status ADD VALUE IF NOT EXISTS 'awaiting_resolution' BEFORE 'pending_resolution';
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+ALTER TABLE markets
+ADD COLUMN IF NOT EXISTS resolution_time TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_markets_resolution_time ON markets(resolution_time)
+    WHERE resolution_time IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS market_watchers (
+    market_id UUID NOT NULL REFERENCES markets(id),
+    user_address VARCHAR(42) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (market_id, user_address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_watchers_user ON market_watchers(user_address, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS market_resolution_reminders (
+    market_id UUID NOT NULL REFERENCES markets(id),
+    lead VARCHAR(8) NOT NULL,
+    recipients INT NOT NULL DEFAULT 0,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (market_id, lead)
+);
+
+COMMENT ON COLUMN markets.resolution_time IS 'When the outcome is due; trading stops and the market awaits resolution after it';
+COMMENT ON TABLE market_watchers IS 'Users following a market for resolution reminders';
+COMMENT ON TABLE market_resolution_reminders IS 'Reminders already sent for the current resolution_time; cleared on reschedule';
+COMMENT ON COLUMN market_resolution_reminders.lead IS '24h, 1h';
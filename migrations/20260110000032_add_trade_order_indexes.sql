@@ -0,0 +1,7 @@
+-- Trade lookups by order
+--
+-- GET /orders/:order_id lists the fills of an order, which match either
+-- side of a trade.
+
+CREATE INDEX IF NOT EXISTS idx_trades_maker_order ON trades(maker_order_id);
+CREATE INDEX IF NOT EXISTS idx_trades_taker_order ON trades(taker_order_id);
@@ -0,0 +1,9 @@
+-- Execution quality
+--
+-- The book midpoint an order saw when it reached the matching engine,
+-- the reference its fills' slippage is measured against. NULL when the
+-- book was one-sided or the order predates this column.
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS quoted_mid DECIMAL(36, 18);
+
+COMMENT ON COLUMN orders.quoted_mid IS 'Best bid/ask midpoint (incl. synthetic complement levels) at submission';
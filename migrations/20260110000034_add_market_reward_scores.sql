@@ -0,0 +1,22 @@
+-- Liquidity rewards scoring
+--
+-- Per market and address: maker and taker volume (effective notional, busted
+-- trades excluded) and time spent quoting at the best bid or ask of one of
+-- the market's books. Maintained by the rewards scoring job and published
+-- on GET /markets/:market_id/leaderboard for incentive campaigns.
+--
+-- Addresses are only shown on leaderboards for users who opted in.
+
+CREATE TABLE IF NOT EXISTS market_reward_scores (
+    market_id UUID NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    user_address VARCHAR(42) NOT NULL,
+    maker_volume DECIMAL(36, 18) NOT NULL DEFAULT 0,
+    taker_volume DECIMAL(36, 18) NOT NULL DEFAULT 0,
+    seconds_at_best BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (market_id, user_address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_reward_scores_user ON market_reward_scores(user_address);
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS leaderboard_opt_in BOOLEAN NOT NULL DEFAULT FALSE;
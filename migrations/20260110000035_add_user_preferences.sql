@@ -0,0 +1,20 @@
+-- User preferences
+--
+-- Display settings applied by the services that render things for a user
+-- (notification emails): the timezone timestamps are shown in (IANA name)
+-- and whether amounts are labelled with the collateral token or in dollars.
+-- Notification opt-ins stay in notification_preferences; both are served
+-- together on /account/preferences.
+
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_address VARCHAR(42) PRIMARY KEY,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    currency_display VARCHAR(16) NOT NULL DEFAULT 'token',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_user_preferences_updated_at ON user_preferences;
+CREATE TRIGGER update_user_preferences_updated_at
+    BEFORE UPDATE ON user_preferences
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,30 @@
+-- Two-person rule for high-risk admin actions
+--
+-- Manual market resolutions and trade busts/adjustments are proposed by one
+-- admin, approved by a different admin and executed by either of them before
+-- expires_at. status: pending -> approved -> executed; rows past expires_at
+-- that were never executed read as expired.
+
+CREATE TABLE IF NOT EXISTS admin_approvals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- market_resolution | trade_bust | trade_adjust
+    action VARCHAR(32) NOT NULL,
+    -- Market or trade the action applies to
+    target_id UUID NOT NULL,
+    -- Request body the action executes with
+    params JSONB NOT NULL DEFAULT '{}',
+    reason TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    proposed_by VARCHAR(42) NOT NULL,
+    approved_by VARCHAR(42),
+    approved_at TIMESTAMPTZ,
+    executed_by VARCHAR(42),
+    executed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT admin_approvals_distinct_approver CHECK (approved_by IS NULL OR approved_by <> proposed_by)
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_approvals_open ON admin_approvals(expires_at)
+    WHERE status IN ('pending', 'approved');
+CREATE INDEX IF NOT EXISTS idx_admin_approvals_target ON admin_approvals(target_id, created_at DESC);
@@ -0,0 +1,153 @@
+-- One-minute probability candles per outcome, maintained by trigger, and the
+-- 1h/24h time-weighted probabilities derived from them.
+--
+-- Prices are the outcome's Yes probability (No trades count as 1 - price)
+-- at the trade's effective price; busted trades are left out. A new trade
+-- updates its candle in place; a bust or re-price rebuilds the candle from
+-- its trades. twap_1h/twap_24h on outcomes are recomputed from the candles
+-- by the market summary refresh job.
+
+CREATE TABLE IF NOT EXISTS outcome_price_candles (
+    outcome_id UUID NOT NULL REFERENCES outcomes(id) ON DELETE CASCADE,
+    bucket TIMESTAMPTZ NOT NULL,
+    open NUMERIC(36, 18) NOT NULL,
+    high NUMERIC(36, 18) NOT NULL,
+    low NUMERIC(36, 18) NOT NULL,
+    close NUMERIC(36, 18) NOT NULL,
+    -- Times of the trades that set open and close
+    open_at TIMESTAMPTZ NOT NULL,
+    close_at TIMESTAMPTZ NOT NULL,
+    volume NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    trade_count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (outcome_id, bucket)
+);
+
+CREATE INDEX IF NOT EXISTS idx_outcome_price_candles_bucket ON outcome_price_candles(bucket DESC);
+
+COMMENT ON TABLE outcome_price_candles IS 'Trigger-maintained 1m probability candles per outcome (TWAP source)';
+
+ALTER TABLE outcomes ADD COLUMN IF NOT EXISTS twap_1h NUMERIC(36, 18);
+ALTER TABLE outcomes ADD COLUMN IF NOT EXISTS twap_24h NUMERIC(36, 18);
+
+COMMENT ON COLUMN outcomes.twap_1h IS 'Time-weighted probability over the last hour';
+COMMENT ON COLUMN outcomes.twap_24h IS 'Time-weighted probability over the last 24 hours';
+
+CREATE OR REPLACE FUNCTION trade_outcome_probability(t trades)
+RETURNS NUMERIC AS $$
+    SELECT CASE
+        WHEN t.share_type = 'no' THEN 1 - COALESCE(t.adjusted_price, t.price)
+        ELSE COALESCE(t.adjusted_price, t.price)
+    END;
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Rebuild one candle from its trades; removes it when none are left
+CREATE OR REPLACE FUNCTION rebuild_outcome_price_candle(p_outcome_id UUID, p_bucket TIMESTAMPTZ)
+RETURNS VOID AS $$
+BEGIN
+    DELETE FROM outcome_price_candles WHERE outcome_id = p_outcome_id AND bucket = p_bucket;
+    INSERT INTO outcome_price_candles (outcome_id, bucket, open, high, low, close, open_at, close_at, volume, trade_count)
+    SELECT p_outcome_id, p_bucket,
+           (array_agg(trade_outcome_probability(t) ORDER BY t.created_at, t.id))[1],
+           MAX(trade_outcome_probability(t)),
+           MIN(trade_outcome_probability(t)),
+           (array_agg(trade_outcome_probability(t) ORDER BY t.created_at DESC, t.id DESC))[1],
+           MIN(t.created_at),
+           MAX(t.created_at),
+           SUM(trade_effective_notional(t)),
+           COUNT(*)
+    FROM trades t
+    WHERE t.outcome_id = p_outcome_id
+      AND t.created_at >= p_bucket AND t.created_at < p_bucket + INTERVAL '1 minute'
+      AND t.adjustment_status IS DISTINCT FROM 'busted'
+      AND EXISTS (SELECT 1 FROM outcomes WHERE id = p_outcome_id)
+    HAVING COUNT(*) > 0;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION maintain_outcome_price_candles()
+RETURNS TRIGGER AS $$
+DECLARE
+    price NUMERIC;
+BEGIN
+    IF NEW.outcome_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'UPDATE' THEN
+        PERFORM rebuild_outcome_price_candle(NEW.outcome_id, date_trunc('minute', NEW.created_at));
+        RETURN NULL;
+    END IF;
+
+    -- Trades of unknown outcomes are ignored rather than failing the insert
+    IF NEW.adjustment_status = 'busted' OR NOT EXISTS (SELECT 1 FROM outcomes WHERE id = NEW.outcome_id) THEN
+        RETURN NULL;
+    END IF;
+
+    price := trade_outcome_probability(NEW);
+    INSERT INTO outcome_price_candles AS c (outcome_id, bucket, open, high, low, close, open_at, close_at, volume, trade_count)
+    VALUES (NEW.outcome_id, date_trunc('minute', NEW.created_at), price, price, price, price, NEW.created_at,
+            NEW.created_at, trade_effective_notional(NEW), 1)
+    ON CONFLICT (outcome_id, bucket) DO UPDATE SET
+        open = CASE WHEN NEW.created_at < c.open_at THEN price ELSE c.open END,
+        high = GREATEST(c.high, price),
+        low = LEAST(c.low, price),
+        close = CASE WHEN NEW.created_at >= c.close_at THEN price ELSE c.close END,
+        open_at = LEAST(c.open_at, NEW.created_at),
+        close_at = GREATEST(c.close_at, NEW.created_at),
+        volume = c.volume + EXCLUDED.volume,
+        trade_count = c.trade_count + 1;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trades_outcome_price_candles_insert ON trades;
+CREATE TRIGGER trades_outcome_price_candles_insert
+    AFTER INSERT ON trades
+    FOR EACH ROW EXECUTE FUNCTION maintain_outcome_price_candles();
+
+DROP TRIGGER IF EXISTS trades_outcome_price_candles_adjust ON trades;
+CREATE TRIGGER trades_outcome_price_candles_adjust
+    AFTER UPDATE OF adjustment_status, adjusted_price ON trades
+    FOR EACH ROW EXECUTE FUNCTION maintain_outcome_price_candles();
+
+-- Listing outcomes carry the TWAPs; refresh them when they change
+CREATE OR REPLACE FUNCTION market_outcomes_json(p_market_id UUID)
+RETURNS JSONB AS $$
+    SELECT COALESCE(
+        jsonb_agg(jsonb_build_object(
+            'id', id,
+            'name', name,
+            'probability', probability::text,
+            'twap_1h', twap_1h::text,
+            'twap_24h', twap_24h::text
+        ) ORDER BY name),
+        '[]'::jsonb
+    )
+    FROM outcomes
+    WHERE market_id = p_market_id;
+$$ LANGUAGE sql STABLE;
+
+DROP TRIGGER IF EXISTS outcomes_market_summary ON outcomes;
+CREATE TRIGGER outcomes_market_summary
+    AFTER INSERT OR DELETE OR UPDATE OF name, probability, twap_1h, twap_24h ON outcomes
+    FOR EACH ROW EXECUTE FUNCTION maintain_market_summary_outcomes();
+
+-- Backfill candles from existing trades
+INSERT INTO outcome_price_candles (outcome_id, bucket, open, high, low, close, open_at, close_at, volume, trade_count)
+SELECT t.outcome_id, date_trunc('minute', t.created_at),
+       (array_agg(trade_outcome_probability(t) ORDER BY t.created_at, t.id))[1],
+       MAX(trade_outcome_probability(t)),
+       MIN(trade_outcome_probability(t)),
+       (array_agg(trade_outcome_probability(t) ORDER BY t.created_at DESC, t.id DESC))[1],
+       MIN(t.created_at),
+       MAX(t.created_at),
+       SUM(trade_effective_notional(t)),
+       COUNT(*)
+FROM trades t
+JOIN outcomes o ON o.id = t.outcome_id
+WHERE t.adjustment_status IS DISTINCT FROM 'busted'
+GROUP BY t.outcome_id, date_trunc('minute', t.created_at)
+ON CONFLICT (outcome_id, bucket) DO NOTHING;
+
+UPDATE market_summaries SET outcomes = market_outcomes_json(market_id);
@@ -0,0 +1,33 @@
+-- Content address of each market's resolution criteria, pinned to IPFS when
+-- the market goes live. Once a CID is recorded the pinned fields can no
+-- longer change, so the CID keeps describing the market.
+
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS criteria_cid TEXT;
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS criteria_sha256 VARCHAR(64);
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS criteria_pinned_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_markets_criteria_unpinned ON markets(created_at) WHERE criteria_cid IS NULL;
+
+COMMENT ON COLUMN markets.criteria_cid IS 'IPFS CID of the pinned criteria document (question, description, resolution source, end time, outcomes)';
+COMMENT ON COLUMN markets.criteria_sha256 IS 'SHA-256 of the pinned criteria document';
+
+CREATE OR REPLACE FUNCTION protect_market_criteria() RETURNS trigger AS $$
+BEGIN
+    IF OLD.criteria_cid IS NOT NULL AND (
+        (NEW.question, NEW.description, NEW.resolution_source, NEW.end_time, NEW.condition_id,
+         NEW.criteria_cid, NEW.criteria_sha256)
+        IS DISTINCT FROM
+        (OLD.question, OLD.description, OLD.resolution_source, OLD.end_time, OLD.condition_id,
+         OLD.criteria_cid, OLD.criteria_sha256)
+    ) THEN
+        RAISE EXCEPTION 'criteria of market % are pinned at % and cannot change', OLD.id, OLD.criteria_cid
+            USING ERRCODE = 'check_violation';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_markets_criteria_immutable ON markets;
+CREATE TRIGGER trg_markets_criteria_immutable
+    BEFORE UPDATE ON markets
+    FOR EACH ROW EXECUTE FUNCTION protect_market_criteria();
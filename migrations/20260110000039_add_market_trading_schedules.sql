@@ -0,0 +1,14 @@
+-- Trading hours of markets that do not trade whenever they are active:
+-- weekly sessions in the market's timezone plus one-off halts. Markets
+-- without a row trade around the clock.
+
+CREATE TABLE IF NOT EXISTS market_trading_schedules (
+    market_id UUID PRIMARY KEY REFERENCES markets(id) ON DELETE CASCADE,
+    -- IANA timezone the session times are in
+    timezone TEXT NOT NULL,
+    -- [{"days": ["Mon", ...], "open": "09:30:00", "close": "16:00:00"}]
+    sessions JSONB NOT NULL DEFAULT '[]'::jsonb,
+    -- [{"starts_at": <ms>, "ends_at": <ms>, "reason": "..."}]
+    halts JSONB NOT NULL DEFAULT '[]'::jsonb,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,12 @@
+-- Account closure. A closing account can only read, cancel and withdraw;
+-- once it holds nothing its personal data is erased and it is closed.
+-- Orders, trades, the ledger, deposits and withdrawals are kept under the
+-- address as financial records.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS closure_requested_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS closed_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_users_closing ON users(closure_requested_at) WHERE closure_requested_at IS NOT NULL AND closed_at IS NULL;
+
+COMMENT ON COLUMN users.closure_requested_at IS 'When the holder asked to close the account; new activity is refused from then on';
+COMMENT ON COLUMN users.closed_at IS 'When the account was closed and its personal data erased';
@@ -0,0 +1,29 @@
+-- Stop and stop-limit orders for prediction markets. The trigger order
+-- table was created for perpetual markets keyed by symbol; trigger orders
+-- now target a market key (market, outcome, share type), watch either its
+-- last trade or its midpoint, and record the order placed when they fire
+-- in executed_order_id.
+
+ALTER TYPE trigger_order_type ADD VALUE IF NOT EXISTS 'stop' BEFORE 'stop_limit';
+
+DO $$ BEGIN
+    CREATE TYPE trigger_price_source AS ENUM ('last_trade', 'midpoint');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TABLE trigger_orders ALTER COLUMN market_symbol DROP NOT NULL;
+ALTER TABLE trigger_orders ALTER COLUMN reduce_only SET DEFAULT false;
+ALTER TABLE trigger_orders ADD COLUMN IF NOT EXISTS market_id UUID REFERENCES markets(id);
+ALTER TABLE trigger_orders ADD COLUMN IF NOT EXISTS outcome_id UUID;
+ALTER TABLE trigger_orders ADD COLUMN IF NOT EXISTS share_type share_type;
+ALTER TABLE trigger_orders ADD COLUMN IF NOT EXISTS order_type order_type NOT NULL DEFAULT 'limit';
+ALTER TABLE trigger_orders ADD COLUMN IF NOT EXISTS price_source trigger_price_source NOT NULL DEFAULT 'last_trade';
+
+CREATE INDEX IF NOT EXISTS idx_trigger_orders_active_key
+    ON trigger_orders(market_id, outcome_id, share_type, price_source)
+    WHERE status = 'active';
+
+COMMENT ON COLUMN trigger_orders.limit_price IS 'Limit price of the placed order; the worst acceptable price for market orders';
+COMMENT ON COLUMN trigger_orders.price_source IS 'Price watched: the last trade or the midpoint of the market key';
+COMMENT ON COLUMN trigger_orders.executed_order_id IS 'Order placed when the trigger fired';
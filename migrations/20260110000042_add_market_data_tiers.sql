@@ -0,0 +1,10 @@
+-- Market data entitlements. Anonymous WebSocket connections get 1s
+-- conflated market data; accounts get real-time data, and market makers
+-- are granted the orderbook delta feed. Support can also hold an account
+-- to delayed data.
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS market_data_tier VARCHAR(16) NOT NULL DEFAULT 'real_time'
+        CHECK (market_data_tier IN ('delayed', 'real_time', 'delta'));
+
+COMMENT ON COLUMN users.market_data_tier IS 'Market data entitlement of the account: delayed, real_time or delta (market makers)';
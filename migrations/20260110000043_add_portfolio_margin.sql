@@ -0,0 +1,19 @@
+-- Portfolio margining. A Yes and a No share of one outcome always merge
+-- into 1 collateral, so buys offset by the other side - held shares or a
+-- resting opposite buy - have bounded loss and need less frozen. The
+-- reduction is kept per account and outcome as a credit against the
+-- collateral frozen for the account's buys.
+
+CREATE TABLE IF NOT EXISTS portfolio_margin_credits (
+    user_address VARCHAR(42) NOT NULL,
+    market_id UUID NOT NULL REFERENCES markets(id),
+    outcome_id UUID NOT NULL,
+    credit DECIMAL(36, 18) NOT NULL DEFAULT 0 CHECK (credit >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_address, outcome_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_portfolio_margin_credits_open
+    ON portfolio_margin_credits(user_address) WHERE credit > 0;
+
+COMMENT ON COLUMN portfolio_margin_credits.credit IS 'Collateral not frozen for the account''s buys on the outcome thanks to offsetting positions and orders';
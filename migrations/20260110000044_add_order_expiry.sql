@@ -0,0 +1,10 @@
+-- Good-til-date orders. A resting order with an expiry is taken off the
+-- book and marked 'expired' once it passes; resting orders of a market
+-- whose end_time passes are expired the same way.
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_orders_expires_at
+    ON orders(expires_at) WHERE expires_at IS NOT NULL AND status IN ('open', 'partially_filled');
+
+COMMENT ON COLUMN orders.expires_at IS 'When the unfilled remainder of a good-til-date order expires; NULL rests until cancelled';
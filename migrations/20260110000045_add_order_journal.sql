@@ -0,0 +1,27 @@
+-- Append-only journal of changes to resting orders, written from the
+-- matching engine in the order they happened. Startup recovery replays the
+-- latest entry of each order to rebuild the books exactly (remaining size,
+-- price and queue priority) without matching. Entries of orders that left
+-- the book are compacted away.
+
+CREATE TABLE IF NOT EXISTS order_journal (
+    seq BIGSERIAL PRIMARY KEY,
+    order_id UUID NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    symbol VARCHAR(200) NOT NULL,
+    user_address VARCHAR(42) NOT NULL,
+    side VARCHAR(10) NOT NULL,
+    price DECIMAL(20, 8) NOT NULL,
+    original_amount DECIMAL(30, 8) NOT NULL,
+    remaining_amount DECIMAL(30, 8) NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    priority BIGINT NOT NULL,
+    trade_id UUID,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN order_journal.kind IS 'accepted, fill, cancelled, amended or expired';
+COMMENT ON COLUMN order_journal.status IS 'Order status after the change; open and partially_filled orders rest';
+COMMENT ON COLUMN order_journal.priority IS 'Book timestamp (ms) the order queues by at its price';
+
+CREATE INDEX IF NOT EXISTS idx_order_journal_order ON order_journal(order_id, seq DESC);
@@ -0,0 +1,22 @@
+-- Per-account API usage, counted by the request middleware and flushed
+-- every few seconds: requests and bytes received and sent per UTC day.
+-- Feeds GET /account/api-usage and the daily quotas.
+
+CREATE TABLE IF NOT EXISTS api_usage (
+    user_address VARCHAR(42) NOT NULL,
+    day DATE NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    bytes_in BIGINT NOT NULL DEFAULT 0,
+    bytes_out BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_address, day)
+);
+
+-- Daily quotas of an account; NULL uses the platform default
+-- (API_DAILY_REQUEST_QUOTA / API_DAILY_BYTE_QUOTA), 0 is unlimited
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS api_daily_request_quota BIGINT CHECK (api_daily_request_quota >= 0),
+    ADD COLUMN IF NOT EXISTS api_daily_byte_quota BIGINT CHECK (api_daily_byte_quota >= 0);
+
+COMMENT ON COLUMN users.api_daily_request_quota IS 'Authenticated API requests per UTC day; NULL = platform default, 0 = unlimited';
+COMMENT ON COLUMN users.api_daily_byte_quota IS 'Authenticated API response bytes per UTC day; NULL = platform default, 0 = unlimited';
@@ -0,0 +1,30 @@
+-- Market maker protections. When an account's fills in one market exceed
+-- max_fills_per_second, or its fills over the last minute lose more than
+-- max_loss_per_minute marked at the latest trade prices, its resting
+-- orders in that market are cancelled and the account is notified.
+
+CREATE TABLE IF NOT EXISTS mm_protections (
+    user_address VARCHAR(42) PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    max_fills_per_second INTEGER CHECK (max_fills_per_second > 0),
+    max_loss_per_minute DECIMAL(36, 18) CHECK (max_loss_per_minute > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS mm_protection_triggers (
+    id BIGSERIAL PRIMARY KEY,
+    user_address VARCHAR(42) NOT NULL,
+    market_id UUID NOT NULL,
+    reason VARCHAR(20) NOT NULL,
+    fills_last_second INTEGER NOT NULL,
+    loss_last_minute DECIMAL(36, 18) NOT NULL,
+    cancelled_orders INTEGER NOT NULL,
+    triggered_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_mm_protection_triggers_user
+    ON mm_protection_triggers(user_address, triggered_at DESC);
+
+COMMENT ON COLUMN mm_protections.max_fills_per_second IS 'Maker fills per market within one second that pull the quotes; NULL = no limit';
+COMMENT ON COLUMN mm_protections.max_loss_per_minute IS 'Mark-to-market loss of the last minute of maker fills per market that pulls the quotes; NULL = no limit';
+COMMENT ON COLUMN mm_protection_triggers.reason IS 'fill_rate, loss';
@@ -0,0 +1,14 @@
+-- Daily operations reports: one summary per UTC day (volume, fees, new
+-- users, deposits and withdrawals, settlement failures, largest
+-- positions), generated by the workers' leader shortly after the day ends
+-- and served on GET /admin/reports.
+
+CREATE TABLE IF NOT EXISTS ops_reports (
+    report_date DATE PRIMARY KEY,
+    summary JSONB NOT NULL,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set once the report went out to OPS_REPORT_EMAILS
+    emailed_at TIMESTAMPTZ
+);
+
+COMMENT ON COLUMN ops_reports.summary IS 'Report sections as returned by GET /admin/reports/:date';
@@ -0,0 +1,18 @@
+-- Mirror No-set conversions on chain through the NegRiskAdapter. A group
+-- whose markets were prepared as the questions of a NegRiskAdapter market
+-- records that market's id; each conversion in the group is then sent as a
+-- convertPositions call over all of its questions and tracked here.
+
+ALTER TABLE market_groups ADD COLUMN IF NOT EXISTS neg_risk_market_id VARCHAR(66);
+
+ALTER TABLE neg_risk_conversions
+    ADD COLUMN IF NOT EXISTS chain_status VARCHAR(20) NOT NULL DEFAULT 'off_chain',
+    ADD COLUMN IF NOT EXISTS tx_hash VARCHAR(66),
+    ADD COLUMN IF NOT EXISTS chain_error TEXT,
+    ADD COLUMN IF NOT EXISTS submitted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_neg_risk_conversions_unsubmitted
+    ON neg_risk_conversions(created_at) WHERE chain_status IN ('pending', 'failed');
+
+COMMENT ON COLUMN market_groups.neg_risk_market_id IS 'NegRiskAdapter market id (bytes32 hex) whose questions are the group''s markets';
+COMMENT ON COLUMN neg_risk_conversions.chain_status IS 'off_chain (ledger only), pending, confirmed or failed';
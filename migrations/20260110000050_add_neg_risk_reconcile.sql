@@ -0,0 +1,9 @@
+-- No-set conversions are claimed ('submitting') before convertPositions is
+-- sent and keep their transaction hash while the receipt is outstanding
+-- ('reconcile'), so a retry never sends a conversion twice. The submitter
+-- picks up pending and reconcile rows.
+
+CREATE INDEX IF NOT EXISTS idx_neg_risk_conversions_outstanding
+    ON neg_risk_conversions(created_at) WHERE chain_status IN ('pending', 'reconcile');
+
+COMMENT ON COLUMN neg_risk_conversions.chain_status IS 'off_chain (ledger only), pending, submitting, reconcile (sent, awaiting receipt), confirmed or failed';
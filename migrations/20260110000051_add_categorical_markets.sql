@@ -0,0 +1,61 @@
+-- Categorical markets: N outcomes, each traded as its own Yes book. One Yes
+-- share of every outcome is a complete set worth 1, so the engine mints sets
+-- from buys across all outcomes and merges them from sells. A set fill pairs
+-- the taker with one maker per other outcome and is stored apart from the
+-- two-party trades.
+
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS market_type VARCHAR(20) NOT NULL DEFAULT 'binary';
+ALTER TABLE markets DROP CONSTRAINT IF EXISTS markets_market_type_check;
+ALTER TABLE markets ADD CONSTRAINT markets_market_type_check CHECK (market_type IN ('binary', 'categorical'));
+
+-- A binary market keeps one Yes and one No outcome (index 0); a categorical
+-- market holds one Yes outcome per index
+ALTER TABLE outcomes ADD COLUMN IF NOT EXISTS outcome_index SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE outcomes DROP CONSTRAINT IF EXISTS outcomes_market_id_share_type_key;
+ALTER TABLE outcomes DROP CONSTRAINT IF EXISTS outcomes_market_id_share_type_index_key;
+ALTER TABLE outcomes ADD CONSTRAINT outcomes_market_id_share_type_index_key UNIQUE (market_id, share_type, outcome_index);
+
+-- Sets minted and merged count towards each outcome's Yes supply only
+ALTER TABLE outcome_share_supply
+    ADD COLUMN IF NOT EXISTS set_minted DECIMAL(30, 8) NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS set_merged DECIMAL(30, 8) NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS set_fills (
+    id UUID PRIMARY KEY,
+    market_id UUID NOT NULL REFERENCES markets(id),
+    match_type match_type NOT NULL,
+    taker_order_id UUID NOT NULL,
+    taker_address VARCHAR(42) NOT NULL,
+    taker_outcome_id UUID NOT NULL REFERENCES outcomes(id),
+    price DECIMAL(30, 8) NOT NULL,
+    amount DECIMAL(30, 8) NOT NULL,
+    taker_fee DECIMAL(30, 8) NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS set_fill_legs (
+    set_fill_id UUID NOT NULL REFERENCES set_fills(id) ON DELETE CASCADE,
+    leg_index SMALLINT NOT NULL,
+    outcome_id UUID NOT NULL REFERENCES outcomes(id),
+    maker_order_id UUID NOT NULL,
+    maker_address VARCHAR(42) NOT NULL,
+    price DECIMAL(30, 8) NOT NULL,
+    maker_fee DECIMAL(30, 8) NOT NULL DEFAULT 0,
+    PRIMARY KEY (set_fill_id, leg_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_set_fills_market ON set_fills(market_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_set_fills_taker_order ON set_fills(taker_order_id);
+CREATE INDEX IF NOT EXISTS idx_set_fill_legs_maker_order ON set_fill_legs(maker_order_id);
+
+-- Failed set fill writes are retried through the trade persist queue
+ALTER TABLE trade_persist_queue ADD COLUMN IF NOT EXISTS kind VARCHAR(20) NOT NULL DEFAULT 'trade';
+
+COMMENT ON COLUMN markets.market_type IS 'binary (one Yes/No pair) or categorical (one Yes outcome per answer)';
+COMMENT ON COLUMN outcomes.outcome_index IS 'Position of the outcome among a categorical market''s answers; 0 for binary markets';
+COMMENT ON COLUMN outcome_share_supply.set_minted IS 'Complete sets of a categorical market minted, counted on each of its outcomes';
+COMMENT ON COLUMN outcome_share_supply.set_merged IS 'Complete sets of a categorical market merged, counted on each of its outcomes';
+COMMENT ON TABLE set_fills IS 'Complete sets minted or merged across the outcomes of a categorical market';
+COMMENT ON TABLE set_fill_legs IS 'Maker of each other outcome in a set fill, at its own price';
+COMMENT ON COLUMN trade_persist_queue.kind IS 'trade (payload is a TradeEvent) or set_fill (payload is a SetFill)';
+COMMENT ON COLUMN trade_persist_queue.trade_id IS 'Trade id, or set id for set fills';
@@ -0,0 +1,8 @@
+//! Versioned API DTOs
+//!
+//! Shapes that differ between API versions live in the version's module.
+//! v1 handlers define their request and response types next to the handler;
+//! only the shapes v2 changes are restated in [`v1`].
+
+pub mod v1;
+pub mod v2;
@@ -0,0 +1,18 @@
+//! API v1 shapes replaced in v2
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::validation::FieldError;
+
+/// Error body returned by v1 handlers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub code: String,
+    /// Offending fields of a request that failed validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    /// Set on 429s from engine admission control
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
@@ -0,0 +1,33 @@
+//! API v2 shapes
+
+use serde::{Deserialize, Serialize};
+
+use super::v1;
+use crate::api::validation::FieldError;
+
+/// Error envelope: every v2 error response is `{"error": {...}}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorEnvelope {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    /// Machine-readable code, unchanged from v1
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl From<v1::ErrorResponse> for ErrorEnvelope {
+    fn from(e: v1::ErrorResponse) -> Self {
+        Self {
+            error: ErrorBody {
+                code: e.code,
+                message: e.error,
+                fields: e.fields,
+            },
+        }
+    }
+}
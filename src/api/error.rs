@@ -0,0 +1,324 @@
+//! HTTP Error Mapping
+//!
+//! [`AppError`] is where domain errors become HTTP responses. Matching
+//! engine, order flow, preferences, admin approval, personal data, trigger
+//! order, UMA oracle, cache, database and blockchain errors get their status
+//! code and machine-readable code here
+//! and are returned in the v1 error body (`{"error", "code"}`, wrapped into
+//! the envelope under `/api/v2`). Handlers return `Result<_, AppError>` and
+//! use `?` on domain errors instead of mapping each one by hand.
+//!
+//! Server-side failures are logged with their cause and answered with a
+//! generic message, so driver and RPC details don't reach clients.
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::api::dto::v1::ErrorResponse;
+use crate::api::validation::FieldError;
+use crate::cache::CacheError;
+use crate::services::admin_approval::ApprovalError;
+use crate::services::matching::{MatchingError, OrderFlowError, RejectReason};
+use crate::services::personal_data::PersonalDataError;
+use crate::services::preferences::PreferencesError;
+use crate::services::trigger_orders::TriggerOrderError;
+use crate::services::uma_oracle::UmaOracleError;
+
+/// Error type of the blockchain client
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// A refusal decided by the handler itself
+    #[error("{message}")]
+    Rejected {
+        status: StatusCode,
+        code: &'static str,
+        message: String,
+        fields: Vec<FieldError>,
+    },
+
+    #[error(transparent)]
+    Matching(#[from] MatchingError),
+
+    #[error(transparent)]
+    OrderFlow(#[from] OrderFlowError),
+
+    #[error(transparent)]
+    Cache(#[from] CacheError),
+
+    #[error(transparent)]
+    Preferences(#[from] PreferencesError),
+
+    #[error(transparent)]
+    Approval(#[from] ApprovalError),
+
+    #[error(transparent)]
+    PersonalData(#[from] PersonalDataError),
+
+    #[error(transparent)]
+    TriggerOrder(#[from] TriggerOrderError),
+
+    #[error(transparent)]
+    UmaOracle(#[from] UmaOracleError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Blockchain client not available")]
+    BlockchainUnavailable,
+
+    #[error("Blockchain error: {0}")]
+    Blockchain(#[from] BoxError),
+}
+
+impl AppError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        AppError::Rejected {
+            status,
+            code,
+            message: message.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Attach the offending fields of the request to a refusal
+    pub fn with_fields(mut self, offending: Vec<FieldError>) -> Self {
+        if let AppError::Rejected { fields, .. } = &mut self {
+            *fields = offending;
+        }
+        self
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+
+    pub fn forbidden(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, code, message)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Rejected { status, .. } => *status,
+            AppError::Matching(MatchingError::OrderNotFound(_)) => StatusCode::NOT_FOUND,
+            AppError::Matching(e) | AppError::OrderFlow(OrderFlowError::Engine(e)) => reject_status(e.reject_reason()),
+            AppError::OrderFlow(OrderFlowError::Database(e))
+            | AppError::Preferences(PreferencesError::Database(e))
+            | AppError::Approval(ApprovalError::Database(e))
+            | AppError::PersonalData(PersonalDataError::Database(e))
+            | AppError::TriggerOrder(TriggerOrderError::Database(e))
+            | AppError::UmaOracle(UmaOracleError::DatabaseError(e))
+            | AppError::Database(e) => database_status(e),
+            AppError::OrderFlow(e) => reject_status(e.reject_reason()),
+            AppError::Cache(CacheError::NotAvailable | CacheError::ConnectionError(_)) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            AppError::Cache(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Preferences(_) => StatusCode::BAD_REQUEST,
+            AppError::Approval(ApprovalError::NotFound) => StatusCode::NOT_FOUND,
+            AppError::Approval(ApprovalError::SelfApproval) => StatusCode::FORBIDDEN,
+            AppError::Approval(_) => StatusCode::CONFLICT,
+            AppError::PersonalData(PersonalDataError::UserNotFound) => StatusCode::NOT_FOUND,
+            AppError::PersonalData(PersonalDataError::AccountClosed) => StatusCode::CONFLICT,
+            AppError::PersonalData(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::TriggerOrder(TriggerOrderError::NotFound | TriggerOrderError::MarketNotFound) => {
+                StatusCode::NOT_FOUND
+            }
+            AppError::TriggerOrder(TriggerOrderError::NotActive(_) | TriggerOrderError::MarketNotActive(_)) => {
+                StatusCode::CONFLICT
+            }
+            AppError::TriggerOrder(TriggerOrderError::TooManyActive(_)) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::TriggerOrder(_) => StatusCode::BAD_REQUEST,
+            AppError::UmaOracle(UmaOracleError::MarketNotFound(_) | UmaOracleError::AssertionNotFound(_)) => {
+                StatusCode::NOT_FOUND
+            }
+            AppError::UmaOracle(UmaOracleError::PendingAssertionExists | UmaOracleError::AlreadyResolved) => {
+                StatusCode::CONFLICT
+            }
+            AppError::UmaOracle(UmaOracleError::NotReadyForSettlement) => StatusCode::PRECONDITION_FAILED,
+            AppError::UmaOracle(UmaOracleError::InvalidOutcome(_)) => StatusCode::BAD_REQUEST,
+            AppError::UmaOracle(_) => StatusCode::BAD_GATEWAY,
+            AppError::BlockchainUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Blockchain(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    /// Machine-readable code of the response body
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Rejected { code, .. } => code,
+            AppError::Matching(MatchingError::OrderNotFound(_)) => "ORDER_NOT_FOUND",
+            AppError::Matching(e) | AppError::OrderFlow(OrderFlowError::Engine(e)) => e.reject_reason().code(),
+            AppError::OrderFlow(OrderFlowError::Database(e))
+            | AppError::Preferences(PreferencesError::Database(e))
+            | AppError::Approval(ApprovalError::Database(e))
+            | AppError::PersonalData(PersonalDataError::Database(e))
+            | AppError::TriggerOrder(TriggerOrderError::Database(e))
+            | AppError::UmaOracle(UmaOracleError::DatabaseError(e))
+            | AppError::Database(e) => match e {
+                sqlx::Error::RowNotFound => "NOT_FOUND",
+                sqlx::Error::PoolTimedOut => "DB_UNAVAILABLE",
+                _ => "DB_ERROR",
+            },
+            AppError::OrderFlow(e) => e.reject_reason().code(),
+            AppError::Cache(CacheError::NotAvailable | CacheError::ConnectionError(_)) => "CACHE_UNAVAILABLE",
+            AppError::Cache(_) => "CACHE_ERROR",
+            AppError::Preferences(e) => e.code(),
+            AppError::Approval(e) => e.code(),
+            AppError::PersonalData(e) => e.code(),
+            AppError::TriggerOrder(e) => e.code(),
+            AppError::UmaOracle(e) => e.code(),
+            AppError::BlockchainUnavailable => "NO_BLOCKCHAIN",
+            AppError::Blockchain(_) => "BLOCKCHAIN_ERROR",
+        }
+    }
+
+    /// Message of the response body; server-side failures get a generic one
+    fn message(&self) -> String {
+        match self {
+            AppError::Rejected { message, .. } => message.clone(),
+            AppError::Database(sqlx::Error::RowNotFound) => "Not found".to_string(),
+            AppError::OrderFlow(OrderFlowError::Database(_))
+            | AppError::Preferences(PreferencesError::Database(_))
+            | AppError::Approval(ApprovalError::Database(_))
+            | AppError::PersonalData(PersonalDataError::Database(_))
+            | AppError::TriggerOrder(TriggerOrderError::Database(_))
+            | AppError::UmaOracle(UmaOracleError::DatabaseError(_))
+            | AppError::Database(_) => "Database error".to_string(),
+            AppError::Cache(_) if self.status() == StatusCode::SERVICE_UNAVAILABLE => "Cache unavailable".to_string(),
+            AppError::Cache(_) => "Cache error".to_string(),
+            AppError::Blockchain(_) => "Blockchain request failed".to_string(),
+            AppError::UmaOracle(UmaOracleError::BlockchainError(_) | UmaOracleError::ContractError(_)) => {
+                "Oracle request failed".to_string()
+            }
+            _ if self.status().is_server_error() && self.status() != StatusCode::SERVICE_UNAVAILABLE => {
+                "Internal error".to_string()
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    fn retry_after_ms(&self) -> Option<u64> {
+        match self {
+            AppError::Matching(e) | AppError::OrderFlow(OrderFlowError::Engine(e)) => e.retry_after_ms(),
+            _ => None,
+        }
+    }
+
+    /// v1 body of the response; also the per-item error of batch results
+    pub(crate) fn body(&self) -> ErrorResponse {
+        ErrorResponse {
+            error: self.message(),
+            code: self.code().to_string(),
+            fields: match self {
+                AppError::Rejected { fields, .. } => fields.clone(),
+                _ => Vec::new(),
+            },
+            retry_after_ms: self.retry_after_ms(),
+        }
+    }
+}
+
+/// Status of a refusal with `reason`; shared with handlers that keep their
+/// own error bodies
+pub(crate) fn reject_status(reason: RejectReason) -> StatusCode {
+    match reason {
+        RejectReason::MarketNotFound => StatusCode::NOT_FOUND,
+        RejectReason::FeatureDisabled => StatusCode::FORBIDDEN,
+        RejectReason::SettlementMode => StatusCode::CONFLICT,
+        RejectReason::Overloaded => StatusCode::TOO_MANY_REQUESTS,
+        RejectReason::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
+
+fn database_status(e: &sqlx::Error) -> StatusCode {
+    match e {
+        sqlx::Error::RowNotFound => StatusCode::NOT_FOUND,
+        sqlx::Error::PoolTimedOut => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!("{} ({}): {}", status, self.code(), self);
+        }
+        let mut response = (status, Json(self.body())).into_response();
+        if let Some(ms) = self.retry_after_ms() {
+            // Retry-After is in whole seconds
+            if let Ok(value) = HeaderValue::from_str(&ms.div_ceil(1000).to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body(response: Response) -> ErrorResponse {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_overloaded_engine_is_429_with_retry_after() {
+        let response = AppError::from(MatchingError::Overloaded {
+            shard: 0,
+            retry_after_ms: 1500,
+        })
+        .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+        let body = body(response).await;
+        assert_eq!((body.code.as_str(), body.retry_after_ms), ("OVERLOADED", Some(1500)));
+    }
+
+    #[test]
+    fn test_domain_errors_map_to_status() {
+        let cases: Vec<(AppError, StatusCode, &str)> = vec![
+            (sqlx::Error::RowNotFound.into(), StatusCode::NOT_FOUND, "NOT_FOUND"),
+            (sqlx::Error::PoolTimedOut.into(), StatusCode::SERVICE_UNAVAILABLE, "DB_UNAVAILABLE"),
+            (CacheError::NotAvailable.into(), StatusCode::SERVICE_UNAVAILABLE, "CACHE_UNAVAILABLE"),
+            (
+                MatchingError::MarketNotFound("m".to_string()).into(),
+                StatusCode::NOT_FOUND,
+                "MARKET_NOT_FOUND",
+            ),
+            (OrderFlowError::InvalidAmount.into(), StatusCode::BAD_REQUEST, "INVALID_AMOUNT"),
+            (AppError::not_found("USER_NOT_FOUND", "User not found"), StatusCode::NOT_FOUND, "USER_NOT_FOUND"),
+            (PersonalDataError::AccountClosed.into(), StatusCode::CONFLICT, "ACCOUNT_CLOSED"),
+            (TriggerOrderError::TooManyActive(20).into(), StatusCode::TOO_MANY_REQUESTS, "TOO_MANY_TRIGGER_ORDERS"),
+            (
+                UmaOracleError::NotReadyForSettlement.into(),
+                StatusCode::PRECONDITION_FAILED,
+                "NOT_READY_FOR_SETTLEMENT",
+            ),
+        ];
+        for (error, status, code) in cases {
+            assert_eq!((error.status(), error.code()), (status, code), "{}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_hide_their_cause() {
+        let rpc: BoxError = "connection refused to 10.0.0.5:8545".into();
+        let response = AppError::from(rpc).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let body = body(response).await;
+        assert_eq!((body.error.as_str(), body.code.as_str()), ("Blockchain request failed", "BLOCKCHAIN_ERROR"));
+    }
+}
@@ -1,22 +1,38 @@
 //! Account API Handlers for Prediction Markets
 //!
-//! Provides endpoints for user profile, balances, shares, orders, and trades.
+//! Provides endpoints for user profile, balances, shares, orders, trades,
+//! fees and API usage.
 
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     Extension, Json,
 };
 use chrono::{DateTime, Utc};
+use ethers::types::U256;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::handlers::market_maker::{self, FeeTier};
+use crate::api::perspective::{self, Perspective, PerspectiveView};
+use crate::api::validation::{self, ValidQuery};
 use crate::auth::middleware::AuthUser;
 use crate::models::market::ShareType;
 use crate::models::{BalanceResponse, UserProfile};
+use crate::services::api_usage::{DailyUsage, QuotaCheck};
+use crate::services::ctf_position;
+use crate::services::execution_stats::{self, ExecutionStats};
+use crate::services::fee_summary;
+use crate::services::notification::NotificationKind;
+use crate::services::payout_preview::{self, PayoutPreview, PreviewError};
+use crate::services::portfolio_risk::{self, RiskReport};
+use crate::services::preferences;
 use crate::services::settlement::{SettlementService, SettlementError};
+use crate::services::settlement_mode::{self, SettlementMode};
 use crate::AppState;
 
 // ============================================================================
@@ -39,12 +55,6 @@ mod datetime_as_millis {
 // Response Types
 // ============================================================================
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: String,
-}
-
 #[derive(Debug, Serialize)]
 pub struct BalancesResponse {
     pub balances: Vec<BalanceResponse>,
@@ -63,6 +73,12 @@ pub struct ShareDetail {
     pub unrealized_pnl: Decimal,
     pub market_question: Option<String>,
     pub outcome_name: Option<String>,
+    /// "internal" for shares held by the exchange, "wallet" for ERC-1155
+    /// positions in a self-custody user's wallet
+    pub custody: &'static str,
+    /// A No position restated as a short Yes position (`perspective=yes`):
+    /// negative amount, Yes prices
+    pub complement: bool,
     #[serde(serialize_with = "datetime_as_millis::serialize")]
     pub created_at: DateTime<Utc>,
     #[serde(serialize_with = "datetime_as_millis::serialize")]
@@ -71,6 +87,7 @@ pub struct ShareDetail {
 
 #[derive(Debug, Serialize)]
 pub struct SharesResponse {
+    pub settlement_mode: SettlementMode,
     pub shares: Vec<ShareDetail>,
     pub total_value: Decimal,
     pub total_cost: Decimal,
@@ -90,6 +107,8 @@ pub struct OrderDetail {
     pub amount: Decimal,
     pub filled_amount: Decimal,
     pub status: String,
+    /// A No order restated as the equivalent Yes order (`perspective=yes`)
+    pub complement: bool,
     #[serde(serialize_with = "datetime_as_millis::serialize")]
     pub created_at: DateTime<Utc>,
     #[serde(serialize_with = "datetime_as_millis::serialize")]
@@ -102,6 +121,31 @@ pub struct OrdersResponse {
     pub total: i64,
 }
 
+/// Open orders in one market
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MarketOrderSummary {
+    pub market_id: Uuid,
+    pub market_question: Option<String>,
+    pub open_orders: i32,
+    pub buy_orders: i32,
+    pub sell_orders: i32,
+    /// Collateral reserved by open buys
+    pub buy_notional: Decimal,
+    /// Shares reserved by open sells
+    pub sell_shares: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrdersSummaryResponse {
+    pub open_orders: i64,
+    pub buy_orders: i64,
+    pub sell_orders: i64,
+    pub buy_notional: Decimal,
+    /// Frozen collateral balance
+    pub frozen_collateral: Decimal,
+    pub markets: Vec<MarketOrderSummary>,
+}
+
 /// Trade record for prediction markets
 #[derive(Debug, Serialize)]
 pub struct TradeRecord {
@@ -113,6 +157,8 @@ pub struct TradeRecord {
     pub price: Decimal,
     pub amount: Decimal,
     pub fee: Decimal,
+    /// A No trade restated as the equivalent Yes trade (`perspective=yes`)
+    pub complement: bool,
     #[serde(serialize_with = "datetime_as_millis::serialize")]
     pub timestamp: DateTime<Utc>,
 }
@@ -123,30 +169,141 @@ pub struct TradesResponse {
     pub total: i64,
 }
 
+impl PerspectiveView for OrderDetail {
+    fn in_perspective(self, perspective: Perspective) -> Self {
+        if !perspective.restates(self.share_type) {
+            return self;
+        }
+        OrderDetail {
+            share_type: ShareType::Yes,
+            side: perspective::complement_side(&self.side),
+            price: perspective::complement_price(self.price),
+            complement: true,
+            ..self
+        }
+    }
+}
+
+impl PerspectiveView for TradeRecord {
+    fn in_perspective(self, perspective: Perspective) -> Self {
+        if !perspective.restates(self.share_type) {
+            return self;
+        }
+        TradeRecord {
+            share_type: ShareType::Yes,
+            side: perspective::complement_side(&self.side),
+            price: perspective::complement_price(self.price),
+            complement: true,
+            ..self
+        }
+    }
+}
+
+impl PerspectiveView for ShareDetail {
+    /// Holding N No shares is being short N Yes shares; the PnL is unchanged
+    fn in_perspective(self, perspective: Perspective) -> Self {
+        if !perspective.restates(self.share_type) {
+            return self;
+        }
+        ShareDetail {
+            share_type: ShareType::Yes,
+            amount: if self.amount.is_zero() { self.amount } else { -self.amount },
+            avg_cost: perspective::complement_price(self.avg_cost),
+            current_price: perspective::complement_price(self.current_price),
+            complement: true,
+            ..self
+        }
+    }
+}
+
+/// Month-to-date fees and rebates with fee tier progress
+#[derive(Debug, Serialize)]
+pub struct FeeSummaryResponse {
+    /// Start of the current month in the user's timezone
+    #[serde(serialize_with = "datetime_as_millis::serialize")]
+    pub period_start: DateTime<Utc>,
+    pub fees_paid: Decimal,
+    pub maker_fees: Decimal,
+    pub taker_fees: Decimal,
+    pub rebates_earned: Decimal,
+    pub maker_rebates: Decimal,
+    pub referral_commission: Decimal,
+    pub trade_count: i64,
+    pub volume_30d: Decimal,
+    pub current_tier: FeeTier,
+    pub next_tier: Option<FeeTier>,
+    pub volume_to_next_tier: Option<Decimal>,
+    /// Share of the way from the current tier's threshold to the next (0-1)
+    pub next_tier_progress: Option<Decimal>,
+}
+
+/// Today's API usage against the daily quotas, and usage by day
+#[derive(Debug, Serialize)]
+pub struct ApiUsageResponse {
+    #[serde(flatten)]
+    pub today: QuotaCheck,
+    pub warn_percent: u8,
+    /// Next UTC midnight, when the quotas reset
+    #[serde(serialize_with = "datetime_as_millis::serialize")]
+    pub resets_at: DateTime<Utc>,
+    /// Newest first
+    pub history: Vec<DailyUsage>,
+}
+
 // ============================================================================
 // Query Parameters
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct OrdersQuery {
     pub market_id: Option<Uuid>,
     pub status: Option<String>,
+    /// `yes` restates No orders as Yes orders
+    pub perspective: Option<Perspective>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
     pub limit: Option<i64>,
+    #[validate(range(min = 0))]
     pub offset: Option<i64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct TradesQuery {
     pub market_id: Option<Uuid>,
+    /// `yes` restates No trades as Yes trades
+    pub perspective: Option<Perspective>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
     pub limit: Option<i64>,
+    #[validate(range(min = 0))]
     pub offset: Option<i64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct SharesQuery {
     pub market_id: Option<Uuid>,
     /// Filter: only show non-zero positions
     pub active_only: Option<bool>,
+    /// `yes` restates No positions as short Yes positions
+    pub perspective: Option<Perspective>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PayoutPreviewQuery {
+    /// Only the scenario of the outcome with this share type
+    pub outcome: Option<ShareType>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApiUsageQuery {
+    /// Days of history (default 30, max 90)
+    #[validate(range(min = 1, max = 90))]
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ExecutionStatsQuery {
+    /// Window length in days (default 30, max 365)
+    #[validate(range(min = 1, max = 365))]
+    pub days: Option<i64>,
 }
 
 // ============================================================================
@@ -158,7 +315,7 @@ pub struct SharesQuery {
 pub async fn get_profile(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<UserProfile>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<UserProfile>, AppError> {
     let user: Option<UserProfile> = sqlx::query_as(
         r#"
         SELECT address, username, avatar_url, created_at, updated_at
@@ -166,29 +323,13 @@ pub async fn get_profile(
         WHERE address = $1
         "#,
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch user profile: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "获取用户资料失败".to_string(),
-                code: "PROFILE_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     match user {
         Some(profile) => Ok(Json(profile)),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "用户不存在".to_string(),
-                code: "USER_NOT_FOUND".to_string(),
-            }),
-        )),
+        None => Err(AppError::not_found("USER_NOT_FOUND", "用户不存在")),
     }
 }
 
@@ -197,7 +338,7 @@ pub async fn get_profile(
 pub async fn get_balances(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<BalancesResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<BalancesResponse>, AppError> {
     let rows: Vec<(String, Decimal, Decimal)> = sqlx::query_as(
         r#"
         SELECT token, available, frozen
@@ -206,19 +347,9 @@ pub async fn get_balances(
         ORDER BY token
         "#,
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch balances: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "获取余额失败".to_string(),
-                code: "BALANCE_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let balances: Vec<BalanceResponse> = rows
         .into_iter()
@@ -238,10 +369,11 @@ pub async fn get_balances(
 pub async fn get_orders(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Query(query): Query<OrdersQuery>,
-) -> Result<Json<OrdersResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidQuery(query): ValidQuery<OrdersQuery>,
+) -> Result<Json<OrdersResponse>, AppError> {
     let limit = query.limit.unwrap_or(50).min(100);
     let offset = query.offset.unwrap_or(0);
+    let perspective = query.perspective.unwrap_or_default();
 
     // Build query with optional filters
     let mut sql = String::from(
@@ -278,7 +410,7 @@ pub async fn get_orders(
         DateTime<Utc>,
     )> = if query.market_id.is_some() && query.status.is_some() {
         sqlx::query_as(&sql)
-            .bind(&auth_user.address.to_lowercase())
+            .bind(&auth_user.address)
             .bind(limit)
             .bind(offset)
             .bind(query.market_id.unwrap())
@@ -287,7 +419,7 @@ pub async fn get_orders(
             .await
     } else if query.market_id.is_some() {
         sqlx::query_as(&sql)
-            .bind(&auth_user.address.to_lowercase())
+            .bind(&auth_user.address)
             .bind(limit)
             .bind(offset)
             .bind(query.market_id.unwrap())
@@ -303,7 +435,7 @@ pub async fn get_orders(
             ORDER BY created_at DESC LIMIT $2 OFFSET $3
         "#;
         sqlx::query_as(sql)
-            .bind(&auth_user.address.to_lowercase())
+            .bind(&auth_user.address)
             .bind(limit)
             .bind(offset)
             .bind(query.status.as_ref().unwrap())
@@ -318,22 +450,12 @@ pub async fn get_orders(
             ORDER BY created_at DESC LIMIT $2 OFFSET $3
         "#;
         sqlx::query_as(sql)
-            .bind(&auth_user.address.to_lowercase())
+            .bind(&auth_user.address)
             .bind(limit)
             .bind(offset)
             .fetch_all(&state.db.pool)
             .await
-    }
-    .map_err(|e| {
-        tracing::error!("Failed to fetch orders: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "获取订单失败".to_string(),
-                code: "ORDER_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    }?;
 
     let orders: Vec<OrderDetail> = rows
         .into_iter()
@@ -363,9 +485,11 @@ pub async fn get_orders(
                     amount,
                     filled_amount,
                     status,
+                    complement: false,
                     created_at,
                     updated_at,
                 }
+                .in_perspective(perspective)
             },
         )
         .collect();
@@ -375,16 +499,141 @@ pub async fn get_orders(
     Ok(Json(OrdersResponse { orders, total }))
 }
 
+/// Open order counts and reserved amounts, overall and per market. Reads
+/// the trigger-maintained `user_open_order_stats` aggregate.
+/// GET /account/orders/summary
+pub async fn get_orders_summary(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<OrdersSummaryResponse>, AppError> {
+    let address = auth_user.address.clone();
+
+    let markets: Vec<MarketOrderSummary> = sqlx::query_as(
+        r#"
+        SELECT s.market_id, m.question AS market_question, s.open_orders, s.buy_orders,
+               s.sell_orders, s.buy_notional, s.sell_shares
+        FROM user_open_order_stats s
+        LEFT JOIN markets m ON m.id = s.market_id
+        WHERE s.user_address = $1
+        ORDER BY s.buy_notional DESC, s.market_id
+        "#,
+    )
+    .bind(&address)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    let frozen_collateral: Option<Decimal> =
+        sqlx::query_scalar("SELECT frozen FROM balances WHERE user_address = $1 AND token = $2")
+            .bind(&address)
+            .bind(state.config.collateral_symbol())
+            .fetch_optional(&state.db.pool)
+            .await?;
+
+    Ok(Json(OrdersSummaryResponse {
+        open_orders: markets.iter().map(|m| i64::from(m.open_orders)).sum(),
+        buy_orders: markets.iter().map(|m| i64::from(m.buy_orders)).sum(),
+        sell_orders: markets.iter().map(|m| i64::from(m.sell_orders)).sum(),
+        buy_notional: markets.iter().map(|m| m.buy_notional).sum(),
+        frozen_collateral: frozen_collateral.unwrap_or(Decimal::ZERO),
+        markets,
+    }))
+}
+
+/// Execution quality of the user's recent orders: slippage against the
+/// midpoint at submission, price improvement and fill rates by order type
+/// GET /account/execution-stats
+pub async fn get_execution_stats(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<ExecutionStatsQuery>,
+) -> Result<Json<ExecutionStats>, AppError> {
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let since = Utc::now() - chrono::Duration::days(days);
+
+    let stats = execution_stats::for_user(&state.db.pool, &auth_user.address, since)
+        .await?;
+
+    Ok(Json(stats))
+}
+
+/// Correlated exposure across the markets of each event the user holds or
+/// has open orders in: net exposure, worst-case loss and collateral at risk
+/// GET /account/risk
+pub async fn get_risk(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<RiskReport>, AppError> {
+    let report = portfolio_risk::report(&state.db.pool, &auth_user.address)
+        .await?;
+
+    Ok(Json(report))
+}
+
+/// Fees paid and rebates earned this month, and progress toward the next
+/// fee tier
+/// GET /account/fees
+pub async fn get_fees(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<FeeSummaryResponse>, AppError> {
+    let prefs = preferences::load(&state.db.pool, &auth_user.address).await?;
+    let since = fee_summary::month_start(prefs.timezone, Utc::now());
+    let totals = fee_summary::totals(&state.db.pool, &auth_user.address, since).await?;
+
+    let volume_30d = market_maker::get_volume_30d(&state.db.pool, &auth_user.address).await;
+    let current_tier = market_maker::get_user_fee_tier(volume_30d);
+    let next_tier = market_maker::get_next_fee_tier(volume_30d);
+    let volume_to_next_tier = next_tier.as_ref().map(|next| next.volume_threshold - volume_30d);
+    let next_tier_progress = next_tier.as_ref().map(|next| {
+        let span = next.volume_threshold - current_tier.volume_threshold;
+        ((volume_30d - current_tier.volume_threshold) / span).round_dp(4)
+    });
+
+    Ok(Json(FeeSummaryResponse {
+        period_start: since,
+        fees_paid: totals.fees_paid(),
+        maker_fees: totals.maker_fees,
+        taker_fees: totals.taker_fees,
+        rebates_earned: totals.rebates_earned(),
+        maker_rebates: totals.maker_rebates,
+        referral_commission: totals.referral_commission,
+        trade_count: totals.trade_count,
+        volume_30d,
+        current_tier,
+        next_tier,
+        volume_to_next_tier,
+        next_tier_progress,
+    }))
+}
+
+/// API calls and bytes used today against the daily quotas, and per day
+/// GET /account/api-usage?days
+pub async fn get_api_usage(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<ApiUsageQuery>,
+) -> Result<Json<ApiUsageResponse>, AppError> {
+    let today = state.api_usage.check(&auth_user.address).await?;
+    let history = state.api_usage.history(&auth_user.address, query.days.unwrap_or(30)).await?;
+    Ok(Json(ApiUsageResponse {
+        resets_at: Utc::now() + chrono::Duration::seconds(today.resets_in_secs()),
+        warn_percent: state.api_usage.warn_percent(),
+        today,
+        history,
+    }))
+}
+
 /// Get user trades
 /// GET /account/trades
 pub async fn get_trades(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Query(query): Query<TradesQuery>,
-) -> Result<Json<TradesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidQuery(query): ValidQuery<TradesQuery>,
+) -> Result<Json<TradesResponse>, AppError> {
     let limit = query.limit.unwrap_or(50).min(100);
     let offset = query.offset.unwrap_or(0);
-    let user_address = auth_user.address.to_lowercase();
+    let perspective = query.perspective.unwrap_or_default();
+    let user_address = auth_user.address.clone();
 
     let rows: Vec<(
         Uuid,
@@ -433,17 +682,7 @@ pub async fn get_trades(
         .bind(offset)
         .fetch_all(&state.db.pool)
         .await
-    }
-    .map_err(|e| {
-        tracing::error!("Failed to fetch trades: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "获取交易记录失败".to_string(),
-                code: "TRADE_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    }?;
 
     let trades: Vec<TradeRecord> = rows
         .into_iter()
@@ -458,8 +697,10 @@ pub async fn get_trades(
                     price,
                     amount,
                     fee,
+                    complement: false,
                     timestamp,
                 }
+                .in_perspective(perspective)
             },
         )
         .collect();
@@ -469,14 +710,73 @@ pub async fn get_trades(
     Ok(Json(TradesResponse { trades, total }))
 }
 
-/// Get user share holdings
+/// ERC-1155 positions in a self-custody user's wallet, for the outcomes
+/// they traded or split / merged. Positions that cannot be read on-chain
+/// are left out.
+async fn wallet_shares(
+    state: &AppState,
+    user_address: &crate::models::Address,
+    market_id: Option<Uuid>,
+    active_only: bool,
+) -> Result<Vec<ShareDetail>, AppError> {
+    let Some(client) = state.blockchain_client.as_ref() else {
+        return Ok(Vec::new());
+    };
+    let owner = user_address.to_h160();
+
+    let outcomes = settlement_mode::wallet_outcomes(&state.db.pool, user_address, market_id)
+        .await?;
+
+    let mut shares = Vec::with_capacity(outcomes.len());
+    for outcome in outcomes {
+        let Ok(position_id) = U256::from_dec_str(&outcome.token_id) else {
+            continue;
+        };
+        let amount = match client.get_position_balance(owner, position_id).await {
+            Ok(balance) => ctf_position::from_base_units(balance).unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("Failed to read wallet position {} of {}: {}", outcome.token_id, user_address, e);
+                continue;
+            }
+        };
+        if active_only && amount.is_zero() {
+            continue;
+        }
+
+        let share_type = outcome.share_type.parse().unwrap_or(ShareType::Yes);
+        let current_price = match share_type {
+            ShareType::Yes => outcome.probability,
+            ShareType::No => Decimal::ONE - outcome.probability,
+        };
+        shares.push(ShareDetail {
+            id: outcome.outcome_id,
+            market_id: outcome.market_id,
+            outcome_id: outcome.outcome_id,
+            share_type,
+            amount,
+            avg_cost: outcome.avg_cost,
+            current_price,
+            unrealized_pnl: amount * (current_price - outcome.avg_cost),
+            market_question: Some(outcome.question),
+            outcome_name: Some(outcome.outcome_name),
+            custody: "wallet",
+            complement: false,
+            created_at: outcome.first_activity,
+            updated_at: outcome.last_activity,
+        });
+    }
+    Ok(shares)
+}
+
+/// Get user share holdings; self-custody users also see the positions in
+/// their wallet
 /// GET /account/shares
 pub async fn get_shares(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Query(query): Query<SharesQuery>,
-) -> Result<Json<SharesResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+    ValidQuery(query): ValidQuery<SharesQuery>,
+) -> Result<Json<SharesResponse>, AppError> {
+    let user_address = auth_user.address.clone();
     let active_only = query.active_only.unwrap_or(true);
 
     // Build query based on filters
@@ -549,22 +849,12 @@ pub async fn get_shares(
             .bind(&user_address)
             .fetch_all(&state.db.pool)
             .await
-    }
-    .map_err(|e| {
-        tracing::error!("Failed to fetch shares: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "获取持仓失败".to_string(),
-                code: "SHARES_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    }?;
 
     let mut total_value = Decimal::ZERO;
     let mut total_cost = Decimal::ZERO;
 
-    let shares: Vec<ShareDetail> = rows
+    let mut shares: Vec<ShareDetail> = rows
         .into_iter()
         .map(
             |(
@@ -606,6 +896,8 @@ pub async fn get_shares(
                     unrealized_pnl,
                     market_question: Some(question),
                     outcome_name: Some(outcome_name),
+                    custody: "internal",
+                    complement: false,
                     created_at,
                     updated_at,
                 }
@@ -613,9 +905,24 @@ pub async fn get_shares(
         )
         .collect();
 
+    let settlement_mode = settlement_mode::get(&state.db.pool, &user_address)
+        .await?;
+    if settlement_mode == SettlementMode::SelfCustody {
+        for share in wallet_shares(&state, &user_address, query.market_id, active_only).await? {
+            total_value += share.amount * share.current_price;
+            total_cost += share.amount * share.avg_cost;
+            shares.push(share);
+        }
+    }
+
+    // Totals are taken before restating; value, cost and PnL do not depend
+    // on the perspective
     let total_unrealized_pnl = total_value - total_cost;
+    let perspective = query.perspective.unwrap_or_default();
+    let shares = shares.into_iter().map(|share| share.in_perspective(perspective)).collect();
 
     Ok(Json(SharesResponse {
+        settlement_mode,
         shares,
         total_value,
         total_cost,
@@ -668,8 +975,8 @@ pub async fn settle_market(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     axum::extract::Path(market_id): axum::extract::Path<Uuid>,
-) -> Result<Json<SettlementResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+) -> Result<Json<SettlementResponse>, AppError> {
+    let user_address = auth_user.address.clone();
 
     let result = SettlementService::settle_user_shares(&state.db.pool, market_id, &user_address)
         .await
@@ -710,13 +1017,7 @@ pub async fn settle_market(
                 }
             };
 
-            (
-                status,
-                Json(ErrorResponse {
-                    error: message.to_string(),
-                    code: code.to_string(),
-                }),
-            )
+            AppError::new(status, code, message)
         })?;
 
     let settlement_type_str = match result.settlement_type {
@@ -741,6 +1042,23 @@ pub async fn settle_market(
         result.total_payout
     );
 
+    if result.total_payout > Decimal::ZERO {
+        state
+            .notification_service
+            .notify(
+                NotificationKind::SettlementPayout,
+                user_address.as_str(),
+                None,
+                serde_json::json!({
+                    "market_id": result.market_id,
+                    "settlement_type": settlement_type_str,
+                    "total_payout": result.total_payout,
+                    "symbol": state.config.collateral_symbol(),
+                }),
+            )
+            .await;
+    }
+
     Ok(Json(SettlementResponse {
         market_id: result.market_id,
         settlement_type: settlement_type_str.to_string(),
@@ -784,8 +1102,8 @@ pub struct PortfolioSummaryResponse {
 pub async fn get_portfolio(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<PortfolioSummaryResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+) -> Result<Json<PortfolioSummaryResponse>, AppError> {
+    let user_address = auth_user.address.clone();
 
     // Get positions summary
     let positions_summary: Option<(Decimal, Decimal, i64)> = sqlx::query_as(
@@ -801,17 +1119,7 @@ pub async fn get_portfolio(
     )
     .bind(&user_address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch positions summary: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "获取持仓汇总失败".to_string(),
-                code: "PORTFOLIO_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let (total_position_value, total_cost_basis, active_positions) = positions_summary
         .unwrap_or((Decimal::ZERO, Decimal::ZERO, 0));
@@ -822,17 +1130,7 @@ pub async fn get_portfolio(
     )
     .bind(&user_address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch balance: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "获取余额失败".to_string(),
-                code: "BALANCE_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let (available_balance, frozen_balance) = balance.unwrap_or((Decimal::ZERO, Decimal::ZERO));
 
@@ -890,8 +1188,8 @@ pub async fn get_settlement_status(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     axum::extract::Path(market_id): axum::extract::Path<Uuid>,
-) -> Result<Json<SettlementStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+) -> Result<Json<SettlementStatusResponse>, AppError> {
+    let user_address = auth_user.address.clone();
 
     let status = SettlementService::get_settlement_status(&state.db.pool, market_id, &user_address)
         .await
@@ -909,13 +1207,7 @@ pub async fn get_settlement_status(
                 ),
             };
             tracing::error!("Failed to get settlement status: {}", e);
-            (
-                status_code,
-                Json(ErrorResponse {
-                    error: message.to_string(),
-                    code: code.to_string(),
-                }),
-            )
+            AppError::new(status_code, code, message)
         })?;
 
     Ok(Json(SettlementStatusResponse {
@@ -927,3 +1219,89 @@ pub async fn get_settlement_status(
         share_count: status.share_count.to_string().parse().unwrap_or(0),
     }))
 }
+
+/// What the caller would be paid for each way an unresolved market can
+/// resolve, from current holdings less shares on resting sells
+/// GET /markets/:market_id/payout-preview
+pub async fn get_payout_preview(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    axum::extract::Path(market_id): axum::extract::Path<Uuid>,
+    ValidQuery(query): ValidQuery<PayoutPreviewQuery>,
+) -> Result<Json<PayoutPreview>, AppError> {
+    let mut preview = payout_preview::load(&state.db.pool, market_id, &auth_user.address)
+        .await
+        .map_err(|e| match e {
+            PreviewError::MarketNotFound => AppError::not_found("MARKET_NOT_FOUND", "市场不存在"),
+            PreviewError::MarketFinal(status) => AppError::new(
+                StatusCode::CONFLICT,
+                "MARKET_FINALIZED",
+                format!("市场已{}，请查看结算状态", if status == "resolved" { "结算" } else { "取消" }),
+            ),
+            PreviewError::Database(e) => e.into(),
+        })?;
+    if let Some(outcome) = query.outcome {
+        preview.scenarios.retain(|scenario| scenario.share_type == outcome);
+    }
+    Ok(Json(preview))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::auth::middleware::UserRole;
+    use crate::models::{OrderSide, OrderStatus};
+    use crate::services::order_gateway::{GatewayOrder, OrderSource};
+    use crate::test_support::{account, gateway_order, TestApp, MAKER, TAKER};
+
+    #[tokio::test]
+    async fn test_open_order_stats_follow_place_fill_and_cancel() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.deposit(TAKER, dec!(100)).await;
+        app.grant_shares(MAKER, market_id, yes, ShareType::Yes, dec!(20)).await;
+        let gateway = &app.state.order_gateway;
+        let summary = |user: &'static str| {
+            let state = app.state.clone();
+            async move {
+                let auth = AuthUser {
+                    address: account(user),
+                    role: UserRole::User,
+                    session_id: None,
+                };
+                get_orders_summary(State(state), Extension(auth)).await.unwrap().0
+            }
+        };
+        let counters = |s: &OrdersSummaryResponse| (s.open_orders, s.buy_orders, s.sell_orders, s.buy_notional);
+
+        let high = gateway.place(gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.4))).await.unwrap();
+        let low = gateway.place(gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.3))).await.unwrap();
+        gateway.place(gateway_order(market_id, yes, MAKER, OrderSide::Sell, dec!(0.6))).await.unwrap();
+        let taker = summary(TAKER).await;
+        assert_eq!(counters(&taker), (2, 2, 0, dec!(7)));
+        assert_eq!(taker.frozen_collateral, dec!(7));
+        let maker = summary(MAKER).await;
+        assert_eq!(counters(&maker), (1, 0, 1, dec!(0)));
+        assert_eq!((maker.markets[0].market_id, maker.markets[0].sell_shares), (market_id, dec!(10)));
+
+        // A partial fill shrinks what the buy still reserves; the sell that
+        // filled completely never counts
+        let sell = GatewayOrder { amount: dec!(4), ..gateway_order(market_id, yes, MAKER, OrderSide::Sell, dec!(0.4)) };
+        assert_eq!(gateway.place(sell).await.unwrap().status, OrderStatus::Filled);
+        assert_eq!(counters(&summary(TAKER).await), (2, 2, 0, dec!(5.4)));
+        assert_eq!(summary(MAKER).await.markets[0].sell_shares, dec!(10));
+
+        assert!(gateway.cancel(OrderSource::Api, &account(TAKER), low.order_id).await.unwrap());
+        let taker = summary(TAKER).await;
+        assert_eq!(counters(&taker), (1, 1, 0, dec!(2.4)));
+        assert_eq!(taker.markets[0].buy_notional, dec!(2.4));
+
+        // The last open order gone, so is the market's row
+        assert!(gateway.cancel(OrderSource::Api, &account(TAKER), high.order_id).await.unwrap());
+        let taker = summary(TAKER).await;
+        assert_eq!(counters(&taker), (0, 0, 0, dec!(0)));
+        assert!(taker.markets.is_empty());
+    }
+}
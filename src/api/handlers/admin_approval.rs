@@ -0,0 +1,304 @@
+//! Admin Approval Handlers
+//!
+//! Propose, approve and execute the admin actions that fall under the
+//! two-person rule (manual market resolution, trade busts and price
+//! adjustments). A proposal carries the request body of the action's own
+//! endpoint, which is checked when proposing and run on execution.
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::handlers::{market, trade_adjustment};
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::models::Address;
+use crate::services::admin_approval::{self, AdminApproval, ApprovalAction};
+use crate::services::webhook::WebhookEventType;
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ProposeRequest {
+    pub action: ApprovalAction,
+    /// Market (resolution) or trade (bust, adjust) to act on
+    pub target_id: Uuid,
+    /// Request body of the action's endpoint; trade corrections default
+    /// their `reason` to the proposal's
+    #[serde(default = "empty_params")]
+    pub params: serde_json::Value,
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+fn empty_params() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListApprovalsQuery {
+    /// pending, approved, executed or expired
+    pub status: Option<String>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApprovalsResponse {
+    pub approvals: Vec<AdminApproval>,
+}
+
+const STATUSES: [&str; 4] = ["pending", "approved", "executed", "expired"];
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Parse and validate a proposal's params as the action's request body
+fn parse_params<T: DeserializeOwned + Validate>(params: &serde_json::Value) -> Result<T, AppError> {
+    let req: T = serde_json::from_value(params.clone())
+        .map_err(|e| AppError::bad_request("INVALID_PARAMS", format!("Invalid params: {}", e)))?;
+    req.validate()
+        .map_err(|e| AppError::bad_request("INVALID_PARAMS", format!("Invalid params: {}", e)))?;
+    Ok(req)
+}
+
+fn check_params(action: ApprovalAction, params: &serde_json::Value) -> Result<(), AppError> {
+    match action {
+        ApprovalAction::MarketResolution => parse_params::<market::ResolveMarketRequest>(params).map(drop),
+        ApprovalAction::TradeBust => parse_params::<trade_adjustment::BustTradeRequest>(params).map(drop),
+        ApprovalAction::TradeAdjust => parse_params::<trade_adjustment::AdjustTradeRequest>(params).map(drop),
+    }
+}
+
+/// Run an approved action as `admin_address`
+async fn run(state: &AppState, approval: &AdminApproval, admin_address: &Address) -> Result<Response, Response> {
+    let target = approval.target_id;
+    match ApprovalAction::parse(&approval.action) {
+        Some(ApprovalAction::MarketResolution) => {
+            let req = parse_params(&approval.params).map_err(IntoResponse::into_response)?;
+            market::resolve(state, admin_address, target, req)
+                .await
+                .map(IntoResponse::into_response)
+                .map_err(IntoResponse::into_response)
+        }
+        Some(ApprovalAction::TradeBust) => {
+            let req = parse_params(&approval.params).map_err(IntoResponse::into_response)?;
+            trade_adjustment::bust(state, admin_address, target, req)
+                .await
+                .map(IntoResponse::into_response)
+                .map_err(IntoResponse::into_response)
+        }
+        Some(ApprovalAction::TradeAdjust) => {
+            let req = parse_params(&approval.params).map_err(IntoResponse::into_response)?;
+            trade_adjustment::adjust(state, admin_address, target, req)
+                .await
+                .map(IntoResponse::into_response)
+                .map_err(IntoResponse::into_response)
+        }
+        None => Err(AppError::bad_request("INVALID_PARAMS", format!("Unknown action {}", approval.action)).into_response()),
+    }
+}
+
+/// Tell platform-wide webhook subscribers about a proposal's new state
+async fn announce(state: &AppState, approval: &AdminApproval) {
+    let data = serde_json::to_value(approval).unwrap_or_default();
+    state
+        .webhook_service
+        .dispatch(WebhookEventType::AdminApproval, Some(&approval.proposed_by), data)
+        .await;
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+/// Propose an action for a second admin to approve (Admin only)
+/// POST /admin/approvals
+pub async fn propose(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(mut req): ValidJson<ProposeRequest>,
+) -> Result<Json<AdminApproval>, AppError> {
+    if matches!(req.action, ApprovalAction::TradeBust | ApprovalAction::TradeAdjust) {
+        if let Some(params) = req.params.as_object_mut() {
+            params
+                .entry("reason")
+                .or_insert_with(|| serde_json::Value::String(req.reason.clone()));
+        }
+    }
+    check_params(req.action, &req.params)?;
+
+    let approval = admin_approval::propose(
+        &state.db.pool,
+        req.action,
+        req.target_id,
+        &req.params,
+        &req.reason,
+        &auth_user.address,
+        Duration::from_secs(state.config.admin_approval_ttl_secs),
+    )
+    .await?;
+    tracing::info!(
+        "Admin {} proposed {} of {} ({})",
+        approval.proposed_by,
+        approval.action,
+        approval.target_id,
+        approval.id
+    );
+    announce(&state, &approval).await;
+    Ok(Json(approval))
+}
+
+/// Recent proposals (Admin only)
+/// GET /admin/approvals
+pub async fn list_approvals(
+    State(state): State<Arc<AppState>>,
+    ValidQuery(query): ValidQuery<ListApprovalsQuery>,
+) -> Result<Json<ApprovalsResponse>, AppError> {
+    if let Some(status) = query.status.as_deref() {
+        if !STATUSES.contains(&status) {
+            return Err(AppError::bad_request(
+                "INVALID_STATUS",
+                format!("status must be one of {}", STATUSES.join(", ")),
+            ));
+        }
+    }
+    let limit = query.limit.unwrap_or(50);
+    let approvals = admin_approval::list(&state.db.pool, query.status.as_deref(), limit).await?;
+    Ok(Json(ApprovalsResponse { approvals }))
+}
+
+/// One proposal (Admin only)
+/// GET /admin/approvals/:approval_id
+pub async fn get_approval(
+    State(state): State<Arc<AppState>>,
+    Path(approval_id): Path<Uuid>,
+) -> Result<Json<AdminApproval>, AppError> {
+    let approval = admin_approval::get(&state.db.pool, approval_id)
+        .await?
+        .ok_or(admin_approval::ApprovalError::NotFound)?;
+    Ok(Json(approval))
+}
+
+/// Approve another admin's proposal (Admin only)
+/// POST /admin/approvals/:approval_id/approve
+pub async fn approve(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(approval_id): Path<Uuid>,
+) -> Result<Json<AdminApproval>, AppError> {
+    let approval = admin_approval::approve(&state.db.pool, approval_id, &auth_user.address).await?;
+    tracing::info!("Admin {} approved {} ({})", auth_user.address, approval.action, approval.id);
+    announce(&state, &approval).await;
+    Ok(Json(approval))
+}
+
+/// Execute an approved proposal; responds like the action's own endpoint
+/// (Admin only)
+/// POST /admin/approvals/:approval_id/execute
+pub async fn execute(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(approval_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let approval = admin_approval::claim(&state.db.pool, approval_id, &auth_user.address).await?;
+
+    match run(&state, &approval, &auth_user.address).await {
+        Ok(response) => {
+            tracing::info!(
+                "Admin {} executed {} of {} ({})",
+                auth_user.address,
+                approval.action,
+                approval.target_id,
+                approval.id
+            );
+            announce(&state, &approval).await;
+            Ok(response)
+        }
+        Err(response) => {
+            // Leave it approved so it can be retried once the cause is fixed
+            admin_approval::release(&state.db.pool, approval.id).await?;
+            Ok(response)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    use crate::auth::middleware::UserRole;
+    use crate::test_support::{account, TestApp};
+
+    #[tokio::test]
+    async fn test_trade_bust_needs_second_admin() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+        let admin = |address: &str| {
+            Extension(AuthUser {
+                address: account(address),
+                role: UserRole::Admin,
+                session_id: None,
+            })
+        };
+        let (first, second) = ("0x00000000000000000000000000000000000000e1", "0x00000000000000000000000000000000000000e2");
+        let state = || State(app.state.clone());
+
+        let direct = trade_adjustment::bust_trade(
+            state(),
+            admin(first),
+            Path(trade.trade_id),
+            ValidJson(trade_adjustment::BustTradeRequest {
+                reason: "fat finger".to_string(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!((direct.status(), direct.code()), (StatusCode::FORBIDDEN, "APPROVAL_REQUIRED"));
+
+        let Json(proposal) = propose(
+            state(),
+            admin(first),
+            ValidJson(ProposeRequest {
+                action: ApprovalAction::TradeBust,
+                target_id: trade.trade_id,
+                params: serde_json::json!({}),
+                reason: "fat finger".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(proposal.params["reason"], "fat finger");
+
+        let early = execute(state(), admin(first), Path(proposal.id)).await.unwrap_err();
+        assert_eq!(early.into_response().status(), StatusCode::CONFLICT);
+        let own = approve(state(), admin(first), Path(proposal.id)).await.unwrap_err();
+        assert_eq!(own.into_response().status(), StatusCode::FORBIDDEN);
+
+        let Json(approved) = approve(state(), admin(second), Path(proposal.id)).await.unwrap();
+        assert_eq!(approved.approved_by.as_deref(), Some(second));
+        let executed = execute(state(), admin(first), Path(proposal.id)).await.unwrap();
+        assert_eq!(executed.status(), StatusCode::OK);
+
+        let status: Option<String> = sqlx::query_scalar("SELECT adjustment_status FROM trades WHERE id = $1")
+            .bind(trade.trade_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(status.as_deref(), Some("busted"));
+    }
+}
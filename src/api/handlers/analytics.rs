@@ -0,0 +1,264 @@
+//! Market Analytics Handlers
+//!
+//! Serves the pre-aggregated `market_analytics` buckets for the market
+//! detail page's analytics tab. Buckets without trades are filled in with
+//! zero volume and the previous probability so charts get a continuous
+//! series.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidQuery};
+use crate::services::analytics::AnalyticsInterval;
+use crate::AppState;
+
+/// Maximum buckets returned per request
+const MAX_BUCKETS: i64 = 720;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AnalyticsQuery {
+    /// Bucket width: "1h" (default) or "1d"
+    pub interval: Option<String>,
+    /// Start of range (unix seconds); defaults to `limit` buckets before `to`
+    pub from: Option<i64>,
+    /// End of range (unix seconds); defaults to now
+    pub to: Option<i64>,
+    /// Number of buckets (default 168, max 720)
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalyticsBucket {
+    /// Bucket start (unix seconds)
+    pub time: i64,
+    /// Collateral notional traded
+    pub volume: Decimal,
+    pub shares_traded: Decimal,
+    pub trade_count: i64,
+    pub unique_traders: i64,
+    pub avg_trade_size: Decimal,
+    /// Implied YES probability at bucket close (carried forward when no trades)
+    pub probability: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyticsSummary {
+    pub total_volume: Decimal,
+    pub total_trades: i64,
+    /// Distinct traders over the market's lifetime
+    pub unique_traders: i64,
+    pub avg_trade_size: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarketAnalyticsResponse {
+    pub market_id: Uuid,
+    pub interval: String,
+    pub buckets: Vec<AnalyticsBucket>,
+    pub summary: AnalyticsSummary,
+}
+
+/// (bucket_start, volume, shares_traded, trade_count, unique_traders, avg_trade_size, probability)
+type BucketRow = (DateTime<Utc>, Decimal, Decimal, i64, i64, Decimal, Option<Decimal>);
+
+/// Fill gaps between `from` and `to` (inclusive bucket starts, unix seconds)
+/// with empty buckets, carrying the last known probability forward.
+fn fill_series(
+    rows: Vec<AnalyticsBucket>,
+    from: i64,
+    to: i64,
+    step: i64,
+    mut probability: Option<Decimal>,
+) -> Vec<AnalyticsBucket> {
+    let mut rows = rows.into_iter().peekable();
+    let mut series = Vec::new();
+    let mut time = from;
+    while time <= to {
+        match rows.peek() {
+            Some(row) if row.time == time => {
+                let mut bucket = rows.next().expect("peeked");
+                if bucket.probability.is_none() {
+                    bucket.probability = probability;
+                }
+                probability = bucket.probability;
+                series.push(bucket);
+            }
+            _ => series.push(AnalyticsBucket {
+                time,
+                volume: Decimal::ZERO,
+                shares_traded: Decimal::ZERO,
+                trade_count: 0,
+                unique_traders: 0,
+                avg_trade_size: Decimal::ZERO,
+                probability,
+            }),
+        }
+        time += step;
+    }
+    series
+}
+
+/// Get time-bucketed market analytics
+/// GET /markets/:market_id/analytics
+pub async fn get_market_analytics(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<AnalyticsQuery>,
+) -> Result<Json<MarketAnalyticsResponse>, AppError> {
+    let interval_str = query.interval.as_deref().unwrap_or("1h");
+    let interval = AnalyticsInterval::parse(interval_str).ok_or_else(|| {
+        AppError::bad_request("INVALID_INTERVAL", "Invalid interval. Use 1h or 1d")
+    })?;
+    let step = interval.duration().num_seconds();
+    let limit = query.limit.unwrap_or(168).clamp(1, MAX_BUCKETS);
+
+    let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM markets WHERE id = $1")
+        .bind(market_id)
+        .fetch_optional(&state.db.pool)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::not_found("MARKET_NOT_FOUND", "Market not found"));
+    }
+
+    // Align the range to bucket boundaries
+    let to = query.to.unwrap_or_else(|| Utc::now().timestamp());
+    let to = to - to.rem_euclid(step);
+    let from = match query.from {
+        Some(from) => from - from.rem_euclid(step),
+        None => to - step * (limit - 1),
+    };
+    if from > to {
+        return Err(AppError::bad_request("INVALID_RANGE", "from must be before to"));
+    }
+    let from = from.max(to - step * (MAX_BUCKETS - 1));
+    let (Some(from_ts), Some(to_ts)) = (DateTime::from_timestamp(from, 0), DateTime::from_timestamp(to, 0)) else {
+        return Err(AppError::bad_request("INVALID_RANGE", "Invalid time range"));
+    };
+
+    let rows: Vec<BucketRow> = sqlx::query_as(
+        r#"
+        SELECT bucket_start, volume, shares_traded, trade_count, unique_traders, avg_trade_size, probability
+        FROM market_analytics
+        WHERE market_id = $1 AND bucket_interval = $2 AND bucket_start >= $3 AND bucket_start <= $4
+        ORDER BY bucket_start
+        "#,
+    )
+    .bind(market_id)
+    .bind(interval.as_str())
+    .bind(from_ts)
+    .bind(to_ts)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    // Probability entering the range, for carrying forward into leading gaps
+    let prior_probability: Option<Decimal> = sqlx::query_scalar(
+        r#"
+        SELECT probability FROM market_analytics
+        WHERE market_id = $1 AND bucket_interval = $2 AND bucket_start < $3 AND probability IS NOT NULL
+        ORDER BY bucket_start DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(market_id)
+    .bind(interval.as_str())
+    .bind(from_ts)
+    .fetch_optional(&state.db.pool)
+    .await?
+    .flatten();
+
+    let buckets = rows
+        .into_iter()
+        .map(|(start, volume, shares_traded, trade_count, unique_traders, avg_trade_size, probability)| AnalyticsBucket {
+            time: start.timestamp(),
+            volume,
+            shares_traded,
+            trade_count,
+            unique_traders,
+            avg_trade_size,
+            probability,
+        })
+        .collect();
+    let buckets = fill_series(buckets, from, to, step, prior_probability);
+
+    let (total_volume, total_trades, unique_traders): (Option<Decimal>, i64, i64) = sqlx::query_as(
+        r#"
+        SELECT SUM(price * amount), COUNT(*),
+               (SELECT COUNT(DISTINCT addr)
+                FROM trades, LATERAL unnest(ARRAY[maker_address, taker_address]) AS addr
+                WHERE market_id = $1)
+        FROM trades
+        WHERE market_id = $1
+        "#,
+    )
+    .bind(market_id)
+    .fetch_one(&state.db.pool)
+    .await?;
+    let total_volume = total_volume.unwrap_or(Decimal::ZERO);
+    let avg_trade_size = if total_trades > 0 {
+        (total_volume / Decimal::from(total_trades)).round_dp(6)
+    } else {
+        Decimal::ZERO
+    };
+
+    Ok(Json(MarketAnalyticsResponse {
+        market_id,
+        interval: interval.as_str().to_string(),
+        buckets,
+        summary: AnalyticsSummary {
+            total_volume,
+            total_trades,
+            unique_traders,
+            avg_trade_size,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn bucket(time: i64, volume: Decimal, probability: Option<Decimal>) -> AnalyticsBucket {
+        AnalyticsBucket {
+            time,
+            volume,
+            shares_traded: volume,
+            trade_count: 1,
+            unique_traders: 2,
+            avg_trade_size: volume,
+            probability,
+        }
+    }
+
+    #[test]
+    fn test_fill_series_carries_probability() {
+        let rows = vec![bucket(3600, dec!(10), Some(dec!(0.6))), bucket(3 * 3600, dec!(5), None)];
+        let series = fill_series(rows, 0, 4 * 3600, 3600, Some(dec!(0.5)));
+
+        assert_eq!(series.len(), 5);
+        assert_eq!(series[0].probability, Some(dec!(0.5)));
+        assert_eq!(series[0].volume, Decimal::ZERO);
+        assert_eq!(series[1].volume, dec!(10));
+        assert_eq!(series[2].probability, Some(dec!(0.6)));
+        assert_eq!(series[3].probability, Some(dec!(0.6)));
+        assert_eq!(series[4].trade_count, 0);
+    }
+}
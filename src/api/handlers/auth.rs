@@ -1,20 +1,25 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::ValidJson;
 use crate::auth::{
     eip712::{get_login_typed_data, verify_login_signature_with_debug, LoginMessage},
     jwt::JwtManager,
+    session,
 };
+use crate::models::Address;
 use crate::AppState;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct LoginRequest {
-    pub address: String,
+    pub address: Address,
     pub signature: String,
     pub timestamp: u64,
 }
@@ -31,20 +36,15 @@ pub struct NonceResponse {
     pub typed_data: serde_json::Value,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub details: Option<serde_json::Value>,
-}
-
 /// Get nonce and EIP-712 typed data for signing
 pub async fn get_nonce(
     State(state): State<Arc<AppState>>,
     Path(address): Path<String>,
-) -> Result<Json<NonceResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let address = address.to_lowercase();
+) -> Result<Json<NonceResponse>, AppError> {
+    let address: String = address
+        .parse::<Address>()
+        .map_err(|e| AppError::bad_request("INVALID_ADDRESS", e.to_string()))?
+        .into();
 
     // Get or create user nonce from database
     let nonce: i64 = match sqlx::query_scalar::<_, i64>(
@@ -52,42 +52,18 @@ pub async fn get_nonce(
     )
     .bind(&address)
     .fetch_optional(&state.db.pool)
-    .await
+    .await?
     {
-        Ok(Some(n)) => n,
-        Ok(None) => {
+        Some(n) => n,
+        None => {
             // Create new user with nonce = 1
-            match sqlx::query(
+            sqlx::query(
                 "INSERT INTO users (address, nonce) VALUES ($1, 1) ON CONFLICT (address) DO NOTHING"
             )
             .bind(&address)
             .execute(&state.db.pool)
-            .await
-            {
-                Ok(_) => 1,
-                Err(e) => {
-                    tracing::error!("Failed to create user: {}", e);
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(ErrorResponse {
-                            error: "数据库错误".to_string(),
-                            code: "DATABASE_ERROR".to_string(),
-                            details: None,
-                        }),
-                    ));
-                }
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to get nonce: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "数据库错误".to_string(),
-                    code: "DATABASE_ERROR".to_string(),
-                    details: None,
-                }),
-            ));
+            .await?;
+            1
         }
     };
 
@@ -108,9 +84,10 @@ pub async fn get_nonce(
 /// Login with EIP-712 typed data signature
 pub async fn login(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let address = req.address.to_lowercase();
+    headers: HeaderMap,
+    ValidJson(req): ValidJson<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let address = req.address.as_str().to_string();
 
     // Validate timestamp (within 5 minutes)
     let now = std::time::SystemTime::now()
@@ -119,52 +96,23 @@ pub async fn login(
         .as_secs();
 
     if now.abs_diff(req.timestamp) > 300 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "时间戳已过期".to_string(),
-                code: "TIMESTAMP_EXPIRED".to_string(),
-                details: Some(serde_json::json!({
-                    "server_time": now,
-                    "request_timestamp": req.timestamp,
-                    "diff_seconds": now.abs_diff(req.timestamp)
-                })),
-            }),
+        return Err(AppError::bad_request(
+            "TIMESTAMP_EXPIRED",
+            format!("时间戳已过期 (server_time={}, request_timestamp={})", now, req.timestamp),
         ));
     }
 
-    // Get user nonce from database
-    let nonce: i64 = match sqlx::query_scalar::<_, i64>(
-        "SELECT nonce FROM users WHERE address = $1"
+    // Get user nonce from database; closed accounts cannot sign in
+    let nonce: i64 = match sqlx::query_as::<_, (i64, bool)>(
+        "SELECT nonce, closed_at IS NOT NULL FROM users WHERE address = $1"
     )
     .bind(&address)
     .fetch_optional(&state.db.pool)
-    .await
+    .await?
     {
-        Ok(Some(n)) => n,
-        Ok(None) => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "用户不存在，请先获取nonce".to_string(),
-                    code: "USER_NOT_FOUND".to_string(),
-                    details: Some(serde_json::json!({
-                        "address": address
-                    })),
-                }),
-            ));
-        }
-        Err(e) => {
-            tracing::error!("Failed to get nonce: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "数据库错误".to_string(),
-                    code: "DATABASE_ERROR".to_string(),
-                    details: None,
-                }),
-            ));
-        }
+        Some((_, true)) => return Err(AppError::forbidden("ACCOUNT_CLOSED", "账户已注销")),
+        Some((n, false)) => n,
+        None => return Err(AppError::not_found("USER_NOT_FOUND", "用户不存在，请先获取nonce")),
     };
 
     // EIP-712 签名验证
@@ -174,44 +122,22 @@ pub async fn login(
         timestamp: req.timestamp,
     };
 
-    let verify_result = match verify_login_signature_with_debug(&login_msg, &req.signature, &address) {
-        Ok(result) => result,
-        Err(e) => {
-            tracing::error!("Signature verification error: {}", e);
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "签名格式无效".to_string(),
-                    code: "INVALID_SIGNATURE_FORMAT".to_string(),
-                    details: Some(serde_json::json!({
-                        "error": e.to_string()
-                    })),
-                }),
-            ));
-        }
-    };
+    let verify_result = verify_login_signature_with_debug(&login_msg, &req.signature, &address).map_err(|e| {
+        tracing::error!("Signature verification error: {}", e);
+        AppError::bad_request("INVALID_SIGNATURE_FORMAT", format!("签名格式无效: {}", e))
+    })?;
 
     if !verify_result.is_valid {
         tracing::warn!(
-            "Login signature verification failed for {}: recovered={}, expected={}",
+            "Login signature verification failed for {}: recovered={}, expected={}, domain_separator={}, struct_hash={}, message_hash={}",
             address,
             verify_result.recovered_address,
-            verify_result.expected_address
+            verify_result.expected_address,
+            verify_result.domain_separator,
+            verify_result.struct_hash,
+            verify_result.message_hash
         );
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "签名验证失败".to_string(),
-                code: "SIGNATURE_INVALID".to_string(),
-                details: Some(serde_json::json!({
-                    "recovered_address": verify_result.recovered_address,
-                    "expected_address": verify_result.expected_address,
-                    "domain_separator": verify_result.domain_separator,
-                    "struct_hash": verify_result.struct_hash,
-                    "message_hash": verify_result.message_hash
-                })),
-            }),
-        ));
+        return Err(AppError::new(StatusCode::UNAUTHORIZED, "SIGNATURE_INVALID", "签名验证失败"));
     }
 
     tracing::info!("EIP-712 signature verified for address: {}", address);
@@ -228,26 +154,31 @@ pub async fn login(
         // Continue anyway - login is still valid
     }
 
+    // Record the session the token belongs to
+    let session_id = uuid::Uuid::new_v4();
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(state.config.jwt_expiry_seconds as i64);
+    let user_agent = headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok());
+    session::create(
+        &state.db.pool,
+        session_id,
+        &address,
+        session::client_ip(&headers).as_deref(),
+        user_agent,
+        expires_at,
+    )
+    .await?;
+
     // Generate JWT token
     let jwt_manager = JwtManager::new(&state.config.jwt_secret, state.config.jwt_expiry_seconds);
-    let token = match jwt_manager.generate_token(&address) {
-        Ok(t) => t,
-        Err(e) => {
-            tracing::error!("Failed to generate JWT: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "JWT生成失败".to_string(),
-                    code: "JWT_GENERATION_FAILED".to_string(),
-                    details: None,
-                }),
-            ));
-        }
-    };
-
-    let expires_at = chrono::Utc::now().timestamp() + state.config.jwt_expiry_seconds as i64;
+    let token = jwt_manager.generate_token(&address, session_id).map_err(|e| {
+        tracing::error!("Failed to generate JWT: {}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "JWT_GENERATION_FAILED", "JWT生成失败")
+    })?;
 
     tracing::info!("User {} logged in successfully", address);
 
-    Ok(Json(LoginResponse { token, expires_at }))
+    Ok(Json(LoginResponse {
+        token,
+        expires_at: expires_at.timestamp(),
+    }))
 }
@@ -0,0 +1,61 @@
+//! Historical Import Admin Handler
+//!
+//! Accepts a JSON bundle (`markets`, `outcomes`, `trades` arrays) or CSV
+//! text per entity. Validation issues are returned with a 422 and nothing is
+//! written; `dry_run` validates without writing.
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Deserialize;
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::ValidJson;
+use crate::services::backfill::{BackfillService, ImportBundle, ImportReport};
+use crate::AppState;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImportRequest {
+    #[serde(default)]
+    pub dry_run: bool,
+    /// JSON records
+    #[serde(flatten)]
+    pub bundle: ImportBundle,
+    /// CSV alternatives (header row required); used when present
+    pub markets_csv: Option<String>,
+    pub outcomes_csv: Option<String>,
+    pub trades_csv: Option<String>,
+}
+
+/// Import historical markets, outcomes and trades
+/// POST /admin/import
+pub async fn import_history(
+    State(state): State<Arc<AppState>>,
+    ValidJson(req): ValidJson<ImportRequest>,
+) -> Result<(StatusCode, Json<ImportReport>), AppError> {
+    let bundle = if req.markets_csv.is_some() || req.outcomes_csv.is_some() || req.trades_csv.is_some() {
+        ImportBundle::from_csv(
+            req.markets_csv.as_deref().map(str::as_bytes),
+            req.outcomes_csv.as_deref().map(str::as_bytes),
+            req.trades_csv.as_deref().map(str::as_bytes),
+        )
+        .map_err(|e| AppError::bad_request("INVALID_CSV", e.to_string()))?
+    } else {
+        req.bundle
+    };
+
+    let report = BackfillService::new(state.db.pool.clone())
+        .import(&bundle, req.dry_run)
+        .await
+        .map_err(|e| {
+            tracing::error!("Import failed: {}", e);
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "IMPORT_FAILED", "Import failed")
+        })?;
+
+    let status = if report.is_valid() {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+    Ok((status, Json(report)))
+}
@@ -0,0 +1,276 @@
+//! Channel Gateway Admin Handlers
+//!
+//! Admins register Telegram chats / Discord channels that receive market
+//! announcements and tune each channel's filters (event types, categories,
+//! minimum trade notional).
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::services::channel_gateway::{ChannelEventType, ChannelPlatform};
+use crate::AppState;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateChannelRequest {
+    /// "telegram" or "discord"
+    pub platform: String,
+    #[validate(length(min = 1))]
+    pub name: String,
+    /// Telegram chat id / Discord channel id
+    #[validate(length(min = 1))]
+    pub chat_id: String,
+    /// Event types to announce; empty = all
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    /// Market categories to announce; empty = all
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[validate(custom = "validation::non_negative")]
+    pub min_trade_notional: Option<Decimal>,
+}
+
+/// Partial update; omitted fields are left unchanged
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateChannelRequest {
+    #[validate(length(min = 1))]
+    pub name: Option<String>,
+    #[validate(length(min = 1))]
+    pub chat_id: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub categories: Option<Vec<String>>,
+    #[validate(custom = "validation::non_negative")]
+    pub min_trade_notional: Option<Decimal>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MessagesQuery {
+    pub status: Option<String>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ChannelInfo {
+    pub id: Uuid,
+    pub platform: String,
+    pub name: String,
+    pub chat_id: String,
+    pub event_types: Vec<String>,
+    pub categories: Vec<String>,
+    pub min_trade_notional: Decimal,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelsResponse {
+    pub channels: Vec<ChannelInfo>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ChannelMessageInfo {
+    pub id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelMessagesResponse {
+    pub messages: Vec<ChannelMessageInfo>,
+}
+
+const CHANNEL_COLUMNS: &str =
+    "id, platform, name, chat_id, event_types, categories, min_trade_notional, is_active, created_at";
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn validate_event_types(event_types: &[String]) -> Result<(), AppError> {
+    if let Some(unknown) = event_types.iter().find(|t| ChannelEventType::parse(t).is_none()) {
+        return Err(AppError::bad_request(
+            "INVALID_EVENT_TYPE",
+            format!("Unknown event type: {}", unknown),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_min_notional(value: Option<Decimal>) -> Result<(), AppError> {
+    match value {
+        Some(v) if v < Decimal::ZERO => Err(AppError::bad_request(
+            "INVALID_THRESHOLD",
+            "min_trade_notional must not be negative",
+        )),
+        _ => Ok(()),
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Register a channel (Admin only)
+/// POST /admin/channels
+pub async fn create_channel(
+    State(state): State<Arc<AppState>>,
+    ValidJson(req): ValidJson<CreateChannelRequest>,
+) -> Result<Json<ChannelInfo>, AppError> {
+    let platform = ChannelPlatform::parse(&req.platform.to_lowercase()).ok_or_else(|| {
+        AppError::bad_request(
+            "INVALID_PLATFORM",
+            "platform must be 'telegram' or 'discord'",
+        )
+    })?;
+    if req.name.trim().is_empty() || req.chat_id.trim().is_empty() {
+        return Err(AppError::bad_request("INVALID_REQUEST", "name and chat_id are required"));
+    }
+    validate_event_types(&req.event_types)?;
+    validate_min_notional(req.min_trade_notional)?;
+
+    let channel: ChannelInfo = sqlx::query_as(&format!(
+        r#"
+        INSERT INTO channel_integrations (platform, name, chat_id, event_types, categories, min_trade_notional)
+        VALUES ($1, $2, $3, $4, $5, COALESCE($6, 1000))
+        RETURNING {}
+        "#,
+        CHANNEL_COLUMNS
+    ))
+    .bind(platform.as_str())
+    .bind(req.name.trim())
+    .bind(req.chat_id.trim())
+    .bind(&req.event_types)
+    .bind(&req.categories)
+    .bind(req.min_trade_notional)
+    .fetch_one(&state.db.pool)
+    .await?;
+
+    tracing::info!("Channel integration {} ({} {}) created", channel.id, channel.platform, channel.chat_id);
+
+    Ok(Json(channel))
+}
+
+/// List channels (Admin only)
+/// GET /admin/channels
+pub async fn list_channels(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ChannelsResponse>, AppError> {
+    let channels: Vec<ChannelInfo> = sqlx::query_as(&format!(
+        "SELECT {} FROM channel_integrations ORDER BY created_at DESC",
+        CHANNEL_COLUMNS
+    ))
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(Json(ChannelsResponse { channels }))
+}
+
+/// Update a channel's filters or pause it (Admin only)
+/// PUT /admin/channels/:channel_id
+pub async fn update_channel(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<Uuid>,
+    ValidJson(req): ValidJson<UpdateChannelRequest>,
+) -> Result<Json<ChannelInfo>, AppError> {
+    if let Some(event_types) = &req.event_types {
+        validate_event_types(event_types)?;
+    }
+    validate_min_notional(req.min_trade_notional)?;
+
+    let channel: Option<ChannelInfo> = sqlx::query_as(&format!(
+        r#"
+        UPDATE channel_integrations SET
+            name = COALESCE($2, name),
+            chat_id = COALESCE($3, chat_id),
+            event_types = COALESCE($4, event_types),
+            categories = COALESCE($5, categories),
+            min_trade_notional = COALESCE($6, min_trade_notional),
+            is_active = COALESCE($7, is_active)
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        CHANNEL_COLUMNS
+    ))
+    .bind(channel_id)
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(req.chat_id.as_deref().map(str::trim))
+    .bind(&req.event_types)
+    .bind(&req.categories)
+    .bind(req.min_trade_notional)
+    .bind(req.is_active)
+    .fetch_optional(&state.db.pool)
+    .await?;
+
+    channel
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("CHANNEL_NOT_FOUND", "Channel not found"))
+}
+
+/// Remove a channel and its message log (Admin only)
+/// DELETE /admin/channels/:channel_id
+pub async fn delete_channel(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let result = sqlx::query("DELETE FROM channel_integrations WHERE id = $1")
+        .bind(channel_id)
+        .execute(&state.db.pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("CHANNEL_NOT_FOUND", "Channel not found"));
+    }
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Recent messages sent to a channel, e.g. `?status=failed` (Admin only)
+/// GET /admin/channels/:channel_id/messages
+pub async fn get_channel_messages(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<MessagesQuery>,
+) -> Result<Json<ChannelMessagesResponse>, AppError> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+
+    let messages: Vec<ChannelMessageInfo> = sqlx::query_as(
+        r#"
+        SELECT id, event_type, payload, status, attempts, last_error, sent_at, created_at
+        FROM channel_messages
+        WHERE integration_id = $1 AND ($2::text IS NULL OR status = $2)
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(channel_id)
+    .bind(&query.status)
+    .bind(limit)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(Json(ChannelMessagesResponse { messages }))
+}
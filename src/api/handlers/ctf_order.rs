@@ -11,27 +11,35 @@ use chrono::Utc;
 use ethers::types::{Address, Bytes, U256};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson};
 use crate::auth::middleware::AuthUser;
 use crate::models::market::ShareType;
 use crate::models::{OrderSide, OrderStatus, OrderType};
 use crate::services::matching::{
-    OrderType as MatchingOrderType, Side as MatchingSide,
+    OrderType as MatchingOrderType, Side as MatchingSide, TimeInForce as MatchingTimeInForce,
 };
 use crate::services::settlement::{MatchType, MatchedOrders, SignedOrder};
+use crate::services::settlement_mode::{self, SettlementMode};
+use crate::services::trading_calendar;
 use crate::AppState;
 
-use super::order::ErrorResponse;
+use super::order::engine_rejection;
+
+/// Signed maker order fields read back for settlement:
+/// (token_id, maker_amount, taker_amount, signature, expiration, fee_rate_bps, sig_type, user_address)
+type MakerOrderRow = (String, String, String, String, i64, i32, i16, crate::models::Address);
 
 // ============================================================================
 // Request/Response Types
 // ============================================================================
 
 /// CTF Order request (Polymarket format)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateCtfOrderRequest {
     // Market identifiers
@@ -41,7 +49,9 @@ pub struct CreateCtfOrderRequest {
 
     // Order parameters
     pub side: OrderSide,
+    #[validate(custom = "validation::price")]
     pub price: Decimal,
+    #[validate(custom = "validation::positive")]
     pub amount: Decimal,
 
     // CTF-specific fields
@@ -92,30 +102,25 @@ where
 pub async fn create_ctf_order(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Json(req): Json<CreateCtfOrderRequest>,
-) -> Result<Json<CreateCtfOrderResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(req): ValidJson<CreateCtfOrderRequest>,
+) -> Result<Json<CreateCtfOrderResponse>, AppError> {
+    // Signed CTF orders settle on-chain to the signer's wallet
+    let settlement_mode = settlement_mode::get(&state.db.pool, &auth_user.address)
+        .await?;
+    if settlement_mode != SettlementMode::SelfCustody {
+        return Err(AppError::new(StatusCode::CONFLICT, "SETTLEMENT_MODE", "托管账户请通过 /orders 下单，或先切换为自托管结算"));
+    }
+
     // Validate price range (0.01 - 0.99)
     let min_price = Decimal::new(1, 2);
     let max_price = Decimal::new(99, 2);
     if req.price < min_price || req.price > max_price {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "价格必须在 0.01 到 0.99 之间".to_string(),
-                code: "INVALID_PRICE".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_PRICE", "价格必须在 0.01 到 0.99 之间"));
     }
 
     // Validate amount
     if req.amount <= Decimal::ZERO {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "订单数量必须大于 0".to_string(),
-                code: "INVALID_AMOUNT".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_AMOUNT", "订单数量必须大于 0"));
     }
 
     // Validate expiration (must be in the future)
@@ -124,66 +129,38 @@ pub async fn create_ctf_order(
         .unwrap()
         .as_secs();
     if req.expiration <= now {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "订单已过期".to_string(),
-                code: "ORDER_EXPIRED".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("ORDER_EXPIRED", "订单已过期"));
+    }
+
+    // Market must be open for trading, within its trading hours; awaiting-resolution
+    // markets only take cancels
+    let market_status: Option<String> = sqlx::query_scalar("SELECT status::text FROM markets WHERE id = $1")
+        .bind(req.market_id)
+        .fetch_optional(&state.db.pool)
+        .await?;
+    if let Some(status) = market_status.filter(|status| status != "active") {
+        return Err(AppError::bad_request("MARKET_NOT_ACTIVE", format!("市场状态为 {}，不接受新订单", status)));
+    }
+    let schedule = trading_calendar::load(&state.db.pool, req.market_id).await?;
+    if schedule.is_some_and(|schedule| !schedule.is_open(Utc::now())) {
+        return Err(AppError::bad_request("OUTSIDE_TRADING_HOURS", "市场不在交易时段内"));
     }
 
     // Parse on-chain values
-    let token_id = U256::from_dec_str(&req.token_id).map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "无效的 tokenId".to_string(),
-                code: "INVALID_TOKEN_ID".to_string(),
-            }),
-        )
-    })?;
-
-    let maker_amount = U256::from_dec_str(&req.maker_amount).map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "无效的 makerAmount".to_string(),
-                code: "INVALID_MAKER_AMOUNT".to_string(),
-            }),
-        )
-    })?;
-
-    let taker_amount = U256::from_dec_str(&req.taker_amount).map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "无效的 takerAmount".to_string(),
-                code: "INVALID_TAKER_AMOUNT".to_string(),
-            }),
-        )
-    })?;
-
-    let maker_address = Address::from_str(&auth_user.address).map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "无效的钱包地址".to_string(),
-                code: "INVALID_ADDRESS".to_string(),
-            }),
-        )
-    })?;
+    let token_id = U256::from_dec_str(&req.token_id)
+        .map_err(|_| AppError::bad_request("INVALID_TOKEN_ID", "无效的 tokenId"))?;
+
+    let maker_amount = U256::from_dec_str(&req.maker_amount)
+        .map_err(|_| AppError::bad_request("INVALID_MAKER_AMOUNT", "无效的 makerAmount"))?;
+
+    let taker_amount = U256::from_dec_str(&req.taker_amount)
+        .map_err(|_| AppError::bad_request("INVALID_TAKER_AMOUNT", "无效的 takerAmount"))?;
+
+    let maker_address = auth_user.address.to_h160();
 
     let signature_bytes = Bytes::from(
-        hex::decode(req.signature.trim_start_matches("0x")).map_err(|_| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "无效的签名格式".to_string(),
-                    code: "INVALID_SIGNATURE".to_string(),
-                }),
-            )
-        })?,
+        hex::decode(req.signature.trim_start_matches("0x"))
+            .map_err(|_| AppError::bad_request("INVALID_SIGNATURE", "无效的签名格式"))?,
     );
 
     // Generate order ID
@@ -191,6 +168,10 @@ pub async fn create_ctf_order(
 
     // Build market key for orderbook
     let market_key = format!("{}:{}:{}", req.market_id, req.outcome_id, req.share_type);
+    state
+        .engine_shards
+        .admit(&market_key)
+        .map_err(|e| engine_rejection(e, "订单提交失败"))?;
 
     // Convert to matching engine types
     let matching_side = match req.side {
@@ -198,37 +179,32 @@ pub async fn create_ctf_order(
         OrderSide::Sell => MatchingSide::Sell,
     };
 
-    // Submit to matching engine
-    let match_result = state
-        .matching_engine
-        .submit_order(
-            order_id,
-            &market_key,
-            &auth_user.address.to_lowercase(),
-            matching_side,
-            MatchingOrderType::Limit,
-            req.amount,
-            Some(req.price),
-            1, // No leverage
-        )
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("订单提交失败: {}", e),
-                    code: "MATCHING_ERROR".to_string(),
-                }),
-            )
-        })?;
+    // Submit to matching engine on the market's shard
+    let (symbol, user, amount, price) = (market_key.clone(), auth_user.address.clone(), req.amount, req.price);
+    let (match_result, quoted_mid) = state
+        .engine_shards
+        .execute(&market_key, move |engine| {
+            let quoted_mid = engine.quoted_mid(&symbol);
+            engine
+                .submit_order(
+                    order_id,
+                    &symbol,
+                    user.as_str(),
+                    matching_side,
+                    MatchingOrderType::Limit,
+                    amount,
+                    Some(price),
+                    1, // No leverage
+                    MatchingTimeInForce::GTC,
+                )
+                .map(|result| (result, quoted_mid))
+        })
+        .await
+        .and_then(|result| result)
+        .map_err(|e| engine_rejection(e, "订单提交失败"))?;
 
     // Convert status
-    let status = match match_result.status {
-        crate::services::matching::OrderStatus::Open => OrderStatus::Open,
-        crate::services::matching::OrderStatus::PartiallyFilled => OrderStatus::PartiallyFilled,
-        crate::services::matching::OrderStatus::Filled => OrderStatus::Filled,
-        crate::services::matching::OrderStatus::Cancelled => OrderStatus::Cancelled,
-        crate::services::matching::OrderStatus::Rejected => OrderStatus::Rejected,
-    };
+    let status: OrderStatus = match_result.status.into();
 
     let now_dt = Utc::now();
 
@@ -239,18 +215,18 @@ pub async fn create_ctf_order(
             id, user_address, symbol, market_id, outcome_id, share_type,
             side, order_type, price, amount, filled_amount, status, signature,
             token_id, maker_amount, taker_amount, expiration, fee_rate_bps, sig_type,
-            created_at, updated_at
+            created_at, updated_at, quoted_mid
         )
         VALUES (
             $1, $2, $3, $4, $5, $6::share_type,
             $7::order_side, $8::order_type, $9, $10, $11, $12::order_status, $13,
             $14, $15, $16, $17, $18, $19,
-            $20, $20
+            $20, $20, $21
         )
         "#,
     )
     .bind(order_id)
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .bind(&market_key)
     .bind(req.market_id)
     .bind(req.outcome_id)
@@ -269,25 +245,16 @@ pub async fn create_ctf_order(
     .bind(req.fee_rate_bps.unwrap_or(200) as i32)
     .bind(req.sig_type.unwrap_or(0) as i16)
     .bind(now_dt)
+    .bind(quoted_mid)
     .execute(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to persist CTF order: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("保存订单失败: {}", e),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Handle trades and submit for on-chain settlement
     let mut settlement_status = "pending".to_string();
 
     for trade_exec in &match_result.trades {
         // Get maker order from database
-        let maker_order_row: Option<(String, String, String, String, i64, i32, i16, String)> = sqlx::query_as(
+        let maker_order_row: Option<MakerOrderRow> = sqlx::query_as(
             r#"
             SELECT token_id, maker_amount, taker_amount, signature, expiration, fee_rate_bps, sig_type, user_address
             FROM orders WHERE id = $1
@@ -295,17 +262,7 @@ pub async fn create_ctf_order(
         )
         .bind(trade_exec.maker_order_id)
         .fetch_optional(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to get maker order: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("获取订单失败: {}", e),
-                    code: "DB_ERROR".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
         if let Some((m_token_id, m_maker_amount, m_taker_amount, m_signature, m_expiration, m_fee_rate, m_sig_type, m_user_address)) = maker_order_row {
             // Persist trade
@@ -327,7 +284,7 @@ pub async fn create_ctf_order(
             .bind(trade_exec.maker_order_id)
             .bind(order_id)
             .bind(&m_user_address)
-            .bind(&auth_user.address.to_lowercase())
+            .bind(&auth_user.address)
             .bind(trade_exec.price)
             .bind(trade_exec.amount)
             .bind(req.side.to_string())
@@ -335,13 +292,27 @@ pub async fn create_ctf_order(
             .await
             .ok();
 
+            // Only fills against another self-custody order can settle
+            // on-chain; a custodial maker's leg lives in internal balances
+            let maker_self_custody = settlement_mode::get(&state.db.pool, &m_user_address)
+                .await
+                .map(|mode| mode == SettlementMode::SelfCustody)
+                .unwrap_or(false);
+            if !maker_self_custody {
+                tracing::warn!(
+                    "Trade {} matched custodial maker {}; not submitted for on-chain settlement",
+                    trade_id,
+                    m_user_address
+                );
+            }
+
             // Submit to settlement service if available
-            if let Some(ref settlement_sender) = state.settlement_sender {
+            if let Some(settlement_sender) = state.settlement_sender.as_ref().filter(|_| maker_self_custody) {
                 let maker_signed = SignedOrder {
                     order_id: trade_exec.maker_order_id,
                     market_id: req.market_id,
                     outcome_id: req.outcome_id,
-                    maker: Address::from_str(&m_user_address).unwrap_or_default(),
+                    maker: m_user_address.to_h160(),
                     taker: Address::zero(),
                     token_id: U256::from_dec_str(&m_token_id).unwrap_or_default(),
                     maker_amount: U256::from_dec_str(&m_maker_amount).unwrap_or_default(),
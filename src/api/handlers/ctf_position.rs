@@ -0,0 +1,265 @@
+//! Split / Merge Preparation Handlers
+//!
+//! Prepare `splitPosition` / `mergePositions` calldata on the
+//! ConditionalTokens contract for the user to sign from their own wallet,
+//! and list the operations tracked for them.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Extension, Json,
+};
+use ethers::types::{Address, U256};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::blockchain::BlockchainClient;
+use crate::services::ctf_position::{self, PositionOp, PositionOpKind};
+use crate::services::settlement::TokenIdCalculator;
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PreparePositionOpRequest {
+    pub market_id: Uuid,
+    /// Collateral to split, or full sets to merge
+    #[validate(custom = "validation::positive")]
+    pub amount: Decimal,
+}
+
+/// A transaction for the user to sign and send
+#[derive(Debug, Serialize)]
+pub struct PreparedTransaction {
+    pub to: String,
+    pub data: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreparePositionOpResponse {
+    pub operation: PositionOp,
+    pub chain_id: u64,
+    pub collateral_token: String,
+    pub condition_id: String,
+    pub partition: Vec<u64>,
+    /// Amount in base units of the collateral
+    pub amount_units: String,
+    /// USDC approval to send first when the ConditionalTokens allowance is
+    /// below the amount (split only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval: Option<PreparedTransaction>,
+    pub transaction: PreparedTransaction,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListOpsQuery {
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PositionOpsResponse {
+    pub operations: Vec<PositionOp>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn chain_error(e: impl std::fmt::Display) -> AppError {
+    tracing::error!("Split/merge chain read failed: {}", e);
+    AppError::new(StatusCode::BAD_GATEWAY, "CHAIN_READ_FAILED", "Failed to read chain state")
+}
+
+fn transaction(to: Address, data: ethers::types::Bytes) -> PreparedTransaction {
+    PreparedTransaction {
+        to: format!("{:?}", to),
+        data: format!("0x{}", hex::encode(data)),
+        value: "0".to_string(),
+    }
+}
+
+/// Wallet's balance of the outcome position at `index_set`
+async fn position_balance(
+    client: &BlockchainClient,
+    owner: Address,
+    condition_id: [u8; 32],
+    index_set: U256,
+) -> Result<U256, AppError> {
+    let collection_id = client
+        .get_collection_id([0u8; 32], condition_id, index_set)
+        .await
+        .map_err(chain_error)?;
+    let position_id = client
+        .get_position_id(client.addresses().usdc, collection_id)
+        .await
+        .map_err(chain_error)?;
+    client.get_position_balance(owner, position_id).await.map_err(chain_error)
+}
+
+async fn prepare(
+    state: &AppState,
+    auth_user: &AuthUser,
+    req: PreparePositionOpRequest,
+    kind: PositionOpKind,
+) -> Result<PreparePositionOpResponse, AppError> {
+    let client = state.blockchain_client.as_ref().ok_or_else(|| {
+        AppError::new(StatusCode::SERVICE_UNAVAILABLE, "CHAIN_UNAVAILABLE", "Blockchain client not configured")
+    })?;
+    let amount_units = ctf_position::to_base_units(req.amount).ok_or_else(|| {
+        AppError::bad_request(
+            "INVALID_AMOUNT",
+            format!("Amount must be positive with at most {} decimals", ctf_position::COLLATERAL_DECIMALS),
+        )
+    })?;
+    let user_address = auth_user.address.clone();
+    let owner = user_address.to_h160();
+
+    let market: Option<(String, String)> =
+        sqlx::query_as("SELECT condition_id, status::text FROM markets WHERE id = $1")
+            .bind(req.market_id)
+            .fetch_optional(&state.db.pool)
+            .await?;
+    let (condition_hex, status) =
+        market.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+    if status == "resolved" || status == "cancelled" {
+        return Err(AppError::new(
+            StatusCode::CONFLICT,
+            "MARKET_FINALIZED",
+            format!("Market is {}; redeem positions instead", status),
+        ));
+    }
+    let condition_id: [u8; 32] = hex::decode(condition_hex.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| AppError::new(StatusCode::CONFLICT, "NO_CONDITION", "Market has no on-chain condition"))?;
+
+    let slot_count = client.get_outcome_slot_count(condition_id).await.map_err(chain_error)?;
+    if slot_count.is_zero() {
+        return Err(AppError::new(
+            StatusCode::CONFLICT,
+            "CONDITION_NOT_PREPARED",
+            "Condition is not prepared on-chain yet",
+        ));
+    }
+
+    let addresses = client.addresses();
+    let (yes, no) = (
+        TokenIdCalculator::calculate_index_set(true),
+        TokenIdCalculator::calculate_index_set(false),
+    );
+    let partition = vec![yes, no];
+
+    let (approval, data) = match kind {
+        PositionOpKind::Split => {
+            let balance = client.get_usdc_balance(owner).await.map_err(chain_error)?;
+            if balance < amount_units {
+                return Err(AppError::bad_request(
+                    "INSUFFICIENT_BALANCE",
+                    "Wallet collateral balance is below the amount",
+                ));
+            }
+            let allowance = client
+                .get_usdc_allowance(owner, addresses.conditional_tokens)
+                .await
+                .map_err(chain_error)?;
+            let approval = (allowance < amount_units)
+                .then(|| client.approve_usdc_calldata(addresses.conditional_tokens, amount_units))
+                .flatten()
+                .map(|data| transaction(addresses.usdc, data));
+            let data =
+                client.split_position_calldata(addresses.usdc, [0u8; 32], condition_id, partition.clone(), amount_units);
+            (approval, data)
+        }
+        PositionOpKind::Merge => {
+            for index_set in [yes, no] {
+                if position_balance(client, owner, condition_id, index_set).await? < amount_units {
+                    return Err(AppError::bad_request(
+                        "INSUFFICIENT_SHARES",
+                        "Wallet holds fewer full sets than the amount",
+                    ));
+                }
+            }
+            let data = client.merge_positions_calldata(
+                addresses.usdc,
+                [0u8; 32],
+                condition_id,
+                partition.clone(),
+                amount_units,
+            );
+            (None, data)
+        }
+    };
+    let data = data.ok_or_else(|| {
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "ENCODING_FAILED", "Failed to encode calldata")
+    })?;
+
+    let operation = ctf_position::record_prepared(
+        &state.db.pool,
+        &user_address,
+        req.market_id,
+        &condition_hex,
+        kind,
+        req.amount,
+    )
+    .await?;
+
+    Ok(PreparePositionOpResponse {
+        operation,
+        chain_id: client.chain_id(),
+        collateral_token: format!("{:?}", addresses.usdc),
+        condition_id: condition_hex,
+        partition: partition.iter().map(|p| p.as_u64()).collect(),
+        amount_units: amount_units.to_string(),
+        approval,
+        transaction: transaction(addresses.conditional_tokens, data),
+    })
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Prepare splitPosition calldata turning wallet collateral into Yes + No
+/// positions
+/// POST /ctf/split/prepare
+pub async fn prepare_split(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<PreparePositionOpRequest>,
+) -> Result<Json<PreparePositionOpResponse>, AppError> {
+    Ok(Json(prepare(&state, &auth_user, req, PositionOpKind::Split).await?))
+}
+
+/// Prepare mergePositions calldata turning Yes + No positions in the wallet
+/// back into collateral
+/// POST /ctf/merge/prepare
+pub async fn prepare_merge(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<PreparePositionOpRequest>,
+) -> Result<Json<PreparePositionOpResponse>, AppError> {
+    Ok(Json(prepare(&state, &auth_user, req, PositionOpKind::Merge).await?))
+}
+
+/// List the user's prepared and detected split / merge operations
+/// GET /ctf/operations?limit
+pub async fn list_operations(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<ListOpsQuery>,
+) -> Result<Json<PositionOpsResponse>, AppError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let operations = ctf_position::list(&state.db.pool, &auth_user.address, limit)
+        .await?;
+    Ok(Json(PositionOpsResponse { operations }))
+}
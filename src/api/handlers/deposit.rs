@@ -6,20 +6,18 @@ use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson};
 use crate::auth::middleware::AuthUser;
+use crate::services::event_bus::BusEvent;
 use crate::{AppState, BalanceUpdateEvent};
 
-// Error response type
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: String,
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct PrepareDepositRequest {
     pub token: String,
+    #[validate(custom = "validation::positive")]
     pub amount: Decimal,
 }
 
@@ -50,11 +48,13 @@ pub struct DepositRecord {
 pub async fn prepare_deposit(
     State(state): State<Arc<AppState>>,
     Extension(_auth_user): Extension<AuthUser>,
-    Json(req): Json<PrepareDepositRequest>,
-) -> Result<Json<PrepareDepositResponse>, StatusCode> {
+    ValidJson(req): ValidJson<PrepareDepositRequest>,
+) -> Result<Json<PrepareDepositResponse>, AppError> {
     // Get token address from config
-    let token_address = state.config.get_token_address(&req.token)
-        .ok_or(StatusCode::BAD_REQUEST)?;
+    let token_address = state
+        .config
+        .get_token_address(&req.token)
+        .ok_or_else(|| AppError::bad_request("UNKNOWN_TOKEN", format!("Unknown token: {}", req.token)))?;
 
     Ok(Json(PrepareDepositResponse {
         contract_address: state.config.vault_address.clone(),
@@ -68,7 +68,7 @@ pub async fn prepare_deposit(
 pub async fn get_history(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<DepositHistoryResponse>, StatusCode> {
+) -> Result<Json<DepositHistoryResponse>, AppError> {
     // Fetch deposit history from database
     let rows: Vec<(Uuid, String, Decimal, String, String, DateTime<Utc>)> = sqlx::query_as(
         r#"
@@ -79,13 +79,9 @@ pub async fn get_history(
         LIMIT 100
         "#
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch deposit history: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    .await?;
 
     let deposits: Vec<DepositRecord> = rows
         .into_iter()
@@ -108,8 +104,9 @@ pub async fn get_history(
 // Deposit Confirmation (for on-chain deposits)
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct ConfirmDepositRequest {
+    #[validate(custom = "validation::tx_hash")]
     pub tx_hash: String,
 }
 
@@ -126,17 +123,14 @@ pub struct ConfirmDepositResponse {
 pub async fn confirm_deposit(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Json(req): Json<ConfirmDepositRequest>,
-) -> Result<Json<ConfirmDepositResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+    ValidJson(req): ValidJson<ConfirmDepositRequest>,
+) -> Result<Json<ConfirmDepositResponse>, AppError> {
+    let user_address = auth_user.address.clone();
     let tx_hash = req.tx_hash.to_lowercase();
 
     // Validate tx_hash format
     if !tx_hash.starts_with("0x") || tx_hash.len() != 66 {
-        return Err((StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "Invalid transaction hash format".to_string(),
-            code: "INVALID_TX_HASH".to_string(),
-        })));
+        return Err(AppError::bad_request("INVALID_TX_HASH", "Invalid transaction hash format"));
     }
 
     // Check if this tx_hash is already processed
@@ -145,41 +139,22 @@ pub async fn confirm_deposit(
     )
     .bind(&tx_hash)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("DB error checking deposit: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Database error".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    .await?;
 
     if let Some((status,)) = existing {
         if status == "confirmed" {
-            return Err((StatusCode::BAD_REQUEST, Json(ErrorResponse {
-                error: "Deposit already confirmed".to_string(),
-                code: "ALREADY_CONFIRMED".to_string(),
-            })));
+            return Err(AppError::bad_request("ALREADY_CONFIRMED", "Deposit already confirmed"));
         }
     }
 
     // Get blockchain client
-    let blockchain_client = state.blockchain_client.as_ref()
-        .ok_or_else(|| {
-            (StatusCode::SERVICE_UNAVAILABLE, Json(ErrorResponse {
-                error: "Blockchain client not available".to_string(),
-                code: "NO_BLOCKCHAIN".to_string(),
-            }))
-        })?;
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
 
     // Parse vault address
     let vault_address: Address = state.config.vault_address.parse()
         .map_err(|_| {
             tracing::error!("Invalid vault address in config: {}", state.config.vault_address);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-                error: "Server configuration error".to_string(),
-                code: "CONFIG_ERROR".to_string(),
-            }))
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "CONFIG_ERROR", "Server configuration error")
         })?;
 
     // Verify the transaction on-chain
@@ -209,19 +184,12 @@ pub async fn confirm_deposit(
                 ("VERIFICATION_FAILED", &error_msg as &str)
             };
 
-            (StatusCode::BAD_REQUEST, Json(ErrorResponse {
-                error: message.to_string(),
-                code: code.to_string(),
-            }))
+            AppError::bad_request(code, message)
         })?;
 
     // Verify the sender matches the authenticated user
-    let sender_address = format!("{:?}", verified.from).to_lowercase();
-    if sender_address != user_address {
-        return Err((StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "Transaction sender does not match authenticated user".to_string(),
-            code: "SENDER_MISMATCH".to_string(),
-        })));
+    if crate::models::Address::from(verified.from) != user_address {
+        return Err(AppError::bad_request("SENDER_MISMATCH", "Transaction sender does not match authenticated user"));
     }
 
     // Convert amount from U256 (with 6 decimals for USDC) to Decimal
@@ -229,20 +197,11 @@ pub async fn confirm_deposit(
     let amount = Decimal::from(amount_u128) / Decimal::from(1_000_000u64); // 6 decimals
 
     if amount <= Decimal::ZERO {
-        return Err((StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "Invalid deposit amount".to_string(),
-            code: "INVALID_AMOUNT".to_string(),
-        })));
+        return Err(AppError::bad_request("INVALID_AMOUNT", "Invalid deposit amount"));
     }
 
     // Start database transaction
-    let mut db_tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Database error".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    let mut db_tx = state.db.pool.begin().await?;
 
     let deposit_id = Uuid::new_v4();
 
@@ -260,14 +219,7 @@ pub async fn confirm_deposit(
     .bind(&tx_hash)
     .bind(verified.block_number as i64)
     .execute(&mut *db_tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to insert deposit: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Failed to record deposit".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    .await?;
 
     // Update or insert balance
     let new_balance: Decimal = sqlx::query_scalar(
@@ -284,23 +236,10 @@ pub async fn confirm_deposit(
     .bind(&user_address)
     .bind(amount)
     .fetch_one(&mut *db_tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to update balance: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Failed to update balance".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    .await?;
 
     // Commit transaction
-    db_tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Transaction failed".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    db_tx.commit().await?;
 
     tracing::info!(
         "On-chain deposit confirmed: {} USDC from {} (tx: {}, block: {}, confirmations: {})",
@@ -308,14 +247,17 @@ pub async fn confirm_deposit(
     );
 
     // Broadcast balance update via WebSocket
-    let _ = state.balance_update_sender.send(BalanceUpdateEvent {
-        user_address: user_address.clone(),
-        token: "USDC".to_string(),
-        available: new_balance.to_string(),
-        frozen: "0".to_string(),
-        total: new_balance.to_string(),
-        event_type: "deposit".to_string(),
-    });
+    state
+        .event_bus
+        .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+            user_address: user_address.clone().into(),
+            token: "USDC".to_string(),
+            available: new_balance.to_string(),
+            frozen: "0".to_string(),
+            total: new_balance.to_string(),
+            event_type: "deposit".to_string(),
+        }))
+        .await;
 
     Ok(Json(ConfirmDepositResponse {
         deposit_id: deposit_id.to_string(),
@@ -329,8 +271,9 @@ pub async fn confirm_deposit(
 // Direct Deposit (for development/testing without on-chain transaction)
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct DirectDepositRequest {
+    #[validate(custom = "validation::positive")]
     pub amount: Decimal,
 }
 
@@ -349,34 +292,22 @@ pub struct DirectDepositResponse {
 pub async fn direct_deposit(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Json(req): Json<DirectDepositRequest>,
-) -> Result<Json<DirectDepositResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(req): ValidJson<DirectDepositRequest>,
+) -> Result<Json<DirectDepositResponse>, AppError> {
     // Only allow in development mode
     if state.config.environment != "development" {
-        return Err((StatusCode::FORBIDDEN, Json(ErrorResponse {
-            error: "Direct deposit only available in development mode".to_string(),
-            code: "FORBIDDEN".to_string(),
-        })));
+        return Err(AppError::forbidden("FORBIDDEN", "Direct deposit only available in development mode"));
     }
 
-    let user_address = auth_user.address.to_lowercase();
+    let user_address = auth_user.address.clone();
     let amount = req.amount;
 
     if amount <= Decimal::ZERO {
-        return Err((StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "Amount must be positive".to_string(),
-            code: "INVALID_AMOUNT".to_string(),
-        })));
+        return Err(AppError::bad_request("INVALID_AMOUNT", "Amount must be positive"));
     }
 
     // Start transaction
-    let mut tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Database error".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    let mut tx = state.db.pool.begin().await?;
 
     // Generate a fake tx_hash for the deposit record
     let deposit_id = Uuid::new_v4();
@@ -394,14 +325,7 @@ pub async fn direct_deposit(
     .bind(amount)
     .bind(&fake_tx_hash)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to insert deposit: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Failed to record deposit".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    .await?;
 
     // Update or insert balance
     let new_balance: Decimal = sqlx::query_scalar(
@@ -418,23 +342,10 @@ pub async fn direct_deposit(
     .bind(&user_address)
     .bind(amount)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to update balance: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Failed to update balance".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    .await?;
 
     // Commit transaction
-    tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Transaction failed".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    tx.commit().await?;
 
     tracing::info!(
         "Direct deposit: {} USDC credited to {} (new balance: {})",
@@ -442,14 +353,17 @@ pub async fn direct_deposit(
     );
 
     // Broadcast balance update via WebSocket
-    let _ = state.balance_update_sender.send(BalanceUpdateEvent {
-        user_address: user_address.clone(),
-        token: "USDC".to_string(),
-        available: new_balance.to_string(),
-        frozen: "0".to_string(), // Direct deposit doesn't affect frozen
-        total: new_balance.to_string(),
-        event_type: "deposit".to_string(),
-    });
+    state
+        .event_bus
+        .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+            user_address: user_address.clone().into(),
+            token: "USDC".to_string(),
+            available: new_balance.to_string(),
+            frozen: "0".to_string(), // Direct deposit doesn't affect frozen
+            total: new_balance.to_string(),
+            event_type: "deposit".to_string(),
+        }))
+        .await;
 
     Ok(Json(DirectDepositResponse {
         deposit_id: deposit_id.to_string(),
@@ -494,42 +408,17 @@ pub struct OnChainBalanceResponse {
 pub async fn get_onchain_balance(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<OnChainBalanceResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address: Address = auth_user.address.parse().map_err(|_| {
-        (StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "Invalid user address".to_string(),
-            code: "INVALID_ADDRESS".to_string(),
-        }))
-    })?;
+) -> Result<Json<OnChainBalanceResponse>, AppError> {
+    let user_address = auth_user.address.to_h160();
 
-    let blockchain_client = state.blockchain_client.as_ref()
-        .ok_or_else(|| {
-            (StatusCode::SERVICE_UNAVAILABLE, Json(ErrorResponse {
-                error: "Blockchain client not available".to_string(),
-                code: "NO_BLOCKCHAIN".to_string(),
-            }))
-        })?;
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
 
     // Get USDC balance
-    let usdc_balance = blockchain_client.get_usdc_balance(user_address).await
-        .map_err(|e| {
-            tracing::error!("Failed to get USDC balance: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-                error: "Failed to get on-chain balance".to_string(),
-                code: "BLOCKCHAIN_ERROR".to_string(),
-            }))
-        })?;
+    let usdc_balance = blockchain_client.get_usdc_balance(user_address).await?;
 
     // Get allowance for CTFExchange
     let ctf_exchange_address = blockchain_client.addresses().ctf_exchange;
-    let allowance = blockchain_client.get_usdc_allowance(user_address, ctf_exchange_address).await
-        .map_err(|e| {
-            tracing::error!("Failed to get USDC allowance: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-                error: "Failed to get allowance".to_string(),
-                code: "BLOCKCHAIN_ERROR".to_string(),
-            }))
-        })?;
+    let allowance = blockchain_client.get_usdc_allowance(user_address, ctf_exchange_address).await?;
 
     // Format with 6 decimals (USDC)
     let balance_formatted = format_usdc(usdc_balance);
@@ -547,7 +436,7 @@ pub async fn get_onchain_balance(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CheckAllowanceRequest {
     pub amount: String, // Amount to check (in USDC with 6 decimals)
 }
@@ -567,39 +456,18 @@ pub struct CheckAllowanceResponse {
 pub async fn check_allowance(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Json(req): Json<CheckAllowanceRequest>,
-) -> Result<Json<CheckAllowanceResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address: Address = auth_user.address.parse().map_err(|_| {
-        (StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "Invalid user address".to_string(),
-            code: "INVALID_ADDRESS".to_string(),
-        }))
-    })?;
+    ValidJson(req): ValidJson<CheckAllowanceRequest>,
+) -> Result<Json<CheckAllowanceResponse>, AppError> {
+    let user_address = auth_user.address.to_h160();
 
     let required_amount = U256::from_dec_str(&req.amount).map_err(|_| {
-        (StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "Invalid amount format".to_string(),
-            code: "INVALID_AMOUNT".to_string(),
-        }))
+        AppError::bad_request("INVALID_AMOUNT", "Invalid amount format")
     })?;
 
-    let blockchain_client = state.blockchain_client.as_ref()
-        .ok_or_else(|| {
-            (StatusCode::SERVICE_UNAVAILABLE, Json(ErrorResponse {
-                error: "Blockchain client not available".to_string(),
-                code: "NO_BLOCKCHAIN".to_string(),
-            }))
-        })?;
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
 
     let ctf_exchange_address = blockchain_client.addresses().ctf_exchange;
-    let current_allowance = blockchain_client.get_usdc_allowance(user_address, ctf_exchange_address).await
-        .map_err(|e| {
-            tracing::error!("Failed to get USDC allowance: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-                error: "Failed to get allowance".to_string(),
-                code: "BLOCKCHAIN_ERROR".to_string(),
-            }))
-        })?;
+    let current_allowance = blockchain_client.get_usdc_allowance(user_address, ctf_exchange_address).await?;
 
     let is_sufficient = current_allowance >= required_amount;
 
@@ -630,8 +498,8 @@ fn format_usdc(amount: U256) -> String {
 pub async fn get_balance(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<BalancesResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+) -> Result<Json<BalancesResponse>, AppError> {
+    let user_address = auth_user.address.clone();
 
     let rows: Vec<(String, Decimal, Decimal)> = sqlx::query_as(
         r#"
@@ -642,14 +510,7 @@ pub async fn get_balance(
     )
     .bind(&user_address)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch balances: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-            error: "Database error".to_string(),
-            code: "DB_ERROR".to_string(),
-        }))
-    })?;
+    .await?;
 
     let balances: Vec<BalanceResponse> = rows
         .into_iter()
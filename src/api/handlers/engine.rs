@@ -0,0 +1,115 @@
+//! Matching Engine Admin Handlers
+
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::services::matching::{MarketHalt, ShardStats};
+use crate::services::order_gateway::OrderSourceStats;
+use crate::services::stale_orders::{CleanupReport, CleanupRun};
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct ShardsResponse {
+    /// 0 = unsharded (orders match on the request's tokio worker)
+    pub shard_count: usize,
+    pub shards: Vec<ShardStats>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct OrderSourcesQuery {
+    /// Look-back window in days (default 7)
+    #[validate(range(min = 1))]
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderSourcesResponse {
+    pub days: i64,
+    pub sources: Vec<OrderSourceStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HaltsResponse {
+    pub halts: Vec<MarketHalt>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResumeTradingRequest {
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+/// Engine shard queue depths and throughput (Admin only)
+/// GET /admin/engine/shards
+pub async fn get_shards(State(state): State<Arc<AppState>>) -> Json<ShardsResponse> {
+    Json(ShardsResponse {
+        shard_count: state.engine_shards.shard_count(),
+        shards: state.engine_shards.stats(),
+    })
+}
+
+/// Orders, fill volume and fees per order source - users vs. the built-in
+/// market maker and settlement (Admin only)
+/// GET /admin/engine/order-sources
+pub async fn get_order_sources(
+    State(state): State<Arc<AppState>>,
+    ValidQuery(query): ValidQuery<OrderSourcesQuery>,
+) -> Result<Json<OrderSourcesResponse>, AppError> {
+    let days = query.days.unwrap_or(7).clamp(1, 365);
+    let sources = state
+        .order_gateway
+        .source_stats(Utc::now() - Duration::days(days))
+        .await?;
+    Ok(Json(OrderSourcesResponse { days, sources }))
+}
+
+/// Orders cancelled for sitting open on resolved or cancelled markets since
+/// this process started (Admin only)
+/// GET /admin/engine/stale-orders
+pub async fn get_stale_orders(State(state): State<Arc<AppState>>) -> Json<CleanupReport> {
+    Json(state.stale_orders.report())
+}
+
+/// Run the stale order cleanup now (Admin only)
+/// POST /admin/engine/stale-orders/sweep
+pub async fn sweep_stale_orders(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CleanupRun>, AppError> {
+    Ok(Json(state.stale_orders.sweep().await?))
+}
+
+/// Markets halted by the circuit breaker (Admin only)
+/// GET /admin/engine/halts
+pub async fn get_halts(State(state): State<Arc<AppState>>) -> Json<HaltsResponse> {
+    Json(HaltsResponse {
+        halts: state.circuit_breaker.halts(),
+    })
+}
+
+/// Lift a circuit breaker halt so the market trades again (Admin only)
+/// POST /admin/markets/:market_id/resume-trading
+pub async fn resume_trading(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(market_id): Path<Uuid>,
+    ValidJson(req): ValidJson<ResumeTradingRequest>,
+) -> Result<Json<MarketHalt>, AppError> {
+    let halt = state
+        .circuit_breaker
+        .resume(market_id, &auth_user.address, &req.reason)
+        .await
+        .ok_or_else(|| {
+            AppError::not_found("MARKET_NOT_HALTED", format!("Market {} is not halted", market_id))
+        })?;
+    Ok(Json(halt))
+}
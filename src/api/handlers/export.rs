@@ -0,0 +1,140 @@
+//! Public Data Export Handlers
+//!
+//! The manifest lists every exported dataset object with its size, row count
+//! and checksum so consumers can sync the bucket without touching the API
+//! again. Admins can trigger (or re-run) the export for a specific date.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::services::export::{ExportError, ExportedObject};
+use crate::AppState;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ManifestQuery {
+    /// Only entries for this date (YYYY-MM-DD)
+    pub date: Option<NaiveDate>,
+    /// Only entries for this dataset
+    pub dataset: Option<String>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RunExportRequest {
+    /// UTC day to export; defaults to yesterday
+    pub date: Option<NaiveDate>,
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ManifestEntry {
+    pub date: NaiveDate,
+    pub dataset: String,
+    pub format: String,
+    pub object_key: String,
+    pub row_count: i64,
+    pub size_bytes: i64,
+    pub sha256: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Public download URL when `EXPORT_PUBLIC_BASE_URL` is set
+    #[sqlx(skip)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestResponse {
+    pub exports: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunExportResponse {
+    pub date: NaiveDate,
+    pub objects: Vec<ExportedObject>,
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// List exported datasets
+/// GET /exports/manifest
+pub async fn get_manifest(
+    State(state): State<Arc<AppState>>,
+    ValidQuery(query): ValidQuery<ManifestQuery>,
+) -> Result<Json<ManifestResponse>, AppError> {
+    let limit = query.limit.unwrap_or(300).clamp(1, 1000);
+
+    let mut exports: Vec<ManifestEntry> = sqlx::query_as(
+        r#"
+        SELECT export_date AS date, dataset, format, object_key, row_count, size_bytes, sha256, created_at
+        FROM data_exports
+        WHERE status = 'completed'
+          AND ($1::date IS NULL OR export_date = $1)
+          AND ($2::text IS NULL OR dataset = $2)
+        ORDER BY export_date DESC, dataset, format
+        LIMIT $3
+        "#,
+    )
+    .bind(query.date)
+    .bind(&query.dataset)
+    .bind(limit)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    if let Some(base) = &state.config.export_public_base_url {
+        let base = base.trim_end_matches('/');
+        for entry in &mut exports {
+            entry.url = Some(format!("{}/{}", base, entry.object_key));
+        }
+    }
+
+    Ok(Json(ManifestResponse { exports }))
+}
+
+/// Run the export for a date (admin)
+/// POST /admin/exports/run
+pub async fn run_export(
+    State(state): State<Arc<AppState>>,
+    ValidJson(req): ValidJson<RunExportRequest>,
+) -> Result<Json<RunExportResponse>, AppError> {
+    let today = Utc::now().date_naive();
+    let date = match req.date {
+        Some(d) => d,
+        None => today.pred_opt().unwrap_or(today),
+    };
+    if date > today {
+        return Err(AppError::bad_request("INVALID_DATE", "Cannot export a future date"));
+    }
+
+    let objects = state.data_exporter.export_date(date).await.map_err(|e| match e {
+        ExportError::NotConfigured => AppError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "EXPORT_NOT_CONFIGURED",
+            "Export storage is not configured",
+        ),
+        ExportError::Database(e) => e.into(),
+        other => {
+            tracing::error!("Export for {} failed: {}", date, other);
+            AppError::new(StatusCode::BAD_GATEWAY, "EXPORT_FAILED", other.to_string())
+        }
+    })?;
+
+    Ok(Json(RunExportResponse { date, objects }))
+}
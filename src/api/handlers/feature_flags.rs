@@ -0,0 +1,197 @@
+//! Feature Flag Handlers
+//!
+//! Admins create and tune flags; signed-in clients fetch their evaluated
+//! flag set so the frontend can hide gated features.
+
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::ValidJson;
+use crate::auth::middleware::AuthUser;
+use crate::services::feature_flags::{FeatureFlag, FLAG_COLUMNS};
+use crate::services::system_events::{self, SystemEventKind};
+use crate::AppState;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+/// Create or partially update a flag; omitted fields keep their current
+/// value (or the default for a new flag)
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpsertFlagRequest {
+    pub description: Option<String>,
+    pub enabled: Option<bool>,
+    pub environments: Option<Vec<String>>,
+    #[validate(range(min = 0, max = 100))]
+    pub rollout_percentage: Option<i16>,
+    pub allowed_users: Option<Vec<String>>,
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct FlagsResponse {
+    pub flags: Vec<FeatureFlag>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EvaluatedFlagsResponse {
+    pub environment: String,
+    pub flags: BTreeMap<String, bool>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= 64 && key.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// Make a write visible on this replica now; others pick it up on refresh
+async fn reload(state: &AppState) {
+    if let Err(e) = state.feature_flags.reload().await {
+        tracing::warn!("Failed to reload feature flags after update: {}", e);
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Flags evaluated for the current user
+/// GET /feature-flags
+pub async fn get_my_flags(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Json<EvaluatedFlagsResponse> {
+    Json(EvaluatedFlagsResponse {
+        environment: state.config.environment.clone(),
+        flags: state.feature_flags.evaluate_all(Some(auth_user.address.as_str())),
+    })
+}
+
+/// List all flags (Admin only)
+/// GET /admin/feature-flags
+pub async fn list_flags(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<FlagsResponse>, AppError> {
+    let flags: Vec<FeatureFlag> = sqlx::query_as(&format!("SELECT {} FROM feature_flags ORDER BY key", FLAG_COLUMNS))
+        .fetch_all(&state.db.pool)
+        .await?;
+
+    Ok(Json(FlagsResponse { flags }))
+}
+
+/// Create or update a flag (Admin only)
+/// PUT /admin/feature-flags/:key
+pub async fn upsert_flag(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(key): Path<String>,
+    ValidJson(req): ValidJson<UpsertFlagRequest>,
+) -> Result<Json<FeatureFlag>, AppError> {
+    if !is_valid_key(&key) {
+        return Err(AppError::bad_request(
+            "INVALID_KEY",
+            "key must be 1-64 characters of a-z, 0-9 and _",
+        ));
+    }
+    if req.rollout_percentage.is_some_and(|p| !(0..=100).contains(&p)) {
+        return Err(AppError::bad_request(
+            "INVALID_ROLLOUT",
+            "rollout_percentage must be between 0 and 100",
+        ));
+    }
+    let allowed_users = req
+        .allowed_users
+        .map(|users| users.iter().map(|u| u.trim().to_lowercase()).collect::<Vec<_>>());
+
+    let flag: FeatureFlag = sqlx::query_as(&format!(
+        r#"
+        INSERT INTO feature_flags (key, description, enabled, environments, rollout_percentage, allowed_users, updated_by)
+        VALUES ($1, COALESCE($2, ''), COALESCE($3, FALSE), COALESCE($4, '{{}}'), COALESCE($5, 0), COALESCE($6, '{{}}'), $7)
+        ON CONFLICT (key) DO UPDATE SET
+            description = COALESCE($2, feature_flags.description),
+            enabled = COALESCE($3, feature_flags.enabled),
+            environments = COALESCE($4, feature_flags.environments),
+            rollout_percentage = COALESCE($5, feature_flags.rollout_percentage),
+            allowed_users = COALESCE($6, feature_flags.allowed_users),
+            updated_by = $7
+        RETURNING {}
+        "#,
+        FLAG_COLUMNS
+    ))
+    .bind(&key)
+    .bind(&req.description)
+    .bind(req.enabled)
+    .bind(&req.environments)
+    .bind(req.rollout_percentage)
+    .bind(&allowed_users)
+    .bind(&auth_user.address)
+    .fetch_one(&state.db.pool)
+    .await?;
+
+    tracing::info!(
+        "Feature flag {} set by {}: enabled={}, rollout={}%, environments={:?}",
+        flag.key,
+        auth_user.address,
+        flag.enabled,
+        flag.rollout_percentage,
+        flag.environments
+    );
+    system_events::record(
+        &state.db.pool,
+        SystemEventKind::FeatureFlagChanged,
+        Some(&flag.key),
+        format!("Feature flag set by {}", auth_user.address),
+        serde_json::json!({
+            "enabled": flag.enabled,
+            "rollout_percentage": flag.rollout_percentage,
+            "environments": flag.environments,
+            "allowed_users": flag.allowed_users.len(),
+        }),
+    )
+    .await;
+    reload(&state).await;
+
+    Ok(Json(flag))
+}
+
+/// Delete a flag; known flags revert to their built-in default (Admin only)
+/// DELETE /admin/feature-flags/:key
+pub async fn delete_flag(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(key): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let result = sqlx::query("DELETE FROM feature_flags WHERE key = $1")
+        .bind(&key)
+        .execute(&state.db.pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("FLAG_NOT_FOUND", "Feature flag not found"));
+    }
+    system_events::record(
+        &state.db.pool,
+        SystemEventKind::FeatureFlagDeleted,
+        Some(&key),
+        format!("Feature flag deleted by {}", auth_user.address),
+        serde_json::json!({}),
+    )
+    .await;
+    reload(&state).await;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
@@ -0,0 +1,101 @@
+//! Market Leaderboard Handlers
+//!
+//! Publishes the rewards scoring job's per-market maker volume, taker
+//! volume and time at the best quote so liquidity incentive campaigns can
+//! be checked by participants. Users choose whether their address is shown.
+
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::services::rewards::{self, Leaderboard, LeaderboardSort};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct LeaderboardQuery {
+    /// maker_volume (default), taker_volume or time_at_best
+    #[serde(default)]
+    pub sort: LeaderboardSort,
+    /// Entries to return (default 50, max 100)
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateDisplayRequest {
+    /// Show the user's address on market leaderboards
+    pub display: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DisplayResponse {
+    pub display: bool,
+}
+
+fn user_not_found() -> AppError {
+    AppError::not_found("USER_NOT_FOUND", "User not found")
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Maker/taker volume and time at the best quote per address
+/// GET /markets/:market_id/leaderboard
+pub async fn get_market_leaderboard(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<LeaderboardQuery>,
+) -> Result<Json<Leaderboard>, AppError> {
+    let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM markets WHERE id = $1")
+        .bind(market_id)
+        .fetch_optional(&state.db.pool)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::not_found("MARKET_NOT_FOUND", "Market not found"));
+    }
+
+    let limit = query.limit.unwrap_or(50).min(100);
+    let leaderboard = rewards::leaderboard(&state.db.pool, market_id, query.sort, limit)
+        .await?;
+    Ok(Json(leaderboard))
+}
+
+/// Whether the user's address is shown on leaderboards
+/// GET /account/leaderboard
+pub async fn get_display(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<DisplayResponse>, AppError> {
+    let display = rewards::display(&state.db.pool, &auth_user.address)
+        .await?
+        .ok_or_else(user_not_found)?;
+    Ok(Json(DisplayResponse { display }))
+}
+
+/// Opt in to or out of being shown on leaderboards
+/// PUT /account/leaderboard
+pub async fn update_display(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<UpdateDisplayRequest>,
+) -> Result<Json<DisplayResponse>, AppError> {
+    if !rewards::set_display(&state.db.pool, &auth_user.address, req.display)
+        .await?
+    {
+        return Err(user_not_found());
+    }
+    Ok(Json(DisplayResponse { display: req.display }))
+}
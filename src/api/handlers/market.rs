@@ -3,35 +3,47 @@
 //! Provides endpoints for listing markets, getting orderbooks, trades, and prices.
 
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Path, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
 use crate::models::market::ShareType;
+use crate::models::{Address, OrderSide};
+use crate::services::matching::{Quote, Side as MatchingSide};
+use crate::services::matching::precision::{self, SharePrecision};
+use crate::services::channel_gateway::ChannelEventType;
+use crate::services::event_bus::{BusEvent, MarketStatusEvent};
+use crate::services::liquidity::MarketLiquidity;
+use crate::services::market_archive::ArchiveSummary;
+use crate::services::neg_risk;
+use crate::services::resolution_evidence::{self, ResolutionEvidence, ResolutionMethod};
+use crate::services::system_events::{self, SystemEventKind};
+use crate::services::trading_calendar::{self, TradingHours, TradingSchedule};
 use crate::AppState;
 
 // ============================================================================
 // Response Types
 // ============================================================================
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: String,
-}
-
 /// Outcome information for a prediction market
 #[derive(Debug, Serialize)]
 pub struct OutcomeInfo {
     pub id: Uuid,
     pub name: String,
     pub probability: Decimal,
+    /// Time-weighted probability over the last hour / 24 hours
+    pub twap_1h: Option<Decimal>,
+    pub twap_24h: Option<Decimal>,
 }
 
 /// Prediction market information
@@ -49,6 +61,40 @@ pub struct MarketInfo {
     pub total_volume: Decimal,
     pub liquidity: Decimal,
     pub created_at: i64,
+    /// Present when the market only trades during set hours
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trading_hours: Option<TradingHours>,
+}
+
+/// Row of the market listing joined with its summary
+#[derive(Debug, sqlx::FromRow)]
+struct MarketListRow {
+    id: Uuid,
+    question: String,
+    description: Option<String>,
+    category: String,
+    status: String,
+    resolution_source: Option<String>,
+    end_time: Option<DateTime<Utc>>,
+    volume_24h: Decimal,
+    total_volume: Decimal,
+    outcomes: sqlx::types::Json<Vec<SummaryOutcome>>,
+    created_at: DateTime<Utc>,
+}
+
+/// id, name, probability, twap_1h, twap_24h of an outcome
+type OutcomeRow = (Uuid, String, Decimal, Option<Decimal>, Option<Decimal>);
+
+/// Outcome as stored in `market_summaries.outcomes`
+#[derive(Debug, Deserialize)]
+struct SummaryOutcome {
+    id: Uuid,
+    name: String,
+    probability: Decimal,
+    #[serde(default)]
+    twap_1h: Option<Decimal>,
+    #[serde(default)]
+    twap_24h: Option<Decimal>,
 }
 
 #[derive(Debug, Serialize)]
@@ -57,7 +103,7 @@ pub struct MarketsResponse {
     pub total: i64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct MarketsQuery {
     /// Search query (searches question and description)
     pub q: Option<String>,
@@ -74,8 +120,10 @@ pub struct MarketsQuery {
     /// Filter by end_time after (timestamp)
     pub ends_after: Option<i64>,
     /// Page limit
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
     pub limit: Option<i64>,
     /// Page offset
+    #[validate(range(min = 0))]
     pub offset: Option<i64>,
 }
 
@@ -94,7 +142,30 @@ pub struct OrderbookResponse {
     pub share_type: ShareType,
     pub bids: Vec<OrderbookLevel>,
     pub asks: Vec<OrderbookLevel>,
+    /// Last orderbook update of the book numbered before this snapshot;
+    /// WebSocket updates with a higher `sequence` continue it
+    pub sequence: u64,
+    /// Last trade of the book numbered before this snapshot (`market_sequence`)
+    pub trade_sequence: u64,
     pub timestamp: i64,
+    /// Final stats once the market is resolved or cancelled (books are empty)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveSummary>,
+}
+
+/// Orderbook of a market outcome as it stood at a past time
+#[derive(Debug, Serialize)]
+pub struct OrderbookHistoryResponse {
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: ShareType,
+    pub bids: Vec<OrderbookLevel>,
+    pub asks: Vec<OrderbookLevel>,
+    pub last_price: Option<Decimal>,
+    /// Requested time (milliseconds)
+    pub at: i64,
+    /// When the snapshot in effect at `at` was taken (milliseconds)
+    pub snapshot_at: i64,
 }
 
 /// Trade record
@@ -104,15 +175,82 @@ pub struct TradeInfo {
     pub price: Decimal,
     pub amount: Decimal,
     pub side: String,
+    /// Taker side; same as `side`
+    pub aggressor_side: String,
+    /// "normal", "mint" or "merge"
+    pub match_type: String,
     pub share_type: ShareType,
+    /// Engine trade sequence number (absent for older trades)
+    pub sequence: Option<i64>,
+    pub is_block_trade: bool,
+    pub is_rfq: bool,
     pub timestamp: i64,
 }
 
+/// `trades` columns served by the trades endpoints
+#[derive(Debug, sqlx::FromRow)]
+struct TradeRow {
+    id: Uuid,
+    price: Decimal,
+    amount: Decimal,
+    side: String,
+    match_type: String,
+    share_type: String,
+    sequence: Option<i64>,
+    is_block_trade: bool,
+    is_rfq: bool,
+    created_at: DateTime<Utc>,
+}
+
+impl From<TradeRow> for TradeInfo {
+    fn from(row: TradeRow) -> Self {
+        Self {
+            id: row.id,
+            price: row.price,
+            amount: row.amount,
+            aggressor_side: row.side.clone(),
+            side: row.side,
+            match_type: row.match_type,
+            share_type: row.share_type.parse().unwrap_or(ShareType::Yes),
+            sequence: row.sequence,
+            is_block_trade: row.is_block_trade,
+            is_rfq: row.is_rfq,
+            timestamp: row.created_at.timestamp_millis(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct TradesResponse {
     pub market_id: Uuid,
     pub outcome_id: Uuid,
     pub trades: Vec<TradeInfo>,
+    /// Final stats once the market is resolved or cancelled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveSummary>,
+}
+
+/// One trade on a market's tape
+#[derive(Debug, Serialize)]
+pub struct TapeTrade {
+    pub id: String,
+    pub outcome_id: String,
+    pub share_type: String,
+    pub match_type: String,
+    pub aggressor_side: String,
+    pub price: String,
+    pub amount: String,
+    pub sequence: u64,
+    pub is_block_trade: bool,
+    pub is_rfq: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TapeResponse {
+    pub market_id: Uuid,
+    /// Highest sequence first
+    pub trades: Vec<TapeTrade>,
 }
 
 /// Price/ticker information for a market
@@ -131,18 +269,52 @@ pub struct OutcomeTicker {
     pub yes_price: Decimal,
     pub no_price: Decimal,
     pub probability: Decimal,
+    /// Time-weighted probability over the last hour / 24 hours
+    pub twap_1h: Option<Decimal>,
+    pub twap_24h: Option<Decimal>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct OrderbookQuery {
     pub outcome_id: Uuid,
     pub share_type: Option<String>,
+    #[validate(range(min = 1))]
     pub depth: Option<usize>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
+pub struct OrderbookHistoryQuery {
+    pub outcome_id: Uuid,
+    pub share_type: Option<String>,
+    /// Point in time (unix milliseconds)
+    pub at: i64,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TapeQuery {
+    /// Only trades with a lower sequence number (pagination)
+    pub before: Option<u64>,
+    #[validate(range(min = 1))]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct QuoteQuery {
+    pub side: OrderSide,
+    /// Share type to trade (default yes)
+    pub outcome: Option<ShareType>,
+    #[validate(custom = "validation::positive")]
+    pub size: Decimal,
+    /// Defaults to the market's Yes outcome
+    pub outcome_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
 pub struct TradesQuery {
     pub outcome_id: Uuid,
+    /// Share type for archived market stats (default yes)
+    pub share_type: Option<String>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
     pub limit: Option<i64>,
 }
 
@@ -165,8 +337,8 @@ pub struct TradesQuery {
 /// - offset: Page offset
 pub async fn list_markets(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<MarketsQuery>,
-) -> Result<Json<MarketsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidQuery(query): ValidQuery<MarketsQuery>,
+) -> Result<Json<MarketsResponse>, AppError> {
     let limit = query.limit.unwrap_or(50).min(100);
     let offset = query.offset.unwrap_or(0);
 
@@ -187,40 +359,40 @@ pub async fn list_markets(
         (Some("created"), _) => "m.created_at DESC",
         (Some("end_time"), Some("asc")) => "m.end_time ASC NULLS LAST",
         (Some("end_time"), _) => "m.end_time DESC NULLS LAST",
-        (Some("volume"), Some("asc")) => "m.volume_24h ASC",
-        (_, Some("asc")) => "m.volume_24h ASC",
-        _ => "m.volume_24h DESC", // default
+        (Some("volume"), Some("asc")) => "COALESCE(s.volume_24h, 0) ASC",
+        (_, Some("asc")) => "COALESCE(s.volume_24h, 0) ASC",
+        _ => "COALESCE(s.volume_24h, 0) DESC", // default
     };
 
-    // Query markets from database with search and filters
+    // Page and total are read from one snapshot so they always agree
+    let mut tx = state.db.pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    // Volume and outcomes come precomputed from market_summaries
     let query_str = format!(
         r#"
-        SELECT m.id, m.question, m.description, m.category, m.status::text,
-               m.resolution_source, m.end_time, m.volume_24h, m.total_volume, m.created_at
+        SELECT m.id, m.question, m.description, m.category, m.status::text AS status,
+               m.resolution_source, m.end_time,
+               COALESCE(s.volume_24h, 0) AS volume_24h,
+               COALESCE(s.total_volume, 0) AS total_volume,
+               COALESCE(s.outcomes, '[]'::jsonb) AS outcomes,
+               m.created_at
         FROM markets m
+        LEFT JOIN market_summaries s ON s.market_id = m.id
         WHERE ($1::text IS NULL OR (m.question ILIKE $1 OR m.description ILIKE $1))
         AND ($2::text IS NULL OR m.category = $2)
         AND ($3::text IS NULL OR m.status::text = $3)
         AND ($4::timestamptz IS NULL OR m.end_time < $4)
         AND ($5::timestamptz IS NULL OR m.end_time > $5)
-        ORDER BY {}
+        ORDER BY {}, m.id
         LIMIT $6 OFFSET $7
         "#,
         order_clause
     );
 
-    let markets_data: Vec<(
-        Uuid,
-        String,
-        Option<String>,
-        String,
-        String,
-        Option<String>,
-        Option<DateTime<Utc>>,
-        Decimal,
-        Decimal,
-        DateTime<Utc>,
-    )> = sqlx::query_as(&query_str)
+    let rows: Vec<MarketListRow> = sqlx::query_as(&query_str)
         .bind(search_pattern.as_ref())
         .bind(query.category.as_ref())
         .bind(query.status.as_ref())
@@ -228,21 +400,8 @@ pub async fn list_markets(
         .bind(ends_after)
         .bind(limit)
         .bind(offset)
-        .fetch_all(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch markets: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to fetch markets".to_string(),
-                    code: "MARKET_FETCH_FAILED".to_string(),
-                }),
-            )
-        })?;
-
-    // Prepare search pattern for count query
-    let search_pattern_count = query.q.as_ref().map(|q| format!("%{}%", q));
+        .fetch_all(&mut *tx)
+        .await?;
 
     // Get total count with same filters
     let total: (i64,) = sqlx::query_as(
@@ -255,68 +414,47 @@ pub async fn list_markets(
         AND ($5::timestamptz IS NULL OR m.end_time > $5)
         "#,
     )
-    .bind(search_pattern_count.as_ref())
+    .bind(search_pattern.as_ref())
     .bind(query.category.as_ref())
     .bind(query.status.as_ref())
     .bind(ends_before)
     .bind(ends_after)
-    .fetch_one(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to count markets: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to count markets".to_string(),
-                code: "MARKET_COUNT_FAILED".to_string(),
-            }),
-        )
-    })?;
-
-    let mut markets = Vec::new();
+    .fetch_one(&mut *tx)
+    .await?;
 
-    for (id, question, description, category, status, resolution_source, end_time, volume_24h, total_volume, created_at) in markets_data {
-        // Get outcomes for each market
-        let outcomes_data: Vec<(Uuid, String, Decimal)> = sqlx::query_as(
-            r#"
-            SELECT id, name, probability
-            FROM outcomes
-            WHERE market_id = $1
-            ORDER BY name
-            "#,
-        )
-        .bind(id)
-        .fetch_all(&state.db.pool)
-        .await
-        .unwrap_or_default();
+    // Read-only; nothing to commit
+    drop(tx);
 
-        let outcomes: Vec<OutcomeInfo> = outcomes_data
-            .into_iter()
-            .map(|(oid, name, probability)| OutcomeInfo {
-                id: oid,
-                name,
-                probability,
-            })
-            .collect();
-
-        // Calculate liquidity (sum of orderbook depth)
-        let liquidity = Decimal::ZERO; // TODO: Calculate from orderbook
-
-        markets.push(MarketInfo {
-            id,
-            question,
-            description,
-            category,
-            outcomes,
-            status,
-            resolution_source,
-            end_time: end_time.map(|t| t.timestamp_millis()),
-            volume_24h,
-            total_volume,
-            liquidity,
-            created_at: created_at.timestamp_millis(),
-        });
-    }
+    let markets = rows
+        .into_iter()
+        .map(|row| MarketInfo {
+            id: row.id,
+            question: row.question,
+            description: row.description,
+            category: row.category,
+            outcomes: row
+                .outcomes
+                .0
+                .into_iter()
+                .map(|o| OutcomeInfo {
+                    id: o.id,
+                    name: o.name,
+                    probability: o.probability,
+                    twap_1h: o.twap_1h,
+                    twap_24h: o.twap_24h,
+                })
+                .collect(),
+            status: row.status,
+            resolution_source: row.resolution_source,
+            end_time: row.end_time.map(|t| t.timestamp_millis()),
+            volume_24h: row.volume_24h,
+            total_volume: row.total_volume,
+            // Calculate liquidity (sum of orderbook depth)
+            liquidity: Decimal::ZERO, // TODO: Calculate from orderbook
+            created_at: row.created_at.timestamp_millis(),
+            trading_hours: None,
+        })
+        .collect();
 
     Ok(Json(MarketsResponse {
         markets,
@@ -329,8 +467,8 @@ pub async fn list_markets(
 pub async fn get_orderbook(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-    Query(query): Query<OrderbookQuery>,
-) -> Result<Json<OrderbookResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidQuery(query): ValidQuery<OrderbookQuery>,
+) -> Result<Json<OrderbookResponse>, AppError> {
     let depth = query.depth.unwrap_or(20).min(100);
     let share_type: ShareType = query
         .share_type
@@ -342,30 +480,31 @@ pub async fn get_orderbook(
     let market_exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM markets WHERE id = $1")
         .bind(market_id)
         .fetch_optional(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to check market: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to check market".to_string(),
-                    code: "MARKET_CHECK_FAILED".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     if market_exists.is_none() {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Market not found".to_string(),
-                code: "MARKET_NOT_FOUND".to_string(),
-            }),
-        ));
+        return Err(AppError::not_found("MARKET_NOT_FOUND", "Market not found"));
     }
 
     // Build orderbook key for matching engine
     let orderbook_key = format!("{}:{}:{}", market_id, query.outcome_id, share_type);
+    // Read before the book, so the snapshot is at least as new
+    let sequence = state.matching_engine.market_sequence(&orderbook_key);
+
+    // Closed markets have no live book
+    if let Some(archive) = archive_summary(&state, market_id, query.outcome_id, share_type).await? {
+        return Ok(Json(OrderbookResponse {
+            market_id,
+            outcome_id: query.outcome_id,
+            share_type,
+            bids: vec![],
+            asks: vec![],
+            sequence: sequence.orderbook,
+            trade_sequence: sequence.trade,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            archive: Some(archive),
+        }));
+    }
 
     // Try to get orderbook from matching engine
     match state.matching_engine.get_orderbook(&orderbook_key, depth) {
@@ -387,7 +526,10 @@ pub async fn get_orderbook(
                 share_type,
                 bids,
                 asks,
+                sequence: sequence.orderbook,
+                trade_sequence: sequence.trade,
                 timestamp: snapshot.timestamp,
+                archive: None,
             }))
         }
         Err(_) => {
@@ -398,27 +540,86 @@ pub async fn get_orderbook(
                 share_type,
                 bids: vec![],
                 asks: vec![],
+                sequence: sequence.orderbook,
+                trade_sequence: sequence.trade,
                 timestamp: chrono::Utc::now().timestamp_millis(),
+                archive: None,
             }))
         }
     }
 }
 
+/// Get the orderbook as it stood at a past time (latest snapshot at or before `at`)
+/// GET /markets/:market_id/orderbook/history
+pub async fn get_orderbook_history(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<OrderbookHistoryQuery>,
+) -> Result<Json<OrderbookHistoryResponse>, AppError> {
+    let share_type: ShareType = query
+        .share_type
+        .as_ref()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(ShareType::Yes);
+
+    let at = DateTime::<Utc>::from_timestamp_millis(query.at)
+        .ok_or_else(|| AppError::bad_request("INVALID_TIMESTAMP", "Invalid timestamp"))?;
+
+    let snapshot = state
+        .orderbook_history
+        .at(market_id, query.outcome_id, share_type, at)
+        .await?
+        .ok_or_else(|| AppError::not_found("SNAPSHOT_NOT_FOUND", "No orderbook snapshot at or before this time"))?;
+
+    let to_levels = |levels: Vec<[String; 2]>| -> Vec<OrderbookLevel> {
+        levels
+            .into_iter()
+            .map(|[price, amount]| OrderbookLevel { price, amount })
+            .collect()
+    };
+
+    Ok(Json(OrderbookHistoryResponse {
+        market_id,
+        outcome_id: query.outcome_id,
+        share_type,
+        bids: to_levels(snapshot.bids.0),
+        asks: to_levels(snapshot.asks.0),
+        last_price: snapshot.last_price,
+        at: query.at,
+        snapshot_at: snapshot.captured_at.timestamp_millis(),
+    }))
+}
+
+/// Final stats for an archived (resolved/cancelled) market, `None` while it trades
+async fn archive_summary(
+    state: &AppState,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    share_type: ShareType,
+) -> Result<Option<ArchiveSummary>, AppError> {
+    state
+        .market_archiver
+        .summary(market_id, outcome_id, share_type)
+        .await
+        .map_err(AppError::from)
+}
+
 /// Get recent trades for a market outcome
 /// GET /markets/:market_id/trades
 pub async fn get_trades(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-    Query(query): Query<TradesQuery>,
-) -> Result<Json<TradesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidQuery(query): ValidQuery<TradesQuery>,
+) -> Result<Json<TradesResponse>, AppError> {
     let limit = query.limit.unwrap_or(50).min(100);
 
-    let rows: Vec<(Uuid, Decimal, Decimal, String, String, DateTime<Utc>)> = sqlx::query_as(
+    let rows: Vec<TradeRow> = sqlx::query_as(
         r#"
-        SELECT id, price, amount, side::text, share_type::text, created_at
+        SELECT id, price, amount, side::text, match_type::text, share_type::text,
+               sequence, is_block_trade, is_rfq, created_at
         FROM trades
         WHERE market_id = $1 AND outcome_id = $2
-        ORDER BY created_at DESC
+        ORDER BY created_at DESC, sequence DESC NULLS LAST
         LIMIT $3
         "#,
     )
@@ -426,75 +627,76 @@ pub async fn get_trades(
     .bind(query.outcome_id)
     .bind(limit)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch trades: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch trades".to_string(),
-                code: "TRADES_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
-    let trades: Vec<TradeInfo> = rows
-        .into_iter()
-        .map(|(id, price, amount, side, share_type, created_at)| TradeInfo {
-            id,
-            price,
-            amount,
-            side,
-            share_type: share_type.parse().unwrap_or(ShareType::Yes),
-            timestamp: created_at.timestamp_millis(),
-        })
-        .collect();
+    let trades: Vec<TradeInfo> = rows.into_iter().map(TradeInfo::from).collect();
+
+    let share_type = query
+        .share_type
+        .as_ref()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(ShareType::Yes);
+    let archive = archive_summary(&state, market_id, query.outcome_id, share_type).await?;
 
     Ok(Json(TradesResponse {
         market_id,
         outcome_id: query.outcome_id,
         trades,
+        archive,
     }))
 }
 
+/// Get the market's recent trade tape across all outcomes, ordered by engine sequence
+/// GET /markets/:market_id/tape
+pub async fn get_trade_tape(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<TapeQuery>,
+) -> Result<Json<TapeResponse>, AppError> {
+    let limit = query.limit.unwrap_or(50).min(500);
+    let trades = state
+        .history_store
+        .get_market_tape(&market_id.to_string(), query.before, limit)
+        .await?
+        .into_iter()
+        .map(|t| TapeTrade {
+            id: t.trade_id,
+            outcome_id: t.outcome_id,
+            share_type: t.share_type,
+            match_type: t.match_type,
+            aggressor_side: t.side,
+            price: t.price,
+            amount: t.amount,
+            sequence: t.sequence,
+            is_block_trade: t.is_block_trade,
+            is_rfq: t.is_rfq,
+            timestamp: t.timestamp,
+        })
+        .collect();
+
+    Ok(Json(TapeResponse { market_id, trades }))
+}
+
 /// Get ticker/price info for a market
 /// GET /markets/:market_id/ticker
 pub async fn get_ticker(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-) -> Result<Json<TickerResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<TickerResponse>, AppError> {
     // Get market info
     let market_data: Option<(Uuid, Decimal)> = sqlx::query_as(
         "SELECT id, volume_24h FROM markets WHERE id = $1",
     )
     .bind(market_id)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch market: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch market".to_string(),
-                code: "MARKET_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
-
-    let (_, volume_24h) = market_data.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Market not found".to_string(),
-                code: "MARKET_NOT_FOUND".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    let (_, volume_24h) = market_data.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
 
     // Get outcomes with probabilities
-    let outcomes_data: Vec<(Uuid, String, Decimal)> = sqlx::query_as(
+    let outcomes_data: Vec<OutcomeRow> = sqlx::query_as(
         r#"
-        SELECT id, name, probability
+        SELECT id, name, probability, twap_1h, twap_24h
         FROM outcomes
         WHERE market_id = $1
         ORDER BY name
@@ -507,7 +709,7 @@ pub async fn get_ticker(
 
     let outcomes: Vec<OutcomeTicker> = outcomes_data
         .into_iter()
-        .map(|(outcome_id, name, probability)| {
+        .map(|(outcome_id, name, probability, twap_1h, twap_24h)| {
             // In prediction markets, Yes price = probability, No price = 1 - probability
             let yes_price = probability;
             let no_price = Decimal::ONE - probability;
@@ -517,6 +719,8 @@ pub async fn get_ticker(
                 yes_price,
                 no_price,
                 probability,
+                twap_1h,
+                twap_24h,
             }
         })
         .collect();
@@ -534,21 +738,74 @@ pub async fn get_ticker(
 pub async fn get_price(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-) -> Result<Json<TickerResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<TickerResponse>, AppError> {
     // Same as ticker for now
     get_ticker(State(state), Path(market_id)).await
 }
 
+/// Best achievable price for a taker order across the direct book and the
+/// synthetic mint/merge route, with the per-route breakdown
+/// GET /markets/:market_id/quote
+pub async fn get_quote(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<QuoteQuery>,
+) -> Result<Json<Quote>, AppError> {
+    if query.size <= Decimal::ZERO {
+        return Err(AppError::bad_request("INVALID_SIZE", "Size must be positive"));
+    }
+
+    let outcome_id: Option<Uuid> = match query.outcome_id {
+        Some(outcome_id) => sqlx::query_scalar("SELECT id FROM outcomes WHERE id = $1 AND market_id = $2")
+            .bind(outcome_id)
+            .bind(market_id),
+        None => sqlx::query_scalar("SELECT id FROM outcomes WHERE market_id = $1 AND share_type = 'yes'")
+            .bind(market_id),
+    }
+    .fetch_optional(&state.db.pool)
+    .await?;
+
+    let outcome_id = outcome_id.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market or outcome not found"))?;
+
+    let share_type = query.outcome.unwrap_or(ShareType::Yes);
+    let side = match query.side {
+        OrderSide::Buy => MatchingSide::Buy,
+        OrderSide::Sell => MatchingSide::Sell,
+    };
+    let market_key = format!("{}:{}:{}", market_id, outcome_id, share_type);
+
+    Ok(Json(state.matching_engine.quote(&market_key, side, query.size)))
+}
+
+/// Spread, top-of-book depth, 24h turnover and liquidity score of the
+/// market's Yes book, for thin-market badges
+/// GET /markets/:market_id/liquidity
+pub async fn get_liquidity(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+) -> Result<Json<MarketLiquidity>, AppError> {
+    let outcome_id: Option<Uuid> =
+        sqlx::query_scalar("SELECT id FROM outcomes WHERE market_id = $1 AND share_type = 'yes'")
+            .bind(market_id)
+            .fetch_optional(&state.db.pool)
+            .await?;
+
+    let outcome_id = outcome_id.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+
+    Ok(Json(state.liquidity_tracker.snapshot(market_id, outcome_id)))
+}
+
 // ============================================================================
 // Admin Handlers for Market Management
 // ============================================================================
 
 /// Create market request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateMarketRequest {
     /// Gnosis Conditional Tokens conditionId
     pub condition_id: String,
     /// Market question
+    #[validate(length(min = 1))]
     pub question: String,
     /// Market description
     pub description: Option<String>,
@@ -558,10 +815,16 @@ pub struct CreateMarketRequest {
     pub resolution_source: Option<String>,
     /// End time (timestamp in milliseconds)
     pub end_time: Option<i64>,
+    /// Resolution time (timestamp in milliseconds); reminders go out ahead
+    /// of it and trading stops once it passes
+    pub resolution_time: Option<i64>,
     /// Yes outcome token ID
     pub yes_token_id: String,
     /// No outcome token ID
     pub no_token_id: String,
+    /// Share quantity decimals (0-6, default 2)
+    #[validate(range(max = 6))]
+    pub share_decimals: Option<u32>,
 }
 
 /// Create market response
@@ -573,18 +836,75 @@ pub struct CreateMarketResponse {
     pub message: String,
 }
 
+/// Most answers a categorical market takes
+const MAX_CATEGORICAL_OUTCOMES: usize = 32;
+
+/// Create categorical market request: one Yes outcome per answer, whose
+/// shares together form a complete set worth 1
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCategoricalMarketRequest {
+    /// Gnosis Conditional Tokens conditionId
+    pub condition_id: String,
+    /// Market question
+    #[validate(length(min = 1))]
+    pub question: String,
+    /// Market description
+    pub description: Option<String>,
+    /// Market category
+    pub category: Option<String>,
+    /// Resolution source (UMA, Chainlink, Manual)
+    pub resolution_source: Option<String>,
+    /// End time (timestamp in milliseconds)
+    pub end_time: Option<i64>,
+    /// Resolution time (timestamp in milliseconds)
+    pub resolution_time: Option<i64>,
+    /// The answers, in display order (3 or more; two are a binary market)
+    #[validate(length(min = 3))]
+    pub outcomes: Vec<CategoricalOutcomeRequest>,
+    /// Share quantity decimals (0-6, default 2)
+    #[validate(range(max = 6))]
+    pub share_decimals: Option<u32>,
+}
+
+/// One answer of a categorical market
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoricalOutcomeRequest {
+    pub name: String,
+    /// Outcome token ID
+    pub token_id: String,
+}
+
+/// Create categorical market response
+#[derive(Debug, Serialize)]
+pub struct CreateCategoricalMarketResponse {
+    pub market_id: Uuid,
+    /// Outcome IDs in the order of the request's answers
+    pub outcome_ids: Vec<Uuid>,
+    pub message: String,
+}
+
 /// Close market request (stops trading)
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CloseMarketRequest {
     pub reason: Option<String>,
 }
 
 /// Resolve market request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct ResolveMarketRequest {
-    /// Which outcome won: "yes" or "no"
+    /// Which outcome of a binary market won: "yes" or "no"
+    #[serde(default)]
     pub winning_outcome: String,
+    /// Which answer of a categorical market won
+    #[serde(default)]
+    pub winning_outcome_id: Option<Uuid>,
+    /// Why the outcome won, published with the resolution evidence
+    #[serde(default)]
+    pub justification: Option<String>,
+    /// Public sources backing the justification (URLs, reports)
+    #[serde(default)]
+    pub sources: Vec<String>,
 }
 
 /// Market status response
@@ -600,9 +920,14 @@ pub struct MarketStatusResponse {
 pub async fn get_market(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-) -> Result<Json<MarketInfo>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<MarketInfo>, AppError> {
     use crate::cache::{CachedMarket, CachedOutcome};
 
+    // Never cached: it moves with the clock
+    let trading_hours = trading_calendar::load(&state.db.pool, market_id)
+        .await?
+        .map(|schedule| schedule.hours(Utc::now()));
+
     // Try cache first
     if let Some(market_cache) = state.cache.market_opt() {
         if let Ok(Some(cached)) = market_cache.get_market(market_id).await {
@@ -619,6 +944,8 @@ pub async fn get_market(
                         id: o.id,
                         name: o.name,
                         probability: o.probability,
+                        twap_1h: o.twap_1h,
+                        twap_24h: o.twap_24h,
                     })
                     .collect(),
                 status: cached.status,
@@ -628,6 +955,7 @@ pub async fn get_market(
                 total_volume: cached.total_volume,
                 liquidity: Decimal::ZERO,
                 created_at: cached.created_at,
+                trading_hours,
             }));
         }
     }
@@ -654,33 +982,15 @@ pub async fn get_market(
     )
     .bind(market_id)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch market: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch market".to_string(),
-                code: "MARKET_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let (id, question, description, category, status, resolution_source, end_time, volume_24h, total_volume, created_at) =
-        market_data.ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Market not found".to_string(),
-                    code: "MARKET_NOT_FOUND".to_string(),
-                }),
-            )
-        })?;
+        market_data.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
 
     // Get outcomes for the market
-    let outcomes_data: Vec<(Uuid, String, Decimal)> = sqlx::query_as(
+    let outcomes_data: Vec<OutcomeRow> = sqlx::query_as(
         r#"
-        SELECT id, name, probability
+        SELECT id, name, probability, twap_1h, twap_24h
         FROM outcomes
         WHERE market_id = $1
         ORDER BY name
@@ -693,10 +1003,12 @@ pub async fn get_market(
 
     let outcomes: Vec<OutcomeInfo> = outcomes_data
         .iter()
-        .map(|(oid, name, probability)| OutcomeInfo {
+        .map(|(oid, name, probability, twap_1h, twap_24h)| OutcomeInfo {
             id: *oid,
             name: name.clone(),
             probability: *probability,
+            twap_1h: *twap_1h,
+            twap_24h: *twap_24h,
         })
         .collect();
 
@@ -714,10 +1026,12 @@ pub async fn get_market(
             end_time: end_time.map(|t| t.timestamp_millis()),
             outcomes: outcomes_data
                 .into_iter()
-                .map(|(oid, name, probability)| CachedOutcome {
+                .map(|(oid, name, probability, twap_1h, twap_24h)| CachedOutcome {
                     id: oid,
                     name,
                     probability,
+                    twap_1h,
+                    twap_24h,
                 })
                 .collect(),
             volume_24h,
@@ -743,6 +1057,7 @@ pub async fn get_market(
         total_volume,
         liquidity,
         created_at: created_at.timestamp_millis(),
+        trading_hours,
     }))
 }
 
@@ -750,46 +1065,9 @@ pub async fn get_market(
 /// POST /admin/markets
 pub async fn create_market(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<CreateMarketRequest>,
-) -> Result<Json<CreateMarketResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Validate condition_id format (should be 66 chars hex string with 0x prefix)
-    if !req.condition_id.starts_with("0x") || req.condition_id.len() != 66 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid condition_id format. Must be 0x + 64 hex chars".to_string(),
-                code: "INVALID_CONDITION_ID".to_string(),
-            }),
-        ));
-    }
-
-    // Check if market with this condition_id already exists
-    let existing: Option<(Uuid,)> = sqlx::query_as(
-        "SELECT id FROM markets WHERE condition_id = $1",
-    )
-    .bind(&req.condition_id)
-    .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to check existing market: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    if existing.is_some() {
-        return Err((
-            StatusCode::CONFLICT,
-            Json(ErrorResponse {
-                error: "Market with this condition_id already exists".to_string(),
-                code: "MARKET_EXISTS".to_string(),
-            }),
-        ));
-    }
+    ValidJson(req): ValidJson<CreateMarketRequest>,
+) -> Result<Json<CreateMarketResponse>, AppError> {
+    let share_precision = check_new_market(&state, &req.condition_id, req.share_decimals).await?;
 
     let market_id = Uuid::new_v4();
     let yes_outcome_id = Uuid::new_v4();
@@ -800,24 +1078,21 @@ pub async fn create_market(
         chrono::DateTime::from_timestamp_millis(ts)
             .unwrap_or_else(chrono::Utc::now)
     });
+    let resolution_time = req
+        .resolution_time
+        .map(|ts| chrono::DateTime::from_timestamp_millis(ts).unwrap_or_else(chrono::Utc::now));
 
     // Start transaction
-    let mut tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
+    let mut tx = state.db.pool.begin().await?;
 
     // Create market
     sqlx::query(
         r#"
-        INSERT INTO markets (id, condition_id, question, description, category, resolution_source, end_time)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO markets (
+            id, condition_id, question, description, category, resolution_source, end_time, share_decimals,
+            resolution_time
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         "#,
     )
     .bind(market_id)
@@ -827,18 +1102,10 @@ pub async fn create_market(
     .bind(&category)
     .bind(&resolution_source)
     .bind(end_time)
+    .bind(share_precision.dp() as i16)
+    .bind(resolution_time)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to create market: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to create market".to_string(),
-                code: "MARKET_CREATE_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Create Yes outcome (without complement_id first)
     sqlx::query(
@@ -851,17 +1118,7 @@ pub async fn create_market(
     .bind(market_id)
     .bind(&req.yes_token_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to create Yes outcome: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to create outcomes".to_string(),
-                code: "OUTCOME_CREATE_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Create No outcome (without complement_id first)
     sqlx::query(
@@ -874,62 +1131,23 @@ pub async fn create_market(
     .bind(market_id)
     .bind(&req.no_token_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to create No outcome: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to create outcomes".to_string(),
-                code: "OUTCOME_CREATE_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Now update complement_id references
     sqlx::query("UPDATE outcomes SET complement_id = $1 WHERE id = $2")
         .bind(no_outcome_id)
         .bind(yes_outcome_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to update Yes complement: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to link outcomes".to_string(),
-                    code: "OUTCOME_LINK_FAILED".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     sqlx::query("UPDATE outcomes SET complement_id = $1 WHERE id = $2")
         .bind(yes_outcome_id)
         .bind(no_outcome_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to update No complement: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to link outcomes".to_string(),
-                    code: "OUTCOME_LINK_FAILED".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     // Commit transaction
-    tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to create market".to_string(),
-                code: "TX_COMMIT_FAILED".to_string(),
-            }),
-        )
-    })?;
+    tx.commit().await?;
 
     tracing::info!(
         "Created market {} with question: {}",
@@ -945,48 +1163,167 @@ pub async fn create_market(
     }))
 }
 
+/// Create a categorical market (Admin only): one Yes outcome per answer,
+/// registered with the matching engine so complete sets mint and merge
+/// across the answers' books
+/// POST /admin/markets/categorical
+pub async fn create_categorical_market(
+    State(state): State<Arc<AppState>>,
+    ValidJson(req): ValidJson<CreateCategoricalMarketRequest>,
+) -> Result<Json<CreateCategoricalMarketResponse>, AppError> {
+    let share_precision = check_new_market(&state, &req.condition_id, req.share_decimals).await?;
+
+    if req.outcomes.len() > MAX_CATEGORICAL_OUTCOMES {
+        return Err(AppError::bad_request(
+            "TOO_MANY_OUTCOMES",
+            format!("A categorical market takes at most {} outcomes", MAX_CATEGORICAL_OUTCOMES),
+        ));
+    }
+    let mut names = std::collections::HashSet::new();
+    for outcome in &req.outcomes {
+        let name = outcome.name.trim();
+        if name.is_empty() || name.len() > 50 {
+            return Err(AppError::bad_request("INVALID_OUTCOME", "Outcome names must be 1 to 50 characters"));
+        }
+        if !names.insert(name.to_lowercase()) {
+            return Err(AppError::bad_request("INVALID_OUTCOME", format!("Outcome '{}' is listed twice", name)));
+        }
+    }
+
+    let market_id = Uuid::new_v4();
+    let outcome_ids: Vec<Uuid> = req.outcomes.iter().map(|_| Uuid::new_v4()).collect();
+    let category = req.category.unwrap_or_else(|| "general".to_string());
+    let resolution_source = req.resolution_source.unwrap_or_else(|| "UMA".to_string());
+    let end_time = req
+        .end_time
+        .map(|ts| chrono::DateTime::from_timestamp_millis(ts).unwrap_or_else(chrono::Utc::now));
+    let resolution_time = req
+        .resolution_time
+        .map(|ts| chrono::DateTime::from_timestamp_millis(ts).unwrap_or_else(chrono::Utc::now));
+    // Each answer starts out as likely as the others
+    let probability = Decimal::ONE / Decimal::from(req.outcomes.len());
+
+    let mut tx = state.db.pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO markets (
+            id, condition_id, question, description, category, resolution_source, end_time, share_decimals,
+            resolution_time, market_type
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 'categorical')
+        "#,
+    )
+    .bind(market_id)
+    .bind(&req.condition_id)
+    .bind(&req.question)
+    .bind(&req.description)
+    .bind(&category)
+    .bind(&resolution_source)
+    .bind(end_time)
+    .bind(share_precision.dp() as i16)
+    .bind(resolution_time)
+    .execute(&mut *tx)
+    .await?;
+
+    for (index, (outcome, outcome_id)) in req.outcomes.iter().zip(&outcome_ids).enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO outcomes (id, market_id, token_id, name, share_type, probability, outcome_index)
+            VALUES ($1, $2, $3, $4, 'yes', $5, $6)
+            "#,
+        )
+        .bind(outcome_id)
+        .bind(market_id)
+        .bind(&outcome.token_id)
+        .bind(outcome.name.trim())
+        .bind(probability.round_dp(8))
+        .bind(index as i16)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    state.matching_engine.register_outcome_set(market_id, outcome_ids.clone())?;
+
+    tracing::info!(
+        "Created categorical market {} with {} outcomes: {}",
+        market_id,
+        outcome_ids.len(),
+        req.question
+    );
+
+    Ok(Json(CreateCategoricalMarketResponse {
+        market_id,
+        outcome_ids,
+        message: "Market created successfully".to_string(),
+    }))
+}
+
+/// Checks shared by market creation: a well-formed condition id no market
+/// has yet, and the share precision
+async fn check_new_market(
+    state: &AppState,
+    condition_id: &str,
+    share_decimals: Option<u32>,
+) -> Result<SharePrecision, AppError> {
+    // Validate condition_id format (should be 66 chars hex string with 0x prefix)
+    if !condition_id.starts_with("0x") || condition_id.len() != 66 {
+        return Err(AppError::bad_request(
+            "INVALID_CONDITION_ID",
+            "Invalid condition_id format. Must be 0x + 64 hex chars",
+        ));
+    }
+
+    // Check if market with this condition_id already exists
+    let existing: Option<(Uuid,)> = sqlx::query_as(
+        "SELECT id FROM markets WHERE condition_id = $1",
+    )
+    .bind(condition_id)
+    .fetch_optional(&state.db.pool)
+    .await?;
+
+    if existing.is_some() {
+        return Err(AppError::new(
+            StatusCode::CONFLICT,
+            "MARKET_EXISTS",
+            "Market with this condition_id already exists",
+        ));
+    }
+
+    match share_decimals {
+        Some(dp) => SharePrecision::new(dp).ok_or_else(|| {
+            AppError::bad_request(
+                "INVALID_SHARE_DECIMALS",
+                format!("share_decimals must be between 0 and {}", precision::MAX_SHARE_DP),
+            )
+        }),
+        None => Ok(SharePrecision::default()),
+    }
+}
+
 /// Close a market (pause trading) - Admin only
 /// POST /admin/markets/:market_id/close
 pub async fn close_market(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-    Json(_req): Json<CloseMarketRequest>,
-) -> Result<Json<MarketStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(_req): ValidJson<CloseMarketRequest>,
+) -> Result<Json<MarketStatusResponse>, AppError> {
     // Check market exists and is active
     let market_status: Option<(String,)> = sqlx::query_as(
         "SELECT status::text FROM markets WHERE id = $1",
     )
     .bind(market_id)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch market: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    let (current_status,) = market_status.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Market not found".to_string(),
-                code: "MARKET_NOT_FOUND".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    let (current_status,) = market_status.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
 
     if current_status != "active" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Cannot close market with status: {}", current_status),
-                code: "INVALID_STATUS".to_string(),
-            }),
+        return Err(AppError::bad_request(
+            "INVALID_STATUS",
+            format!("Cannot close market with status: {}", current_status),
         ));
     }
 
@@ -994,19 +1331,13 @@ pub async fn close_market(
     sqlx::query("UPDATE markets SET status = 'paused' WHERE id = $1")
         .bind(market_id)
         .execute(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to close market: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to close market".to_string(),
-                    code: "MARKET_CLOSE_FAILED".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     tracing::info!("Closed market {}", market_id);
+    state
+        .event_bus
+        .publish(BusEvent::MarketStatus(MarketStatusEvent::new(market_id, "paused")))
+        .await;
 
     Ok(Json(MarketStatusResponse {
         market_id,
@@ -1017,92 +1348,94 @@ pub async fn close_market(
 
 /// Resolve a market (set winning outcome) - Admin only
 /// POST /admin/markets/:market_id/resolve
+///
+/// Under the two-person rule this goes through `/admin/approvals` instead.
 pub async fn resolve_market(
     State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
     Path(market_id): Path<Uuid>,
-    Json(req): Json<ResolveMarketRequest>,
-) -> Result<Json<MarketStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Validate winning_outcome
-    let winning_share_type = match req.winning_outcome.to_lowercase().as_str() {
-        "yes" => "yes",
-        "no" => "no",
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "winning_outcome must be 'yes' or 'no'".to_string(),
-                    code: "INVALID_OUTCOME".to_string(),
-                }),
-            ));
-        }
-    };
+    ValidJson(req): ValidJson<ResolveMarketRequest>,
+) -> Result<Json<MarketStatusResponse>, AppError> {
+    if state.config.admin_two_person_rule {
+        return Err(AppError::forbidden(
+            "APPROVAL_REQUIRED",
+            "Manual resolution needs a second admin's approval; propose it via /admin/approvals",
+        ));
+    }
+    resolve(&state, &auth_user.address, market_id, req).await
+}
 
+/// Resolve `market_id` by hand as `admin_address`
+pub(crate) async fn resolve(
+    state: &AppState,
+    admin_address: &Address,
+    market_id: Uuid,
+    req: ResolveMarketRequest,
+) -> Result<Json<MarketStatusResponse>, AppError> {
     // Check market exists and is active or paused
-    let market_status: Option<(String,)> = sqlx::query_as(
-        "SELECT status::text FROM markets WHERE id = $1",
+    let market: Option<(String, String)> = sqlx::query_as(
+        "SELECT status::text, market_type FROM markets WHERE id = $1",
     )
     .bind(market_id)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch market: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    let (current_status,) = market_status.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Market not found".to_string(),
-                code: "MARKET_NOT_FOUND".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    let (current_status, market_type) =
+        market.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
 
     if current_status == "resolved" || current_status == "cancelled" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Cannot resolve market with status: {}", current_status),
-                code: "INVALID_STATUS".to_string(),
-            }),
+        return Err(AppError::bad_request(
+            "INVALID_STATUS",
+            format!("Cannot resolve market with status: {}", current_status),
         ));
     }
 
-    // Get winning outcome ID
-    let winning_outcome: Option<(Uuid,)> = sqlx::query_as(
-        "SELECT id FROM outcomes WHERE market_id = $1 AND share_type = $2::share_type",
-    )
-    .bind(market_id)
-    .bind(winning_share_type)
-    .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch outcome: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    let (winning_outcome_id,) = winning_outcome.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Winning outcome not found".to_string(),
-                code: "OUTCOME_NOT_FOUND".to_string(),
-            }),
+    // A categorical market resolves to one of its answers, whose Yes
+    // shares pay out; a binary market to its Yes or No outcome
+    let (winning_outcome_id, winning_share_type, winning_label) = if market_type == "categorical" {
+        let outcome_id = req.winning_outcome_id.ok_or_else(|| {
+            AppError::bad_request("INVALID_OUTCOME", "winning_outcome_id must name one of the market's outcomes")
+        })?;
+        let name: Option<String> = sqlx::query_scalar("SELECT name FROM outcomes WHERE id = $1 AND market_id = $2")
+            .bind(outcome_id)
+            .bind(market_id)
+            .fetch_optional(&state.db.pool)
+            .await?;
+        let name = name.ok_or_else(|| AppError::not_found("OUTCOME_NOT_FOUND", "Winning outcome not found"))?;
+        (outcome_id, "yes", name)
+    } else {
+        // Validate winning_outcome
+        let winning_share_type = match req.winning_outcome.to_lowercase().as_str() {
+            "yes" => "yes",
+            "no" => "no",
+            _ => {
+                return Err(AppError::bad_request("INVALID_OUTCOME", "winning_outcome must be 'yes' or 'no'"));
+            }
+        };
+
+        // Get winning outcome ID
+        let winning_outcome: Option<(Uuid,)> = sqlx::query_as(
+            "SELECT id FROM outcomes WHERE market_id = $1 AND share_type = $2::share_type",
         )
-    })?;
+        .bind(market_id)
+        .bind(winning_share_type)
+        .fetch_optional(&state.db.pool)
+        .await?;
+
+        let (winning_outcome_id,) = winning_outcome
+            .ok_or_else(|| AppError::not_found("OUTCOME_NOT_FOUND", "Winning outcome not found"))?;
+
+        // At most one market of a negative-risk group resolves Yes
+        if winning_share_type == "yes" {
+            neg_risk::check_yes_resolution(&state.db.pool, market_id)
+                .await
+                .map_err(|e| match e {
+                    neg_risk::NegRiskError::Database(e) => AppError::from(e),
+                    e => AppError::new(StatusCode::CONFLICT, e.code(), e.to_string()),
+                })?;
+        }
+        (winning_outcome_id, winning_share_type, winning_share_type.to_string())
+    };
 
     // Update market status and winning outcome
     sqlx::query(
@@ -1117,17 +1450,7 @@ pub async fn resolve_market(
     .bind(winning_outcome_id)
     .bind(market_id)
     .execute(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to resolve market: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to resolve market".to_string(),
-                code: "MARKET_RESOLVE_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Update probabilities: winning = 1.0, losing = 0.0
     sqlx::query(
@@ -1143,37 +1466,71 @@ pub async fn resolve_market(
     .bind(winning_outcome_id)
     .bind(market_id)
     .execute(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to update outcome probabilities: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to update probabilities".to_string(),
-                code: "PROBABILITY_UPDATE_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    let evidence = ResolutionEvidence {
+        method: ResolutionMethod::Admin,
+        winning_outcome_id: Some(winning_outcome_id),
+        resolved_by: Some(admin_address.clone().into()),
+        justification: req.justification.map(|j| j.trim().to_string()).filter(|j| !j.is_empty()),
+        oracle_data: serde_json::json!({ "winning_share_type": winning_share_type, "sources": req.sources }),
+        tx_hashes: Vec::new(),
+    };
+    let recorded = match state.db.pool.acquire().await {
+        Ok(mut conn) => resolution_evidence::record(&mut conn, market_id, &evidence).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = recorded {
+        tracing::error!("Failed to record resolution evidence of market {}: {}", market_id, e);
+    }
 
     tracing::info!(
         "Resolved market {} with winning outcome: {}",
         market_id,
-        winning_share_type
+        winning_label
     );
+    system_events::record(
+        &state.db.pool,
+        SystemEventKind::MarketResolved,
+        Some(&market_id.to_string()),
+        format!("Market resolved {}", winning_label),
+        serde_json::json!({ "winning_outcome_id": winning_outcome_id, "winning_share_type": winning_share_type }),
+    )
+    .await;
+
+    // Webhooks and channel announcements go out from the bus consumer
+    state
+        .event_bus
+        .publish(BusEvent::MarketStatus(MarketStatusEvent {
+            winning_outcome_id: Some(winning_outcome_id),
+            winning_share_type: Some(winning_share_type.to_string()),
+            ..MarketStatusEvent::new(market_id, "resolved")
+        }))
+        .await;
+
+    archive_closed_market(state, market_id).await;
 
     Ok(Json(MarketStatusResponse {
         market_id,
         status: "resolved".to_string(),
-        message: format!("Market resolved. Winning outcome: {}", winning_share_type),
+        message: format!("Market resolved. Winning outcome: {}", winning_label),
     }))
 }
 
+/// Free the market's books right away instead of waiting for the sweep
+async fn archive_closed_market(state: &AppState, market_id: Uuid) {
+    if let Err(e) = state.market_archiver.archive(market_id).await {
+        tracing::warn!("Failed to archive market {}, the sweep will retry: {}", market_id, e);
+    }
+}
+
 /// Update probability request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateProbabilityRequest {
     /// Outcome ID to update (must be a Yes outcome)
     pub outcome_id: Uuid,
     /// New probability (0.01 - 0.99)
+    #[validate(custom = "validation::price")]
     pub probability: Decimal,
 }
 
@@ -1188,7 +1545,7 @@ pub struct UpdateProbabilityResponse {
 }
 
 /// Refresh probability request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct RefreshProbabilityRequest {
     /// Source: "orderbook" or oracle name like "chainlink", "uma"
     pub source: Option<String>,
@@ -1199,19 +1556,13 @@ pub struct RefreshProbabilityRequest {
 pub async fn update_probability(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-    Json(req): Json<UpdateProbabilityRequest>,
-) -> Result<Json<UpdateProbabilityResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(req): ValidJson<UpdateProbabilityRequest>,
+) -> Result<Json<UpdateProbabilityResponse>, AppError> {
     use crate::services::oracle::{OracleError, PriceOracle};
 
     // Validate probability range
     if req.probability < Decimal::new(1, 2) || req.probability > Decimal::new(99, 2) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Probability must be between 0.01 and 0.99".to_string(),
-                code: "INVALID_PROBABILITY".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_PROBABILITY", "Probability must be between 0.01 and 0.99"));
     }
 
     // Create oracle service
@@ -1260,13 +1611,7 @@ pub async fn update_probability(
                     )
                 }
             };
-            (
-                status,
-                Json(ErrorResponse {
-                    error: message.to_string(),
-                    code: code.to_string(),
-                }),
-            )
+            AppError::new(status, code, message)
         })?;
 
     let no_probability = Decimal::ONE - req.probability;
@@ -1290,8 +1635,8 @@ pub async fn update_probability(
 pub async fn refresh_probability(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-    Json(req): Json<RefreshProbabilityRequest>,
-) -> Result<Json<UpdateProbabilityResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(req): ValidJson<RefreshProbabilityRequest>,
+) -> Result<Json<UpdateProbabilityResponse>, AppError> {
     use crate::services::oracle::PriceOracle;
 
     let source = req.source.unwrap_or_else(|| "orderbook".to_string());
@@ -1302,27 +1647,9 @@ pub async fn refresh_probability(
     )
     .bind(market_id)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch outcome: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    let (outcome_id,) = outcome.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Market or outcome not found".to_string(),
-                code: "NOT_FOUND".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    let (outcome_id,) = outcome.ok_or_else(|| AppError::not_found("NOT_FOUND", "Market or outcome not found"))?;
 
     // Create oracle service
     let oracle = PriceOracle::new(state.db.pool.clone(), state.matching_engine.clone());
@@ -1334,28 +1661,14 @@ pub async fn refresh_probability(
             .await
             .map_err(|e| {
                 tracing::error!("Failed to refresh from orderbook: {}", e);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: format!("Failed to refresh: {}", e),
-                        code: "REFRESH_FAILED".to_string(),
-                    }),
-                )
+                AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "REFRESH_FAILED", "Failed to refresh probability")
             })?
     } else {
         // Try external oracle
         oracle
             .fetch_from_external(market_id, &source)
             .await
-            .map_err(|e| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse {
-                        error: format!("{}", e),
-                        code: "ORACLE_ERROR".to_string(),
-                    }),
-                )
-            })?
+            .map_err(|e| AppError::bad_request("ORACLE_ERROR", format!("{}", e)))?
     };
 
     let no_probability = Decimal::ONE - probability;
@@ -1374,42 +1687,21 @@ pub async fn refresh_probability(
 pub async fn cancel_market(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-) -> Result<Json<MarketStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<MarketStatusResponse>, AppError> {
     // Check market exists and is not already cancelled/resolved
     let market_status: Option<(String,)> = sqlx::query_as(
         "SELECT status::text FROM markets WHERE id = $1",
     )
     .bind(market_id)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch market: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    let (current_status,) = market_status.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Market not found".to_string(),
-                code: "MARKET_NOT_FOUND".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    let (current_status,) = market_status.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
 
     if current_status == "resolved" || current_status == "cancelled" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Cannot cancel market with status: {}", current_status),
-                code: "INVALID_STATUS".to_string(),
-            }),
+        return Err(AppError::bad_request(
+            "INVALID_STATUS",
+            format!("Cannot cancel market with status: {}", current_status),
         ));
     }
 
@@ -1417,19 +1709,23 @@ pub async fn cancel_market(
     sqlx::query("UPDATE markets SET status = 'cancelled' WHERE id = $1")
         .bind(market_id)
         .execute(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to cancel market: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to cancel market".to_string(),
-                    code: "MARKET_CANCEL_FAILED".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     tracing::info!("Cancelled market {}", market_id);
+    state
+        .event_bus
+        .publish(BusEvent::MarketStatus(MarketStatusEvent::new(market_id, "cancelled")))
+        .await;
+    system_events::record(
+        &state.db.pool,
+        SystemEventKind::MarketCancelled,
+        Some(&market_id.to_string()),
+        "Market cancelled",
+        serde_json::json!({}),
+    )
+    .await;
+
+    archive_closed_market(&state, market_id).await;
 
     Ok(Json(MarketStatusResponse {
         market_id,
@@ -1438,6 +1734,124 @@ pub async fn cancel_market(
     }))
 }
 
+/// Feature market request
+#[derive(Debug, Deserialize, Validate)]
+pub struct FeatureMarketRequest {
+    pub featured: bool,
+}
+
+/// Feature or unfeature a market - Admin only
+/// POST /admin/markets/:market_id/feature
+///
+/// Newly featured active markets are announced to channel integrations.
+pub async fn feature_market(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidJson(req): ValidJson<FeatureMarketRequest>,
+) -> Result<Json<MarketStatusResponse>, AppError> {
+    // Returns the previous flag so only a false -> true transition is announced
+    let row: Option<(String, bool)> = sqlx::query_as(
+        r#"
+        UPDATE markets m
+        SET is_featured = $2,
+            featured_at = CASE WHEN $2 THEN COALESCE(m.featured_at, NOW()) ELSE NULL END
+        FROM (SELECT id, is_featured FROM markets WHERE id = $1 FOR UPDATE) prev
+        WHERE m.id = prev.id
+        RETURNING m.status::text, prev.is_featured
+        "#,
+    )
+    .bind(market_id)
+    .bind(req.featured)
+    .fetch_optional(&state.db.pool)
+    .await?;
+
+    let (status, was_featured) = row.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+
+    if req.featured && !was_featured && status == "active" {
+        state
+            .channel_gateway
+            .publish(ChannelEventType::FeaturedMarket, market_id, None, serde_json::json!({}))
+            .await;
+    }
+
+    tracing::info!("Market {} featured = {}", market_id, req.featured);
+
+    Ok(Json(MarketStatusResponse {
+        market_id,
+        status,
+        message: if req.featured {
+            "Market is now featured".to_string()
+        } else {
+            "Market is no longer featured".to_string()
+        },
+    }))
+}
+
+/// Trading schedule request
+#[derive(Debug, Deserialize, Validate)]
+pub struct TradingScheduleRequest {
+    #[serde(flatten)]
+    pub schedule: TradingSchedule,
+}
+
+/// A market's trading schedule and where it stands now
+#[derive(Debug, Serialize)]
+pub struct TradingScheduleResponse {
+    pub market_id: Uuid,
+    pub schedule: Option<TradingSchedule>,
+    pub trading_hours: Option<TradingHours>,
+}
+
+/// Limit a market to trading sessions and halts - Admin only
+/// PUT /admin/markets/:market_id/trading-schedule
+///
+/// Outside the schedule new orders are refused; resting orders stay on the
+/// book and can be cancelled.
+pub async fn set_trading_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidJson(req): ValidJson<TradingScheduleRequest>,
+) -> Result<Json<TradingScheduleResponse>, AppError> {
+    let schedule = req.schedule;
+    schedule.validate()
+        .map_err(|e| AppError::bad_request("INVALID_TRADING_SCHEDULE", format!("Invalid trading schedule: {}", e)))?;
+
+    if !trading_calendar::save(&state.db.pool, market_id, &schedule).await? {
+        return Err(AppError::not_found("MARKET_NOT_FOUND", "Market not found"));
+    }
+
+    tracing::info!(
+        "Market {} trades {} sessions in {} with {} halts",
+        market_id,
+        schedule.sessions.len(),
+        schedule.timezone,
+        schedule.halts.len()
+    );
+
+    Ok(Json(TradingScheduleResponse {
+        market_id,
+        trading_hours: Some(schedule.hours(Utc::now())),
+        schedule: Some(schedule),
+    }))
+}
+
+/// Let a market trade whenever it is active again - Admin only
+/// DELETE /admin/markets/:market_id/trading-schedule
+pub async fn clear_trading_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+) -> Result<Json<TradingScheduleResponse>, AppError> {
+    trading_calendar::clear(&state.db.pool, market_id).await?;
+
+    tracing::info!("Cleared trading schedule of market {}", market_id);
+
+    Ok(Json(TradingScheduleResponse {
+        market_id,
+        schedule: None,
+        trading_hours: None,
+    }))
+}
+
 
 // ============================================================================
 // Market Discovery Endpoints
@@ -1460,7 +1874,7 @@ pub struct CategoryInfo {
 /// GET /markets/categories
 pub async fn get_categories(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<CategoriesResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<CategoriesResponse>, AppError> {
     let categories: Vec<(String, i64, Decimal)> = sqlx::query_as(
         r#"
         SELECT category, COUNT(*) as market_count, COALESCE(SUM(volume_24h), 0) as volume_24h
@@ -1471,17 +1885,7 @@ pub async fn get_categories(
         "#,
     )
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch categories: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch categories".to_string(),
-                code: "CATEGORY_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let categories = categories
         .into_iter()
@@ -1505,8 +1909,8 @@ pub struct TrendingMarketsResponse {
 /// GET /markets/trending
 pub async fn get_trending_markets(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<TrendingQuery>,
-) -> Result<Json<TrendingMarketsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidQuery(query): ValidQuery<TrendingQuery>,
+) -> Result<Json<TrendingMarketsResponse>, AppError> {
     let limit = query.limit.unwrap_or(10).min(50);
 
     let markets_data: Vec<(
@@ -1532,22 +1936,12 @@ pub async fn get_trending_markets(
     )
     .bind(limit)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch trending markets: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch trending markets".to_string(),
-                code: "TRENDING_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let mut markets = Vec::new();
     for (id, question, description, category, status, resolution_source, end_time, volume_24h, total_volume, created_at) in markets_data {
-        let outcomes_data: Vec<(Uuid, String, Decimal)> = sqlx::query_as(
-            "SELECT id, name, probability FROM outcomes WHERE market_id = $1 ORDER BY name",
+        let outcomes_data: Vec<OutcomeRow> = sqlx::query_as(
+            "SELECT id, name, probability, twap_1h, twap_24h FROM outcomes WHERE market_id = $1 ORDER BY name",
         )
         .bind(id)
         .fetch_all(&state.db.pool)
@@ -1556,7 +1950,13 @@ pub async fn get_trending_markets(
 
         let outcomes: Vec<OutcomeInfo> = outcomes_data
             .into_iter()
-            .map(|(oid, name, probability)| OutcomeInfo { id: oid, name, probability })
+            .map(|(oid, name, probability, twap_1h, twap_24h)| OutcomeInfo {
+                id: oid,
+                name,
+                probability,
+                twap_1h,
+                twap_24h,
+            })
             .collect();
 
         markets.push(MarketInfo {
@@ -1572,14 +1972,16 @@ pub async fn get_trending_markets(
             total_volume,
             liquidity: Decimal::ZERO,
             created_at: created_at.timestamp_millis(),
+            trading_hours: None,
         });
     }
 
     Ok(Json(TrendingMarketsResponse { markets }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct TrendingQuery {
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
     pub limit: Option<i64>,
 }
 
@@ -1587,8 +1989,8 @@ pub struct TrendingQuery {
 /// GET /markets/ending-soon
 pub async fn get_ending_soon(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<EndingSoonQuery>,
-) -> Result<Json<TrendingMarketsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidQuery(query): ValidQuery<EndingSoonQuery>,
+) -> Result<Json<TrendingMarketsResponse>, AppError> {
     let limit = query.limit.unwrap_or(10).min(50);
     let hours = query.hours.unwrap_or(24);
     
@@ -1621,22 +2023,12 @@ pub async fn get_ending_soon(
     .bind(cutoff)
     .bind(limit)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch ending soon markets: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch ending soon markets".to_string(),
-                code: "ENDING_SOON_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let mut markets = Vec::new();
     for (id, question, description, category, status, resolution_source, end_time, volume_24h, total_volume, created_at) in markets_data {
-        let outcomes_data: Vec<(Uuid, String, Decimal)> = sqlx::query_as(
-            "SELECT id, name, probability FROM outcomes WHERE market_id = $1 ORDER BY name",
+        let outcomes_data: Vec<OutcomeRow> = sqlx::query_as(
+            "SELECT id, name, probability, twap_1h, twap_24h FROM outcomes WHERE market_id = $1 ORDER BY name",
         )
         .bind(id)
         .fetch_all(&state.db.pool)
@@ -1645,7 +2037,13 @@ pub async fn get_ending_soon(
 
         let outcomes: Vec<OutcomeInfo> = outcomes_data
             .into_iter()
-            .map(|(oid, name, probability)| OutcomeInfo { id: oid, name, probability })
+            .map(|(oid, name, probability, twap_1h, twap_24h)| OutcomeInfo {
+                id: oid,
+                name,
+                probability,
+                twap_1h,
+                twap_24h,
+            })
             .collect();
 
         markets.push(MarketInfo {
@@ -1661,16 +2059,19 @@ pub async fn get_ending_soon(
             total_volume,
             liquidity: Decimal::ZERO,
             created_at: created_at.timestamp_millis(),
+            trading_hours: None,
         });
     }
 
     Ok(Json(TrendingMarketsResponse { markets }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct EndingSoonQuery {
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
     pub limit: Option<i64>,
     /// Hours from now to consider "ending soon" (default: 24)
+    #[validate(range(min = 1))]
     pub hours: Option<i64>,
 }
 
@@ -1678,8 +2079,8 @@ pub struct EndingSoonQuery {
 /// GET /markets/new
 pub async fn get_new_markets(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<TrendingQuery>,
-) -> Result<Json<TrendingMarketsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidQuery(query): ValidQuery<TrendingQuery>,
+) -> Result<Json<TrendingMarketsResponse>, AppError> {
     let limit = query.limit.unwrap_or(10).min(50);
 
     let markets_data: Vec<(
@@ -1705,22 +2106,12 @@ pub async fn get_new_markets(
     )
     .bind(limit)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch new markets: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch new markets".to_string(),
-                code: "NEW_MARKETS_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let mut markets = Vec::new();
     for (id, question, description, category, status, resolution_source, end_time, volume_24h, total_volume, created_at) in markets_data {
-        let outcomes_data: Vec<(Uuid, String, Decimal)> = sqlx::query_as(
-            "SELECT id, name, probability FROM outcomes WHERE market_id = $1 ORDER BY name",
+        let outcomes_data: Vec<OutcomeRow> = sqlx::query_as(
+            "SELECT id, name, probability, twap_1h, twap_24h FROM outcomes WHERE market_id = $1 ORDER BY name",
         )
         .bind(id)
         .fetch_all(&state.db.pool)
@@ -1729,7 +2120,13 @@ pub async fn get_new_markets(
 
         let outcomes: Vec<OutcomeInfo> = outcomes_data
             .into_iter()
-            .map(|(oid, name, probability)| OutcomeInfo { id: oid, name, probability })
+            .map(|(oid, name, probability, twap_1h, twap_24h)| OutcomeInfo {
+                id: oid,
+                name,
+                probability,
+                twap_1h,
+                twap_24h,
+            })
             .collect();
 
         markets.push(MarketInfo {
@@ -1745,9 +2142,281 @@ pub async fn get_new_markets(
             total_volume,
             liquidity: Decimal::ZERO,
             created_at: created_at.timestamp_millis(),
+            trading_hours: None,
         });
     }
 
     Ok(Json(TrendingMarketsResponse { markets }))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::models::OrderStatus;
+    use crate::services::matching::OrderFlowError;
+    use crate::services::order_gateway::OrderGatewayError;
+    use crate::services::settlement::SettlementService;
+    use crate::test_support::{account, gateway_order, TestApp, MAKER, TAKER};
+
+    /// Create a categorical market with the given answers, returning its id
+    /// and outcome ids in answer order
+    async fn categorical_market(app: &TestApp, answers: &[&str]) -> (Uuid, Vec<Uuid>) {
+        let request = CreateCategoricalMarketRequest {
+            condition_id: format!("0x{}", hex::encode(Uuid::new_v4().as_bytes()).repeat(2)),
+            question: "Which answer?".to_string(),
+            description: None,
+            category: None,
+            resolution_source: None,
+            end_time: None,
+            resolution_time: None,
+            outcomes: answers
+                .iter()
+                .map(|name| CategoricalOutcomeRequest {
+                    name: name.to_string(),
+                    token_id: format!("{}-token", name),
+                })
+                .collect(),
+            share_decimals: None,
+        };
+        let Json(created) = create_categorical_market(State(app.state.clone()), ValidJson(request)).await.unwrap();
+        assert_eq!(created.outcome_ids.len(), answers.len());
+        (created.market_id, created.outcome_ids)
+    }
+
+    async fn holding(app: &TestApp, user: &str, outcome_id: Uuid) -> Decimal {
+        sqlx::query_scalar("SELECT COALESCE(SUM(amount), 0) FROM shares WHERE user_address = $1 AND outcome_id = $2")
+            .bind(user)
+            .bind(outcome_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap()
+    }
+
+    async fn balance(app: &TestApp, user: &str) -> (Decimal, Decimal) {
+        sqlx::query_as("SELECT available, frozen FROM balances WHERE user_address = $1 AND token = $2")
+            .bind(user)
+            .bind(app.state.config.collateral_symbol())
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap()
+    }
+
+    fn markets_query(sort: Option<&str>, order: Option<&str>) -> ValidQuery<MarketsQuery> {
+        ValidQuery(MarketsQuery {
+            q: None,
+            category: None,
+            status: None,
+            sort: sort.map(str::to_string),
+            order: order.map(str::to_string),
+            ends_before: None,
+            ends_after: None,
+            limit: None,
+            offset: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_market_list_reads_trade_aggregates() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+        let (quiet, _, _) = app.create_market().await;
+        let notional = trade.price * trade.amount;
+
+        let Json(listed) = list_markets(State(app.state.clone()), markets_query(None, None)).await.unwrap();
+        assert_eq!(listed.total, 2);
+        let ids: Vec<Uuid> = listed.markets.iter().map(|m| m.id).collect();
+        assert_eq!(ids, [trade.market_id, quiet]);
+        let traded = &listed.markets[0];
+        assert_eq!((traded.volume_24h, traded.total_volume), (notional, notional));
+        let mut outcomes: Vec<&str> = traded.outcomes.iter().map(|o| o.name.as_str()).collect();
+        outcomes.sort();
+        assert_eq!(outcomes, ["no", "yes"]);
+        assert_eq!(listed.markets[1].total_volume, Decimal::ZERO);
+
+        let Json(ascending) =
+            list_markets(State(app.state.clone()), markets_query(Some("volume"), Some("asc"))).await.unwrap();
+        assert_eq!(ascending.markets[0].id, quiet);
+
+        // A busted trade no longer counts
+        sqlx::query("UPDATE trades SET adjustment_status = 'busted' WHERE id = $1")
+            .bind(trade.trade_id)
+            .execute(&app.db.pool)
+            .await
+            .unwrap();
+        let Json(listed) = list_markets(State(app.state.clone()), markets_query(None, None)).await.unwrap();
+        let traded = listed.markets.iter().find(|m| m.id == trade.market_id).unwrap();
+        assert_eq!((traded.volume_24h, traded.total_volume), (Decimal::ZERO, Decimal::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_orderbook_history_serves_the_book_as_it_stood() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.deposit(TAKER, dec!(100)).await;
+        let gateway = &app.state.order_gateway;
+        let history = &app.state.orderbook_history;
+        let at = |at: i64| {
+            ValidQuery(OrderbookHistoryQuery {
+                outcome_id: yes,
+                share_type: None,
+                at,
+            })
+        };
+        let bid_prices = |levels: &[OrderbookLevel]| -> Vec<Decimal> {
+            levels.iter().map(|level| level.price.parse().unwrap()).collect()
+        };
+
+        // The Yes book and the empty No book it is matched against
+        gateway.place(gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.4))).await.unwrap();
+        assert_eq!(history.capture().await.unwrap(), 2);
+        // Unchanged books are not written again
+        assert_eq!(history.capture().await.unwrap(), 0);
+
+        let first = Utc::now().timestamp_millis();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        gateway.place(gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.3))).await.unwrap();
+        assert_eq!(history.capture().await.unwrap(), 1);
+
+        let Json(then) = get_orderbook_history(State(app.state.clone()), Path(market_id), at(first)).await.unwrap();
+        assert_eq!(bid_prices(&then.bids), [dec!(0.4)]);
+        assert!(then.snapshot_at <= first);
+        assert_eq!((then.share_type, then.at), (ShareType::Yes, first));
+
+        let now = Utc::now().timestamp_millis();
+        let Json(latest) = get_orderbook_history(State(app.state.clone()), Path(market_id), at(now)).await.unwrap();
+        assert_eq!(bid_prices(&latest.bids), [dec!(0.4), dec!(0.3)]);
+        assert!(latest.asks.is_empty());
+
+        let before = get_orderbook_history(State(app.state.clone()), Path(market_id), at(0)).await.unwrap_err();
+        assert_eq!(before.code(), "SNAPSHOT_NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_categorical_buys_mint_a_set_and_the_winner_pays() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcomes) = categorical_market(&app, &["Red", "Green", "Blue"]).await;
+        app.deposit(MAKER, dec!(100)).await;
+        app.deposit(TAKER, dec!(100)).await;
+        let gateway = &app.state.order_gateway;
+
+        // Answers trade Yes shares only
+        let mut no_order = gateway_order(market_id, outcomes[0], TAKER, OrderSide::Buy, dec!(0.5));
+        no_order.share_type = ShareType::No;
+        let refused = gateway.place(no_order).await.unwrap_err();
+        assert!(matches!(refused, OrderGatewayError::Flow(OrderFlowError::CategoricalNoShares)));
+
+        // Bids on two answers leave 0.4 for the third
+        let mut makers = Vec::new();
+        for outcome_id in &outcomes[..2] {
+            let bid = gateway_order(market_id, *outcome_id, MAKER, OrderSide::Buy, dec!(0.3));
+            let resting = gateway.place(bid).await.unwrap();
+            assert_eq!(resting.status, OrderStatus::Open);
+            makers.push(resting.order_id);
+        }
+        let taking = gateway
+            .place(gateway_order(market_id, outcomes[2], TAKER, OrderSide::Buy, dec!(0.5)))
+            .await
+            .unwrap();
+        assert_eq!((taking.status, taking.filled_amount), (OrderStatus::Filled, dec!(10)));
+        assert!(taking.trades.is_empty());
+
+        let (set_id, match_type, price, amount): (Uuid, String, Decimal, Decimal) = sqlx::query_as(
+            "SELECT id, match_type::text, price, amount FROM set_fills WHERE taker_order_id = $1",
+        )
+        .bind(taking.order_id)
+        .fetch_one(&app.db.pool)
+        .await
+        .unwrap();
+        assert_eq!((match_type.as_str(), price, amount), ("mint", dec!(0.4), dec!(10)));
+        let legs: Vec<(Uuid, Uuid)> = sqlx::query_as(
+            "SELECT outcome_id, maker_order_id FROM set_fill_legs WHERE set_fill_id = $1 ORDER BY leg_index",
+        )
+        .bind(set_id)
+        .fetch_all(&app.db.pool)
+        .await
+        .unwrap();
+        assert_eq!(legs, [(outcomes[0], makers[0]), (outcomes[1], makers[1])]);
+
+        // One share of every answer was minted per set
+        assert_eq!(holding(&app, MAKER, outcomes[0]).await, dec!(10));
+        assert_eq!(holding(&app, MAKER, outcomes[1]).await, dec!(10));
+        assert_eq!(holding(&app, TAKER, outcomes[2]).await, dec!(10));
+        let minted: Vec<Decimal> = sqlx::query_scalar(
+            "SELECT set_minted FROM outcome_share_supply WHERE market_id = $1 ORDER BY outcome_id",
+        )
+        .bind(market_id)
+        .fetch_all(&app.db.pool)
+        .await
+        .unwrap();
+        assert_eq!(minted, [dec!(10); 3]);
+
+        // The taker pays the set's price, not its limit
+        assert_eq!(balance(&app, TAKER).await, (dec!(96), Decimal::ZERO));
+        assert_eq!(balance(&app, MAKER).await, (dec!(94), Decimal::ZERO));
+        let statuses: Vec<String> = sqlx::query_scalar("SELECT status::text FROM orders WHERE id = ANY($1)")
+            .bind(&makers)
+            .fetch_all(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(statuses, ["filled", "filled"]);
+
+        let request = ResolveMarketRequest {
+            winning_outcome: String::new(),
+            winning_outcome_id: Some(outcomes[2]),
+            justification: None,
+            sources: Vec::new(),
+        };
+        let Json(resolved) = resolve(&app.state, &account(MAKER), market_id, request).await.unwrap();
+        assert_eq!(resolved.status, "resolved");
+
+        let winner = SettlementService::settle_user_shares(&app.db.pool, market_id, &account(TAKER)).await.unwrap();
+        assert_eq!(winner.total_payout, dec!(10));
+        let loser = SettlementService::settle_user_shares(&app.db.pool, market_id, &account(MAKER)).await.unwrap();
+        assert_eq!(loser.total_payout, Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_categorical_sells_merge_a_set() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcomes) = categorical_market(&app, &["North", "East", "South", "West"]).await;
+        for outcome_id in &outcomes[..3] {
+            app.grant_shares(MAKER, market_id, *outcome_id, ShareType::Yes, dec!(10)).await;
+        }
+        app.grant_shares(TAKER, market_id, outcomes[3], ShareType::Yes, dec!(10)).await;
+        app.deposit(MAKER, Decimal::ZERO).await;
+        app.deposit(TAKER, Decimal::ZERO).await;
+        let gateway = &app.state.order_gateway;
+
+        // Asks on three answers at 0.6 together leave the fourth 0.4
+        for outcome_id in &outcomes[..3] {
+            gateway.place(gateway_order(market_id, *outcome_id, MAKER, OrderSide::Sell, dec!(0.2))).await.unwrap();
+        }
+        let taking = gateway
+            .place(gateway_order(market_id, outcomes[3], TAKER, OrderSide::Sell, dec!(0.3)))
+            .await
+            .unwrap();
+        assert_eq!(taking.status, OrderStatus::Filled);
+
+        let (match_type, price): (String, Decimal) =
+            sqlx::query_as("SELECT match_type::text, price FROM set_fills WHERE taker_order_id = $1")
+                .bind(taking.order_id)
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!((match_type.as_str(), price), ("merge", dec!(0.4)));
+        for outcome_id in &outcomes[..3] {
+            assert_eq!(holding(&app, MAKER, *outcome_id).await, Decimal::ZERO);
+        }
+        assert_eq!(holding(&app, TAKER, outcomes[3]).await, Decimal::ZERO);
+        let merged: Vec<Decimal> = sqlx::query_scalar(
+            "SELECT set_merged FROM outcome_share_supply WHERE market_id = $1",
+        )
+        .bind(market_id)
+        .fetch_all(&app.db.pool)
+        .await
+        .unwrap();
+        assert_eq!(merged, [dec!(10); 4]);
+    }
+}
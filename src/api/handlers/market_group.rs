@@ -0,0 +1,182 @@
+//! Market Group Handlers
+//!
+//! Admins group the sibling markets of one event; in negative-risk groups
+//! users can convert complete sets of No shares into collateral, mirrored
+//! on chain through the NegRiskAdapter when the group has a market there.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson};
+use crate::auth::middleware::AuthUser;
+use crate::services::feature_flags;
+use crate::services::neg_risk::{self, Conversion, ConversionPreview, GroupMarket, MarketGroup, NegRiskError};
+use crate::AppState;
+
+// ============================================================================
+// Request/Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateGroupRequest {
+    #[validate(length(min = 1))]
+    pub title: String,
+    pub market_ids: Vec<Uuid>,
+    /// Outcomes are mutually exclusive across the markets (default true)
+    #[serde(default = "default_neg_risk")]
+    pub neg_risk: bool,
+    /// NegRiskAdapter market (bytes32 hex) whose questions are the markets
+    pub neg_risk_market_id: Option<String>,
+}
+
+fn default_neg_risk() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ConvertRequest {
+    /// Complete No sets to convert; all of them when omitted
+    #[validate(custom = "validation::positive")]
+    pub amount: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupResponse {
+    #[serde(flatten)]
+    pub group: MarketGroup,
+    pub markets: Vec<GroupMarket>,
+}
+
+fn neg_risk_error(e: NegRiskError) -> AppError {
+    let status = match e {
+        NegRiskError::GroupNotFound | NegRiskError::MarketNotFound(_) | NegRiskError::ConversionNotFound => {
+            StatusCode::NOT_FOUND
+        }
+        NegRiskError::AlreadyGrouped(_) | NegRiskError::SiblingResolvedYes(_) | NegRiskError::NotSubmittable(_) => {
+            StatusCode::CONFLICT
+        }
+        NegRiskError::Database(e) => return e.into(),
+        _ => StatusCode::BAD_REQUEST,
+    };
+    AppError::new(status, e.code(), e.to_string())
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Group active markets of one event
+/// POST /admin/market-groups
+pub async fn create_group(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<CreateGroupRequest>,
+) -> Result<(StatusCode, Json<GroupResponse>), AppError> {
+    if req.title.trim().is_empty() {
+        return Err(AppError::bad_request("INVALID_TITLE", "title is required"));
+    }
+
+    let group = neg_risk::create_group(
+        &state.db.pool,
+        req.title.trim(),
+        req.neg_risk,
+        req.neg_risk_market_id.as_deref(),
+        &req.market_ids,
+        &auth_user.address,
+    )
+    .await
+    .map_err(neg_risk_error)?;
+    let mut conn = state.db.pool.acquire().await.map_err(|e| neg_risk_error(e.into()))?;
+    let markets = neg_risk::group_markets(&mut conn, group.id, None)
+        .await
+        .map_err(|e| neg_risk_error(e.into()))?;
+
+    tracing::info!("Market group {} created by {} with {} markets", group.id, auth_user.address, markets.len());
+    Ok((StatusCode::CREATED, Json(GroupResponse { group, markets })))
+}
+
+/// Get a group and its markets
+/// GET /market-groups/:group_id
+pub async fn get_group(
+    State(state): State<Arc<AppState>>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<GroupResponse>, AppError> {
+    let group = neg_risk::get_group(&state.db.pool, group_id).await.map_err(neg_risk_error)?;
+    let mut conn = state.db.pool.acquire().await.map_err(|e| neg_risk_error(e.into()))?;
+    let markets = neg_risk::group_markets(&mut conn, group_id, None)
+        .await
+        .map_err(|e| neg_risk_error(e.into()))?;
+    Ok(Json(GroupResponse { group, markets }))
+}
+
+/// The caller's No positions in a negative-risk group and their conversion value
+/// GET /market-groups/:group_id/conversion
+pub async fn get_conversion_preview(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<ConversionPreview>, AppError> {
+    let preview = neg_risk::preview(&state.db.pool, group_id, &auth_user.address)
+        .await
+        .map_err(neg_risk_error)?;
+    Ok(Json(preview))
+}
+
+/// Convert complete No sets of a negative-risk group into collateral; a
+/// conversion left `pending` is sent on chain in the background
+/// POST /market-groups/:group_id/convert
+pub async fn convert(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(group_id): Path<Uuid>,
+    ValidJson(req): ValidJson<ConvertRequest>,
+) -> Result<Json<Conversion>, AppError> {
+    if !state
+        .feature_flags
+        .is_enabled(feature_flags::NEG_RISK_CONVERSION, Some(auth_user.address.as_str()))
+    {
+        return Err(AppError::forbidden("FEATURE_DISABLED", "No-set conversion is not enabled"));
+    }
+
+    let conversion = neg_risk::convert(
+        &state.db.pool,
+        group_id,
+        &auth_user.address,
+        req.amount,
+        state.config.collateral_symbol(),
+    )
+    .await
+    .map_err(neg_risk_error)?;
+    Ok(Json(conversion))
+}
+
+/// Send a pending or failed conversion to the NegRiskAdapter again, or one
+/// whose submission was interrupted once it is checked not to have landed
+/// POST /admin/neg-risk-conversions/:conversion_id/submit
+pub async fn submit_conversion(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(conversion_id): Path<Uuid>,
+) -> Result<Json<Conversion>, AppError> {
+    let client = state.blockchain_client.as_ref().ok_or_else(|| {
+        AppError::new(StatusCode::SERVICE_UNAVAILABLE, "CHAIN_UNAVAILABLE", "Blockchain client not configured")
+    })?;
+
+    let conversion = neg_risk::submit_onchain(&state.db.pool, client.as_ref(), conversion_id)
+        .await
+        .map_err(neg_risk_error)?;
+    tracing::info!(
+        "Conversion {} submitted on chain by {}: {}",
+        conversion_id, auth_user.address, conversion.chain_status
+    );
+    Ok(Json(conversion))
+}
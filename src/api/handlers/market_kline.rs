@@ -3,8 +3,7 @@
 //! Generates OHLC candlestick data from prediction market trades.
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::{Path, State},
     Json,
 };
 use chrono::{DateTime, Duration, DurationRound, Utc};
@@ -12,11 +11,14 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidQuery};
 use crate::AppState;
 
 /// Query parameters for market klines
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct MarketKlinesQuery {
     /// Outcome ID to get klines for
     pub outcome_id: Uuid,
@@ -28,6 +30,7 @@ pub struct MarketKlinesQuery {
     pub period: String,
     /// Maximum number of candles (default: 100, max: 500)
     #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
     pub limit: i64,
 }
 
@@ -70,15 +73,8 @@ pub struct MarketKlinesResponse {
     pub candles: Vec<Candlestick>,
 }
 
-/// Error response
-#[derive(Debug, Serialize)]
-pub struct KlineErrorResponse {
-    pub error: String,
-    pub code: String,
-}
-
 /// Get period duration in seconds
-fn get_period_seconds(period: &str) -> Option<i64> {
+pub fn get_period_seconds(period: &str) -> Option<i64> {
     match period {
         "1m" => Some(60),
         "5m" => Some(300),
@@ -97,17 +93,11 @@ fn get_period_seconds(period: &str) -> Option<i64> {
 pub async fn get_market_klines(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-    Query(query): Query<MarketKlinesQuery>,
-) -> Result<Json<MarketKlinesResponse>, (StatusCode, Json<KlineErrorResponse>)> {
+    ValidQuery(query): ValidQuery<MarketKlinesQuery>,
+) -> Result<Json<MarketKlinesResponse>, AppError> {
     // Validate period
     let period_seconds = get_period_seconds(&query.period).ok_or_else(|| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(KlineErrorResponse {
-                error: "Invalid period. Must be one of: 1m, 5m, 15m, 30m, 1h, 4h, 1d".to_string(),
-                code: "INVALID_PERIOD".to_string(),
-            }),
-        )
+        AppError::bad_request("INVALID_PERIOD", "Invalid period. Must be one of: 1m, 5m, 15m, 30m, 1h, 4h, 1d")
     })?;
 
     // Validate limit
@@ -134,17 +124,7 @@ pub async fn get_market_klines(
     .bind(&query.share_type)
     .bind(start_time)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch trades for klines: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(KlineErrorResponse {
-                error: "Failed to fetch trade data".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Aggregate trades into candles
     let mut candles: Vec<Candlestick> = Vec::new();
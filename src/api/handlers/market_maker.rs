@@ -5,43 +5,50 @@
 //! - Spread management
 //! - Market maker statistics and performance
 //! - Fee tier information
+//! - Settlement reconciliation of fills to on-chain transactions
+//! - Fill rate and loss protections that pull quotes on toxic flow
 
-use axum::{
-    extract::State,
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::State, Json};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
 use crate::models::market::ShareType;
-use crate::models::order::OrderSide;
+use crate::models::order::{OrderSide, OrderType};
+use crate::models::Address;
+use crate::services::mm_protection::{Protection, ProtectionTrigger};
+use crate::services::order_gateway::{GatewayOrder, OrderSource};
+use crate::services::settlement::reconciliation::{self, ReconciliationFilter, SettlementReconciliation};
 use crate::AppState;
 
-use super::market::ErrorResponse;
-
 // ============================================================================
 // Request/Response Types
 // ============================================================================
 
 /// Single order in a batch
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct BatchOrderItem {
     pub market_id: Uuid,
     pub outcome_id: Uuid,
     pub share_type: ShareType,
     pub side: OrderSide,
+    #[validate(custom = "validation::price")]
     pub price: Decimal,
+    #[validate(custom = "validation::positive")]
     pub amount: Decimal,
     /// Optional client order ID for tracking
     pub client_order_id: Option<String>,
 }
 
 /// Batch order placement request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct BatchOrderRequest {
+    #[validate]
     pub orders: Vec<BatchOrderItem>,
     /// If true, all orders must succeed or none are placed
     pub atomic: Option<bool>,
@@ -67,7 +74,7 @@ pub struct BatchOrderResponse {
 }
 
 /// Batch cancel request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct BatchCancelRequest {
     /// List of order IDs to cancel
     pub order_ids: Option<Vec<Uuid>>,
@@ -88,20 +95,25 @@ pub struct BatchCancelResponse {
 }
 
 /// Two-sided quote for market making
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct TwoSidedQuote {
     pub market_id: Uuid,
     pub outcome_id: Uuid,
     pub share_type: ShareType,
+    #[validate(custom = "validation::price")]
     pub bid_price: Decimal,
+    #[validate(custom = "validation::positive")]
     pub bid_size: Decimal,
+    #[validate(custom = "validation::price")]
     pub ask_price: Decimal,
+    #[validate(custom = "validation::positive")]
     pub ask_size: Decimal,
 }
 
 /// Quote update request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateQuotesRequest {
+    #[validate]
     pub quotes: Vec<TwoSidedQuote>,
     /// Cancel existing orders before placing new ones
     pub replace_existing: Option<bool>,
@@ -125,9 +137,10 @@ pub struct QuoteResult {
 }
 
 /// Market maker stats request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct StatsQuery {
     pub market_id: Option<Uuid>,
+    #[validate(range(min = 1))]
     pub days: Option<i32>,
 }
 
@@ -171,7 +184,7 @@ pub struct FeeTiersResponse {
 // Fee Tier Configuration
 // ============================================================================
 
-fn get_all_fee_tiers() -> Vec<FeeTier> {
+pub(crate) fn get_all_fee_tiers() -> Vec<FeeTier> {
     vec![
         FeeTier {
             tier: "standard".to_string(),
@@ -206,7 +219,7 @@ fn get_all_fee_tiers() -> Vec<FeeTier> {
     ]
 }
 
-fn get_user_fee_tier(volume_30d: Decimal) -> FeeTier {
+pub(crate) fn get_user_fee_tier(volume_30d: Decimal) -> FeeTier {
     let tiers = get_all_fee_tiers();
     let mut current_tier = tiers[0].clone();
 
@@ -221,6 +234,30 @@ fn get_user_fee_tier(volume_30d: Decimal) -> FeeTier {
     current_tier
 }
 
+/// Lowest tier above `volume_30d`, if any
+pub(crate) fn get_next_fee_tier(volume_30d: Decimal) -> Option<FeeTier> {
+    get_all_fee_tiers()
+        .into_iter()
+        .find(|tier| tier.volume_threshold > volume_30d)
+}
+
+/// 30-day traded notional the user's fee tier is based on
+pub(crate) async fn get_volume_30d(pool: &sqlx::PgPool, user_address: &Address) -> Decimal {
+    let volume_30d: (Option<Decimal>,) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(filled_amount * price), 0)
+        FROM orders
+        WHERE user_address = $1 AND created_at >= NOW() - INTERVAL '30 days'
+        "#,
+    )
+    .bind(user_address)
+    .fetch_one(pool)
+    .await
+    .unwrap_or((None,));
+
+    volume_30d.0.unwrap_or(Decimal::ZERO)
+}
+
 // ============================================================================
 // Handlers
 // ============================================================================
@@ -229,9 +266,9 @@ fn get_user_fee_tier(volume_30d: Decimal) -> FeeTier {
 /// POST /api/v1/mm/orders/batch
 pub async fn batch_place_orders(
     State(state): State<Arc<AppState>>,
-    axum::Extension(user_address): axum::Extension<String>,
-    Json(req): Json<BatchOrderRequest>,
-) -> Result<Json<BatchOrderResponse>, (StatusCode, Json<ErrorResponse>)> {
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+    ValidJson(req): ValidJson<BatchOrderRequest>,
+) -> Result<Json<BatchOrderResponse>, AppError> {
     let atomic = req.atomic.unwrap_or(false);
     let mut results = Vec::new();
     let mut successful = 0;
@@ -282,13 +319,7 @@ pub async fn batch_place_orders(
                             }
                         }
                     }
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        Json(ErrorResponse {
-                            error: "Atomic batch failed".to_string(),
-                            code: "ATOMIC_BATCH_FAILED".to_string(),
-                        }),
-                    ));
+                    return Err(AppError::bad_request("ATOMIC_BATCH_FAILED", "Atomic batch failed"));
                 }
             }
         }
@@ -306,9 +337,9 @@ pub async fn batch_place_orders(
 /// DELETE /api/v1/mm/orders/batch
 pub async fn batch_cancel_orders(
     State(state): State<Arc<AppState>>,
-    axum::Extension(user_address): axum::Extension<String>,
-    Json(req): Json<BatchCancelRequest>,
-) -> Result<Json<BatchCancelResponse>, (StatusCode, Json<ErrorResponse>)> {
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+    ValidJson(req): ValidJson<BatchCancelRequest>,
+) -> Result<Json<BatchCancelResponse>, AppError> {
     let mut order_ids_to_cancel: Vec<Uuid> = Vec::new();
 
     // If specific order IDs provided
@@ -388,17 +419,7 @@ pub async fn batch_cancel_orders(
                     .await
             }
             _ => Ok(vec![]),
-        }
-        .map_err(|e| {
-            tracing::error!("Failed to fetch orders: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to fetch orders".to_string(),
-                    code: "ORDER_FETCH_FAILED".to_string(),
-                }),
-            )
-        })?;
+        }?;
 
         order_ids_to_cancel.extend(orders.into_iter().map(|(id,)| id));
     }
@@ -431,9 +452,9 @@ pub async fn batch_cancel_orders(
 /// PUT /api/v1/mm/quotes
 pub async fn update_quotes(
     State(state): State<Arc<AppState>>,
-    axum::Extension(user_address): axum::Extension<String>,
-    Json(req): Json<UpdateQuotesRequest>,
-) -> Result<Json<UpdateQuotesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+    ValidJson(req): ValidJson<UpdateQuotesRequest>,
+) -> Result<Json<UpdateQuotesResponse>, AppError> {
     let replace = req.replace_existing.unwrap_or(true);
     let mut cancelled = 0;
     let mut placed = 0;
@@ -538,9 +559,9 @@ pub async fn update_quotes(
 /// GET /api/v1/mm/stats
 pub async fn get_mm_stats(
     State(state): State<Arc<AppState>>,
-    axum::Extension(user_address): axum::Extension<String>,
-    Query(query): Query<StatsQuery>,
-) -> Result<Json<MarketMakerStats>, (StatusCode, Json<ErrorResponse>)> {
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<StatsQuery>,
+) -> Result<Json<MarketMakerStats>, AppError> {
     let days = query.days.unwrap_or(30);
     let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
 
@@ -560,17 +581,7 @@ pub async fn get_mm_stats(
     .bind(cutoff)
     .bind(query.market_id)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch order stats: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch stats".to_string(),
-                code: "STATS_FETCH_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let (total_orders, total_fills, total_volume) = order_stats.unwrap_or((0, 0, None));
     let total_volume = total_volume.unwrap_or(Decimal::ZERO);
@@ -592,23 +603,11 @@ pub async fn get_mm_stats(
     };
 
     // Get fee tier based on 30-day volume
-    let volume_30d: (Option<Decimal>,) = sqlx::query_as(
-        r#"
-        SELECT COALESCE(SUM(filled_amount * price), 0)
-        FROM orders
-        WHERE user_address = $1 AND created_at >= NOW() - INTERVAL '30 days'
-        "#,
-    )
-    .bind(&user_address)
-    .fetch_one(&state.db.pool)
-    .await
-    .unwrap_or((None,));
-
-    let volume_30d = volume_30d.0.unwrap_or(Decimal::ZERO);
+    let volume_30d = get_volume_30d(&state.db.pool, &user_address).await;
     let fee_tier = get_user_fee_tier(volume_30d);
 
     Ok(Json(MarketMakerStats {
-        address: user_address,
+        address: user_address.into(),
         total_orders,
         total_fills,
         total_volume,
@@ -627,40 +626,15 @@ pub async fn get_mm_stats(
 /// GET /api/v1/mm/fee-tiers
 pub async fn get_fee_tiers(
     State(state): State<Arc<AppState>>,
-    axum::Extension(user_address): axum::Extension<String>,
-) -> Result<Json<FeeTiersResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Get 30-day volume
-    let volume_30d: (Option<Decimal>,) = sqlx::query_as(
-        r#"
-        SELECT COALESCE(SUM(filled_amount * price), 0)
-        FROM orders
-        WHERE user_address = $1 AND created_at >= NOW() - INTERVAL '30 days'
-        "#,
-    )
-    .bind(&user_address)
-    .fetch_one(&state.db.pool)
-    .await
-    .unwrap_or((None,));
-
-    let volume_30d = volume_30d.0.unwrap_or(Decimal::ZERO);
-    let tiers = get_all_fee_tiers();
-    let current_tier = get_user_fee_tier(volume_30d);
-
-    // Find next tier
-    let mut next_tier = None;
-    let mut volume_to_next = None;
-
-    for tier in &tiers {
-        if tier.volume_threshold > volume_30d {
-            next_tier = Some(tier.clone());
-            volume_to_next = Some(tier.volume_threshold - volume_30d);
-            break;
-        }
-    }
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+) -> Result<Json<FeeTiersResponse>, AppError> {
+    let volume_30d = get_volume_30d(&state.db.pool, &user_address).await;
+    let next_tier = get_next_fee_tier(volume_30d);
+    let volume_to_next = next_tier.as_ref().map(|tier| tier.volume_threshold - volume_30d);
 
     Ok(Json(FeeTiersResponse {
-        tiers,
-        current_tier,
+        tiers: get_all_fee_tiers(),
+        current_tier: get_user_fee_tier(volume_30d),
         volume_30d,
         next_tier,
         volume_to_next_tier: volume_to_next,
@@ -671,9 +645,9 @@ pub async fn get_fee_tiers(
 /// GET /api/v1/mm/orders
 pub async fn get_mm_orders(
     State(state): State<Arc<AppState>>,
-    axum::Extension(user_address): axum::Extension<String>,
-    Query(query): Query<MmOrdersQuery>,
-) -> Result<Json<MmOrdersResponse>, (StatusCode, Json<ErrorResponse>)> {
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<MmOrdersQuery>,
+) -> Result<Json<MmOrdersResponse>, AppError> {
     let limit = query.limit.unwrap_or(100).min(500);
 
     let orders: Vec<(Uuid, Uuid, Uuid, String, String, Decimal, Decimal, Decimal, String)> =
@@ -691,17 +665,7 @@ pub async fn get_mm_orders(
         .bind(query.market_id)
         .bind(limit)
         .fetch_all(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch MM orders: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to fetch orders".to_string(),
-                    code: "ORDER_FETCH_FAILED".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     let orders: Vec<MmOrderInfo> = orders
         .into_iter()
@@ -729,9 +693,10 @@ pub async fn get_mm_orders(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct MmOrdersQuery {
     pub market_id: Option<Uuid>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
     pub limit: Option<i64>,
 }
 
@@ -755,13 +720,110 @@ pub struct MmOrderInfo {
     pub status: String,
 }
 
+/// Get the caller's fills grouped by settlement transaction
+/// GET /api/v1/mm/settlements
+pub async fn get_settlements(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<SettlementsQuery>,
+) -> Result<Json<SettlementReconciliation>, AppError> {
+    if let Some(status) = query.status.as_deref() {
+        if !SETTLEMENT_STATUSES.contains(&status) {
+            return Err(AppError::bad_request(
+                "INVALID_STATUS",
+                format!("status must be one of {}", SETTLEMENT_STATUSES.join(", ")),
+            ));
+        }
+    }
+    let filter = ReconciliationFilter {
+        market_id: query.market_id,
+        status: query.status,
+        since: query.since,
+        limit: query.limit.unwrap_or(200),
+    };
+    Ok(Json(reconciliation::reconcile(&state.db.pool, &user_address, &filter).await?))
+}
+
+const SETTLEMENT_STATUSES: [&str; 4] = ["pending", "submitted", "confirmed", "failed"];
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SettlementsQuery {
+    pub market_id: Option<Uuid>,
+    /// pending, submitted, confirmed or failed
+    pub status: Option<String>,
+    /// Only fills at or after this time
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Maximum number of fills
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+/// Recent quote pulls returned with the protections
+const RECENT_TRIGGERS: i64 = 20;
+
+/// Get the caller's protections and their latest quote pulls
+/// GET /api/v1/mm/protections
+pub async fn get_protections(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+) -> Result<Json<ProtectionsResponse>, AppError> {
+    let protection = state.mm_protection.get(&user_address).await?;
+    let recent_triggers = state.mm_protection.triggers(&user_address, RECENT_TRIGGERS).await?;
+    Ok(Json(ProtectionsResponse {
+        protection,
+        recent_triggers,
+    }))
+}
+
+/// Replace the caller's protections
+/// PUT /api/v1/mm/protections
+pub async fn set_protections(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(AuthUser { address: user_address, .. }): axum::Extension<AuthUser>,
+    ValidJson(req): ValidJson<ProtectionsRequest>,
+) -> Result<Json<ProtectionsResponse>, AppError> {
+    let protection = state
+        .mm_protection
+        .set(
+            &user_address,
+            req.enabled.unwrap_or(true),
+            req.max_fills_per_second,
+            req.max_loss_per_minute,
+        )
+        .await?;
+    let recent_triggers = state.mm_protection.triggers(&user_address, RECENT_TRIGGERS).await?;
+    Ok(Json(ProtectionsResponse {
+        protection: Some(protection),
+        recent_triggers,
+    }))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ProtectionsRequest {
+    /// Defaults to true
+    pub enabled: Option<bool>,
+    /// Maker fills per market within one second; omitted = no limit
+    #[validate(range(min = 1))]
+    pub max_fills_per_second: Option<i32>,
+    /// Loss of the last minute of maker fills per market; omitted = no limit
+    #[validate(custom = "validation::positive")]
+    pub max_loss_per_minute: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProtectionsResponse {
+    /// `null` until protections are set
+    pub protection: Option<Protection>,
+    pub recent_triggers: Vec<ProtectionTrigger>,
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
 async fn place_single_order(
     state: &Arc<AppState>,
-    user_address: &str,
+    user_address: &Address,
     market_id: Uuid,
     outcome_id: Uuid,
     share_type: ShareType,
@@ -769,107 +831,38 @@ async fn place_single_order(
     price: Decimal,
     amount: Decimal,
 ) -> Result<Uuid, String> {
-    // Validate market exists and is active
-    let market_status: Option<(String,)> =
-        sqlx::query_as("SELECT status::text FROM markets WHERE id = $1")
-            .bind(market_id)
-            .fetch_optional(&state.db.pool)
-            .await
-            .map_err(|e| format!("DB error: {}", e))?;
-
-    let (status,) = market_status.ok_or("Market not found")?;
-    if status != "active" {
-        return Err(format!("Market not active: {}", status));
-    }
-
-    // Create order
-    let order_id = Uuid::new_v4();
-    let now = chrono::Utc::now();
-
-    sqlx::query(
-        r#"
-        INSERT INTO orders (
-            id, user_address, market_id, outcome_id, share_type, side,
-            order_type, price, amount, filled_amount, status, created_at
-        )
-        VALUES ($1, $2, $3, $4, $5::share_type, $6, 'limit', $7, $8, 0, 'open', $9)
-        "#,
-    )
-    .bind(order_id)
-    .bind(user_address)
-    .bind(market_id)
-    .bind(outcome_id)
-    .bind(share_type.to_string())
-    .bind(side.to_string())
-    .bind(price)
-    .bind(amount)
-    .bind(now)
-    .execute(&state.db.pool)
-    .await
-    .map_err(|e| format!("Failed to create order: {}", e))?;
-
-    // Submit to matching engine
-    let orderbook_key = format!("{}:{}:{}", market_id, outcome_id, share_type);
-    let order_side = match side {
-        OrderSide::Buy => crate::services::matching::Side::Buy,
-        OrderSide::Sell => crate::services::matching::Side::Sell,
-    };
-
-    if let Err(e) = state.matching_engine.submit_order(
-        order_id,
-        &orderbook_key,
-        user_address,
-        order_side,
-        crate::services::matching::OrderType::Limit,
-        amount,
-        Some(price),
-        1,  // leverage (not used for prediction markets)
-    ) {
-        tracing::warn!("Failed to submit to matching engine: {}", e);
-    }
+    // Same market, admission and balance checks as internal order flow
+    let placed = state
+        .order_gateway
+        .place(GatewayOrder {
+            source: OrderSource::Api,
+            user_address: user_address.clone().into(),
+            market_id,
+            outcome_id,
+            share_type,
+            side,
+            order_type: OrderType::Limit,
+            price,
+            amount,
+        })
+        .await
+        .map_err(|e| e.to_string())?;
 
-    Ok(order_id)
+    Ok(placed.order_id)
 }
 
 async fn cancel_order_internal(
     state: &Arc<AppState>,
-    user_address: &str,
+    user_address: &Address,
     order_id: Uuid,
 ) -> Result<(), String> {
-    // Check order ownership and status
-    let order: Option<(Uuid, Uuid, String, String)> = sqlx::query_as(
-        r#"
-        SELECT market_id, outcome_id, share_type, status
-        FROM orders
-        WHERE id = $1 AND user_address = $2
-        "#,
-    )
-    .bind(order_id)
-    .bind(user_address)
-    .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| format!("DB error: {}", e))?;
-
-    let (market_id, outcome_id, share_type, status) = order.ok_or("Order not found")?;
-
-    if status != "open" {
-        return Err(format!("Cannot cancel order with status: {}", status));
-    }
-
-    // Update order status
-    sqlx::query("UPDATE orders SET status = 'cancelled', updated_at = NOW() WHERE id = $1")
-        .bind(order_id)
-        .execute(&state.db.pool)
+    let cancelled = state
+        .order_gateway
+        .cancel(OrderSource::Api, user_address, order_id)
         .await
-        .map_err(|e| format!("Failed to cancel order: {}", e))?;
-
-    // Remove from matching engine
-    let orderbook_key = format!("{}:{}:{}", market_id, outcome_id, share_type);
-    let _ = state
-        .matching_engine
-        .cancel_order(&orderbook_key, order_id, user_address);
-
+        .map_err(|e| e.to_string())?;
+    if !cancelled {
+        return Err("Order is not open".to_string());
+    }
     Ok(())
 }
-
-use axum::extract::Query;
@@ -0,0 +1,201 @@
+//! Outcome Token Handlers
+//!
+//! Maps a market's outcomes to their ConditionalTokens (ERC-1155) positions,
+//! so users can hold, transfer, split or merge their shares on-chain
+//! without going through the exchange.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use ethers::types::U256;
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::api::error::AppError;
+use crate::blockchain::BlockchainClient;
+use crate::services::settlement::TokenIdCalculator;
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct OutcomeToken {
+    pub outcome_id: Uuid,
+    pub name: String,
+    pub share_type: String,
+    /// CTF index set of the outcome slot (1 = Yes, 2 = No)
+    pub index_set: u64,
+    /// getCollectionId(0x0, conditionId, indexSet); None if not read on-chain
+    pub collection_id: Option<String>,
+    /// ERC-1155 token id, getPositionId(collateral, collectionId), in decimal
+    pub position_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarketTokensResponse {
+    pub market_id: Uuid,
+    pub condition_id: String,
+    pub chain_id: Option<u64>,
+    pub collateral_token: Option<String>,
+    pub conditional_tokens: Option<String>,
+    /// Whether the condition is prepared on the ConditionalTokens contract
+    pub condition_prepared: bool,
+    /// "onchain" when the ids were read from the contract, "database" when
+    /// no chain connection is configured and the stored token ids are served
+    pub source: &'static str,
+    pub tokens: Vec<OutcomeToken>,
+}
+
+fn parse_bytes32(hex_str: &str) -> Option<[u8; 32]> {
+    let bytes = hex::decode(hex_str.trim_start_matches("0x")).ok()?;
+    bytes.try_into().ok()
+}
+
+/// Collection and position id of one outcome slot, read from the contract
+async fn onchain_position(
+    client: &BlockchainClient,
+    condition_id: [u8; 32],
+    index_set: U256,
+) -> Result<([u8; 32], U256), Box<dyn std::error::Error + Send + Sync>> {
+    let collection_id = client.get_collection_id([0u8; 32], condition_id, index_set).await?;
+    let position_id = client
+        .get_position_id(client.addresses().usdc, collection_id)
+        .await?;
+    Ok((collection_id, position_id))
+}
+
+/// Get the on-chain ERC-1155 position ids, condition id and collateral of a
+/// market's outcomes
+/// GET /markets/:market_id/tokens
+pub async fn get_market_tokens(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+) -> Result<Json<MarketTokensResponse>, AppError> {
+    let condition_id: Option<String> = sqlx::query_scalar("SELECT condition_id FROM markets WHERE id = $1")
+        .bind(market_id)
+        .fetch_optional(&state.db.pool)
+        .await?;
+    let condition_id =
+        condition_id.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+
+    let outcomes: Vec<(Uuid, String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT id, name, share_type::text, token_id
+        FROM outcomes
+        WHERE market_id = $1
+        ORDER BY share_type
+        "#,
+    )
+    .bind(market_id)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    let onchain = match (state.blockchain_client.as_ref(), parse_bytes32(&condition_id)) {
+        (Some(client), Some(condition)) => Some((client, condition)),
+        _ => None,
+    };
+
+    let Some((client, condition)) = onchain else {
+        let tokens = outcomes
+            .into_iter()
+            .map(|(outcome_id, name, share_type, token_id)| OutcomeToken {
+                index_set: TokenIdCalculator::calculate_index_set(share_type == "yes").as_u64(),
+                outcome_id,
+                name,
+                share_type,
+                collection_id: None,
+                position_id: token_id,
+            })
+            .collect();
+        return Ok(Json(MarketTokensResponse {
+            market_id,
+            condition_id,
+            chain_id: None,
+            collateral_token: None,
+            conditional_tokens: None,
+            condition_prepared: false,
+            source: "database",
+            tokens,
+        }));
+    };
+
+    let slot_count = client.get_outcome_slot_count(condition).await.map_err(|e| {
+        tracing::error!("Failed to read condition {}: {}", condition_id, e);
+        AppError::new(StatusCode::BAD_GATEWAY, "CHAIN_READ_FAILED", "Failed to read ConditionalTokens contract")
+    })?;
+
+    let mut tokens = Vec::with_capacity(outcomes.len());
+    for (outcome_id, name, share_type, stored_token_id) in outcomes {
+        let index_set = TokenIdCalculator::calculate_index_set(share_type == "yes");
+        let (collection_id, position_id) = onchain_position(client, condition, index_set).await.map_err(|e| {
+            tracing::error!("Failed to read position of outcome {}: {}", outcome_id, e);
+            AppError::new(StatusCode::BAD_GATEWAY, "CHAIN_READ_FAILED", "Failed to read ConditionalTokens contract")
+        })?;
+        let position_id = position_id.to_string();
+        if position_id != stored_token_id {
+            tracing::warn!(
+                "Outcome {} stores token id {} but its on-chain position id is {}",
+                outcome_id,
+                stored_token_id,
+                position_id
+            );
+        }
+        tokens.push(OutcomeToken {
+            outcome_id,
+            name,
+            share_type,
+            index_set: index_set.as_u64(),
+            collection_id: Some(format!("0x{}", hex::encode(collection_id))),
+            position_id,
+        });
+    }
+
+    let addresses = client.addresses();
+    Ok(Json(MarketTokensResponse {
+        market_id,
+        condition_id,
+        chain_id: Some(client.chain_id()),
+        collateral_token: Some(format!("{:?}", addresses.usdc)),
+        conditional_tokens: Some(format!("{:?}", addresses.conditional_tokens)),
+        condition_prepared: !slot_count.is_zero(),
+        source: "onchain",
+        tokens,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn test_condition_ids_must_be_32_bytes() {
+        assert_eq!(parse_bytes32(&format!("0x{}", "ab".repeat(32))), Some([0xab; 32]));
+        assert_eq!(parse_bytes32(&"ab".repeat(32)), Some([0xab; 32]));
+        assert_eq!(parse_bytes32("0xabcd"), None);
+        assert_eq!(parse_bytes32("not hex"), None);
+    }
+
+    #[tokio::test]
+    async fn test_tokens_fall_back_to_stored_ids_without_a_chain() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, no) = app.create_market().await;
+
+        let Json(response) = get_market_tokens(State(app.state.clone()), Path(market_id)).await.unwrap();
+        assert_eq!(response.source, "database");
+        assert!(!response.condition_prepared);
+        assert_eq!(response.condition_id, format!("0x{}", hex::encode(market_id.as_bytes()).repeat(2)));
+        assert_eq!(response.chain_id, None);
+        let tokens: Vec<(Uuid, &str, u64, &str)> = response
+            .tokens
+            .iter()
+            .map(|t| (t.outcome_id, t.share_type.as_str(), t.index_set, t.position_id.as_str()))
+            .collect();
+        let (yes_id, no_id) = (yes.as_u128().to_string(), no.as_u128().to_string());
+        assert_eq!(tokens, [(no, "no", 2, no_id.as_str()), (yes, "yes", 1, yes_id.as_str())]);
+
+        let missing = get_market_tokens(State(app.state.clone()), Path(Uuid::new_v4())).await.unwrap_err();
+        assert_eq!(missing.code(), "MARKET_NOT_FOUND");
+    }
+}
@@ -1,15 +1,46 @@
 //! API Handlers for Prediction Market
 
 pub mod account;
+pub mod admin_approval;
+pub mod analytics;
 pub mod auth;
+pub mod backfill;
+pub mod channel;
 pub mod ctf_order;
+pub mod ctf_position;
 pub mod deposit;
+pub mod engine;
+pub mod export;
+pub mod feature_flags;
+pub mod leaderboard;
 pub mod market;
+pub mod market_group;
 pub mod market_kline;
 pub mod market_maker;
+pub mod market_tokens;
+pub mod notification;
+pub mod ops_report;
 pub mod oracle;
 pub mod order;
+pub mod paper;
+pub mod personal_data;
+pub mod preferences;
+pub mod relayer;
+pub mod replay;
 pub mod resolution;
+pub mod resolution_schedule;
+pub mod sandbox;
+pub mod session;
+pub mod settlement_mode;
+pub mod system_events;
+pub mod time;
+pub mod trade_adjustment;
+pub mod trade_persistence;
+pub mod transfer;
+pub mod trigger_orders;
+pub mod user_admin;
+pub mod webhook;
+pub mod widget;
 pub mod withdraw;
 
 // TODO: Re-enable when needed
@@ -19,4 +50,3 @@ pub mod withdraw;
 // pub mod liquidation;
 // pub mod position;
 // pub mod referral;
-// pub mod trigger_orders;
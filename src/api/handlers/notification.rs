@@ -0,0 +1,77 @@
+//! Notification Preference Handlers
+//!
+//! Users manage the email address notifications go to and which events
+//! (fills above a notional threshold, settlement payouts, withdrawal status
+//! changes) generate an email. The same settings are part of
+//! `/account/preferences`.
+
+use axum::{extract::State, Extension, Json};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson};
+use crate::auth::middleware::AuthUser;
+use crate::services::preferences::{self, NotificationPreferences, NotificationPreferencesUpdate, PreferencesUpdate};
+use crate::AppState;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+/// Partial update; omitted fields are left unchanged
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdatePreferencesRequest {
+    /// Contact email; empty string removes it
+    pub email: Option<String>,
+    pub email_enabled: Option<bool>,
+    pub notify_fills: Option<bool>,
+    #[validate(custom = "validation::non_negative")]
+    pub fill_min_notional: Option<Decimal>,
+    pub notify_settlements: Option<bool>,
+    pub notify_withdrawals: Option<bool>,
+}
+
+impl From<UpdatePreferencesRequest> for NotificationPreferencesUpdate {
+    fn from(req: UpdatePreferencesRequest) -> Self {
+        Self {
+            email: req.email,
+            email_enabled: req.email_enabled,
+            notify_fills: req.notify_fills,
+            fill_min_notional: req.fill_min_notional,
+            notify_settlements: req.notify_settlements,
+            notify_withdrawals: req.notify_withdrawals,
+        }
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Get the user's notification preferences
+/// GET /account/notifications
+pub async fn get_preferences(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<NotificationPreferences>, AppError> {
+    let prefs = preferences::load(&state.db.pool, &auth_user.address).await?;
+    Ok(Json(prefs.notifications))
+}
+
+/// Update the user's notification preferences and contact email
+/// PUT /account/notifications
+pub async fn update_preferences(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<UpdatePreferencesRequest>,
+) -> Result<Json<NotificationPreferences>, AppError> {
+    let update = PreferencesUpdate {
+        notifications: Some(req.into()),
+        ..Default::default()
+    };
+    let prefs = preferences::update(&state.db.pool, &auth_user.address, &update).await?;
+    Ok(Json(prefs.notifications))
+}
@@ -0,0 +1,79 @@
+//! Daily Operations Report Admin Handlers
+//!
+//! Read back the daily ops reports, or regenerate one after a late
+//! correction (bust, backfill) changed the day.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidQuery};
+use crate::services::ops_report::{self, StoredReport};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReportsQuery {
+    /// Inclusive first day (YYYY-MM-DD)
+    pub from: Option<NaiveDate>,
+    /// Inclusive last day (YYYY-MM-DD)
+    pub to: Option<NaiveDate>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportsResponse {
+    pub reports: Vec<StoredReport>,
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+/// Stored reports, newest first (Admin only)
+/// GET /admin/reports
+pub async fn list_reports(
+    State(state): State<Arc<AppState>>,
+    ValidQuery(query): ValidQuery<ReportsQuery>,
+) -> Result<Json<ReportsResponse>, AppError> {
+    let limit = query.limit.unwrap_or(30);
+    let reports = ops_report::list(&state.db.pool, query.from, query.to, limit).await?;
+    Ok(Json(ReportsResponse { reports }))
+}
+
+/// One day's report (Admin only)
+/// GET /admin/reports/:date
+pub async fn get_report(
+    State(state): State<Arc<AppState>>,
+    Path(date): Path<NaiveDate>,
+) -> Result<Json<StoredReport>, AppError> {
+    ops_report::get(&state.db.pool, date)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("REPORT_NOT_FOUND", format!("No report for {}", date)))
+}
+
+/// Regenerate a finished day's report, replacing the stored one; a report
+/// already emailed is not sent again (Admin only)
+/// POST /admin/reports/:date/generate
+pub async fn generate_report(
+    State(state): State<Arc<AppState>>,
+    Path(date): Path<NaiveDate>,
+) -> Result<Json<StoredReport>, AppError> {
+    if date >= Utc::now().date_naive() {
+        return Err(AppError::bad_request("DAY_NOT_ENDED", format!("{} has not ended yet", date)));
+    }
+    let report = ops_report::generate(&state.db.pool, date).await?;
+    let stored = ops_report::store(&state.db.pool, &report).await?;
+    Ok(Json(stored))
+}
@@ -3,25 +3,22 @@
 //! Provides endpoints for querying external oracle prices (Chainlink, etc.)
 
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Path, State},
     http::StatusCode,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::ValidQuery;
 use crate::AppState;
 
 // ============================================================================
 // Response Types
 // ============================================================================
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: String,
-}
-
 /// Chainlink price response
 #[derive(Debug, Serialize)]
 pub struct ChainlinkPriceResponse {
@@ -46,7 +43,7 @@ pub struct FeedInfo {
 }
 
 /// Query params for price endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct PriceQuery {
     /// Preferred network (optional): ethereum_mainnet, ethereum_sepolia, polygon_mainnet, polygon_mumbai
     pub network: Option<String>,
@@ -60,16 +57,10 @@ pub struct PriceQuery {
 /// List available Chainlink price feeds
 pub async fn list_chainlink_feeds(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<AvailableFeedsResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<AvailableFeedsResponse>, AppError> {
     // Check if Chainlink is configured
     if state.chainlink_client.is_none() {
-        return Err((
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "Chainlink oracle not configured".to_string(),
-                code: "CHAINLINK_NOT_CONFIGURED".to_string(),
-            }),
-        ));
+        return Err(not_configured());
     }
 
     // Return available feeds (hardcoded for now, could be dynamic)
@@ -114,17 +105,9 @@ pub async fn list_chainlink_feeds(
 pub async fn get_chainlink_price(
     State(state): State<Arc<AppState>>,
     Path(feed): Path<String>,
-    Query(query): Query<PriceQuery>,
-) -> Result<Json<ChainlinkPriceResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let client = state.chainlink_client.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "Chainlink oracle not configured".to_string(),
-                code: "CHAINLINK_NOT_CONFIGURED".to_string(),
-            }),
-        )
-    })?;
+    ValidQuery(query): ValidQuery<PriceQuery>,
+) -> Result<Json<ChainlinkPriceResponse>, AppError> {
+    let client = state.chainlink_client.as_ref().ok_or_else(not_configured)?;
 
     // Normalize feed name (e.g., "btcusd" -> "BTC/USD")
     let normalized_feed = normalize_feed_name(&feed);
@@ -133,12 +116,9 @@ pub async fn get_chainlink_price(
     let price_data = if let Some(network_str) = query.network {
         // Parse network from string
         let network = parse_network(&network_str).ok_or_else(|| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: format!("Invalid network: {}. Valid options: ethereum_mainnet, ethereum_sepolia, polygon_mainnet, polygon_mumbai", network_str),
-                    code: "INVALID_NETWORK".to_string(),
-                }),
+            AppError::bad_request(
+                "INVALID_NETWORK",
+                format!("Invalid network: {}. Valid options: ethereum_mainnet, ethereum_sepolia, polygon_mainnet, polygon_mumbai", network_str),
             )
         })?;
 
@@ -157,19 +137,13 @@ pub async fn get_chainlink_price(
             updated_at: data.updated_at,
             round_id: data.round_id.to_string(),
         })),
-        Err(e) => Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Failed to fetch price: {}", e),
-                code: "PRICE_FETCH_FAILED".to_string(),
-            }),
-        )),
+        Err(e) => Err(AppError::bad_request("PRICE_FETCH_FAILED", format!("Failed to fetch price: {}", e))),
     }
 }
 
 /// GET /oracle/chainlink/prices
 /// Get prices for multiple feeds at once
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct MultiPriceQuery {
     /// Comma-separated list of feeds (e.g., "BTC/USD,ETH/USD")
     pub feeds: String,
@@ -191,17 +165,9 @@ pub struct PriceResult {
 /// GET /oracle/chainlink/prices?feeds=BTC/USD,ETH/USD
 pub async fn get_chainlink_prices(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<MultiPriceQuery>,
-) -> Result<Json<MultiPriceResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let client = state.chainlink_client.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "Chainlink oracle not configured".to_string(),
-                code: "CHAINLINK_NOT_CONFIGURED".to_string(),
-            }),
-        )
-    })?;
+    ValidQuery(query): ValidQuery<MultiPriceQuery>,
+) -> Result<Json<MultiPriceResponse>, AppError> {
+    let client = state.chainlink_client.as_ref().ok_or_else(not_configured)?;
 
     let feed_list: Vec<&str> = query.feeds.split(',').map(|s| s.trim()).collect();
     let mut prices = Vec::new();
@@ -278,6 +244,10 @@ pub async fn get_oracle_status(
 // Helper Functions
 // ============================================================================
 
+fn not_configured() -> AppError {
+    AppError::new(StatusCode::SERVICE_UNAVAILABLE, "CHAINLINK_NOT_CONFIGURED", "Chainlink oracle not configured")
+}
+
 /// Normalize feed name to standard format (e.g., "btcusd" -> "BTC/USD")
 fn normalize_feed_name(feed: &str) -> String {
     let upper = feed.to_uppercase().replace('-', "/").replace('_', "/");
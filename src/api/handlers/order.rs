@@ -12,33 +12,65 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::dto::v2;
+use crate::api::error::{self, AppError};
+use crate::api::validation::ValidJson;
 use crate::auth::eip712::{
-    verify_cancel_order_signature, verify_create_order_signature_with_debug,
-    CancelOrderMessage, CreateOrderMessage,
+    verify_amend_order_signature, verify_cancel_order_signature, verify_create_order_signature_with_debug,
+    AmendOrderMessage, CancelOrderMessage, CreateOrderMessage,
 };
 use crate::auth::middleware::AuthUser;
-use crate::models::market::ShareType;
+use crate::models::market::{DbShareType, ShareType};
 use crate::models::{
-    CreateOrderRequest, Order, OrderResponse, OrderSide, OrderStatus, OrderType,
+    CreateOrderRequest, Order, OrderResponse, OrderSide, OrderStatus, OrderType, TimeInForce,
 };
+use crate::services::matching::precision::{self, Collateral};
 use crate::services::matching::{
-    OrderFlowOrchestrator, OrderType as MatchingOrderType, Side as MatchingSide, TradeEvent,
+    BatchPlacement, MatchType, MatchingEngine, MatchingError, OrderAmendment, OrderFlowError, OrderIntent,
+    PlacedOrder, QueuePosition, RejectReason, SelfTradePrevention,
 };
-use crate::AppState;
+use crate::services::event_bus::BusEvent;
+use crate::services::feature_flags;
+use crate::services::order_gateway::OrderSource;
+use crate::services::portfolio_margin;
+use crate::services::settlement_mode::{self, SettlementMode};
+use crate::{AppState, OrdersCancelledEvent};
 
 // ============================================================================
 // Request/Response Types
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CancelOrderRequest {
     pub signature: String,
     pub timestamp: u64,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct AmendOrderRequest {
+    /// New limit price; unchanged if absent
+    pub price: Option<Decimal>,
+    /// New total size, filled part included; unchanged if absent
+    pub amount: Option<Decimal>,
+    pub signature: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AmendOrderResponse {
+    #[serde(flatten)]
+    pub order: OrderResponse,
+    /// Only a size reduction at the same price keeps the order's place in
+    /// its price level's queue
+    pub kept_priority: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<QueuePosition>,
+}
+
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct BatchCancelRequest {
     pub order_ids: Vec<Uuid>,
     pub signature: String,
@@ -51,10 +83,122 @@ pub struct BatchCancelResponse {
     pub failed: Vec<Uuid>,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct BatchPlaceRequest {
+    /// Each order signed like a single `POST /orders`; an invalid one is
+    /// reported in its result
+    #[validate(length(min = 1, max = 20))]
+    pub orders: Vec<CreateOrderRequest>,
+    /// All orders rest as makers, or none is placed (see [`place_batch`])
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchPlaceResponse {
+    pub atomic: bool,
+    pub placed: usize,
+    pub failed: usize,
+    /// An order failed and the atomic batch was rolled back
+    pub rolled_back: bool,
+    /// One per requested order, in request order
+    pub results: Vec<BatchPlaceResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchPlaceResult {
+    pub index: usize,
+    /// "placed", "failed" or "rolled_back"
+    pub status: &'static str,
+    /// The order as placed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<CreateOrderResponse>,
+    /// Why it failed, in the v2 error body shape
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<v2::ErrorBody>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ClosePositionRequest {
+    /// Close only this share type; every share type held when absent
+    pub share_type: Option<ShareType>,
+    /// How far below the best bid the exit may fill (default 0.05)
+    pub max_slippage: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClosePositionResponse {
+    pub market_id: Uuid,
+    /// One per share type held, Yes first
+    pub results: Vec<ClosedHolding>,
+}
+
+/// The exit order placed for one holding
+#[derive(Debug, Serialize)]
+pub struct ClosedHolding {
+    pub outcome_id: Uuid,
+    pub share_type: ShareType,
+    /// Shares held when the close was requested
+    pub held: Decimal,
+    /// Of those, shares committed to resting sells, which are not sold here
+    pub pending_sell: Decimal,
+    /// Best price a sell could fill at, directly or through a merge with
+    /// the complement book; absent when neither book has liquidity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_price: Option<Decimal>,
+    /// Limit of the IOC sell: `reference_price` less the slippage cap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<Uuid>,
+    pub filled_amount: Decimal,
+    /// Shares still held: what the books could not take above the limit
+    pub remaining_amount: Decimal,
+    pub average_price: Decimal,
+    pub fills: Vec<ClosedFill>,
+    /// Why nothing was sold, in the v2 error body shape
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<v2::ErrorBody>,
+}
+
+/// One fill of an exit order
+#[derive(Debug, Serialize)]
+pub struct ClosedFill {
+    /// Trade id, or set id for a complete set merged across the outcomes
+    /// of a categorical market
+    pub trade_id: Uuid,
+    /// `normal` against a bid, `merge` against a complement ask or a set
+    pub route: MatchType,
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub fee: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CancelAllResponse {
+    pub cancelled: Vec<Uuid>,
+    pub count: usize,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CancelAllAfterRequest {
+    /// Milliseconds until all open orders are cancelled; 0 disarms
+    pub timeout_ms: u64,
+}
+
 #[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: String,
+pub struct CancelAllAfterResponse {
+    pub armed: bool,
+    pub timeout_ms: u64,
+    /// When the orders will be cancelled (ms), unless refreshed first
+    pub cancel_at: Option<i64>,
+}
+
+/// One entry of the reject reason catalog
+#[derive(Debug, Serialize)]
+pub struct RejectReasonInfo {
+    pub code: &'static str,
+    pub description: &'static str,
 }
 
 #[derive(Debug, Serialize)]
@@ -63,12 +207,42 @@ pub struct CreateOrderResponse {
     pub market_id: Uuid,
     pub outcome_id: Uuid,
     pub share_type: ShareType,
+    pub time_in_force: TimeInForce,
+    pub post_only: bool,
+    /// An IOC order whose remainder was cancelled is `cancelled` with a
+    /// non-zero `filled_amount`
     pub status: OrderStatus,
     pub filled_amount: Decimal,
     pub remaining_amount: Decimal,
     pub average_price: Decimal,
     #[serde(serialize_with = "serialize_datetime_as_millis")]
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Set when the order was transformed before placement; the fields
+    /// above describe the order actually placed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transformation: Option<OrderTransformation>,
+    /// Where the unfilled remainder rests: 1-based position at its price
+    /// level and the size queued ahead of it. Absent when nothing rests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<QueuePosition>,
+    /// When the server received the request (milliseconds)
+    pub received_at: i64,
+    /// When the server finished processing it (milliseconds); `created_at`
+    /// is when the engine matched it
+    pub processed_at: i64,
+}
+
+/// An order placed differently from how it was requested
+#[derive(Debug, Serialize)]
+pub struct OrderTransformation {
+    /// "short_sell": a sell of shares the user doesn't hold placed as a buy
+    /// of the complement share at `1 - price`
+    pub kind: &'static str,
+    pub requested_side: OrderSide,
+    pub requested_share_type: ShareType,
+    pub requested_price: Decimal,
+    /// Collateral locked for the placed order
+    pub locked_collateral: Decimal,
 }
 
 fn serialize_datetime_as_millis<S>(
@@ -94,6 +268,94 @@ fn validate_timestamp(timestamp: u64) -> bool {
     now.abs_diff(timestamp) <= 300
 }
 
+/// Error for an order refused with `reason`
+fn rejection(status: StatusCode, reason: RejectReason, error: impl Into<String>) -> AppError {
+    AppError::new(status, reason.code(), error)
+}
+
+/// Error for an order the matching engine refused, with the status
+/// [`AppError`] gives it; an overload keeps the shard's retry hint and an
+/// internal failure its generic message
+pub(crate) fn engine_rejection(e: MatchingError, context: &str) -> AppError {
+    let reason = e.reject_reason();
+    let status = error::reject_status(reason);
+    if e.retry_after_ms().is_some() || status.is_server_error() {
+        return e.into();
+    }
+    AppError::new(status, reason.code(), format!("{}: {}", context, e))
+}
+
+/// Error for an order the order flow refused
+fn flow_rejection(e: OrderFlowError, collateral_symbol: &str) -> AppError {
+    let reason = e.reject_reason();
+    match e {
+        OrderFlowError::Engine(e) => engine_rejection(e, "订单提交失败"),
+        OrderFlowError::MarketNotFound => rejection(StatusCode::NOT_FOUND, reason, "市场不存在"),
+        OrderFlowError::MarketNotActive(status) => rejection(
+            StatusCode::BAD_REQUEST,
+            reason,
+            format!("市场状态为 {}，不接受新订单", status),
+        ),
+        OrderFlowError::OutsideTradingHours { next_open } => rejection(
+            StatusCode::BAD_REQUEST,
+            reason,
+            match next_open {
+                Some(at) => format!("市场不在交易时段内，下次开市时间 {}", at.to_rfc3339()),
+                None => "市场不在交易时段内".to_string(),
+            },
+        ),
+        OrderFlowError::InvalidPrice(_) => {
+            rejection(StatusCode::BAD_REQUEST, reason, "价格必须在 0.01 到 0.99 之间")
+        }
+        OrderFlowError::InvalidAmount => rejection(StatusCode::BAD_REQUEST, reason, "订单数量必须大于 0"),
+        OrderFlowError::AmountPrecision(dp) => rejection(
+            StatusCode::BAD_REQUEST,
+            reason,
+            format!("订单数量最多支持 {} 位小数", dp),
+        ),
+        OrderFlowError::InsufficientBalance { required, available } => rejection(
+            StatusCode::BAD_REQUEST,
+            reason,
+            format!("余额不足，需要 {} {}，当前可用 {}", required, collateral_symbol, available),
+        ),
+        OrderFlowError::InsufficientShares { required, held } => rejection(
+            StatusCode::BAD_REQUEST,
+            reason,
+            format!("持仓不足，需要 {}，当前持有 {}", required, held),
+        ),
+        OrderFlowError::TooManyOpenOrders { scope, limit } => rejection(
+            StatusCode::BAD_REQUEST,
+            reason,
+            match scope {
+                "account" => format!("该市场挂单数已达上限 {}，请先撤销部分订单", limit),
+                _ => format!("该市场总挂单数已达上限 {}，暂不接受新挂单", limit),
+            },
+        ),
+        OrderFlowError::AtomicBatchMarkets => {
+            rejection(StatusCode::BAD_REQUEST, reason, "原子批量的订单必须属于同一市场")
+        }
+        OrderFlowError::AtomicBatchTaker => {
+            rejection(StatusCode::BAD_REQUEST, reason, "原子批量仅支持 post-only 的 GTC 限价单")
+        }
+        OrderFlowError::CategoricalNoShares => {
+            rejection(StatusCode::BAD_REQUEST, reason, "多选项市场仅支持交易各选项的 Yes 份额")
+        }
+        OrderFlowError::Database(_) => e.into(),
+    }
+}
+
+/// Shortest non-zero cancel-all-after timeout
+const MIN_CANCEL_ALL_AFTER_MS: u64 = 1_000;
+
+/// Longest cancel-all-after timeout (10 minutes)
+const MAX_CANCEL_ALL_AFTER_MS: u64 = 600_000;
+
+/// Slippage cap of a position close when the request sets none
+const DEFAULT_CLOSE_SLIPPAGE: Decimal = Decimal::from_parts(5, 0, 0, false, 2);
+
+/// Widest slippage cap a position close accepts
+const MAX_CLOSE_SLIPPAGE: Decimal = Decimal::from_parts(50, 0, 0, false, 2);
+
 /// Validate price is within prediction market range (0.01 - 0.99)
 fn validate_price(price: Decimal) -> bool {
     let min = Decimal::new(1, 2); // 0.01
@@ -110,44 +372,84 @@ fn validate_price(price: Decimal) -> bool {
 pub async fn create_order(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Json(req): Json<CreateOrderRequest>,
-) -> Result<Json<CreateOrderResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(req): ValidJson<CreateOrderRequest>,
+) -> Result<Json<CreateOrderResponse>, AppError> {
+    let received_at = chrono::Utc::now().timestamp_millis();
+    let (intent, req, transformation) = prepare_order(&state, &auth_user, req).await?;
+
+    // Validate, reserve, match, persist, notify and enqueue fills for
+    // settlement; failed steps are compensated
+    let placed = state
+        .order_flow
+        .place(&intent)
+        .await
+        .map_err(|e| flow_rejection(e, state.config.collateral_symbol()))?;
+    Ok(Json(created_order(&req, &placed, transformation, received_at)))
+}
+
+/// Check an order request up to the intent placed for it: feature flags,
+/// settlement mode, terms, timestamp and signature. A short sell comes back
+/// as the complement buy it is placed as, with its transformation; the
+/// signature is checked against the sell as requested.
+async fn prepare_order(
+    state: &AppState,
+    auth_user: &AuthUser,
+    mut req: CreateOrderRequest,
+) -> Result<(OrderIntent, CreateOrderRequest, Option<OrderTransformation>), AppError> {
+    // Order types behind feature flags
+    if matches!(req.order_type, OrderType::Market)
+        && !state
+            .feature_flags
+            .is_enabled(feature_flags::MARKET_ORDERS, Some(auth_user.address.as_str()))
+    {
+        return Err(rejection(StatusCode::FORBIDDEN, RejectReason::FeatureDisabled, "市价单暂未开放"));
+    }
+
+    // Internal-book fills settle into internal balances; self-custody
+    // accounts trade with signed CTF orders that settle to their wallet
+    let settlement_mode = settlement_mode::get(&state.db.pool, &auth_user.address)
+        .await?;
+    if settlement_mode == SettlementMode::SelfCustody {
+        return Err(rejection(
+            StatusCode::CONFLICT,
+            RejectReason::SettlementMode,
+            "自托管账户请通过 /orders/ctf 提交签名订单",
+        ));
+    }
+
     // Validate price range
     if !validate_price(req.price) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "价格必须在 0.01 到 0.99 之间".to_string(),
-                code: "INVALID_PRICE".to_string(),
-            }),
-        ));
+        return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::InvalidPrice, "价格必须在 0.01 到 0.99 之间"));
     }
 
     // Validate amount
     if req.amount <= Decimal::ZERO {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "订单数量必须大于 0".to_string(),
-                code: "INVALID_AMOUNT".to_string(),
-            }),
-        ));
+        return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::InvalidAmount, "订单数量必须大于 0"));
+    }
+
+    // Post-only orders exist to rest in the book
+    if req.post_only && (!matches!(req.order_type, OrderType::Limit) || req.time_in_force != TimeInForce::Gtc) {
+        return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::PostOnlyWouldTake, "只挂单仅支持 GTC 限价单"));
+    }
+
+    // Only resting orders can expire
+    if let Some(expires_at) = req.expires_at {
+        if !matches!(req.order_type, OrderType::Limit) || req.time_in_force != TimeInForce::Gtc {
+            return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::InvalidExpiry, "到期时间仅支持 GTC 限价单"));
+        }
+        if expires_at <= Utc::now() {
+            return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::InvalidExpiry, "到期时间必须晚于当前时间"));
+        }
     }
 
     // Validate timestamp
     if !state.config.is_auth_disabled() && !validate_timestamp(req.timestamp) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "时间戳已过期".to_string(),
-                code: "TIMESTAMP_EXPIRED".to_string(),
-            }),
-        ));
+        return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::TimestampExpired, "时间戳已过期"));
     }
 
     // Create EIP-712 message for signature verification
     let order_msg = CreateOrderMessage {
-        wallet: auth_user.address.to_lowercase(),
+        wallet: auth_user.address.clone().into(),
         market_id: req.market_id.to_string(),
         outcome_id: req.outcome_id.to_string(),
         share_type: req.share_type.to_string(),
@@ -160,282 +462,153 @@ pub async fn create_order(
 
     // Verify EIP-712 signature
     if !state.config.is_auth_disabled() {
-        let verify_result = verify_create_order_signature_with_debug(&order_msg, &req.signature, &auth_user.address)
-            .map_err(|e| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse {
-                        error: format!("签名验证失败: {}", e),
-                        code: "SIGNATURE_INVALID".to_string(),
-                    }),
-                )
-            })?;
+        let verify_result =
+            verify_create_order_signature_with_debug(&order_msg, &req.signature, auth_user.address.as_str())
+                .map_err(|e| {
+                    rejection(StatusCode::BAD_REQUEST, RejectReason::SignatureInvalid, format!("签名验证失败: {}", e))
+                })?;
 
         if !verify_result.is_valid {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "签名验证失败".to_string(),
-                    code: "SIGNATURE_INVALID".to_string(),
-                }),
-            ));
+            return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::SignatureInvalid, "签名验证失败"));
         }
     }
 
-    // Check balance for buy orders
-    if matches!(req.side, OrderSide::Buy) {
-        let required_collateral = req.amount * req.price;
-        let collateral_symbol = state.config.collateral_symbol();
+    // From here on the order is authenticated: refusals are recorded
+    let user_address = auth_user.address.clone();
 
-        let balance: Option<Decimal> = sqlx::query_scalar(
-            "SELECT available FROM balances WHERE user_address = $1 AND token = $2"
+    // Short sell: a sell of shares the user doesn't hold becomes a buy of the
+    // complement share at 1 - price (selling Yes @ p = buying No @ 1 - p).
+    // A sell holdings only partly cover is placed as the plain sell the
+    // signature covers and refused for insufficient shares: the user sells
+    // what they hold first and shorts the rest with a new order.
+    let mut transformation = None;
+    if matches!(req.side, OrderSide::Sell) && req.allow_short {
+        let held: Option<Decimal> = sqlx::query_scalar(
+            "SELECT amount FROM shares WHERE user_address = $1 AND outcome_id = $2 AND share_type = $3::share_type",
         )
-        .bind(&auth_user.address.to_lowercase())
-        .bind(&collateral_symbol)
+        .bind(&user_address)
+        .bind(req.outcome_id)
+        .bind(req.share_type.to_string())
         .fetch_optional(&state.db.pool)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("查询余额失败: {}", e),
-                    code: "DB_ERROR".to_string(),
-                }),
-            )
-        })?;
-
-        let available_balance = balance.unwrap_or(Decimal::ZERO);
-        if available_balance < required_collateral {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: format!(
-                        "余额不足，需要 {} {}，当前可用 {}",
-                        required_collateral, collateral_symbol, available_balance
-                    ),
-                    code: "INSUFFICIENT_BALANCE".to_string(),
-                }),
-            ));
+        .await?;
+        if held.unwrap_or(Decimal::ZERO).is_zero() {
+            let placed = req.short_as_complement_buy();
+            transformation = Some(OrderTransformation {
+                kind: "short_sell",
+                requested_side: req.side,
+                requested_share_type: req.share_type,
+                requested_price: req.price,
+                locked_collateral: Collateral::notional(placed.price, placed.amount).value(),
+            });
+            tracing::info!(
+                "Short sell {} {} @ {} by {} placed as buy {} @ {}",
+                req.amount, req.share_type, req.price, user_address, placed.share_type, placed.price
+            );
+            req = placed;
         }
+    }
 
-        // Freeze collateral
-        sqlx::query(
-            "UPDATE balances SET available = available - $1, frozen = frozen + $1, updated_at = NOW()
-             WHERE user_address = $2 AND token = $3"
-        )
-        .bind(required_collateral)
-        .bind(&auth_user.address.to_lowercase())
-        .bind(&collateral_symbol)
-        .execute(&state.db.pool)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("冻结资金失败: {}", e),
-                    code: "DB_ERROR".to_string(),
-                }),
-            )
-        })?;
-    }
-
-    // Convert to matching engine types
-    let matching_side = match req.side {
-        OrderSide::Buy => MatchingSide::Buy,
-        OrderSide::Sell => MatchingSide::Sell,
-    };
-
-    let matching_order_type = match req.order_type {
-        OrderType::Limit => MatchingOrderType::Limit,
-        OrderType::Market => MatchingOrderType::Market,
-    };
-
-    // Generate order ID
-    let order_id = Uuid::new_v4();
-
-    // Build market key for orderbook: market_id:outcome_id:share_type
-    let market_key = format!("{}:{}:{}", req.market_id, req.outcome_id, req.share_type);
-
-    // Submit to matching engine
-    // For prediction markets, we use market_key as the "symbol" and leverage=1
-    let match_result = state
-        .matching_engine
-        .submit_order(
-            order_id,
-            &market_key,
-            &auth_user.address.to_lowercase(),
-            matching_side,
-            matching_order_type,
-            req.amount,
-            Some(req.price),
-            1, // No leverage in prediction markets
-        )
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("订单提交失败: {}", e),
-                    code: "MATCHING_ERROR".to_string(),
-                }),
-            )
-        })?;
-
-    // Convert status
-    let status = match match_result.status {
-        crate::services::matching::OrderStatus::Open => OrderStatus::Open,
-        crate::services::matching::OrderStatus::PartiallyFilled => OrderStatus::PartiallyFilled,
-        crate::services::matching::OrderStatus::Filled => OrderStatus::Filled,
-        crate::services::matching::OrderStatus::Cancelled => OrderStatus::Cancelled,
-        crate::services::matching::OrderStatus::Rejected => OrderStatus::Rejected,
-    };
-
-    // Calculate average price
-    let average_price = if match_result.filled_amount > Decimal::ZERO {
-        match_result
-            .trades
-            .iter()
-            .map(|t| t.price * t.amount)
-            .sum::<Decimal>()
-            / match_result.filled_amount
-    } else {
-        Decimal::ZERO
+    let intent = OrderIntent {
+        source: OrderSource::Api,
+        user_address: user_address.into(),
+        market_id: req.market_id,
+        outcome_id: req.outcome_id,
+        share_type: req.share_type,
+        side: req.side,
+        order_type: req.order_type,
+        time_in_force: req.time_in_force,
+        post_only: req.post_only,
+        stp: req.stp,
+        price: req.price,
+        amount: req.amount,
+        expires_at: req.expires_at,
+        signature: req.signature.clone(),
     };
+    Ok((intent, req, transformation))
+}
 
-    let now = Utc::now();
+/// Response for `req` placed as `placed`
+fn created_order(
+    req: &CreateOrderRequest,
+    placed: &PlacedOrder,
+    transformation: Option<OrderTransformation>,
+    received_at: i64,
+) -> CreateOrderResponse {
+    let filled_notional: Collateral = placed
+        .trades
+        .iter()
+        .map(|t| Collateral::notional(t.price, t.amount))
+        .chain(placed.set_fills.iter().map(|f| Collateral::notional(f.price, f.amount)))
+        .sum();
+    let average_price = precision::average_price(filled_notional.value(), placed.filled_amount)
+        .unwrap_or(Decimal::ZERO);
 
-    // Persist order to database
-    sqlx::query(
-        r#"
-        INSERT INTO orders (
-            id, user_address, symbol, market_id, outcome_id, share_type,
-            side, order_type, price, amount, filled_amount, status, signature,
-            created_at, updated_at
-        )
-        VALUES (
-            $1, $2, $3, $4, $5, $6::share_type,
-            $7::order_side, $8::order_type, $9, $10, $11, $12::order_status, $13,
-            $14, $14
-        )
-        "#,
-    )
-    .bind(order_id)
-    .bind(&auth_user.address.to_lowercase())
-    .bind(&market_key)
-    .bind(req.market_id)
-    .bind(req.outcome_id)
-    .bind(req.share_type.to_string())
-    .bind(req.side.to_string())
-    .bind(req.order_type.to_string())
-    .bind(req.price)
-    .bind(req.amount)
-    .bind(match_result.filled_amount)
-    .bind(status.to_string())
-    .bind(&req.signature)
-    .bind(now)
-    .execute(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to persist order: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("保存订单失败: {}", e),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    // Persist trades to database
-    for trade_exec in &match_result.trades {
-        let trade_event = TradeEvent::from_execution(
-            trade_exec,
-            market_key.clone(),
-            auth_user.address.to_lowercase(),
-            matching_side,
-        );
-
-        if let Err(e) = OrderFlowOrchestrator::persist_trade(&state.db.pool, &trade_event).await {
-            tracing::error!("Failed to persist trade {}: {}", trade_exec.trade_id, e);
-            // Continue processing other trades, don't fail the order
-        } else {
-            tracing::debug!("Persisted trade: {}", trade_exec.trade_id);
-        }
-
-        // Update maker order's filled_amount in database
-        if let Err(e) = sqlx::query(
-            r#"
-            UPDATE orders
-            SET filled_amount = filled_amount + $1,
-                status = CASE
-                    WHEN filled_amount + $1 >= amount THEN 'filled'::order_status
-                    ELSE 'partially_filled'::order_status
-                END,
-                updated_at = NOW()
-            WHERE id = $2
-            "#,
-        )
-        .bind(trade_exec.amount)
-        .bind(trade_exec.maker_order_id)
-        .execute(&state.db.pool)
-        .await
-        {
-            tracing::error!("Failed to update maker order {}: {}", trade_exec.maker_order_id, e);
-        }
-    }
-
-    Ok(Json(CreateOrderResponse {
-        order_id,
+    CreateOrderResponse {
+        order_id: placed.order_id,
         market_id: req.market_id,
         outcome_id: req.outcome_id,
         share_type: req.share_type,
-        status,
-        filled_amount: match_result.filled_amount,
-        remaining_amount: req.amount - match_result.filled_amount,
+        time_in_force: req.time_in_force,
+        post_only: req.post_only,
+        status: placed.status,
+        filled_amount: placed.filled_amount,
+        remaining_amount: placed.amount - placed.filled_amount,
         average_price,
-        created_at: now,
-    }))
+        created_at: placed.created_at,
+        transformation,
+        queue_position: placed.queue_position,
+        received_at,
+        processed_at: chrono::Utc::now().timestamp_millis(),
+    }
 }
 
-/// Get order by ID
+/// List every reject reason code with its meaning
+/// GET /orders/reject-reasons
+pub async fn get_reject_reasons() -> Json<Vec<RejectReasonInfo>> {
+    Json(
+        RejectReason::ALL
+            .into_iter()
+            .map(|reason| RejectReasonInfo {
+                code: reason.code(),
+                description: reason.description(),
+            })
+            .collect(),
+    )
+}
+
+/// Get order by ID, with its fills
 /// GET /orders/:order_id
 pub async fn get_order(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Path(order_id): Path<Uuid>,
-) -> Result<Json<OrderResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<OrderResponse>, AppError> {
     let order: Option<Order> = sqlx::query_as(
         r#"
         SELECT id, user_address, market_id, outcome_id, share_type,
-               side, order_type, price, amount, filled_amount, status, signature,
-               created_at, updated_at
+               side, order_type, COALESCE(time_in_force, 'gtc') AS time_in_force,
+               COALESCE(post_only, FALSE) AS post_only,
+               price, amount, filled_amount, status, reject_reason, source, signature,
+               created_at, updated_at, expires_at
         FROM orders
         WHERE id = $1 AND user_address = $2
         "#,
     )
     .bind(order_id)
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("查询订单失败: {}", e),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    match order {
-        Some(order) => Ok(Json(OrderResponse::from(order))),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "订单不存在".to_string(),
-                code: "ORDER_NOT_FOUND".to_string(),
-            }),
-        )),
-    }
+    .await?;
+
+    let order = order.ok_or_else(|| AppError::not_found("ORDER_NOT_FOUND", "订单不存在"))?;
+
+    let fills = state
+        .history_store
+        .get_order_fills(order.market_id, order.id)
+        .await?;
+
+    let mut response = OrderResponse::from(order);
+    response.fills = Some(fills);
+    Ok(Json(response))
 }
 
 /// Cancel an order
@@ -444,46 +617,26 @@ pub async fn cancel_order(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Path(order_id): Path<Uuid>,
-    Json(req): Json<CancelOrderRequest>,
-) -> Result<Json<OrderResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(req): ValidJson<CancelOrderRequest>,
+) -> Result<Json<OrderResponse>, AppError> {
     // Validate timestamp
     if !state.config.is_auth_disabled() && !validate_timestamp(req.timestamp) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "时间戳已过期".to_string(),
-                code: "TIMESTAMP_EXPIRED".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("TIMESTAMP_EXPIRED", "时间戳已过期"));
     }
 
     // Verify signature
     if !state.config.is_auth_disabled() {
         let cancel_msg = CancelOrderMessage {
-            wallet: auth_user.address.to_lowercase(),
+            wallet: auth_user.address.clone().into(),
             order_id: order_id.to_string(),
             timestamp: req.timestamp,
         };
 
-        let valid = verify_cancel_order_signature(&cancel_msg, &req.signature, &auth_user.address)
-            .map_err(|e| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse {
-                        error: format!("签名验证失败: {}", e),
-                        code: "SIGNATURE_INVALID".to_string(),
-                    }),
-                )
-            })?;
+        let valid = verify_cancel_order_signature(&cancel_msg, &req.signature, auth_user.address.as_str())
+            .map_err(|e| AppError::bad_request("SIGNATURE_INVALID", format!("签名验证失败: {}", e)))?;
 
         if !valid {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "签名验证失败".to_string(),
-                    code: "SIGNATURE_INVALID".to_string(),
-                }),
-            ));
+            return Err(AppError::bad_request("SIGNATURE_INVALID", "签名验证失败"));
         }
     }
 
@@ -491,96 +644,51 @@ pub async fn cancel_order(
     let order: Option<Order> = sqlx::query_as(
         r#"
         SELECT id, user_address, market_id, outcome_id, share_type,
-               side, order_type, price, amount, filled_amount, status, signature,
-               created_at, updated_at
+               side, order_type, COALESCE(time_in_force, 'gtc') AS time_in_force,
+               COALESCE(post_only, FALSE) AS post_only,
+               price, amount, filled_amount, status, reject_reason, source, signature,
+               created_at, updated_at, expires_at
         FROM orders
         WHERE id = $1 AND user_address = $2
         "#,
     )
     .bind(order_id)
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("查询订单失败: {}", e),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    let order = order.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "订单不存在".to_string(),
-                code: "ORDER_NOT_FOUND".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    let order = order.ok_or_else(|| AppError::not_found("ORDER_NOT_FOUND", "订单不存在"))?;
 
     // Check if order can be cancelled
     if !order.is_cancellable() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("订单状态 {} 无法取消", order.status),
-                code: "ORDER_NOT_CANCELLABLE".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("ORDER_NOT_CANCELLABLE", format!("订单状态 {} 无法取消", order.status)));
     }
 
     // Build market key for orderbook: market_id:outcome_id:share_type
     let market_key = format!("{}:{}:{}", order.market_id, order.outcome_id, order.share_type);
 
-    // Cancel in matching engine
+    // Cancel in matching engine on the market's shard
+    let (symbol, user) = (market_key.clone(), auth_user.address.clone());
     let cancelled = state
-        .matching_engine
-        .cancel_order(
-            &market_key,
-            order_id,
-            &auth_user.address.to_lowercase(),
-        )
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("取消订单失败: {}", e),
-                    code: "MATCHING_ERROR".to_string(),
-                }),
-            )
-        })?;
+        .engine_shards
+        .execute(&market_key, move |engine| engine.cancel_order(&symbol, order_id, user.as_str()))
+        .await
+        .and_then(|result| result)
+        .map_err(|e| engine_rejection(e, "取消订单失败"))?;
 
     if !cancelled {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "订单取消失败".to_string(),
-                code: "CANCEL_FAILED".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("CANCEL_FAILED", "订单取消失败"));
     }
 
     // Update order status in database
     sqlx::query("UPDATE orders SET status = 'cancelled'::order_status, updated_at = NOW() WHERE id = $1")
         .bind(order_id)
         .execute(&state.db.pool)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("更新订单状态失败: {}", e),
-                    code: "DB_ERROR".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     // Unfreeze collateral for buy orders
     if matches!(order.side, OrderSide::Buy) {
-        let remaining_collateral = order.remaining_amount() * order.price;
+        let remaining_collateral = Collateral::notional(order.price, order.remaining_amount()).value();
         let collateral_symbol = state.config.collateral_symbol();
 
         sqlx::query(
@@ -588,20 +696,14 @@ pub async fn cancel_order(
              WHERE user_address = $2 AND token = $3"
         )
         .bind(remaining_collateral)
-        .bind(&auth_user.address.to_lowercase())
+        .bind(&auth_user.address)
         .bind(&collateral_symbol)
         .execute(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to unfreeze collateral: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("解冻资金失败: {}", e),
-                    code: "DB_ERROR".to_string(),
-                }),
-            )
-        })?;
+        .await?;
+        // The margin credit the order earned no longer applies
+        if let Err(e) = portfolio_margin::refresh_account(&state.db.pool, &auth_user.address, collateral_symbol).await {
+            tracing::error!("Failed to resync portfolio margin of {}: {}", auth_user.address, e);
+        }
     }
 
     // Return updated order
@@ -614,22 +716,430 @@ pub async fn cancel_order(
     Ok(Json(OrderResponse::from(updated_order)))
 }
 
+/// Change the price and/or total size of a resting limit order, keeping its
+/// id and fills. Reducing the size at the same price keeps its time
+/// priority; any other change queues it behind the orders already at its
+/// price. The amended order never trades: a price that would cross the book
+/// is refused with `POST_ONLY_WOULD_TAKE`.
+/// PUT /orders/:order_id
+pub async fn amend_order(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(order_id): Path<Uuid>,
+    ValidJson(req): ValidJson<AmendOrderRequest>,
+) -> Result<Json<AmendOrderResponse>, AppError> {
+    if req.price.is_none() && req.amount.is_none() {
+        return Err(AppError::bad_request("NOTHING_TO_AMEND", "请指定新的价格或数量"));
+    }
+    if req.price.is_some_and(|price| !validate_price(price)) {
+        return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::InvalidPrice, "价格必须在 0.01 到 0.99 之间"));
+    }
+    if req.amount.is_some_and(|amount| amount <= Decimal::ZERO) {
+        return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::InvalidAmount, "订单数量必须大于 0"));
+    }
+
+    if !state.config.is_auth_disabled() {
+        if !validate_timestamp(req.timestamp) {
+            return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::TimestampExpired, "时间戳已过期"));
+        }
+        let amend_msg = AmendOrderMessage {
+            wallet: auth_user.address.clone().into(),
+            order_id: order_id.to_string(),
+            price: req.price.map(|price| price.to_string()).unwrap_or_default(),
+            amount: req.amount.map(|amount| amount.to_string()).unwrap_or_default(),
+            timestamp: req.timestamp,
+        };
+        let valid = verify_amend_order_signature(&amend_msg, &req.signature, auth_user.address.as_str())
+            .map_err(|e| rejection(StatusCode::BAD_REQUEST, RejectReason::SignatureInvalid, format!("签名验证失败: {}", e)))?;
+        if !valid {
+            return Err(rejection(StatusCode::BAD_REQUEST, RejectReason::SignatureInvalid, "签名验证失败"));
+        }
+    }
+
+    let user_address = auth_user.address.clone();
+    let not_amendable = |error: String| AppError::bad_request("ORDER_NOT_AMENDABLE", error);
+
+    // Signed CTF orders can't change without a new signature
+    let settlement_mode = settlement_mode::get(&state.db.pool, &user_address).await?;
+    if settlement_mode == SettlementMode::SelfCustody {
+        return Err(rejection(
+            StatusCode::CONFLICT,
+            RejectReason::SettlementMode,
+            "自托管账户的签名订单无法修改，请撤单后重新下单",
+        ));
+    }
+
+    let order: Order = sqlx::query_as(
+        r#"
+        SELECT id, user_address, market_id, outcome_id, share_type,
+               side, order_type, COALESCE(time_in_force, 'gtc') AS time_in_force,
+               COALESCE(post_only, FALSE) AS post_only,
+               price, amount, filled_amount, status, reject_reason, source, signature,
+               created_at, updated_at, expires_at
+        FROM orders
+        WHERE id = $1 AND user_address = $2
+        "#,
+    )
+    .bind(order_id)
+    .bind(&user_address)
+    .fetch_optional(&state.db.pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("ORDER_NOT_FOUND", "订单不存在"))?;
+    if !order.is_cancellable() || !matches!(order.order_type, OrderType::Limit) {
+        return Err(not_amendable(format!("订单状态 {} 无法修改", order.status)));
+    }
+
+    let amendment = OrderAmendment {
+        order_id,
+        user_address: user_address.into(),
+        market_id: order.market_id,
+        outcome_id: order.outcome_id,
+        share_type: order.share_type,
+        side: order.side,
+        price: order.price,
+        amount: order.amount,
+        filled_amount: order.filled_amount,
+        new_price: req.price.unwrap_or(order.price),
+        new_amount: req.amount.unwrap_or(order.amount),
+    };
+    let amended = state
+        .order_flow
+        .amend(&amendment)
+        .await
+        .map_err(|e| flow_rejection(e, state.config.collateral_symbol()))?
+        .ok_or_else(|| not_amendable("订单已不在挂单簿中".to_string()))?;
+
+    let order = Order {
+        price: amended.price,
+        amount: amended.amount,
+        filled_amount: amended.filled_amount,
+        status: if amended.filled_amount > Decimal::ZERO {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Open
+        },
+        updated_at: Utc::now(),
+        ..order
+    };
+    Ok(Json(AmendOrderResponse {
+        order: OrderResponse::from(order),
+        kept_priority: amended.kept_priority,
+        queue_position: amended.queue_position,
+    }))
+}
+
+/// Arm, refresh or disarm the caller's dead man's switch: unless called
+/// again within `timeout_ms`, all their open orders are cancelled
+/// POST /orders/cancel-all-after
+pub async fn cancel_all_after(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<CancelAllAfterRequest>,
+) -> Result<Json<CancelAllAfterResponse>, AppError> {
+    if req.timeout_ms != 0 && !(MIN_CANCEL_ALL_AFTER_MS..=MAX_CANCEL_ALL_AFTER_MS).contains(&req.timeout_ms) {
+        return Err(AppError::bad_request(
+            "INVALID_TIMEOUT",
+            format!("timeout_ms 必须为 0 或介于 {} 与 {} 之间", MIN_CANCEL_ALL_AFTER_MS, MAX_CANCEL_ALL_AFTER_MS),
+        ));
+    }
+
+    let cancel_at = state.cancel_all_after.arm(&auth_user.address, req.timeout_ms);
+
+    Ok(Json(CancelAllAfterResponse {
+        armed: cancel_at.is_some(),
+        timeout_ms: req.timeout_ms,
+        cancel_at,
+    }))
+}
+
+/// Place several orders in one request, each checked and signed like a
+/// single order. In atomic mode the batch is maker-only: it must hold
+/// post-only GTC limit orders of one market that all rest, and a taker or
+/// cross-market order refuses it outright. An invalid order, a sell the
+/// holding can't cover alongside the batch's other sells, or one that would
+/// trade on arrival places nothing. Otherwise every order is placed or
+/// refused on its own.
+/// POST /orders/batch (v2)
+pub async fn place_batch(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<BatchPlaceRequest>,
+) -> Result<Json<BatchPlaceResponse>, AppError> {
+    let received_at = chrono::Utc::now().timestamp_millis();
+    let atomic = req.atomic;
+
+    let mut prepared = Vec::with_capacity(req.orders.len());
+    let mut refused = Vec::new();
+    for (index, order) in req.orders.into_iter().enumerate() {
+        match prepare_order(&state, &auth_user, order).await {
+            Ok(order) => prepared.push((index, order)),
+            Err(error) => refused.push((index, item_error(error))),
+        }
+    }
+
+    let mut results: Vec<BatchPlaceResult> = refused
+        .into_iter()
+        .map(|(index, error)| BatchPlaceResult {
+            index,
+            status: "failed",
+            order: None,
+            error: Some(error),
+        })
+        .collect();
+    if atomic && !results.is_empty() {
+        results.extend(prepared.into_iter().map(|(index, _)| BatchPlaceResult {
+            index,
+            status: "rolled_back",
+            order: None,
+            error: None,
+        }));
+    } else {
+        let intents: Vec<OrderIntent> = prepared.iter().map(|(_, (intent, _, _))| intent.clone()).collect();
+        let placements = state.order_flow.place_batch(&intents, atomic).await;
+        for ((index, (_, order, transformation)), placement) in prepared.into_iter().zip(placements) {
+            results.push(match placement {
+                BatchPlacement::Placed(placed) => BatchPlaceResult {
+                    index,
+                    status: "placed",
+                    order: Some(created_order(&order, &placed, transformation, received_at)),
+                    error: None,
+                },
+                BatchPlacement::Failed(e) => BatchPlaceResult {
+                    index,
+                    status: "failed",
+                    order: None,
+                    error: Some(item_error(flow_rejection(e, state.config.collateral_symbol()))),
+                },
+                BatchPlacement::RolledBack => BatchPlaceResult {
+                    index,
+                    status: "rolled_back",
+                    order: None,
+                    error: None,
+                },
+            });
+        }
+    }
+    results.sort_by_key(|result| result.index);
+
+    let placed = results.iter().filter(|result| result.status == "placed").count();
+    let failed = results.iter().filter(|result| result.status == "failed").count();
+    Ok(Json(BatchPlaceResponse {
+        atomic,
+        placed,
+        failed,
+        rolled_back: atomic && failed > 0,
+        results,
+    }))
+}
+
+fn item_error(error: AppError) -> v2::ErrorBody {
+    v2::ErrorEnvelope::from(error.body()).error
+}
+
+/// Kill switch: cancel every resting order of the caller across all
+/// markets in one pass, pushed to the `orders` channel as one
+/// `orders_cancelled` message
+/// POST /orders/cancel-all
+pub async fn cancel_all(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<CancelAllResponse>, AppError> {
+    let cancelled = state.cancel_all_after.cancel_all(&auth_user.address).await?;
+
+    if !cancelled.is_empty() {
+        tracing::info!("Cancel-all by {}: {} orders cancelled", auth_user.address, cancelled.len());
+        state
+            .event_bus
+            .publish(BusEvent::OrdersCancelled(OrdersCancelledEvent {
+                user_address: auth_user.address.clone().into(),
+                order_ids: cancelled.clone(),
+                timestamp: Utc::now().timestamp_millis(),
+            }))
+            .await;
+    }
+
+    Ok(Json(CancelAllResponse {
+        count: cancelled.len(),
+        cancelled,
+    }))
+}
+
+/// One-click exit: sell the caller's whole holding in a market as IOC
+/// orders limited to the best bid less a slippage cap, one per share type
+/// held. Shares committed to the caller's resting sells are left to them.
+/// A sell fills against bids and, through a merge, against asks of the
+/// complement share; a short opened with `allow_short` is held as the
+/// complement share and closes the same way. What the books can't take
+/// within the cap stays held. Authorized by the session, like cancel-all.
+/// POST /account/positions/:market_id/close
+pub async fn close_position(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(market_id): Path<Uuid>,
+    ValidJson(req): ValidJson<ClosePositionRequest>,
+) -> Result<Json<ClosePositionResponse>, AppError> {
+    let max_slippage = req.max_slippage.unwrap_or(DEFAULT_CLOSE_SLIPPAGE);
+    if max_slippage < Decimal::ZERO || max_slippage > MAX_CLOSE_SLIPPAGE {
+        return Err(AppError::bad_request(
+            "INVALID_SLIPPAGE",
+            format!("max_slippage 必须介于 0 与 {} 之间", MAX_CLOSE_SLIPPAGE),
+        ));
+    }
+
+    let holdings: Vec<(Uuid, DbShareType, Decimal, Decimal)> = sqlx::query_as(
+        r#"
+        SELECT s.outcome_id, s.share_type, s.amount,
+               COALESCE((
+                   SELECT SUM(o.amount - o.filled_amount) FROM orders o
+                   WHERE o.user_address = s.user_address AND o.outcome_id = s.outcome_id
+                     AND o.share_type = s.share_type AND o.side = 'sell'
+                     AND o.status IN ('pending', 'open', 'partially_filled')
+               ), 0) AS pending_sell
+        FROM shares s
+        WHERE s.user_address = $1 AND s.market_id = $2 AND s.amount > 0
+          AND ($3::share_type IS NULL OR s.share_type = $3::share_type)
+        ORDER BY s.share_type
+        "#,
+    )
+    .bind(&auth_user.address)
+    .bind(market_id)
+    .bind(req.share_type.map(|share_type| share_type.to_string()))
+    .fetch_all(&state.db.pool)
+    .await?;
+    if holdings.is_empty() {
+        return Err(AppError::not_found("NO_POSITION", "该市场无持仓"));
+    }
+
+    let mut results = Vec::with_capacity(holdings.len());
+    for (outcome_id, DbShareType(share_type), held, pending_sell) in holdings {
+        let market_key = format!("{}:{}:{}", market_id, outcome_id, share_type);
+        let reference_price = best_exit_price(&state.matching_engine, &market_key, share_type);
+        let mut result = ClosedHolding {
+            outcome_id,
+            share_type,
+            held,
+            pending_sell,
+            reference_price,
+            limit_price: None,
+            order_id: None,
+            filled_amount: Decimal::ZERO,
+            remaining_amount: held,
+            average_price: Decimal::ZERO,
+            fills: Vec::new(),
+            error: None,
+        };
+        let sellable = held - pending_sell;
+        if sellable <= Decimal::ZERO {
+            result.error = Some(v2::ErrorBody {
+                code: "ALREADY_SELLING".to_string(),
+                message: "持仓已全部挂单卖出".to_string(),
+                fields: Vec::new(),
+            });
+            results.push(result);
+            continue;
+        }
+        let Some(reference_price) = reference_price else {
+            result.error = Some(v2::ErrorBody {
+                code: "NO_LIQUIDITY".to_string(),
+                message: "订单簿无可成交的买单".to_string(),
+                fields: Vec::new(),
+            });
+            results.push(result);
+            continue;
+        };
+        let limit_price = (reference_price - max_slippage).max(Decimal::new(1, 2));
+        result.limit_price = Some(limit_price);
+
+        let intent = OrderIntent {
+            source: OrderSource::Api,
+            user_address: auth_user.address.clone().into(),
+            market_id,
+            outcome_id,
+            share_type,
+            side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Ioc,
+            post_only: false,
+            stp: SelfTradePrevention::default(),
+            price: limit_price,
+            amount: sellable,
+            expires_at: None,
+            signature: String::new(),
+        };
+        match state.order_flow.place(&intent).await {
+            Ok(placed) => {
+                let filled_notional: Collateral = placed
+                    .trades
+                    .iter()
+                    .map(|t| Collateral::notional(t.price, t.amount))
+                    .chain(placed.set_fills.iter().map(|f| Collateral::notional(f.price, f.amount)))
+                    .sum();
+                result.order_id = Some(placed.order_id);
+                result.filled_amount = placed.filled_amount;
+                result.remaining_amount = held - placed.filled_amount;
+                result.average_price = precision::average_price(filled_notional.value(), placed.filled_amount)
+                    .unwrap_or(Decimal::ZERO);
+                result.fills = placed
+                    .trades
+                    .iter()
+                    .map(|t| ClosedFill {
+                        trade_id: t.trade_id,
+                        route: t.match_type,
+                        price: t.price,
+                        amount: t.amount,
+                        fee: t.taker_fee,
+                    })
+                    .chain(placed.set_fills.iter().map(|f| ClosedFill {
+                        trade_id: f.set_id,
+                        route: f.match_type,
+                        price: f.price,
+                        amount: f.amount,
+                        fee: f.taker_fee,
+                    }))
+                    .collect();
+            }
+            Err(e) => result.error = Some(item_error(flow_rejection(e, state.config.collateral_symbol()))),
+        }
+        results.push(result);
+    }
+
+    tracing::info!(
+        "Position close by {} in market {}: {} of {} holdings sold",
+        auth_user.address,
+        market_id,
+        results.iter().filter(|r| r.filled_amount > Decimal::ZERO).count(),
+        results.len()
+    );
+    Ok(Json(ClosePositionResponse { market_id, results }))
+}
+
+/// Best price a sell on `market_key` fills at: the book's best bid, or one
+/// minus the complement book's best ask when merges are enabled
+fn best_exit_price(engine: &MatchingEngine, market_key: &str, share_type: ShareType) -> Option<Decimal> {
+    let bid = engine.get_best_prices(market_key).ok().and_then(|(bid, _)| bid);
+    if !engine.complement_matching_enabled() {
+        return bid;
+    }
+    let (prefix, _) = market_key.rsplit_once(':')?;
+    let complement_key = format!("{}:{}", prefix, share_type.complement());
+    let merge_bid = engine
+        .get_best_prices(&complement_key)
+        .ok()
+        .and_then(|(_, ask)| ask)
+        .map(|ask| Decimal::ONE - ask);
+    bid.into_iter().chain(merge_bid).max()
+}
+
 /// Batch cancel orders
 /// POST /orders/batch
 pub async fn batch_cancel(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Json(req): Json<BatchCancelRequest>,
-) -> Result<Json<BatchCancelResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(req): ValidJson<BatchCancelRequest>,
+) -> Result<Json<BatchCancelResponse>, AppError> {
     // Validate timestamp
     if !state.config.is_auth_disabled() && !validate_timestamp(req.timestamp) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "时间戳已过期".to_string(),
-                code: "TIMESTAMP_EXPIRED".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("TIMESTAMP_EXPIRED", "时间戳已过期"));
     }
 
     let mut cancelled = Vec::new();
@@ -640,14 +1150,16 @@ pub async fn batch_cancel(
         let order: Option<Order> = sqlx::query_as(
             r#"
             SELECT id, user_address, market_id, outcome_id, share_type,
-                   side, order_type, price, amount, filled_amount, status, signature,
-                   created_at, updated_at
+                   side, order_type, COALESCE(time_in_force, 'gtc') AS time_in_force,
+                   COALESCE(post_only, FALSE) AS post_only,
+                   price, amount, filled_amount, status, reject_reason, source, signature,
+                   created_at, updated_at, expires_at
             FROM orders
             WHERE id = $1 AND user_address = $2
             "#,
         )
         .bind(order_id)
-        .bind(&auth_user.address.to_lowercase())
+        .bind(&auth_user.address)
         .fetch_optional(&state.db.pool)
         .await
         .unwrap_or(None);
@@ -658,11 +1170,12 @@ pub async fn batch_cancel(
                 let market_key = format!("{}:{}:{}", order.market_id, order.outcome_id, order.share_type);
 
                 // Try to cancel in matching engine
-                let result = state.matching_engine.cancel_order(
-                    &market_key,
-                    order_id,
-                    &auth_user.address.to_lowercase(),
-                );
+                let (symbol, user) = (market_key.clone(), auth_user.address.clone());
+                let result = state
+                    .engine_shards
+                    .execute(&market_key, move |engine| engine.cancel_order(&symbol, order_id, user.as_str()))
+                    .await
+                    .and_then(|result| result);
 
                 if result.is_ok() && result.unwrap() {
                     // Update database
@@ -675,7 +1188,7 @@ pub async fn batch_cancel(
 
                     // Unfreeze collateral for buy orders
                     if matches!(order.side, OrderSide::Buy) {
-                        let remaining_collateral = order.remaining_amount() * order.price;
+                        let remaining_collateral = Collateral::notional(order.price, order.remaining_amount()).value();
                         let collateral_symbol = state.config.collateral_symbol();
 
                         let _ = sqlx::query(
@@ -683,10 +1196,16 @@ pub async fn batch_cancel(
                              WHERE user_address = $2 AND token = $3"
                         )
                         .bind(remaining_collateral)
-                        .bind(&auth_user.address.to_lowercase())
+                        .bind(&auth_user.address)
                         .bind(&collateral_symbol)
                         .execute(&state.db.pool)
                         .await;
+                        let _ = portfolio_margin::refresh_account(
+                            &state.db.pool,
+                            &auth_user.address,
+                            collateral_symbol,
+                        )
+                        .await;
                     }
 
                     cancelled.push(order_id);
@@ -703,3 +1222,179 @@ pub async fn batch_cancel(
 
     Ok(Json(BatchCancelResponse { cancelled, failed }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Path, State};
+    use axum::Extension;
+    use rust_decimal_macros::dec;
+
+    use crate::auth::middleware::{AuthUser, UserRole};
+    use crate::services::order_gateway::GatewayOrder;
+    use crate::test_support::{account, gateway_order, TestApp, MAKER, TAKER};
+
+    #[tokio::test]
+    async fn test_cancel_all_across_markets() {
+        let app = TestApp::builder().build().await;
+        app.deposit(TAKER, dec!(100)).await;
+        let mut placed = Vec::new();
+        for _ in 0..2 {
+            let (market_id, yes, _) = app.create_market().await;
+            let order = app
+                .state
+                .order_gateway
+                .place(gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.4)))
+                .await
+                .unwrap();
+            placed.push(order.order_id);
+        }
+        let mut pushed = app.state.event_bus.subscribe_orders_cancelled();
+
+        let auth = || {
+            Extension(AuthUser {
+                address: account(TAKER),
+                role: UserRole::User,
+                session_id: None,
+            })
+        };
+        let response = cancel_all(State(app.state.clone()), auth()).await.unwrap().0;
+        assert_eq!(response.count, 2);
+        let mut cancelled = response.cancelled.clone();
+        cancelled.sort();
+        placed.sort();
+        assert_eq!(cancelled, placed);
+
+        // One consolidated push for the whole cancel
+        let event = pushed.recv().await.unwrap();
+        assert_eq!((event.user_address.as_str(), event.order_ids), (TAKER, response.cancelled));
+        assert!(pushed.try_recv().is_err());
+
+        let statuses: Vec<String> = sqlx::query_scalar("SELECT status::text FROM orders WHERE id = ANY($1)")
+            .bind(&placed)
+            .fetch_all(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(statuses, ["cancelled", "cancelled"]);
+        let (available, frozen): (Decimal, Decimal) =
+            sqlx::query_as("SELECT available, frozen FROM balances WHERE user_address = $1")
+                .bind(TAKER)
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!((available, frozen), (dec!(100), dec!(0)));
+
+        // Nothing left to cancel, nothing pushed
+        assert_eq!(cancel_all(State(app.state.clone()), auth()).await.unwrap().0.count, 0);
+        assert!(pushed.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_close_position_sells_within_slippage_cap() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.grant_shares(TAKER, market_id, yes, ShareType::Yes, dec!(15)).await;
+        app.grant_shares(TAKER, market_id, yes, ShareType::No, dec!(5)).await;
+        app.deposit(MAKER, dec!(100)).await;
+        let gateway = &app.state.order_gateway;
+        gateway.place(gateway_order(market_id, yes, MAKER, OrderSide::Buy, dec!(0.5))).await.unwrap();
+        gateway.place(gateway_order(market_id, yes, MAKER, OrderSide::Buy, dec!(0.4))).await.unwrap();
+        let resting = GatewayOrder { amount: dec!(8), ..gateway_order(market_id, yes, TAKER, OrderSide::Sell, dec!(0.9)) };
+        gateway.place(resting.clone()).await.unwrap();
+        gateway.place(GatewayOrder { share_type: ShareType::No, amount: dec!(5), ..resting }).await.unwrap();
+
+        let response = close_position(
+            State(app.state.clone()),
+            Extension(AuthUser {
+                address: account(TAKER),
+                role: UserRole::User,
+                session_id: None,
+            }),
+            Path(market_id),
+            ValidJson(ClosePositionRequest {
+                share_type: None,
+                max_slippage: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .0;
+        assert_eq!(response.results.len(), 2);
+
+        // 7 Yes are not on offer yet, and all of them fit under the cap
+        let sold = &response.results[0];
+        assert_eq!(sold.share_type, ShareType::Yes);
+        assert_eq!(sold.pending_sell, dec!(8));
+        assert_eq!((sold.reference_price, sold.limit_price), (Some(dec!(0.5)), Some(dec!(0.45))));
+        assert_eq!((sold.filled_amount, sold.remaining_amount), (dec!(7), dec!(8)));
+        assert_eq!(sold.average_price, dec!(0.5));
+        assert_eq!(sold.fills.len(), 1);
+
+        // Every No share already rests on the book
+        let unsold = &response.results[1];
+        assert_eq!(unsold.share_type, ShareType::No);
+        assert_eq!(unsold.error.as_ref().map(|e| e.code.as_str()), Some("ALREADY_SELLING"));
+        assert_eq!(unsold.order_id, None);
+
+        // The resting sells were left alone
+        let open: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM orders WHERE user_address = $1 AND side = 'sell' AND status = 'open'",
+        )
+        .bind(TAKER)
+        .fetch_one(&app.state.db.pool)
+        .await
+        .unwrap();
+        assert_eq!(open, 2);
+    }
+
+    #[tokio::test]
+    async fn test_short_sell_needs_no_holding() {
+        let app = TestApp::builder().config(serde_json::json!({ "auth_disabled": true })).build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.grant_shares(TAKER, market_id, yes, ShareType::Yes, dec!(3)).await;
+        app.deposit(TAKER, dec!(100)).await;
+        let sell = |share_type: ShareType, price: Decimal, amount: Decimal| {
+            let auth = Extension(AuthUser {
+                address: account(TAKER),
+                role: UserRole::User,
+                session_id: None,
+            });
+            let req = CreateOrderRequest {
+                market_id,
+                outcome_id: yes,
+                share_type,
+                side: OrderSide::Sell,
+                order_type: OrderType::Limit,
+                time_in_force: TimeInForce::Gtc,
+                post_only: false,
+                stp: Default::default(),
+                price,
+                amount,
+                signature: String::new(),
+                timestamp: 0,
+                allow_short: true,
+                expires_at: None,
+            };
+            create_order(State(app.state.clone()), auth, ValidJson(req))
+        };
+
+        // 3 Yes held don't cover a sell of 10: refused, not turned into a
+        // buy of 10 No
+        let refused = sell(ShareType::Yes, dec!(0.6), dec!(10)).await.unwrap_err();
+        assert_eq!(refused.code(), "INSUFFICIENT_BALANCE");
+        let placed: Vec<(String, String)> = sqlx::query_as(
+            "SELECT share_type::text, status::text FROM orders WHERE user_address = $1",
+        )
+        .bind(TAKER)
+        .fetch_all(&app.db.pool)
+        .await
+        .unwrap();
+        assert_eq!(placed, [("yes".to_string(), "rejected".to_string())]);
+
+        // No No held: the sell is placed as a buy of Yes at 1 - p
+        let response = sell(ShareType::No, dec!(0.3), dec!(5)).await.unwrap().0;
+        let transformation = response.transformation.unwrap();
+        assert_eq!((transformation.requested_share_type, transformation.requested_price), (ShareType::No, dec!(0.3)));
+        assert_eq!((response.share_type, transformation.locked_collateral), (ShareType::Yes, dec!(3.5)));
+    }
+}
@@ -0,0 +1,194 @@
+//! Paper Trading Handlers
+//!
+//! Practice trading with a virtual balance: orders match in simulated
+//! orderbooks seeded from real market prices and never touch real
+//! collateral, positions or books.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::models::market::ShareType;
+use crate::services::feature_flags;
+use crate::services::matching::OrderbookSnapshot;
+use crate::services::paper_trading::{PaperAccount, PaperError, PaperOrder, PaperOrderRequest, PaperTrading};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListPaperOrdersQuery {
+    /// Only open and partially filled orders
+    #[serde(default)]
+    pub open: bool,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaperOrdersResponse {
+    pub orders: Vec<PaperOrder>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PaperOrderbookQuery {
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: ShareType,
+    #[validate(range(min = 1))]
+    pub depth: Option<usize>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn paper_error(e: PaperError) -> AppError {
+    let status = match e {
+        PaperError::MarketNotFound | PaperError::OrderNotFound => StatusCode::NOT_FOUND,
+        PaperError::MarketNotActive => StatusCode::CONFLICT,
+        PaperError::Database(e) => return e.into(),
+        _ => StatusCode::BAD_REQUEST,
+    };
+    AppError::new(status, e.code(), e.to_string())
+}
+
+fn ensure_enabled(state: &AppState, auth_user: &AuthUser) -> Result<(), AppError> {
+    if state
+        .feature_flags
+        .is_enabled(feature_flags::PAPER_TRADING, Some(auth_user.address.as_str()))
+    {
+        return Ok(());
+    }
+    Err(AppError::forbidden("FEATURE_DISABLED", "Paper trading is not enabled"))
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Get the paper balance and positions, valued at current prices
+/// GET /paper/account
+pub async fn get_account(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<PaperAccount>, AppError> {
+    ensure_enabled(&state, &auth_user)?;
+    let account = state
+        .paper_trading
+        .account(&state.db.pool, &auth_user.address)
+        .await
+        .map_err(paper_error)?;
+    Ok(Json(account))
+}
+
+/// Place a paper order
+/// POST /paper/orders
+pub async fn place_order(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<PaperOrderRequest>,
+) -> Result<Json<PaperOrder>, AppError> {
+    ensure_enabled(&state, &auth_user)?;
+    let order = state
+        .paper_trading
+        .place(
+            &state.db.pool,
+            &state.matching_engine,
+            &auth_user.address,
+            &req,
+        )
+        .await
+        .map_err(paper_error)?;
+    Ok(Json(order))
+}
+
+/// List paper orders, newest first
+/// GET /paper/orders?open&limit
+pub async fn list_orders(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<ListPaperOrdersQuery>,
+) -> Result<Json<PaperOrdersResponse>, AppError> {
+    ensure_enabled(&state, &auth_user)?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let orders = state
+        .paper_trading
+        .orders(&state.db.pool, &auth_user.address, query.open, limit)
+        .await
+        .map_err(|e| paper_error(e.into()))?;
+    Ok(Json(PaperOrdersResponse { orders }))
+}
+
+/// Cancel an open paper order
+/// DELETE /paper/orders/:order_id
+pub async fn cancel_order(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<PaperOrder>, AppError> {
+    ensure_enabled(&state, &auth_user)?;
+    let order = state
+        .paper_trading
+        .cancel(&state.db.pool, &auth_user.address, order_id)
+        .await
+        .map_err(paper_error)?;
+    Ok(Json(order))
+}
+
+/// Cancel all paper orders, close all paper positions and restore the
+/// starting balance
+/// POST /paper/reset
+pub async fn reset(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<PaperAccount>, AppError> {
+    ensure_enabled(&state, &auth_user)?;
+    let user_address = auth_user.address.clone();
+    state
+        .paper_trading
+        .reset(&state.db.pool, &user_address)
+        .await
+        .map_err(paper_error)?;
+    let account = state
+        .paper_trading
+        .account(&state.db.pool, &user_address)
+        .await
+        .map_err(paper_error)?;
+    Ok(Json(account))
+}
+
+/// Get a simulated orderbook, house liquidity included
+/// GET /paper/orderbook?market_id&outcome_id&share_type&depth
+pub async fn get_orderbook(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<PaperOrderbookQuery>,
+) -> Result<Json<OrderbookSnapshot>, AppError> {
+    ensure_enabled(&state, &auth_user)?;
+    let depth = query.depth.unwrap_or(20).clamp(1, 100);
+    state
+        .paper_trading
+        .orderbook(query.market_id, query.outcome_id, query.share_type, depth)
+        .map(Json)
+        .ok_or_else(|| {
+            AppError::not_found(
+                "ORDERBOOK_NOT_FOUND",
+                format!(
+                    "No paper book for {} yet; it is seeded by the first paper order",
+                    PaperTrading::book_key(query.market_id, query.outcome_id, query.share_type)
+                ),
+            )
+        })
+}
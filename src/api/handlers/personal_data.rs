@@ -0,0 +1,90 @@
+//! Personal Data Handlers
+//!
+//! Download of everything stored about the account, and account closure:
+//! open and trigger orders are cancelled, the account is limited to reading, cancelling
+//! and withdrawing, and it closes once emptied.
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::api::error::AppError;
+use crate::auth::middleware::AuthUser;
+use crate::services::account_admin;
+use crate::services::order_gateway::OrderSource;
+use crate::services::personal_data::{self, ClosureStatus};
+use crate::services::trigger_orders;
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct ClosureResponse {
+    #[serde(flatten)]
+    pub status: ClosureStatus,
+    /// Orders cancelled by this request
+    pub cancelled_orders: Vec<Uuid>,
+}
+
+/// Download everything stored about the account as a zip of JSON files
+/// GET /account/export
+pub async fn export_data(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Response, AppError> {
+    let address = auth_user.address.clone();
+    let archive = personal_data::export(&state.db.pool, &address).await?;
+    let filename = format!("account-{}-{}.zip", address, chrono::Utc::now().format("%Y%m%d"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+/// Where the account's closure stands and what still blocks it
+/// GET /account/closure
+pub async fn get_closure(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<ClosureStatus>, AppError> {
+    let status = personal_data::status(&state.db.pool, &auth_user.address).await?;
+    Ok(Json(status))
+}
+
+/// Close the account: cancel its open orders and close it once it holds
+/// nothing. Call again after withdrawing to finish a pending closure.
+/// POST /account/closure
+pub async fn close_account(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<ClosureResponse>, AppError> {
+    let address = auth_user.address.clone();
+    let pool = &state.db.pool;
+    personal_data::request_closure(pool, &address).await?;
+
+    let mut conn = pool.acquire().await?;
+    let orders = account_admin::open_orders(&mut conn, &address).await?;
+    drop(conn);
+    trigger_orders::cancel_all(pool, &address).await?;
+    let mut cancelled_orders = Vec::new();
+    for order in orders {
+        let source = OrderSource::parse(&order.source).unwrap_or(OrderSource::Api);
+        match state.order_gateway.cancel(source, &address, order.id).await {
+            Ok(true) => cancelled_orders.push(order.id),
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to cancel order {} of closing account {}: {}", order.id, address, e),
+        }
+    }
+
+    personal_data::complete_closure(pool, &address).await?;
+    let status = personal_data::status(pool, &address).await?;
+    Ok(Json(ClosureResponse { status, cancelled_orders }))
+}
@@ -107,7 +107,7 @@ pub async fn get_position(
         .ok_or(StatusCode::NOT_FOUND)?;
 
     // Verify ownership
-    if position.user_address.to_lowercase() != auth_user.address.to_lowercase() {
+    if auth_user.address != *position.user_address {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -131,7 +131,7 @@ pub async fn open_position(
     Extension(auth_user): Extension<AuthUser>,
     Json(req): Json<OpenPositionRequest>,
 ) -> Result<Json<PositionActionResponse>, (StatusCode, Json<PositionErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+    let user_address = auth_user.address.clone();
 
     // Check user balance before opening position
     let collateral_symbol = state.config.collateral_symbol();
@@ -285,7 +285,7 @@ pub async fn close_position(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    if position.user_address.to_lowercase() != auth_user.address.to_lowercase() {
+    if auth_user.address != *position.user_address {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -340,7 +340,7 @@ pub async fn close_position(
         "#
     )
     .bind(order_id)
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .bind(&position.symbol)
     .bind(order_side)
     .bind(OrderType::Market)
@@ -374,7 +374,7 @@ pub async fn close_position(
 
             // Send order update to WebSocket broadcast channel
             let event = OrderUpdateEvent {
-                user_address: auth_user.address.to_lowercase(),
+                user_address: auth_user.address.clone(),
                 order: order.clone(),
             };
             if let Err(e) = state.order_update_sender.send(event) {
@@ -418,7 +418,7 @@ pub async fn add_collateral(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    if position.user_address.to_lowercase() != auth_user.address.to_lowercase() {
+    if auth_user.address != *position.user_address {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -465,7 +465,7 @@ pub async fn remove_collateral(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    if position.user_address.to_lowercase() != auth_user.address.to_lowercase() {
+    if auth_user.address != *position.user_address {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -511,7 +511,7 @@ pub async fn check_liquidation(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    if position.user_address.to_lowercase() != auth_user.address.to_lowercase() {
+    if auth_user.address != *position.user_address {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -0,0 +1,61 @@
+//! User Preference Handlers
+//!
+//! One place for the settings the backend applies when it renders things
+//! for a user: display timezone, how amounts are labelled, and the
+//! notification settings also served on `/account/notifications`.
+
+use axum::{extract::State, Extension, Json};
+use serde::Deserialize;
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::handlers::notification::UpdatePreferencesRequest as UpdateNotificationsRequest;
+use crate::api::validation::ValidJson;
+use crate::auth::middleware::AuthUser;
+use crate::services::preferences::{self, CurrencyDisplay, PreferencesUpdate, UserPreferences};
+use crate::AppState;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+/// Partial update; omitted fields are left unchanged
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdatePreferencesRequest {
+    /// IANA timezone name, e.g. "Europe/Berlin"
+    #[validate(length(max = 64))]
+    pub timezone: Option<String>,
+    /// "token" (e.g. 12.5 USDC) or "usd" (12.5 USD)
+    pub currency_display: Option<CurrencyDisplay>,
+    #[validate]
+    pub notifications: Option<UpdateNotificationsRequest>,
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Get the user's preferences
+/// GET /account/preferences
+pub async fn get_preferences(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<UserPreferences>, AppError> {
+    Ok(Json(preferences::load(&state.db.pool, &auth_user.address).await?))
+}
+
+/// Update the user's preferences
+/// PUT /account/preferences
+pub async fn update_preferences(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<UpdatePreferencesRequest>,
+) -> Result<Json<UserPreferences>, AppError> {
+    let update = PreferencesUpdate {
+        timezone: req.timezone,
+        currency_display: req.currency_display,
+        notifications: req.notifications.map(Into::into),
+    };
+    Ok(Json(preferences::update(&state.db.pool, &auth_user.address, &update).await?))
+}
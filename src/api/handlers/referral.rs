@@ -11,12 +11,13 @@ use std::sync::Arc;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::api::error::AppError;
 use crate::auth::middleware::AuthUser;
 use crate::auth::eip712::{
     verify_create_referral_signature, verify_bind_referral_signature,
     CreateReferralMessage, BindReferralMessage,
 };
-use crate::models::{BindReferralRequest, CreateReferralCodeRequest};
+use crate::models::{Address, BindReferralRequest, CreateReferralCodeRequest};
 use crate::AppState;
 
 // Helper module to serialize DateTime as milliseconds timestamp
@@ -54,12 +55,6 @@ pub struct ClaimResponse {
     pub tx_hash: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: String,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ReferralActivity {
     pub referral_address: String,
@@ -147,21 +142,15 @@ pub async fn create_code(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Json(req): Json<CreateReferralCodeRequest>,
-) -> Result<Json<CreateCodeResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<CreateCodeResponse>, AppError> {
     // Validate timestamp
     if !validate_timestamp(req.timestamp) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "时间戳已过期".to_string(),
-                code: "TIMESTAMP_EXPIRED".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("TIMESTAMP_EXPIRED", "时间戳已过期"));
     }
 
     // EIP-712 签名验证
     let create_msg = CreateReferralMessage {
-        wallet: auth_user.address.to_lowercase(),
+        wallet: auth_user.address.clone(),
         timestamp: req.timestamp,
     };
 
@@ -169,25 +158,13 @@ pub async fn create_code(
         Ok(v) => v,
         Err(e) => {
             tracing::error!("Create referral code signature verification error: {}", e);
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "签名格式无效".to_string(),
-                    code: "INVALID_SIGNATURE_FORMAT".to_string(),
-                }),
-            ));
+            return Err(AppError::bad_request("INVALID_SIGNATURE_FORMAT", "签名格式无效"));
         }
     };
 
     if !valid {
         tracing::warn!("Create referral code signature verification failed for address: {}", auth_user.address);
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "创建推荐码签名验证失败".to_string(),
-                code: "SIGNATURE_INVALID".to_string(),
-            }),
-        ));
+        return Err(AppError::new(StatusCode::UNAUTHORIZED, "SIGNATURE_INVALID", "创建推荐码签名验证失败"));
     }
 
     tracing::info!("EIP-712 create referral code signature verified for address: {}", auth_user.address);
@@ -196,28 +173,12 @@ pub async fn create_code(
     let existing: Option<String> = sqlx::query_scalar(
         "SELECT code FROM referral_codes WHERE owner_address = $1"
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to check existing referral code: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "数据库查询失败".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     if let Some(existing_code) = existing {
-        return Err((
-            StatusCode::CONFLICT,
-            Json(ErrorResponse {
-                error: format!("您已经有推荐码: {}", existing_code),
-                code: "CODE_ALREADY_EXISTS".to_string(),
-            }),
-        ));
+        return Err(AppError::new(StatusCode::CONFLICT, "CODE_ALREADY_EXISTS", format!("您已经有推荐码: {}", existing_code)));
     }
 
     // Auto-generate referral code
@@ -234,25 +195,15 @@ pub async fn create_code(
     )
     .bind(Uuid::new_v4())
     .bind(&code)
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .bind(now)
     .execute(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to create referral code: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "创建推荐码失败".to_string(),
-                code: "CREATE_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Update user record
     sqlx::query("UPDATE users SET referral_code = $1 WHERE address = $2")
         .bind(&code)
-        .bind(&auth_user.address.to_lowercase())
+        .bind(&auth_user.address)
         .execute(&state.db.pool)
         .await
         .ok();
@@ -272,21 +223,15 @@ pub async fn bind_code(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Json(req): Json<BindReferralRequest>,
-) -> Result<Json<BindCodeResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<BindCodeResponse>, AppError> {
     // Validate timestamp
     if !validate_timestamp(req.timestamp) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "时间戳已过期".to_string(),
-                code: "TIMESTAMP_EXPIRED".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("TIMESTAMP_EXPIRED", "时间戳已过期"));
     }
 
     // EIP-712 签名验证
     let bind_msg = BindReferralMessage {
-        wallet: auth_user.address.to_lowercase(),
+        wallet: auth_user.address.clone(),
         code: req.code.clone(),
         timestamp: req.timestamp,
     };
@@ -295,25 +240,13 @@ pub async fn bind_code(
         Ok(v) => v,
         Err(e) => {
             tracing::error!("Bind referral code signature verification error: {}", e);
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "签名格式无效".to_string(),
-                    code: "INVALID_SIGNATURE_FORMAT".to_string(),
-                }),
-            ));
+            return Err(AppError::bad_request("INVALID_SIGNATURE_FORMAT", "签名格式无效"));
         }
     };
 
     if !valid {
         tracing::warn!("Bind referral code signature verification failed for address: {}", auth_user.address);
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "绑定推荐码签名验证失败".to_string(),
-                code: "SIGNATURE_INVALID".to_string(),
-            }),
-        ));
+        return Err(AppError::new(StatusCode::UNAUTHORIZED, "SIGNATURE_INVALID", "绑定推荐码签名验证失败"));
     }
 
     tracing::info!("EIP-712 bind referral code signature verified for address: {}", auth_user.address);
@@ -322,66 +255,28 @@ pub async fn bind_code(
     let existing: Option<String> = sqlx::query_scalar(
         "SELECT referrer_address FROM users WHERE address = $1"
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to check existing binding: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "数据库查询失败".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?
+    .await?
     .flatten();
 
     if existing.is_some() {
-        return Err((
-            StatusCode::CONFLICT,
-            Json(ErrorResponse {
-                error: "您已绑定推荐人".to_string(),
-                code: "ALREADY_BOUND".to_string(),
-            }),
-        ));
+        return Err(AppError::new(StatusCode::CONFLICT, "ALREADY_BOUND", "您已绑定推荐人"));
     }
 
     // Find referral code
-    let referrer: Option<String> = sqlx::query_scalar(
+    let referrer: Option<Address> = sqlx::query_scalar(
         "SELECT owner_address FROM referral_codes WHERE UPPER(code) = UPPER($1)"
     )
     .bind(&req.code)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to find referral code: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "数据库查询失败".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    let referrer_address = referrer.ok_or((
-        StatusCode::NOT_FOUND,
-        Json(ErrorResponse {
-            error: "推荐码不存在".to_string(),
-            code: "CODE_NOT_FOUND".to_string(),
-        }),
-    ))?;
+    .await?;
+
+    let referrer_address = referrer.ok_or_else(|| AppError::not_found("CODE_NOT_FOUND", "推荐码不存在"))?;
 
     // Can't refer yourself
-    if referrer_address.to_lowercase() == auth_user.address.to_lowercase() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "不能使用自己的推荐码".to_string(),
-                code: "SELF_REFERRAL".to_string(),
-            }),
-        ));
+    if referrer_address == auth_user.address {
+        return Err(AppError::bad_request("SELF_REFERRAL", "不能使用自己的推荐码"));
     }
 
     let now = Utc::now();
@@ -395,26 +290,16 @@ pub async fn bind_code(
     )
     .bind(Uuid::new_v4())
     .bind(&referrer_address)
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .bind(&req.code.to_uppercase())
     .bind(now)
     .execute(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to create referral relationship: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "绑定失败".to_string(),
-                code: "BIND_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Update user record
     sqlx::query("UPDATE users SET referrer_address = $1 WHERE address = $2")
         .bind(&referrer_address)
-        .bind(&auth_user.address.to_lowercase())
+        .bind(&auth_user.address)
         .execute(&state.db.pool)
         .await
         .ok();
@@ -440,30 +325,20 @@ pub async fn bind_code(
 pub async fn get_dashboard(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<DashboardResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<DashboardResponse>, AppError> {
     // Get user's referral code
     let code: Option<String> = sqlx::query_scalar(
         "SELECT code FROM referral_codes WHERE owner_address = $1"
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch referral code: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "数据库查询失败".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Get total and active referrals count
     let total_referrals: i64 = sqlx::query_scalar(
         "SELECT COUNT(*) FROM referral_relations WHERE referrer_address = $1"
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_one(&state.db.pool)
     .await
     .unwrap_or(0);
@@ -478,7 +353,7 @@ pub async fn get_dashboard(
         AND t.created_at > NOW() - INTERVAL '30 days'
         "#
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_one(&state.db.pool)
     .await
     .unwrap_or(0);
@@ -493,19 +368,9 @@ pub async fn get_dashboard(
         WHERE referrer_address = $1
         "#
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch earnings: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "数据库查询失败".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let (total_earnings, pending_earnings) = earnings.unwrap_or((Decimal::ZERO, Decimal::ZERO));
     let claimed_earnings = total_earnings - pending_earnings;
@@ -525,7 +390,7 @@ pub async fn get_dashboard(
         LIMIT 20
         "#
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_all(&state.db.pool)
     .await
     .unwrap_or_default();
@@ -628,33 +493,22 @@ fn get_tier_name_from_index(tier: u8) -> &'static str {
 pub async fn get_on_chain_user_rebate(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(address): axum::extract::Path<String>,
-) -> Result<Json<OnChainUserRebateResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<OnChainUserRebateResponse>, AppError> {
     // Validate address format
-    if !address.starts_with("0x") || address.len() != 42 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid address format".to_string(),
-                code: "INVALID_ADDRESS".to_string(),
-            }),
-        ));
-    }
+    let address: String = address
+        .parse::<Address>()
+        .map_err(|e| AppError::bad_request("INVALID_ADDRESS", e.to_string()))?
+        .into();
 
     let rebate_info = state.referral_service.get_user_rebate_info(&address)
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch on-chain user rebate for {}: {}", address, e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to fetch on-chain data".to_string(),
-                    code: "CHAIN_ERROR".to_string(),
-                }),
-            )
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "CHAIN_ERROR", "Failed to fetch on-chain data")
         })?;
 
     Ok(Json(OnChainUserRebateResponse {
-        address: address.to_lowercase(),
+        address,
         claimed_usd: rebate_info.claimed.to_string(),
         nonce: rebate_info.nonce,
         referral_code: rebate_info.referral_code,
@@ -669,33 +523,22 @@ pub async fn get_on_chain_user_rebate(
 pub async fn get_on_chain_referral_info(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(address): axum::extract::Path<String>,
-) -> Result<Json<OnChainReferralInfoResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<OnChainReferralInfoResponse>, AppError> {
     // Validate address format
-    if !address.starts_with("0x") || address.len() != 42 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid address format".to_string(),
-                code: "INVALID_ADDRESS".to_string(),
-            }),
-        ));
-    }
+    let address: String = address
+        .parse::<Address>()
+        .map_err(|e| AppError::bad_request("INVALID_ADDRESS", e.to_string()))?
+        .into();
 
     let referral_info = state.referral_service.get_referral_info(&address)
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch on-chain referral info for {}: {}", address, e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to fetch on-chain data".to_string(),
-                    code: "CHAIN_ERROR".to_string(),
-                }),
-            )
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "CHAIN_ERROR", "Failed to fetch on-chain data")
         })?;
 
     Ok(Json(OnChainReferralInfoResponse {
-        address: address.to_lowercase(),
+        address,
         code: referral_info.code,
         referrer: referral_info.referrer,
         total_rebate_bps: referral_info.total_rebate_bps,
@@ -709,33 +552,22 @@ pub async fn get_on_chain_referral_info(
 pub async fn get_on_chain_claimed(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(address): axum::extract::Path<String>,
-) -> Result<Json<ClaimedAmountResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ClaimedAmountResponse>, AppError> {
     // Validate address format
-    if !address.starts_with("0x") || address.len() != 42 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid address format".to_string(),
-                code: "INVALID_ADDRESS".to_string(),
-            }),
-        ));
-    }
+    let address: String = address
+        .parse::<Address>()
+        .map_err(|e| AppError::bad_request("INVALID_ADDRESS", e.to_string()))?
+        .into();
 
     let claimed = state.referral_service.get_claimed_rebates(&address)
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch on-chain claimed for {}: {}", address, e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to fetch on-chain data".to_string(),
-                    code: "CHAIN_ERROR".to_string(),
-                }),
-            )
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "CHAIN_ERROR", "Failed to fetch on-chain data")
         })?;
 
     Ok(Json(ClaimedAmountResponse {
-        address: address.to_lowercase(),
+        address,
         claimed_usd: claimed.to_string(),
     }))
 }
@@ -744,36 +576,19 @@ pub async fn get_on_chain_claimed(
 /// GET /referral/on-chain/operator-status
 pub async fn get_operator_status(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
-    // Get the backend signer address from config
-    let backend_signer = &state.config.backend_signer_private_key;
-
-    // Parse private key to get address
-    use ethers::signers::{LocalWallet, Signer};
-    let wallet: LocalWallet = backend_signer.parse().map_err(|e| {
-        tracing::error!("Failed to parse backend signer private key: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Configuration error".to_string(),
-                code: "CONFIG_ERROR".to_string(),
-            }),
-        )
-    })?;
-
-    let signer_address = format!("{:?}", wallet.address());
+) -> Result<Json<serde_json::Value>, AppError> {
+    // The operator signer loaded at startup (env key, keystore, Vault or KMS)
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
+    let signer_address = blockchain_client
+        .get_signer_address()
+        .map_err(|e| AppError::new(StatusCode::SERVICE_UNAVAILABLE, "NO_OPERATOR_SIGNER", e))?;
+    let signer_address = format!("{:?}", signer_address);
 
     let is_operator = state.referral_service.check_operator_status(&signer_address)
         .await
         .map_err(|e| {
             tracing::error!("Failed to check operator status: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to check operator status".to_string(),
-                    code: "CHAIN_ERROR".to_string(),
-                }),
-            )
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "CHAIN_ERROR", "Failed to check operator status")
         })?;
 
     Ok(Json(serde_json::json!({
@@ -795,26 +610,15 @@ pub async fn get_claim_signature(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Json(req): Json<OnChainClaimRequest>,
-) -> Result<Json<ClaimSignatureResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ClaimSignatureResponse>, AppError> {
     // Parse amount
-    let amount: Decimal = req.amount.parse().map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid amount format".to_string(),
-                code: "INVALID_AMOUNT".to_string(),
-            }),
-        )
-    })?;
+    let amount: Decimal = req
+        .amount
+        .parse()
+        .map_err(|_| AppError::bad_request("INVALID_AMOUNT", "Invalid amount format"))?;
 
     if amount <= Decimal::ZERO {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Amount must be positive".to_string(),
-                code: "INVALID_AMOUNT".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_AMOUNT", "Amount must be positive"));
     }
 
     // Generate EIP-712 signature (1 hour deadline)
@@ -823,13 +627,7 @@ pub async fn get_claim_signature(
         .await
         .map_err(|e| {
             tracing::error!("Failed to generate claim signature: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to generate signature".to_string(),
-                    code: "SIGNATURE_ERROR".to_string(),
-                }),
-            )
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "SIGNATURE_ERROR", "Failed to generate signature")
         })?;
 
     tracing::info!(
@@ -852,68 +650,37 @@ pub async fn get_claim_signature(
 pub async fn claim_earnings(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<ClaimResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ClaimResponse>, AppError> {
     // Get pending earnings
     let pending: Decimal = sqlx::query_scalar(
         "SELECT COALESCE(SUM(commission), 0) FROM referral_earnings WHERE referrer_address = $1 AND status = 'pending'"
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .fetch_one(&state.db.pool)
     .await
     .unwrap_or(Decimal::ZERO);
 
     if pending <= Decimal::ZERO {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "没有待领取的佣金".to_string(),
-                code: "NO_PENDING_EARNINGS".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("NO_PENDING_EARNINGS", "没有待领取的佣金"));
     }
 
     // Minimum claim amount
     let collateral_symbol = state.config.collateral_symbol();
     let min_claim = Decimal::new(10, 0); // 10 minimum
     if pending < min_claim {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("最低领取金额为 {} {}", min_claim, collateral_symbol),
-                code: "BELOW_MINIMUM".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("BELOW_MINIMUM", format!("最低领取金额为 {} {}", min_claim, collateral_symbol)));
     }
 
     // Begin transaction
-    let mut tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to begin transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "数据库事务失败".to_string(),
-                code: "DB_ERROR".to_string(),
-            }),
-        )
-    })?;
+    let mut tx = state.db.pool.begin().await?;
 
     // Update earnings status to claimed
     sqlx::query(
         "UPDATE referral_earnings SET status = 'claimed', claimed_at = NOW() WHERE referrer_address = $1 AND status = 'pending'"
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to update earnings status: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "更新状态失败".to_string(),
-                code: "UPDATE_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Add to user balance - use collateral token from config
     sqlx::query(
@@ -924,32 +691,13 @@ pub async fn claim_earnings(
         DO UPDATE SET available = balances.available + $3
         "#
     )
-    .bind(&auth_user.address.to_lowercase())
+    .bind(&auth_user.address)
     .bind(collateral_symbol)
     .bind(pending)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to add balance: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "添加余额失败".to_string(),
-                code: "BALANCE_UPDATE_FAILED".to_string(),
-            }),
-        )
-    })?;
-
-    tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "事务提交失败".to_string(),
-                code: "TX_COMMIT_FAILED".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    tx.commit().await?;
 
     tracing::info!("Referral earnings claimed: {} {} for {}", pending, collateral_symbol, auth_user.address);
 
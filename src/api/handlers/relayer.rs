@@ -0,0 +1,272 @@
+//! Gasless Relay Handlers
+//!
+//! Users submit ERC-2771 forward requests for redemptions and approvals
+//! signed with their wallet; the operator relays them and pays the gas,
+//! within a per-user daily quota.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Extension, Json,
+};
+use ethers::types::{Address, Bytes, U256};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::blockchain::contracts::ForwardRequest;
+use crate::blockchain::BlockchainClient;
+use crate::services::relayer::{self, RelayError, RelayedTransaction};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+/// ERC-2771 forward request as signed by the user
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForwardRequestBody {
+    #[validate(custom = "validation::address")]
+    pub from: String,
+    #[validate(custom = "validation::address")]
+    pub to: String,
+    /// Decimal strings
+    #[serde(default = "zero")]
+    pub value: String,
+    pub gas: String,
+    pub nonce: String,
+    /// 0x-prefixed calldata of the target call
+    pub data: String,
+}
+
+fn zero() -> String {
+    "0".to_string()
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RelayRequest {
+    #[validate]
+    pub request: ForwardRequestBody,
+    /// EIP-712 signature of the request
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForwarderDomain {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub chain_id: u64,
+    pub verifying_contract: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelayQuota {
+    pub limit: i64,
+    pub used: i64,
+    pub remaining: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelayConfigResponse {
+    pub domain: ForwarderDomain,
+    /// Nonce to sign the next request with
+    pub nonce: String,
+    pub max_gas: u64,
+    pub quota: RelayQuota,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListRelaysQuery {
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelayedTransactionsResponse {
+    pub transactions: Vec<RelayedTransaction>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn relay_error(e: RelayError) -> AppError {
+    let status = match e {
+        RelayError::NotConfigured => StatusCode::SERVICE_UNAVAILABLE,
+        RelayError::SignerMismatch => StatusCode::FORBIDDEN,
+        RelayError::AlreadyRelayed => StatusCode::CONFLICT,
+        RelayError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+        RelayError::Chain(ref msg) => {
+            tracing::error!("Relay chain call failed: {}", msg);
+            StatusCode::BAD_GATEWAY
+        }
+        RelayError::Database(e) => return e.into(),
+        _ => StatusCode::BAD_REQUEST,
+    };
+    AppError::new(status, e.code(), e.to_string())
+}
+
+fn invalid(msg: &str) -> AppError {
+    AppError::bad_request("INVALID_REQUEST", msg)
+}
+
+/// Chain client and forwarder address, if relaying is configured
+fn relay_setup(state: &AppState) -> Result<(&BlockchainClient, Address), AppError> {
+    let client = state.blockchain_client.as_deref();
+    let forwarder = state
+        .config
+        .relayer_forwarder_address
+        .as_deref()
+        .and_then(|a| a.parse::<Address>().ok());
+    match (client, forwarder) {
+        (Some(client), Some(forwarder)) => Ok((client, forwarder)),
+        _ => Err(relay_error(RelayError::NotConfigured)),
+    }
+}
+
+fn parse_bytes(hex_str: &str) -> Option<Bytes> {
+    hex::decode(hex_str.trim_start_matches("0x")).ok().map(Bytes::from)
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Forwarder domain, next nonce and remaining quota for signing a relayed
+/// request
+/// GET /relay/config
+pub async fn get_relay_config(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<RelayConfigResponse>, AppError> {
+    let (client, forwarder) = relay_setup(&state)?;
+    let user_address = auth_user.address.clone();
+    let from = user_address.to_h160();
+
+    let nonce = client
+        .get_forwarder_nonce(forwarder, from)
+        .await
+        .map_err(|e| relay_error(RelayError::Chain(e.to_string())))?;
+    let used = relayer::used_quota(&state.db.pool, &user_address)
+        .await
+        .map_err(|e| relay_error(e.into()))?;
+    let limit = state.config.relayer_daily_quota;
+
+    Ok(Json(RelayConfigResponse {
+        domain: ForwarderDomain {
+            name: relayer::FORWARDER_DOMAIN_NAME,
+            version: relayer::FORWARDER_DOMAIN_VERSION,
+            chain_id: client.chain_id(),
+            verifying_contract: format!("{:?}", forwarder),
+        },
+        nonce: nonce.to_string(),
+        max_gas: state.config.relayer_max_gas,
+        quota: RelayQuota {
+            limit,
+            used,
+            remaining: (limit - used).max(0),
+        },
+    }))
+}
+
+/// Relay a signed redemption or approval, paying its gas from the operator
+/// POST /relay
+pub async fn relay(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<RelayRequest>,
+) -> Result<Json<RelayedTransaction>, AppError> {
+    let (client, forwarder) = relay_setup(&state)?;
+    let user_address = auth_user.address.clone();
+
+    let body = &req.request;
+    let from: Address = body.from.parse().map_err(|_| invalid("Invalid from address"))?;
+    let to: Address = body.to.parse().map_err(|_| invalid("Invalid to address"))?;
+    let value = U256::from_dec_str(&body.value).map_err(|_| invalid("Invalid value"))?;
+    let gas = U256::from_dec_str(&body.gas).map_err(|_| invalid("Invalid gas"))?;
+    let nonce = U256::from_dec_str(&body.nonce).map_err(|_| invalid("Invalid nonce"))?;
+    let data = parse_bytes(&body.data).ok_or_else(|| invalid("Invalid calldata"))?;
+    let signature = parse_bytes(&req.signature).ok_or_else(|| invalid("Invalid signature"))?;
+
+    if crate::models::Address::from(from) != user_address {
+        return Err(relay_error(RelayError::SignerMismatch));
+    }
+    let max_gas = state.config.relayer_max_gas;
+    if gas.is_zero() || gas > U256::from(max_gas) {
+        return Err(relay_error(RelayError::GasTooHigh(max_gas)));
+    }
+
+    let call = relayer::classify(client.addresses(), to, value, &data).map_err(relay_error)?;
+    if let Some(condition_id) = &call.condition_id {
+        let redeemable = relayer::is_redeemable(&state.db.pool, condition_id)
+            .await
+            .map_err(|e| relay_error(e.into()))?;
+        if !redeemable {
+            return Err(relay_error(RelayError::ConditionNotRedeemable));
+        }
+    }
+
+    let forward_request = ForwardRequest {
+        from,
+        to,
+        value,
+        gas,
+        nonce,
+        data: data.clone(),
+    };
+    let valid = client
+        .verify_forward_request(forwarder, forward_request.clone(), signature.clone())
+        .await
+        .map_err(|e| relay_error(RelayError::Chain(e.to_string())))?;
+    if !valid {
+        return Err(relay_error(RelayError::InvalidRequest));
+    }
+
+    let id = relayer::reserve(
+        &state.db.pool,
+        &user_address,
+        &call,
+        to,
+        nonce,
+        &data,
+        state.config.relayer_daily_quota,
+    )
+    .await
+    .map_err(relay_error)?;
+
+    let result = client.execute_forward_request(forwarder, forward_request, signature).await;
+    let relayed = match &result {
+        Ok(tx) => relayer::complete(&state.db.pool, id, Ok(tx)).await,
+        Err(e) => {
+            tracing::error!("Relay {} of {} failed: {}", id, user_address, e);
+            relayer::complete(&state.db.pool, id, Err(&e.to_string())).await
+        }
+    }
+    .map_err(|e| relay_error(e.into()))?;
+
+    tracing::info!(
+        "Relayed {} for {}: status={}, tx={:?}",
+        call.kind.as_str(),
+        user_address,
+        relayed.status,
+        relayed.tx_hash
+    );
+    Ok(Json(relayed))
+}
+
+/// The user's relayed transactions, newest first
+/// GET /relay/transactions?limit
+pub async fn list_relayed(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<ListRelaysQuery>,
+) -> Result<Json<RelayedTransactionsResponse>, AppError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let transactions = relayer::list(&state.db.pool, &auth_user.address, limit)
+        .await
+        .map_err(|e| relay_error(e.into()))?;
+    Ok(Json(RelayedTransactionsResponse { transactions }))
+}
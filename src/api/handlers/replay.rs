@@ -0,0 +1,117 @@
+//! Market Replay Handlers
+//!
+//! Historical order flow, trades and book snapshots of a market for
+//! strategy backtesting. The paced stream variant lives with the other SSE
+//! streams (`GET /sse/markets/:market_id/replay`).
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::handlers::market_kline::get_period_seconds;
+use crate::api::validation::ValidQuery;
+use crate::services::market_replay::{self, ReplayEvent, ReplayQuery, MAX_EVENTS, MAX_WINDOW};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReplayParams {
+    /// Window start, Unix milliseconds
+    pub from: i64,
+    /// Window end, Unix milliseconds (default: `from` + 1h, capped at now)
+    pub to: Option<i64>,
+    pub outcome_id: Option<Uuid>,
+    /// Emit candles closed at this period: 1m, 5m, 15m, 30m, 1h, 4h, 1d
+    pub interval: Option<String>,
+    /// Events per page (default and max 20000)
+    #[validate(range(min = 1))]
+    pub limit: Option<usize>,
+    /// Playback speed multiple for the SSE stream (default 1, 0 = no
+    /// pacing)
+    #[validate(range(min = 0.0))]
+    pub speed: Option<f64>,
+}
+
+impl ReplayParams {
+    /// Validate into a service query for `market_id`
+    pub fn into_query(self, market_id: Uuid) -> Result<ReplayQuery, AppError> {
+        let invalid = |msg: &str, code: &'static str| AppError::bad_request(code, msg);
+
+        let from = DateTime::<Utc>::from_timestamp_millis(self.from)
+            .ok_or_else(|| invalid("Invalid from timestamp", "INVALID_TIMESTAMP"))?;
+        let to = match self.to {
+            Some(to) => DateTime::<Utc>::from_timestamp_millis(to)
+                .ok_or_else(|| invalid("Invalid to timestamp", "INVALID_TIMESTAMP"))?,
+            None => (from + chrono::Duration::hours(1)).min(Utc::now()),
+        };
+        if to < from {
+            return Err(invalid("to must not be before from", "INVALID_WINDOW"));
+        }
+        if to - from > MAX_WINDOW {
+            return Err(invalid("Window must not exceed 7 days", "WINDOW_TOO_LONG"));
+        }
+
+        let candle_secs = match self.interval.as_deref() {
+            Some(interval) => Some(get_period_seconds(interval).ok_or_else(|| {
+                invalid("Invalid interval. Must be one of: 1m, 5m, 15m, 30m, 1h, 4h, 1d", "INVALID_PERIOD")
+            })?),
+            None => None,
+        };
+
+        Ok(ReplayQuery {
+            market_id,
+            outcome_id: self.outcome_id,
+            from,
+            to,
+            candle_secs,
+            limit: self.limit.unwrap_or(MAX_EVENTS).clamp(1, MAX_EVENTS),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayResponse {
+    pub market_id: Uuid,
+    pub from: i64,
+    pub to: i64,
+    pub events: Vec<ReplayEvent>,
+    /// Request again with this `from` for the rest of the window
+    pub next_from: Option<i64>,
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Replay a market's book snapshots, order flow and trades over a window
+/// GET /markets/:market_id/replay?from&to&outcome_id&interval&limit
+pub async fn get_replay(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(params): ValidQuery<ReplayParams>,
+) -> Result<Json<ReplayResponse>, AppError> {
+    let query = params.into_query(market_id)?;
+    let page = market_replay::load(&state.db.pool, &query).await.map_err(|e| {
+        tracing::error!("Failed to load market replay: {}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "REPLAY_FAILED", "Failed to load replay")
+    })?;
+
+    Ok(Json(ReplayResponse {
+        market_id,
+        from: query.from.timestamp_millis(),
+        to: query.to.timestamp_millis(),
+        events: page.events,
+        next_from: page.next_from,
+    }))
+}
@@ -5,6 +5,7 @@
 //! - Settling assertions after liveness period
 //! - Querying assertion status
 //! - Disputing assertions
+//! - Market criteria and their IPFS pin
 
 use axum::{
     extract::{Path, State},
@@ -15,19 +16,24 @@ use ethers::types::{Address, U256};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson};
+use crate::services::criteria_pin::{self, CriteriaDocument, CriteriaPin};
+use crate::services::resolution_evidence::{self, ResolutionBundle};
 use crate::services::uma_oracle::{
     AssertionDetails, AssertionStatus, MarketResolutionAssertion, UmaOracleClient, UmaOracleConfig,
-    UmaOracleError,
 };
 use crate::AppState;
 
 /// Request to make a market resolution assertion
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct AssertMarketRequest {
     /// The outcome ID to assert as the winner
     pub outcome_id: Uuid,
     /// The asserter's address (must have approved bond)
+    #[validate(custom = "validation::address")]
     pub asserter: String,
     /// Signature proving the asserter authorized this assertion
     pub signature: String,
@@ -80,28 +86,33 @@ pub struct OracleInfoResponse {
     pub supported: bool,
 }
 
+/// UMA Oracle settings from the app config
+fn oracle_config(state: &AppState) -> Result<UmaOracleConfig, AppError> {
+    Ok(UmaOracleConfig {
+        oracle_address: state.config.get_uma_oracle_address().parse().map_err(|_| {
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "CONFIG_ERROR", "Invalid UMA Oracle address")
+        })?,
+        liveness_seconds: state.config.uma_liveness_seconds,
+        bond_amount: U256::from_dec_str(&state.config.uma_bond_amount).unwrap_or(U256::from(100_000_000u64)),
+        currency: state.config.ctf_usdc_address.parse().unwrap_or_default(),
+    })
+}
+
 /// Make a market resolution assertion
 /// POST /api/v1/markets/:market_id/assert
 pub async fn assert_market_resolution(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-    Json(req): Json<AssertMarketRequest>,
-) -> Result<Json<AssertMarketResponse>, (StatusCode, String)> {
+    ValidJson(req): ValidJson<AssertMarketRequest>,
+) -> Result<Json<AssertMarketResponse>, AppError> {
     // Check if UMA Oracle is configured
-    let blockchain_client = state.blockchain_client.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            "Blockchain client not configured".to_string(),
-        )
-    })?;
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
 
     // Parse asserter address
-    let asserter: Address = req.asserter.parse().map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            "Invalid asserter address".to_string(),
-        )
-    })?;
+    let asserter: Address = req
+        .asserter
+        .parse()
+        .map_err(|_| AppError::bad_request("INVALID_ADDRESS", "Invalid asserter address"))?;
 
     // Get outcome name
     let outcome = sqlx::query!(
@@ -109,27 +120,11 @@ pub async fn assert_market_resolution(
         req.outcome_id
     )
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-    .ok_or_else(|| (StatusCode::NOT_FOUND, "Outcome not found".to_string()))?;
+    .await?
+    .ok_or_else(|| AppError::not_found("OUTCOME_NOT_FOUND", "Outcome not found"))?;
 
     // Create UMA Oracle client
-    let oracle_config = UmaOracleConfig {
-        oracle_address: state
-            .config
-            .get_uma_oracle_address()
-            .parse()
-            .map_err(|_| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Invalid UMA Oracle address".to_string(),
-                )
-            })?,
-        liveness_seconds: state.config.uma_liveness_seconds,
-        bond_amount: U256::from_dec_str(&state.config.uma_bond_amount)
-            .unwrap_or(U256::from(100_000_000u64)),
-        currency: state.config.ctf_usdc_address.parse().unwrap_or_default(),
-    };
+    let oracle_config = oracle_config(&state)?;
 
     let uma_client = UmaOracleClient::new(
         blockchain_client.provider().clone().into(),
@@ -140,13 +135,7 @@ pub async fn assert_market_resolution(
     // Make the assertion
     let assertion_id = uma_client
         .assert_market_resolution(market_id, req.outcome_id, &outcome.name, asserter)
-        .await
-        .map_err(|e| match e {
-            UmaOracleError::MarketNotFound(_) => (StatusCode::NOT_FOUND, e.to_string()),
-            UmaOracleError::AlreadyResolved => (StatusCode::CONFLICT, e.to_string()),
-            UmaOracleError::PendingAssertionExists => (StatusCode::CONFLICT, e.to_string()),
-            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-        })?;
+        .await?;
 
     // Calculate expiration time
     let expiration = chrono::Utc::now() + chrono::Duration::seconds(oracle_config.liveness_seconds as i64);
@@ -167,13 +156,8 @@ pub async fn assert_market_resolution(
 pub async fn settle_market_assertion(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-) -> Result<Json<SettleAssertionResponse>, (StatusCode, String)> {
-    let blockchain_client = state.blockchain_client.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            "Blockchain client not configured".to_string(),
-        )
-    })?;
+) -> Result<Json<SettleAssertionResponse>, AppError> {
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
 
     // Get pending assertion for this market
     let assertion = sqlx::query!(
@@ -187,27 +171,11 @@ pub async fn settle_market_assertion(
         market_id
     )
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-    .ok_or_else(|| (StatusCode::NOT_FOUND, "No pending assertion found".to_string()))?;
+    .await?
+    .ok_or_else(|| AppError::not_found("ASSERTION_NOT_FOUND", "No pending assertion found"))?;
 
     // Create UMA Oracle client
-    let oracle_config = UmaOracleConfig {
-        oracle_address: state
-            .config
-            .get_uma_oracle_address()
-            .parse()
-            .map_err(|_| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Invalid UMA Oracle address".to_string(),
-                )
-            })?,
-        liveness_seconds: state.config.uma_liveness_seconds,
-        bond_amount: U256::from_dec_str(&state.config.uma_bond_amount)
-            .unwrap_or(U256::from(100_000_000u64)),
-        currency: state.config.ctf_usdc_address.parse().unwrap_or_default(),
-    };
+    let oracle_config = oracle_config(&state)?;
 
     let uma_client = UmaOracleClient::new(
         blockchain_client.provider().clone().into(),
@@ -216,14 +184,7 @@ pub async fn settle_market_assertion(
     );
 
     // Settle the assertion
-    let result = uma_client
-        .settle_assertion(&assertion.assertion_id)
-        .await
-        .map_err(|e| match e {
-            UmaOracleError::NotReadyForSettlement => (StatusCode::PRECONDITION_FAILED, e.to_string()),
-            UmaOracleError::AssertionNotFound(_) => (StatusCode::NOT_FOUND, e.to_string()),
-            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-        })?;
+    let result = uma_client.settle_assertion(&assertion.assertion_id).await?;
 
     let message = if result {
         "Market resolved successfully".to_string()
@@ -240,35 +201,65 @@ pub async fn settle_market_assertion(
     }))
 }
 
+/// Get a market's resolution with its evidence bundle (oracle data or
+/// admin justification, timestamps, transaction hashes)
+/// GET /api/v1/markets/:market_id/resolution
+pub async fn get_market_resolution(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+) -> Result<Json<ResolutionBundle>, AppError> {
+    let bundle = resolution_evidence::bundle(&state.db.pool, market_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+    Ok(Json(bundle))
+}
+
+/// A market's criteria document with its IPFS pin
+#[derive(Debug, Serialize)]
+pub struct MarketCriteriaResponse {
+    pub market_id: Uuid,
+    /// Absent until the market's criteria have been pinned
+    pub pin: Option<CriteriaPin>,
+    /// Link to the pinned document on the configured gateway
+    pub gateway_url: Option<String>,
+    /// The criteria as they stand now
+    pub document: CriteriaDocument,
+    /// SHA-256 of `document`; equals `pin.sha256` while nothing has changed
+    pub sha256: String,
+}
+
+/// Get a market's resolution criteria and the IPFS CID they were pinned as,
+/// so the pinned copy can be checked against what the market shows
+/// GET /api/v1/markets/:market_id/criteria
+pub async fn get_market_criteria(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+) -> Result<Json<MarketCriteriaResponse>, AppError> {
+    let document = criteria_pin::document(&state.db.pool, market_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+    let pin = criteria_pin::pin(&state.db.pool, market_id).await?;
+    let gateway_url = pin
+        .as_ref()
+        .map(|pin| format!("{}/{}", state.config.ipfs_gateway_url.trim_end_matches('/'), pin.cid));
+    Ok(Json(MarketCriteriaResponse {
+        market_id,
+        sha256: document.sha256(),
+        pin,
+        gateway_url,
+        document,
+    }))
+}
+
 /// Get assertions for a market
 /// GET /api/v1/markets/:market_id/assertions
 pub async fn get_market_assertions(
     State(state): State<Arc<AppState>>,
     Path(market_id): Path<Uuid>,
-) -> Result<Json<MarketAssertionsResponse>, (StatusCode, String)> {
-    let blockchain_client = state.blockchain_client.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            "Blockchain client not configured".to_string(),
-        )
-    })?;
-
-    let oracle_config = UmaOracleConfig {
-        oracle_address: state
-            .config
-            .get_uma_oracle_address()
-            .parse()
-            .map_err(|_| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Invalid UMA Oracle address".to_string(),
-                )
-            })?,
-        liveness_seconds: state.config.uma_liveness_seconds,
-        bond_amount: U256::from_dec_str(&state.config.uma_bond_amount)
-            .unwrap_or(U256::from(100_000_000u64)),
-        currency: state.config.ctf_usdc_address.parse().unwrap_or_default(),
-    };
+) -> Result<Json<MarketAssertionsResponse>, AppError> {
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
+
+    let oracle_config = oracle_config(&state)?;
 
     let uma_client = UmaOracleClient::new(
         blockchain_client.provider().clone().into(),
@@ -276,10 +267,7 @@ pub async fn get_market_assertions(
         state.db.pool.clone(),
     );
 
-    let assertions = uma_client
-        .get_market_assertions(market_id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let assertions = uma_client.get_market_assertions(market_id).await?;
 
     Ok(Json(MarketAssertionsResponse {
         market_id,
@@ -292,30 +280,10 @@ pub async fn get_market_assertions(
 pub async fn get_assertion_details(
     State(state): State<Arc<AppState>>,
     Path(assertion_id): Path<String>,
-) -> Result<Json<AssertionResponse>, (StatusCode, String)> {
-    let blockchain_client = state.blockchain_client.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            "Blockchain client not configured".to_string(),
-        )
-    })?;
-
-    let oracle_config = UmaOracleConfig {
-        oracle_address: state
-            .config
-            .get_uma_oracle_address()
-            .parse()
-            .map_err(|_| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Invalid UMA Oracle address".to_string(),
-                )
-            })?,
-        liveness_seconds: state.config.uma_liveness_seconds,
-        bond_amount: U256::from_dec_str(&state.config.uma_bond_amount)
-            .unwrap_or(U256::from(100_000_000u64)),
-        currency: state.config.ctf_usdc_address.parse().unwrap_or_default(),
-    };
+) -> Result<Json<AssertionResponse>, AppError> {
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
+
+    let oracle_config = oracle_config(&state)?;
 
     let uma_client = UmaOracleClient::new(
         blockchain_client.provider().clone().into(),
@@ -323,13 +291,7 @@ pub async fn get_assertion_details(
         state.db.pool.clone(),
     );
 
-    let assertion = uma_client
-        .get_assertion(&assertion_id)
-        .await
-        .map_err(|e| match e {
-            UmaOracleError::AssertionNotFound(_) => (StatusCode::NOT_FOUND, e.to_string()),
-            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-        })?;
+    let assertion = uma_client.get_assertion(&assertion_id).await?;
 
     let can_settle = uma_client
         .can_settle(&assertion_id)
@@ -358,7 +320,7 @@ pub async fn get_assertion_details(
 /// GET /api/v1/oracle/uma
 pub async fn get_uma_oracle_info(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<OracleInfoResponse>, (StatusCode, String)> {
+) -> Result<Json<OracleInfoResponse>, AppError> {
     let blockchain_client = match state.blockchain_client.as_ref() {
         Some(client) => client,
         None => {
@@ -372,22 +334,7 @@ pub async fn get_uma_oracle_info(
         }
     };
 
-    let oracle_config = UmaOracleConfig {
-        oracle_address: state
-            .config
-            .get_uma_oracle_address()
-            .parse()
-            .map_err(|_| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Invalid UMA Oracle address".to_string(),
-                )
-            })?,
-        liveness_seconds: state.config.uma_liveness_seconds,
-        bond_amount: U256::from_dec_str(&state.config.uma_bond_amount)
-            .unwrap_or(U256::from(100_000_000u64)),
-        currency: state.config.ctf_usdc_address.parse().unwrap_or_default(),
-    };
+    let oracle_config = oracle_config(&state)?;
 
     let uma_client = UmaOracleClient::new(
         blockchain_client.provider().clone().into(),
@@ -413,3 +360,59 @@ pub async fn get_uma_oracle_info(
         })),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::handlers::market::{self, ResolveMarketRequest};
+    use crate::services::resolution_evidence::{ResolutionEvidence, ResolutionMethod};
+    use crate::test_support::{account, TestApp, MAKER};
+
+    #[tokio::test]
+    async fn test_resolution_bundle_publishes_the_admin_evidence() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+
+        let Json(unresolved) = get_market_resolution(State(app.state.clone()), Path(market_id)).await.unwrap();
+        assert_eq!(unresolved.status, "active");
+        assert!(unresolved.evidence.is_none());
+
+        let request = ResolveMarketRequest {
+            winning_outcome: "yes".to_string(),
+            winning_outcome_id: None,
+            justification: Some("  Announced by the organiser  ".to_string()),
+            sources: vec!["https://example.com/result".to_string()],
+        };
+        let Json(resolved) = market::resolve(&app.state, &account(MAKER), market_id, request).await.unwrap();
+        assert_eq!(resolved.status, "resolved");
+
+        let Json(bundle) = get_market_resolution(State(app.state.clone()), Path(market_id)).await.unwrap();
+        assert_eq!(bundle.status, "resolved");
+        assert_eq!((bundle.winning_outcome_id, bundle.winning_outcome.as_deref()), (Some(yes), Some("yes")));
+        assert!(bundle.resolved_at.is_some());
+        let evidence = bundle.evidence.unwrap();
+        assert_eq!(evidence.method, "admin");
+        assert_eq!(evidence.resolved_by.as_deref(), Some(MAKER));
+        assert_eq!(evidence.justification.as_deref(), Some("Announced by the organiser"));
+        assert_eq!(evidence.oracle_data["sources"][0], "https://example.com/result");
+        assert!(evidence.tx_hashes.is_empty());
+
+        // A market resolves once; later evidence is not recorded over it
+        let onchain = ResolutionEvidence {
+            method: ResolutionMethod::Onchain,
+            winning_outcome_id: Some(yes),
+            resolved_by: None,
+            justification: None,
+            oracle_data: serde_json::json!({ "payouts": [1, 0] }),
+            tx_hashes: vec!["0xabc".to_string()],
+        };
+        let mut conn = app.db.pool.acquire().await.unwrap();
+        assert!(!resolution_evidence::record(&mut conn, market_id, &onchain).await.unwrap());
+        let Json(bundle) = get_market_resolution(State(app.state.clone()), Path(market_id)).await.unwrap();
+        assert_eq!(bundle.evidence.unwrap().method, "admin");
+
+        let missing = get_market_resolution(State(app.state.clone()), Path(Uuid::new_v4())).await.unwrap_err();
+        assert_eq!(missing.code(), "MARKET_NOT_FOUND");
+    }
+}
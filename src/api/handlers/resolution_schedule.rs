@@ -0,0 +1,130 @@
+//! Resolution Schedule Handlers
+//!
+//! Users watch markets to be reminded ahead of their resolution time;
+//! admins set and move that time.
+
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::ValidJson;
+use crate::auth::middleware::AuthUser;
+use crate::services::resolution_schedule::{self, WatchedMarket};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ScheduleResolutionRequest {
+    /// New resolution time (timestamp in milliseconds); `null` unschedules
+    pub resolution_time: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduleResolutionResponse {
+    pub market_id: Uuid,
+    pub status: String,
+    pub resolution_time: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WatchResponse {
+    pub market_id: Uuid,
+    pub watching: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WatchlistResponse {
+    pub markets: Vec<WatchedMarket>,
+}
+
+fn market_not_found() -> AppError {
+    AppError::not_found("MARKET_NOT_FOUND", "Market not found")
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Watch a market for resolution reminders
+/// POST /markets/:market_id/watch
+pub async fn watch_market(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(market_id): Path<Uuid>,
+) -> Result<Json<WatchResponse>, AppError> {
+    let address = auth_user.address.clone();
+    if !resolution_schedule::watch(&state.db.pool, market_id, &address)
+        .await?
+    {
+        return Err(market_not_found());
+    }
+    Ok(Json(WatchResponse {
+        market_id,
+        watching: true,
+    }))
+}
+
+/// Stop watching a market
+/// DELETE /markets/:market_id/watch
+pub async fn unwatch_market(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(market_id): Path<Uuid>,
+) -> Result<Json<WatchResponse>, AppError> {
+    let address = auth_user.address.clone();
+    resolution_schedule::unwatch(&state.db.pool, market_id, &address)
+        .await?;
+    Ok(Json(WatchResponse {
+        market_id,
+        watching: false,
+    }))
+}
+
+/// Markets the user watches, soonest resolution first
+/// GET /account/watchlist
+pub async fn get_watchlist(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<WatchlistResponse>, AppError> {
+    let markets = resolution_schedule::watchlist(&state.db.pool, &auth_user.address)
+        .await?;
+    Ok(Json(WatchlistResponse { markets }))
+}
+
+/// Set, move or clear a market's resolution time - Admin only.
+/// Moving the time into the future reopens a market awaiting resolution.
+/// PUT /admin/markets/:market_id/resolution-time
+pub async fn schedule_resolution(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidJson(req): ValidJson<ScheduleResolutionRequest>,
+) -> Result<Json<ScheduleResolutionResponse>, AppError> {
+    let resolution_time = match req.resolution_time {
+        Some(ms) => Some(DateTime::<Utc>::from_timestamp_millis(ms).ok_or_else(|| {
+            AppError::bad_request("INVALID_RESOLUTION_TIME", "Invalid resolution_time")
+        })?),
+        None => None,
+    };
+
+    let status = resolution_schedule::reschedule(&state.db.pool, market_id, resolution_time, Utc::now())
+        .await?
+        .ok_or_else(market_not_found)?;
+
+    tracing::info!("Scheduled resolution of market {} at {:?}", market_id, resolution_time);
+
+    Ok(Json(ScheduleResolutionResponse {
+        market_id,
+        status,
+        resolution_time: resolution_time.map(|t| t.timestamp_millis()),
+    }))
+}
@@ -0,0 +1,64 @@
+//! Sandbox Market Admin Handler
+//!
+//! Seeds demo markets with synthetic two-sided liquidity and trade history
+//! for staging and demo environments. Refused unless
+//! `sandbox_tools_enabled` is set.
+
+use axum::{extract::State, http::StatusCode, Json};
+use std::sync::Arc;
+
+use crate::api::error::AppError;
+use crate::api::validation::{FieldError, ValidJson};
+use crate::services::sandbox::{self, SandboxError, SandboxParams, SandboxReport};
+use crate::AppState;
+
+fn sandbox_error(e: SandboxError) -> AppError {
+    let status = match &e {
+        SandboxError::InvalidParams(_) => StatusCode::BAD_REQUEST,
+        SandboxError::Import(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        SandboxError::Liquidity(_) | SandboxError::Database(_) => {
+            tracing::error!("Sandbox seeding failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    let code = e.code();
+    match e {
+        SandboxError::Import(issues) => {
+            let fields = issues
+                .into_iter()
+                .map(|issue| FieldError {
+                    field: format!("{}s[{}]", issue.entity, issue.row),
+                    code: "invalid".to_string(),
+                    message: issue.message,
+                })
+                .collect();
+            AppError::new(status, code, "Generated market failed import validation").with_fields(fields)
+        }
+        _ if status.is_server_error() => AppError::new(status, code, "Sandbox seeding failed"),
+        other => AppError::new(status, code, other.to_string()),
+    }
+}
+
+/// Create a sandbox market with synthetic liquidity and trade history (Admin only)
+/// POST /admin/sandbox/markets
+pub async fn seed_market(
+    State(state): State<Arc<AppState>>,
+    ValidJson(params): ValidJson<SandboxParams>,
+) -> Result<(StatusCode, Json<SandboxReport>), AppError> {
+    if !state.config.sandbox_tools_enabled {
+        return Err(AppError::forbidden(
+            "SANDBOX_DISABLED",
+            "Sandbox tools are disabled in this environment",
+        ));
+    }
+
+    let report = sandbox::seed(
+        &state.db.pool,
+        Some(&state.order_gateway),
+        state.config.collateral_symbol(),
+        &params,
+    )
+    .await
+    .map_err(sandbox_error)?;
+    Ok((StatusCode::CREATED, Json(report)))
+}
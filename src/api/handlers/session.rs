@@ -0,0 +1,137 @@
+//! Session Management Handlers
+//!
+//! Users list the devices they are logged in on and revoke any of them.
+//! A revoked session's token is rejected by the auth middleware and the
+//! WebSocket auth even before it expires.
+
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::ValidQuery;
+use crate::auth::middleware::AuthUser;
+use crate::auth::session::{self, Session};
+use crate::AppState;
+
+// Helper module to serialize DateTime as milliseconds timestamp
+mod datetime_as_millis {
+    use chrono::{DateTime, Utc};
+    use serde::Serializer;
+
+    pub fn serialize<S>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i64(dt.timestamp_millis())
+    }
+}
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(serialize_with = "datetime_as_millis::serialize")]
+    pub issued_at: DateTime<Utc>,
+    #[serde(serialize_with = "datetime_as_millis::serialize")]
+    pub last_used_at: DateTime<Utc>,
+    #[serde(serialize_with = "datetime_as_millis::serialize")]
+    pub expires_at: DateTime<Utc>,
+    /// Session of the token making this request
+    pub current: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RevokeAllQuery {
+    /// Keep the session making the request logged in
+    #[serde(default)]
+    pub keep_current: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevokeResponse {
+    pub revoked: u64,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn to_response(session: Session, current: Option<Uuid>) -> SessionResponse {
+    SessionResponse {
+        current: current == Some(session.id),
+        id: session.id,
+        ip_address: session.ip_address,
+        user_agent: session.user_agent,
+        issued_at: session.issued_at,
+        last_used_at: session.last_used_at,
+        expires_at: session.expires_at,
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// List the user's active sessions, newest first
+/// GET /account/sessions
+pub async fn list_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<SessionsResponse>, AppError> {
+    let user_address = auth_user.address.clone();
+    let sessions = session::list_active(&state.db.pool, &user_address)
+        .await?
+        .into_iter()
+        .map(|s| to_response(s, auth_user.session_id))
+        .collect();
+    Ok(Json(SessionsResponse { sessions }))
+}
+
+/// Revoke one of the user's sessions
+/// DELETE /account/sessions/:session_id
+pub async fn revoke_session(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<RevokeResponse>, AppError> {
+    let user_address = auth_user.address.clone();
+    let revoked = session::revoke(&state.db.pool, &user_address, session_id)
+        .await?;
+    if !revoked {
+        return Err(AppError::not_found("SESSION_NOT_FOUND", "Session not found"));
+    }
+    tracing::info!("User {} revoked session {}", user_address, session_id);
+    Ok(Json(RevokeResponse { revoked: 1 }))
+}
+
+/// Revoke all of the user's sessions, optionally keeping the current one
+/// POST /account/sessions/revoke-all?keep_current=true
+pub async fn revoke_all_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<RevokeAllQuery>,
+) -> Result<Json<RevokeResponse>, AppError> {
+    let user_address = auth_user.address.clone();
+    let keep = if query.keep_current { auth_user.session_id } else { None };
+    let revoked = session::revoke_all(&state.db.pool, &user_address, keep)
+        .await?;
+    tracing::info!("User {} revoked {} sessions", user_address, revoked);
+    Ok(Json(RevokeResponse { revoked }))
+}
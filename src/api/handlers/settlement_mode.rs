@@ -0,0 +1,91 @@
+//! Settlement Mode Handlers
+//!
+//! Users choose whether their fills settle into internal balances
+//! (custodial) or on-chain to their own wallet (self-custody).
+
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::ValidJson;
+use crate::auth::middleware::AuthUser;
+use crate::services::settlement_mode::{self, SettlementMode, SettlementModeError};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateSettlementModeRequest {
+    pub mode: SettlementMode,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SettlementModeResponse {
+    pub mode: SettlementMode,
+    /// Endpoint orders of this mode are placed through
+    pub order_endpoint: &'static str,
+    /// Whether on-chain settlement is available on this deployment
+    pub onchain_settlement: bool,
+}
+
+fn mode_error(e: SettlementModeError) -> AppError {
+    let status = match e {
+        SettlementModeError::UserNotFound => StatusCode::NOT_FOUND,
+        SettlementModeError::OpenOrders(_) => StatusCode::CONFLICT,
+        SettlementModeError::Database(e) => return e.into(),
+    };
+    AppError::new(status, e.code(), e.to_string())
+}
+
+fn response(state: &AppState, mode: SettlementMode) -> SettlementModeResponse {
+    SettlementModeResponse {
+        mode,
+        order_endpoint: match mode {
+            SettlementMode::Custodial => "/orders",
+            SettlementMode::SelfCustody => "/orders/ctf",
+        },
+        onchain_settlement: state.settlement_sender.is_some(),
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Get the user's settlement mode
+/// GET /account/settlement-mode
+pub async fn get_settlement_mode(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<SettlementModeResponse>, AppError> {
+    let mode = settlement_mode::get(&state.db.pool, &auth_user.address)
+        .await
+        .map_err(|e| mode_error(e.into()))?;
+    Ok(Json(response(&state, mode)))
+}
+
+/// Switch the user's settlement mode; refused while they have open orders
+/// PUT /account/settlement-mode
+pub async fn update_settlement_mode(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<UpdateSettlementModeRequest>,
+) -> Result<Json<SettlementModeResponse>, AppError> {
+    if req.mode == SettlementMode::SelfCustody && state.settlement_sender.is_none() {
+        return Err(AppError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SETTLEMENT_UNAVAILABLE",
+            "On-chain settlement is not available",
+        ));
+    }
+    let address = auth_user.address.clone();
+    settlement_mode::set(&state.db.pool, &address, req.mode)
+        .await
+        .map_err(mode_error)?;
+    tracing::info!("User {} switched to {} settlement", address, req.mode.as_str());
+    Ok(Json(response(&state, req.mode)))
+}
@@ -0,0 +1,85 @@
+//! System Event Timeline Admin Handlers
+//!
+//! Read back the operational event timeline when reconstructing an incident.
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidQuery};
+use crate::services::system_events::{self, SystemEvent, SystemEventFilter, SystemEventKind};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SystemEventsQuery {
+    /// engine, market, settlement, trading or config
+    pub category: Option<String>,
+    /// e.g. engine_started, trade_busted
+    pub kind: Option<String>,
+    /// info, warning or critical
+    pub severity: Option<String>,
+    /// Market id, trade id, flag key or user address the event is about
+    pub subject: Option<String>,
+    /// Inclusive lower bound (Unix ms)
+    pub since: Option<i64>,
+    /// Exclusive upper bound (Unix ms)
+    pub until: Option<i64>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SystemEventsResponse {
+    pub events: Vec<SystemEvent>,
+}
+
+fn timestamp(ms: Option<i64>, name: &str) -> Result<Option<DateTime<Utc>>, AppError> {
+    ms.map(|ms| {
+        DateTime::<Utc>::from_timestamp_millis(ms)
+            .ok_or_else(|| AppError::bad_request("INVALID_TIMESTAMP", format!("Invalid {} timestamp", name)))
+    })
+    .transpose()
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+/// System event timeline, newest first (Admin only)
+/// GET /admin/system-events
+pub async fn list_events(
+    State(state): State<Arc<AppState>>,
+    ValidQuery(query): ValidQuery<SystemEventsQuery>,
+) -> Result<Json<SystemEventsResponse>, AppError> {
+    if let Some(kind) = query.kind.as_deref().filter(|k| SystemEventKind::parse(k).is_none()) {
+        return Err(AppError::bad_request("INVALID_KIND", format!("Unknown event kind: {}", kind)));
+    }
+    if let Some(severity) = query
+        .severity
+        .as_deref()
+        .filter(|s| !matches!(*s, "info" | "warning" | "critical"))
+    {
+        return Err(AppError::bad_request("INVALID_SEVERITY", format!("Unknown severity: {}", severity)));
+    }
+
+    let filter = SystemEventFilter {
+        category: query.category,
+        kind: query.kind,
+        severity: query.severity,
+        subject: query.subject,
+        since: timestamp(query.since, "since")?,
+        until: timestamp(query.until, "until")?,
+        limit: query.limit.unwrap_or(200).clamp(1, 1000),
+    };
+
+    let events = system_events::query(&state.db.pool, &filter).await?;
+
+    Ok(Json(SystemEventsResponse { events }))
+}
@@ -0,0 +1,25 @@
+//! Server Time Handler
+
+use axum::{extract::State, Json};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct ServerTimeResponse {
+    /// Server clock (milliseconds)
+    pub server_time: i64,
+    /// Highest trade sequence number the matching engine has handed out
+    pub sequence: u64,
+}
+
+/// Server clock and engine sequence high-water mark, for signature
+/// timestamps and latency measurement
+/// GET /time
+pub async fn get_time(State(state): State<Arc<AppState>>) -> Json<ServerTimeResponse> {
+    Json(ServerTimeResponse {
+        server_time: chrono::Utc::now().timestamp_millis(),
+        sequence: state.matching_engine.last_trade_sequence(),
+    })
+}
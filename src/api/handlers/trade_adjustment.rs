@@ -0,0 +1,210 @@
+//! Trade Bust / Adjustment Admin Handlers
+//!
+//! Bust or re-price erroneous trades. Both parties are compensated through
+//! holdings and ledger entries and notified; the trade record itself is only
+//! annotated.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson};
+use crate::auth::middleware::AuthUser;
+use crate::models::Address;
+use crate::services::notification::NotificationKind;
+use crate::services::system_events::{self, SystemEventKind};
+use crate::services::trade_adjustment::{self, TradeAdjustment, TradeAdjustmentError};
+use crate::services::webhook::WebhookEventType;
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct BustTradeRequest {
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AdjustTradeRequest {
+    /// New effective price of the trade
+    #[validate(custom = "validation::price")]
+    pub price: Decimal,
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdjustmentsResponse {
+    pub trade_id: Uuid,
+    pub adjustments: Vec<TradeAdjustment>,
+}
+
+fn adjustment_error(e: TradeAdjustmentError) -> AppError {
+    let status = match e {
+        TradeAdjustmentError::TradeNotFound => StatusCode::NOT_FOUND,
+        TradeAdjustmentError::AlreadyBusted | TradeAdjustmentError::MarketClosed(_) => StatusCode::CONFLICT,
+        TradeAdjustmentError::Database(e) => return e.into(),
+        _ => StatusCode::BAD_REQUEST,
+    };
+    AppError::new(status, e.code(), e.to_string())
+}
+
+/// Record the correction on the system timeline and tell both parties what
+/// it did to them
+async fn notify_parties(state: &AppState, adjustment: &TradeAdjustment) {
+    let (status, kind) = match adjustment.adjusted_price {
+        Some(_) => ("price adjusted", SystemEventKind::TradeAdjusted),
+        None => ("busted", SystemEventKind::TradeBusted),
+    };
+    system_events::record(
+        &state.db.pool,
+        kind,
+        Some(&adjustment.trade_id.to_string()),
+        format!("Trade {} by {}: {}", status, adjustment.admin_address, adjustment.reason),
+        serde_json::json!({
+            "adjustment_id": adjustment.id,
+            "market_id": adjustment.market_id,
+            "previous_price": adjustment.previous_price,
+            "adjusted_price": adjustment.adjusted_price,
+            "amount": adjustment.amount,
+        }),
+    )
+    .await;
+    for comp in &adjustment.compensations {
+        let data = serde_json::json!({
+            "user_address": comp.user_address,
+            "adjustment_id": adjustment.id,
+            "trade_id": adjustment.trade_id,
+            "order_id": comp.order_id,
+            "market_id": adjustment.market_id,
+            "kind": adjustment.kind,
+            "status": status,
+            "role": comp.role,
+            "share_type": comp.share_type,
+            "amount": adjustment.amount,
+            "previous_price": adjustment.previous_price,
+            "adjusted_price": adjustment.adjusted_price,
+            "shares": comp.shares,
+            "collateral": comp.collateral,
+            "reason": adjustment.reason,
+            "symbol": state.config.collateral_symbol(),
+        });
+        state
+            .webhook_service
+            .dispatch(WebhookEventType::TradeAdjusted, Some(&comp.user_address), data.clone())
+            .await;
+        state
+            .notification_service
+            .notify(NotificationKind::TradeAdjusted, &comp.user_address, None, data)
+            .await;
+    }
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+/// Bust a trade: reverse both parties' shares and collateral (Admin only)
+/// POST /admin/trades/:trade_id/bust
+///
+/// Under the two-person rule this goes through `/admin/approvals` instead.
+pub async fn bust_trade(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(trade_id): Path<Uuid>,
+    ValidJson(req): ValidJson<BustTradeRequest>,
+) -> Result<Json<TradeAdjustment>, AppError> {
+    require_no_approval(&state)?;
+    bust(&state, &auth_user.address, trade_id, req).await
+}
+
+/// Re-price a trade, moving the price difference between the parties (Admin only)
+/// POST /admin/trades/:trade_id/adjust
+///
+/// Under the two-person rule this goes through `/admin/approvals` instead.
+pub async fn adjust_trade(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(trade_id): Path<Uuid>,
+    ValidJson(req): ValidJson<AdjustTradeRequest>,
+) -> Result<Json<TradeAdjustment>, AppError> {
+    require_no_approval(&state)?;
+    adjust(&state, &auth_user.address, trade_id, req).await
+}
+
+fn require_no_approval(state: &AppState) -> Result<(), AppError> {
+    if state.config.admin_two_person_rule {
+        return Err(AppError::forbidden(
+            "APPROVAL_REQUIRED",
+            "Trade corrections need a second admin's approval; propose them via /admin/approvals",
+        ));
+    }
+    Ok(())
+}
+
+/// Bust `trade_id` as `admin_address`
+pub(crate) async fn bust(
+    state: &AppState,
+    admin_address: &Address,
+    trade_id: Uuid,
+    req: BustTradeRequest,
+) -> Result<Json<TradeAdjustment>, AppError> {
+    let adjustment = trade_adjustment::adjust_trade(
+        &state.db.pool,
+        trade_id,
+        None,
+        &req.reason,
+        admin_address,
+        state.config.collateral_symbol(),
+    )
+    .await
+    .map_err(adjustment_error)?;
+
+    notify_parties(state, &adjustment).await;
+    Ok(Json(adjustment))
+}
+
+/// Re-price `trade_id` as `admin_address`
+pub(crate) async fn adjust(
+    state: &AppState,
+    admin_address: &Address,
+    trade_id: Uuid,
+    req: AdjustTradeRequest,
+) -> Result<Json<TradeAdjustment>, AppError> {
+    let adjustment = trade_adjustment::adjust_trade(
+        &state.db.pool,
+        trade_id,
+        Some(req.price),
+        &req.reason,
+        admin_address,
+        state.config.collateral_symbol(),
+    )
+    .await
+    .map_err(adjustment_error)?;
+
+    notify_parties(state, &adjustment).await;
+    Ok(Json(adjustment))
+}
+
+/// Corrections recorded for a trade (Admin only)
+/// GET /admin/trades/:trade_id/adjustments
+pub async fn list_adjustments(
+    State(state): State<Arc<AppState>>,
+    Path(trade_id): Path<Uuid>,
+) -> Result<Json<AdjustmentsResponse>, AppError> {
+    let adjustments = trade_adjustment::list_adjustments(&state.db.pool, trade_id)
+        .await
+        .map_err(|e| adjustment_error(e.into()))?;
+    Ok(Json(AdjustmentsResponse { trade_id, adjustments }))
+}
@@ -0,0 +1,110 @@
+//! Trade Persist Queue Admin Handlers
+//!
+//! Inspect trades whose persistence failed after matching, and reprocess
+//! entries the retry worker gave up on.
+
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::services::trade_persistence::{QueuedTrade, ReprocessOutcome};
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct QueueQuery {
+    /// "pending" or "dead"
+    pub status: Option<String>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueResponse {
+    pub pending: i64,
+    pub dead: i64,
+    /// Held in this instance's memory while the queue table is unreachable
+    pub buffered: usize,
+    pub entries: Vec<QueuedTrade>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReprocessResponse {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub outcome: ReprocessOutcome,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequeueResponse {
+    pub requeued: u64,
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+/// Failed trade persists, e.g. `?status=dead` (Admin only)
+/// GET /admin/trade-persist-queue
+pub async fn list_queue(
+    State(state): State<Arc<AppState>>,
+    ValidQuery(query): ValidQuery<QueueQuery>,
+) -> Result<Json<QueueResponse>, AppError> {
+    if let Some(status) = query.status.as_deref().filter(|s| !matches!(*s, "pending" | "dead")) {
+        return Err(AppError::bad_request(
+            "INVALID_STATUS",
+            format!("Unknown status: {}", status),
+        ));
+    }
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+
+    let queue = &state.trade_persist_queue;
+    let (pending, dead) = queue.counts().await?;
+    let entries = queue.list(query.status.as_deref(), limit).await?;
+
+    Ok(Json(QueueResponse {
+        pending,
+        dead,
+        buffered: queue.buffered(),
+        entries,
+    }))
+}
+
+/// Retry one entry immediately (Admin only)
+/// POST /admin/trade-persist-queue/:id/reprocess
+pub async fn reprocess_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReprocessResponse>, AppError> {
+    let outcome = state
+        .trade_persist_queue
+        .reprocess(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("ENTRY_NOT_FOUND", "Queue entry not found"))?;
+
+    tracing::info!("Trade persist entry {} reprocessed by admin {}: {:?}", id, auth_user.address, outcome);
+    Ok(Json(ReprocessResponse { id, outcome }))
+}
+
+/// Give every dead entry a fresh retry budget (Admin only)
+/// POST /admin/trade-persist-queue/requeue-dead
+pub async fn requeue_dead(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<RequeueResponse>, AppError> {
+    let requeued = state.trade_persist_queue.requeue_dead().await?;
+    tracing::info!("{} dead trade persist entries requeued by admin {}", requeued, auth_user.address);
+    Ok(Json(RequeueResponse { requeued }))
+}
@@ -0,0 +1,466 @@
+//! Internal Transfer API Handlers
+//!
+//! Instant off-chain collateral transfers between platform addresses
+//! (OTC settlements, team accounts). Every transfer writes a debit and a
+//! credit entry to the balance ledger, pushes a balance update to both
+//! parties over WebSocket and emails the recipient.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::models::Address;
+use crate::services::event_bus::BusEvent;
+use crate::services::ledger::{self, LedgerEntry, LedgerEntryType};
+use crate::services::matching::precision::{Collateral, COLLATERAL_DP};
+use crate::services::notification::NotificationKind;
+use crate::{AppState, BalanceUpdateEvent};
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TransferRequest {
+    /// Recipient platform address
+    #[validate(custom = "validation::address")]
+    pub to_address: String,
+    #[validate(custom = "validation::positive")]
+    pub amount: Decimal,
+    /// Optional note shown to both parties
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TransferHistoryQuery {
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct TransferResponse {
+    pub transfer_id: Uuid,
+    pub from_address: Address,
+    pub to_address: Address,
+    pub token: String,
+    pub amount: Decimal,
+    pub memo: Option<String>,
+    pub status: String,
+    pub available_after: Decimal,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferRecord {
+    pub id: Uuid,
+    /// "in" or "out" relative to the requesting user
+    pub direction: String,
+    pub counterparty: String,
+    pub token: String,
+    pub amount: Decimal,
+    pub memo: Option<String>,
+    pub status: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferHistoryResponse {
+    pub transfers: Vec<TransferRecord>,
+}
+
+/// (id, from_address, to_address, token, amount, memo, status, created_at)
+type TransferRow = (Uuid, String, String, String, Decimal, Option<String>, String, DateTime<Utc>);
+
+const MAX_MEMO_LENGTH: usize = 140;
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Transfer collateral to another platform user
+/// POST /account/transfer
+pub async fn create_transfer(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<TransferRequest>,
+) -> Result<Json<TransferResponse>, AppError> {
+    let from_address = auth_user.address.clone();
+    let token = state.config.collateral_symbol().to_string();
+
+    // Validate request
+    let to_address: Address = req
+        .to_address
+        .parse()
+        .map_err(|_| AppError::bad_request("INVALID_ADDRESS", "Invalid recipient address"))?;
+    if to_address == from_address {
+        return Err(AppError::bad_request("SELF_TRANSFER", "Cannot transfer to yourself"));
+    }
+    if req.amount <= Decimal::ZERO {
+        return Err(AppError::bad_request("INVALID_AMOUNT", "Amount must be positive"));
+    }
+    if Collateral::exact(req.amount).is_none() {
+        return Err(AppError::bad_request(
+            "INVALID_AMOUNT",
+            format!("Amount supports at most {} decimals", COLLATERAL_DP),
+        ));
+    }
+    let min_amount = state.config.transfer_min_amount();
+    if req.amount < min_amount {
+        return Err(AppError::bad_request(
+            "AMOUNT_TOO_SMALL",
+            format!("Amount below minimum transfer of {}", min_amount),
+        ));
+    }
+    let max_amount = state.config.transfer_max_amount();
+    if req.amount > max_amount {
+        return Err(AppError::bad_request(
+            "AMOUNT_TOO_LARGE",
+            format!("Amount exceeds maximum transfer of {}", max_amount),
+        ));
+    }
+    let memo = req.memo.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    if memo.as_ref().map(|m| m.chars().count() > MAX_MEMO_LENGTH).unwrap_or(false) {
+        return Err(AppError::bad_request(
+            "MEMO_TOO_LONG",
+            format!("Memo must be at most {} characters", MAX_MEMO_LENGTH),
+        ));
+    }
+
+    // Recipient must be a registered platform user
+    let recipient: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM users WHERE address = $1")
+        .bind(&to_address)
+        .fetch_optional(&state.db.pool)
+        .await?;
+    if recipient.is_none() {
+        return Err(AppError::not_found("RECIPIENT_NOT_FOUND", "Recipient is not a platform user"));
+    }
+
+    let mut tx = state.db.pool.begin().await?;
+
+    // Lock the sender's balance row first so concurrent transfers serialize
+    // and the velocity check below sees every committed transfer.
+    let sender_balance: Option<(Decimal, Decimal)> = sqlx::query_as(
+        "SELECT available, frozen FROM balances WHERE user_address = $1 AND token = $2 FOR UPDATE",
+    )
+    .bind(&from_address)
+    .bind(&token)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let (sender_available, sender_frozen) = sender_balance.unwrap_or((Decimal::ZERO, Decimal::ZERO));
+    if sender_available < req.amount {
+        return Err(AppError::bad_request(
+            "INSUFFICIENT_BALANCE",
+            format!("Insufficient balance: {} < {}", sender_available, req.amount),
+        ));
+    }
+
+    // Velocity limits: outbound count per rolling hour, outbound total per rolling day
+    let (hourly_count, daily_total): (i64, Option<Decimal>) = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE created_at > NOW() - INTERVAL '1 hour'),
+            SUM(amount)
+        FROM internal_transfers
+        WHERE from_address = $1 AND token = $2 AND created_at > NOW() - INTERVAL '24 hours'
+        "#,
+    )
+    .bind(&from_address)
+    .bind(&token)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if hourly_count >= state.config.transfer_hourly_count {
+        return Err(AppError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "TRANSFER_RATE_LIMITED",
+            format!("Transfer limit of {} per hour reached", state.config.transfer_hourly_count),
+        ));
+    }
+    let daily_limit = state.config.transfer_daily_limit();
+    let daily_total = daily_total.unwrap_or(Decimal::ZERO);
+    if daily_total + req.amount > daily_limit {
+        return Err(AppError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "TRANSFER_DAILY_LIMIT",
+            format!(
+                "Daily transfer limit exceeded: {} remaining of {}",
+                (daily_limit - daily_total).max(Decimal::ZERO),
+                daily_limit
+            ),
+        ));
+    }
+
+    // Debit sender
+    let sender_after = sender_available - req.amount;
+    sqlx::query(
+        r#"
+        UPDATE balances
+        SET available = available - $1, updated_at = NOW()
+        WHERE user_address = $2 AND token = $3
+        "#,
+    )
+    .bind(req.amount)
+    .bind(&from_address)
+    .bind(&token)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to debit sender: {}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "TRANSFER_FAILED", "Failed to process transfer")
+    })?;
+
+    // Credit recipient (create the balance row on first receipt)
+    let (recipient_available, recipient_frozen): (Decimal, Decimal) = sqlx::query_as(
+        r#"
+        INSERT INTO balances (user_address, token, available, frozen)
+        VALUES ($1, $2, $3, 0)
+        ON CONFLICT (user_address, token)
+        DO UPDATE SET available = balances.available + $3, updated_at = NOW()
+        RETURNING available, frozen
+        "#,
+    )
+    .bind(&to_address)
+    .bind(&token)
+    .bind(req.amount)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to credit recipient: {}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "TRANSFER_FAILED", "Failed to process transfer")
+    })?;
+
+    let transfer_id = Uuid::new_v4();
+    let created_at: DateTime<Utc> = Utc::now();
+    sqlx::query(
+        r#"
+        INSERT INTO internal_transfers (id, from_address, to_address, token, amount, memo, status, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, 'completed', $7)
+        "#,
+    )
+    .bind(transfer_id)
+    .bind(&from_address)
+    .bind(&to_address)
+    .bind(&token)
+    .bind(req.amount)
+    .bind(&memo)
+    .bind(created_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record transfer: {}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "TRANSFER_FAILED", "Failed to process transfer")
+    })?;
+
+    for entry in [
+        LedgerEntry {
+            user_address: from_address.as_str(),
+            token: &token,
+            entry_type: LedgerEntryType::TransferOut,
+            amount: -req.amount,
+            balance_after: sender_after,
+            reference_id: Some(transfer_id),
+            counterparty: Some(to_address.as_str()),
+        },
+        LedgerEntry {
+            user_address: to_address.as_str(),
+            token: &token,
+            entry_type: LedgerEntryType::TransferIn,
+            amount: req.amount,
+            balance_after: recipient_available,
+            reference_id: Some(transfer_id),
+            counterparty: Some(from_address.as_str()),
+        },
+    ] {
+        ledger::record_entry(&mut tx, &entry).await.map_err(|e| {
+            tracing::error!("Failed to write ledger entry: {}", e);
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "TRANSFER_FAILED", "Failed to process transfer")
+        })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transfer: {}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "TRANSFER_FAILED", "Failed to process transfer")
+    })?;
+
+    tracing::info!(
+        "Internal transfer {} - from: {}, to: {}, token: {}, amount: {}",
+        transfer_id,
+        from_address,
+        to_address,
+        token,
+        req.amount
+    );
+
+    // Notify both parties
+    state
+        .event_bus
+        .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+            user_address: from_address.as_str().to_string(),
+            token: token.clone(),
+            available: sender_after.to_string(),
+            frozen: sender_frozen.to_string(),
+            total: (sender_after + sender_frozen).to_string(),
+            event_type: LedgerEntryType::TransferOut.to_string(),
+        }))
+        .await;
+    state
+        .event_bus
+        .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+            user_address: to_address.as_str().to_string(),
+            token: token.clone(),
+            available: recipient_available.to_string(),
+            frozen: recipient_frozen.to_string(),
+            total: (recipient_available + recipient_frozen).to_string(),
+            event_type: LedgerEntryType::TransferIn.to_string(),
+        }))
+        .await;
+    state
+        .notification_service
+        .notify(
+            NotificationKind::TransferReceived,
+            to_address.as_str(),
+            None,
+            serde_json::json!({
+                "transfer_id": transfer_id,
+                "from_address": from_address.as_str(),
+                "amount": req.amount,
+                "symbol": token,
+                "memo": memo,
+            }),
+        )
+        .await;
+
+    Ok(Json(TransferResponse {
+        transfer_id,
+        from_address,
+        to_address,
+        token,
+        amount: req.amount,
+        memo,
+        status: "completed".to_string(),
+        available_after: sender_after,
+        created_at: created_at.timestamp_millis(),
+    }))
+}
+
+/// Get incoming and outgoing transfers for the current user
+/// GET /account/transfers
+pub async fn get_transfers(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidQuery(query): ValidQuery<TransferHistoryQuery>,
+) -> Result<Json<TransferHistoryResponse>, AppError> {
+    let user_address = auth_user.address.clone();
+    let limit = query.limit.unwrap_or(50).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let rows: Vec<TransferRow> =
+        sqlx::query_as(
+            r#"
+            SELECT id, from_address, to_address, token, amount, memo, status, created_at
+            FROM internal_transfers
+            WHERE from_address = $1 OR to_address = $1
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(&user_address)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&state.db.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch transfers: {}", e);
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "TRANSFER_FETCH_FAILED", "Failed to fetch transfers")
+        })?;
+
+    let transfers = rows
+        .into_iter()
+        .map(|(id, from, to, token, amount, memo, status, created_at)| {
+            let outgoing = from == user_address.as_str();
+            TransferRecord {
+                id,
+                direction: if outgoing { "out" } else { "in" }.to_string(),
+                counterparty: if outgoing { to } else { from },
+                token,
+                amount,
+                memo,
+                status,
+                created_at: created_at.timestamp_millis(),
+            }
+        })
+        .collect();
+
+    Ok(Json(TransferHistoryResponse { transfers }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::middleware::UserRole;
+    use crate::test_support::{account, TestApp, MAKER, TAKER};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_transfer_emails_recipient() {
+        let app = TestApp::builder().build().await;
+        let pool = &app.db.pool;
+        app.deposit(TAKER, dec!(50)).await;
+        for user in [MAKER, TAKER] {
+            sqlx::query("INSERT INTO users (address, nonce, email) VALUES ($1, 1, $2) ON CONFLICT (address) DO NOTHING")
+                .bind(account(user))
+                .bind(format!("{}@example.com", &user[..6]))
+                .execute(pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO notification_preferences (user_address, email_enabled) VALUES ($1, TRUE)")
+                .bind(account(user))
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+
+        let auth = Extension(AuthUser {
+            address: account(TAKER),
+            role: UserRole::User,
+            session_id: None,
+        });
+        let req = TransferRequest {
+            to_address: MAKER.to_string(),
+            amount: dec!(12.5),
+            memo: Some("OTC settlement".to_string()),
+        };
+        let transfer = create_transfer(State(app.state.clone()), auth, ValidJson(req)).await.unwrap().0;
+
+        let queued: Vec<(Address, String, serde_json::Value)> =
+            sqlx::query_as("SELECT user_address, kind, payload FROM notification_queue ORDER BY created_at")
+                .fetch_all(pool)
+                .await
+                .unwrap();
+        assert_eq!(queued.len(), 1);
+        let (user_address, kind, payload) = &queued[0];
+        assert_eq!((user_address, kind.as_str()), (&account(MAKER), "transfer_received"));
+        assert_eq!(payload["transfer_id"], transfer.transfer_id.to_string());
+        assert_eq!(payload["from_address"], TAKER);
+        assert_eq!(payload["amount"], "12.5");
+        assert_eq!(payload["memo"], "OTC settlement");
+    }
+}
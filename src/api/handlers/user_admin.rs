@@ -0,0 +1,325 @@
+//! Admin User Management Handlers
+//!
+//! Support console for user accounts: look an account up, freeze or
+//! unfreeze it, force-cancel its open orders, set its market data tier and
+//! API quotas, and leave support notes.
+//! Every call is written to the admin audit log.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::models::Address;
+use crate::services::account_admin::{
+    self, AccountAdminError, AccountOverview, AdminAction, AuditEntry, SupportNote,
+};
+use crate::services::market_data_tier::MarketDataTier;
+use crate::services::order_gateway::OrderSource;
+use crate::AppState;
+
+// ============================================================================
+// Request / Response Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReasonRequest {
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AddNoteRequest {
+    #[validate(length(min = 1))]
+    pub note: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MarketDataTierRequest {
+    pub tier: MarketDataTier,
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+/// Daily API quotas of an account; omitted (null) limits fall back to the
+/// platform default, 0 is unlimited
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApiQuotaRequest {
+    #[validate(range(min = 0))]
+    pub requests: Option<i64>,
+    #[validate(range(min = 0))]
+    pub bytes: Option<i64>,
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AuditQuery {
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FreezeResponse {
+    pub address: Address,
+    pub frozen: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarketDataTierResponse {
+    pub address: Address,
+    pub tier: MarketDataTier,
+    pub previous: MarketDataTier,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiQuotaLimits {
+    pub requests: Option<i64>,
+    pub bytes: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiQuotaResponse {
+    pub address: Address,
+    pub quota: ApiQuotaLimits,
+    pub previous: ApiQuotaLimits,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CancelOrdersResponse {
+    pub cancelled: Vec<Uuid>,
+    /// Orders that were no longer open or could not be cancelled
+    pub skipped: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub address: Address,
+    pub entries: Vec<AuditEntry>,
+}
+
+fn admin_error(e: AccountAdminError) -> AppError {
+    let status = match e {
+        AccountAdminError::UserNotFound => StatusCode::NOT_FOUND,
+        AccountAdminError::AlreadyFrozen | AccountAdminError::NotFrozen => StatusCode::CONFLICT,
+        AccountAdminError::Database(e) => return e.into(),
+        _ => StatusCode::BAD_REQUEST,
+    };
+    AppError::new(status, e.code(), e.to_string())
+}
+
+/// Canonical form of a path address
+fn parse_address(address: &str) -> Result<Address, AppError> {
+    address.parse::<Address>().map_err(|e| {
+        AppError::bad_request("INVALID_ADDRESS", e.to_string())
+    })
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Look up a user's balances, holdings, open orders, recent logins, flags
+/// and support notes (Admin only)
+/// GET /admin/users/:address
+pub async fn get_user(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(address): Path<String>,
+) -> Result<Json<AccountOverview>, AppError> {
+    let overview = account_admin::overview(&state.db.pool, &auth_user.address, &parse_address(&address)?)
+        .await
+        .map_err(admin_error)?;
+    Ok(Json(overview))
+}
+
+/// Freeze a user's account; it keeps read access but every state-changing
+/// request is refused (Admin only)
+/// POST /admin/users/:address/freeze
+pub async fn freeze_user(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(address): Path<String>,
+    ValidJson(req): ValidJson<ReasonRequest>,
+) -> Result<Json<FreezeResponse>, AppError> {
+    let address = parse_address(&address)?;
+    account_admin::freeze(&state.db.pool, &auth_user.address, &address, &req.reason)
+        .await
+        .map_err(admin_error)?;
+    tracing::warn!("Account {} frozen by {}: {}", address, auth_user.address, req.reason);
+    Ok(Json(FreezeResponse { address, frozen: true }))
+}
+
+/// Lift the freeze of a user's account (Admin only)
+/// POST /admin/users/:address/unfreeze
+pub async fn unfreeze_user(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(address): Path<String>,
+    ValidJson(req): ValidJson<ReasonRequest>,
+) -> Result<Json<FreezeResponse>, AppError> {
+    let address = parse_address(&address)?;
+    account_admin::unfreeze(&state.db.pool, &auth_user.address, &address, &req.reason)
+        .await
+        .map_err(admin_error)?;
+    tracing::info!("Account {} unfrozen by {}: {}", address, auth_user.address, req.reason);
+    Ok(Json(FreezeResponse { address, frozen: false }))
+}
+
+/// Force-cancel all open orders of a user (Admin only)
+/// POST /admin/users/:address/cancel-orders
+pub async fn cancel_user_orders(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(address): Path<String>,
+    ValidJson(req): ValidJson<ReasonRequest>,
+) -> Result<Json<CancelOrdersResponse>, AppError> {
+    let address = parse_address(&address)?;
+    if req.reason.trim().is_empty() {
+        return Err(admin_error(AccountAdminError::MissingReason));
+    }
+
+    let mut conn = state
+        .db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| admin_error(e.into()))?;
+    let orders = account_admin::open_orders(&mut conn, &address)
+        .await
+        .map_err(|e| admin_error(e.into()))?;
+
+    let (mut cancelled, mut skipped) = (Vec::new(), Vec::new());
+    for order in orders {
+        let source = OrderSource::parse(&order.source).unwrap_or(OrderSource::Api);
+        match state.order_gateway.cancel(source, &address, order.id).await {
+            Ok(true) => cancelled.push(order.id),
+            Ok(false) => skipped.push(order.id),
+            Err(e) => {
+                tracing::error!("Admin cancel of order {} failed: {}", order.id, e);
+                skipped.push(order.id);
+            }
+        }
+    }
+
+    account_admin::audit(
+        &mut conn,
+        &auth_user.address,
+        AdminAction::CancelOrders,
+        &address,
+        serde_json::json!({
+            "reason": req.reason.trim(),
+            "cancelled": cancelled,
+            "skipped": skipped,
+        }),
+    )
+    .await
+    .map_err(|e| admin_error(e.into()))?;
+    tracing::warn!(
+        "Admin {} cancelled {} orders of {} ({} skipped)",
+        auth_user.address,
+        cancelled.len(),
+        address,
+        skipped.len()
+    );
+
+    Ok(Json(CancelOrdersResponse { cancelled, skipped }))
+}
+
+/// Set a user's market data tier, e.g. grant a market maker the orderbook
+/// delta feed; applies to the user's next WebSocket subscriptions (Admin only)
+/// PUT /admin/users/:address/market-data-tier
+pub async fn set_market_data_tier(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(address): Path<String>,
+    ValidJson(req): ValidJson<MarketDataTierRequest>,
+) -> Result<Json<MarketDataTierResponse>, AppError> {
+    let address = parse_address(&address)?;
+    let previous = account_admin::set_market_data_tier(&state.db.pool, &auth_user.address, &address, req.tier, &req.reason)
+        .await
+        .map_err(admin_error)?;
+    tracing::info!(
+        "Market data tier of {} set to {} by {}: {}",
+        address,
+        req.tier.as_str(),
+        auth_user.address,
+        req.reason
+    );
+    Ok(Json(MarketDataTierResponse { address, tier: req.tier, previous }))
+}
+
+/// Set a user's own daily API quotas (Admin only)
+/// PUT /admin/users/:address/api-quota
+pub async fn set_api_quota(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(address): Path<String>,
+    ValidJson(req): ValidJson<ApiQuotaRequest>,
+) -> Result<Json<ApiQuotaResponse>, AppError> {
+    let address = parse_address(&address)?;
+    let (requests, bytes) = account_admin::set_api_quota(
+        &state.db.pool,
+        &auth_user.address,
+        &address,
+        req.requests,
+        req.bytes,
+        &req.reason,
+    )
+    .await
+    .map_err(admin_error)?;
+    state.api_usage.set_quota(&address, req.requests, req.bytes);
+    tracing::info!(
+        "API quota of {} set to {:?} requests / {:?} bytes by {}: {}",
+        address,
+        req.requests,
+        req.bytes,
+        auth_user.address,
+        req.reason
+    );
+    Ok(Json(ApiQuotaResponse {
+        address,
+        quota: ApiQuotaLimits {
+            requests: req.requests,
+            bytes: req.bytes,
+        },
+        previous: ApiQuotaLimits { requests, bytes },
+    }))
+}
+
+/// Attach a support note to a user's account (Admin only)
+/// POST /admin/users/:address/notes
+pub async fn add_note(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(address): Path<String>,
+    ValidJson(req): ValidJson<AddNoteRequest>,
+) -> Result<Json<SupportNote>, AppError> {
+    let note = account_admin::add_note(&state.db.pool, &auth_user.address, &parse_address(&address)?, &req.note)
+        .await
+        .map_err(admin_error)?;
+    Ok(Json(note))
+}
+
+/// Admin audit trail of a user's account, newest first (Admin only)
+/// GET /admin/users/:address/audit?limit
+pub async fn get_audit_log(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    ValidQuery(query): ValidQuery<AuditQuery>,
+) -> Result<Json<AuditLogResponse>, AppError> {
+    let address = parse_address(&address)?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let entries = account_admin::audit_log(&state.db.pool, &address, limit)
+        .await
+        .map_err(|e| admin_error(e.into()))?;
+    Ok(Json(AuditLogResponse { address, entries }))
+}
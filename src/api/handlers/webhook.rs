@@ -0,0 +1,436 @@
+//! Webhook Subscription API Handlers
+//!
+//! Users register endpoints for their own events; admins can register
+//! platform-wide endpoints that receive every user's events. Delivery logs
+//! can be inspected and individual deliveries replayed.
+
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson, ValidQuery};
+use crate::auth::middleware::AuthUser;
+use crate::services::webhook::{generate_secret, WebhookEventType};
+use crate::AppState;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateWebhookRequest {
+    #[validate(url)]
+    pub url: String,
+    /// Event types to receive (e.g. "order.filled"); empty = all
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct DeliveryQuery {
+    pub status: Option<String>,
+    #[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]
+    pub limit: Option<i64>,
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct WebhookInfo {
+    pub id: Uuid,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub description: Option<String>,
+    pub is_active: bool,
+    /// Platform-wide subscription (admin)
+    pub global: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: WebhookInfo,
+    /// Signing secret; only returned once at creation
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhooksResponse {
+    pub webhooks: Vec<WebhookInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeliveryInfo {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub replay_of: Option<Uuid>,
+    pub next_attempt_at: i64,
+    pub delivered_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeliveriesResponse {
+    pub deliveries: Vec<DeliveryInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayResponse {
+    pub delivery_id: Uuid,
+    pub replay_of: Uuid,
+}
+
+/// (id, owner_address, url, event_types, description, is_active, created_at)
+type WebhookRow = (Uuid, Option<String>, String, Vec<String>, Option<String>, bool, DateTime<Utc>);
+
+#[derive(sqlx::FromRow)]
+struct DeliveryRow {
+    id: Uuid,
+    subscription_id: Uuid,
+    event_id: Uuid,
+    event_type: String,
+    status: String,
+    attempts: i32,
+    last_status_code: Option<i32>,
+    last_error: Option<String>,
+    replay_of: Option<Uuid>,
+    next_attempt_at: DateTime<Utc>,
+    delivered_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<WebhookRow> for WebhookInfo {
+    fn from((id, owner, url, event_types, description, is_active, created_at): WebhookRow) -> Self {
+        Self {
+            id,
+            url,
+            event_types,
+            description,
+            is_active,
+            global: owner.is_none(),
+            created_at: created_at.timestamp_millis(),
+        }
+    }
+}
+
+impl From<DeliveryRow> for DeliveryInfo {
+    fn from(row: DeliveryRow) -> Self {
+        Self {
+            id: row.id,
+            subscription_id: row.subscription_id,
+            event_id: row.event_id,
+            event_type: row.event_type,
+            status: row.status,
+            attempts: row.attempts,
+            last_status_code: row.last_status_code,
+            last_error: row.last_error,
+            replay_of: row.replay_of,
+            next_attempt_at: row.next_attempt_at.timestamp_millis(),
+            delivered_at: row.delivered_at.map(|t| t.timestamp_millis()),
+            created_at: row.created_at.timestamp_millis(),
+        }
+    }
+}
+
+const MAX_WEBHOOKS_PER_USER: i64 = 10;
+
+fn validate_request(req: &CreateWebhookRequest, allow_http: bool) -> Result<(), AppError> {
+    let url = reqwest::Url::parse(&req.url)
+        .map_err(|_| AppError::bad_request("INVALID_URL", "Invalid webhook URL"))?;
+    let scheme_ok = url.scheme() == "https" || (allow_http && url.scheme() == "http");
+    if !scheme_ok || url.host_str().is_none() {
+        return Err(AppError::bad_request(
+            "INVALID_URL",
+            "Webhook URL must use https",
+        ));
+    }
+    if let Some(bad) = req.event_types.iter().find(|t| WebhookEventType::parse(t).is_none()) {
+        return Err(AppError::bad_request(
+            "INVALID_EVENT_TYPE",
+            format!("Unknown event type: {}", bad),
+        ));
+    }
+    Ok(())
+}
+
+async fn insert_subscription(
+    state: &AppState,
+    owner: Option<&str>,
+    req: CreateWebhookRequest,
+) -> Result<CreateWebhookResponse, AppError> {
+    let secret = generate_secret();
+    let row: WebhookRow = sqlx::query_as(
+        r#"
+        INSERT INTO webhook_subscriptions (owner_address, url, secret, event_types, description)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, owner_address, url, event_types, description, is_active, created_at
+        "#,
+    )
+    .bind(owner)
+    .bind(&req.url)
+    .bind(&secret)
+    .bind(&req.event_types)
+    .bind(&req.description)
+    .fetch_one(&state.db.pool)
+    .await?;
+
+    Ok(CreateWebhookResponse {
+        webhook: row.into(),
+        secret,
+    })
+}
+
+// ============================================================================
+// User Handlers
+// ============================================================================
+
+/// Register a webhook for the current user's events
+/// POST /webhooks
+pub async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<CreateWebhookRequest>,
+) -> Result<Json<CreateWebhookResponse>, AppError> {
+    let user_address = auth_user.address.clone();
+    validate_request(&req, state.config.environment == "development")?;
+
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM webhook_subscriptions WHERE owner_address = $1 AND is_active",
+    )
+    .bind(&user_address)
+    .fetch_one(&state.db.pool)
+    .await?;
+
+    if count >= MAX_WEBHOOKS_PER_USER {
+        return Err(AppError::bad_request(
+            "WEBHOOK_LIMIT",
+            format!("At most {} active webhooks per user", MAX_WEBHOOKS_PER_USER),
+        ));
+    }
+
+    let response = insert_subscription(&state, Some(user_address.as_str()), req).await?;
+    tracing::info!("Webhook {} registered by {}", response.webhook.id, user_address);
+    Ok(Json(response))
+}
+
+/// List the current user's webhooks
+/// GET /webhooks
+pub async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<WebhooksResponse>, AppError> {
+    let rows: Vec<WebhookRow> = sqlx::query_as(
+        r#"
+        SELECT id, owner_address, url, event_types, description, is_active, created_at
+        FROM webhook_subscriptions
+        WHERE owner_address = $1
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(&auth_user.address)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(Json(WebhooksResponse {
+        webhooks: rows.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Delete (deactivate) one of the current user's webhooks
+/// DELETE /webhooks/:webhook_id
+pub async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let result = sqlx::query(
+        "UPDATE webhook_subscriptions SET is_active = FALSE WHERE id = $1 AND owner_address = $2",
+    )
+    .bind(webhook_id)
+    .bind(&auth_user.address)
+    .execute(&state.db.pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("WEBHOOK_NOT_FOUND", "Webhook not found"));
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Webhook deleted"
+    })))
+}
+
+/// Delivery log for one of the current user's webhooks
+/// GET /webhooks/:webhook_id/deliveries
+pub async fn get_deliveries(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(webhook_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<DeliveryQuery>,
+) -> Result<Json<DeliveriesResponse>, AppError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+    let rows: Vec<DeliveryRow> = sqlx::query_as(
+        r#"
+        SELECT d.id, d.subscription_id, d.event_id, d.event_type, d.status, d.attempts,
+               d.last_status_code, d.last_error, d.replay_of, d.next_attempt_at,
+               d.delivered_at, d.created_at
+        FROM webhook_deliveries d
+        JOIN webhook_subscriptions s ON s.id = d.subscription_id
+        WHERE d.subscription_id = $1 AND s.owner_address = $2
+          AND ($3::text IS NULL OR d.status = $3)
+        ORDER BY d.created_at DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(webhook_id)
+    .bind(&auth_user.address)
+    .bind(&query.status)
+    .bind(limit)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(Json(DeliveriesResponse {
+        deliveries: rows.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Replay a delivery of one of the current user's webhooks
+/// POST /webhooks/deliveries/:delivery_id/replay
+pub async fn replay_delivery(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(delivery_id): Path<Uuid>,
+) -> Result<Json<ReplayResponse>, AppError> {
+    let owned: Option<(Uuid,)> = sqlx::query_as(
+        r#"
+        SELECT d.id FROM webhook_deliveries d
+        JOIN webhook_subscriptions s ON s.id = d.subscription_id
+        WHERE d.id = $1 AND s.owner_address = $2 AND s.is_active
+        "#,
+    )
+    .bind(delivery_id)
+    .bind(&auth_user.address)
+    .fetch_optional(&state.db.pool)
+    .await?;
+
+    if owned.is_none() {
+        return Err(AppError::not_found("DELIVERY_NOT_FOUND", "Delivery not found"));
+    }
+
+    let new_id = state.webhook_service.replay(delivery_id).await?;
+    Ok(Json(ReplayResponse {
+        delivery_id: new_id,
+        replay_of: delivery_id,
+    }))
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+/// Register a platform-wide webhook receiving all users' events (Admin only)
+/// POST /admin/webhooks
+pub async fn admin_create_webhook(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<CreateWebhookRequest>,
+) -> Result<Json<CreateWebhookResponse>, AppError> {
+    validate_request(&req, state.config.environment == "development")?;
+    let response = insert_subscription(&state, None, req).await?;
+    tracing::info!(
+        "Global webhook {} registered by admin {}",
+        response.webhook.id,
+        auth_user.address
+    );
+    Ok(Json(response))
+}
+
+/// List all webhooks (Admin only)
+/// GET /admin/webhooks
+pub async fn admin_list_webhooks(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<WebhooksResponse>, AppError> {
+    let rows: Vec<WebhookRow> = sqlx::query_as(
+        r#"
+        SELECT id, owner_address, url, event_types, description, is_active, created_at
+        FROM webhook_subscriptions
+        ORDER BY created_at DESC
+        LIMIT 500
+        "#,
+    )
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(Json(WebhooksResponse {
+        webhooks: rows.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Delivery log across all webhooks, e.g. `?status=failed` (Admin only)
+/// GET /admin/webhooks/deliveries
+pub async fn admin_get_deliveries(
+    State(state): State<Arc<AppState>>,
+    ValidQuery(query): ValidQuery<DeliveryQuery>,
+) -> Result<Json<DeliveriesResponse>, AppError> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+
+    let rows: Vec<DeliveryRow> = sqlx::query_as(
+        r#"
+        SELECT id, subscription_id, event_id, event_type, status, attempts,
+               last_status_code, last_error, replay_of, next_attempt_at,
+               delivered_at, created_at
+        FROM webhook_deliveries
+        WHERE ($1::text IS NULL OR status = $1)
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(&query.status)
+    .bind(limit)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(Json(DeliveriesResponse {
+        deliveries: rows.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Replay any delivery (Admin only)
+/// POST /admin/webhooks/deliveries/:delivery_id/replay
+pub async fn admin_replay_delivery(
+    State(state): State<Arc<AppState>>,
+    Path(delivery_id): Path<Uuid>,
+) -> Result<Json<ReplayResponse>, AppError> {
+    let new_id = state
+        .webhook_service
+        .replay(delivery_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => AppError::not_found("DELIVERY_NOT_FOUND", "Delivery not found"),
+            e => e.into(),
+        })?;
+
+    Ok(Json(ReplayResponse {
+        delivery_id: new_id,
+        replay_of: delivery_id,
+    }))
+}
@@ -0,0 +1,299 @@
+//! Public Widget API Handlers
+//!
+//! Read-only endpoints for third-party embeds, served under `/public/v1`.
+//! Responses carry only public market data, are cached in-process for
+//! `PUBLIC_API_CACHE_TTL_SECS` and advertise the same TTL to CDNs and
+//! browsers via `Cache-Control`.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::validation::ValidQuery;
+use crate::services::analytics::AnalyticsInterval;
+use crate::AppState;
+
+/// Maximum levels per side in the mini orderbook
+const MAX_MINI_DEPTH: usize = 10;
+
+/// Maximum sparkline points
+const MAX_SPARKLINE_POINTS: i64 = 168;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MiniOrderbookQuery {
+    /// "yes" (default) or "no"
+    pub share_type: Option<String>,
+    /// Levels per side (default 5, max 10)
+    #[validate(range(min = 1))]
+    pub depth: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SparklineQuery {
+    /// "1h" (default) or "1d"
+    pub interval: Option<String>,
+    /// Number of points (default 24, max 168)
+    #[validate(range(min = 1))]
+    pub points: Option<i64>,
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct WidgetOutcome {
+    pub id: Uuid,
+    pub name: String,
+    pub share_type: String,
+    pub probability: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarketCard {
+    pub id: Uuid,
+    pub question: String,
+    pub category: String,
+    pub status: String,
+    pub end_time: Option<DateTime<Utc>>,
+    pub volume_24h: Decimal,
+    pub total_volume: Decimal,
+    pub outcomes: Vec<WidgetOutcome>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MiniOrderbook {
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: String,
+    /// `[price, size]` pairs, best first
+    pub bids: Vec<[String; 2]>,
+    pub asks: Vec<[String; 2]>,
+    pub best_bid: Option<String>,
+    pub best_ask: Option<String>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SparklinePoint {
+    /// Unix seconds
+    pub t: i64,
+    /// YES probability
+    pub p: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Sparkline {
+    pub market_id: Uuid,
+    pub interval: String,
+    pub points: Vec<SparklinePoint>,
+}
+
+/// (question, category, status, end_time, volume_24h, total_volume)
+type CardRow = (String, String, String, Option<DateTime<Utc>>, Decimal, Decimal);
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, AppError> {
+    serde_json::to_value(value).map_err(|e| {
+        tracing::error!("Failed to serialize widget response: {}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", "Serialization error")
+    })
+}
+
+/// JSON response with public cache headers matching the in-process TTL
+fn cached_json(state: &AppState, value: Value) -> Response {
+    let max_age = state.widget_cache.ttl().as_secs();
+    (
+        [(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}, stale-while-revalidate={}", max_age, max_age * 3),
+        )],
+        Json(value),
+    )
+        .into_response()
+}
+
+async fn market_outcomes(state: &AppState, market_id: Uuid) -> Result<Vec<WidgetOutcome>, AppError> {
+    let rows: Vec<(Uuid, String, String, Decimal)> = sqlx::query_as(
+        "SELECT id, name, share_type::text, probability FROM outcomes WHERE market_id = $1 ORDER BY share_type",
+    )
+    .bind(market_id)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, name, share_type, probability)| WidgetOutcome {
+            id,
+            name,
+            share_type,
+            probability,
+        })
+        .collect())
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Market card data (question, status, volume, outcome probabilities)
+/// GET /public/v1/markets/:market_id/card
+pub async fn get_market_card(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let key = format!("card:{}", market_id);
+    let value = state
+        .widget_cache
+        .get_or_try_insert(key, || async {
+            let row: Option<CardRow> = sqlx::query_as(
+                r#"
+                SELECT question, category, status::text, end_time, volume_24h, total_volume
+                FROM markets
+                WHERE id = $1
+                "#,
+            )
+            .bind(market_id)
+            .fetch_optional(&state.db.pool)
+            .await?;
+            let (question, category, status, end_time, volume_24h, total_volume) =
+                row.ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+
+            to_value(&MarketCard {
+                id: market_id,
+                question,
+                category,
+                status,
+                end_time,
+                volume_24h,
+                total_volume,
+                outcomes: market_outcomes(&state, market_id).await?,
+            })
+        })
+        .await?;
+
+    Ok(cached_json(&state, value))
+}
+
+/// Top-of-book snapshot for one share type
+/// GET /public/v1/markets/:market_id/orderbook
+pub async fn get_mini_orderbook(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<MiniOrderbookQuery>,
+) -> Result<Response, AppError> {
+    let share_type = match query.share_type.as_deref().unwrap_or("yes") {
+        "yes" => "yes",
+        "no" => "no",
+        _ => return Err(AppError::bad_request("INVALID_SHARE_TYPE", "share_type must be yes or no")),
+    };
+    let depth = query.depth.unwrap_or(5).clamp(1, MAX_MINI_DEPTH);
+
+    let key = format!("orderbook:{}:{}:{}", market_id, share_type, depth);
+    let value = state
+        .widget_cache
+        .get_or_try_insert(key, || async {
+            let outcome_id: Uuid = sqlx::query_scalar(
+                "SELECT id FROM outcomes WHERE market_id = $1 AND share_type::text = $2",
+            )
+            .bind(market_id)
+            .bind(share_type)
+            .fetch_optional(&state.db.pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+
+            let symbol = format!("{}:{}:{}", market_id, outcome_id, share_type);
+            let (bids, asks, timestamp) = match state.matching_engine.get_orderbook(&symbol, depth) {
+                Ok(book) => (book.bids, book.asks, book.timestamp),
+                // No orders yet: the book has not been created in the engine
+                Err(_) => (Vec::new(), Vec::new(), Utc::now().timestamp_millis()),
+            };
+
+            to_value(&MiniOrderbook {
+                market_id,
+                outcome_id,
+                share_type: share_type.to_string(),
+                best_bid: bids.first().map(|[price, _]| price.clone()),
+                best_ask: asks.first().map(|[price, _]| price.clone()),
+                bids,
+                asks,
+                timestamp,
+            })
+        })
+        .await?;
+
+    Ok(cached_json(&state, value))
+}
+
+/// YES probability series for sparklines
+/// GET /public/v1/markets/:market_id/sparkline
+pub async fn get_sparkline(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<SparklineQuery>,
+) -> Result<Response, AppError> {
+    let interval = AnalyticsInterval::parse(query.interval.as_deref().unwrap_or("1h"))
+        .ok_or_else(|| AppError::bad_request("INVALID_INTERVAL", "Invalid interval. Use 1h or 1d"))?;
+    let points = query.points.unwrap_or(24).clamp(2, MAX_SPARKLINE_POINTS);
+
+    let key = format!("sparkline:{}:{}:{}", market_id, interval.as_str(), points);
+    let value = state
+        .widget_cache
+        .get_or_try_insert(key, || async {
+            let outcomes = market_outcomes(&state, market_id).await?;
+            let current = outcomes
+                .iter()
+                .find(|o| o.share_type == "yes")
+                .map(|o| o.probability)
+                .ok_or_else(|| AppError::not_found("MARKET_NOT_FOUND", "Market not found"))?;
+
+            let mut rows: Vec<(DateTime<Utc>, Decimal)> = sqlx::query_as(
+                r#"
+                SELECT bucket_start, probability
+                FROM market_analytics
+                WHERE market_id = $1 AND bucket_interval = $2 AND probability IS NOT NULL
+                ORDER BY bucket_start DESC
+                LIMIT $3
+                "#,
+            )
+            .bind(market_id)
+            .bind(interval.as_str())
+            .bind(points - 1)
+            .fetch_all(&state.db.pool)
+            .await?;
+            rows.reverse();
+
+            let mut series: Vec<SparklinePoint> = rows
+                .into_iter()
+                .map(|(t, p)| SparklinePoint { t: t.timestamp(), p })
+                .collect();
+            // Close the line at the live probability
+            series.push(SparklinePoint {
+                t: Utc::now().timestamp(),
+                p: current,
+            });
+
+            to_value(&Sparkline {
+                market_id,
+                interval: interval.as_str().to_string(),
+                points: series,
+            })
+        })
+        .await?;
+
+    Ok(cached_json(&state, value))
+}
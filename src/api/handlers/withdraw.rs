@@ -13,23 +13,36 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::api::error::AppError;
+use crate::api::validation::{self, ValidJson};
 use crate::auth::middleware::AuthUser;
 use crate::blockchain::types::TxStatus;
+use crate::services::ledger::{self, LedgerEntry, LedgerEntryType};
+use crate::services::notification::NotificationKind;
+use crate::services::webhook::WebhookEventType;
+use crate::services::withdrawal_policy::{WithdrawalPolicy, WithdrawalPolicyError, WithdrawalQuote};
+use crate::services::event_bus::BusEvent;
 use crate::{AppState, BalanceUpdateEvent};
 
 // ============================================================================
 // Request Types
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct WithdrawRequest {
     pub token: String,
+    #[validate(custom = "validation::positive")]
     pub amount: Decimal,
+    /// Highest fee the user accepts, usually `total_fee` from `/withdraw/prepare`
+    #[serde(default)]
+    pub max_fee: Option<Decimal>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct ConfirmWithdrawRequest {
+    #[validate(custom = "validation::tx_hash")]
     pub tx_hash: String,
 }
 
@@ -37,20 +50,26 @@ pub struct ConfirmWithdrawRequest {
 // Response Types
 // ============================================================================
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-}
-
 #[derive(Debug, Serialize)]
 pub struct WithdrawResponse {
     pub withdraw_id: String,
     pub token: String,
     pub amount: String,
+    pub fee: String,
+    pub net_amount: String,
+    pub dust_swept: String,
     pub status: String,
     pub created_at: i64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct PrepareWithdrawResponse {
+    pub token: String,
+    pub available: Decimal,
+    #[serde(flatten)]
+    pub quote: WithdrawalQuote,
+}
+
 #[derive(Debug, Serialize)]
 pub struct WithdrawHistoryResponse {
     pub withdrawals: Vec<WithdrawHistoryRecord>,
@@ -61,32 +80,174 @@ pub struct WithdrawHistoryRecord {
     pub id: String,
     pub token: String,
     pub amount: Decimal,
+    pub fee: Decimal,
+    pub net_amount: Decimal,
     pub tx_hash: Option<String>,
     pub status: String,
     pub created_at: i64,
 }
 
+/// (id, token, amount, fee, net_amount, tx_hash, status, created_at)
+type WithdrawalRow = (Uuid, String, Decimal, Decimal, Decimal, Option<String>, String, DateTime<Utc>);
+
+// ============================================================================
+// Fee Policy Helpers
+// ============================================================================
+
+fn policy_error(e: WithdrawalPolicyError) -> AppError {
+    let code = match e {
+        WithdrawalPolicyError::InvalidAmount | WithdrawalPolicyError::TooManyDecimals(_) => "INVALID_AMOUNT",
+        WithdrawalPolicyError::BelowMinimum(_) => "BELOW_MINIMUM",
+        WithdrawalPolicyError::AmountBelowFee(_) => "AMOUNT_BELOW_FEE",
+        WithdrawalPolicyError::InsufficientBalance { .. } => "INSUFFICIENT_BALANCE",
+        WithdrawalPolicyError::FeeAboveMax { .. } => "FEE_ABOVE_MAX",
+    };
+    AppError::bad_request(code, e.to_string())
+}
+
+/// Current gas price in gwei, if the chain is reachable.
+/// Falls back to flat-fee only when no blockchain client is configured.
+async fn current_gas_price_gwei(state: &AppState) -> Option<Decimal> {
+    let client = state.blockchain_client.as_ref()?;
+    match client.get_gas_price().await {
+        Ok(wei) => wei
+            .to_string()
+            .parse::<Decimal>()
+            .ok()
+            .map(|w| w / Decimal::from(1_000_000_000u64)),
+        Err(e) => {
+            tracing::warn!("Failed to fetch gas price for withdrawal fee: {}", e);
+            None
+        }
+    }
+}
+
+/// Quote a withdrawal against the configured fee policy, refusing a fee
+/// above `max_fee`
+async fn quote_withdrawal(
+    state: &AppState,
+    amount: Decimal,
+    available: Decimal,
+    max_fee: Option<Decimal>,
+) -> Result<WithdrawalQuote, AppError> {
+    let policy = WithdrawalPolicy::from_config(&state.config);
+    let gas_price = current_gas_price_gwei(state).await;
+    policy.binding_quote(amount, available, gas_price, max_fee).map_err(policy_error)
+}
+
+/// Write the withdrawal debit and fee entries to the ledger
+async fn record_withdrawal_ledger(
+    conn: &mut sqlx::PgConnection,
+    user_address: &crate::models::Address,
+    token: &str,
+    withdraw_id: Uuid,
+    available_before: Decimal,
+    quote: &WithdrawalQuote,
+) -> Result<(), sqlx::Error> {
+    let after_payout = available_before - quote.net_amount;
+    ledger::record_entry(
+        conn,
+        &LedgerEntry {
+            user_address: user_address.as_str(),
+            token,
+            entry_type: LedgerEntryType::Withdrawal,
+            amount: -quote.net_amount,
+            balance_after: after_payout,
+            reference_id: Some(withdraw_id),
+            counterparty: None,
+        },
+    )
+    .await?;
+
+    if quote.total_fee > Decimal::ZERO {
+        ledger::record_entry(
+            conn,
+            &LedgerEntry {
+                user_address: user_address.as_str(),
+                token,
+                entry_type: LedgerEntryType::WithdrawalFee,
+                amount: -quote.total_fee,
+                balance_after: after_payout - quote.total_fee,
+                reference_id: Some(withdraw_id),
+                counterparty: None,
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Queue a withdrawal status email (subject to the user's preferences)
+async fn notify_withdrawal_status(
+    state: &AppState,
+    user_address: &crate::models::Address,
+    withdrawal_id: Uuid,
+    status: &str,
+    amount: Decimal,
+    tx_hash: Option<&str>,
+) {
+    state
+        .notification_service
+        .notify(
+            NotificationKind::WithdrawalStatus,
+            user_address.as_str(),
+            None,
+            serde_json::json!({
+                "withdrawal_id": withdrawal_id,
+                "status": status,
+                "amount": amount,
+                "symbol": state.config.collateral_symbol(),
+                "to_address": user_address,
+                "tx_hash": tx_hash.unwrap_or("-"),
+            }),
+        )
+        .await;
+}
+
 // ============================================================================
 // Handlers
 // ============================================================================
 
+/// Preview fees, minimums and dust handling for a withdrawal
+/// POST /withdraw/prepare
+pub async fn prepare_withdraw(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    ValidJson(req): ValidJson<WithdrawRequest>,
+) -> Result<Json<PrepareWithdrawResponse>, AppError> {
+    let user_address = auth_user.address.clone();
+
+    let balance: Option<(Decimal,)> = sqlx::query_as(
+        "SELECT available FROM balances WHERE user_address = $1 AND token = $2",
+    )
+    .bind(&user_address)
+    .bind(&req.token)
+    .fetch_optional(&state.db.pool)
+    .await?;
+
+    let available = balance.map(|(b,)| b).unwrap_or(Decimal::ZERO);
+    let quote = quote_withdrawal(&state, req.amount, available, None).await?;
+
+    Ok(Json(PrepareWithdrawResponse {
+        token: req.token,
+        available,
+        quote,
+    }))
+}
+
 /// Request a withdrawal
 /// POST /withdraw
 pub async fn request_withdraw(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Json(req): Json<WithdrawRequest>,
-) -> Result<Json<WithdrawResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+    ValidJson(req): ValidJson<WithdrawRequest>,
+) -> Result<Json<WithdrawResponse>, AppError> {
+    let user_address = auth_user.address.clone();
 
     // Validate amount
     if req.amount <= Decimal::ZERO {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Amount must be positive".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_AMOUNT", "Amount must be positive"));
     }
 
     // Check user balance
@@ -96,61 +257,38 @@ pub async fn request_withdraw(
     .bind(&user_address)
     .bind(&req.token)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to check balance: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to check balance".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let available = balance.map(|(b,)| b).unwrap_or(Decimal::ZERO);
     if available < req.amount {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Insufficient balance: {} < {}", available, req.amount),
-            }),
-        ));
+        return Err(AppError::bad_request("INSUFFICIENT_BALANCE", format!("Insufficient balance: {} < {}", available, req.amount)));
     }
 
+    // Apply fee policy (minimum, flat + gas-linked fee, dust sweep)
+    let quote = quote_withdrawal(&state, req.amount, available, req.max_fee).await?;
+    let gross_amount = quote.gross_amount;
+
     // Create withdrawal record and freeze funds in a transaction
     let withdraw_id = Uuid::new_v4();
-    let mut tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to process withdrawal".to_string(),
-            }),
-        )
-    })?;
+    let mut tx = state.db.pool.begin().await?;
 
-    // Freeze funds
-    sqlx::query(
+    // Freeze funds (gross amount, fee is taken out of it at payout)
+    let frozen_rows = sqlx::query(
         r#"
         UPDATE balances
         SET available = available - $1, frozen = frozen + $1
         WHERE user_address = $2 AND token = $3 AND available >= $1
         "#,
     )
-    .bind(req.amount)
+    .bind(gross_amount)
     .bind(&user_address)
     .bind(&req.token)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to freeze funds: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to freeze funds".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    if frozen_rows.rows_affected() == 0 {
+        return Err(AppError::bad_request("INSUFFICIENT_BALANCE", format!("Insufficient balance: {} < {}", available, gross_amount)));
+    }
 
     // Create withdrawal record
     let created_at = Utc::now();
@@ -159,63 +297,63 @@ pub async fn request_withdraw(
 
     sqlx::query(
         r#"
-        INSERT INTO withdrawals (id, user_address, token, amount, to_address, nonce, expiry, status, created_at)
-        VALUES ($1, $2, $3, $4, $2, $5, $6, 'pending', $7)
+        INSERT INTO withdrawals (id, user_address, token, amount, fee, gas_fee, dust_swept, net_amount,
+                                 to_address, nonce, expiry, status, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $2, $9, $10, 'pending', $11)
         "#,
     )
     .bind(withdraw_id)
     .bind(&user_address)
     .bind(&req.token)
-    .bind(req.amount)
+    .bind(gross_amount)
+    .bind(quote.total_fee)
+    .bind(quote.gas_fee)
+    .bind(quote.dust_swept)
+    .bind(quote.net_amount)
     .bind(nonce)
     .bind(expiry)
     .bind(created_at)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to create withdrawal: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to create withdrawal".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
-    tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to process withdrawal".to_string(),
-            }),
-        )
-    })?;
+    record_withdrawal_ledger(&mut tx, &user_address, &req.token, withdraw_id, available, &quote)
+        .await?;
+
+    tx.commit().await?;
 
     tracing::info!(
-        "Withdrawal requested - user: {}, token: {}, amount: {}, id: {}",
+        "Withdrawal requested - user: {}, token: {}, amount: {}, fee: {}, id: {}",
         user_address,
         req.token,
-        req.amount,
+        gross_amount,
+        quote.total_fee,
         withdraw_id
     );
 
+    notify_withdrawal_status(&state, &user_address, withdraw_id, "requested", gross_amount, None).await;
+
     // Broadcast balance update (funds frozen)
-    let new_available = available - req.amount;
-    let new_frozen = req.amount; // This is the newly frozen amount, not total frozen
-    let _ = state.balance_update_sender.send(BalanceUpdateEvent {
-        user_address: user_address.clone(),
-        token: req.token.clone(),
-        available: new_available.to_string(),
-        frozen: new_frozen.to_string(),
-        total: available.to_string(), // Total unchanged
-        event_type: "freeze".to_string(),
-    });
+    let new_available = available - gross_amount;
+    let new_frozen = gross_amount; // This is the newly frozen amount, not total frozen
+    state
+        .event_bus
+        .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+            user_address: user_address.clone().into(),
+            token: req.token.clone(),
+            available: new_available.to_string(),
+            frozen: new_frozen.to_string(),
+            total: available.to_string(), // Total unchanged
+            event_type: "freeze".to_string(),
+        }))
+        .await;
 
     Ok(Json(WithdrawResponse {
         withdraw_id: withdraw_id.to_string(),
         token: req.token,
-        amount: req.amount.to_string(),
+        amount: gross_amount.to_string(),
+        fee: quote.total_fee.to_string(),
+        net_amount: quote.net_amount.to_string(),
+        dust_swept: quote.dust_swept.to_string(),
         status: "pending".to_string(),
         created_at: created_at.timestamp_millis(),
     }))
@@ -226,12 +364,12 @@ pub async fn request_withdraw(
 pub async fn get_history(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-) -> Result<Json<WithdrawHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+) -> Result<Json<WithdrawHistoryResponse>, AppError> {
+    let user_address = auth_user.address.clone();
 
-    let rows: Vec<(Uuid, String, Decimal, Option<String>, String, DateTime<Utc>)> = sqlx::query_as(
+    let rows: Vec<WithdrawalRow> = sqlx::query_as(
         r#"
-        SELECT id, token, amount, tx_hash, status::text, created_at
+        SELECT id, token, amount, fee, COALESCE(net_amount, amount), tx_hash, status::text, created_at
         FROM withdrawals
         WHERE user_address = $1
         ORDER BY created_at DESC
@@ -240,23 +378,16 @@ pub async fn get_history(
     )
     .bind(&user_address)
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch withdrawals: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch withdrawal history".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let withdrawals: Vec<WithdrawHistoryRecord> = rows
         .into_iter()
-        .map(|(id, token, amount, tx_hash, status, created_at)| WithdrawHistoryRecord {
+        .map(|(id, token, amount, fee, net_amount, tx_hash, status, created_at)| WithdrawHistoryRecord {
             id: id.to_string(),
             token,
             amount,
+            fee,
+            net_amount,
             tx_hash,
             status,
             created_at: created_at.timestamp_millis(),
@@ -272,13 +403,13 @@ pub async fn get_withdrawal(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Path(withdrawal_id): Path<Uuid>,
-) -> Result<Json<WithdrawHistoryRecord>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+) -> Result<Json<WithdrawHistoryRecord>, AppError> {
+    let user_address = auth_user.address.clone();
 
-    let row: Option<(Uuid, String, Decimal, Option<String>, String, DateTime<Utc>)> =
+    let row: Option<WithdrawalRow> =
         sqlx::query_as(
             r#"
-        SELECT id, token, amount, tx_hash, status::text, created_at
+        SELECT id, token, amount, fee, COALESCE(net_amount, amount), tx_hash, status::text, created_at
         FROM withdrawals
         WHERE id = $1 AND user_address = $2
         "#,
@@ -286,34 +417,22 @@ pub async fn get_withdrawal(
         .bind(withdrawal_id)
         .bind(&user_address)
         .fetch_optional(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch withdrawal: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to fetch withdrawal".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     match row {
-        Some((id, token, amount, tx_hash, status, created_at)) => {
+        Some((id, token, amount, fee, net_amount, tx_hash, status, created_at)) => {
             Ok(Json(WithdrawHistoryRecord {
                 id: id.to_string(),
                 token,
                 amount,
+                fee,
+                net_amount,
                 tx_hash,
                 status,
                 created_at: created_at.timestamp_millis(),
             }))
         }
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Withdrawal not found".to_string(),
-            }),
-        )),
+        None => Err(AppError::not_found("WITHDRAWAL_NOT_FOUND", "Withdrawal not found")),
     }
 }
 
@@ -323,8 +442,8 @@ pub async fn cancel_withdraw(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Path(withdrawal_id): Path<Uuid>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_address = auth_user.address.clone();
 
     // Get withdrawal info
     let withdrawal: Option<(String, Decimal, String)> = sqlx::query_as(
@@ -333,93 +452,54 @@ pub async fn cancel_withdraw(
     .bind(withdrawal_id)
     .bind(&user_address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch withdrawal: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch withdrawal".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
-    let (token, amount, status) = withdrawal.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Withdrawal not found".to_string(),
-            }),
-        )
-    })?;
+    let (token, amount, status) = withdrawal.ok_or_else(|| AppError::not_found("WITHDRAWAL_NOT_FOUND", "Withdrawal not found"))?;
 
     if status != "pending" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Cannot cancel withdrawal with status: {}", status),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_STATUS", format!("Cannot cancel withdrawal with status: {}", status)));
     }
 
     // Unfreeze funds and cancel withdrawal
-    let mut tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to cancel withdrawal".to_string(),
-            }),
-        )
-    })?;
+    let mut tx = state.db.pool.begin().await?;
 
     // Unfreeze funds
-    sqlx::query(
+    let available_after: Decimal = sqlx::query_scalar(
         r#"
         UPDATE balances
         SET available = available + $1, frozen = frozen - $1
         WHERE user_address = $2 AND token = $3
+        RETURNING available
         "#,
     )
     .bind(amount)
     .bind(&user_address)
     .bind(&token)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to unfreeze funds: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to unfreeze funds".to_string(),
-            }),
-        )
-    })?;
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Reverse the withdrawal and fee ledger entries in one credit
+    ledger::record_entry(
+        &mut tx,
+        &LedgerEntry {
+            user_address: user_address.as_str(),
+            token: &token,
+            entry_type: LedgerEntryType::WithdrawalReversal,
+            amount,
+            balance_after: available_after,
+            reference_id: Some(withdrawal_id),
+            counterparty: None,
+        },
+    )
+    .await?;
 
     // Update withdrawal status
     sqlx::query("UPDATE withdrawals SET status = 'cancelled' WHERE id = $1")
         .bind(withdrawal_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to cancel withdrawal: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to cancel withdrawal".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
-    tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to cancel withdrawal".to_string(),
-            }),
-        )
-    })?;
+    tx.commit().await?;
 
     tracing::info!(
         "Withdrawal cancelled - user: {}, id: {}",
@@ -427,15 +507,20 @@ pub async fn cancel_withdraw(
         withdrawal_id
     );
 
+    notify_withdrawal_status(&state, &user_address, withdrawal_id, "cancelled", amount, None).await;
+
     // Broadcast balance update (funds unfrozen)
-    let _ = state.balance_update_sender.send(BalanceUpdateEvent {
-        user_address: user_address.clone(),
-        token: token.clone(),
-        available: amount.to_string(), // Amount returned to available
-        frozen: (-amount).to_string(), // Negative indicates decrease in frozen
-        total: "0".to_string(), // Total unchanged (delta is 0)
-        event_type: "unfreeze".to_string(),
-    });
+    state
+        .event_bus
+        .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+            user_address: user_address.clone().into(),
+            token: token.clone(),
+            available: amount.to_string(), // Amount returned to available
+            frozen: (-amount).to_string(), // Negative indicates decrease in frozen
+            total: "0".to_string(), // Total unchanged (delta is 0)
+            event_type: "unfreeze".to_string(),
+        }))
+        .await;
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -449,9 +534,9 @@ pub async fn confirm_withdraw(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Path(withdrawal_id): Path<Uuid>,
-    Json(req): Json<ConfirmWithdrawRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+    ValidJson(req): ValidJson<ConfirmWithdrawRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_address = auth_user.address.clone();
 
     // Get withdrawal info
     let withdrawal: Option<(String, Decimal, String)> = sqlx::query_as(
@@ -460,45 +545,16 @@ pub async fn confirm_withdraw(
     .bind(withdrawal_id)
     .bind(&user_address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch withdrawal: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch withdrawal".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
-    let (token, amount, status) = withdrawal.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Withdrawal not found".to_string(),
-            }),
-        )
-    })?;
+    let (token, amount, status) = withdrawal.ok_or_else(|| AppError::not_found("WITHDRAWAL_NOT_FOUND", "Withdrawal not found"))?;
 
     if status != "pending" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Cannot confirm withdrawal with status: {}", status),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_STATUS", format!("Cannot confirm withdrawal with status: {}", status)));
     }
 
     // Update withdrawal with tx_hash and deduct frozen balance
-    let mut tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to confirm withdrawal".to_string(),
-            }),
-        )
-    })?;
+    let mut tx = state.db.pool.begin().await?;
 
     // Deduct frozen balance
     sqlx::query(
@@ -512,42 +568,16 @@ pub async fn confirm_withdraw(
     .bind(&user_address)
     .bind(&token)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to deduct frozen balance: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to deduct frozen balance".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Update withdrawal status
     sqlx::query("UPDATE withdrawals SET status = 'completed', tx_hash = $1 WHERE id = $2")
         .bind(&req.tx_hash)
         .bind(withdrawal_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to confirm withdrawal: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to confirm withdrawal".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
-    tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to confirm withdrawal".to_string(),
-            }),
-        )
-    })?;
+    tx.commit().await?;
 
     tracing::info!(
         "Withdrawal confirmed - user: {}, id: {}, tx: {}",
@@ -556,6 +586,22 @@ pub async fn confirm_withdraw(
         req.tx_hash
     );
 
+    state
+        .webhook_service
+        .dispatch(
+            WebhookEventType::WithdrawalCompleted,
+            Some(user_address.as_str()),
+            serde_json::json!({
+                "user_address": user_address,
+                "withdrawal_id": withdrawal_id,
+                "token": token,
+                "amount": amount,
+                "tx_hash": req.tx_hash,
+            }),
+        )
+        .await;
+    notify_withdrawal_status(&state, &user_address, withdrawal_id, "completed", amount, Some(&req.tx_hash)).await;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "message": "Withdrawal confirmed"
@@ -571,6 +617,7 @@ pub struct ProcessWithdrawResponse {
     pub withdraw_id: String,
     pub tx_hash: String,
     pub amount: Decimal,
+    pub net_amount: Decimal,
     pub status: String,
     pub new_balance: Decimal,
 }
@@ -582,102 +629,50 @@ pub async fn process_withdraw(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
     Path(withdrawal_id): Path<Uuid>,
-) -> Result<Json<ProcessWithdrawResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_address = auth_user.address.to_lowercase();
+) -> Result<Json<ProcessWithdrawResponse>, AppError> {
+    let user_address = auth_user.address.clone();
 
     // Get blockchain client
-    let blockchain_client = state.blockchain_client.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "Blockchain client not available".to_string(),
-            }),
-        )
-    })?;
+    let blockchain_client = state.blockchain_client.as_ref().ok_or(AppError::BlockchainUnavailable)?;
 
     // Get withdrawal info
-    let withdrawal: Option<(String, Decimal, String, String)> = sqlx::query_as(
-        "SELECT token, amount, status::text, to_address FROM withdrawals WHERE id = $1 AND user_address = $2",
+    let withdrawal: Option<(String, Decimal, Decimal, String, String)> = sqlx::query_as(
+        "SELECT token, amount, COALESCE(net_amount, amount), status::text, to_address FROM withdrawals WHERE id = $1 AND user_address = $2",
     )
     .bind(withdrawal_id)
     .bind(&user_address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch withdrawal: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to fetch withdrawal".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
-    let (token, amount, status, to_address) = withdrawal.ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Withdrawal not found".to_string(),
-            }),
-        )
-    })?;
+    let (token, amount, net_amount, status, to_address) =
+        withdrawal.ok_or_else(|| AppError::not_found("WITHDRAWAL_NOT_FOUND", "Withdrawal not found"))?;
 
     if status != "pending" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Cannot process withdrawal with status: {}", status),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_STATUS", format!("Cannot process withdrawal with status: {}", status)));
     }
 
     if token != "USDC" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Only USDC withdrawals are supported for on-chain processing".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("UNSUPPORTED_TOKEN", "Only USDC withdrawals are supported for on-chain processing"));
     }
 
     // Parse recipient address
     let recipient: Address = to_address.parse().map_err(|_| {
         tracing::error!("Invalid recipient address: {}", to_address);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Invalid recipient address".to_string(),
-            }),
-        )
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "INVALID_RECIPIENT", "Invalid recipient address")
     })?;
 
-    // Convert Decimal to U256 (USDC has 6 decimals)
-    let amount_u128: u128 = (amount * Decimal::from(1_000_000u64))
+    // Convert Decimal to U256 (USDC has 6 decimals). Only the net amount is
+    // paid out; the fee stays with the platform.
+    let amount_u128: u128 = (net_amount * Decimal::from(1_000_000u64))
         .try_into()
-        .map_err(|_| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Invalid withdrawal amount".to_string(),
-                }),
-            )
-        })?;
+        .map_err(|_| AppError::bad_request("INVALID_AMOUNT", "Invalid withdrawal amount"))?;
     let amount_u256 = U256::from(amount_u128);
 
     // Mark withdrawal as processing
     sqlx::query("UPDATE withdrawals SET status = 'processing' WHERE id = $1")
         .bind(withdrawal_id)
         .execute(&state.db.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to update withdrawal status: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to update withdrawal status".to_string(),
-                }),
-            )
-        })?;
+        .await?;
 
     // Send USDC on-chain
     let tx_result = blockchain_client
@@ -692,12 +687,7 @@ pub async fn process_withdraw(
                     .execute(&state.db.pool)
                     .await
             });
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("On-chain transfer failed: {}", e),
-                }),
-            )
+            AppError::Blockchain(e)
         })?;
 
     // Check if transaction was successful
@@ -709,26 +699,13 @@ pub async fn process_withdraw(
             .await
             .ok();
 
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "On-chain transaction failed".to_string(),
-            }),
-        ));
+        return Err(AppError::new(StatusCode::BAD_GATEWAY, "TX_FAILED", "On-chain transaction failed"));
     }
 
     let tx_hash = format!("{:?}", tx_result.tx_hash);
 
     // Start database transaction to finalize
-    let mut db_tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-            }),
-        )
-    })?;
+    let mut db_tx = state.db.pool.begin().await?;
 
     // Deduct frozen balance
     let new_balance: Decimal = sqlx::query_scalar(
@@ -742,16 +719,7 @@ pub async fn process_withdraw(
     .bind(amount)
     .bind(&user_address)
     .fetch_one(&mut *db_tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to deduct frozen balance: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to update balance".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Update withdrawal status to completed
     sqlx::query(
@@ -760,27 +728,10 @@ pub async fn process_withdraw(
     .bind(&tx_hash)
     .bind(withdrawal_id)
     .execute(&mut *db_tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to complete withdrawal: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to complete withdrawal".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Commit transaction
-    db_tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Transaction failed".to_string(),
-            }),
-        )
-    })?;
+    db_tx.commit().await?;
 
     tracing::info!(
         "On-chain withdrawal completed - user: {}, id: {}, amount: {}, tx: {}",
@@ -790,20 +741,41 @@ pub async fn process_withdraw(
         tx_hash
     );
 
+    state
+        .webhook_service
+        .dispatch(
+            WebhookEventType::WithdrawalCompleted,
+            Some(user_address.as_str()),
+            serde_json::json!({
+                "user_address": user_address,
+                "withdrawal_id": withdrawal_id,
+                "token": token,
+                "amount": amount,
+                "net_amount": net_amount,
+                "tx_hash": tx_hash,
+            }),
+        )
+        .await;
+    notify_withdrawal_status(&state, &user_address, withdrawal_id, "completed", net_amount, Some(&tx_hash)).await;
+
     // Broadcast balance update
-    let _ = state.balance_update_sender.send(BalanceUpdateEvent {
-        user_address: user_address.clone(),
-        token: "USDC".to_string(),
-        available: new_balance.to_string(),
-        frozen: (-amount).to_string(),
-        total: (new_balance - amount).to_string(),
-        event_type: "withdrawal".to_string(),
-    });
+    state
+        .event_bus
+        .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+            user_address: user_address.clone().into(),
+            token: "USDC".to_string(),
+            available: new_balance.to_string(),
+            frozen: (-amount).to_string(),
+            total: (new_balance - amount).to_string(),
+            event_type: "withdrawal".to_string(),
+        }))
+        .await;
 
     Ok(Json(ProcessWithdrawResponse {
         withdraw_id: withdrawal_id.to_string(),
         tx_hash,
         amount,
+        net_amount,
         status: "completed".to_string(),
         new_balance,
     }))
@@ -813,15 +785,20 @@ pub async fn process_withdraw(
 // Direct Withdrawal (for development/testing)
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct DirectWithdrawRequest {
+    #[validate(custom = "validation::positive")]
     pub amount: Decimal,
+    #[serde(default)]
+    pub max_fee: Option<Decimal>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct DirectWithdrawResponse {
     pub withdraw_id: String,
     pub amount: Decimal,
+    pub fee: Decimal,
+    pub net_amount: Decimal,
     pub new_balance: Decimal,
     pub message: String,
 }
@@ -833,28 +810,18 @@ pub struct DirectWithdrawResponse {
 pub async fn direct_withdraw(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
-    Json(req): Json<DirectWithdrawRequest>,
-) -> Result<Json<DirectWithdrawResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ValidJson(req): ValidJson<DirectWithdrawRequest>,
+) -> Result<Json<DirectWithdrawResponse>, AppError> {
     // Only allow in development mode
     if state.config.environment != "development" {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(ErrorResponse {
-                error: "Direct withdrawal only available in development mode".to_string(),
-            }),
-        ));
+        return Err(AppError::forbidden("DEVELOPMENT_ONLY", "Direct withdrawal only available in development mode"));
     }
 
-    let user_address = auth_user.address.to_lowercase();
+    let user_address = auth_user.address.clone();
     let amount = req.amount;
 
     if amount <= Decimal::ZERO {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Amount must be positive".to_string(),
-            }),
-        ));
+        return Err(AppError::bad_request("INVALID_AMOUNT", "Amount must be positive"));
     }
 
     // Check available balance
@@ -863,40 +830,20 @@ pub async fn direct_withdraw(
     )
     .bind(&user_address)
     .fetch_optional(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to check balance: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to check balance".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     let available = balance.map(|(b,)| b).unwrap_or(Decimal::ZERO);
     if available < amount {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!(
+        return Err(AppError::bad_request("INSUFFICIENT_BALANCE", format!(
                     "Insufficient balance: available {} USDC, requested {} USDC",
                     available, amount
-                ),
-            }),
-        ));
+                )));
     }
 
+    let quote = quote_withdrawal(&state, amount, available, req.max_fee).await?;
+
     // Start transaction
-    let mut tx = state.db.pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Database error".to_string(),
-            }),
-        )
-    })?;
+    let mut tx = state.db.pool.begin().await?;
 
     // Generate withdrawal ID and fake tx_hash
     let withdraw_id = Uuid::new_v4();
@@ -906,26 +853,22 @@ pub async fn direct_withdraw(
     let now_ts = Utc::now().timestamp_millis();
     sqlx::query(
         r#"
-        INSERT INTO withdrawals (id, user_address, token, amount, to_address, nonce, expiry, tx_hash, status, created_at)
-        VALUES ($1, $2, 'USDC', $3, $2, $4, $4, $5, 'completed', NOW())
+        INSERT INTO withdrawals (id, user_address, token, amount, fee, gas_fee, dust_swept, net_amount,
+                                 to_address, nonce, expiry, tx_hash, status, created_at)
+        VALUES ($1, $2, 'USDC', $3, $4, $5, $6, $7, $2, $8, $8, $9, 'completed', NOW())
         "#,
     )
     .bind(withdraw_id)
     .bind(&user_address)
-    .bind(amount)
+    .bind(quote.gross_amount)
+    .bind(quote.total_fee)
+    .bind(quote.gas_fee)
+    .bind(quote.dust_swept)
+    .bind(quote.net_amount)
     .bind(now_ts)
     .bind(&fake_tx_hash)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to insert withdrawal: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to record withdrawal".to_string(),
-            }),
-        )
-    })?;
+    .await?;
 
     // Deduct from available balance
     let new_balance: Decimal = sqlx::query_scalar(
@@ -936,51 +879,53 @@ pub async fn direct_withdraw(
         RETURNING available
         "#,
     )
-    .bind(amount)
+    .bind(quote.gross_amount)
     .bind(&user_address)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to update balance: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Failed to update balance".to_string(),
-            }),
-        )
-    })?;
+    .await?;
+
+    record_withdrawal_ledger(&mut tx, &user_address, "USDC", withdraw_id, available, &quote)
+        .await?;
 
     // Commit transaction
-    tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Transaction failed".to_string(),
-            }),
-        )
-    })?;
+    tx.commit().await?;
 
     tracing::info!(
-        "Direct withdrawal: {} USDC withdrawn from {} (new balance: {})",
-        amount,
+        "Direct withdrawal: {} USDC withdrawn from {} (fee: {}, new balance: {})",
+        quote.gross_amount,
         user_address,
+        quote.total_fee,
         new_balance
     );
 
+    notify_withdrawal_status(
+        &state,
+        &user_address,
+        withdraw_id,
+        "completed",
+        quote.net_amount,
+        Some(&fake_tx_hash),
+    )
+    .await;
+
     // Broadcast balance update
-    let _ = state.balance_update_sender.send(BalanceUpdateEvent {
-        user_address: user_address.clone(),
-        token: "USDC".to_string(),
-        available: new_balance.to_string(),
-        frozen: "0".to_string(),
-        total: new_balance.to_string(),
-        event_type: "withdrawal".to_string(),
-    });
+    state
+        .event_bus
+        .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+            user_address: user_address.clone().into(),
+            token: "USDC".to_string(),
+            available: new_balance.to_string(),
+            frozen: "0".to_string(),
+            total: new_balance.to_string(),
+            event_type: "withdrawal".to_string(),
+        }))
+        .await;
 
     Ok(Json(DirectWithdrawResponse {
         withdraw_id: withdraw_id.to_string(),
-        amount,
+        amount: quote.gross_amount,
+        fee: quote.total_fee,
+        net_amount: quote.net_amount,
         new_balance,
         message: "Development withdrawal successful. In production, use on-chain withdrawals."
             .to_string(),
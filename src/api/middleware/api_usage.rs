@@ -0,0 +1,78 @@
+//! API Usage Middleware
+//!
+//! Counts every authenticated request against its account's daily quota
+//! (see [`crate::services::api_usage`]). Requests past the quota get a 429
+//! `QUOTA_EXCEEDED` with `Retry-After` set to the next UTC midnight; the
+//! others carry the remaining quota, and a warning header once the account
+//! nears it. Runs after the auth middleware, which identifies the account.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::api::error::AppError;
+use crate::auth::middleware::AuthUser;
+use crate::services::api_usage::{QuotaCheck, QuotaStatus};
+use crate::AppState;
+
+pub async fn api_usage_middleware(State(state): State<Arc<AppState>>, request: Request<Body>, next: Next) -> Response {
+    let Some(address) = request.extensions().get::<AuthUser>().map(|user| user.address.clone()) else {
+        return next.run(request).await;
+    };
+    let check = match state.api_usage.check(&address).await {
+        Ok(check) => check,
+        Err(e) => {
+            // Metering must not take the API down with it
+            tracing::warn!("Failed to load API usage of {}: {}", address, e);
+            return next.run(request).await;
+        }
+    };
+
+    if check.status == QuotaStatus::Exceeded {
+        let mut response = AppError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "QUOTA_EXCEEDED",
+            "Daily API quota exceeded; it resets at 00:00 UTC",
+        )
+        .into_response();
+        set_quota_headers(response.headers_mut(), &check);
+        if let Ok(value) = HeaderValue::from_str(&check.resets_in_secs().to_string()) {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+        return response;
+    }
+
+    let bytes_in = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let mut response = next.run(request).await;
+    let bytes_out = response.body().size_hint().lower();
+    state.api_usage.record(&address, check.day, bytes_in, bytes_out);
+    set_quota_headers(response.headers_mut(), &check);
+    response
+}
+
+fn set_quota_headers(headers: &mut HeaderMap, check: &QuotaCheck) {
+    let mut set = |name: &'static str, value: String| {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    };
+    if let Some(remaining) = check.remaining_requests() {
+        set("x-api-quota-requests-remaining", remaining.to_string());
+    }
+    if let Some(remaining) = check.remaining_bytes() {
+        set("x-api-quota-bytes-remaining", remaining.to_string());
+    }
+    if let (QuotaStatus::Warning, Some(used)) = (check.status, check.used_percent) {
+        set("x-api-quota-warning", format!("{}% of the daily API quota used", used));
+    }
+}
@@ -2,9 +2,12 @@
 //!
 //! Contains middleware for:
 //! - HTTP metrics recording
+//! - Per-account API usage and daily quotas
 //! - Rate limiting (future)
 //! - Request logging
 
+pub mod api_usage;
 pub mod metrics;
 
+pub use api_usage::api_usage_middleware;
 pub use metrics::metrics_middleware;
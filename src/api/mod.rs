@@ -1,5 +1,10 @@
+pub mod dto;
+pub mod error;
 pub mod handlers;
 pub mod middleware;
+pub mod perspective;
 pub mod routes;
+pub mod validation;
+pub mod versioning;
 
 // pub use routes::*;
@@ -0,0 +1,70 @@
+//! Yes-Perspective Views
+//!
+//! Buying No at `p` is economically the same as selling Yes at `1 - p`, and
+//! many frontends prefer to show every order in terms of the Yes price.
+//! With `?perspective=yes` the order, trade and position endpoints restate
+//! No rows that way (share type Yes, price `1 - p`, side flipped, and a No
+//! position shown as a short Yes position) so clients do not each carry
+//! their own conversion. Restated rows are marked `complement: true`.
+//!
+//! The default `native` perspective returns rows as stored.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::market::ShareType;
+use crate::models::OrderSide;
+
+/// How order, trade and position rows are presented
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Perspective {
+    /// As placed
+    #[default]
+    Native,
+    /// No rows restated as the equivalent Yes rows
+    Yes,
+}
+
+impl Perspective {
+    /// Whether a row of `share_type` is restated in this perspective
+    pub fn restates(self, share_type: ShareType) -> bool {
+        self == Perspective::Yes && share_type == ShareType::No
+    }
+}
+
+/// The price of the complementary outcome
+pub fn complement_price(price: Decimal) -> Decimal {
+    Decimal::ONE - price
+}
+
+/// The side that has the same effect on the complementary outcome
+pub fn complement_side(side: &str) -> String {
+    side.parse::<OrderSide>()
+        .map(|side| side.opposite().to_string())
+        .unwrap_or_else(|_| side.to_string())
+}
+
+/// A row that can be restated in another perspective
+pub trait PerspectiveView: Sized {
+    fn in_perspective(self, perspective: Perspective) -> Self;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_no_rows_restated_as_yes() {
+        assert!(Perspective::Yes.restates(ShareType::No));
+        assert!(!Perspective::Yes.restates(ShareType::Yes));
+        assert!(!Perspective::Native.restates(ShareType::No));
+
+        // Buy No at 0.35 == sell Yes at 0.65
+        assert_eq!(complement_price(dec!(0.35)), dec!(0.65));
+        assert_eq!(complement_side("buy"), "sell");
+        assert_eq!(complement_side("sell"), "buy");
+        assert_eq!(complement_side("unknown"), "unknown");
+    }
+}
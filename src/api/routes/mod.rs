@@ -1,17 +1,24 @@
+pub mod v2;
+
 use axum::{
+    http::Method,
     middleware as axum_middleware,
     routing::{delete, get, post},
     Router,
 };
 use std::sync::Arc;
+use tower_http::cors::{Any, CorsLayer};
 
 use crate::api::handlers;
+use crate::api::middleware::api_usage_middleware;
 use crate::auth::middleware::{admin_middleware, auth_middleware};
+use crate::auth::rate_limit::{rate_limit_by_header, RateLimitConfig, RateLimiterState};
 use crate::AppState;
 
 pub fn create_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
     // Public routes (no auth required)
     let public_routes = Router::new()
+        .route("/time", get(handlers::time::get_time))
         // Auth
         .route("/auth/login", post(handlers::auth::login))
         .route("/auth/nonce/:address", get(handlers::auth::get_nonce))
@@ -23,11 +30,25 @@ pub fn create_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/markets/new", get(handlers::market::get_new_markets))
         .route("/markets/:market_id", get(handlers::market::get_market))
         .route("/markets/:market_id/orderbook", get(handlers::market::get_orderbook))
+        .route("/markets/:market_id/orderbook/history", get(handlers::market::get_orderbook_history))
         .route("/markets/:market_id/trades", get(handlers::market::get_trades))
+        .route("/markets/:market_id/tape", get(handlers::market::get_trade_tape))
         .route("/markets/:market_id/ticker", get(handlers::market::get_ticker))
         .route("/markets/:market_id/price", get(handlers::market::get_price))
+        .route("/markets/:market_id/quote", get(handlers::market::get_quote))
+        .route("/markets/:market_id/liquidity", get(handlers::market::get_liquidity))
+        .route("/markets/:market_id/leaderboard", get(handlers::leaderboard::get_market_leaderboard))
+        .route("/markets/:market_id/tokens", get(handlers::market_tokens::get_market_tokens))
         .route("/markets/:market_id/klines", get(handlers::market_kline::get_market_klines))
+        .route("/markets/:market_id/replay", get(handlers::replay::get_replay))
+        .route("/markets/:market_id/analytics", get(handlers::analytics::get_market_analytics))
         .route("/markets/:market_id/assertions", get(handlers::resolution::get_market_assertions))
+        .route("/markets/:market_id/resolution", get(handlers::resolution::get_market_resolution))
+        .route("/markets/:market_id/criteria", get(handlers::resolution::get_market_criteria))
+        // Market groups (sibling markets of one event)
+        .route("/market-groups/:group_id", get(handlers::market_group::get_group))
+        // Order reject reason catalog
+        .route("/orders/reject-reasons", get(handlers::order::get_reject_reasons))
         // Oracle (Chainlink price feeds)
         .route("/oracle/status", get(handlers::oracle::get_oracle_status))
         .route("/oracle/chainlink/feeds", get(handlers::oracle::list_chainlink_feeds))
@@ -35,7 +56,9 @@ pub fn create_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/oracle/chainlink/prices", get(handlers::oracle::get_chainlink_prices))
         // UMA Optimistic Oracle
         .route("/oracle/uma", get(handlers::resolution::get_uma_oracle_info))
-        .route("/assertions/:assertion_id", get(handlers::resolution::get_assertion_details));
+        .route("/assertions/:assertion_id", get(handlers::resolution::get_assertion_details))
+        // Public data exports
+        .route("/exports/manifest", get(handlers::export::get_manifest));
 
     // Protected routes (auth required)
     let protected_routes = Router::new()
@@ -45,16 +68,72 @@ pub fn create_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/account/balances", get(handlers::account::get_balances))
         .route("/account/shares", get(handlers::account::get_shares))
         .route("/account/orders", get(handlers::account::get_orders))
+        .route("/account/orders/summary", get(handlers::account::get_orders_summary))
         .route("/account/trades", get(handlers::account::get_trades))
+        .route("/account/execution-stats", get(handlers::account::get_execution_stats))
+        .route("/account/risk", get(handlers::account::get_risk))
+        .route("/account/fees", get(handlers::account::get_fees))
+        .route("/account/api-usage", get(handlers::account::get_api_usage))
+        // Personal data export and account closure
+        .route("/account/export", get(handlers::personal_data::export_data))
+        .route("/account/closure", get(handlers::personal_data::get_closure))
+        .route("/account/closure", post(handlers::personal_data::close_account))
+        // Internal transfers
+        .route("/account/transfer", post(handlers::transfer::create_transfer))
+        .route("/account/transfers", get(handlers::transfer::get_transfers))
+        // Watchlist (resolution reminders)
+        .route("/account/watchlist", get(handlers::resolution_schedule::get_watchlist))
+        .route("/markets/:market_id/watch", post(handlers::resolution_schedule::watch_market))
+        .route("/markets/:market_id/watch", delete(handlers::resolution_schedule::unwatch_market))
+        // Display and notification preferences
+        .route("/account/preferences", get(handlers::preferences::get_preferences))
+        .route("/account/preferences", axum::routing::put(handlers::preferences::update_preferences))
+        .route("/account/notifications", get(handlers::notification::get_preferences))
+        .route("/account/notifications", axum::routing::put(handlers::notification::update_preferences))
+        // Leaderboard display opt-in
+        .route("/account/leaderboard", get(handlers::leaderboard::get_display))
+        .route("/account/leaderboard", axum::routing::put(handlers::leaderboard::update_display))
+        // Login sessions
+        .route("/account/sessions", get(handlers::session::list_sessions))
+        .route("/account/sessions/revoke-all", post(handlers::session::revoke_all_sessions))
+        .route("/account/sessions/:session_id", delete(handlers::session::revoke_session))
         // Settlement
+        .route("/account/settlement-mode", get(handlers::settlement_mode::get_settlement_mode))
+        .route("/account/settlement-mode", axum::routing::put(handlers::settlement_mode::update_settlement_mode))
         .route("/account/settle/:market_id", post(handlers::account::settle_market))
         .route("/account/settle/:market_id/status", get(handlers::account::get_settlement_status))
+        .route("/markets/:market_id/payout-preview", get(handlers::account::get_payout_preview))
+        // One-click position exit
+        .route("/account/positions/:market_id/close", post(handlers::order::close_position))
         // Orders
         .route("/orders", post(handlers::order::create_order))
         .route("/orders/ctf", post(handlers::ctf_order::create_ctf_order))
         .route("/orders/:order_id", get(handlers::order::get_order))
         .route("/orders/:order_id", delete(handlers::order::cancel_order))
+        .route("/orders/:order_id", axum::routing::put(handlers::order::amend_order))
         .route("/orders/batch", post(handlers::order::batch_cancel))
+        .route("/orders/cancel-all", post(handlers::order::cancel_all))
+        .route("/orders/cancel-all-after", post(handlers::order::cancel_all_after))
+        // Stop and stop-limit orders
+        .route("/trigger-orders", post(handlers::trigger_orders::create_trigger_order))
+        .route("/trigger-orders", get(handlers::trigger_orders::get_trigger_orders))
+        .route("/trigger-orders/:order_id", get(handlers::trigger_orders::get_trigger_order))
+        .route("/trigger-orders/:order_id", delete(handlers::trigger_orders::cancel_trigger_order))
+        // User-directed on-chain split / merge
+        .route("/ctf/split/prepare", post(handlers::ctf_position::prepare_split))
+        .route("/ctf/merge/prepare", post(handlers::ctf_position::prepare_merge))
+        .route("/ctf/operations", get(handlers::ctf_position::list_operations))
+        // Gasless relay of redemptions and approvals
+        .route("/relay", post(handlers::relayer::relay))
+        .route("/relay/config", get(handlers::relayer::get_relay_config))
+        .route("/relay/transactions", get(handlers::relayer::list_relayed))
+        // Paper trading (virtual balance, simulated books)
+        .route("/paper/account", get(handlers::paper::get_account))
+        .route("/paper/orders", post(handlers::paper::place_order))
+        .route("/paper/orders", get(handlers::paper::list_orders))
+        .route("/paper/orders/:order_id", delete(handlers::paper::cancel_order))
+        .route("/paper/reset", post(handlers::paper::reset))
+        .route("/paper/orderbook", get(handlers::paper::get_orderbook))
         // Deposits & Withdrawals
         .route("/deposit/prepare", post(handlers::deposit::prepare_deposit))
         .route("/deposit/confirm", post(handlers::deposit::confirm_deposit))
@@ -64,6 +143,7 @@ pub fn create_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         // On-chain balance and allowance (Polymarket-style approve mode)
         .route("/deposit/onchain-balance", get(handlers::deposit::get_onchain_balance))
         .route("/deposit/check-allowance", post(handlers::deposit::check_allowance))
+        .route("/withdraw/prepare", post(handlers::withdraw::prepare_withdraw))
         .route("/withdraw/request", post(handlers::withdraw::request_withdraw))
         .route("/withdraw/direct", post(handlers::withdraw::direct_withdraw))
         .route("/withdraw/history", get(handlers::withdraw::get_history))
@@ -81,16 +161,94 @@ pub fn create_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/mm/stats", get(handlers::market_maker::get_mm_stats))
         .route("/mm/fee-tiers", get(handlers::market_maker::get_fee_tiers))
         .route("/mm/orders", get(handlers::market_maker::get_mm_orders))
+        .route("/mm/settlements", get(handlers::market_maker::get_settlements))
+        .route(
+            "/mm/protections",
+            get(handlers::market_maker::get_protections).put(handlers::market_maker::set_protections),
+        )
+        // Webhooks
+        .route("/webhooks", post(handlers::webhook::create_webhook))
+        .route("/webhooks", get(handlers::webhook::list_webhooks))
+        .route("/webhooks/:webhook_id", delete(handlers::webhook::delete_webhook))
+        .route("/webhooks/:webhook_id/deliveries", get(handlers::webhook::get_deliveries))
+        .route("/webhooks/deliveries/:delivery_id/replay", post(handlers::webhook::replay_delivery))
+        // Negative-risk No-set conversion
+        .route("/market-groups/:group_id/conversion", get(handlers::market_group::get_conversion_preview))
+        .route("/market-groups/:group_id/convert", post(handlers::market_group::convert))
+        // Feature flags evaluated for the current user
+        .route("/feature-flags", get(handlers::feature_flags::get_my_flags))
+        // Usage is counted per account, so it runs after auth
+        .layer(axum_middleware::from_fn_with_state(state.clone(), api_usage_middleware))
         .layer(axum_middleware::from_fn_with_state(state.clone(), auth_middleware));
 
     // Admin routes (auth required + admin role check)
     let admin_routes = Router::new()
         .route("/admin/markets", post(handlers::market::create_market))
+        .route("/admin/markets/categorical", post(handlers::market::create_categorical_market))
         .route("/admin/markets/:market_id/close", post(handlers::market::close_market))
         .route("/admin/markets/:market_id/resolve", post(handlers::market::resolve_market))
         .route("/admin/markets/:market_id/cancel", post(handlers::market::cancel_market))
         .route("/admin/markets/:market_id/probability", post(handlers::market::update_probability))
         .route("/admin/markets/:market_id/refresh-probability", post(handlers::market::refresh_probability))
+        .route("/admin/markets/:market_id/feature", post(handlers::market::feature_market))
+        .route("/admin/markets/:market_id/resume-trading", post(handlers::engine::resume_trading))
+        .route(
+            "/admin/markets/:market_id/resolution-time",
+            axum::routing::put(handlers::resolution_schedule::schedule_resolution),
+        )
+        .route(
+            "/admin/markets/:market_id/trading-schedule",
+            axum::routing::put(handlers::market::set_trading_schedule)
+                .delete(handlers::market::clear_trading_schedule),
+        )
+        .route("/admin/market-groups", post(handlers::market_group::create_group))
+        .route(
+            "/admin/neg-risk-conversions/:conversion_id/submit",
+            post(handlers::market_group::submit_conversion),
+        )
+        .route("/admin/webhooks", post(handlers::webhook::admin_create_webhook))
+        .route("/admin/webhooks", get(handlers::webhook::admin_list_webhooks))
+        .route("/admin/webhooks/deliveries", get(handlers::webhook::admin_get_deliveries))
+        .route("/admin/webhooks/deliveries/:delivery_id/replay", post(handlers::webhook::admin_replay_delivery))
+        .route("/admin/approvals", post(handlers::admin_approval::propose))
+        .route("/admin/approvals", get(handlers::admin_approval::list_approvals))
+        .route("/admin/approvals/:approval_id", get(handlers::admin_approval::get_approval))
+        .route("/admin/approvals/:approval_id/approve", post(handlers::admin_approval::approve))
+        .route("/admin/approvals/:approval_id/execute", post(handlers::admin_approval::execute))
+        .route("/admin/trades/:trade_id/bust", post(handlers::trade_adjustment::bust_trade))
+        .route("/admin/trades/:trade_id/adjust", post(handlers::trade_adjustment::adjust_trade))
+        .route("/admin/trades/:trade_id/adjustments", get(handlers::trade_adjustment::list_adjustments))
+        .route("/admin/trade-persist-queue", get(handlers::trade_persistence::list_queue))
+        .route("/admin/trade-persist-queue/requeue-dead", post(handlers::trade_persistence::requeue_dead))
+        .route("/admin/trade-persist-queue/:id/reprocess", post(handlers::trade_persistence::reprocess_entry))
+        .route("/admin/channels", post(handlers::channel::create_channel))
+        .route("/admin/channels", get(handlers::channel::list_channels))
+        .route("/admin/channels/:channel_id", axum::routing::put(handlers::channel::update_channel))
+        .route("/admin/channels/:channel_id", delete(handlers::channel::delete_channel))
+        .route("/admin/channels/:channel_id/messages", get(handlers::channel::get_channel_messages))
+        .route("/admin/exports/run", post(handlers::export::run_export))
+        .route("/admin/import", post(handlers::backfill::import_history))
+        .route("/admin/sandbox/markets", post(handlers::sandbox::seed_market))
+        .route("/admin/system-events", get(handlers::system_events::list_events))
+        .route("/admin/reports", get(handlers::ops_report::list_reports))
+        .route("/admin/reports/:date", get(handlers::ops_report::get_report))
+        .route("/admin/reports/:date/generate", post(handlers::ops_report::generate_report))
+        .route("/admin/engine/shards", get(handlers::engine::get_shards))
+        .route("/admin/engine/order-sources", get(handlers::engine::get_order_sources))
+        .route("/admin/engine/stale-orders", get(handlers::engine::get_stale_orders))
+        .route("/admin/engine/stale-orders/sweep", post(handlers::engine::sweep_stale_orders))
+        .route("/admin/engine/halts", get(handlers::engine::get_halts))
+        .route("/admin/users/:address", get(handlers::user_admin::get_user))
+        .route("/admin/users/:address/freeze", post(handlers::user_admin::freeze_user))
+        .route("/admin/users/:address/unfreeze", post(handlers::user_admin::unfreeze_user))
+        .route("/admin/users/:address/cancel-orders", post(handlers::user_admin::cancel_user_orders))
+        .route("/admin/users/:address/market-data-tier", axum::routing::put(handlers::user_admin::set_market_data_tier))
+        .route("/admin/users/:address/api-quota", axum::routing::put(handlers::user_admin::set_api_quota))
+        .route("/admin/users/:address/notes", post(handlers::user_admin::add_note))
+        .route("/admin/users/:address/audit", get(handlers::user_admin::get_audit_log))
+        .route("/admin/feature-flags", get(handlers::feature_flags::list_flags))
+        .route("/admin/feature-flags/:key", axum::routing::put(handlers::feature_flags::upsert_flag))
+        .route("/admin/feature-flags/:key", delete(handlers::feature_flags::delete_flag))
         // Admin middleware must come BEFORE auth middleware in the layer chain
         // (layers are applied in reverse order, so auth runs first, then admin)
         .layer(axum_middleware::from_fn(admin_middleware))
@@ -101,3 +259,27 @@ pub fn create_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .merge(protected_routes)
         .merge(admin_routes)
 }
+
+/// Public widget API for third-party embeds, mounted at `/public/v1`.
+///
+/// GET-only, credential-free CORS from any origin, and its own per-client
+/// rate limiter so embed traffic cannot exhaust the main API.
+pub fn create_public_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let limiter = RateLimiterState::new(RateLimitConfig {
+        max_requests: state.config.public_api_rate_limit,
+        window_secs: 60,
+        skip_authenticated: false,
+    });
+
+    Router::new()
+        .route("/markets/:market_id/card", get(handlers::widget::get_market_card))
+        .route("/markets/:market_id/orderbook", get(handlers::widget::get_mini_orderbook))
+        .route("/markets/:market_id/sparkline", get(handlers::widget::get_sparkline))
+        .layer(axum_middleware::from_fn_with_state(limiter, rate_limit_by_header))
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods([Method::GET, Method::OPTIONS])
+                .allow_headers(Any),
+        )
+}
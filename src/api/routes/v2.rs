@@ -0,0 +1,34 @@
+//! API v2 Router
+//!
+//! Routes redefined in v2 are registered here; every other path falls
+//! through to the v1 router, so v2 serves the full API from day one.
+//!
+//! Redefined:
+//! - `POST /orders/batch` places a batch of orders; batch cancel, which v1
+//!   serves there, moves to `DELETE /orders/batch` as on `/mm/orders/batch`
+
+use axum::{middleware as axum_middleware, routing::post, Router};
+use std::sync::Arc;
+
+use crate::api::handlers;
+use crate::api::middleware::api_usage_middleware;
+use crate::api::versioning::v2_responses;
+use crate::auth::middleware::auth_middleware;
+use crate::AppState;
+
+pub fn create_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let v1 = super::create_router(state.clone()).with_state(state.clone());
+
+    let protected_routes = Router::new()
+        .route(
+            "/orders/batch",
+            post(handlers::order::place_batch).delete(handlers::order::batch_cancel),
+        )
+        .layer(axum_middleware::from_fn_with_state(state.clone(), api_usage_middleware))
+        .layer(axum_middleware::from_fn_with_state(state.clone(), auth_middleware));
+
+    Router::new()
+        .merge(protected_routes)
+        .fallback_service(v1)
+        .layer(axum_middleware::from_fn(v2_responses))
+}
@@ -0,0 +1,425 @@
+//! Request Validation
+//!
+//! Handlers take request bodies and query strings through [`ValidJson`] and
+//! [`ValidQuery`] rather than axum's `Json` and `Query`. Both deserialize
+//! with the path of the failing field tracked and then run the DTO's
+//! `validator` rules, so a bad request is answered with a 422 listing every
+//! offending field instead of serde's rejection text, or a 500 once the
+//! value reaches a database constraint:
+//!
+//! ```json
+//! {"error": "Invalid request", "code": "VALIDATION_FAILED",
+//!  "fields": [{"field": "price", "code": "price", "message": "must be between 0 and 1, exclusive"}]}
+//! ```
+//!
+//! Rules shared across DTOs (prices, amounts, addresses, page sizes) are
+//! the functions and constants below, used as
+//! `#[validate(custom = "validation::price")]` or
+//! `#[validate(range(min = 1, max = "validation::MAX_PAGE_LIMIT"))]`.
+
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::api::dto::v1;
+use crate::models::{Address, AddressError};
+
+/// Largest `limit` accepted by paginated endpoints; most clamp lower
+pub const MAX_PAGE_LIMIT: i64 = 1000;
+
+/// Error code of a request rejected field by field
+pub const VALIDATION_FAILED: &str = "VALIDATION_FAILED";
+
+/// One offending field of a rejected request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Path of the field in the request, e.g. `orders[2].price`
+    pub field: String,
+    /// Machine-readable rule that failed: `required`, `invalid`, `range`,
+    /// `length`, `price`, ...
+    pub code: String,
+    pub message: String,
+}
+
+/// A request body or query string that failed to parse or validate
+#[derive(Debug)]
+pub struct ValidationRejection {
+    status: StatusCode,
+    error: String,
+    code: &'static str,
+    fields: Vec<FieldError>,
+}
+
+impl ValidationRejection {
+    fn fields(fields: Vec<FieldError>) -> Self {
+        Self {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            error: "Invalid request".to_string(),
+            code: VALIDATION_FAILED,
+            fields,
+        }
+    }
+
+    /// Deserialization failed at `path`
+    fn deserialize<E: Display>(path: &serde_path_to_error::Path, error: E) -> Self {
+        Self::fields(vec![deserialize_error(&path.to_string(), &error.to_string())])
+    }
+
+    fn body(rejection: JsonRejection) -> Self {
+        let code = match rejection {
+            JsonRejection::MissingJsonContentType(_) => "UNSUPPORTED_MEDIA_TYPE",
+            JsonRejection::JsonSyntaxError(_) => "INVALID_JSON",
+            _ => "INVALID_BODY",
+        };
+        Self {
+            status: rejection.status(),
+            error: rejection.body_text(),
+            code,
+            fields: Vec::new(),
+        }
+    }
+}
+
+impl From<ValidationErrors> for ValidationRejection {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Vec::new();
+        collect_errors("", &errors, &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        Self::fields(fields)
+    }
+}
+
+impl IntoResponse for ValidationRejection {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(v1::ErrorResponse {
+                error: self.error,
+                code: self.code.to_string(),
+                fields: self.fields,
+                retry_after_ms: None,
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// JSON body extractor that validates the deserialized DTO
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ValidationRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<serde_json::Value>::from_request(req, state)
+            .await
+            .map_err(ValidationRejection::body)?;
+        let dto: T = serde_path_to_error::deserialize(value)
+            .map_err(|e| ValidationRejection::deserialize(e.path(), e.inner()))?;
+        dto.validate()?;
+        Ok(Self(dto))
+    }
+}
+
+/// Query string extractor that validates the deserialized DTO
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ValidationRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer = serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        let dto: T = serde_path_to_error::deserialize(deserializer)
+            .map_err(|e| ValidationRejection::deserialize(e.path(), e.inner()))?;
+        dto.validate()?;
+        Ok(Self(dto))
+    }
+}
+
+/// Field error for a serde failure at `path` (`.` for the top level)
+fn deserialize_error(path: &str, message: &str) -> FieldError {
+    let path = if path == "." { "" } else { path };
+    // serde reports a missing field against its parent
+    match message.strip_prefix("missing field `").and_then(|m| m.strip_suffix('`')) {
+        Some(name) => FieldError {
+            field: join(path, name),
+            code: "required".to_string(),
+            message: "is required".to_string(),
+        },
+        None => FieldError {
+            field: path.to_string(),
+            code: "invalid".to_string(),
+            message: message.to_string(),
+        },
+    }
+}
+
+fn join(prefix: &str, field: &str) -> String {
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", prefix, field)
+    }
+}
+
+fn collect_errors(prefix: &str, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = join(prefix, field);
+        match kind {
+            ValidationErrorsKind::Field(errors) => out.extend(errors.iter().map(|e| FieldError {
+                field: path.clone(),
+                code: e.code.to_string(),
+                message: message(e),
+            })),
+            ValidationErrorsKind::Struct(errors) => collect_errors(&path, errors, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_errors(&format!("{}[{}]", path, index), errors, out);
+                }
+            }
+        }
+    }
+}
+
+/// The rule's own message, or one built from its parameters
+fn message(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| {
+        error.params.get(name).map(|v| match v.as_f64() {
+            Some(n) if n.fract() == 0.0 => format!("{}", n as i64),
+            _ => v.to_string(),
+        })
+    };
+    let (min, max) = (param("min"), param("max"));
+    match error.code.as_ref() {
+        "range" => match (min, max) {
+            (Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+            (Some(min), None) => format!("must be at least {}", min),
+            (None, Some(max)) => format!("must be at most {}", max),
+            (None, None) => "is out of range".to_string(),
+        },
+        "length" => match (param("equal"), min, max) {
+            (Some(equal), _, _) => format!("must have length {}", equal),
+            (None, Some(min), Some(max)) => format!("must have length between {} and {}", min, max),
+            (None, Some(min), None) if min == "1" => "must not be empty".to_string(),
+            (None, Some(min), None) => format!("must have length at least {}", min),
+            (None, None, Some(max)) => format!("must have length at most {}", max),
+            (None, None, None) => "has an invalid length".to_string(),
+        },
+        "email" => "must be an email address".to_string(),
+        "url" => "must be a URL".to_string(),
+        _ => "is invalid".to_string(),
+    }
+}
+
+fn rule(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::Borrowed(message));
+    error
+}
+
+/// Prices are probabilities, strictly between 0 and 1
+pub fn price(value: &Decimal) -> Result<(), ValidationError> {
+    if *value > Decimal::ZERO && *value < Decimal::ONE {
+        Ok(())
+    } else {
+        Err(rule("price", "must be between 0 and 1, exclusive"))
+    }
+}
+
+/// Order sizes and collateral amounts
+pub fn positive(value: &Decimal) -> Result<(), ValidationError> {
+    if *value > Decimal::ZERO {
+        Ok(())
+    } else {
+        Err(rule("positive", "must be greater than 0"))
+    }
+}
+
+/// Thresholds where 0 means "no minimum"
+pub fn non_negative(value: &Decimal) -> Result<(), ValidationError> {
+    if *value >= Decimal::ZERO {
+        Ok(())
+    } else {
+        Err(rule("non_negative", "must not be negative"))
+    }
+}
+
+/// `0x`-prefixed 20-byte hex address, either case; mixed case must match
+/// its EIP-55 checksum
+pub fn address(value: &str) -> Result<(), ValidationError> {
+    match value.parse::<Address>() {
+        Ok(_) => Ok(()),
+        Err(AddressError::Format(_)) => Err(rule("address", "must be a 0x-prefixed 40 hex digit address")),
+        Err(AddressError::Checksum(_)) => Err(rule("address", "does not match its EIP-55 checksum")),
+    }
+}
+
+/// `0x`-prefixed 32-byte hex transaction hash
+pub fn tx_hash(value: &str) -> Result<(), ValidationError> {
+    if is_hex(value, 64) {
+        Ok(())
+    } else {
+        Err(rule("tx_hash", "must be a 0x-prefixed 64 hex digit transaction hash"))
+    }
+}
+
+fn is_hex(value: &str, digits: usize) -> bool {
+    value
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == digits && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::header;
+    use rust_decimal_macros::dec;
+
+    #[derive(Debug, Serialize, Deserialize, Validate)]
+    struct Item {
+        #[validate(custom = "price")]
+        price: Decimal,
+        #[validate(custom = "positive")]
+        amount: Decimal,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Batch {
+        #[validate(length(min = 1))]
+        #[validate]
+        items: Vec<Item>,
+        #[validate(custom = "address")]
+        owner: String,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Page {
+        #[validate(range(min = 1, max = "MAX_PAGE_LIMIT"))]
+        limit: Option<i64>,
+        #[validate(range(min = 0))]
+        offset: Option<i64>,
+        market_id: Option<uuid::Uuid>,
+    }
+
+    fn json_request(body: &str) -> Request {
+        Request::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn rejected_body<T: std::fmt::Debug + DeserializeOwned + Validate>(body: &str) -> (StatusCode, v1::ErrorResponse) {
+        let rejection = ValidJson::<T>::from_request(json_request(body), &()).await.unwrap_err();
+        let response = rejection.into_response();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    async fn query<T: std::fmt::Debug + DeserializeOwned + Validate>(query: &str) -> Result<T, ValidationRejection> {
+        let (mut parts, _) = Request::builder()
+            .uri(format!("/x?{}", query))
+            .body(Body::empty())
+            .unwrap()
+            .into_parts();
+        ValidQuery::<T>::from_request_parts(&mut parts, &()).await.map(|ValidQuery(dto)| dto)
+    }
+
+    #[tokio::test]
+    async fn test_rules_report_every_field_by_path() {
+        let (status, body) = rejected_body::<Batch>(
+            r#"{"items": [{"price": "0.5", "amount": "1"}, {"price": "1.5", "amount": "0"}], "owner": "alice"}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body.code, VALIDATION_FAILED);
+        let fields: Vec<_> = body.fields.iter().map(|f| (f.field.as_str(), f.code.as_str())).collect();
+        assert_eq!(
+            fields,
+            vec![("items[1].amount", "positive"), ("items[1].price", "price"), ("owner", "address")]
+        );
+
+        let (_, body) = rejected_body::<Batch>(
+            r#"{"items": [], "owner": "0x00000000000000000000000000000000000000aB"}"#,
+        )
+        .await;
+        assert_eq!(body.fields[0].field, "items");
+        assert_eq!(body.fields[0].message, "must not be empty");
+    }
+
+    #[tokio::test]
+    async fn test_deserialization_errors_name_the_field() {
+        let (status, body) = rejected_body::<Batch>(r#"{"items": [{"price": "abc", "amount": "1"}], "owner": "0x"}"#).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!((body.fields[0].field.as_str(), body.fields[0].code.as_str()), ("items[0].price", "invalid"));
+
+        let (_, body) = rejected_body::<Batch>(r#"{"items": [{"price": "0.5"}], "owner": "0x"}"#).await;
+        assert_eq!(body.fields[0].field, "items[0].amount");
+        assert_eq!(body.fields[0].code, "required");
+
+        // Malformed JSON is not a field problem
+        let (status, body) = rejected_body::<Batch>("{").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, "INVALID_JSON");
+        assert!(body.fields.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_bounds() {
+        let page: Page = query("limit=50&offset=0").await.unwrap();
+        assert_eq!((page.limit, page.offset, page.market_id), (Some(50), Some(0), None));
+        assert!(query::<Page>("").await.is_ok());
+
+        let rejection = query::<Page>("limit=0&offset=-1").await.unwrap_err();
+        let fields: Vec<_> = rejection.fields.iter().map(|f| (f.field.as_str(), f.message.as_str())).collect();
+        assert_eq!(
+            fields,
+            vec![("limit", "must be between 1 and 1000"), ("offset", "must be at least 0")]
+        );
+
+        let rejection = query::<Page>("market_id=not-a-uuid").await.unwrap_err();
+        assert_eq!(rejection.fields[0].field, "market_id");
+        assert_eq!(rejection.fields[0].code, "invalid");
+    }
+
+    #[test]
+    fn test_shared_rules() {
+        assert!(price(&dec!(0.01)).is_ok());
+        assert!(price(&Decimal::ZERO).is_err());
+        assert!(price(&Decimal::ONE).is_err());
+        assert!(positive(&dec!(0.000001)).is_ok());
+        assert!(positive(&Decimal::ZERO).is_err());
+        assert!(non_negative(&Decimal::ZERO).is_ok());
+        assert!(address("0x2222222222222222222222222222222222222222").is_ok());
+        assert!(address("2222222222222222222222222222222222222222").is_err());
+        assert!(tx_hash(&format!("0x{}", "ab".repeat(32))).is_ok());
+        assert!(tx_hash("0xabc").is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! API Versioning
+//!
+//! The REST API is served under `/api/v1` and `/api/v2`. v2 starts as v1
+//! with a uniform error envelope ([`dto::v2::ErrorEnvelope`]): endpoints v2
+//! has not redefined fall through to their v1 handlers, whose error bodies
+//! are rewritten on the way out. v1 responses carry `Deprecation`, `Link`
+//! (successor version) and, once a date is configured, `Sunset` headers.
+//!
+//! `GET /version` reports the build and the supported versions so clients
+//! can pick one. Changes between versions are listed in
+//! `docs/API_CHANGELOG.md`.
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::api::dto::{v1, v2};
+use crate::AppState;
+
+/// Response header naming the API version that served the request
+pub const API_VERSION_HEADER: &str = "api-version";
+
+/// When v1 was deprecated (v2 release), as a Unix timestamp
+const V1_DEPRECATED_AT: i64 = 1_792_195_200;
+
+/// Largest error body rewritten into the v2 envelope
+const MAX_ERROR_BODY: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    /// Every served version, oldest first
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    /// The version new clients should use
+    pub const CURRENT: ApiVersion = ApiVersion::V2;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+
+    pub fn base_path(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/api/v1",
+            ApiVersion::V2 => "/api/v2",
+        }
+    }
+
+    fn deprecated_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            ApiVersion::V1 => DateTime::from_timestamp(V1_DEPRECATED_AT, 0),
+            ApiVersion::V2 => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionStatus {
+    Current,
+    Deprecated,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SupportedVersion {
+    pub version: ApiVersion,
+    pub base_path: &'static str,
+    pub status: VersionStatus,
+    /// Deprecation date (ms)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated_at: Option<i64>,
+    /// Date after which the version may be removed (ms)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunset_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    /// Set at compile time from `GIT_COMMIT_SHA`
+    pub git_commit: Option<&'static str>,
+    /// Set at compile time from `BUILD_TIMESTAMP`
+    pub built_at: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionResponse {
+    pub build: BuildInfo,
+    pub current_version: ApiVersion,
+    pub versions: Vec<SupportedVersion>,
+}
+
+/// Build info and supported API versions
+/// GET /version
+pub async fn get_version(State(state): State<Arc<AppState>>) -> Json<VersionResponse> {
+    let sunset = state.config.api_v1_sunset();
+    Json(VersionResponse {
+        build: BuildInfo {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: option_env!("GIT_COMMIT_SHA"),
+            built_at: option_env!("BUILD_TIMESTAMP"),
+        },
+        current_version: ApiVersion::CURRENT,
+        versions: ApiVersion::ALL
+            .iter()
+            .map(|version| {
+                let deprecated_at = version.deprecated_at();
+                SupportedVersion {
+                    version: *version,
+                    base_path: version.base_path(),
+                    status: if deprecated_at.is_some() {
+                        VersionStatus::Deprecated
+                    } else {
+                        VersionStatus::Current
+                    },
+                    deprecated_at: deprecated_at.map(|t| t.timestamp_millis()),
+                    sunset_at: sunset.filter(|_| *version == ApiVersion::V1).map(|t| t.timestamp_millis()),
+                }
+            })
+            .collect(),
+    })
+}
+
+/// Tag v1 responses as deprecated in favour of v2
+pub async fn v1_deprecation(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(API_VERSION_HEADER, HeaderValue::from_static(ApiVersion::V1.as_str()));
+    if let Some(value) = ApiVersion::V1.deprecated_at().and_then(|t| HeaderValue::from_str(&format!("@{}", t.timestamp())).ok()) {
+        headers.insert("deprecation", value);
+    }
+    if let Some(value) = state.config.api_v1_sunset().and_then(|t| HeaderValue::from_str(&http_date(t)).ok()) {
+        headers.insert("sunset", value);
+    }
+    headers.insert(
+        header::LINK,
+        HeaderValue::from_static(r#"</api/v2>; rel="successor-version""#),
+    );
+    response
+}
+
+/// Tag v2 responses and rewrite v1-shaped JSON errors into the v2 envelope
+pub async fn v2_responses(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+
+    let failed = response.status().is_client_error() || response.status().is_server_error();
+    let mut response = if failed && is_json {
+        rewrite_error(response).await
+    } else {
+        response
+    };
+    response
+        .headers_mut()
+        .insert(API_VERSION_HEADER, HeaderValue::from_static(ApiVersion::V2.as_str()));
+    response
+}
+
+async fn rewrite_error(response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    // Handler error bodies are far below the limit
+    let Ok(bytes) = to_bytes(body, MAX_ERROR_BODY).await else {
+        parts.headers.remove(header::CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    };
+    let body = match serde_json::from_slice::<v1::ErrorResponse>(&bytes) {
+        Ok(error) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Body::from(serde_json::to_vec(&v2::ErrorEnvelope::from(error)).unwrap_or_default())
+        }
+        Err(_) => Body::from(bytes),
+    };
+    Response::from_parts(parts, body)
+}
+
+/// RFC 9110 IMF-fixdate, as used by `Sunset`
+fn http_date(t: DateTime<Utc>) -> String {
+    t.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use axum::Router;
+    use uuid::Uuid;
+
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn test_http_date() {
+        let t = DateTime::parse_from_rfc3339("2027-04-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(http_date(t), "Thu, 01 Apr 2027 00:00:00 GMT");
+    }
+
+    #[tokio::test]
+    async fn test_v1_errors_get_v2_envelope() {
+        let v1_error = (
+            StatusCode::NOT_FOUND,
+            Json(v1::ErrorResponse {
+                error: "Market not found".to_string(),
+                code: "MARKET_NOT_FOUND".to_string(),
+                fields: Vec::new(),
+                retry_after_ms: None,
+            }),
+        )
+            .into_response();
+        let response = rewrite_error(v1_error).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let bytes = to_bytes(response.into_body(), MAX_ERROR_BODY).await.unwrap();
+        let envelope: v2::ErrorEnvelope = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(envelope.error.code, "MARKET_NOT_FOUND");
+        assert_eq!(envelope.error.message, "Market not found");
+
+        // Bodies of any other shape pass through
+        let other = (StatusCode::BAD_REQUEST, "invalid query").into_response();
+        let bytes = to_bytes(rewrite_error(other).await.into_body(), MAX_ERROR_BODY).await.unwrap();
+        assert_eq!(&bytes[..], b"invalid query");
+    }
+
+    #[tokio::test]
+    async fn test_versioned_routing() {
+        let app = TestApp::builder().build().await;
+        let state = app.state.clone();
+        let router: Router = Router::new()
+            .nest(
+                "/api/v1",
+                crate::api::routes::create_router(state.clone())
+                    .layer(axum::middleware::from_fn_with_state(state.clone(), v1_deprecation)),
+            )
+            .nest("/api/v2", crate::api::routes::v2::create_router(state.clone()))
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let missing = Uuid::new_v4();
+
+        let response = reqwest::get(format!("{}/api/v1/markets/{}", base, missing)).await.unwrap();
+        assert_eq!(response.status(), 404);
+        assert_eq!(response.headers()["api-version"], "v1");
+        assert!(response.headers().contains_key("deprecation"));
+        assert_eq!(response.json::<v1::ErrorResponse>().await.unwrap().code, "MARKET_NOT_FOUND");
+
+        // v2 falls through to the v1 handler and wraps its error
+        let response = reqwest::get(format!("{}/api/v2/markets/{}", base, missing)).await.unwrap();
+        assert_eq!(response.status(), 404);
+        assert_eq!(response.headers()["api-version"], "v2");
+        assert!(!response.headers().contains_key("deprecation"));
+        assert_eq!(response.json::<v2::ErrorEnvelope>().await.unwrap().error.code, "MARKET_NOT_FOUND");
+
+        // Field errors from request validation survive the rewrite
+        let response = reqwest::get(format!("{}/api/v2/markets?limit=-5", base)).await.unwrap();
+        assert_eq!(response.status(), 422);
+        let error = response.json::<v2::ErrorEnvelope>().await.unwrap().error;
+        assert_eq!(error.code, "VALIDATION_FAILED");
+        assert_eq!(error.fields[0].field, "limit");
+    }
+}
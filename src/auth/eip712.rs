@@ -10,10 +10,11 @@ use std::sync::OnceLock;
 pub const LOGIN_TYPEHASH: &str = "Login(address wallet,uint256 nonce,uint256 timestamp)";
 pub const CREATE_ORDER_TYPEHASH: &str = "CreateOrder(address wallet,string marketId,string outcomeId,string shareType,string side,string orderType,string price,string amount,uint256 timestamp)";
 pub const CANCEL_ORDER_TYPEHASH: &str = "CancelOrder(address wallet,string orderId,uint256 timestamp)";
+pub const AMEND_ORDER_TYPEHASH: &str = "AmendOrder(address wallet,string orderId,string price,string amount,uint256 timestamp)";
 pub const BATCH_CANCEL_TYPEHASH: &str = "BatchCancelOrders(address wallet,string orderIds,uint256 timestamp)";
 pub const CREATE_REFERRAL_TYPEHASH: &str = "CreateReferralCode(address wallet,uint256 timestamp)";
 pub const BIND_REFERRAL_TYPEHASH: &str = "BindReferralCode(address wallet,string code,uint256 timestamp)";
-pub const WS_AUTH_TYPEHASH: &str = "WebSocketAuth(address wallet,uint256 timestamp)";
+pub const WS_AUTH_TYPEHASH: &str = "WebSocketAuth(address wallet,string nonce,uint256 timestamp)";
 
 /// Global EIP-712 domain configuration (initialized from AppConfig at startup)
 static DOMAIN: OnceLock<EIP712Domain> = OnceLock::new();
@@ -145,6 +146,34 @@ impl CancelOrderMessage {
     }
 }
 
+/// Amend Order message for EIP-712 signature verification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmendOrderMessage {
+    pub wallet: String,
+    pub order_id: String,
+    pub price: String,
+    pub amount: String,
+    pub timestamp: u64,
+}
+
+impl AmendOrderMessage {
+    pub fn struct_hash(&self) -> H256 {
+        let type_hash = keccak256(AMEND_ORDER_TYPEHASH.as_bytes());
+        let wallet_address = Address::from_str(&self.wallet).unwrap_or_default();
+
+        let encoded = ethers::abi::encode(&[
+            Token::FixedBytes(type_hash.to_vec()),
+            Token::Address(wallet_address),
+            Token::FixedBytes(keccak256(self.order_id.as_bytes()).to_vec()),
+            Token::FixedBytes(keccak256(self.price.as_bytes()).to_vec()),
+            Token::FixedBytes(keccak256(self.amount.as_bytes()).to_vec()),
+            Token::Uint(U256::from(self.timestamp)),
+        ]);
+
+        H256::from(keccak256(&encoded))
+    }
+}
+
 /// Batch Cancel Orders message for EIP-712 signature verification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchCancelMessage {
@@ -219,6 +248,8 @@ impl BindReferralMessage {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebSocketAuthMessage {
     pub wallet: String,
+    /// Challenge issued by the server for this connection
+    pub nonce: String,
     pub timestamp: u64,
 }
 
@@ -230,6 +261,7 @@ impl WebSocketAuthMessage {
         let encoded = ethers::abi::encode(&[
             Token::FixedBytes(type_hash.to_vec()),
             Token::Address(wallet_address),
+            Token::FixedBytes(keccak256(self.nonce.as_bytes()).to_vec()),
             Token::Uint(U256::from(self.timestamp)),
         ]);
 
@@ -307,6 +339,17 @@ pub fn verify_cancel_order_signature(
     verify_typed_signature(domain, struct_hash, signature, expected_address)
 }
 
+/// Verify EIP-712 typed data signature for amending an order
+pub fn verify_amend_order_signature(
+    msg: &AmendOrderMessage,
+    signature: &str,
+    expected_address: &str,
+) -> anyhow::Result<bool> {
+    let domain = get_domain();
+    let struct_hash = msg.struct_hash();
+    verify_typed_signature(domain, struct_hash, signature, expected_address)
+}
+
 /// Verify EIP-712 typed data signature for batch canceling orders
 pub fn verify_batch_cancel_signature(
     msg: &BatchCancelMessage,
@@ -519,4 +562,14 @@ mod tests {
         let separator = compute_domain_separator(&domain);
         assert!(!separator.is_zero());
     }
+
+    #[test]
+    fn test_ws_auth_hash_binds_nonce() {
+        let msg = |nonce: &str| WebSocketAuthMessage {
+            wallet: "0x0000000000000000000000000000000000000001".to_string(),
+            nonce: nonce.to_string(),
+            timestamp: 1_700_000_000,
+        };
+        assert_ne!(msg("a").struct_hash(), msg("b").struct_hash());
+    }
 }
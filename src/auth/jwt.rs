@@ -1,12 +1,15 @@
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,       // User address
     pub exp: i64,          // Expiration time
     pub iat: i64,          // Issued at
+    #[serde(default)]
+    pub sid: Option<Uuid>, // Session id (see auth::session)
 }
 
 pub struct JwtManager {
@@ -31,7 +34,8 @@ impl JwtManager {
         }
     }
 
-    pub fn generate_token(&self, address: &str) -> anyhow::Result<String> {
+    /// Token for `address` belonging to session `session_id`
+    pub fn generate_token(&self, address: &str, session_id: Uuid) -> anyhow::Result<String> {
         let now = Utc::now();
         let exp = now + Duration::seconds(self.expiry_seconds as i64);
 
@@ -39,6 +43,7 @@ impl JwtManager {
             sub: address.to_lowercase(),
             exp: exp.timestamp(),
             iat: now.timestamp(),
+            sid: Some(session_id),
         };
 
         let token = encode(&Header::default(), &claims, &self.encoding_key)?;
@@ -61,9 +66,12 @@ mod tests {
         let manager = JwtManager::new("test_secret", 3600);
         let address = "0x1234567890abcdef1234567890abcdef12345678";
 
-        let token = manager.generate_token(address).unwrap();
+        let session_id = Uuid::new_v4();
+
+        let token = manager.generate_token(address, session_id).unwrap();
         let claims = manager.verify_token(&token).unwrap();
 
         assert_eq!(claims.sub, address.to_lowercase());
+        assert_eq!(claims.sid, Some(session_id));
     }
 }
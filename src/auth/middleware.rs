@@ -1,13 +1,15 @@
 use axum::{
     body::Body,
-    extract::State,
-    http::{header, Request, StatusCode},
+    extract::{OriginalUri, State},
+    http::{header, Method, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
 use std::sync::Arc;
 
 use crate::auth::jwt::JwtManager;
+use crate::auth::session;
+use crate::models::Address;
 use crate::AppState;
 
 /// User role enum
@@ -34,8 +36,10 @@ impl UserRole {
 
 #[derive(Clone)]
 pub struct AuthUser {
-    pub address: String,
+    pub address: Address,
     pub role: UserRole,
+    /// Session of the presented token (None with auth disabled)
+    pub session_id: Option<uuid::Uuid>,
 }
 
 pub async fn auth_middleware(
@@ -47,12 +51,14 @@ pub async fn auth_middleware(
     if state.config.is_auth_disabled() {
         // Use a default test address when auth is disabled
         // Try to extract address from header if provided, otherwise use default
-        let address = request
-            .headers()
-            .get("X-Test-Address")
-            .and_then(|h| h.to_str().ok())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "0x0000000000000000000000000000000000000001".to_string());
+        let address = match request.headers().get("X-Test-Address") {
+            Some(header) => header
+                .to_str()
+                .ok()
+                .and_then(|s| s.parse::<Address>().ok())
+                .ok_or(StatusCode::BAD_REQUEST)?,
+            None => ethers::types::H160::from_low_u64_be(1).into(),
+        };
 
         // Check for admin role header in dev mode
         let role = request
@@ -63,7 +69,11 @@ pub async fn auth_middleware(
             .unwrap_or(UserRole::User);
 
         tracing::debug!("Auth disabled - using address: {}, role: {:?}", address, role);
-        request.extensions_mut().insert(AuthUser { address, role });
+        request.extensions_mut().insert(AuthUser {
+            address,
+            role,
+            session_id: None,
+        });
         return Ok(next.run(request).await);
     }
 
@@ -84,13 +94,42 @@ pub async fn auth_middleware(
         .verify_token(token)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
 
-    let address = claims.sub.to_lowercase();
+    let address: Address = claims.sub.parse().map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    // Reject tokens whose session was revoked (or that predate sessions)
+    let session_id = claims.sid.ok_or(StatusCode::UNAUTHORIZED)?;
+    let active = session::is_active(&state.db.pool, session_id, &address)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check session {}: {}", session_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !active {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
 
-    // Fetch user role from database
-    let role = fetch_user_role(&state.db.pool, &address).await;
+    // Fetch user role and freeze/closure state from database; without
+    // them the request is refused rather than let through
+    let (role, standing) = fetch_user_status(&state.db.pool, &address).await.map_err(|e| {
+        tracing::error!("Failed to fetch status of {}: {}", address, e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+    // The full path: v1 and v2 serve different things at POST /orders/batch
+    let path = match request.extensions().get::<OriginalUri>() {
+        Some(uri) => uri.path(),
+        None => request.uri().path(),
+    };
+    if !standing.allows(request.method(), path) {
+        tracing::warn!("Refused {} {} of {:?} account {}", request.method(), path, standing, address);
+        return Err(StatusCode::FORBIDDEN);
+    }
 
     // Insert auth user into request extensions
-    request.extensions_mut().insert(AuthUser { address, role });
+    request.extensions_mut().insert(AuthUser {
+        address,
+        role,
+        session_id: Some(session_id),
+    });
 
     Ok(next.run(request).await)
 }
@@ -126,19 +165,108 @@ pub async fn admin_middleware(
     Ok(next.run(request).await)
 }
 
-/// Fetch user role from database
-async fn fetch_user_role(pool: &sqlx::PgPool, address: &str) -> UserRole {
-    let result: Option<(String,)> = sqlx::query_as(
-        r#"SELECT role::text FROM users WHERE address = $1"#
+/// What an account may still do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Standing {
+    Active,
+    Frozen,
+    /// Closure requested, waiting for the account to be emptied
+    Closing,
+    Closed,
+}
+
+impl Standing {
+    fn allows(self, method: &Method, path: &str) -> bool {
+        match self {
+            Standing::Active => true,
+            Standing::Frozen => allowed_while_frozen(method, path),
+            Standing::Closing => allowed_while_closing(method, path),
+            Standing::Closed => false,
+        }
+    }
+}
+
+/// Frozen accounts may still read and cancel (orders, sessions), but not
+/// place, transfer or withdraw
+fn allowed_while_frozen(method: &Method, path: &str) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::DELETE)
+        || (*method == Method::POST && is_cancel_route(path))
+}
+
+/// Cancels served over POST; v1 serves batch cancel at `POST /orders/batch`,
+/// where v2 places a batch
+fn is_cancel_route(path: &str) -> bool {
+    path.ends_with("/orders/cancel-all") || path.ends_with("/orders/cancel-all-after") || path == "/api/v1/orders/batch"
+}
+
+/// Closing accounts may also exit their positions, withdraw what they hold
+/// and retry the closure
+fn allowed_while_closing(method: &Method, path: &str) -> bool {
+    allowed_while_frozen(method, path)
+        || (*method == Method::POST
+            && (path.contains("/withdraw/") || path.ends_with("/account/closure") || is_position_close(path)))
+}
+
+/// `POST /account/positions/:market_id/close`, which only ever sells
+fn is_position_close(path: &str) -> bool {
+    path.strip_suffix("/close").is_some_and(|rest| rest.rsplit_once('/').is_some_and(|(head, id)| {
+        !id.is_empty() && head.ends_with("/account/positions")
+    }))
+}
+
+/// Fetch user role and whether the account is frozen or closing from database
+async fn fetch_user_status(pool: &sqlx::PgPool, address: &Address) -> Result<(UserRole, Standing), sqlx::Error> {
+    let result: Option<(String, bool, bool, bool)> = sqlx::query_as(
+        r#"
+        SELECT role::text, frozen_at IS NOT NULL, closure_requested_at IS NOT NULL, closed_at IS NOT NULL
+        FROM users WHERE address = $1
+        "#
     )
     .bind(address)
     .fetch_optional(pool)
-    .await
-    .ok()
-    .flatten();
+    .await?;
+
+    Ok(match result {
+        Some((role_str, frozen, closing, closed)) => {
+            let standing = match (frozen, closing, closed) {
+                (_, _, true) => Standing::Closed,
+                (true, _, _) => Standing::Frozen,
+                (_, true, _) => Standing::Closing,
+                _ => Standing::Active,
+            };
+            (UserRole::from_str(&role_str), standing)
+        }
+        None => (UserRole::User, Standing::Active),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frozen_accounts_may_only_read_and_cancel() {
+        assert!(allowed_while_frozen(&Method::GET, "/api/v1/orders"));
+        assert!(allowed_while_frozen(&Method::DELETE, "/api/v1/orders/batch"));
+        assert!(allowed_while_frozen(&Method::POST, "/api/v1/orders/cancel-all"));
+        assert!(allowed_while_frozen(&Method::POST, "/api/v2/orders/cancel-all-after"));
+        assert!(allowed_while_frozen(&Method::POST, "/api/v1/orders/batch"));
+        assert!(!allowed_while_frozen(&Method::POST, "/api/v2/orders/batch"));
+        assert!(!allowed_while_frozen(&Method::POST, "/api/v1/orders"));
+        assert!(!allowed_while_frozen(&Method::PUT, "/api/v1/orders/1"));
+    }
 
-    match result {
-        Some((role_str,)) => UserRole::from_str(&role_str),
-        None => UserRole::User,
+    #[test]
+    fn test_closing_accounts_may_also_withdraw_and_exit() {
+        let closing = Standing::Closing;
+        assert!(closing.allows(&Method::GET, "/account/export"));
+        assert!(closing.allows(&Method::POST, "/withdraw/request"));
+        assert!(closing.allows(&Method::POST, "/account/closure"));
+        assert!(closing.allows(&Method::POST, "/api/v1/orders/cancel-all"));
+        assert!(closing.allows(&Method::POST, "/api/v1/account/positions/0f3c/close"));
+        assert!(!closing.allows(&Method::POST, "/api/v1/account/positions//close"));
+        assert!(!closing.allows(&Method::POST, "/orders"));
+        assert!(!closing.allows(&Method::POST, "/account/transfer"));
+        assert!(!Standing::Closed.allows(&Method::GET, "/account/profile"));
     }
 }
@@ -1,7 +1,11 @@
 pub mod eip712;
 pub mod jwt;
 pub mod middleware;
-// pub mod rate_limit; // Rate limiting handled by nginx for HFT performance
+pub mod session;
+// Rate limiting for the trading API is handled by nginx for HFT performance;
+// the in-process limiter only guards the public widget namespace.
+#[allow(dead_code)]
+pub mod rate_limit;
 
 // pub use eip712::*;
 // pub use jwt::*;
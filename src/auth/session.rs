@@ -0,0 +1,158 @@
+//! JWT Session Tracking
+//!
+//! Every login creates a `user_sessions` row whose id travels in the token
+//! as the `sid` claim. Authentication accepts a token only while its
+//! session is active, which makes tokens revocable before they expire.
+
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::jwt::{validate_token, Claims};
+use crate::models::Address;
+
+/// An active login session
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Session {
+    pub id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub issued_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Record a new session for `address`
+pub async fn create(
+    pool: &PgPool,
+    id: Uuid,
+    address: &str,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_sessions (id, user_address, ip_address, user_agent, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(id)
+    .bind(address)
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Whether session `id` of `address` is active. Refreshes its last use at
+/// most once a minute, so busy clients don't write on every request.
+pub async fn is_active(pool: &PgPool, id: Uuid, address: &Address) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        WITH session AS (
+            SELECT id, last_used_at
+            FROM user_sessions
+            WHERE id = $1 AND user_address = $2 AND revoked_at IS NULL AND expires_at > NOW()
+        ),
+        touched AS (
+            UPDATE user_sessions u
+            SET last_used_at = NOW()
+            FROM session s
+            WHERE u.id = s.id AND s.last_used_at < NOW() - INTERVAL '1 minute'
+        )
+        SELECT EXISTS (SELECT 1 FROM session)
+        "#,
+    )
+    .bind(id)
+    .bind(address)
+    .fetch_one(pool)
+    .await
+}
+
+/// Decode `token` and check its session is still active
+pub async fn validate_session_token(pool: &PgPool, token: &str, secret: &str) -> anyhow::Result<Claims> {
+    let claims = validate_token(token, secret)?;
+    let sid = claims.sid.ok_or_else(|| anyhow::anyhow!("token has no session"))?;
+    let address: Address = claims.sub.parse()?;
+    if !is_active(pool, sid, &address).await? {
+        anyhow::bail!("session revoked or expired");
+    }
+    Ok(claims)
+}
+
+/// Active sessions of `address`, newest first
+pub async fn list_active(pool: &PgPool, address: &Address) -> Result<Vec<Session>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, ip_address, user_agent, issued_at, last_used_at, expires_at
+        FROM user_sessions
+        WHERE user_address = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY issued_at DESC
+        "#,
+    )
+    .bind(address)
+    .fetch_all(pool)
+    .await
+}
+
+/// Revoke one session of `address`; false if it was not active
+pub async fn revoke(pool: &PgPool, address: &Address, id: Uuid) -> Result<bool, sqlx::Error> {
+    let revoked = sqlx::query(
+        "UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1 AND user_address = $2 AND revoked_at IS NULL",
+    )
+    .bind(id)
+    .bind(address)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(revoked > 0)
+}
+
+/// Revoke every active session of `address` except `keep`. Returns the
+/// number of sessions revoked.
+pub async fn revoke_all(pool: &PgPool, address: &Address, keep: Option<Uuid>) -> Result<u64, sqlx::Error> {
+    let revoked = sqlx::query(
+        r#"
+        UPDATE user_sessions SET revoked_at = NOW()
+        WHERE user_address = $1 AND revoked_at IS NULL AND ($2::uuid IS NULL OR id <> $2)
+        "#,
+    )
+    .bind(address)
+    .bind(keep)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(revoked)
+}
+
+/// Client address as reported by the reverse proxy
+pub fn client_ip(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("X-Forwarded-For")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .or_else(|| headers.get("X-Real-IP").and_then(|v| v.to_str().ok()))
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_ip_prefers_first_forwarded_hop() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_ip(&headers), None);
+
+        headers.insert("X-Real-IP", "10.0.0.2".parse().unwrap());
+        assert_eq!(client_ip(&headers).as_deref(), Some("10.0.0.2"));
+
+        headers.insert("X-Forwarded-For", "203.0.113.7, 10.0.0.1".parse().unwrap());
+        assert_eq!(client_ip(&headers).as_deref(), Some("203.0.113.7"));
+    }
+}
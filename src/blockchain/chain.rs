@@ -0,0 +1,113 @@
+//! Settlement chain interface
+//!
+//! The subset of [`BlockchainClient`] that trade settlement and No-set
+//! conversions need, so they can run against a scripted chain in tests.
+
+use async_trait::async_trait;
+use ethers::types::{Address, Bytes, H256, U256};
+
+use crate::blockchain::client::BlockchainClient;
+use crate::blockchain::types::{ContractAddresses, OnChainOrder, TxResult};
+
+type ChainError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Contract calls made by the settlement service and No-set conversions
+// Token id lookups are not used by order entry yet
+#[allow(dead_code)]
+#[async_trait]
+pub trait SettlementChain: Send + Sync {
+    fn addresses(&self) -> &ContractAddresses;
+
+    /// Submit a matched order pair to `CTFExchange.matchOrders`
+    async fn match_orders(
+        &self,
+        maker_order: &OnChainOrder,
+        taker_order: &OnChainOrder,
+        maker_signature: Bytes,
+        taker_signature: Bytes,
+        maker_fill_amount: U256,
+        taker_fill_amount: U256,
+    ) -> Result<TxResult, ChainError>;
+
+    async fn get_collection_id(
+        &self,
+        parent_collection_id: [u8; 32],
+        condition_id: [u8; 32],
+        index_set: U256,
+    ) -> Result<[u8; 32], ChainError>;
+
+    async fn get_position_id(&self, collateral_token: Address, collection_id: [u8; 32]) -> Result<U256, ChainError>;
+
+    async fn get_outcome_slot_count(&self, condition_id: [u8; 32]) -> Result<U256, ChainError>;
+
+    /// Mirror a No-set conversion through `NegRiskAdapter.convertPositions`;
+    /// returns the transaction hash once sent, without waiting for it to be
+    /// mined
+    async fn convert_positions(
+        &self,
+        neg_risk_market_id: [u8; 32],
+        index_set: U256,
+        amount: U256,
+    ) -> Result<H256, ChainError>;
+
+    /// Outcome of a sent transaction; `None` while it is not mined
+    async fn transaction_result(&self, tx_hash: H256) -> Result<Option<TxResult>, ChainError>;
+}
+
+#[async_trait]
+impl SettlementChain for BlockchainClient {
+    fn addresses(&self) -> &ContractAddresses {
+        BlockchainClient::addresses(self)
+    }
+
+    async fn match_orders(
+        &self,
+        maker_order: &OnChainOrder,
+        taker_order: &OnChainOrder,
+        maker_signature: Bytes,
+        taker_signature: Bytes,
+        maker_fill_amount: U256,
+        taker_fill_amount: U256,
+    ) -> Result<TxResult, ChainError> {
+        BlockchainClient::match_orders(
+            self,
+            maker_order,
+            taker_order,
+            maker_signature,
+            taker_signature,
+            maker_fill_amount,
+            taker_fill_amount,
+        )
+        .await
+    }
+
+    async fn get_collection_id(
+        &self,
+        parent_collection_id: [u8; 32],
+        condition_id: [u8; 32],
+        index_set: U256,
+    ) -> Result<[u8; 32], ChainError> {
+        BlockchainClient::get_collection_id(self, parent_collection_id, condition_id, index_set).await
+    }
+
+    async fn get_position_id(&self, collateral_token: Address, collection_id: [u8; 32]) -> Result<U256, ChainError> {
+        BlockchainClient::get_position_id(self, collateral_token, collection_id).await
+    }
+
+    async fn get_outcome_slot_count(&self, condition_id: [u8; 32]) -> Result<U256, ChainError> {
+        BlockchainClient::get_outcome_slot_count(self, condition_id).await
+    }
+
+    async fn convert_positions(
+        &self,
+        neg_risk_market_id: [u8; 32],
+        index_set: U256,
+        amount: U256,
+    ) -> Result<H256, ChainError> {
+        BlockchainClient::convert_positions(self, neg_risk_market_id, index_set, amount).await
+    }
+
+    async fn transaction_result(&self, tx_hash: H256) -> Result<Option<TxResult>, ChainError> {
+        BlockchainClient::transaction_result(self, tx_hash).await
+    }
+}
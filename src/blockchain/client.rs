@@ -8,11 +8,13 @@ use ethers::signers::{LocalWallet, Signer};
 use ethers::types::{Address, Bytes, H256, U256};
 
 use crate::blockchain::contracts::{
-    ConditionalTokensContract, CTFExchangeContract, MockUSDCContract,
+    ConditionalTokensContract, CTFExchangeContract, ForwardRequest, MinimalForwarderContract, MockUSDCContract,
+    NegRiskAdapterContract,
 };
+use crate::blockchain::signer::OperatorSigner;
 use crate::blockchain::types::{ContractAddresses, OnChainOrder, TxResult, TxStatus, VerifiedTransfer};
 
-type SignerMiddleware = ethers::middleware::SignerMiddleware<Provider<Http>, LocalWallet>;
+type SignerMiddleware = ethers::middleware::SignerMiddleware<Provider<Http>, OperatorSigner>;
 
 /// Blockchain client for interacting with prediction market contracts
 #[derive(Clone)]
@@ -41,10 +43,21 @@ impl BlockchainClient {
         private_key: &str,
         addresses: ContractAddresses,
         chain_id: u64,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let wallet: LocalWallet = private_key.parse::<LocalWallet>()?;
+        Self::with_operator_signer(rpc_url, OperatorSigner::Local(wallet), addresses, chain_id)
+    }
+
+    /// Create a new blockchain client signing through an operator signer
+    /// (local key, keystore, Vault or AWS KMS)
+    pub fn with_operator_signer(
+        rpc_url: &str,
+        operator: OperatorSigner,
+        addresses: ContractAddresses,
+        chain_id: u64,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let provider = Provider::<Http>::try_from(rpc_url)?;
-        let wallet: LocalWallet = private_key.parse::<LocalWallet>()?.with_chain_id(chain_id);
-        let signer = SignerMiddleware::new(provider.clone(), wallet);
+        let signer = SignerMiddleware::new(provider.clone(), operator.with_chain_id(chain_id));
 
         Ok(Self {
             provider: Arc::new(provider),
@@ -214,6 +227,39 @@ impl BlockchainClient {
         Ok(balance)
     }
 
+    /// Calldata of splitPosition for the caller to sign and send themselves
+    pub fn split_position_calldata(
+        &self,
+        collateral_token: Address,
+        parent_collection_id: [u8; 32],
+        condition_id: [u8; 32],
+        partition: Vec<U256>,
+        amount: U256,
+    ) -> Option<Bytes> {
+        self.ctf()
+            .split_position(collateral_token, parent_collection_id, condition_id, partition, amount)
+            .calldata()
+    }
+
+    /// Calldata of mergePositions for the caller to sign and send themselves
+    pub fn merge_positions_calldata(
+        &self,
+        collateral_token: Address,
+        parent_collection_id: [u8; 32],
+        condition_id: [u8; 32],
+        partition: Vec<U256>,
+        amount: U256,
+    ) -> Option<Bytes> {
+        self.ctf()
+            .merge_positions(collateral_token, parent_collection_id, condition_id, partition, amount)
+            .calldata()
+    }
+
+    /// Calldata of a USDC approve for the caller to sign and send themselves
+    pub fn approve_usdc_calldata(&self, spender: Address, amount: U256) -> Option<Bytes> {
+        self.usdc().approve(spender, amount).calldata()
+    }
+
     /// Prepare a new condition
     pub async fn prepare_condition(
         &self,
@@ -394,6 +440,79 @@ impl BlockchainClient {
         Ok(self.parse_receipt(receipt))
     }
 
+    // ============ NegRiskAdapter Methods ============
+
+    /// Get NegRiskAdapter contract instance (read-only)
+    pub fn neg_risk_adapter(&self) -> Result<NegRiskAdapterContract<Provider<Http>>, &'static str> {
+        let adapter = self.addresses.neg_risk_adapter.ok_or("No NegRiskAdapter configured")?;
+        Ok(NegRiskAdapterContract::new(adapter, self.provider.clone()))
+    }
+
+    /// Number of questions (markets) prepared under a negative-risk market
+    pub async fn get_neg_risk_question_count(
+        &self,
+        neg_risk_market_id: [u8; 32],
+    ) -> Result<U256, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.neg_risk_adapter()?.get_question_count(neg_risk_market_id).call().await?)
+    }
+
+    /// Convert the signer's No positions in the questions of `index_set`
+    /// into collateral and Yes positions in the other questions. Returns
+    /// the transaction hash once sent; see [`Self::transaction_result`].
+    pub async fn convert_positions(
+        &self,
+        neg_risk_market_id: [u8; 32],
+        index_set: U256,
+        amount: U256,
+    ) -> Result<H256, Box<dyn std::error::Error + Send + Sync>> {
+        let adapter = self.addresses.neg_risk_adapter.ok_or("No NegRiskAdapter configured")?;
+        let signer = self.get_signer()?;
+        let contract = NegRiskAdapterContract::new(adapter, signer);
+        let call = contract.convert_positions(neg_risk_market_id, index_set, amount);
+        let pending_tx = call.send().await?;
+        Ok(pending_tx.tx_hash())
+    }
+
+    // ============ Forwarder (ERC-2771) Methods ============
+
+    /// Next meta-transaction nonce of `from` at the forwarder
+    pub async fn get_forwarder_nonce(
+        &self,
+        forwarder: Address,
+        from: Address,
+    ) -> Result<U256, Box<dyn std::error::Error + Send + Sync>> {
+        let contract = MinimalForwarderContract::new(forwarder, self.provider.clone());
+        Ok(contract.get_nonce(from).call().await?)
+    }
+
+    /// Whether the forwarder accepts a signed request (signature by
+    /// `req.from` and current nonce)
+    pub async fn verify_forward_request(
+        &self,
+        forwarder: Address,
+        req: ForwardRequest,
+        signature: Bytes,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let contract = MinimalForwarderContract::new(forwarder, self.provider.clone());
+        Ok(contract.verify(req, signature).call().await?)
+    }
+
+    /// Execute a signed request through the forwarder, paying gas from the
+    /// operator
+    pub async fn execute_forward_request(
+        &self,
+        forwarder: Address,
+        req: ForwardRequest,
+        signature: Bytes,
+    ) -> Result<TxResult, Box<dyn std::error::Error + Send + Sync>> {
+        let signer = self.get_signer()?;
+        let contract = MinimalForwarderContract::new(forwarder, signer);
+        let call = contract.execute(req, signature);
+        let pending_tx = call.send().await?;
+        let receipt = pending_tx.await?;
+        Ok(self.parse_receipt(receipt))
+    }
+
     // ============ Utility Methods ============
 
     /// Get current block number
@@ -402,6 +521,12 @@ impl BlockchainClient {
         Ok(block.as_u64())
     }
 
+    /// Get current gas price (wei)
+    pub async fn get_gas_price(&self) -> Result<U256, Box<dyn std::error::Error + Send + Sync>> {
+        let gas_price = self.provider.get_gas_price().await?;
+        Ok(gas_price)
+    }
+
     /// Get ETH balance
     pub async fn get_eth_balance(&self, address: Address) -> Result<U256, Box<dyn std::error::Error + Send + Sync>> {
         let balance = self.provider.get_balance(address, None).await?;
@@ -417,6 +542,15 @@ impl BlockchainClient {
         Ok(receipt)
     }
 
+    /// Outcome of a sent transaction; `None` while it is not mined
+    pub async fn transaction_result(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<TxResult>, Box<dyn std::error::Error + Send + Sync>> {
+        let receipt = self.provider.get_transaction_receipt(tx_hash).await?;
+        Ok(receipt.map(|receipt| self.parse_receipt(Some(receipt))))
+    }
+
     /// Verify a USDC transfer transaction
     ///
     /// This method verifies that a transaction:
@@ -30,6 +30,22 @@ abigen!(
     event_derives(serde::Deserialize, serde::Serialize)
 );
 
+// Generate type-safe bindings for the ERC-2771 MinimalForwarder used by
+// the gasless relayer
+abigen!(
+    MinimalForwarderContract,
+    "src/blockchain/abi/MinimalForwarder.json",
+    event_derives(serde::Deserialize, serde::Serialize)
+);
+
+// Generate type-safe bindings for the NegRiskAdapter, which converts No
+// positions across the markets of a negative-risk event
+abigen!(
+    NegRiskAdapterContract,
+    "src/blockchain/abi/NegRiskAdapter.json",
+    event_derives(serde::Deserialize, serde::Serialize)
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
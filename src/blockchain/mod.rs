@@ -1,16 +1,21 @@
 //! Blockchain integration module for Polymarket prediction markets
 //!
 //! This module provides:
-//! - Contract bindings for MockUSDC, ConditionalTokens, and CTFExchange
+//! - Contract bindings for MockUSDC, ConditionalTokens, CTFExchange and NegRiskAdapter
 //! - Blockchain client for interacting with contracts
+//! - Settlement chain interface, mockable in tests
+//! - Operator signer backed by env, keystore, Vault or AWS KMS
 //! - Event listener for monitoring on-chain events
 //! - Transaction management utilities
 
+pub mod chain;
 pub mod client;
 pub mod contracts;
 pub mod events;
+pub mod signer;
 pub mod types;
 
+pub use chain::SettlementChain;
 pub use client::BlockchainClient;
 pub use events::{BlockchainEvent, EventListener};
 pub use types::ContractAddresses;
@@ -0,0 +1,264 @@
+//! Operator Signer
+//!
+//! The operator key signs CTF, settlement and withdrawal transactions. In
+//! development it can be read straight from the environment; in production
+//! it should come from a secret provider so the raw key never sits in plain
+//! env vars:
+//!
+//! - `encrypted_file`: an Ethereum JSON keystore, decrypted at startup
+//! - `vault`: a HashiCorp Vault KV secret, fetched at startup
+//! - `aws_kms`: an AWS KMS secp256k1 key; digests are signed remotely and
+//!   the key never leaves KMS
+//!
+//! Every provider yields an [`OperatorSigner`], which implements ethers'
+//! [`Signer`] so [`BlockchainClient`](super::BlockchainClient) does not care
+//! where the key lives.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ethers::signers::{AwsSigner, AwsSignerError, LocalWallet, Signer, WalletError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, Signature};
+use rusoto_core::Region;
+use rusoto_kms::KmsClient;
+
+/// Supported `SIGNER_PROVIDER` values
+pub const SIGNER_PROVIDERS: [&str; 4] = ["env", "encrypted_file", "vault", "aws_kms"];
+
+/// Timeout for the Vault secret fetch
+const VAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SignerError {
+    #[error("signer provider misconfigured: {0}")]
+    Config(String),
+    #[error("failed to load operator key: {0}")]
+    Load(String),
+    #[error(transparent)]
+    Wallet(#[from] WalletError),
+    /// Boxed: rusoto errors are large
+    #[error(transparent)]
+    AwsKms(Box<AwsSignerError>),
+}
+
+impl From<AwsSignerError> for SignerError {
+    fn from(e: AwsSignerError) -> Self {
+        SignerError::AwsKms(Box::new(e))
+    }
+}
+
+/// Keystore password, inline or read from a file (e.g. a mounted secret)
+#[derive(Clone)]
+pub enum KeystorePassword {
+    Inline(String),
+    File(PathBuf),
+}
+
+impl KeystorePassword {
+    fn resolve(&self) -> Result<String, SignerError> {
+        match self {
+            KeystorePassword::Inline(password) => Ok(password.clone()),
+            KeystorePassword::File(path) => std::fs::read_to_string(path)
+                .map(|s| s.trim_end_matches(['\r', '\n']).to_string())
+                .map_err(|e| SignerError::Load(format!("cannot read {}: {}", path.display(), e))),
+        }
+    }
+}
+
+/// Where the operator signer is loaded from. Deliberately not `Debug`:
+/// several variants carry secrets.
+#[derive(Clone)]
+pub enum SignerSource {
+    Env {
+        private_key: String,
+    },
+    EncryptedFile {
+        path: PathBuf,
+        password: KeystorePassword,
+    },
+    Vault {
+        addr: String,
+        token: String,
+        path: String,
+        field: String,
+    },
+    AwsKms {
+        key_id: String,
+        /// Falls back to `AWS_DEFAULT_REGION` / `AWS_REGION`
+        region: Option<String>,
+    },
+}
+
+impl SignerSource {
+    /// `SIGNER_PROVIDER` name
+    pub fn provider(&self) -> &'static str {
+        match self {
+            SignerSource::Env { .. } => "env",
+            SignerSource::EncryptedFile { .. } => "encrypted_file",
+            SignerSource::Vault { .. } => "vault",
+            SignerSource::AwsKms { .. } => "aws_kms",
+        }
+    }
+
+    /// Load the signer, bound to `chain_id`
+    pub async fn load(&self, chain_id: u64) -> Result<OperatorSigner, SignerError> {
+        let signer = match self {
+            SignerSource::Env { private_key } => OperatorSigner::Local(private_key.parse::<LocalWallet>()?),
+            SignerSource::EncryptedFile { path, password } => {
+                let password = password.resolve()?;
+                let path = path.clone();
+                // Keystore decryption runs scrypt; keep it off the async workers
+                let wallet = tokio::task::spawn_blocking(move || LocalWallet::decrypt_keystore(path, password))
+                    .await
+                    .map_err(|e| SignerError::Load(e.to_string()))??;
+                OperatorSigner::Local(wallet)
+            }
+            SignerSource::Vault { addr, token, path, field } => {
+                let key = fetch_vault_secret(addr, token, path, field).await?;
+                OperatorSigner::Local(key.parse::<LocalWallet>()?)
+            }
+            SignerSource::AwsKms { key_id, region } => {
+                let region = match region {
+                    Some(region) => region
+                        .parse::<Region>()
+                        .map_err(|e| SignerError::Config(format!("invalid AWS region: {}", e)))?,
+                    None => Region::default(),
+                };
+                OperatorSigner::AwsKms(AwsSigner::new(KmsClient::new(region), key_id, chain_id).await?)
+            }
+        };
+
+        Ok(signer.with_chain_id(chain_id))
+    }
+}
+
+/// Read `field` from the secret at `path` (e.g. `secret/data/operator`)
+async fn fetch_vault_secret(addr: &str, token: &str, path: &str, field: &str) -> Result<String, SignerError> {
+    let url = format!("{}/v1/{}", addr.trim_end_matches('/'), path.trim_start_matches('/'));
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header("X-Vault-Token", token)
+        .timeout(VAULT_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| SignerError::Load(format!("vault request failed: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(SignerError::Load(format!("vault returned {} for {}", response.status(), path)));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| SignerError::Load(format!("invalid vault response: {}", e)))?;
+
+    vault_secret_field(&body, field)
+        .map(str::to_string)
+        .ok_or_else(|| SignerError::Load(format!("vault secret {} has no '{}' field", path, field)))
+}
+
+/// KV v2 nests the secret under `data.data`, KV v1 directly under `data`
+fn vault_secret_field<'a>(body: &'a serde_json::Value, field: &str) -> Option<&'a str> {
+    let data = &body["data"];
+    let data = if data["data"].is_object() { &data["data"] } else { data };
+    data[field].as_str()
+}
+
+/// Operator signer backed by a local key or a remote KMS key
+#[derive(Debug, Clone)]
+pub enum OperatorSigner {
+    Local(LocalWallet),
+    AwsKms(AwsSigner),
+}
+
+#[async_trait]
+impl Signer for OperatorSigner {
+    type Error = SignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        match self {
+            OperatorSigner::Local(wallet) => Ok(wallet.sign_message(message).await?),
+            OperatorSigner::AwsKms(signer) => Ok(signer.sign_message(message).await?),
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            OperatorSigner::Local(wallet) => Ok(wallet.sign_transaction(tx).await?),
+            OperatorSigner::AwsKms(signer) => Ok(signer.sign_transaction(tx).await?),
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        match self {
+            OperatorSigner::Local(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            OperatorSigner::AwsKms(signer) => Ok(signer.sign_typed_data(payload).await?),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            OperatorSigner::Local(wallet) => wallet.address(),
+            OperatorSigner::AwsKms(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            OperatorSigner::Local(wallet) => wallet.chain_id(),
+            OperatorSigner::AwsKms(signer) => signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            OperatorSigner::Local(wallet) => OperatorSigner::Local(wallet.with_chain_id(chain_id)),
+            OperatorSigner::AwsKms(signer) => OperatorSigner::AwsKms(signer.with_chain_id(chain_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn test_vault_secret_field_kv_versions() {
+        let v2 = serde_json::json!({ "data": { "data": { "private_key": "0xabc" }, "metadata": {} } });
+        let v1 = serde_json::json!({ "data": { "private_key": "0xdef" } });
+        assert_eq!(vault_secret_field(&v2, "private_key"), Some("0xabc"));
+        assert_eq!(vault_secret_field(&v1, "private_key"), Some("0xdef"));
+        assert_eq!(vault_secret_field(&v1, "missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_file_matches_env_key() {
+        let dir = std::env::temp_dir().join(format!("signer-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let password_file = dir.join("password");
+        std::fs::write(&password_file, "hunter2\n").unwrap();
+        LocalWallet::encrypt_keystore(&dir, &mut rand::thread_rng(), hex::decode(KEY).unwrap(), "hunter2", Some("operator"))
+            .unwrap();
+
+        let from_file = SignerSource::EncryptedFile {
+            path: dir.join("operator"),
+            password: KeystorePassword::File(password_file),
+        }
+        .load(31337)
+        .await
+        .unwrap();
+        let from_env = SignerSource::Env { private_key: KEY.to_string() }.load(31337).await.unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(from_file.address(), from_env.address());
+        assert_eq!(from_file.chain_id(), 31337);
+        assert_eq!(
+            from_file.sign_message("hello").await.unwrap(),
+            from_env.sign_message("hello").await.unwrap()
+        );
+    }
+}
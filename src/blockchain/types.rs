@@ -227,6 +227,8 @@ pub struct ContractAddresses {
     pub conditional_tokens: Address,
     pub ctf_exchange: Address,
     pub uma_oracle: Option<Address>,
+    /// NegRiskAdapter for No-set conversions; not deployed on every network
+    pub neg_risk_adapter: Option<Address>,
 }
 
 impl Default for ContractAddresses {
@@ -247,6 +249,7 @@ impl Default for ContractAddresses {
                     .parse()
                     .unwrap(),
             ),
+            neg_risk_adapter: None,
         }
     }
 }
@@ -23,6 +23,7 @@ pub mod prefix {
     pub const OUTCOME: &str = "outcome";
     pub const SHARE: &str = "share";
     pub const PROBABILITY: &str = "prob";
+    pub const FEATURE_FLAGS: &str = "flags";
 }
 
 /// Cache TTL values in seconds
@@ -58,6 +59,8 @@ pub mod ttl {
     pub const SHARES: u64 = 10;
     /// Market orderbook TTL (2 seconds)
     pub const MARKET_ORDERBOOK: u64 = 2;
+    /// Feature flag set TTL (30 seconds)
+    pub const FEATURE_FLAGS: u64 = 30;
 }
 
 /// Cache key builders
@@ -377,6 +380,13 @@ impl CacheKey {
     pub fn pattern_user_shares(address: &str) -> String {
         format!("{}:{}:*", prefix::SHARE, address.to_lowercase())
     }
+
+    // ==================== Feature Flag Keys ====================
+
+    /// Key for the full feature flag set: flags:all
+    pub fn feature_flags() -> String {
+        format!("{}:all", prefix::FEATURE_FLAGS)
+    }
 }
 
 #[cfg(test)]
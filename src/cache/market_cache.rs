@@ -40,6 +40,10 @@ pub struct CachedOutcome {
     pub id: Uuid,
     pub name: String,
     pub probability: Decimal,
+    #[serde(default)]
+    pub twap_1h: Option<Decimal>,
+    #[serde(default)]
+    pub twap_24h: Option<Decimal>,
 }
 
 /// Cached user share holding
@@ -456,6 +460,8 @@ mod tests {
                 id: Uuid::new_v4(),
                 name: "Yes".to_string(),
                 probability: Decimal::new(55, 2),
+                twap_1h: None,
+                twap_24h: None,
             }],
             volume_24h: Decimal::new(1000, 0),
             total_volume: Decimal::new(50000, 0),
@@ -59,6 +59,7 @@ pub mod orderbook_cache;
 pub mod price_cache;
 pub mod pubsub;
 pub mod redis_client;
+pub mod response_cache;
 pub mod user_cache;
 
 use std::sync::Arc;
@@ -69,6 +70,7 @@ pub use orderbook_cache::OrderbookCache;
 pub use price_cache::PriceCache;
 pub use pubsub::PubSubManager;
 pub use redis_client::{RedisClient, RedisConfig};
+pub use response_cache::ResponseCache;
 pub use user_cache::UserCache;
 
 /// Cache configuration
@@ -0,0 +1,83 @@
+//! In-Process Response Cache
+//!
+//! Short-TTL cache of serialized JSON responses for the public widget API.
+//! Embeds generate many identical requests, so even a few seconds of caching
+//! collapses them into one database / engine read per key. Kept in memory
+//! rather than Redis so widget traffic never touches the shared cache.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde_json::Value;
+
+/// Entries are swept once the map grows past this many keys
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// TTL cache of JSON values keyed by request
+pub struct ResponseCache {
+    entries: DashMap<String, (Instant, Value)>,
+    ttl: Duration,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: DashMap::new(),
+            ttl,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Fresh cached value for `key`
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let entry = self.entries.get(key)?;
+        let (stored_at, value) = entry.value();
+        (stored_at.elapsed() < self.ttl).then(|| value.clone())
+    }
+
+    pub fn insert(&self, key: String, value: Value) {
+        if self.entries.len() >= SWEEP_THRESHOLD {
+            let ttl = self.ttl;
+            self.entries.retain(|_, (stored_at, _)| stored_at.elapsed() < ttl);
+        }
+        self.entries.insert(key, (Instant::now(), value));
+    }
+
+    /// Return the cached value or compute, cache and return it. Errors are
+    /// not cached.
+    pub async fn get_or_try_insert<F, Fut, E>(&self, key: String, compute: F) -> Result<Value, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value, E>>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+        let value = compute().await?;
+        self.insert(key, value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_get_or_try_insert_caches_until_expiry() {
+        let cache = ResponseCache::new(Duration::from_millis(50));
+
+        let first: Result<Value, ()> = cache.get_or_try_insert("k".into(), || async { Ok(json!(1)) }).await;
+        assert_eq!(first, Ok(json!(1)));
+        let cached: Result<Value, ()> = cache.get_or_try_insert("k".into(), || async { Ok(json!(2)) }).await;
+        assert_eq!(cached, Ok(json!(1)));
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(cache.get("k").is_none());
+    }
+}
@@ -0,0 +1,627 @@
+//! Command Line Interface
+//!
+//! `polymarket-backend` (or `polymarket-backend serve`) runs the API server.
+//! `--role api` / `--role keeper` split request serving from the background
+//! workers so each half can be scaled and deployed on its own; the default
+//! `all` runs both in one process. The remaining subcommands are one-off operator tasks that share the
+//! server's configuration loading and database setup, so routine
+//! maintenance does not need direct psql access.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::validation::{self, Severity};
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::models::Address;
+use crate::services::backfill::{BackfillService, ImportBundle};
+use crate::services::channel_gateway::{ChannelEventType, ChannelGateway, ChannelGatewayConfig};
+use crate::services::export::{DataExporter, ExportFormat};
+use crate::services::resolution_evidence::{self, ResolutionEvidence, ResolutionMethod};
+use crate::services::sandbox::{self, SandboxError, SandboxParams};
+use crate::services::webhook::{WebhookConfig, WebhookEventType, WebhookService};
+
+#[derive(Debug, Parser)]
+#[command(name = "polymarket-backend", version, about = "Polymarket prediction market backend")]
+pub struct Cli {
+    /// Which part of the service `serve` runs
+    #[arg(long, env = "ROLE", value_enum, default_value_t = Role::All, global = true)]
+    pub role: Role,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Role {
+    /// Request serving and background workers in one process
+    All,
+    /// HTTP/WebSocket/SSE, the matching engine and trade settlement
+    Api,
+    /// Background workers only: event scanner, delivery queues, schedulers
+    Keeper,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::All => "all",
+            Role::Api => "api",
+            Role::Keeper => "keeper",
+        }
+    }
+
+    /// Binds the public HTTP/WS listeners and runs the matching engine
+    pub fn serves_requests(&self) -> bool {
+        *self != Role::Keeper
+    }
+
+    /// Runs the background workers and joins their leader election
+    pub fn runs_workers(&self) -> bool {
+        *self != Role::Api
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the API server (default)
+    Serve,
+    /// Validate configuration and print the self-check report
+    CheckConfig,
+    /// Apply pending database migrations
+    Migrate,
+    /// Resolve a market with a winning outcome
+    ResolveMarket {
+        market_id: Uuid,
+        #[arg(long, value_enum)]
+        winning_outcome: WinningOutcome,
+        /// Why the outcome won, published with the resolution evidence
+        #[arg(long)]
+        justification: Option<String>,
+    },
+    /// Compare frozen collateral with open buy-order reservations
+    ReconcileBalances {
+        /// Only check one address
+        #[arg(long)]
+        user: Option<Address>,
+        /// Move the difference between frozen and available
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Replay an account's balance ledger and report untracked movements
+    ReplayJournal {
+        #[arg(long)]
+        user: Address,
+        /// Defaults to the collateral token
+        #[arg(long)]
+        token: Option<String>,
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+    },
+    /// Write one UTC day of trades to a local file
+    ExportTrades {
+        #[arg(long)]
+        date: NaiveDate,
+        #[arg(long, value_enum, default_value = "csv")]
+        format: FileFormat,
+        /// Defaults to `trades-{date}.{ext}` in the working directory
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Import historical markets, outcomes and trades
+    Import {
+        #[arg(long, conflicts_with_all = ["markets", "outcomes", "trades"])]
+        json: Option<PathBuf>,
+        #[arg(long)]
+        markets: Option<PathBuf>,
+        #[arg(long)]
+        outcomes: Option<PathBuf>,
+        #[arg(long)]
+        trades: Option<PathBuf>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Create a demo market with synthetic liquidity and trade history.
+    /// Its resting orders reach the books when the API servers next start.
+    SeedSandbox {
+        question: String,
+        #[arg(long)]
+        category: Option<String>,
+        /// Yes price the history starts at
+        #[arg(long)]
+        start_price: Option<Decimal>,
+        /// Standard deviation of each price step
+        #[arg(long)]
+        volatility: Option<f64>,
+        /// Best bid to best ask distance of the seeded book
+        #[arg(long)]
+        spread: Option<Decimal>,
+        #[arg(long)]
+        history_days: Option<i64>,
+        #[arg(long)]
+        trades_per_day: Option<u32>,
+        /// Price levels per outcome
+        #[arg(long)]
+        levels: Option<u32>,
+        /// Shares per level
+        #[arg(long)]
+        level_size: Option<Decimal>,
+        /// Fixes the generated history
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum WinningOutcome {
+    Yes,
+    No,
+}
+
+impl WinningOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WinningOutcome::Yes => "yes",
+            WinningOutcome::No => "no",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum FileFormat {
+    Csv,
+    Parquet,
+}
+
+impl From<FileFormat> for ExportFormat {
+    fn from(format: FileFormat) -> Self {
+        match format {
+            FileFormat::Csv => ExportFormat::Csv,
+            FileFormat::Parquet => ExportFormat::Parquet,
+        }
+    }
+}
+
+/// Run an operator subcommand (everything except `serve`)
+pub async fn run(command: Command, config: &AppConfig) -> anyhow::Result<()> {
+    if let Command::CheckConfig = command {
+        return check_config(config).await;
+    }
+    let db = Database::connect(&config.database_url).await?;
+
+    match command {
+        Command::Serve | Command::CheckConfig => unreachable!("handled above"),
+        Command::Migrate => migrate(&db).await,
+        Command::ResolveMarket {
+            market_id,
+            winning_outcome,
+            justification,
+        } => resolve_market(config, &db, market_id, winning_outcome, justification).await,
+        Command::ReconcileBalances { user, fix } => reconcile_balances(config, &db, user, fix).await,
+        Command::ReplayJournal { user, token, since } => {
+            let token = token.unwrap_or_else(|| config.collateral_symbol().to_string());
+            replay_journal(&db, &user, &token, since).await
+        }
+        Command::ExportTrades { date, format, out } => export_trades(config, &db, date, format.into(), out).await,
+        Command::Import {
+            json,
+            markets,
+            outcomes,
+            trades,
+            dry_run,
+        } => import(&db, json, [markets, outcomes, trades], dry_run).await,
+        Command::SeedSandbox {
+            question,
+            category,
+            start_price,
+            volatility,
+            spread,
+            history_days,
+            trades_per_day,
+            levels,
+            level_size,
+            seed,
+        } => {
+            let mut params = SandboxParams::new(question);
+            params.category = category.unwrap_or(params.category);
+            params.start_price = start_price.unwrap_or(params.start_price);
+            params.volatility = volatility.unwrap_or(params.volatility);
+            params.spread = spread.unwrap_or(params.spread);
+            params.history_days = history_days.unwrap_or(params.history_days);
+            params.trades_per_day = trades_per_day.unwrap_or(params.trades_per_day);
+            params.levels = levels.unwrap_or(params.levels);
+            params.level_size = level_size.unwrap_or(params.level_size);
+            params.seed = seed;
+            seed_sandbox(config, &db, &params).await
+        }
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+// ============================================================================
+// check-config
+// ============================================================================
+
+async fn check_config(config: &AppConfig) -> anyhow::Result<()> {
+    let report = validation::self_check(config).await;
+    print_json(&report)?;
+    if report.has_critical() {
+        anyhow::bail!("{} critical configuration error(s)", report.count(Severity::Critical));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// migrate
+// ============================================================================
+
+async fn migrate(db: &Database) -> anyhow::Result<()> {
+    let migrator = sqlx::migrate!("./migrations");
+    migrator.run(&db.pool).await?;
+    tracing::info!("Database is at migration {}", migrator.iter().map(|m| m.version).max().unwrap_or(0));
+    Ok(())
+}
+
+// ============================================================================
+// resolve-market
+// ============================================================================
+
+async fn resolve_market(
+    config: &AppConfig,
+    db: &Database,
+    market_id: Uuid,
+    winning_outcome: WinningOutcome,
+    justification: Option<String>,
+) -> anyhow::Result<()> {
+    let share_type = winning_outcome.as_str();
+    let mut tx = db.pool.begin().await?;
+
+    let status: Option<String> = sqlx::query_scalar("SELECT status::text FROM markets WHERE id = $1 FOR UPDATE")
+        .bind(market_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let status = status.ok_or_else(|| anyhow::anyhow!("Market {} not found", market_id))?;
+    if status == "resolved" || status == "cancelled" {
+        anyhow::bail!("Cannot resolve market with status: {}", status);
+    }
+
+    let winning_outcome_id: Uuid =
+        sqlx::query_scalar("SELECT id FROM outcomes WHERE market_id = $1 AND share_type = $2::share_type")
+            .bind(market_id)
+            .bind(share_type)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Market {} has no {} outcome", market_id, share_type))?;
+
+    sqlx::query("UPDATE markets SET status = 'resolved', winning_outcome_id = $1, resolved_at = NOW() WHERE id = $2")
+        .bind(winning_outcome_id)
+        .bind(market_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE outcomes SET probability = CASE WHEN id = $1 THEN 1.0 ELSE 0.0 END WHERE market_id = $2")
+        .bind(winning_outcome_id)
+        .bind(market_id)
+        .execute(&mut *tx)
+        .await?;
+    let evidence = ResolutionEvidence {
+        method: ResolutionMethod::Admin,
+        winning_outcome_id: Some(winning_outcome_id),
+        resolved_by: Some("cli".to_string()),
+        justification,
+        oracle_data: serde_json::json!({ "winning_share_type": share_type }),
+        tx_hashes: Vec::new(),
+    };
+    resolution_evidence::record(&mut tx, market_id, &evidence).await?;
+    tx.commit().await?;
+
+    // Queue the same notifications as the admin endpoint; the server's
+    // delivery workers pick them up.
+    WebhookService::new(db.pool.clone(), WebhookConfig::default())
+        .dispatch(
+            WebhookEventType::MarketResolved,
+            None,
+            serde_json::json!({
+                "market_id": market_id,
+                "winning_outcome_id": winning_outcome_id,
+                "winning_share_type": share_type,
+            }),
+        )
+        .await;
+    ChannelGateway::new(db.pool.clone(), ChannelGatewayConfig::from_config(config))
+        .publish(
+            ChannelEventType::MarketResolved,
+            market_id,
+            None,
+            serde_json::json!({ "winning_share_type": share_type }),
+        )
+        .await;
+
+    println!("Resolved market {} (winning outcome: {})", market_id, share_type);
+    Ok(())
+}
+
+// ============================================================================
+// reconcile-balances
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+struct BalanceMismatch {
+    user_address: String,
+    available: Decimal,
+    frozen: Decimal,
+    /// Collateral reserved by open buy orders
+    reserved: Decimal,
+    fixed: bool,
+}
+
+/// (user_address, available, frozen, reserved)
+type ReconcileRow = (String, Decimal, Decimal, Decimal);
+
+/// New (available, frozen) that makes frozen match the open-order
+/// reservation while preserving the total, or `None` when the account
+/// cannot cover its reservations.
+fn corrected_balance(available: Decimal, frozen: Decimal, reserved: Decimal) -> Option<(Decimal, Decimal)> {
+    let new_available = available + frozen - reserved;
+    (new_available >= Decimal::ZERO).then_some((new_available, reserved))
+}
+
+async fn reconcile_balances(config: &AppConfig, db: &Database, user: Option<Address>, fix: bool) -> anyhow::Result<()> {
+    let token = config.collateral_symbol();
+    let rows: Vec<ReconcileRow> = sqlx::query_as(
+        r#"
+        WITH reserved AS (
+            SELECT user_address, SUM(price * (amount - filled_amount)) AS reserved
+            FROM orders
+            WHERE side = 'buy'
+              AND status IN ('pending', 'open', 'partially_filled')
+              AND price IS NOT NULL
+            GROUP BY user_address
+        )
+        SELECT b.user_address, b.available, b.frozen, COALESCE(r.reserved, 0)
+        FROM balances b
+        LEFT JOIN reserved r ON r.user_address = b.user_address
+        WHERE b.token = $1
+          AND ($2::text IS NULL OR b.user_address = $2)
+          AND (b.frozen <> COALESCE(r.reserved, 0) OR b.available < 0)
+        ORDER BY b.user_address
+        "#,
+    )
+    .bind(token)
+    .bind(user)
+    .fetch_all(&db.pool)
+    .await?;
+
+    let mut mismatches = Vec::with_capacity(rows.len());
+    for (user_address, available, frozen, reserved) in rows {
+        let mut fixed = false;
+        if fix {
+            match corrected_balance(available, frozen, reserved) {
+                Some((new_available, new_frozen)) => {
+                    // Guard against concurrent changes since the read
+                    let result = sqlx::query(
+                        r#"
+                        UPDATE balances SET available = $1, frozen = $2, updated_at = NOW()
+                        WHERE user_address = $3 AND token = $4 AND available = $5 AND frozen = $6
+                        "#,
+                    )
+                    .bind(new_available)
+                    .bind(new_frozen)
+                    .bind(&user_address)
+                    .bind(token)
+                    .bind(available)
+                    .bind(frozen)
+                    .execute(&db.pool)
+                    .await?;
+                    fixed = result.rows_affected() == 1;
+                }
+                None => tracing::warn!("{} cannot cover {} reserved {}; not fixing", user_address, reserved, token),
+            }
+        }
+        mismatches.push(BalanceMismatch {
+            user_address,
+            available,
+            frozen,
+            reserved,
+            fixed,
+        });
+    }
+
+    print_json(&mismatches)?;
+    if !fix && !mismatches.is_empty() {
+        anyhow::bail!("{} balance mismatch(es); re-run with --fix to correct", mismatches.len());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// replay-journal
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+struct JournalReplay {
+    user_address: Address,
+    token: String,
+    entries: usize,
+    /// Net ledger movement per entry type
+    totals: BTreeMap<String, Decimal>,
+    /// Movements between ledger entries not recorded in the ledger
+    /// (deposits, trades, order freezes)
+    untracked_between_entries: Decimal,
+    last_balance_after: Option<Decimal>,
+    current_available: Option<Decimal>,
+}
+
+/// (entry_type, amount, balance_after)
+type JournalRow = (String, Decimal, Decimal);
+
+async fn replay_journal(db: &Database, user: &Address, token: &str, since: Option<DateTime<Utc>>) -> anyhow::Result<()> {
+    let rows: Vec<JournalRow> = sqlx::query_as(
+        r#"
+        SELECT entry_type, amount, balance_after
+        FROM balance_ledger
+        WHERE user_address = $1 AND token = $2
+          AND ($3::timestamptz IS NULL OR created_at >= $3)
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(user)
+    .bind(token)
+    .bind(since)
+    .fetch_all(&db.pool)
+    .await?;
+
+    let current_available: Option<Decimal> =
+        sqlx::query_scalar("SELECT available FROM balances WHERE user_address = $1 AND token = $2")
+            .bind(user)
+            .bind(token)
+            .fetch_optional(&db.pool)
+            .await?;
+
+    let mut totals = BTreeMap::new();
+    let mut untracked = Decimal::ZERO;
+    let mut previous: Option<Decimal> = None;
+    for (entry_type, amount, balance_after) in &rows {
+        *totals.entry(entry_type.clone()).or_insert(Decimal::ZERO) += *amount;
+        if let Some(prev) = previous {
+            untracked += *balance_after - amount - prev;
+        }
+        previous = Some(*balance_after);
+    }
+
+    print_json(&JournalReplay {
+        user_address: user.clone(),
+        token: token.to_string(),
+        entries: rows.len(),
+        totals,
+        untracked_between_entries: untracked,
+        last_balance_after: previous,
+        current_available,
+    })
+}
+
+// ============================================================================
+// export-trades
+// ============================================================================
+
+async fn export_trades(
+    config: &AppConfig,
+    db: &Database,
+    date: NaiveDate,
+    format: ExportFormat,
+    out: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let exporter = DataExporter::new(db.pool.clone(), None, config);
+    let (bytes, rows) = exporter.encode_trades(date, format).await?;
+    let path = out.unwrap_or_else(|| PathBuf::from(format!("trades-{}.{}", date, format.extension())));
+    std::fs::write(&path, bytes)?;
+    println!("Wrote {} trades to {}", rows, path.display());
+    Ok(())
+}
+
+// ============================================================================
+// import
+// ============================================================================
+
+async fn import(
+    db: &Database,
+    json: Option<PathBuf>,
+    csv: [Option<PathBuf>; 3],
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let bundle = match json {
+        Some(path) => ImportBundle::from_json(&std::fs::read(path)?)?,
+        None if csv.iter().all(Option::is_none) => {
+            anyhow::bail!("Nothing to import: pass --json or --markets/--outcomes/--trades")
+        }
+        None => {
+            let [markets, outcomes, trades] = csv.map(|path| path.map(std::fs::read).transpose());
+            ImportBundle::from_csv(markets?.as_deref(), outcomes?.as_deref(), trades?.as_deref())?
+        }
+    };
+
+    let report = BackfillService::new(db.pool.clone()).import(&bundle, dry_run).await?;
+    print_json(&report)?;
+
+    if !report.is_valid() {
+        anyhow::bail!("Import rejected: {} validation issue(s)", report.issues.len());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// seed-sandbox
+// ============================================================================
+
+async fn seed_sandbox(config: &AppConfig, db: &Database, params: &SandboxParams) -> anyhow::Result<()> {
+    if !config.sandbox_tools_enabled {
+        anyhow::bail!("Sandbox tools are disabled; set SANDBOX_TOOLS_ENABLED=true in staging/demo environments");
+    }
+    match sandbox::seed(&db.pool, None, config.collateral_symbol(), params).await {
+        Ok(report) => print_json(&report),
+        Err(SandboxError::Import(issues)) => {
+            print_json(&issues)?;
+            anyhow::bail!("Generated market failed import validation")
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parse_subcommands() {
+        assert!(Cli::try_parse_from(["polymarket-backend"]).unwrap().command.is_none());
+
+        let cli = Cli::try_parse_from([
+            "polymarket-backend",
+            "resolve-market",
+            "6f1c1a52-2a7e-4d2b-9a0e-6f0a3f2b9c11",
+            "--winning-outcome",
+            "no",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::ResolveMarket {
+                winning_outcome: WinningOutcome::No,
+                ..
+            })
+        ));
+
+        assert!(Cli::try_parse_from(["polymarket-backend", "import", "--json", "a.json", "--trades", "t.csv"]).is_err());
+
+        let cli = Cli::try_parse_from(["polymarket-backend", "seed-sandbox", "Demo?", "--volatility", "0.05"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::SeedSandbox { volatility: Some(v), spread: None, .. }) if v == 0.05
+        ));
+    }
+
+    #[test]
+    fn test_parse_role() {
+        assert_eq!(Cli::try_parse_from(["polymarket-backend"]).unwrap().role, Role::All);
+        let keeper = Cli::try_parse_from(["polymarket-backend", "--role", "keeper"]).unwrap();
+        assert!(keeper.role.runs_workers() && !keeper.role.serves_requests());
+        let api = Cli::try_parse_from(["polymarket-backend", "serve", "--role", "api"]).unwrap();
+        assert!(api.role.serves_requests() && !api.role.runs_workers());
+    }
+
+    #[test]
+    fn test_corrected_balance_preserves_total() {
+        assert_eq!(corrected_balance(dec!(10), dec!(5), dec!(3)), Some((dec!(12), dec!(3))));
+        assert_eq!(corrected_balance(dec!(1), dec!(0), dec!(4)), None);
+    }
+}
@@ -1,6 +1,12 @@
+pub mod validation;
+
+use chrono::{DateTime, Utc};
+use ethers::signers::Signer;
+use rust_decimal::Decimal;
 use serde::Deserialize;
 use std::collections::HashMap;
 
+use crate::blockchain::signer::{KeystorePassword, SignerError, SignerSource, SIGNER_PROVIDERS};
 use crate::blockchain::types::ContractAddresses;
 use crate::services::chainlink::{ChainlinkClient, Network};
 
@@ -68,9 +74,46 @@ pub struct AppConfig {
     #[serde(default = "default_trading_pairs")]
     pub trading_pairs: String,
 
-    // Backend signer for withdrawals
+    // Plaintext backend signer key. Optional: only SIGNER_PROVIDER=env reads
+    // it, and other providers leave it empty and load the operator signer
+    // from their own store (see `signer_source`).
+    #[serde(default)]
     pub backend_signer_private_key: String,
 
+    // Operator signer provider: env | encrypted_file | vault | aws_kms
+    #[serde(default = "default_signer_provider")]
+    pub signer_provider: String,
+
+    // encrypted_file: JSON keystore and its password (inline or from a file)
+    #[serde(default)]
+    pub signer_keystore_path: Option<String>,
+
+    #[serde(default)]
+    pub signer_keystore_password: Option<String>,
+
+    #[serde(default)]
+    pub signer_keystore_password_file: Option<String>,
+
+    // vault: KV secret holding the operator key
+    #[serde(default)]
+    pub secrets_vault_addr: Option<String>,
+
+    #[serde(default)]
+    pub secrets_vault_token: Option<String>,
+
+    #[serde(default)]
+    pub secrets_vault_path: Option<String>,
+
+    #[serde(default = "default_secrets_vault_field")]
+    pub secrets_vault_field: String,
+
+    // aws_kms: secp256k1 signing key (ECC_SECG_P256K1)
+    #[serde(default)]
+    pub signer_kms_key_id: Option<String>,
+
+    #[serde(default)]
+    pub signer_kms_region: Option<String>,
+
     // Price feed settings
     #[serde(default = "default_price_feed_top_markets")]
     pub price_feed_top_markets: usize,
@@ -145,11 +188,450 @@ pub struct AppConfig {
     #[serde(default)]
     pub uma_finder_address: Option<String>,
 
+    // NegRiskAdapter mirroring No-set conversions on chain
+    #[serde(default)]
+    pub neg_risk_adapter_address: Option<String>,
+
     #[serde(default = "default_uma_liveness_seconds")]
     pub uma_liveness_seconds: u64,
 
     #[serde(default = "default_uma_bond_amount")]
     pub uma_bond_amount: String,
+
+    // Internal transfer velocity limits (collateral units)
+    #[serde(default = "default_transfer_min_amount")]
+    pub transfer_min_amount: String,
+
+    #[serde(default = "default_transfer_max_amount")]
+    pub transfer_max_amount: String,
+
+    #[serde(default = "default_transfer_daily_limit")]
+    pub transfer_daily_limit: String,
+
+    #[serde(default = "default_transfer_hourly_count")]
+    pub transfer_hourly_count: i64,
+
+    // Withdrawal fee policy (collateral units)
+    #[serde(default = "default_withdraw_min_amount")]
+    pub withdraw_min_amount: String,
+
+    #[serde(default = "default_withdraw_fee_flat")]
+    pub withdraw_fee_flat: String,
+
+    #[serde(default = "default_withdraw_fee_gas_units")]
+    pub withdraw_fee_gas_units: u64,
+
+    // Price of the native gas token in collateral units ("0" disables the gas-linked fee)
+    #[serde(default = "default_withdraw_native_token_price")]
+    pub withdraw_native_token_price: String,
+
+    #[serde(default = "default_withdraw_max_gas_fee")]
+    pub withdraw_max_gas_fee: String,
+
+    #[serde(default = "default_withdraw_dust_threshold")]
+    pub withdraw_dust_threshold: String,
+
+    #[serde(default = "default_true")]
+    pub withdraw_dust_sweep: bool,
+
+    // Email notifications: "log" (default, no delivery), "smtp" or "api"
+    #[serde(default = "default_email_provider")]
+    pub email_provider: String,
+
+    #[serde(default = "default_email_from")]
+    pub email_from: String,
+
+    #[serde(default)]
+    pub smtp_host: Option<String>,
+
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+
+    #[serde(default)]
+    pub smtp_username: Option<String>,
+
+    #[serde(default)]
+    pub smtp_password: Option<String>,
+
+    // HTTP email provider (JSON POST with bearer key)
+    #[serde(default)]
+    pub email_api_url: Option<String>,
+
+    #[serde(default)]
+    pub email_api_key: Option<String>,
+
+    // Channel gateway bot credentials (announcements are skipped when unset)
+    #[serde(default)]
+    pub telegram_bot_token: Option<String>,
+
+    #[serde(default)]
+    pub discord_bot_token: Option<String>,
+
+    // Public data exports (S3-compatible object storage)
+    #[serde(default)]
+    pub export_enabled: bool,
+
+    #[serde(default)]
+    pub export_bucket: Option<String>,
+
+    // Custom endpoint for S3-compatible stores (MinIO, R2, ...)
+    #[serde(default)]
+    pub export_endpoint: Option<String>,
+
+    #[serde(default = "default_export_region")]
+    pub export_region: String,
+
+    #[serde(default)]
+    pub export_access_key_id: Option<String>,
+
+    #[serde(default)]
+    pub export_secret_access_key: Option<String>,
+
+    // Write to a local directory instead of S3 (development)
+    #[serde(default)]
+    pub export_local_dir: Option<String>,
+
+    #[serde(default = "default_export_prefix")]
+    pub export_prefix: String,
+
+    // "parquet", "csv" or "both"
+    #[serde(default = "default_export_format")]
+    pub export_format: String,
+
+    // Hour (UTC) after which the previous day is exported
+    #[serde(default = "default_export_hour_utc")]
+    pub export_hour_utc: u32,
+
+    // Public URL prefix for exported objects, used in the manifest
+    #[serde(default)]
+    pub export_public_base_url: Option<String>,
+
+    // IPFS HTTP API (Kubo RPC) that market criteria are pinned to; pinning
+    // is off when unset
+    #[serde(default)]
+    pub ipfs_api_url: Option<String>,
+
+    // Authorization header for hosted IPFS APIs, e.g. "Basic ..."
+    #[serde(default)]
+    pub ipfs_api_auth: Option<String>,
+
+    // Gateway URL prefix for links to pinned criteria
+    #[serde(default = "default_ipfs_gateway_url")]
+    pub ipfs_gateway_url: String,
+
+    // Public widget API (/public/v1): per-client requests per minute
+    #[serde(default = "default_public_api_rate_limit")]
+    pub public_api_rate_limit: u32,
+
+    // Public widget API response cache TTL (seconds)
+    #[serde(default = "default_public_api_cache_ttl_secs")]
+    pub public_api_cache_ttl_secs: u64,
+
+    // Elect a single replica to run singleton background workers (event
+    // processor, analytics aggregation, exports). Disable only for
+    // single-instance deployments.
+    #[serde(default = "default_true")]
+    pub leader_election_enabled: bool,
+
+    // After restoring open orders on startup, match orders left crossed
+    // (e.g. by a crash between matching and persistence). Off by default:
+    // the trades it produces happen at restart, not when users placed them.
+    #[serde(default)]
+    pub recovery_resolve_crossed: bool,
+
+    // Matching engine shard threads; markets are assigned to a shard by id.
+    // 0 matches on the request's own tokio worker (unsharded).
+    #[serde(default)]
+    pub engine_shards: usize,
+
+    // Jobs a shard queues before refusing orders for its markets
+    #[serde(default = "default_engine_shard_queue_capacity")]
+    pub engine_shard_queue_capacity: usize,
+
+    // Shard backlog at which new orders get 429; cancels may queue past it
+    #[serde(default = "default_engine_admission_limit")]
+    pub engine_admission_limit: usize,
+
+    // Pin each shard thread to a CPU core
+    #[serde(default)]
+    pub engine_pin_threads: bool,
+
+    // Limit orders priced further than this from the book's last trade (or
+    // midpoint) are rejected (0 = no band)
+    #[serde(default = "default_price_band")]
+    pub price_band: String,
+
+    // A market whose price moves more than circuit_breaker_move within
+    // circuit_breaker_window_secs is halted until an admin resumes it
+    // (0 = no breaker)
+    #[serde(default = "default_circuit_breaker_move")]
+    pub circuit_breaker_move: String,
+
+    #[serde(default = "default_circuit_breaker_window_secs")]
+    pub circuit_breaker_window_secs: u64,
+
+    // Fills are written in batches flushed this often (or when
+    // write_batch_max trades are pending); order acks wait for the flush
+    #[serde(default = "default_write_batch_interval_ms")]
+    pub write_batch_interval_ms: u64,
+
+    #[serde(default = "default_write_batch_max")]
+    pub write_batch_max: usize,
+
+    // Resting (GTC limit) orders one account may keep open in a market, and
+    // all accounts together; further orders are rejected (0 = no cap)
+    #[serde(default = "default_max_open_orders_per_account_market")]
+    pub max_open_orders_per_account_market: i64,
+
+    #[serde(default = "default_max_open_orders_per_market")]
+    pub max_open_orders_per_market: i64,
+
+    // Internal event bus: trades, order, balance and market status events
+    // go through this Redis Stream when Redis is available (in-process
+    // delivery otherwise); the stream is trimmed to about max_len entries
+    #[serde(default = "default_true")]
+    pub event_bus_enabled: bool,
+
+    #[serde(default = "default_event_bus_stream")]
+    pub event_bus_stream: String,
+
+    #[serde(default = "default_event_bus_max_len")]
+    pub event_bus_max_len: usize,
+
+    // Entries a consumer has left unacknowledged this long are taken over
+    // by another consumer of its group
+    #[serde(default = "default_event_bus_claim_idle_ms")]
+    pub event_bus_claim_idle_ms: usize,
+
+    // Gasless relayer: ERC-2771 forwarder the operator submits signed
+    // redemptions and approvals through (relaying is off when unset)
+    #[serde(default)]
+    pub relayer_forwarder_address: Option<String>,
+
+    // Relayed transactions per user per rolling 24h
+    #[serde(default = "default_relayer_daily_quota")]
+    pub relayer_daily_quota: i64,
+
+    // Largest inner-call gas a relayed request may ask for
+    #[serde(default = "default_relayer_max_gas")]
+    pub relayer_max_gas: u64,
+
+    // Virtual collateral a paper trading account starts (and resets) with
+    #[serde(default = "default_paper_starting_balance")]
+    pub paper_starting_balance: String,
+
+    // Size of the house bid and ask seeded into each paper book
+    #[serde(default = "default_paper_house_depth")]
+    pub paper_house_depth: String,
+
+    // Allow seeding sandbox markets with synthetic history and liquidity
+    // (staging/demo environments only)
+    #[serde(default)]
+    pub sandbox_tools_enabled: bool,
+
+    // Date (RFC 3339) after which /api/v1 may be removed, announced in the
+    // Sunset header of v1 responses
+    #[serde(default)]
+    pub api_v1_sunset: Option<String>,
+
+    // Manual market resolution and trade busts/adjustments need a second
+    // admin's approval (propose/approve/execute under /admin/approvals)
+    #[serde(default = "default_true")]
+    pub admin_two_person_rule: bool,
+
+    // How long a proposed admin action can be approved and executed
+    #[serde(default = "default_admin_approval_ttl_secs")]
+    pub admin_approval_ttl_secs: u64,
+
+    // Daily (UTC) quotas on each account's authenticated API use: requests,
+    // and response bytes (0 = unlimited). Accounts can be given their own
+    // with PUT /admin/users/:address/api-quota. Responses carry a warning
+    // once api_quota_warn_percent of a quota is used; past it, 429.
+    #[serde(default)]
+    pub api_daily_request_quota: u64,
+
+    #[serde(default)]
+    pub api_daily_byte_quota: u64,
+
+    #[serde(default = "default_api_quota_warn_percent")]
+    pub api_quota_warn_percent: u8,
+
+    // Daily operations report: the previous UTC day is summarised after
+    // ops_report_hour_utc and emailed to ops_report_emails (comma
+    // separated; stored only when empty)
+    #[serde(default = "default_ops_report_hour_utc")]
+    pub ops_report_hour_utc: u32,
+
+    #[serde(default)]
+    pub ops_report_emails: String,
+}
+
+fn default_ops_report_hour_utc() -> u32 {
+    1
+}
+
+fn default_admin_approval_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_api_quota_warn_percent() -> u8 {
+    80
+}
+
+fn default_relayer_daily_quota() -> i64 {
+    5
+}
+
+fn default_relayer_max_gas() -> u64 {
+    300_000
+}
+
+fn default_paper_starting_balance() -> String {
+    "1000".to_string()
+}
+
+fn default_paper_house_depth() -> String {
+    "10000".to_string()
+}
+
+fn default_transfer_min_amount() -> String {
+    "1".to_string()
+}
+
+fn default_transfer_max_amount() -> String {
+    "50000".to_string()
+}
+
+fn default_transfer_daily_limit() -> String {
+    "100000".to_string() // Rolling 24h outbound total per sender
+}
+
+fn default_transfer_hourly_count() -> i64 {
+    20 // Max outbound transfers per sender per rolling hour
+}
+
+fn default_withdraw_min_amount() -> String {
+    "5".to_string()
+}
+
+fn default_withdraw_fee_flat() -> String {
+    "0.5".to_string()
+}
+
+fn default_withdraw_fee_gas_units() -> u64 {
+    65000 // ERC20 transfer
+}
+
+fn default_withdraw_native_token_price() -> String {
+    "0".to_string()
+}
+
+fn default_withdraw_max_gas_fee() -> String {
+    "5".to_string()
+}
+
+fn default_withdraw_dust_threshold() -> String {
+    "0.01".to_string()
+}
+
+fn default_email_provider() -> String {
+    "log".to_string()
+}
+
+fn default_email_from() -> String {
+    "Polymarket <no-reply@localhost>".to_string()
+}
+
+fn default_smtp_port() -> u16 {
+    587 // STARTTLS
+}
+
+fn default_export_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_export_prefix() -> String {
+    "exports".to_string()
+}
+
+fn default_export_format() -> String {
+    "parquet".to_string()
+}
+
+fn default_export_hour_utc() -> u32 {
+    1
+}
+
+fn default_public_api_rate_limit() -> u32 {
+    300
+}
+
+fn default_public_api_cache_ttl_secs() -> u64 {
+    10
+}
+
+fn default_engine_shard_queue_capacity() -> usize {
+    10_000
+}
+
+fn default_engine_admission_limit() -> usize {
+    8_000
+}
+
+fn default_price_band() -> String {
+    "0".to_string()
+}
+
+fn default_circuit_breaker_move() -> String {
+    "0".to_string()
+}
+
+fn default_circuit_breaker_window_secs() -> u64 {
+    60
+}
+
+fn default_write_batch_interval_ms() -> u64 {
+    5
+}
+
+fn default_write_batch_max() -> usize {
+    500
+}
+
+fn default_max_open_orders_per_account_market() -> i64 {
+    200
+}
+
+fn default_max_open_orders_per_market() -> i64 {
+    20000
+}
+
+fn default_event_bus_stream() -> String {
+    "events".to_string()
+}
+
+fn default_event_bus_max_len() -> usize {
+    1_000_000
+}
+
+fn default_event_bus_claim_idle_ms() -> usize {
+    60_000
+}
+
+fn default_signer_provider() -> String {
+    "env".to_string()
+}
+
+fn default_secrets_vault_field() -> String {
+    "private_key".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_ipfs_gateway_url() -> String {
+    "https://ipfs.io/ipfs/".to_string()
 }
 
 fn default_chainlink_max_price_age() -> u64 {
@@ -319,6 +801,58 @@ impl AppConfig {
         self.collateral_token_decimals
     }
 
+    /// Minimum amount for a single internal transfer
+    pub fn transfer_min_amount(&self) -> Decimal {
+        self.transfer_min_amount.parse().unwrap_or(Decimal::ONE)
+    }
+
+    /// Maximum amount for a single internal transfer
+    pub fn transfer_max_amount(&self) -> Decimal {
+        self.transfer_max_amount.parse().unwrap_or(Decimal::new(50000, 0))
+    }
+
+    /// Maximum total a sender may transfer out in a rolling 24h window
+    pub fn transfer_daily_limit(&self) -> Decimal {
+        self.transfer_daily_limit.parse().unwrap_or(Decimal::new(100000, 0))
+    }
+
+    /// Virtual collateral of a new or reset paper trading account
+    pub fn paper_starting_balance(&self) -> Decimal {
+        self.paper_starting_balance.parse().unwrap_or(Decimal::new(1000, 0))
+    }
+
+    /// Shares on each side of the house quotes in paper books
+    pub fn paper_house_depth(&self) -> Decimal {
+        self.paper_house_depth.parse().unwrap_or(Decimal::new(10000, 0))
+    }
+
+    /// Widest distance of a limit price from the reference price, if banded
+    pub fn price_band(&self) -> Option<Decimal> {
+        self.price_band.parse().ok().filter(|band: &Decimal| *band > Decimal::ZERO)
+    }
+
+    /// Price move that trips the circuit breaker, if enabled
+    pub fn circuit_breaker_move(&self) -> Option<Decimal> {
+        self.circuit_breaker_move.parse().ok().filter(|m: &Decimal| *m > Decimal::ZERO)
+    }
+
+    /// Announced removal date of API v1
+    pub fn api_v1_sunset(&self) -> Option<DateTime<Utc>> {
+        self.api_v1_sunset
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Addresses the daily operations report is emailed to
+    pub fn ops_report_recipients(&self) -> Vec<String> {
+        self.ops_report_emails
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
     /// Get supported trading pairs as a vector
     pub fn get_trading_pairs(&self) -> Vec<String> {
         self.trading_pairs
@@ -395,22 +929,74 @@ impl AppConfig {
             conditional_tokens: self.ctf_conditional_tokens_address.parse().unwrap_or_default(),
             ctf_exchange: self.ctf_exchange_address.parse().unwrap_or_default(),
             uma_oracle: self.uma_oracle_address.as_ref().and_then(|a| a.parse().ok()),
+            neg_risk_adapter: self.neg_risk_adapter_address.as_ref().and_then(|a| a.parse().ok()),
         }
     }
 
+    /// Where the operator signer comes from. With `SIGNER_PROVIDER=env`
+    /// this is `CTF_SIGNER_PRIVATE_KEY` (`None` when unset: read-only).
+    pub fn signer_source(&self) -> Result<Option<SignerSource>, SignerError> {
+        fn required(value: &Option<String>, name: &str) -> Result<String, SignerError> {
+            value
+                .clone()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| SignerError::Config(format!("{} is required", name)))
+        }
+
+        let source = match self.signer_provider.as_str() {
+            "env" => match self.ctf_signer_private_key.as_deref().filter(|k| !k.is_empty()) {
+                Some(key) => SignerSource::Env { private_key: key.to_string() },
+                None => return Ok(None),
+            },
+            "encrypted_file" => {
+                let password = match (&self.signer_keystore_password_file, &self.signer_keystore_password) {
+                    (Some(file), _) if !file.is_empty() => KeystorePassword::File(file.into()),
+                    (_, Some(password)) => KeystorePassword::Inline(password.clone()),
+                    _ => {
+                        return Err(SignerError::Config(
+                            "SIGNER_KEYSTORE_PASSWORD_FILE or SIGNER_KEYSTORE_PASSWORD is required".to_string(),
+                        ))
+                    }
+                };
+                SignerSource::EncryptedFile {
+                    path: required(&self.signer_keystore_path, "SIGNER_KEYSTORE_PATH")?.into(),
+                    password,
+                }
+            }
+            "vault" => SignerSource::Vault {
+                addr: required(&self.secrets_vault_addr, "SECRETS_VAULT_ADDR")?,
+                token: required(&self.secrets_vault_token, "SECRETS_VAULT_TOKEN")?,
+                path: required(&self.secrets_vault_path, "SECRETS_VAULT_PATH")?,
+                field: self.secrets_vault_field.clone(),
+            },
+            "aws_kms" => SignerSource::AwsKms {
+                key_id: required(&self.signer_kms_key_id, "SIGNER_KMS_KEY_ID")?,
+                region: self.signer_kms_region.clone().filter(|r| !r.is_empty()),
+            },
+            other => {
+                return Err(SignerError::Config(format!(
+                    "unknown SIGNER_PROVIDER '{}' (expected one of {})",
+                    other,
+                    SIGNER_PROVIDERS.join(", ")
+                )))
+            }
+        };
+        Ok(Some(source))
+    }
+
     /// Create a BlockchainClient for CTF operations
-    pub fn create_blockchain_client(&self) -> Result<crate::blockchain::BlockchainClient, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn create_blockchain_client(&self) -> Result<crate::blockchain::BlockchainClient, Box<dyn std::error::Error + Send + Sync>> {
         let addresses = self.get_ctf_contract_addresses();
 
-        if let Some(ref private_key) = self.ctf_signer_private_key {
-            if !private_key.is_empty() {
-                return crate::blockchain::BlockchainClient::new_with_signer(
-                    &self.rpc_url,
-                    private_key,
-                    addresses,
-                    self.chain_id,
-                );
-            }
+        if let Some(source) = self.signer_source()? {
+            let operator = source.load(self.chain_id).await?;
+            tracing::info!("Operator signer {:?} loaded via {}", operator.address(), source.provider());
+            return crate::blockchain::BlockchainClient::with_operator_signer(
+                &self.rpc_url,
+                operator,
+                addresses,
+                self.chain_id,
+            );
         }
 
         crate::blockchain::BlockchainClient::new(&self.rpc_url, addresses, self.chain_id)
@@ -0,0 +1,520 @@
+//! Startup Configuration Self-Check
+//!
+//! `AppConfig::load` only checks that values deserialize; bad addresses,
+//! zero limits or a signer that does not control the vault otherwise surface
+//! much later as runtime failures. The self-check validates the loaded
+//! config (static checks) and then probes the chain (RPC reachability,
+//! chain id, collateral decimals, operator signer vs. vault). Critical findings stop
+//! the server from starting; warnings are logged.
+
+use std::time::Duration;
+
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, Bytes, TransactionRequest, U256};
+use ethers::utils::to_checksum;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use super::AppConfig;
+
+/// Timeout for each RPC probe
+const RPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// JWT secret shipped in `.env.example`
+const EXAMPLE_JWT_SECRET: &str = "your-super-secret-jwt-key-change-in-production";
+
+/// `decimals()` selector
+const DECIMALS_SELECTOR: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationReport {
+    pub checks: Vec<Check>,
+}
+
+impl ValidationReport {
+    fn push(&mut self, name: &str, severity: Severity, message: impl Into<String>) {
+        self.checks.push(Check {
+            name: name.to_string(),
+            severity,
+            message: message.into(),
+        });
+    }
+
+    fn ok(&mut self, name: &str, message: impl Into<String>) {
+        self.push(name, Severity::Ok, message);
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.checks.iter().filter(|c| c.severity == severity).count()
+    }
+
+    pub fn has_critical(&self) -> bool {
+        self.count(Severity::Critical) > 0
+    }
+
+    /// Log every check and a summary line
+    pub fn log(&self) {
+        for check in &self.checks {
+            match check.severity {
+                Severity::Ok => tracing::info!("[config ok] {}: {}", check.name, check.message),
+                Severity::Warning => tracing::warn!("[config warning] {}: {}", check.name, check.message),
+                Severity::Critical => tracing::error!("[config CRITICAL] {}: {}", check.name, check.message),
+            }
+        }
+        tracing::info!(
+            "Config self-check: {} ok, {} warning(s), {} critical",
+            self.count(Severity::Ok),
+            self.count(Severity::Warning),
+            self.count(Severity::Critical)
+        );
+    }
+}
+
+/// Run static validation followed by the on-chain probes
+pub async fn self_check(config: &AppConfig) -> ValidationReport {
+    let mut report = validate(config);
+    check_chain(config, &mut report).await;
+    report
+}
+
+// ============================================================================
+// Static checks
+// ============================================================================
+
+/// Checks that need no network access
+pub fn validate(config: &AppConfig) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let production = config.environment.eq_ignore_ascii_case("production");
+
+    // Addresses
+    check_address(&mut report, "vault_address", &config.vault_address, true, Severity::Critical);
+    check_address(
+        &mut report,
+        "collateral_token_address",
+        &config.collateral_token_address,
+        true,
+        Severity::Critical,
+    );
+    check_address(&mut report, "ctf_usdc_address", &config.ctf_usdc_address, false, Severity::Critical);
+    check_address(
+        &mut report,
+        "ctf_conditional_tokens_address",
+        &config.ctf_conditional_tokens_address,
+        false,
+        Severity::Critical,
+    );
+    check_address(&mut report, "ctf_exchange_address", &config.ctf_exchange_address, false, Severity::Critical);
+    if let Some(uma) = &config.uma_oracle_address {
+        check_address(&mut report, "uma_oracle_address", uma, true, Severity::Critical);
+    }
+    if let Some(adapter) = &config.neg_risk_adapter_address {
+        check_address(&mut report, "neg_risk_adapter_address", adapter, true, Severity::Critical);
+    }
+    if let Some(forwarder) = &config.relayer_forwarder_address {
+        check_address(&mut report, "relayer_forwarder_address", forwarder, true, Severity::Critical);
+    }
+    // Legacy contracts from the perpetuals deployment
+    check_address(
+        &mut report,
+        "referral_storage_address",
+        &config.referral_storage_address,
+        false,
+        Severity::Warning,
+    );
+    check_address(
+        &mut report,
+        "referral_rebate_address",
+        &config.referral_rebate_address,
+        false,
+        Severity::Warning,
+    );
+
+    // Signer keys
+    if config.signer_provider == "env" {
+        match config.backend_signer_private_key.parse::<LocalWallet>() {
+            Ok(wallet) => report.ok("backend_signer_private_key", format!("signer {}", to_checksum(&wallet.address(), None))),
+            Err(_) => report.push("backend_signer_private_key", Severity::Critical, "not a valid private key"),
+        }
+        if let Some(key) = config.ctf_signer_private_key.as_deref().filter(|k| !k.is_empty()) {
+            match key.parse::<LocalWallet>() {
+                Ok(wallet) => report.ok("ctf_signer_private_key", format!("signer {}", to_checksum(&wallet.address(), None))),
+                Err(_) => report.push("ctf_signer_private_key", Severity::Critical, "not a valid private key"),
+            }
+        }
+        if production {
+            report.push("signer_provider", Severity::Warning, "operator keys are read from plain env vars");
+        }
+    } else {
+        match config.signer_source() {
+            Ok(_) => report.ok("signer_provider", config.signer_provider.clone()),
+            Err(e) => report.push("signer_provider", Severity::Critical, e.to_string()),
+        }
+        let plaintext = !config.backend_signer_private_key.is_empty()
+            || config.ctf_signer_private_key.as_deref().is_some_and(|k| !k.is_empty());
+        if plaintext {
+            report.push(
+                "signer_provider",
+                Severity::Warning,
+                format!("plaintext signer keys are set but ignored under {}; remove them", config.signer_provider),
+            );
+        }
+    }
+
+    // Auth
+    if config.jwt_secret.is_empty() {
+        report.push("jwt_secret", Severity::Critical, "must not be empty");
+    } else if production && (config.jwt_secret == EXAMPLE_JWT_SECRET || config.jwt_secret.len() < 32) {
+        report.push("jwt_secret", Severity::Critical, "example or shorter than 32 characters in production");
+    } else if config.jwt_secret.len() < 32 {
+        report.push("jwt_secret", Severity::Warning, "shorter than 32 characters");
+    } else {
+        report.ok("jwt_secret", "set");
+    }
+    if config.auth_disabled {
+        let severity = if production { Severity::Critical } else { Severity::Warning };
+        report.push("auth_disabled", severity, "JWT and signature verification are disabled");
+    }
+
+    // Numeric sanity
+    check_nonzero(&mut report, "port", u64::from(config.port), Severity::Critical);
+    check_nonzero(&mut report, "chain_id", config.chain_id, Severity::Critical);
+    check_nonzero(&mut report, "jwt_expiry_seconds", config.jwt_expiry_seconds, Severity::Critical);
+    check_nonzero(
+        &mut report,
+        "public_api_rate_limit",
+        u64::from(config.public_api_rate_limit),
+        Severity::Warning,
+    );
+    check_nonzero(
+        &mut report,
+        "min_confirmations",
+        config.min_confirmations,
+        if production { Severity::Critical } else { Severity::Warning },
+    );
+    if config.collateral_token_decimals > 18 {
+        report.push(
+            "collateral_token_decimals",
+            Severity::Critical,
+            format!("{} is not a plausible ERC-20 decimals value", config.collateral_token_decimals),
+        );
+    }
+    if config.export_hour_utc > 23 {
+        report.push("export_hour_utc", Severity::Critical, format!("{} is not an hour (0-23)", config.export_hour_utc));
+    }
+    if config.ops_report_hour_utc > 23 {
+        report.push(
+            "ops_report_hour_utc",
+            Severity::Critical,
+            format!("{} is not an hour (0-23)", config.ops_report_hour_utc),
+        );
+    }
+    for address in config.ops_report_recipients().iter().filter(|a| !a.contains('@')) {
+        report.push("ops_report_emails", Severity::Warning, format!("'{}' is not an email address", address));
+    }
+
+    // Decimal amounts are parsed lazily with silent fallbacks; catch typos here
+    for (name, value) in [
+        ("transfer_min_amount", &config.transfer_min_amount),
+        ("transfer_max_amount", &config.transfer_max_amount),
+        ("transfer_daily_limit", &config.transfer_daily_limit),
+        ("withdraw_min_amount", &config.withdraw_min_amount),
+        ("withdraw_fee_flat", &config.withdraw_fee_flat),
+        ("withdraw_native_token_price", &config.withdraw_native_token_price),
+        ("withdraw_max_gas_fee", &config.withdraw_max_gas_fee),
+        ("withdraw_dust_threshold", &config.withdraw_dust_threshold),
+        ("price_band", &config.price_band),
+        ("circuit_breaker_move", &config.circuit_breaker_move),
+    ] {
+        match value.parse::<Decimal>() {
+            Ok(v) if v < Decimal::ZERO => report.push(name, Severity::Critical, format!("{} is negative", value)),
+            Ok(_) => {}
+            Err(_) => report.push(name, Severity::Critical, format!("'{}' is not a decimal", value)),
+        }
+    }
+    if let (Ok(min), Ok(max)) = (
+        config.transfer_min_amount.parse::<Decimal>(),
+        config.transfer_max_amount.parse::<Decimal>(),
+    ) {
+        if min > max {
+            report.push("transfer_max_amount", Severity::Critical, "smaller than transfer_min_amount");
+        }
+    }
+
+    if config.circuit_breaker_move().is_some() {
+        check_nonzero(
+            &mut report,
+            "circuit_breaker_window_secs",
+            config.circuit_breaker_window_secs,
+            Severity::Critical,
+        );
+    }
+    check_nonzero(&mut report, "admin_approval_ttl_secs", config.admin_approval_ttl_secs, Severity::Critical);
+    if production && !config.admin_two_person_rule {
+        report.push(
+            "admin_two_person_rule",
+            Severity::Warning,
+            "market resolutions and trade busts need only one admin",
+        );
+    }
+
+    if !(1..=100).contains(&config.api_quota_warn_percent) {
+        report.push(
+            "api_quota_warn_percent",
+            Severity::Warning,
+            format!("{} is not a percentage (1-100)", config.api_quota_warn_percent),
+        );
+    }
+
+    if let Some(sunset) = config.api_v1_sunset.as_deref() {
+        if config.api_v1_sunset().is_none() {
+            report.push("api_v1_sunset", Severity::Warning, format!("'{}' is not an RFC 3339 date", sunset));
+        }
+    }
+
+    // URLs
+    match reqwest::Url::parse(&config.rpc_url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https" | "ws" | "wss") => report.ok("rpc_url", "well-formed"),
+        Ok(url) => report.push("rpc_url", Severity::Critical, format!("unsupported scheme '{}'", url.scheme())),
+        Err(e) => report.push("rpc_url", Severity::Critical, format!("invalid URL: {}", e)),
+    }
+    for (name, value) in [
+        ("redis_url", &config.redis_url),
+        ("export_endpoint", &config.export_endpoint),
+        ("email_api_url", &config.email_api_url),
+        ("ipfs_api_url", &config.ipfs_api_url),
+    ] {
+        if let Some(Err(e)) = value.as_deref().map(reqwest::Url::parse) {
+            report.push(name, Severity::Warning, format!("invalid URL: {}", e));
+        }
+    }
+
+    report
+}
+
+fn check_address(report: &mut ValidationReport, name: &str, value: &str, nonzero: bool, severity: Severity) {
+    let address = match value.parse::<Address>() {
+        Ok(address) => address,
+        Err(_) => return report.push(name, severity, format!("'{}' is not a valid address", value)),
+    };
+
+    // Mixed case means the value claims an EIP-55 checksum; verify it
+    let hex = value.trim_start_matches("0x");
+    let mixed_case = hex.chars().any(|c| c.is_ascii_uppercase()) && hex.chars().any(|c| c.is_ascii_lowercase());
+    if mixed_case && to_checksum(&address, None) != value {
+        return report.push(name, severity, format!("{} fails its EIP-55 checksum (typo?)", value));
+    }
+    if nonzero && address.is_zero() {
+        return report.push(name, severity, "is the zero address");
+    }
+    report.ok(name, to_checksum(&address, None));
+}
+
+fn check_nonzero(report: &mut ValidationReport, name: &str, value: u64, severity: Severity) {
+    if value == 0 {
+        report.push(name, severity, "must be greater than zero");
+    }
+}
+
+// ============================================================================
+// On-chain probes
+// ============================================================================
+
+async fn check_chain(config: &AppConfig, report: &mut ValidationReport) {
+    // Independent of the RPC, so probe the secret provider first
+    let signer = operator_address(config, report).await;
+
+    let provider = match Provider::<Http>::try_from(config.rpc_url.as_str()) {
+        Ok(provider) => provider,
+        // Malformed or non-HTTP URLs are already reported by `validate`
+        Err(_) => return,
+    };
+
+    match tokio::time::timeout(RPC_TIMEOUT, provider.get_chainid()).await {
+        Ok(Ok(chain_id)) if chain_id == U256::from(config.chain_id) => {
+            report.ok("rpc_chain_id", format!("RPC reachable on chain {}", chain_id));
+        }
+        Ok(Ok(chain_id)) => {
+            report.push(
+                "rpc_chain_id",
+                Severity::Critical,
+                format!("RPC is on chain {} but CHAIN_ID is {}", chain_id, config.chain_id),
+            );
+            return;
+        }
+        Ok(Err(e)) => return report.push("rpc_url", Severity::Warning, format!("RPC unreachable: {}", e)),
+        Err(_) => return report.push("rpc_url", Severity::Warning, "RPC did not respond in time"),
+    }
+
+    if let Ok(token) = config.collateral_token_address.parse::<Address>() {
+        let call = TransactionRequest::new().to(token).data(Bytes::from(DECIMALS_SELECTOR.to_vec()));
+        match tokio::time::timeout(RPC_TIMEOUT, provider.call(&call.into(), None)).await {
+            Ok(Ok(bytes)) if bytes.len() == 32 => {
+                let on_chain = U256::from_big_endian(&bytes);
+                if on_chain == U256::from(config.collateral_token_decimals) {
+                    report.ok("collateral_token_decimals", format!("matches token ({})", on_chain));
+                } else {
+                    report.push(
+                        "collateral_token_decimals",
+                        Severity::Critical,
+                        format!(
+                            "configured {} but token reports {}",
+                            config.collateral_token_decimals, on_chain
+                        ),
+                    );
+                }
+            }
+            Ok(Ok(_)) => report.push(
+                "collateral_token_decimals",
+                Severity::Critical,
+                "collateral token address does not implement decimals()",
+            ),
+            Ok(Err(e)) => report.push("collateral_token_decimals", Severity::Warning, format!("decimals() call failed: {}", e)),
+            Err(_) => report.push("collateral_token_decimals", Severity::Warning, "decimals() call timed out"),
+        }
+    }
+
+    let (Some(signer), Ok(vault)) = (signer, config.vault_address.parse::<Address>()) else {
+        return;
+    };
+    match tokio::time::timeout(RPC_TIMEOUT, provider.get_code(vault, None)).await {
+        Ok(Ok(code)) if !code.is_empty() => report.ok("vault_signer", "vault is a contract; signer not compared"),
+        Ok(Ok(_)) if signer == vault => report.ok("vault_signer", "backend signer controls the vault"),
+        Ok(Ok(_)) => report.push(
+            "vault_signer",
+            if config.environment.eq_ignore_ascii_case("production") {
+                Severity::Critical
+            } else {
+                Severity::Warning
+            },
+            format!(
+                "vault {} is an EOA but the backend signer is {}",
+                to_checksum(&vault, None),
+                to_checksum(&signer, None)
+            ),
+        ),
+        Ok(Err(e)) => report.push("vault_signer", Severity::Warning, format!("could not inspect vault: {}", e)),
+        Err(_) => report.push("vault_signer", Severity::Warning, "vault lookup timed out"),
+    }
+}
+
+/// The operator signer's address. Non-env providers are loaded here, so an
+/// unreachable Vault or KMS key fails the self-check rather than the first
+/// transaction.
+async fn operator_address(config: &AppConfig, report: &mut ValidationReport) -> Option<Address> {
+    if config.signer_provider == "env" {
+        return config.backend_signer_private_key.parse::<LocalWallet>().ok().map(|w| w.address());
+    }
+    let source = config.signer_source().ok()??;
+    match tokio::time::timeout(RPC_TIMEOUT * 2, source.load(config.chain_id)).await {
+        Ok(Ok(signer)) => {
+            report.ok("operator_signer", format!("{} via {}", to_checksum(&signer.address(), None), source.provider()));
+            Some(signer.address())
+        }
+        Ok(Err(e)) => {
+            report.push("operator_signer", Severity::Critical, e.to_string());
+            None
+        }
+        Err(_) => {
+            report.push("operator_signer", Severity::Critical, format!("{} did not respond in time", source.provider()));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(overrides: serde_json::Value) -> AppConfig {
+        let mut base = serde_json::json!({
+            "database_url": "postgres://localhost/test",
+            "jwt_secret": "0123456789abcdef0123456789abcdef",
+            "rpc_url": "http://localhost:8545",
+            "chain_id": 31337,
+            "vault_address": "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            "referral_storage_address": "0x0000000000000000000000000000000000000000",
+            "referral_rebate_address": "0x0000000000000000000000000000000000000000",
+            "backend_signer_private_key": "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        });
+        base.as_object_mut()
+            .unwrap()
+            .extend(overrides.as_object().unwrap().clone());
+        serde_json::from_value(base).unwrap()
+    }
+
+    fn severity_of(report: &ValidationReport, name: &str) -> Option<Severity> {
+        report.checks.iter().filter(|c| c.name == name).map(|c| c.severity).max()
+    }
+
+    #[test]
+    fn test_valid_config_has_no_critical() {
+        let report = validate(&config(serde_json::json!({})));
+        assert!(!report.has_critical(), "{:?}", report.checks);
+    }
+
+    #[test]
+    fn test_critical_findings() {
+        let report = validate(&config(serde_json::json!({
+            // First letter's case flipped: checksum mismatch
+            "vault_address": "0xF39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            "collateral_token_address": "0x0000000000000000000000000000000000000000",
+            "chain_id": 0,
+            "withdraw_fee_flat": "abc",
+            "backend_signer_private_key": "not-a-key",
+        })));
+        for name in ["vault_address", "collateral_token_address", "chain_id", "withdraw_fee_flat", "backend_signer_private_key"] {
+            assert_eq!(severity_of(&report, name), Some(Severity::Critical), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_production_rules() {
+        let report = validate(&config(serde_json::json!({
+            "environment": "production",
+            "jwt_secret": EXAMPLE_JWT_SECRET,
+            "auth_disabled": true,
+        })));
+        assert_eq!(severity_of(&report, "jwt_secret"), Some(Severity::Critical));
+        assert_eq!(severity_of(&report, "auth_disabled"), Some(Severity::Critical));
+
+        let dev = validate(&config(serde_json::json!({ "auth_disabled": true })));
+        assert_eq!(severity_of(&dev, "auth_disabled"), Some(Severity::Warning));
+    }
+
+    #[test]
+    fn test_signer_provider_settings() {
+        let missing = validate(&config(serde_json::json!({
+            "signer_provider": "vault",
+            "backend_signer_private_key": "",
+            "secrets_vault_addr": "https://vault.internal:8200",
+        })));
+        assert_eq!(severity_of(&missing, "signer_provider"), Some(Severity::Critical));
+        assert_eq!(severity_of(&missing, "backend_signer_private_key"), None);
+
+        let kms = validate(&config(serde_json::json!({
+            "signer_provider": "aws_kms",
+            "backend_signer_private_key": "",
+            "signer_kms_key_id": "alias/operator",
+        })));
+        assert_eq!(severity_of(&kms, "signer_provider"), Some(Severity::Ok));
+
+        let unknown = validate(&config(serde_json::json!({ "signer_provider": "hsm" })));
+        assert_eq!(severity_of(&unknown, "signer_provider"), Some(Severity::Critical));
+    }
+}
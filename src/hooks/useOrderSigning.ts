@@ -101,11 +101,13 @@ export function useWsAuthSigning() {
   const WS_AUTH_TYPES = {
     WebSocketAuth: [
       { name: "wallet", type: "address" },
+      { name: "nonce", type: "string" },
       { name: "timestamp", type: "uint256" },
     ],
   } as const;
 
-  const signWsAuth = useCallback(async () => {
+  // nonce: challenge sent by the server on this connection
+  const signWsAuth = useCallback(async (nonce: string) => {
     if (!isConnected || !address) {
       throw new Error("Wallet not connected");
     }
@@ -118,6 +120,7 @@ export function useWsAuthSigning() {
       primaryType: "WebSocketAuth",
       message: {
         wallet: address,
+        nonce,
         timestamp: BigInt(timestamp),
       },
     });
@@ -126,6 +129,7 @@ export function useWsAuthSigning() {
       address,
       signature,
       timestamp,
+      nonce,
     };
   }, [address, isConnected, chainId, signTypedDataAsync]);
 
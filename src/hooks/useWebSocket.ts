@@ -9,6 +9,8 @@ interface WebSocketState {
   isConnected: boolean;
   isAuthenticated: boolean;
   subscriptions: Set<string>;
+  // Challenge to sign for the next auth attempt on this connection
+  authNonce: string | null;
 }
 
 export function useWebSocket() {
@@ -16,6 +18,7 @@ export function useWebSocket() {
     isConnected: false,
     isAuthenticated: false,
     subscriptions: new Set(),
+    authNonce: null,
   });
 
   const wsRef = useRef<WebSocket | null>(null);
@@ -57,7 +60,7 @@ export function useWebSocket() {
 
       if (!isMountedRef.current) return;
 
-      setState((prev) => ({ ...prev, isConnected: false, isAuthenticated: false }));
+      setState((prev) => ({ ...prev, isConnected: false, isAuthenticated: false, authNonce: null }));
 
       // Attempt to reconnect after 5 seconds (only if mounted)
       if (isMountedRef.current) {
@@ -77,6 +80,11 @@ export function useWebSocket() {
       try {
         const data = JSON.parse(event.data);
 
+        // A new challenge arrives on connect and after every auth attempt
+        if (data.type === "challenge") {
+          setState((prev) => ({ ...prev, authNonce: data.nonce }));
+        }
+
         // Handle auth result
         if (data.type === "authresult") {
           setState((prev) => ({ ...prev, isAuthenticated: data.success }));
@@ -134,7 +142,7 @@ export function useWebSocket() {
   }, []);
 
   // Authenticate with signature
-  const authenticate = useCallback((address: string, signature: string, timestamp: number) => {
+  const authenticate = useCallback((address: string, signature: string, timestamp: number, nonce: string) => {
     if (wsRef.current?.readyState !== WebSocket.OPEN) return;
 
     wsRef.current.send(JSON.stringify({
@@ -142,6 +150,7 @@ export function useWebSocket() {
       address,
       signature,
       timestamp,
+      nonce,
     }));
   }, []);
 
@@ -2,21 +2,30 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::{middleware, routing::get, Router};
-use serde::Serialize;
-use tokio::sync::broadcast;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 /// Order update event for real-time WebSocket push
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderUpdateEvent {
     pub user_address: String,
     pub order: models::order::OrderResponse,
 }
 
+/// A user's orders cancelled together by `POST /orders/cancel-all`, pushed as
+/// one WebSocket message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrdersCancelledEvent {
+    pub user_address: String,
+    pub order_ids: Vec<uuid::Uuid>,
+    pub timestamp: i64,
+}
+
 /// Balance update event for real-time WebSocket push
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BalanceUpdateEvent {
     pub user_address: String,
     pub token: String,
@@ -30,6 +39,7 @@ mod api;
 mod auth;
 mod blockchain;
 mod cache;
+mod cli;
 mod config;
 mod db;
 mod metrics;
@@ -37,16 +47,53 @@ mod models;
 mod services;
 mod utils;
 mod websocket;
+// Harness API is broader than the tests using it so far
+#[cfg(test)]
+#[allow(dead_code)]
+mod test_support;
 
 use crate::blockchain::{BlockchainClient, EventListener};
-use crate::cache::{CacheConfig, CacheManager};
+use crate::cache::{CacheConfig, CacheManager, ResponseCache};
 use crate::config::AppConfig;
 use crate::db::Database;
 use crate::services::chainlink::ChainlinkClient;
+use crate::services::event_bus::{EventBus, EventBusConfig, MarketStatusNotifier};
 use crate::services::event_processor::{EventProcessor, EventProcessorConfig};
-use crate::services::matching::MatchingEngine;
+use crate::services::matching::{
+    EngineShards, FillNotifier, HistoryStore, MatchingEngine, OpenOrderCaps, OrderFlowOrchestrator, OrderJournal,
+    PriceGuardConfig, ShardConfig,
+};
 use crate::services::market::MarketService;
 use crate::services::settlement::{MatchedOrders, SettlementConfig, SettlementService};
+use crate::services::analytics::MarketAnalyticsJob;
+use crate::services::channel_gateway::{ChannelGateway, ChannelGatewayConfig};
+use crate::services::criteria_pin::{CriteriaPinner, IpfsClient};
+use crate::services::neg_risk::ConversionSubmitter;
+use crate::services::export::DataExporter;
+use crate::services::feature_flags::FeatureFlagService;
+use crate::services::cancel_all_after::CancelAllAfter;
+use crate::services::circuit_breaker::CircuitBreaker;
+use crate::services::paper_trading::PaperTrading;
+use crate::services::market_archive::MarketArchiver;
+use crate::services::stale_orders::StaleOrderJanitor;
+use crate::services::api_usage::ApiUsageMeter;
+use crate::services::mm_protection::MmProtection;
+use crate::services::market_summary::MarketSummaryRefresher;
+use crate::services::ops_report::OpsReportJob;
+use crate::services::rewards::RewardsScoringJob;
+use crate::services::resolution_schedule::ResolutionScheduler;
+use crate::services::orderbook_history::OrderbookHistory;
+use crate::services::liquidity::LiquidityTracker;
+use crate::services::leader_election::LeaderElection;
+use crate::services::system_events::{self, SystemEventKind};
+use crate::services::notification::{sender_from_config, NotificationConfig, NotificationService};
+use crate::services::trade_persistence::{TradePersistConfig, TradePersistQueue};
+use crate::services::trigger_orders::TriggerMonitor;
+use crate::services::write_batcher::{WriteBatchConfig, WriteBatcher};
+use crate::services::order_expiry::OrderExpiry;
+use crate::services::order_gateway::OrderGateway;
+use crate::services::webhook::{WebhookConfig, WebhookService};
+use crate::websocket::sse::SseHub;
 use ethers::types::Address;
 use metrics_exporter_prometheus::PrometheusHandle;
 use std::str::FromStr;
@@ -57,18 +104,60 @@ pub struct AppState {
     pub db: Database,
     pub cache: Arc<CacheManager>,
     pub matching_engine: Arc<MatchingEngine>,
+    /// Runs order submission/cancellation on the market's engine shard
+    pub engine_shards: Arc<EngineShards>,
     pub market_service: Arc<MarketService>,
-    pub order_update_sender: broadcast::Sender<OrderUpdateEvent>,
-    pub balance_update_sender: broadcast::Sender<BalanceUpdateEvent>,
+    /// Trades, order, balance, market status and trigger order events for every node
+    pub event_bus: Arc<EventBus>,
     pub metrics_handle: PrometheusHandle,
     pub chainlink_client: Option<Arc<ChainlinkClient>>,
     pub blockchain_client: Option<Arc<BlockchainClient>>,
     /// Settlement queue sender for on-chain order settlement
     pub settlement_sender: Option<mpsc::Sender<MatchedOrders>>,
+    /// Retry queue for failed trade persists
+    pub trade_persist_queue: Arc<TradePersistQueue>,
+    /// Batched fill writes (trades + maker order updates)
+    pub write_batcher: Arc<WriteBatcher>,
+    /// In-process order entry for the built-in market maker and settlement
+    pub order_gateway: Arc<OrderGateway>,
+    /// Order placement saga for user orders
+    pub order_flow: Arc<OrderFlowOrchestrator>,
+    /// Outbound webhook dispatcher
+    pub webhook_service: Arc<WebhookService>,
+    /// Email notification queue
+    pub notification_service: Arc<NotificationService>,
+    /// Telegram/Discord announcement gateway
+    pub channel_gateway: Arc<ChannelGateway>,
+    /// Sequenced market events for SSE streams
+    pub sse_hub: Arc<SseHub>,
+    /// Daily public dataset exporter
+    pub data_exporter: Arc<DataExporter>,
+    /// Response cache for the public widget API
+    pub widget_cache: Arc<ResponseCache>,
+    /// Runtime feature flags
+    pub feature_flags: Arc<FeatureFlagService>,
+    /// Resolved/cancelled markets served read-only
+    pub market_archiver: Arc<MarketArchiver>,
+    /// Cancels orders left open on closed markets
+    pub stale_orders: Arc<StaleOrderJanitor>,
+    pub orderbook_history: Arc<OrderbookHistory>,
+    /// Spread, depth and turnover per market for liquidity badges
+    pub liquidity_tracker: Arc<LiquidityTracker>,
+    pub history_store: Arc<HistoryStore>,
+    pub cancel_all_after: Arc<CancelAllAfter>,
+    pub circuit_breaker: Arc<CircuitBreaker>,
+    pub order_expiry: Arc<OrderExpiry>,
+    /// Simulated order entry with virtual balances
+    pub paper_trading: Arc<PaperTrading>,
+    /// Per-account API usage and daily quotas
+    pub api_usage: Arc<ApiUsageMeter>,
+    pub mm_protection: Arc<MmProtection>,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = cli::Cli::parse();
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(
@@ -82,9 +171,30 @@ async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
     let config = AppConfig::load()?;
 
-    tracing::info!("Starting Polymarket Backend v{}", env!("CARGO_PKG_VERSION"));
+    // Operator subcommands run once and exit; no subcommand means `serve`
+    let role = cli.role;
+    match cli.command.unwrap_or(cli::Command::Serve) {
+        cli::Command::Serve => {}
+        command => return cli::run(command, &config).await,
+    }
+
+    tracing::info!(
+        "Starting Polymarket Backend v{} (role: {})",
+        env!("CARGO_PKG_VERSION"),
+        role.as_str()
+    );
     tracing::info!("Environment: {}", config.environment);
 
+    // Refuse to start on critical configuration errors
+    let report = crate::config::validation::self_check(&config).await;
+    report.log();
+    if report.has_critical() {
+        anyhow::bail!(
+            "Configuration self-check failed with {} critical error(s)",
+            report.count(crate::config::validation::Severity::Critical)
+        );
+    }
+
     // Initialize Prometheus metrics
     let metrics_handle = metrics::init_metrics();
     tracing::info!("Prometheus metrics initialized");
@@ -105,36 +215,107 @@ async fn main() -> anyhow::Result<()> {
         tracing::warn!("Cache manager running without Redis (graceful degradation)");
     }
 
+    // Elect one replica to run singleton background workers. API-only
+    // replicas stay out of the election so they never hold the lock.
+    let leader_election = Arc::new(LeaderElection::new(
+        "background-workers",
+        &config.database_url,
+        config.leader_election_enabled,
+    ));
+    if role.runs_workers() {
+        leader_election.clone().start();
+    }
+
     // Initialize market service
     let market_service = Arc::new(MarketService::new());
     tracing::info!("Market service initialized");
 
-    // Initialize matching engine
-    let matching_engine = Arc::new(MatchingEngine::new());
+    // Initialize matching engine, with the price band and circuit breaker
+    let matching_engine = Arc::new(MatchingEngine::new().with_price_guard(PriceGuardConfig {
+        band: config.price_band(),
+        halt_move: config.circuit_breaker_move(),
+        halt_window_ms: (config.circuit_breaker_window_secs * 1000) as i64,
+    }));
     tracing::info!("Matching engine initialized");
 
-    // Recover open limit orders from database
-    match matching_engine.recover_orders_from_db(&db.pool).await {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("Recovered {} open limit orders to orderbook", count);
-            } else {
-                tracing::info!("No open orders to recover");
-            }
+    // Shard threads only where orders are matched
+    let engine_shards = Arc::new(EngineShards::new(
+        matching_engine.clone(),
+        &ShardConfig {
+            shards: if role.serves_requests() { config.engine_shards } else { 0 },
+            queue_capacity: config.engine_shard_queue_capacity,
+            admission_limit: config.engine_admission_limit,
+            pin_threads: config.engine_pin_threads,
+        },
+    ));
+    let order_journal = Arc::new(OrderJournal::new(db.pool.clone(), matching_engine.clone()));
+
+    // Load feature flags (also toggles engine features such as Mint/Merge matching)
+    let feature_flags = Arc::new(FeatureFlagService::new(
+        db.pool.clone(),
+        cache.clone(),
+        &config.environment,
+        matching_engine.clone(),
+    ));
+
+    // Only request-serving processes own a live orderbook
+    if role.serves_requests() {
+        // Continue trade sequence numbers from the last persisted trade
+        match sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(sequence) FROM trades")
+            .fetch_one(&db.pool)
+            .await
+        {
+            Ok(last) => matching_engine.seed_trade_sequence(last.unwrap_or(0) as u64),
+            Err(e) => tracing::warn!("Failed to load last trade sequence: {}", e),
         }
-        Err(e) => {
-            tracing::error!("Failed to recover orders from database: {}", e);
-            tracing::warn!("Starting with empty orderbook");
+
+        feature_flags.clone().start().await;
+        tracing::info!("Feature flags loaded");
+
+        // Every book change from here on is journaled for the next recovery
+        order_journal.clone().start();
+
+        // Recover resting orders from the order journal
+        match services::matching::recover_orders_from_db(&matching_engine, &db.pool).await {
+            Ok(count) => {
+                if count > 0 {
+                    tracing::info!("Recovered {} open limit orders to orderbook", count);
+                } else {
+                    tracing::info!("No open orders to recover");
+                }
+                system_events::record(
+                    &db.pool,
+                    SystemEventKind::OrdersRecovered,
+                    None,
+                    format!("Recovered {} open limit orders", count),
+                    serde_json::json!({ "count": count }),
+                )
+                .await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to recover orders from database: {}", e);
+                tracing::warn!("Starting with empty orderbook");
+                system_events::record(
+                    &db.pool,
+                    SystemEventKind::RecoveryFailed,
+                    None,
+                    "Order recovery failed; starting with an empty orderbook",
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+                .await;
+            }
         }
     }
 
-    // Create order update broadcast channel for real-time WebSocket push
-    let (order_update_sender, _) = broadcast::channel::<OrderUpdateEvent>(1000);
-    tracing::info!("Order update broadcast channel created");
-
-    // Create balance update broadcast channel for real-time WebSocket push
-    let (balance_update_sender, _) = broadcast::channel::<BalanceUpdateEvent>(1000);
-    tracing::info!("Balance update broadcast channel created");
+    // Event bus for real-time pushes and event consumers (a Redis Stream
+    // shared by every node when Redis is available)
+    let event_bus = Arc::new(
+        EventBus::connect(
+            cache.is_available().then(|| cache.config().redis_url.as_str()),
+            EventBusConfig::from_config(&config),
+        )
+        .await,
+    );
 
     // Initialize Chainlink client (optional)
     let chainlink_client = config.create_chainlink_client().map(|client| {
@@ -147,7 +328,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize Blockchain client for CTF contracts (optional)
     let blockchain_client = if config.has_ctf_config() {
-        match config.create_blockchain_client() {
+        match config.create_blockchain_client().await {
             Ok(client) => {
                 tracing::info!(
                     "Blockchain client initialized for chain_id={}, contracts: USDC={}, CTF={}, Exchange={}",
@@ -168,8 +349,11 @@ async fn main() -> anyhow::Result<()> {
         None
     };
 
-    // Initialize settlement service if blockchain client is available
-    let settlement_sender = if let Some(ref bc) = blockchain_client {
+    // Initialize settlement service if blockchain client is available. Its
+    // queue is fed by in-process matches, so it runs next to the engine; the
+    // matches go through the event bus, so any settlement node may submit
+    // them.
+    let settlement_sender = if let Some(bc) = blockchain_client.as_ref().filter(|_| role.serves_requests()) {
         let settlement_config = SettlementConfig {
             enabled: std::env::var("SETTLEMENT_ENABLED")
                 .map(|v| v == "true" || v == "1")
@@ -198,19 +382,21 @@ async fn main() -> anyhow::Result<()> {
             settlement_config.clone(),
         );
 
-        let sender = settlement_service.start_worker();
+        let sender = settlement_service.start_worker(event_bus.clone());
         tracing::info!(
             "Settlement service started (enabled: {})",
             settlement_config.enabled
         );
         Some(sender)
-    } else {
+    } else if role.serves_requests() {
         tracing::info!("Settlement service disabled (no blockchain client)");
         None
+    } else {
+        None
     };
 
     // Initialize event processor if blockchain client is available
-    if let Some(ref bc) = blockchain_client {
+    if let Some(bc) = blockchain_client.as_ref().filter(|_| role.runs_workers()) {
         let event_processor_enabled = std::env::var("EVENT_PROCESSOR_ENABLED")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(false);
@@ -240,58 +426,329 @@ async fn main() -> anyhow::Result<()> {
                 db.pool.clone(),
                 event_config,
                 addresses,
-                balance_update_sender.clone(),
-            );
-
-            event_processor.start(event_listener);
-            tracing::info!("Event processor started from block {}", start_block);
+                event_bus.clone(),
+            )
+            .with_leader_election(leader_election.clone());
+
+            // Only the leader scans the chain and credits deposits
+            let leader = leader_election.clone();
+            tokio::spawn(async move {
+                leader.wait_for_leadership().await;
+                event_processor.start(event_listener);
+                tracing::info!("Event processor started from block {}", start_block);
+            });
         } else {
             tracing::info!("Event processor disabled");
         }
     }
 
+    // Failed trade persists are retried in every role (see TradePersistQueue)
+    let trade_persist_queue = Arc::new(TradePersistQueue::new(
+        db.pool.clone(),
+        TradePersistConfig {
+            collateral_token: config.collateral_symbol().to_string(),
+            ..Default::default()
+        },
+    ));
+    trade_persist_queue.clone().start_worker();
+
+    let write_batcher = Arc::new(WriteBatcher::new(
+        db.pool.clone(),
+        trade_persist_queue.clone(),
+        WriteBatchConfig {
+            flush_interval_ms: config.write_batch_interval_ms,
+            max_batch: config.write_batch_max,
+            collateral_token: config.collateral_symbol().to_string(),
+        },
+    ));
+    let order_gateway = Arc::new(OrderGateway::new(
+        db.pool.clone(),
+        engine_shards.clone(),
+        write_batcher.clone(),
+        config.collateral_symbol().to_string(),
+    ));
+
+    // Matching orders restored crossed is an explicit, opt-in step
+    if role.serves_requests() && config.recovery_resolve_crossed {
+        if let Err(e) =
+            services::matching::resolve_crossed_books(&matching_engine, &db.pool, &trade_persist_queue).await
+        {
+            tracing::error!("Failed to resolve crossed orderbooks: {}", e);
+        }
+    }
+
+    // Webhook, email and channel deliveries are DB-backed queues: any role
+    // can enqueue, only worker processes deliver
+    let webhook_service = Arc::new(WebhookService::new(db.pool.clone(), WebhookConfig::default()));
+    let notification_service = Arc::new(NotificationService::new(
+        db.pool.clone(),
+        sender_from_config(&config),
+        NotificationConfig::default(),
+    ));
+    let channel_gateway = Arc::new(ChannelGateway::new(
+        db.pool.clone(),
+        ChannelGatewayConfig::from_config(&config),
+    ));
+    let order_flow = Arc::new(
+        OrderFlowOrchestrator::new(
+            db.pool.clone(),
+            engine_shards.clone(),
+            write_batcher.clone(),
+            config.collateral_symbol().to_string(),
+        )
+        .with_notifier(FillNotifier {
+            webhook_service: webhook_service.clone(),
+            notification_service: notification_service.clone(),
+            channel_gateway: channel_gateway.clone(),
+        })
+        .with_open_order_caps(OpenOrderCaps::from_config(&config)),
+    );
+    if role.runs_workers() {
+        webhook_service.clone().start_worker();
+        notification_service.clone().start_worker();
+        channel_gateway.clone().start_worker();
+    }
+
+    // Closed markets release their books and serve archived data
+    let market_archiver = Arc::new(MarketArchiver::new(
+        db.pool.clone(),
+        engine_shards.clone(),
+        config.collateral_symbol(),
+    ));
+    // Orders that slipped in around a close are swept up later
+    let stale_orders = Arc::new(StaleOrderJanitor::new(
+        db.pool.clone(),
+        engine_shards.clone(),
+        config.collateral_symbol(),
+    ));
+
+    // Trade/order history: bounded in memory, older records from Postgres
+    let history_store = Arc::new(HistoryStore::new(matching_engine.clone(), db.pool.clone()));
+
+    // Dead man's switch: cancels a user's orders when their timer lapses
+    let cancel_all_after = Arc::new(CancelAllAfter::new(
+        db.pool.clone(),
+        engine_shards.clone(),
+        config.collateral_symbol(),
+    ));
+
+    // Markets halted by the circuit breaker, recorded and resumed
+    let circuit_breaker = Arc::new(CircuitBreaker::new(db.pool.clone(), matching_engine.clone()));
+
+    // Good-til-date orders and the books of markets past their end time
+    let order_expiry = Arc::new(OrderExpiry::new(
+        db.pool.clone(),
+        engine_shards.clone(),
+        config.collateral_symbol(),
+    ));
+
+    // Minute orderbook snapshots for historical book queries
+    let orderbook_history = Arc::new(OrderbookHistory::new(db.pool.clone(), matching_engine.clone()));
+
+    // Liquidity snapshots kept current from the engine's book and trade streams
+    let liquidity_tracker = Arc::new(LiquidityTracker::new(db.pool.clone(), matching_engine.clone()));
+
+    // Paper books are in memory only; orders left open by a previous
+    // process are cancelled. One shard thread: paper traffic is light.
+    let paper_trading = Arc::new(PaperTrading::new(
+        config.paper_starting_balance(),
+        config.paper_house_depth(),
+        &ShardConfig {
+            shards: if role.serves_requests() { 1 } else { 0 },
+            queue_capacity: config.engine_shard_queue_capacity,
+            admission_limit: config.engine_admission_limit,
+            pin_threads: false,
+        },
+    ));
+    if role.serves_requests() {
+        match PaperTrading::expire_open_orders(&db.pool).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Released paper orders of {} accounts", n),
+            Err(e) => tracing::warn!("Failed to expire open paper orders: {}", e),
+        }
+    }
+
+    // API calls and bytes per account, against their daily quotas
+    let api_usage = Arc::new(ApiUsageMeter::new(db.pool.clone(), &config));
+
+    // Market maker fill throttles and loss limits on this node's books
+    let mm_protection = Arc::new(MmProtection::new(
+        db.pool.clone(),
+        engine_shards.clone(),
+        config.collateral_symbol(),
+        webhook_service.clone(),
+        notification_service.clone(),
+    ));
+
+    // Initialize SSE hub (sequenced market events with replay buffer)
+    let sse_hub = Arc::new(SseHub::new());
+    if role.serves_requests() {
+        api_usage.clone().start();
+        market_archiver.clone().start().await;
+        stale_orders.clone().start();
+        orderbook_history.clone().start();
+        liquidity_tracker.clone().start();
+        history_store.clone().start();
+        cancel_all_after.clone().start();
+        circuit_breaker.clone().start();
+        order_expiry.clone().start();
+        mm_protection.clone().start().await;
+        // Stop orders watch this node's books
+        Arc::new(TriggerMonitor::new(
+            db.pool.clone(),
+            matching_engine.clone(),
+            order_flow.clone(),
+            event_bus.clone(),
+        ))
+        .start();
+        write_batcher.clone().start();
+        sse_hub.clone().start(&matching_engine, &event_bus);
+        // Every engine-side order change reaches its owner's `orders` channel
+        websocket::order_events::start(&matching_engine, event_bus.clone());
+        event_bus.clone().forward_trades(&matching_engine);
+        event_bus.clone().start_relay();
+    }
+
+    // Initialize public data exporter (daily Parquet/CSV snapshots); without
+    // a live engine it rebuilds books from open orders
+    let data_exporter = Arc::new(DataExporter::new(
+        db.pool.clone(),
+        role.serves_requests().then(|| matching_engine.clone()),
+        &config,
+    ));
+
+    if role.runs_workers() {
+        // Start market analytics aggregation (hourly/daily buckets)
+        Arc::new(MarketAnalyticsJob::new(db.pool.clone())).start(leader_election.clone());
+
+        // Decay the trigger-maintained 24h volume of market summaries
+        Arc::new(MarketSummaryRefresher::new(db.pool.clone())).start(leader_election.clone());
+
+        // Maker/taker volume and time at the best quote for market leaderboards
+        Arc::new(RewardsScoringJob::new(db.pool.clone())).start(leader_election.clone());
+
+        // Pre-resolution reminders and closing markets at their resolution time
+        Arc::new(ResolutionScheduler::new(
+            db.pool.clone(),
+            webhook_service.clone(),
+            notification_service.clone(),
+        ))
+        .start(leader_election.clone());
+
+        if config.export_enabled {
+            data_exporter.clone().start_scheduler(leader_election.clone());
+        }
+
+        // Daily operations report, emailed to OPS_REPORT_EMAILS when set
+        Arc::new(OpsReportJob::new(db.pool.clone(), &config)).start(leader_election.clone());
+
+        // Pin the criteria of newly live markets to IPFS
+        match IpfsClient::from_config(&config) {
+            Some(ipfs) => Arc::new(CriteriaPinner::new(db.pool.clone(), ipfs)).start(leader_election.clone()),
+            None => tracing::info!("IPFS_API_URL not set; market criteria are not pinned"),
+        }
+
+        // Mirror No-set conversions through the NegRiskAdapter
+        if let Some(bc) = blockchain_client.as_ref() {
+            Arc::new(ConversionSubmitter::new(db.pool.clone(), bc.clone())).start(leader_election.clone());
+        }
+    }
+
+    // With a shared stream the notifier consumes on the workers; in-process
+    // events only reach consumers on the node that published them
+    if (event_bus.is_durable() && role.runs_workers()) || (!event_bus.is_durable() && role.serves_requests()) {
+        Arc::new(MarketStatusNotifier {
+            webhook_service: webhook_service.clone(),
+            channel_gateway: channel_gateway.clone(),
+        })
+        .start(event_bus.clone());
+    }
+
+    // Trades a crashed node matched but never wrote are recovered from the
+    // stream; in-process events die with their node, so there is nothing to
+    // recover without one
+    if event_bus.is_durable() && role.runs_workers() {
+        trade_persist_queue.clone().start_consumer(event_bus.clone());
+    }
+
     // Build application state
     let state = Arc::new(AppState {
         config: config.clone(),
         db,
         cache,
         matching_engine,
+        engine_shards,
         market_service,
-        order_update_sender,
-        balance_update_sender,
+        event_bus,
         metrics_handle,
         chainlink_client,
         blockchain_client,
         settlement_sender,
+        trade_persist_queue,
+        order_gateway,
+        order_flow,
+        write_batcher,
+        webhook_service,
+        notification_service,
+        channel_gateway,
+        sse_hub,
+        data_exporter,
+        widget_cache: Arc::new(ResponseCache::new(std::time::Duration::from_secs(
+            config.public_api_cache_ttl_secs,
+        ))),
+        feature_flags,
+        market_archiver,
+        stale_orders,
+        orderbook_history,
+        liquidity_tracker,
+        history_store,
+        cancel_all_after,
+        circuit_breaker,
+        order_expiry,
+        paper_trading,
+        api_usage,
+        mm_protection,
     });
 
-    // Note: Trade persistence is now handled synchronously in the order handler.
-    // The matching engine still broadcasts trades for websocket subscribers.
-    // Keeping a subscriber to prevent channel backpressure.
-    let mut trade_receiver = state.matching_engine.subscribe_trades();
-    tokio::spawn(async move {
-        tracing::info!("Trade broadcast consumer started");
-        while let Ok(_trade_event) = trade_receiver.recv().await {
-            // Trades are persisted synchronously in order handler
-            // This consumer just drains the channel for websocket broadcasts
-        }
-        tracing::warn!("Trade broadcast consumer stopped");
-    });
-    tracing::info!("Trade broadcast consumer spawned");
+    // Keepers only expose health and metrics for the orchestrator
+    if !role.serves_requests() {
+        let app = Router::new()
+            .route("/health", get(health_check))
+            .route("/metrics", get(metrics_endpoint))
+            .with_state(state);
+        let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+        tracing::info!("Keeper running; health and metrics on {}", addr);
+
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, app).await?;
+        return Ok(());
+    }
+
+    let pool = state.db.pool.clone();
+    let api_usage = state.api_usage.clone();
 
     // Build router
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/metrics", get(metrics_endpoint))
-        .nest("/api/v1", api::routes::create_router(state.clone()))
+        .route("/version", get(api::versioning::get_version))
+        .nest(
+            "/api/v1",
+            api::routes::create_router(state.clone())
+                .layer(middleware::from_fn_with_state(state.clone(), api::versioning::v1_deprecation)),
+        )
+        .nest("/api/v2", api::routes::v2::create_router(state.clone()))
         .nest("/ws", websocket::routes::create_router(state.clone()))
-        .layer(middleware::from_fn(api::middleware::metrics_middleware))
+        .nest("/sse", websocket::sse::create_router(state.clone()))
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
                 .allow_methods(Any)
                 .allow_headers(Any),
         )
+        // Public widget API has its own read-only CORS policy and rate limits
+        .nest("/public/v1", api::routes::create_public_router(state.clone()))
+        .layer(middleware::from_fn(api::middleware::metrics_middleware))
         .layer(TraceLayer::new_for_http())
         .with_state(state);
 
@@ -299,12 +756,57 @@ async fn main() -> anyhow::Result<()> {
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     tracing::info!("Server listening on {}", addr);
 
+    let instance_details = serde_json::json!({
+        "role": role.as_str(),
+        "version": env!("CARGO_PKG_VERSION"),
+        "address": addr.to_string(),
+    });
+    system_events::record(&pool, SystemEventKind::EngineStarted, None, "Matching engine started", instance_details.clone()).await;
+
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await?;
+    if !order_journal.flush(std::time::Duration::from_secs(5)).await {
+        tracing::warn!("Order journal still had unwritten book changes at shutdown");
+    }
+    if let Err(e) = api_usage.flush().await {
+        tracing::warn!("Failed to flush API usage at shutdown: {}", e);
+    }
 
+    system_events::record(&pool, SystemEventKind::EngineStopped, None, "Matching engine stopped", instance_details).await;
     Ok(())
 }
 
+/// Resolve on Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("Shutdown signal received, draining connections");
+}
+
 async fn health_check() -> &'static str {
     "OK"
 }
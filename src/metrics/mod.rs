@@ -62,6 +62,25 @@ pub mod names {
     // Oracle Metrics
     pub const ORACLE_UPDATES_TOTAL: &str = "oracle_updates_total";
     pub const ORACLE_ERRORS_TOTAL: &str = "oracle_errors_total";
+
+    // Webhook Metrics
+    pub const WEBHOOK_DELIVERIES_TOTAL: &str = "webhook_deliveries_total";
+
+    // Trade Persistence Metrics
+    pub const TRADE_PERSIST_RETRIES_TOTAL: &str = "trade_persist_retries_total";
+    pub const WRITE_BATCH_TRADES: &str = "write_batch_trades";
+    pub const WRITE_BATCH_DURATION_SECONDS: &str = "write_batch_duration_seconds";
+    pub const GATEWAY_ORDERS_TOTAL: &str = "gateway_orders_total";
+    pub const ORDER_FLOW_STEPS_TOTAL: &str = "order_flow_steps_total";
+    pub const ORDER_FLOW_STEP_DURATION_SECONDS: &str = "order_flow_step_duration_seconds";
+    pub const HOLDINGS_INVARIANT_VIOLATIONS_TOTAL: &str = "holdings_invariant_violations_total";
+    pub const STALE_ORDERS_CANCELLED_TOTAL: &str = "stale_orders_cancelled_total";
+
+    // Notification Metrics
+    pub const NOTIFICATIONS_SENT_TOTAL: &str = "notifications_sent_total";
+
+    // Coordination Metrics
+    pub const LEADER_STATUS: &str = "leader_status";
 }
 
 /// Label keys
@@ -79,6 +98,10 @@ pub mod labels {
     pub const OPERATION: &str = "operation";
     pub const QUERY_TYPE: &str = "query_type";
     pub const SOURCE: &str = "source";
+    pub const RESULT: &str = "result";
+    pub const KIND: &str = "kind";
+    pub const ELECTION: &str = "election";
+    pub const STEP: &str = "step";
 }
 
 /// Initialize Prometheus metrics exporter
@@ -99,6 +122,12 @@ pub fn init_metrics() -> PrometheusHandle {
             &[0.0001, 0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5],
         )
         .unwrap()
+        // Order flow step duration buckets (in seconds)
+        .set_buckets_for_metric(
+            Matcher::Full(names::ORDER_FLOW_STEP_DURATION_SECONDS.to_string()),
+            &[0.0001, 0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0],
+        )
+        .unwrap()
         // Cache operation duration buckets
         .set_buckets_for_metric(
             Matcher::Full(names::CACHE_OPERATION_DURATION_SECONDS.to_string()),
@@ -148,6 +177,12 @@ pub fn set_http_requests_in_flight(count: i64) {
 
 // ============================================================================
 // Matching Engine Metrics
+// ============================================================================
+//
+// Recorded by the `polymarket-engine` crate through the same `metrics`
+// facade (see `polymarket_engine::metrics`); names above are kept for bucket
+// configuration and dashboards.
+
 // ============================================================================
 
 /// Record order submission
@@ -359,6 +394,112 @@ pub fn record_oracle_error(source: &str) {
     .increment(1);
 }
 
+// ============================================================================
+// Webhook Metrics
+// ============================================================================
+
+/// Record webhook delivery attempt result ("delivered", "retry", "failed")
+pub fn record_webhook_delivery(result: &str) {
+    counter!(
+        names::WEBHOOK_DELIVERIES_TOTAL,
+        labels::RESULT => result.to_string()
+    )
+    .increment(1);
+}
+
+// ============================================================================
+// Trade Persistence Metrics
+// ============================================================================
+
+/// Record a trade persist retry event ("queued", "buffered", "dropped",
+/// "retry", "persisted", "dead", "recovered" from the event stream)
+pub fn record_trade_persist(result: &str) {
+    counter!(
+        names::TRADE_PERSIST_RETRIES_TOTAL,
+        labels::RESULT => result.to_string()
+    )
+    .increment(1);
+}
+
+/// Record a flushed fill batch ("written" or "fallback" to per-trade persists)
+pub fn record_write_batch(trades: usize, result: &str, duration_secs: f64) {
+    histogram!(names::WRITE_BATCH_TRADES, labels::RESULT => result.to_string()).record(trades as f64);
+    histogram!(names::WRITE_BATCH_DURATION_SECONDS, labels::RESULT => result.to_string()).record(duration_secs);
+}
+
+/// Record an order placed, refused or cancelled through the internal order gateway
+pub fn record_gateway_order(source: &str, result: &str) {
+    counter!(
+        names::GATEWAY_ORDERS_TOTAL,
+        labels::SOURCE => source.to_string(),
+        labels::RESULT => result.to_string()
+    )
+    .increment(1);
+}
+
+/// Record a step of the order flow saga ("ok", "failed", "compensated" or
+/// "compensation_failed")
+pub fn record_order_flow_step(step: &str, result: &str, duration_secs: f64) {
+    counter!(
+        names::ORDER_FLOW_STEPS_TOTAL,
+        labels::STEP => step.to_string(),
+        labels::RESULT => result.to_string()
+    )
+    .increment(1);
+    histogram!(
+        names::ORDER_FLOW_STEP_DURATION_SECONDS,
+        labels::STEP => step.to_string(),
+        labels::RESULT => result.to_string()
+    )
+    .record(duration_secs);
+}
+
+/// Record a Yes/No outstanding vs. minted pairs mismatch after a holdings update
+pub fn record_holdings_invariant_violation(market_id: &str) {
+    counter!(
+        names::HOLDINGS_INVARIANT_VIOLATIONS_TOTAL,
+        labels::MARKET_ID => market_id.to_string()
+    )
+    .increment(1);
+}
+
+/// Record orders left open on a closed market and cancelled by the janitor,
+/// by the market's status ("resolved" or "cancelled")
+pub fn record_stale_orders_cancelled(market_status: &str, count: u64) {
+    counter!(
+        names::STALE_ORDERS_CANCELLED_TOTAL,
+        labels::STATUS => market_status.to_string()
+    )
+    .increment(count);
+}
+
+// ============================================================================
+// Notification Metrics
+// ============================================================================
+
+/// Record notification send attempt result ("sent", "retry", "failed")
+pub fn record_notification(kind: &str, result: &str) {
+    counter!(
+        names::NOTIFICATIONS_SENT_TOTAL,
+        labels::KIND => kind.to_string(),
+        labels::RESULT => result.to_string()
+    )
+    .increment(1);
+}
+
+// ============================================================================
+// Coordination Metrics
+// ============================================================================
+
+/// Set whether this instance currently leads an election (1) or not (0)
+pub fn set_leader(election: &str, leader: bool) {
+    gauge!(
+        names::LEADER_STATUS,
+        labels::ELECTION => election.to_string()
+    )
+    .set(if leader { 1.0 } else { 0.0 });
+}
+
 // ============================================================================
 // Timer Helper
 // ============================================================================
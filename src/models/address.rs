@@ -0,0 +1,146 @@
+//! Account Addresses
+//!
+//! Addresses are stored, compared and used as keys in one canonical form:
+//! `0x` followed by 40 lowercase hex digits. [`Address`] holds that form, so
+//! two addresses are equal exactly when their accounts are. It displays
+//! with its EIP-55 checksum; serialized and bound to queries it is the
+//! canonical form the database holds.
+//!
+//! Parsing accepts either case, but a mixed-case address must carry a
+//! valid checksum: a typo in a checksummed address is refused instead of
+//! naming some other account.
+
+use std::fmt;
+use std::str::FromStr;
+
+use ethers::types::H160;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AddressError {
+    #[error("'{0}' is not a 0x-prefixed 40 hex digit address")]
+    Format(String),
+
+    #[error("'{0}' does not match its EIP-55 checksum")]
+    Checksum(String),
+}
+
+/// An account address in canonical (lowercase) form
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
+pub struct Address(String);
+
+impl Address {
+    /// The canonical form: `0x` and 40 lowercase hex digits
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The EIP-55 checksummed form, for display
+    pub fn checksum(&self) -> String {
+        ethers::utils::to_checksum(&self.to_h160(), None)
+    }
+
+    pub fn to_h160(&self) -> H160 {
+        self.0.parse().unwrap_or_default()
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s
+            .strip_prefix("0x")
+            .filter(|hex| hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| AddressError::Format(s.to_string()))?;
+
+        let address = Address(format!("0x{}", hex.to_ascii_lowercase()));
+        let mixed_case = hex.bytes().any(|b| b.is_ascii_lowercase()) && hex.bytes().any(|b| b.is_ascii_uppercase());
+        if mixed_case && address.checksum() != s {
+            return Err(AddressError::Checksum(s.to_string()));
+        }
+        Ok(address)
+    }
+}
+
+impl TryFrom<String> for Address {
+    type Error = AddressError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Address> for String {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+impl From<H160> for Address {
+    fn from(address: H160) -> Self {
+        Address(format!("{:#x}", address))
+    }
+}
+
+impl AsRef<str> for Address {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Address {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.checksum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The EIP-55 specification's examples
+    const CHECKSUMMED: [&str; 4] = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn test_parse_canonicalizes_and_checks_checksum() {
+        for checksummed in CHECKSUMMED {
+            let address: Address = checksummed.parse().unwrap();
+            assert_eq!(address.as_str(), checksummed.to_lowercase());
+            assert_eq!(address.to_string(), checksummed);
+            // Single-case input carries no checksum
+            assert_eq!(checksummed.to_lowercase().parse::<Address>(), Ok(address.clone()));
+            assert_eq!(format!("0x{}", checksummed[2..].to_uppercase()).parse::<Address>(), Ok(address));
+        }
+
+        let typo = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD";
+        assert_eq!(typo.parse::<Address>(), Err(AddressError::Checksum(typo.to_string())));
+        for bad in ["", "0x", "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beae", "0xzaaeb6053f3e94c9b9a09f33669435e7ef1beaed"] {
+            assert!(matches!(bad.parse::<Address>(), Err(AddressError::Format(_))));
+        }
+    }
+
+    #[test]
+    fn test_h160_and_serde_round_trip() {
+        let address: Address = CHECKSUMMED[0].parse().unwrap();
+        assert_eq!(Address::from(address.to_h160()), address);
+
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(json, format!("\"{}\"", address.as_str()));
+        assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), address);
+        assert!(serde_json::from_str::<Address>("\"0x1234\"").is_err());
+    }
+}
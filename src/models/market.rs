@@ -7,58 +7,43 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::{FromRow, Postgres};
 use uuid::Uuid;
 
-/// 份额类型
-///
-/// 预测市场中的两种结果份额：Yes 和 No
-/// Yes + No 的价格总和始终等于 1
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "share_type", rename_all = "lowercase")]
-#[serde(rename_all = "lowercase")]
-pub enum ShareType {
-    /// Yes 份额 - 预测事件会发生
-    Yes,
-    /// No 份额 - 预测事件不会发生
-    No,
-}
+/// 份额类型 (defined in the engine crate, shared with the API and DB layers)
+pub use polymarket_engine::ShareType;
 
-impl ShareType {
-    /// 获取互补份额类型
-    ///
-    /// Yes 的互补是 No，No 的互补是 Yes
-    pub fn complement(&self) -> ShareType {
-        match self {
-            ShareType::Yes => ShareType::No,
-            ShareType::No => ShareType::Yes,
-        }
-    }
+/// [`ShareType`] as the Postgres `share_type` enum. The engine crate knows
+/// nothing of the database, so binds go through this and rows decode
+/// through it (`#[sqlx(try_from = "DbShareType")]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbShareType(pub ShareType);
 
-    /// 转换为字符串
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            ShareType::Yes => "yes",
-            ShareType::No => "no",
-        }
+impl sqlx::Type<Postgres> for DbShareType {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("share_type")
     }
 }
 
-impl std::fmt::Display for ShareType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_str())
+impl sqlx::Encode<'_, Postgres> for DbShareType {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        <&str as sqlx::Encode<Postgres>>::encode(self.0.as_str(), buf)
     }
 }
 
-impl std::str::FromStr for ShareType {
-    type Err = String;
+impl<'r> sqlx::Decode<'r, Postgres> for DbShareType {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let label = <&str as sqlx::Decode<Postgres>>::decode(value)?;
+        Ok(Self(label.parse()?))
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "yes" => Ok(ShareType::Yes),
-            "no" => Ok(ShareType::No),
-            _ => Err(format!("Invalid share type: {}", s)),
-        }
+impl From<DbShareType> for ShareType {
+    fn from(share_type: DbShareType) -> Self {
+        share_type.0
     }
 }
 
@@ -178,6 +163,7 @@ pub struct Outcome {
     pub name: String,
 
     /// 份额类型
+    #[sqlx(try_from = "DbShareType")]
     pub share_type: ShareType,
 
     /// 互补结果 ID
@@ -274,21 +260,6 @@ pub struct CreateMarketRequest {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_share_type_complement() {
-        assert_eq!(ShareType::Yes.complement(), ShareType::No);
-        assert_eq!(ShareType::No.complement(), ShareType::Yes);
-    }
-
-    #[test]
-    fn test_share_type_from_str() {
-        assert_eq!("yes".parse::<ShareType>().unwrap(), ShareType::Yes);
-        assert_eq!("YES".parse::<ShareType>().unwrap(), ShareType::Yes);
-        assert_eq!("no".parse::<ShareType>().unwrap(), ShareType::No);
-        assert_eq!("No".parse::<ShareType>().unwrap(), ShareType::No);
-        assert!("invalid".parse::<ShareType>().is_err());
-    }
-
     #[test]
     fn test_market_status_tradable() {
         assert!(MarketStatus::Active.is_tradable());
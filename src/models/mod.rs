@@ -1,8 +1,10 @@
+pub mod address;
 pub mod user;
 pub mod order;
 pub mod market;
 pub mod balance;
 
+pub use address::{Address, AddressError};
 pub use user::*;
 pub use order::*;
 #[allow(unused_imports)]
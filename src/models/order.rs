@@ -8,13 +8,16 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::fmt;
 use uuid::Uuid;
+use validator::Validate;
 
-use super::market::ShareType;
+use crate::api::validation;
+
+use super::market::{DbShareType, ShareType};
 
 /// 序列化 DateTime 为毫秒时间戳
 mod datetime_as_millis {
     use chrono::{DateTime, Utc};
-    use serde::Serializer;
+    use serde::{Deserialize, Deserializer, Serializer};
 
     pub fn serialize<S>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -22,13 +25,20 @@ mod datetime_as_millis {
     {
         serializer.serialize_i64(dt.timestamp_millis())
     }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let millis = i64::deserialize(deserializer)?;
+        DateTime::from_timestamp_millis(millis).ok_or_else(|| serde::de::Error::custom("timestamp out of range"))
+    }
 }
 
 /// 可选 DateTime 序列化为毫秒时间戳
-#[allow(dead_code)]
 mod option_datetime_as_millis {
     use chrono::{DateTime, Utc};
-    use serde::Serializer;
+    use serde::{Deserialize, Deserializer, Serializer};
 
     pub fn serialize<S>(dt: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -39,6 +49,18 @@ mod option_datetime_as_millis {
             None => serializer.serialize_none(),
         }
     }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<i64>::deserialize(deserializer)? {
+            Some(millis) => DateTime::from_timestamp_millis(millis)
+                .map(Some)
+                .ok_or_else(|| serde::de::Error::custom("timestamp out of range")),
+            None => Ok(None),
+        }
+    }
 }
 
 /// 订单方向
@@ -115,13 +137,54 @@ impl std::str::FromStr for OrderType {
     }
 }
 
+/// 订单有效期
+///
+/// 决定限价单未成交部分的去向: GTC 挂入订单簿，IOC 撤销，FOK 无法全部成交时
+/// 直接拒绝。市价单从不挂单。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "time_in_force", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TimeInForce {
+    /// 一直有效直到撤销
+    #[default]
+    Gtc,
+    /// 立即成交可成交部分，剩余撤销
+    Ioc,
+    /// 全部成交或全部拒绝
+    Fok,
+}
+
+impl fmt::Display for TimeInForce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeInForce::Gtc => write!(f, "gtc"),
+            TimeInForce::Ioc => write!(f, "ioc"),
+            TimeInForce::Fok => write!(f, "fok"),
+        }
+    }
+}
+
+impl From<TimeInForce> for polymarket_engine::TimeInForce {
+    fn from(time_in_force: TimeInForce) -> Self {
+        match time_in_force {
+            TimeInForce::Gtc => polymarket_engine::TimeInForce::GTC,
+            TimeInForce::Ioc => polymarket_engine::TimeInForce::IOC,
+            TimeInForce::Fok => polymarket_engine::TimeInForce::FOK,
+        }
+    }
+}
+
 /// 订单状态
+///
+/// 状态机与撮合引擎一致，见 [`polymarket_engine::OrderStatus::can_transition_to`]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "order_status", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum OrderStatus {
-    /// 等待中
+    /// 等待中 (旧数据)
     Pending,
+    /// 已接受，撮合中
+    Accepted,
     /// 挂单中
     Open,
     /// 部分成交
@@ -130,19 +193,27 @@ pub enum OrderStatus {
     Filled,
     /// 已取消
     Cancelled,
-    /// 已拒绝
+    /// 已过期 (市价单未成交部分)
+    Expired,
+    /// 已拒绝，原因见 reject_reason
     Rejected,
 }
 
 impl OrderStatus {
     /// 检查订单是否处于活动状态
     pub fn is_active(&self) -> bool {
-        matches!(self, OrderStatus::Pending | OrderStatus::Open | OrderStatus::PartiallyFilled)
+        matches!(
+            self,
+            OrderStatus::Pending | OrderStatus::Accepted | OrderStatus::Open | OrderStatus::PartiallyFilled
+        )
     }
 
     /// 检查订单是否已结束
     pub fn is_final(&self) -> bool {
-        matches!(self, OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Rejected)
+        matches!(
+            self,
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Expired | OrderStatus::Rejected
+        )
     }
 }
 
@@ -150,16 +221,32 @@ impl fmt::Display for OrderStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
             OrderStatus::Pending => "pending",
+            OrderStatus::Accepted => "accepted",
             OrderStatus::Open => "open",
             OrderStatus::PartiallyFilled => "partially_filled",
             OrderStatus::Filled => "filled",
             OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Expired => "expired",
             OrderStatus::Rejected => "rejected",
         };
         write!(f, "{}", s)
     }
 }
 
+impl From<polymarket_engine::OrderStatus> for OrderStatus {
+    fn from(status: polymarket_engine::OrderStatus) -> Self {
+        match status {
+            polymarket_engine::OrderStatus::Accepted => OrderStatus::Accepted,
+            polymarket_engine::OrderStatus::Open => OrderStatus::Open,
+            polymarket_engine::OrderStatus::PartiallyFilled => OrderStatus::PartiallyFilled,
+            polymarket_engine::OrderStatus::Filled => OrderStatus::Filled,
+            polymarket_engine::OrderStatus::Cancelled => OrderStatus::Cancelled,
+            polymarket_engine::OrderStatus::Expired => OrderStatus::Expired,
+            polymarket_engine::OrderStatus::Rejected => OrderStatus::Rejected,
+        }
+    }
+}
+
 /// 预测市场订单
 ///
 /// 表示用户在预测市场中的一个订单
@@ -178,6 +265,7 @@ pub struct Order {
     pub outcome_id: Uuid,
 
     /// 份额类型 (Yes/No)
+    #[sqlx(try_from = "DbShareType")]
     pub share_type: ShareType,
 
     /// 订单方向 (Buy/Sell)
@@ -186,6 +274,12 @@ pub struct Order {
     /// 订单类型 (Limit/Market)
     pub order_type: OrderType,
 
+    /// 订单有效期 (GTC/IOC/FOK)
+    pub time_in_force: TimeInForce,
+
+    /// 只挂单: 下单时会立即成交则拒绝
+    pub post_only: bool,
+
     /// 概率价格 (0.01 - 0.99)
     /// 对于限价单，这是用户指定的价格
     /// 对于市价单，这是成交后的平均价格
@@ -200,6 +294,13 @@ pub struct Order {
     /// 订单状态
     pub status: OrderStatus,
 
+    /// 拒绝原因代码 (仅 rejected)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<String>,
+
+    /// 下单来源 (api / auto_mm / settlement)
+    pub source: String,
+
     /// EIP-712 签名
     pub signature: String,
 
@@ -210,6 +311,11 @@ pub struct Order {
     /// 更新时间
     #[serde(serialize_with = "datetime_as_millis::serialize")]
     pub updated_at: DateTime<Utc>,
+
+    /// 到期时间 (GTD 订单)，未查询该列时为空
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "option_datetime_as_millis")]
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl Order {
@@ -271,7 +377,7 @@ pub enum OrderValidationError {
 }
 
 /// 创建订单请求
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct CreateOrderRequest {
     /// 市场 ID
     pub market_id: Uuid,
@@ -288,10 +394,24 @@ pub struct CreateOrderRequest {
     /// 订单类型
     pub order_type: OrderType,
 
+    /// 订单有效期，默认 GTC
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+
+    /// 只挂单: 会立即成交 (含与互补订单簿的铸造/合并) 时拒绝，保证以 maker 成交
+    #[serde(default)]
+    pub post_only: bool,
+
+    /// 自成交保护: 与自己的挂单相遇时如何处理，默认撤销新订单的剩余部分
+    #[serde(default)]
+    pub stp: polymarket_engine::SelfTradePrevention,
+
     /// 概率价格 (0.01 - 0.99)
+    #[validate(custom = "validation::price")]
     pub price: Decimal,
 
     /// 订单数量 (份额)
+    #[validate(custom = "validation::positive")]
     pub amount: Decimal,
 
     /// EIP-712 签名
@@ -299,6 +419,28 @@ pub struct CreateOrderRequest {
 
     /// 签名时间戳 (毫秒)
     pub timestamp: u64,
+
+    /// 允许卖空: 无持仓的卖单转为以 1-p 买入互补份额；部分持仓的卖单不转换，按持仓不足拒绝
+    #[serde(default)]
+    pub allow_short: bool,
+
+    /// 到期时间 (毫秒，仅 GTC 限价单): 未成交部分届时过期
+    #[serde(default, with = "option_datetime_as_millis")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl CreateOrderRequest {
+    /// 卖空转换: 卖出 X 份 Yes @ p 等价于买入 X 份 No @ 1-p，
+    /// 锁定 (1-p) * X 抵押品
+    pub fn short_as_complement_buy(&self) -> Self {
+        Self {
+            share_type: self.share_type.complement(),
+            side: OrderSide::Buy,
+            price: Decimal::ONE - self.price,
+            signature: self.signature.clone(),
+            ..*self
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -355,6 +497,45 @@ impl CreateOrderRequest {
     }
 }
 
+/// 成交方角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillLiquidity {
+    /// 挂单被动成交
+    Maker,
+    /// 主动吃单
+    Taker,
+}
+
+/// 订单的单笔成交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderFill {
+    /// 成交 ID
+    pub trade_id: Uuid,
+
+    /// 成交价格 (调整后价格优先)
+    pub price: Decimal,
+
+    /// 成交数量
+    pub amount: Decimal,
+
+    /// 本订单一方支付的手续费
+    pub fee: Decimal,
+
+    /// 本订单在成交中的角色
+    pub liquidity: FillLiquidity,
+
+    /// 链上结算状态 (pending / submitted / confirmed / failed)
+    pub settlement_status: String,
+
+    /// 成交被调整时的状态 (busted / price_adjusted)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustment_status: Option<String>,
+
+    /// 成交时间 (毫秒)
+    pub timestamp: i64,
+}
+
 /// 订单响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderResponse {
@@ -376,6 +557,14 @@ pub struct OrderResponse {
     /// 订单类型
     pub order_type: OrderType,
 
+    /// 订单有效期，引擎推送的更新中不含
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+
+    /// 只挂单，引擎推送的更新中不含
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_only: Option<bool>,
+
     /// 概率价格
     pub price: Decimal,
 
@@ -391,9 +580,25 @@ pub struct OrderResponse {
     /// 订单状态
     pub status: OrderStatus,
 
+    /// 拒绝原因代码 (仅 rejected)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<String>,
+
+    /// 下单来源 (api / auto_mm / settlement)，引擎推送的更新中不含
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
     /// 创建时间
-    #[serde(serialize_with = "datetime_as_millis::serialize")]
+    #[serde(with = "datetime_as_millis")]
     pub created_at: DateTime<Utc>,
+
+    /// 到期时间 (毫秒)，引擎推送的更新中不含
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "option_datetime_as_millis")]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// 逐笔成交 (仅订单详情返回)，按时间从早到晚
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fills: Option<Vec<OrderFill>>,
 }
 
 impl From<Order> for OrderResponse {
@@ -405,12 +610,18 @@ impl From<Order> for OrderResponse {
             share_type: order.share_type,
             side: order.side,
             order_type: order.order_type,
+            time_in_force: Some(order.time_in_force),
+            post_only: Some(order.post_only),
             price: order.price,
             amount: order.amount,
             filled_amount: order.filled_amount,
             remaining_amount: order.remaining_amount(),
             status: order.status,
+            reject_reason: order.reject_reason,
+            source: Some(order.source),
             created_at: order.created_at,
+            expires_at: order.expires_at,
+            fills: None,
         }
     }
 }
@@ -483,13 +694,18 @@ mod tests {
             share_type: ShareType::Yes,
             side: OrderSide::Buy,
             order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
             price: dec!(0.65),
             amount: dec!(100),
             filled_amount: dec!(30),
             status: OrderStatus::PartiallyFilled,
+            reject_reason: None,
+            source: "api".to_string(),
             signature: "0x".to_string(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            expires_at: None,
         };
 
         assert_eq!(order.remaining_amount(), dec!(70));
@@ -505,18 +721,47 @@ mod tests {
             share_type: ShareType::Yes,
             side: OrderSide::Buy,
             order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
             price: dec!(0.65),
             amount: dec!(100),
             filled_amount: dec!(0),
             status: OrderStatus::Open,
+            reject_reason: None,
+            source: "api".to_string(),
             signature: "0x".to_string(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            expires_at: None,
         };
 
         assert_eq!(order.complement_price(), dec!(0.35));
     }
 
+    #[test]
+    fn test_short_sell_becomes_complement_buy() {
+        let sell_yes = CreateOrderRequest {
+            market_id: Uuid::new_v4(),
+            outcome_id: Uuid::new_v4(),
+            share_type: ShareType::Yes,
+            side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Ioc,
+            post_only: false,
+            stp: Default::default(),
+            price: dec!(0.70),
+            amount: dec!(10),
+            signature: "0x".to_string(),
+            timestamp: 1704067200000,
+            allow_short: true,
+            expires_at: None,
+        };
+        let placed = sell_yes.short_as_complement_buy();
+        assert_eq!((placed.share_type, placed.side, placed.price), (ShareType::No, OrderSide::Buy, dec!(0.30)));
+        assert_eq!((placed.outcome_id, placed.amount), (sell_yes.outcome_id, dec!(10)));
+        assert_eq!(placed.time_in_force, TimeInForce::Ioc);
+    }
+
     #[test]
     fn test_create_order_request_validation() {
         // Valid request
@@ -526,10 +771,15 @@ mod tests {
             share_type: ShareType::Yes,
             side: OrderSide::Buy,
             order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            stp: Default::default(),
             price: dec!(0.65),
             amount: dec!(10),
             signature: "0x".to_string(),
             timestamp: 1704067200000,
+            allow_short: false,
+            expires_at: None,
         };
         assert!(valid_req.validate().is_ok());
 
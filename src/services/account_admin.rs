@@ -0,0 +1,500 @@
+//! Admin Account Management
+//!
+//! Support looks up an account (balances, holdings, open orders, recent
+//! logins, flags and notes), freezes or unfreezes it, force-cancels its
+//! orders, sets its market data tier and annotates it. Every one of those accesses - lookups included -
+//! is appended to `admin_audit_log`; mutations write their audit entry in
+//! the same transaction, so an unaudited change cannot be committed.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::market_data_tier::MarketDataTier;
+
+/// Logins shown in an account overview
+const RECENT_LOGINS: i64 = 20;
+/// Support notes shown in an account overview
+const RECENT_NOTES: i64 = 50;
+
+#[derive(Debug, Error)]
+pub enum AccountAdminError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Account is already frozen")]
+    AlreadyFrozen,
+    #[error("Account is not frozen")]
+    NotFrozen,
+    #[error("A reason is required")]
+    MissingReason,
+    #[error("Note must not be empty")]
+    EmptyNote,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl AccountAdminError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            AccountAdminError::UserNotFound => "USER_NOT_FOUND",
+            AccountAdminError::AlreadyFrozen => "ACCOUNT_ALREADY_FROZEN",
+            AccountAdminError::NotFrozen => "ACCOUNT_NOT_FROZEN",
+            AccountAdminError::MissingReason => "MISSING_REASON",
+            AccountAdminError::EmptyNote => "EMPTY_NOTE",
+            AccountAdminError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+/// Audited admin actions on an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    ViewAccount,
+    Freeze,
+    Unfreeze,
+    CancelOrders,
+    AddNote,
+    SetMarketDataTier,
+    SetApiQuota,
+}
+
+impl AdminAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminAction::ViewAccount => "view_account",
+            AdminAction::Freeze => "freeze",
+            AdminAction::Unfreeze => "unfreeze",
+            AdminAction::CancelOrders => "cancel_orders",
+            AdminAction::AddNote => "add_note",
+            AdminAction::SetMarketDataTier => "set_market_data_tier",
+            AdminAction::SetApiQuota => "set_api_quota",
+        }
+    }
+}
+
+/// Freeze state and market data tier of an account
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccountFlags {
+    pub role: String,
+    pub market_data_tier: String,
+    pub frozen_at: Option<DateTime<Utc>>,
+    pub frozen_reason: Option<String>,
+    pub frozen_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccountBalance {
+    pub token: String,
+    pub available: Decimal,
+    pub frozen: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccountHolding {
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: String,
+    pub amount: Decimal,
+    pub avg_cost: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccountOpenOrder {
+    pub id: Uuid,
+    pub market_id: Option<Uuid>,
+    pub outcome_id: Option<Uuid>,
+    pub share_type: Option<String>,
+    pub side: String,
+    pub price: Option<Decimal>,
+    pub amount: Decimal,
+    pub filled_amount: Decimal,
+    pub status: String,
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A login session, revoked ones included
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccountLogin {
+    pub id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub issued_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SupportNote {
+    pub id: Uuid,
+    pub admin_address: String,
+    pub note: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub admin_address: String,
+    pub action: String,
+    pub target_address: String,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Everything support needs to see about one account
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountOverview {
+    pub address: Address,
+    pub flags: AccountFlags,
+    pub balances: Vec<AccountBalance>,
+    pub holdings: Vec<AccountHolding>,
+    pub open_orders: Vec<AccountOpenOrder>,
+    pub recent_logins: Vec<AccountLogin>,
+    pub notes: Vec<SupportNote>,
+}
+
+/// Append an audit entry
+pub async fn audit(
+    conn: &mut PgConnection,
+    admin_address: &Address,
+    action: AdminAction,
+    target_address: &Address,
+    details: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO admin_audit_log (admin_address, action, target_address, details) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(admin_address)
+    .bind(action.as_str())
+    .bind(target_address)
+    .bind(&details)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Look up `address` on behalf of `admin_address`
+pub async fn overview(
+    pool: &PgPool,
+    admin_address: &Address,
+    address: &Address,
+) -> Result<AccountOverview, AccountAdminError> {
+    let flags: AccountFlags = sqlx::query_as(
+        "SELECT role::text AS role, market_data_tier, frozen_at, frozen_reason, frozen_by, created_at FROM users WHERE address = $1",
+    )
+    .bind(address)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AccountAdminError::UserNotFound)?;
+
+    let mut conn = pool.acquire().await?;
+    audit(&mut conn, admin_address, AdminAction::ViewAccount, address, serde_json::json!({})).await?;
+
+    let balances = sqlx::query_as(
+        "SELECT token, available, frozen FROM balances WHERE user_address = $1 ORDER BY token",
+    )
+    .bind(address)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let holdings = sqlx::query_as(
+        r#"
+        SELECT market_id, outcome_id, share_type::text AS share_type, amount, avg_cost
+        FROM shares
+        WHERE user_address = $1 AND amount > 0
+        ORDER BY updated_at DESC
+        "#,
+    )
+    .bind(address)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let open_orders = open_orders(&mut conn, address).await?;
+
+    let recent_logins = sqlx::query_as(
+        r#"
+        SELECT id, ip_address, user_agent, issued_at, last_used_at, revoked_at
+        FROM user_sessions
+        WHERE user_address = $1
+        ORDER BY issued_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(address)
+    .bind(RECENT_LOGINS)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let notes = sqlx::query_as(
+        r#"
+        SELECT id, admin_address, note, created_at
+        FROM user_support_notes
+        WHERE user_address = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(address)
+    .bind(RECENT_NOTES)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(AccountOverview {
+        address: address.clone(),
+        flags,
+        balances,
+        holdings,
+        open_orders,
+        recent_logins,
+        notes,
+    })
+}
+
+/// Open orders of `address`, oldest first
+pub async fn open_orders(conn: &mut PgConnection, address: &Address) -> Result<Vec<AccountOpenOrder>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, market_id, outcome_id, share_type::text AS share_type, side::text AS side,
+               price, amount, filled_amount, status::text AS status, source, created_at
+        FROM orders
+        WHERE user_address = $1 AND status IN ('open', 'partially_filled')
+        ORDER BY created_at
+        "#,
+    )
+    .bind(address)
+    .fetch_all(conn)
+    .await
+}
+
+/// Freeze `address`; state-changing requests of the account are refused
+/// until it is unfrozen
+pub async fn freeze(
+    pool: &PgPool,
+    admin_address: &Address,
+    address: &Address,
+    reason: &str,
+) -> Result<(), AccountAdminError> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(AccountAdminError::MissingReason);
+    }
+
+    let mut tx = pool.begin().await?;
+    let frozen_at: Option<Option<DateTime<Utc>>> =
+        sqlx::query_scalar("SELECT frozen_at FROM users WHERE address = $1 FOR UPDATE")
+            .bind(address)
+            .fetch_optional(&mut *tx)
+            .await?;
+    match frozen_at {
+        None => return Err(AccountAdminError::UserNotFound),
+        Some(Some(_)) => return Err(AccountAdminError::AlreadyFrozen),
+        Some(None) => {}
+    }
+
+    sqlx::query("UPDATE users SET frozen_at = NOW(), frozen_reason = $2, frozen_by = $3 WHERE address = $1")
+        .bind(address)
+        .bind(reason)
+        .bind(admin_address)
+        .execute(&mut *tx)
+        .await?;
+    audit(&mut tx, admin_address, AdminAction::Freeze, address, serde_json::json!({ "reason": reason })).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Move `address` to market data `tier`; returns the previous tier. Takes
+/// effect on the account's next WebSocket subscriptions.
+pub async fn set_market_data_tier(
+    pool: &PgPool,
+    admin_address: &Address,
+    address: &Address,
+    tier: MarketDataTier,
+    reason: &str,
+) -> Result<MarketDataTier, AccountAdminError> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(AccountAdminError::MissingReason);
+    }
+
+    let mut tx = pool.begin().await?;
+    let previous: Option<String> = sqlx::query_scalar("SELECT market_data_tier FROM users WHERE address = $1 FOR UPDATE")
+        .bind(address)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let previous = previous.ok_or(AccountAdminError::UserNotFound)?;
+
+    sqlx::query("UPDATE users SET market_data_tier = $2 WHERE address = $1")
+        .bind(address)
+        .bind(tier.as_str())
+        .execute(&mut *tx)
+        .await?;
+    audit(
+        &mut tx,
+        admin_address,
+        AdminAction::SetMarketDataTier,
+        address,
+        serde_json::json!({
+            "reason": reason,
+            "tier": tier.as_str(),
+            "previous": previous,
+        }),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(MarketDataTier::parse(&previous).unwrap_or(MarketDataTier::RealTime))
+}
+
+/// Give `address` its own daily API quotas (`None` returns it to the
+/// platform default, 0 is unlimited); returns the previous ones
+pub async fn set_api_quota(
+    pool: &PgPool,
+    admin_address: &Address,
+    address: &Address,
+    requests: Option<i64>,
+    bytes: Option<i64>,
+    reason: &str,
+) -> Result<(Option<i64>, Option<i64>), AccountAdminError> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(AccountAdminError::MissingReason);
+    }
+
+    let mut tx = pool.begin().await?;
+    let previous: Option<(Option<i64>, Option<i64>)> = sqlx::query_as(
+        "SELECT api_daily_request_quota, api_daily_byte_quota FROM users WHERE address = $1 FOR UPDATE",
+    )
+    .bind(address)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let previous = previous.ok_or(AccountAdminError::UserNotFound)?;
+
+    sqlx::query("UPDATE users SET api_daily_request_quota = $2, api_daily_byte_quota = $3 WHERE address = $1")
+        .bind(address)
+        .bind(requests)
+        .bind(bytes)
+        .execute(&mut *tx)
+        .await?;
+    audit(
+        &mut tx,
+        admin_address,
+        AdminAction::SetApiQuota,
+        address,
+        serde_json::json!({
+            "reason": reason,
+            "requests": requests,
+            "bytes": bytes,
+            "previous": {"requests": previous.0, "bytes": previous.1},
+        }),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(previous)
+}
+
+/// Lift the freeze of `address`
+pub async fn unfreeze(
+    pool: &PgPool,
+    admin_address: &Address,
+    address: &Address,
+    reason: &str,
+) -> Result<(), AccountAdminError> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(AccountAdminError::MissingReason);
+    }
+
+    let mut tx = pool.begin().await?;
+    let previous: Option<(Option<DateTime<Utc>>, Option<String>)> =
+        sqlx::query_as("SELECT frozen_at, frozen_reason FROM users WHERE address = $1 FOR UPDATE")
+            .bind(address)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let (frozen_at, frozen_reason) = match previous {
+        None => return Err(AccountAdminError::UserNotFound),
+        Some((None, _)) => return Err(AccountAdminError::NotFrozen),
+        Some((Some(at), why)) => (at, why),
+    };
+
+    sqlx::query("UPDATE users SET frozen_at = NULL, frozen_reason = NULL, frozen_by = NULL WHERE address = $1")
+        .bind(address)
+        .execute(&mut *tx)
+        .await?;
+    audit(
+        &mut tx,
+        admin_address,
+        AdminAction::Unfreeze,
+        address,
+        serde_json::json!({
+            "reason": reason,
+            "frozen_at": frozen_at,
+            "frozen_reason": frozen_reason,
+        }),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Attach a support note to `address`
+pub async fn add_note(
+    pool: &PgPool,
+    admin_address: &Address,
+    address: &Address,
+    note: &str,
+) -> Result<SupportNote, AccountAdminError> {
+    let note = note.trim();
+    if note.is_empty() {
+        return Err(AccountAdminError::EmptyNote);
+    }
+
+    let mut tx = pool.begin().await?;
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE address = $1)")
+        .bind(address)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !exists {
+        return Err(AccountAdminError::UserNotFound);
+    }
+
+    let note: SupportNote = sqlx::query_as(
+        r#"
+        INSERT INTO user_support_notes (user_address, admin_address, note)
+        VALUES ($1, $2, $3)
+        RETURNING id, admin_address, note, created_at
+        "#,
+    )
+    .bind(address)
+    .bind(admin_address)
+    .bind(note)
+    .fetch_one(&mut *tx)
+    .await?;
+    audit(&mut tx, admin_address, AdminAction::AddNote, address, serde_json::json!({ "note_id": note.id })).await?;
+    tx.commit().await?;
+    Ok(note)
+}
+
+/// Audit entries about `address`, newest first
+pub async fn audit_log(pool: &PgPool, address: &Address, limit: i64) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, admin_address, action, target_address, details, created_at
+        FROM admin_audit_log
+        WHERE target_address = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(address)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
@@ -0,0 +1,290 @@
+//! Two-Person Rule for High-Risk Admin Actions
+//!
+//! Resolving a market by hand and busting or re-pricing a trade move user
+//! funds on one admin's word. While `ADMIN_TWO_PERSON_RULE` is on, their
+//! admin endpoints refuse to act directly. Instead an admin proposes the
+//! action with the request body it should run with, a *different* admin
+//! approves it, and either of them executes it before it expires:
+//!
+//! ```text
+//! pending --approve--> approved --execute--> executed
+//!    \______________________\____ expires_at ____> expired
+//! ```
+//!
+//! Each step is dispatched to platform-wide webhook subscriptions
+//! (`admin.approval`), so an approver can check the proposal against an
+//! independent record before signing off.
+//!
+//! Treasury sweeps are not covered: there is no admin endpoint that moves
+//! treasury funds yet. When one is added it needs its own
+//! [`ApprovalAction`] variant and the same direct-call refusal.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::Address;
+
+#[derive(Debug, Error)]
+pub enum ApprovalError {
+    #[error("Approval not found")]
+    NotFound,
+    #[error("Approval expired")]
+    Expired,
+    #[error("The proposing admin cannot approve their own proposal")]
+    SelfApproval,
+    #[error("Approval is {0}")]
+    InvalidStatus(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl ApprovalError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApprovalError::NotFound => "APPROVAL_NOT_FOUND",
+            ApprovalError::Expired => "APPROVAL_EXPIRED",
+            ApprovalError::SelfApproval => "SELF_APPROVAL",
+            ApprovalError::InvalidStatus(_) => "INVALID_STATUS",
+            ApprovalError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+/// Admin actions that need a second approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalAction {
+    /// `POST /admin/markets/:market_id/resolve`
+    MarketResolution,
+    /// `POST /admin/trades/:trade_id/bust`
+    TradeBust,
+    /// `POST /admin/trades/:trade_id/adjust`
+    TradeAdjust,
+}
+
+impl ApprovalAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalAction::MarketResolution => "market_resolution",
+            ApprovalAction::TradeBust => "trade_bust",
+            ApprovalAction::TradeAdjust => "trade_adjust",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "market_resolution" => Some(ApprovalAction::MarketResolution),
+            "trade_bust" => Some(ApprovalAction::TradeBust),
+            "trade_adjust" => Some(ApprovalAction::TradeAdjust),
+            _ => None,
+        }
+    }
+}
+
+/// A proposed admin action
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AdminApproval {
+    pub id: Uuid,
+    pub action: String,
+    /// Market or trade the action applies to
+    pub target_id: Uuid,
+    /// Request body the action executes with
+    pub params: serde_json::Value,
+    pub reason: String,
+    /// pending, approved, executed or expired
+    pub status: String,
+    pub proposed_by: String,
+    pub approved_by: Option<String>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub executed_by: Option<String>,
+    pub executed_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Columns of [`AdminApproval`]; unexecuted rows past their expiry read as
+/// `expired`
+const COLUMNS: &str = r#"
+    id, action, target_id, params, reason,
+    CASE WHEN status IN ('pending', 'approved') AND expires_at <= NOW() THEN 'expired' ELSE status END AS status,
+    proposed_by, approved_by, approved_at, executed_by, executed_at, expires_at, created_at
+"#;
+
+/// Record a proposal by `admin_address`, open for approval for `ttl`
+pub async fn propose(
+    pool: &PgPool,
+    action: ApprovalAction,
+    target_id: Uuid,
+    params: &serde_json::Value,
+    reason: &str,
+    admin_address: &Address,
+    ttl: Duration,
+) -> Result<AdminApproval, sqlx::Error> {
+    sqlx::query_as(&format!(
+        r#"
+        INSERT INTO admin_approvals (action, target_id, params, reason, proposed_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(secs => $6))
+        RETURNING {}
+        "#,
+        COLUMNS
+    ))
+    .bind(action.as_str())
+    .bind(target_id)
+    .bind(params)
+    .bind(reason)
+    .bind(admin_address)
+    .bind(ttl.as_secs_f64())
+    .fetch_one(pool)
+    .await
+}
+
+/// Approve a pending proposal; the approver must not be the proposer
+pub async fn approve(pool: &PgPool, id: Uuid, admin_address: &Address) -> Result<AdminApproval, ApprovalError> {
+    let approved: Option<AdminApproval> = sqlx::query_as(&format!(
+        r#"
+        UPDATE admin_approvals
+        SET status = 'approved', approved_by = $2, approved_at = NOW()
+        WHERE id = $1 AND status = 'pending' AND expires_at > NOW() AND proposed_by <> $2
+        RETURNING {}
+        "#,
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(admin_address)
+    .fetch_optional(pool)
+    .await?;
+
+    match approved {
+        Some(approval) => Ok(approval),
+        None => {
+            let current = get(pool, id).await?.ok_or(ApprovalError::NotFound)?;
+            Err(match current.status.as_str() {
+                "expired" => ApprovalError::Expired,
+                "pending" if current.proposed_by == admin_address.as_str() => ApprovalError::SelfApproval,
+                status => ApprovalError::InvalidStatus(status.to_string()),
+            })
+        }
+    }
+}
+
+/// Claim an approved proposal for execution by `admin_address`. Call
+/// [`release`] if the action then fails so it can be retried.
+pub async fn claim(pool: &PgPool, id: Uuid, admin_address: &Address) -> Result<AdminApproval, ApprovalError> {
+    let claimed: Option<AdminApproval> = sqlx::query_as(&format!(
+        r#"
+        UPDATE admin_approvals
+        SET status = 'executed', executed_by = $2, executed_at = NOW()
+        WHERE id = $1 AND status = 'approved' AND expires_at > NOW()
+        RETURNING {}
+        "#,
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(admin_address)
+    .fetch_optional(pool)
+    .await?;
+
+    match claimed {
+        Some(approval) => Ok(approval),
+        None => {
+            let current = get(pool, id).await?.ok_or(ApprovalError::NotFound)?;
+            Err(match current.status.as_str() {
+                "expired" => ApprovalError::Expired,
+                status => ApprovalError::InvalidStatus(status.to_string()),
+            })
+        }
+    }
+}
+
+/// Return a claimed proposal whose action failed to `approved`
+pub async fn release(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE admin_approvals SET status = 'approved', executed_by = NULL, executed_at = NULL WHERE id = $1 AND status = 'executed'",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get(pool: &PgPool, id: Uuid) -> Result<Option<AdminApproval>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM admin_approvals WHERE id = $1", COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Most recent proposals, optionally only those with `status`
+pub async fn list(pool: &PgPool, status: Option<&str>, limit: i64) -> Result<Vec<AdminApproval>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        r#"
+        SELECT * FROM (SELECT {} FROM admin_approvals) a
+        WHERE ($1::text IS NULL OR a.status = $1)
+        ORDER BY a.created_at DESC
+        LIMIT $2
+        "#,
+        COLUMNS
+    ))
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    const PROPOSER: &str = "0x00000000000000000000000000000000000000d1";
+    const APPROVER: &str = "0x00000000000000000000000000000000000000d2";
+
+    #[tokio::test]
+    async fn test_second_admin_must_approve_before_execution() {
+        let app = TestApp::builder().build().await;
+        let pool = &app.db.pool;
+        let params = serde_json::json!({ "reason": "fat finger" });
+        let ttl = Duration::from_secs(600);
+        let (proposer, approver): (Address, Address) = (PROPOSER.parse().unwrap(), APPROVER.parse().unwrap());
+
+        let proposal = propose(pool, ApprovalAction::TradeBust, Uuid::new_v4(), &params, "fat finger", &proposer, ttl)
+            .await
+            .unwrap();
+        assert_eq!(proposal.status, "pending");
+        assert!(matches!(
+            claim(pool, proposal.id, &proposer).await,
+            Err(ApprovalError::InvalidStatus(s)) if s == "pending"
+        ));
+        assert!(matches!(approve(pool, proposal.id, &proposer).await, Err(ApprovalError::SelfApproval)));
+
+        let approved = approve(pool, proposal.id, &approver).await.unwrap();
+        assert_eq!((approved.status.as_str(), approved.approved_by.as_deref()), ("approved", Some(APPROVER)));
+
+        // A failed execution can be retried; a successful one only once
+        claim(pool, proposal.id, &proposer).await.unwrap();
+        release(pool, proposal.id).await.unwrap();
+        let executed = claim(pool, proposal.id, &approver).await.unwrap();
+        assert_eq!(executed.executed_by.as_deref(), Some(APPROVER));
+        assert!(matches!(
+            claim(pool, proposal.id, &approver).await,
+            Err(ApprovalError::InvalidStatus(s)) if s == "executed"
+        ));
+
+        let stale = propose(pool, ApprovalAction::TradeBust, Uuid::new_v4(), &params, "late", &proposer, ttl)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE admin_approvals SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+            .bind(stale.id)
+            .execute(pool)
+            .await
+            .unwrap();
+        assert!(matches!(approve(pool, stale.id, &approver).await, Err(ApprovalError::Expired)));
+        assert_eq!(list(pool, Some("expired"), 10).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,180 @@
+//! Market Analytics Aggregation
+//!
+//! Rolls trades up into hourly and daily `market_analytics` buckets (volume,
+//! trade count, unique traders, average trade size, implied probability) so
+//! the market analytics tab reads a handful of pre-aggregated rows instead of
+//! scanning `trades`. The first pass after gaining leadership rebuilds every bucket;
+//! later passes only recompute the most recent buckets of each interval.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+
+use crate::services::leader_election::LeaderElection;
+
+/// How often the aggregation job runs
+const AGGREGATION_INTERVAL_SECS: u64 = 60;
+
+/// Bucket width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalyticsInterval {
+    Hour,
+    Day,
+}
+
+impl AnalyticsInterval {
+    pub const ALL: [AnalyticsInterval; 2] = [AnalyticsInterval::Hour, AnalyticsInterval::Day];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnalyticsInterval::Hour => "1h",
+            AnalyticsInterval::Day => "1d",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|i| i.as_str() == s)
+    }
+
+    /// `date_trunc` unit
+    fn trunc_unit(&self) -> &'static str {
+        match self {
+            AnalyticsInterval::Hour => "hour",
+            AnalyticsInterval::Day => "day",
+        }
+    }
+
+    pub fn duration(&self) -> ChronoDuration {
+        match self {
+            AnalyticsInterval::Hour => ChronoDuration::hours(1),
+            AnalyticsInterval::Day => ChronoDuration::days(1),
+        }
+    }
+
+    /// Window recomputed on incremental passes; covers the open bucket and
+    /// the one before it so late-persisted trades are picked up.
+    fn refresh_window(&self) -> ChronoDuration {
+        self.duration() * 2
+    }
+}
+
+/// Background job maintaining `market_analytics`
+pub struct MarketAnalyticsJob {
+    pool: PgPool,
+}
+
+impl MarketAnalyticsJob {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Spawn the aggregation loop; only the elected leader aggregates
+    pub fn start(self: Arc<Self>, leader: Arc<LeaderElection>) {
+        tokio::spawn(async move {
+            tracing::info!("Market analytics aggregation job started");
+            let mut interval = tokio::time::interval(Duration::from_secs(AGGREGATION_INTERVAL_SECS));
+            let mut full_rebuild_done = false;
+            loop {
+                interval.tick().await;
+                if !leader.is_leader() {
+                    // A newly elected leader starts with a full rebuild
+                    full_rebuild_done = false;
+                    continue;
+                }
+                let now = Utc::now();
+                let mut ok = true;
+                for bucket in AnalyticsInterval::ALL {
+                    let since = if full_rebuild_done {
+                        Some(now - bucket.refresh_window())
+                    } else {
+                        None
+                    };
+                    match self.aggregate(bucket, since).await {
+                        Ok(rows) => tracing::debug!("Aggregated {} {} analytics buckets", rows, bucket.as_str()),
+                        Err(e) => {
+                            ok = false;
+                            tracing::error!("Market analytics aggregation ({}) failed: {}", bucket.as_str(), e);
+                        }
+                    }
+                }
+                full_rebuild_done |= ok;
+            }
+        });
+    }
+
+    /// Recompute buckets of `interval` starting at or after `since`
+    /// (all buckets when `None`). Returns the number of buckets written.
+    pub async fn aggregate(&self, interval: AnalyticsInterval, since: Option<DateTime<Utc>>) -> Result<u64, sqlx::Error> {
+        // Probability is the last trade in the bucket expressed as the YES
+        // price (a NO trade at p implies 1 - p).
+        let result = sqlx::query(
+            r#"
+            WITH bucketed AS (
+                SELECT id, market_id, share_type, price, amount, created_at,
+                       maker_address, taker_address,
+                       date_trunc($2, created_at AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS bucket_start
+                FROM trades
+                WHERE market_id IS NOT NULL
+                  AND ($3::timestamptz IS NULL
+                       OR created_at >= date_trunc($2, $3::timestamptz AT TIME ZONE 'UTC') AT TIME ZONE 'UTC')
+            ),
+            traders AS (
+                SELECT market_id, bucket_start, COUNT(DISTINCT addr) AS unique_traders
+                FROM bucketed, LATERAL unnest(ARRAY[maker_address, taker_address]) AS addr
+                GROUP BY market_id, bucket_start
+            ),
+            last_trade AS (
+                SELECT DISTINCT ON (market_id, bucket_start)
+                       market_id, bucket_start,
+                       CASE WHEN share_type = 'no' THEN 1 - price ELSE price END AS probability
+                FROM bucketed
+                WHERE share_type IS NOT NULL
+                ORDER BY market_id, bucket_start, created_at DESC, id DESC
+            )
+            INSERT INTO market_analytics (
+                market_id, bucket_interval, bucket_start, volume, shares_traded,
+                trade_count, unique_traders, avg_trade_size, probability, updated_at
+            )
+            SELECT b.market_id, $1, b.bucket_start,
+                   SUM(b.price * b.amount), SUM(b.amount), COUNT(*),
+                   tr.unique_traders, SUM(b.price * b.amount) / COUNT(*),
+                   lt.probability, NOW()
+            FROM bucketed b
+            JOIN traders tr ON tr.market_id = b.market_id AND tr.bucket_start = b.bucket_start
+            LEFT JOIN last_trade lt ON lt.market_id = b.market_id AND lt.bucket_start = b.bucket_start
+            GROUP BY b.market_id, b.bucket_start, tr.unique_traders, lt.probability
+            ON CONFLICT (market_id, bucket_interval, bucket_start) DO UPDATE SET
+                volume = EXCLUDED.volume,
+                shares_traded = EXCLUDED.shares_traded,
+                trade_count = EXCLUDED.trade_count,
+                unique_traders = EXCLUDED.unique_traders,
+                avg_trade_size = EXCLUDED.avg_trade_size,
+                probability = EXCLUDED.probability,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(interval.as_str())
+        .bind(interval.trunc_unit())
+        .bind(since)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_parse_round_trip() {
+        for interval in AnalyticsInterval::ALL {
+            assert_eq!(AnalyticsInterval::parse(interval.as_str()), Some(interval));
+        }
+        assert_eq!(AnalyticsInterval::parse("5m"), None);
+        assert_eq!(AnalyticsInterval::Day.duration(), ChronoDuration::hours(24));
+    }
+}
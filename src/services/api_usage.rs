@@ -0,0 +1,468 @@
+//! API Usage Accounting
+//!
+//! Counts each account's authenticated API requests and the bytes it sent
+//! and received, per UTC day, as the basis for billing data access. Counts
+//! are kept in memory and flushed to `api_usage` every few seconds; each
+//! flush also picks up what other nodes counted for the account.
+//!
+//! Accounts have daily quotas on requests and response bytes, the platform
+//! defaults unless support gave them their own. Once `api_quota_warn_percent`
+//! of a quota is used responses carry a warning, and requests past it are
+//! refused until the next UTC day.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::config::AppConfig;
+use crate::models::Address;
+
+/// How often counts are written to the database
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Requests and bytes of one account in one day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct UsageCounts {
+    pub requests: u64,
+    /// Request bodies received
+    pub bytes_in: u64,
+    /// Response bodies sent
+    pub bytes_out: u64,
+}
+
+impl UsageCounts {
+    fn add(&mut self, other: UsageCounts) {
+        self.requests += other.requests;
+        self.bytes_in += other.bytes_in;
+        self.bytes_out += other.bytes_out;
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == UsageCounts::default()
+    }
+}
+
+/// Daily quotas of an account; `None` is unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ApiQuota {
+    pub requests: Option<u64>,
+    /// Response bytes
+    pub bytes: Option<u64>,
+}
+
+impl ApiQuota {
+    /// Quota from configured limits, 0 meaning unlimited
+    pub fn from_limits(requests: u64, bytes: u64) -> Self {
+        Self {
+            requests: Some(requests).filter(|n| *n > 0),
+            bytes: Some(bytes).filter(|n| *n > 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaStatus {
+    Ok,
+    /// Past the warning threshold of a quota
+    Warning,
+    /// A quota is used up; requests are refused
+    Exceeded,
+}
+
+/// Usage of an account today against its quota
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaCheck {
+    pub day: NaiveDate,
+    pub usage: UsageCounts,
+    pub quota: ApiQuota,
+    pub status: QuotaStatus,
+    /// Share of the most used quota, in percent
+    pub used_percent: Option<u64>,
+}
+
+impl QuotaCheck {
+    fn new(day: NaiveDate, usage: UsageCounts, quota: ApiQuota, warn_percent: u8) -> Self {
+        let used = |count: u64, limit: Option<u64>| limit.map(|limit| count.saturating_mul(100) / limit);
+        let used_percent = used(usage.requests, quota.requests).max(used(usage.bytes_out, quota.bytes));
+        let status = match used_percent {
+            Some(percent) if percent >= 100 => QuotaStatus::Exceeded,
+            Some(percent) if percent >= u64::from(warn_percent) => QuotaStatus::Warning,
+            _ => QuotaStatus::Ok,
+        };
+        Self {
+            day,
+            usage,
+            quota,
+            status,
+            used_percent,
+        }
+    }
+
+    /// Requests left today, counting the one being checked
+    pub fn remaining_requests(&self) -> Option<u64> {
+        self.quota.requests.map(|limit| limit.saturating_sub(self.usage.requests + 1))
+    }
+
+    /// Response bytes left today, before the one being checked
+    pub fn remaining_bytes(&self) -> Option<u64> {
+        self.quota.bytes.map(|limit| limit.saturating_sub(self.usage.bytes_out))
+    }
+
+    /// Seconds until the quota resets at the next UTC midnight
+    pub fn resets_in_secs(&self) -> i64 {
+        let midnight = self.day.succ_opt().and_then(|d| d.and_hms_opt(0, 0, 0)).map(|t| t.and_utc());
+        midnight.map(|t| (t - Utc::now()).num_seconds().max(0)).unwrap_or_default()
+    }
+}
+
+/// One day of an account's usage
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    pub requests: i64,
+    pub bytes_in: i64,
+    pub bytes_out: i64,
+}
+
+/// An account's counts for one day
+struct Account {
+    quota: ApiQuota,
+    /// Totals as of the last flush, from every node
+    persisted: UsageCounts,
+    /// Being written by a flush
+    in_flight: UsageCounts,
+    /// Counted since
+    pending: UsageCounts,
+}
+
+impl Account {
+    fn usage(&self) -> UsageCounts {
+        let mut usage = self.persisted;
+        usage.add(self.in_flight);
+        usage.add(self.pending);
+        usage
+    }
+}
+
+/// Counts API usage per account and day and enforces the daily quotas
+pub struct ApiUsageMeter {
+    pool: PgPool,
+    default_quota: ApiQuota,
+    warn_percent: u8,
+    accounts: DashMap<(String, NaiveDate), Account>,
+}
+
+impl ApiUsageMeter {
+    pub fn new(pool: PgPool, config: &AppConfig) -> Self {
+        Self {
+            pool,
+            default_quota: ApiQuota::from_limits(config.api_daily_request_quota, config.api_daily_byte_quota),
+            warn_percent: config.api_quota_warn_percent,
+            accounts: DashMap::new(),
+        }
+    }
+
+    pub fn warn_percent(&self) -> u8 {
+        self.warn_percent
+    }
+
+    /// Spawn the periodic flush
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.flush().await {
+                    tracing::warn!("Failed to flush API usage: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Today's usage of `address` before the current request, loaded from
+    /// the database the first time the account is seen that day
+    pub async fn check(&self, address: &Address) -> Result<QuotaCheck, sqlx::Error> {
+        let day = Utc::now().date_naive();
+        let key = (address.as_str().to_owned(), day);
+        let loaded = self.accounts.get(&key).map(|a| (a.usage(), a.quota));
+        let (usage, quota) = match loaded {
+            Some(loaded) => loaded,
+            None => {
+                let (persisted, quota) = self.load(address, day).await?;
+                let account = self.accounts.entry(key).or_insert(Account {
+                    quota,
+                    persisted,
+                    in_flight: UsageCounts::default(),
+                    pending: UsageCounts::default(),
+                });
+                (account.usage(), account.quota)
+            }
+        };
+        Ok(QuotaCheck::new(day, usage, quota, self.warn_percent))
+    }
+
+    /// Count a request of `address` on `day` (from its [`QuotaCheck`])
+    pub fn record(&self, address: &Address, day: NaiveDate, bytes_in: u64, bytes_out: u64) {
+        let mut account = self.accounts.entry((address.as_str().to_owned(), day)).or_insert_with(|| Account {
+            quota: self.default_quota,
+            persisted: UsageCounts::default(),
+            in_flight: UsageCounts::default(),
+            pending: UsageCounts::default(),
+        });
+        account.pending.add(UsageCounts {
+            requests: 1,
+            bytes_in,
+            bytes_out,
+        });
+    }
+
+    /// Apply a changed quota of `address` (`None` for the platform default)
+    /// to its counts on this node; other nodes pick it up with their next
+    /// flush
+    pub fn set_quota(&self, address: &Address, requests: Option<i64>, bytes: Option<i64>) {
+        let quota = self.resolve_quota(requests, bytes);
+        for mut account in self.accounts.iter_mut().filter(|a| a.key().0 == address.as_str()) {
+            account.quota = quota;
+        }
+    }
+
+    /// Write the counts gathered since the last flush
+    pub async fn flush(&self) -> Result<(), sqlx::Error> {
+        let mut keys = Vec::new();
+        let mut counts = Vec::new();
+        for mut account in self.accounts.iter_mut() {
+            if !account.pending.is_empty() {
+                let pending = std::mem::take(&mut account.pending);
+                account.in_flight.add(pending);
+                keys.push(account.key().clone());
+                counts.push(pending);
+            }
+        }
+
+        if !keys.is_empty() {
+            match self.append(&keys, &counts).await {
+                Ok(totals) => {
+                    for (key, total, quota) in totals {
+                        if let Some(mut account) = self.accounts.get_mut(&key) {
+                            account.persisted = total;
+                            account.in_flight = UsageCounts::default();
+                            account.quota = quota;
+                        }
+                    }
+                }
+                Err(e) => {
+                    // Counted again with the next flush
+                    for key in &keys {
+                        if let Some(mut account) = self.accounts.get_mut(key) {
+                            let in_flight = std::mem::take(&mut account.in_flight);
+                            account.pending.add(in_flight);
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        let today = Utc::now().date_naive();
+        self.accounts.retain(|(_, day), account| *day >= today || !account.pending.is_empty());
+        Ok(())
+    }
+
+    /// Daily usage of `address` over the last `days` days, newest first,
+    /// including what is not flushed yet
+    pub async fn history(&self, address: &Address, days: i64) -> Result<Vec<DailyUsage>, sqlx::Error> {
+        let since = Utc::now().date_naive() - chrono::Duration::days(days - 1);
+        let mut history: Vec<DailyUsage> = sqlx::query_as(
+            r#"
+            SELECT day, requests, bytes_in, bytes_out
+            FROM api_usage
+            WHERE user_address = $1 AND day >= $2
+            ORDER BY day DESC
+            "#,
+        )
+        .bind(address)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for account in self.accounts.iter().filter(|a| a.key().0 == address.as_str() && a.key().1 >= since) {
+            let mut unflushed = account.in_flight;
+            unflushed.add(account.pending);
+            if unflushed.is_empty() {
+                continue;
+            }
+            let day = account.key().1;
+            let position = history.iter().position(|d| d.day <= day).unwrap_or(history.len());
+            if history.get(position).map(|d| d.day) != Some(day) {
+                history.insert(position, DailyUsage { day, requests: 0, bytes_in: 0, bytes_out: 0 });
+            }
+            let entry = &mut history[position];
+            entry.requests += unflushed.requests as i64;
+            entry.bytes_in += unflushed.bytes_in as i64;
+            entry.bytes_out += unflushed.bytes_out as i64;
+        }
+        Ok(history)
+    }
+
+    /// Usage already recorded for `address` on `day`, and its quota
+    async fn load(&self, address: &Address, day: NaiveDate) -> Result<(UsageCounts, ApiQuota), sqlx::Error> {
+        #[allow(clippy::type_complexity)]
+        let (request_quota, byte_quota, requests, bytes_in, bytes_out): (
+            Option<i64>,
+            Option<i64>,
+            Option<i64>,
+            Option<i64>,
+            Option<i64>,
+        ) = sqlx::query_as(
+            r#"
+            SELECT u.api_daily_request_quota, u.api_daily_byte_quota, a.requests, a.bytes_in, a.bytes_out
+            FROM (SELECT $1::text AS address) k
+            LEFT JOIN users u ON u.address = k.address
+            LEFT JOIN api_usage a ON a.user_address = k.address AND a.day = $2
+            "#,
+        )
+        .bind(address)
+        .bind(day)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let quota = self.resolve_quota(request_quota, byte_quota);
+        let usage = UsageCounts {
+            requests: requests.unwrap_or_default() as u64,
+            bytes_in: bytes_in.unwrap_or_default() as u64,
+            bytes_out: bytes_out.unwrap_or_default() as u64,
+        };
+        Ok((usage, quota))
+    }
+
+    /// Quota from an account's own limits, the platform default where unset
+    fn resolve_quota(&self, requests: Option<i64>, bytes: Option<i64>) -> ApiQuota {
+        ApiQuota {
+            requests: match requests {
+                Some(limit) => ApiQuota::from_limits(limit as u64, 0).requests,
+                None => self.default_quota.requests,
+            },
+            bytes: match bytes {
+                Some(limit) => ApiQuota::from_limits(0, limit as u64).bytes,
+                None => self.default_quota.bytes,
+            },
+        }
+    }
+
+    /// Add `counts` to the stored days, returning the new totals and the
+    /// accounts' current quotas
+    async fn append(
+        &self,
+        keys: &[(String, NaiveDate)],
+        counts: &[UsageCounts],
+    ) -> Result<Vec<((String, NaiveDate), UsageCounts, ApiQuota)>, sqlx::Error> {
+        let addresses: Vec<&str> = keys.iter().map(|(address, _)| address.as_str()).collect();
+        let days: Vec<NaiveDate> = keys.iter().map(|(_, day)| *day).collect();
+        let requests: Vec<i64> = counts.iter().map(|c| c.requests as i64).collect();
+        let bytes_in: Vec<i64> = counts.iter().map(|c| c.bytes_in as i64).collect();
+        let bytes_out: Vec<i64> = counts.iter().map(|c| c.bytes_out as i64).collect();
+
+        #[allow(clippy::type_complexity)]
+        let rows: Vec<(String, NaiveDate, i64, i64, i64, Option<i64>, Option<i64>)> = sqlx::query_as(
+            r#"
+            WITH counted AS (
+                INSERT INTO api_usage (user_address, day, requests, bytes_in, bytes_out)
+                SELECT * FROM UNNEST($1::text[], $2::date[], $3::bigint[], $4::bigint[], $5::bigint[])
+                ON CONFLICT (user_address, day) DO UPDATE SET
+                    requests = api_usage.requests + EXCLUDED.requests,
+                    bytes_in = api_usage.bytes_in + EXCLUDED.bytes_in,
+                    bytes_out = api_usage.bytes_out + EXCLUDED.bytes_out,
+                    updated_at = NOW()
+                RETURNING user_address, day, requests, bytes_in, bytes_out
+            )
+            SELECT c.user_address, c.day, c.requests, c.bytes_in, c.bytes_out,
+                   u.api_daily_request_quota, u.api_daily_byte_quota
+            FROM counted c
+            LEFT JOIN users u ON u.address = c.user_address
+            "#,
+        )
+        .bind(&addresses)
+        .bind(&days)
+        .bind(&requests)
+        .bind(&bytes_in)
+        .bind(&bytes_out)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(address, day, requests, bytes_in, bytes_out, request_quota, byte_quota)| {
+                let total = UsageCounts {
+                    requests: requests as u64,
+                    bytes_in: bytes_in as u64,
+                    bytes_out: bytes_out as u64,
+                };
+                ((address, day), total, self.resolve_quota(request_quota, byte_quota))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    const USER: &str = "0x9999999999999999999999999999999999999999";
+    const ADMIN: &str = "0x1111111111111111111111111111111111111111";
+
+    #[tokio::test]
+    async fn test_quota_warns_then_refuses_across_flushes() {
+        let app = TestApp::builder()
+            .config(serde_json::json!({ "api_daily_request_quota": 5 }))
+            .build()
+            .await;
+        let meter = &app.state.api_usage;
+        let (user, admin): (Address, Address) = (USER.parse().unwrap(), ADMIN.parse().unwrap());
+
+        for _ in 0..4 {
+            let check = meter.check(&user).await.unwrap();
+            assert_eq!(check.status, QuotaStatus::Ok);
+            meter.record(&user, check.day, 10, 200);
+        }
+        let check = meter.check(&user).await.unwrap();
+        assert_eq!(
+            (check.status, check.used_percent, check.remaining_requests()),
+            (QuotaStatus::Warning, Some(80), Some(0))
+        );
+        meter.record(&user, check.day, 0, 200);
+        meter.flush().await.unwrap();
+
+        // Another node (or a restart) starts from the stored count
+        let other = ApiUsageMeter::new(app.db.pool.clone(), &app.state.config);
+        let check = other.check(&user).await.unwrap();
+        assert_eq!(check.status, QuotaStatus::Exceeded);
+        assert_eq!(check.usage, UsageCounts { requests: 5, bytes_in: 40, bytes_out: 1000 });
+
+        other.record(&user, check.day, 0, 50);
+        let history = other.history(&user, 7).await.unwrap();
+        assert_eq!((history.len(), history[0].requests, history[0].bytes_out), (1, 6, 1050));
+
+        // Given its own quota without a request limit, which the other node
+        // picks up with its next flush
+        sqlx::query("INSERT INTO users (address, nonce) VALUES ($1, 1)")
+            .bind(USER)
+            .execute(&app.db.pool)
+            .await
+            .unwrap();
+        crate::services::account_admin::set_api_quota(&app.db.pool, &admin, &user, Some(0), None, "data plan")
+            .await
+            .unwrap();
+        other.set_quota(&user, Some(0), None);
+        assert_eq!(other.check(&user).await.unwrap().status, QuotaStatus::Ok);
+        assert_eq!(meter.check(&user).await.unwrap().status, QuotaStatus::Exceeded);
+        meter.record(&user, check.day, 0, 0);
+        meter.flush().await.unwrap();
+        assert_eq!(meter.check(&user).await.unwrap().quota, ApiQuota::default());
+    }
+}
@@ -0,0 +1,611 @@
+//! Historical Backfill / Import
+//!
+//! Ingests markets, outcomes and historical trades exported from the previous
+//! system (one JSON document, or one CSV file per entity). The whole bundle is
+//! validated for referential integrity before anything is written; a bundle
+//! with any issue is rejected as a unit. Valid bundles are written in a
+//! single transaction and rows that already exist (same id) are skipped, so
+//! an import can be re-run safely.
+//!
+//! Historical trades have no order records, so each imported trade gets a
+//! pair of synthetic `filled` orders (signature `imported`) to satisfy the
+//! trade → order foreign keys. After writing, market volume stats and the
+//! analytics buckets are rebuilt; candles are derived from `trades` at query
+//! time and need no rebuild.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::analytics::{AnalyticsInterval, MarketAnalyticsJob};
+
+/// Issues reported before giving up on validation
+const MAX_ISSUES: usize = 100;
+
+const MARKET_STATUSES: [&str; 5] = ["active", "paused", "pending_resolution", "resolved", "cancelled"];
+
+// ============================================================================
+// Input Records
+// ============================================================================
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportMarket {
+    pub id: Uuid,
+    pub condition_id: String,
+    pub question: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub resolution_source: Option<String>,
+    /// Defaults to "active"
+    pub status: Option<String>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub winning_outcome_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportOutcome {
+    pub id: Uuid,
+    pub market_id: Uuid,
+    pub token_id: String,
+    pub name: String,
+    pub share_type: String,
+    pub probability: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportTrade {
+    pub id: Uuid,
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    /// Must match the outcome's share type when given
+    pub share_type: Option<String>,
+    /// Taker side: "buy" or "sell"
+    pub side: String,
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub maker_address: String,
+    pub taker_address: String,
+    pub maker_fee: Option<Decimal>,
+    pub taker_fee: Option<Decimal>,
+    /// "normal" (default), "mint" or "merge"
+    pub match_type: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Everything to import in one run
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImportBundle {
+    #[serde(default)]
+    pub markets: Vec<ImportMarket>,
+    #[serde(default)]
+    pub outcomes: Vec<ImportOutcome>,
+    #[serde(default)]
+    pub trades: Vec<ImportTrade>,
+}
+
+/// Parse errors
+#[derive(Debug, thiserror::Error)]
+pub enum ImportParseError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid {file} CSV: {source}")]
+    Csv {
+        file: &'static str,
+        #[source]
+        source: csv::Error,
+    },
+}
+
+fn parse_csv<T: for<'de> Deserialize<'de>>(file: &'static str, data: &[u8]) -> Result<Vec<T>, ImportParseError> {
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data)
+        .deserialize()
+        .collect::<Result<Vec<T>, _>>()
+        .map_err(|source| ImportParseError::Csv { file, source })
+}
+
+impl ImportBundle {
+    pub fn from_json(data: &[u8]) -> Result<Self, ImportParseError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    /// One CSV per entity, each with a header row matching the field names
+    pub fn from_csv(
+        markets: Option<&[u8]>,
+        outcomes: Option<&[u8]>,
+        trades: Option<&[u8]>,
+    ) -> Result<Self, ImportParseError> {
+        Ok(Self {
+            markets: markets.map(|d| parse_csv("markets", d)).transpose()?.unwrap_or_default(),
+            outcomes: outcomes.map(|d| parse_csv("outcomes", d)).transpose()?.unwrap_or_default(),
+            trades: trades.map(|d| parse_csv("trades", d)).transpose()?.unwrap_or_default(),
+        })
+    }
+
+    fn referenced_market_ids(&self) -> Vec<Uuid> {
+        let ids: HashSet<Uuid> = self
+            .outcomes
+            .iter()
+            .map(|o| o.market_id)
+            .chain(self.trades.iter().map(|t| t.market_id))
+            .collect();
+        ids.into_iter().collect()
+    }
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ImportIssue {
+    /// "market", "outcome" or "trade"
+    pub entity: &'static str,
+    /// Row index within its list (0-based)
+    pub row: usize,
+    pub message: String,
+}
+
+/// Outcomes already in the database: id -> (market_id, share_type)
+pub type KnownOutcomes = HashMap<Uuid, (Uuid, String)>;
+
+fn is_valid_address(address: &str) -> bool {
+    address.parse::<Address>().is_ok()
+}
+
+/// Check the bundle against itself and the rows already in the database
+pub fn validate_bundle(
+    bundle: &ImportBundle,
+    known_markets: &HashSet<Uuid>,
+    known_outcomes: &KnownOutcomes,
+) -> Vec<ImportIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |entity: &'static str, row: usize, message: String| {
+        if issues.len() < MAX_ISSUES {
+            issues.push(ImportIssue { entity, row, message });
+        }
+    };
+
+    // Outcomes visible to this import: existing rows plus the bundle's own
+    let mut outcomes: KnownOutcomes = known_outcomes.clone();
+    let mut markets: HashSet<Uuid> = known_markets.clone();
+
+    let mut seen = HashSet::new();
+    let mut condition_ids = HashSet::new();
+    for (row, m) in bundle.markets.iter().enumerate() {
+        if !seen.insert(m.id) {
+            issue("market", row, format!("duplicate market id {}", m.id));
+        }
+        if m.question.trim().is_empty() {
+            issue("market", row, "question is required".to_string());
+        }
+        if m.condition_id.is_empty() || m.condition_id.len() > 66 {
+            issue("market", row, "condition_id must be 1-66 characters".to_string());
+        } else if !condition_ids.insert(m.condition_id.as_str()) {
+            issue("market", row, format!("duplicate condition_id {}", m.condition_id));
+        }
+        if let Some(status) = &m.status {
+            if !MARKET_STATUSES.contains(&status.as_str()) {
+                issue("market", row, format!("invalid status '{}'", status));
+            }
+        }
+        if m.category.as_ref().is_some_and(|c| c.len() > 50) {
+            issue("market", row, "category exceeds 50 characters".to_string());
+        }
+        markets.insert(m.id);
+    }
+
+    seen.clear();
+    let mut market_share_types = HashSet::new();
+    for (row, o) in bundle.outcomes.iter().enumerate() {
+        if !seen.insert(o.id) {
+            issue("outcome", row, format!("duplicate outcome id {}", o.id));
+        }
+        if !markets.contains(&o.market_id) {
+            issue("outcome", row, format!("unknown market {}", o.market_id));
+        }
+        if o.share_type != "yes" && o.share_type != "no" {
+            issue("outcome", row, format!("invalid share_type '{}'", o.share_type));
+        } else if !market_share_types.insert((o.market_id, o.share_type.as_str())) {
+            issue("outcome", row, format!("market {} already has a {} outcome", o.market_id, o.share_type));
+        }
+        if o.name.trim().is_empty() || o.name.len() > 50 {
+            issue("outcome", row, "name must be 1-50 characters".to_string());
+        }
+        if o.token_id.is_empty() || o.token_id.len() > 78 {
+            issue("outcome", row, "token_id must be 1-78 characters".to_string());
+        }
+        if o.probability.is_some_and(|p| p < Decimal::ZERO || p > Decimal::ONE) {
+            issue("outcome", row, "probability must be between 0 and 1".to_string());
+        }
+        outcomes.insert(o.id, (o.market_id, o.share_type.clone()));
+    }
+
+    for (row, m) in bundle.markets.iter().enumerate() {
+        if let Some(winner) = m.winning_outcome_id {
+            if outcomes.get(&winner).map(|(market_id, _)| *market_id) != Some(m.id) {
+                issue("market", row, format!("winning outcome {} does not belong to market", winner));
+            }
+        }
+    }
+
+    seen.clear();
+    for (row, t) in bundle.trades.iter().enumerate() {
+        if !seen.insert(t.id) {
+            issue("trade", row, format!("duplicate trade id {}", t.id));
+        }
+        match outcomes.get(&t.outcome_id) {
+            None => issue("trade", row, format!("unknown outcome {}", t.outcome_id)),
+            Some((market_id, share_type)) => {
+                if *market_id != t.market_id {
+                    issue("trade", row, format!("outcome {} does not belong to market {}", t.outcome_id, t.market_id));
+                }
+                if t.share_type.as_ref().is_some_and(|s| s != share_type) {
+                    issue("trade", row, format!("share_type does not match outcome ({})", share_type));
+                }
+            }
+        }
+        if t.side != "buy" && t.side != "sell" {
+            issue("trade", row, format!("invalid side '{}'", t.side));
+        }
+        if let Some(match_type) = &t.match_type {
+            if !["normal", "mint", "merge"].contains(&match_type.as_str()) {
+                issue("trade", row, format!("invalid match_type '{}'", match_type));
+            }
+        }
+        if t.price <= Decimal::ZERO || t.price >= Decimal::ONE {
+            issue("trade", row, "price must be between 0 and 1 (exclusive)".to_string());
+        }
+        if t.amount <= Decimal::ZERO {
+            issue("trade", row, "amount must be positive".to_string());
+        }
+        if t.maker_fee.unwrap_or_default() < Decimal::ZERO || t.taker_fee.unwrap_or_default() < Decimal::ZERO {
+            issue("trade", row, "fees cannot be negative".to_string());
+        }
+        if !is_valid_address(&t.maker_address) || !is_valid_address(&t.taker_address) {
+            issue("trade", row, "maker/taker address must be 0x-prefixed 20-byte hex".to_string());
+        }
+    }
+
+    issues
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub markets_inserted: u64,
+    pub markets_skipped: u64,
+    pub outcomes_inserted: u64,
+    pub outcomes_skipped: u64,
+    pub trades_inserted: u64,
+    pub trades_skipped: u64,
+    /// Markets whose stats and analytics were rebuilt
+    pub markets_rebuilt: usize,
+    pub issues: Vec<ImportIssue>,
+}
+
+impl ImportReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Runs validated imports against the database
+pub struct BackfillService {
+    pool: PgPool,
+}
+
+impl BackfillService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Validate and (unless `dry_run`) write the bundle
+    pub async fn import(&self, bundle: &ImportBundle, dry_run: bool) -> Result<ImportReport, sqlx::Error> {
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+
+        let referenced = bundle.referenced_market_ids();
+        let known_markets: HashSet<Uuid> = sqlx::query_scalar::<_, Uuid>("SELECT id FROM markets WHERE id = ANY($1)")
+            .bind(&referenced)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .collect();
+        let known_outcomes: KnownOutcomes = sqlx::query_as::<_, (Uuid, Uuid, String)>(
+            "SELECT id, market_id, share_type::text FROM outcomes WHERE market_id = ANY($1)",
+        )
+        .bind(&referenced)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|(id, market_id, share_type)| (id, (market_id, share_type)))
+        .collect();
+
+        report.issues = validate_bundle(bundle, &known_markets, &known_outcomes);
+        if !report.is_valid() || dry_run {
+            return Ok(report);
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // Markets first (winners set after outcomes exist)
+        for m in &bundle.markets {
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO markets (id, condition_id, question, description, category, resolution_source,
+                                     status, end_time, created_at, resolved_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7::market_status, $8, COALESCE($9, NOW()), $10)
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(m.id)
+            .bind(&m.condition_id)
+            .bind(&m.question)
+            .bind(&m.description)
+            .bind(m.category.as_deref().unwrap_or("general"))
+            .bind(m.resolution_source.as_deref().unwrap_or("UMA"))
+            .bind(m.status.as_deref().unwrap_or("active"))
+            .bind(m.end_time)
+            .bind(m.created_at)
+            .bind(m.resolved_at)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if inserted > 0 {
+                report.markets_inserted += 1;
+            } else {
+                report.markets_skipped += 1;
+            }
+        }
+
+        for o in &bundle.outcomes {
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO outcomes (id, market_id, token_id, name, share_type, probability)
+                VALUES ($1, $2, $3, $4, $5::share_type, $6)
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(o.id)
+            .bind(o.market_id)
+            .bind(&o.token_id)
+            .bind(&o.name)
+            .bind(&o.share_type)
+            .bind(o.probability.unwrap_or(Decimal::new(5, 1)))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if inserted > 0 {
+                report.outcomes_inserted += 1;
+            } else {
+                report.outcomes_skipped += 1;
+            }
+        }
+
+        let market_ids: Vec<Uuid> = bundle.markets.iter().map(|m| m.id).collect();
+        sqlx::query(
+            r#"
+            UPDATE outcomes o SET complement_id = c.id
+            FROM outcomes c
+            WHERE c.market_id = o.market_id AND c.share_type <> o.share_type
+              AND o.market_id = ANY($1) AND o.complement_id IS NULL
+            "#,
+        )
+        .bind(&market_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        for m in bundle.markets.iter().filter(|m| m.winning_outcome_id.is_some()) {
+            sqlx::query("UPDATE markets SET winning_outcome_id = $2 WHERE id = $1 AND winning_outcome_id IS NULL")
+                .bind(m.id)
+                .bind(m.winning_outcome_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let trade_ids: Vec<Uuid> = bundle.trades.iter().map(|t| t.id).collect();
+        let existing_trades: HashSet<Uuid> = sqlx::query_scalar::<_, Uuid>("SELECT id FROM trades WHERE id = ANY($1)")
+            .bind(&trade_ids)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut earliest_trade: Option<DateTime<Utc>> = None;
+        let mut touched_markets: HashSet<Uuid> = HashSet::new();
+        for t in &bundle.trades {
+            if existing_trades.contains(&t.id) {
+                report.trades_skipped += 1;
+                continue;
+            }
+            // Validation above rejects bundles with malformed addresses
+            let (Ok(maker_address), Ok(taker_address)) =
+                (t.maker_address.parse::<Address>(), t.taker_address.parse::<Address>())
+            else {
+                report.trades_skipped += 1;
+                continue;
+            };
+            let share_type = known_outcomes
+                .get(&t.outcome_id)
+                .map(|(_, s)| s.clone())
+                .or_else(|| bundle.outcomes.iter().find(|o| o.id == t.outcome_id).map(|o| o.share_type.clone()))
+                .unwrap_or_default();
+            let symbol = format!("{}:{}:{}", t.market_id, t.outcome_id, share_type);
+            let maker_side = if t.side == "buy" { "sell" } else { "buy" };
+            let maker_order_id = Uuid::new_v4();
+            let taker_order_id = Uuid::new_v4();
+
+            for (order_id, address, side) in [
+                (maker_order_id, &maker_address, maker_side),
+                (taker_order_id, &taker_address, t.side.as_str()),
+            ] {
+                sqlx::query(
+                    r#"
+                    INSERT INTO orders (
+                        id, user_address, symbol, market_id, outcome_id, share_type,
+                        side, order_type, price, amount, filled_amount, status, signature,
+                        created_at, updated_at
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6::share_type, $7::order_side, 'limit', $8, $9, $9, 'filled',
+                            'imported', $10, $10)
+                    "#,
+                )
+                .bind(order_id)
+                .bind(address)
+                .bind(&symbol)
+                .bind(t.market_id)
+                .bind(t.outcome_id)
+                .bind(&share_type)
+                .bind(side)
+                .bind(t.price)
+                .bind(t.amount)
+                .bind(t.created_at)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO trades (
+                    id, symbol, market_id, outcome_id, share_type, match_type,
+                    maker_order_id, taker_order_id, maker_address, taker_address,
+                    side, price, amount, maker_fee, taker_fee, created_at, settlement_status
+                )
+                VALUES ($1, $2, $3, $4, $5::share_type, $6::match_type, $7, $8, $9, $10,
+                        $11::order_side, $12, $13, $14, $15, $16, 'confirmed')
+                "#,
+            )
+            .bind(t.id)
+            .bind(&symbol)
+            .bind(t.market_id)
+            .bind(t.outcome_id)
+            .bind(&share_type)
+            .bind(t.match_type.as_deref().unwrap_or("normal"))
+            .bind(maker_order_id)
+            .bind(taker_order_id)
+            .bind(&maker_address)
+            .bind(&taker_address)
+            .bind(&t.side)
+            .bind(t.price)
+            .bind(t.amount)
+            .bind(t.maker_fee.unwrap_or_default())
+            .bind(t.taker_fee.unwrap_or_default())
+            .bind(t.created_at)
+            .execute(&mut *tx)
+            .await?;
+
+            report.trades_inserted += 1;
+            touched_markets.insert(t.market_id);
+            earliest_trade = Some(earliest_trade.map_or(t.created_at, |e| e.min(t.created_at)));
+        }
+
+        tx.commit().await?;
+
+        // Rebuild derived stats for markets that received trades
+        let touched: Vec<Uuid> = touched_markets.into_iter().collect();
+        self.rebuild_market_stats(&touched).await?;
+        if let Some(since) = earliest_trade {
+            let analytics = MarketAnalyticsJob::new(self.pool.clone());
+            for interval in AnalyticsInterval::ALL {
+                analytics.aggregate(interval, Some(since)).await?;
+            }
+        }
+        report.markets_rebuilt = touched.len();
+
+        tracing::info!(
+            "Import complete: {} markets, {} outcomes, {} trades inserted",
+            report.markets_inserted,
+            report.outcomes_inserted,
+            report.trades_inserted
+        );
+        Ok(report)
+    }
+
+    /// Recompute total / 24h volume from trades
+    async fn rebuild_market_stats(&self, market_ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        if market_ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(
+            r#"
+            UPDATE markets m SET
+                total_volume = COALESCE(s.total_volume, 0),
+                volume_24h = COALESCE(s.volume_24h, 0)
+            FROM (
+                SELECT market_id,
+                       SUM(price * amount) AS total_volume,
+                       SUM(price * amount) FILTER (WHERE created_at >= NOW() - INTERVAL '24 hours') AS volume_24h
+                FROM trades
+                WHERE market_id = ANY($1)
+                GROUP BY market_id
+            ) s
+            WHERE m.id = s.market_id
+            "#,
+        )
+        .bind(market_ids)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKET: &str = "11111111-1111-1111-1111-111111111111";
+    const YES: &str = "22222222-2222-2222-2222-222222222222";
+
+    fn bundle() -> ImportBundle {
+        let markets = format!("id,condition_id,question,status\n{},0xabc,Will it rain?,active\n", MARKET);
+        let outcomes = format!("id,market_id,token_id,name,share_type,probability\n{},{},1,Yes,yes,0.6\n", YES, MARKET);
+        let trades = format!(
+            "id,market_id,outcome_id,side,price,amount,maker_address,taker_address,created_at\n\
+             33333333-3333-3333-3333-333333333333,{},{},buy,0.6,10,0x{},0x{},2025-06-01T12:00:00Z\n",
+            MARKET,
+            YES,
+            "a".repeat(40),
+            "b".repeat(40)
+        );
+        ImportBundle::from_csv(Some(markets.as_bytes()), Some(outcomes.as_bytes()), Some(trades.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn test_csv_bundle_validates() {
+        let bundle = bundle();
+        assert_eq!(bundle.trades.len(), 1);
+        assert!(bundle.markets[0].end_time.is_none());
+        assert!(validate_bundle(&bundle, &HashSet::new(), &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_referential_integrity_errors() {
+        let mut bundle = bundle();
+        bundle.markets.clear();
+        bundle.trades[0].price = Decimal::ONE;
+
+        let issues = validate_bundle(&bundle, &HashSet::new(), &HashMap::new());
+        assert!(issues.iter().any(|i| i.entity == "outcome" && i.message.starts_with("unknown market")));
+        assert!(issues.iter().any(|i| i.entity == "trade" && i.message.starts_with("price")));
+
+        // Market already in the database satisfies the outcome reference
+        let known: HashSet<Uuid> = [MARKET.parse().unwrap()].into_iter().collect();
+        let issues = validate_bundle(&bundle, &known, &HashMap::new());
+        assert_eq!(issues.len(), 1);
+    }
+}
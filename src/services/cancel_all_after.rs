@@ -0,0 +1,220 @@
+//! Cancel-All-After (Dead Man's Switch)
+//!
+//! Market makers arm a per-user timer through `POST /orders/cancel-all-after`
+//! and keep refreshing it while connected. The deadlines live in the matching
+//! engine; this service fires the expired ones once a second and mirrors the
+//! cancellations in the database, releasing buy reservations.
+//!
+//! `POST /orders/cancel-all` is the same cancel-all fired on demand: a kill
+//! switch for a market maker whose pricing has gone stale.
+//!
+//! Orders are cancelled book by book on each market's engine shard, in line
+//! with its matching. A fired switch that left books behind on an overloaded
+//! shard is re-armed to fire again on the next check.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::matching::precision::Collateral;
+use crate::services::matching::{EngineShards, OrderEntry};
+use crate::services::portfolio_margin;
+use crate::services::system_events::{self, SystemEventKind};
+use crate::utils::clock::{Clock, SystemClock};
+
+/// How often expired switches are fired
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fires cancel-all-after switches
+pub struct CancelAllAfter {
+    pool: PgPool,
+    shards: Arc<EngineShards>,
+    collateral_token: String,
+    clock: Arc<dyn Clock>,
+}
+
+impl CancelAllAfter {
+    pub fn new(pool: PgPool, shards: Arc<EngineShards>, collateral_token: &str) -> Self {
+        Self {
+            pool,
+            shards,
+            collateral_token: collateral_token.to_string(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read deadlines against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Arm (or with `timeout_ms == 0` disarm) the switch of `user_address`,
+    /// returning its deadline in epoch milliseconds
+    pub fn arm(&self, user_address: &Address, timeout_ms: u64) -> Option<i64> {
+        self.shards
+            .engine()
+            .cancel_all_after(user_address.as_str(), timeout_ms, self.clock.now().timestamp_millis())
+    }
+
+    /// Spawn the loop firing expired switches
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            tracing::info!("Cancel-all-after monitor started");
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                self.fire_expired().await;
+            }
+        });
+    }
+
+    /// Fire every switch whose deadline has passed, returning the number of
+    /// users whose orders were cancelled
+    pub async fn fire_expired(&self) -> usize {
+        let now_ms = self.clock.now().timestamp_millis();
+        let fired = self.shards.engine().take_fired_cancel_all_after(now_ms);
+        let users = fired.len();
+        for user in fired {
+            let (cancelled, complete) = self.cancel_on_shards(&user).await;
+            if !complete {
+                // Fire again on the next check for the books left behind
+                self.shards.engine().cancel_all_after(&user, 1, now_ms);
+            }
+            tracing::info!("Cancel-all-after fired for {}: {} orders cancelled", user, cancelled.len());
+            let order_ids: Vec<Uuid> = cancelled.iter().map(|(_, entry)| entry.id).collect();
+            system_events::record(
+                &self.pool,
+                SystemEventKind::CancelAllAfterFired,
+                Some(&user),
+                format!("Cancel-all-after fired, cancelling {} orders", order_ids.len()),
+                serde_json::json!({ "order_ids": order_ids }),
+            )
+            .await;
+            if let Err(e) = self.cancel_in_db(&user, &order_ids).await {
+                tracing::error!("Cancel-all-after for {} not persisted: {}", user, e);
+            }
+        }
+        users
+    }
+
+    /// Cancel every resting order of `user_address` across all books now,
+    /// returning the cancelled order ids. The database is updated in one
+    /// transaction.
+    pub async fn cancel_all(&self, user_address: &Address) -> Result<Vec<Uuid>, sqlx::Error> {
+        let (cancelled, _) = self.cancel_on_shards(user_address.as_str()).await;
+        let order_ids: Vec<Uuid> = cancelled.iter().map(|(_, entry)| entry.id).collect();
+        self.cancel_in_db(user_address.as_str(), &order_ids).await?;
+        Ok(order_ids)
+    }
+
+    /// Cancel the user's resting orders book by book, each on its market's
+    /// shard. Also returns whether every book was reached.
+    async fn cancel_on_shards(&self, user_address: &str) -> (Vec<(String, OrderEntry)>, bool) {
+        let mut cancelled = Vec::new();
+        let mut complete = true;
+        for symbol in self.shards.engine().user_books(user_address) {
+            let (book, user) = (symbol.clone(), user_address.to_string());
+            match self
+                .shards
+                .execute(&symbol, move |engine| engine.cancel_book_orders_for_user(&book, &user))
+                .await
+            {
+                Ok(orders) => cancelled.extend(orders),
+                Err(e) => {
+                    tracing::warn!("Orders of {} in {} not cancelled: {}", user_address, symbol, e);
+                    complete = false;
+                }
+            }
+        }
+        (cancelled, complete)
+    }
+
+    /// Mark the engine-cancelled orders cancelled and release buy reservations
+    async fn cancel_in_db(&self, user_address: &str, order_ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        if order_ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let cancelled: Vec<(String, Option<Decimal>, Decimal)> = sqlx::query_as(
+            r#"
+            UPDATE orders
+            SET status = 'cancelled', updated_at = NOW()
+            WHERE id = ANY($1) AND status IN ('pending', 'accepted', 'open', 'partially_filled')
+            RETURNING side::text, price, amount - filled_amount
+            "#,
+        )
+        .bind(order_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let released: Collateral = cancelled
+            .iter()
+            .filter(|(side, _, _)| side == "buy")
+            .filter_map(|(_, price, remaining)| price.map(|p| Collateral::notional(p, *remaining)))
+            .sum();
+        if released.value() > Decimal::ZERO {
+            sqlx::query(
+                "UPDATE balances SET available = available + $1, frozen = frozen - $1, updated_at = NOW()
+                 WHERE user_address = $2 AND token = $3",
+            )
+            .bind(released.value())
+            .bind(user_address)
+            .bind(&self.collateral_token)
+            .execute(&mut *tx)
+            .await?;
+            portfolio_margin::resync_account(&mut tx, user_address, &self.collateral_token).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use rust_decimal_macros::dec;
+
+    use crate::models::OrderSide;
+    use crate::test_support::{account, gateway_order, TestApp, TAKER};
+
+    #[tokio::test]
+    async fn test_cancel_all_after_follows_clock() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.deposit(TAKER, dec!(100)).await;
+        let placed = app
+            .state
+            .order_gateway
+            .place(gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.4)))
+            .await
+            .unwrap();
+
+        app.state.cancel_all_after.arm(&account(TAKER), 60_000).unwrap();
+        app.clock.advance(Duration::seconds(59));
+        assert_eq!(app.state.cancel_all_after.fire_expired().await, 0);
+        app.clock.advance(Duration::seconds(1));
+        assert_eq!(app.state.cancel_all_after.fire_expired().await, 1);
+
+        let status: String = sqlx::query_scalar("SELECT status::text FROM orders WHERE id = $1")
+            .bind(placed.order_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "cancelled");
+        let (available, frozen): (Decimal, Decimal) =
+            sqlx::query_as("SELECT available, frozen FROM balances WHERE user_address = $1")
+                .bind(TAKER)
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!((available, frozen), (dec!(100), dec!(0)));
+    }
+}
@@ -0,0 +1,374 @@
+//! Telegram/Discord Channel Gateway
+//!
+//! Announces market resolutions, large trades and newly featured markets to
+//! admin-configured Telegram chats and Discord channels. Each integration
+//! filters by event type, market category and (for trades) a minimum
+//! notional. Matching messages are queued in `channel_messages` and posted by
+//! a background worker through the bot APIs with retry/backoff.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::services::retry::RetryPolicy;
+
+/// Chat platform of an integration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelPlatform {
+    Telegram,
+    Discord,
+}
+
+impl ChannelPlatform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelPlatform::Telegram => "telegram",
+            ChannelPlatform::Discord => "discord",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "telegram" => Some(ChannelPlatform::Telegram),
+            "discord" => Some(ChannelPlatform::Discord),
+            _ => None,
+        }
+    }
+}
+
+/// Announcements that can be pushed to channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelEventType {
+    MarketResolved,
+    LargeTrade,
+    FeaturedMarket,
+}
+
+impl ChannelEventType {
+    pub const ALL: [ChannelEventType; 3] = [
+        ChannelEventType::MarketResolved,
+        ChannelEventType::LargeTrade,
+        ChannelEventType::FeaturedMarket,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelEventType::MarketResolved => "market_resolved",
+            ChannelEventType::LargeTrade => "large_trade",
+            ChannelEventType::FeaturedMarket => "featured_market",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+}
+
+impl std::fmt::Display for ChannelEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Gateway worker configuration
+#[derive(Debug, Clone)]
+pub struct ChannelGatewayConfig {
+    pub telegram_bot_token: Option<String>,
+    pub discord_bot_token: Option<String>,
+    /// Send attempts before a message is marked failed, and the delay
+    /// between them
+    pub retry: RetryPolicy,
+    /// Worker poll interval
+    pub poll_interval_ms: u64,
+    /// Messages fetched per poll
+    pub batch_size: i64,
+}
+
+impl ChannelGatewayConfig {
+    pub fn from_config(config: &crate::config::AppConfig) -> Self {
+        Self {
+            telegram_bot_token: config.telegram_bot_token.clone().filter(|t| !t.is_empty()),
+            discord_bot_token: config.discord_bot_token.clone().filter(|t| !t.is_empty()),
+            retry: RetryPolicy::new(6, 15, 1800),
+            poll_interval_ms: 2000,
+            batch_size: 20,
+        }
+    }
+}
+
+/// Render the announcement text for a queued message.
+///
+/// The payload carries the event data plus the market's `question` and
+/// `category`, merged in at enqueue time.
+pub fn render_message(event_type: ChannelEventType, payload: &Value) -> String {
+    let field = |key: &str| match payload.get(key) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    };
+    let question = field("question");
+
+    match event_type {
+        ChannelEventType::MarketResolved => format!(
+            "✅ Market resolved: {}\nWinning outcome: {}",
+            question,
+            field("winning_share_type").to_uppercase()
+        ),
+        ChannelEventType::LargeTrade => format!(
+            "🐋 Large trade on \"{}\"\n{} {} {} @ {} ({} {})",
+            question,
+            field("side").to_uppercase(),
+            field("amount"),
+            field("share_type").to_uppercase(),
+            field("price"),
+            field("notional"),
+            field("symbol")
+        ),
+        ChannelEventType::FeaturedMarket => {
+            format!("⭐ New featured market: {}\nCategory: {}", question, field("category"))
+        }
+    }
+}
+
+/// Pending message loaded by the worker
+#[derive(Debug, sqlx::FromRow)]
+struct DueMessage {
+    id: Uuid,
+    event_type: String,
+    payload: Value,
+    attempts: i32,
+    platform: String,
+    chat_id: String,
+}
+
+/// Announcement enqueuer and bot delivery worker
+pub struct ChannelGateway {
+    pool: PgPool,
+    http: reqwest::Client,
+    config: ChannelGatewayConfig,
+}
+
+impl ChannelGateway {
+    pub fn new(pool: PgPool, config: ChannelGatewayConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self { pool, http, config }
+    }
+
+    /// Queue an announcement about `market_id` for every active integration
+    /// whose filters match. `notional` is only checked for trades.
+    /// Failures are logged and swallowed: announcements must never fail the
+    /// calling request.
+    pub async fn publish(&self, event_type: ChannelEventType, market_id: Uuid, notional: Option<Decimal>, data: Value) {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO channel_messages (integration_id, event_type, payload)
+            SELECT c.id, $1, $3 || jsonb_build_object('question', m.question, 'category', m.category)
+            FROM channel_integrations c, markets m
+            WHERE m.id = $2
+              AND c.is_active
+              AND (cardinality(c.event_types) = 0 OR $1 = ANY(c.event_types))
+              AND (cardinality(c.categories) = 0 OR m.category = ANY(c.categories))
+              AND ($4::numeric IS NULL OR $4 >= c.min_trade_notional)
+            "#,
+        )
+        .bind(event_type.as_str())
+        .bind(market_id)
+        .bind(&data)
+        .bind(notional)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(r) if r.rows_affected() > 0 => {
+                tracing::debug!(
+                    "Queued {} channel messages for {} on market {}",
+                    r.rows_affected(),
+                    event_type,
+                    market_id
+                );
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to queue channel announcement {}: {}", event_type, e),
+        }
+    }
+
+    /// Spawn the background delivery worker
+    pub fn start_worker(self: Arc<Self>) {
+        tokio::spawn(async move {
+            tracing::info!(
+                "Channel gateway worker started (telegram: {}, discord: {})",
+                self.config.telegram_bot_token.is_some(),
+                self.config.discord_bot_token.is_some()
+            );
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.process_due().await {
+                    tracing::error!("Channel gateway worker error: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Send all due messages once
+    async fn process_due(&self) -> Result<(), sqlx::Error> {
+        // Lease a batch by pushing next_attempt_at forward; SKIP LOCKED lets
+        // several instances share the queue without double-posting.
+        let due: Vec<DueMessage> = sqlx::query_as(
+            r#"
+            WITH claimed AS (
+                SELECT id
+                FROM channel_messages
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE channel_messages m
+            SET next_attempt_at = NOW() + INTERVAL '60 seconds'
+            FROM claimed, channel_integrations c
+            WHERE m.id = claimed.id AND c.id = m.integration_id
+            RETURNING m.id, m.event_type, m.payload, m.attempts, c.platform, c.chat_id
+            "#,
+        )
+        .bind(self.config.batch_size)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for message in due {
+            self.deliver(message).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Post a message through the platform's bot API
+    async fn post(&self, platform: ChannelPlatform, chat_id: &str, text: &str) -> Result<(), String> {
+        let request = match platform {
+            ChannelPlatform::Telegram => {
+                let token = self.config.telegram_bot_token.as_ref().ok_or("TELEGRAM_BOT_TOKEN not configured")?;
+                self.http
+                    .post(format!("https://api.telegram.org/bot{}/sendMessage", token))
+                    .json(&serde_json::json!({
+                        "chat_id": chat_id,
+                        "text": text,
+                        "disable_web_page_preview": true,
+                    }))
+            }
+            ChannelPlatform::Discord => {
+                let token = self.config.discord_bot_token.as_ref().ok_or("DISCORD_BOT_TOKEN not configured")?;
+                self.http
+                    .post(format!("https://discord.com/api/v10/channels/{}/messages", chat_id))
+                    .header("Authorization", format!("Bot {}", token))
+                    .json(&serde_json::json!({ "content": text }))
+            }
+        };
+
+        match request.send().await {
+            Ok(resp) if resp.status().is_success() => Ok(()),
+            Ok(resp) => Err(format!("HTTP {}", resp.status())),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn deliver(&self, message: DueMessage) -> Result<(), sqlx::Error> {
+        let attempts = message.attempts + 1;
+
+        let result = match (
+            ChannelPlatform::parse(&message.platform),
+            ChannelEventType::parse(&message.event_type),
+        ) {
+            (Some(platform), Some(event_type)) => {
+                let text = render_message(event_type, &message.payload);
+                self.post(platform, &message.chat_id, &text).await
+            }
+            _ => Err(format!(
+                "unsupported platform/event '{}'/'{}'",
+                message.platform, message.event_type
+            )),
+        };
+
+        let error = match result {
+            Ok(()) => {
+                sqlx::query(
+                    "UPDATE channel_messages SET status = 'sent', attempts = $1, last_error = NULL, sent_at = NOW() WHERE id = $2",
+                )
+                .bind(attempts)
+                .bind(message.id)
+                .execute(&self.pool)
+                .await?;
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        let exhausted = self.config.retry.exhausted(attempts);
+        let next_attempt = self.config.retry.next_attempt_at(attempts);
+
+        sqlx::query(
+            "UPDATE channel_messages SET status = $1, attempts = $2, last_error = $3, next_attempt_at = $4 WHERE id = $5",
+        )
+        .bind(if exhausted { "failed" } else { "pending" })
+        .bind(attempts)
+        .bind(&error)
+        .bind(next_attempt)
+        .bind(message.id)
+        .execute(&self.pool)
+        .await?;
+
+        if exhausted {
+            tracing::warn!(
+                "Channel message {} to {} {} failed permanently after {} attempts: {}",
+                message.id,
+                message.platform,
+                message.chat_id,
+                attempts,
+                error
+            );
+        } else {
+            tracing::debug!("Channel message {} attempt {} failed: {}", message.id, attempts, error);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_type_round_trip() {
+        for t in ChannelEventType::ALL {
+            assert_eq!(ChannelEventType::parse(t.as_str()), Some(t));
+        }
+        assert_eq!(ChannelPlatform::parse("discord"), Some(ChannelPlatform::Discord));
+        assert_eq!(ChannelPlatform::parse("slack"), None);
+    }
+
+    #[test]
+    fn test_render_large_trade() {
+        let payload = serde_json::json!({
+            "question": "Will it rain?",
+            "side": "buy",
+            "share_type": "yes",
+            "amount": "5000",
+            "price": "0.6",
+            "notional": "3000",
+            "symbol": "USDC",
+        });
+        let text = render_message(ChannelEventType::LargeTrade, &payload);
+        assert!(text.contains("\"Will it rain?\""));
+        assert!(text.contains("BUY 5000 YES @ 0.6 (3000 USDC)"));
+    }
+}
@@ -0,0 +1,135 @@
+//! Circuit Breaker Halts
+//!
+//! The matching engine halts a market whose price moves more than
+//! `CIRCUIT_BREAKER_MOVE` within `CIRCUIT_BREAKER_WINDOW_SECS`, and rejects
+//! orders for it until it is resumed (see [`PriceGuardConfig`]). This
+//! service records each halt in the system event timeline and lifts halts
+//! for `POST /admin/markets/:market_id/resume-trading`.
+//!
+//! [`PriceGuardConfig`]: crate::services::matching::PriceGuardConfig
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::matching::{MarketHalt, MatchingEngine};
+use crate::services::system_events::{self, SystemEventKind};
+
+/// Records and lifts circuit breaker halts
+pub struct CircuitBreaker {
+    pool: PgPool,
+    engine: Arc<MatchingEngine>,
+}
+
+impl CircuitBreaker {
+    pub fn new(pool: PgPool, engine: Arc<MatchingEngine>) -> Self {
+        Self { pool, engine }
+    }
+
+    /// Spawn the loop recording halts as the engine trips them
+    pub fn start(self: Arc<Self>) {
+        let mut halts = self.engine.subscribe_halts();
+        tokio::spawn(async move {
+            tracing::info!("Circuit breaker monitor started");
+            loop {
+                match halts.recv().await {
+                    Ok(halt) => self.record_halt(&halt).await,
+                    Err(RecvError::Lagged(n)) => tracing::warn!("Circuit breaker monitor missed {} halts", n),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Markets currently halted
+    pub fn halts(&self) -> Vec<MarketHalt> {
+        self.engine.halts()
+    }
+
+    /// Lift the halt of `market_id` on behalf of `admin`; `None` if it
+    /// wasn't halted
+    pub async fn resume(&self, market_id: Uuid, admin: &Address, reason: &str) -> Option<MarketHalt> {
+        let halt = self.engine.resume_market(market_id)?;
+        system_events::record(
+            &self.pool,
+            SystemEventKind::MarketResumed,
+            Some(&halt.market),
+            format!("Trading resumed by {}: {}", admin, reason),
+            serde_json::json!({ "halt": halt, "admin": admin, "reason": reason }),
+        )
+        .await;
+        Some(halt)
+    }
+
+    async fn record_halt(&self, halt: &MarketHalt) {
+        system_events::record(
+            &self.pool,
+            SystemEventKind::MarketHalted,
+            Some(&halt.market),
+            format!(
+                "Circuit breaker halted trading: {} -> {} within {}s",
+                halt.from_price,
+                halt.to_price,
+                halt.window_ms / 1000
+            ),
+            serde_json::to_value(halt).unwrap_or_default(),
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::system_events::SystemEventFilter;
+    use crate::services::matching::{OrderType, PriceGuardConfig, Side, TimeInForce};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_halt_recorded_and_resumed() {
+        let app = crate::test_support::TestApp::builder().build().await;
+        let engine = Arc::new(MatchingEngine::new().with_price_guard(PriceGuardConfig {
+            band: None,
+            halt_move: Some(dec!(0.1)),
+            halt_window_ms: 60_000,
+        }));
+        let breaker = Arc::new(CircuitBreaker::new(app.db.pool.clone(), engine.clone()));
+        breaker.clone().start();
+
+        let market_id = Uuid::new_v4();
+        let key = format!("{}:{}:Yes", market_id, Uuid::new_v4());
+        for price in [dec!(0.3), dec!(0.5)] {
+            engine
+                .submit_order(Uuid::new_v4(), &key, "0xa", Side::Sell, OrderType::Limit, dec!(1), Some(price), 1, TimeInForce::GTC)
+                .unwrap();
+            engine
+                .submit_order(Uuid::new_v4(), &key, "0xb", Side::Buy, OrderType::Limit, dec!(1), Some(price), 1, TimeInForce::GTC)
+                .unwrap();
+        }
+        assert_eq!(breaker.halts().len(), 1);
+
+        let filter = SystemEventFilter {
+            kind: Some("market_halted".to_string()),
+            subject: Some(market_id.to_string()),
+            limit: 10,
+            ..Default::default()
+        };
+        let mut recorded = Vec::new();
+        for _ in 0..50 {
+            recorded = system_events::query(&app.db.pool, &filter).await.unwrap();
+            if !recorded.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(recorded.len(), 1);
+
+        let admin: Address = "0x00000000000000000000000000000000000000ad".parse().unwrap();
+        assert!(breaker.resume(market_id, &admin, "checked the news").await.is_some());
+        assert!(breaker.resume(market_id, &admin, "again").await.is_none());
+        assert!(breaker.halts().is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+//! Immutable Market Criteria
+//!
+//! Traders price a market against its question, description and resolution
+//! source. Shortly after a market goes live, the pinner serializes those
+//! (with the end time, condition and outcome tokens) into a JSON document,
+//! adds it to IPFS and records the CID and the document's SHA-256 on the
+//! market row. From then on the database refuses edits to the pinned
+//! fields, and anyone can fetch the document by CID from any gateway and
+//! compare it with what the API serves.
+//!
+//! Pinning runs on the elected leader and picks up active markets without
+//! a CID, so a failed attempt is simply retried on the next pass.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::services::leader_election::LeaderElection;
+
+/// How often unpinned markets are looked for
+const PIN_INTERVAL_SECS: u64 = 30;
+
+/// Markets pinned per pass
+const PIN_BATCH_SIZE: i64 = 20;
+
+/// Version of the document layout
+const DOCUMENT_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PinError {
+    #[error("IPFS request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("IPFS API returned {0}: {1}")]
+    Status(u16, String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Outcome token as pinned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CriteriaOutcome {
+    pub name: String,
+    pub share_type: String,
+    pub token_id: String,
+}
+
+/// What a market is pinned with. Field order is the serialized order, so
+/// re-serializing a loaded document reproduces the pinned bytes.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CriteriaDocument {
+    #[sqlx(skip)]
+    pub version: u32,
+    pub market_id: Uuid,
+    pub condition_id: String,
+    pub question: String,
+    pub description: Option<String>,
+    pub resolution_source: String,
+    pub end_time: Option<DateTime<Utc>>,
+    #[sqlx(json)]
+    pub outcomes: Vec<CriteriaOutcome>,
+}
+
+impl CriteriaDocument {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    /// Hex SHA-256 of [`Self::to_bytes`]
+    pub fn sha256(&self) -> String {
+        hex::encode(Sha256::digest(self.to_bytes()))
+    }
+}
+
+/// A market's pin, if it has one
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CriteriaPin {
+    pub cid: String,
+    pub sha256: String,
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// The market's criteria document as it stands now
+pub async fn document(pool: &PgPool, market_id: Uuid) -> Result<Option<CriteriaDocument>, sqlx::Error> {
+    let document: Option<CriteriaDocument> = sqlx::query_as(
+        r#"
+        SELECT m.id AS market_id, m.condition_id, m.question, m.description, m.resolution_source, m.end_time,
+               COALESCE((
+                   SELECT jsonb_agg(jsonb_build_object('name', o.name, 'share_type', o.share_type::text, 'token_id', o.token_id)
+                                    ORDER BY o.share_type)
+                   FROM outcomes o
+                   WHERE o.market_id = m.id
+               ), '[]'::jsonb) AS outcomes
+        FROM markets m
+        WHERE m.id = $1
+        "#,
+    )
+    .bind(market_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(document.map(|d| CriteriaDocument { version: DOCUMENT_VERSION, ..d }))
+}
+
+/// The pin recorded for a market
+pub async fn pin(pool: &PgPool, market_id: Uuid) -> Result<Option<CriteriaPin>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT criteria_cid AS cid, criteria_sha256 AS sha256, criteria_pinned_at AS pinned_at
+        FROM markets
+        WHERE id = $1 AND criteria_cid IS NOT NULL
+        "#,
+    )
+    .bind(market_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Client for the `add` call of an IPFS HTTP API (Kubo RPC and hosted
+/// services compatible with it)
+pub struct IpfsClient {
+    http: reqwest::Client,
+    api_url: String,
+    auth: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+impl IpfsClient {
+    /// `None` unless `IPFS_API_URL` is set
+    pub fn from_config(config: &crate::config::AppConfig) -> Option<Self> {
+        let api_url = config.ipfs_api_url.clone().filter(|u| !u.is_empty())?;
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
+        Some(Self {
+            http,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            auth: config.ipfs_api_auth.clone().filter(|a| !a.is_empty()),
+        })
+    }
+
+    /// Add and pin `content` as a CIDv1 file; returns the CID
+    pub async fn add(&self, name: &str, content: &[u8]) -> Result<String, PinError> {
+        let boundary = format!("------criteria{}", Uuid::new_v4().simple());
+        let mut body = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: application/json\r\n\r\n",
+            boundary, name
+        )
+        .into_bytes();
+        body.extend_from_slice(content);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        let mut request = self
+            .http
+            .post(format!("{}/api/v0/add?pin=true&cid-version=1", self.api_url))
+            .header(reqwest::header::CONTENT_TYPE, format!("multipart/form-data; boundary={}", boundary))
+            .body(body);
+        if let Some(auth) = &self.auth {
+            request = request.header(reqwest::header::AUTHORIZATION, auth);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(PinError::Status(status.as_u16(), text));
+        }
+        Ok(response.json::<AddResponse>().await?.hash)
+    }
+}
+
+/// Background job pinning the criteria of newly live markets
+pub struct CriteriaPinner {
+    pool: PgPool,
+    ipfs: IpfsClient,
+}
+
+impl CriteriaPinner {
+    pub fn new(pool: PgPool, ipfs: IpfsClient) -> Self {
+        Self { pool, ipfs }
+    }
+
+    /// Spawn the pin loop; only the elected leader pins
+    pub fn start(self: Arc<Self>, leader: Arc<LeaderElection>) {
+        tokio::spawn(async move {
+            tracing::info!("Market criteria pinner started");
+            let mut interval = tokio::time::interval(Duration::from_secs(PIN_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                if !leader.is_leader() {
+                    continue;
+                }
+                match self.pin_pending().await {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!("Pinned criteria of {} markets", n),
+                    Err(e) => tracing::error!("Market criteria pinning failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Pin active markets that have no CID yet, oldest first. Returns the
+    /// number pinned; a market that fails is logged and retried next pass.
+    pub async fn pin_pending(&self) -> Result<usize, sqlx::Error> {
+        let market_ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM markets WHERE criteria_cid IS NULL AND status = 'active' ORDER BY created_at LIMIT $1",
+        )
+        .bind(PIN_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut pinned = 0;
+        for market_id in market_ids {
+            match self.pin_market(market_id).await {
+                Ok(Some(pin)) => {
+                    tracing::info!("Pinned criteria of market {} as {}", market_id, pin.cid);
+                    pinned += 1;
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to pin criteria of market {}: {}", market_id, e),
+            }
+        }
+        Ok(pinned)
+    }
+
+    /// Pin one market's criteria and record the CID. `None` if the market
+    /// does not exist or was pinned meanwhile.
+    pub async fn pin_market(&self, market_id: Uuid) -> Result<Option<CriteriaPin>, PinError> {
+        let Some(document) = document(&self.pool, market_id).await? else {
+            return Ok(None);
+        };
+        let cid = self
+            .ipfs
+            .add(&format!("market-{}.json", market_id), &document.to_bytes())
+            .await?;
+
+        let pin = sqlx::query_as(
+            r#"
+            UPDATE markets
+            SET criteria_cid = $2, criteria_sha256 = $3, criteria_pinned_at = NOW()
+            WHERE id = $1 AND criteria_cid IS NULL
+            RETURNING criteria_cid AS cid, criteria_sha256 AS sha256, criteria_pinned_at AS pinned_at
+            "#,
+        )
+        .bind(market_id)
+        .bind(&cid)
+        .bind(document.sha256())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(pin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Router};
+
+    use crate::test_support::TestApp;
+
+    #[tokio::test]
+    async fn test_pinned_criteria_are_immutable() {
+        let app = TestApp::builder().build().await;
+        let (market_id, _, _) = app.create_market().await;
+
+        // Stand-in for the IPFS API: checks the upload and returns a fixed CID
+        let router = Router::new().route(
+            "/api/v0/add",
+            post(|body: String| async move {
+                assert!(body.contains("\"question\":\"Test market?\""));
+                axum::Json(serde_json::json!({ "Name": "market.json", "Hash": "bafytestcid", "Size": "1" }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = crate::config::AppConfig {
+            ipfs_api_url: Some(format!("http://{}/", listener.local_addr().unwrap())),
+            ..app.state.config.clone()
+        };
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let pinner = CriteriaPinner::new(app.db.pool.clone(), IpfsClient::from_config(&config).unwrap());
+        let pinned = pinner.pin_market(market_id).await.unwrap().unwrap();
+        assert_eq!(pinned.cid, "bafytestcid");
+        assert_eq!(pinned.sha256, document(&app.db.pool, market_id).await.unwrap().unwrap().sha256());
+        // Only the first pin counts
+        assert!(pinner.pin_market(market_id).await.unwrap().is_none());
+
+        let edit = sqlx::query("UPDATE markets SET question = 'Edited?' WHERE id = $1")
+            .bind(market_id)
+            .execute(&app.db.pool)
+            .await;
+        assert!(edit.is_err());
+        sqlx::query("UPDATE markets SET status = 'paused' WHERE id = $1")
+            .bind(market_id)
+            .execute(&app.db.pool)
+            .await
+            .unwrap();
+    }
+}
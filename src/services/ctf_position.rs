@@ -0,0 +1,205 @@
+//! User-Directed Split / Merge
+//!
+//! Users holding collateral in their own wallet can mint a full set of
+//! outcome tokens (`splitPosition`) or burn one back into collateral
+//! (`mergePositions`) on the ConditionalTokens contract. The API prepares
+//! the calldata after checking the wallet's on-chain balances; the user
+//! signs and sends the transaction. The prepared operation is tracked in
+//! `ctf_position_ops` and confirmed when the event listener sees the
+//! matching `PositionSplit` / `PositionsMerge` event.
+
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+
+/// Decimals of the collateral token and of outcome positions
+pub const COLLATERAL_DECIMALS: u32 = 6;
+
+/// Split or merge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionOpKind {
+    Split,
+    Merge,
+}
+
+impl PositionOpKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PositionOpKind::Split => "split",
+            PositionOpKind::Merge => "merge",
+        }
+    }
+}
+
+/// A tracked split or merge
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PositionOp {
+    pub id: Uuid,
+    pub market_id: Option<Uuid>,
+    pub condition_id: String,
+    pub kind: String,
+    pub amount: Decimal,
+    pub status: String,
+    pub tx_hash: Option<String>,
+    pub block_number: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+}
+
+/// `amount` in base units of the collateral; None if it is not positive or
+/// has more precision than the token
+pub fn to_base_units(amount: Decimal) -> Option<U256> {
+    if amount <= Decimal::ZERO || amount.normalize().scale() > COLLATERAL_DECIMALS {
+        return None;
+    }
+    let units = (amount * Decimal::from(10u64.pow(COLLATERAL_DECIMALS))).trunc();
+    units.to_string().parse::<u128>().ok().map(U256::from)
+}
+
+/// Base units of the collateral (or of a position) as a decimal amount;
+/// None if the value does not fit
+pub fn from_base_units(units: U256) -> Option<Decimal> {
+    let units = i128::try_from(u128::try_from(units).ok()?).ok()?;
+    Decimal::try_from_i128_with_scale(units, COLLATERAL_DECIMALS).ok()
+}
+
+/// Track an operation prepared for `user_address`
+pub async fn record_prepared(
+    pool: &PgPool,
+    user_address: &Address,
+    market_id: Uuid,
+    condition_id: &str,
+    kind: PositionOpKind,
+    amount: Decimal,
+) -> Result<PositionOp, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        INSERT INTO ctf_position_ops (user_address, market_id, condition_id, kind, amount)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, market_id, condition_id, kind, amount, status, tx_hash, block_number, created_at, confirmed_at
+        "#,
+    )
+    .bind(user_address)
+    .bind(market_id)
+    .bind(condition_id)
+    .bind(kind.as_str())
+    .bind(amount)
+    .fetch_one(pool)
+    .await
+}
+
+/// Confirm the oldest prepared operation matching an on-chain event, or
+/// record the event as an operation of its own if none matches. Events
+/// already on record are ignored.
+pub async fn record_event(
+    pool: &PgPool,
+    user_address: &str,
+    condition_id: &str,
+    kind: PositionOpKind,
+    amount: Decimal,
+    tx_hash: &str,
+    block_number: i64,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let seen: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM ctf_position_ops WHERE tx_hash = $1 AND kind = $2)")
+            .bind(tx_hash)
+            .bind(kind.as_str())
+            .fetch_one(&mut *tx)
+            .await?;
+    if seen {
+        return Ok(());
+    }
+
+    let confirmed = sqlx::query(
+        r#"
+        UPDATE ctf_position_ops
+        SET status = 'confirmed', tx_hash = $5, block_number = $6, confirmed_at = NOW()
+        WHERE id = (
+            SELECT id FROM ctf_position_ops
+            WHERE user_address = $1 AND condition_id = $2 AND kind = $3 AND amount = $4 AND status = 'prepared'
+            ORDER BY created_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        "#,
+    )
+    .bind(user_address)
+    .bind(condition_id)
+    .bind(kind.as_str())
+    .bind(amount)
+    .bind(tx_hash)
+    .bind(block_number)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if confirmed == 0 {
+        sqlx::query(
+            r#"
+            INSERT INTO ctf_position_ops (
+                user_address, market_id, condition_id, kind, amount, status, tx_hash, block_number, confirmed_at
+            )
+            SELECT $1, (SELECT id FROM markets WHERE condition_id = $2), $2, $3, $4, 'confirmed', $5, $6, NOW()
+            "#,
+        )
+        .bind(user_address)
+        .bind(condition_id)
+        .bind(kind.as_str())
+        .bind(amount)
+        .bind(tx_hash)
+        .bind(block_number)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Operations of `user_address`, newest first
+pub async fn list(pool: &PgPool, user_address: &Address, limit: i64) -> Result<Vec<PositionOp>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, market_id, condition_id, kind, amount, status, tx_hash, block_number, created_at, confirmed_at
+        FROM ctf_position_ops
+        WHERE user_address = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(user_address)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_to_base_units() {
+        assert_eq!(to_base_units(dec!(1.5)), Some(U256::from(1_500_000u64)));
+        assert_eq!(to_base_units(dec!(0.000001)), Some(U256::from(1u64)));
+        assert_eq!(to_base_units(dec!(2.5000000)), Some(U256::from(2_500_000u64)));
+        assert_eq!(to_base_units(dec!(0.0000001)), None);
+        assert_eq!(to_base_units(Decimal::ZERO), None);
+        assert_eq!(to_base_units(dec!(-1)), None);
+    }
+
+    #[test]
+    fn test_from_base_units() {
+        assert_eq!(from_base_units(U256::from(1_500_000u64)), Some(dec!(1.5)));
+        assert_eq!(from_base_units(U256::zero()), Some(Decimal::ZERO));
+        assert_eq!(from_base_units(U256::MAX), None);
+    }
+}
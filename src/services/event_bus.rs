@@ -0,0 +1,602 @@
+//! Internal Event Bus
+//!
+//! Trades, order updates, mass cancels, balance updates, market status
+//! changes, trigger order updates and matches sent for on-chain settlement
+//! are published to one bus instead of a broadcast channel per kind. With
+//! Redis available, every event is appended to a single Redis Stream, so
+//! all nodes see the same events in the same order:
+//!
+//! - each node tails the stream into in-process channels that its
+//!   WebSocket and SSE clients subscribe to
+//! - services consume it through consumer groups: a group sees every event
+//!   once, and an event is acknowledged only after its handler succeeds.
+//!   Unacknowledged events are redelivered to the consumer (named after the
+//!   host) when it restarts, and taken over by another consumer of the
+//!   group once they have been pending longer than `claim_idle_ms`, so a
+//!   node that never comes back doesn't strand them. A group created later
+//!   starts at the end of the stream; `XGROUP SETID` replays it from any
+//!   retained entry.
+//!
+//! The notifier, the trade persistence backstop and the settlement service
+//! consume the bus (see [`MarketStatusNotifier`],
+//! [`crate::services::trade_persistence::TradePersistQueue::start_consumer`]
+//! and [`crate::services::settlement::SettlementService::start_worker`]).
+//!
+//! Without Redis (or with `EVENT_BUS_ENABLED=false`) events are delivered
+//! in-process only, which is how a single node has always run.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use redis::aio::ConnectionManager;
+use redis::streams::{
+    StreamClaimReply, StreamMaxlen, StreamPendingCountReply, StreamPendingId, StreamReadOptions, StreamReadReply,
+};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::services::channel_gateway::{ChannelEventType, ChannelGateway};
+use crate::services::matching::{MatchingEngine, TradeEvent};
+use crate::services::settlement::MatchedOrders;
+use crate::services::trigger_orders::TriggerOrderUpdateEvent;
+use crate::services::webhook::{WebhookEventType, WebhookService};
+use crate::{BalanceUpdateEvent, OrderUpdateEvent, OrdersCancelledEvent};
+
+/// Capacity of each in-process channel
+const LOCAL_CAPACITY: usize = 1000;
+
+/// Entries read per stream call
+const READ_COUNT: usize = 100;
+
+/// How long a stream read waits for new entries
+const READ_BLOCK_MS: usize = 5000;
+
+/// Pause after a failed stream call or handler
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A market opened, paused, resolved or cancelled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStatusEvent {
+    pub market_id: Uuid,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winning_outcome_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winning_share_type: Option<String>,
+    pub timestamp: i64,
+}
+
+impl MarketStatusEvent {
+    pub fn new(market_id: Uuid, status: &str) -> Self {
+        Self {
+            market_id,
+            status: status.to_string(),
+            winning_outcome_id: None,
+            winning_share_type: None,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// Everything that travels on the bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum BusEvent {
+    Trade(TradeEvent),
+    OrderUpdate(OrderUpdateEvent),
+    OrdersCancelled(OrdersCancelledEvent),
+    BalanceUpdate(BalanceUpdateEvent),
+    MarketStatus(MarketStatusEvent),
+    TriggerOrderUpdate(TriggerOrderUpdateEvent),
+    /// A match of signed orders to submit on-chain
+    Settlement(Box<MatchedOrders>),
+}
+
+impl BusEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BusEvent::Trade(_) => "trade",
+            BusEvent::OrderUpdate(_) => "order_update",
+            BusEvent::OrdersCancelled(_) => "orders_cancelled",
+            BusEvent::BalanceUpdate(_) => "balance_update",
+            BusEvent::MarketStatus(_) => "market_status",
+            BusEvent::TriggerOrderUpdate(_) => "trigger_order_update",
+            BusEvent::Settlement(_) => "settlement",
+        }
+    }
+}
+
+/// Stream settings
+#[derive(Debug, Clone)]
+pub struct EventBusConfig {
+    /// Whether to use Redis when it is available
+    pub enabled: bool,
+    /// Stream key
+    pub stream: String,
+    /// Entries kept; older ones are trimmed (approximately)
+    pub max_len: usize,
+    /// How long an entry stays pending with one consumer before another
+    /// consumer of its group takes it over
+    pub claim_idle_ms: usize,
+}
+
+impl EventBusConfig {
+    pub fn from_config(config: &crate::config::AppConfig) -> Self {
+        Self {
+            enabled: config.event_bus_enabled,
+            stream: config.event_bus_stream.clone(),
+            max_len: config.event_bus_max_len,
+            claim_idle_ms: config.event_bus_claim_idle_ms,
+        }
+    }
+}
+
+/// The Redis side of the bus
+struct RedisStream {
+    client: redis::Client,
+    publisher: ConnectionManager,
+    stream: String,
+    max_len: usize,
+    claim_idle_ms: usize,
+}
+
+/// In-process channels, fed directly or from the stream
+struct LocalChannels {
+    all: broadcast::Sender<BusEvent>,
+    trades: broadcast::Sender<TradeEvent>,
+    order_updates: broadcast::Sender<OrderUpdateEvent>,
+    orders_cancelled: broadcast::Sender<OrdersCancelledEvent>,
+    balance_updates: broadcast::Sender<BalanceUpdateEvent>,
+    trigger_updates: broadcast::Sender<TriggerOrderUpdateEvent>,
+}
+
+pub struct EventBus {
+    redis: Option<RedisStream>,
+    local: LocalChannels,
+}
+
+impl EventBus {
+    /// A bus that delivers in-process only
+    pub fn local() -> Self {
+        Self {
+            redis: None,
+            local: LocalChannels {
+                all: broadcast::channel(LOCAL_CAPACITY).0,
+                trades: broadcast::channel(LOCAL_CAPACITY).0,
+                order_updates: broadcast::channel(LOCAL_CAPACITY).0,
+                orders_cancelled: broadcast::channel(LOCAL_CAPACITY).0,
+                balance_updates: broadcast::channel(LOCAL_CAPACITY).0,
+                trigger_updates: broadcast::channel(LOCAL_CAPACITY).0,
+            },
+        }
+    }
+
+    /// A bus on the Redis Stream at `redis_url`; in-process only if Redis
+    /// is not configured or cannot be reached
+    pub async fn connect(redis_url: Option<&str>, config: EventBusConfig) -> Self {
+        let mut bus = Self::local();
+        let Some(url) = redis_url.filter(|_| config.enabled) else {
+            tracing::info!("Event bus is in-process only");
+            return bus;
+        };
+        let connected = async {
+            let client = redis::Client::open(url)?;
+            let publisher = ConnectionManager::new(client.clone()).await?;
+            Ok::<_, redis::RedisError>((client, publisher))
+        };
+        match connected.await {
+            Ok((client, publisher)) => {
+                tracing::info!("Event bus on Redis stream {}", config.stream);
+                bus.redis = Some(RedisStream {
+                    client,
+                    publisher,
+                    stream: config.stream,
+                    max_len: config.max_len,
+                    claim_idle_ms: config.claim_idle_ms,
+                });
+            }
+            Err(e) => tracing::warn!("Event bus falling back to in-process delivery: {}", e),
+        }
+        bus
+    }
+
+    /// Whether events go through Redis (and survive restarts)
+    pub fn is_durable(&self) -> bool {
+        self.redis.is_some()
+    }
+
+    /// Publish `event` to every node. If the stream cannot be written the
+    /// event is still delivered on this node.
+    pub async fn publish(&self, event: BusEvent) {
+        let Some(redis) = &self.redis else {
+            self.local.deliver(event);
+            return;
+        };
+        let result = match serde_json::to_string(&event) {
+            Ok(data) => {
+                let mut conn = redis.publisher.clone();
+                conn.xadd_maxlen::<_, _, _, _, String>(
+                    &redis.stream,
+                    StreamMaxlen::Approx(redis.max_len),
+                    "*",
+                    &[("type", event.kind()), ("data", data.as_str())],
+                )
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to append {} event to the stream: {}", event.kind(), e);
+            self.local.deliver(event);
+        }
+    }
+
+    pub fn subscribe_trades(&self) -> broadcast::Receiver<TradeEvent> {
+        self.local.trades.subscribe()
+    }
+
+    pub fn subscribe_order_updates(&self) -> broadcast::Receiver<OrderUpdateEvent> {
+        self.local.order_updates.subscribe()
+    }
+
+    pub fn subscribe_orders_cancelled(&self) -> broadcast::Receiver<OrdersCancelledEvent> {
+        self.local.orders_cancelled.subscribe()
+    }
+
+    pub fn subscribe_balance_updates(&self) -> broadcast::Receiver<BalanceUpdateEvent> {
+        self.local.balance_updates.subscribe()
+    }
+
+    pub fn subscribe_trigger_updates(&self) -> broadcast::Receiver<TriggerOrderUpdateEvent> {
+        self.local.trigger_updates.subscribe()
+    }
+
+    /// Publish the engine's trades
+    pub fn forward_trades(self: Arc<Self>, engine: &MatchingEngine) {
+        let mut receiver = engine.subscribe_trades();
+        tokio::spawn(async move {
+            tracing::info!("Trade forwarder started");
+            loop {
+                match receiver.recv().await {
+                    Ok(trade) => self.publish(BusEvent::Trade(trade)).await,
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Trade forwarder lagged by {} trades", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            tracing::warn!("Trade forwarder stopped");
+        });
+    }
+
+    /// Tail the stream into this node's channels; a no-op without Redis
+    pub fn start_relay(self: Arc<Self>) {
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let (client, stream) = (redis.client.clone(), redis.stream.clone());
+        tokio::spawn(async move {
+            tracing::info!("Event bus relay started");
+            // Only events published from now on
+            let mut last_id = "$".to_string();
+            loop {
+                let read = async {
+                    // Blocking reads get their own connection
+                    let mut conn = client.get_multiplexed_tokio_connection().await?;
+                    loop {
+                        let options = StreamReadOptions::default().count(READ_COUNT).block(READ_BLOCK_MS);
+                        let reply: Option<StreamReadReply> =
+                            conn.xread_options(&[&stream], &[&last_id], &options).await?;
+                        for entry in reply.into_iter().flat_map(|r| r.keys).flat_map(|k| k.ids) {
+                            if let Some(event) = decode(&entry) {
+                                self.local.deliver(event);
+                            }
+                            last_id = entry.id;
+                        }
+                    }
+                };
+                let result: Result<(), redis::RedisError> = read.await;
+                if let Err(e) = result {
+                    tracing::warn!("Event bus relay read failed: {}", e);
+                }
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        });
+    }
+
+    /// Run `handler` on every event as consumer group `group`. With Redis an
+    /// event is acknowledged once `handler` succeeds and retried otherwise,
+    /// and entries other consumers of the group left pending for
+    /// `claim_idle_ms` are claimed and handled here; in-process, events are
+    /// handled once as they arrive.
+    pub fn consume<F, Fut>(self: Arc<Self>, group: &'static str, handler: F)
+    where
+        F: Fn(BusEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send,
+    {
+        let Some(redis) = &self.redis else {
+            let mut receiver = self.local.all.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) => {
+                            if let Err(e) = handler(event).await {
+                                tracing::warn!("Event consumer {} failed: {}", group, e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            tracing::warn!("Event consumer {} lagged by {} events", group, n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+            return;
+        };
+
+        let (client, stream, claim_idle_ms) = (redis.client.clone(), redis.stream.clone(), redis.claim_idle_ms);
+        // Stable across restarts, so a restarted consumer gets its own
+        // unacknowledged events back
+        let consumer = std::env::var("HOSTNAME").unwrap_or_else(|_| "local".to_string());
+        tokio::spawn(async move {
+            tracing::info!("Event consumer {} started as {}", group, consumer);
+            loop {
+                let result: Result<(), redis::RedisError> = async {
+                    let mut conn = client.get_multiplexed_tokio_connection().await?;
+                    let created: Result<(), redis::RedisError> =
+                        conn.xgroup_create_mkstream(&stream, group, "$").await;
+                    if let Err(e) = created {
+                        if e.code() != Some("BUSYGROUP") {
+                            return Err(e);
+                        }
+                    }
+
+                    // Pending entries first ("0"), then new ones (">"); entries
+                    // claimed from other consumers join this one's pending list
+                    let mut pending = true;
+                    let mut claimed_at: Option<Instant> = None;
+                    loop {
+                        if !pending && claimed_at.is_none_or(|at| at.elapsed().as_millis() >= claim_idle_ms as u128) {
+                            claimed_at = Some(Instant::now());
+                            let reply: StreamPendingCountReply =
+                                conn.xpending_count(&stream, group, "-", "+", READ_COUNT).await?;
+                            let stale = stale_entries(&reply.ids, &consumer, claim_idle_ms);
+                            if !stale.is_empty() {
+                                let claimed: StreamClaimReply =
+                                    conn.xclaim(&stream, group, &consumer, claim_idle_ms, &stale).await?;
+                                tracing::warn!(
+                                    "Event consumer {} took over {} stale entries",
+                                    group,
+                                    claimed.ids.len()
+                                );
+                                pending = true;
+                            }
+                        }
+                        let mut options = StreamReadOptions::default().group(group, &consumer).count(READ_COUNT);
+                        if !pending {
+                            options = options.block(READ_BLOCK_MS.min(claim_idle_ms.max(1)));
+                        }
+                        let from = if pending { "0" } else { ">" };
+                        let reply: Option<StreamReadReply> = conn.xread_options(&[&stream], &[from], &options).await?;
+                        let entries: Vec<_> = reply.into_iter().flat_map(|r| r.keys).flat_map(|k| k.ids).collect();
+                        if pending && entries.is_empty() {
+                            pending = false;
+                            continue;
+                        }
+
+                        for entry in entries {
+                            let handled = match decode(&entry) {
+                                Some(event) => handler(event).await,
+                                // Trimmed or unreadable entries are skipped
+                                None => Ok(()),
+                            };
+                            match handled {
+                                Ok(()) => {
+                                    let _: i64 = conn.xack(&stream, group, &[&entry.id]).await?;
+                                }
+                                Err(e) => {
+                                    tracing::warn!("Event consumer {} failed on {}: {}", group, entry.id, e);
+                                    // Left pending; retried from the pending list
+                                    pending = true;
+                                    tokio::time::sleep(RETRY_DELAY).await;
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+                .await;
+                if let Err(e) = result {
+                    tracing::warn!("Event consumer {} stream error: {}", group, e);
+                }
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        });
+    }
+}
+
+impl LocalChannels {
+    fn deliver(&self, event: BusEvent) {
+        // No receivers just means nobody is listening on this node
+        match &event {
+            BusEvent::Trade(trade) => {
+                let _ = self.trades.send(trade.clone());
+            }
+            BusEvent::OrderUpdate(update) => {
+                let _ = self.order_updates.send(update.clone());
+            }
+            BusEvent::OrdersCancelled(cancelled) => {
+                let _ = self.orders_cancelled.send(cancelled.clone());
+            }
+            BusEvent::BalanceUpdate(update) => {
+                let _ = self.balance_updates.send(update.clone());
+            }
+            BusEvent::TriggerOrderUpdate(update) => {
+                let _ = self.trigger_updates.send(update.clone());
+            }
+            BusEvent::MarketStatus(_) | BusEvent::Settlement(_) => {}
+        }
+        let _ = self.all.send(event);
+    }
+}
+
+/// Announces resolved markets to webhooks and channels, once per event
+/// when consuming the stream as group [`MarketStatusNotifier::GROUP`]
+pub struct MarketStatusNotifier {
+    pub webhook_service: Arc<WebhookService>,
+    pub channel_gateway: Arc<ChannelGateway>,
+}
+
+impl MarketStatusNotifier {
+    pub const GROUP: &'static str = "notifier";
+
+    pub fn start(self: Arc<Self>, bus: Arc<EventBus>) {
+        bus.consume(Self::GROUP, move |event| {
+            let notifier = self.clone();
+            async move {
+                if let BusEvent::MarketStatus(event) = event {
+                    notifier.announce(&event).await;
+                }
+                Ok(())
+            }
+        });
+    }
+
+    async fn announce(&self, event: &MarketStatusEvent) {
+        if event.status != "resolved" {
+            return;
+        }
+        self.webhook_service
+            .dispatch(
+                WebhookEventType::MarketResolved,
+                None,
+                serde_json::json!({
+                    "market_id": event.market_id,
+                    "winning_outcome_id": event.winning_outcome_id,
+                    "winning_share_type": event.winning_share_type,
+                }),
+            )
+            .await;
+        self.channel_gateway
+            .publish(
+                ChannelEventType::MarketResolved,
+                event.market_id,
+                None,
+                serde_json::json!({ "winning_share_type": event.winning_share_type }),
+            )
+            .await;
+    }
+}
+
+/// Ids of the entries another consumer than `consumer` has held
+/// unacknowledged for at least `idle_ms`
+fn stale_entries(pending: &[StreamPendingId], consumer: &str, idle_ms: usize) -> Vec<String> {
+    pending
+        .iter()
+        .filter(|entry| entry.consumer != consumer && entry.last_delivered_ms >= idle_ms)
+        .map(|entry| entry.id.clone())
+        .collect()
+}
+
+/// The event in a stream entry, if it has one
+fn decode(entry: &redis::streams::StreamId) -> Option<BusEvent> {
+    let data: String = entry.get("data")?;
+    serde_json::from_str(&data)
+        .map_err(|e| tracing::warn!("Unreadable event bus entry {}: {}", entry.id, e))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_bus_delivers_by_kind() {
+        let bus = EventBus::local();
+        let mut balances = bus.subscribe_balance_updates();
+        let mut all = bus.local.all.subscribe();
+
+        let update = BalanceUpdateEvent {
+            user_address: "0xabc".to_string(),
+            token: "USDC".to_string(),
+            available: "10".to_string(),
+            frozen: "0".to_string(),
+            total: "10".to_string(),
+            event_type: "deposit".to_string(),
+        };
+        bus.publish(BusEvent::BalanceUpdate(update)).await;
+        bus.publish(BusEvent::MarketStatus(MarketStatusEvent::new(Uuid::nil(), "paused"))).await;
+
+        assert_eq!(balances.recv().await.unwrap().event_type, "deposit");
+        assert_eq!(all.recv().await.unwrap().kind(), "balance_update");
+        assert_eq!(all.recv().await.unwrap().kind(), "market_status");
+
+        // What the stream carries reads back as the same event
+        let json = serde_json::to_string(&BusEvent::MarketStatus(MarketStatusEvent::new(Uuid::nil(), "paused"))).unwrap();
+        assert!(json.starts_with(r#"{"type":"market_status","data":{"#));
+        assert!(matches!(serde_json::from_str(&json).unwrap(), BusEvent::MarketStatus(e) if e.status == "paused"));
+    }
+
+    #[test]
+    fn test_only_other_consumers_idle_entries_are_stale() {
+        let entry = |id: &str, consumer: &str, idle: usize| StreamPendingId {
+            id: id.to_string(),
+            consumer: consumer.to_string(),
+            last_delivered_ms: idle,
+            times_delivered: 1,
+        };
+        let pending = [entry("1-0", "crashed", 500), entry("2-0", "crashed", 50), entry("3-0", "me", 500)];
+
+        assert_eq!(stale_entries(&pending, "me", 100), vec!["1-0".to_string()]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at TEST_REDIS_URL"]
+    async fn test_crashed_consumer_entries_are_redelivered() {
+        let url = std::env::var("TEST_REDIS_URL").expect("TEST_REDIS_URL");
+        let stream = format!("test_events_{}", Uuid::new_v4().simple());
+        let bus = Arc::new(
+            EventBus::connect(
+                Some(&url),
+                EventBusConfig {
+                    enabled: true,
+                    stream: stream.clone(),
+                    max_len: 1000,
+                    claim_idle_ms: 100,
+                },
+            )
+            .await,
+        );
+        assert!(bus.is_durable());
+
+        // A consumer reads the event and dies before acknowledging it
+        let mut conn = redis::Client::open(url.as_str()).unwrap().get_multiplexed_tokio_connection().await.unwrap();
+        let _: () = conn.xgroup_create_mkstream(&stream, "test", "$").await.unwrap();
+        bus.publish(BusEvent::MarketStatus(MarketStatusEvent::new(Uuid::nil(), "resolved"))).await;
+        let options = StreamReadOptions::default().group("test", "crashed").count(READ_COUNT);
+        let read: StreamReadReply = conn.xread_options(&[&stream], &[">"], &options).await.unwrap();
+        assert_eq!(read.keys[0].ids.len(), 1);
+
+        // Another consumer of the group takes it over once it has idled
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        bus.clone().consume("test", move |event| {
+            let tx = tx.clone();
+            async move {
+                let _ = tx.send(event.kind());
+                Ok(())
+            }
+        });
+        let kind = tokio::time::timeout(Duration::from_secs(10), rx.recv()).await.unwrap();
+        assert_eq!(kind, Some("market_status"));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let pending: StreamPendingCountReply = conn.xpending_count(&stream, "test", "-", "+", 10).await.unwrap();
+        assert!(pending.ids.is_empty());
+        let _: () = conn.del(&stream).await.unwrap();
+    }
+}
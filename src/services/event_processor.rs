@@ -11,11 +11,16 @@ use std::sync::Arc;
 use ethers::types::{Address, H256, U256};
 use rust_decimal::Decimal;
 use sqlx::PgPool;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
 use crate::blockchain::events::{BlockchainEvent, EventListener};
 use crate::blockchain::types::ContractAddresses;
+use crate::models;
+use crate::services::ctf_position::{self, PositionOpKind};
+use crate::services::leader_election::LeaderElection;
+use crate::services::resolution_evidence::{self, ResolutionEvidence, ResolutionMethod};
+use crate::services::event_bus::{BusEvent, EventBus};
 use crate::BalanceUpdateEvent;
 
 /// Event processor configuration
@@ -44,7 +49,9 @@ pub struct EventProcessor {
     pool: PgPool,
     config: EventProcessorConfig,
     addresses: ContractAddresses,
-    balance_sender: broadcast::Sender<BalanceUpdateEvent>,
+    event_bus: Arc<EventBus>,
+    /// Events are only applied while this instance is leader
+    leader: Option<Arc<LeaderElection>>,
 }
 
 impl EventProcessor {
@@ -53,16 +60,24 @@ impl EventProcessor {
         pool: PgPool,
         config: EventProcessorConfig,
         addresses: ContractAddresses,
-        balance_sender: broadcast::Sender<BalanceUpdateEvent>,
+        event_bus: Arc<EventBus>,
     ) -> Self {
         Self {
             pool,
             config,
             addresses,
-            balance_sender,
+            event_bus,
+            leader: None,
         }
     }
 
+    /// Only apply events while holding leadership, so replicas never
+    /// double-credit deposits
+    pub fn with_leader_election(mut self, leader: Arc<LeaderElection>) -> Self {
+        self.leader = Some(leader);
+        self
+    }
+
     /// Start the event processor
     /// This spawns a background task that listens for events
     pub fn start(
@@ -93,6 +108,10 @@ impl EventProcessor {
                     continue;
                 }
 
+                if self.leader.as_ref().is_some_and(|l| !l.is_leader()) {
+                    continue;
+                }
+
                 if let Err(e) = self.process_event(event).await {
                     error!("Failed to process event: {}", e);
                 }
@@ -161,7 +180,7 @@ impl EventProcessor {
 
         // Check if this is a deposit to CTFExchange
         if to == self.addresses.ctf_exchange {
-            let user_address = format!("{:?}", from).to_lowercase();
+            let user_address: String = models::Address::from(from).into();
             info!(
                 "Deposit detected: {} USDC from {} (block {})",
                 amount_decimal, user_address, block_number
@@ -192,7 +211,7 @@ impl EventProcessor {
 
         // Check if this is a withdrawal from CTFExchange
         if from == self.addresses.ctf_exchange {
-            let user_address = format!("{:?}", to).to_lowercase();
+            let user_address: String = models::Address::from(to).into();
             info!(
                 "Withdrawal detected: {} USDC to {} (block {})",
                 amount_decimal, user_address, block_number
@@ -259,8 +278,8 @@ impl EventProcessor {
         event: crate::blockchain::types::TradeEvent,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let tx_hash_str = format!("{:?}", event.tx_hash);
-        let maker_address = format!("{:?}", event.maker).to_lowercase();
-        let taker_address = format!("{:?}", event.taker).to_lowercase();
+        let maker_address: String = models::Address::from(event.maker).into();
+        let taker_address: String = models::Address::from(event.taker).into();
         let amount = u256_to_decimal(event.amount);
         let price = u256_to_decimal(event.price);
 
@@ -306,7 +325,7 @@ impl EventProcessor {
         &self,
         event: crate::blockchain::types::PositionSplitEvent,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let user_address = format!("{:?}", event.stakeholder).to_lowercase();
+        let user_address: String = models::Address::from(event.stakeholder).into();
         let amount = u256_to_decimal(event.amount);
         let condition_id = format!("0x{}", hex::encode(event.condition_id));
 
@@ -333,6 +352,17 @@ impl EventProcessor {
         .execute(&self.pool)
         .await?;
 
+        ctf_position::record_event(
+            &self.pool,
+            &user_address,
+            &condition_id,
+            PositionOpKind::Split,
+            amount,
+            &format!("{:?}", event.tx_hash),
+            event.block_number as i64,
+        )
+        .await?;
+
         Ok(())
     }
 
@@ -341,7 +371,7 @@ impl EventProcessor {
         &self,
         event: crate::blockchain::types::PositionMergeEvent,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let user_address = format!("{:?}", event.stakeholder).to_lowercase();
+        let user_address: String = models::Address::from(event.stakeholder).into();
         let amount = u256_to_decimal(event.amount);
         let condition_id = format!("0x{}", hex::encode(event.condition_id));
 
@@ -368,6 +398,17 @@ impl EventProcessor {
         .execute(&self.pool)
         .await?;
 
+        ctf_position::record_event(
+            &self.pool,
+            &user_address,
+            &condition_id,
+            PositionOpKind::Merge,
+            amount,
+            &format!("{:?}", event.tx_hash),
+            event.block_number as i64,
+        )
+        .await?;
+
         Ok(())
     }
 
@@ -378,7 +419,7 @@ impl EventProcessor {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let condition_id = format!("0x{}", hex::encode(event.condition_id));
         let question_id = format!("0x{}", hex::encode(event.question_id));
-        let oracle_address = format!("{:?}", event.oracle).to_lowercase();
+        let oracle_address: String = models::Address::from(event.oracle).into();
 
         info!(
             "Condition prepared: {} (oracle: {}, outcomes: {})",
@@ -426,7 +467,8 @@ impl EventProcessor {
 
         if let Some(idx) = winning_index {
             // Update market as resolved
-            sqlx::query(
+            let tx_hash = format!("{:?}", event.tx_hash);
+            let market_ids: Vec<uuid::Uuid> = sqlx::query_scalar(
                 r#"
                 UPDATE markets
                 SET status = 'resolved',
@@ -434,14 +476,41 @@ impl EventProcessor {
                     resolution_tx_hash = $1,
                     updated_at = NOW()
                 WHERE condition_id = $2 OR question_id = $3
+                RETURNING id
                 "#,
             )
-            .bind(format!("{:?}", event.tx_hash))
+            .bind(&tx_hash)
             .bind(&condition_id)
             .bind(&question_id)
-            .execute(&self.pool)
+            .fetch_all(&self.pool)
             .await?;
 
+            let winning_share_type = if idx == 0 { "yes" } else { "no" };
+            let mut conn = self.pool.acquire().await?;
+            for market_id in market_ids {
+                let winning_outcome_id: Option<uuid::Uuid> =
+                    sqlx::query_scalar("SELECT id FROM outcomes WHERE market_id = $1 AND share_type = $2::share_type")
+                        .bind(market_id)
+                        .bind(winning_share_type)
+                        .fetch_optional(&mut *conn)
+                        .await?;
+                let evidence = ResolutionEvidence {
+                    method: ResolutionMethod::Onchain,
+                    winning_outcome_id,
+                    resolved_by: None,
+                    justification: None,
+                    oracle_data: serde_json::json!({
+                        "condition_id": condition_id,
+                        "question_id": question_id,
+                        "oracle": format!("{:?}", event.oracle),
+                        "payout_numerators": event.payout_numerators.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+                        "block_number": event.block_number,
+                    }),
+                    tx_hashes: vec![tx_hash.clone()],
+                };
+                resolution_evidence::record(&mut conn, market_id, &evidence).await?;
+            }
+
             info!("Market resolved: winning outcome index = {}", idx);
         }
 
@@ -490,14 +559,16 @@ impl EventProcessor {
 
         if let Some((available, frozen)) = balance {
             let total = available + frozen;
-            let _ = self.balance_sender.send(BalanceUpdateEvent {
-                user_address: user_address.to_string(),
-                token: token.to_string(),
-                available: available.to_string(),
-                frozen: frozen.to_string(),
-                total: total.to_string(),
-                event_type: event_type.to_string(),
-            });
+            self.event_bus
+                .publish(BusEvent::BalanceUpdate(BalanceUpdateEvent {
+                    user_address: user_address.to_string(),
+                    token: token.to_string(),
+                    available: available.to_string(),
+                    frozen: frozen.to_string(),
+                    total: total.to_string(),
+                    event_type: event_type.to_string(),
+                }))
+                .await;
         }
 
         Ok(())
@@ -0,0 +1,331 @@
+//! Execution Quality Statistics
+//!
+//! Orders record the book midpoint they found on submission
+//! (`orders.quoted_mid`). A user's execution quality over a window compares
+//! each order's volume-weighted fill price with that midpoint (slippage)
+//! and, for limit orders, with the limit price (price improvement), and
+//! counts how often orders of each type filled.
+//!
+//! Busted trades are left out. Fills on the complement book (mint/merge
+//! matches, where the trade is priced in the taker's share) are converted
+//! to the order's own share first.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::models::Address;
+
+/// Decimal places kept on averaged prices and rates
+const DP: u32 = 8;
+
+/// One order of the window with its fills summed
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OrderExecution {
+    pub order_type: String,
+    pub side: String,
+    pub status: String,
+    pub limit_price: Option<Decimal>,
+    pub amount: Decimal,
+    pub filled_amount: Decimal,
+    pub quoted_mid: Option<Decimal>,
+    /// Shares filled by non-busted trades
+    pub fill_amount: Decimal,
+    /// Sum of price * amount over those trades, in the order's share
+    pub fill_notional: Decimal,
+}
+
+impl OrderExecution {
+    fn average_fill_price(&self) -> Option<Decimal> {
+        (self.fill_amount > Decimal::ZERO).then(|| self.fill_notional / self.fill_amount)
+    }
+
+    /// +1 when a higher fill price is worse for the user (buys), -1 otherwise
+    fn adverse_direction(&self) -> Decimal {
+        if self.side == "buy" {
+            Decimal::ONE
+        } else {
+            Decimal::NEGATIVE_ONE
+        }
+    }
+}
+
+/// Fill price against the midpoint at submission
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct SlippageStats {
+    /// Filled orders that saw a two-sided book
+    pub orders: i64,
+    /// Shares filled across them
+    pub filled_amount: Decimal,
+    /// Volume-weighted distance of the fill price from the midpoint;
+    /// positive is worse than mid
+    pub average_slippage: Option<Decimal>,
+    /// The same, in basis points of the midpoint
+    pub average_slippage_bps: Option<Decimal>,
+}
+
+/// Fill price against the limit price of limit orders
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct PriceImprovementStats {
+    /// Filled limit orders
+    pub orders: i64,
+    /// Shares filled across them
+    pub filled_amount: Decimal,
+    /// Volume-weighted distance of the fill price from the limit price;
+    /// positive is better than the limit
+    pub average_improvement: Option<Decimal>,
+}
+
+/// How often orders of one type filled
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FillRateStats {
+    pub order_type: String,
+    pub orders: i64,
+    pub filled: i64,
+    pub partially_filled: i64,
+    pub unfilled: i64,
+    /// Refused orders, left out of the rates
+    pub rejected: i64,
+    /// Share of accepted orders that filled completely
+    pub fill_rate: Option<Decimal>,
+    /// Share of accepted quantity that filled
+    pub amount_fill_rate: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExecutionStats {
+    /// Window start (ms)
+    pub since: i64,
+    pub orders: i64,
+    pub slippage: SlippageStats,
+    pub price_improvement: PriceImprovementStats,
+    pub by_order_type: Vec<FillRateStats>,
+}
+
+/// Execution quality of `user_address`'s orders created since `since`
+pub async fn for_user(
+    pool: &PgPool,
+    user_address: &Address,
+    since: DateTime<Utc>,
+) -> Result<ExecutionStats, sqlx::Error> {
+    let orders: Vec<OrderExecution> = sqlx::query_as(
+        r#"
+        SELECT o.order_type::text AS order_type, o.side::text AS side, o.status::text AS status,
+               o.price AS limit_price, o.amount, o.filled_amount, o.quoted_mid,
+               COALESCE(SUM(t.amount), 0) AS fill_amount,
+               COALESCE(SUM(
+                   CASE WHEN t.share_type = o.share_type
+                        THEN COALESCE(t.adjusted_price, t.price)
+                        ELSE 1 - COALESCE(t.adjusted_price, t.price)
+                   END * t.amount
+               ), 0) AS fill_notional
+        FROM orders o
+        LEFT JOIN trades t
+               ON (t.maker_order_id = o.id OR t.taker_order_id = o.id)
+              AND t.adjustment_status IS DISTINCT FROM 'busted'
+        WHERE o.user_address = $1 AND o.created_at >= $2
+        GROUP BY o.id
+        "#,
+    )
+    .bind(user_address)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(aggregate(&orders, since))
+}
+
+/// Fold per-order executions into window statistics
+pub fn aggregate(orders: &[OrderExecution], since: DateTime<Utc>) -> ExecutionStats {
+    let mut slippage = SlippageStats::default();
+    let mut slippage_sum = Decimal::ZERO;
+    let mut slippage_bps_sum = Decimal::ZERO;
+    let mut improvement = PriceImprovementStats::default();
+    let mut improvement_sum = Decimal::ZERO;
+    let mut by_type: Vec<(FillRateStats, Decimal, Decimal)> = Vec::new();
+
+    for order in orders {
+        let index = match by_type.iter().position(|(s, _, _)| s.order_type == order.order_type) {
+            Some(index) => index,
+            None => {
+                by_type.push((
+                    FillRateStats {
+                        order_type: order.order_type.clone(),
+                        orders: 0,
+                        filled: 0,
+                        partially_filled: 0,
+                        unfilled: 0,
+                        rejected: 0,
+                        fill_rate: None,
+                        amount_fill_rate: None,
+                    },
+                    Decimal::ZERO,
+                    Decimal::ZERO,
+                ));
+                by_type.len() - 1
+            }
+        };
+        let (rates, amount, filled) = &mut by_type[index];
+        rates.orders += 1;
+        if order.status == "rejected" {
+            rates.rejected += 1;
+        } else {
+            *amount += order.amount;
+            *filled += order.filled_amount;
+            if order.filled_amount >= order.amount {
+                rates.filled += 1;
+            } else if order.filled_amount > Decimal::ZERO {
+                rates.partially_filled += 1;
+            } else {
+                rates.unfilled += 1;
+            }
+        }
+
+        let Some(fill_price) = order.average_fill_price() else { continue };
+        let direction = order.adverse_direction();
+        if let Some(mid) = order.quoted_mid.filter(|mid| *mid > Decimal::ZERO) {
+            let slip = (fill_price - mid) * direction;
+            slippage.orders += 1;
+            slippage.filled_amount += order.fill_amount;
+            slippage_sum += slip * order.fill_amount;
+            slippage_bps_sum += slip / mid * Decimal::from(10_000) * order.fill_amount;
+        }
+        if let Some(limit) = order.limit_price.filter(|_| order.order_type == "limit") {
+            improvement.orders += 1;
+            improvement.filled_amount += order.fill_amount;
+            improvement_sum += (limit - fill_price) * direction * order.fill_amount;
+        }
+    }
+
+    if slippage.filled_amount > Decimal::ZERO {
+        slippage.average_slippage = Some((slippage_sum / slippage.filled_amount).round_dp(DP));
+        slippage.average_slippage_bps = Some((slippage_bps_sum / slippage.filled_amount).round_dp(2));
+    }
+    if improvement.filled_amount > Decimal::ZERO {
+        improvement.average_improvement = Some((improvement_sum / improvement.filled_amount).round_dp(DP));
+    }
+
+    let mut by_order_type: Vec<FillRateStats> = by_type
+        .into_iter()
+        .map(|(mut rates, amount, filled)| {
+            let accepted = rates.orders - rates.rejected;
+            if accepted > 0 {
+                rates.fill_rate = Some((Decimal::from(rates.filled) / Decimal::from(accepted)).round_dp(DP));
+            }
+            if amount > Decimal::ZERO {
+                rates.amount_fill_rate = Some((filled / amount).round_dp(DP));
+            }
+            rates
+        })
+        .collect();
+    by_order_type.sort_by(|a, b| a.order_type.cmp(&b.order_type));
+
+    ExecutionStats {
+        since: since.timestamp_millis(),
+        orders: orders.len() as i64,
+        slippage,
+        price_improvement: improvement,
+        by_order_type,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use chrono::Duration;
+
+    use crate::models::market::ShareType;
+    use crate::models::{OrderSide, OrderStatus};
+    use crate::test_support::{account, gateway_order, TestApp, MAKER, TAKER};
+
+    fn execution(order_type: &str, side: &str, limit: Decimal, amount: Decimal, fills: &[(Decimal, Decimal)]) -> OrderExecution {
+        let fill_amount: Decimal = fills.iter().map(|(_, a)| *a).sum();
+        OrderExecution {
+            order_type: order_type.to_string(),
+            side: side.to_string(),
+            status: if fill_amount >= amount { "filled" } else { "open" }.to_string(),
+            limit_price: Some(limit),
+            amount,
+            filled_amount: fill_amount,
+            quoted_mid: Some(dec!(0.50)),
+            fill_amount,
+            fill_notional: fills.iter().map(|(p, a)| p * a).sum(),
+        }
+    }
+
+    #[test]
+    fn test_slippage_and_improvement_are_volume_weighted() {
+        let orders = vec![
+            // Bought 10 at 0.52 against mid 0.50: 2 cents worse, 2 better than the limit
+            execution("limit", "buy", dec!(0.54), dec!(10), &[(dec!(0.52), dec!(10))]),
+            // Sold 30 at 0.49: 1 cent worse than mid, 1 better than the limit
+            execution("limit", "sell", dec!(0.48), dec!(30), &[(dec!(0.49), dec!(30))]),
+        ];
+        let stats = aggregate(&orders, Utc::now());
+
+        assert_eq!(stats.slippage.orders, 2);
+        assert_eq!(stats.slippage.filled_amount, dec!(40));
+        assert_eq!(stats.slippage.average_slippage, Some(dec!(0.0125)));
+        assert_eq!(stats.slippage.average_slippage_bps, Some(dec!(250)));
+        assert_eq!(stats.price_improvement.average_improvement, Some(dec!(0.0125)));
+    }
+
+    #[test]
+    fn test_fill_rates_by_order_type() {
+        let mut rejected = execution("limit", "buy", dec!(0.5), dec!(10), &[]);
+        rejected.status = "rejected".to_string();
+        let mut one_sided = execution("market", "buy", dec!(0.99), dec!(10), &[(dec!(0.6), dec!(10))]);
+        one_sided.quoted_mid = None;
+        let orders = vec![
+            execution("limit", "buy", dec!(0.5), dec!(10), &[(dec!(0.5), dec!(10))]),
+            execution("limit", "buy", dec!(0.5), dec!(10), &[(dec!(0.5), dec!(4))]),
+            execution("limit", "buy", dec!(0.5), dec!(10), &[]),
+            rejected,
+            one_sided,
+        ];
+        let stats = aggregate(&orders, Utc::now());
+
+        assert_eq!(stats.orders, 5);
+        let limit = &stats.by_order_type[0];
+        assert_eq!(limit.order_type, "limit");
+        assert_eq!(
+            (limit.orders, limit.filled, limit.partially_filled, limit.unfilled, limit.rejected),
+            (4, 1, 1, 1, 1)
+        );
+        assert_eq!(limit.fill_rate, Some(dec!(0.33333333)));
+        assert_eq!(limit.amount_fill_rate, Some(dec!(0.46666667)));
+        let market = &stats.by_order_type[1];
+        assert_eq!((market.orders, market.fill_rate), (1, Some(dec!(1))));
+
+        // Orders without a two-sided book count toward fill rates only;
+        // market orders have no limit to improve on
+        assert_eq!(stats.slippage.orders, 2);
+        assert_eq!(stats.price_improvement.orders, 2);
+    }
+
+    #[tokio::test]
+    async fn test_execution_stats() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.grant_shares(MAKER, market_id, yes, ShareType::Yes, dec!(10)).await;
+        app.deposit(TAKER, dec!(100)).await;
+
+        let gateway = &app.state.order_gateway;
+        gateway.place(gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.4))).await.unwrap();
+        gateway.place(gateway_order(market_id, yes, MAKER, OrderSide::Sell, dec!(0.5))).await.unwrap();
+        // Quoted mid 0.45, filled at 0.5 against a 0.6 limit
+        let taking = gateway.place(gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.6))).await.unwrap();
+        assert_eq!(taking.status, OrderStatus::Filled);
+
+        let stats = for_user(&app.db.pool, &account(TAKER), Utc::now() - Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(stats.orders, 2);
+        assert_eq!((stats.slippage.orders, stats.slippage.average_slippage), (1, Some(dec!(0.05))));
+        assert_eq!(stats.price_improvement.average_improvement, Some(dec!(0.1)));
+        let limit = &stats.by_order_type[0];
+        assert_eq!((limit.filled, limit.unfilled, limit.fill_rate), (1, 1, Some(dec!(0.5))));
+    }
+}
@@ -0,0 +1,509 @@
+//! Public Data Exporter
+//!
+//! Writes daily public datasets to S3-compatible object storage so
+//! researchers and analytics never need to query production Postgres:
+//!
+//! - `trades` — every trade executed that day (no user addresses)
+//! - `orderbook_snapshots` — top levels of every active book at export time
+//!   (read from the live engine, or rebuilt from open orders when the
+//!   exporter runs without one, e.g. in the keeper role)
+//! - `resolutions` — markets resolved or cancelled that day
+//!
+//! Objects are laid out as `{prefix}/{dataset}/date={YYYY-MM-DD}/{dataset}.{ext}`
+//! and recorded in `data_exports`, which backs the public manifest endpoint.
+//! The scheduler exports the previous UTC day once `export_hour_utc` has
+//! passed; re-running a date overwrites its objects.
+
+mod dataset;
+
+use dataset::{ColumnKind, Dataset};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, PutPayload};
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::services::leader_election::LeaderElection;
+use crate::services::matching::{restore_orders, MatchingEngine};
+
+/// Orderbook levels per side included in snapshots
+const SNAPSHOT_DEPTH: usize = 50;
+
+/// Export errors
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("Export storage not configured")]
+    NotConfigured,
+    #[error("Storage error: {0}")]
+    Storage(#[from] object_store::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Encoding error: {0}")]
+    Encoding(String),
+    #[error("Orderbook rebuild failed: {0}")]
+    Orderbook(String),
+}
+
+/// Output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Parquet,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    /// Parse `EXPORT_FORMAT` ("parquet", "csv" or "both")
+    pub fn from_setting(s: &str) -> Vec<ExportFormat> {
+        match s.to_lowercase().as_str() {
+            "csv" => vec![ExportFormat::Csv],
+            "both" => vec![ExportFormat::Parquet, ExportFormat::Csv],
+            _ => vec![ExportFormat::Parquet],
+        }
+    }
+}
+
+/// Result of exporting one dataset in one format
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ExportedObject {
+    pub dataset: String,
+    pub format: String,
+    pub object_key: String,
+    pub row_count: i64,
+    pub size_bytes: i64,
+}
+
+/// Object key for a dataset export
+pub fn object_key(prefix: &str, dataset: &str, date: NaiveDate, format: ExportFormat) -> String {
+    let prefix = prefix.trim_matches('/');
+    let file = format!("{}/date={}/{}.{}", dataset, date, dataset, format.extension());
+    if prefix.is_empty() {
+        file
+    } else {
+        format!("{}/{}", prefix, file)
+    }
+}
+
+fn encode(dataset: &Dataset, format: ExportFormat) -> Result<Vec<u8>, ExportError> {
+    match format {
+        ExportFormat::Parquet => dataset.to_parquet().map_err(|e| ExportError::Encoding(e.to_string())),
+        ExportFormat::Csv => dataset.to_csv().map_err(|e| ExportError::Encoding(e.to_string())),
+    }
+}
+
+/// (id, market_id, outcome_id, share_type, side, match_type, price, amount, created_at)
+type TradeExportRow = (Uuid, Option<Uuid>, Option<Uuid>, Option<String>, String, Option<String>, Decimal, Decimal, DateTime<Utc>);
+
+/// (market_id, question, category, status, winning_outcome_id, winning_share_type, resolved_at, resolution_tx_hash)
+type ResolutionExportRow = (Uuid, String, String, String, Option<Uuid>, Option<String>, Option<DateTime<Utc>>, Option<String>);
+
+/// Daily dataset exporter
+pub struct DataExporter {
+    pool: PgPool,
+    /// Live engine; `None` rebuilds books from open orders per snapshot
+    engine: Option<Arc<MatchingEngine>>,
+    store: Option<Arc<dyn ObjectStore>>,
+    prefix: String,
+    formats: Vec<ExportFormat>,
+    export_hour_utc: u32,
+}
+
+impl DataExporter {
+    pub fn new(pool: PgPool, engine: Option<Arc<MatchingEngine>>, config: &AppConfig) -> Self {
+        Self {
+            pool,
+            engine,
+            store: Self::build_store(config),
+            prefix: config.export_prefix.clone(),
+            formats: ExportFormat::from_setting(&config.export_format),
+            export_hour_utc: config.export_hour_utc.min(23),
+        }
+    }
+
+    fn build_store(config: &AppConfig) -> Option<Arc<dyn ObjectStore>> {
+        if let Some(bucket) = &config.export_bucket {
+            let mut builder = AmazonS3Builder::new()
+                .with_bucket_name(bucket)
+                .with_region(&config.export_region);
+            if let Some(endpoint) = &config.export_endpoint {
+                builder = builder
+                    .with_endpoint(endpoint)
+                    .with_allow_http(endpoint.starts_with("http://"))
+                    .with_virtual_hosted_style_request(false);
+            }
+            if let (Some(key), Some(secret)) = (&config.export_access_key_id, &config.export_secret_access_key) {
+                builder = builder.with_access_key_id(key).with_secret_access_key(secret);
+            }
+            return match builder.build() {
+                Ok(store) => Some(Arc::new(store)),
+                Err(e) => {
+                    tracing::error!("Failed to configure S3 export store: {}", e);
+                    None
+                }
+            };
+        }
+
+        if let Some(dir) = &config.export_local_dir {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                tracing::error!("Failed to create export directory {}: {}", dir, e);
+                return None;
+            }
+            return match LocalFileSystem::new_with_prefix(dir) {
+                Ok(store) => Some(Arc::new(store)),
+                Err(e) => {
+                    tracing::error!("Failed to configure local export store: {}", e);
+                    None
+                }
+            };
+        }
+
+        None
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Spawn the daily export scheduler; only the elected leader exports
+    pub fn start_scheduler(self: Arc<Self>, leader: Arc<LeaderElection>) {
+        if !self.is_configured() {
+            tracing::warn!("Data export enabled but no EXPORT_BUCKET / EXPORT_LOCAL_DIR configured");
+            return;
+        }
+
+        tokio::spawn(async move {
+            tracing::info!("Data export scheduler started (after {:02}:00 UTC)", self.export_hour_utc);
+            let mut interval = tokio::time::interval(Duration::from_secs(600));
+            loop {
+                interval.tick().await;
+                if !leader.is_leader() {
+                    continue;
+                }
+                let now = Utc::now();
+                if now.hour() < self.export_hour_utc {
+                    continue;
+                }
+                let Some(date) = now.date_naive().pred_opt() else {
+                    continue;
+                };
+                match self.is_exported(date).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        if let Err(e) = self.export_date(date).await {
+                            tracing::error!("Daily export for {} failed: {}", date, e);
+                        }
+                    }
+                    Err(e) => tracing::error!("Failed to check export status for {}: {}", date, e),
+                }
+            }
+        });
+    }
+
+    async fn is_exported(&self, date: NaiveDate) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM data_exports WHERE export_date = $1 AND status = 'completed'",
+        )
+        .bind(date)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count as usize >= 3 * self.formats.len())
+    }
+
+    /// Export every dataset for `date` (UTC day)
+    pub async fn export_date(&self, date: NaiveDate) -> Result<Vec<ExportedObject>, ExportError> {
+        let store = self.store.clone().ok_or(ExportError::NotConfigured)?;
+
+        let datasets = [
+            self.trades(date).await?,
+            self.orderbook_snapshots().await?,
+            self.resolutions(date).await?,
+        ];
+
+        let mut exported = Vec::new();
+        for dataset in &datasets {
+            if dataset.is_empty() {
+                tracing::debug!("Dataset {} has no rows for {}; writing header-only file", dataset.name, date);
+            }
+            for format in &self.formats {
+                let key = object_key(&self.prefix, dataset.name, date, *format);
+                match self.write(&store, dataset, *format, &key).await {
+                    Ok((size, sha)) => {
+                        self.record(date, dataset, *format, &key, size, Some(&sha), "completed", None)
+                            .await?;
+                        exported.push(ExportedObject {
+                            dataset: dataset.name.to_string(),
+                            format: format.extension().to_string(),
+                            object_key: key,
+                            row_count: dataset.len() as i64,
+                            size_bytes: size,
+                        });
+                    }
+                    Err(e) => {
+                        tracing::error!("Export of {} ({}) for {} failed: {}", dataset.name, format.extension(), date, e);
+                        self.record(date, dataset, *format, &key, 0, None, "failed", Some(&e.to_string()))
+                            .await?;
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        tracing::info!("Exported {} objects for {}", exported.len(), date);
+        Ok(exported)
+    }
+
+    /// Encode the `trades` dataset for `date` without uploading it.
+    /// Returns the encoded bytes and the row count.
+    pub async fn encode_trades(&self, date: NaiveDate, format: ExportFormat) -> Result<(Vec<u8>, usize), ExportError> {
+        let dataset = self.trades(date).await?;
+        Ok((encode(&dataset, format)?, dataset.len()))
+    }
+
+    async fn write(
+        &self,
+        store: &Arc<dyn ObjectStore>,
+        dataset: &Dataset,
+        format: ExportFormat,
+        key: &str,
+    ) -> Result<(i64, String), ExportError> {
+        let bytes = encode(dataset, format)?;
+        let size = bytes.len() as i64;
+        let sha = hex::encode(Sha256::digest(&bytes));
+        store.put(&ObjectPath::from(key), PutPayload::from(bytes)).await?;
+        Ok((size, sha))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn record(
+        &self,
+        date: NaiveDate,
+        dataset: &Dataset,
+        format: ExportFormat,
+        key: &str,
+        size: i64,
+        sha256: Option<&str>,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO data_exports (export_date, dataset, format, object_key, row_count, size_bytes, sha256, status, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (export_date, dataset, format) DO UPDATE SET
+                object_key = EXCLUDED.object_key,
+                row_count = EXCLUDED.row_count,
+                size_bytes = EXCLUDED.size_bytes,
+                sha256 = EXCLUDED.sha256,
+                status = EXCLUDED.status,
+                error = EXCLUDED.error,
+                created_at = NOW()
+            "#,
+        )
+        .bind(date)
+        .bind(dataset.name)
+        .bind(format.extension())
+        .bind(key)
+        .bind(dataset.len() as i64)
+        .bind(size)
+        .bind(sha256)
+        .bind(status)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // ========================================================================
+    // Datasets
+    // ========================================================================
+
+    async fn trades(&self, date: NaiveDate) -> Result<Dataset, sqlx::Error> {
+        let rows: Vec<TradeExportRow> = sqlx::query_as(
+            r#"
+            SELECT id, market_id, outcome_id, share_type::text, side::text, match_type::text,
+                   price, amount, created_at
+            FROM trades
+            WHERE created_at >= $1::date AND created_at < $1::date + INTERVAL '1 day'
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut ds = Dataset::new(
+            "trades",
+            &[
+                ("trade_id", ColumnKind::Utf8),
+                ("market_id", ColumnKind::Utf8),
+                ("outcome_id", ColumnKind::Utf8),
+                ("share_type", ColumnKind::Utf8),
+                ("side", ColumnKind::Utf8),
+                ("match_type", ColumnKind::Utf8),
+                ("price", ColumnKind::Utf8),
+                ("amount", ColumnKind::Utf8),
+                ("timestamp_ms", ColumnKind::Int64),
+            ],
+        );
+        for (id, market_id, outcome_id, share_type, side, match_type, price, amount, created_at) in rows {
+            ds.push(vec![
+                id.to_string().into(),
+                market_id.map(|v| v.to_string()).into(),
+                outcome_id.map(|v| v.to_string()).into(),
+                share_type.into(),
+                side.into(),
+                match_type.into(),
+                price.normalize().to_string().into(),
+                amount.normalize().to_string().into(),
+                created_at.timestamp_millis().into(),
+            ]);
+        }
+        Ok(ds)
+    }
+
+    async fn orderbook_snapshots(&self) -> Result<Dataset, ExportError> {
+        let engine = match &self.engine {
+            Some(engine) => engine.clone(),
+            None => {
+                let engine = Arc::new(MatchingEngine::new());
+                restore_orders(&engine, &self.pool)
+                    .await
+                    .map_err(|e| ExportError::Orderbook(e.to_string()))?;
+                engine
+            }
+        };
+
+        let books: Vec<(Uuid, Uuid, String)> = sqlx::query_as(
+            r#"
+            SELECT o.market_id, o.id, o.share_type::text
+            FROM outcomes o
+            JOIN markets m ON m.id = o.market_id
+            WHERE m.status = 'active'
+            ORDER BY o.market_id, o.id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let snapshot_time = Utc::now().timestamp_millis();
+        let mut ds = Dataset::new(
+            "orderbook_snapshots",
+            &[
+                ("snapshot_ms", ColumnKind::Int64),
+                ("market_id", ColumnKind::Utf8),
+                ("outcome_id", ColumnKind::Utf8),
+                ("share_type", ColumnKind::Utf8),
+                ("side", ColumnKind::Utf8),
+                ("level", ColumnKind::Int64),
+                ("price", ColumnKind::Utf8),
+                ("amount", ColumnKind::Utf8),
+            ],
+        );
+
+        for (market_id, outcome_id, share_type) in books {
+            let symbol = format!("{}:{}:{}", market_id, outcome_id, share_type);
+            let Ok(book) = engine.get_orderbook(&symbol, SNAPSHOT_DEPTH) else {
+                continue;
+            };
+            for (side, levels) in [("bid", &book.bids), ("ask", &book.asks)] {
+                for (level, [price, amount]) in levels.iter().enumerate() {
+                    ds.push(vec![
+                        snapshot_time.into(),
+                        market_id.to_string().into(),
+                        outcome_id.to_string().into(),
+                        share_type.clone().into(),
+                        side.to_string().into(),
+                        (level as i64 + 1).into(),
+                        price.clone().into(),
+                        amount.clone().into(),
+                    ]);
+                }
+            }
+        }
+        Ok(ds)
+    }
+
+    async fn resolutions(&self, date: NaiveDate) -> Result<Dataset, sqlx::Error> {
+        let rows: Vec<ResolutionExportRow> = sqlx::query_as(
+            r#"
+            SELECT m.id, m.question, m.category, m.status::text, m.winning_outcome_id,
+                   o.share_type::text, m.resolved_at, m.resolution_tx_hash
+            FROM markets m
+            LEFT JOIN outcomes o ON o.id = m.winning_outcome_id
+            WHERE m.status IN ('resolved', 'cancelled')
+              AND m.resolved_at >= $1::date AND m.resolved_at < $1::date + INTERVAL '1 day'
+            ORDER BY m.resolved_at, m.id
+            "#,
+        )
+        .bind(date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut ds = Dataset::new(
+            "resolutions",
+            &[
+                ("market_id", ColumnKind::Utf8),
+                ("question", ColumnKind::Utf8),
+                ("category", ColumnKind::Utf8),
+                ("status", ColumnKind::Utf8),
+                ("winning_outcome_id", ColumnKind::Utf8),
+                ("winning_share_type", ColumnKind::Utf8),
+                ("resolved_at_ms", ColumnKind::Int64),
+                ("resolution_tx_hash", ColumnKind::Utf8),
+            ],
+        );
+        for (market_id, question, category, status, winning_outcome_id, winning_share_type, resolved_at, tx_hash) in rows {
+            ds.push(vec![
+                market_id.to_string().into(),
+                question.into(),
+                category.into(),
+                status.into(),
+                winning_outcome_id.map(|v| v.to_string()).into(),
+                winning_share_type.into(),
+                resolved_at.map(|t| t.timestamp_millis()).into(),
+                tx_hash.into(),
+            ]);
+        }
+        Ok(ds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_key_layout() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 9).unwrap();
+        assert_eq!(
+            object_key("exports/", "trades", date, ExportFormat::Parquet),
+            "exports/trades/date=2026-01-09/trades.parquet"
+        );
+        assert_eq!(
+            object_key("", "resolutions", date, ExportFormat::Csv),
+            "resolutions/date=2026-01-09/resolutions.csv"
+        );
+    }
+
+    #[test]
+    fn test_format_setting() {
+        assert_eq!(ExportFormat::from_setting("both").len(), 2);
+        assert_eq!(ExportFormat::from_setting("CSV"), vec![ExportFormat::Csv]);
+        assert_eq!(ExportFormat::from_setting("unknown"), vec![ExportFormat::Parquet]);
+    }
+}
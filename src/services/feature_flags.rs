@@ -0,0 +1,243 @@
+//! Feature Flags
+//!
+//! Runtime switches for risky features (Mint/Merge matching, automated
+//! market making, new order types). Flags live in `feature_flags`; the full
+//! set is cached in Redis so replicas don't query Postgres on every refresh,
+//! and each replica evaluates against an in-process snapshot refreshed every
+//! few seconds, so checks from handlers and the matching engine never block.
+//!
+//! A flag is on for a user when it is enabled, the current environment is
+//! listed (or none are), and the user is allow-listed or falls inside the
+//! rollout percentage. Checks without a user only pass at 100% rollout.
+//! Flags without a row fall back to the defaults in [`KNOWN_FLAGS`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::cache::keys::{ttl, CacheKey};
+use crate::cache::CacheManager;
+use crate::services::matching::MatchingEngine;
+
+/// Mint/Merge matching against the complement orderbook
+pub const COMPLEMENT_MATCHING: &str = "complement_matching";
+/// Market (non-limit) orders
+pub const MARKET_ORDERS: &str = "market_orders";
+/// Automated market making
+pub const AUTO_MM: &str = "auto_mm";
+/// Converting complete No sets of negative-risk groups into collateral
+pub const NEG_RISK_CONVERSION: &str = "neg_risk_conversion";
+/// Paper trading against simulated orderbooks with virtual balances
+pub const PAPER_TRADING: &str = "paper_trading";
+
+/// (key, default when no row exists, description)
+pub const KNOWN_FLAGS: [(&str, bool, &str); 5] = [
+    (COMPLEMENT_MATCHING, true, "Mint/Merge matching against the complement orderbook"),
+    (MARKET_ORDERS, true, "Market (non-limit) order type"),
+    (AUTO_MM, false, "Automated market making"),
+    (NEG_RISK_CONVERSION, false, "Convert complete No sets of negative-risk groups into collateral"),
+    (PAPER_TRADING, true, "Paper trading with virtual balances in simulated orderbooks"),
+];
+
+/// How often each replica refreshes its snapshot
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Columns selected into [`FeatureFlag`]
+pub const FLAG_COLUMNS: &str =
+    "key, description, enabled, environments, rollout_percentage, allowed_users, updated_by, updated_at";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FeatureFlag {
+    pub key: String,
+    pub description: String,
+    pub enabled: bool,
+    /// Empty = all environments
+    pub environments: Vec<String>,
+    pub rollout_percentage: i16,
+    /// Lowercased addresses
+    pub allowed_users: Vec<String>,
+    pub updated_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FeatureFlag {
+    /// Evaluate the flag for `user` in `environment`
+    pub fn evaluate(&self, environment: &str, user: Option<&str>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if !self.environments.is_empty() && !self.environments.iter().any(|e| e.eq_ignore_ascii_case(environment)) {
+            return false;
+        }
+        if self.rollout_percentage >= 100 {
+            return true;
+        }
+        match user {
+            Some(user) => {
+                let user = user.to_lowercase();
+                self.allowed_users.contains(&user) || i16::from(rollout_bucket(&self.key, &user)) < self.rollout_percentage
+            }
+            None => false,
+        }
+    }
+}
+
+/// Stable 0-99 bucket for a user, salted by flag key so cohorts differ per flag
+fn rollout_bucket(key: &str, user: &str) -> u8 {
+    let digest = Sha256::digest(format!("{}:{}", key, user).as_bytes());
+    (u16::from_be_bytes([digest[0], digest[1]]) % 100) as u8
+}
+
+fn default_enabled(key: &str) -> bool {
+    KNOWN_FLAGS.iter().any(|(k, default, _)| *k == key && *default)
+}
+
+/// DB-backed feature flags with Redis and in-process caching
+pub struct FeatureFlagService {
+    pool: PgPool,
+    cache: Arc<CacheManager>,
+    environment: String,
+    engine: Arc<MatchingEngine>,
+    flags: RwLock<HashMap<String, FeatureFlag>>,
+}
+
+impl FeatureFlagService {
+    pub fn new(pool: PgPool, cache: Arc<CacheManager>, environment: &str, engine: Arc<MatchingEngine>) -> Self {
+        Self {
+            pool,
+            cache,
+            environment: environment.to_string(),
+            engine,
+            flags: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `key` is on for `user` (or globally when `None`)
+    pub fn is_enabled(&self, key: &str, user: Option<&str>) -> bool {
+        match self.flags.read().get(key) {
+            Some(flag) => flag.evaluate(&self.environment, user),
+            None => default_enabled(key),
+        }
+    }
+
+    /// Evaluate every known and stored flag for `user`
+    pub fn evaluate_all(&self, user: Option<&str>) -> BTreeMap<String, bool> {
+        let mut keys: Vec<String> = KNOWN_FLAGS.iter().map(|(k, _, _)| k.to_string()).collect();
+        keys.extend(self.flags.read().keys().cloned());
+        keys.into_iter()
+            .map(|key| {
+                let enabled = self.is_enabled(&key, user);
+                (key, enabled)
+            })
+            .collect()
+    }
+
+    /// Load the initial snapshot and spawn the refresh loop
+    pub async fn start(self: Arc<Self>) {
+        if let Err(e) = self.refresh().await {
+            tracing::warn!("Failed to load feature flags, using defaults: {}", e);
+        }
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.refresh().await {
+                    tracing::warn!("Feature flag refresh failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Refresh the snapshot from Redis, falling back to Postgres
+    pub async fn refresh(&self) -> Result<(), sqlx::Error> {
+        if let Some(flags) = self.read_cached().await {
+            self.store(flags);
+            return Ok(());
+        }
+        self.reload().await
+    }
+
+    /// Reload from Postgres and repopulate Redis; call after writes so the
+    /// change is visible immediately on this replica.
+    pub async fn reload(&self) -> Result<(), sqlx::Error> {
+        let flags: Vec<FeatureFlag> = sqlx::query_as(&format!("SELECT {} FROM feature_flags", FLAG_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+
+        if let Some(redis) = self.cache.redis() {
+            match serde_json::to_string(&flags) {
+                Ok(json) => {
+                    if let Err(e) = redis.set_ex(&CacheKey::feature_flags(), json, ttl::FEATURE_FLAGS).await {
+                        tracing::debug!("Failed to cache feature flags: {}", e);
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to serialize feature flags: {}", e),
+            }
+        }
+
+        self.store(flags);
+        Ok(())
+    }
+
+    async fn read_cached(&self) -> Option<Vec<FeatureFlag>> {
+        let redis = self.cache.redis()?;
+        let json: String = redis.get(&CacheKey::feature_flags()).await.ok()??;
+        serde_json::from_str(&json).ok()
+    }
+
+    fn store(&self, flags: Vec<FeatureFlag>) {
+        *self.flags.write() = flags.into_iter().map(|f| (f.key.clone(), f)).collect();
+        self.engine
+            .set_complement_matching(self.is_enabled(COMPLEMENT_MATCHING, None));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(enabled: bool, environments: &[&str], rollout: i16, allowed: &[&str]) -> FeatureFlag {
+        FeatureFlag {
+            key: "test_flag".to_string(),
+            description: String::new(),
+            enabled,
+            environments: environments.iter().map(|s| s.to_string()).collect(),
+            rollout_percentage: rollout,
+            allowed_users: allowed.iter().map(|s| s.to_string()).collect(),
+            updated_by: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_evaluate_kill_switch_and_environment() {
+        assert!(!flag(false, &[], 100, &[]).evaluate("production", Some("0xabc")));
+        assert!(flag(true, &[], 100, &[]).evaluate("production", None));
+        assert!(!flag(true, &["staging"], 100, &[]).evaluate("production", None));
+        assert!(flag(true, &["Staging"], 100, &[]).evaluate("staging", None));
+    }
+
+    #[test]
+    fn test_evaluate_cohorts() {
+        // Allow-listed users are on regardless of rollout
+        let allow = flag(true, &[], 0, &["0xabc"]);
+        assert!(allow.evaluate("production", Some("0xABC")));
+        assert!(!allow.evaluate("production", Some("0xdef")));
+        assert!(!allow.evaluate("production", None));
+
+        // Rollout buckets are stable and roughly proportional
+        let half = flag(true, &[], 50, &[]);
+        let on = (0..1000)
+            .filter(|i| half.evaluate("production", Some(&format!("0x{:040x}", i))))
+            .count();
+        assert!((400..600).contains(&on), "{} of 1000 users enabled", on);
+        assert_eq!(rollout_bucket("a", "0x1"), rollout_bucket("a", "0x1"));
+    }
+}
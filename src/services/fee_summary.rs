@@ -0,0 +1,124 @@
+//! Realized Fee Totals
+//!
+//! What a user has paid in trading fees and earned back since a point in
+//! time, typically the start of the month in their own timezone. Negative
+//! maker fees are rebates, as are referral commissions earned on the
+//! trading of referred users. Busted trades count for nothing.
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::models::Address;
+
+/// Fees and rebates since `since`
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FeeTotals {
+    pub since: DateTime<Utc>,
+    pub maker_fees: Decimal,
+    pub taker_fees: Decimal,
+    /// Maker fee rebates (negative maker fees)
+    pub maker_rebates: Decimal,
+    /// Referral commission earned
+    pub referral_commission: Decimal,
+    /// Fills the totals cover
+    pub trade_count: i64,
+}
+
+impl FeeTotals {
+    pub fn fees_paid(&self) -> Decimal {
+        self.maker_fees + self.taker_fees
+    }
+
+    pub fn rebates_earned(&self) -> Decimal {
+        self.maker_rebates + self.referral_commission
+    }
+}
+
+/// Midnight on the first of `now`'s month in `tz`
+pub fn month_start(tz: Tz, now: DateTime<Utc>) -> DateTime<Utc> {
+    let local = now.with_timezone(&tz);
+    tz.with_ymd_and_hms(local.year(), local.month(), 1, 0, 0, 0)
+        .earliest()
+        .map(|start| start.with_timezone(&Utc))
+        // Midnight skipped by a DST change; fall back to UTC midnight
+        .unwrap_or_else(|| Utc.with_ymd_and_hms(local.year(), local.month(), 1, 0, 0, 0).unwrap())
+}
+
+/// A user's fee totals since `since`
+pub async fn totals(pool: &PgPool, user_address: &Address, since: DateTime<Utc>) -> Result<FeeTotals, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT $2::timestamptz AS since,
+               COALESCE(SUM(GREATEST(f.fee, 0)) FILTER (WHERE f.is_maker), 0) AS maker_fees,
+               COALESCE(SUM(GREATEST(f.fee, 0)) FILTER (WHERE NOT f.is_maker), 0) AS taker_fees,
+               COALESCE(SUM(-LEAST(f.fee, 0)), 0) AS maker_rebates,
+               (
+                   SELECT COALESCE(SUM(commission), 0) FROM referral_earnings
+                   WHERE referrer_address = $1 AND created_at >= $2
+               ) AS referral_commission,
+               COUNT(f.fee) AS trade_count
+        FROM trades t
+        CROSS JOIN LATERAL (
+            VALUES (TRUE, t.maker_address, t.maker_fee), (FALSE, t.taker_address, t.taker_fee)
+        ) f(is_maker, address, fee)
+        WHERE f.address = $1
+          AND t.created_at >= $2
+          AND t.adjustment_status IS DISTINCT FROM 'busted'
+        "#,
+    )
+    .bind(user_address)
+    .bind(since)
+    .fetch_one(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use rust_decimal_macros::dec;
+
+    use crate::test_support::{account, TestApp, MAKER, TAKER};
+
+    #[test]
+    fn test_month_start_follows_timezone() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 2, 0, 0).unwrap();
+        assert_eq!(month_start(chrono_tz::UTC, now), Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap());
+        // Still February in New York (UTC-5)
+        assert_eq!(
+            month_start(chrono_tz::America::New_York, now),
+            Utc.with_ymd_and_hms(2026, 2, 1, 5, 0, 0).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fee_totals() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+        let pool = &app.state.db.pool;
+        let since = Utc::now() - Duration::hours(1);
+
+        let taker = totals(pool, &account(TAKER), since).await.unwrap();
+        assert_eq!((taker.taker_fees, taker.maker_fees, taker.trade_count), (trade.taker_fee, dec!(0), 1));
+        assert_eq!(taker.fees_paid(), trade.taker_fee);
+
+        // Commission on a referred user's trade is a rebate
+        sqlx::query(
+            "INSERT INTO referral_earnings (referrer_address, referee_address, trade_id, volume, commission)
+             VALUES ($1, $2, $3, 5, 0.01)",
+        )
+        .bind(MAKER)
+        .bind(TAKER)
+        .bind(trade.trade_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        let maker = totals(pool, &account(MAKER), since).await.unwrap();
+        assert_eq!((maker.maker_fees, maker.trade_count), (trade.maker_fee.max(dec!(0)), 1));
+        assert_eq!((maker.referral_commission, maker.rebates_earned()), (dec!(0.01), dec!(0.01) - trade.maker_fee.min(dec!(0))));
+        assert_eq!(totals(pool, &account(MAKER), Utc::now()).await.unwrap().trade_count, 0);
+    }
+}
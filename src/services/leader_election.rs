@@ -0,0 +1,145 @@
+//! Leader Election
+//!
+//! Singleton background work (chain event scanning / deposit crediting,
+//! analytics aggregation, scheduled exports) must run on exactly one replica.
+//! Replicas compete for a Postgres session-level advisory lock held on a
+//! dedicated connection outside the pool; whoever holds it is the leader.
+//! If the leader dies its session ends, Postgres releases the lock and
+//! another replica acquires it on its next attempt.
+//!
+//! Queue workers (webhooks, notifications, channel gateway) already claim
+//! rows with `FOR UPDATE SKIP LOCKED` and run on every replica.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, PgConnection};
+use tokio::sync::watch;
+
+/// Interval between lock attempts while following
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Interval between liveness checks of the lock connection while leading
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Advisory lock key derived from the election name
+pub fn lock_key(name: &str) -> i64 {
+    let digest = Sha256::digest(name.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    i64::from_be_bytes(bytes)
+}
+
+/// Postgres advisory-lock leader election
+pub struct LeaderElection {
+    name: String,
+    database_url: String,
+    key: i64,
+    enabled: bool,
+    status: watch::Sender<bool>,
+}
+
+impl LeaderElection {
+    /// When `enabled` is false this instance always considers itself leader
+    /// (single-replica deployments).
+    pub fn new(name: &str, database_url: &str, enabled: bool) -> Self {
+        let (status, _) = watch::channel(!enabled);
+        Self {
+            name: name.to_string(),
+            database_url: database_url.to_string(),
+            key: lock_key(name),
+            enabled,
+            status,
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        *self.status.borrow()
+    }
+
+    /// Resolve once this instance holds leadership
+    pub async fn wait_for_leadership(&self) {
+        let mut rx = self.status.subscribe();
+        // The sender lives as long as `self`, so this only errors on drop
+        let _ = rx.wait_for(|leader| *leader).await;
+    }
+
+    fn set_leader(&self, leader: bool) {
+        self.status.send_if_modified(|current| {
+            if *current == leader {
+                return false;
+            }
+            *current = leader;
+            true
+        });
+        crate::metrics::set_leader(&self.name, leader);
+    }
+
+    /// Spawn the election loop
+    pub fn start(self: Arc<Self>) {
+        if !self.enabled {
+            tracing::info!("Leader election '{}' disabled; running as sole instance", self.name);
+            crate::metrics::set_leader(&self.name, true);
+            return;
+        }
+
+        tokio::spawn(async move {
+            tracing::info!("Leader election '{}' started (lock key {})", self.name, self.key);
+            loop {
+                match self.campaign().await {
+                    Ok(()) => {}
+                    Err(e) => tracing::warn!("Leader election '{}' connection error: {}", self.name, e),
+                }
+                if self.is_leader() {
+                    tracing::warn!("Lost leadership for '{}'", self.name);
+                    self.set_leader(false);
+                }
+                tokio::time::sleep(RETRY_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Hold one connection, try to take the lock and keep it alive. Returns
+    /// when the connection fails; dropping it releases any held lock.
+    async fn campaign(&self) -> Result<(), sqlx::Error> {
+        let mut conn = PgConnection::connect(&self.database_url).await?;
+
+        loop {
+            if self.is_leader() {
+                sqlx::query("SELECT 1").execute(&mut conn).await?;
+                tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+                continue;
+            }
+
+            let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+                .bind(self.key)
+                .fetch_one(&mut conn)
+                .await?;
+            if acquired {
+                tracing::info!("Acquired leadership for '{}'", self.name);
+                self.set_leader(true);
+            } else {
+                tokio::time::sleep(RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_key_is_stable_per_name() {
+        assert_eq!(lock_key("background-workers"), lock_key("background-workers"));
+        assert_ne!(lock_key("background-workers"), lock_key("other"));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_election_is_always_leader() {
+        let election = LeaderElection::new("test", "postgres://unused", false);
+        assert!(election.is_leader());
+        election.wait_for_leadership().await;
+    }
+}
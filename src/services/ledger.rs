@@ -0,0 +1,99 @@
+//! Balance Ledger
+//!
+//! Append-only record of available-balance movements. Callers write ledger
+//! entries inside the same database transaction that mutates `balances`, so
+//! the ledger never drifts from the balance table.
+
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::services::matching::precision::Collateral;
+
+/// Kind of balance movement recorded in the ledger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerEntryType {
+    TransferIn,
+    TransferOut,
+    Withdrawal,
+    WithdrawalFee,
+    WithdrawalReversal,
+    NegRiskConversion,
+    TradeBust,
+    TradeAdjustment,
+    SandboxFunding,
+    PairMerge,
+}
+
+impl LedgerEntryType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerEntryType::TransferIn => "transfer_in",
+            LedgerEntryType::TransferOut => "transfer_out",
+            LedgerEntryType::Withdrawal => "withdrawal",
+            LedgerEntryType::WithdrawalFee => "withdrawal_fee",
+            LedgerEntryType::WithdrawalReversal => "withdrawal_reversal",
+            LedgerEntryType::NegRiskConversion => "neg_risk_conversion",
+            LedgerEntryType::TradeBust => "trade_bust",
+            LedgerEntryType::TradeAdjustment => "trade_adjustment",
+            LedgerEntryType::SandboxFunding => "sandbox_funding",
+            LedgerEntryType::PairMerge => "pair_merge",
+        }
+    }
+}
+
+impl std::fmt::Display for LedgerEntryType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single ledger entry to be written
+#[derive(Debug, Clone)]
+pub struct LedgerEntry<'a> {
+    pub user_address: &'a str,
+    pub token: &'a str,
+    pub entry_type: LedgerEntryType,
+    /// Signed amount: positive = credit, negative = debit
+    pub amount: Decimal,
+    /// Available balance after the movement
+    pub balance_after: Decimal,
+    pub reference_id: Option<Uuid>,
+    pub counterparty: Option<&'a str>,
+}
+
+/// Insert a ledger entry using the caller's connection/transaction.
+/// Amounts are stored at collateral precision.
+pub async fn record_entry(conn: &mut PgConnection, entry: &LedgerEntry<'_>) -> Result<Uuid, sqlx::Error> {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO balance_ledger
+            (id, user_address, token, entry_type, amount, balance_after, reference_id, counterparty, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+        "#,
+    )
+    .bind(id)
+    .bind(entry.user_address)
+    .bind(entry.token)
+    .bind(entry.entry_type.as_str())
+    .bind(Collateral::new(entry.amount).value())
+    .bind(Collateral::new(entry.balance_after).value())
+    .bind(entry.reference_id)
+    .bind(entry.counterparty)
+    .execute(conn)
+    .await?;
+
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_type_str() {
+        assert_eq!(LedgerEntryType::TransferIn.as_str(), "transfer_in");
+        assert_eq!(LedgerEntryType::TransferOut.to_string(), "transfer_out");
+    }
+}
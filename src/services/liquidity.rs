@@ -0,0 +1,343 @@
+//! Market Liquidity Snapshots
+//!
+//! The frontend badges markets whose books are thin. A market's snapshot is
+//! its spread, the depth of the top three levels on each side, its 24h
+//! turnover and a 0-100 liquidity score combining the three.
+//!
+//! The inputs are kept in memory and maintained as they change: the top
+//! levels of each book are replaced from the engine's orderbook stream, and
+//! turnover grows with every trade. Trades leaving the 24h window produce
+//! no event, so turnover is periodically reset from the trigger-maintained
+//! `market_summaries.volume_24h` (see `market_summary`). A request only
+//! combines the stored figures.
+//!
+//! Spread and depth are those a taker sees on the Yes book: with complement
+//! matching enabled, No bids and asks count as synthetic Yes asks and bids
+//! at the complementary price.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use dashmap::DashMap;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::market::ShareType;
+use crate::services::matching::{MatchingEngine, OrderbookSnapshot};
+
+/// Price levels per side counted as depth
+pub const DEPTH_LEVELS: usize = 3;
+
+/// How often turnover is reset from `market_summaries`
+const TURNOVER_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spread at which the spread term of the score reaches zero
+const WIDE_SPREAD: Decimal = Decimal::from_parts(10, 0, 0, false, 2);
+
+/// Depth (shares on the thinner side) that earns the full depth term
+const FULL_DEPTH: Decimal = Decimal::from_parts(5000, 0, 0, false, 0);
+
+/// 24h turnover that earns the full turnover term
+const FULL_TURNOVER: Decimal = Decimal::from_parts(10000, 0, 0, false, 0);
+
+/// Markets scoring below this are badged thin
+pub const THIN_SCORE: u32 = 40;
+
+/// Aggregated `(price, amount)` levels, best first
+type Levels = Vec<(Decimal, Decimal)>;
+
+/// Top levels of one book
+#[derive(Debug, Clone, Default, PartialEq)]
+struct BookTop {
+    bids: Levels,
+    asks: Levels,
+}
+
+impl BookTop {
+    fn from_strings(bids: &[[String; 2]], asks: &[[String; 2]]) -> Self {
+        let parse = |levels: &[[String; 2]]| -> Levels {
+            levels
+                .iter()
+                .take(DEPTH_LEVELS)
+                .filter_map(|[price, amount]| Some((price.parse().ok()?, amount.parse().ok()?)))
+                .collect()
+        };
+        Self {
+            bids: parse(bids),
+            asks: parse(asks),
+        }
+    }
+
+    /// The same book seen from the complement share: its bids become asks
+    /// at `1 - price` and its asks bids
+    fn complement(&self) -> Self {
+        let flip = |levels: &Levels| levels.iter().map(|(p, a)| (Decimal::ONE - p, *a)).collect();
+        Self {
+            bids: flip(&self.asks),
+            asks: flip(&self.bids),
+        }
+    }
+}
+
+/// Liquidity of one market
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketLiquidity {
+    pub market_id: Uuid,
+    pub best_bid: Option<Decimal>,
+    pub best_ask: Option<Decimal>,
+    pub spread: Option<Decimal>,
+    /// Shares bid across the top [`DEPTH_LEVELS`] levels
+    pub bid_depth: Decimal,
+    /// Shares offered across the top [`DEPTH_LEVELS`] levels
+    pub ask_depth: Decimal,
+    /// Traded notional over the last 24 hours
+    pub turnover_24h: Decimal,
+    /// 0 (no book, no trading) to 100
+    pub score: u32,
+    pub thin: bool,
+    pub updated_at: i64,
+}
+
+/// Keeps per-book top levels and per-market turnover current
+pub struct LiquidityTracker {
+    pool: PgPool,
+    engine: Arc<MatchingEngine>,
+    /// Top levels per book key, as last broadcast
+    books: DashMap<String, BookTop>,
+    /// 24h turnover per market
+    turnover: DashMap<Uuid, Decimal>,
+}
+
+impl LiquidityTracker {
+    pub fn new(pool: PgPool, engine: Arc<MatchingEngine>) -> Self {
+        Self {
+            pool,
+            engine,
+            books: DashMap::new(),
+            turnover: DashMap::new(),
+        }
+    }
+
+    /// Follow the engine's book and trade streams and reset turnover from
+    /// `market_summaries` every [`TURNOVER_REFRESH_INTERVAL`]
+    pub fn start(self: Arc<Self>) {
+        let mut trade_receiver = self.engine.subscribe_trades();
+        let mut orderbook_receiver = self.engine.subscribe_orderbook();
+
+        tokio::spawn(async move {
+            tracing::info!("Liquidity tracker started");
+            let mut interval = tokio::time::interval(TURNOVER_REFRESH_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        if let Err(e) = self.refresh_turnover().await {
+                            tracing::warn!("Liquidity turnover refresh failed: {}", e);
+                        }
+                    }
+                    trade = trade_receiver.recv() => match trade {
+                        Ok(trade) if OrderbookSnapshot::split_namespace(&trade.symbol).0.is_none() => {
+                            self.record_trade(trade.market_id, trade.price * trade.amount);
+                        }
+                        Ok(_) => {}
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            tracing::warn!("Liquidity tracker trade receiver lagged by {} messages", n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    orderbook = orderbook_receiver.recv() => match orderbook {
+                        Ok(update) => self.record_book(&update.symbol, &update.bids, &update.asks),
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            // Missed updates leave stale levels; reload books on demand
+                            tracing::warn!("Liquidity tracker orderbook receiver lagged by {} messages", n);
+                            self.books.clear();
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+            tracing::warn!("Liquidity tracker stopped");
+        });
+    }
+
+    fn record_book(&self, symbol: &str, bids: &[[String; 2]], asks: &[[String; 2]]) {
+        // Paper and sandbox books live in their own namespaces
+        if OrderbookSnapshot::split_namespace(symbol).0.is_some() {
+            return;
+        }
+        self.books.insert(symbol.to_string(), BookTop::from_strings(bids, asks));
+    }
+
+    fn record_trade(&self, market_id: Uuid, notional: Decimal) {
+        *self.turnover.entry(market_id).or_default() += notional;
+    }
+
+    /// Replace turnover with the decayed `market_summaries` figures.
+    /// Returns the number of markets with turnover.
+    pub async fn refresh_turnover(&self) -> Result<usize, sqlx::Error> {
+        let rows: Vec<(Uuid, Decimal)> =
+            sqlx::query_as("SELECT market_id, volume_24h FROM market_summaries WHERE volume_24h <> 0")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let current: HashMap<Uuid, Decimal> = rows.into_iter().collect();
+        self.turnover.retain(|market_id, _| current.contains_key(market_id));
+        for (market_id, volume_24h) in &current {
+            self.turnover.insert(*market_id, *volume_24h);
+        }
+        Ok(current.len())
+    }
+
+    /// Top levels of `key`, read from the engine if no update was seen yet
+    fn book(&self, key: &str) -> BookTop {
+        if let Some(top) = self.books.get(key) {
+            return top.clone();
+        }
+        let Ok(snapshot) = self.engine.get_orderbook(key, DEPTH_LEVELS) else {
+            return BookTop::default();
+        };
+        let top = BookTop::from_strings(&snapshot.bids, &snapshot.asks);
+        self.books.insert(key.to_string(), top.clone());
+        top
+    }
+
+    /// Liquidity of the Yes book of `outcome_id` in `market_id`
+    pub fn snapshot(&self, market_id: Uuid, outcome_id: Uuid) -> MarketLiquidity {
+        let yes = self.book(&format!("{}:{}:{}", market_id, outcome_id, ShareType::Yes));
+        let no = self
+            .engine
+            .complement_matching_enabled()
+            .then(|| self.book(&format!("{}:{}:{}", market_id, outcome_id, ShareType::No)).complement());
+        let turnover = self.turnover.get(&market_id).map(|t| *t).unwrap_or_default();
+        assess(market_id, &yes, no.as_ref(), turnover)
+    }
+}
+
+/// Best [`DEPTH_LEVELS`] of two level lists, with equal prices merged;
+/// bids rank highest first, asks lowest first
+fn merge(a: &Levels, b: &Levels, bids: bool) -> Levels {
+    let mut merged: Levels = Vec::with_capacity(a.len() + b.len());
+    for &(price, amount) in a.iter().chain(b) {
+        match merged.iter_mut().find(|(p, _)| *p == price) {
+            Some(level) => level.1 += amount,
+            None => merged.push((price, amount)),
+        }
+    }
+    if bids {
+        merged.sort_by_key(|&(price, _)| std::cmp::Reverse(price));
+    } else {
+        merged.sort_by_key(|&(price, _)| price);
+    }
+    merged.truncate(DEPTH_LEVELS);
+    merged
+}
+
+/// Combine the Yes book, the synthetic levels from the No book and
+/// turnover into a snapshot
+fn assess(market_id: Uuid, yes: &BookTop, synthetic: Option<&BookTop>, turnover_24h: Decimal) -> MarketLiquidity {
+    let empty = BookTop::default();
+    let synthetic = synthetic.unwrap_or(&empty);
+    let bids = merge(&yes.bids, &synthetic.bids, true);
+    let asks = merge(&yes.asks, &synthetic.asks, false);
+
+    let best_bid = bids.first().map(|(p, _)| *p);
+    let best_ask = asks.first().map(|(p, _)| *p);
+    let spread = best_bid.zip(best_ask).map(|(bid, ask)| ask - bid);
+    let bid_depth: Decimal = bids.iter().map(|(_, a)| a).sum();
+    let ask_depth: Decimal = asks.iter().map(|(_, a)| a).sum();
+
+    // 40 points for a tight spread, 30 for depth on the thinner side and
+    // 30 for turnover, each scaled linearly and capped
+    let spread_term = spread
+        .map(|s| (Decimal::ONE - s / WIDE_SPREAD).max(Decimal::ZERO))
+        .unwrap_or_default();
+    let depth_term = (bid_depth.min(ask_depth) / FULL_DEPTH).min(Decimal::ONE);
+    let turnover_term = (turnover_24h / FULL_TURNOVER).min(Decimal::ONE);
+    let score = (spread_term * Decimal::from(40) + depth_term * Decimal::from(30) + turnover_term * Decimal::from(30))
+        .round()
+        .to_u32()
+        .unwrap_or(0);
+
+    MarketLiquidity {
+        market_id,
+        best_bid,
+        best_ask,
+        spread,
+        bid_depth,
+        ask_depth,
+        turnover_24h,
+        score,
+        thin: score < THIN_SCORE,
+        updated_at: Utc::now().timestamp_millis(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::models::OrderSide;
+    use crate::test_support::{gateway_order, TestApp, TAKER};
+
+    fn levels(levels: &[(&str, &str)]) -> Vec<[String; 2]> {
+        levels.iter().map(|(p, a)| [p.to_string(), a.to_string()]).collect()
+    }
+
+    #[test]
+    fn test_top_levels_include_synthetic_book() {
+        let yes = BookTop::from_strings(
+            &levels(&[("0.45", "100"), ("0.44", "100"), ("0.43", "100"), ("0.42", "100")]),
+            &levels(&[("0.55", "50")]),
+        );
+        // No bid at 0.48 offers Yes at 0.52; No ask at 0.56 bids Yes at 0.44
+        let no = BookTop::from_strings(&levels(&[("0.48", "20")]), &levels(&[("0.56", "30")]));
+        let liquidity = assess(Uuid::nil(), &yes, Some(&no.complement()), Decimal::ZERO);
+
+        assert_eq!((liquidity.best_bid, liquidity.best_ask), (Some(dec!(0.45)), Some(dec!(0.52))));
+        assert_eq!(liquidity.spread, Some(dec!(0.07)));
+        // 0.45 + 0.44 (100 direct + 30 synthetic) + 0.43
+        assert_eq!(liquidity.bid_depth, dec!(330));
+        assert_eq!(liquidity.ask_depth, dec!(70));
+
+        let direct = assess(Uuid::nil(), &yes, None, Decimal::ZERO);
+        assert_eq!((direct.spread, direct.bid_depth), (Some(dec!(0.10)), dec!(300)));
+    }
+
+    #[test]
+    fn test_score_and_thin_badge() {
+        let empty = assess(Uuid::nil(), &BookTop::default(), None, Decimal::ZERO);
+        assert_eq!((empty.score, empty.thin, empty.spread), (0, true, None));
+
+        let deep = BookTop {
+            bids: vec![(dec!(0.49), dec!(5000))],
+            asks: vec![(dec!(0.50), dec!(6000))],
+        };
+        let liquid = assess(Uuid::nil(), &deep, None, dec!(20000));
+        // Spread 0.01 scores 36 of 40; depth and turnover are capped at 30
+        assert_eq!((liquid.score, liquid.thin), (96, false));
+
+        let quiet = assess(Uuid::nil(), &deep, None, Decimal::ZERO);
+        assert_eq!((quiet.score, quiet.thin), (66, false));
+    }
+
+    #[tokio::test]
+    async fn test_liquidity_snapshot() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+        let gateway = &app.state.order_gateway;
+        gateway.place(gateway_order(trade.market_id, trade.outcome_id, TAKER, OrderSide::Buy, dec!(0.4))).await.unwrap();
+
+        let tracker = &app.state.liquidity_tracker;
+        assert_eq!(tracker.refresh_turnover().await.unwrap(), 1);
+        let liquidity = tracker.snapshot(trade.market_id, trade.outcome_id);
+        assert_eq!((liquidity.best_bid, liquidity.best_ask, liquidity.spread), (Some(dec!(0.4)), None, None));
+        assert_eq!((liquidity.bid_depth, liquidity.ask_depth), (dec!(10), dec!(0)));
+        assert_eq!(liquidity.turnover_24h, dec!(5));
+        assert!(liquidity.thin);
+    }
+}
@@ -0,0 +1,299 @@
+//! Closed Market Archival
+//!
+//! Once a market is resolved or cancelled its books can no longer trade. The
+//! archiver drops the market's orderbooks from the matching engine, cancels
+//! orders still open in the database (releasing buy reservations) and keeps
+//! the market in an in-process set, so market data endpoints switch to a
+//! read-only view (final stats and settlement price) and WebSocket
+//! subscriptions to its live channels are refused.
+//!
+//! Markets are closed from several places (admin API, UMA oracle, chain
+//! events, CLI), so every request-serving process sweeps for newly closed
+//! markets; the admin handlers also archive immediately.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::market::ShareType;
+use crate::models::Address;
+use crate::services::matching::precision::Collateral;
+use crate::services::matching::EngineShards;
+use crate::services::portfolio_margin;
+use crate::services::system_events::{self, SystemEventKind};
+
+/// How often closed markets are swept
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Closed market as remembered by this process
+#[derive(Debug, Clone)]
+pub struct ArchivedMarket {
+    /// "resolved" or "cancelled"
+    pub status: String,
+    pub winning_outcome_id: Option<Uuid>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl ArchivedMarket {
+    /// Final value of one share: 1 or 0 once resolved, `None` for cancelled
+    /// markets (positions are refunded at cost instead)
+    pub fn settlement_price(&self, outcome_id: Uuid, share_type: ShareType) -> Option<Decimal> {
+        let winner = self.winning_outcome_id?;
+        let wins = match share_type {
+            ShareType::Yes => outcome_id == winner,
+            ShareType::No => outcome_id != winner,
+        };
+        Some(if wins { Decimal::ONE } else { Decimal::ZERO })
+    }
+}
+
+/// Read-only market data for a closed market
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveSummary {
+    pub status: String,
+    pub settlement_price: Option<Decimal>,
+    pub closed_at: Option<i64>,
+    pub total_volume: Decimal,
+    pub trade_count: i64,
+    pub last_price: Option<Decimal>,
+}
+
+/// Archives closed markets and answers "is this market archived?"
+pub struct MarketArchiver {
+    pool: PgPool,
+    shards: Arc<EngineShards>,
+    collateral_token: String,
+    archived: DashMap<Uuid, ArchivedMarket>,
+}
+
+impl MarketArchiver {
+    pub fn new(pool: PgPool, shards: Arc<EngineShards>, collateral_token: &str) -> Self {
+        Self {
+            pool,
+            shards,
+            collateral_token: collateral_token.to_string(),
+            archived: DashMap::new(),
+        }
+    }
+
+    /// The market's archive record, if it is closed
+    pub fn get(&self, market_id: Uuid) -> Option<ArchivedMarket> {
+        self.archived.get(&market_id).map(|m| m.clone())
+    }
+
+    pub fn is_archived(&self, market_id: Uuid) -> bool {
+        self.archived.contains_key(&market_id)
+    }
+
+    /// Final stats for one outcome/share type of an archived market
+    pub async fn summary(
+        &self,
+        market_id: Uuid,
+        outcome_id: Uuid,
+        share_type: ShareType,
+    ) -> Result<Option<ArchiveSummary>, sqlx::Error> {
+        let Some(market) = self.get(market_id) else {
+            return Ok(None);
+        };
+
+        let (total_volume, trade_count, last_price): (Decimal, i64, Option<Decimal>) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM(amount), 0),
+                   COUNT(*),
+                   (SELECT price FROM trades
+                    WHERE market_id = $1 AND outcome_id = $2 AND share_type = $3::share_type
+                    ORDER BY created_at DESC LIMIT 1)
+            FROM trades
+            WHERE market_id = $1 AND outcome_id = $2 AND share_type = $3::share_type
+            "#,
+        )
+        .bind(market_id)
+        .bind(outcome_id)
+        .bind(share_type.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Some(ArchiveSummary {
+            status: market.status.clone(),
+            settlement_price: market.settlement_price(outcome_id, share_type),
+            closed_at: market.closed_at.map(|t| t.timestamp_millis()),
+            total_volume,
+            trade_count,
+            last_price,
+        }))
+    }
+
+    /// Archive `market_id` if it is closed. Returns whether it is archived.
+    pub async fn archive(&self, market_id: Uuid) -> Result<bool, sqlx::Error> {
+        let row: Option<(String, Option<Uuid>, Option<DateTime<Utc>>)> = sqlx::query_as(
+            r#"
+            SELECT status::text, winning_outcome_id, resolved_at
+            FROM markets
+            WHERE id = $1 AND status IN ('resolved', 'cancelled')
+            "#,
+        )
+        .bind(market_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((status, winning_outcome_id, closed_at)) = row else {
+            return Ok(false);
+        };
+
+        // On the market's shard, so no order is still matching into the
+        // books being dropped; an overloaded shard leaves it to the next sweep
+        if let Err(e) = self
+            .shards
+            .execute(&market_id.to_string(), move |engine| engine.close_market(market_id))
+            .await
+        {
+            tracing::warn!("Archiving market {} deferred: {}", market_id, e);
+            return Ok(false);
+        }
+        let cancelled = self.cancel_open_orders(market_id).await?;
+        if cancelled > 0 {
+            tracing::info!("Archived market {}: cancelled {} open orders", market_id, cancelled);
+            // Every restart re-archives closed markets; only the first pass
+            // (the one that cancelled orders) belongs on the timeline
+            system_events::record(
+                &self.pool,
+                SystemEventKind::MarketArchived,
+                Some(&market_id.to_string()),
+                format!("Market archived ({}), {} open orders cancelled", status, cancelled),
+                serde_json::json!({ "status": status, "cancelled_orders": cancelled }),
+            )
+            .await;
+        }
+
+        self.archived.insert(
+            market_id,
+            ArchivedMarket {
+                status,
+                winning_outcome_id,
+                closed_at,
+            },
+        );
+        Ok(true)
+    }
+
+    /// Archive every closed market not yet archived by this process
+    pub async fn sweep(&self) -> Result<usize, sqlx::Error> {
+        let closed: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM markets WHERE status IN ('resolved', 'cancelled')")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut archived = 0;
+        for market_id in closed.into_iter().filter(|id| !self.is_archived(*id)) {
+            if self.archive(market_id).await? {
+                archived += 1;
+            }
+        }
+        Ok(archived)
+    }
+
+    /// Sweep once now, then periodically
+    pub async fn start(self: Arc<Self>) {
+        match self.sweep().await {
+            Ok(n) if n > 0 => tracing::info!("Archived {} closed markets", n),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Closed market sweep failed: {}", e),
+        }
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.sweep().await {
+                    tracing::warn!("Closed market sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Cancel the market's open orders and release buy reservations
+    async fn cancel_open_orders(&self, market_id: Uuid) -> Result<usize, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let cancelled: Vec<(Address, String, Option<Decimal>, Decimal)> = sqlx::query_as(
+            r#"
+            UPDATE orders
+            SET status = 'cancelled', updated_at = NOW()
+            WHERE market_id = $1 AND status IN ('pending', 'open', 'partially_filled')
+            RETURNING user_address, side::text, price, amount - filled_amount
+            "#,
+        )
+        .bind(market_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for (user_address, side, price, remaining) in &cancelled {
+            let Some(price) = price.filter(|_| side == "buy") else {
+                continue;
+            };
+            sqlx::query(
+                "UPDATE balances SET available = available + $1, frozen = frozen - $1, updated_at = NOW()
+                 WHERE user_address = $2 AND token = $3",
+            )
+            .bind(Collateral::notional(price, *remaining).value())
+            .bind(user_address)
+            .bind(&self.collateral_token)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let mut users: Vec<&Address> = cancelled.iter().map(|(user, ..)| user).collect();
+        users.sort();
+        users.dedup();
+        for user_address in &users {
+            portfolio_margin::resync_account(&mut tx, user_address.as_str(), &self.collateral_token).await?;
+        }
+
+        tx.commit().await?;
+        Ok(cancelled.len())
+    }
+}
+
+/// Market id of a live market data channel (`orderbook:`, `trades:`,
+/// `ticker:` followed by a `market_id:outcome_id:share_type` key)
+pub fn live_channel_market(channel: &str) -> Option<Uuid> {
+    let key = ["orderbook:", "orderbook-delta:", "trades:", "ticker:"]
+        .iter()
+        .find_map(|prefix| channel.strip_prefix(prefix))?;
+    Uuid::parse_str(key.split(':').next()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settlement_price() {
+        let (yes_outcome, no_outcome) = (Uuid::new_v4(), Uuid::new_v4());
+        let resolved = ArchivedMarket {
+            status: "resolved".to_string(),
+            winning_outcome_id: Some(yes_outcome),
+            closed_at: None,
+        };
+        assert_eq!(resolved.settlement_price(yes_outcome, ShareType::Yes), Some(Decimal::ONE));
+        assert_eq!(resolved.settlement_price(yes_outcome, ShareType::No), Some(Decimal::ZERO));
+        assert_eq!(resolved.settlement_price(no_outcome, ShareType::No), Some(Decimal::ONE));
+
+        let cancelled = ArchivedMarket { status: "cancelled".to_string(), winning_outcome_id: None, closed_at: None };
+        assert_eq!(cancelled.settlement_price(yes_outcome, ShareType::Yes), None);
+    }
+
+    #[test]
+    fn test_live_channel_market() {
+        let market_id = Uuid::new_v4();
+        let key = format!("{}:{}:yes", market_id, Uuid::new_v4());
+        assert_eq!(live_channel_market(&format!("orderbook:{}", key)), Some(market_id));
+        assert_eq!(live_channel_market(&format!("trades:{}", key)), Some(market_id));
+        assert_eq!(live_channel_market("orderbook:BTCUSDT"), None);
+        assert_eq!(live_channel_market("orders"), None);
+    }
+}
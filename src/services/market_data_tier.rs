@@ -0,0 +1,53 @@
+//! Market Data Entitlements
+//!
+//! How fresh the market data of a WebSocket connection is. Anonymous
+//! connections are on the delayed tier: their trades and orderbooks are
+//! conflated and sent once a second. Accounts get real-time data, and
+//! market makers are granted the orderbook delta feed. Support sets an
+//! account's tier through the admin console.
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+/// Market data entitlement, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketDataTier {
+    /// Conflated and flushed every second; anonymous connections
+    Delayed,
+    /// Every trade and orderbook update as it happens
+    RealTime,
+    /// Real-time, plus incremental orderbook updates (market makers)
+    Delta,
+}
+
+impl MarketDataTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketDataTier::Delayed => "delayed",
+            MarketDataTier::RealTime => "real_time",
+            MarketDataTier::Delta => "delta",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "delayed" => Some(MarketDataTier::Delayed),
+            "real_time" => Some(MarketDataTier::RealTime),
+            "delta" => Some(MarketDataTier::Delta),
+            _ => None,
+        }
+    }
+}
+
+/// Tier of the authenticated account `address`; unknown users are real-time
+pub async fn get(pool: &PgPool, address: &str) -> Result<MarketDataTier, sqlx::Error> {
+    let tier: Option<String> = sqlx::query_scalar("SELECT market_data_tier FROM users WHERE address = $1")
+        .bind(address)
+        .fetch_optional(pool)
+        .await?;
+    Ok(tier
+        .as_deref()
+        .and_then(MarketDataTier::parse)
+        .unwrap_or(MarketDataTier::RealTime))
+}
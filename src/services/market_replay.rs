@@ -0,0 +1,463 @@
+//! Historical Market Replay
+//!
+//! Rebuilds a market's order flow over a past window for backtesting: the
+//! book at the start (latest `orderbook_snapshots` row at or before it),
+//! later snapshots, order placements and cancellations / expiries from
+//! `orders`, and executed trades, merged into one timeline. Trade events can
+//! be followed by OHLCV candles closed at a chosen interval. Addresses are
+//! never included.
+//!
+//! Served as pages (`GET /markets/:market_id/replay`) and as a paced SSE
+//! stream (`GET /sse/markets/:market_id/replay`).
+
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Longest window one request may cover
+pub const MAX_WINDOW: Duration = Duration::days(7);
+/// Most events returned per page
+pub const MAX_EVENTS: usize = 20_000;
+
+type Levels = Vec<[String; 2]>;
+
+/// One event of the replayed timeline; `ts` is Unix milliseconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayEvent {
+    /// Book of one outcome side as snapshotted
+    Book {
+        ts: i64,
+        outcome_id: Uuid,
+        share_type: String,
+        bids: Levels,
+        asks: Levels,
+        last_price: Option<Decimal>,
+    },
+    /// An order accepted by the engine
+    Order {
+        ts: i64,
+        order_id: Uuid,
+        outcome_id: Uuid,
+        share_type: String,
+        side: String,
+        order_type: String,
+        price: Option<Decimal>,
+        amount: Decimal,
+    },
+    /// An order removed from the book without filling completely
+    OrderClosed {
+        ts: i64,
+        order_id: Uuid,
+        status: String,
+        filled_amount: Decimal,
+    },
+    Trade {
+        ts: i64,
+        trade_id: Uuid,
+        outcome_id: Uuid,
+        share_type: String,
+        side: String,
+        price: Decimal,
+        amount: Decimal,
+        match_type: String,
+        maker_order_id: Uuid,
+        taker_order_id: Uuid,
+        /// Set when the trade was later busted or price-adjusted
+        adjustment_status: Option<String>,
+    },
+    /// OHLCV of one outcome side over `[ts - interval, ts)`
+    Candle {
+        ts: i64,
+        outcome_id: Uuid,
+        share_type: String,
+        open: Decimal,
+        high: Decimal,
+        low: Decimal,
+        close: Decimal,
+        volume: Decimal,
+    },
+}
+
+impl ReplayEvent {
+    pub fn ts(&self) -> i64 {
+        match self {
+            ReplayEvent::Book { ts, .. }
+            | ReplayEvent::Order { ts, .. }
+            | ReplayEvent::OrderClosed { ts, .. }
+            | ReplayEvent::Trade { ts, .. }
+            | ReplayEvent::Candle { ts, .. } => *ts,
+        }
+    }
+
+    /// SSE event name
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ReplayEvent::Book { .. } => "book",
+            ReplayEvent::Order { .. } => "order",
+            ReplayEvent::OrderClosed { .. } => "order_closed",
+            ReplayEvent::Trade { .. } => "trade",
+            ReplayEvent::Candle { .. } => "candle",
+        }
+    }
+
+    /// Order of events sharing a timestamp: candles close before anything
+    /// new happens, books describe the state the flow then acts on
+    fn rank(&self) -> u8 {
+        match self {
+            ReplayEvent::Candle { .. } => 0,
+            ReplayEvent::Book { .. } => 1,
+            ReplayEvent::Order { .. } => 2,
+            ReplayEvent::Trade { .. } => 3,
+            ReplayEvent::OrderClosed { .. } => 4,
+        }
+    }
+}
+
+/// What to replay
+#[derive(Debug, Clone)]
+pub struct ReplayQuery {
+    pub market_id: Uuid,
+    /// Restrict to one outcome
+    pub outcome_id: Option<Uuid>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Candle interval in seconds; no candles when unset
+    pub candle_secs: Option<i64>,
+    pub limit: usize,
+}
+
+/// A page of the timeline
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayPage {
+    pub events: Vec<ReplayEvent>,
+    /// Pass as `from` to continue when the page was cut short; the page ends
+    /// before this timestamp
+    pub next_from: Option<i64>,
+}
+
+/// Cut a sorted timeline to at most `limit` events without splitting the
+/// events of one timestamp across pages. Returns the timestamp the next page
+/// starts at when events were dropped.
+pub fn truncate_at_boundary(events: &mut Vec<ReplayEvent>, limit: usize) -> Option<i64> {
+    if events.len() <= limit {
+        return None;
+    }
+    let next_from = events[limit].ts();
+    let keep = events[..limit].iter().take_while(|e| e.ts() < next_from).count();
+    // A single timestamp with more than `limit` events is returned whole
+    let keep = if keep == 0 {
+        events.iter().take_while(|e| e.ts() == next_from).count()
+    } else {
+        keep
+    };
+    let next_from = events.get(keep).map(ReplayEvent::ts);
+    events.truncate(keep);
+    next_from
+}
+
+/// Candle events from the trades of a sorted timeline, each stamped at the
+/// close of its `interval_ms` bucket. Only buckets closing at or before
+/// `until` are emitted.
+pub fn candles(events: &[ReplayEvent], interval_ms: i64, until: i64) -> Vec<ReplayEvent> {
+    let mut open: Vec<ReplayEvent> = Vec::new();
+    let mut closed = Vec::new();
+    for event in events {
+        let ReplayEvent::Trade { ts, outcome_id, share_type, price, amount, .. } = event else {
+            continue;
+        };
+        let close_ts = (ts.div_euclid(interval_ms) + 1) * interval_ms;
+        let existing = open.iter_mut().find(|c| {
+            matches!(c, ReplayEvent::Candle { ts, outcome_id: o, share_type: s, .. }
+                if *ts == close_ts && o == outcome_id && s == share_type)
+        });
+        match existing {
+            Some(ReplayEvent::Candle { high, low, close, volume, .. }) => {
+                *high = (*high).max(*price);
+                *low = (*low).min(*price);
+                *close = *price;
+                *volume += *amount;
+            }
+            _ => {
+                // Trades are sorted, so earlier buckets of this side are complete
+                open.retain(|c| {
+                    let done = matches!(c, ReplayEvent::Candle { outcome_id: o, share_type: s, .. }
+                        if o == outcome_id && s == share_type);
+                    if done {
+                        closed.push(c.clone());
+                    }
+                    !done
+                });
+                open.push(ReplayEvent::Candle {
+                    ts: close_ts,
+                    outcome_id: *outcome_id,
+                    share_type: share_type.clone(),
+                    open: *price,
+                    high: *price,
+                    low: *price,
+                    close: *price,
+                    volume: *amount,
+                });
+            }
+        }
+    }
+    closed.extend(open);
+    closed.retain(|c| c.ts() <= until);
+    closed.sort_by_key(ReplayEvent::ts);
+    closed
+}
+
+/// Wall-clock wait before emitting an event `gap_ms` after the previous one
+/// at `speed`x; 0 replays without waiting. Gaps are capped at `max_wait`.
+pub fn replay_delay(gap_ms: i64, speed: f64, max_wait: std::time::Duration) -> std::time::Duration {
+    if speed <= 0.0 || gap_ms <= 0 {
+        return std::time::Duration::ZERO;
+    }
+    std::time::Duration::from_secs_f64(gap_ms as f64 / 1000.0 / speed).min(max_wait)
+}
+
+type SnapshotRow = (DateTime<Utc>, Uuid, String, Json<Levels>, Json<Levels>, Option<Decimal>);
+type OrderRow = (DateTime<Utc>, Uuid, Uuid, String, String, String, Option<Decimal>, Decimal);
+type ClosedRow = (DateTime<Utc>, Uuid, String, Decimal);
+type TradeRow = (
+    DateTime<Utc>,
+    Uuid,
+    Uuid,
+    String,
+    String,
+    Decimal,
+    Decimal,
+    String,
+    Uuid,
+    Uuid,
+    Option<String>,
+);
+
+/// Load one page of a market's timeline within `[from, to]`
+pub async fn load(pool: &PgPool, query: &ReplayQuery) -> Result<ReplayPage, sqlx::Error> {
+    // Each source is cut at `limit + 1`, which is enough to know the first
+    // `limit` events of the merge and whether there are more
+    let fetch = query.limit as i64 + 1;
+
+    let books: Vec<SnapshotRow> = sqlx::query_as(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (outcome_id, share_type)
+                   GREATEST(captured_at, $3), outcome_id, share_type, bids, asks, last_price
+            FROM orderbook_snapshots
+            WHERE market_id = $1 AND ($2::uuid IS NULL OR outcome_id = $2) AND captured_at <= $3
+            ORDER BY outcome_id, share_type, captured_at DESC
+        ) initial
+        UNION ALL
+        (
+            SELECT captured_at, outcome_id, share_type, bids, asks, last_price
+            FROM orderbook_snapshots
+            WHERE market_id = $1 AND ($2::uuid IS NULL OR outcome_id = $2)
+              AND captured_at > $3 AND captured_at <= $4
+            ORDER BY captured_at
+            LIMIT $5
+        )
+        "#,
+    )
+    .bind(query.market_id)
+    .bind(query.outcome_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(fetch)
+    .fetch_all(pool)
+    .await?;
+
+    let orders: Vec<OrderRow> = sqlx::query_as(
+        r#"
+        SELECT created_at, id, outcome_id, share_type::text, side::text, order_type::text, price, amount
+        FROM orders
+        WHERE market_id = $1 AND ($2::uuid IS NULL OR outcome_id = $2)
+          AND created_at >= $3 AND created_at <= $4
+          AND status NOT IN ('pending', 'rejected')
+        ORDER BY created_at
+        LIMIT $5
+        "#,
+    )
+    .bind(query.market_id)
+    .bind(query.outcome_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(fetch)
+    .fetch_all(pool)
+    .await?;
+
+    let closed: Vec<ClosedRow> = sqlx::query_as(
+        r#"
+        SELECT updated_at, id, status::text, filled_amount
+        FROM orders
+        WHERE market_id = $1 AND ($2::uuid IS NULL OR outcome_id = $2)
+          AND status IN ('cancelled', 'expired')
+          AND updated_at >= $3 AND updated_at <= $4
+        ORDER BY updated_at
+        LIMIT $5
+        "#,
+    )
+    .bind(query.market_id)
+    .bind(query.outcome_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(fetch)
+    .fetch_all(pool)
+    .await?;
+
+    let trades: Vec<TradeRow> = sqlx::query_as(
+        r#"
+        SELECT created_at, id, outcome_id, share_type::text, side::text, price, amount,
+               COALESCE(match_type::text, 'normal'), maker_order_id, taker_order_id, adjustment_status
+        FROM trades
+        WHERE market_id = $1 AND ($2::uuid IS NULL OR outcome_id = $2)
+          AND created_at >= $3 AND created_at <= $4
+        ORDER BY created_at, sequence NULLS LAST
+        LIMIT $5
+        "#,
+    )
+    .bind(query.market_id)
+    .bind(query.outcome_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(fetch)
+    .fetch_all(pool)
+    .await?;
+
+    let mut events: Vec<ReplayEvent> = books
+        .into_iter()
+        .map(|(at, outcome_id, share_type, bids, asks, last_price)| ReplayEvent::Book {
+            ts: at.timestamp_millis(),
+            outcome_id,
+            share_type,
+            bids: bids.0,
+            asks: asks.0,
+            last_price,
+        })
+        .chain(orders.into_iter().map(
+            |(at, order_id, outcome_id, share_type, side, order_type, price, amount)| ReplayEvent::Order {
+                ts: at.timestamp_millis(),
+                order_id,
+                outcome_id,
+                share_type,
+                side,
+                order_type,
+                price,
+                amount,
+            },
+        ))
+        .chain(closed.into_iter().map(|(at, order_id, status, filled_amount)| ReplayEvent::OrderClosed {
+            ts: at.timestamp_millis(),
+            order_id,
+            status,
+            filled_amount,
+        }))
+        .chain(trades.into_iter().map(
+            |(at, trade_id, outcome_id, share_type, side, price, amount, match_type, maker, taker, adjustment)| {
+                ReplayEvent::Trade {
+                    ts: at.timestamp_millis(),
+                    trade_id,
+                    outcome_id,
+                    share_type,
+                    side,
+                    price,
+                    amount,
+                    match_type,
+                    maker_order_id: maker,
+                    taker_order_id: taker,
+                    adjustment_status: adjustment,
+                }
+            },
+        ))
+        .collect();
+    // Stable, so trades keep their sequence order within a timestamp
+    events.sort_by_key(|e| (e.ts(), e.rank()));
+
+    let next_from = truncate_at_boundary(&mut events, query.limit);
+    if let Some(interval) = query.candle_secs {
+        let until = next_from.map_or(query.to.timestamp_millis(), |next| next - 1);
+        let mut with_candles = candles(&events, interval * 1000, until);
+        with_candles.append(&mut events);
+        with_candles.sort_by_key(|e| (e.ts(), e.rank()));
+        events = with_candles;
+    }
+
+    Ok(ReplayPage { events, next_from })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn trade(ts: i64, price: Decimal, amount: Decimal) -> ReplayEvent {
+        ReplayEvent::Trade {
+            ts,
+            trade_id: Uuid::nil(),
+            outcome_id: Uuid::nil(),
+            share_type: "yes".to_string(),
+            side: "buy".to_string(),
+            price,
+            amount,
+            match_type: "normal".to_string(),
+            maker_order_id: Uuid::nil(),
+            taker_order_id: Uuid::nil(),
+            adjustment_status: None,
+        }
+    }
+
+    #[test]
+    fn test_truncate_at_boundary() {
+        let timeline = |stamps: &[i64]| stamps.iter().map(|ts| trade(*ts, dec!(0.5), dec!(1))).collect::<Vec<_>>();
+
+        let mut events = timeline(&[1, 2, 3]);
+        assert_eq!(truncate_at_boundary(&mut events, 3), None);
+        assert_eq!(events.len(), 3);
+
+        // The cut never splits a timestamp
+        let mut events = timeline(&[1, 2, 2, 2, 3]);
+        assert_eq!(truncate_at_boundary(&mut events, 3), Some(2));
+        assert_eq!(events.len(), 1);
+
+        // One timestamp over the limit is returned whole
+        let mut events = timeline(&[5, 5, 5, 6]);
+        assert_eq!(truncate_at_boundary(&mut events, 2), Some(6));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn test_candles() {
+        let events = vec![
+            trade(1_000, dec!(0.50), dec!(10)),
+            trade(30_000, dec!(0.55), dec!(5)),
+            trade(59_999, dec!(0.45), dec!(2)),
+            trade(61_000, dec!(0.60), dec!(1)),
+        ];
+        let out = candles(&events, 60_000, 200_000);
+        assert_eq!(out.len(), 2);
+        match &out[0] {
+            ReplayEvent::Candle { ts, open, high, low, close, volume, .. } => {
+                assert_eq!(*ts, 60_000);
+                assert_eq!((*open, *high, *low, *close, *volume), (dec!(0.50), dec!(0.55), dec!(0.45), dec!(0.45), dec!(17)));
+            }
+            other => panic!("expected a candle, got {:?}", other),
+        }
+        assert_eq!(out[1].ts(), 120_000);
+
+        // Buckets still open at the end of the window are held back
+        assert_eq!(candles(&events, 60_000, 100_000).len(), 1);
+    }
+
+    #[test]
+    fn test_replay_delay() {
+        let cap = std::time::Duration::from_secs(5);
+        assert_eq!(replay_delay(1_000, 1.0, cap), std::time::Duration::from_secs(1));
+        assert_eq!(replay_delay(1_000, 10.0, cap), std::time::Duration::from_millis(100));
+        assert_eq!(replay_delay(60_000, 1.0, cap), cap);
+        assert_eq!(replay_delay(1_000, 0.0, cap), std::time::Duration::ZERO);
+        assert_eq!(replay_delay(0, 1.0, cap), std::time::Duration::ZERO);
+    }
+}
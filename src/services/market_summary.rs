@@ -0,0 +1,257 @@
+//! Market Summary Refresh
+//!
+//! `market_summaries` is kept current by triggers on `trades`, `outcomes`
+//! and `markets`: every new trade adds to the market's total and 24h volume
+//! and moves its last price. Trades leaving the 24h window produce no
+//! event, so this job periodically recomputes `volume_24h` for markets that
+//! still carry some, letting it decay back to zero.
+//!
+//! A trade inserted while a refresh runs can be missed by that refresh; the
+//! next pass recomputes from `trades` and picks it up.
+//!
+//! The same pass recomputes each recently traded outcome's 1h and 24h
+//! time-weighted probability (`outcomes.twap_1h`, `twap_24h`) from the
+//! trigger-maintained one-minute `outcome_price_candles`. The probability
+//! is taken to step to a candle's close at the time of its last trade and
+//! hold until the next one, so a quiet outcome's TWAP converges to its last
+//! price.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::services::leader_election::LeaderElection;
+
+/// How often 24h volume and TWAPs are recomputed
+const REFRESH_INTERVAL_SECS: u64 = 60;
+
+/// Decimal places TWAPs are stored with
+const TWAP_DP: u32 = 6;
+
+/// Time-weighted average of a step function over `[from, to]`. The value
+/// is `prior` until the first of `points` (time-ordered `(time, value)`
+/// changes) and each point's value until the next. Time before the first
+/// known value is left out; `None` when there is no value at all.
+pub fn twap(
+    prior: Option<Decimal>,
+    points: &[(DateTime<Utc>, Decimal)],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Option<Decimal> {
+    let mut current = prior;
+    let mut since = from;
+    let mut weighted = Decimal::ZERO;
+    let mut covered = 0i64;
+
+    let mut accumulate = |value: Option<Decimal>, start: DateTime<Utc>, end: DateTime<Utc>| {
+        let millis = (end - start).num_milliseconds();
+        if let (Some(value), true) = (value, millis > 0) {
+            weighted += value * Decimal::from(millis);
+            covered += millis;
+        }
+    };
+    for &(at, value) in points.iter().filter(|(at, _)| *at > from && *at <= to) {
+        accumulate(current, since, at);
+        current = Some(value);
+        since = at;
+    }
+    accumulate(current, since, to);
+
+    if covered == 0 {
+        current
+    } else {
+        Some(weighted / Decimal::from(covered))
+    }
+}
+
+/// Background job decaying `market_summaries.volume_24h`
+pub struct MarketSummaryRefresher {
+    pool: PgPool,
+}
+
+impl MarketSummaryRefresher {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Spawn the refresh loop; only the elected leader refreshes
+    pub fn start(self: Arc<Self>, leader: Arc<LeaderElection>) {
+        tokio::spawn(async move {
+            tracing::info!("Market summary refresh job started");
+            let mut interval = tokio::time::interval(Duration::from_secs(REFRESH_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                if !leader.is_leader() {
+                    continue;
+                }
+                match self.refresh_volume_24h().await {
+                    Ok(rows) => tracing::debug!("Refreshed 24h volume of {} markets", rows),
+                    Err(e) => tracing::error!("Market summary refresh failed: {}", e),
+                }
+                match self.refresh_twap(Utc::now()).await {
+                    Ok(rows) => tracing::debug!("Refreshed TWAP of {} outcomes", rows),
+                    Err(e) => tracing::error!("Outcome TWAP refresh failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Recompute `volume_24h` of every market with volume in the window.
+    /// Returns the number of summaries that changed.
+    pub async fn refresh_volume_24h(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE market_summaries s
+            SET volume_24h = v.volume_24h, updated_at = NOW()
+            FROM (
+                SELECT s2.market_id,
+                       COALESCE((
+                           SELECT SUM(trade_effective_notional(t))
+                           FROM trades t
+                           WHERE t.market_id = s2.market_id
+                             AND t.created_at >= NOW() - INTERVAL '24 hours'
+                       ), 0) AS volume_24h
+                FROM market_summaries s2
+                WHERE s2.volume_24h <> 0
+            ) v
+            WHERE s.market_id = v.market_id
+              AND s.volume_24h <> v.volume_24h
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Recompute `twap_1h` and `twap_24h` as of `now` for every outcome
+    /// with a candle in the last 25 hours (the extra hour lets a TWAP settle
+    /// on the last price once its trades leave the window). Returns the
+    /// number of outcomes that changed.
+    pub async fn refresh_twap(&self, now: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let day_ago = now - ChronoDuration::hours(24);
+        let hour_ago = now - ChronoDuration::hours(1);
+
+        // Last close before the window, then every close inside it
+        let rows: Vec<(Uuid, DateTime<Utc>, Decimal)> = sqlx::query_as(
+            r#"
+            WITH active AS (
+                SELECT DISTINCT outcome_id FROM outcome_price_candles
+                WHERE bucket >= $1 - INTERVAL '1 hour' AND bucket <= $2
+            )
+            SELECT p.outcome_id, p.close_at, p.close
+            FROM active a
+            CROSS JOIN LATERAL (
+                SELECT outcome_id, close_at, close FROM outcome_price_candles
+                WHERE outcome_id = a.outcome_id AND bucket < $1
+                ORDER BY bucket DESC
+                LIMIT 1
+            ) p
+            UNION ALL
+            SELECT c.outcome_id, c.close_at, c.close
+            FROM outcome_price_candles c
+            JOIN active a ON a.outcome_id = c.outcome_id
+            WHERE c.bucket >= $1 AND c.bucket <= $2
+            ORDER BY 1, 2
+            "#,
+        )
+        .bind(day_ago)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut closes: HashMap<Uuid, Vec<(DateTime<Utc>, Decimal)>> = HashMap::new();
+        for (outcome_id, at, close) in rows {
+            closes.entry(outcome_id).or_default().push((at, close));
+        }
+
+        let mut ids = Vec::with_capacity(closes.len());
+        let mut twaps_1h = Vec::with_capacity(closes.len());
+        let mut twaps_24h = Vec::with_capacity(closes.len());
+        for (outcome_id, points) in closes {
+            let prior_to = |from: DateTime<Utc>| points.iter().take_while(|(at, _)| *at <= from).last().map(|p| p.1);
+            ids.push(outcome_id);
+            twaps_1h.push(twap(prior_to(hour_ago), &points, hour_ago, now).map(|v| v.round_dp(TWAP_DP)));
+            twaps_24h.push(twap(prior_to(day_ago), &points, day_ago, now).map(|v| v.round_dp(TWAP_DP)));
+        }
+
+        let result = sqlx::query(
+            r#"
+            UPDATE outcomes o
+            SET twap_1h = v.twap_1h, twap_24h = v.twap_24h
+            FROM UNNEST($1::uuid[], $2::numeric[], $3::numeric[]) AS v(id, twap_1h, twap_24h)
+            WHERE o.id = v.id
+              AND (o.twap_1h IS DISTINCT FROM v.twap_1h OR o.twap_24h IS DISTINCT FROM v.twap_24h)
+            "#,
+        )
+        .bind(&ids)
+        .bind(&twaps_1h)
+        .bind(&twaps_24h)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use axum::extract::{Path, State};
+
+    use crate::api::handlers::market;
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn test_twap_weights_by_time_held() {
+        let from = DateTime::from_timestamp(0, 0).unwrap();
+        let at = |mins: i64| from + ChronoDuration::minutes(mins);
+
+        // 0.4 for 15 minutes, then 0.8 for 45
+        let points = [(at(15), dec!(0.8))];
+        assert_eq!(twap(Some(dec!(0.4)), &points, from, at(60)), Some(dec!(0.7)));
+        // Without a prior value only the time since the first trade counts
+        assert_eq!(twap(None, &points, from, at(60)), Some(dec!(0.8)));
+        // Points outside the window only matter through `prior`
+        assert_eq!(twap(Some(dec!(0.3)), &[(at(90), dec!(0.9))], from, at(60)), Some(dec!(0.3)));
+        assert_eq!(twap(None, &[], from, at(60)), None);
+    }
+
+    #[tokio::test]
+    async fn test_outcome_twap() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+        let pool = &app.state.db.pool;
+        let (close, count): (Decimal, i32) =
+            sqlx::query_as("SELECT close, trade_count FROM outcome_price_candles WHERE outcome_id = $1")
+                .bind(trade.outcome_id)
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        assert_eq!((close, count), (dec!(0.5), 1));
+
+        // Half an hour later the price has been 0.5 since the trade
+        let refresher = MarketSummaryRefresher::new(pool.clone());
+        assert_eq!(refresher.refresh_twap(Utc::now() + ChronoDuration::minutes(30)).await.unwrap(), 1);
+        let ticker = market::get_ticker(State(app.state.clone()), Path(trade.market_id)).await.unwrap();
+        let yes = ticker.outcomes.iter().find(|o| o.outcome_id == trade.outcome_id).unwrap();
+        assert_eq!((yes.twap_1h, yes.twap_24h), (Some(dec!(0.5)), Some(dec!(0.5))));
+
+        // Busting the trade empties its candle
+        sqlx::query("UPDATE trades SET adjustment_status = 'busted' WHERE id = $1")
+            .bind(trade.trade_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        let candles: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outcome_price_candles WHERE outcome_id = $1")
+            .bind(trade.outcome_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(candles, 0);
+    }
+}
@@ -0,0 +1,385 @@
+//! Trade and Order History with Database Fall-through
+//!
+//! The engine's [`HistoryManager`](polymarket_engine::HistoryManager) only
+//! holds a bounded, recent window. Queries are answered from memory first;
+//! when a page is not full and the query reaches past the in-memory window
+//! (older records were evicted, or the process restarted), the rest of the
+//! page is read from Postgres.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{
+    MatchingEngine, OrderHistoryQuery, OrderHistoryRecord, OrderHistoryResponse, TradeHistoryQuery,
+    TradeHistoryResponse, TradeRecord,
+};
+use crate::models::order::{FillLiquidity, OrderFill};
+use crate::models::Address;
+
+/// How often expired in-memory history is evicted
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// History reads spanning memory and Postgres
+pub struct HistoryStore {
+    engine: Arc<MatchingEngine>,
+    pool: PgPool,
+}
+
+impl HistoryStore {
+    pub fn new(engine: Arc<MatchingEngine>, pool: PgPool) -> Self {
+        Self { engine, pool }
+    }
+
+    /// Spawn the periodic eviction of expired in-memory history
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(EVICTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                self.engine.history().evict_expired(Utc::now().timestamp_millis());
+            }
+        });
+    }
+
+    /// Trades of one book (`market_id:outcome_id:share_type`), newest first
+    pub async fn get_trades(&self, symbol: &str, query: &TradeHistoryQuery) -> Result<TradeHistoryResponse, sqlx::Error> {
+        let history = self.engine.history();
+        let limit = query.get_limit();
+        let mut page = history.get_trades(symbol, query);
+
+        let window_start = history.trade_window_start(symbol);
+        if page.trades.len() >= limit || query.after.is_some_and(|after| after >= window_start) {
+            return Ok(page);
+        }
+
+        // Continue from the oldest trade already on the page (same-millisecond
+        // trades are deduplicated below)
+        let before = match page.trades.last() {
+            Some(oldest) => Some(oldest.timestamp),
+            None => query.before.map(|ts| ts - 1),
+        };
+        let seen: HashSet<String> = page.trades.iter().map(|t| t.trade_id.clone()).collect();
+        let rows: Vec<TradeRow> = sqlx::query_as(
+            r#"
+            SELECT id::text, market_id::text, outcome_id::text, share_type::text, match_type::text,
+                   side::text, price, amount, maker_order_id::text, taker_order_id::text,
+                   maker_address, taker_address, maker_fee, taker_fee, created_at,
+                   sequence, is_block_trade, is_rfq
+            FROM trades
+            WHERE symbol = $1
+              AND ($2::timestamptz IS NULL OR created_at <= $2)
+              AND ($3::timestamptz IS NULL OR created_at > $3)
+            ORDER BY created_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(symbol)
+        .bind(before.and_then(DateTime::<Utc>::from_timestamp_millis))
+        .bind(query.after.and_then(DateTime::<Utc>::from_timestamp_millis))
+        .bind((limit + seen.len() + 1) as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let older = rows.into_iter().map(TradeRecord::from).filter(|t| !seen.contains(&t.trade_id));
+        page.trades.extend(older);
+        page.has_more = page.trades.len() > limit;
+        page.trades.truncate(limit);
+        page.total_count = page.trades.len();
+        Ok(page)
+    }
+
+    /// A market's trade tape across all its books, highest sequence first
+    pub async fn get_market_tape(&self, market_id: &str, before: Option<u64>, limit: usize) -> Result<Vec<TradeRecord>, sqlx::Error> {
+        let history = self.engine.history();
+        let mut tape = history.get_market_tape(market_id, before, limit);
+        if tape.len() >= limit {
+            return Ok(tape);
+        }
+
+        let before = tape.last().map(|t| t.sequence).or(before);
+        let rows: Vec<TradeRow> = sqlx::query_as(
+            r#"
+            SELECT id::text, market_id::text, outcome_id::text, share_type::text, match_type::text,
+                   side::text, price, amount, maker_order_id::text, taker_order_id::text,
+                   maker_address, taker_address, maker_fee, taker_fee, created_at,
+                   sequence, is_block_trade, is_rfq
+            FROM trades
+            WHERE market_id::text = $1
+              AND sequence IS NOT NULL
+              AND ($2::bigint IS NULL OR sequence < $2)
+            ORDER BY sequence DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(market_id)
+        .bind(before.map(|seq| seq as i64))
+        .bind((limit - tape.len()) as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        tape.extend(rows.into_iter().map(TradeRecord::from));
+        Ok(tape)
+    }
+
+    /// Fills of one order, oldest first.
+    ///
+    /// Persisted trades carry their settlement and adjustment state; trades
+    /// still only in the engine's market tape (not yet written) are reported
+    /// as pending settlement.
+    pub async fn get_order_fills(&self, market_id: Uuid, order_id: Uuid) -> Result<Vec<OrderFill>, sqlx::Error> {
+        let rows: Vec<FillRow> = sqlx::query_as(
+            r#"
+            SELECT id, COALESCE(adjusted_price, price) AS price, amount,
+                   maker_order_id = $1 AS is_maker,
+                   CASE WHEN maker_order_id = $1 THEN maker_fee ELSE taker_fee END AS fee,
+                   COALESCE(settlement_status::text, 'pending') AS settlement_status,
+                   adjustment_status, created_at
+            FROM trades
+            WHERE maker_order_id = $1 OR taker_order_id = $1
+            "#,
+        )
+        .bind(order_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut fills: Vec<OrderFill> = rows.into_iter().map(OrderFill::from).collect();
+        let persisted: HashSet<Uuid> = fills.iter().map(|f| f.trade_id).collect();
+        let order_id = order_id.to_string();
+        let recent = self.engine.history().get_market_tape(&market_id.to_string(), None, usize::MAX);
+        fills.extend(
+            recent
+                .iter()
+                .filter(|t| t.maker_order_id == order_id || t.taker_order_id == order_id)
+                .filter_map(|t| unpersisted_fill(t, &order_id, &persisted)),
+        );
+        fills.sort_by_key(|f| f.timestamp);
+        Ok(fills)
+    }
+
+    /// A user's orders, most recently created first
+    pub async fn get_orders(&self, user_address: &Address, query: &OrderHistoryQuery) -> Result<OrderHistoryResponse, sqlx::Error> {
+        let history = self.engine.history();
+        let limit = query.get_limit();
+        let mut page = history.get_orders(user_address.as_str(), query);
+
+        let window_start = history.order_window_start(user_address.as_str());
+        if page.orders.len() >= limit || query.after.is_some_and(|after| after >= window_start) {
+            return Ok(page);
+        }
+
+        let seen: HashSet<String> = page.orders.iter().map(|o| o.order_id.clone()).collect();
+        let status = query.status.as_deref().filter(|s| *s != "all");
+        let rows: Vec<OrderRow> = sqlx::query_as(
+            r#"
+            SELECT id::text, user_address, symbol, side::text, order_type::text, price,
+                   amount, filled_amount, status::text, leverage, created_at, updated_at, reject_reason
+            FROM orders
+            WHERE user_address = $1
+              AND ($2::text IS NULL OR status::text = $2)
+              AND ($3::uuid IS NULL OR market_id = $3)
+              AND ($4::text IS NULL OR share_type::text = $4)
+              AND ($5::timestamptz IS NULL OR created_at < $5)
+              AND ($6::timestamptz IS NULL OR created_at > $6)
+            ORDER BY created_at DESC
+            LIMIT $7
+            "#,
+        )
+        .bind(user_address)
+        .bind(status)
+        .bind(query.market_id)
+        .bind(query.share_type.as_deref())
+        .bind(query.before.and_then(DateTime::<Utc>::from_timestamp_millis))
+        .bind(query.after.and_then(DateTime::<Utc>::from_timestamp_millis))
+        .bind((limit + seen.len() + 1) as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Memory holds the latest state of its orders; the database fills in the rest
+        page.orders.extend(rows.into_iter().map(OrderHistoryRecord::from).filter(|o| !seen.contains(&o.order_id)));
+        page.orders.sort_by_key(|o| std::cmp::Reverse(o.created_at));
+        page.has_more = page.orders.len() > limit;
+        page.orders.truncate(limit);
+        page.total_count = page.orders.len();
+        Ok(page)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TradeRow {
+    id: String,
+    market_id: String,
+    outcome_id: String,
+    share_type: String,
+    match_type: String,
+    side: String,
+    price: Decimal,
+    amount: Decimal,
+    maker_order_id: String,
+    taker_order_id: String,
+    maker_address: String,
+    taker_address: String,
+    maker_fee: Decimal,
+    taker_fee: Decimal,
+    created_at: DateTime<Utc>,
+    sequence: Option<i64>,
+    is_block_trade: bool,
+    is_rfq: bool,
+}
+
+impl From<TradeRow> for TradeRecord {
+    fn from(row: TradeRow) -> Self {
+        Self {
+            trade_id: row.id,
+            market_id: row.market_id,
+            outcome_id: row.outcome_id,
+            share_type: row.share_type,
+            match_type: row.match_type,
+            side: row.side,
+            price: row.price.to_string(),
+            amount: row.amount.to_string(),
+            maker_order_id: row.maker_order_id,
+            taker_order_id: row.taker_order_id,
+            maker_address: row.maker_address,
+            taker_address: row.taker_address,
+            maker_fee: row.maker_fee.to_string(),
+            taker_fee: row.taker_fee.to_string(),
+            timestamp: row.created_at.timestamp_millis(),
+            sequence: row.sequence.unwrap_or(0) as u64,
+            is_block_trade: row.is_block_trade,
+            is_rfq: row.is_rfq,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct FillRow {
+    id: Uuid,
+    price: Decimal,
+    amount: Decimal,
+    is_maker: bool,
+    fee: Decimal,
+    settlement_status: String,
+    adjustment_status: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<FillRow> for OrderFill {
+    fn from(row: FillRow) -> Self {
+        Self {
+            trade_id: row.id,
+            price: row.price,
+            amount: row.amount,
+            fee: row.fee,
+            liquidity: if row.is_maker { FillLiquidity::Maker } else { FillLiquidity::Taker },
+            settlement_status: row.settlement_status,
+            adjustment_status: row.adjustment_status,
+            timestamp: row.created_at.timestamp_millis(),
+        }
+    }
+}
+
+/// `order_id`'s side of an in-memory trade not yet in `persisted`
+fn unpersisted_fill(trade: &TradeRecord, order_id: &str, persisted: &HashSet<Uuid>) -> Option<OrderFill> {
+    let trade_id: Uuid = trade.trade_id.parse().ok()?;
+    if persisted.contains(&trade_id) {
+        return None;
+    }
+    let is_maker = trade.maker_order_id == order_id;
+    let fee = if is_maker { &trade.maker_fee } else { &trade.taker_fee };
+    Some(OrderFill {
+        trade_id,
+        price: trade.price.parse().ok()?,
+        amount: trade.amount.parse().ok()?,
+        fee: fee.parse().ok()?,
+        liquidity: if is_maker { FillLiquidity::Maker } else { FillLiquidity::Taker },
+        settlement_status: "pending".to_string(),
+        adjustment_status: None,
+        timestamp: trade.timestamp,
+    })
+}
+
+#[derive(sqlx::FromRow)]
+struct OrderRow {
+    id: String,
+    user_address: String,
+    symbol: String,
+    side: String,
+    order_type: String,
+    price: Option<Decimal>,
+    amount: Decimal,
+    filled_amount: Decimal,
+    status: String,
+    leverage: i32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    reject_reason: Option<String>,
+}
+
+impl From<OrderRow> for OrderHistoryRecord {
+    fn from(row: OrderRow) -> Self {
+        Self {
+            order_id: row.id,
+            user_address: row.user_address,
+            symbol: row.symbol,
+            side: row.side,
+            order_type: row.order_type,
+            price: row.price.unwrap_or(Decimal::ZERO).to_string(),
+            original_amount: row.amount.to_string(),
+            filled_amount: row.filled_amount.to_string(),
+            remaining_amount: (row.amount - row.filled_amount).to_string(),
+            status: row.status,
+            leverage: row.leverage.max(1) as u32,
+            created_at: row.created_at.timestamp_millis(),
+            updated_at: row.updated_at.timestamp_millis(),
+            avg_fill_price: None,
+            trade_ids: Vec::new(),
+            reject_reason: row.reject_reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::services::matching::TradeRecord;
+    use crate::test_support::{matched, TestApp};
+
+    #[tokio::test]
+    async fn test_order_fills() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+        let history = &app.state.history_store;
+
+        let fills = history.get_order_fills(trade.market_id, trade.maker_order_id).await.unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].trade_id, fills[0].price, fills[0].amount), (trade.trade_id, dec!(0.5), dec!(10)));
+        assert_eq!((fills[0].liquidity, fills[0].fee), (FillLiquidity::Maker, trade.maker_fee));
+        assert_eq!(fills[0].settlement_status, "pending");
+
+        app.state.settlement_sender.as_ref().unwrap().send(matched(&trade)).await.unwrap();
+        app.settle_queued().await;
+        let fills = history.get_order_fills(trade.market_id, trade.taker_order_id).await.unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].liquidity, fills[0].fee), (FillLiquidity::Taker, trade.taker_fee));
+        assert_eq!(fills[0].settlement_status, "confirmed");
+
+        // A fill the engine recorded but the writer has not persisted yet
+        let mut unwritten = TradeRecord::from(&trade);
+        unwritten.trade_id = Uuid::new_v4().to_string();
+        unwritten.timestamp += 1;
+        unwritten.sequence += 1;
+        app.state.matching_engine.history().store_trade(unwritten.clone());
+        let fills = history.get_order_fills(trade.market_id, trade.taker_order_id).await.unwrap();
+        assert_eq!(fills.len(), 2);
+        assert_eq!(fills[1].trade_id.to_string(), unwritten.trade_id);
+        assert_eq!(fills[1].settlement_status, "pending");
+    }
+}
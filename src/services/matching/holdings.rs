@@ -0,0 +1,559 @@
+//! Share Holdings Updates
+//!
+//! Applies a trade to `shares` (exposed as `share_holdings`), `balances`,
+//! `outcome_share_supply` and `share_changes` in the caller's transaction:
+//!
+//! | Match  | Taker                       | Maker                             |
+//! |--------|-----------------------------|-----------------------------------|
+//! | Normal | buys or sells at `price`    | opposite side at `price`          |
+//! | Mint   | buys at `price`             | buys complement at `1 - price`    |
+//! | Merge  | sells at `price`            | sells complement at `1 - price`   |
+//!
+//! Buyers reserved `limit * amount` when they placed the order, so a fill
+//! releases that reservation from `frozen` and refunds any price improvement
+//! to `available`; sellers are credited the proceeds. Mints add pairs to the
+//! outcome's supply and merges remove them, after which the Yes and No
+//! outstanding totals are checked against the supply. Both parties' margin
+//! credits on the outcome are then resynced (see
+//! [`crate::services::portfolio_margin`]).
+//!
+//! A [`SetFill`] of a categorical market is applied the same way to the
+//! taker and to the maker of every leg, each on its own outcome's Yes
+//! shares: a mint buys one share per outcome, a merge sells one. The set
+//! is counted in `set_minted`/`set_merged` on every outcome, which only the
+//! Yes supply includes.
+
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::models::market::ShareType;
+use crate::services::portfolio_margin;
+use polymarket_engine::precision::Collateral;
+use polymarket_engine::types::{MatchType, SetFill, TradeEvent};
+
+/// One party's side of a trade
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartyChange<'a> {
+    pub user_address: &'a str,
+    pub order_id: Uuid,
+    pub share_type: ShareType,
+    /// "buy", "sell", "mint" or "merge"
+    pub change_type: &'static str,
+    /// Signed share delta
+    pub shares: Decimal,
+    /// Price this party paid or received per share
+    pub price: Decimal,
+    /// Reservation released from `frozen`
+    pub frozen_release: Decimal,
+    /// Credit to `available` (proceeds or price improvement)
+    pub available_credit: Decimal,
+}
+
+/// Split a trade into the taker's and maker's changes. `taker_limit` and
+/// `maker_limit` are the order prices buyers reserved collateral at.
+pub fn party_changes<'a>(
+    trade: &'a TradeEvent,
+    taker_limit: Decimal,
+    maker_limit: Decimal,
+) -> [PartyChange<'a>; 2] {
+    let taker_buys = trade.side.eq_ignore_ascii_case("buy");
+    let complement_price = Decimal::ONE - trade.price;
+
+    let (taker_buys, maker_buys, maker_share_type, maker_price, change_types) = match trade.match_type {
+        MatchType::Normal => (
+            taker_buys,
+            !taker_buys,
+            trade.share_type,
+            trade.price,
+            if taker_buys { ("buy", "sell") } else { ("sell", "buy") },
+        ),
+        MatchType::Mint => (true, true, trade.share_type.complement(), complement_price, ("mint", "mint")),
+        MatchType::Merge => (false, false, trade.share_type.complement(), complement_price, ("merge", "merge")),
+    };
+
+    [
+        party_change(
+            &trade.taker_address,
+            trade.taker_order_id,
+            trade.share_type,
+            change_types.0,
+            taker_buys,
+            trade.price,
+            taker_limit,
+            trade.amount,
+        ),
+        party_change(
+            &trade.maker_address,
+            trade.maker_order_id,
+            maker_share_type,
+            change_types.1,
+            maker_buys,
+            maker_price,
+            maker_limit,
+            trade.amount,
+        ),
+    ]
+}
+
+/// Split a set fill into the taker's change and one per leg, each with the
+/// outcome it holds. `taker_limit` and `maker_limits` (in leg order) are
+/// the order prices buyers reserved collateral at.
+pub fn set_party_changes<'a>(
+    fill: &'a SetFill,
+    taker_limit: Decimal,
+    maker_limits: &[Decimal],
+) -> Vec<(Uuid, PartyChange<'a>)> {
+    let (buys, change_type) = match fill.match_type {
+        MatchType::Merge => (false, "merge"),
+        MatchType::Mint | MatchType::Normal => (true, "mint"),
+    };
+    let taker = party_change(
+        &fill.taker_address,
+        fill.taker_order_id,
+        ShareType::Yes,
+        change_type,
+        buys,
+        fill.price,
+        taker_limit,
+        fill.amount,
+    );
+    let legs = fill.legs.iter().zip(maker_limits).map(|(leg, &limit)| {
+        let maker = party_change(
+            &leg.maker_address,
+            leg.maker_order_id,
+            ShareType::Yes,
+            change_type,
+            buys,
+            leg.price,
+            limit,
+            fill.amount,
+        );
+        (leg.outcome_id, maker)
+    });
+    std::iter::once((fill.taker_outcome_id, taker)).chain(legs).collect()
+}
+
+/// One party buying or selling `amount` shares at `price`
+#[allow(clippy::too_many_arguments)]
+fn party_change<'a>(
+    user_address: &'a str,
+    order_id: Uuid,
+    share_type: ShareType,
+    change_type: &'static str,
+    buys: bool,
+    price: Decimal,
+    limit: Decimal,
+    amount: Decimal,
+) -> PartyChange<'a> {
+    let cost = Collateral::notional(price, amount);
+    if buys {
+        let reserved = Collateral::notional(limit, amount);
+        PartyChange {
+            user_address,
+            order_id,
+            share_type,
+            change_type,
+            shares: amount,
+            price,
+            frozen_release: reserved.value(),
+            available_credit: (reserved - cost).value(),
+        }
+    } else {
+        PartyChange {
+            user_address,
+            order_id,
+            share_type,
+            change_type,
+            shares: -amount,
+            price,
+            frozen_release: Decimal::ZERO,
+            available_credit: cost.value(),
+        }
+    }
+}
+
+/// Apply `trade` to holdings, balances and supply, then check the supply
+/// invariant for its outcome
+pub async fn apply_trade(
+    conn: &mut PgConnection,
+    trade: &TradeEvent,
+    collateral_token: &str,
+) -> Result<(), sqlx::Error> {
+    let taker_limit = order_price(conn, trade.taker_order_id).await?;
+    let maker_limit = order_price(conn, trade.maker_order_id).await?;
+    let complement_price = Decimal::ONE - trade.price;
+    let [taker, maker] = party_changes(
+        trade,
+        taker_limit.unwrap_or(trade.price),
+        maker_limit.unwrap_or(match trade.match_type {
+            MatchType::Normal => trade.price,
+            MatchType::Mint | MatchType::Merge => complement_price,
+        }),
+    );
+
+    for party in [&taker, &maker] {
+        apply_party(conn, trade.market_id, trade.outcome_id, trade.trade_id, party, collateral_token).await?;
+    }
+
+    let (minted, merged) = match trade.match_type {
+        MatchType::Normal => (Decimal::ZERO, Decimal::ZERO),
+        MatchType::Mint => (trade.amount, Decimal::ZERO),
+        MatchType::Merge => (Decimal::ZERO, trade.amount),
+    };
+    update_supply(conn, trade.market_id, trade.outcome_id, minted, merged, Decimal::ZERO, Decimal::ZERO).await?;
+    check_supply_invariant(conn, trade.market_id, trade.outcome_id).await?;
+
+    for party in [&taker, &maker] {
+        portfolio_margin::resync(conn, party.user_address, trade.market_id, trade.outcome_id, collateral_token).await?;
+    }
+    Ok(())
+}
+
+/// Apply `fill` to holdings, balances and supply for the taker and every
+/// leg, then check the supply invariant of each outcome of the set
+pub async fn apply_set_fill(
+    conn: &mut PgConnection,
+    fill: &SetFill,
+    collateral_token: &str,
+) -> Result<(), sqlx::Error> {
+    let taker_limit = order_price(conn, fill.taker_order_id).await?.unwrap_or(fill.price);
+    let mut maker_limits = Vec::with_capacity(fill.legs.len());
+    for leg in &fill.legs {
+        maker_limits.push(order_price(conn, leg.maker_order_id).await?.unwrap_or(leg.price));
+    }
+    let parties = set_party_changes(fill, taker_limit, &maker_limits);
+
+    for (outcome_id, party) in &parties {
+        apply_party(conn, fill.market_id, *outcome_id, fill.set_id, party, collateral_token).await?;
+    }
+
+    let (minted, merged) = match fill.match_type {
+        MatchType::Merge => (Decimal::ZERO, fill.amount),
+        MatchType::Mint | MatchType::Normal => (fill.amount, Decimal::ZERO),
+    };
+    for (outcome_id, _) in &parties {
+        update_set_supply(conn, fill.market_id, *outcome_id, minted, merged).await?;
+        check_supply_invariant(conn, fill.market_id, *outcome_id).await?;
+    }
+
+    for (outcome_id, party) in &parties {
+        portfolio_margin::resync(conn, party.user_address, fill.market_id, *outcome_id, collateral_token).await?;
+    }
+    Ok(())
+}
+
+/// Record pairs a holder merged back into collateral against the outcome's
+/// supply
+pub async fn record_merge(
+    conn: &mut PgConnection,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    pairs: Decimal,
+) -> Result<(), sqlx::Error> {
+    update_supply(conn, market_id, outcome_id, Decimal::ZERO, pairs, Decimal::ZERO, Decimal::ZERO).await?;
+    check_supply_invariant(conn, market_id, outcome_id).await
+}
+
+/// Record shares redeemed at settlement against the outcome's supply
+pub async fn record_redemption(
+    conn: &mut PgConnection,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    share_type: ShareType,
+    amount: Decimal,
+) -> Result<(), sqlx::Error> {
+    let (yes, no) = match share_type {
+        ShareType::Yes => (amount, Decimal::ZERO),
+        ShareType::No => (Decimal::ZERO, amount),
+    };
+    update_supply(conn, market_id, outcome_id, Decimal::ZERO, Decimal::ZERO, yes, no).await
+}
+
+/// Take the pairs a busted mint added (or give back those a busted merge
+/// removed) out of the outcome's supply, then check the supply invariant
+pub async fn reverse_pairs(
+    conn: &mut PgConnection,
+    trade: &TradeEvent,
+) -> Result<(), sqlx::Error> {
+    let (minted, merged) = match trade.match_type {
+        MatchType::Normal => (Decimal::ZERO, Decimal::ZERO),
+        MatchType::Mint => (-trade.amount, Decimal::ZERO),
+        MatchType::Merge => (Decimal::ZERO, -trade.amount),
+    };
+    update_supply(conn, trade.market_id, trade.outcome_id, minted, merged, Decimal::ZERO, Decimal::ZERO).await?;
+    check_supply_invariant(conn, trade.market_id, trade.outcome_id).await
+}
+
+/// Price the order reserved collateral at, if it has been persisted
+async fn order_price(conn: &mut PgConnection, order_id: Uuid) -> Result<Option<Decimal>, sqlx::Error> {
+    let price: Option<Option<Decimal>> = sqlx::query_scalar("SELECT price FROM orders WHERE id = $1")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(price.flatten())
+}
+
+async fn apply_party(
+    conn: &mut PgConnection,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    trade_id: Uuid,
+    party: &PartyChange<'_>,
+    collateral_token: &str,
+) -> Result<(), sqlx::Error> {
+    // Average cost only moves on buys into a long position
+    sqlx::query(
+        r#"
+        INSERT INTO shares (user_address, market_id, outcome_id, share_type, amount, avg_cost)
+        VALUES ($1, $2, $3, $4::share_type, $5, $6)
+        ON CONFLICT (user_address, outcome_id, share_type) DO UPDATE SET
+            amount = shares.amount + $5,
+            avg_cost = CASE
+                WHEN $5 <= 0 THEN shares.avg_cost
+                WHEN shares.amount <= 0 THEN $6
+                ELSE (shares.avg_cost * shares.amount + $6 * $5) / (shares.amount + $5)
+            END,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(party.user_address)
+    .bind(market_id)
+    .bind(outcome_id)
+    .bind(party.share_type.to_string())
+    .bind(party.shares)
+    .bind(party.price)
+    .execute(&mut *conn)
+    .await?;
+
+    if !party.frozen_release.is_zero() || !party.available_credit.is_zero() {
+        sqlx::query(
+            r#"
+            INSERT INTO balances (user_address, token, available, frozen, updated_at)
+            VALUES ($1, $2, $3, -$4, NOW())
+            ON CONFLICT (user_address, token) DO UPDATE SET
+                available = balances.available + $3,
+                frozen = balances.frozen - $4,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(party.user_address)
+        .bind(collateral_token)
+        .bind(party.available_credit)
+        .bind(party.frozen_release)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO share_changes (
+            user_address, market_id, outcome_id, share_type,
+            change_type, amount, price, trade_id, order_id
+        )
+        VALUES ($1, $2, $3, $4::share_type, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(party.user_address)
+    .bind(market_id)
+    .bind(outcome_id)
+    .bind(party.share_type.to_string())
+    .bind(party.change_type)
+    .bind(party.shares)
+    .bind(party.price)
+    .bind(trade_id)
+    .bind(party.order_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn update_supply(
+    conn: &mut PgConnection,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    minted: Decimal,
+    merged: Decimal,
+    yes_redeemed: Decimal,
+    no_redeemed: Decimal,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO outcome_share_supply (outcome_id, market_id, minted, merged, yes_redeemed, no_redeemed)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (outcome_id) DO UPDATE SET
+            minted = outcome_share_supply.minted + $3,
+            merged = outcome_share_supply.merged + $4,
+            yes_redeemed = outcome_share_supply.yes_redeemed + $5,
+            no_redeemed = outcome_share_supply.no_redeemed + $6,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(outcome_id)
+    .bind(market_id)
+    .bind(minted)
+    .bind(merged)
+    .bind(yes_redeemed)
+    .bind(no_redeemed)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn update_set_supply(
+    conn: &mut PgConnection,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    minted: Decimal,
+    merged: Decimal,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO outcome_share_supply (outcome_id, market_id, set_minted, set_merged)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (outcome_id) DO UPDATE SET
+            set_minted = outcome_share_supply.set_minted + $3,
+            set_merged = outcome_share_supply.set_merged + $4,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(outcome_id)
+    .bind(market_id)
+    .bind(minted)
+    .bind(merged)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Yes and No outstanding must each equal the pairs still in circulation;
+/// the complete sets of a categorical market add to Yes only.
+/// A mismatch is reported rather than rolled back: the trade has already
+/// happened in the engine, and refusing to record it would only widen the gap.
+async fn check_supply_invariant(conn: &mut PgConnection, market_id: Uuid, outcome_id: Uuid) -> Result<(), sqlx::Error> {
+    let (yes_outstanding, no_outstanding, yes_expected, no_expected): (Decimal, Decimal, Decimal, Decimal) =
+        sqlx::query_as(
+            r#"
+            SELECT
+                COALESCE((SELECT SUM(amount) FROM shares WHERE outcome_id = $1 AND share_type = 'yes'), 0),
+                COALESCE((SELECT SUM(amount) FROM shares WHERE outcome_id = $1 AND share_type = 'no'), 0),
+                COALESCE((SELECT minted + set_minted - merged - set_merged - yes_redeemed
+                          FROM outcome_share_supply WHERE outcome_id = $1), 0),
+                COALESCE((SELECT minted - merged - no_redeemed FROM outcome_share_supply WHERE outcome_id = $1), 0)
+            "#,
+        )
+        .bind(outcome_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    if yes_outstanding != yes_expected || no_outstanding != no_expected {
+        error!(
+            "Share supply invariant violated for outcome {}: yes {} (expected {}), no {} (expected {})",
+            outcome_id, yes_outstanding, yes_expected, no_outstanding, no_expected
+        );
+        crate::metrics::record_holdings_invariant_violation(&market_id.to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn trade(match_type: MatchType, side: &str) -> TradeEvent {
+        TradeEvent {
+            symbol: String::new(),
+            market_id: Uuid::new_v4(),
+            outcome_id: Uuid::new_v4(),
+            share_type: ShareType::Yes,
+            match_type,
+            trade_id: Uuid::new_v4(),
+            maker_order_id: Uuid::new_v4(),
+            taker_order_id: Uuid::new_v4(),
+            maker_address: "0xmaker".to_string(),
+            taker_address: "0xtaker".to_string(),
+            side: side.to_string(),
+            price: dec!(0.6),
+            amount: dec!(10),
+            maker_fee: Decimal::ZERO,
+            taker_fee: Decimal::ZERO,
+            timestamp: 0,
+            sequence: 0,
+            is_block_trade: false,
+            is_rfq: false,
+            market_sequence: 0,
+        }
+    }
+
+    #[test]
+    fn test_normal_buy_refunds_price_improvement() {
+        let t = trade(MatchType::Normal, "buy");
+        let [taker, maker] = party_changes(&t, dec!(0.65), dec!(0.6));
+
+        assert_eq!((taker.shares, taker.change_type), (dec!(10), "buy"));
+        assert_eq!((taker.frozen_release, taker.available_credit), (dec!(6.5), dec!(0.5)));
+        assert_eq!((maker.shares, maker.change_type), (dec!(-10), "sell"));
+        assert_eq!((maker.frozen_release, maker.available_credit), (Decimal::ZERO, dec!(6)));
+    }
+
+    #[test]
+    fn test_mint_and_merge_use_complement_side() {
+        let t = trade(MatchType::Mint, "buy");
+        let [taker, maker] = party_changes(&t, dec!(0.6), dec!(0.45));
+        assert_eq!((taker.share_type, maker.share_type), (ShareType::Yes, ShareType::No));
+        assert_eq!(maker.price, dec!(0.4));
+        // Maker bid 0.45 but only pays the complement 0.40
+        assert_eq!((maker.frozen_release, maker.available_credit), (dec!(4.5), dec!(0.5)));
+        assert_eq!(maker.shares + taker.shares, dec!(20));
+
+        let t = trade(MatchType::Merge, "sell");
+        let [taker, maker] = party_changes(&t, dec!(0.6), dec!(0.4));
+        assert_eq!((taker.shares, maker.shares), (dec!(-10), dec!(-10)));
+        // Both sides together receive one unit of collateral per pair
+        assert_eq!(taker.available_credit + maker.available_credit, dec!(10));
+    }
+
+    #[test]
+    fn test_set_fill_moves_one_share_of_every_outcome() {
+        let leg = |price| polymarket_engine::types::SetLeg {
+            symbol: String::new(),
+            outcome_id: Uuid::new_v4(),
+            maker_order_id: Uuid::new_v4(),
+            maker_address: "0xmaker".to_string(),
+            price,
+            maker_fee: Decimal::ZERO,
+        };
+        let mut fill = SetFill {
+            set_id: Uuid::new_v4(),
+            market_id: Uuid::new_v4(),
+            match_type: MatchType::Mint,
+            taker_order_id: Uuid::new_v4(),
+            taker_address: "0xtaker".to_string(),
+            taker_outcome_id: Uuid::new_v4(),
+            price: dec!(0.35),
+            amount: dec!(10),
+            taker_fee: Decimal::ZERO,
+            legs: vec![leg(dec!(0.35)), leg(dec!(0.3))],
+            timestamp: 0,
+        };
+
+        let parties = set_party_changes(&fill, dec!(0.4), &[dec!(0.35), dec!(0.3)]);
+        let outcomes: Vec<Uuid> = parties.iter().map(|(outcome, _)| *outcome).collect();
+        assert_eq!(outcomes, [fill.taker_outcome_id, fill.legs[0].outcome_id, fill.legs[1].outcome_id]);
+        assert!(parties
+            .iter()
+            .all(|(_, p)| p.shares == dec!(10) && p.share_type == ShareType::Yes && p.change_type == "mint"));
+        // The taker bid 0.40 and pays 0.35; the three pay one unit per set
+        assert_eq!((parties[0].1.frozen_release, parties[0].1.available_credit), (dec!(4), dec!(0.5)));
+        let paid: Decimal = parties.iter().map(|(_, p)| p.frozen_release - p.available_credit).sum();
+        assert_eq!(paid, dec!(10));
+
+        fill.match_type = MatchType::Merge;
+        let parties = set_party_changes(&fill, dec!(0.35), &[dec!(0.35), dec!(0.3)]);
+        assert!(parties.iter().all(|(_, p)| p.shares == dec!(-10) && p.change_type == "merge"));
+        assert_eq!(parties.iter().map(|(_, p)| p.available_credit).sum::<Decimal>(), dec!(10));
+    }
+}
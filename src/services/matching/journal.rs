@@ -0,0 +1,339 @@
+//! Order Journal
+//!
+//! Write-behind log of the books. The engine sends every change to a resting
+//! order once it has made it (see [`MatchingEngine::attach_journal`]) and the
+//! writer appends them to `order_journal` in that order, in batches, retrying
+//! until Postgres takes them so no change is reordered. On startup
+//! [`super::recovery`] replays the latest entry of each order instead of
+//! re-submitting the orders table, so partial fills, amended sizes and queue
+//! priority come back as they were.
+//!
+//! A crash loses the changes still on their way to Postgres, so replay
+//! reconciles each order with its persisted trades: fills the journal had
+//! not caught up with still come off the restored size.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use polymarket_engine::{MatchingEngine, OrderEvent};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::models::Address;
+
+/// Most changes appended in one insert
+const MAX_BATCH: usize = 500;
+
+/// Longest wait between attempts while Postgres refuses a batch
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// How often entries of orders that left the book are removed
+const COMPACT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// The last journaled state of a resting order
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct JournaledOrder {
+    pub order_id: Uuid,
+    pub symbol: String,
+    pub user_address: Address,
+    pub side: String,
+    pub price: Decimal,
+    pub original_amount: Decimal,
+    pub remaining_amount: Decimal,
+    pub priority: i64,
+    /// Journal position at which the order took its place in the queue
+    pub queued_seq: i64,
+}
+
+/// Appends the engine's book changes to `order_journal`
+pub struct OrderJournal {
+    pool: PgPool,
+    engine: Arc<MatchingEngine>,
+    written: AtomicU64,
+}
+
+impl OrderJournal {
+    pub fn new(pool: PgPool, engine: Arc<MatchingEngine>) -> Self {
+        Self {
+            pool,
+            engine,
+            written: AtomicU64::new(0),
+        }
+    }
+
+    /// Attach to the engine and spawn the writer and the compaction loop.
+    /// Does nothing if the engine already has a journal.
+    pub fn start(self: Arc<Self>) {
+        let Some(changes) = self.engine.attach_journal() else {
+            tracing::warn!("Matching engine already has an order journal");
+            return;
+        };
+        let writer = self.clone();
+        tokio::spawn(async move { writer.run(changes).await });
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(COMPACT_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = compact(&self.pool).await {
+                    tracing::warn!("Order journal compaction failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Wait until every change the engine has made so far is written, or
+    /// `timeout` passes. Returns whether the journal caught up.
+    pub async fn flush(&self, timeout: Duration) -> bool {
+        let target = self.engine.journaled();
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.written.load(Ordering::Acquire) < target {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        true
+    }
+
+    async fn run(&self, mut changes: mpsc::UnboundedReceiver<OrderEvent>) {
+        tracing::info!("Order journal writer started");
+        let mut batch = Vec::with_capacity(MAX_BATCH);
+        while changes.recv_many(&mut batch, MAX_BATCH).await > 0 {
+            let mut delay = Duration::from_millis(100);
+            // Later changes must not overtake this batch, so keep retrying it
+            while let Err(e) = append(&self.pool, &batch).await {
+                tracing::error!("Failed to journal {} order changes, retrying: {}", batch.len(), e);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+            self.written.fetch_add(batch.len() as u64, Ordering::AcqRel);
+            batch.clear();
+        }
+    }
+}
+
+/// Append `changes` in order
+pub async fn append(pool: &PgPool, changes: &[OrderEvent]) -> Result<(), sqlx::Error> {
+    let mut order_ids = Vec::with_capacity(changes.len());
+    let mut kinds = Vec::with_capacity(changes.len());
+    let mut symbols = Vec::with_capacity(changes.len());
+    let mut users = Vec::with_capacity(changes.len());
+    let mut sides = Vec::with_capacity(changes.len());
+    let mut prices = Vec::with_capacity(changes.len());
+    let mut original_amounts = Vec::with_capacity(changes.len());
+    let mut remaining_amounts = Vec::with_capacity(changes.len());
+    let mut statuses = Vec::with_capacity(changes.len());
+    let mut priorities = Vec::with_capacity(changes.len());
+    let mut trade_ids = Vec::with_capacity(changes.len());
+    for change in changes {
+        let user = match change.user_address.parse::<Address>() {
+            Ok(user) => user,
+            Err(e) => {
+                tracing::error!("Order change of {} not journaled: {}", change.order_id, e);
+                continue;
+            }
+        };
+        order_ids.push(change.order_id);
+        kinds.push(format!("{:?}", change.kind).to_lowercase());
+        symbols.push(change.symbol.clone());
+        users.push(user);
+        sides.push(change.side.to_string());
+        prices.push(change.price.unwrap_or_default());
+        original_amounts.push(change.original_amount);
+        remaining_amounts.push(change.remaining_amount);
+        statuses.push(change.status.to_string());
+        priorities.push(change.created_at);
+        trade_ids.push(change.trade_id);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO order_journal (
+            order_id, kind, symbol, user_address, side, price,
+            original_amount, remaining_amount, status, priority, trade_id
+        )
+        SELECT order_id, kind, symbol, user_address, side, price,
+               original_amount, remaining_amount, status, priority, trade_id
+        FROM UNNEST(
+            $1::uuid[], $2::text[], $3::text[], $4::text[], $5::text[], $6::numeric[],
+            $7::numeric[], $8::numeric[], $9::text[], $10::bigint[], $11::uuid[]
+        ) WITH ORDINALITY AS c(
+            order_id, kind, symbol, user_address, side, price,
+            original_amount, remaining_amount, status, priority, trade_id, n
+        )
+        ORDER BY n
+        "#,
+    )
+    .bind(&order_ids)
+    .bind(&kinds)
+    .bind(&symbols)
+    .bind(&users)
+    .bind(&sides)
+    .bind(&prices)
+    .bind(&original_amounts)
+    .bind(&remaining_amounts)
+    .bind(&statuses)
+    .bind(&priorities)
+    .bind(&trade_ids)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Orders whose latest journal entry leaves them resting, in the order
+/// they queue (book timestamp, then journal position). The remaining size
+/// is the smaller of the journal's and what the order's persisted trades
+/// and complete sets leave. Orders the database no longer has open (e.g.
+/// cancelled while this process was down) or whose fills fill them are
+/// left out.
+pub async fn resting_orders(pool: &PgPool) -> Result<Vec<JournaledOrder>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT latest.order_id, latest.symbol, latest.user_address, latest.side, latest.price,
+               latest.original_amount, reconciled.remaining_amount, latest.priority, latest.queued_seq
+        FROM (
+            SELECT DISTINCT ON (order_id)
+                   order_id, symbol, user_address, side, price, original_amount,
+                   remaining_amount, status, priority,
+                   MIN(seq) OVER (PARTITION BY order_id, priority) AS queued_seq
+            FROM order_journal
+            ORDER BY order_id, seq DESC
+        ) latest
+        JOIN orders o ON o.id = latest.order_id
+        CROSS JOIN LATERAL (
+            SELECT LEAST(
+                       latest.remaining_amount,
+                       latest.original_amount
+                           - COALESCE((
+                                 SELECT SUM(t.amount) FROM trades t
+                                 WHERE t.maker_order_id = latest.order_id OR t.taker_order_id = latest.order_id
+                             ), 0)
+                           - COALESCE((
+                                 SELECT SUM(s.amount) FROM set_fills s
+                                 WHERE s.taker_order_id = latest.order_id
+                                    OR EXISTS (
+                                        SELECT 1 FROM set_fill_legs l
+                                        WHERE l.set_fill_id = s.id AND l.maker_order_id = latest.order_id
+                                    )
+                             ), 0)
+                   ) AS remaining_amount
+        ) reconciled
+        WHERE latest.status IN ('open', 'partially_filled')
+          AND reconciled.remaining_amount > 0
+          AND o.status IN ('pending', 'accepted', 'open', 'partially_filled')
+        ORDER BY latest.priority, latest.queued_seq
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Remove the entries of orders whose latest entry took them off the book.
+/// Returns the number of entries removed.
+pub async fn compact(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let removed = sqlx::query(
+        r#"
+        DELETE FROM order_journal j
+        USING (
+            SELECT DISTINCT ON (order_id) order_id, seq, status
+            FROM order_journal
+            ORDER BY order_id, seq DESC
+        ) latest
+        WHERE j.order_id = latest.order_id
+          AND j.seq <= latest.seq
+          AND latest.status NOT IN ('open', 'partially_filled')
+        "#,
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+    if removed > 0 {
+        tracing::info!("Compacted {} order journal entries", removed);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market::ShareType;
+    use crate::models::{OrderSide, OrderType, TimeInForce};
+    use crate::services::matching::{restore_orders, OrderIntent, SelfTradePrevention};
+    use crate::services::order_gateway::OrderSource;
+    use crate::test_support::TestApp;
+    use rust_decimal_macros::dec;
+
+    const FIRST: &str = "0x6666666666666666666666666666666666666666";
+    const SECOND: &str = "0x7777777777777777777777777777777777777777";
+    const SELLER: &str = "0x8888888888888888888888888888888888888888";
+
+    fn limit(market_id: Uuid, outcome_id: Uuid, user: &str, side: OrderSide, amount: Decimal) -> OrderIntent {
+        OrderIntent {
+            source: OrderSource::Api,
+            user_address: user.to_string(),
+            market_id,
+            outcome_id,
+            share_type: ShareType::Yes,
+            side,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            stp: SelfTradePrevention::default(),
+            price: dec!(0.4),
+            amount,
+            expires_at: None,
+            signature: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_restores_fills_and_priority() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.deposit(FIRST, dec!(100)).await;
+        app.deposit(SECOND, dec!(100)).await;
+        app.grant_shares(SELLER, market_id, yes, ShareType::Yes, dec!(4)).await;
+        let journal = Arc::new(OrderJournal::new(app.db.pool.clone(), app.state.matching_engine.clone()));
+        journal.clone().start();
+
+        let flow = &app.state.order_flow;
+        let first = flow.place(&limit(market_id, yes, FIRST, OrderSide::Buy, dec!(10))).await.unwrap();
+        let second = flow.place(&limit(market_id, yes, SECOND, OrderSide::Buy, dec!(10))).await.unwrap();
+        flow.place(&limit(market_id, yes, SELLER, OrderSide::Sell, dec!(4))).await.unwrap();
+        assert!(journal.flush(Duration::from_secs(5)).await);
+
+        let restarted = MatchingEngine::new();
+        let report = restore_orders(&restarted, &app.db.pool).await.unwrap();
+        assert_eq!(report.restored_count(), 2);
+        let key = format!("{}:{}:yes", market_id, yes);
+        let book = restarted.get_orderbook_ref(&key).unwrap();
+        let queued = book.orders();
+        assert_eq!(
+            queued.iter().map(|o| (o.id, o.remaining_amount)).collect::<Vec<_>>(),
+            [(first.order_id, dec!(6)), (second.order_id, dec!(10))]
+        );
+        assert_eq!(book.queue_position(&first.order_id).unwrap().position, 1);
+
+        // A fill the journal lost in a crash still comes off, by its trade
+        sqlx::query(
+            "DELETE FROM order_journal WHERE seq = (SELECT MAX(seq) FROM order_journal WHERE order_id = $1)",
+        )
+        .bind(first.order_id)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+        let restarted = MatchingEngine::new();
+        restore_orders(&restarted, &app.db.pool).await.unwrap();
+        let book = restarted.get_orderbook_ref(&key).unwrap();
+        assert_eq!(book.orders()[0].remaining_amount, dec!(6));
+
+        // Once off the book an order's entries are compacted away
+        app.state.matching_engine.cancel_order(&key, second.order_id, SECOND).unwrap();
+        assert!(journal.flush(Duration::from_secs(5)).await);
+        assert_eq!(compact(&app.db.pool).await.unwrap(), 2);
+    }
+}
@@ -1,245 +1,1319 @@
 //! Order Flow Orchestrator
 //!
-//! Orchestrates the complete order processing flow for prediction markets:
-//! 1. Receive order from API
-//! 2. Execute matching via MatchingEngine
-//! 3. Process match results (including Mint/Merge logic)
-//! 4. Update share positions
-//! 5. Persist to database asynchronously
-//! 6. Broadcast updates via WebSocket
-
-#![allow(dead_code)]
-
-use super::engine::MatchingEngine;
-use super::types::*;
-use crate::models::market::ShareType;
-use rust_decimal::Decimal;
-use sqlx::PgPool;
+//! Places an order as an explicit saga:
+//!
+//! ```text
+//! validate → reserve → match → persist → notify → settle-enqueue
+//! ```
+//!
+//! | Step           | Does                                                      | Compensation                    |
+//! |----------------|-----------------------------------------------------------|---------------------------------|
+//! | validate       | price, amount, market status/hours, precision, admission  | -                               |
+//! | reserve        | freeze margined collateral, check a sell's holdings       | release the unfilled collateral |
+//! | match          | submit to the engine on the market's shard                | cancel the resting remainder    |
+//! | persist        | insert the order row                                      | -                               |
+//! | notify         | webhooks, notifications and channel events for the fills  | -                               |
+//! | settle-enqueue | hand the fills to the write batcher                       | -                               |
+//!
+//! In a categorical market an order can also fill in complete sets across
+//! the other outcomes' books ([`SetFill`]); settle-enqueue writes those
+//! with their legs straight away (see [`WriteBatcher::persist_set_fills`]).
+//!
+//! When a step fails, the steps already completed are compensated in
+//! reverse order, so a failed order leaves no collateral frozen and nothing
+//! resting on the book. Fills are the exception: once the engine has
+//! matched, the makers' orders are filled, so fills go to settlement even
+//! when persisting the taker's order failed. Orders refused at reserve or
+//! match are recorded as rejected with their reason.
+//!
+//! A batch runs its orders through the saga one after another. An atomic
+//! batch skips the saga: it holds post-only GTC limit orders of one market
+//! that must all rest, and is refused whole otherwise. They are validated,
+//! reserved and written in one transaction; once it commits they are put
+//! on the book in one step on the market's shard, so nothing trades
+//! against them before the batch is whole. An order that would trade
+//! refuses the batch; the step takes the orders it already rested off
+//! again and the committed rows and reserves are unwound.
+//!
+//! Every step records its outcome and duration (`order_flow_steps_total`,
+//! `order_flow_step_duration_seconds`).
+//!
+//! Collateral is frozen net of the account's portfolio margin credit on the
+//! outcome (see [`crate::services::portfolio_margin`]); every release
+//! resyncs that credit.
+
+use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::broadcast;
-use tracing::{debug, error, info, warn};
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use polymarket_engine::types::*;
+use polymarket_engine::EngineShards;
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use tracing::debug;
 use uuid::Uuid;
 
-/// Order flow orchestrator for prediction markets
-///
-/// Connects matching engine with database persistence and WebSocket broadcasting.
-/// All database operations are async and non-blocking.
-pub struct OrderFlowOrchestrator {
-    /// The matching engine
-    engine: Arc<MatchingEngine>,
+use super::holdings;
+use super::precision::{Collateral, SharePrecision};
+use crate::models::market::ShareType as MarketShareType;
+use crate::models::{OrderSide, OrderStatus, OrderType as ModelOrderType, TimeInForce as ModelTimeInForce};
+use crate::services::channel_gateway::{ChannelEventType, ChannelGateway};
+use crate::services::notification::{NotificationKind, NotificationService};
+use crate::services::order_gateway::OrderSource;
+use crate::services::portfolio_margin::{self, MarginError, NewOrder};
+use crate::services::trading_calendar;
+use crate::services::webhook::{WebhookEventType, WebhookService};
+use crate::services::write_batcher::WriteBatcher;
 
-    /// Database connection pool
-    pool: PgPool,
+/// A step of the order flow saga
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowStep {
+    Validate,
+    Reserve,
+    Match,
+    Persist,
+    Notify,
+    SettleEnqueue,
+}
 
-    /// Trade event receiver for persistence
-    trade_receiver: Option<broadcast::Receiver<TradeEvent>>,
+impl FlowStep {
+    /// Value of the `step` metrics label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlowStep::Validate => "validate",
+            FlowStep::Reserve => "reserve",
+            FlowStep::Match => "match",
+            FlowStep::Persist => "persist",
+            FlowStep::Notify => "notify",
+            FlowStep::SettleEnqueue => "settle_enqueue",
+        }
+    }
 }
 
-impl OrderFlowOrchestrator {
-    /// Create a new orchestrator
-    pub fn new(engine: Arc<MatchingEngine>, pool: PgPool) -> Self {
-        let trade_receiver = Some(engine.subscribe_trades());
+/// An order to place
+#[derive(Debug, Clone)]
+pub struct OrderIntent {
+    pub source: OrderSource,
+    pub user_address: String,
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: MarketShareType,
+    pub side: OrderSide,
+    pub order_type: ModelOrderType,
+    pub time_in_force: ModelTimeInForce,
+    /// Rejected rather than matched if it would trade on arrival
+    pub post_only: bool,
+    /// What happens if it meets the account's own resting orders
+    pub stp: SelfTradePrevention,
+    pub price: Decimal,
+    pub amount: Decimal,
+    /// When a resting remainder expires (good-til-date); `None` rests
+    /// until cancelled
+    pub expires_at: Option<DateTime<Utc>>,
+    /// EIP-712 signature of user orders; empty for internal orders
+    pub signature: String,
+}
+
+/// An order that went through the saga
+#[derive(Debug, Clone)]
+pub struct PlacedOrder {
+    pub order_id: Uuid,
+    pub status: OrderStatus,
+    /// Size of the order once self-trade prevention took its part
+    pub amount: Decimal,
+    pub filled_amount: Decimal,
+    /// Fills of the order, already persisted (or queued for retry)
+    pub trades: Vec<TradeEvent>,
+    /// Complete sets the order minted or merged, persisted like `trades`
+    pub set_fills: Vec<SetFill>,
+    /// Where the unfilled remainder rests, if it does
+    pub queue_position: Option<QueuePosition>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What became of one order of a batch
+#[derive(Debug)]
+pub enum BatchPlacement {
+    Placed(PlacedOrder),
+    Failed(OrderFlowError),
+    /// Not placed because another order of its atomic batch failed
+    RolledBack,
+}
+
+/// A change to a resting order's price and total size
+#[derive(Debug, Clone)]
+pub struct OrderAmendment {
+    pub order_id: Uuid,
+    pub user_address: String,
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: MarketShareType,
+    pub side: OrderSide,
+    /// Terms the order rests at, as stored
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub filled_amount: Decimal,
+    /// Terms it should rest at; `new_amount` includes what has filled
+    pub new_price: Decimal,
+    pub new_amount: Decimal,
+}
+
+/// A resting order after its amendment
+#[derive(Debug, Clone)]
+pub struct AmendedOrder {
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub filled_amount: Decimal,
+    /// Whether it kept its place in its price level's queue
+    pub kept_priority: bool,
+    pub queue_position: Option<QueuePosition>,
+}
+
+/// Caps on resting orders per market; 0 leaves a cap off
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenOrderCaps {
+    /// Open orders one account may have in a market
+    pub per_account: i64,
+    /// Open orders across all accounts in a market
+    pub per_market: i64,
+}
+
+impl OpenOrderCaps {
+    pub fn from_config(config: &crate::config::AppConfig) -> Self {
+        Self {
+            per_account: config.max_open_orders_per_account_market,
+            per_market: config.max_open_orders_per_market,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OrderFlowError {
+    #[error("Market not found")]
+    MarketNotFound,
+
+    #[error("Market is {0}")]
+    MarketNotActive(String),
+
+    #[error("Market is outside its trading hours")]
+    OutsideTradingHours { next_open: Option<DateTime<Utc>> },
+
+    #[error("Invalid order: price {0} outside (0, 1)")]
+    InvalidPrice(Decimal),
+
+    #[error("Invalid order: amount must be positive")]
+    InvalidAmount,
+
+    #[error("Invalid order: amount has more than {0} decimals")]
+    AmountPrecision(u32),
+
+    #[error("Insufficient balance: need {required}, available {available}")]
+    InsufficientBalance { required: Decimal, available: Decimal },
+
+    #[error("Insufficient shares: need {required}, held {held}")]
+    InsufficientShares { required: Decimal, held: Decimal },
+
+    #[error("Too many open orders: {scope} cap of {limit} reached")]
+    TooManyOpenOrders { scope: &'static str, limit: i64 },
+
+    #[error("Atomic batch orders must all be in one market")]
+    AtomicBatchMarkets,
+
+    #[error("Categorical market outcomes trade Yes shares only")]
+    CategoricalNoShares,
+
+    #[error("Atomic batch orders must be post-only GTC limit orders")]
+    AtomicBatchTaker,
+
+    #[error(transparent)]
+    Engine(#[from] MatchingError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl OrderFlowError {
+    /// Reason recorded on the rejected order
+    pub fn reject_reason(&self) -> RejectReason {
+        match self {
+            OrderFlowError::MarketNotFound => RejectReason::MarketNotFound,
+            OrderFlowError::MarketNotActive(_) => RejectReason::MarketNotActive,
+            OrderFlowError::OutsideTradingHours { .. } => RejectReason::OutsideTradingHours,
+            OrderFlowError::InvalidPrice(_) => RejectReason::InvalidPrice,
+            OrderFlowError::InvalidAmount | OrderFlowError::AmountPrecision(_) => RejectReason::InvalidAmount,
+            // Shares are the balance a sell spends
+            OrderFlowError::InsufficientBalance { .. } | OrderFlowError::InsufficientShares { .. } => {
+                RejectReason::InsufficientBalance
+            }
+            OrderFlowError::TooManyOpenOrders { .. } => RejectReason::TooManyOpenOrders,
+            OrderFlowError::AtomicBatchMarkets | OrderFlowError::AtomicBatchTaker => RejectReason::InvalidBatch,
+            OrderFlowError::CategoricalNoShares => RejectReason::InvalidSide,
+            OrderFlowError::Engine(e) => e.reject_reason(),
+            OrderFlowError::Database(_) => RejectReason::InternalError,
+        }
+    }
+}
+
+impl From<MarginError> for OrderFlowError {
+    fn from(e: MarginError) -> Self {
+        match e {
+            MarginError::InsufficientBalance { required, available } => {
+                OrderFlowError::InsufficientBalance { required, available }
+            }
+            MarginError::Database(e) => OrderFlowError::Database(e),
+        }
+    }
+}
+
+/// Announces the fills of placed orders: webhooks and notifications to
+/// both sides of each fill, and a channel event for the market
+pub struct FillNotifier {
+    pub webhook_service: Arc<WebhookService>,
+    pub notification_service: Arc<NotificationService>,
+    pub channel_gateway: Arc<ChannelGateway>,
+}
+
+impl FillNotifier {
+    async fn notify(&self, intent: &OrderIntent, order_id: Uuid, trades: &[TradeEvent], collateral_token: &str) {
+        let maker_side = match intent.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        for trade in trades {
+            let notional = Collateral::notional(trade.price, trade.amount).value();
+            self.channel_gateway
+                .publish(
+                    ChannelEventType::LargeTrade,
+                    trade.market_id,
+                    Some(notional),
+                    serde_json::json!({
+                        "trade_id": trade.trade_id,
+                        "side": intent.side,
+                        "share_type": trade.share_type,
+                        "price": trade.price,
+                        "amount": trade.amount,
+                        "notional": notional,
+                        "symbol": collateral_token,
+                    }),
+                )
+                .await;
+            for (user, fill_order_id, side, role) in [
+                (intent.user_address.as_str(), order_id, intent.side, "taker"),
+                (trade.maker_address.as_str(), trade.maker_order_id, maker_side, "maker"),
+            ] {
+                let data = serde_json::json!({
+                    "user_address": user,
+                    "order_id": fill_order_id,
+                    "trade_id": trade.trade_id,
+                    "market_id": trade.market_id,
+                    "outcome_id": trade.outcome_id,
+                    "share_type": trade.share_type,
+                    "side": side,
+                    "role": role,
+                    "price": trade.price,
+                    "amount": trade.amount,
+                    "notional": notional,
+                    "fee": if role == "maker" { trade.maker_fee } else { trade.taker_fee },
+                    "symbol": collateral_token,
+                });
+                self.webhook_service
+                    .dispatch(WebhookEventType::OrderFilled, Some(user), data.clone())
+                    .await;
+                self.notification_service
+                    .notify(NotificationKind::OrderFilled, user, Some(notional), data)
+                    .await;
+            }
+        }
+    }
+}
+
+/// Progress of one order through the saga
+struct Saga {
+    order_id: Uuid,
+    /// Orderbook key: market_id:outcome_id:share_type
+    market_key: String,
+    /// Completed steps, in order
+    completed: Vec<FlowStep>,
+    /// Amount the engine filled; its collateral is spent, not released
+    filled_amount: Decimal,
+    /// Amount self-trade prevention took off the order
+    decremented: Decimal,
+}
+
+impl Saga {
+    fn new(intent: &OrderIntent) -> Self {
+        Self {
+            order_id: Uuid::new_v4(),
+            market_key: format!("{}:{}:{}", intent.market_id, intent.outcome_id, intent.share_type),
+            completed: Vec::new(),
+            filled_amount: Decimal::ZERO,
+            decremented: Decimal::ZERO,
+        }
+    }
+}
 
-        info!("OrderFlowOrchestrator initialized");
+/// Run one step, recording its outcome and duration
+async fn run_step<T>(
+    step: FlowStep,
+    work: impl Future<Output = Result<T, OrderFlowError>>,
+) -> Result<T, OrderFlowError> {
+    let started = Instant::now();
+    let result = work.await;
+    crate::metrics::record_order_flow_step(
+        step.as_str(),
+        if result.is_ok() { "ok" } else { "failed" },
+        started.elapsed().as_secs_f64(),
+    );
+    result
+}
+
+/// Runs orders through the validate → reserve → match → persist → notify →
+/// settle-enqueue saga
+pub struct OrderFlowOrchestrator {
+    pool: PgPool,
+    shards: Arc<EngineShards>,
+    write_batcher: Arc<WriteBatcher>,
+    /// Balance token that trades settle in
+    collateral_token: String,
+    /// Without a notifier, the notify step is skipped
+    notifier: Option<FillNotifier>,
+    open_order_caps: OpenOrderCaps,
+}
 
+impl OrderFlowOrchestrator {
+    pub fn new(pool: PgPool, shards: Arc<EngineShards>, write_batcher: Arc<WriteBatcher>, collateral_token: String) -> Self {
         Self {
-            engine,
             pool,
-            trade_receiver,
+            shards,
+            write_batcher,
+            collateral_token,
+            notifier: None,
+            open_order_caps: OpenOrderCaps::default(),
         }
     }
 
-    /// Get reference to matching engine
-    pub fn engine(&self) -> &Arc<MatchingEngine> {
-        &self.engine
+    /// Announce the fills of placed orders
+    pub fn with_notifier(mut self, notifier: FillNotifier) -> Self {
+        self.notifier = Some(notifier);
+        self
     }
 
-    /// Start the background persistence worker
-    pub fn start_persistence_worker(mut self) -> Arc<MatchingEngine> {
-        let pool = self.pool.clone();
-        let engine = Arc::clone(&self.engine);
-        let receiver = self.trade_receiver.take();
+    /// Refuse orders that could rest once a market's caps are reached
+    pub fn with_open_order_caps(mut self, caps: OpenOrderCaps) -> Self {
+        self.open_order_caps = caps;
+        self
+    }
 
-        if let Some(mut rx) = receiver {
-            tokio::spawn(async move {
-                info!("Trade persistence worker started");
+    /// Place `intent`, compensating the completed steps if one fails
+    pub async fn place(&self, intent: &OrderIntent) -> Result<PlacedOrder, OrderFlowError> {
+        let mut saga = Saga::new(intent);
+
+        if let Err(e) = run_step(FlowStep::Validate, self.validate(intent)).await {
+            // Spam refused by the caps stays visible in the owner's history
+            if matches!(e, OrderFlowError::TooManyOpenOrders { .. }) {
+                self.record_rejected(&saga, intent, e.reject_reason()).await;
+            }
+            return Err(e);
+        }
+        saga.completed.push(FlowStep::Validate);
 
-                loop {
-                    match rx.recv().await {
-                        Ok(trade) => {
-                            if let Err(e) = Self::persist_trade(&pool, &trade).await {
-                                error!("Failed to persist trade: {}", e);
+        let reserve = async {
+            let mut tx = self.pool.begin().await?;
+            self.reserve(&mut tx, intent, Decimal::ZERO).await?;
+            tx.commit().await?;
+            Ok(())
+        };
+        if let Err(e) = run_step(FlowStep::Reserve, reserve).await {
+            self.record_rejected(&saga, intent, e.reject_reason()).await;
+            return Err(e);
+        }
+        saga.completed.push(FlowStep::Reserve);
+
+        let (match_result, quoted_mid) = match run_step(FlowStep::Match, self.submit(&saga, intent)).await {
+            Ok(matched) => matched,
+            Err(e) => {
+                self.compensate(&saga, intent).await;
+                self.record_rejected(&saga, intent, e.reject_reason()).await;
+                return Err(e);
+            }
+        };
+        saga.completed.push(FlowStep::Match);
+        saga.filled_amount = match_result.filled_amount;
+        saga.decremented = match_result.decremented;
+
+        let matching_side = match intent.side {
+            OrderSide::Buy => Side::Buy,
+            OrderSide::Sell => Side::Sell,
+        };
+        let trades: Vec<TradeEvent> = match_result
+            .trades
+            .iter()
+            .map(|trade| {
+                TradeEvent::from_execution(trade, saga.market_key.clone(), intent.user_address.clone(), matching_side)
+            })
+            .collect();
+        let set_fills = match_result.set_fills.clone();
+        let status: OrderStatus = match_result.status.into();
+        let created_at = Utc::now();
+
+        let persist = self.persist(&self.pool, &saga, intent, status, quoted_mid, created_at);
+        if let Err(e) = run_step(FlowStep::Persist, persist).await {
+            tracing::error!("Failed to persist order {}: {}", saga.order_id, e);
+            self.compensate(&saga, intent).await;
+            // The makers' orders were filled regardless
+            let _ = run_step(FlowStep::SettleEnqueue, self.settle_enqueue(trades, set_fills)).await;
+            return Err(e);
+        }
+        saga.completed.push(FlowStep::Persist);
+
+        // An order that ended without resting (IOC remainder, market order)
+        // no longer needs the collateral of its unfilled part
+        if status.is_final() && saga.filled_amount < intent.amount {
+            if let Err(e) = self.release_reserve(&saga, intent).await {
+                tracing::error!("Failed to release unfilled reserve of order {}: {}", saga.order_id, e);
+            }
+        } else if saga.decremented > Decimal::ZERO && intent.side == OrderSide::Buy {
+            // A resting order shrunk by self-trade prevention
+            let decremented = Collateral::notional(intent.price, saga.decremented).value();
+            if let Err(e) = self.release_collateral(&intent.user_address, decremented).await {
+                tracing::error!("Failed to release decremented reserve of order {}: {}", saga.order_id, e);
+            }
+        }
+        if let Err(e) = self.apply_self_trades(&match_result.self_trades).await {
+            tracing::error!("Failed to record self-trade prevention of order {}: {}", saga.order_id, e);
+        }
+        // Released collateral can shrink the margin credit; fills resync it
+        // once they are applied to holdings
+        let released = (status.is_final() && saga.filled_amount < intent.amount)
+            || saga.decremented > Decimal::ZERO
+            || !match_result.self_trades.is_empty();
+        if released && trades.is_empty() && set_fills.is_empty() {
+            if let Err(e) = self.resync_margin(&intent.user_address, intent.market_id, intent.outcome_id).await {
+                tracing::error!("Failed to resync portfolio margin after order {}: {}", saga.order_id, e);
+            }
+        }
+
+        if let Some(notifier) = &self.notifier {
+            let notify = async {
+                notifier.notify(intent, saga.order_id, &trades, &self.collateral_token).await;
+                Ok(())
+            };
+            let _ = run_step(FlowStep::Notify, notify).await;
+        }
+        let _ = run_step(FlowStep::SettleEnqueue, self.settle_enqueue(trades.clone(), set_fills.clone())).await;
+
+        debug!(
+            "{} order {} placed: {} {} @ {} ({} filled)",
+            intent.source.as_str(),
+            saga.order_id,
+            intent.side,
+            intent.amount,
+            intent.price,
+            saga.filled_amount
+        );
+        Ok(PlacedOrder {
+            order_id: saga.order_id,
+            status,
+            amount: intent.amount - saga.decremented,
+            filled_amount: saga.filled_amount,
+            trades,
+            set_fills,
+            queue_position: match_result.queue_position,
+            created_at,
+        })
+    }
+
+    /// Place `intents` in order, each on its own; with `atomic`, all of
+    /// them or none (see [`place_atomic`](Self::place_atomic))
+    pub async fn place_batch(&self, intents: &[OrderIntent], atomic: bool) -> Vec<BatchPlacement> {
+        if atomic {
+            return self.place_atomic(intents).await;
+        }
+        let mut results = Vec::with_capacity(intents.len());
+        for intent in intents {
+            results.push(match self.place(intent).await {
+                Ok(placed) => BatchPlacement::Placed(placed),
+                Err(e) => BatchPlacement::Failed(e),
+            });
+        }
+        results
+    }
+
+    /// Rest post-only GTC limit orders of one market, all of them or none:
+    /// an atomic batch only ever makes, it never trades on arrival. A batch
+    /// spanning markets, or holding an order that isn't post-only, is
+    /// refused as a whole ([`OrderFlowError::AtomicBatchMarkets`],
+    /// [`OrderFlowError::AtomicBatchTaker`]) rather than narrowed.
+    ///
+    /// Every order is validated, reserved and written in one transaction,
+    /// sells covering the shares sold by the batch's earlier orders too.
+    /// Only once that commits does one step on the market's shard submit
+    /// them, so the book never holds an order the database lacks. One that
+    /// would trade, against the book or an order earlier in the batch, is
+    /// refused and the step takes the orders it rested off again; the
+    /// committed rows and reserves are then unwound.
+    async fn place_atomic(&self, intents: &[OrderIntent]) -> Vec<BatchPlacement> {
+        let refused = |index: usize, e: OrderFlowError| {
+            let mut results: Vec<BatchPlacement> = intents.iter().map(|_| BatchPlacement::RolledBack).collect();
+            results[index] = BatchPlacement::Failed(e);
+            results
+        };
+        let Some(first) = intents.first() else {
+            return Vec::new();
+        };
+        // One market is one shard, so the batch can rest in one step
+        if let Some(index) = intents.iter().position(|intent| intent.market_id != first.market_id) {
+            return refused(index, OrderFlowError::AtomicBatchMarkets);
+        }
+        if let Some(index) = intents.iter().position(|intent| {
+            !intent.post_only
+                || intent.order_type != ModelOrderType::Limit
+                || intent.time_in_force != ModelTimeInForce::Gtc
+        }) {
+            return refused(index, OrderFlowError::AtomicBatchTaker);
+        }
+        let sagas: Vec<Saga> = intents.iter().map(Saga::new).collect();
+        let created_at = Utc::now();
+
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return refused(0, e.into()),
+        };
+        for (index, (intent, saga)) in intents.iter().zip(&sagas).enumerate() {
+            // The caps count the orders of the batch already written
+            let validate = async {
+                self.validate_terms(intent.market_id, intent.price, intent.amount).await?;
+                self.check_open_order_caps(&mut *tx, intent).await
+            };
+            if let Err(e) = run_step(FlowStep::Validate, validate).await {
+                drop(tx);
+                if matches!(e, OrderFlowError::TooManyOpenOrders { .. }) {
+                    self.record_rejected(saga, intent, e.reject_reason()).await;
+                }
+                return refused(index, e);
+            }
+            // Margin of the next order sees this one through its row, and
+            // its sell covers the shares the batch sold before it
+            let committed: Decimal = intents[..index]
+                .iter()
+                .filter(|o| {
+                    o.side == OrderSide::Sell && o.outcome_id == intent.outcome_id && o.share_type == intent.share_type
+                })
+                .map(|o| o.amount)
+                .sum();
+            let reserve = async {
+                self.reserve(&mut tx, intent, committed).await?;
+                self.persist(&mut *tx, saga, intent, OrderStatus::Open, None, created_at).await
+            };
+            if let Err(e) = run_step(FlowStep::Reserve, reserve).await {
+                drop(tx);
+                self.record_rejected(saga, intent, e.reject_reason()).await;
+                return refused(index, e);
+            }
+        }
+        let commit = async {
+            tx.commit().await?;
+            Ok(())
+        };
+        if let Err(e) = run_step(FlowStep::Persist, commit).await {
+            tracing::error!("Failed to persist batch of {}: {}", first.user_address, e);
+            return refused(0, e);
+        }
+
+        let orders: Vec<(Uuid, String, OrderIntent)> = intents
+            .iter()
+            .zip(&sagas)
+            .map(|(intent, saga)| (saga.order_id, saga.market_key.clone(), intent.clone()))
+            .collect();
+        let started = Instant::now();
+        let submitted = self
+            .shards
+            .execute(&sagas[0].market_key, move |engine| {
+                let mut rested = Vec::with_capacity(orders.len());
+                for (index, (order_id, symbol, intent)) in orders.iter().enumerate() {
+                    let side = match intent.side {
+                        OrderSide::Buy => Side::Buy,
+                        OrderSide::Sell => Side::Sell,
+                    };
+                    // The book as the order finds it, for execution quality stats
+                    let quoted_mid = engine.quoted_mid(symbol);
+                    let result = engine.submit_order_with_stp(
+                        *order_id,
+                        symbol,
+                        &intent.user_address,
+                        side,
+                        OrderType::Limit,
+                        intent.amount,
+                        Some(intent.price),
+                        1,
+                        TimeInForce::PostOnly,
+                        intent.stp,
+                    );
+                    match result {
+                        Ok(result) => {
+                            if let Some(expires_at) = intent.expires_at {
+                                engine.set_order_expiry(symbol, *order_id, expires_at.timestamp_millis());
                             }
+                            rested.push((result, quoted_mid));
                         }
-                        Err(broadcast::error::RecvError::Lagged(n)) => {
-                            warn!("Trade persistence lagged {} messages", n);
-                        }
-                        Err(broadcast::error::RecvError::Closed) => {
-                            info!("Trade channel closed, stopping persistence worker");
-                            break;
+                        Err(e) => {
+                            for (order_id, symbol, intent) in &orders[..index] {
+                                if let Err(e) = engine.cancel_order(symbol, *order_id, &intent.user_address) {
+                                    tracing::error!("Failed to take batch order {} off the book: {}", order_id, e);
+                                }
+                            }
+                            return Err((index, e));
                         }
                     }
                 }
-            });
+                Ok(rested)
+            })
+            .await;
+        crate::metrics::record_order_flow_step(
+            FlowStep::Match.as_str(),
+            if matches!(submitted, Ok(Ok(_))) { "ok" } else { "failed" },
+            started.elapsed().as_secs_f64(),
+        );
+        let rested = match submitted {
+            Ok(Ok(rested)) => rested,
+            Ok(Err((index, e))) => {
+                self.unwind_atomic(intents, &sagas, Some((index, e.reject_reason()))).await;
+                return refused(index, e.into());
+            }
+            Err(e) => {
+                self.unwind_atomic(intents, &sagas, None).await;
+                return refused(0, e.into());
+            }
+        };
+
+        // Execution quality stats only; the batch stands without them
+        let order_ids: Vec<Uuid> = sagas.iter().map(|saga| saga.order_id).collect();
+        let quoted_mids: Vec<Option<Decimal>> = rested.iter().map(|(_, quoted_mid)| *quoted_mid).collect();
+        if let Err(e) = sqlx::query(
+            "UPDATE orders SET quoted_mid = q.mid FROM UNNEST($1::uuid[], $2::numeric[]) AS q(id, mid)
+             WHERE orders.id = q.id",
+        )
+        .bind(&order_ids)
+        .bind(&quoted_mids)
+        .execute(&self.pool)
+        .await
+        {
+            tracing::warn!("Failed to record quoted mids of batch of {}: {}", first.user_address, e);
         }
 
-        engine
+        debug!("{} order batch of {} rested: {} orders", first.source.as_str(), first.user_address, intents.len());
+        intents
+            .iter()
+            .zip(sagas)
+            .zip(rested)
+            .map(|((intent, saga), (result, _))| {
+                BatchPlacement::Placed(PlacedOrder {
+                    order_id: saga.order_id,
+                    status: result.status.into(),
+                    amount: intent.amount,
+                    filled_amount: Decimal::ZERO,
+                    trades: Vec::new(),
+                    set_fills: Vec::new(),
+                    queue_position: result.queue_position,
+                    created_at,
+                })
+            })
+            .collect()
     }
 
-    /// Process a new order for prediction market
-    pub async fn process_order(
-        &self,
-        market_id: Uuid,
-        outcome_id: Uuid,
-        share_type: ShareType,
-        user_address: &str,
-        side: Side,
-        order_type: OrderType,
-        amount: Decimal,
-        price: Decimal,
-    ) -> Result<MatchResult, MatchingError> {
+    /// Change the price and total size of a resting order in one step on
+    /// its market's shard, keeping its id and fills. The new terms are
+    /// validated like a new order's, a growing buy must be funded first and
+    /// a growing sell covered by held shares; an amendment that would trade
+    /// is refused. Returns `None` if the order stopped resting first.
+    pub async fn amend(&self, amendment: &OrderAmendment) -> Result<Option<AmendedOrder>, OrderFlowError> {
+        let a = amendment;
+        self.validate_terms(a.market_id, a.new_price, a.new_amount).await?;
+        if a.new_amount <= a.filled_amount {
+            return Err(MatchingError::InvalidAmount(format!(
+                "Amount {} must exceed the {} already filled",
+                a.new_amount, a.filled_amount
+            ))
+            .into());
+        }
+
+        // Fund the growth up front; the credit it earns is resynced after
+        let frozen = match a.side {
+            OrderSide::Buy => (Collateral::notional(a.new_price, a.new_amount - a.filled_amount).value()
+                - Collateral::notional(a.price, a.amount - a.filled_amount).value())
+            .max(Decimal::ZERO),
+            OrderSide::Sell => {
+                if a.new_amount > a.amount {
+                    let required = a.new_amount - a.filled_amount;
+                    self.check_shares(&self.pool, &a.user_address, a.outcome_id, a.share_type, required).await?;
+                }
+                Decimal::ZERO
+            }
+        };
+        if frozen > Decimal::ZERO {
+            self.freeze_collateral(&a.user_address, frozen).await?;
+        }
+
+        let market_key = format!("{}:{}:{}", a.market_id, a.outcome_id, a.share_type);
+        let (symbol, order_id, user, price, amount) =
+            (market_key.clone(), a.order_id, a.user_address.clone(), a.new_price, a.new_amount);
+        let amended = self
+            .shards
+            .execute(&market_key, move |engine| {
+                let amended = engine.amend_order(&symbol, order_id, &user, price, amount)?;
+                let position = engine.get_orderbook_ref(&symbol).and_then(|book| book.queue_position(&order_id));
+                Ok(amended.map(|amendment| (amendment, position)))
+            })
+            .await
+            .and_then(|result| result);
+        let (amendment, queue_position) = match amended {
+            Ok(Some(amended)) => amended,
+            refused => {
+                if frozen > Decimal::ZERO {
+                    if let Err(e) = self.release_collateral(&a.user_address, frozen).await {
+                        tracing::error!("Failed to release funding of amendment to order {}: {}", a.order_id, e);
+                    }
+                }
+                return refused.map(|_| None).map_err(Into::into);
+            }
+        };
+        let (before, after) = (&amendment.before, &amendment.after);
+
+        sqlx::query("UPDATE orders SET price = $2, amount = $3, updated_at = NOW() WHERE id = $1")
+            .bind(a.order_id)
+            .bind(after.price)
+            .bind(after.original_amount)
+            .execute(&self.pool)
+            .await?;
+        if a.side == OrderSide::Buy {
+            // The engine's view decides: fills may have landed since the
+            // order was read
+            let needed = Collateral::notional(after.price, after.remaining_amount).value()
+                - Collateral::notional(before.price, before.remaining_amount).value();
+            if needed != frozen {
+                if let Err(e) = self.release_collateral(&a.user_address, frozen - needed).await {
+                    tracing::error!("Failed to settle the reserve of amended order {}: {}", a.order_id, e);
+                }
+            }
+        }
+        if let Err(e) = self.resync_margin(&a.user_address, a.market_id, a.outcome_id).await {
+            tracing::error!("Failed to resync portfolio margin after amending order {}: {}", a.order_id, e);
+        }
+
         debug!(
-            "Processing order: market={}, outcome={}, share_type={:?}, user={}, side={:?}, type={:?}, amount={}, price={}",
-            market_id, outcome_id, share_type, user_address, side, order_type, amount, price
+            "Order {} amended: {} @ {} -> {} @ {}",
+            a.order_id, before.remaining_amount, before.price, after.remaining_amount, after.price
         );
+        Ok(Some(AmendedOrder {
+            price: after.price,
+            amount: after.original_amount,
+            filled_amount: after.original_amount - after.remaining_amount,
+            kept_priority: amendment.kept_priority,
+            queue_position,
+        }))
+    }
 
-        // Validate price range (0 < price < 1)
+    // ========================================================================
+    // Steps
+    // ========================================================================
+
+    /// Price in (0, 1), positive amount that fits the market's share
+    /// precision, active market within its trading hours, the shard
+    /// admits new orders, and No shares only on binary markets
+    async fn validate(&self, intent: &OrderIntent) -> Result<(), OrderFlowError> {
+        self.validate_terms(intent.market_id, intent.price, intent.amount).await?;
+        if intent.share_type == MarketShareType::No {
+            let market_type: String = sqlx::query_scalar("SELECT market_type FROM markets WHERE id = $1")
+                .bind(intent.market_id)
+                .fetch_one(&self.pool)
+                .await?;
+            if market_type == "categorical" {
+                return Err(OrderFlowError::CategoricalNoShares);
+            }
+        }
+        self.check_open_order_caps(&self.pool, intent).await
+    }
+
+    /// The checks of [`validate`](Self::validate) that don't depend on the
+    /// order being new
+    async fn validate_terms(&self, market_id: Uuid, price: Decimal, amount: Decimal) -> Result<(), OrderFlowError> {
         if price <= Decimal::ZERO || price >= Decimal::ONE {
-            return Err(MatchingError::InvalidPrice(format!(
-                "Price must be between 0 and 1, got {}",
-                price
-            )));
-        }
-
-        // Generate order ID
-        let order_id = Uuid::new_v4();
-
-        // Build market key for orderbook: market_id:outcome_id:share_type
-        let market_key = format!("{}:{}:{}", market_id, outcome_id, share_type);
-
-        // Submit to matching engine (use market_key as "symbol" and leverage=1 for prediction markets)
-        let result = self.engine.submit_order(
-            order_id,
-            &market_key,
-            user_address,
-            side,
-            order_type,
-            amount,
-            Some(price),
-            1, // No leverage in prediction markets
-        )?;
-
-        // Spawn async task for database persistence
-        let pool = self.pool.clone();
-        let user_address = user_address.to_string();
-        let result_clone = result.clone();
-        let share_type_clone = share_type.clone();
-
-        tokio::spawn(async move {
-            if let Err(e) = Self::persist_order(
-                &pool,
-                market_id,
-                outcome_id,
-                share_type_clone,
-                &user_address,
-                &result_clone,
-                side,
-                order_type,
-                amount,
-                price,
-            ).await {
-                error!("Failed to persist order {}: {}", order_id, e);
+            return Err(OrderFlowError::InvalidPrice(price));
+        }
+        if amount <= Decimal::ZERO {
+            return Err(OrderFlowError::InvalidAmount);
+        }
+
+        // Fast-fail while the market's shard is backed up
+        self.shards.admit(&market_id.to_string())?;
+
+        let (status, share_decimals): (String, i16) =
+            sqlx::query_as("SELECT status::text, share_decimals FROM markets WHERE id = $1")
+                .bind(market_id)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or(OrderFlowError::MarketNotFound)?;
+        if status != "active" {
+            return Err(OrderFlowError::MarketNotActive(status));
+        }
+        if let Some(schedule) = trading_calendar::load(&self.pool, market_id).await? {
+            let now = Utc::now();
+            if !schedule.is_open(now) {
+                return Err(OrderFlowError::OutsideTradingHours { next_open: schedule.next_open(now) });
             }
-        });
+        }
+        let precision = SharePrecision::new(share_decimals as u32).unwrap_or_default();
+        if !precision.accepts(amount) {
+            return Err(OrderFlowError::AmountPrecision(precision.dp()));
+        }
+        Ok(())
+    }
 
-        info!(
-            "Order processed: id={}, status={:?}, filled={}",
-            result.order_id, result.status, result.filled_amount
-        );
+    /// Only GTC limit orders can rest, so only they count against the caps
+    async fn check_open_order_caps(
+        &self,
+        conn: impl PgExecutor<'_>,
+        intent: &OrderIntent,
+    ) -> Result<(), OrderFlowError> {
+        let caps = self.open_order_caps;
+        if (caps.per_account <= 0 && caps.per_market <= 0)
+            || intent.order_type != ModelOrderType::Limit
+            || intent.time_in_force != ModelTimeInForce::Gtc
+        {
+            return Ok(());
+        }
 
-        Ok(result)
+        let (account, market): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COALESCE((
+                       SELECT open_orders::bigint FROM user_open_order_stats
+                       WHERE user_address = $1 AND market_id = $2
+                   ), 0),
+                   (
+                       SELECT COUNT(*) FROM orders
+                       WHERE market_id = $2 AND status IN ('pending', 'open', 'partially_filled')
+                   )
+            "#,
+        )
+        .bind(&intent.user_address)
+        .bind(intent.market_id)
+        .fetch_one(conn)
+        .await?;
+
+        if caps.per_account > 0 && account >= caps.per_account {
+            return Err(OrderFlowError::TooManyOpenOrders { scope: "account", limit: caps.per_account });
+        }
+        if caps.per_market > 0 && market >= caps.per_market {
+            return Err(OrderFlowError::TooManyOpenOrders { scope: "market", limit: caps.per_market });
+        }
+        Ok(())
     }
 
-    /// Cancel an order
-    pub async fn cancel_order(
+    /// Freeze a buy's collateral net of the margin credit it earns; a sell
+    /// must be covered by held shares, on top of the `committed` shares that
+    /// orders placed with it sell, and freezes the credit they stop covering
+    async fn reserve(
         &self,
-        market_id: Uuid,
-        outcome_id: Uuid,
-        share_type: ShareType,
-        order_id: Uuid,
+        conn: &mut PgConnection,
+        intent: &OrderIntent,
+        committed: Decimal,
+    ) -> Result<(), OrderFlowError> {
+        if intent.side == OrderSide::Sell {
+            let required = committed + intent.amount;
+            self.check_shares(&mut *conn, &intent.user_address, intent.outcome_id, intent.share_type, required)
+                .await?;
+        }
+        let order = NewOrder {
+            share_type: intent.share_type,
+            side: intent.side,
+            price: intent.price,
+            amount: intent.amount,
+        };
+        portfolio_margin::reserve(
+            conn,
+            &intent.user_address,
+            intent.market_id,
+            intent.outcome_id,
+            &self.collateral_token,
+            &order,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// A sell must be covered by the shares held
+    async fn check_shares(
+        &self,
+        conn: impl PgExecutor<'_>,
         user_address: &str,
-    ) -> Result<bool, MatchingError> {
-        debug!("Cancelling order: id={}, market={}", order_id, market_id);
+        outcome_id: Uuid,
+        share_type: MarketShareType,
+        required: Decimal,
+    ) -> Result<(), OrderFlowError> {
+        let held: Option<Decimal> = sqlx::query_scalar(
+            "SELECT amount FROM shares WHERE user_address = $1 AND outcome_id = $2 AND share_type = $3::share_type",
+        )
+        .bind(user_address)
+        .bind(outcome_id)
+        .bind(share_type.to_string())
+        .fetch_optional(conn)
+        .await?;
+        let held = held.unwrap_or(Decimal::ZERO);
+        if held < required {
+            return Err(OrderFlowError::InsufficientShares { required, held });
+        }
+        Ok(())
+    }
 
-        // Build market key for orderbook
-        let market_key = format!("{}:{}:{}", market_id, outcome_id, share_type);
+    /// Match on the market's shard; also returns the mid the order found
+    async fn submit(&self, saga: &Saga, intent: &OrderIntent) -> Result<(MatchResult, Option<Decimal>), OrderFlowError> {
+        let side = match intent.side {
+            OrderSide::Buy => Side::Buy,
+            OrderSide::Sell => Side::Sell,
+        };
+        let order_type = match intent.order_type {
+            ModelOrderType::Limit => OrderType::Limit,
+            ModelOrderType::Market => OrderType::Market,
+        };
+        let time_in_force = match intent.post_only {
+            true => TimeInForce::PostOnly,
+            false => intent.time_in_force.into(),
+        };
+        let (order_id, symbol, user, amount, price, stp, expires_at) = (
+            saga.order_id,
+            saga.market_key.clone(),
+            intent.user_address.clone(),
+            intent.amount,
+            intent.price,
+            intent.stp,
+            intent.expires_at,
+        );
+        let matched = self
+            .shards
+            .execute(&saga.market_key, move |engine| {
+                // The book as the order finds it, for execution quality stats
+                let quoted_mid = engine.quoted_mid(&symbol);
+                let result = engine
+                    .submit_order_with_stp(order_id, &symbol, &user, side, order_type, amount, Some(price), 1, time_in_force, stp)?;
+                if let Some(expires_at) = expires_at.filter(|_| result.queue_position.is_some()) {
+                    engine.set_order_expiry(&symbol, order_id, expires_at.timestamp_millis());
+                }
+                Ok((result, quoted_mid))
+            })
+            .await
+            .and_then(|result| result)?;
+        Ok(matched)
+    }
 
-        // Cancel in matching engine
-        let cancelled = self.engine.cancel_order(&market_key, order_id, user_address)?;
+    async fn persist(
+        &self,
+        conn: impl PgExecutor<'_>,
+        saga: &Saga,
+        intent: &OrderIntent,
+        status: OrderStatus,
+        quoted_mid: Option<Decimal>,
+        created_at: DateTime<Utc>,
+    ) -> Result<(), OrderFlowError> {
+        sqlx::query(
+            r#"
+            INSERT INTO orders (
+                id, user_address, symbol, market_id, outcome_id, share_type,
+                side, order_type, price, amount, filled_amount, status, source, signature,
+                created_at, updated_at, quoted_mid, time_in_force, post_only, expires_at
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6::share_type,
+                $7::order_side, $8::order_type, $9, $10, $11, $12::order_status, $13, $14,
+                $15, $15, $16, $17::time_in_force, $18, $19
+            )
+            "#,
+        )
+        .bind(saga.order_id)
+        .bind(&intent.user_address)
+        .bind(&saga.market_key)
+        .bind(intent.market_id)
+        .bind(intent.outcome_id)
+        .bind(intent.share_type.to_string())
+        .bind(intent.side.to_string())
+        .bind(intent.order_type.to_string())
+        .bind(intent.price)
+        // Self-trade prevention may have shrunk the order
+        .bind(intent.amount - saga.decremented)
+        .bind(saga.filled_amount)
+        .bind(status.to_string())
+        .bind(intent.source.as_str())
+        .bind(&intent.signature)
+        .bind(created_at)
+        .bind(quoted_mid)
+        .bind(intent.time_in_force.to_string())
+        .bind(intent.post_only)
+        .bind(intent.expires_at)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
 
-        if cancelled {
-            // Update database asynchronously
-            let pool = self.pool.clone();
+    /// Persist the fills with the current write batch, and complete sets on
+    /// their own; failures are queued for retry and don't fail the order
+    async fn settle_enqueue(&self, trades: Vec<TradeEvent>, set_fills: Vec<SetFill>) -> Result<(), OrderFlowError> {
+        self.write_batcher.persist_fills(trades).await;
+        self.write_batcher.persist_set_fills(set_fills).await;
+        Ok(())
+    }
 
-            tokio::spawn(async move {
-                if let Err(e) = Self::update_order_status(&pool, order_id, "cancelled").await {
-                    error!("Failed to update order status: {}", e);
+    // ========================================================================
+    // Compensation
+    // ========================================================================
+
+    /// Undo the completed steps of `saga`, latest first
+    async fn compensate(&self, saga: &Saga, intent: &OrderIntent) {
+        for &step in saga.completed.iter().rev() {
+            let started = Instant::now();
+            let result = match step {
+                FlowStep::Reserve => self.release_reserve(saga, intent).await,
+                FlowStep::Match => self.cancel_remainder(saga, intent).await,
+                _ => continue,
+            };
+            crate::metrics::record_order_flow_step(
+                step.as_str(),
+                if result.is_ok() { "compensated" } else { "compensation_failed" },
+                started.elapsed().as_secs_f64(),
+            );
+            if let Err(e) = result {
+                tracing::error!("Failed to compensate {} of order {}: {}", step.as_str(), saga.order_id, e);
+            }
+        }
+        if let Err(e) = self.resync_margin(&intent.user_address, intent.market_id, intent.outcome_id).await {
+            tracing::error!("Failed to resync portfolio margin after order {}: {}", saga.order_id, e);
+        }
+    }
+
+    /// Undo an atomic batch the engine refused after its transaction
+    /// committed: its rows are deleted, the buys' collateral is released
+    /// and the margin credits resynced in one transaction, then the refused
+    /// order is recorded as rejected with its reason
+    async fn unwind_atomic(&self, intents: &[OrderIntent], sagas: &[Saga], refused: Option<(usize, RejectReason)>) {
+        let started = Instant::now();
+        let unwind = async {
+            let mut tx = self.pool.begin().await?;
+            for (intent, saga) in intents.iter().zip(sagas) {
+                sqlx::query("DELETE FROM orders WHERE id = $1")
+                    .bind(saga.order_id)
+                    .execute(&mut *tx)
+                    .await?;
+                if intent.side == OrderSide::Buy {
+                    sqlx::query(
+                        "UPDATE balances SET available = available + $1, frozen = frozen - $1, updated_at = NOW()
+                         WHERE user_address = $2 AND token = $3",
+                    )
+                    .bind(Collateral::notional(intent.price, intent.amount).value())
+                    .bind(&intent.user_address)
+                    .bind(&self.collateral_token)
+                    .execute(&mut *tx)
+                    .await?;
                 }
-            });
+            }
+            let mut outcomes: Vec<Uuid> = intents.iter().map(|intent| intent.outcome_id).collect();
+            outcomes.sort();
+            outcomes.dedup();
+            for outcome_id in outcomes {
+                portfolio_margin::resync(
+                    &mut tx,
+                    &intents[0].user_address,
+                    intents[0].market_id,
+                    outcome_id,
+                    &self.collateral_token,
+                )
+                .await?;
+            }
+            tx.commit().await?;
+            Ok::<_, OrderFlowError>(())
+        };
+        let result = unwind.await;
+        crate::metrics::record_order_flow_step(
+            FlowStep::Reserve.as_str(),
+            if result.is_ok() { "compensated" } else { "compensation_failed" },
+            started.elapsed().as_secs_f64(),
+        );
+        if let Err(e) = result {
+            tracing::error!("Failed to unwind batch of {}: {}", intents[0].user_address, e);
+            return;
+        }
+        if let Some((index, reason)) = refused {
+            self.record_rejected(&sagas[index], &intents[index], reason).await;
+        }
+    }
+
+    /// Bring the margin credit on the order's outcome back in line once
+    /// the order is settled on the book
+    async fn resync_margin(&self, user_address: &str, market_id: Uuid, outcome_id: Uuid) -> Result<(), OrderFlowError> {
+        let mut tx = self.pool.begin().await?;
+        portfolio_margin::resync(&mut tx, user_address, market_id, outcome_id, &self.collateral_token).await?;
+        tx.commit().await?;
+        Ok(())
+    }
 
-            info!("Order cancelled: id={}", order_id);
+    /// Release the collateral frozen for the part of a buy that did not fill
+    async fn release_reserve(&self, saga: &Saga, intent: &OrderIntent) -> Result<(), OrderFlowError> {
+        if intent.side != OrderSide::Buy {
+            return Ok(());
         }
+        let unfilled = Collateral::notional(intent.price, intent.amount - saga.filled_amount).value();
+        self.release_collateral(&intent.user_address, unfilled).await
+    }
 
-        Ok(cancelled)
+    /// Move `value` of a user's available collateral to frozen, if they
+    /// have that much
+    async fn freeze_collateral(&self, user_address: &str, value: Decimal) -> Result<(), OrderFlowError> {
+        let frozen = sqlx::query(
+            "UPDATE balances SET available = available - $1, frozen = frozen + $1, updated_at = NOW()
+             WHERE user_address = $2 AND token = $3 AND available >= $1",
+        )
+        .bind(value)
+        .bind(user_address)
+        .bind(&self.collateral_token)
+        .execute(&self.pool)
+        .await?;
+        if frozen.rows_affected() == 0 {
+            let available: Option<Decimal> =
+                sqlx::query_scalar("SELECT available FROM balances WHERE user_address = $1 AND token = $2")
+                    .bind(user_address)
+                    .bind(&self.collateral_token)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            return Err(OrderFlowError::InsufficientBalance {
+                required: value,
+                available: available.unwrap_or(Decimal::ZERO),
+            });
+        }
+        Ok(())
     }
 
-    /// Get orderbook for an outcome
-    pub fn get_orderbook(
-        &self,
-        market_id: Uuid,
-        outcome_id: Uuid,
-        share_type: ShareType,
-        depth: usize,
-    ) -> Result<OrderbookSnapshot, MatchingError> {
-        // Build market key for orderbook
-        let market_key = format!("{}:{}:{}", market_id, outcome_id, share_type);
-        self.engine.get_orderbook(&market_key, depth)
+    /// Move `value` of a user's frozen collateral back to available
+    async fn release_collateral(&self, user_address: &str, value: Decimal) -> Result<(), OrderFlowError> {
+        sqlx::query(
+            "UPDATE balances SET available = available + $1, frozen = frozen - $1, updated_at = NOW()
+             WHERE user_address = $2 AND token = $3",
+        )
+        .bind(value)
+        .bind(user_address)
+        .bind(&self.collateral_token)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
     }
 
-    /// Get trade history
-    pub fn get_trades(&self, market_id: Uuid, query: &TradeHistoryQuery) -> TradeHistoryResponse {
-        // Use market_id as the symbol prefix for trade lookup
-        self.engine.get_trades(&market_id.to_string(), query)
+    /// Record the own resting orders self-trade prevention cancelled or
+    /// shrank, releasing the collateral of buys
+    async fn apply_self_trades(&self, self_trades: &[SelfTradeReduction]) -> Result<(), OrderFlowError> {
+        for reduction in self_trades {
+            let order = &reduction.order;
+            let updated = if reduction.cancelled() {
+                sqlx::query(
+                    "UPDATE orders SET status = 'cancelled', updated_at = NOW()
+                     WHERE id = $1 AND status IN ('pending', 'accepted', 'open', 'partially_filled')",
+                )
+                .bind(order.id)
+                .execute(&self.pool)
+                .await?
+            } else {
+                sqlx::query(
+                    "UPDATE orders SET amount = amount - $2, updated_at = NOW()
+                     WHERE id = $1 AND status IN ('pending', 'accepted', 'open', 'partially_filled')",
+                )
+                .bind(order.id)
+                .bind(reduction.amount)
+                .execute(&self.pool)
+                .await?
+            };
+            if updated.rows_affected() > 0 && order.side == Side::Buy {
+                self.release_collateral(&order.user_address, Collateral::notional(order.price, reduction.amount).value())
+                    .await?;
+            }
+        }
+        Ok(())
     }
 
-    /// Get order history
-    pub fn get_orders(&self, user_address: &str, query: &OrderHistoryQuery) -> OrderHistoryResponse {
-        self.engine.get_orders(user_address, query)
+    /// Take whatever is left of the order off the book
+    async fn cancel_remainder(&self, saga: &Saga, intent: &OrderIntent) -> Result<(), OrderFlowError> {
+        let (order_id, symbol, user) = (saga.order_id, saga.market_key.clone(), intent.user_address.clone());
+        self.shards
+            .execute(&saga.market_key, move |engine| engine.cancel_order(&symbol, order_id, &user))
+            .await
+            .and_then(|result| result)?;
+        Ok(())
+    }
+
+    /// Keep refused orders in their owner's history with the reason
+    async fn record_rejected(&self, saga: &Saga, intent: &OrderIntent, reason: RejectReason) {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO orders (
+                id, user_address, symbol, market_id, outcome_id, share_type,
+                side, order_type, price, amount, filled_amount, status, reject_reason, source,
+                signature, created_at, updated_at, time_in_force, post_only
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6::share_type,
+                $7::order_side, $8::order_type, $9, $10, 0, 'rejected'::order_status, $11, $12,
+                $13, NOW(), NOW(), $14::time_in_force, $15
+            )
+            "#,
+        )
+        .bind(saga.order_id)
+        .bind(&intent.user_address)
+        .bind(&saga.market_key)
+        .bind(intent.market_id)
+        .bind(intent.outcome_id)
+        .bind(intent.share_type.to_string())
+        .bind(intent.side.to_string())
+        .bind(intent.order_type.to_string())
+        .bind(intent.price)
+        .bind(intent.amount)
+        .bind(reason.code())
+        .bind(intent.source.as_str())
+        .bind(&intent.signature)
+        .bind(intent.time_in_force.to_string())
+        .bind(intent.post_only)
+        .execute(&self.pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::error!("Failed to record rejected {} order {}: {}", intent.source.as_str(), saga.order_id, e);
+        }
     }
 
     // ========================================================================
-    // Database Persistence
+    // Trade Persistence
     // ========================================================================
 
-    /// Persist a trade to database and update share positions
-    pub async fn persist_trade(pool: &PgPool, trade: &TradeEvent) -> Result<(), sqlx::Error> {
+    /// Persist a trade to database and update share holdings and balances.
+    ///
+    /// Runs in one transaction and is idempotent: a trade that is already
+    /// stored is skipped, so failed persists can be retried safely.
+    pub async fn persist_trade(pool: &PgPool, trade: &TradeEvent, collateral_token: &str) -> Result<(), sqlx::Error> {
         // Use the fees calculated by the matching engine
         let maker_fee = trade.maker_fee;
         let taker_fee = trade.taker_fee;
         let _trade_value = trade.amount * trade.price;
 
+        let mut tx = pool.begin().await?;
+
         // 1. Save trade record
-        sqlx::query(
+        let inserted = sqlx::query(
             r#"
             INSERT INTO trades (
                 id, symbol, market_id, outcome_id, share_type, match_type,
                 maker_order_id, taker_order_id, maker_address, taker_address,
-                side, price, amount, maker_fee, taker_fee, created_at
+                side, price, amount, maker_fee, taker_fee, created_at,
+                sequence, is_block_trade, is_rfq
             )
             VALUES (
                 $1, $2, $3, $4, $5::share_type, $6::match_type,
                 $7, $8, $9, $10,
-                $11::order_side, $12, $13, $14, $15, to_timestamp($16::double precision / 1000)
+                $11::order_side, $12, $13, $14, $15, to_timestamp($16::double precision / 1000),
+                $17, $18, $19
             )
             ON CONFLICT (id) DO NOTHING
             "#
@@ -260,334 +1334,531 @@ impl OrderFlowOrchestrator {
         .bind(maker_fee)
         .bind(taker_fee)
         .bind(trade.timestamp as f64)
-        .execute(pool)
-        .await?;
+        .bind((trade.sequence > 0).then_some(trade.sequence as i64))
+        .bind(trade.is_block_trade)
+        .bind(trade.is_rfq)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted == 0 {
+            debug!("Trade {} already persisted", trade.trade_id);
+            return Ok(());
+        }
 
         debug!("Persisted trade: {} (match_type={:?})", trade.trade_id, trade.match_type);
 
-        // 2. Update share positions based on match type
-        match trade.match_type {
-            MatchType::Normal => {
-                // Normal trade: transfer shares between maker and taker
-                Self::update_shares_normal(pool, trade).await?;
-            }
-            MatchType::Mint => {
-                // Mint: both parties receive new shares
-                Self::update_shares_mint(pool, trade).await?;
-            }
-            MatchType::Merge => {
-                // Merge: both parties redeem shares for collateral
-                Self::update_shares_merge(pool, trade).await?;
-            }
-        }
-
-        // 3. Record share changes for audit trail
-        Self::record_share_changes(pool, trade).await?;
+        // 2. Apply to holdings, balances and pair supply
+        holdings::apply_trade(&mut tx, trade, collateral_token).await?;
 
+        tx.commit().await?;
         debug!("Updated share positions for trade: {}", trade.trade_id);
         Ok(())
     }
+}
 
-    /// Update shares for normal trade (transfer between parties)
-    async fn update_shares_normal(pool: &PgPool, trade: &TradeEvent) -> Result<(), sqlx::Error> {
-        // Determine buyer and seller based on taker's side
-        let is_buy = trade.side.to_lowercase() == "buy";
-        let (buyer_address, seller_address) = if is_buy {
-            (&trade.taker_address, &trade.maker_address)
-        } else {
-            (&trade.maker_address, &trade.taker_address)
-        };
-
-        // Decrease seller's shares
-        sqlx::query(
-            r#"
-            INSERT INTO shares (user_address, market_id, outcome_id, share_type, amount, avg_cost)
-            VALUES ($1, $2, $3, $4::share_type, -$5, $6)
-            ON CONFLICT (user_address, outcome_id) DO UPDATE SET
-                amount = shares.amount - $5,
-                updated_at = NOW()
-            "#
-        )
-        .bind(seller_address)
-        .bind(trade.market_id)
-        .bind(trade.outcome_id)
-        .bind(trade.share_type.to_string())
-        .bind(trade.amount)
-        .bind(trade.price)
-        .execute(pool)
-        .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+    use rust_decimal_macros::dec;
 
-        // Increase buyer's shares
-        sqlx::query(
-            r#"
-            INSERT INTO shares (user_address, market_id, outcome_id, share_type, amount, avg_cost)
-            VALUES ($1, $2, $3, $4::share_type, $5, $6)
-            ON CONFLICT (user_address, outcome_id) DO UPDATE SET
-                amount = shares.amount + $5,
-                avg_cost = (shares.avg_cost * shares.amount + $6 * $5) / (shares.amount + $5),
-                updated_at = NOW()
-            "#
-        )
-        .bind(buyer_address)
-        .bind(trade.market_id)
-        .bind(trade.outcome_id)
-        .bind(trade.share_type.to_string())
-        .bind(trade.amount)
-        .bind(trade.price)
-        .execute(pool)
-        .await?;
+    const USER: &str = "0x3333333333333333333333333333333333333333";
 
-        Ok(())
+    fn buy(market_id: Uuid, outcome_id: Uuid, price: Decimal) -> OrderIntent {
+        OrderIntent {
+            source: OrderSource::Api,
+            user_address: USER.to_string(),
+            market_id,
+            outcome_id,
+            share_type: MarketShareType::Yes,
+            side: OrderSide::Buy,
+            order_type: ModelOrderType::Limit,
+            time_in_force: ModelTimeInForce::Gtc,
+            post_only: false,
+            stp: SelfTradePrevention::default(),
+            price,
+            amount: dec!(10),
+            expires_at: None,
+            signature: String::new(),
+        }
     }
 
-    /// Update shares for mint trade (create new shares)
-    async fn update_shares_mint(pool: &PgPool, trade: &TradeEvent) -> Result<(), sqlx::Error> {
-        // Both parties are buyers - each gets shares of their respective type
-        // Maker gets shares of the complement type (since match was cross-outcome)
-        let maker_share_type = trade.share_type.complement();
-        let taker_share_type = trade.share_type.clone();
+    /// A post-only buy, the only kind an atomic batch takes
+    fn maker(market_id: Uuid, outcome_id: Uuid, price: Decimal) -> OrderIntent {
+        OrderIntent {
+            post_only: true,
+            ..buy(market_id, outcome_id, price)
+        }
+    }
 
-        // Maker gets complement shares
-        sqlx::query(
-            r#"
-            INSERT INTO shares (user_address, market_id, outcome_id, share_type, amount, avg_cost)
-            VALUES ($1, $2, $3, $4::share_type, $5, $6)
-            ON CONFLICT (user_address, outcome_id) DO UPDATE SET
-                amount = shares.amount + $5,
-                avg_cost = (shares.avg_cost * shares.amount + $6 * $5) / (shares.amount + $5),
-                updated_at = NOW()
-            "#
-        )
-        .bind(&trade.maker_address)
-        .bind(trade.market_id)
-        .bind(trade.outcome_id)
-        .bind(maker_share_type.to_string())
-        .bind(trade.amount)
-        .bind(Decimal::ONE - trade.price)  // Complement price
-        .execute(pool)
-        .await?;
+    async fn balance(app: &TestApp) -> (Decimal, Decimal) {
+        sqlx::query_as("SELECT available, frozen FROM balances WHERE user_address = $1 AND token = $2")
+            .bind(USER)
+            .bind(app.state.config.collateral_symbol())
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap()
+    }
 
-        // Taker gets taker's share type
-        sqlx::query(
-            r#"
-            INSERT INTO shares (user_address, market_id, outcome_id, share_type, amount, avg_cost)
-            VALUES ($1, $2, $3, $4::share_type, $5, $6)
-            ON CONFLICT (user_address, outcome_id) DO UPDATE SET
-                amount = shares.amount + $5,
-                avg_cost = (shares.avg_cost * shares.amount + $6 * $5) / (shares.amount + $5),
-                updated_at = NOW()
-            "#
-        )
-        .bind(&trade.taker_address)
-        .bind(trade.market_id)
-        .bind(trade.outcome_id)
-        .bind(taker_share_type.to_string())
-        .bind(trade.amount)
-        .bind(trade.price)
-        .execute(pool)
-        .await?;
+    #[tokio::test]
+    async fn test_refused_reserve_is_recorded() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        app.deposit(USER, dec!(1)).await;
 
-        Ok(())
+        let err = app.state.order_flow.place(&buy(market_id, outcome_id, dec!(0.4))).await.unwrap_err();
+        assert!(matches!(err, OrderFlowError::InsufficientBalance { .. }));
+        assert_eq!(balance(&app).await, (dec!(1), dec!(0)));
+        let reason: String = sqlx::query_scalar("SELECT reject_reason FROM orders WHERE market_id = $1")
+            .bind(market_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(reason, "INSUFFICIENT_BALANCE");
     }
 
-    /// Update shares for merge trade (redeem shares for collateral)
-    async fn update_shares_merge(pool: &PgPool, trade: &TradeEvent) -> Result<(), sqlx::Error> {
-        // Both parties are sellers - each loses shares, gets collateral back
-        let maker_share_type = trade.share_type.complement();
-        let taker_share_type = trade.share_type.clone();
+    #[tokio::test]
+    async fn test_orders_refused_outside_trading_hours() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        app.deposit(USER, dec!(100)).await;
 
-        // Decrease maker's shares
-        sqlx::query(
-            r#"
-            INSERT INTO shares (user_address, market_id, outcome_id, share_type, amount, avg_cost)
-            VALUES ($1, $2, $3, $4::share_type, -$5, 0)
-            ON CONFLICT (user_address, outcome_id) DO UPDATE SET
-                amount = shares.amount - $5,
-                updated_at = NOW()
-            "#
-        )
-        .bind(&trade.maker_address)
-        .bind(trade.market_id)
-        .bind(trade.outcome_id)
-        .bind(maker_share_type.to_string())
-        .bind(trade.amount)
-        .execute(pool)
-        .await?;
+        let now = Utc::now();
+        let halt_end = now + chrono::Duration::hours(1);
+        let schedule = trading_calendar::TradingSchedule {
+            timezone: chrono_tz::Tz::UTC,
+            sessions: Vec::new(),
+            halts: vec![trading_calendar::Halt {
+                starts_at: now - chrono::Duration::hours(1),
+                ends_at: halt_end,
+                reason: Some("maintenance".to_string()),
+            }],
+        };
+        assert!(trading_calendar::save(&app.db.pool, market_id, &schedule).await.unwrap());
 
-        // Decrease taker's shares
-        sqlx::query(
-            r#"
-            INSERT INTO shares (user_address, market_id, outcome_id, share_type, amount, avg_cost)
-            VALUES ($1, $2, $3, $4::share_type, -$5, 0)
-            ON CONFLICT (user_address, outcome_id) DO UPDATE SET
-                amount = shares.amount - $5,
-                updated_at = NOW()
-            "#
-        )
-        .bind(&trade.taker_address)
-        .bind(trade.market_id)
-        .bind(trade.outcome_id)
-        .bind(taker_share_type.to_string())
-        .bind(trade.amount)
-        .execute(pool)
-        .await?;
+        let err = app.state.order_flow.place(&buy(market_id, outcome_id, dec!(0.4))).await.unwrap_err();
+        match err {
+            OrderFlowError::OutsideTradingHours { next_open } => {
+                assert_eq!(next_open.map(|t| t.timestamp_millis()), Some(halt_end.timestamp_millis()))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
 
-        // TODO: Credit collateral back to both parties' balances
-        // This should be done through the balance service
+        trading_calendar::clear(&app.db.pool, market_id).await.unwrap();
+        app.state.order_flow.place(&buy(market_id, outcome_id, dec!(0.4))).await.unwrap();
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn test_unfilled_fok_and_ioc_release_collateral() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        let user = format!("0x{}00000000", Uuid::new_v4().simple());
+        app.deposit(&user, dec!(100)).await;
+        let flow = &app.state.order_flow;
+        let order = |time_in_force| OrderIntent {
+            user_address: user.clone(),
+            time_in_force,
+            ..buy(market_id, outcome_id, dec!(0.4))
+        };
+
+        // Nothing to fill against: FOK is rejected, IOC is cancelled
+        let err = flow.place(&order(ModelTimeInForce::Fok)).await.unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::NoLiquidity);
+        let placed = flow.place(&order(ModelTimeInForce::Ioc)).await.unwrap();
+        assert_eq!((placed.status, placed.filled_amount), (OrderStatus::Cancelled, dec!(0)));
+
+        let balance: (Decimal, Decimal) =
+            sqlx::query_as("SELECT available, frozen FROM balances WHERE user_address = $1 AND token = $2")
+                .bind(&user)
+                .bind(app.state.config.collateral_symbol())
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!(balance, (dec!(100), dec!(0)));
+        let recorded: Vec<(String, String)> =
+            sqlx::query_as("SELECT time_in_force::text, status::text FROM orders WHERE user_address = $1 ORDER BY 1")
+                .bind(&user)
+                .fetch_all(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            recorded,
+            [("fok".to_string(), "rejected".to_string()), ("ioc".to_string(), "cancelled".to_string())]
+        );
     }
 
-    /// Record share changes for audit trail
-    async fn record_share_changes(pool: &PgPool, trade: &TradeEvent) -> Result<(), sqlx::Error> {
-        let change_type = match trade.match_type {
-            MatchType::Normal => if trade.side.to_lowercase() == "buy" { "buy" } else { "sell" },
-            MatchType::Mint => "mint",
-            MatchType::Merge => "merge",
+    #[tokio::test]
+    async fn test_post_only_rejected_when_it_would_take() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        let (maker, user) = (
+            format!("0x{}00000000", Uuid::new_v4().simple()),
+            format!("0x{}00000000", Uuid::new_v4().simple()),
+        );
+        app.deposit(&maker, dec!(100)).await;
+        app.deposit(&user, dec!(100)).await;
+        let flow = &app.state.order_flow;
+        // A No bid at 0.55 mints against Yes bids at 0.45 and up
+        let no_bid = OrderIntent {
+            user_address: maker.clone(),
+            share_type: MarketShareType::No,
+            ..buy(market_id, outcome_id, dec!(0.55))
         };
+        flow.place(&no_bid).await.unwrap();
 
-        // Record maker change
-        sqlx::query(
-            r#"
-            INSERT INTO share_changes (
-                user_address, market_id, outcome_id, share_type,
-                change_type, amount, price, trade_id, order_id
-            )
-            VALUES ($1, $2, $3, $4::share_type, $5, $6, $7, $8, $9)
-            "#
+        let post_only = |price| OrderIntent {
+            user_address: user.clone(),
+            post_only: true,
+            ..buy(market_id, outcome_id, price)
+        };
+        let err = flow.place(&post_only(dec!(0.45))).await.unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::PostOnlyWouldTake);
+        let placed = flow.place(&post_only(dec!(0.44))).await.unwrap();
+        assert_eq!((placed.status, placed.filled_amount), (OrderStatus::Open, dec!(0)));
+
+        let recorded: Vec<(bool, String)> =
+            sqlx::query_as("SELECT post_only, status::text FROM orders WHERE user_address = $1 ORDER BY price")
+                .bind(&user)
+                .fetch_all(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!(recorded, [(true, "open".to_string()), (true, "rejected".to_string())]);
+        // Only the resting order holds collateral
+        let balance: (Decimal, Decimal) =
+            sqlx::query_as("SELECT available, frozen FROM balances WHERE user_address = $1 AND token = $2")
+                .bind(&user)
+                .bind(app.state.config.collateral_symbol())
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!(balance, (dec!(95.6), dec!(4.4)));
+    }
+
+    #[tokio::test]
+    async fn test_self_trade_prevention_releases_collateral() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        app.deposit(USER, dec!(100)).await;
+        let flow = &app.state.order_flow;
+        let before = balance(&app).await;
+
+        // Own No bid for 4 at 0.55 would mint with a Yes bid at 0.50
+        let no_bid = flow
+            .place(&OrderIntent {
+                share_type: MarketShareType::No,
+                amount: dec!(4),
+                ..buy(market_id, outcome_id, dec!(0.55))
+            })
+            .await
+            .unwrap();
+        let placed = flow
+            .place(&OrderIntent {
+                stp: SelfTradePrevention::Decrement,
+                ..buy(market_id, outcome_id, dec!(0.50))
+            })
+            .await
+            .unwrap();
+        assert_eq!((placed.status, placed.filled_amount), (OrderStatus::Open, dec!(0)));
+
+        // The No bid is gone and the Yes bid rests for the other 6
+        let recorded: Vec<(Uuid, Decimal, String)> = sqlx::query_as(
+            "SELECT id, amount, status::text FROM orders WHERE id = ANY($1) ORDER BY amount",
         )
-        .bind(&trade.maker_address)
-        .bind(trade.market_id)
-        .bind(trade.outcome_id)
-        .bind(trade.share_type.to_string())
-        .bind(change_type)
-        .bind(trade.amount)
-        .bind(trade.price)
-        .bind(trade.trade_id)
-        .bind(trade.maker_order_id)
-        .execute(pool)
-        .await?;
+        .bind(vec![no_bid.order_id, placed.order_id])
+        .fetch_all(&app.db.pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            recorded,
+            [
+                (no_bid.order_id, dec!(4), "cancelled".to_string()),
+                (placed.order_id, dec!(6), "open".to_string())
+            ]
+        );
+        // Only the 6 left at 0.50 hold collateral
+        assert_eq!(balance(&app).await, (before.0 - dec!(3), before.1 + dec!(3)));
+    }
 
-        // Record taker change
-        sqlx::query(
-            r#"
-            INSERT INTO share_changes (
-                user_address, market_id, outcome_id, share_type,
-                change_type, amount, price, trade_id, order_id
+    #[tokio::test]
+    async fn test_amendment_funds_growth_and_releases_shrink() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        let (maker, user) = (
+            format!("0x{}00000000", Uuid::new_v4().simple()),
+            format!("0x{}00000000", Uuid::new_v4().simple()),
+        );
+        app.deposit(&maker, dec!(100)).await;
+        app.deposit(&user, dec!(10)).await;
+        let flow = &app.state.order_flow;
+        let balance = || async {
+            sqlx::query_as::<_, (Decimal, Decimal)>(
+                "SELECT available, frozen FROM balances WHERE user_address = $1 AND token = $2",
             )
-            VALUES ($1, $2, $3, $4::share_type, $5, $6, $7, $8, $9)
-            "#
+            .bind(&user)
+            .bind(app.state.config.collateral_symbol())
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap()
+        };
+        // A No bid at 0.45 mints against Yes bids at 0.55 and up
+        flow.place(&OrderIntent {
+            user_address: maker.clone(),
+            share_type: MarketShareType::No,
+            ..buy(market_id, outcome_id, dec!(0.45))
+        })
+        .await
+        .unwrap();
+        let placed = flow
+            .place(&OrderIntent { user_address: user.clone(), ..buy(market_id, outcome_id, dec!(0.4)) })
+            .await
+            .unwrap();
+        assert_eq!(balance().await, (dec!(6), dec!(4)));
+
+        let mut amendment = OrderAmendment {
+            order_id: placed.order_id,
+            user_address: user.clone(),
+            market_id,
+            outcome_id,
+            share_type: MarketShareType::Yes,
+            side: OrderSide::Buy,
+            price: dec!(0.4),
+            amount: dec!(10),
+            filled_amount: dec!(0),
+            new_price: dec!(0.5),
+            new_amount: dec!(20),
+        };
+        let amended = flow.amend(&amendment).await.unwrap().unwrap();
+        assert!(!amended.kept_priority);
+        assert_eq!(balance().await, (dec!(0), dec!(10)));
+
+        // Unfunded growth and prices that would trade leave the order as it was
+        (amendment.price, amendment.amount) = (dec!(0.5), dec!(20));
+        let err = flow.amend(&OrderAmendment { new_amount: dec!(30), ..amendment.clone() }).await.unwrap_err();
+        assert!(matches!(err, OrderFlowError::InsufficientBalance { .. }));
+        let err = flow.amend(&OrderAmendment { new_price: dec!(0.55), new_amount: dec!(5), ..amendment.clone() }).await.unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::PostOnlyWouldTake);
+        assert_eq!(balance().await, (dec!(0), dec!(10)));
+
+        let amended = flow.amend(&OrderAmendment { new_amount: dec!(5), ..amendment }).await.unwrap().unwrap();
+        assert!(amended.kept_priority);
+        assert_eq!(balance().await, (dec!(7.5), dec!(2.5)));
+        let stored: (Decimal, Decimal) = sqlx::query_as("SELECT price, amount FROM orders WHERE id = $1")
+            .bind(placed.order_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, (dec!(0.5), dec!(5)));
+    }
+
+    #[tokio::test]
+    async fn test_open_order_caps_and_queue_position() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        let flow = OrderFlowOrchestrator::new(
+            app.db.pool.clone(),
+            app.state.engine_shards.clone(),
+            app.state.write_batcher.clone(),
+            app.state.config.collateral_symbol().to_string(),
         )
-        .bind(&trade.taker_address)
-        .bind(trade.market_id)
-        .bind(trade.outcome_id)
-        .bind(trade.share_type.to_string())
-        .bind(change_type)
-        .bind(trade.amount)
-        .bind(trade.price)
-        .bind(trade.trade_id)
-        .bind(trade.taker_order_id)
-        .execute(pool)
-        .await?;
+        .with_open_order_caps(OpenOrderCaps { per_account: 2, per_market: 3 });
+        let (alice, bob) = (
+            format!("0x{}00000000", Uuid::new_v4().simple()),
+            format!("0x{}00000000", Uuid::new_v4().simple()),
+        );
+        app.deposit(&alice, dec!(100)).await;
+        app.deposit(&bob, dec!(100)).await;
+        let order = |user: &str| OrderIntent { user_address: user.to_string(), ..buy(market_id, outcome_id, dec!(0.4)) };
 
-        Ok(())
+        let first = flow.place(&order(&alice)).await.unwrap();
+        let second = flow.place(&order(&alice)).await.unwrap();
+        assert_eq!(
+            (first.queue_position.unwrap().position, first.queue_position.unwrap().ahead_amount),
+            (1, dec!(0))
+        );
+        let queued = second.queue_position.unwrap();
+        assert_eq!((queued.price, queued.position, queued.ahead_amount, queued.level_amount), (dec!(0.4), 2, dec!(10), dec!(20)));
+
+        let err = flow.place(&order(&alice)).await.unwrap_err();
+        assert_eq!(err.reject_reason(), RejectReason::TooManyOpenOrders);
+        // Orders that cannot rest are not capped
+        let ioc = OrderIntent { time_in_force: ModelTimeInForce::Ioc, ..order(&alice) };
+        assert!(flow.place(&ioc).await.unwrap().queue_position.is_none());
+
+        flow.place(&order(&bob)).await.unwrap();
+        let err = flow.place(&order(&bob)).await.unwrap_err();
+        assert!(matches!(err, OrderFlowError::TooManyOpenOrders { scope: "market", limit: 3 }));
     }
 
-    /// Persist an order to database
-    async fn persist_order(
-        pool: &PgPool,
-        market_id: Uuid,
-        outcome_id: Uuid,
-        share_type: ShareType,
-        user_address: &str,
-        result: &MatchResult,
-        side: Side,
-        order_type: OrderType,
-        amount: Decimal,
-        price: Decimal,
-    ) -> Result<(), sqlx::Error> {
-        let status = match result.status {
-            OrderStatus::Open => "open",
-            OrderStatus::PartiallyFilled => "partially_filled",
-            OrderStatus::Filled => "filled",
-            OrderStatus::Cancelled => "cancelled",
-            OrderStatus::Rejected => "rejected",
+    #[tokio::test]
+    async fn test_atomic_batch_places_all_or_nothing() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        app.deposit(USER, dec!(100)).await;
+        let flow = &app.state.order_flow;
+        let resting = maker(market_id, outcome_id, dec!(0.4));
+        // Mints against the first order of the batch
+        let crossing = OrderIntent {
+            share_type: MarketShareType::No,
+            ..maker(market_id, outcome_id, dec!(0.7))
+        };
+        let fok = OrderIntent {
+            time_in_force: ModelTimeInForce::Fok,
+            ..buy(market_id, outcome_id, dec!(0.5))
+        };
+        let invalid = maker(market_id, outcome_id, dec!(1.5));
+        let statuses = || async {
+            sqlx::query_scalar::<_, String>("SELECT status::text FROM orders WHERE market_id = $1 ORDER BY 1")
+                .bind(market_id)
+                .fetch_all(&app.db.pool)
+                .await
+                .unwrap()
         };
 
-        sqlx::query(
-            r#"
-            INSERT INTO orders (
-                id, market_id, outcome_id, share_type, user_address,
-                side, order_type, status, price, amount, filled_amount, created_at
-            )
-            VALUES (
-                $1, $2, $3, $4::share_type, $5,
-                $6::order_side, $7::order_type, $8::order_status, $9, $10, $11, NOW()
-            )
-            ON CONFLICT (id) DO UPDATE SET
-                status = $8::order_status,
-                filled_amount = $11,
-                updated_at = NOW()
-            "#
-        )
-        .bind(result.order_id)
-        .bind(market_id)
-        .bind(outcome_id)
-        .bind(share_type.to_string())
-        .bind(user_address)
-        .bind(side.to_string())
-        .bind(order_type.to_string())
-        .bind(status)
-        .bind(price)
-        .bind(amount)
-        .bind(result.filled_amount)
-        .execute(pool)
-        .await?;
+        // Bad terms anywhere place nothing
+        let results = flow.place_batch(&[resting.clone(), invalid], true).await;
+        assert!(matches!(results[0], BatchPlacement::RolledBack));
+        assert!(matches!(results[1], BatchPlacement::Failed(OrderFlowError::InvalidPrice(_))));
+        assert!(statuses().await.is_empty());
 
-        // Update maker orders if there were trades
-        for trade in &result.trades {
-            sqlx::query(
-                r#"
-                UPDATE orders
-                SET filled_amount = filled_amount + $1,
-                    status = CASE
-                        WHEN filled_amount + $1 >= amount THEN 'filled'::order_status
-                        ELSE 'partially_filled'::order_status
-                    END,
-                    updated_at = NOW()
-                WHERE id = $2
-                "#
-            )
-            .bind(trade.amount)
-            .bind(trade.maker_order_id)
-            .execute(pool)
-            .await?;
-        }
+        // Only post-only orders of one market, refused rather than narrowed
+        let results = flow.place_batch(&[resting.clone(), fok.clone()], true).await;
+        assert!(matches!(results[1], BatchPlacement::Failed(OrderFlowError::AtomicBatchTaker)));
+        let taker = buy(market_id, outcome_id, dec!(0.4));
+        let results = flow.place_batch(&[resting.clone(), taker], true).await;
+        assert!(matches!(results[0], BatchPlacement::RolledBack));
+        assert!(matches!(results[1], BatchPlacement::Failed(OrderFlowError::AtomicBatchTaker)));
+        let elsewhere = OrderIntent {
+            market_id: Uuid::new_v4(),
+            ..resting.clone()
+        };
+        let results = flow.place_batch(&[resting.clone(), elsewhere], true).await;
+        assert!(matches!(results[1], BatchPlacement::Failed(OrderFlowError::AtomicBatchMarkets)));
+        assert!(statuses().await.is_empty());
 
-        debug!("Persisted order: {}", result.order_id);
-        Ok(())
+        // An order that would trade refuses the batch before anything fills
+        let results = flow.place_batch(&[resting.clone(), crossing, resting.clone()], true).await;
+        assert!(matches!(results[0], BatchPlacement::RolledBack));
+        let BatchPlacement::Failed(e) = &results[1] else { panic!("{:?}", results[1]) };
+        assert_eq!(e.reject_reason(), RejectReason::PostOnlyWouldTake);
+        assert!(matches!(results[2], BatchPlacement::RolledBack));
+        assert_eq!(statuses().await, vec!["rejected"]);
+        assert_eq!(balance(&app).await, (dec!(100), dec!(0)));
+        let key = format!("{}:{}:{}", market_id, outcome_id, resting.share_type);
+        let book_key = key.clone();
+        let book = app
+            .state
+            .engine_shards
+            .execute(&key, move |engine| engine.get_orderbook(&book_key, 10))
+            .await
+            .and_then(|book| book)
+            .unwrap();
+        assert!(book.bids.is_empty());
+
+        let results = flow.place_batch(&[resting.clone(), resting.clone()], true).await;
+        let positions: Vec<usize> = results
+            .iter()
+            .map(|result| match result {
+                BatchPlacement::Placed(placed) => placed.queue_position.as_ref().unwrap().position,
+                other => panic!("{:?}", other),
+            })
+            .collect();
+        assert_eq!(positions, vec![1, 2]);
+        assert_eq!(statuses().await, vec!["open", "open", "rejected"]);
+        assert_eq!(balance(&app).await, (dec!(92), dec!(8)));
+
+        // Sells of the batch share the holding between them
+        app.grant_shares(USER, market_id, outcome_id, MarketShareType::Yes, dec!(15)).await;
+        let sell = OrderIntent {
+            side: OrderSide::Sell,
+            ..maker(market_id, outcome_id, dec!(0.9))
+        };
+        let results = flow.place_batch(&[sell.clone(), sell.clone()], true).await;
+        assert!(matches!(results[0], BatchPlacement::RolledBack));
+        assert!(matches!(
+            results[1],
+            BatchPlacement::Failed(OrderFlowError::InsufficientShares { required, held })
+                if required == dec!(20) && held == dec!(15)
+        ));
+        assert!(matches!(flow.place_batch(&[sell], true).await[0], BatchPlacement::Placed(_)));
+
+        // Without atomic, each order stands on its own
+        let results = flow.place_batch(&[resting, fok], false).await;
+        assert!(matches!(results[0], BatchPlacement::Placed(_)));
+        assert!(matches!(results[1], BatchPlacement::Failed(_)));
+        assert_eq!(balance(&app).await, (dec!(88), dec!(12)));
     }
 
-    /// Update order status
-    async fn update_order_status(pool: &PgPool, order_id: Uuid, status: &str) -> Result<(), sqlx::Error> {
-        sqlx::query(
-            r#"
-            UPDATE orders
-            SET status = $1::order_status, updated_at = NOW()
-            WHERE id = $2
-            "#
-        )
-        .bind(status)
-        .bind(order_id)
-        .execute(pool)
-        .await?;
+    #[tokio::test]
+    async fn test_atomic_batch_rests_nothing_when_its_commit_fails() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        app.deposit(USER, dec!(100)).await;
+        // Fails the batch's transaction at commit, after every step inside it passed
+        for statement in [
+            "CREATE FUNCTION fail_commit() RETURNS TRIGGER AS $$
+             BEGIN RAISE EXCEPTION 'commit refused'; END;
+             $$ LANGUAGE plpgsql",
+            "CREATE CONSTRAINT TRIGGER orders_fail_commit AFTER INSERT ON orders
+             DEFERRABLE INITIALLY DEFERRED FOR EACH ROW EXECUTE FUNCTION fail_commit()",
+        ] {
+            sqlx::query(statement).execute(&app.db.pool).await.unwrap();
+        }
+        let resting = maker(market_id, outcome_id, dec!(0.4));
 
-        debug!("Updated order status: id={}, status={}", order_id, status);
-        Ok(())
+        let results = app.state.order_flow.place_batch(&[resting.clone(), resting.clone()], true).await;
+        assert!(matches!(results[0], BatchPlacement::Failed(OrderFlowError::Database(_))));
+        assert!(matches!(results[1], BatchPlacement::RolledBack));
+        let orders: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders")
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(orders, 0);
+        assert_eq!(balance(&app).await, (dec!(100), dec!(0)));
+        let key = format!("{}:{}:{}", market_id, outcome_id, resting.share_type);
+        let book_key = key.clone();
+        let book = app
+            .state
+            .engine_shards
+            .execute(&key, move |engine| engine.get_orderbook(&book_key, 10))
+            .await
+            .and_then(|book| book)
+            .ok();
+        assert!(book.is_none_or(|book| book.bids.is_empty()));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    // Integration tests would require a database connection
+    #[tokio::test]
+    async fn test_compensation_releases_collateral_and_clears_book() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        app.deposit(USER, dec!(100)).await;
+        let flow = &app.state.order_flow;
+        let intent = buy(market_id, outcome_id, dec!(0.4));
+        let mut saga = Saga {
+            order_id: Uuid::new_v4(),
+            market_key: format!("{}:{}:{}", market_id, outcome_id, intent.share_type),
+            completed: vec![FlowStep::Validate],
+            filled_amount: Decimal::ZERO,
+            decremented: Decimal::ZERO,
+        };
+
+        let mut tx = app.db.pool.begin().await.unwrap();
+        flow.reserve(&mut tx, &intent, Decimal::ZERO).await.unwrap();
+        tx.commit().await.unwrap();
+        saga.completed.push(FlowStep::Reserve);
+        flow.submit(&saga, &intent).await.unwrap();
+        saga.completed.push(FlowStep::Match);
+        assert_eq!(balance(&app).await, (dec!(96), dec!(4)));
+
+        flow.compensate(&saga, &intent).await;
+        assert_eq!(balance(&app).await, (dec!(100), dec!(0)));
+        let key = saga.market_key.clone();
+        let book = app
+            .state
+            .engine_shards
+            .execute(&saga.market_key, move |engine| engine.get_orderbook(&key, 10))
+            .await
+            .and_then(|book| book)
+            .unwrap();
+        assert!(book.bids.is_empty());
+    }
 }
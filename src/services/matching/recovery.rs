@@ -0,0 +1,487 @@
+//! Orderbook Recovery
+//!
+//! Rebuilds the in-memory orderbooks on startup, so resting liquidity
+//! survives restarts.
+//!
+//! Orders are replayed from the order journal (see [`super::journal`]) as
+//! they last rested: remaining size, price and queue priority, without
+//! matching.
+//! If the persisted books are crossed, matching them is a separate step,
+//! [`resolve_crossed_books`], enabled by `RECOVERY_RESOLVE_CROSSED`.
+//!
+//! Every recovered order is written to `order_recovery_log`. Entries whose
+//! order filled or could not be restored are delivered to the owner on their
+//! next `orders` subscription (see [`take_pending_notices`]).
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use polymarket_engine::{MatchingEngine, OrderEntry, Side, TimeInForce, TradeEvent};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Row};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::journal;
+use crate::models::{Address, OrderSide};
+use crate::services::trade_persistence::TradePersistQueue;
+
+/// What recovery did to one order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryOutcome {
+    /// Back on the book unchanged
+    Restored,
+    /// Matched part of its remaining amount when crossed books were resolved
+    PartiallyFilled,
+    /// Matched its whole remaining amount when crossed books were resolved
+    Filled,
+    /// Rejected by the engine; the order is not on the book
+    Failed,
+}
+
+impl RecoveryOutcome {
+    fn from_remaining(before: Decimal, after: Decimal) -> Self {
+        if after <= Decimal::ZERO {
+            Self::Filled
+        } else if after < before {
+            Self::PartiallyFilled
+        } else {
+            Self::Restored
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Restored => "restored",
+            Self::PartiallyFilled => "partially_filled",
+            Self::Filled => "filled",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// One order re-inserted into the engine
+#[derive(Debug, Clone)]
+pub struct RecoveredOrder {
+    pub order_id: Uuid,
+    pub user_address: Address,
+    pub symbol: String,
+    pub side: Side,
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub remaining_before: Decimal,
+    pub remaining_after: Decimal,
+    pub outcome: RecoveryOutcome,
+    pub trade_ids: Vec<Uuid>,
+    pub error: Option<String>,
+}
+
+/// Everything a recovery run did, in submission order
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    pub orders: Vec<RecoveredOrder>,
+}
+
+impl RecoveryReport {
+    /// Orders now resting on the book (possibly partially filled)
+    pub fn restored_count(&self) -> usize {
+        self.orders
+            .iter()
+            .filter(|o| matches!(o.outcome, RecoveryOutcome::Restored | RecoveryOutcome::PartiallyFilled))
+            .count()
+    }
+
+    /// Account for a trade the order took part in
+    fn record_fill(&mut self, index: &HashMap<Uuid, usize>, order_id: Uuid, trade_id: Uuid, amount: Decimal) {
+        let Some(order) = index.get(&order_id).map(|&i| &mut self.orders[i]) else {
+            return;
+        };
+        order.remaining_after -= amount;
+        order.trade_ids.push(trade_id);
+        order.outcome = RecoveryOutcome::from_remaining(order.remaining_before, order.remaining_after);
+    }
+}
+
+/// Recover open limit orders from database on startup
+/// This ensures orderbook state is preserved after restart
+pub async fn recover_orders_from_db(engine: &MatchingEngine, pool: &PgPool) -> anyhow::Result<usize> {
+    info!("🔄 Starting order recovery from database...");
+
+    if let Err(e) = journal::compact(pool).await {
+        warn!("Failed to compact order journal: {}", e);
+    }
+    let report = restore_orders(engine, pool).await?;
+    if let Err(e) = record_recovery(pool, &report).await {
+        warn!("Failed to record order recovery audit log: {}", e);
+    }
+
+    let failed = report.orders.iter().filter(|o| o.outcome == RecoveryOutcome::Failed).count();
+    if failed > 0 {
+        warn!("{} open orders could not be restored; owners will be notified", failed);
+    }
+
+    let recovered_count = report.restored_count();
+    info!("✅ Order recovery complete: {} orders restored to orderbook", recovered_count);
+    Ok(recovered_count)
+}
+
+/// Put resting orders back on their books without matching or recording
+/// anything. Orders are replayed from the order journal as it last saw
+/// them; open orders the journal never saw (placed before it existed) are
+/// taken from the orders table. The outcomes of open categorical markets
+/// are registered with the engine first, so their books match in sets.
+pub async fn restore_orders(engine: &MatchingEngine, pool: &PgPool) -> anyhow::Result<RecoveryReport> {
+    register_outcome_sets(engine, pool).await?;
+
+    let mut resting: Vec<(i64, i64, OrderEntry, String, Address)> = journal::resting_orders(pool)
+        .await?
+        .into_iter()
+        .map(|order| {
+            let entry = OrderEntry {
+                id: order.order_id,
+                user_address: order.user_address.as_str().to_string(),
+                price: order.price,
+                original_amount: order.original_amount,
+                remaining_amount: order.remaining_amount,
+                side: if order.side == "sell" { Side::Sell } else { Side::Buy },
+                time_in_force: TimeInForce::GTC,
+                timestamp: order.priority,
+            };
+            (order.priority, order.queued_seq, entry, order.symbol, order.user_address)
+        })
+        .collect();
+
+    let rows = sqlx::query(
+        r#"
+        SELECT id, symbol, user_address, side, price, amount, filled_amount, created_at
+        FROM orders o
+        WHERE status IN ('open', 'partially_filled') AND order_type = 'limit'
+          AND NOT EXISTS (SELECT 1 FROM order_journal j WHERE j.order_id = o.id)
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    for row in rows {
+        let order_id: Uuid = row.get("id");
+        let amount: Decimal = row.get("amount");
+        let remaining_amount = amount - row.get::<Decimal, _>("filled_amount");
+        if remaining_amount <= Decimal::ZERO {
+            warn!("Order {} has no remaining amount, skipping", order_id);
+            continue;
+        }
+        let created_at: chrono::DateTime<chrono::Utc> = row.get("created_at");
+        let user_address: Address = row.get("user_address");
+        let entry = OrderEntry {
+            id: order_id,
+            user_address: user_address.as_str().to_string(),
+            price: row.get("price"),
+            original_amount: amount,
+            remaining_amount,
+            side: match row.get::<OrderSide, _>("side") {
+                OrderSide::Buy => Side::Buy,
+                OrderSide::Sell => Side::Sell,
+            },
+            time_in_force: TimeInForce::GTC,
+            timestamp: created_at.timestamp_millis(),
+        };
+        resting.push((entry.timestamp, i64::MAX, entry, row.get("symbol"), user_address));
+    }
+    resting.sort_by_key(|(priority, seq, ..)| (*priority, *seq));
+
+    let order_ids: Vec<Uuid> = resting.iter().map(|(_, _, entry, ..)| entry.id).collect();
+    let expiries: HashMap<Uuid, chrono::DateTime<chrono::Utc>> =
+        sqlx::query_as("SELECT id, expires_at FROM orders WHERE id = ANY($1) AND expires_at IS NOT NULL")
+            .bind(&order_ids)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect();
+
+    let mut report = RecoveryReport::default();
+    for (_, _, entry, symbol, user_address) in resting {
+        let mut recovered = RecoveredOrder {
+            order_id: entry.id,
+            user_address,
+            symbol: symbol.clone(),
+            side: entry.side,
+            price: entry.price,
+            amount: entry.original_amount,
+            remaining_before: entry.remaining_amount,
+            remaining_after: entry.remaining_amount,
+            outcome: RecoveryOutcome::Restored,
+            trade_ids: Vec::new(),
+            error: None,
+        };
+
+        // Back on the book with its queue priority
+        let (order_id, side, price, remaining_amount) = (entry.id, entry.side, entry.price, entry.remaining_amount);
+        match engine.restore_order(&symbol, entry) {
+            Ok(()) => {
+                // Already lapsed ones expire on the sweeper's first pass
+                if let Some(expires_at) = expiries.get(&order_id) {
+                    engine.set_order_expiry(&symbol, order_id, expires_at.timestamp_millis());
+                }
+                debug!("✅ Recovered order {}: {} {} @ {} (remaining: {})",
+                    order_id, side, symbol, price, remaining_amount);
+            }
+            Err(e) => {
+                warn!("Failed to recover order {}: {}", order_id, e);
+                recovered.outcome = RecoveryOutcome::Failed;
+                recovered.error = Some(e.to_string());
+            }
+        }
+
+        report.orders.push(recovered);
+    }
+
+    Ok(report)
+}
+
+/// Add a fill to the report entry of `order_id`, loading the order into
+/// the report the first time it fills
+async fn track_fill(
+    pool: &PgPool,
+    report: &mut RecoveryReport,
+    index: &mut HashMap<Uuid, usize>,
+    order_id: Uuid,
+    trade_id: Uuid,
+    amount: Decimal,
+) -> anyhow::Result<()> {
+    if let Entry::Vacant(slot) = index.entry(order_id) {
+        if let Some(order) = load_recovered(pool, order_id).await? {
+            slot.insert(report.orders.len());
+            report.orders.push(order);
+        }
+    }
+    report.record_fill(index, order_id, trade_id, amount);
+    Ok(())
+}
+
+/// Register the outcomes of every categorical market not yet resolved or
+/// cancelled (see [`MatchingEngine::register_outcome_set`])
+pub async fn register_outcome_sets(engine: &MatchingEngine, pool: &PgPool) -> anyhow::Result<usize> {
+    let sets: Vec<(Uuid, Vec<Uuid>)> = sqlx::query_as(
+        r#"
+        SELECT m.id, ARRAY_AGG(o.id ORDER BY o.outcome_index)
+        FROM markets m
+        JOIN outcomes o ON o.market_id = m.id
+        WHERE m.market_type = 'categorical' AND m.status NOT IN ('resolved', 'cancelled')
+        GROUP BY m.id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut registered = 0;
+    for (market_id, outcome_ids) in sets {
+        match engine.register_outcome_set(market_id, outcome_ids) {
+            Ok(()) => registered += 1,
+            Err(e) => warn!("Categorical market {} not registered: {}", market_id, e),
+        }
+    }
+    Ok(registered)
+}
+
+/// Match orders that [`restore_orders`] left crossed. The later order of
+/// each crossing pair trades as the taker; trades are persisted, the orders
+/// updated and every order involved is logged for its owner. Returns the
+/// number of trades.
+pub async fn resolve_crossed_books(
+    engine: &MatchingEngine,
+    pool: &PgPool,
+    persist_queue: &TradePersistQueue,
+) -> anyhow::Result<usize> {
+    let mut report = RecoveryReport::default();
+    let mut index = HashMap::new();
+    let mut trade_count = 0;
+
+    for symbol in engine.crossed_books() {
+        warn!("Orderbook {} was restored crossed, resolving", symbol);
+        for (key, taker, result) in engine.resolve_crossed(&symbol) {
+            for trade in &result.trades {
+                // Restored orders carry the canonical address of their account
+                let event = TradeEvent::from_execution(trade, key.clone(), taker.user_address.clone(), taker.side);
+                persist_queue.persist(&event).await;
+                for order_id in [trade.maker_order_id, trade.taker_order_id] {
+                    track_fill(pool, &mut report, &mut index, order_id, trade.trade_id, trade.amount).await?;
+                    mark_filled(pool, order_id, trade.amount).await?;
+                }
+                trade_count += 1;
+            }
+            // A categorical market's order can also fill in complete sets;
+            // writing a set updates its legs' maker orders
+            for fill in &result.set_fills {
+                persist_queue.persist_set(fill).await;
+                let makers = fill.legs.iter().map(|leg| leg.maker_order_id);
+                for order_id in std::iter::once(fill.taker_order_id).chain(makers) {
+                    track_fill(pool, &mut report, &mut index, order_id, fill.set_id, fill.amount).await?;
+                }
+                mark_filled(pool, fill.taker_order_id, fill.amount).await?;
+                trade_count += 1;
+            }
+        }
+    }
+
+    record_recovery(pool, &report).await?;
+    if trade_count > 0 {
+        info!("Resolved crossed orderbooks: {} trades, {} orders involved", trade_count, report.orders.len());
+    }
+    Ok(trade_count)
+}
+
+/// An open order as it stands before resolution fills it
+async fn load_recovered(pool: &PgPool, order_id: Uuid) -> Result<Option<RecoveredOrder>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT symbol, user_address, side, price, amount, filled_amount FROM orders WHERE id = $1",
+    )
+    .bind(order_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| {
+        let side: OrderSide = row.get("side");
+        let amount: Decimal = row.get("amount");
+        let remaining = amount - row.get::<Decimal, _>("filled_amount");
+        RecoveredOrder {
+            order_id,
+            user_address: row.get("user_address"),
+            symbol: row.get("symbol"),
+            side: match side {
+                OrderSide::Buy => Side::Buy,
+                OrderSide::Sell => Side::Sell,
+            },
+            price: row.get("price"),
+            amount,
+            remaining_before: remaining,
+            remaining_after: remaining,
+            outcome: RecoveryOutcome::Restored,
+            trade_ids: Vec::new(),
+            error: None,
+        }
+    }))
+}
+
+async fn mark_filled(pool: &PgPool, order_id: Uuid, amount: Decimal) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE orders
+        SET filled_amount = filled_amount + $1,
+            status = CASE
+                WHEN filled_amount + $1 >= amount THEN 'filled'::order_status
+                ELSE 'partially_filled'::order_status
+            END,
+            updated_at = NOW()
+        WHERE id = $2
+        "#,
+    )
+    .bind(amount)
+    .bind(order_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Write one audit row per recovered order
+async fn record_recovery(pool: &PgPool, report: &RecoveryReport) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for order in &report.orders {
+        sqlx::query(
+            r#"
+            INSERT INTO order_recovery_log (
+                order_id, user_address, symbol, side, price, amount,
+                remaining_before, remaining_after, outcome, trade_ids, error
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(order.order_id)
+        .bind(&order.user_address)
+        .bind(&order.symbol)
+        .bind(match order.side {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        })
+        .bind(order.price)
+        .bind(order.amount)
+        .bind(order.remaining_before)
+        .bind(order.remaining_after)
+        .bind(order.outcome.as_str())
+        .bind(&order.trade_ids)
+        .bind(&order.error)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+/// Recovery entry not yet delivered to its owner
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RecoveryNotice {
+    pub order_id: Uuid,
+    pub symbol: String,
+    pub side: String,
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub remaining_after: Decimal,
+    pub outcome: String,
+    pub recovered_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Fetch and mark delivered the user's recovery entries whose order filled
+/// or failed during recovery
+pub async fn take_pending_notices(pool: &PgPool, user_address: &Address) -> Result<Vec<RecoveryNotice>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        UPDATE order_recovery_log
+        SET notified_at = NOW()
+        WHERE user_address = $1 AND notified_at IS NULL AND outcome <> 'restored'
+        RETURNING order_id, symbol, side, price, amount, remaining_after, outcome, recovered_at
+        "#,
+    )
+    .bind(user_address)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_outcome_from_remaining() {
+        assert_eq!(RecoveryOutcome::from_remaining(dec!(10), dec!(10)), RecoveryOutcome::Restored);
+        assert_eq!(RecoveryOutcome::from_remaining(dec!(10), dec!(4)), RecoveryOutcome::PartiallyFilled);
+        assert_eq!(RecoveryOutcome::from_remaining(dec!(10), dec!(0)), RecoveryOutcome::Filled);
+    }
+
+    #[test]
+    fn test_fill_updates_recovered_order() {
+        let maker_id = Uuid::new_v4();
+        let mut report = RecoveryReport {
+            orders: vec![RecoveredOrder {
+                order_id: maker_id,
+                user_address: "0x1111111111111111111111111111111111111111".parse().unwrap(),
+                symbol: "m:o:yes".to_string(),
+                side: Side::Sell,
+                price: dec!(0.5),
+                amount: dec!(10),
+                remaining_before: dec!(10),
+                remaining_after: dec!(10),
+                outcome: RecoveryOutcome::Restored,
+                trade_ids: Vec::new(),
+                error: None,
+            }],
+        };
+        let index = HashMap::from([(maker_id, 0)]);
+        let trade_id = Uuid::new_v4();
+
+        report.record_fill(&index, maker_id, trade_id, dec!(4));
+        report.record_fill(&index, Uuid::new_v4(), Uuid::new_v4(), dec!(1));
+
+        let maker = &report.orders[0];
+        assert_eq!((maker.remaining_after, maker.outcome), (dec!(6), RecoveryOutcome::PartiallyFilled));
+        assert_eq!(maker.trade_ids, vec![trade_id]);
+        assert_eq!(report.restored_count(), 1);
+    }
+}
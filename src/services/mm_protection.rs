@@ -0,0 +1,582 @@
+//! Market Maker Protections
+//!
+//! Market makers set limits on how fast they can be filled in one market
+//! (`max_fills_per_second`) and how much their fills of the last minute may
+//! lose (`max_loss_per_minute`) through `PUT /mm/protections`. Every maker
+//! fill is checked against the maker's limits: the loss marks each fill of
+//! the last minute at the latest trade price of its book. On a breach the
+//! maker's resting orders in that market are pulled, reservations released,
+//! and the maker is told through the `mm.protection_triggered` webhook and a
+//! notification email. Fills in that market are counted afresh afterwards.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::market::ShareType;
+use crate::models::Address;
+use crate::services::matching::precision::Collateral;
+use crate::services::matching::{EngineShards, MatchType, OrderbookSnapshot, TradeEvent};
+use crate::services::notification::{NotificationKind, NotificationService};
+use crate::services::portfolio_margin;
+use crate::services::system_events::{self, SystemEventKind};
+use crate::services::webhook::{WebhookEventType, WebhookService};
+
+/// Window of the fill rate limit, in milliseconds
+const FILL_RATE_WINDOW_MS: i64 = 1_000;
+
+/// Window of the loss limit, in milliseconds
+const LOSS_WINDOW_MS: i64 = 60_000;
+
+/// How often protections are re-read from the database
+const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A market maker's protection limits
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Protection {
+    pub enabled: bool,
+    /// Maker fills per market within one second; `None` = no limit
+    pub max_fills_per_second: Option<i32>,
+    /// Loss of the last minute of maker fills per market; `None` = no limit
+    pub max_loss_per_minute: Option<Decimal>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Limit that pulled a maker's quotes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerReason {
+    FillRate,
+    Loss,
+}
+
+impl TriggerReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerReason::FillRate => "fill_rate",
+            TriggerReason::Loss => "loss",
+        }
+    }
+}
+
+/// A recorded quote pull
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProtectionTrigger {
+    pub id: i64,
+    pub market_id: Uuid,
+    pub reason: String,
+    pub fills_last_second: i32,
+    pub loss_last_minute: Decimal,
+    pub cancelled_orders: i32,
+    pub triggered_at: DateTime<Utc>,
+}
+
+/// One maker fill, in the maker's own terms
+#[derive(Debug, Clone, Copy)]
+struct Fill {
+    at_ms: i64,
+    outcome_id: Uuid,
+    share_type: ShareType,
+    buys: bool,
+    price: Decimal,
+    amount: Decimal,
+}
+
+impl Fill {
+    /// The maker's side of `trade`
+    fn maker(trade: &TradeEvent) -> Self {
+        let taker_buys = trade.side.eq_ignore_ascii_case("buy");
+        let (share_type, buys, price) = match trade.match_type {
+            MatchType::Normal => (trade.share_type, !taker_buys, trade.price),
+            MatchType::Mint => (trade.share_type.complement(), true, Decimal::ONE - trade.price),
+            MatchType::Merge => (trade.share_type.complement(), false, Decimal::ONE - trade.price),
+        };
+        Self {
+            at_ms: trade.timestamp,
+            outcome_id: trade.outcome_id,
+            share_type,
+            buys,
+            price,
+            amount: trade.amount,
+        }
+    }
+}
+
+/// Last trade price of each book
+type Marks = HashMap<(Uuid, ShareType), Decimal>;
+
+/// A maker's fills in one market over the loss window
+#[derive(Debug, Default)]
+struct MakerFills {
+    fills: VecDeque<Fill>,
+}
+
+impl MakerFills {
+    /// Add `fill` and forget fills older than the loss window
+    fn push(&mut self, fill: Fill) {
+        while self.fills.front().is_some_and(|f| f.at_ms <= fill.at_ms - LOSS_WINDOW_MS) {
+            self.fills.pop_front();
+        }
+        self.fills.push_back(fill);
+    }
+
+    /// Fills within the fill rate window before `now_ms`
+    fn last_second(&self, now_ms: i64) -> usize {
+        self.fills.iter().rev().take_while(|f| f.at_ms > now_ms - FILL_RATE_WINDOW_MS).count()
+    }
+
+    /// Loss of the fills at `marks` (negative for a gain)
+    fn loss(&self, marks: &Marks) -> Decimal {
+        self.fills
+            .iter()
+            .map(|f| {
+                let mark = marks.get(&(f.outcome_id, f.share_type)).copied().unwrap_or(f.price);
+                let per_share = if f.buys { f.price - mark } else { mark - f.price };
+                per_share * f.amount
+            })
+            .sum()
+    }
+}
+
+/// A breach found on a fill
+#[derive(Debug, Clone)]
+struct Breach {
+    user_address: Address,
+    market_id: Uuid,
+    reason: TriggerReason,
+    fills_last_second: usize,
+    loss_last_minute: Decimal,
+}
+
+/// Watches maker fills and pulls quotes when a protection trips
+pub struct MmProtection {
+    pool: PgPool,
+    shards: Arc<EngineShards>,
+    collateral_token: String,
+    webhooks: Arc<WebhookService>,
+    notifications: Arc<NotificationService>,
+    protections: DashMap<Address, Protection>,
+    marks: Mutex<Marks>,
+    fills: Mutex<HashMap<(Address, Uuid), MakerFills>>,
+}
+
+impl MmProtection {
+    pub fn new(
+        pool: PgPool,
+        shards: Arc<EngineShards>,
+        collateral_token: &str,
+        webhooks: Arc<WebhookService>,
+        notifications: Arc<NotificationService>,
+    ) -> Self {
+        Self {
+            pool,
+            shards,
+            collateral_token: collateral_token.to_string(),
+            webhooks,
+            notifications,
+            protections: DashMap::new(),
+            marks: Mutex::new(HashMap::new()),
+            fills: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load the stored protections and spawn the loop checking fills and
+    /// picking up changes made on other nodes
+    pub async fn start(self: Arc<Self>) {
+        if let Err(e) = self.load().await {
+            tracing::error!("Failed to load market maker protections: {}", e);
+        }
+        let mut trades = self.shards.engine().subscribe_trades();
+        tokio::spawn(async move {
+            tracing::info!("Market maker protection started");
+            let mut reload = tokio::time::interval(RELOAD_INTERVAL);
+            reload.tick().await;
+            loop {
+                tokio::select! {
+                    trade = trades.recv() => match trade {
+                        Ok(trade) => self.on_trade(&trade).await,
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            tracing::warn!("Market maker protection lagged by {} trades", n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = reload.tick() => {
+                        if let Err(e) = self.load().await {
+                            tracing::warn!("Failed to reload market maker protections: {}", e);
+                        }
+                    }
+                }
+            }
+            tracing::warn!("Market maker protection stopped");
+        });
+    }
+
+    async fn load(&self) -> Result<(), sqlx::Error> {
+        #[allow(clippy::type_complexity)]
+        let rows: Vec<(Address, bool, Option<i32>, Option<Decimal>, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT user_address, enabled, max_fills_per_second, max_loss_per_minute, updated_at FROM mm_protections",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for (user, enabled, max_fills_per_second, max_loss_per_minute, updated_at) in rows {
+            self.protections.insert(
+                user,
+                Protection {
+                    enabled,
+                    max_fills_per_second,
+                    max_loss_per_minute,
+                    updated_at,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// The protections of `user_address`, if set
+    pub async fn get(&self, user_address: &Address) -> Result<Option<Protection>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT enabled, max_fills_per_second, max_loss_per_minute, updated_at
+             FROM mm_protections WHERE user_address = $1",
+        )
+        .bind(user_address)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Replace the protections of `user_address`
+    pub async fn set(
+        &self,
+        user_address: &Address,
+        enabled: bool,
+        max_fills_per_second: Option<i32>,
+        max_loss_per_minute: Option<Decimal>,
+    ) -> Result<Protection, sqlx::Error> {
+        let protection: Protection = sqlx::query_as(
+            r#"
+            INSERT INTO mm_protections (user_address, enabled, max_fills_per_second, max_loss_per_minute)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_address) DO UPDATE
+            SET enabled = EXCLUDED.enabled,
+                max_fills_per_second = EXCLUDED.max_fills_per_second,
+                max_loss_per_minute = EXCLUDED.max_loss_per_minute,
+                updated_at = NOW()
+            RETURNING enabled, max_fills_per_second, max_loss_per_minute, updated_at
+            "#,
+        )
+        .bind(user_address)
+        .bind(enabled)
+        .bind(max_fills_per_second)
+        .bind(max_loss_per_minute)
+        .fetch_one(&self.pool)
+        .await?;
+        self.protections.insert(user_address.clone(), protection.clone());
+        self.fills.lock().retain(|(maker, _), _| maker != user_address);
+        Ok(protection)
+    }
+
+    /// The latest quote pulls of `user_address`, newest first
+    pub async fn triggers(&self, user_address: &Address, limit: i64) -> Result<Vec<ProtectionTrigger>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, market_id, reason, fills_last_second, loss_last_minute, cancelled_orders, triggered_at
+            FROM mm_protection_triggers
+            WHERE user_address = $1
+            ORDER BY triggered_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_address)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Mark the trade's books and check its maker's protections
+    pub async fn on_trade(&self, trade: &TradeEvent) {
+        // Sandbox books live in their own namespaces
+        if OrderbookSnapshot::split_namespace(&trade.symbol).0.is_some() {
+            return;
+        }
+        let Some(breach) = self.record(trade) else {
+            return;
+        };
+        if let Err(e) = self.pull_quotes(&breach).await {
+            tracing::error!(
+                "Failed to pull quotes of {} in market {}: {}",
+                breach.user_address,
+                breach.market_id,
+                e
+            );
+        }
+    }
+
+    /// Record the maker's fill, returning the breach it causes
+    fn record(&self, trade: &TradeEvent) -> Option<Breach> {
+        let mut marks = self.marks.lock();
+        marks.insert((trade.outcome_id, trade.share_type), trade.price);
+        marks.insert((trade.outcome_id, trade.share_type.complement()), Decimal::ONE - trade.price);
+
+        let maker: Address = trade.maker_address.parse().ok()?;
+        let protection = self.protections.get(&maker)?.clone();
+        if !protection.enabled {
+            return None;
+        }
+
+        let key = (maker.clone(), trade.market_id);
+        let mut all_fills = self.fills.lock();
+        let fills = all_fills.entry(key.clone()).or_default();
+        fills.push(Fill::maker(trade));
+        let fills_last_second = fills.last_second(trade.timestamp);
+        let loss_last_minute = fills.loss(&marks);
+
+        let reason = if protection
+            .max_fills_per_second
+            .is_some_and(|max| fills_last_second > max as usize)
+        {
+            TriggerReason::FillRate
+        } else if protection.max_loss_per_minute.is_some_and(|max| loss_last_minute > max) {
+            TriggerReason::Loss
+        } else {
+            return None;
+        };
+        all_fills.remove(&key);
+        Some(Breach {
+            user_address: maker,
+            market_id: trade.market_id,
+            reason,
+            fills_last_second,
+            loss_last_minute,
+        })
+    }
+
+    /// Cancel the maker's resting orders in the market (on its engine
+    /// shard) and tell them why
+    async fn pull_quotes(&self, breach: &Breach) -> Result<(), sqlx::Error> {
+        let (user, market_id) = (breach.user_address.clone(), breach.market_id);
+        let cancelled = match self
+            .shards
+            .execute(&market_id.to_string(), move |engine| {
+                engine.cancel_market_orders_for_user(user.as_str(), market_id)
+            })
+            .await
+        {
+            Ok(cancelled) => cancelled,
+            Err(e) => {
+                tracing::error!(
+                    "Market maker protection could not pull the orders of {} in market {}: {}",
+                    breach.user_address,
+                    market_id,
+                    e
+                );
+                return Ok(());
+            }
+        };
+        let order_ids: Vec<Uuid> = cancelled.iter().map(|(_, entry)| entry.id).collect();
+        tracing::warn!(
+            "Market maker protection ({}) pulled {} orders of {} in market {}",
+            breach.reason.as_str(),
+            order_ids.len(),
+            breach.user_address,
+            breach.market_id
+        );
+        self.cancel_in_db(&breach.user_address, &order_ids).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO mm_protection_triggers
+                (user_address, market_id, reason, fills_last_second, loss_last_minute, cancelled_orders)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(&breach.user_address)
+        .bind(breach.market_id)
+        .bind(breach.reason.as_str())
+        .bind(breach.fills_last_second as i32)
+        .bind(breach.loss_last_minute)
+        .bind(order_ids.len() as i32)
+        .execute(&self.pool)
+        .await?;
+
+        let data = serde_json::json!({
+            "market_id": breach.market_id,
+            "reason": breach.reason.as_str(),
+            "fills_last_second": breach.fills_last_second,
+            "loss_last_minute": breach.loss_last_minute.round_dp(6).to_string(),
+            "cancelled_orders": order_ids.len(),
+            "order_ids": order_ids,
+        });
+        system_events::record(
+            &self.pool,
+            SystemEventKind::MmProtectionTriggered,
+            Some(breach.user_address.as_str()),
+            format!(
+                "Market maker protection ({}) pulled {} orders in market {}",
+                breach.reason.as_str(),
+                order_ids.len(),
+                breach.market_id
+            ),
+            data.clone(),
+        )
+        .await;
+        self.webhooks
+            .dispatch(WebhookEventType::MmProtectionTriggered, Some(breach.user_address.as_str()), data.clone())
+            .await;
+        self.notifications
+            .notify(NotificationKind::MmProtectionTriggered, breach.user_address.as_str(), None, data)
+            .await;
+        Ok(())
+    }
+
+    /// Mark the engine-cancelled orders cancelled and release buy reservations
+    async fn cancel_in_db(&self, user_address: &Address, order_ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        if order_ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let cancelled: Vec<(String, Option<Decimal>, Decimal)> = sqlx::query_as(
+            r#"
+            UPDATE orders
+            SET status = 'cancelled', updated_at = NOW()
+            WHERE id = ANY($1) AND status IN ('pending', 'accepted', 'open', 'partially_filled')
+            RETURNING side::text, price, amount - filled_amount
+            "#,
+        )
+        .bind(order_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let released: Collateral = cancelled
+            .iter()
+            .filter(|(side, _, _)| side == "buy")
+            .filter_map(|(_, price, remaining)| price.map(|p| Collateral::notional(p, *remaining)))
+            .sum();
+        if released.value() > Decimal::ZERO {
+            sqlx::query(
+                "UPDATE balances SET available = available + $1, frozen = frozen - $1, updated_at = NOW()
+                 WHERE user_address = $2 AND token = $3",
+            )
+            .bind(released.value())
+            .bind(user_address)
+            .bind(&self.collateral_token)
+            .execute(&mut *tx)
+            .await?;
+            portfolio_margin::resync_account(&mut tx, user_address.as_str(), &self.collateral_token).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{OrderSide, OrderType, TimeInForce};
+    use crate::services::matching::{OrderIntent, SelfTradePrevention};
+    use crate::services::order_gateway::OrderSource;
+    use crate::test_support::TestApp;
+    use rust_decimal_macros::dec;
+
+    const MAKER: &str = "0x9191919191919191919191919191919191919191";
+    const TAKER: &str = "0x9292929292929292929292929292929292929292";
+
+    fn fill(at_ms: i64, outcome_id: Uuid, buys: bool, price: Decimal) -> Fill {
+        Fill {
+            at_ms,
+            outcome_id,
+            share_type: ShareType::Yes,
+            buys,
+            price,
+            amount: dec!(10),
+        }
+    }
+
+    fn limit(market_id: Uuid, outcome_id: Uuid, user: &str, side: OrderSide, price: Decimal) -> OrderIntent {
+        OrderIntent {
+            source: OrderSource::Api,
+            user_address: user.to_string(),
+            market_id,
+            outcome_id,
+            share_type: ShareType::Yes,
+            side,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            stp: SelfTradePrevention::default(),
+            price,
+            amount: dec!(1),
+            expires_at: None,
+            signature: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_fills_marked_at_last_trade() {
+        let outcome = Uuid::new_v4();
+        let mut fills = MakerFills::default();
+        fills.push(fill(0, outcome, true, dec!(0.5)));
+        fills.push(fill(500, outcome, false, dec!(0.45)));
+        assert_eq!(fills.last_second(500), 2);
+        assert_eq!(fills.last_second(1_200), 1);
+
+        // Bought 10 at 0.5 and sold 10 at 0.45, marked at 0.4
+        let marks = Marks::from([((outcome, ShareType::Yes), dec!(0.4))]);
+        assert_eq!(fills.loss(&marks), dec!(1.0) + dec!(-0.5));
+
+        // A minute later only the new fill counts
+        fills.push(fill(60_000, outcome, true, dec!(0.4)));
+        assert_eq!(fills.fills.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fill_rate_pulls_market_quotes() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.grant_shares(MAKER, market_id, yes, ShareType::Yes, dec!(10)).await;
+        app.deposit(TAKER, dec!(100)).await;
+        let protection = app.state.mm_protection.clone();
+        let maker: Address = MAKER.parse().unwrap();
+        protection.set(&maker, true, Some(2), None).await.unwrap();
+
+        let flow = &app.state.order_flow;
+        let mut resting = Vec::new();
+        for price in [dec!(0.40), dec!(0.41), dec!(0.42), dec!(0.45)] {
+            let placed = flow.place(&limit(market_id, yes, MAKER, OrderSide::Sell, price)).await.unwrap();
+            resting.push(placed.order_id);
+        }
+
+        let mut trades = app.state.matching_engine.subscribe_trades();
+        let mut sweep = limit(market_id, yes, TAKER, OrderSide::Buy, dec!(0.43));
+        sweep.amount = dec!(3);
+        flow.place(&sweep).await.unwrap();
+        for _ in 0..3 {
+            protection.on_trade(&trades.recv().await.unwrap()).await;
+        }
+
+        // The third fill within a second pulled the last quote
+        let (status,): (String,) = sqlx::query_as("SELECT status::text FROM orders WHERE id = $1")
+            .bind(resting[3])
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "cancelled");
+        let key = format!("{}:{}:yes", market_id, yes);
+        assert!(app.state.matching_engine.get_orderbook_ref(&key).unwrap().orders().is_empty());
+
+        let triggers = protection.triggers(&maker, 10).await.unwrap();
+        assert_eq!(triggers.len(), 1);
+        assert_eq!(triggers[0].reason, "fill_rate");
+        assert_eq!(triggers[0].fills_last_second, 3);
+        assert_eq!(triggers[0].cancelled_orders, 1);
+    }
+}
@@ -1,9 +1,60 @@
 //! Business logic services
 
+pub mod account_admin;
+pub mod admin_approval;
+pub mod analytics;
+pub mod api_usage;
+pub mod backfill;
+pub mod cancel_all_after;
 pub mod chainlink;
+pub mod channel_gateway;
+pub mod circuit_breaker;
+pub mod criteria_pin;
+pub mod ctf_position;
+pub mod event_bus;
 pub mod event_processor;
+pub mod execution_stats;
+pub mod fee_summary;
+pub mod export;
+pub mod feature_flags;
+pub mod leader_election;
+pub mod liquidity;
+pub mod ledger;
 pub mod matching;
+pub mod notification;
 pub mod market;
+pub mod market_archive;
+pub mod market_data_tier;
+pub mod market_replay;
+pub mod mm_protection;
+pub mod market_summary;
+pub mod neg_risk;
+pub mod ops_report;
 pub mod oracle;
+pub mod order_expiry;
+pub mod order_gateway;
+pub mod orderbook_history;
+pub mod paper_trading;
+pub mod payout_preview;
+pub mod personal_data;
+pub mod portfolio_margin;
+pub mod portfolio_risk;
+pub mod preferences;
+pub mod relayer;
+pub mod resolution_schedule;
+pub mod retry;
+pub mod rewards;
+pub mod resolution_evidence;
+pub mod sandbox;
 pub mod settlement;
+pub mod settlement_mode;
+pub mod stale_orders;
+pub mod system_events;
+pub mod trade_adjustment;
+pub mod trade_persistence;
+pub mod trading_calendar;
+pub mod trigger_orders;
 pub mod uma_oracle;
+pub mod webhook;
+pub mod withdrawal_policy;
+pub mod write_batcher;
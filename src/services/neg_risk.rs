@@ -0,0 +1,875 @@
+//! Negative-Risk Market Groups
+//!
+//! A market group ties together the binary markets of one event whose
+//! outcomes are mutually exclusive (one market per election candidate). In a
+//! negative-risk group at most one market resolves Yes, which gives two
+//! guarantees this module enforces and exploits:
+//!
+//! - **Resolution guard**: once a market of the group resolved Yes, no
+//!   sibling may resolve Yes as well.
+//! - **No-set conversion**: holding one No share in every market of an N
+//!   market group pays out at least N-1 whatever happens, so the set can be
+//!   burned for N-1 collateral instead of waiting for resolution.
+//!
+//! No positions are the `no` shares on each market's Yes outcome (the
+//! `market:yes_outcome:no` book).
+//!
+//! Conversions settle on the ledger at once. When the group's markets are
+//! the questions of a NegRiskAdapter market, each conversion is also
+//! mirrored on chain with `convertPositions` over every question, and its
+//! `chain_status` tracks the transaction (see [`submit_onchain`]). The
+//! [`ConversionSubmitter`] sends pending conversions in the background and
+//! reconciles sent ones from their receipts.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ethers::types::{H256, U256};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::blockchain::types::TxStatus;
+use crate::blockchain::SettlementChain;
+use crate::models::Address;
+use crate::models::market::ShareType;
+use crate::services::ctf_position;
+use crate::services::leader_election::LeaderElection;
+use crate::services::ledger::{self, LedgerEntry, LedgerEntryType};
+use crate::services::matching::holdings;
+use crate::services::matching::precision::Collateral;
+
+/// Fewest markets a group can hold
+pub const MIN_GROUP_MARKETS: usize = 2;
+
+/// How often the submitter sends pending conversions and reconciles sent ones
+const SUBMIT_INTERVAL_SECS: u64 = 15;
+/// Conversions handled per pass
+const SUBMIT_BATCH_SIZE: i64 = 50;
+/// A claim older than this is taken to have been interrupted before its
+/// transaction hash was stored
+const SUBMIT_TIMEOUT_SECS: f64 = 300.0;
+
+#[derive(Debug, Error)]
+pub enum NegRiskError {
+    #[error("A group needs at least {MIN_GROUP_MARKETS} distinct markets")]
+    NotEnoughMarkets,
+    #[error("Market {0} not found")]
+    MarketNotFound(Uuid),
+    #[error("Market {0} is not active")]
+    MarketNotActive(Uuid),
+    #[error("Market {0} already belongs to a group")]
+    AlreadyGrouped(Uuid),
+    #[error("Market {0} is categorical; groups hold binary markets")]
+    NotBinary(Uuid),
+    #[error("Market group not found")]
+    GroupNotFound,
+    #[error("Market group is not negative-risk")]
+    NotNegRisk,
+    #[error("Market {0} of the group already resolved Yes")]
+    SiblingResolvedYes(Uuid),
+    #[error("Conversion amount must be positive")]
+    InvalidAmount,
+    #[error("Only {available} complete No sets available")]
+    InsufficientShares { available: Decimal },
+    #[error("NegRiskAdapter market id must be 32 bytes of hex")]
+    InvalidNegRiskMarketId,
+    #[error("Conversion not found")]
+    ConversionNotFound,
+    #[error("Conversion is {0}, not awaiting submission on chain")]
+    NotSubmittable(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl NegRiskError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            NegRiskError::NotEnoughMarkets => "NOT_ENOUGH_MARKETS",
+            NegRiskError::MarketNotFound(_) => "MARKET_NOT_FOUND",
+            NegRiskError::MarketNotActive(_) => "MARKET_NOT_ACTIVE",
+            NegRiskError::AlreadyGrouped(_) => "MARKET_ALREADY_GROUPED",
+            NegRiskError::NotBinary(_) => "MARKET_NOT_BINARY",
+            NegRiskError::GroupNotFound => "GROUP_NOT_FOUND",
+            NegRiskError::NotNegRisk => "GROUP_NOT_NEG_RISK",
+            NegRiskError::SiblingResolvedYes(_) => "NEG_RISK_CONFLICT",
+            NegRiskError::InvalidAmount => "INVALID_AMOUNT",
+            NegRiskError::InsufficientShares { .. } => "INSUFFICIENT_SHARES",
+            NegRiskError::InvalidNegRiskMarketId => "INVALID_NEG_RISK_MARKET_ID",
+            NegRiskError::ConversionNotFound => "CONVERSION_NOT_FOUND",
+            NegRiskError::NotSubmittable(_) => "CONVERSION_NOT_SUBMITTABLE",
+            NegRiskError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MarketGroup {
+    pub id: Uuid,
+    pub title: String,
+    pub neg_risk: bool,
+    /// NegRiskAdapter market whose questions are the group's markets
+    pub neg_risk_market_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One market of a group with the caller's No position in it
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GroupMarket {
+    pub market_id: Uuid,
+    pub question: String,
+    pub status: String,
+    /// The market's Yes outcome; its `no` shares are the No position
+    pub outcome_id: Uuid,
+    pub no_shares: Decimal,
+}
+
+/// What a user's No positions across a group are worth converted
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionPreview {
+    pub group_id: Uuid,
+    pub markets: Vec<GroupMarket>,
+    /// Complete No sets held (smallest No position across the group)
+    pub convertible: Decimal,
+    /// Collateral paid per complete set (N-1)
+    pub payout_per_set: Decimal,
+    pub payout: Decimal,
+    /// False when a market of the group is no longer active
+    pub available: bool,
+}
+
+/// Conversion only settled on the ledger
+pub const CHAIN_OFF_CHAIN: &str = "off_chain";
+/// Awaiting `convertPositions` (or a retry of it)
+pub const CHAIN_PENDING: &str = "pending";
+/// Claimed by a submission that has not stored its transaction hash yet
+pub const CHAIN_SUBMITTING: &str = "submitting";
+/// Sent with its hash stored, the outcome is read back from the receipt.
+/// An interrupted submission lands here without a hash: it may or may not
+/// have been sent, so it waits for an operator to check and resubmit.
+pub const CHAIN_RECONCILE: &str = "reconcile";
+pub const CHAIN_CONFIRMED: &str = "confirmed";
+pub const CHAIN_FAILED: &str = "failed";
+
+/// A completed conversion
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Conversion {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub amount: Decimal,
+    pub payout: Decimal,
+    /// `off_chain`, `pending`, `submitting`, `reconcile`, `confirmed` or
+    /// `failed`
+    pub chain_status: String,
+    pub tx_hash: Option<String>,
+    pub chain_error: Option<String>,
+}
+
+/// Complete No sets held: the smallest No position, zero for fewer than
+/// two markets
+pub fn complete_sets(no_positions: &[Decimal]) -> Decimal {
+    if no_positions.len() < MIN_GROUP_MARKETS {
+        return Decimal::ZERO;
+    }
+    no_positions.iter().copied().min().unwrap_or_default().max(Decimal::ZERO)
+}
+
+/// Collateral for converting `sets` complete No sets of an N-market group
+pub fn set_payout(market_count: usize, sets: Decimal) -> Decimal {
+    Collateral::new(sets * Decimal::from(market_count.saturating_sub(1))).value()
+}
+
+/// Index set of every question of an N-market group: the lowest N bits
+pub fn full_index_set(market_count: usize) -> U256 {
+    (U256::one() << market_count) - U256::one()
+}
+
+/// A NegRiskAdapter market id (`0x` and 64 hex digits)
+pub fn parse_neg_risk_market_id(id: &str) -> Option<[u8; 32]> {
+    let hex_str = id.strip_prefix("0x")?;
+    hex::decode(hex_str).ok()?.try_into().ok()
+}
+
+/// Create a group from active, ungrouped markets. With a NegRiskAdapter
+/// market id, its conversions are mirrored on chain; the markets must be
+/// that market's questions.
+pub async fn create_group(
+    pool: &PgPool,
+    title: &str,
+    neg_risk: bool,
+    neg_risk_market_id: Option<&str>,
+    market_ids: &[Uuid],
+    created_by: &Address,
+) -> Result<MarketGroup, NegRiskError> {
+    let neg_risk_market_id = match neg_risk_market_id {
+        Some(id) if !neg_risk || parse_neg_risk_market_id(id).is_none() => {
+            return Err(NegRiskError::InvalidNegRiskMarketId)
+        }
+        id => id.map(str::to_lowercase),
+    };
+    let mut ids = market_ids.to_vec();
+    ids.sort();
+    ids.dedup();
+    if ids.len() < MIN_GROUP_MARKETS {
+        return Err(NegRiskError::NotEnoughMarkets);
+    }
+
+    let mut tx = pool.begin().await?;
+    let rows: Vec<(Uuid, String, Option<Uuid>, String)> = sqlx::query_as(
+        "SELECT id, status::text, group_id, market_type FROM markets WHERE id = ANY($1) FOR UPDATE",
+    )
+    .bind(&ids)
+    .fetch_all(&mut *tx)
+    .await?;
+    for id in &ids {
+        let Some((_, status, group_id, market_type)) = rows.iter().find(|(market_id, ..)| market_id == id) else {
+            return Err(NegRiskError::MarketNotFound(*id));
+        };
+        if status != "active" {
+            return Err(NegRiskError::MarketNotActive(*id));
+        }
+        if group_id.is_some() {
+            return Err(NegRiskError::AlreadyGrouped(*id));
+        }
+        if market_type != "binary" {
+            return Err(NegRiskError::NotBinary(*id));
+        }
+    }
+
+    let group: MarketGroup = sqlx::query_as(
+        r#"
+        INSERT INTO market_groups (title, neg_risk, neg_risk_market_id, created_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, title, neg_risk, neg_risk_market_id, created_at
+        "#,
+    )
+    .bind(title)
+    .bind(neg_risk)
+    .bind(neg_risk_market_id)
+    .bind(created_by)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE markets SET group_id = $1 WHERE id = ANY($2)")
+        .bind(group.id)
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(group)
+}
+
+pub async fn get_group(pool: &PgPool, group_id: Uuid) -> Result<MarketGroup, NegRiskError> {
+    sqlx::query_as("SELECT id, title, neg_risk, neg_risk_market_id, created_at FROM market_groups WHERE id = $1")
+        .bind(group_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(NegRiskError::GroupNotFound)
+}
+
+/// The group's markets with `user_address`'s No position in each (zero
+/// without a user)
+pub async fn group_markets(
+    conn: &mut PgConnection,
+    group_id: Uuid,
+    user_address: Option<&Address>,
+) -> Result<Vec<GroupMarket>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT m.id AS market_id, m.question, m.status::text AS status, o.id AS outcome_id,
+               COALESCE(s.amount, 0) AS no_shares
+        FROM markets m
+        JOIN outcomes o ON o.market_id = m.id AND o.share_type = 'yes'
+        LEFT JOIN shares s ON s.outcome_id = o.id AND s.share_type = 'no' AND s.user_address = $2
+        WHERE m.group_id = $1
+        ORDER BY m.created_at, m.id
+        "#,
+    )
+    .bind(group_id)
+    .bind(user_address)
+    .fetch_all(conn)
+    .await
+}
+
+/// Refuse resolving `market_id` Yes when a sibling in its negative-risk
+/// group already resolved Yes
+pub async fn check_yes_resolution(pool: &PgPool, market_id: Uuid) -> Result<(), NegRiskError> {
+    let sibling: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT sibling.id
+        FROM markets m
+        JOIN market_groups g ON g.id = m.group_id AND g.neg_risk
+        JOIN markets sibling ON sibling.group_id = m.group_id AND sibling.id <> m.id
+        JOIN outcomes o ON o.id = sibling.winning_outcome_id AND o.share_type = 'yes'
+        WHERE m.id = $1 AND sibling.status = 'resolved'
+        LIMIT 1
+        "#,
+    )
+    .bind(market_id)
+    .fetch_optional(pool)
+    .await?;
+
+    match sibling {
+        Some(sibling) => Err(NegRiskError::SiblingResolvedYes(sibling)),
+        None => Ok(()),
+    }
+}
+
+/// The user's No positions across the group and what converting them pays
+pub async fn preview(pool: &PgPool, group_id: Uuid, user_address: &Address) -> Result<ConversionPreview, NegRiskError> {
+    let group = get_group(pool, group_id).await?;
+    if !group.neg_risk {
+        return Err(NegRiskError::NotNegRisk);
+    }
+
+    let mut conn = pool.acquire().await?;
+    let markets = group_markets(&mut conn, group_id, Some(user_address)).await?;
+    let positions: Vec<Decimal> = markets.iter().map(|m| m.no_shares).collect();
+    let convertible = complete_sets(&positions);
+    Ok(ConversionPreview {
+        group_id,
+        convertible,
+        payout_per_set: Decimal::from(markets.len().saturating_sub(1)),
+        payout: set_payout(markets.len(), convertible),
+        available: markets.len() >= MIN_GROUP_MARKETS && markets.iter().all(|m| m.status == "active"),
+        markets,
+    })
+}
+
+/// Burn `amount` complete No sets (all of them when `None`) and credit the
+/// collateral they are guaranteed to pay. The conversion is left `pending`
+/// for the [`ConversionSubmitter`] when the group has a NegRiskAdapter
+/// market.
+pub async fn convert(
+    pool: &PgPool,
+    group_id: Uuid,
+    user_address: &Address,
+    amount: Option<Decimal>,
+    collateral_token: &str,
+) -> Result<Conversion, NegRiskError> {
+    let group = get_group(pool, group_id).await?;
+    if !group.neg_risk {
+        return Err(NegRiskError::NotNegRisk);
+    }
+
+    let mut tx = pool.begin().await?;
+    let markets = group_markets(&mut tx, group_id, Some(user_address)).await?;
+    if markets.len() < MIN_GROUP_MARKETS {
+        return Err(NegRiskError::NotEnoughMarkets);
+    }
+    if let Some(closed) = markets.iter().find(|m| m.status != "active") {
+        return Err(NegRiskError::MarketNotActive(closed.market_id));
+    }
+
+    // Lock the positions before checking them
+    let outcome_ids: Vec<Uuid> = markets.iter().map(|m| m.outcome_id).collect();
+    let held: Vec<(Uuid, Decimal)> = sqlx::query_as(
+        r#"
+        SELECT outcome_id, amount FROM shares
+        WHERE user_address = $1 AND share_type = 'no' AND outcome_id = ANY($2)
+        FOR UPDATE
+        "#,
+    )
+    .bind(user_address)
+    .bind(&outcome_ids)
+    .fetch_all(&mut *tx)
+    .await?;
+    let positions: Vec<Decimal> = outcome_ids
+        .iter()
+        .map(|id| held.iter().find(|(o, _)| o == id).map(|(_, a)| *a).unwrap_or_default())
+        .collect();
+    let available = complete_sets(&positions);
+
+    let amount = amount.unwrap_or(available);
+    if amount <= Decimal::ZERO {
+        return Err(if available > Decimal::ZERO {
+            NegRiskError::InvalidAmount
+        } else {
+            NegRiskError::InsufficientShares { available }
+        });
+    }
+    if amount > available {
+        return Err(NegRiskError::InsufficientShares { available });
+    }
+
+    let payout = set_payout(markets.len(), amount);
+    let price_per_share = Collateral::new(payout / (amount * Decimal::from(markets.len()))).value();
+    let conversion_id = Uuid::new_v4();
+    let chain_status = if group.neg_risk_market_id.is_some() { CHAIN_PENDING } else { CHAIN_OFF_CHAIN };
+
+    for market in &markets {
+        sqlx::query(
+            "UPDATE shares SET amount = amount - $1, updated_at = NOW()
+             WHERE user_address = $2 AND outcome_id = $3 AND share_type = 'no'",
+        )
+        .bind(amount)
+        .bind(user_address)
+        .bind(market.outcome_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO share_changes (user_address, market_id, outcome_id, share_type, change_type, amount, price)
+            VALUES ($1, $2, $3, 'no', 'convert', $4, $5)
+            "#,
+        )
+        .bind(user_address)
+        .bind(market.market_id)
+        .bind(market.outcome_id)
+        .bind(-amount)
+        .bind(price_per_share)
+        .execute(&mut *tx)
+        .await?;
+
+        // Burned No shares leave the outcome's supply like redeemed ones
+        holdings::record_redemption(&mut tx, market.market_id, market.outcome_id, ShareType::No, amount).await?;
+    }
+
+    let balance_after: Decimal = sqlx::query_scalar(
+        r#"
+        INSERT INTO balances (user_address, token, available, frozen, updated_at)
+        VALUES ($1, $2, $3, 0, NOW())
+        ON CONFLICT (user_address, token) DO UPDATE SET
+            available = balances.available + $3,
+            updated_at = NOW()
+        RETURNING available
+        "#,
+    )
+    .bind(user_address)
+    .bind(collateral_token)
+    .bind(payout)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    ledger::record_entry(
+        &mut tx,
+        &LedgerEntry {
+            user_address: user_address.as_str(),
+            token: collateral_token,
+            entry_type: LedgerEntryType::NegRiskConversion,
+            amount: payout,
+            balance_after,
+            reference_id: Some(conversion_id),
+            counterparty: None,
+        },
+    )
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO neg_risk_conversions (id, group_id, user_address, amount, market_count, payout, chain_status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(conversion_id)
+    .bind(group_id)
+    .bind(user_address)
+    .bind(amount)
+    .bind(markets.len() as i32)
+    .bind(payout)
+    .bind(chain_status)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    tracing::info!(
+        "Converted {} No sets of group {} for {} into {} collateral",
+        amount, group_id, user_address, payout
+    );
+
+    Ok(Conversion {
+        id: conversion_id,
+        group_id,
+        amount,
+        payout,
+        chain_status: chain_status.to_string(),
+        tx_hash: None,
+        chain_error: None,
+    })
+}
+
+/// Send a `pending` (or `failed`) conversion to the NegRiskAdapter as
+/// `convertPositions` over all of the group's questions. The row is claimed
+/// first, so concurrent submissions send one transaction, and its hash is
+/// stored as soon as it is sent: from then on the conversion is reconciled
+/// from the receipt, never sent again. An interrupted submission, left to
+/// reconcile without a hash, may be resubmitted once checked. The ledger
+/// side is already settled either way.
+pub async fn submit_onchain(
+    pool: &PgPool,
+    chain: &dyn SettlementChain,
+    conversion_id: Uuid,
+) -> Result<Conversion, NegRiskError> {
+    let claimed: Option<(Decimal, i32, Option<String>)> = sqlx::query_as(
+        r#"
+        UPDATE neg_risk_conversions c
+        SET chain_status = 'submitting', chain_error = NULL, submitted_at = NOW()
+        FROM market_groups g
+        WHERE c.id = $1 AND g.id = c.group_id
+          AND (c.chain_status IN ('pending', 'failed') OR (c.chain_status = 'reconcile' AND c.tx_hash IS NULL))
+        RETURNING c.amount, c.market_count, g.neg_risk_market_id
+        "#,
+    )
+    .bind(conversion_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((amount, market_count, neg_risk_market_id)) = claimed else {
+        let conversion = get_conversion(pool, conversion_id).await?;
+        return Err(NegRiskError::NotSubmittable(conversion.chain_status));
+    };
+
+    let neg_risk_market_id = neg_risk_market_id.as_deref().and_then(parse_neg_risk_market_id);
+    let sent = match (neg_risk_market_id, ctf_position::to_base_units(amount)) {
+        (None, _) => Err("Group has no NegRiskAdapter market".to_string()),
+        (_, None) => Err(format!("{} shares do not fit the token's precision", amount)),
+        (Some(market), Some(units)) => chain
+            .convert_positions(market, full_index_set(market_count as usize), units)
+            .await
+            .map_err(|e| e.to_string()),
+    };
+    let tx_hash = match sent {
+        Ok(tx_hash) => tx_hash,
+        Err(e) => {
+            tracing::warn!("NegRiskAdapter conversion {} failed: {}", conversion_id, e);
+            return transition(pool, conversion_id, CHAIN_SUBMITTING, CHAIN_FAILED, None, Some(e)).await;
+        }
+    };
+    let tx_hash = format!("{:?}", tx_hash);
+    transition(pool, conversion_id, CHAIN_SUBMITTING, CHAIN_RECONCILE, Some(tx_hash), None).await?;
+    reconcile_onchain(pool, chain, conversion_id).await
+}
+
+/// Read a sent conversion's outcome back from its receipt: `confirmed` or
+/// `failed` once mined, left to reconcile while the chain has no answer.
+/// Any other conversion is returned as it is.
+pub async fn reconcile_onchain(
+    pool: &PgPool,
+    chain: &dyn SettlementChain,
+    conversion_id: Uuid,
+) -> Result<Conversion, NegRiskError> {
+    let conversion = get_conversion(pool, conversion_id).await?;
+    if conversion.chain_status != CHAIN_RECONCILE {
+        return Ok(conversion);
+    }
+    let Some(tx_hash) = conversion.tx_hash.as_deref().and_then(|hash| hash.parse::<H256>().ok()) else {
+        tracing::error!("Conversion {} awaits reconciling without a transaction hash", conversion_id);
+        return Ok(conversion);
+    };
+
+    let (status, chain_error) = match chain.transaction_result(tx_hash).await {
+        Ok(Some(tx)) if tx.status == TxStatus::Confirmed => (CHAIN_CONFIRMED, None),
+        Ok(Some(tx)) if tx.status == TxStatus::Failed => {
+            (CHAIN_FAILED, Some(tx.error.unwrap_or_else(|| "Transaction reverted".to_string())))
+        }
+        Ok(_) => return Ok(conversion),
+        Err(e) => {
+            tracing::warn!("Failed to read the receipt of conversion {}: {}", conversion_id, e);
+            return Ok(conversion);
+        }
+    };
+    match &chain_error {
+        Some(e) => tracing::warn!("NegRiskAdapter conversion {} failed: {}", conversion_id, e),
+        None => tracing::info!("NegRiskAdapter conversion {} confirmed", conversion_id),
+    }
+    transition(pool, conversion_id, CHAIN_RECONCILE, status, None, chain_error).await
+}
+
+async fn get_conversion(pool: &PgPool, conversion_id: Uuid) -> Result<Conversion, NegRiskError> {
+    sqlx::query_as(
+        "SELECT id, group_id, amount, payout, chain_status, tx_hash, chain_error
+         FROM neg_risk_conversions WHERE id = $1",
+    )
+    .bind(conversion_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(NegRiskError::ConversionNotFound)
+}
+
+/// Move a conversion from `from` to `to`, keeping a stored hash; one moved
+/// on meanwhile is returned as it is now
+async fn transition(
+    pool: &PgPool,
+    conversion_id: Uuid,
+    from: &str,
+    to: &str,
+    tx_hash: Option<String>,
+    chain_error: Option<String>,
+) -> Result<Conversion, NegRiskError> {
+    let updated: Option<Conversion> = sqlx::query_as(
+        r#"
+        UPDATE neg_risk_conversions
+        SET chain_status = $3, tx_hash = COALESCE($4, tx_hash), chain_error = $5
+        WHERE id = $1 AND chain_status = $2
+        RETURNING id, group_id, amount, payout, chain_status, tx_hash, chain_error
+        "#,
+    )
+    .bind(conversion_id)
+    .bind(from)
+    .bind(to)
+    .bind(tx_hash)
+    .bind(chain_error)
+    .fetch_optional(pool)
+    .await?;
+    match updated {
+        Some(conversion) => Ok(conversion),
+        None => get_conversion(pool, conversion_id).await,
+    }
+}
+
+/// Background job sending pending conversions to the NegRiskAdapter and
+/// reconciling sent ones; failed conversions wait for an admin to resubmit
+pub struct ConversionSubmitter {
+    pool: PgPool,
+    chain: Arc<dyn SettlementChain>,
+}
+
+impl ConversionSubmitter {
+    pub fn new(pool: PgPool, chain: Arc<dyn SettlementChain>) -> Self {
+        Self { pool, chain }
+    }
+
+    /// Spawn the submit loop; only the elected leader submits
+    pub fn start(self: Arc<Self>, leader: Arc<LeaderElection>) {
+        tokio::spawn(async move {
+            tracing::info!("No-set conversion submitter started");
+            let mut interval = tokio::time::interval(Duration::from_secs(SUBMIT_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                if !leader.is_leader() {
+                    continue;
+                }
+                match self.submit_pending().await {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!("Settled {} No-set conversions on chain", n),
+                    Err(e) => tracing::error!("No-set conversion submission failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Send pending conversions and reconcile sent ones, oldest first, after
+    /// handing stale claims to reconcile. Returns the number now confirmed or
+    /// failed; one that errors is logged and retried next pass.
+    pub async fn submit_pending(&self) -> Result<usize, sqlx::Error> {
+        let interrupted = sqlx::query(
+            r#"
+            UPDATE neg_risk_conversions
+            SET chain_status = 'reconcile',
+                chain_error = 'Submission interrupted before its transaction hash was stored'
+            WHERE chain_status = 'submitting' AND submitted_at < NOW() - make_interval(secs => $1)
+            "#,
+        )
+        .bind(SUBMIT_TIMEOUT_SECS)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        if interrupted > 0 {
+            tracing::error!(
+                "{} No-set conversion submissions were interrupted and need checking on chain",
+                interrupted
+            );
+        }
+
+        let outstanding: Vec<(Uuid, String)> = sqlx::query_as(
+            r#"
+            SELECT id, chain_status FROM neg_risk_conversions
+            WHERE chain_status = 'pending' OR (chain_status = 'reconcile' AND tx_hash IS NOT NULL)
+            ORDER BY created_at
+            LIMIT $1
+            "#,
+        )
+        .bind(SUBMIT_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut settled = 0;
+        for (conversion_id, status) in outstanding {
+            let result = match status.as_str() {
+                CHAIN_PENDING => submit_onchain(&self.pool, self.chain.as_ref(), conversion_id).await,
+                _ => reconcile_onchain(&self.pool, self.chain.as_ref(), conversion_id).await,
+            };
+            match result {
+                Ok(conversion) => {
+                    if conversion.chain_status == CHAIN_CONFIRMED || conversion.chain_status == CHAIN_FAILED {
+                        settled += 1;
+                    }
+                }
+                // Submitted meanwhile by an admin
+                Err(NegRiskError::NotSubmittable(_)) => {}
+                Err(e) => tracing::warn!("Failed to submit conversion {}: {}", conversion_id, e),
+            }
+        }
+        Ok(settled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::test_support::{account, MatchOutcome, TestApp, MAKER, TAKER};
+
+    #[test]
+    fn test_complete_sets_is_smallest_position() {
+        assert_eq!(complete_sets(&[dec!(10), dec!(4), dec!(7)]), dec!(4));
+        assert_eq!(complete_sets(&[dec!(10), dec!(0)]), dec!(0));
+        assert_eq!(complete_sets(&[dec!(10)]), dec!(0));
+        assert_eq!(complete_sets(&[dec!(-1), dec!(5)]), dec!(0));
+    }
+
+    #[test]
+    fn test_set_payout_is_n_minus_one() {
+        assert_eq!(set_payout(3, dec!(4)), dec!(8));
+        assert_eq!(set_payout(2, dec!(2.5)), dec!(2.5));
+    }
+
+    #[test]
+    fn test_full_index_set_covers_every_question() {
+        assert_eq!(full_index_set(2), U256::from(0b11));
+        assert_eq!(full_index_set(5), U256::from(0b11111));
+    }
+
+    #[test]
+    fn test_parse_neg_risk_market_id() {
+        let id = format!("0x{}", "ab".repeat(32));
+        assert_eq!(parse_neg_risk_market_id(&id), Some([0xab; 32]));
+        assert!(parse_neg_risk_market_id(&id[2..]).is_none());
+        assert!(parse_neg_risk_market_id("0x1234").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_neg_risk_conversion_mirrored_on_chain() {
+        let app = TestApp::builder().build().await;
+        let pool = &app.state.db.pool;
+        let mut markets = Vec::new();
+        for _ in 0..3 {
+            let (market_id, yes, _) = app.create_market().await;
+            app.grant_shares(TAKER, market_id, yes, ShareType::No, dec!(4)).await;
+            markets.push((market_id, yes));
+        }
+        let market_ids: Vec<Uuid> = markets.iter().map(|(market_id, _)| *market_id).collect();
+        let adapter_market = format!("0x{}", "42".repeat(32));
+        assert!(matches!(
+            create_group(pool, "Election", true, Some("0x42"), &market_ids, &account(MAKER)).await,
+            Err(NegRiskError::InvalidNegRiskMarketId)
+        ));
+        let group = create_group(pool, "Election", true, Some(&adapter_market), &market_ids, &account(MAKER))
+            .await
+            .unwrap();
+
+        // The ledger is credited at once; the chain side waits its turn
+        let conversion = convert(pool, group.id, &account(TAKER), None, app.state.config.collateral_symbol())
+            .await
+            .unwrap();
+        assert_eq!((conversion.amount, conversion.payout), (dec!(4), dec!(8)));
+        assert_eq!(conversion.chain_status, CHAIN_PENDING);
+
+        app.chain.set_outcome(MatchOutcome::Error("nonce too low".to_string()));
+        let failed = submit_onchain(pool, app.chain.as_ref(), conversion.id).await.unwrap();
+        assert_eq!(failed.chain_status, CHAIN_FAILED);
+        assert_eq!(failed.chain_error.as_deref(), Some("nonce too low"));
+        assert!(app.chain.conversions().is_empty());
+
+        // Concurrent resubmits send one transaction, whose hash is kept
+        // while it is not mined
+        app.chain.set_outcome(MatchOutcome::Unmined);
+        let (first, second) = tokio::join!(
+            submit_onchain(pool, app.chain.as_ref(), conversion.id),
+            submit_onchain(pool, app.chain.as_ref(), conversion.id),
+        );
+        let sent = match (first, second) {
+            (Ok(sent), Err(NegRiskError::NotSubmittable(_))) | (Err(NegRiskError::NotSubmittable(_)), Ok(sent)) => sent,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(sent.chain_status, CHAIN_RECONCILE);
+        let submitted = app.chain.conversions();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(submitted[0].neg_risk_market_id, [0x42; 32]);
+        assert_eq!((submitted[0].index_set, submitted[0].amount), (U256::from(0b111), U256::from(4_000_000)));
+        assert_eq!(sent.tx_hash, Some(format!("{:?}", submitted[0].tx_hash)));
+        assert!(matches!(
+            submit_onchain(pool, app.chain.as_ref(), conversion.id).await,
+            Err(NegRiskError::NotSubmittable(status)) if status == CHAIN_RECONCILE
+        ));
+
+        // The submitter sends new conversions and reconciles sent ones
+        for (market_id, yes) in &markets {
+            app.grant_shares(MAKER, *market_id, *yes, ShareType::No, dec!(1)).await;
+        }
+        let next = convert(pool, group.id, &account(MAKER), None, app.state.config.collateral_symbol())
+            .await
+            .unwrap();
+        app.chain.set_outcome(MatchOutcome::Confirmed);
+        let submitter = ConversionSubmitter::new(pool.clone(), app.chain.clone());
+        assert_eq!(submitter.submit_pending().await.unwrap(), 2);
+        assert_eq!(app.chain.conversions().len(), 2);
+        let next_hash = Some(format!("{:?}", app.chain.conversions()[1].tx_hash));
+        for (id, tx_hash) in [(conversion.id, sent.tx_hash), (next.id, next_hash)] {
+            let confirmed = reconcile_onchain(pool, app.chain.as_ref(), id).await.unwrap();
+            assert_eq!((confirmed.chain_status.as_str(), confirmed.tx_hash), (CHAIN_CONFIRMED, tx_hash));
+        }
+        assert!(matches!(
+            submit_onchain(pool, app.chain.as_ref(), conversion.id).await,
+            Err(NegRiskError::NotSubmittable(status)) if status == CHAIN_CONFIRMED
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_neg_risk_submission_is_reconciled() {
+        let app = TestApp::builder().build().await;
+        let pool = &app.state.db.pool;
+        let mut market_ids = Vec::new();
+        for _ in 0..2 {
+            let (market_id, yes, _) = app.create_market().await;
+            app.grant_shares(TAKER, market_id, yes, ShareType::No, dec!(2)).await;
+            market_ids.push(market_id);
+        }
+        let adapter_market = format!("0x{}", "42".repeat(32));
+        let group = create_group(pool, "Election", true, Some(&adapter_market), &market_ids, &account(MAKER))
+            .await
+            .unwrap();
+        let conversion = convert(pool, group.id, &account(TAKER), None, app.state.config.collateral_symbol())
+            .await
+            .unwrap();
+
+        // A submitter that crashed after claiming the row: a fresh claim is
+        // left alone, a stale one handed to reconcile without being resent
+        let claim = |age: &'static str| {
+            sqlx::query(
+                "UPDATE neg_risk_conversions SET chain_status = 'submitting', submitted_at = NOW() - $2::interval
+                 WHERE id = $1",
+            )
+            .bind(conversion.id)
+            .bind(age)
+        };
+        claim("1 minute").execute(pool).await.unwrap();
+        let submitter = ConversionSubmitter::new(pool.clone(), app.chain.clone());
+        assert_eq!(submitter.submit_pending().await.unwrap(), 0);
+        let status = || {
+            sqlx::query_as::<_, (String, Option<String>)>(
+                "SELECT chain_status, tx_hash FROM neg_risk_conversions WHERE id = $1",
+            )
+            .bind(conversion.id)
+        };
+        assert_eq!(status().fetch_one(pool).await.unwrap().0, CHAIN_SUBMITTING);
+
+        claim("1 hour").execute(pool).await.unwrap();
+        assert_eq!(submitter.submit_pending().await.unwrap(), 0);
+        assert_eq!(submitter.submit_pending().await.unwrap(), 0);
+        assert_eq!(status().fetch_one(pool).await.unwrap(), (CHAIN_RECONCILE.to_string(), None));
+        assert!(app.chain.conversions().is_empty());
+
+        // Once checked, an operator resubmits it
+        app.chain.set_outcome(MatchOutcome::Confirmed);
+        let sent = submit_onchain(pool, app.chain.as_ref(), conversion.id).await.unwrap();
+        assert_eq!(sent.chain_status, CHAIN_CONFIRMED);
+        assert_eq!(app.chain.conversions().len(), 1);
+    }
+}
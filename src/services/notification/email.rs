@@ -0,0 +1,192 @@
+//! Pluggable email senders
+
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox, Message, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+use crate::config::AppConfig;
+
+use super::templates::RenderedEmail;
+
+/// Email delivery error
+#[derive(Debug, thiserror::Error)]
+pub enum EmailError {
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Transport error: {0}")]
+    Transport(String),
+    #[error("Provider rejected message: HTTP {0}")]
+    Rejected(u16),
+}
+
+/// Anything that can deliver a rendered email
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    async fn send(&self, to: &str, email: &RenderedEmail) -> Result<(), EmailError>;
+
+    fn name(&self) -> &'static str;
+}
+
+/// Build the sender selected by `EMAIL_PROVIDER`, falling back to logging
+pub fn sender_from_config(config: &AppConfig) -> Box<dyn EmailSender> {
+    match config.email_provider.as_str() {
+        "smtp" => match SmtpSender::from_config(config) {
+            Ok(sender) => return Box::new(sender),
+            Err(e) => tracing::error!("SMTP email sender misconfigured, falling back to log: {}", e),
+        },
+        "api" => match (&config.email_api_url, &config.email_api_key) {
+            (Some(url), Some(key)) => {
+                return Box::new(HttpApiSender::new(url.clone(), key.clone(), config.email_from.clone()));
+            }
+            _ => tracing::error!("EMAIL_API_URL / EMAIL_API_KEY not set, falling back to log"),
+        },
+        "log" => {}
+        other => tracing::warn!("Unknown EMAIL_PROVIDER '{}', falling back to log", other),
+    }
+    Box::new(LogSender)
+}
+
+// ============================================================================
+// SMTP
+// ============================================================================
+
+/// SMTP sender (STARTTLS)
+pub struct SmtpSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpSender {
+    pub fn from_config(config: &AppConfig) -> Result<Self, EmailError> {
+        let host = config
+            .smtp_host
+            .as_deref()
+            .ok_or_else(|| EmailError::Transport("SMTP_HOST not set".to_string()))?;
+
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| EmailError::Transport(e.to_string()))?
+            .port(config.smtp_port);
+
+        if let (Some(user), Some(pass)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(user.clone(), pass.clone()));
+        }
+
+        let from = config
+            .email_from
+            .parse()
+            .map_err(|_| EmailError::InvalidAddress(config.email_from.clone()))?;
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpSender {
+    async fn send(&self, to: &str, email: &RenderedEmail) -> Result<(), EmailError> {
+        let to: Mailbox = to.parse().map_err(|_| EmailError::InvalidAddress(to.to_string()))?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&email.subject)
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(
+                        SinglePart::builder()
+                            .header(ContentType::TEXT_PLAIN)
+                            .body(email.text.clone()),
+                    )
+                    .singlepart(
+                        SinglePart::builder()
+                            .header(ContentType::TEXT_HTML)
+                            .body(email.html.clone()),
+                    ),
+            )
+            .map_err(|e| EmailError::Transport(e.to_string()))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| EmailError::Transport(e.to_string()))?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+}
+
+// ============================================================================
+// HTTP provider API
+// ============================================================================
+
+/// Generic transactional-email provider: POSTs
+/// `{from, to, subject, text, html}` as JSON with a bearer API key
+pub struct HttpApiSender {
+    http: reqwest::Client,
+    url: String,
+    api_key: String,
+    from: String,
+}
+
+impl HttpApiSender {
+    pub fn new(url: String, api_key: String, from: String) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self { http, url, api_key, from }
+    }
+}
+
+#[async_trait]
+impl EmailSender for HttpApiSender {
+    async fn send(&self, to: &str, email: &RenderedEmail) -> Result<(), EmailError> {
+        let resp = self
+            .http
+            .post(&self.url)
+            .bearer_auth(&self.api_key)
+            .json(&serde_json::json!({
+                "from": self.from,
+                "to": [to],
+                "subject": email.subject,
+                "text": email.text,
+                "html": email.html,
+            }))
+            .send()
+            .await
+            .map_err(|e| EmailError::Transport(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            return Err(EmailError::Rejected(resp.status().as_u16()));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "api"
+    }
+}
+
+// ============================================================================
+// Log (development)
+// ============================================================================
+
+/// Writes emails to the log instead of sending them
+pub struct LogSender;
+
+#[async_trait]
+impl EmailSender for LogSender {
+    async fn send(&self, to: &str, email: &RenderedEmail) -> Result<(), EmailError> {
+        tracing::info!("[email] to={} subject={:?}\n{}", to, email.subject, email.text);
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "log"
+    }
+}
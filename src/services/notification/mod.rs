@@ -0,0 +1,310 @@
+//! Email Notification Service
+//!
+//! Turns account events (large fills, settlement payouts, withdrawal status
+//! changes, incoming transfers) into emails. Events are filtered against the user's
+//! `notification_preferences`, written to `notification_queue`, and rendered
+//! in the user's display preferences and sent by a background worker
+//! through a pluggable [`EmailSender`], so request handlers never wait on a
+//! mail server.
+
+mod email;
+mod templates;
+
+pub use email::{sender_from_config, EmailError, EmailSender};
+pub use templates::RenderedEmail;
+use templates::render;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::preferences;
+use crate::services::retry::RetryPolicy;
+
+/// Kinds of notifications a user can receive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    OrderFilled,
+    SettlementPayout,
+    WithdrawalStatus,
+    TradeAdjusted,
+    ResolutionReminder,
+    MmProtectionTriggered,
+    TransferReceived,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 7] = [
+        NotificationKind::OrderFilled,
+        NotificationKind::SettlementPayout,
+        NotificationKind::WithdrawalStatus,
+        NotificationKind::TradeAdjusted,
+        NotificationKind::ResolutionReminder,
+        NotificationKind::MmProtectionTriggered,
+        NotificationKind::TransferReceived,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::OrderFilled => "order_filled",
+            NotificationKind::SettlementPayout => "settlement_payout",
+            NotificationKind::WithdrawalStatus => "withdrawal_status",
+            NotificationKind::TradeAdjusted => "trade_adjusted",
+            NotificationKind::ResolutionReminder => "resolution_reminder",
+            NotificationKind::MmProtectionTriggered => "mm_protection_triggered",
+            NotificationKind::TransferReceived => "transfer_received",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == s)
+    }
+
+    /// Preference column that opts the user in to this kind
+    fn preference_column(&self) -> &'static str {
+        match self {
+            NotificationKind::OrderFilled => "notify_fills",
+            NotificationKind::SettlementPayout => "notify_settlements",
+            NotificationKind::WithdrawalStatus => "notify_withdrawals",
+            // Corrections to a user's trades cannot be opted out of
+            NotificationKind::TradeAdjusted => "email_enabled",
+            // Watching a market or trading in it is the opt-in
+            NotificationKind::ResolutionReminder => "email_enabled",
+            // Setting a protection is the opt-in
+            NotificationKind::MmProtectionTriggered => "email_enabled",
+            // Funds arriving unannounced are always worth a mail
+            NotificationKind::TransferReceived => "email_enabled",
+        }
+    }
+}
+
+impl std::fmt::Display for NotificationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Notification worker configuration
+#[derive(Debug, Clone)]
+pub struct NotificationConfig {
+    /// Send attempts before a notification is marked failed, and the delay
+    /// between them
+    pub retry: RetryPolicy,
+    /// Worker poll interval
+    pub poll_interval_ms: u64,
+    /// Notifications fetched per poll
+    pub batch_size: i64,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::new(5, 30, 3600),
+            poll_interval_ms: 2000,
+            batch_size: 50,
+        }
+    }
+}
+
+/// Pending notification loaded by the worker
+#[derive(Debug, sqlx::FromRow)]
+struct DueNotification {
+    id: Uuid,
+    user_address: Address,
+    kind: String,
+    recipient: String,
+    payload: serde_json::Value,
+    attempts: i32,
+}
+
+/// Notification enqueuer and email worker
+pub struct NotificationService {
+    pool: PgPool,
+    sender: Box<dyn EmailSender>,
+    config: NotificationConfig,
+}
+
+impl NotificationService {
+    pub fn new(pool: PgPool, sender: Box<dyn EmailSender>, config: NotificationConfig) -> Self {
+        Self { pool, sender, config }
+    }
+
+    /// Queue a notification for `user_address` if they have an email on file
+    /// and have opted in to this kind.
+    ///
+    /// Fills carry a `notional` that must reach the user's
+    /// `fill_min_notional`. Failures are logged and swallowed: notifications
+    /// must never fail the calling request.
+    pub async fn notify(
+        &self,
+        kind: NotificationKind,
+        user_address: &str,
+        notional: Option<Decimal>,
+        data: serde_json::Value,
+    ) {
+        let query = format!(
+            r#"
+            INSERT INTO notification_queue (user_address, kind, recipient, payload)
+            SELECT u.address, $2, u.email, $3
+            FROM users u
+            JOIN notification_preferences p ON p.user_address = u.address
+            WHERE u.address = $1
+              AND u.email IS NOT NULL
+              AND p.email_enabled
+              AND p.{}
+              AND ($4::numeric IS NULL OR $4 >= p.fill_min_notional)
+            "#,
+            kind.preference_column()
+        );
+
+        let result = sqlx::query(&query)
+            .bind(user_address)
+            .bind(kind.as_str())
+            .bind(&data)
+            .bind(notional)
+            .execute(&self.pool)
+            .await;
+
+        match result {
+            Ok(r) if r.rows_affected() > 0 => {
+                tracing::debug!("Queued {} notification for {}", kind, user_address);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to queue {} notification for {}: {}", kind, user_address, e),
+        }
+    }
+
+    /// Spawn the background send worker
+    pub fn start_worker(self: Arc<Self>) {
+        tokio::spawn(async move {
+            tracing::info!("Notification worker started (sender: {})", self.sender.name());
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.process_due().await {
+                    tracing::error!("Notification worker error: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Send all due notifications once
+    async fn process_due(&self) -> Result<(), sqlx::Error> {
+        // Lease a batch by pushing next_attempt_at forward; SKIP LOCKED lets
+        // several instances share the queue without double-sending.
+        let due: Vec<DueNotification> = sqlx::query_as(
+            r#"
+            WITH claimed AS (
+                SELECT id
+                FROM notification_queue
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE notification_queue q
+            SET next_attempt_at = NOW() + INTERVAL '60 seconds'
+            FROM claimed
+            WHERE q.id = claimed.id
+            RETURNING q.id, q.user_address, q.kind, q.recipient, q.payload, q.attempts
+            "#,
+        )
+        .bind(self.config.batch_size)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for notification in due {
+            self.send(notification).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send(&self, notification: DueNotification) -> Result<(), sqlx::Error> {
+        let attempts = notification.attempts + 1;
+
+        let Some(kind) = NotificationKind::parse(&notification.kind) else {
+            sqlx::query("UPDATE notification_queue SET status = 'failed', attempts = $1, last_error = $2 WHERE id = $3")
+                .bind(attempts)
+                .bind(format!("unknown notification kind '{}'", notification.kind))
+                .bind(notification.id)
+                .execute(&self.pool)
+                .await?;
+            return Ok(());
+        };
+
+        // Display preferences are applied at send time so changes reach
+        // notifications already queued
+        let mut payload = notification.payload;
+        preferences::load(&self.pool, &notification.user_address)
+            .await?
+            .localize(&mut payload);
+        let email = render(kind, &payload);
+
+        let error = match self.sender.send(&notification.recipient, &email).await {
+            Ok(()) => {
+                sqlx::query(
+                    "UPDATE notification_queue SET status = 'sent', attempts = $1, last_error = NULL, sent_at = NOW() WHERE id = $2",
+                )
+                .bind(attempts)
+                .bind(notification.id)
+                .execute(&self.pool)
+                .await?;
+                crate::metrics::record_notification(kind.as_str(), "sent");
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        // An unparseable recipient will never succeed; don't retry it
+        let exhausted = self.config.retry.exhausted(attempts) || matches!(error, EmailError::InvalidAddress(_));
+        let error = error.to_string();
+        let next_attempt = self.config.retry.next_attempt_at(attempts);
+
+        sqlx::query(
+            "UPDATE notification_queue SET status = $1, attempts = $2, last_error = $3, next_attempt_at = $4 WHERE id = $5",
+        )
+        .bind(if exhausted { "failed" } else { "pending" })
+        .bind(attempts)
+        .bind(&error)
+        .bind(next_attempt)
+        .bind(notification.id)
+        .execute(&self.pool)
+        .await?;
+
+        if exhausted {
+            tracing::warn!(
+                "Notification {} to {} failed permanently after {} attempts: {}",
+                notification.id,
+                notification.recipient,
+                attempts,
+                error
+            );
+            crate::metrics::record_notification(kind.as_str(), "failed");
+        } else {
+            tracing::debug!("Notification {} attempt {} failed: {}", notification.id, attempts, error);
+            crate::metrics::record_notification(kind.as_str(), "retry");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_round_trip() {
+        for k in NotificationKind::ALL {
+            assert_eq!(NotificationKind::parse(k.as_str()), Some(k));
+        }
+        assert_eq!(NotificationKind::parse("unknown"), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Email templates
+//!
+//! Templates use `{{key}}` placeholders filled from the queued payload's
+//! top-level fields. Values are HTML-escaped in the HTML body; unknown
+//! placeholders render as empty strings.
+
+use serde_json::Value;
+
+use super::NotificationKind;
+
+/// A fully rendered email
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+struct Template {
+    subject: &'static str,
+    text: &'static str,
+    html: &'static str,
+}
+
+const ORDER_FILLED: Template = Template {
+    subject: "Order filled: {{side}} {{amount}} {{share_type}} @ {{price}}",
+    text: "Your {{side}} order {{order_id}} was filled.\n\n\
+           Market: {{market_id}}\n\
+           Outcome: {{share_type}}\n\
+           Amount: {{amount}} shares @ {{price}}\n\
+           Notional: {{notional}} {{symbol}}\n\
+           Fee: {{fee}} {{symbol}}\n",
+    html: "<p>Your <b>{{side}}</b> order <code>{{order_id}}</code> was filled.</p>\
+           <table>\
+           <tr><td>Market</td><td>{{market_id}}</td></tr>\
+           <tr><td>Outcome</td><td>{{share_type}}</td></tr>\
+           <tr><td>Amount</td><td>{{amount}} shares @ {{price}}</td></tr>\
+           <tr><td>Notional</td><td>{{notional}} {{symbol}}</td></tr>\
+           <tr><td>Fee</td><td>{{fee}} {{symbol}}</td></tr>\
+           </table>",
+};
+
+const SETTLEMENT_PAYOUT: Template = Template {
+    subject: "Settlement payout: {{total_payout}} {{symbol}}",
+    text: "Your positions in market {{market_id}} were settled ({{settlement_type}}).\n\n\
+           Payout credited: {{total_payout}} {{symbol}}\n",
+    html: "<p>Your positions in market <code>{{market_id}}</code> were settled ({{settlement_type}}).</p>\
+           <p>Payout credited: <b>{{total_payout}} {{symbol}}</b></p>",
+};
+
+const WITHDRAWAL_STATUS: Template = Template {
+    subject: "Withdrawal {{status}}: {{amount}} {{symbol}}",
+    text: "Your withdrawal {{withdrawal_id}} is now {{status}}.\n\n\
+           Amount: {{amount}} {{symbol}}\n\
+           To: {{to_address}}\n\
+           Transaction: {{tx_hash}}\n",
+    html: "<p>Your withdrawal <code>{{withdrawal_id}}</code> is now <b>{{status}}</b>.</p>\
+           <table>\
+           <tr><td>Amount</td><td>{{amount}} {{symbol}}</td></tr>\
+           <tr><td>To</td><td>{{to_address}}</td></tr>\
+           <tr><td>Transaction</td><td>{{tx_hash}}</td></tr>\
+           </table>",
+};
+
+const TRADE_ADJUSTED: Template = Template {
+    subject: "Trade {{status}}: {{amount}} {{share_type}} @ {{previous_price}}",
+    text: "Your trade {{trade_id}} (order {{order_id}}) was {{status}} by the exchange.\n\n\
+           Market: {{market_id}}\n\
+           Amount: {{amount}} {{share_type}} shares @ {{previous_price}}\n\
+           Adjusted price: {{adjusted_price}}\n\
+           Shares change: {{shares}}\n\
+           Balance change: {{collateral}} {{symbol}}\n\
+           Reason: {{reason}}\n",
+    html: "<p>Your trade <code>{{trade_id}}</code> (order <code>{{order_id}}</code>) was <b>{{status}}</b> by the exchange.</p>\
+           <table>\
+           <tr><td>Market</td><td>{{market_id}}</td></tr>\
+           <tr><td>Amount</td><td>{{amount}} {{share_type}} shares @ {{previous_price}}</td></tr>\
+           <tr><td>Adjusted price</td><td>{{adjusted_price}}</td></tr>\
+           <tr><td>Shares change</td><td>{{shares}}</td></tr>\
+           <tr><td>Balance change</td><td>{{collateral}} {{symbol}}</td></tr>\
+           <tr><td>Reason</td><td>{{reason}}</td></tr>\
+           </table>",
+};
+
+const RESOLUTION_REMINDER: Template = Template {
+    subject: "Resolving in {{lead}}: {{question}}",
+    text: "Market \"{{question}}\" is scheduled to resolve at {{resolution_time}}.\n\n\
+           Market: {{market_id}}\n\
+           Trading stops at the resolution time; open orders can still be cancelled afterwards.\n",
+    html: "<p>Market <b>{{question}}</b> is scheduled to resolve at {{resolution_time}}.</p>\
+           <table>\
+           <tr><td>Market</td><td>{{market_id}}</td></tr>\
+           </table>\
+           <p>Trading stops at the resolution time; open orders can still be cancelled afterwards.</p>",
+};
+
+const MM_PROTECTION_TRIGGERED: Template = Template {
+    subject: "Quotes pulled in market {{market_id}}",
+    text: "A market maker protection ({{reason}}) was triggered and {{cancelled_orders}} of your orders in market {{market_id}} were cancelled.\n\n\
+           Fills in the last second: {{fills_last_second}}\n\
+           Loss over the last minute: {{loss_last_minute}}\n\
+           Review your quotes before placing new orders in this market.\n",
+    html: "<p>A market maker protection (<b>{{reason}}</b>) was triggered and {{cancelled_orders}} of your orders in market <code>{{market_id}}</code> were cancelled.</p>\
+           <table>\
+           <tr><td>Fills in the last second</td><td>{{fills_last_second}}</td></tr>\
+           <tr><td>Loss over the last minute</td><td>{{loss_last_minute}}</td></tr>\
+           </table>\
+           <p>Review your quotes before placing new orders in this market.</p>",
+};
+
+const TRANSFER_RECEIVED: Template = Template {
+    subject: "Transfer received: {{amount}} {{symbol}}",
+    text: "{{from_address}} sent you {{amount}} {{symbol}}.\n\n\
+           Transfer: {{transfer_id}}\n\
+           Memo: {{memo}}\n",
+    html: "<p><code>{{from_address}}</code> sent you <b>{{amount}} {{symbol}}</b>.</p>\
+           <table>\
+           <tr><td>Transfer</td><td>{{transfer_id}}</td></tr>\
+           <tr><td>Memo</td><td>{{memo}}</td></tr>\
+           </table>",
+};
+
+/// Render the email for a queued notification
+pub fn render(kind: NotificationKind, payload: &Value) -> RenderedEmail {
+    let template = match kind {
+        NotificationKind::OrderFilled => &ORDER_FILLED,
+        NotificationKind::SettlementPayout => &SETTLEMENT_PAYOUT,
+        NotificationKind::WithdrawalStatus => &WITHDRAWAL_STATUS,
+        NotificationKind::TradeAdjusted => &TRADE_ADJUSTED,
+        NotificationKind::ResolutionReminder => &RESOLUTION_REMINDER,
+        NotificationKind::MmProtectionTriggered => &MM_PROTECTION_TRIGGERED,
+        NotificationKind::TransferReceived => &TRANSFER_RECEIVED,
+    };
+
+    RenderedEmail {
+        subject: fill(template.subject, payload, false),
+        text: fill(template.text, payload, false),
+        html: fill(template.html, payload, true),
+    }
+}
+
+fn fill(template: &str, payload: &Value, escape: bool) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+
+        let value = match payload.get(after[..end].trim()) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(v) => v.to_string(),
+        };
+        if escape {
+            out.push_str(&html_escape(&value));
+        } else {
+            out.push_str(&value);
+        }
+        rest = &after[end + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_placeholders() {
+        let payload = serde_json::json!({ "a": "x", "n": 3, "h": "<b>" });
+        assert_eq!(fill("{{a}}-{{ n }}-{{missing}}", &payload, false), "x-3-");
+        assert_eq!(fill("{{h}}", &payload, true), "&lt;b&gt;");
+        assert_eq!(fill("open {{a", &payload, false), "open {{a");
+    }
+
+    #[test]
+    fn test_render_withdrawal() {
+        let payload = serde_json::json!({
+            "withdrawal_id": "w1",
+            "status": "completed",
+            "amount": "10",
+            "symbol": "USDC",
+        });
+        let email = render(NotificationKind::WithdrawalStatus, &payload);
+        assert_eq!(email.subject, "Withdrawal completed: 10 USDC");
+        assert!(email.text.contains("w1"));
+    }
+}
@@ -0,0 +1,561 @@
+//! Daily Operations Reports
+//!
+//! Once a UTC day has ended (after `OPS_REPORT_HOUR_UTC`), the workers'
+//! leader summarises it into `ops_reports`: trading volume and the busiest
+//! markets, fees, new users, deposits and withdrawals, settlement failures
+//! and the largest open positions. Reports are read back through
+//! `/admin/reports` and, when `OPS_REPORT_EMAILS` is set, emailed to the ops
+//! list. Positions are as of generation; everything else covers the day.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::services::leader_election::LeaderElection;
+use crate::services::notification::{sender_from_config, EmailSender, RenderedEmail};
+
+/// How often the scheduler checks for a due report
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Markets listed by volume
+const TOP_MARKETS: i64 = 5;
+
+/// Positions listed by cost
+const LARGEST_POSITIONS: i64 = 10;
+
+/// Trades of the day (busted trades excluded, adjusted ones at their new price)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradingSummary {
+    pub trades: i64,
+    pub volume: Decimal,
+    pub block_trades: i64,
+    /// Distinct makers and takers
+    pub traders: i64,
+    pub top_markets: Vec<MarketVolume>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MarketVolume {
+    pub market_id: Uuid,
+    pub question: String,
+    pub trades: i64,
+    pub volume: Decimal,
+}
+
+/// Fees charged on the day's trades; negative maker fees are rebates
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeSummary {
+    pub maker_fees: Decimal,
+    pub taker_fees: Decimal,
+    pub total: Decimal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserSummary {
+    pub new_users: i64,
+    /// Registered by the end of the day
+    pub total_users: i64,
+}
+
+/// Deposits confirmed and withdrawals requested, completed and failed on
+/// the day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FundsSummary {
+    pub deposits: i64,
+    pub deposit_amount: Decimal,
+    pub withdrawals_requested: i64,
+    pub withdrawal_amount: Decimal,
+    pub withdrawals_completed: i64,
+    pub withdrawals_failed: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SettlementSummary {
+    /// Trades of the day whose settlement failed
+    pub failed_trades: i64,
+    /// Trades of the day not yet confirmed on-chain
+    pub unconfirmed_trades: i64,
+    /// Settlement failures recorded in the system event timeline
+    pub failure_events: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Position {
+    pub user_address: String,
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: String,
+    pub amount: Decimal,
+    /// Amount times average cost
+    pub cost: Decimal,
+}
+
+/// Summary of one UTC day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpsReport {
+    pub date: NaiveDate,
+    pub trading: TradingSummary,
+    pub fees: FeeSummary,
+    pub users: UserSummary,
+    pub funds: FundsSummary,
+    pub settlement: SettlementSummary,
+    pub largest_positions: Vec<Position>,
+}
+
+/// A stored report
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StoredReport {
+    pub report_date: NaiveDate,
+    #[sqlx(json)]
+    pub summary: OpsReport,
+    pub generated_at: DateTime<Utc>,
+    pub emailed_at: Option<DateTime<Utc>>,
+}
+
+/// Summarise `date` (UTC day)
+pub async fn generate(pool: &PgPool, date: NaiveDate) -> Result<OpsReport, sqlx::Error> {
+    let start = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = start + chrono::Duration::days(1);
+
+    #[allow(clippy::type_complexity)]
+    let (trades, volume, block_trades, traders, maker_fees, taker_fees): (
+        i64,
+        Decimal,
+        i64,
+        i64,
+        Decimal,
+        Decimal,
+    ) = sqlx::query_as(
+        r#"
+        WITH day AS (
+            SELECT * FROM trades
+            WHERE created_at >= $1 AND created_at < $2
+              AND adjustment_status IS DISTINCT FROM 'busted'
+        )
+        SELECT COUNT(*),
+               COALESCE(SUM(COALESCE(adjusted_price, price) * amount), 0),
+               COUNT(*) FILTER (WHERE is_block_trade),
+               (SELECT COUNT(DISTINCT a) FROM day, UNNEST(ARRAY[maker_address, taker_address]) a),
+               COALESCE(SUM(maker_fee), 0),
+               COALESCE(SUM(taker_fee), 0)
+        FROM day
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    let top_markets: Vec<MarketVolume> = sqlx::query_as(
+        r#"
+        SELECT t.market_id, m.question, COUNT(*) AS trades,
+               SUM(COALESCE(t.adjusted_price, t.price) * t.amount) AS volume
+        FROM trades t
+        JOIN markets m ON m.id = t.market_id
+        WHERE t.created_at >= $1 AND t.created_at < $2
+          AND t.adjustment_status IS DISTINCT FROM 'busted'
+        GROUP BY t.market_id, m.question
+        ORDER BY volume DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(TOP_MARKETS)
+    .fetch_all(pool)
+    .await?;
+
+    let (new_users, total_users): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*) FILTER (WHERE created_at >= $1), COUNT(*) FROM users WHERE created_at < $2",
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    let (deposits, deposit_amount): (i64, Decimal) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(amount), 0) FROM deposits
+         WHERE status = 'confirmed' AND created_at >= $1 AND created_at < $2",
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    let (withdrawals_requested, withdrawal_amount, withdrawals_completed, withdrawals_failed): (
+        i64,
+        Decimal,
+        i64,
+        i64,
+    ) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FILTER (WHERE created_at >= $1 AND created_at < $2),
+               COALESCE(SUM(amount) FILTER (WHERE created_at >= $1 AND created_at < $2), 0),
+               COUNT(*) FILTER (WHERE status = 'completed' AND updated_at >= $1 AND updated_at < $2),
+               COUNT(*) FILTER (WHERE status = 'failed' AND updated_at >= $1 AND updated_at < $2)
+        FROM withdrawals
+        WHERE (created_at >= $1 AND created_at < $2) OR (updated_at >= $1 AND updated_at < $2)
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    let (failed_trades, unconfirmed_trades): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FILTER (WHERE settlement_status = 'failed'),
+               COUNT(*) FILTER (WHERE settlement_status IN ('pending', 'submitted'))
+        FROM trades
+        WHERE created_at >= $1 AND created_at < $2
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    let failure_events: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM system_events WHERE kind = 'settlement_failed' AND created_at >= $1 AND created_at < $2",
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    let largest_positions: Vec<Position> = sqlx::query_as(
+        r#"
+        SELECT user_address, market_id, outcome_id, share_type::text AS share_type, amount,
+               amount * COALESCE(avg_cost, 0) AS cost
+        FROM shares
+        WHERE amount > 0
+        ORDER BY cost DESC, amount DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(LARGEST_POSITIONS)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(OpsReport {
+        date,
+        trading: TradingSummary {
+            trades,
+            volume,
+            block_trades,
+            traders,
+            top_markets,
+        },
+        fees: FeeSummary {
+            maker_fees,
+            taker_fees,
+            total: maker_fees + taker_fees,
+        },
+        users: UserSummary { new_users, total_users },
+        funds: FundsSummary {
+            deposits,
+            deposit_amount,
+            withdrawals_requested,
+            withdrawal_amount,
+            withdrawals_completed,
+            withdrawals_failed,
+        },
+        settlement: SettlementSummary {
+            failed_trades,
+            unconfirmed_trades,
+            failure_events,
+        },
+        largest_positions,
+    })
+}
+
+/// Store `report`, replacing an earlier one of the same day (which keeps
+/// its `emailed_at`, so a regenerated report isn't emailed twice)
+pub async fn store(pool: &PgPool, report: &OpsReport) -> Result<StoredReport, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        INSERT INTO ops_reports (report_date, summary)
+        VALUES ($1, $2)
+        ON CONFLICT (report_date) DO UPDATE
+        SET summary = EXCLUDED.summary, generated_at = NOW()
+        RETURNING report_date, summary, generated_at, emailed_at
+        "#,
+    )
+    .bind(report.date)
+    .bind(sqlx::types::Json(report))
+    .fetch_one(pool)
+    .await
+}
+
+/// The report of `date`, if generated
+pub async fn get(pool: &PgPool, date: NaiveDate) -> Result<Option<StoredReport>, sqlx::Error> {
+    sqlx::query_as("SELECT report_date, summary, generated_at, emailed_at FROM ops_reports WHERE report_date = $1")
+        .bind(date)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Reports between `from` and `to` (inclusive), newest first
+pub async fn list(
+    pool: &PgPool,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    limit: i64,
+) -> Result<Vec<StoredReport>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT report_date, summary, generated_at, emailed_at
+        FROM ops_reports
+        WHERE ($1::date IS NULL OR report_date >= $1)
+          AND ($2::date IS NULL OR report_date <= $2)
+        ORDER BY report_date DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Plain-text and HTML email of `report`
+pub fn render_email(report: &OpsReport) -> RenderedEmail {
+    let t = &report.trading;
+    let rows = [
+        ("Trades", t.trades.to_string()),
+        ("Volume", t.volume.round_dp(2).to_string()),
+        ("Block trades", t.block_trades.to_string()),
+        ("Traders", t.traders.to_string()),
+        ("Fees (maker / taker)", format!("{} / {}", report.fees.maker_fees.round_dp(2), report.fees.taker_fees.round_dp(2))),
+        ("New users", format!("{} ({} total)", report.users.new_users, report.users.total_users)),
+        ("Deposits", format!("{} ({})", report.funds.deposits, report.funds.deposit_amount.round_dp(2))),
+        (
+            "Withdrawals requested",
+            format!("{} ({})", report.funds.withdrawals_requested, report.funds.withdrawal_amount.round_dp(2)),
+        ),
+        (
+            "Withdrawals completed / failed",
+            format!("{} / {}", report.funds.withdrawals_completed, report.funds.withdrawals_failed),
+        ),
+        (
+            "Settlement failures",
+            format!(
+                "{} trades, {} events ({} unconfirmed)",
+                report.settlement.failed_trades, report.settlement.failure_events, report.settlement.unconfirmed_trades
+            ),
+        ),
+    ];
+
+    let mut text = format!("Operations report for {}\n\n", report.date);
+    let mut html = format!("<p>Operations report for <b>{}</b></p><table>", report.date);
+    for (label, value) in &rows {
+        text.push_str(&format!("{}: {}\n", label, value));
+        html.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>", label, value));
+    }
+    html.push_str("</table>");
+
+    text.push_str("\nTop markets by volume\n");
+    html.push_str("<p>Top markets by volume</p><table>");
+    for m in &t.top_markets {
+        text.push_str(&format!("  {} ({}): {} in {} trades\n", m.question, m.market_id, m.volume.round_dp(2), m.trades));
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&m.question),
+            m.volume.round_dp(2),
+            m.trades
+        ));
+    }
+    html.push_str("</table>");
+
+    text.push_str("\nLargest positions\n");
+    html.push_str("<p>Largest positions</p><table>");
+    for p in &report.largest_positions {
+        text.push_str(&format!(
+            "  {} {} {} of {}: cost {}\n",
+            p.user_address,
+            p.amount.normalize(),
+            p.share_type,
+            p.outcome_id,
+            p.cost.round_dp(2)
+        ));
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{} {}</td><td><code>{}</code></td><td>{}</td></tr>",
+            p.user_address,
+            p.amount.normalize(),
+            p.share_type,
+            p.outcome_id,
+            p.cost.round_dp(2)
+        ));
+    }
+    html.push_str("</table>");
+
+    RenderedEmail {
+        subject: format!("Operations report {}: {} volume, {} trades", report.date, t.volume.round_dp(2), t.trades),
+        text,
+        html,
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Generates each day's report after it ends and emails it to the ops list
+pub struct OpsReportJob {
+    pool: PgPool,
+    sender: Box<dyn EmailSender>,
+    recipients: Vec<String>,
+    hour_utc: u32,
+}
+
+impl OpsReportJob {
+    pub fn new(pool: PgPool, config: &AppConfig) -> Self {
+        Self {
+            pool,
+            sender: sender_from_config(config),
+            recipients: config.ops_report_recipients(),
+            hour_utc: config.ops_report_hour_utc,
+        }
+    }
+
+    /// Spawn the scheduler; only the elected leader generates
+    pub fn start(self: Arc<Self>, leader: Arc<LeaderElection>) {
+        tokio::spawn(async move {
+            tracing::info!("Ops report scheduler started (after {:02}:00 UTC)", self.hour_utc);
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if !leader.is_leader() {
+                    continue;
+                }
+                if let Err(e) = self.run_due(Utc::now()).await {
+                    tracing::error!("Daily ops report failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Generate the previous day's report once `now` is past the report
+    /// hour, and email it if it hasn't gone out yet
+    pub async fn run_due(&self, now: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        if now.hour() < self.hour_utc {
+            return Ok(());
+        }
+        let Some(date) = now.date_naive().pred_opt() else {
+            return Ok(());
+        };
+        let stored = match get(&self.pool, date).await? {
+            Some(stored) => stored,
+            None => {
+                let stored = store(&self.pool, &generate(&self.pool, date).await?).await?;
+                tracing::info!(
+                    "Generated ops report for {}: {} trades, {} volume",
+                    date,
+                    stored.summary.trading.trades,
+                    stored.summary.trading.volume
+                );
+                stored
+            }
+        };
+        if stored.emailed_at.is_none() && !self.recipients.is_empty() {
+            self.email(&stored.summary).await?;
+        }
+        Ok(())
+    }
+
+    /// Email `report` to the ops list; marked sent once every recipient
+    /// took it, so a failure is retried on the next check
+    async fn email(&self, report: &OpsReport) -> Result<(), sqlx::Error> {
+        let email = render_email(report);
+        let mut delivered = true;
+        for to in &self.recipients {
+            if let Err(e) = self.sender.send(to, &email).await {
+                tracing::warn!("Failed to email ops report for {} to {}: {}", report.date, to, e);
+                delivered = false;
+            }
+        }
+        if delivered {
+            sqlx::query("UPDATE ops_reports SET emailed_at = NOW() WHERE report_date = $1")
+                .bind(report.date)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market::ShareType;
+    use crate::models::{OrderSide, OrderType, TimeInForce};
+    use crate::services::matching::{OrderIntent, SelfTradePrevention};
+    use crate::services::order_gateway::OrderSource;
+    use crate::test_support::TestApp;
+    use rust_decimal_macros::dec;
+
+    const SELLER: &str = "0x9393939393939393939393939393939393939393";
+    const BUYER: &str = "0x9494949494949494949494949494949494949494";
+
+    fn limit(market_id: Uuid, outcome_id: Uuid, user: &str, side: OrderSide) -> OrderIntent {
+        OrderIntent {
+            source: OrderSource::Api,
+            user_address: user.to_string(),
+            market_id,
+            outcome_id,
+            share_type: ShareType::Yes,
+            side,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            stp: SelfTradePrevention::default(),
+            price: dec!(0.4),
+            amount: dec!(10),
+            expires_at: None,
+            signature: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_summarises_day() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.grant_shares(SELLER, market_id, yes, ShareType::Yes, dec!(10)).await;
+        app.deposit(BUYER, dec!(100)).await;
+        sqlx::query(
+            "INSERT INTO deposits (user_address, token, amount, tx_hash, block_number, status)
+             VALUES ($1, 'USDC', 100, '0xreportdeposit', 1, 'confirmed')",
+        )
+        .bind(BUYER)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+
+        let flow = &app.state.order_flow;
+        flow.place(&limit(market_id, yes, SELLER, OrderSide::Sell)).await.unwrap();
+        flow.place(&limit(market_id, yes, BUYER, OrderSide::Buy)).await.unwrap();
+
+        let today = Utc::now().date_naive();
+        let report = generate(&app.db.pool, today).await.unwrap();
+        assert_eq!((report.trading.trades, report.trading.volume, report.trading.traders), (1, dec!(4), 2));
+        assert_eq!(report.trading.top_markets[0].market_id, market_id);
+        assert_eq!((report.funds.deposits, report.funds.deposit_amount), (1, dec!(100)));
+        assert_eq!(report.largest_positions[0].user_address, BUYER);
+
+        // The previous day is empty
+        let yesterday = generate(&app.db.pool, today.pred_opt().unwrap()).await.unwrap();
+        assert_eq!(yesterday.trading.trades, 0);
+
+        store(&app.db.pool, &report).await.unwrap();
+        let stored = get(&app.db.pool, today).await.unwrap().unwrap();
+        assert_eq!(stored.summary, report);
+        assert!(render_email(&report).text.contains("Volume: 4"));
+    }
+}
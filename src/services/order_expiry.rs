@@ -0,0 +1,247 @@
+//! Good-Til-Date Order Expiry
+//!
+//! Orders placed with an `expires_at` rest until then at most. The expiries
+//! live in the matching engine next to the books; this service sweeps them
+//! once a second, together with the resting orders of markets whose
+//! `end_time` has passed, and mirrors the expiries in the database,
+//! releasing buy reservations. Owners get the usual order update and book
+//! subscribers the new book from the engine.
+//!
+//! Each book is expired on its market's engine shard, in line with the
+//! orders matching there. A book whose shard is overloaded is left for the
+//! next sweep.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::matching::precision::Collateral;
+use crate::services::matching::{EngineShards, OrderEntry};
+use crate::services::portfolio_margin;
+use crate::utils::clock::{Clock, SystemClock};
+
+/// How often expiries are swept
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Expires good-til-date orders and the orders of markets that stopped trading
+pub struct OrderExpiry {
+    pool: PgPool,
+    shards: Arc<EngineShards>,
+    collateral_token: String,
+    clock: Arc<dyn Clock>,
+}
+
+impl OrderExpiry {
+    pub fn new(pool: PgPool, shards: Arc<EngineShards>, collateral_token: &str) -> Self {
+        Self {
+            pool,
+            shards,
+            collateral_token: collateral_token.to_string(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read expiries against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Spawn the sweeping loop
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            tracing::info!("Order expiry sweeper started");
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.sweep().await {
+                    tracing::error!("Order expiry sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Expire every order whose expiry or market end time has passed,
+    /// returning how many were taken off the books
+    pub async fn sweep(&self) -> Result<usize, sqlx::Error> {
+        let now = self.clock.now();
+        let now_ms = now.timestamp_millis();
+        let mut expired: Vec<(String, OrderEntry)> = Vec::new();
+        for symbol in self.shards.engine().expiring_books(now_ms) {
+            let book = symbol.clone();
+            match self.shards.execute(&symbol, move |engine| engine.expire_orders(&book, now_ms)).await {
+                Ok(orders) => expired.extend(orders),
+                Err(e) => tracing::warn!("Expiry of {} deferred: {}", symbol, e),
+            }
+        }
+        for market_id in self.ended_markets(now).await? {
+            match self
+                .shards
+                .execute(&market_id.to_string(), move |engine| engine.expire_market(market_id))
+                .await
+            {
+                Ok(orders) => expired.extend(orders),
+                Err(e) => tracing::warn!("Expiry of market {} deferred: {}", market_id, e),
+            }
+        }
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        let mut by_user: BTreeMap<Address, Vec<Uuid>> = BTreeMap::new();
+        for (_, entry) in &expired {
+            match entry.user_address.parse::<Address>() {
+                Ok(user) => by_user.entry(user).or_default().push(entry.id),
+                Err(e) => tracing::error!("Expired order {} not persisted: {}", entry.id, e),
+            }
+        }
+        for (user, order_ids) in &by_user {
+            if let Err(e) = self.expire_in_db(user, order_ids).await {
+                tracing::error!("Expiry of {} orders of {} not persisted: {}", order_ids.len(), user, e);
+            }
+        }
+        tracing::info!("Expired {} resting orders", expired.len());
+        Ok(expired.len())
+    }
+
+    /// Markets past their end time that still have resting orders
+    async fn ended_markets(&self, now: DateTime<Utc>) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT m.id
+            FROM markets m
+            WHERE m.end_time <= $1
+              AND EXISTS (
+                  SELECT 1 FROM orders o
+                  WHERE o.market_id = m.id AND o.status IN ('open', 'partially_filled')
+              )
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Mark the engine-expired orders expired and release buy reservations
+    async fn expire_in_db(&self, user_address: &Address, order_ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let expired: Vec<(String, Option<Decimal>, Decimal)> = sqlx::query_as(
+            r#"
+            UPDATE orders
+            SET status = 'expired', updated_at = NOW()
+            WHERE id = ANY($1) AND status IN ('pending', 'accepted', 'open', 'partially_filled')
+            RETURNING side::text, price, amount - filled_amount
+            "#,
+        )
+        .bind(order_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let released: Collateral = expired
+            .iter()
+            .filter(|(side, _, _)| side == "buy")
+            .filter_map(|(_, price, remaining)| price.map(|p| Collateral::notional(p, *remaining)))
+            .sum();
+        if released.value() > Decimal::ZERO {
+            sqlx::query(
+                "UPDATE balances SET available = available + $1, frozen = frozen - $1, updated_at = NOW()
+                 WHERE user_address = $2 AND token = $3",
+            )
+            .bind(released.value())
+            .bind(user_address)
+            .bind(&self.collateral_token)
+            .execute(&mut *tx)
+            .await?;
+            portfolio_margin::resync_account(&mut tx, user_address.as_str(), &self.collateral_token).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use rust_decimal_macros::dec;
+
+    use crate::models::market::ShareType;
+    use crate::models::{OrderSide, OrderType, TimeInForce};
+    use crate::services::matching::{OrderIntent, SelfTradePrevention};
+    use crate::services::order_gateway::OrderSource;
+    use crate::test_support::TestApp;
+
+    const USER: &str = "0x4444444444444444444444444444444444444444";
+
+    fn buy(market_id: Uuid, outcome_id: Uuid, expires_at: Option<DateTime<Utc>>) -> OrderIntent {
+        OrderIntent {
+            source: OrderSource::Api,
+            user_address: USER.to_string(),
+            market_id,
+            outcome_id,
+            share_type: ShareType::Yes,
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            stp: SelfTradePrevention::default(),
+            price: dec!(0.4),
+            amount: dec!(10),
+            expires_at,
+            signature: String::new(),
+        }
+    }
+
+    async fn status(app: &TestApp, order_id: Uuid) -> String {
+        sqlx::query_scalar("SELECT status::text FROM orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_expires_at_deadline_and_market_end() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.deposit(USER, dec!(100)).await;
+        let expiry = &app.state.order_expiry;
+
+        let deadline = app.clock.now() + Duration::seconds(60);
+        let gtd = app.state.order_flow.place(&buy(market_id, yes, Some(deadline))).await.unwrap();
+        let gtc = app.state.order_flow.place(&buy(market_id, yes, None)).await.unwrap();
+
+        app.clock.advance(Duration::seconds(59));
+        assert_eq!(expiry.sweep().await.unwrap(), 0);
+        app.clock.advance(Duration::seconds(1));
+        assert_eq!(expiry.sweep().await.unwrap(), 1);
+        assert_eq!(status(&app, gtd.order_id).await, "expired");
+        assert_eq!(status(&app, gtc.order_id).await, "open");
+
+        sqlx::query("UPDATE markets SET end_time = $1 WHERE id = $2")
+            .bind(app.clock.now())
+            .bind(market_id)
+            .execute(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(expiry.sweep().await.unwrap(), 1);
+        assert_eq!(status(&app, gtc.order_id).await, "expired");
+        assert_eq!(expiry.sweep().await.unwrap(), 0);
+
+        let (available, frozen): (Decimal, Decimal) =
+            sqlx::query_as("SELECT available, frozen FROM balances WHERE user_address = $1")
+                .bind(USER)
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!((available, frozen), (dec!(100), dec!(0)));
+    }
+}
@@ -0,0 +1,306 @@
+//! Internal Order Gateway
+//!
+//! In-process order entry for services that trade on the exchange
+//! themselves - the built-in market maker and the settlement service. Orders
+//! skip the HTTP stack (no signature, no JSON round trip) but not the
+//! exchange's rules: they run through the same order flow saga as user
+//! orders (see [`OrderFlowOrchestrator`]), without fill notifications.
+//!
+//! Every order carries an [`OrderSource`] that is stored on the order row,
+//! so internal orders, their fills and their fees can be told apart from
+//! user flow in history and reporting.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::market::ShareType;
+use crate::models::{Address, OrderSide, OrderStatus, OrderType, TimeInForce};
+use crate::services::matching::precision::Collateral;
+use crate::services::portfolio_margin;
+use crate::services::matching::{
+    EngineShards, MatchingError, OrderFlowError, OrderFlowOrchestrator, OrderIntent, SelfTradePrevention, TradeEvent,
+};
+use crate::services::write_batcher::WriteBatcher;
+
+/// Who placed an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSource {
+    /// Users, through the REST API
+    Api,
+    /// Built-in automated market maker
+    AutoMm,
+    /// Settlement service
+    Settlement,
+    /// Synthetic liquidity of sandbox markets
+    Sandbox,
+}
+
+impl OrderSource {
+    pub const ALL: [OrderSource; 4] = [
+        OrderSource::Api,
+        OrderSource::AutoMm,
+        OrderSource::Settlement,
+        OrderSource::Sandbox,
+    ];
+
+    /// Value stored in `orders.source`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderSource::Api => "api",
+            OrderSource::AutoMm => "auto_mm",
+            OrderSource::Settlement => "settlement",
+            OrderSource::Sandbox => "sandbox",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.as_str() == s)
+    }
+}
+
+/// An order placed through the gateway
+#[derive(Debug, Clone)]
+pub struct GatewayOrder {
+    pub source: OrderSource,
+    pub user_address: String,
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: ShareType,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub price: Decimal,
+    pub amount: Decimal,
+}
+
+/// Outcome of a placed order
+#[derive(Debug, Clone)]
+pub struct GatewayFill {
+    pub order_id: Uuid,
+    pub status: OrderStatus,
+    pub filled_amount: Decimal,
+    /// Fills of the order, already persisted (or queued for retry)
+    pub trades: Vec<TradeEvent>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OrderGatewayError {
+    #[error("Order not found")]
+    OrderNotFound,
+
+    #[error(transparent)]
+    Flow(#[from] OrderFlowError),
+
+    #[error(transparent)]
+    Engine(#[from] MatchingError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Order volume and fees attributed to one source
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OrderSourceStats {
+    pub source: String,
+    pub orders: i64,
+    pub filled_orders: i64,
+    /// Notional of fills the source's orders took part in
+    pub volume: Decimal,
+    /// Maker and taker fees charged to the source's orders
+    pub fees: Decimal,
+}
+
+/// Order fields needed to cancel it
+#[derive(Debug, sqlx::FromRow)]
+struct OpenOrderRow {
+    market_id: Uuid,
+    outcome_id: Uuid,
+    share_type: String,
+    side: String,
+    price: Decimal,
+    amount: Decimal,
+    filled_amount: Decimal,
+    status: String,
+}
+
+/// Places and cancels orders on behalf of internal services
+pub struct OrderGateway {
+    pool: PgPool,
+    shards: Arc<EngineShards>,
+    flow: OrderFlowOrchestrator,
+    collateral_token: String,
+}
+
+impl OrderGateway {
+    pub fn new(pool: PgPool, shards: Arc<EngineShards>, write_batcher: Arc<WriteBatcher>, collateral_token: String) -> Self {
+        Self {
+            flow: OrderFlowOrchestrator::new(pool.clone(), shards.clone(), write_batcher, collateral_token.clone()),
+            pool,
+            shards,
+            collateral_token,
+        }
+    }
+
+    /// Validate, fund and match `order`, then persist it with its fills
+    pub async fn place(&self, order: GatewayOrder) -> Result<GatewayFill, OrderGatewayError> {
+        let result = self.place_inner(&order).await;
+        crate::metrics::record_gateway_order(
+            order.source.as_str(),
+            match &result {
+                Ok(_) => "placed",
+                Err(_) => "rejected",
+            },
+        );
+        result
+    }
+
+    async fn place_inner(&self, order: &GatewayOrder) -> Result<GatewayFill, OrderGatewayError> {
+        let placed = self
+            .flow
+            .place(&OrderIntent {
+                source: order.source,
+                user_address: order.user_address.clone(),
+                market_id: order.market_id,
+                outcome_id: order.outcome_id,
+                share_type: order.share_type,
+                side: order.side,
+                order_type: order.order_type,
+                time_in_force: TimeInForce::Gtc,
+                post_only: false,
+                stp: SelfTradePrevention::default(),
+                price: order.price,
+                amount: order.amount,
+                expires_at: None,
+                signature: String::new(),
+            })
+            .await?;
+        Ok(GatewayFill {
+            order_id: placed.order_id,
+            status: placed.status,
+            filled_amount: placed.filled_amount,
+            trades: placed.trades,
+        })
+    }
+
+    /// Cancel an open order of `user_address`, releasing the collateral of
+    /// its unfilled part. Returns false if it was no longer open.
+    pub async fn cancel(
+        &self,
+        source: OrderSource,
+        user_address: &Address,
+        order_id: Uuid,
+    ) -> Result<bool, OrderGatewayError> {
+        let order: OpenOrderRow = sqlx::query_as(
+            r#"
+            SELECT market_id, outcome_id, share_type::text, side::text, price, amount, filled_amount, status::text
+            FROM orders
+            WHERE id = $1 AND user_address = $2
+            "#,
+        )
+        .bind(order_id)
+        .bind(user_address)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(OrderGatewayError::OrderNotFound)?;
+        if !matches!(order.status.as_str(), "open" | "partially_filled") {
+            return Ok(false);
+        }
+
+        let market_key = format!("{}:{}:{}", order.market_id, order.outcome_id, order.share_type);
+        let (symbol, user) = (market_key.clone(), user_address.as_str().to_owned());
+        let cancelled = self
+            .shards
+            .execute(&market_key, move |engine| engine.cancel_order(&symbol, order_id, &user))
+            .await
+            .and_then(|result| result)?;
+        if !cancelled {
+            return Ok(false);
+        }
+
+        sqlx::query("UPDATE orders SET status = 'cancelled'::order_status, updated_at = NOW() WHERE id = $1")
+            .bind(order_id)
+            .execute(&self.pool)
+            .await?;
+        if order.side == "buy" {
+            let remaining = order.amount - order.filled_amount;
+            self.unfreeze(user_address.as_str(), Collateral::notional(order.price, remaining).value())
+                .await?;
+            portfolio_margin::refresh_account(&self.pool, user_address, &self.collateral_token).await?;
+        }
+        crate::metrics::record_gateway_order(source.as_str(), "cancelled");
+        Ok(true)
+    }
+
+    /// Orders, fill volume and fees per source since `since`
+    pub async fn source_stats(&self, since: DateTime<Utc>) -> Result<Vec<OrderSourceStats>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            WITH placed AS (
+                SELECT source,
+                       COUNT(*) AS orders,
+                       COUNT(*) FILTER (WHERE filled_amount > 0) AS filled_orders
+                FROM orders
+                WHERE created_at >= $1
+                GROUP BY source
+            ),
+            fills AS (
+                SELECT o.source, t.price * t.amount AS notional, t.maker_fee AS fee
+                FROM trades t
+                JOIN orders o ON o.id = t.maker_order_id
+                WHERE t.created_at >= $1
+                UNION ALL
+                SELECT o.source, t.price * t.amount, t.taker_fee
+                FROM trades t
+                JOIN orders o ON o.id = t.taker_order_id
+                WHERE t.created_at >= $1
+            ),
+            filled AS (
+                SELECT source, SUM(notional) AS volume, SUM(fee) AS fees
+                FROM fills
+                GROUP BY source
+            )
+            SELECT COALESCE(p.source, f.source) AS source,
+                   COALESCE(p.orders, 0) AS orders,
+                   COALESCE(p.filled_orders, 0) AS filled_orders,
+                   COALESCE(f.volume, 0) AS volume,
+                   COALESCE(f.fees, 0) AS fees
+            FROM placed p
+            FULL JOIN filled f ON f.source = p.source
+            ORDER BY 1
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn unfreeze(&self, user_address: &str, amount: Decimal) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE balances SET available = available + $1, frozen = frozen - $1, updated_at = NOW()
+             WHERE user_address = $2 AND token = $3",
+        )
+        .bind(amount)
+        .bind(user_address)
+        .bind(&self.collateral_token)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_source_round_trips() {
+        for source in OrderSource::ALL {
+            assert_eq!(OrderSource::parse(source.as_str()), Some(source));
+        }
+        assert_eq!(OrderSource::parse("web"), None);
+    }
+}
@@ -0,0 +1,157 @@
+//! Orderbook Snapshot History
+//!
+//! Every minute the books held by the matching engine are written to
+//! `orderbook_snapshots` (only those that changed since their previous
+//! snapshot), so the book as it stood at any past time can be served for
+//! disputes and liquidity analytics. Snapshots older than the retention
+//! window are pruned.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use sqlx::types::Json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::market::ShareType;
+use crate::services::matching::{MatchingEngine, OrderbookSnapshot};
+
+/// How often books are snapshotted
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Price levels stored per side
+const SNAPSHOT_DEPTH: usize = 50;
+
+/// How long snapshots are kept
+const RETENTION_DAYS: i64 = 90;
+
+/// Prune once per this many snapshot passes (hourly)
+const PRUNE_EVERY: u32 = 60;
+
+type Levels = Vec<[String; 2]>;
+
+/// The book of one outcome/share type as it stood at `captured_at`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct HistoricalOrderbook {
+    pub bids: Json<Levels>,
+    pub asks: Json<Levels>,
+    pub last_price: Option<Decimal>,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Writes and reads orderbook snapshots
+pub struct OrderbookHistory {
+    pool: PgPool,
+    engine: Arc<MatchingEngine>,
+    /// Levels last written per book key
+    last_written: DashMap<String, (Levels, Levels)>,
+}
+
+impl OrderbookHistory {
+    pub fn new(pool: PgPool, engine: Arc<MatchingEngine>) -> Self {
+        Self {
+            pool,
+            engine,
+            last_written: DashMap::new(),
+        }
+    }
+
+    /// Spawn the snapshot loop
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            tracing::info!("Orderbook snapshot job started");
+            let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+            let mut passes: u32 = 0;
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.capture().await {
+                    tracing::warn!("Orderbook snapshot failed: {}", e);
+                }
+                passes = passes.wrapping_add(1);
+                if passes.is_multiple_of(PRUNE_EVERY) {
+                    match self.prune().await {
+                        Ok(n) if n > 0 => tracing::info!("Pruned {} orderbook snapshots", n),
+                        Ok(_) => {}
+                        Err(e) => tracing::warn!("Orderbook snapshot pruning failed: {}", e),
+                    }
+                }
+            }
+        });
+    }
+
+    /// Snapshot every book that changed since it was last written. Returns
+    /// the number of snapshots written.
+    pub async fn capture(&self) -> Result<usize, sqlx::Error> {
+        let mut written = 0;
+        for key in self.engine.orderbook_keys() {
+            let Some((market_id, outcome_id, share_type)) = OrderbookSnapshot::parse_market_key(&key) else {
+                continue;
+            };
+            let Ok(snapshot) = self.engine.get_orderbook(&key, SNAPSHOT_DEPTH) else {
+                continue;
+            };
+            let levels = (snapshot.bids, snapshot.asks);
+            if self.last_written.get(&key).is_some_and(|last| *last == levels) {
+                continue;
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO orderbook_snapshots (market_id, outcome_id, share_type, bids, asks, last_price, captured_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+            )
+            .bind(market_id)
+            .bind(outcome_id)
+            .bind(share_type.to_string())
+            .bind(Json(&levels.0))
+            .bind(Json(&levels.1))
+            .bind(snapshot.last_price)
+            .bind(DateTime::<Utc>::from_timestamp_millis(snapshot.timestamp).unwrap_or_else(Utc::now))
+            .execute(&self.pool)
+            .await?;
+
+            self.last_written.insert(key, levels);
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Delete snapshots past the retention window
+    pub async fn prune(&self) -> Result<u64, sqlx::Error> {
+        let cutoff = Utc::now() - ChronoDuration::days(RETENTION_DAYS);
+        let result = sqlx::query("DELETE FROM orderbook_snapshots WHERE captured_at < $1")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// The latest snapshot of the book at or before `at`
+    pub async fn at(
+        &self,
+        market_id: Uuid,
+        outcome_id: Uuid,
+        share_type: ShareType,
+        at: DateTime<Utc>,
+    ) -> Result<Option<HistoricalOrderbook>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT bids, asks, last_price, captured_at
+            FROM orderbook_snapshots
+            WHERE market_id = $1 AND outcome_id = $2 AND share_type = $3 AND captured_at <= $4
+            ORDER BY captured_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(market_id)
+        .bind(outcome_id)
+        .bind(share_type.to_string())
+        .bind(at)
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,791 @@
+//! Paper Trading
+//!
+//! A practice mode with virtual collateral. Paper orders match in their own
+//! matching engine under namespaced market keys (`paper/{market}:{outcome}:
+//! {share_type}`), so they never touch the real books, balances or trade
+//! stream. Before every paper order the book is reseeded with house
+//! liquidity at the real market's best bid / ask (or around the outcome
+//! probability when the real book is empty on a side), so fills track real
+//! prices.
+//!
+//! Fills are split into party changes exactly like real trades
+//! ([`holdings::party_changes`]) and applied to `paper_accounts`,
+//! `paper_positions` and `paper_orders`; the house side is not booked.
+//! Paper books live in memory only: open paper orders are cancelled and
+//! their reservations released on startup. Like the real engine, the paper
+//! engine changes its books only on its shard threads
+//! ([`EngineShards`]), never from request tasks.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::validation;
+use crate::models::market::ShareType;
+use crate::models::Address;
+use crate::services::matching::holdings;
+use crate::services::matching::{
+    EngineShards, MatchResult, MatchingEngine, OrderStatus, OrderType, OrderbookSnapshot, ShardConfig, Side,
+    TimeInForce, TradeEvent,
+};
+
+/// Namespace of paper orderbook keys
+pub const NAMESPACE: &str = "paper";
+/// Owner of the seeded house liquidity
+pub const HOUSE_ADDRESS: &str = "paper-house";
+
+/// Distance of house quotes from the reference price when the real book
+/// has no quote on a side
+const SEED_SPREAD: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+const MIN_PRICE: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+const MAX_PRICE: Decimal = Decimal::from_parts(99, 0, 0, false, 2);
+
+#[derive(Debug, Error)]
+pub enum PaperError {
+    #[error("Market or outcome not found")]
+    MarketNotFound,
+    #[error("Market is not open for trading")]
+    MarketNotActive,
+    #[error("Price must be between 0.01 and 0.99")]
+    InvalidPrice,
+    #[error("Amount must be positive")]
+    InvalidAmount,
+    #[error("Insufficient paper balance")]
+    InsufficientBalance,
+    #[error("Insufficient paper shares")]
+    InsufficientShares,
+    #[error("Paper order not found or no longer open")]
+    OrderNotFound,
+    #[error("Matching failed: {0}")]
+    Matching(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl PaperError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            PaperError::MarketNotFound => "MARKET_NOT_FOUND",
+            PaperError::MarketNotActive => "MARKET_NOT_ACTIVE",
+            PaperError::InvalidPrice => "INVALID_PRICE",
+            PaperError::InvalidAmount => "INVALID_AMOUNT",
+            PaperError::InsufficientBalance => "INSUFFICIENT_BALANCE",
+            PaperError::InsufficientShares => "INSUFFICIENT_SHARES",
+            PaperError::OrderNotFound => "ORDER_NOT_FOUND",
+            PaperError::Matching(_) => "MATCHING_ERROR",
+            PaperError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+/// A paper order to place
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct PaperOrderRequest {
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: ShareType,
+    pub side: Side,
+    pub order_type: OrderType,
+    /// Required for limit orders
+    #[validate(custom = "validation::price")]
+    pub price: Option<Decimal>,
+    #[validate(custom = "validation::positive")]
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PaperOrder {
+    pub id: Uuid,
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: String,
+    pub side: String,
+    pub order_type: String,
+    pub price: Option<Decimal>,
+    pub amount: Decimal,
+    pub filled_amount: Decimal,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const ORDER_COLUMNS: &str = "id, market_id, outcome_id, share_type::text AS share_type, side::text AS side, \
+                             order_type, price, amount, filled_amount, status, created_at, updated_at";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PaperPosition {
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: String,
+    pub market_question: String,
+    pub outcome_name: String,
+    pub amount: Decimal,
+    pub avg_cost: Decimal,
+    pub current_price: Decimal,
+    pub value: Decimal,
+    pub unrealized_pnl: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperAccount {
+    pub available: Decimal,
+    pub frozen: Decimal,
+    pub starting_balance: Decimal,
+    /// Collateral plus positions at current prices
+    pub equity: Decimal,
+    /// Equity minus the starting balance
+    pub pnl: Decimal,
+    pub reset_at: Option<DateTime<Utc>>,
+    pub positions: Vec<PaperPosition>,
+}
+
+/// House bid and ask for a paper book: the real best prices, falling back
+/// to `reference` ± the seed spread, kept inside the tradable range and
+/// uncrossed
+pub fn seed_prices(best_bid: Option<Decimal>, best_ask: Option<Decimal>, reference: Decimal) -> (Decimal, Decimal) {
+    let bid = best_bid.unwrap_or(reference - SEED_SPREAD).clamp(MIN_PRICE, MAX_PRICE - SEED_SPREAD);
+    let ask = best_ask.unwrap_or(reference + SEED_SPREAD).clamp(MIN_PRICE + SEED_SPREAD, MAX_PRICE);
+    if bid < ask {
+        (bid, ask)
+    } else {
+        (bid, (bid + SEED_SPREAD).min(MAX_PRICE))
+    }
+}
+
+/// Paper order entry: the simulated engine plus the house quotes resting in it
+pub struct PaperTrading {
+    shards: EngineShards,
+    /// House order ids per paper book; guarded with the submission lock
+    house_orders: Mutex<HashMap<String, Vec<Uuid>>>,
+    starting_balance: Decimal,
+    house_depth: Decimal,
+}
+
+impl PaperTrading {
+    pub fn new(starting_balance: Decimal, house_depth: Decimal, shards: &ShardConfig) -> Self {
+        Self {
+            shards: EngineShards::new(Arc::new(MatchingEngine::new()), shards),
+            house_orders: Mutex::new(HashMap::new()),
+            starting_balance,
+            house_depth,
+        }
+    }
+
+    /// Paper book key of an outcome side
+    pub fn book_key(market_id: Uuid, outcome_id: Uuid, share_type: ShareType) -> String {
+        OrderbookSnapshot::namespaced_key(NAMESPACE, &format!("{}:{}:{}", market_id, outcome_id, share_type))
+    }
+
+    /// Current paper book, house liquidity included
+    pub fn orderbook(&self, market_id: Uuid, outcome_id: Uuid, share_type: ShareType, depth: usize) -> Option<OrderbookSnapshot> {
+        self.shards
+            .engine()
+            .get_orderbook(&Self::book_key(market_id, outcome_id, share_type), depth)
+            .ok()
+    }
+
+    /// Place a paper order for `user_address`, matching it against the
+    /// reseeded paper book
+    pub async fn place(
+        &self,
+        pool: &PgPool,
+        real_engine: &MatchingEngine,
+        user_address: &Address,
+        req: &PaperOrderRequest,
+    ) -> Result<PaperOrder, PaperError> {
+        if req.amount <= Decimal::ZERO {
+            return Err(PaperError::InvalidAmount);
+        }
+        let limit = match req.order_type {
+            OrderType::Limit => match req.price {
+                Some(price) if (MIN_PRICE..=MAX_PRICE).contains(&price) => Some(price),
+                _ => return Err(PaperError::InvalidPrice),
+            },
+            OrderType::Market => None,
+        };
+
+        let market: Option<(String, Decimal)> = sqlx::query_as(
+            r#"
+            SELECT m.status::text, o.probability
+            FROM outcomes o
+            JOIN markets m ON m.id = o.market_id
+            WHERE o.id = $1 AND o.market_id = $2
+            "#,
+        )
+        .bind(req.outcome_id)
+        .bind(req.market_id)
+        .fetch_optional(pool)
+        .await?;
+        let (status, probability) = market.ok_or(PaperError::MarketNotFound)?;
+        if status != "active" {
+            return Err(PaperError::MarketNotActive);
+        }
+
+        // Serializes paper matching, so reseeding, matching and booking
+        // the fills of one order do not interleave with another's
+        let mut house_orders = self.house_orders.lock().await;
+
+        let order_id = Uuid::new_v4();
+        let mut conn = pool.acquire().await?;
+        self.ensure_account(&mut conn, user_address.as_str()).await?;
+        self.reserve(&mut conn, user_address.as_str(), order_id, req, limit).await?;
+
+        let key = Self::book_key(req.market_id, req.outcome_id, req.share_type);
+        let real_key = format!("{}:{}:{}", req.market_id, req.outcome_id, req.share_type);
+        let (best_bid, best_ask) = real_engine.get_best_prices(&real_key).unwrap_or((None, None));
+        let reference = match req.share_type {
+            ShareType::Yes => probability,
+            ShareType::No => Decimal::ONE - probability,
+        };
+        let (bid, ask) = seed_prices(best_bid, best_ask, reference);
+
+        // Reseed and match in one step on the book's shard
+        let stale = house_orders.remove(&key).unwrap_or_default();
+        let (symbol, user, house_depth) = (key.clone(), user_address.as_str().to_owned(), self.house_depth);
+        let (side, order_type, amount) = (req.side, req.order_type, req.amount);
+        let job_stale = stale.clone();
+        let matched = self
+            .shards
+            .execute(&key, move |engine| {
+                for stale in job_stale {
+                    let _ = engine.cancel_order(&symbol, stale, HOUSE_ADDRESS);
+                }
+                let mut trades = Vec::new();
+                let mut house = Vec::new();
+                for (side, price) in [(Side::Buy, bid), (Side::Sell, ask)] {
+                    let house_id = Uuid::new_v4();
+                    if let Ok(result) = engine.submit_order(
+                        house_id,
+                        &symbol,
+                        HOUSE_ADDRESS,
+                        side,
+                        OrderType::Limit,
+                        house_depth,
+                        Some(price),
+                        1,
+                        TimeInForce::GTC,
+                    ) {
+                        trades.extend(Self::trade_events(&symbol, HOUSE_ADDRESS, side, &result));
+                        house.push(house_id);
+                    }
+                }
+                let result =
+                    engine.submit_order(order_id, &symbol, &user, side, order_type, amount, limit, 1, TimeInForce::GTC);
+                (trades, house, result)
+            })
+            .await;
+        let (trades, result) = match matched {
+            Ok((trades, house, result)) => {
+                house_orders.insert(key.clone(), house);
+                (trades, result)
+            }
+            // Not run: the old house quotes still rest
+            Err(e) => {
+                house_orders.insert(key.clone(), stale);
+                (Vec::new(), Err(e))
+            }
+        };
+
+        let mut tx = pool.begin().await?;
+        for trade in &trades {
+            Self::apply_trade(&mut tx, trade).await?;
+        }
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                Self::release(&mut tx, order_id, "cancelled").await?;
+                tx.commit().await?;
+                return Err(PaperError::Matching(e.to_string()));
+            }
+        };
+        for trade in Self::trade_events(&key, user_address.as_str(), req.side, &result) {
+            Self::apply_trade(&mut tx, &trade).await?;
+        }
+        match result.status {
+            OrderStatus::Open | OrderStatus::PartiallyFilled if req.order_type == OrderType::Limit => {}
+            OrderStatus::Filled => Self::release(&mut tx, order_id, "filled").await?,
+            _ => Self::release(&mut tx, order_id, "cancelled").await?,
+        }
+        let order = Self::order(&mut tx, order_id).await?;
+        tx.commit().await?;
+        Ok(order)
+    }
+
+    /// Cancel an open paper order and release its reservation
+    pub async fn cancel(
+        &self,
+        pool: &PgPool,
+        user_address: &Address,
+        order_id: Uuid,
+    ) -> Result<PaperOrder, PaperError> {
+        let _guard = self.house_orders.lock().await;
+        let mut tx = pool.begin().await?;
+
+        let open: Option<(Uuid, Uuid, String)> = sqlx::query_as(
+            r#"
+            SELECT market_id, outcome_id, share_type::text
+            FROM paper_orders
+            WHERE id = $1 AND user_address = $2 AND status IN ('open', 'partially_filled')
+            FOR UPDATE
+            "#,
+        )
+        .bind(order_id)
+        .bind(user_address)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let (market_id, outcome_id, share_type) = open.ok_or(PaperError::OrderNotFound)?;
+
+        let share_type = share_type.parse().unwrap_or(ShareType::Yes);
+        self.cancel_in_engine(Self::book_key(market_id, outcome_id, share_type), order_id, user_address.as_str())
+            .await?;
+        Self::release(&mut tx, order_id, "cancelled").await?;
+        let order = Self::order(&mut tx, order_id).await?;
+        tx.commit().await?;
+        Ok(order)
+    }
+
+    /// Cancel every open paper order, drop all positions and restore the
+    /// starting balance
+    pub async fn reset(&self, pool: &PgPool, user_address: &Address) -> Result<(), PaperError> {
+        let _guard = self.house_orders.lock().await;
+        let mut tx = pool.begin().await?;
+
+        let open: Vec<(Uuid, Uuid, Uuid, String)> = sqlx::query_as(
+            r#"
+            UPDATE paper_orders
+            SET status = 'cancelled', reserved = 0, updated_at = NOW()
+            WHERE user_address = $1 AND status IN ('open', 'partially_filled')
+            RETURNING id, market_id, outcome_id, share_type::text
+            "#,
+        )
+        .bind(user_address)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (order_id, market_id, outcome_id, share_type) in open {
+            let share_type = share_type.parse().unwrap_or(ShareType::Yes);
+            self.cancel_in_engine(Self::book_key(market_id, outcome_id, share_type), order_id, user_address.as_str())
+                .await?;
+        }
+
+        sqlx::query("DELETE FROM paper_positions WHERE user_address = $1")
+            .bind(user_address)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO paper_accounts (user_address, available, starting_balance, reset_at)
+            VALUES ($1, $2, $2, NOW())
+            ON CONFLICT (user_address) DO UPDATE SET
+                available = $2, frozen = 0, starting_balance = $2, reset_at = NOW(), updated_at = NOW()
+            "#,
+        )
+        .bind(user_address)
+        .bind(self.starting_balance)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Paper balances and positions valued at current outcome prices
+    pub async fn account(&self, pool: &PgPool, user_address: &Address) -> Result<PaperAccount, PaperError> {
+        let mut conn = pool.acquire().await?;
+        self.ensure_account(&mut conn, user_address.as_str()).await?;
+
+        let (available, frozen, starting_balance, reset_at): (Decimal, Decimal, Decimal, Option<DateTime<Utc>>) =
+            sqlx::query_as(
+                "SELECT available, frozen, starting_balance, reset_at FROM paper_accounts WHERE user_address = $1",
+            )
+            .bind(user_address)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        let positions: Vec<PaperPosition> = sqlx::query_as(
+            r#"
+            SELECT p.market_id, p.outcome_id, p.share_type::text AS share_type,
+                   m.question AS market_question, o.name AS outcome_name,
+                   p.amount, p.avg_cost, q.price AS current_price,
+                   p.amount * q.price AS value,
+                   p.amount * (q.price - p.avg_cost) AS unrealized_pnl
+            FROM paper_positions p
+            JOIN markets m ON m.id = p.market_id
+            JOIN outcomes o ON o.id = p.outcome_id
+            CROSS JOIN LATERAL (
+                SELECT CASE WHEN p.share_type = 'yes' THEN o.probability ELSE 1 - o.probability END AS price
+            ) q
+            WHERE p.user_address = $1 AND p.amount > 0
+            ORDER BY p.updated_at DESC
+            "#,
+        )
+        .bind(user_address)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let equity = available + frozen + positions.iter().map(|p| p.value).sum::<Decimal>();
+        Ok(PaperAccount {
+            available,
+            frozen,
+            starting_balance,
+            equity,
+            pnl: equity - starting_balance,
+            reset_at,
+            positions,
+        })
+    }
+
+    /// Paper orders of `user_address`, newest first
+    pub async fn orders(
+        &self,
+        pool: &PgPool,
+        user_address: &Address,
+        open_only: bool,
+        limit: i64,
+    ) -> Result<Vec<PaperOrder>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {}
+            FROM paper_orders
+            WHERE user_address = $1 AND (NOT $2 OR status IN ('open', 'partially_filled'))
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#,
+            ORDER_COLUMNS
+        ))
+        .bind(user_address)
+        .bind(open_only)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Cancel paper orders left open by a previous process (their books
+    /// were in memory) and release what they reserved
+    pub async fn expire_open_orders(pool: &PgPool) -> Result<u64, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let released: Vec<(String, Decimal)> = sqlx::query_as(
+            r#"
+            WITH open AS (
+                SELECT id, user_address, reserved
+                FROM paper_orders
+                WHERE status IN ('open', 'partially_filled')
+                FOR UPDATE
+            ), cancelled AS (
+                UPDATE paper_orders p
+                SET status = 'cancelled', reserved = 0, updated_at = NOW()
+                FROM open
+                WHERE p.id = open.id
+            )
+            SELECT user_address, SUM(reserved) FROM open GROUP BY user_address
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for (user_address, reserved) in &released {
+            sqlx::query(
+                r#"
+                UPDATE paper_accounts
+                SET available = available + $2, frozen = frozen - $2, updated_at = NOW()
+                WHERE user_address = $1
+                "#,
+            )
+            .bind(user_address)
+            .bind(reserved)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(released.len() as u64)
+    }
+
+    // ------------------------------------------------------------------------
+    // Booking
+    // ------------------------------------------------------------------------
+
+    async fn ensure_account(&self, conn: &mut PgConnection, user_address: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO paper_accounts (user_address, available, starting_balance)
+            VALUES ($1, $2, $2)
+            ON CONFLICT (user_address) DO NOTHING
+            "#,
+        )
+        .bind(user_address)
+        .bind(self.starting_balance)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Reserve collateral (buys) or check free shares (sells), then record
+    /// the order
+    async fn reserve(
+        &self,
+        conn: &mut PgConnection,
+        user_address: &str,
+        order_id: Uuid,
+        req: &PaperOrderRequest,
+        limit: Option<Decimal>,
+    ) -> Result<(), PaperError> {
+        let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+
+        let available: Decimal =
+            sqlx::query_scalar("SELECT available FROM paper_accounts WHERE user_address = $1 FOR UPDATE")
+                .bind(user_address)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let reserved = match req.side {
+            Side::Buy => {
+                // Market buys reserve at the highest possible price
+                let reserved = limit.unwrap_or(Decimal::ONE) * req.amount;
+                if reserved > available {
+                    return Err(PaperError::InsufficientBalance);
+                }
+                sqlx::query(
+                    r#"
+                    UPDATE paper_accounts
+                    SET available = available - $2, frozen = frozen + $2, updated_at = NOW()
+                    WHERE user_address = $1
+                    "#,
+                )
+                .bind(user_address)
+                .bind(reserved)
+                .execute(&mut *tx)
+                .await?;
+                reserved
+            }
+            Side::Sell => {
+                let free: Decimal = sqlx::query_scalar(
+                    r#"
+                    SELECT COALESCE((
+                        SELECT amount FROM paper_positions
+                        WHERE user_address = $1 AND outcome_id = $2 AND share_type = $3::share_type
+                    ), 0) - COALESCE((
+                        SELECT SUM(amount - filled_amount) FROM paper_orders
+                        WHERE user_address = $1 AND outcome_id = $2 AND share_type = $3::share_type
+                          AND side = 'sell' AND status IN ('open', 'partially_filled')
+                    ), 0)
+                    "#,
+                )
+                .bind(user_address)
+                .bind(req.outcome_id)
+                .bind(req.share_type.to_string())
+                .fetch_one(&mut *tx)
+                .await?;
+                if req.amount > free {
+                    return Err(PaperError::InsufficientShares);
+                }
+                Decimal::ZERO
+            }
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO paper_orders (
+                id, user_address, market_id, outcome_id, share_type, side, order_type, price, amount, reserved
+            )
+            VALUES ($1, $2, $3, $4, $5::share_type, $6::order_side, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(order_id)
+        .bind(user_address)
+        .bind(req.market_id)
+        .bind(req.outcome_id)
+        .bind(req.share_type.to_string())
+        .bind(req.side.to_string())
+        .bind(match req.order_type {
+            OrderType::Limit => "limit",
+            OrderType::Market => "market",
+        })
+        .bind(limit)
+        .bind(req.amount)
+        .bind(reserved)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Take a paper order off its book, on the book's shard
+    async fn cancel_in_engine(&self, key: String, order_id: Uuid, user_address: &str) -> Result<(), PaperError> {
+        let user = user_address.to_string();
+        self.shards
+            .execute(&key.clone(), move |engine| engine.cancel_order(&key, order_id, &user))
+            .await
+            .map(|_| ())
+            .map_err(|e| PaperError::Matching(e.to_string()))
+    }
+
+    fn trade_events(key: &str, taker_address: &str, side: Side, result: &MatchResult) -> Vec<TradeEvent> {
+        result
+            .trades
+            .iter()
+            .map(|execution| TradeEvent::from_execution(execution, key.to_string(), taker_address.to_string(), side))
+            .collect()
+    }
+
+    /// Book both parties of a paper trade, skipping the house
+    async fn apply_trade(conn: &mut PgConnection, trade: &TradeEvent) -> Result<(), sqlx::Error> {
+        let taker_limit = Self::reserve_price(conn, trade.taker_order_id).await?;
+        let maker_limit = Self::reserve_price(conn, trade.maker_order_id).await?;
+        let parties = holdings::party_changes(
+            trade,
+            taker_limit.unwrap_or(trade.price),
+            maker_limit.unwrap_or(trade.price),
+        );
+
+        for party in parties.iter().filter(|p| p.user_address != HOUSE_ADDRESS) {
+            sqlx::query(
+                r#"
+                INSERT INTO paper_positions (user_address, market_id, outcome_id, share_type, amount, avg_cost)
+                VALUES ($1, $2, $3, $4::share_type, $5, $6)
+                ON CONFLICT (user_address, outcome_id, share_type) DO UPDATE SET
+                    amount = paper_positions.amount + $5,
+                    avg_cost = CASE
+                        WHEN $5 <= 0 THEN paper_positions.avg_cost
+                        WHEN paper_positions.amount <= 0 THEN $6
+                        ELSE (paper_positions.avg_cost * paper_positions.amount + $6 * $5)
+                             / (paper_positions.amount + $5)
+                    END,
+                    updated_at = NOW()
+                "#,
+            )
+            .bind(party.user_address)
+            .bind(trade.market_id)
+            .bind(trade.outcome_id)
+            .bind(party.share_type.to_string())
+            .bind(party.shares)
+            .bind(party.price)
+            .execute(&mut *conn)
+            .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE paper_accounts
+                SET available = available + $2, frozen = frozen - $3, updated_at = NOW()
+                WHERE user_address = $1
+                "#,
+            )
+            .bind(party.user_address)
+            .bind(party.available_credit)
+            .bind(party.frozen_release)
+            .execute(&mut *conn)
+            .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE paper_orders
+                SET filled_amount = filled_amount + $2,
+                    reserved = reserved - $3,
+                    status = CASE WHEN filled_amount + $2 >= amount THEN 'filled' ELSE 'partially_filled' END,
+                    updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(party.order_id)
+            .bind(trade.amount)
+            .bind(party.frozen_release)
+            .execute(&mut *conn)
+            .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO paper_fills (
+                    trade_id, order_id, user_address, market_id, outcome_id, share_type, change_type, amount, price
+                )
+                VALUES ($1, $2, $3, $4, $5, $6::share_type, $7, $8, $9)
+                "#,
+            )
+            .bind(trade.trade_id)
+            .bind(party.order_id)
+            .bind(party.user_address)
+            .bind(trade.market_id)
+            .bind(trade.outcome_id)
+            .bind(party.share_type.to_string())
+            .bind(party.change_type)
+            .bind(party.shares)
+            .bind(party.price)
+            .execute(&mut *conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Price a paper buy reserved collateral at (1 for market buys); None
+    /// for house orders
+    async fn reserve_price(conn: &mut PgConnection, order_id: Uuid) -> Result<Option<Decimal>, sqlx::Error> {
+        let row: Option<(Option<Decimal>,)> = sqlx::query_as("SELECT price FROM paper_orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_optional(conn)
+            .await?;
+        Ok(row.map(|(price,)| price.unwrap_or(Decimal::ONE)))
+    }
+
+    /// Return what an order still reserves and close it with `status`
+    async fn release(conn: &mut PgConnection, order_id: Uuid, status: &str) -> Result<(), sqlx::Error> {
+        let released: Option<(String, Decimal)> = sqlx::query_as(
+            r#"
+            UPDATE paper_orders p
+            SET status = $2, reserved = 0, updated_at = NOW()
+            FROM (SELECT id, reserved FROM paper_orders WHERE id = $1 FOR UPDATE) before
+            WHERE p.id = before.id
+            RETURNING p.user_address, before.reserved
+            "#,
+        )
+        .bind(order_id)
+        .bind(status)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if let Some((user_address, reserved)) = released.filter(|(_, reserved)| !reserved.is_zero()) {
+            sqlx::query(
+                r#"
+                UPDATE paper_accounts
+                SET available = available + $2, frozen = frozen - $2, updated_at = NOW()
+                WHERE user_address = $1
+                "#,
+            )
+            .bind(user_address)
+            .bind(reserved)
+            .execute(conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn order(conn: &mut PgConnection, order_id: Uuid) -> Result<PaperOrder, sqlx::Error> {
+        sqlx::query_as(&format!("SELECT {} FROM paper_orders WHERE id = $1", ORDER_COLUMNS))
+            .bind(order_id)
+            .fetch_one(conn)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_seed_prices() {
+        // Real quotes are used as they are
+        assert_eq!(seed_prices(Some(dec!(0.42)), Some(dec!(0.45)), dec!(0.5)), (dec!(0.42), dec!(0.45)));
+        // Missing sides fall back around the reference price
+        assert_eq!(seed_prices(None, None, dec!(0.5)), (dec!(0.49), dec!(0.51)));
+        assert_eq!(seed_prices(Some(dec!(0.30)), None, dec!(0.5)), (dec!(0.30), dec!(0.51)));
+        // Extreme references stay tradable and uncrossed
+        assert_eq!(seed_prices(None, None, dec!(0.999)), (dec!(0.98), dec!(0.99)));
+        assert_eq!(seed_prices(None, None, dec!(0.001)), (dec!(0.01), dec!(0.02)));
+        assert_eq!(seed_prices(None, Some(dec!(0.40)), dec!(0.6)), (dec!(0.59), dec!(0.60)));
+    }
+}
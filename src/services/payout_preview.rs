@@ -0,0 +1,257 @@
+//! Resolution Payout Preview
+//!
+//! What a user would be paid for a market under each way it can resolve,
+//! before it does: every outcome winning in turn, and the market being
+//! cancelled. Shares are paid by the rule settlement applies (see
+//! [`pays_on_resolution`]); a cancelled market refunds them at cost.
+//!
+//! Only shares the user is sure to still hold count: those committed to
+//! resting sell orders may be sold before resolution and are left out.
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::models::market::{DbShareType, ShareType};
+use crate::services::matching::precision::Collateral;
+use crate::services::settlement::pays_on_resolution;
+
+/// An outcome the market can resolve to
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OutcomeRow {
+    pub outcome_id: Uuid,
+    pub name: String,
+    #[sqlx(try_from = "DbShareType")]
+    pub share_type: ShareType,
+}
+
+/// One of the user's holdings in the market
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct HoldingRow {
+    pub outcome_id: Uuid,
+    #[sqlx(try_from = "DbShareType")]
+    pub share_type: ShareType,
+    pub amount: Decimal,
+    pub avg_cost: Decimal,
+    /// Unfilled size of the user's resting sells of these shares
+    pub pending_sell: Decimal,
+}
+
+impl HoldingRow {
+    /// Shares held whatever the resting sells do
+    fn counted(&self) -> Decimal {
+        (self.amount - self.pending_sell).max(Decimal::ZERO)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreviewHolding {
+    pub outcome_id: Uuid,
+    pub share_type: ShareType,
+    pub held: Decimal,
+    pub pending_sell: Decimal,
+    /// Held less pending sells: the shares the preview pays out
+    pub counted: Decimal,
+    pub avg_cost: Decimal,
+}
+
+/// Payout if the market resolves to one outcome
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PayoutScenario {
+    pub outcome_id: Uuid,
+    pub name: String,
+    pub share_type: ShareType,
+    pub payout: Decimal,
+    /// Payout less the cost of the counted shares
+    pub profit: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PayoutPreview {
+    pub market_id: Uuid,
+    pub market_status: String,
+    pub holdings: Vec<PreviewHolding>,
+    /// Cost of the counted shares
+    pub cost_basis: Decimal,
+    /// One per outcome, in the order the market lists them
+    pub scenarios: Vec<PayoutScenario>,
+    /// Refund if the market is cancelled instead
+    pub if_cancelled: Decimal,
+}
+
+/// Errors loading a preview
+#[derive(Debug, thiserror::Error)]
+pub enum PreviewError {
+    #[error("Market not found")]
+    MarketNotFound,
+
+    /// Resolved or cancelled: the payout is final, see the settlement status
+    #[error("Market is already {0}")]
+    MarketFinal(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Preview of `user_address`'s payouts in `market_id`
+pub async fn load(pool: &PgPool, market_id: Uuid, user_address: &Address) -> Result<PayoutPreview, PreviewError> {
+    let status: String = sqlx::query_scalar("SELECT status::text FROM markets WHERE id = $1")
+        .bind(market_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(PreviewError::MarketNotFound)?;
+    if status == "resolved" || status == "cancelled" {
+        return Err(PreviewError::MarketFinal(status));
+    }
+
+    let outcomes: Vec<OutcomeRow> = sqlx::query_as(
+        r#"
+        SELECT id AS outcome_id, name, share_type
+        FROM outcomes
+        WHERE market_id = $1
+        ORDER BY share_type
+        "#,
+    )
+    .bind(market_id)
+    .fetch_all(pool)
+    .await?;
+
+    let holdings: Vec<HoldingRow> = sqlx::query_as(
+        r#"
+        SELECT s.outcome_id, s.share_type, s.amount, s.avg_cost,
+               COALESCE((
+                   SELECT SUM(o.amount - o.filled_amount) FROM orders o
+                   WHERE o.user_address = s.user_address AND o.outcome_id = s.outcome_id
+                     AND o.share_type = s.share_type AND o.side = 'sell'
+                     AND o.status IN ('pending', 'open', 'partially_filled')
+               ), 0) AS pending_sell
+        FROM shares s
+        WHERE s.user_address = $1 AND s.market_id = $2 AND s.amount > 0
+        ORDER BY s.share_type, s.outcome_id
+        "#,
+    )
+    .bind(user_address)
+    .bind(market_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(build_preview(market_id, status, &outcomes, &holdings))
+}
+
+/// Payouts of `holdings` under each of `outcomes` and under cancellation
+pub fn build_preview(
+    market_id: Uuid,
+    market_status: String,
+    outcomes: &[OutcomeRow],
+    holdings: &[HoldingRow],
+) -> PayoutPreview {
+    let cost_basis = holdings
+        .iter()
+        .map(|h| Collateral::notional(h.avg_cost, h.counted()))
+        .sum::<Collateral>()
+        .value();
+
+    let scenarios = outcomes
+        .iter()
+        .map(|outcome| {
+            let payout = holdings
+                .iter()
+                .filter(|h| pays_on_resolution(h.outcome_id, h.share_type, outcome.outcome_id))
+                .map(|h| Collateral::new(h.counted()))
+                .sum::<Collateral>()
+                .value();
+            PayoutScenario {
+                outcome_id: outcome.outcome_id,
+                name: outcome.name.clone(),
+                share_type: outcome.share_type,
+                payout,
+                profit: payout - cost_basis,
+            }
+        })
+        .collect();
+
+    PayoutPreview {
+        market_id,
+        market_status,
+        holdings: holdings
+            .iter()
+            .map(|h| PreviewHolding {
+                outcome_id: h.outcome_id,
+                share_type: h.share_type,
+                held: h.amount,
+                pending_sell: h.pending_sell,
+                counted: h.counted(),
+                avg_cost: h.avg_cost,
+            })
+            .collect(),
+        cost_basis,
+        scenarios,
+        // Settlement refunds each position at its average cost
+        if_cancelled: cost_basis,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn binary() -> Vec<OutcomeRow> {
+        [ShareType::Yes, ShareType::No]
+            .into_iter()
+            .map(|share_type| OutcomeRow {
+                outcome_id: Uuid::new_v4(),
+                name: share_type.to_string(),
+                share_type,
+            })
+            .collect()
+    }
+
+    fn holding(outcome_id: Uuid, share_type: ShareType, amount: Decimal, avg_cost: Decimal) -> HoldingRow {
+        HoldingRow {
+            outcome_id,
+            share_type,
+            amount,
+            avg_cost,
+            pending_sell: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_each_outcome_pays_its_winning_shares() {
+        let outcomes = binary();
+        let yes = outcomes[0].outcome_id;
+        // 100 Yes at 0.3 and 40 No at 0.6 on the Yes outcome
+        let holdings = vec![
+            holding(yes, ShareType::Yes, dec!(100), dec!(0.3)),
+            holding(yes, ShareType::No, dec!(40), dec!(0.6)),
+        ];
+        let preview = build_preview(Uuid::new_v4(), "active".to_string(), &outcomes, &holdings);
+
+        assert_eq!(preview.cost_basis, dec!(54));
+        assert_eq!(preview.scenarios[0].payout, dec!(100));
+        assert_eq!(preview.scenarios[0].profit, dec!(46));
+        assert_eq!(preview.scenarios[1].payout, dec!(40));
+        assert_eq!(preview.scenarios[1].profit, dec!(-14));
+        assert_eq!(preview.if_cancelled, dec!(54));
+    }
+
+    #[test]
+    fn test_shares_on_resting_sells_are_not_counted() {
+        let outcomes = binary();
+        let yes = outcomes[0].outcome_id;
+        let mut selling = holding(yes, ShareType::Yes, dec!(100), dec!(0.5));
+        selling.pending_sell = dec!(30);
+        let mut oversold = holding(yes, ShareType::No, dec!(10), dec!(0.5));
+        oversold.pending_sell = dec!(25);
+        let preview = build_preview(Uuid::new_v4(), "active".to_string(), &outcomes, &[selling, oversold]);
+
+        assert_eq!(preview.holdings[0].counted, dec!(70));
+        assert_eq!(preview.holdings[1].counted, dec!(0));
+        assert_eq!(preview.scenarios[0].payout, dec!(70));
+        assert_eq!(preview.scenarios[1].payout, dec!(0));
+        assert_eq!(preview.cost_basis, dec!(35));
+    }
+}
@@ -0,0 +1,325 @@
+//! Personal Data Export and Account Closure
+//!
+//! Account holders can download everything stored about them as a zip of
+//! JSON files, one per dataset (profile, balances, holdings, orders, trades,
+//! ledger, deposits, withdrawals, transfers, logins, preferences and
+//! notifications), with a `manifest.json` listing the record counts.
+//!
+//! Closing an account takes two steps. Asking for closure cancels the open
+//! orders and from then on the account can only read, cancel and withdraw.
+//! Once it holds no balance, shares or open orders the closure completes:
+//! the profile (username, avatar, email), preferences, queued notifications
+//! and the IPs and user agents of its logins are erased, its sessions are
+//! revoked and it can no longer sign in. Orders, trades, the ledger,
+//! deposits and withdrawals are kept under the wallet address as financial
+//! records.
+
+use std::io::{Cursor, Write};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use thiserror::Error;
+
+use crate::models::Address;
+
+/// Datasets of an export: file name, and a query returning one JSON
+/// object per record of the user `$1`
+const DATASETS: &[(&str, &str)] = &[
+    ("profile", "SELECT to_jsonb(u) - 'nonce' FROM users u WHERE address = $1"),
+    ("balances", "SELECT to_jsonb(b) FROM balances b WHERE user_address = $1 ORDER BY token"),
+    ("shares", "SELECT to_jsonb(s) FROM shares s WHERE user_address = $1 ORDER BY created_at"),
+    ("orders", "SELECT to_jsonb(o) FROM orders o WHERE user_address = $1 ORDER BY created_at"),
+    ("trigger_orders", "SELECT to_jsonb(t) FROM trigger_orders t WHERE user_address = $1 ORDER BY created_at"),
+    // Counterparties are not the holder's data
+    (
+        "trades",
+        "SELECT to_jsonb(t) - 'maker_address' - 'taker_address'
+                || jsonb_build_object('liquidity', CASE WHEN t.maker_address = $1 THEN 'maker' ELSE 'taker' END)
+         FROM trades t WHERE maker_address = $1 OR taker_address = $1 ORDER BY created_at",
+    ),
+    ("ledger", "SELECT to_jsonb(l) FROM balance_ledger l WHERE user_address = $1 ORDER BY created_at"),
+    ("deposits", "SELECT to_jsonb(d) FROM deposits d WHERE user_address = $1 ORDER BY created_at"),
+    ("withdrawals", "SELECT to_jsonb(w) FROM withdrawals w WHERE user_address = $1 ORDER BY created_at"),
+    (
+        "transfers",
+        "SELECT to_jsonb(t) FROM internal_transfers t WHERE from_address = $1 OR to_address = $1 ORDER BY created_at",
+    ),
+    ("sessions", "SELECT to_jsonb(s) FROM user_sessions s WHERE user_address = $1 ORDER BY issued_at"),
+    ("preferences", "SELECT to_jsonb(p) FROM user_preferences p WHERE user_address = $1"),
+    ("notification_preferences", "SELECT to_jsonb(p) FROM notification_preferences p WHERE user_address = $1"),
+    ("notifications", "SELECT to_jsonb(n) FROM notification_queue n WHERE user_address = $1 ORDER BY created_at"),
+];
+
+#[derive(Debug, Error)]
+pub enum PersonalDataError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Account is closed")]
+    AccountClosed,
+    #[error("Archive error: {0}")]
+    Archive(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl PersonalDataError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            PersonalDataError::UserNotFound => "USER_NOT_FOUND",
+            PersonalDataError::AccountClosed => "ACCOUNT_CLOSED",
+            PersonalDataError::Archive(_) => "EXPORT_FAILED",
+            PersonalDataError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+/// Where an account is in its closure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClosureState {
+    #[default]
+    Open,
+    /// Closure requested; waiting for the account to be emptied
+    Closing,
+    Closed,
+}
+
+/// A balance still held by a closing account
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RemainingBalance {
+    pub token: String,
+    pub available: Decimal,
+    pub frozen: Decimal,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClosureStatus {
+    pub state: ClosureState,
+    pub requested_at: Option<DateTime<Utc>>,
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Balances that must be withdrawn before the account can close
+    pub balances: Vec<RemainingBalance>,
+    /// Markets the account still holds shares in
+    pub open_positions: i64,
+    pub open_orders: i64,
+}
+
+impl ClosureStatus {
+    /// Whether nothing keeps the account from closing
+    pub fn is_empty(&self) -> bool {
+        self.balances.is_empty() && self.open_positions == 0 && self.open_orders == 0
+    }
+}
+
+/// Everything stored about `address`, as a zip archive
+pub async fn export(pool: &PgPool, address: &Address) -> Result<Vec<u8>, PersonalDataError> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE address = $1)")
+        .bind(address)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(PersonalDataError::UserNotFound);
+    }
+
+    let archive_error = |e: zip::result::ZipError| PersonalDataError::Archive(e.to_string());
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let mut counts = serde_json::Map::new();
+    for (name, query) in DATASETS {
+        let records: Vec<serde_json::Value> = sqlx::query_scalar(query).bind(address).fetch_all(pool).await?;
+        counts.insert(name.to_string(), records.len().into());
+        archive.start_file(format!("{}.json", name), options).map_err(archive_error)?;
+        write_json(&mut archive, &records)?;
+    }
+
+    let manifest = serde_json::json!({
+        "address": address,
+        "generated_at": Utc::now(),
+        "records": counts,
+    });
+    archive.start_file("manifest.json", options).map_err(archive_error)?;
+    write_json(&mut archive, &manifest)?;
+    Ok(archive.finish().map_err(archive_error)?.into_inner())
+}
+
+fn write_json<W: Write, T: Serialize>(writer: &mut W, value: &T) -> Result<(), PersonalDataError> {
+    serde_json::to_writer_pretty(writer, value).map_err(|e| PersonalDataError::Archive(e.to_string()))
+}
+
+/// Where the closure of `address` stands and what still blocks it
+pub async fn status(pool: &PgPool, address: &Address) -> Result<ClosureStatus, PersonalDataError> {
+    let (requested_at, closed_at): (Option<DateTime<Utc>>, Option<DateTime<Utc>>) =
+        sqlx::query_as("SELECT closure_requested_at, closed_at FROM users WHERE address = $1")
+            .bind(address)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(PersonalDataError::UserNotFound)?;
+    let state = match (requested_at, closed_at) {
+        (_, Some(_)) => ClosureState::Closed,
+        (Some(_), None) => ClosureState::Closing,
+        (None, None) => ClosureState::Open,
+    };
+    let mut conn = pool.acquire().await?;
+    let status = ClosureStatus { state, requested_at, closed_at, ..ClosureStatus::default() };
+    holdings(&mut conn, status, address.as_str()).await
+}
+
+/// `status` with what `address` still holds filled in
+async fn holdings(
+    conn: &mut PgConnection,
+    status: ClosureStatus,
+    address: &str,
+) -> Result<ClosureStatus, PersonalDataError> {
+    let balances = sqlx::query_as(
+        "SELECT token, available, frozen FROM balances WHERE user_address = $1 AND (available <> 0 OR frozen <> 0) ORDER BY token",
+    )
+    .bind(address)
+    .fetch_all(&mut *conn)
+    .await?;
+    let (open_positions, open_orders): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT (SELECT COUNT(DISTINCT market_id) FROM shares WHERE user_address = $1 AND amount > 0),
+               (SELECT COUNT(*) FROM orders WHERE user_address = $1 AND status IN ('pending', 'accepted', 'open', 'partially_filled'))
+        "#,
+    )
+    .bind(address)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(ClosureStatus {
+        balances,
+        open_positions,
+        open_orders,
+        ..status
+    })
+}
+
+/// Start closing `address`; returns when closure was first requested
+pub async fn request_closure(pool: &PgPool, address: &Address) -> Result<DateTime<Utc>, PersonalDataError> {
+    let requested_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        r#"
+        UPDATE users SET closure_requested_at = COALESCE(closure_requested_at, NOW()), updated_at = NOW()
+        WHERE address = $1 AND closed_at IS NULL
+        RETURNING closure_requested_at
+        "#,
+    )
+    .bind(address)
+    .fetch_optional(pool)
+    .await?;
+    match requested_at {
+        Some(at) => Ok(at),
+        None => match status(pool, address).await?.state {
+            ClosureState::Closed => Err(PersonalDataError::AccountClosed),
+            _ => Err(PersonalDataError::UserNotFound),
+        },
+    }
+}
+
+/// Close `address` and erase its personal data if it is closing and holds
+/// nothing; returns whether it is closed
+pub async fn complete_closure(pool: &PgPool, address: &Address) -> Result<bool, PersonalDataError> {
+    let mut tx = pool.begin().await?;
+    // Lock the account so nothing is credited between the check and the erasure
+    let state: Option<(bool, bool)> = sqlx::query_as(
+        "SELECT closure_requested_at IS NOT NULL, closed_at IS NOT NULL FROM users WHERE address = $1 FOR UPDATE",
+    )
+    .bind(address)
+    .fetch_optional(&mut *tx)
+    .await?;
+    match state {
+        None => return Err(PersonalDataError::UserNotFound),
+        Some((_, true)) => return Ok(true),
+        Some((false, false)) => return Ok(false),
+        Some((true, false)) => {}
+    }
+    sqlx::query("SELECT 1 FROM balances WHERE user_address = $1 FOR UPDATE")
+        .bind(address)
+        .execute(&mut *tx)
+        .await?;
+    if !holdings(&mut tx, ClosureStatus::default(), address.as_str()).await?.is_empty() {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET username = NULL, avatar_url = NULL, email = NULL, leaderboard_opt_in = FALSE,
+            closed_at = NOW(), updated_at = NOW()
+        WHERE address = $1
+        "#,
+    )
+    .bind(address)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE user_sessions SET ip_address = NULL, user_agent = NULL, revoked_at = COALESCE(revoked_at, NOW())
+         WHERE user_address = $1",
+    )
+    .bind(address)
+    .execute(&mut *tx)
+    .await?;
+    for table in ["user_preferences", "notification_preferences", "notification_queue"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE user_address = $1", table))
+            .bind(address)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    tracing::info!("Account {} closed and its personal data erased", address);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::io::Read;
+
+    use crate::test_support::TestApp;
+
+    #[tokio::test]
+    async fn test_export_and_close_account() {
+        let app = TestApp::builder().build().await;
+        let pool = &app.db.pool;
+        let address: Address = format!("0x{}00000000", uuid::Uuid::new_v4().simple()).parse().unwrap();
+        sqlx::query("INSERT INTO users (address, username, email) VALUES ($1, 'alice', 'alice@example.com')")
+            .bind(&address)
+            .execute(pool)
+            .await
+            .unwrap();
+        app.deposit(address.as_str(), dec!(25)).await;
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(export(pool, &address).await.unwrap())).unwrap();
+        let mut profile = String::new();
+        archive.by_name("profile.json").unwrap().read_to_string(&mut profile).unwrap();
+        let profile: serde_json::Value = serde_json::from_str(&profile).unwrap();
+        assert_eq!(profile[0]["email"], "alice@example.com");
+        assert!(profile[0].get("nonce").is_none());
+        assert!(archive.by_name("manifest.json").is_ok());
+
+        // Closing waits for the balance to be withdrawn
+        request_closure(pool, &address).await.unwrap();
+        assert!(!complete_closure(pool, &address).await.unwrap());
+        let closing = status(pool, &address).await.unwrap();
+        assert_eq!(closing.state, ClosureState::Closing);
+        assert_eq!(closing.balances.len(), 1);
+
+        sqlx::query("UPDATE balances SET available = 0 WHERE user_address = $1")
+            .bind(&address)
+            .execute(pool)
+            .await
+            .unwrap();
+        assert!(complete_closure(pool, &address).await.unwrap());
+        let (username, email): (Option<String>, Option<String>) =
+            sqlx::query_as("SELECT username, email FROM users WHERE address = $1")
+                .bind(&address)
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        assert_eq!((username, email), (None, None));
+        assert_eq!(status(pool, &address).await.unwrap().state, ClosureState::Closed);
+        assert!(matches!(request_closure(pool, &address).await, Err(PersonalDataError::AccountClosed)));
+    }
+}
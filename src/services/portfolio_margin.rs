@@ -0,0 +1,561 @@
+//! Portfolio Margin
+//!
+//! A Yes and a No share of the same outcome always merge into exactly 1
+//! collateral, so buys offset by the other side of their outcome can't lose
+//! their full cost:
+//!
+//! - a buy covered by held shares of the opposite side needs nothing frozen:
+//!   once it fills, the pair it forms merges for at least what it cost;
+//! - a resting Yes buy and a resting No buy can't both lose; for each pair
+//!   of them only the dearer is frozen.
+//!
+//! What these offsets save is the outcome's margin credit, kept in
+//! `portfolio_margin_credits`: an account's `frozen` is the collateral its
+//! open buys reserved at their limit price, less its credits. The credit is
+//! recomputed when an order is placed, when a fill changes holdings and
+//! after cancels. A credit that shrinks is frozen again; if a fill leaves
+//! the account short, the pairs it holds are merged into collateral -
+//! positions are never liquidated.
+//!
+//! Shares offered by resting sells don't cover buys, and shares that cover
+//! a buy can only be sold once the collateral they stood in for is frozen.
+//! Self-custody accounts hold their shares on-chain and are not margined.
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::{PgConnection, PgPool};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::market::ShareType;
+use crate::models::{Address, OrderSide};
+use crate::services::ledger::{self, LedgerEntry, LedgerEntryType};
+use crate::services::matching::holdings;
+use crate::services::matching::precision::Collateral;
+
+#[derive(Debug, Error)]
+pub enum MarginError {
+    #[error("Insufficient balance: need {required}, available {available}")]
+    InsufficientBalance { required: Decimal, available: Decimal },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Unfilled part of a buy at its limit price
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bid {
+    pub price: Decimal,
+    pub remaining: Decimal,
+}
+
+/// An account's holdings and open orders on one outcome
+#[derive(Debug, Clone, Default)]
+pub struct OutcomeExposure {
+    pub yes_held: Decimal,
+    pub no_held: Decimal,
+    /// Shares offered by resting sells
+    pub yes_offered: Decimal,
+    pub no_offered: Decimal,
+    pub yes_bids: Vec<Bid>,
+    pub no_bids: Vec<Bid>,
+}
+
+/// What an exposure's offsets are worth
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Margin {
+    /// Collateral its buys need not freeze
+    pub credit: Decimal,
+    /// Held pairs covering no buy, which can be merged
+    pub mergeable_pairs: Decimal,
+}
+
+impl OutcomeExposure {
+    /// Count an open order
+    pub fn add_order(&mut self, share_type: ShareType, side: OrderSide, price: Decimal, remaining: Decimal) {
+        match (side, share_type) {
+            (OrderSide::Buy, ShareType::Yes) => self.yes_bids.push(Bid { price, remaining }),
+            (OrderSide::Buy, ShareType::No) => self.no_bids.push(Bid { price, remaining }),
+            (OrderSide::Sell, ShareType::Yes) => self.yes_offered += remaining,
+            (OrderSide::Sell, ShareType::No) => self.no_offered += remaining,
+        }
+    }
+
+    /// Credit of the exposure. Dearest buys are offset first: held shares
+    /// cover buys of the other side, then the remaining Yes and No buys are
+    /// paired off.
+    pub fn margin(&self) -> Margin {
+        let by_price_desc = |bids: &[Bid]| {
+            let mut bids = bids.to_vec();
+            bids.sort_by_key(|bid| std::cmp::Reverse(bid.price));
+            bids
+        };
+        let (mut yes_bids, mut no_bids) = (by_price_desc(&self.yes_bids), by_price_desc(&self.no_bids));
+        let mut free_yes = (self.yes_held - self.yes_offered).max(Decimal::ZERO);
+        let mut free_no = (self.no_held - self.no_offered).max(Decimal::ZERO);
+
+        let covered = cover(&mut no_bids, &mut free_yes) + cover(&mut yes_bids, &mut free_no);
+        let paired = pair(&mut yes_bids, &mut no_bids);
+        Margin {
+            credit: covered + paired,
+            mergeable_pairs: free_yes.min(free_no),
+        }
+    }
+}
+
+/// Cover `bids` with held shares of the other side; the covered part
+/// needs no collateral
+fn cover(bids: &mut [Bid], shares: &mut Decimal) -> Decimal {
+    let mut credit = Decimal::ZERO;
+    for bid in bids.iter_mut() {
+        if shares.is_zero() {
+            break;
+        }
+        let covered = bid.remaining.min(*shares);
+        credit += Collateral::notional(bid.price, covered).value();
+        bid.remaining -= covered;
+        *shares -= covered;
+    }
+    credit
+}
+
+/// Pair Yes buys with No buys; a pair only needs its dearer side frozen
+fn pair(yes_bids: &mut [Bid], no_bids: &mut [Bid]) -> Decimal {
+    let mut credit = Decimal::ZERO;
+    let (mut i, mut j) = (0, 0);
+    while i < yes_bids.len() && j < no_bids.len() {
+        let (yes, no) = (&mut yes_bids[i], &mut no_bids[j]);
+        let paired = yes.remaining.min(no.remaining);
+        credit += Collateral::notional(yes.price.min(no.price), paired).value();
+        yes.remaining -= paired;
+        no.remaining -= paired;
+        if yes.remaining.is_zero() {
+            i += 1;
+        }
+        if no.remaining.is_zero() {
+            j += 1;
+        }
+    }
+    credit
+}
+
+/// An order about to be placed
+#[derive(Debug, Clone, Copy)]
+pub struct NewOrder {
+    pub share_type: ShareType,
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub amount: Decimal,
+}
+
+/// Reserve collateral for `order` in the caller's transaction: a buy
+/// freezes its notional less the credit it adds, a sell freezes the credit
+/// its shares stop covering. Returns the collateral frozen (negative if
+/// released). The order's row must be written before the transaction
+/// reserves another order of the account on the outcome.
+pub async fn reserve(
+    conn: &mut PgConnection,
+    user_address: &str,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    collateral_token: &str,
+    order: &NewOrder,
+) -> Result<Decimal, MarginError> {
+    lock(conn, user_address, outcome_id).await?;
+    let previous = stored_credit(&mut *conn, user_address, outcome_id).await?;
+    let credit = match margined(&mut *conn, user_address).await? {
+        true => {
+            let mut exposure = load(&mut *conn, user_address, outcome_id).await?;
+            exposure.add_order(order.share_type, order.side, order.price, order.amount);
+            exposure.margin().credit
+        }
+        false => Decimal::ZERO,
+    };
+
+    let notional = match order.side {
+        OrderSide::Buy => Collateral::notional(order.price, order.amount).value(),
+        OrderSide::Sell => Decimal::ZERO,
+    };
+    let required = notional - (credit - previous);
+    if required > Decimal::ZERO {
+        // Check and freeze in one statement so concurrent orders can't
+        // both spend the same balance
+        let frozen = sqlx::query(
+            "UPDATE balances SET available = available - $1, frozen = frozen + $1, updated_at = NOW()
+             WHERE user_address = $2 AND token = $3 AND available >= $1",
+        )
+        .bind(required)
+        .bind(user_address)
+        .bind(collateral_token)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        if frozen == 0 {
+            let available: Option<Decimal> =
+                sqlx::query_scalar("SELECT available FROM balances WHERE user_address = $1 AND token = $2")
+                    .bind(user_address)
+                    .bind(collateral_token)
+                    .fetch_optional(&mut *conn)
+                    .await?;
+            return Err(MarginError::InsufficientBalance {
+                required,
+                available: available.unwrap_or(Decimal::ZERO),
+            });
+        }
+    } else if required < Decimal::ZERO {
+        freeze(&mut *conn, user_address, collateral_token, required).await?;
+    }
+    if credit != previous {
+        store_credit(&mut *conn, user_address, market_id, outcome_id, credit).await?;
+    }
+    Ok(required)
+}
+
+/// Bring the outcome's credit in line with the account's holdings and open
+/// orders, in the caller's transaction. A shrinking credit is frozen again;
+/// if that leaves the account short, held pairs are merged to cover it.
+pub async fn resync(
+    conn: &mut PgConnection,
+    user_address: &str,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    collateral_token: &str,
+) -> Result<(), sqlx::Error> {
+    lock(conn, user_address, outcome_id).await?;
+    let previous = stored_credit(conn, user_address, outcome_id).await?;
+    let margin = match margined(conn, user_address).await? {
+        true => load(conn, user_address, outcome_id).await?.margin(),
+        false => Margin::default(),
+    };
+    if margin.credit == previous {
+        return Ok(());
+    }
+
+    freeze(conn, user_address, collateral_token, previous - margin.credit).await?;
+    store_credit(conn, user_address, market_id, outcome_id, margin.credit).await?;
+    if margin.credit > previous {
+        return Ok(());
+    }
+
+    let available: Decimal =
+        sqlx::query_scalar("SELECT available FROM balances WHERE user_address = $1 AND token = $2")
+            .bind(user_address)
+            .bind(collateral_token)
+            .fetch_optional(&mut *conn)
+            .await?
+            .unwrap_or(Decimal::ZERO);
+    if available >= Decimal::ZERO {
+        return Ok(());
+    }
+    let share_decimals: i16 = sqlx::query_scalar("SELECT share_decimals FROM markets WHERE id = $1")
+        .bind(market_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let needed = (-available).round_dp_with_strategy(share_decimals as u32, RoundingStrategy::AwayFromZero);
+    let pairs = needed.min(margin.mergeable_pairs);
+    if pairs > Decimal::ZERO {
+        merge_pairs(conn, user_address, market_id, outcome_id, collateral_token, pairs).await?;
+    }
+    if pairs < needed {
+        tracing::error!(
+            "Account {} is short {} collateral on outcome {} after its margin credit shrank",
+            user_address,
+            needed - pairs,
+            outcome_id
+        );
+    }
+    Ok(())
+}
+
+/// Resync every outcome `user_address` holds a credit on, in the caller's
+/// transaction; cancels call this after releasing their reservations
+pub async fn resync_account(conn: &mut PgConnection, user_address: &str, collateral_token: &str) -> Result<(), sqlx::Error> {
+    let outcomes: Vec<(Uuid, Uuid)> = sqlx::query_as(
+        "SELECT market_id, outcome_id FROM portfolio_margin_credits WHERE user_address = $1 AND credit > 0",
+    )
+    .bind(user_address)
+    .fetch_all(&mut *conn)
+    .await?;
+    for (market_id, outcome_id) in outcomes {
+        resync(conn, user_address, market_id, outcome_id, collateral_token).await?;
+    }
+    Ok(())
+}
+
+/// [`resync_account`] in a transaction of its own
+pub async fn refresh_account(pool: &PgPool, user_address: &Address, collateral_token: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    resync_account(&mut tx, user_address.as_str(), collateral_token).await?;
+    tx.commit().await
+}
+
+/// Serialize credit changes of one account and outcome
+async fn lock(conn: &mut PgConnection, user_address: &str, outcome_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("portfolio_margin:{}:{}", user_address, outcome_id))
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Custodial accounts are margined; self-custody ones hold their shares
+/// on-chain
+async fn margined(conn: &mut PgConnection, user_address: &str) -> Result<bool, sqlx::Error> {
+    let mode: Option<String> = sqlx::query_scalar("SELECT settlement_mode FROM users WHERE address = $1")
+        .bind(user_address)
+        .fetch_optional(conn)
+        .await?;
+    Ok(mode.as_deref() != Some("self_custody"))
+}
+
+async fn stored_credit(conn: &mut PgConnection, user_address: &str, outcome_id: Uuid) -> Result<Decimal, sqlx::Error> {
+    let credit: Option<Decimal> = sqlx::query_scalar(
+        "SELECT credit FROM portfolio_margin_credits WHERE user_address = $1 AND outcome_id = $2",
+    )
+    .bind(user_address)
+    .bind(outcome_id)
+    .fetch_optional(conn)
+    .await?;
+    Ok(credit.unwrap_or(Decimal::ZERO))
+}
+
+async fn store_credit(
+    conn: &mut PgConnection,
+    user_address: &str,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    credit: Decimal,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO portfolio_margin_credits (user_address, market_id, outcome_id, credit)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_address, outcome_id) DO UPDATE SET credit = $4, updated_at = NOW()
+        "#,
+    )
+    .bind(user_address)
+    .bind(market_id)
+    .bind(outcome_id)
+    .bind(credit)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Move `amount` from available to frozen (back if negative)
+async fn freeze(conn: &mut PgConnection, user_address: &str, collateral_token: &str, amount: Decimal) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO balances (user_address, token, available, frozen, updated_at)
+        VALUES ($1, $2, -$3, $3, NOW())
+        ON CONFLICT (user_address, token) DO UPDATE SET
+            available = balances.available - $3,
+            frozen = balances.frozen + $3,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(user_address)
+    .bind(collateral_token)
+    .bind(amount)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn load(conn: &mut PgConnection, user_address: &str, outcome_id: Uuid) -> Result<OutcomeExposure, sqlx::Error> {
+    let mut exposure = OutcomeExposure::default();
+    let held: Vec<(String, Decimal)> = sqlx::query_as(
+        "SELECT share_type::text, amount FROM shares WHERE user_address = $1 AND outcome_id = $2 AND amount > 0",
+    )
+    .bind(user_address)
+    .bind(outcome_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    for (share_type, amount) in held {
+        match share_type.parse() {
+            Ok(ShareType::Yes) => exposure.yes_held += amount,
+            Ok(ShareType::No) => exposure.no_held += amount,
+            Err(_) => {}
+        }
+    }
+
+    let orders: Vec<(String, OrderSide, Decimal, Decimal)> = sqlx::query_as(
+        r#"
+        SELECT share_type::text, side, price, amount - filled_amount
+        FROM orders
+        WHERE user_address = $1 AND outcome_id = $2 AND price IS NOT NULL AND amount > filled_amount
+          AND status IN ('pending', 'accepted', 'open', 'partially_filled')
+        "#,
+    )
+    .bind(user_address)
+    .bind(outcome_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    for (share_type, side, price, remaining) in orders {
+        if let Ok(share_type) = share_type.parse() {
+            exposure.add_order(share_type, side, price, remaining);
+        }
+    }
+    Ok(exposure)
+}
+
+/// Merge `pairs` held Yes/No pairs of the outcome into collateral
+async fn merge_pairs(
+    conn: &mut PgConnection,
+    user_address: &str,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    collateral_token: &str,
+    pairs: Decimal,
+) -> Result<(), sqlx::Error> {
+    for share_type in [ShareType::Yes, ShareType::No] {
+        sqlx::query(
+            "UPDATE shares SET amount = amount - $1, updated_at = NOW()
+             WHERE user_address = $2 AND outcome_id = $3 AND share_type = $4::share_type",
+        )
+        .bind(pairs)
+        .bind(user_address)
+        .bind(outcome_id)
+        .bind(share_type.as_str())
+        .execute(&mut *conn)
+        .await?;
+        // A pair pays 1, half a share each
+        sqlx::query(
+            r#"
+            INSERT INTO share_changes (user_address, market_id, outcome_id, share_type, change_type, amount, price)
+            VALUES ($1, $2, $3, $4::share_type, 'merge', $5, 0.5)
+            "#,
+        )
+        .bind(user_address)
+        .bind(market_id)
+        .bind(outcome_id)
+        .bind(share_type.as_str())
+        .bind(-pairs)
+        .execute(&mut *conn)
+        .await?;
+    }
+    holdings::record_merge(conn, market_id, outcome_id, pairs).await?;
+
+    let balance_after: Decimal = sqlx::query_scalar(
+        "UPDATE balances SET available = available + $1, updated_at = NOW()
+         WHERE user_address = $2 AND token = $3
+         RETURNING available",
+    )
+    .bind(pairs)
+    .bind(user_address)
+    .bind(collateral_token)
+    .fetch_one(&mut *conn)
+    .await?;
+    ledger::record_entry(
+        conn,
+        &LedgerEntry {
+            user_address,
+            token: collateral_token,
+            entry_type: LedgerEntryType::PairMerge,
+            amount: pairs,
+            balance_after,
+            reference_id: Some(outcome_id),
+            counterparty: None,
+        },
+    )
+    .await?;
+    tracing::info!("Merged {} pairs of outcome {} for {} to cover its margin", pairs, outcome_id, user_address);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::matching::OrderIntent;
+    use crate::services::order_gateway::OrderSource;
+    use crate::test_support::TestApp;
+    use rust_decimal_macros::dec;
+
+    fn bid(price: Decimal, remaining: Decimal) -> Bid {
+        Bid { price, remaining }
+    }
+
+    #[test]
+    fn test_held_shares_cover_opposite_buys() {
+        let exposure = OutcomeExposure {
+            yes_held: dec!(10),
+            no_bids: vec![bid(dec!(0.3), dec!(4)), bid(dec!(0.6), dec!(8))],
+            ..Default::default()
+        };
+        // 8 @ 0.6 then 2 of the 4 @ 0.3
+        assert_eq!(exposure.margin(), Margin { credit: dec!(5.4), mergeable_pairs: dec!(0) });
+
+        // Shares offered for sale cover nothing
+        let offered = OutcomeExposure { yes_offered: dec!(10), ..exposure.clone() };
+        assert_eq!(offered.margin().credit, dec!(0));
+    }
+
+    #[test]
+    fn test_opposite_buys_pair_off() {
+        let exposure = OutcomeExposure {
+            yes_bids: vec![bid(dec!(0.6), dec!(10))],
+            no_bids: vec![bid(dec!(0.3), dec!(4)), bid(dec!(0.2), dec!(10))],
+            ..Default::default()
+        };
+        // 4 pairs save 0.3, 6 pairs save 0.2
+        assert_eq!(exposure.margin().credit, dec!(2.4));
+
+        let with_pairs = OutcomeExposure {
+            yes_held: dec!(5),
+            no_held: dec!(3),
+            ..Default::default()
+        };
+        assert_eq!(with_pairs.margin(), Margin { credit: dec!(0), mergeable_pairs: dec!(3) });
+    }
+
+    #[tokio::test]
+    async fn test_paired_bids_freeze_the_dearer_side() {
+        let app = TestApp::builder().build().await;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        let user: Address = format!("0x{}00000000", Uuid::new_v4().simple()).parse().unwrap();
+        let token = app.state.config.collateral_symbol();
+        app.deposit(user.as_str(), dec!(6)).await;
+        let bid = |share_type, price| OrderIntent {
+            source: OrderSource::Api,
+            user_address: user.clone().into(),
+            market_id,
+            outcome_id,
+            share_type,
+            side: OrderSide::Buy,
+            order_type: crate::models::OrderType::Limit,
+            time_in_force: crate::models::TimeInForce::Gtc,
+            post_only: false,
+            stp: Default::default(),
+            price,
+            amount: dec!(10),
+            expires_at: None,
+            signature: String::new(),
+        };
+        let balance = || async {
+            sqlx::query_as::<_, (Decimal, Decimal)>(
+                "SELECT available, frozen FROM balances WHERE user_address = $1 AND token = $2",
+            )
+            .bind(&user)
+            .bind(token)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap()
+        };
+
+        // Without margining the pair would need 6 + 3
+        app.state.order_flow.place(&bid(ShareType::Yes, dec!(0.6))).await.unwrap();
+        let no_bid = app.state.order_flow.place(&bid(ShareType::No, dec!(0.3))).await.unwrap();
+        assert_eq!(balance().await, (dec!(0), dec!(6)));
+
+        // Cancelling one leg freezes the other in full
+        assert!(app.state.order_gateway.cancel(OrderSource::Api, &user, no_bid.order_id).await.unwrap());
+        assert_eq!(balance().await, (dec!(0), dec!(6)));
+        let yes_bid: Uuid = sqlx::query_scalar(
+            "SELECT id FROM orders WHERE user_address = $1 AND share_type = 'yes' AND status = 'open'",
+        )
+        .bind(&user)
+        .fetch_one(&app.db.pool)
+        .await
+        .unwrap();
+        assert!(app.state.order_gateway.cancel(OrderSource::Api, &user, yes_bid).await.unwrap());
+        assert_eq!(balance().await, (dec!(6), dec!(0)));
+    }
+}
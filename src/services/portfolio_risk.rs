@@ -0,0 +1,345 @@
+//! Cross-Market Portfolio Risk
+//!
+//! Markets of one event (a market group) resolve together: in a
+//! negative-risk group at most one of them resolves Yes, so positions across
+//! the group are correlated and their risk is not the sum of per-market
+//! risks. For every event the user is involved in, the report scores each
+//! way the event can resolve - one market Yes and the rest No, or all No -
+//! and keeps the worst. Markets of an ordinary group resolve independently,
+//! so their worst case is each market's own worst outcome.
+//!
+//! Open orders count against the user: in each scenario an order is assumed
+//! to fill exactly when filling loses money (a buy above what the shares
+//! pay, a sell below it).
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::matching::precision::Collateral;
+
+/// A user's holdings in one unresolved market of an event
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PositionRow {
+    pub group_id: Uuid,
+    pub group_title: String,
+    pub neg_risk: bool,
+    pub market_id: Uuid,
+    pub question: String,
+    /// Current Yes probability, used to mark positions
+    pub probability: Decimal,
+    pub yes_shares: Decimal,
+    pub no_shares: Decimal,
+    /// Sum of amount * avg_cost over both share types
+    pub cost_basis: Decimal,
+}
+
+/// The unfilled part of a live order
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LiveOrder {
+    pub market_id: Uuid,
+    pub buy: bool,
+    /// Order is for Yes shares (else No)
+    pub yes: bool,
+    pub price: Decimal,
+    pub remaining: Decimal,
+}
+
+impl LiveOrder {
+    /// Value change if the order filled and the market then resolved
+    /// `resolves_yes`
+    fn fill_pnl(&self, resolves_yes: bool) -> Decimal {
+        let payout = if self.yes == resolves_yes { Decimal::ONE } else { Decimal::ZERO };
+        let per_share = if self.buy { payout - self.price } else { self.price - payout };
+        per_share * self.remaining
+    }
+
+    fn reserved_collateral(&self) -> Decimal {
+        if self.buy {
+            self.price * self.remaining
+        } else {
+            Decimal::ZERO
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketExposure {
+    pub market_id: Uuid,
+    pub question: String,
+    pub yes_shares: Decimal,
+    pub no_shares: Decimal,
+    /// Yes minus No shares
+    pub net_shares: Decimal,
+    pub open_orders: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventRisk {
+    pub group_id: Uuid,
+    pub title: String,
+    pub neg_risk: bool,
+    pub markets: Vec<MarketExposure>,
+    /// Net Yes shares across the event's markets
+    pub net_exposure: Decimal,
+    /// Positions valued at current probabilities
+    pub mark_value: Decimal,
+    pub cost_basis: Decimal,
+    /// Collateral reserved by open buy orders
+    pub open_order_collateral: Decimal,
+    /// Cost basis plus collateral reserved by open buys
+    pub collateral_at_risk: Decimal,
+    /// Smallest total payout over the ways the event can resolve, after
+    /// adverse fills of open orders
+    pub worst_case_payout: Decimal,
+    /// Markets resolving Yes in that scenario (none: all resolve No)
+    pub worst_case_yes: Vec<Uuid>,
+    /// Cost basis minus the worst-case payout; zero when every scenario
+    /// returns at least the cost
+    pub worst_case_loss: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RiskReport {
+    pub events: Vec<EventRisk>,
+    pub collateral_at_risk: Decimal,
+    pub worst_case_loss: Decimal,
+}
+
+/// Risk of `user_address`'s positions and open orders in grouped markets
+pub async fn report(pool: &PgPool, user_address: &Address) -> Result<RiskReport, sqlx::Error> {
+    let positions: Vec<PositionRow> = sqlx::query_as(
+        r#"
+        WITH involved AS (
+            SELECT m.group_id
+            FROM shares s
+            JOIN markets m ON m.id = s.market_id
+            WHERE s.user_address = $1 AND s.amount > 0 AND m.group_id IS NOT NULL
+            UNION
+            SELECT m.group_id
+            FROM orders o
+            JOIN markets m ON m.id = o.market_id
+            WHERE o.user_address = $1 AND o.status IN ('pending', 'open', 'partially_filled')
+              AND m.group_id IS NOT NULL
+        )
+        SELECT g.id AS group_id, g.title AS group_title, g.neg_risk, m.id AS market_id, m.question,
+               COALESCE(yo.probability, 0) AS probability,
+               COALESCE(SUM(s.amount) FILTER (WHERE s.share_type = 'yes'), 0) AS yes_shares,
+               COALESCE(SUM(s.amount) FILTER (WHERE s.share_type = 'no'), 0) AS no_shares,
+               COALESCE(SUM(s.amount * s.avg_cost), 0) AS cost_basis
+        FROM involved i
+        JOIN market_groups g ON g.id = i.group_id
+        JOIN markets m ON m.group_id = g.id AND m.status NOT IN ('resolved', 'cancelled')
+        LEFT JOIN outcomes yo ON yo.market_id = m.id AND yo.share_type = 'yes'
+        LEFT JOIN shares s ON s.market_id = m.id AND s.user_address = $1
+        GROUP BY g.id, m.id, yo.probability
+        ORDER BY g.created_at, g.id, m.created_at, m.id
+        "#,
+    )
+    .bind(user_address)
+    .fetch_all(pool)
+    .await?;
+
+    let orders: Vec<LiveOrder> = sqlx::query_as(
+        r#"
+        SELECT o.market_id, o.side = 'buy' AS buy, o.share_type = 'yes' AS yes, o.price,
+               o.amount - o.filled_amount AS remaining
+        FROM orders o
+        JOIN markets m ON m.id = o.market_id
+        WHERE o.user_address = $1 AND o.status IN ('pending', 'open', 'partially_filled')
+          AND m.group_id IS NOT NULL AND o.price IS NOT NULL AND o.amount > o.filled_amount
+        "#,
+    )
+    .bind(user_address)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(build_report(&positions, &orders))
+}
+
+/// Assess every event in `positions` (rows grouped by event, in order)
+pub fn build_report(positions: &[PositionRow], orders: &[LiveOrder]) -> RiskReport {
+    let events: Vec<EventRisk> = positions
+        .chunk_by(|a, b| a.group_id == b.group_id)
+        .map(|markets| assess_event(markets, orders))
+        .collect();
+    RiskReport {
+        collateral_at_risk: events.iter().map(|e| e.collateral_at_risk).sum(),
+        worst_case_loss: events.iter().map(|e| e.worst_case_loss).sum(),
+        events,
+    }
+}
+
+/// Risk of one event; `markets` are its unresolved markets
+fn assess_event(markets: &[PositionRow], orders: &[LiveOrder]) -> EventRisk {
+    let first = &markets[0];
+    let orders_of = |market_id: Uuid| orders.iter().filter(move |o| o.market_id == market_id);
+
+    // Payout of a market resolving `yes`, its open orders filling adversely
+    let value = |row: &PositionRow, yes: bool| -> Decimal {
+        let held = if yes { row.yes_shares } else { row.no_shares };
+        held + orders_of(row.market_id)
+            .map(|o| o.fill_pnl(yes).min(Decimal::ZERO))
+            .sum::<Decimal>()
+    };
+
+    let (worst_case_payout, worst_case_yes) = if first.neg_risk {
+        // Market k resolves Yes and every other No, or all resolve No
+        (0..=markets.len())
+            .map(|k| {
+                let payout: Decimal = markets.iter().enumerate().map(|(i, row)| value(row, i == k)).sum();
+                (payout, markets.get(k).map(|row| row.market_id).into_iter().collect::<Vec<_>>())
+            })
+            .min_by(|a, b| a.0.cmp(&b.0))
+            .unwrap_or_default()
+    } else {
+        let mut payout = Decimal::ZERO;
+        let mut yes = Vec::new();
+        for row in markets {
+            let (if_yes, if_no) = (value(row, true), value(row, false));
+            if if_yes < if_no {
+                yes.push(row.market_id);
+            }
+            payout += if_yes.min(if_no);
+        }
+        (payout, yes)
+    };
+
+    let cost_basis: Decimal = markets.iter().map(|row| row.cost_basis).sum();
+    let open_order_collateral: Decimal = markets
+        .iter()
+        .flat_map(|row| orders_of(row.market_id))
+        .map(LiveOrder::reserved_collateral)
+        .sum();
+
+    EventRisk {
+        group_id: first.group_id,
+        title: first.group_title.clone(),
+        neg_risk: first.neg_risk,
+        markets: markets
+            .iter()
+            .map(|row| MarketExposure {
+                market_id: row.market_id,
+                question: row.question.clone(),
+                yes_shares: row.yes_shares,
+                no_shares: row.no_shares,
+                net_shares: row.yes_shares - row.no_shares,
+                open_orders: orders_of(row.market_id).count() as i64,
+            })
+            .collect(),
+        net_exposure: markets.iter().map(|row| row.yes_shares - row.no_shares).sum(),
+        mark_value: Collateral::new(
+            markets
+                .iter()
+                .map(|row| row.yes_shares * row.probability + row.no_shares * (Decimal::ONE - row.probability))
+                .sum(),
+        )
+        .value(),
+        cost_basis: Collateral::new(cost_basis).value(),
+        open_order_collateral: Collateral::new(open_order_collateral).value(),
+        collateral_at_risk: Collateral::new(cost_basis + open_order_collateral).value(),
+        worst_case_payout: Collateral::new(worst_case_payout).value(),
+        worst_case_yes,
+        worst_case_loss: Collateral::new((cost_basis - worst_case_payout).max(Decimal::ZERO)).value(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn position(group_id: Uuid, neg_risk: bool, yes: Decimal, no: Decimal, cost: Decimal) -> PositionRow {
+        PositionRow {
+            group_id,
+            group_title: "Election".to_string(),
+            neg_risk,
+            market_id: Uuid::new_v4(),
+            question: "Will X win?".to_string(),
+            probability: dec!(0.5),
+            yes_shares: yes,
+            no_shares: no,
+            cost_basis: cost,
+        }
+    }
+
+    #[test]
+    fn test_neg_risk_worst_case_uses_exclusive_scenarios() {
+        let group = Uuid::new_v4();
+        // Yes on A (cost 40) and No on B (cost 30): A winning pays 100 + 100,
+        // B winning pays nothing, neither winning pays 100
+        let markets = vec![
+            position(group, true, dec!(100), dec!(0), dec!(40)),
+            position(group, true, dec!(0), dec!(100), dec!(30)),
+        ];
+        let report = build_report(&markets, &[]);
+        let event = &report.events[0];
+
+        assert_eq!(event.worst_case_payout, dec!(0));
+        assert_eq!(event.worst_case_yes, vec![markets[1].market_id]);
+        assert_eq!(event.worst_case_loss, dec!(70));
+        assert_eq!(event.collateral_at_risk, dec!(70));
+        assert_eq!(event.net_exposure, dec!(0));
+        assert_eq!(event.mark_value, dec!(100));
+    }
+
+    #[test]
+    fn test_independent_markets_and_adverse_fills() {
+        let group = Uuid::new_v4();
+        let markets = vec![
+            position(group, false, dec!(100), dec!(0), dec!(40)),
+            position(group, false, dec!(0), dec!(0), dec!(0)),
+        ];
+        let orders = vec![
+            // Resting buy of 50 Yes at 0.3 in the second market loses 15 if it resolves No
+            LiveOrder {
+                market_id: markets[1].market_id,
+                buy: true,
+                yes: true,
+                price: dec!(0.3),
+                remaining: dec!(50),
+            },
+            // Selling 100 Yes at 0.6 in the first market forgoes 40 if it resolves Yes
+            LiveOrder {
+                market_id: markets[0].market_id,
+                buy: false,
+                yes: true,
+                price: dec!(0.6),
+                remaining: dec!(100),
+            },
+        ];
+        let report = build_report(&markets, &orders);
+        let event = &report.events[0];
+
+        // First market: Yes pays 100 - 40 = 60, No pays 0; second: No costs 15
+        assert_eq!(event.worst_case_payout, dec!(-15));
+        assert_eq!(event.worst_case_yes, Vec::<Uuid>::new());
+        assert_eq!(event.worst_case_loss, dec!(55));
+        assert_eq!(event.open_order_collateral, dec!(15));
+        assert_eq!(event.collateral_at_risk, dec!(55));
+        assert_eq!(event.markets[1].open_orders, 1);
+    }
+
+    #[test]
+    fn test_hedged_event_has_no_loss_and_totals_add_up() {
+        let hedged = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let markets = vec![
+            // A full No set in a 2-market neg-risk group pays at least 100
+            position(hedged, true, dec!(0), dec!(100), dec!(45)),
+            position(hedged, true, dec!(0), dec!(100), dec!(45)),
+            position(other, true, dec!(10), dec!(0), dec!(5)),
+            position(other, true, dec!(0), dec!(0), dec!(0)),
+        ];
+        let report = build_report(&markets, &[]);
+
+        assert_eq!(report.events.len(), 2);
+        assert_eq!(report.events[0].worst_case_payout, dec!(100));
+        assert_eq!(report.events[0].worst_case_loss, dec!(0));
+        assert_eq!(report.events[1].worst_case_loss, dec!(5));
+        assert_eq!(report.worst_case_loss, dec!(5));
+        assert_eq!(report.collateral_at_risk, dec!(95));
+    }
+}
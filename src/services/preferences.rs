@@ -0,0 +1,362 @@
+//! User Preferences
+//!
+//! Per-account settings read by the services that render things for a user,
+//! so endpoints don't each take their own display parameters:
+//!
+//! - `timezone` (IANA name) and `currency_display` in `user_preferences`,
+//!   applied to notification emails when they are rendered;
+//! - notification opt-ins in `notification_preferences` and the contact
+//!   email in `users.email`, used when notifications are queued.
+//!
+//! Accounts without rows get the defaults: UTC, amounts labelled with the
+//! collateral token, email off.
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::models::Address;
+
+/// Longest accepted contact email
+const MAX_EMAIL_LEN: usize = 255;
+
+/// How amounts are labelled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurrencyDisplay {
+    /// With the collateral token, e.g. `12.5 USDC`
+    #[default]
+    Token,
+    /// In dollars, e.g. `12.5 USD`
+    Usd,
+}
+
+impl CurrencyDisplay {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CurrencyDisplay::Token => "token",
+            CurrencyDisplay::Usd => "usd",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "token" => Some(CurrencyDisplay::Token),
+            "usd" => Some(CurrencyDisplay::Usd),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NotificationPreferences {
+    pub email: Option<String>,
+    pub email_enabled: bool,
+    pub notify_fills: bool,
+    /// Fills below this notional (price * amount) don't generate an email
+    pub fill_min_notional: Decimal,
+    pub notify_settlements: bool,
+    pub notify_withdrawals: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            email: None,
+            email_enabled: false,
+            notify_fills: true,
+            fill_min_notional: Decimal::ONE_HUNDRED,
+            notify_settlements: true,
+            notify_withdrawals: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UserPreferences {
+    pub timezone: Tz,
+    pub currency_display: CurrencyDisplay,
+    pub notifications: NotificationPreferences,
+}
+
+/// Partial notification update; `None` fields are left unchanged
+#[derive(Debug, Clone, Default)]
+pub struct NotificationPreferencesUpdate {
+    /// Contact email; empty string removes it
+    pub email: Option<String>,
+    pub email_enabled: Option<bool>,
+    pub notify_fills: Option<bool>,
+    pub fill_min_notional: Option<Decimal>,
+    pub notify_settlements: Option<bool>,
+    pub notify_withdrawals: Option<bool>,
+}
+
+/// Partial update; `None` fields are left unchanged
+#[derive(Debug, Clone, Default)]
+pub struct PreferencesUpdate {
+    /// IANA timezone name, e.g. `Europe/Berlin`
+    pub timezone: Option<String>,
+    pub currency_display: Option<CurrencyDisplay>,
+    pub notifications: Option<NotificationPreferencesUpdate>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreferencesError {
+    #[error("Unknown timezone: {0}")]
+    InvalidTimezone(String),
+    #[error("Invalid email address")]
+    InvalidEmail,
+    #[error("fill_min_notional must not be negative")]
+    NegativeThreshold,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl PreferencesError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PreferencesError::InvalidTimezone(_) => "INVALID_TIMEZONE",
+            PreferencesError::InvalidEmail => "INVALID_EMAIL",
+            PreferencesError::NegativeThreshold => "INVALID_THRESHOLD",
+            PreferencesError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PreferencesRow {
+    email: Option<String>,
+    email_enabled: Option<bool>,
+    notify_fills: Option<bool>,
+    fill_min_notional: Option<Decimal>,
+    notify_settlements: Option<bool>,
+    notify_withdrawals: Option<bool>,
+    timezone: Option<String>,
+    currency_display: Option<String>,
+}
+
+impl UserPreferences {
+    /// `t` in the user's timezone, e.g. `2026-01-10 15:00 CET`
+    pub fn format_time(&self, t: DateTime<Utc>) -> String {
+        t.with_timezone(&self.timezone).format("%Y-%m-%d %H:%M %Z").to_string()
+    }
+
+    /// Apply the display settings to a notification payload: RFC 3339
+    /// timestamps are shown in the user's timezone and `symbol` follows
+    /// `currency_display`
+    pub fn localize(&self, payload: &mut Value) {
+        let Some(fields) = payload.as_object_mut() else {
+            return;
+        };
+        for (key, value) in fields.iter_mut() {
+            let Value::String(s) = value else { continue };
+            if key == "symbol" && self.currency_display == CurrencyDisplay::Usd {
+                *s = "USD".to_string();
+            } else if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+                *s = self.format_time(t.with_timezone(&Utc));
+            }
+        }
+    }
+}
+
+/// Preferences of `user_address`; defaults for users without any
+pub async fn load(pool: &PgPool, user_address: &Address) -> Result<UserPreferences, sqlx::Error> {
+    let row: Option<PreferencesRow> = sqlx::query_as(
+        r#"
+        SELECT u.email, p.email_enabled, p.notify_fills, p.fill_min_notional,
+               p.notify_settlements, p.notify_withdrawals, up.timezone, up.currency_display
+        FROM users u
+        LEFT JOIN notification_preferences p ON p.user_address = u.address
+        LEFT JOIN user_preferences up ON up.user_address = u.address
+        WHERE u.address = $1
+        "#,
+    )
+    .bind(user_address)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(UserPreferences::default());
+    };
+    let defaults = NotificationPreferences::default();
+    Ok(UserPreferences {
+        timezone: row.timezone.and_then(|tz| tz.parse().ok()).unwrap_or_default(),
+        currency_display: row
+            .currency_display
+            .as_deref()
+            .and_then(CurrencyDisplay::parse)
+            .unwrap_or_default(),
+        notifications: NotificationPreferences {
+            email: row.email,
+            email_enabled: row.email_enabled.unwrap_or(defaults.email_enabled),
+            notify_fills: row.notify_fills.unwrap_or(defaults.notify_fills),
+            fill_min_notional: row.fill_min_notional.unwrap_or(defaults.fill_min_notional),
+            notify_settlements: row.notify_settlements.unwrap_or(defaults.notify_settlements),
+            notify_withdrawals: row.notify_withdrawals.unwrap_or(defaults.notify_withdrawals),
+        },
+    })
+}
+
+/// Apply `update` and return the resulting preferences
+pub async fn update(
+    pool: &PgPool,
+    user_address: &Address,
+    update: &PreferencesUpdate,
+) -> Result<UserPreferences, PreferencesError> {
+
+    let timezone = match update.timezone.as_deref().map(str::trim) {
+        Some(name) => Some(
+            name.parse::<Tz>()
+                .map_err(|_| PreferencesError::InvalidTimezone(name.to_string()))?,
+        ),
+        None => None,
+    };
+
+    let email = match update.notifications.as_ref().and_then(|n| n.email.as_deref()).map(str::trim) {
+        None => None,
+        Some("") => Some(None),
+        Some(e) => {
+            if e.len() > MAX_EMAIL_LEN || e.parse::<lettre::Address>().is_err() {
+                return Err(PreferencesError::InvalidEmail);
+            }
+            Some(Some(e.to_lowercase()))
+        }
+    };
+
+    if let Some(threshold) = update.notifications.as_ref().and_then(|n| n.fill_min_notional) {
+        if threshold < Decimal::ZERO {
+            return Err(PreferencesError::NegativeThreshold);
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+
+    if let Some(email) = email {
+        sqlx::query("UPDATE users SET email = $1 WHERE address = $2")
+            .bind(email)
+            .bind(user_address)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if let Some(n) = &update.notifications {
+        sqlx::query(
+            r#"
+            INSERT INTO notification_preferences
+                (user_address, email_enabled, notify_fills, fill_min_notional, notify_settlements, notify_withdrawals)
+            VALUES ($1, COALESCE($2, FALSE), COALESCE($3, TRUE), COALESCE($4, 100), COALESCE($5, TRUE), COALESCE($6, TRUE))
+            ON CONFLICT (user_address) DO UPDATE SET
+                email_enabled = COALESCE($2, notification_preferences.email_enabled),
+                notify_fills = COALESCE($3, notification_preferences.notify_fills),
+                fill_min_notional = COALESCE($4, notification_preferences.fill_min_notional),
+                notify_settlements = COALESCE($5, notification_preferences.notify_settlements),
+                notify_withdrawals = COALESCE($6, notification_preferences.notify_withdrawals)
+            "#,
+        )
+        .bind(user_address)
+        .bind(n.email_enabled)
+        .bind(n.notify_fills)
+        .bind(n.fill_min_notional)
+        .bind(n.notify_settlements)
+        .bind(n.notify_withdrawals)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if timezone.is_some() || update.currency_display.is_some() {
+        sqlx::query(
+            r#"
+            INSERT INTO user_preferences (user_address, timezone, currency_display)
+            VALUES ($1, COALESCE($2, 'UTC'), COALESCE($3, 'token'))
+            ON CONFLICT (user_address) DO UPDATE SET
+                timezone = COALESCE($2, user_preferences.timezone),
+                currency_display = COALESCE($3, user_preferences.currency_display)
+            "#,
+        )
+        .bind(user_address)
+        .bind(timezone.map(|tz| tz.name()))
+        .bind(update.currency_display.map(|c| c.as_str()))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(load(pool, user_address).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn test_localize_payload() {
+        let prefs = UserPreferences {
+            timezone: chrono_tz::Europe::Berlin,
+            currency_display: CurrencyDisplay::Usd,
+            ..Default::default()
+        };
+        let mut payload = serde_json::json!({
+            "resolution_time": "2026-01-10T14:00:00+00:00",
+            "amount": "12.5",
+            "symbol": "USDC",
+        });
+        prefs.localize(&mut payload);
+        assert_eq!(payload["resolution_time"], "2026-01-10 15:00 CET");
+        assert_eq!(payload["amount"], "12.5");
+        assert_eq!(payload["symbol"], "USD");
+
+        let mut payload = serde_json::json!({ "symbol": "USDC" });
+        UserPreferences::default().localize(&mut payload);
+        assert_eq!(payload["symbol"], "USDC");
+    }
+
+    #[tokio::test]
+    async fn test_partial_updates_keep_other_settings() {
+        let app = TestApp::builder().build().await;
+        let pool = &app.db.pool;
+        let user = &"0x00000000000000000000000000000000000000c3".parse::<Address>().unwrap();
+        sqlx::query("INSERT INTO users (address, nonce) VALUES ($1, 1) ON CONFLICT DO NOTHING")
+            .bind(user)
+            .execute(pool)
+            .await
+            .unwrap();
+        assert_eq!(load(pool, user).await.unwrap(), UserPreferences::default());
+
+        let update_tz = PreferencesUpdate {
+            timezone: Some("America/New_York".to_string()),
+            ..Default::default()
+        };
+        let prefs = update(pool, user, &update_tz).await.unwrap();
+        assert_eq!(prefs.timezone, chrono_tz::America::New_York);
+
+        let update_notifications = PreferencesUpdate {
+            currency_display: Some(CurrencyDisplay::Usd),
+            notifications: Some(NotificationPreferencesUpdate {
+                email: Some("Trader@Example.com".to_string()),
+                email_enabled: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let prefs = update(pool, user, &update_notifications).await.unwrap();
+        assert_eq!(prefs.timezone, chrono_tz::America::New_York);
+        assert_eq!(prefs.currency_display, CurrencyDisplay::Usd);
+        assert_eq!(prefs.notifications.email.as_deref(), Some("trader@example.com"));
+        assert!(prefs.notifications.email_enabled && prefs.notifications.notify_fills);
+
+        let invalid = PreferencesUpdate {
+            timezone: Some("Mars/Olympus".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            update(pool, user, &invalid).await,
+            Err(PreferencesError::InvalidTimezone(_))
+        ));
+        assert_eq!(load(pool, user).await.unwrap(), prefs);
+    }
+}
@@ -0,0 +1,357 @@
+//! Gasless Meta-Transaction Relay
+//!
+//! Small winners often hold no native gas token, so claiming a payout or
+//! approving the exchange costs them a top-up first. Instead they sign an
+//! ERC-2771 forward request (EIP-712 typed data of the trusted forwarder)
+//! and the operator submits it through the forwarder, paying the gas.
+//!
+//! Only calls the exchange needs are relayed:
+//! - `ConditionalTokens.redeemPositions` for a condition of a resolved market
+//! - `Collateral.approve` to the exchange or ConditionalTokens
+//! - `ConditionalTokens.setApprovalForAll` to the exchange
+//!
+//! Targets must accept the forwarder as trusted (ERC-2771 context) for the
+//! call to act on behalf of the signer. Every relayed request is recorded in
+//! `relayed_transactions`, which the per-user rolling 24h quota counts.
+
+use chrono::{DateTime, Utc};
+use ethers::abi::AbiDecode;
+use ethers::types::{Address, U256};
+use serde::Serialize;
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::blockchain::contracts::conditional_tokens_contract::ConditionalTokensContractCalls;
+use crate::blockchain::contracts::mock_usdc_contract::MockUSDCContractCalls;
+use crate::blockchain::types::{ContractAddresses, TxResult, TxStatus};
+use crate::models::Address as UserAddress;
+
+/// EIP-712 domain name of the OpenZeppelin MinimalForwarder
+pub const FORWARDER_DOMAIN_NAME: &str = "MinimalForwarder";
+/// EIP-712 domain version of the OpenZeppelin MinimalForwarder
+pub const FORWARDER_DOMAIN_VERSION: &str = "0.0.1";
+
+/// A call the relayer pays for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayKind {
+    RedeemPositions,
+    ApproveCollateral,
+    ApproveOutcomeTokens,
+}
+
+impl RelayKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelayKind::RedeemPositions => "redeem_positions",
+            RelayKind::ApproveCollateral => "approve_collateral",
+            RelayKind::ApproveOutcomeTokens => "approve_outcome_tokens",
+        }
+    }
+}
+
+/// A relayable call, decoded from a forward request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayCall {
+    pub kind: RelayKind,
+    /// Condition being redeemed (redeemPositions only)
+    pub condition_id: Option<[u8; 32]>,
+}
+
+#[derive(Debug, Error)]
+pub enum RelayError {
+    #[error("Relaying is not configured")]
+    NotConfigured,
+    #[error("The request must be signed by the authenticated wallet")]
+    SignerMismatch,
+    #[error("Only redeemPositions and exchange approvals are relayed")]
+    CallNotAllowed,
+    #[error("Relayed calls cannot carry value")]
+    ValueNotAllowed,
+    #[error("Requested gas exceeds the relay limit of {0}")]
+    GasTooHigh(u64),
+    #[error("Condition does not belong to a resolved market")]
+    ConditionNotRedeemable,
+    #[error("Forwarder rejected the signature or nonce")]
+    InvalidRequest,
+    #[error("Request was already relayed")]
+    AlreadyRelayed,
+    #[error("Daily relay quota of {0} transactions used up")]
+    QuotaExceeded(i64),
+    #[error("Chain call failed: {0}")]
+    Chain(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl RelayError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            RelayError::NotConfigured => "RELAY_UNAVAILABLE",
+            RelayError::SignerMismatch => "SIGNER_MISMATCH",
+            RelayError::CallNotAllowed => "CALL_NOT_ALLOWED",
+            RelayError::ValueNotAllowed => "VALUE_NOT_ALLOWED",
+            RelayError::GasTooHigh(_) => "GAS_TOO_HIGH",
+            RelayError::ConditionNotRedeemable => "CONDITION_NOT_REDEEMABLE",
+            RelayError::InvalidRequest => "INVALID_REQUEST",
+            RelayError::AlreadyRelayed => "ALREADY_RELAYED",
+            RelayError::QuotaExceeded(_) => "QUOTA_EXCEEDED",
+            RelayError::Chain(_) => "CHAIN_ERROR",
+            RelayError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+/// Decode a forward request's target call, accepting only the allowlisted
+/// calls with spenders / operators of the exchange
+pub fn classify(addresses: &ContractAddresses, to: Address, value: U256, data: &[u8]) -> Result<RelayCall, RelayError> {
+    if !value.is_zero() {
+        return Err(RelayError::ValueNotAllowed);
+    }
+
+    if to == addresses.conditional_tokens {
+        return match ConditionalTokensContractCalls::decode(data) {
+            Ok(ConditionalTokensContractCalls::RedeemPositions(call)) if call.collateral_token == addresses.usdc => {
+                Ok(RelayCall {
+                    kind: RelayKind::RedeemPositions,
+                    condition_id: Some(call.condition_id),
+                })
+            }
+            Ok(ConditionalTokensContractCalls::SetApprovalForAll(call)) if call.operator == addresses.ctf_exchange => {
+                Ok(RelayCall {
+                    kind: RelayKind::ApproveOutcomeTokens,
+                    condition_id: None,
+                })
+            }
+            _ => Err(RelayError::CallNotAllowed),
+        };
+    }
+
+    if to == addresses.usdc {
+        return match MockUSDCContractCalls::decode(data) {
+            Ok(MockUSDCContractCalls::Approve(call))
+                if call.spender == addresses.ctf_exchange || call.spender == addresses.conditional_tokens =>
+            {
+                Ok(RelayCall {
+                    kind: RelayKind::ApproveCollateral,
+                    condition_id: None,
+                })
+            }
+            _ => Err(RelayError::CallNotAllowed),
+        };
+    }
+
+    Err(RelayError::CallNotAllowed)
+}
+
+/// Whether `condition_id` belongs to a resolved market
+pub async fn is_redeemable(pool: &PgPool, condition_id: &[u8; 32]) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM markets WHERE LOWER(condition_id) = $1 AND status = 'resolved')",
+    )
+    .bind(format!("0x{}", hex::encode(condition_id)))
+    .fetch_one(pool)
+    .await
+}
+
+/// A relayed request
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RelayedTransaction {
+    pub id: Uuid,
+    pub kind: String,
+    pub target: String,
+    pub forwarder_nonce: String,
+    pub status: String,
+    pub tx_hash: Option<String>,
+    pub block_number: Option<i64>,
+    pub gas_used: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const RELAYED_COLUMNS: &str = "id, kind, target, forwarder_nonce::text AS forwarder_nonce, status, tx_hash, \
+                               block_number, gas_used::text AS gas_used, error, created_at, updated_at";
+
+/// Relays `user_address` made in the last 24 hours
+pub async fn used_quota(pool: &PgPool, user_address: &UserAddress) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM relayed_transactions WHERE user_address = $1 AND created_at > NOW() - INTERVAL '24 hours'",
+    )
+    .bind(user_address)
+    .fetch_one(pool)
+    .await
+}
+
+/// Record a relay about to be submitted, within the user's quota. The
+/// user's row is locked so concurrent requests cannot both take the last
+/// slot.
+pub async fn reserve(
+    pool: &PgPool,
+    user_address: &UserAddress,
+    call: &RelayCall,
+    target: Address,
+    forwarder_nonce: U256,
+    call_data: &[u8],
+    daily_quota: i64,
+) -> Result<Uuid, RelayError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT 1 FROM users WHERE address = $1 FOR UPDATE")
+        .bind(user_address)
+        .execute(&mut *tx)
+        .await?;
+    let used: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM relayed_transactions WHERE user_address = $1 AND created_at > NOW() - INTERVAL '24 hours'",
+    )
+    .bind(user_address)
+    .fetch_one(&mut *tx)
+    .await?;
+    if used >= daily_quota {
+        return Err(RelayError::QuotaExceeded(daily_quota));
+    }
+
+    let id: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO relayed_transactions (user_address, kind, target, forwarder_nonce, call_data)
+        VALUES ($1, $2, $3, $4::numeric, $5)
+        ON CONFLICT (user_address, forwarder_nonce) WHERE status <> 'failed' DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(user_address)
+    .bind(call.kind.as_str())
+    .bind(format!("{:?}", target))
+    .bind(forwarder_nonce.to_string())
+    .bind(format!("0x{}", hex::encode(call_data)))
+    .fetch_optional(&mut *tx)
+    .await?;
+    let id = id.ok_or(RelayError::AlreadyRelayed)?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Store the outcome of a relay
+pub async fn complete(pool: &PgPool, id: Uuid, result: Result<&TxResult, &str>) -> Result<RelayedTransaction, sqlx::Error> {
+    let (status, tx_hash, block_number, gas_used, error) = match result {
+        Ok(r) => (
+            match r.status {
+                TxStatus::Confirmed => "confirmed",
+                TxStatus::Failed => "failed",
+                TxStatus::Pending => "submitted",
+            },
+            (!r.tx_hash.is_zero()).then(|| format!("{:?}", r.tx_hash)),
+            r.block_number.map(|b| b as i64),
+            r.gas_used.map(|g| g.to_string()),
+            r.error.clone(),
+        ),
+        Err(e) => ("failed", None, None, None, Some(e.to_string())),
+    };
+
+    sqlx::query_as(&format!(
+        r#"
+        UPDATE relayed_transactions
+        SET status = $2, tx_hash = $3, block_number = $4, gas_used = $5::numeric, error = $6, updated_at = NOW()
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        RELAYED_COLUMNS
+    ))
+    .bind(id)
+    .bind(status)
+    .bind(tx_hash)
+    .bind(block_number)
+    .bind(gas_used)
+    .bind(error)
+    .fetch_one(pool)
+    .await
+}
+
+/// Relays of `user_address`, newest first
+pub async fn list(
+    pool: &PgPool,
+    user_address: &UserAddress,
+    limit: i64,
+) -> Result<Vec<RelayedTransaction>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        r#"
+        SELECT {}
+        FROM relayed_transactions
+        WHERE user_address = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+        RELAYED_COLUMNS
+    ))
+    .bind(user_address)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::contracts::conditional_tokens_contract::{RedeemPositionsCall, SetApprovalForAllCall};
+    use crate::blockchain::contracts::mock_usdc_contract::ApproveCall;
+    use ethers::abi::AbiEncode;
+
+    #[test]
+    fn test_classify_allowlist() {
+        let addresses = ContractAddresses::default();
+        let redeem = RedeemPositionsCall {
+            collateral_token: addresses.usdc,
+            parent_collection_id: [0u8; 32],
+            condition_id: [7u8; 32],
+            index_sets: vec![U256::from(1), U256::from(2)],
+        }
+        .encode();
+        assert_eq!(
+            classify(&addresses, addresses.conditional_tokens, U256::zero(), &redeem).unwrap(),
+            RelayCall {
+                kind: RelayKind::RedeemPositions,
+                condition_id: Some([7u8; 32]),
+            }
+        );
+        // Value and wrong targets are refused
+        assert!(matches!(
+            classify(&addresses, addresses.conditional_tokens, U256::one(), &redeem),
+            Err(RelayError::ValueNotAllowed)
+        ));
+        assert!(matches!(
+            classify(&addresses, addresses.usdc, U256::zero(), &redeem),
+            Err(RelayError::CallNotAllowed)
+        ));
+
+        let approve_exchange = ApproveCall {
+            spender: addresses.ctf_exchange,
+            value: U256::MAX,
+        }
+        .encode();
+        assert_eq!(
+            classify(&addresses, addresses.usdc, U256::zero(), &approve_exchange).unwrap().kind,
+            RelayKind::ApproveCollateral
+        );
+        let approve_other = ApproveCall {
+            spender: Address::repeat_byte(9),
+            value: U256::MAX,
+        }
+        .encode();
+        assert!(classify(&addresses, addresses.usdc, U256::zero(), &approve_other).is_err());
+
+        let approve_outcomes = SetApprovalForAllCall {
+            operator: addresses.ctf_exchange,
+            approved: true,
+        }
+        .encode();
+        assert_eq!(
+            classify(&addresses, addresses.conditional_tokens, U256::zero(), &approve_outcomes)
+                .unwrap()
+                .kind,
+            RelayKind::ApproveOutcomeTokens
+        );
+    }
+}
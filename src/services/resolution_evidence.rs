@@ -0,0 +1,189 @@
+//! Market Resolution Evidence
+//!
+//! Each resolution path stores what it was based on next to the market:
+//! admins their written justification, the UMA path the settled assertion
+//! (claim, asserter, bond, liveness) and the on-chain path the condition's
+//! payout vector, each with the transaction hashes involved. The bundle is
+//! public, so anyone can check an outcome against its source instead of
+//! taking the exchange's word for it.
+//!
+//! The first evidence recorded for a market is kept; a market only
+//! resolves once.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// How a market was resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResolutionMethod {
+    /// Admin endpoint or operator CLI, with a justification
+    Admin,
+    /// Settled UMA Optimistic Oracle assertion
+    Uma,
+    /// ConditionResolution event of the CTF contract
+    Onchain,
+}
+
+impl ResolutionMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResolutionMethod::Admin => "admin",
+            ResolutionMethod::Uma => "uma",
+            ResolutionMethod::Onchain => "onchain",
+        }
+    }
+}
+
+/// Evidence of one resolution, as recorded by its resolution path
+#[derive(Debug, Clone)]
+pub struct ResolutionEvidence {
+    pub method: ResolutionMethod,
+    pub winning_outcome_id: Option<Uuid>,
+    pub resolved_by: Option<String>,
+    pub justification: Option<String>,
+    pub oracle_data: serde_json::Value,
+    pub tx_hashes: Vec<String>,
+}
+
+/// Stored evidence of a market
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EvidenceRecord {
+    pub method: String,
+    pub resolved_by: Option<String>,
+    pub justification: Option<String>,
+    pub oracle_data: serde_json::Value,
+    pub tx_hashes: Vec<String>,
+    pub resolved_at: DateTime<Utc>,
+}
+
+/// Resolution of a market with its full provenance
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ResolutionBundle {
+    pub market_id: Uuid,
+    pub question: String,
+    pub status: String,
+    pub resolution_source: String,
+    pub winning_outcome_id: Option<Uuid>,
+    pub winning_outcome: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// None for markets resolved before evidence was recorded
+    #[sqlx(skip)]
+    pub evidence: Option<EvidenceRecord>,
+}
+
+/// Store the evidence of `market_id`'s resolution. Returns false if the
+/// market already has evidence on record.
+pub async fn record(
+    conn: &mut PgConnection,
+    market_id: Uuid,
+    evidence: &ResolutionEvidence,
+) -> Result<bool, sqlx::Error> {
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO market_resolution_evidence (
+            market_id, method, winning_outcome_id, resolved_by, justification, oracle_data, tx_hashes
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (market_id) DO NOTHING
+        "#,
+    )
+    .bind(market_id)
+    .bind(evidence.method.as_str())
+    .bind(evidence.winning_outcome_id)
+    .bind(&evidence.resolved_by)
+    .bind(&evidence.justification)
+    .bind(&evidence.oracle_data)
+    .bind(&evidence.tx_hashes)
+    .execute(conn)
+    .await?
+    .rows_affected();
+    Ok(inserted > 0)
+}
+
+/// Evidence of a settled UMA assertion, read from its stored record
+pub async fn uma_evidence(conn: &mut PgConnection, assertion_id: &str) -> Result<Option<ResolutionEvidence>, sqlx::Error> {
+    let row: Option<UmaAssertionRow> = sqlx::query_as(
+        r#"
+        SELECT outcome_id, claim, asserter, assertion_time, expiration_time, bond_amount,
+               status, tx_hash, settlement_tx_hash, settled_at, disputed_at, disputer
+        FROM market_assertions
+        WHERE assertion_id = $1
+        "#,
+    )
+    .bind(assertion_id)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(row.map(|a| ResolutionEvidence {
+        method: ResolutionMethod::Uma,
+        winning_outcome_id: Some(a.outcome_id),
+        resolved_by: None,
+        justification: None,
+        oracle_data: serde_json::json!({
+            "assertion_id": assertion_id,
+            "claim": a.claim,
+            "asserter": a.asserter,
+            "bond_amount": a.bond_amount,
+            "assertion_time": a.assertion_time,
+            "expiration_time": a.expiration_time,
+            "status": a.status,
+            "settled_at": a.settled_at,
+            "disputed_at": a.disputed_at,
+            "disputer": a.disputer,
+        }),
+        tx_hashes: [a.tx_hash, a.settlement_tx_hash].into_iter().flatten().collect(),
+    }))
+}
+
+#[derive(sqlx::FromRow)]
+struct UmaAssertionRow {
+    outcome_id: Uuid,
+    claim: String,
+    asserter: String,
+    assertion_time: DateTime<Utc>,
+    expiration_time: DateTime<Utc>,
+    bond_amount: String,
+    status: String,
+    tx_hash: Option<String>,
+    settlement_tx_hash: Option<String>,
+    settled_at: Option<DateTime<Utc>>,
+    disputed_at: Option<DateTime<Utc>>,
+    disputer: Option<String>,
+}
+
+/// Resolution bundle of `market_id`; None if the market does not exist
+pub async fn bundle(pool: &PgPool, market_id: Uuid) -> Result<Option<ResolutionBundle>, sqlx::Error> {
+    let bundle: Option<ResolutionBundle> = sqlx::query_as(
+        r#"
+        SELECT m.id AS market_id, m.question, m.status::text AS status, m.resolution_source,
+               m.winning_outcome_id, o.name AS winning_outcome,
+               m.created_at, m.end_time, m.resolved_at
+        FROM markets m
+        LEFT JOIN outcomes o ON o.id = m.winning_outcome_id
+        WHERE m.id = $1
+        "#,
+    )
+    .bind(market_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some(mut bundle) = bundle else {
+        return Ok(None);
+    };
+
+    bundle.evidence = sqlx::query_as(
+        r#"
+        SELECT method, resolved_by, justification, oracle_data, tx_hashes, resolved_at
+        FROM market_resolution_evidence
+        WHERE market_id = $1
+        "#,
+    )
+    .bind(market_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(Some(bundle))
+}
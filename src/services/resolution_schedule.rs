@@ -0,0 +1,465 @@
+//! Market Resolution Scheduling
+//!
+//! Markets with a `resolution_time` are walked towards resolution by a
+//! leader-only background job. 24 hours and 1 hour before the deadline,
+//! everyone watching the market or holding open orders in it is reminded
+//! through their webhooks and email. Once the deadline passes, an active
+//! market moves to `awaiting_resolution`: new orders are refused, but
+//! resting orders can still be cancelled until the market is resolved.
+//!
+//! Each reminder is claimed by inserting its `market_resolution_reminders`
+//! row before anything is sent, so it goes out at most once per deadline
+//! even across leader changes. Rescheduling clears the claims.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::leader_election::LeaderElection;
+use crate::services::notification::{NotificationKind, NotificationService};
+use crate::services::system_events::{self, SystemEventKind};
+use crate::services::webhook::{WebhookEventType, WebhookService};
+use crate::utils::clock::{Clock, SystemClock};
+
+/// How often deadlines are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long before the resolution time a reminder goes out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReminderLead {
+    Day,
+    Hour,
+}
+
+impl ReminderLead {
+    /// Longest lead first
+    pub const ALL: [ReminderLead; 2] = [ReminderLead::Day, ReminderLead::Hour];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReminderLead::Day => "24h",
+            ReminderLead::Hour => "1h",
+        }
+    }
+
+    pub fn before(&self) -> chrono::Duration {
+        match self {
+            ReminderLead::Day => chrono::Duration::hours(24),
+            ReminderLead::Hour => chrono::Duration::hours(1),
+        }
+    }
+
+    /// The next shorter lead, whose reminder supersedes this one once due
+    fn next(&self) -> Option<ReminderLead> {
+        match self {
+            ReminderLead::Day => Some(ReminderLead::Hour),
+            ReminderLead::Hour => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ReminderLead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What one scheduler pass did
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TickSummary {
+    /// Reminders sent, one per market and lead
+    pub reminders: usize,
+    /// Users reminded across all of them
+    pub recipients: usize,
+    /// Markets moved to `awaiting_resolution`
+    pub awaiting: Vec<Uuid>,
+}
+
+/// Sends pre-resolution reminders and closes markets past their deadline
+pub struct ResolutionScheduler {
+    pool: PgPool,
+    webhooks: Arc<WebhookService>,
+    notifications: Arc<NotificationService>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ResolutionScheduler {
+    pub fn new(pool: PgPool, webhooks: Arc<WebhookService>, notifications: Arc<NotificationService>) -> Self {
+        Self {
+            pool,
+            webhooks,
+            notifications,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read deadlines against `clock` instead of the system clock
+    #[cfg(test)]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Spawn the scheduling loop; only the elected leader acts
+    pub fn start(self: Arc<Self>, leader: Arc<LeaderElection>) {
+        tokio::spawn(async move {
+            tracing::info!("Resolution scheduler started");
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if !leader.is_leader() {
+                    continue;
+                }
+                match self.tick().await {
+                    Ok(summary) if summary.reminders > 0 || !summary.awaiting.is_empty() => tracing::info!(
+                        "Sent {} resolution reminders to {} users, {} markets awaiting resolution",
+                        summary.reminders,
+                        summary.recipients,
+                        summary.awaiting.len()
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("Resolution scheduling failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Send the reminders that are due, then move active markets past their
+    /// resolution time to `awaiting_resolution`
+    pub async fn tick(&self) -> Result<TickSummary, sqlx::Error> {
+        let now = self.clock.now();
+        let mut summary = TickSummary::default();
+
+        for lead in ReminderLead::ALL {
+            // A market already inside a shorter lead only gets that reminder
+            let window_start = now + lead.next().map(|l| l.before()).unwrap_or_else(chrono::Duration::zero);
+            let due: Vec<(Uuid, String, DateTime<Utc>)> = sqlx::query_as(
+                r#"
+                SELECT m.id, m.question, m.resolution_time
+                FROM markets m
+                WHERE m.status = 'active'
+                  AND m.resolution_time > $1
+                  AND m.resolution_time <= $2
+                  AND NOT EXISTS (
+                      SELECT 1 FROM market_resolution_reminders r
+                      WHERE r.market_id = m.id AND r.lead = $3
+                  )
+                "#,
+            )
+            .bind(window_start)
+            .bind(now + lead.before())
+            .bind(lead.as_str())
+            .fetch_all(&self.pool)
+            .await?;
+
+            for (market_id, question, resolution_time) in due {
+                if let Some(recipients) = self.remind(market_id, &question, resolution_time, lead).await? {
+                    summary.reminders += 1;
+                    summary.recipients += recipients;
+                }
+            }
+        }
+
+        let closed: Vec<(Uuid, String, DateTime<Utc>)> = sqlx::query_as(
+            r#"
+            UPDATE markets
+            SET status = 'awaiting_resolution'
+            WHERE status = 'active' AND resolution_time <= $1
+            RETURNING id, question, resolution_time
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for (market_id, question, resolution_time) in closed {
+            system_events::record(
+                &self.pool,
+                SystemEventKind::MarketAwaitingResolution,
+                Some(&market_id.to_string()),
+                format!("Market \"{}\" reached its resolution time; trading stopped", question),
+                serde_json::json!({ "resolution_time": resolution_time.to_rfc3339() }),
+            )
+            .await;
+            summary.awaiting.push(market_id);
+        }
+
+        Ok(summary)
+    }
+
+    /// Claim and send one reminder, returning how many users it reached, or
+    /// `None` if another pass already claimed it
+    async fn remind(
+        &self,
+        market_id: Uuid,
+        question: &str,
+        resolution_time: DateTime<Utc>,
+        lead: ReminderLead,
+    ) -> Result<Option<usize>, sqlx::Error> {
+        let claimed = sqlx::query(
+            r#"
+            INSERT INTO market_resolution_reminders (market_id, lead)
+            VALUES ($1, $2)
+            ON CONFLICT (market_id, lead) DO NOTHING
+            "#,
+        )
+        .bind(market_id)
+        .bind(lead.as_str())
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            > 0;
+        if !claimed {
+            return Ok(None);
+        }
+
+        let recipients = recipients(&self.pool, market_id).await?;
+        let data = serde_json::json!({
+            "market_id": market_id,
+            "question": question,
+            "lead": lead.as_str(),
+            "resolution_time": resolution_time.to_rfc3339(),
+        });
+        for user in &recipients {
+            self.webhooks
+                .dispatch(WebhookEventType::ResolutionReminder, Some(user), data.clone())
+                .await;
+            self.notifications
+                .notify(NotificationKind::ResolutionReminder, user, None, data.clone())
+                .await;
+        }
+
+        sqlx::query("UPDATE market_resolution_reminders SET recipients = $3 WHERE market_id = $1 AND lead = $2")
+            .bind(market_id)
+            .bind(lead.as_str())
+            .bind(recipients.len() as i32)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Some(recipients.len()))
+    }
+}
+
+/// Watchers of `market_id` and users with open orders in it
+pub async fn recipients(pool: &PgPool, market_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT user_address FROM market_watchers WHERE market_id = $1
+        UNION
+        SELECT user_address FROM orders
+        WHERE market_id = $1 AND status IN ('pending', 'open', 'partially_filled')
+        ORDER BY 1
+        "#,
+    )
+    .bind(market_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// A market on a user's watchlist
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct WatchedMarket {
+    pub market_id: Uuid,
+    pub question: String,
+    pub status: String,
+    pub resolution_time: Option<DateTime<Utc>>,
+    pub watched_at: DateTime<Utc>,
+}
+
+/// Add `market_id` to the watchlist of `user_address`; `false` if the
+/// market doesn't exist. Watching twice is a no-op.
+pub async fn watch(pool: &PgPool, market_id: Uuid, user_address: &Address) -> Result<bool, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM markets WHERE id = $1)")
+        .bind(market_id)
+        .fetch_one(pool)
+        .await?;
+    if exists {
+        sqlx::query(
+            r#"
+            INSERT INTO market_watchers (market_id, user_address)
+            VALUES ($1, $2)
+            ON CONFLICT (market_id, user_address) DO NOTHING
+            "#,
+        )
+        .bind(market_id)
+        .bind(user_address)
+        .execute(pool)
+        .await?;
+    }
+    Ok(exists)
+}
+
+/// Remove `market_id` from the watchlist of `user_address`; `false` if it
+/// wasn't on it
+pub async fn unwatch(pool: &PgPool, market_id: Uuid, user_address: &Address) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM market_watchers WHERE market_id = $1 AND user_address = $2")
+        .bind(market_id)
+        .bind(user_address)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Markets watched by `user_address`, soonest resolution first
+pub async fn watchlist(pool: &PgPool, user_address: &Address) -> Result<Vec<WatchedMarket>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT w.market_id, m.question, m.status::text AS status, m.resolution_time, w.created_at AS watched_at
+        FROM market_watchers w
+        JOIN markets m ON m.id = w.market_id
+        WHERE w.user_address = $1
+        ORDER BY m.resolution_time ASC NULLS LAST, w.created_at DESC
+        "#,
+    )
+    .bind(user_address)
+    .fetch_all(pool)
+    .await
+}
+
+/// Set or clear the resolution time of `market_id`.
+///
+/// Reminders already sent for the previous deadline are forgotten, and a
+/// market that was awaiting resolution reopens for trading when the new
+/// deadline is in the future. Returns the market's status afterwards, or
+/// `None` if it doesn't exist.
+pub async fn reschedule(
+    pool: &PgPool,
+    market_id: Uuid,
+    resolution_time: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Option<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let status: Option<String> = sqlx::query_scalar(
+        r#"
+        UPDATE markets
+        SET resolution_time = $2,
+            status = CASE
+                WHEN status = 'awaiting_resolution' AND ($2::timestamptz IS NULL OR $2 > $3) THEN 'active'
+                ELSE status
+            END
+        WHERE id = $1
+        RETURNING status::text
+        "#,
+    )
+    .bind(market_id)
+    .bind(resolution_time)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if status.is_some() {
+        sqlx::query("DELETE FROM market_resolution_reminders WHERE market_id = $1")
+            .bind(market_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    async fn reminders(pool: &PgPool, market_id: Uuid) -> Vec<(String, i32)> {
+        sqlx::query_as("SELECT lead, recipients FROM market_resolution_reminders WHERE market_id = $1 ORDER BY sent_at, lead")
+            .bind(market_id)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn status(pool: &PgPool, market_id: Uuid) -> String {
+        sqlx::query_scalar("SELECT status::text FROM markets WHERE id = $1")
+            .bind(market_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_leads_longest_first() {
+        let befores: Vec<_> = ReminderLead::ALL.iter().map(|l| l.before()).collect();
+        assert!(befores.windows(2).all(|w| w[0] > w[1]));
+        assert_eq!(ReminderLead::Day.next(), Some(ReminderLead::Hour));
+        assert_eq!(ReminderLead::Hour.next(), None);
+    }
+
+    #[tokio::test]
+    async fn test_reminders_then_awaiting_resolution() {
+        let app = TestApp::builder().build().await;
+        let pool = &app.db.pool;
+        let scheduler = ResolutionScheduler::new(
+            pool.clone(),
+            app.state.webhook_service.clone(),
+            app.state.notification_service.clone(),
+        )
+        .with_clock(app.clock.clone());
+
+        let (market_id, yes_id, _) = app.create_market().await;
+        let deadline = app.clock.now() + chrono::Duration::hours(48);
+        reschedule(pool, market_id, Some(deadline), app.clock.now()).await.unwrap();
+
+        let watcher = &"0x00000000000000000000000000000000000000a1".parse::<Address>().unwrap();
+        let trader = "0x00000000000000000000000000000000000000b2";
+        assert!(watch(pool, market_id, watcher).await.unwrap());
+        assert!(watch(pool, market_id, watcher).await.unwrap());
+        assert!(!watch(pool, Uuid::new_v4(), watcher).await.unwrap());
+        let watched = watchlist(pool, watcher).await.unwrap();
+        assert_eq!(watched.len(), 1);
+        assert_eq!(watched[0].resolution_time, Some(deadline));
+        sqlx::query(
+            r#"
+            INSERT INTO orders (id, user_address, market_id, outcome_id, share_type, symbol, side, order_type, price, amount, status, signature)
+            VALUES ($1, $2, $3, $4, 'yes', 'test', 'buy', 'limit', 0.5, 10, 'open', '0x')
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(trader)
+        .bind(market_id)
+        .bind(yes_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        assert_eq!(recipients(pool, market_id).await.unwrap(), vec![watcher.as_str(), trader]);
+
+        // Nothing is due two days out
+        assert_eq!(scheduler.tick().await.unwrap(), TickSummary::default());
+
+        app.clock.advance(chrono::Duration::hours(25));
+        let summary = scheduler.tick().await.unwrap();
+        assert_eq!((summary.reminders, summary.recipients), (1, 2));
+        // Each reminder is sent once
+        assert_eq!(scheduler.tick().await.unwrap().reminders, 0);
+
+        app.clock.advance(chrono::Duration::minutes(22 * 60 + 30));
+        assert_eq!(scheduler.tick().await.unwrap().reminders, 1);
+        assert_eq!(
+            reminders(pool, market_id).await,
+            vec![("24h".to_string(), 2), ("1h".to_string(), 2)]
+        );
+        assert_eq!(status(pool, market_id).await, "active");
+
+        app.clock.advance(chrono::Duration::minutes(30));
+        let summary = scheduler.tick().await.unwrap();
+        assert_eq!(summary.awaiting, vec![market_id]);
+        assert_eq!(status(pool, market_id).await, "awaiting_resolution");
+
+        // Pushing the deadline out reopens the market and re-arms reminders
+        reschedule(pool, market_id, Some(app.clock.now() + chrono::Duration::minutes(30)), app.clock.now())
+            .await
+            .unwrap();
+        assert_eq!(status(pool, market_id).await, "active");
+        assert!(reminders(pool, market_id).await.is_empty());
+        // Only the shorter lead is due for a deadline 30 minutes out
+        assert_eq!(scheduler.tick().await.unwrap().reminders, 1);
+        assert_eq!(reminders(pool, market_id).await, vec![("1h".to_string(), 2)]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Retry Policy
+//!
+//! Exponential backoff shared by the workers that retry failed items from a
+//! queue table: webhook deliveries, notifications, channel messages and
+//! trade persists.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+/// How often and how far apart a worker retries a failed item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Failed attempts before an item is given up on
+    pub max_attempts: i32,
+    /// Base backoff delay (doubles on each attempt)
+    pub base_backoff_secs: u64,
+    /// Maximum backoff delay
+    pub max_backoff_secs: u64,
+}
+
+impl RetryPolicy {
+    pub const fn new(max_attempts: i32, base_backoff_secs: u64, max_backoff_secs: u64) -> Self {
+        Self {
+            max_attempts,
+            base_backoff_secs,
+            max_backoff_secs,
+        }
+    }
+
+    /// Delay before the next attempt after `attempts` failed attempts
+    pub fn backoff(&self, attempts: i32) -> Duration {
+        let exp = attempts.clamp(0, 20) as u32;
+        let secs = self.base_backoff_secs.saturating_mul(1u64 << exp);
+        Duration::from_secs(secs.min(self.max_backoff_secs))
+    }
+
+    /// Whether `attempts` failed attempts use up the budget
+    pub fn exhausted(&self, attempts: i32) -> bool {
+        attempts >= self.max_attempts
+    }
+
+    /// When to attempt again after `attempts` failed attempts
+    pub fn next_attempt_at(&self, attempts: i32) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::from_std(self.backoff(attempts)).unwrap_or_else(|_| chrono::Duration::hours(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = RetryPolicy::new(3, 10, 3600);
+        assert_eq!(policy.backoff(0), Duration::from_secs(10));
+        assert_eq!(policy.backoff(3), Duration::from_secs(80));
+        assert_eq!(policy.backoff(15), Duration::from_secs(3600));
+        assert_eq!(policy.backoff(i32::MAX), Duration::from_secs(3600));
+        assert!(!policy.exhausted(2));
+        assert!(policy.exhausted(3));
+    }
+}
@@ -0,0 +1,307 @@
+//! Liquidity Rewards Scoring
+//!
+//! Incentive campaigns pay makers for volume and for quoting at the top of
+//! the book. This job keeps `market_reward_scores` current, per market and
+//! address:
+//!
+//! - maker and taker volume, recomputed from `trades` for every market that
+//!   traded since the previous pass (all markets after gaining leadership),
+//!   like the analytics rollup;
+//! - time at the best quote: each pass takes the resting limit orders at the
+//!   best bid and best ask of every book and credits their owners one
+//!   sampling interval. Owners quoting on several books of a market are
+//!   credited once.
+//!
+//! Books are read from open orders rather than the engine so that worker
+//! processes without a live engine can score. Leaderboards only name
+//! addresses whose owners opted in (`users.leaderboard_opt_in`).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::leader_election::LeaderElection;
+
+/// How often scores are updated; also the time credited per sample
+pub const SCORING_INTERVAL_SECS: u64 = 60;
+
+/// Markets traded within this many minutes are rescored on incremental
+/// passes; covers trades persisted late
+const VOLUME_REFRESH_MINUTES: i64 = 10;
+
+/// Leaderboard ordering
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardSort {
+    #[default]
+    MakerVolume,
+    TakerVolume,
+    TimeAtBest,
+}
+
+impl LeaderboardSort {
+    fn order_by(&self) -> &'static str {
+        match self {
+            LeaderboardSort::MakerVolume => "s.maker_volume DESC",
+            LeaderboardSort::TakerVolume => "s.taker_volume DESC",
+            LeaderboardSort::TimeAtBest => "s.seconds_at_best DESC",
+        }
+    }
+}
+
+/// One address on a market's leaderboard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    /// `None` unless the owner opted in to being shown
+    pub address: Option<String>,
+    pub maker_volume: Decimal,
+    pub taker_volume: Decimal,
+    pub seconds_at_best: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Leaderboard {
+    pub market_id: Uuid,
+    pub sort: LeaderboardSort,
+    /// Last time any score of the market changed (ms)
+    pub updated_at: Option<i64>,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ScoreRow {
+    user_address: String,
+    opt_in: bool,
+    maker_volume: Decimal,
+    taker_volume: Decimal,
+    seconds_at_best: i64,
+    updated_at: DateTime<Utc>,
+}
+
+/// Background job maintaining `market_reward_scores`
+pub struct RewardsScoringJob {
+    pool: PgPool,
+}
+
+impl RewardsScoringJob {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Spawn the scoring loop; only the elected leader scores
+    pub fn start(self: Arc<Self>, leader: Arc<LeaderElection>) {
+        tokio::spawn(async move {
+            tracing::info!("Rewards scoring job started");
+            let mut interval = tokio::time::interval(Duration::from_secs(SCORING_INTERVAL_SECS));
+            let mut full_rescore_done = false;
+            loop {
+                interval.tick().await;
+                if !leader.is_leader() {
+                    full_rescore_done = false;
+                    continue;
+                }
+                if let Err(e) = self.sample_quotes(SCORING_INTERVAL_SECS as i64).await {
+                    tracing::error!("Rewards quote sampling failed: {}", e);
+                }
+                let since = full_rescore_done.then(|| Utc::now() - ChronoDuration::minutes(VOLUME_REFRESH_MINUTES));
+                match self.score_volume(since).await {
+                    Ok(rows) => {
+                        full_rescore_done = true;
+                        tracing::debug!("Rescored volume of {} market participants", rows);
+                    }
+                    Err(e) => tracing::error!("Rewards volume scoring failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Credit `seconds` to every address resting an order at the best bid
+    /// or ask of a book. Returns the number of (market, address) pairs
+    /// credited.
+    pub async fn sample_quotes(&self, seconds: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            WITH resting AS (
+                SELECT market_id, outcome_id, share_type, side, price, user_address
+                FROM orders
+                WHERE status IN ('open', 'partially_filled')
+                  AND order_type = 'limit'
+                  AND market_id IS NOT NULL
+                  AND amount > filled_amount
+                  AND position('/' IN symbol) = 0
+            ),
+            best AS (
+                SELECT market_id, outcome_id, share_type, side,
+                       CASE WHEN side = 'buy' THEN MAX(price) ELSE MIN(price) END AS price
+                FROM resting
+                GROUP BY market_id, outcome_id, share_type, side
+            )
+            INSERT INTO market_reward_scores (market_id, user_address, seconds_at_best, updated_at)
+            SELECT DISTINCT r.market_id, r.user_address, $1::bigint, NOW()
+            FROM resting r
+            JOIN best b
+              ON b.market_id = r.market_id AND b.outcome_id = r.outcome_id
+             AND b.share_type = r.share_type AND b.side = r.side AND b.price = r.price
+            ON CONFLICT (market_id, user_address) DO UPDATE SET
+                seconds_at_best = market_reward_scores.seconds_at_best + EXCLUDED.seconds_at_best,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(seconds)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Recompute maker and taker volume of every market with a trade at or
+    /// after `since` (all markets when `None`). Returns the number of
+    /// scores that changed.
+    pub async fn score_volume(&self, since: Option<DateTime<Utc>>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            WITH scored AS (
+                SELECT DISTINCT market_id
+                FROM trades
+                WHERE market_id IS NOT NULL
+                  AND ($1::timestamptz IS NULL OR created_at >= $1)
+            ),
+            volume AS (
+                SELECT t.market_id, v.address,
+                       SUM(v.maker) AS maker_volume, SUM(v.taker) AS taker_volume
+                FROM trades t
+                JOIN scored s ON s.market_id = t.market_id
+                CROSS JOIN LATERAL (VALUES
+                    (t.maker_address, trade_effective_notional(t), 0::numeric),
+                    (t.taker_address, 0::numeric, trade_effective_notional(t))
+                ) AS v(address, maker, taker)
+                GROUP BY t.market_id, v.address
+            )
+            INSERT INTO market_reward_scores (market_id, user_address, maker_volume, taker_volume, updated_at)
+            SELECT market_id, address, maker_volume, taker_volume, NOW()
+            FROM volume
+            ON CONFLICT (market_id, user_address) DO UPDATE SET
+                maker_volume = EXCLUDED.maker_volume,
+                taker_volume = EXCLUDED.taker_volume,
+                updated_at = NOW()
+            WHERE (market_reward_scores.maker_volume, market_reward_scores.taker_volume)
+                  IS DISTINCT FROM (EXCLUDED.maker_volume, EXCLUDED.taker_volume)
+            "#,
+        )
+        .bind(since)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// The top `limit` scores of `market_id`, addresses of users who have not
+/// opted in withheld
+pub async fn leaderboard(
+    pool: &PgPool,
+    market_id: Uuid,
+    sort: LeaderboardSort,
+    limit: i64,
+) -> Result<Leaderboard, sqlx::Error> {
+    let rows: Vec<ScoreRow> = sqlx::query_as(&format!(
+        r#"
+        SELECT s.user_address, COALESCE(u.leaderboard_opt_in, FALSE) AS opt_in,
+               s.maker_volume, s.taker_volume, s.seconds_at_best, s.updated_at
+        FROM market_reward_scores s
+        LEFT JOIN users u ON u.address = s.user_address
+        WHERE s.market_id = $1
+        ORDER BY {}, s.user_address
+        LIMIT $2
+        "#,
+        sort.order_by()
+    ))
+    .bind(market_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Leaderboard {
+        market_id,
+        sort,
+        updated_at: rows.iter().map(|r| r.updated_at.timestamp_millis()).max(),
+        entries: rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| LeaderboardEntry {
+                rank: i as i64 + 1,
+                address: row.opt_in.then_some(row.user_address),
+                maker_volume: row.maker_volume,
+                taker_volume: row.taker_volume,
+                seconds_at_best: row.seconds_at_best,
+            })
+            .collect(),
+    })
+}
+
+/// Whether `user_address` is named on leaderboards
+pub async fn display(pool: &PgPool, user_address: &Address) -> Result<Option<bool>, sqlx::Error> {
+    sqlx::query_scalar("SELECT leaderboard_opt_in FROM users WHERE address = $1")
+        .bind(user_address)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Opt `user_address` in to or out of being named on leaderboards.
+/// Returns `false` if the user does not exist.
+pub async fn set_display(pool: &PgPool, user_address: &Address, opt_in: bool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE users SET leaderboard_opt_in = $2, updated_at = NOW() WHERE address = $1")
+        .bind(user_address)
+        .bind(opt_in)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::models::OrderSide;
+    use crate::test_support::{account, gateway_order, TestApp, MAKER, TAKER};
+
+    #[tokio::test]
+    async fn test_market_leaderboard() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+        let gateway = &app.state.order_gateway;
+        gateway.place(gateway_order(trade.market_id, trade.outcome_id, TAKER, OrderSide::Buy, dec!(0.4))).await.unwrap();
+        let pool = &app.state.db.pool;
+        for (user, opt_in) in [(MAKER, true), (TAKER, false)] {
+            sqlx::query("INSERT INTO users (address, nonce) VALUES ($1, 1) ON CONFLICT (address) DO NOTHING")
+                .bind(account(user))
+                .execute(pool)
+                .await
+                .unwrap();
+            assert!(set_display(pool, &account(user), opt_in).await.unwrap());
+        }
+
+        let job = RewardsScoringJob::new(pool.clone());
+        assert_eq!(job.sample_quotes(60).await.unwrap(), 1);
+        job.score_volume(None).await.unwrap();
+        // Unchanged volume is not rewritten
+        assert_eq!(job.score_volume(None).await.unwrap(), 0);
+
+        let board = leaderboard(pool, trade.market_id, LeaderboardSort::MakerVolume, 10).await.unwrap();
+        assert_eq!(board.entries.len(), 2);
+        let (maker, taker) = (&board.entries[0], &board.entries[1]);
+        assert_eq!((maker.rank, maker.address.as_deref()), (1, Some(account(MAKER).as_str())));
+        assert_eq!((maker.maker_volume, maker.taker_volume, maker.seconds_at_best), (dec!(5), dec!(0), 0));
+        assert_eq!((taker.rank, taker.address.as_deref()), (2, None));
+        assert_eq!((taker.maker_volume, taker.taker_volume, taker.seconds_at_best), (dec!(0), dec!(5), 60));
+
+        let board = leaderboard(pool, trade.market_id, LeaderboardSort::TimeAtBest, 1).await.unwrap();
+        assert_eq!(board.entries[0].seconds_at_best, 60);
+    }
+}
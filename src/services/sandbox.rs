@@ -0,0 +1,536 @@
+//! Sandbox Market Seeding
+//!
+//! Creates demo markets for staging and demo environments: the market and
+//! its outcomes plus a random-walk trade history go through the historical
+//! import ([`BackfillService`]), so volume stats, analytics and candles
+//! (derived from `trades`) come out exactly like imported data. A sandbox
+//! maker account is funded and quotes a ladder of bids on both outcomes
+//! around the final price; a No bid at `q` is a Yes ask at `1 - q` through
+//! complement matching, so the book is two-sided without minting shares.
+//!
+//! With a running engine the ladder is placed through the order gateway
+//! (source `sandbox`). Without one (the CLI) the orders are written as open
+//! rows and reach the books when the API servers next restore open orders.
+
+use chrono::{DateTime, Duration, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::validation;
+use crate::models::market::ShareType;
+use crate::models::{OrderSide, OrderType};
+use crate::services::backfill::{BackfillService, ImportBundle, ImportIssue, ImportMarket, ImportOutcome, ImportTrade};
+use crate::services::ledger::{self, LedgerEntry, LedgerEntryType};
+use crate::services::matching::precision::Collateral;
+use crate::services::order_gateway::{GatewayOrder, OrderGateway, OrderGatewayError, OrderSource};
+
+/// Account quoting the synthetic liquidity
+pub const SANDBOX_MAKER_ADDRESS: &str = "0x5a4d0000000000000000000000000000000000a1";
+/// Counterparty of the synthetic trade history
+pub const SANDBOX_TAKER_ADDRESS: &str = "0x5a4d0000000000000000000000000000000000b2";
+
+/// Price step of the walk and the ladder
+const TICK: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+/// The walk stays inside [MIN_WALK, 1 - MIN_WALK]
+const MIN_WALK: Decimal = Decimal::from_parts(2, 0, 0, false, 2);
+/// Upper bound on generated trades
+const MAX_TRADES: u32 = 100_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SandboxError {
+    #[error("Invalid parameters: {0}")]
+    InvalidParams(String),
+    #[error("Generated market failed import validation")]
+    Import(Vec<ImportIssue>),
+    #[error("Failed to place liquidity: {0}")]
+    Liquidity(#[from] OrderGatewayError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl SandboxError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SandboxError::InvalidParams(_) => "INVALID_PARAMS",
+            SandboxError::Import(_) => "IMPORT_FAILED",
+            SandboxError::Liquidity(_) => "LIQUIDITY_FAILED",
+            SandboxError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+/// Shape of a sandbox market
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SandboxParams {
+    #[validate(length(min = 1))]
+    pub question: String,
+    #[serde(default = "default_category")]
+    pub category: String,
+    /// Yes price the trade history starts at
+    #[serde(default = "default_start_price")]
+    #[validate(custom = "validation::price")]
+    pub start_price: Decimal,
+    /// Standard deviation of each step of the price walk
+    #[serde(default = "default_volatility")]
+    #[validate(range(min = 0.0))]
+    pub volatility: f64,
+    /// Distance between the best Yes bid and the best Yes ask
+    #[serde(default = "default_spread")]
+    #[validate(custom = "validation::positive")]
+    pub spread: Decimal,
+    #[serde(default = "default_history_days")]
+    pub history_days: i64,
+    #[serde(default = "default_trades_per_day")]
+    pub trades_per_day: u32,
+    /// Price levels quoted on each outcome
+    #[serde(default = "default_levels")]
+    pub levels: u32,
+    /// Shares per price level
+    #[serde(default = "default_level_size")]
+    #[validate(custom = "validation::positive")]
+    pub level_size: Decimal,
+    /// Defaults to 30 days from now
+    pub end_time: Option<DateTime<Utc>>,
+    /// Fixes the generated history
+    pub seed: Option<u64>,
+}
+
+fn default_category() -> String {
+    "sandbox".to_string()
+}
+
+fn default_start_price() -> Decimal {
+    Decimal::new(5, 1)
+}
+
+fn default_volatility() -> f64 {
+    0.02
+}
+
+fn default_spread() -> Decimal {
+    Decimal::new(2, 2)
+}
+
+fn default_history_days() -> i64 {
+    7
+}
+
+fn default_trades_per_day() -> u32 {
+    48
+}
+
+fn default_levels() -> u32 {
+    5
+}
+
+fn default_level_size() -> Decimal {
+    Decimal::from(100)
+}
+
+impl SandboxParams {
+    pub fn new(question: impl Into<String>) -> Self {
+        Self {
+            question: question.into(),
+            category: default_category(),
+            start_price: default_start_price(),
+            volatility: default_volatility(),
+            spread: default_spread(),
+            history_days: default_history_days(),
+            trades_per_day: default_trades_per_day(),
+            levels: default_levels(),
+            level_size: default_level_size(),
+            end_time: None,
+            seed: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), SandboxError> {
+        let invalid = |msg: &str| Err(SandboxError::InvalidParams(msg.to_string()));
+        if self.question.trim().is_empty() {
+            return invalid("question is required");
+        }
+        if self.start_price < MIN_WALK || self.start_price > Decimal::ONE - MIN_WALK {
+            return invalid("start_price must be between 0.02 and 0.98");
+        }
+        if !(0.0..=0.25).contains(&self.volatility) {
+            return invalid("volatility must be between 0 and 0.25");
+        }
+        if self.spread < TICK || self.spread > Decimal::new(5, 1) {
+            return invalid("spread must be between 0.01 and 0.5");
+        }
+        if !(0..=365).contains(&self.history_days) {
+            return invalid("history_days must be between 0 and 365");
+        }
+        if self.history_days as u64 * self.trades_per_day as u64 > MAX_TRADES as u64 {
+            return invalid("history_days * trades_per_day must not exceed 100000");
+        }
+        if self.levels > 50 {
+            return invalid("levels must not exceed 50");
+        }
+        if self.level_size <= Decimal::ZERO {
+            return invalid("level_size must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// A resting sandbox bid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LadderLevel {
+    pub share_type: ShareType,
+    pub price: Decimal,
+    pub amount: Decimal,
+}
+
+/// Bids on both outcomes around the Yes price `mid`: the best Yes bid sits
+/// half a spread below it and the best No bid half a spread below `1 - mid`,
+/// each following level one tick further out
+pub fn ladder(mid: Decimal, spread: Decimal, levels: u32, level_size: Decimal) -> Vec<LadderLevel> {
+    let half = spread / Decimal::TWO;
+    let best = |reference: Decimal| ((reference - half) / TICK).floor() * TICK;
+    [(ShareType::Yes, best(mid)), (ShareType::No, best(Decimal::ONE - mid))]
+        .into_iter()
+        .flat_map(|(share_type, top)| {
+            (0..levels)
+                .map(move |i| top - TICK * Decimal::from(i))
+                .take_while(|price| *price >= TICK)
+                .map(move |price| LadderLevel {
+                    share_type,
+                    price,
+                    amount: level_size,
+                })
+        })
+        .collect()
+}
+
+/// A generated market, ready to import
+#[derive(Debug, Clone)]
+pub struct SandboxPlan {
+    pub bundle: ImportBundle,
+    pub market_id: Uuid,
+    pub yes_outcome_id: Uuid,
+    pub no_outcome_id: Uuid,
+    /// Yes price at the end of the history
+    pub final_price: Decimal,
+}
+
+fn random_id(rng: &mut impl Rng) -> Uuid {
+    uuid::Builder::from_random_bytes(rng.gen()).into_uuid()
+}
+
+/// Generate the market, its outcomes and a random-walk trade history ending
+/// at `now`. Trades alternate randomly between the Yes book (at the walk's
+/// price) and the No book (at its complement).
+pub fn generate(params: &SandboxParams, now: DateTime<Utc>, rng: &mut impl Rng) -> SandboxPlan {
+    let market_id = random_id(rng);
+    let yes_outcome_id = random_id(rng);
+    let no_outcome_id = random_id(rng);
+    let start = now - Duration::days(params.history_days);
+
+    let count = (params.history_days as u32 * params.trades_per_day).min(MAX_TRADES);
+    let step_ms = if count == 0 {
+        0
+    } else {
+        (now - start).num_milliseconds() / count as i64
+    };
+
+    let mut price = params.start_price;
+    let mut trades = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        // Box-Muller normal step, rounded to the tick
+        let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen());
+        let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        let step = Decimal::from_f64(z * params.volatility).unwrap_or_default();
+        price = ((price + step) / TICK).round() * TICK;
+        price = price.clamp(MIN_WALK, Decimal::ONE - MIN_WALK);
+
+        let jitter = rng.gen_range(0..step_ms.max(1));
+        let yes = rng.gen_bool(0.5);
+        trades.push(ImportTrade {
+            id: random_id(rng),
+            market_id,
+            outcome_id: if yes { yes_outcome_id } else { no_outcome_id },
+            share_type: Some(if yes { "yes" } else { "no" }.to_string()),
+            side: if rng.gen_bool(0.5) { "buy" } else { "sell" }.to_string(),
+            price: if yes { price } else { Decimal::ONE - price },
+            amount: Decimal::from(rng.gen_range(1..=50)),
+            maker_address: SANDBOX_MAKER_ADDRESS.to_string(),
+            taker_address: SANDBOX_TAKER_ADDRESS.to_string(),
+            maker_fee: None,
+            taker_fee: None,
+            match_type: None,
+            created_at: start + Duration::milliseconds(step_ms * i as i64 + jitter),
+        });
+    }
+
+    let condition: [u8; 32] = rng.gen();
+    let bundle = ImportBundle {
+        markets: vec![ImportMarket {
+            id: market_id,
+            condition_id: format!("0x{}", hex::encode(condition)),
+            question: params.question.clone(),
+            description: Some("Sandbox market with synthetic liquidity and trade history".to_string()),
+            category: Some(params.category.clone()),
+            resolution_source: Some("sandbox".to_string()),
+            status: Some("active".to_string()),
+            end_time: Some(params.end_time.unwrap_or(now + Duration::days(30))),
+            created_at: Some(start),
+            resolved_at: None,
+            winning_outcome_id: None,
+        }],
+        outcomes: [(yes_outcome_id, "Yes", "yes", price), (no_outcome_id, "No", "no", Decimal::ONE - price)]
+            .into_iter()
+            .map(|(id, name, share_type, probability)| ImportOutcome {
+                id,
+                market_id,
+                token_id: rng.gen::<u128>().to_string(),
+                name: name.to_string(),
+                share_type: share_type.to_string(),
+                probability: Some(probability),
+            })
+            .collect(),
+        trades,
+    };
+
+    SandboxPlan {
+        bundle,
+        market_id,
+        yes_outcome_id,
+        no_outcome_id,
+        final_price: price,
+    }
+}
+
+/// What [`seed`] created
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxReport {
+    pub market_id: Uuid,
+    pub yes_outcome_id: Uuid,
+    pub no_outcome_id: Uuid,
+    pub final_price: Decimal,
+    pub trades: u64,
+    pub maker_address: &'static str,
+    /// Collateral credited to the maker to back the ladder
+    pub funding: Decimal,
+    pub orders: Vec<Uuid>,
+    /// True when the orders were written for the next restore instead of
+    /// being placed on a running engine
+    pub orders_pending_restore: bool,
+}
+
+/// Create a sandbox market with history and liquidity
+pub async fn seed(
+    pool: &PgPool,
+    gateway: Option<&OrderGateway>,
+    collateral_token: &str,
+    params: &SandboxParams,
+) -> Result<SandboxReport, SandboxError> {
+    params.validate()?;
+    let mut rng = match params.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let plan = generate(params, Utc::now(), &mut rng);
+
+    let import = BackfillService::new(pool.clone()).import(&plan.bundle, false).await?;
+    if !import.is_valid() {
+        return Err(SandboxError::Import(import.issues));
+    }
+
+    let levels = ladder(plan.final_price, params.spread, params.levels, params.level_size);
+    let funding: Decimal = levels
+        .iter()
+        .map(|level| Collateral::notional(level.price, level.amount).value())
+        .sum();
+    fund_maker(pool, collateral_token, funding, plan.market_id).await?;
+
+    let outcome_of = |share_type| match share_type {
+        ShareType::Yes => plan.yes_outcome_id,
+        ShareType::No => plan.no_outcome_id,
+    };
+    let mut orders = Vec::with_capacity(levels.len());
+    for level in &levels {
+        let order = GatewayOrder {
+            source: OrderSource::Sandbox,
+            user_address: SANDBOX_MAKER_ADDRESS.to_string(),
+            market_id: plan.market_id,
+            outcome_id: outcome_of(level.share_type),
+            share_type: level.share_type,
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            price: level.price,
+            amount: level.amount,
+        };
+        let order_id = match gateway {
+            Some(gateway) => gateway.place(order).await?.order_id,
+            None => write_open_order(pool, collateral_token, &order).await?,
+        };
+        orders.push(order_id);
+    }
+
+    tracing::info!(
+        "Seeded sandbox market {} ({} trades, {} resting orders)",
+        plan.market_id,
+        import.trades_inserted,
+        orders.len()
+    );
+    Ok(SandboxReport {
+        market_id: plan.market_id,
+        yes_outcome_id: plan.yes_outcome_id,
+        no_outcome_id: plan.no_outcome_id,
+        final_price: plan.final_price,
+        trades: import.trades_inserted,
+        maker_address: SANDBOX_MAKER_ADDRESS,
+        funding,
+        orders,
+        orders_pending_restore: gateway.is_none(),
+    })
+}
+
+async fn fund_maker(pool: &PgPool, token: &str, amount: Decimal, market_id: Uuid) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let balance_after: Decimal = sqlx::query_scalar(
+        r#"
+        INSERT INTO balances (user_address, token, available, frozen)
+        VALUES ($1, $2, $3, 0)
+        ON CONFLICT (user_address, token) DO UPDATE SET
+            available = balances.available + $3,
+            updated_at = NOW()
+        RETURNING available
+        "#,
+    )
+    .bind(SANDBOX_MAKER_ADDRESS)
+    .bind(token)
+    .bind(amount)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    ledger::record_entry(
+        &mut tx,
+        &LedgerEntry {
+            user_address: SANDBOX_MAKER_ADDRESS,
+            token,
+            entry_type: LedgerEntryType::SandboxFunding,
+            amount,
+            balance_after,
+            reference_id: Some(market_id),
+            counterparty: None,
+        },
+    )
+    .await?;
+    tx.commit().await
+}
+
+/// Record a resting buy with its collateral frozen, for the books to pick
+/// up on their next restore
+async fn write_open_order(pool: &PgPool, token: &str, order: &GatewayOrder) -> Result<Uuid, sqlx::Error> {
+    let order_id = Uuid::new_v4();
+    let required = Collateral::notional(order.price, order.amount).value();
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "UPDATE balances SET available = available - $1, frozen = frozen + $1, updated_at = NOW()
+         WHERE user_address = $2 AND token = $3",
+    )
+    .bind(required)
+    .bind(&order.user_address)
+    .bind(token)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO orders (
+            id, user_address, symbol, market_id, outcome_id, share_type,
+            side, order_type, price, amount, filled_amount, status, source, signature,
+            created_at, updated_at
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, $6::share_type,
+            $7::order_side, $8::order_type, $9, $10, 0, 'open', $11, '',
+            NOW(), NOW()
+        )
+        "#,
+    )
+    .bind(order_id)
+    .bind(&order.user_address)
+    .bind(format!("{}:{}:{}", order.market_id, order.outcome_id, order.share_type))
+    .bind(order.market_id)
+    .bind(order.outcome_id)
+    .bind(order.share_type.to_string())
+    .bind(order.side.to_string())
+    .bind(order.order_type.to_string())
+    .bind(order.price)
+    .bind(order.amount)
+    .bind(order.source.as_str())
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(order_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::backfill::validate_bundle;
+    use rust_decimal_macros::dec;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_ladder() {
+        let levels = ladder(dec!(0.60), dec!(0.04), 3, dec!(100));
+        let prices = |share_type| {
+            levels
+                .iter()
+                .filter(|l| l.share_type == share_type)
+                .map(|l| l.price)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(prices(ShareType::Yes), vec![dec!(0.58), dec!(0.57), dec!(0.56)]);
+        assert_eq!(prices(ShareType::No), vec![dec!(0.38), dec!(0.37), dec!(0.36)]);
+
+        // Best Yes ask (1 - best No bid) is a spread above the best Yes bid
+        assert_eq!(Decimal::ONE - dec!(0.38) - dec!(0.58), dec!(0.04));
+
+        // Levels stop at the minimum price
+        let thin = ladder(dec!(0.03), dec!(0.02), 5, dec!(10));
+        assert_eq!(thin.iter().filter(|l| l.share_type == ShareType::Yes).count(), 2);
+    }
+
+    #[test]
+    fn test_generate_is_valid_and_deterministic() {
+        let mut params = SandboxParams::new("Will the demo work?");
+        params.volatility = 0.1;
+        let now = Utc::now();
+
+        let plan = generate(&params, now, &mut StdRng::seed_from_u64(7));
+        assert_eq!(plan.bundle.trades.len(), 7 * 48);
+        assert!(validate_bundle(&plan.bundle, &HashSet::new(), &HashMap::new()).is_empty());
+        assert!(plan
+            .bundle
+            .trades
+            .iter()
+            .all(|t| t.price >= MIN_WALK && t.price <= Decimal::ONE - MIN_WALK && t.created_at <= now));
+        assert!(plan.bundle.trades.windows(2).all(|w| w[0].created_at <= w[1].created_at));
+
+        let again = generate(&params, now, &mut StdRng::seed_from_u64(7));
+        assert_eq!(again.market_id, plan.market_id);
+        assert_eq!(again.final_price, plan.final_price);
+    }
+
+    #[test]
+    fn test_validate_params() {
+        assert!(SandboxParams::new("q").validate().is_ok());
+        let mut params = SandboxParams::new("q");
+        params.start_price = dec!(0.99);
+        assert!(params.validate().is_err());
+        let mut params = SandboxParams::new("q");
+        params.history_days = 365;
+        params.trades_per_day = 1000;
+        assert!(params.validate().is_err());
+    }
+}
@@ -7,6 +7,7 @@
 //! 3. Operator submits matched orders to chain
 //! 4. CTFExchange handles minting/merging automatically
 
+pub mod reconciliation;
 mod service;
 mod types;
 
@@ -0,0 +1,226 @@
+//! Settlement Reconciliation
+//!
+//! Lets a market maker's back office tie its internal fills to on-chain
+//! transfers. A user's trades are grouped by the transaction that settled
+//! them; trades without one (not yet submitted, custodial, or rejected
+//! before broadcast) are grouped by settlement status instead.
+//!
+//! A failure caused by one of the signed orders themselves (bad signature,
+//! expired order, used nonce) will fail again on every retry, so those are
+//! flagged as needing the user to re-sign. Anything else (RPC errors, gas,
+//! operator nonce) is ours to retry.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+
+/// Which trades to reconcile
+#[derive(Debug, Clone, Default)]
+pub struct ReconciliationFilter {
+    pub market_id: Option<Uuid>,
+    /// pending, submitted, confirmed or failed
+    pub status: Option<String>,
+    /// Only trades created at or after this time
+    pub since: Option<DateTime<Utc>>,
+    pub limit: i64,
+}
+
+/// One of the user's fills
+#[derive(Debug, Clone, Serialize)]
+pub struct SettlementFill {
+    pub trade_id: Uuid,
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: String,
+    /// "maker" or "taker"
+    pub role: &'static str,
+    pub order_id: Uuid,
+    pub side: String,
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub error: Option<String>,
+    /// The order must be signed again before the trade can settle
+    pub needs_resignature: bool,
+}
+
+/// Fills settled by one transaction, or awaiting one
+#[derive(Debug, Clone, Serialize)]
+pub struct SettlementBatch {
+    pub tx_hash: Option<String>,
+    pub status: String,
+    pub block_number: Option<i64>,
+    pub settled_at: Option<DateTime<Utc>>,
+    pub fills: Vec<SettlementFill>,
+}
+
+/// Fill counts by settlement status
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SettlementSummary {
+    pub pending: usize,
+    pub submitted: usize,
+    pub confirmed: usize,
+    pub failed: usize,
+    pub needs_resignature: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettlementReconciliation {
+    pub summary: SettlementSummary,
+    pub batches: Vec<SettlementBatch>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ReconciliationRow {
+    id: Uuid,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    share_type: String,
+    is_maker: bool,
+    order_id: Uuid,
+    side: String,
+    price: Decimal,
+    amount: Decimal,
+    fee: Decimal,
+    created_at: DateTime<Utc>,
+    settlement_status: String,
+    settlement_tx_hash: Option<String>,
+    settlement_block: Option<i64>,
+    settlement_error: Option<String>,
+    settled_at: Option<DateTime<Utc>>,
+}
+
+/// Whether a settlement error was caused by a signed order, so resubmitting
+/// the same orders cannot succeed
+pub fn needs_resignature(error: &str) -> bool {
+    let error = error.to_lowercase();
+    error.contains("signature") || error.contains("order expired") || error.contains("invalid nonce")
+}
+
+/// A user's fills, most recent first, grouped into settlement batches
+pub async fn reconcile(
+    pool: &PgPool,
+    user_address: &Address,
+    filter: &ReconciliationFilter,
+) -> Result<SettlementReconciliation, sqlx::Error> {
+    let rows: Vec<ReconciliationRow> = sqlx::query_as(
+        r#"
+        SELECT t.id, t.market_id, t.outcome_id, t.share_type::text AS share_type,
+               t.maker_address = $1 AS is_maker,
+               o.id AS order_id, o.side::text AS side,
+               COALESCE(t.adjusted_price, t.price) AS price, t.amount,
+               CASE WHEN t.maker_address = $1 THEN t.maker_fee ELSE t.taker_fee END AS fee,
+               t.created_at,
+               COALESCE(t.settlement_status::text, 'pending') AS settlement_status,
+               t.settlement_tx_hash, t.settlement_block, t.settlement_error, t.settled_at
+        FROM trades t
+        JOIN orders o ON o.id = CASE WHEN t.maker_address = $1 THEN t.maker_order_id ELSE t.taker_order_id END
+        WHERE (t.maker_address = $1 OR t.taker_address = $1)
+          AND ($2::uuid IS NULL OR t.market_id = $2)
+          AND ($3::text IS NULL OR COALESCE(t.settlement_status::text, 'pending') = $3)
+          AND ($4::timestamptz IS NULL OR t.created_at >= $4)
+        ORDER BY t.created_at DESC
+        LIMIT $5
+        "#,
+    )
+    .bind(user_address)
+    .bind(filter.market_id)
+    .bind(filter.status.as_deref())
+    .bind(filter.since)
+    .bind(filter.limit)
+    .fetch_all(pool)
+    .await?;
+
+    let mut summary = SettlementSummary::default();
+    let mut batches: Vec<SettlementBatch> = Vec::new();
+    let mut index: HashMap<(Option<String>, String), usize> = HashMap::new();
+
+    for row in rows {
+        let needs_resignature =
+            row.settlement_status == "failed" && row.settlement_error.as_deref().is_some_and(needs_resignature);
+        match row.settlement_status.as_str() {
+            "submitted" => summary.submitted += 1,
+            "confirmed" => summary.confirmed += 1,
+            "failed" => summary.failed += 1,
+            _ => summary.pending += 1,
+        }
+        if needs_resignature {
+            summary.needs_resignature += 1;
+        }
+
+        let key = (row.settlement_tx_hash.clone(), row.settlement_status.clone());
+        let i = *index.entry(key).or_insert_with(|| {
+            batches.push(SettlementBatch {
+                tx_hash: row.settlement_tx_hash.clone(),
+                status: row.settlement_status.clone(),
+                block_number: row.settlement_block,
+                settled_at: row.settled_at,
+                fills: Vec::new(),
+            });
+            batches.len() - 1
+        });
+        batches[i].fills.push(SettlementFill {
+            trade_id: row.id,
+            market_id: row.market_id,
+            outcome_id: row.outcome_id,
+            share_type: row.share_type,
+            role: if row.is_maker { "maker" } else { "taker" },
+            order_id: row.order_id,
+            side: row.side,
+            price: row.price,
+            amount: row.amount,
+            fee: row.fee,
+            created_at: row.created_at,
+            error: row.settlement_error,
+            needs_resignature,
+        });
+    }
+
+    Ok(SettlementReconciliation { summary, batches })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{account, matched, MatchOutcome, TestApp, MAKER};
+
+    #[test]
+    fn test_needs_resignature() {
+        assert!(needs_resignature("Invalid signature"));
+        assert!(needs_resignature("execution reverted: order expired"));
+        assert!(needs_resignature("InvalidNonce: invalid nonce"));
+        assert!(!needs_resignature("nonce too low"));
+        assert!(!needs_resignature("insufficient funds for gas"));
+    }
+
+    #[tokio::test]
+    async fn test_settlement_reconciliation() {
+        let app = TestApp::builder().build().await;
+        let settled = app.cross().await;
+        app.state.settlement_sender.as_ref().unwrap().send(matched(&settled)).await.unwrap();
+        app.settle_queued().await;
+        let rejected = app.cross().await;
+        app.chain.set_outcome(MatchOutcome::Error("Invalid signature".to_string()));
+        app.state.settlement_sender.as_ref().unwrap().send(matched(&rejected)).await.unwrap();
+        app.settle_queued().await;
+
+        let filter = ReconciliationFilter { limit: 10, ..Default::default() };
+        let report = reconcile(&app.db.pool, &account(MAKER), &filter).await.unwrap();
+        assert_eq!((report.summary.confirmed, report.summary.failed, report.summary.needs_resignature), (1, 1, 1));
+        assert_eq!(report.batches.len(), 2);
+        let failed = &report.batches[0];
+        assert_eq!((failed.status.as_str(), failed.tx_hash.as_deref()), ("failed", None));
+        assert_eq!((failed.fills[0].trade_id, failed.fills[0].role), (rejected.trade_id, "maker"));
+        assert!(failed.fills[0].needs_resignature);
+        let confirmed = &report.batches[1];
+        assert_eq!(confirmed.tx_hash, Some(format!("{:?}", app.chain.submitted()[0].tx_hash)));
+        assert_eq!((confirmed.fills[0].order_id, confirmed.fills[0].side.as_str()), (settled.maker_order_id, "sell"));
+    }
+}
@@ -13,16 +13,21 @@ use tokio::sync::mpsc;
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::blockchain::client::BlockchainClient;
+use crate::blockchain::SettlementChain;
 use crate::blockchain::types::TxStatus;
+use crate::models::Address;
 use crate::models::market::ShareType;
+use crate::services::event_bus::{BusEvent, EventBus};
+use crate::services::matching::holdings;
+use crate::services::matching::precision::Collateral;
+use crate::services::system_events::{self, SystemEventKind};
 
 use super::types::*;
 
 /// Settlement service for on-chain order settlement
 pub struct SettlementService {
-    /// Blockchain client
-    blockchain: Arc<BlockchainClient>,
+    /// Blockchain client (or a scripted chain in tests)
+    blockchain: Arc<dyn SettlementChain>,
     /// Database pool
     pool: PgPool,
     /// Configuration
@@ -36,7 +41,7 @@ pub struct SettlementService {
 impl SettlementService {
     /// Create a new settlement service
     pub fn new(
-        blockchain: Arc<BlockchainClient>,
+        blockchain: Arc<dyn SettlementChain>,
         pool: PgPool,
         config: SettlementConfig,
     ) -> Self {
@@ -61,53 +66,99 @@ impl SettlementService {
         self.queue_tx.clone()
     }
 
-    /// Start the settlement worker
+    /// Consumer group the settlement worker reads the event bus as
+    pub const GROUP: &'static str = "settlement";
+
+    /// Start the settlement worker. Queued matches are published to the
+    /// event bus and settled by whichever node consumes them as group
+    /// [`SettlementService::GROUP`], so a match queued just before a crash
+    /// is still settled once the stream is durable.
     /// Returns the queue sender for submitting settlements
-    pub fn start_worker(mut self) -> mpsc::Sender<MatchedOrders> {
+    pub fn start_worker(mut self, bus: Arc<EventBus>) -> mpsc::Sender<MatchedOrders> {
         let queue_tx = self.queue_tx.clone();
         let mut queue_rx = self.queue_rx.take().expect("Worker already started");
+        info!("Settlement worker started (enabled: {})", self.config.enabled);
+
+        let service = Arc::new(self);
+        bus.clone().consume(Self::GROUP, move |event| {
+            let service = service.clone();
+            async move {
+                if let BusEvent::Settlement(matched) = event {
+                    service.settle_once(&matched).await?;
+                }
+                Ok(())
+            }
+        });
 
         tokio::spawn(async move {
-            info!("Settlement worker started (enabled: {})", self.config.enabled);
-
             while let Some(matched) = queue_rx.recv().await {
-                if !self.config.enabled {
-                    info!(
-                        "On-chain settlement disabled, skipping trade {}",
-                        matched.trade_id
-                    );
-                    continue;
-                }
-
-                match self.settle_matched_orders(&matched).await {
-                    Ok(result) => {
-                        info!(
-                            "Trade {} settled on-chain: tx={:?}, status={:?}",
-                            matched.trade_id, result.tx_hash, result.status
-                        );
-
-                        // Update database with settlement result
-                        if let Err(e) = self.update_trade_settlement(&matched.trade_id, &result).await {
-                            error!("Failed to update trade settlement: {}", e);
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to settle trade {}: {}", matched.trade_id, e);
-
-                        // Mark trade as settlement failed
-                        if let Err(e) = self.mark_settlement_failed(&matched.trade_id, &e.to_string()).await {
-                            error!("Failed to mark settlement failed: {}", e);
-                        }
-                    }
-                }
+                bus.publish(BusEvent::Settlement(Box::new(matched))).await;
             }
-
             info!("Settlement worker stopped");
         });
 
         queue_tx
     }
 
+    /// Settle a match delivered by the event bus, unless settlement is off
+    /// or its trade already has a transaction (a redelivery after a crash)
+    pub async fn settle_once(&self, matched: &MatchedOrders) -> Result<(), String> {
+        if !self.config.enabled {
+            info!(
+                "On-chain settlement disabled, skipping trade {}",
+                matched.trade_id
+            );
+            return Ok(());
+        }
+
+        let tx_hash: Option<Option<String>> =
+            sqlx::query_scalar("SELECT settlement_tx_hash FROM trades WHERE id = $1")
+                .bind(matched.trade_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        if let Some(Some(tx_hash)) = tx_hash {
+            info!("Trade {} already settled in {}, skipping", matched.trade_id, tx_hash);
+            return Ok(());
+        }
+
+        self.settle(matched).await;
+        Ok(())
+    }
+
+    /// Submit one matched pair and record the outcome on its trade
+    pub async fn settle(&self, matched: &MatchedOrders) {
+        match self.settle_matched_orders(matched).await {
+            Ok(result) => {
+                info!(
+                    "Trade {} settled on-chain: tx={:?}, status={:?}",
+                    matched.trade_id, result.tx_hash, result.status
+                );
+
+                // Update database with settlement result
+                if let Err(e) = self.update_trade_settlement(&matched.trade_id, &result).await {
+                    error!("Failed to update trade settlement: {}", e);
+                }
+            }
+            Err(e) => {
+                error!("Failed to settle trade {}: {}", matched.trade_id, e);
+
+                // Mark trade as settlement failed
+                if let Err(e) = self.mark_settlement_failed(&matched.trade_id, &e.to_string()).await {
+                    error!("Failed to mark settlement failed: {}", e);
+                }
+                system_events::record(
+                    &self.pool,
+                    SystemEventKind::SettlementFailed,
+                    Some(&matched.trade_id.to_string()),
+                    "On-chain trade settlement failed",
+                    serde_json::json!({ "match_type": format!("{:?}", matched.match_type), "error": e.to_string() }),
+                )
+                .await;
+            }
+        }
+    }
+
     /// Settle matched orders on-chain
     async fn settle_matched_orders(
         &self,
@@ -165,14 +216,14 @@ impl SettlementService {
             UPDATE trades
             SET
                 settlement_tx_hash = $1,
-                settlement_status = $2,
+                settlement_status = $2::settlement_status,
                 settlement_block = $3,
-                updated_at = NOW()
+                settled_at = CASE WHEN $2 = 'confirmed' THEN NOW() ELSE settled_at END
             WHERE id = $4
             "#,
         )
         .bind(format!("{:?}", result.tx_hash))
-        .bind(format!("{:?}", result.status))
+        .bind(result.status.as_str())
         .bind(result.block_number.map(|b| b as i64))
         .bind(trade_id)
         .execute(&self.pool)
@@ -192,8 +243,7 @@ impl SettlementService {
             UPDATE trades
             SET
                 settlement_status = 'failed',
-                settlement_error = $1,
-                updated_at = NOW()
+                settlement_error = $1
             WHERE id = $2
             "#,
         )
@@ -251,10 +301,8 @@ impl SettlementService {
     pub async fn settle_user_shares(
         pool: &PgPool,
         market_id: Uuid,
-        user_address: &str,
+        user_address: &Address,
     ) -> Result<ShareSettlementResult, SettlementError> {
-        let user_address = user_address.to_lowercase();
-
         // 1. Get market status and winning outcome
         let market: Option<(String, Option<Uuid>)> = sqlx::query_as(
             r#"
@@ -289,7 +337,7 @@ impl SettlementService {
             WHERE user_address = $1 AND market_id = $2 AND change_type = 'redeem'
             "#
         )
-        .bind(&user_address)
+        .bind(user_address)
         .bind(market_id)
         .fetch_optional(pool)
         .await?;
@@ -308,7 +356,7 @@ impl SettlementService {
             WHERE user_address = $1 AND market_id = $2 AND amount > 0
             "#
         )
-        .bind(&user_address)
+        .bind(user_address)
         .bind(market_id)
         .fetch_all(pool)
         .await?;
@@ -319,7 +367,7 @@ impl SettlementService {
 
         // 5. Calculate payouts and execute settlement
         let mut share_settlements = Vec::new();
-        let mut total_payout = Decimal::ZERO;
+        let mut total_payout = Collateral::ZERO;
 
         // Begin transaction
         let mut tx = pool.begin().await?;
@@ -333,20 +381,16 @@ impl SettlementService {
                     // - Winning YES shares pay 1.0 USDC each
                     // - Winning NO shares (when NO wins) pay 1.0 USDC each
                     // - Losing shares pay 0
-                    let is_winning = outcome_id == winning_outcome_id.unwrap()
-                        && share_type == ShareType::Yes;
-                    let is_winning_no = outcome_id != winning_outcome_id.unwrap()
-                        && share_type == ShareType::No;
-
-                    if is_winning || is_winning_no {
-                        (Decimal::ONE, amount)
+                    if pays_on_resolution(outcome_id, share_type, winning_outcome_id.unwrap()) {
+                        (Decimal::ONE, Collateral::new(amount))
                     } else {
-                        (Decimal::ZERO, Decimal::ZERO)
+                        (Decimal::ZERO, Collateral::ZERO)
                     }
                 }
                 ShareSettlementType::Cancellation => {
-                    // For cancelled markets: refund at avg_cost
-                    (avg_cost, amount * avg_cost)
+                    // For cancelled markets: refund at avg_cost, rounded once
+                    // per position so the credits sum to the reported total
+                    (avg_cost, Collateral::notional(avg_cost, amount))
                 }
             };
 
@@ -361,7 +405,7 @@ impl SettlementService {
                     VALUES ($1, $2, $3, $4::share_type, 'redeem', $5, $6, NULL, NULL)
                     "#
                 )
-                .bind(&user_address)
+                .bind(user_address)
                 .bind(market_id)
                 .bind(outcome_id)
                 .bind(share_type.to_string())
@@ -370,6 +414,8 @@ impl SettlementService {
                 .execute(&mut *tx)
                 .await?;
 
+                holdings::record_redemption(&mut tx, market_id, outcome_id, share_type, amount).await?;
+
                 // Zero out user's shares
                 sqlx::query(
                     r#"
@@ -383,7 +429,7 @@ impl SettlementService {
                 .await?;
 
                 // Add payout to user's balance
-                if share_payout > Decimal::ZERO {
+                if !share_payout.is_zero() {
                     sqlx::query(
                         r#"
                         INSERT INTO balances (user_address, token, available, frozen, updated_at)
@@ -392,8 +438,8 @@ impl SettlementService {
                         DO UPDATE SET available = balances.available + $2, updated_at = NOW()
                         "#
                     )
-                    .bind(&user_address)
-                    .bind(share_payout)
+                    .bind(user_address)
+                    .bind(share_payout.value())
                     .execute(&mut *tx)
                     .await?;
                 }
@@ -403,7 +449,7 @@ impl SettlementService {
                     share_type,
                     amount,
                     payout_per_share,
-                    total_payout: share_payout,
+                    total_payout: share_payout.value(),
                 });
 
                 total_payout += share_payout;
@@ -420,10 +466,10 @@ impl SettlementService {
 
         Ok(ShareSettlementResult {
             market_id,
-            user_address,
+            user_address: user_address.clone().into(),
             settlement_type,
             shares_settled: share_settlements,
-            total_payout,
+            total_payout: total_payout.value(),
         })
     }
 
@@ -431,9 +477,8 @@ impl SettlementService {
     pub async fn get_settlement_status(
         pool: &PgPool,
         market_id: Uuid,
-        user_address: &str,
+        user_address: &Address,
     ) -> Result<UserSettlementStatus, SettlementError> {
-        let user_address = user_address.to_lowercase();
 
         // Get market info
         let market: Option<(String, Option<Uuid>)> = sqlx::query_as(
@@ -457,7 +502,7 @@ impl SettlementService {
             WHERE user_address = $1 AND market_id = $2 AND change_type = 'redeem'
             "#
         )
-        .bind(&user_address)
+        .bind(user_address)
         .bind(market_id)
         .fetch_optional(pool)
         .await?;
@@ -472,7 +517,7 @@ impl SettlementService {
             WHERE user_address = $1 AND market_id = $2 AND amount > 0
             "#
         )
-        .bind(&user_address)
+        .bind(user_address)
         .bind(market_id)
         .fetch_all(pool)
         .await?;
@@ -485,15 +530,18 @@ impl SettlementService {
                 .iter()
                 .filter(|(oid, st, _, _)| {
                     let share_type: ShareType = st.parse().unwrap_or(ShareType::Yes);
-                    let is_winning = *oid == winning_outcome_id.unwrap() && share_type == ShareType::Yes;
-                    let is_winning_no = *oid != winning_outcome_id.unwrap() && share_type == ShareType::No;
-                    is_winning || is_winning_no
+                    pays_on_resolution(*oid, share_type, winning_outcome_id.unwrap())
                 })
-                .map(|(_, _, a, _)| *a)
-                .sum()
+                .map(|(_, _, a, _)| Collateral::new(*a))
+                .sum::<Collateral>()
+                .value()
         } else if status == "cancelled" {
             // For cancelled markets, refund at avg_cost
-            shares.iter().map(|(_, _, a, c)| *a * *c).sum()
+            shares
+                .iter()
+                .map(|(_, _, a, c)| Collateral::notional(*c, *a))
+                .sum::<Collateral>()
+                .value()
         } else {
             Decimal::ZERO
         };
@@ -505,7 +553,7 @@ impl SettlementService {
 
         Ok(UserSettlementStatus {
             market_id,
-            user_address,
+            user_address: user_address.clone().into(),
             is_settled,
             market_status: status,
             winning_outcome_id,
@@ -520,10 +568,82 @@ impl SettlementService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal_macros::dec;
+    use crate::test_support::{matched, MatchOutcome, TestApp};
 
     #[test]
     fn test_index_set_calculation() {
         assert_eq!(TokenIdCalculator::calculate_index_set(true), U256::from(1));
         assert_eq!(TokenIdCalculator::calculate_index_set(false), U256::from(2));
     }
+
+    #[tokio::test]
+    async fn test_order_match_persist_settle() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+
+        let (price, amount, status): (Decimal, Decimal, String) =
+            sqlx::query_as("SELECT price, amount, settlement_status::text FROM trades WHERE id = $1")
+                .bind(trade.trade_id)
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!((price, amount, status.as_str()), (dec!(0.5), dec!(10), "pending"));
+        let maker_status: String = sqlx::query_scalar("SELECT status::text FROM orders WHERE id = $1")
+            .bind(trade.maker_order_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(maker_status, "filled");
+
+        let sender = app.state.settlement_sender.as_ref().unwrap();
+        sender.send(matched(&trade)).await.unwrap();
+        assert_eq!(app.settle_queued().await, 1);
+
+        let submitted = app.chain.submitted();
+        assert_eq!(submitted.len(), 1);
+        let (status, tx_hash, block): (String, String, i64) = sqlx::query_as(
+            "SELECT settlement_status::text, settlement_tx_hash, settlement_block FROM trades WHERE id = $1",
+        )
+        .bind(trade.trade_id)
+        .fetch_one(&app.db.pool)
+        .await
+        .unwrap();
+        assert_eq!(status, "confirmed");
+        assert_eq!(tx_hash, format!("{:?}", submitted[0].tx_hash));
+        assert_eq!(block, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_settlement_is_recorded() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+
+        app.chain.set_outcome(MatchOutcome::Error("nonce too low".to_string()));
+        app.state.settlement_sender.as_ref().unwrap().send(matched(&trade)).await.unwrap();
+        app.settle_queued().await;
+
+        let (status, error): (String, Option<String>) =
+            sqlx::query_as("SELECT settlement_status::text, settlement_error FROM trades WHERE id = $1")
+                .bind(trade.trade_id)
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!(status, "failed");
+        assert_eq!(error.as_deref(), Some("nonce too low"));
+        assert!(app.chain.submitted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_match_is_not_resubmitted() {
+        let app = TestApp::builder().build().await;
+        let trade = app.cross().await;
+
+        // First delivery settles; the bus redelivers it after a crash
+        // between submitting and acknowledging
+        app.settlement.settle_once(&matched(&trade)).await.unwrap();
+        app.settlement.settle_once(&matched(&trade)).await.unwrap();
+
+        assert_eq!(app.chain.submitted().len(), 1);
+    }
 }
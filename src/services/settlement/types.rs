@@ -64,6 +64,15 @@ pub struct ShareSettlement {
     pub total_payout: Decimal,
 }
 
+/// Whether a share pays 1 once its market resolves with `winning_outcome_id`:
+/// Yes shares of the winning outcome and No shares of every other outcome
+pub fn pays_on_resolution(outcome_id: Uuid, share_type: ShareType, winning_outcome_id: Uuid) -> bool {
+    match share_type {
+        ShareType::Yes => outcome_id == winning_outcome_id,
+        ShareType::No => outcome_id != winning_outcome_id,
+    }
+}
+
 /// Settlement status for a user's shares in a market
 #[derive(Debug, Clone)]
 pub struct UserSettlementStatus {
@@ -130,7 +139,7 @@ impl SignedOrder {
 }
 
 /// Matched orders ready for settlement
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchedOrders {
     pub trade_id: Uuid,
     pub maker_order: SignedOrder,
@@ -168,6 +177,18 @@ pub enum SettlementStatus {
     Failed,
 }
 
+impl SettlementStatus {
+    /// Value stored in `trades.settlement_status`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettlementStatus::Pending => "pending",
+            SettlementStatus::Submitted => "submitted",
+            SettlementStatus::Confirmed => "confirmed",
+            SettlementStatus::Failed => "failed",
+        }
+    }
+}
+
 /// Settlement configuration
 #[derive(Debug, Clone)]
 pub struct SettlementConfig {
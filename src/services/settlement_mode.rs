@@ -0,0 +1,173 @@
+//! Per-User Settlement Mode
+//!
+//! A custodial account trades through the internal book: its fills settle
+//! into internal balances and the `shares` table. A self-custody account
+//! trades with signed CTF orders: its fills are submitted to
+//! `CTFExchange.matchOrders` by the settlement service and its positions
+//! are the ERC-1155 balances of its own wallet.
+//!
+//! The mode can only change while the account has no open orders, so an
+//! order never settles differently from how it was placed.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::Address;
+
+/// Where a user's fills settle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementMode {
+    /// Internal balances held by the exchange
+    Custodial,
+    /// On-chain, to the user's wallet
+    SelfCustody,
+}
+
+impl SettlementMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettlementMode::Custodial => "custodial",
+            SettlementMode::SelfCustody => "self_custody",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "custodial" => Some(SettlementMode::Custodial),
+            "self_custody" => Some(SettlementMode::SelfCustody),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SettlementModeError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Cancel the {0} open orders before switching settlement mode")]
+    OpenOrders(i64),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl SettlementModeError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            SettlementModeError::UserNotFound => "USER_NOT_FOUND",
+            SettlementModeError::OpenOrders(_) => "OPEN_ORDERS",
+            SettlementModeError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+/// Settlement mode of `address`; unknown users are custodial
+pub async fn get(pool: &PgPool, address: &Address) -> Result<SettlementMode, sqlx::Error> {
+    let mode: Option<String> = sqlx::query_scalar("SELECT settlement_mode FROM users WHERE address = $1")
+        .bind(address)
+        .fetch_optional(pool)
+        .await?;
+    Ok(mode
+        .as_deref()
+        .and_then(SettlementMode::parse)
+        .unwrap_or(SettlementMode::Custodial))
+}
+
+/// Switch `address` to `mode`. Refused while the account has open orders.
+pub async fn set(pool: &PgPool, address: &Address, mode: SettlementMode) -> Result<(), SettlementModeError> {
+    let mut tx = pool.begin().await?;
+
+    let current: Option<String> =
+        sqlx::query_scalar("SELECT settlement_mode FROM users WHERE address = $1 FOR UPDATE")
+            .bind(address)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let current = current.ok_or(SettlementModeError::UserNotFound)?;
+    if current == mode.as_str() {
+        return Ok(());
+    }
+
+    let open_orders: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM orders
+        WHERE user_address = $1 AND status IN ('open', 'pending', 'partially_filled')
+        "#,
+    )
+    .bind(address)
+    .fetch_one(&mut *tx)
+    .await?;
+    if open_orders > 0 {
+        return Err(SettlementModeError::OpenOrders(open_orders));
+    }
+
+    sqlx::query("UPDATE users SET settlement_mode = $2, updated_at = NOW() WHERE address = $1")
+        .bind(address)
+        .bind(mode.as_str())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// An outcome whose tokens may sit in a self-custody wallet: one the user
+/// traded or split / merged, with the average price paid on buys
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WalletOutcome {
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: String,
+    pub token_id: String,
+    pub question: String,
+    pub outcome_name: String,
+    pub probability: Decimal,
+    pub avg_cost: Decimal,
+    pub first_activity: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+}
+
+/// Outcomes to read wallet balances for, optionally limited to one market
+pub async fn wallet_outcomes(
+    pool: &PgPool,
+    address: &Address,
+    market_id: Option<Uuid>,
+) -> Result<Vec<WalletOutcome>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        WITH activity AS (
+            SELECT t.outcome_id, t.created_at,
+                   CASE WHEN (t.taker_address = $1 AND t.side = 'buy')
+                          OR (t.maker_address = $1 AND t.side = 'sell')
+                        THEN t.amount ELSE 0 END AS bought,
+                   CASE WHEN (t.taker_address = $1 AND t.side = 'buy')
+                          OR (t.maker_address = $1 AND t.side = 'sell')
+                        THEN t.amount * t.price ELSE 0 END AS paid
+            FROM trades t
+            WHERE (t.maker_address = $1 OR t.taker_address = $1) AND t.outcome_id IS NOT NULL
+            UNION ALL
+            SELECT o.id, op.created_at, 0, 0
+            FROM ctf_position_ops op
+            JOIN outcomes o ON o.market_id = op.market_id
+            WHERE op.user_address = $1
+        )
+        SELECT o.market_id, o.id AS outcome_id, o.share_type::text AS share_type, o.token_id,
+               m.question, o.name AS outcome_name, o.probability,
+               COALESCE(SUM(a.paid) / NULLIF(SUM(a.bought), 0), 0) AS avg_cost,
+               MIN(a.created_at) AS first_activity, MAX(a.created_at) AS last_activity
+        FROM activity a
+        JOIN outcomes o ON o.id = a.outcome_id
+        JOIN markets m ON m.id = o.market_id
+        WHERE $2::uuid IS NULL OR o.market_id = $2
+        GROUP BY o.market_id, o.id, o.share_type, o.token_id, m.question, o.name, o.probability
+        ORDER BY MAX(a.created_at) DESC
+        "#,
+    )
+    .bind(address)
+    .bind(market_id)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,285 @@
+//! Stale Order Cleanup
+//!
+//! The archiver cancels a market's orders once, when it first sees the
+//! market resolved or cancelled. An order accepted concurrently with the
+//! close (or on another node) can still land afterwards and would then sit
+//! open against a market that never trades again, its reservation frozen.
+//! This janitor periodically looks for such orders, drops whatever books
+//! they re-created in the engine, cancels them in the database and releases
+//! buy reservations. Counts go to the `stale_orders_cancelled_total` metric
+//! and to the report served on `GET /admin/engine/stale-orders`.
+//!
+//! Books are dropped on the market's engine shard, in line with its
+//! matching; a market whose shard is overloaded waits for the next pass.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Address;
+use crate::services::matching::precision::Collateral;
+use crate::services::matching::EngineShards;
+use crate::services::portfolio_margin;
+
+/// How often closed markets are checked for open orders
+const SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Outcome of one cleanup pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanupRun {
+    pub ran_at: Option<DateTime<Utc>>,
+    /// Orders cancelled, by the status of their market
+    pub cancelled: BTreeMap<String, u64>,
+    /// Markets that still had open orders
+    pub markets: Vec<Uuid>,
+    /// Accounts whose reservations were released
+    pub accounts: usize,
+}
+
+impl CleanupRun {
+    pub fn total(&self) -> u64 {
+        self.cancelled.values().sum()
+    }
+}
+
+/// Counts since this process started
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanupReport {
+    pub runs: u64,
+    pub total_cancelled: u64,
+    pub cancelled: BTreeMap<String, u64>,
+    pub last_run: Option<CleanupRun>,
+    /// Last run that found anything
+    pub last_cleanup: Option<CleanupRun>,
+}
+
+/// Cancels open orders left behind on resolved or cancelled markets
+pub struct StaleOrderJanitor {
+    pool: PgPool,
+    shards: Arc<EngineShards>,
+    collateral_token: String,
+    report: RwLock<CleanupReport>,
+}
+
+impl StaleOrderJanitor {
+    pub fn new(pool: PgPool, shards: Arc<EngineShards>, collateral_token: &str) -> Self {
+        Self {
+            pool,
+            shards,
+            collateral_token: collateral_token.to_string(),
+            report: RwLock::new(CleanupReport::default()),
+        }
+    }
+
+    /// Counts of the cleanups run by this process
+    pub fn report(&self) -> CleanupReport {
+        self.report.read().clone()
+    }
+
+    /// Spawn the periodic cleanup
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.sweep().await {
+                    tracing::warn!("Stale order cleanup failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Cancel every open order of a resolved or cancelled market
+    pub async fn sweep(&self) -> Result<CleanupRun, sqlx::Error> {
+        let markets: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT o.market_id
+            FROM orders o
+            JOIN markets m ON m.id = o.market_id
+            WHERE m.status IN ('resolved', 'cancelled')
+              AND o.status IN ('pending', 'accepted', 'open', 'partially_filled')
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut closed = Vec::with_capacity(markets.len());
+        for market_id in markets {
+            // Books re-created by the late orders; owners get the cancellation
+            match self
+                .shards
+                .execute(&market_id.to_string(), move |engine| engine.close_market(market_id))
+                .await
+            {
+                Ok(_) => closed.push(market_id),
+                Err(e) => tracing::warn!("Stale orders of market {} left for the next pass: {}", market_id, e),
+            }
+        }
+        let markets = closed;
+
+        let mut run = CleanupRun {
+            ran_at: Some(Utc::now()),
+            markets: markets.clone(),
+            ..Default::default()
+        };
+        if !markets.is_empty() {
+            self.cancel_in_db(&markets, &mut run).await?;
+        }
+
+        for (market_status, count) in &run.cancelled {
+            crate::metrics::record_stale_orders_cancelled(market_status, *count);
+        }
+        if run.total() > 0 {
+            tracing::warn!(
+                "Cancelled {} stale orders on {} closed markets",
+                run.total(),
+                run.markets.len()
+            );
+        }
+
+        let mut report = self.report.write();
+        report.runs += 1;
+        report.total_cancelled += run.total();
+        for (market_status, count) in &run.cancelled {
+            *report.cancelled.entry(market_status.clone()).or_default() += count;
+        }
+        if run.total() > 0 {
+            report.last_cleanup = Some(run.clone());
+        }
+        report.last_run = Some(run.clone());
+        Ok(run)
+    }
+
+    /// Cancel the markets' open orders and release buy reservations
+    async fn cancel_in_db(&self, markets: &[Uuid], run: &mut CleanupRun) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let cancelled: Vec<(String, Address, String, Option<Decimal>, Decimal)> = sqlx::query_as(
+            r#"
+            UPDATE orders o
+            SET status = 'cancelled', updated_at = NOW()
+            FROM markets m
+            WHERE m.id = o.market_id
+              AND o.market_id = ANY($1)
+              AND m.status IN ('resolved', 'cancelled')
+              AND o.status IN ('pending', 'accepted', 'open', 'partially_filled')
+            RETURNING m.status::text, o.user_address, o.side::text, o.price, o.amount - o.filled_amount
+            "#,
+        )
+        .bind(markets)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut released: BTreeMap<Address, Collateral> = BTreeMap::new();
+        for (market_status, user_address, side, price, remaining) in &cancelled {
+            *run.cancelled.entry(market_status.clone()).or_default() += 1;
+            let user = released.entry(user_address.clone()).or_default();
+            if let Some(price) = price.filter(|_| side == "buy") {
+                *user += Collateral::notional(price, *remaining);
+            }
+        }
+        for (user_address, amount) in &released {
+            if amount.value() > Decimal::ZERO {
+                sqlx::query(
+                    "UPDATE balances SET available = available + $1, frozen = frozen - $1, updated_at = NOW()
+                     WHERE user_address = $2 AND token = $3",
+                )
+                .bind(amount.value())
+                .bind(user_address)
+                .bind(&self.collateral_token)
+                .execute(&mut *tx)
+                .await?;
+            }
+            portfolio_margin::resync_account(&mut tx, user_address.as_str(), &self.collateral_token).await?;
+        }
+        run.accounts = released.len();
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use crate::models::market::ShareType;
+    use crate::models::{OrderSide, OrderType, TimeInForce};
+    use crate::services::matching::{OrderIntent, SelfTradePrevention};
+    use crate::services::order_gateway::OrderSource;
+    use crate::test_support::TestApp;
+
+    const USER: &str = "0x5555555555555555555555555555555555555555";
+
+    #[tokio::test]
+    async fn test_sweep_cancels_orders_of_closed_markets() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.deposit(USER, dec!(100)).await;
+        let placed = app
+            .state
+            .order_flow
+            .place(&OrderIntent {
+                source: OrderSource::Api,
+                user_address: USER.to_string(),
+                market_id,
+                outcome_id: yes,
+                share_type: ShareType::Yes,
+                side: OrderSide::Buy,
+                order_type: OrderType::Limit,
+                time_in_force: TimeInForce::Gtc,
+                post_only: false,
+                stp: SelfTradePrevention::default(),
+                price: dec!(0.4),
+                amount: dec!(10),
+                expires_at: None,
+                signature: String::new(),
+            })
+            .await
+            .unwrap();
+
+        let market_books = || {
+            let market = market_id.to_string();
+            app.state.matching_engine.orderbook_keys().into_iter().filter(|key| key.contains(&market)).count()
+        };
+        assert_ne!(market_books(), 0);
+        let janitor = &app.state.stale_orders;
+        assert_eq!(janitor.sweep().await.unwrap().total(), 0);
+
+        // Closed behind the archiver's back, as when an order races the close
+        sqlx::query("UPDATE markets SET status = 'resolved' WHERE id = $1")
+            .bind(market_id)
+            .execute(&app.db.pool)
+            .await
+            .unwrap();
+        let run = janitor.sweep().await.unwrap();
+        assert_eq!((run.total(), run.markets.clone(), run.accounts), (1, vec![market_id], 1));
+        assert_eq!(market_books(), 0);
+
+        let status: String = sqlx::query_scalar("SELECT status::text FROM orders WHERE id = $1")
+            .bind(placed.order_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "cancelled");
+        let (available, frozen): (rust_decimal::Decimal, rust_decimal::Decimal) =
+            sqlx::query_as("SELECT available, frozen FROM balances WHERE user_address = $1")
+                .bind(USER)
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap();
+        assert_eq!((available, frozen), (dec!(100), dec!(0)));
+
+        let report = janitor.report();
+        assert_eq!((report.runs, report.total_cancelled), (2, 1));
+        assert_eq!(report.cancelled.get("resolved"), Some(&1));
+        assert_eq!(report.last_cleanup.map(|run| run.markets), Some(vec![market_id]));
+    }
+}
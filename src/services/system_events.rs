@@ -0,0 +1,254 @@
+//! System Event Timeline
+//!
+//! Significant operational events are appended to `system_events` with the
+//! instance that saw them, so a post-incident timeline is one query away
+//! (`GET /admin/system-events`) instead of being pieced together from the
+//! logs of every replica. Recording never fails the caller: database errors
+//! are logged and swallowed.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+/// How bad an event is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl EventSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventSeverity::Info => "info",
+            EventSeverity::Warning => "warning",
+            EventSeverity::Critical => "critical",
+        }
+    }
+}
+
+/// Kinds of recorded events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemEventKind {
+    EngineStarted,
+    EngineStopped,
+    OrdersRecovered,
+    RecoveryFailed,
+    MarketResolved,
+    MarketCancelled,
+    MarketArchived,
+    MarketAwaitingResolution,
+    MarketHalted,
+    MarketResumed,
+    SettlementFailed,
+    TradePersistDeadLettered,
+    TradeBusted,
+    TradeAdjusted,
+    CancelAllAfterFired,
+    MmProtectionTriggered,
+    FeatureFlagChanged,
+    FeatureFlagDeleted,
+}
+
+impl SystemEventKind {
+    pub const ALL: [SystemEventKind; 18] = [
+        SystemEventKind::EngineStarted,
+        SystemEventKind::EngineStopped,
+        SystemEventKind::OrdersRecovered,
+        SystemEventKind::RecoveryFailed,
+        SystemEventKind::MarketResolved,
+        SystemEventKind::MarketCancelled,
+        SystemEventKind::MarketArchived,
+        SystemEventKind::MarketAwaitingResolution,
+        SystemEventKind::MarketHalted,
+        SystemEventKind::MarketResumed,
+        SystemEventKind::SettlementFailed,
+        SystemEventKind::TradePersistDeadLettered,
+        SystemEventKind::TradeBusted,
+        SystemEventKind::TradeAdjusted,
+        SystemEventKind::CancelAllAfterFired,
+        SystemEventKind::MmProtectionTriggered,
+        SystemEventKind::FeatureFlagChanged,
+        SystemEventKind::FeatureFlagDeleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemEventKind::EngineStarted => "engine_started",
+            SystemEventKind::EngineStopped => "engine_stopped",
+            SystemEventKind::OrdersRecovered => "orders_recovered",
+            SystemEventKind::RecoveryFailed => "recovery_failed",
+            SystemEventKind::MarketResolved => "market_resolved",
+            SystemEventKind::MarketCancelled => "market_cancelled",
+            SystemEventKind::MarketArchived => "market_archived",
+            SystemEventKind::MarketAwaitingResolution => "market_awaiting_resolution",
+            SystemEventKind::MarketHalted => "market_halted",
+            SystemEventKind::MarketResumed => "market_resumed",
+            SystemEventKind::SettlementFailed => "settlement_failed",
+            SystemEventKind::TradePersistDeadLettered => "trade_persist_dead_lettered",
+            SystemEventKind::TradeBusted => "trade_busted",
+            SystemEventKind::TradeAdjusted => "trade_adjusted",
+            SystemEventKind::CancelAllAfterFired => "cancel_all_after_fired",
+            SystemEventKind::MmProtectionTriggered => "mm_protection_triggered",
+            SystemEventKind::FeatureFlagChanged => "feature_flag_changed",
+            SystemEventKind::FeatureFlagDeleted => "feature_flag_deleted",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == s)
+    }
+
+    /// Timeline category the kind is filed under
+    pub fn category(&self) -> &'static str {
+        match self {
+            SystemEventKind::EngineStarted
+            | SystemEventKind::EngineStopped
+            | SystemEventKind::OrdersRecovered
+            | SystemEventKind::RecoveryFailed => "engine",
+            SystemEventKind::MarketResolved
+            | SystemEventKind::MarketCancelled
+            | SystemEventKind::MarketArchived
+            | SystemEventKind::MarketAwaitingResolution
+            | SystemEventKind::MarketHalted
+            | SystemEventKind::MarketResumed => "market",
+            SystemEventKind::SettlementFailed | SystemEventKind::TradePersistDeadLettered => "settlement",
+            SystemEventKind::TradeBusted
+            | SystemEventKind::TradeAdjusted
+            | SystemEventKind::CancelAllAfterFired
+            | SystemEventKind::MmProtectionTriggered => "trading",
+            SystemEventKind::FeatureFlagChanged | SystemEventKind::FeatureFlagDeleted => "config",
+        }
+    }
+
+    /// Severity the kind is recorded at
+    pub fn severity(&self) -> EventSeverity {
+        match self {
+            SystemEventKind::RecoveryFailed | SystemEventKind::TradePersistDeadLettered => EventSeverity::Critical,
+            SystemEventKind::EngineStopped
+            | SystemEventKind::SettlementFailed
+            | SystemEventKind::TradeBusted
+            | SystemEventKind::TradeAdjusted
+            | SystemEventKind::MmProtectionTriggered
+            | SystemEventKind::MarketHalted => EventSeverity::Warning,
+            _ => EventSeverity::Info,
+        }
+    }
+}
+
+impl std::fmt::Display for SystemEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A recorded event
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SystemEvent {
+    pub id: i64,
+    pub kind: String,
+    pub category: String,
+    pub severity: String,
+    pub instance: String,
+    pub subject: Option<String>,
+    pub message: String,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Timeline filters; all optional
+#[derive(Debug, Default, Clone)]
+pub struct SystemEventFilter {
+    pub category: Option<String>,
+    pub kind: Option<String>,
+    pub severity: Option<String>,
+    pub subject: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: i64,
+}
+
+/// `host:pid` of this process
+pub fn instance() -> &'static str {
+    static INSTANCE: OnceLock<String> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+        format!("{}:{}", host, std::process::id())
+    })
+}
+
+/// Append an event to the timeline
+pub async fn record(
+    pool: &PgPool,
+    kind: SystemEventKind,
+    subject: Option<&str>,
+    message: impl AsRef<str>,
+    details: serde_json::Value,
+) {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO system_events (kind, category, severity, instance, subject, message, details)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(kind.category())
+    .bind(kind.severity().as_str())
+    .bind(instance())
+    .bind(subject)
+    .bind(message.as_ref())
+    .bind(&details)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        tracing::error!("Failed to record system event {}: {}", kind, e);
+    }
+}
+
+/// Events matching `filter`, newest first
+pub async fn query(pool: &PgPool, filter: &SystemEventFilter) -> Result<Vec<SystemEvent>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, kind, category, severity, instance, subject, message, details, created_at
+        FROM system_events
+        WHERE ($1::text IS NULL OR category = $1)
+          AND ($2::text IS NULL OR kind = $2)
+          AND ($3::text IS NULL OR severity = $3)
+          AND ($4::text IS NULL OR subject = $4)
+          AND ($5::timestamptz IS NULL OR created_at >= $5)
+          AND ($6::timestamptz IS NULL OR created_at < $6)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $7
+        "#,
+    )
+    .bind(&filter.category)
+    .bind(&filter.kind)
+    .bind(&filter.severity)
+    .bind(&filter.subject)
+    .bind(filter.since)
+    .bind(filter.until)
+    .bind(filter.limit)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_round_trip_and_category() {
+        for kind in SystemEventKind::ALL {
+            assert_eq!(SystemEventKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(SystemEventKind::FeatureFlagChanged.category(), "config");
+        assert_eq!(SystemEventKind::RecoveryFailed.severity(), EventSeverity::Critical);
+        assert!(SystemEventKind::parse("nope").is_none());
+    }
+}
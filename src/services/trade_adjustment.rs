@@ -0,0 +1,512 @@
+//! Trade Busts and Price Adjustments
+//!
+//! Admins correct erroneous trades (fat-finger fills during an incident)
+//! without touching the trade itself. A **bust** unwinds the trade: both
+//! parties' shares are reversed and the collateral that changed hands is
+//! returned. A **price adjustment** keeps the shares where they are and moves
+//! the difference between the old and new price between the parties.
+//!
+//! Corrections are compensating entries: holdings changes go to
+//! `share_changes`, collateral movements to the balance ledger, and the
+//! correction itself to `trade_adjustments`. The trade row is only annotated
+//! (`adjustment_status`, `adjusted_price`); its executed price and amount
+//! stay as matched.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::market::{DbShareType, ShareType};
+use crate::models::Address;
+use crate::services::ledger::{self, LedgerEntry, LedgerEntryType};
+use crate::services::matching::holdings::{self, party_changes};
+use crate::services::matching::{MatchType, TradeEvent};
+
+#[derive(Debug, Error)]
+pub enum TradeAdjustmentError {
+    #[error("Trade not found")]
+    TradeNotFound,
+    #[error("Trade has already been busted")]
+    AlreadyBusted,
+    #[error("Market of the trade is {0}; its trades can no longer be adjusted")]
+    MarketClosed(String),
+    #[error("Adjusted price must be between 0 and 1")]
+    InvalidPrice,
+    #[error("Trade is already effective at {0}")]
+    UnchangedPrice(Decimal),
+    #[error("A reason is required")]
+    MissingReason,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl TradeAdjustmentError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            TradeAdjustmentError::TradeNotFound => "TRADE_NOT_FOUND",
+            TradeAdjustmentError::AlreadyBusted => "TRADE_ALREADY_BUSTED",
+            TradeAdjustmentError::MarketClosed(_) => "MARKET_CLOSED",
+            TradeAdjustmentError::InvalidPrice => "INVALID_PRICE",
+            TradeAdjustmentError::UnchangedPrice(_) => "PRICE_UNCHANGED",
+            TradeAdjustmentError::MissingReason => "MISSING_REASON",
+            TradeAdjustmentError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+/// Kind of correction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentKind {
+    Bust,
+    PriceAdjust,
+}
+
+impl AdjustmentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdjustmentKind::Bust => "bust",
+            AdjustmentKind::PriceAdjust => "price_adjust",
+        }
+    }
+
+    /// `trades.adjustment_status` after this correction
+    fn trade_status(&self) -> &'static str {
+        match self {
+            AdjustmentKind::Bust => "busted",
+            AdjustmentKind::PriceAdjust => "price_adjusted",
+        }
+    }
+}
+
+/// What a correction did to one party
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+pub struct Compensation {
+    pub user_address: String,
+    pub order_id: Uuid,
+    pub role: String,
+    pub share_type: ShareType,
+    /// Signed share delta
+    pub shares: Decimal,
+    /// Signed credit to available collateral
+    pub collateral: Decimal,
+}
+
+/// A recorded correction
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeAdjustment {
+    pub id: Uuid,
+    pub trade_id: Uuid,
+    pub market_id: Uuid,
+    pub kind: String,
+    pub previous_price: Decimal,
+    pub adjusted_price: Option<Decimal>,
+    pub amount: Decimal,
+    pub reason: String,
+    pub admin_address: String,
+    pub compensations: Vec<Compensation>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct AdjustmentRow {
+    id: Uuid,
+    trade_id: Uuid,
+    market_id: Uuid,
+    kind: String,
+    previous_price: Decimal,
+    adjusted_price: Option<Decimal>,
+    amount: Decimal,
+    reason: String,
+    admin_address: String,
+    compensations: serde_json::Value,
+    created_at: DateTime<Utc>,
+}
+
+impl From<AdjustmentRow> for TradeAdjustment {
+    fn from(row: AdjustmentRow) -> Self {
+        Self {
+            id: row.id,
+            trade_id: row.trade_id,
+            market_id: row.market_id,
+            kind: row.kind,
+            previous_price: row.previous_price,
+            adjusted_price: row.adjusted_price,
+            amount: row.amount,
+            reason: row.reason,
+            admin_address: row.admin_address,
+            compensations: serde_json::from_value(row.compensations).unwrap_or_default(),
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TradeRow {
+    id: Uuid,
+    symbol: String,
+    market_id: Uuid,
+    outcome_id: Uuid,
+    #[sqlx(try_from = "DbShareType")]
+    share_type: ShareType,
+    match_type: String,
+    maker_order_id: Uuid,
+    taker_order_id: Uuid,
+    maker_address: String,
+    taker_address: String,
+    side: String,
+    price: Decimal,
+    amount: Decimal,
+    maker_fee: Decimal,
+    taker_fee: Decimal,
+    created_at: DateTime<Utc>,
+    sequence: Option<i64>,
+    is_block_trade: bool,
+    is_rfq: bool,
+    adjustment_status: Option<String>,
+    adjusted_price: Option<Decimal>,
+    market_status: String,
+}
+
+impl TradeRow {
+    fn to_event(&self) -> TradeEvent {
+        TradeEvent {
+            symbol: self.symbol.clone(),
+            market_id: self.market_id,
+            outcome_id: self.outcome_id,
+            share_type: self.share_type,
+            match_type: match self.match_type.as_str() {
+                "mint" => MatchType::Mint,
+                "merge" => MatchType::Merge,
+                _ => MatchType::Normal,
+            },
+            trade_id: self.id,
+            maker_order_id: self.maker_order_id,
+            taker_order_id: self.taker_order_id,
+            maker_address: self.maker_address.clone(),
+            taker_address: self.taker_address.clone(),
+            side: self.side.clone(),
+            price: self.adjusted_price.unwrap_or(self.price),
+            amount: self.amount,
+            maker_fee: self.maker_fee,
+            taker_fee: self.taker_fee,
+            timestamp: self.created_at.timestamp_millis(),
+            sequence: self.sequence.unwrap_or(0) as u64,
+            is_block_trade: self.is_block_trade,
+            is_rfq: self.is_rfq,
+            // Only numbered when broadcast
+            market_sequence: 0,
+        }
+    }
+}
+
+/// Net share and collateral effect of `trade` at its price on the taker and
+/// the maker. Reservations are ignored: only what changed hands counts.
+fn net_effect(trade: &TradeEvent) -> [(Decimal, Decimal); 2] {
+    let maker_price = match trade.match_type {
+        MatchType::Normal => trade.price,
+        MatchType::Mint | MatchType::Merge => Decimal::ONE - trade.price,
+    };
+    party_changes(trade, trade.price, maker_price).map(|p| (p.shares, p.available_credit - p.frozen_release))
+}
+
+/// Compensations for busting `trade` (`new_price` = None) or re-pricing it
+/// from its current effective price (`trade.price`) to `new_price`
+pub fn compensations(trade: &TradeEvent, new_price: Option<Decimal>) -> Vec<Compensation> {
+    let before = net_effect(trade);
+    let deltas = match new_price {
+        None => before.map(|(shares, collateral)| (-shares, -collateral)),
+        Some(price) => {
+            let after = net_effect(&TradeEvent { price, ..trade.clone() });
+            [0, 1].map(|i| (Decimal::ZERO, after[i].1 - before[i].1))
+        }
+    };
+
+    let parties = party_changes(trade, trade.price, trade.price);
+    parties
+        .iter()
+        .zip(deltas)
+        .zip(["taker", "maker"])
+        .map(|((party, (shares, collateral)), role)| Compensation {
+            user_address: party.user_address.to_string(),
+            order_id: party.order_id,
+            role: role.to_string(),
+            share_type: party.share_type,
+            shares,
+            collateral,
+        })
+        .collect()
+}
+
+/// Bust `trade_id` (`new_price` = None) or adjust its price
+pub async fn adjust_trade(
+    pool: &PgPool,
+    trade_id: Uuid,
+    new_price: Option<Decimal>,
+    reason: &str,
+    admin_address: &Address,
+    collateral_token: &str,
+) -> Result<TradeAdjustment, TradeAdjustmentError> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(TradeAdjustmentError::MissingReason);
+    }
+    if new_price.is_some_and(|p| p <= Decimal::ZERO || p >= Decimal::ONE) {
+        return Err(TradeAdjustmentError::InvalidPrice);
+    }
+    let kind = if new_price.is_some() { AdjustmentKind::PriceAdjust } else { AdjustmentKind::Bust };
+
+    let mut tx = pool.begin().await?;
+    let row: TradeRow = sqlx::query_as(
+        r#"
+        SELECT t.id, t.symbol, t.market_id, t.outcome_id, t.share_type, t.match_type::text AS match_type,
+               t.maker_order_id, t.taker_order_id, t.maker_address, t.taker_address,
+               t.side::text AS side, t.price, t.amount, t.maker_fee, t.taker_fee, t.created_at,
+               t.sequence, t.is_block_trade, t.is_rfq, t.adjustment_status, t.adjusted_price,
+               m.status::text AS market_status
+        FROM trades t
+        JOIN markets m ON m.id = t.market_id
+        WHERE t.id = $1
+        FOR UPDATE OF t
+        "#,
+    )
+    .bind(trade_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(TradeAdjustmentError::TradeNotFound)?;
+
+    if row.adjustment_status.as_deref() == Some("busted") {
+        return Err(TradeAdjustmentError::AlreadyBusted);
+    }
+    // Payouts have been (or are being) computed from the holdings
+    if matches!(row.market_status.as_str(), "resolved" | "cancelled") {
+        return Err(TradeAdjustmentError::MarketClosed(row.market_status));
+    }
+
+    let trade = row.to_event();
+    if new_price == Some(trade.price) {
+        return Err(TradeAdjustmentError::UnchangedPrice(trade.price));
+    }
+    let compensations = compensations(&trade, new_price);
+    let adjustment_id = Uuid::new_v4();
+    let (entry_type, change_type) = match kind {
+        AdjustmentKind::Bust => (LedgerEntryType::TradeBust, "bust"),
+        AdjustmentKind::PriceAdjust => (LedgerEntryType::TradeAdjustment, "adjust"),
+    };
+
+    for (i, comp) in compensations.iter().enumerate() {
+        let counterparty = &compensations[1 - i].user_address;
+
+        if !comp.shares.is_zero() {
+            sqlx::query(
+                r#"
+                INSERT INTO shares (user_address, market_id, outcome_id, share_type, amount, avg_cost)
+                VALUES ($1, $2, $3, $4::share_type, $5, 0)
+                ON CONFLICT (user_address, outcome_id, share_type) DO UPDATE SET
+                    amount = shares.amount + $5,
+                    updated_at = NOW()
+                "#,
+            )
+            .bind(&comp.user_address)
+            .bind(trade.market_id)
+            .bind(trade.outcome_id)
+            .bind(comp.share_type.to_string())
+            .bind(comp.shares)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO share_changes (
+                    user_address, market_id, outcome_id, share_type,
+                    change_type, amount, price, trade_id, order_id
+                )
+                VALUES ($1, $2, $3, $4::share_type, $5, $6, $7, $8, $9)
+                "#,
+            )
+            .bind(&comp.user_address)
+            .bind(trade.market_id)
+            .bind(trade.outcome_id)
+            .bind(comp.share_type.to_string())
+            .bind(change_type)
+            .bind(comp.shares)
+            .bind(trade.price)
+            .bind(trade.trade_id)
+            .bind(comp.order_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if !comp.collateral.is_zero() {
+            let balance_after: Decimal = sqlx::query_scalar(
+                r#"
+                INSERT INTO balances (user_address, token, available, frozen, updated_at)
+                VALUES ($1, $2, $3, 0, NOW())
+                ON CONFLICT (user_address, token) DO UPDATE SET
+                    available = balances.available + $3,
+                    updated_at = NOW()
+                RETURNING available
+                "#,
+            )
+            .bind(&comp.user_address)
+            .bind(collateral_token)
+            .bind(comp.collateral)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            ledger::record_entry(
+                &mut tx,
+                &LedgerEntry {
+                    user_address: &comp.user_address,
+                    token: collateral_token,
+                    entry_type,
+                    amount: comp.collateral,
+                    balance_after,
+                    reference_id: Some(adjustment_id),
+                    counterparty: Some(counterparty),
+                },
+            )
+            .await?;
+        }
+    }
+
+    if kind == AdjustmentKind::Bust {
+        holdings::reverse_pairs(&mut tx, &trade).await?;
+    }
+
+    let created_at: DateTime<Utc> = sqlx::query_scalar(
+        r#"
+        INSERT INTO trade_adjustments
+            (id, trade_id, kind, previous_price, adjusted_price, amount, reason, admin_address, compensations)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING created_at
+        "#,
+    )
+    .bind(adjustment_id)
+    .bind(trade_id)
+    .bind(kind.as_str())
+    .bind(trade.price)
+    .bind(new_price)
+    .bind(trade.amount)
+    .bind(reason)
+    .bind(admin_address)
+    .bind(serde_json::to_value(&compensations).unwrap_or_default())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE trades SET adjustment_status = $2, adjusted_price = COALESCE($3, adjusted_price), adjusted_at = NOW() WHERE id = $1",
+    )
+    .bind(trade_id)
+    .bind(kind.trade_status())
+    .bind(new_price)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    tracing::warn!(
+        "Trade {} {} by {} ({} @ {} -> {:?}): {}",
+        trade_id,
+        kind.trade_status(),
+        admin_address,
+        trade.amount,
+        trade.price,
+        new_price,
+        reason
+    );
+
+    Ok(TradeAdjustment {
+        id: adjustment_id,
+        trade_id,
+        market_id: trade.market_id,
+        kind: kind.as_str().to_string(),
+        previous_price: trade.price,
+        adjusted_price: new_price,
+        amount: trade.amount,
+        reason: reason.to_string(),
+        admin_address: admin_address.clone().into(),
+        compensations,
+        created_at,
+    })
+}
+
+/// Corrections recorded for a trade, oldest first
+pub async fn list_adjustments(pool: &PgPool, trade_id: Uuid) -> Result<Vec<TradeAdjustment>, sqlx::Error> {
+    let rows: Vec<AdjustmentRow> = sqlx::query_as(
+        r#"
+        SELECT a.id, a.trade_id, t.market_id, a.kind, a.previous_price, a.adjusted_price, a.amount,
+               a.reason, a.admin_address, a.compensations, a.created_at
+        FROM trade_adjustments a
+        JOIN trades t ON t.id = a.trade_id
+        WHERE a.trade_id = $1
+        ORDER BY a.created_at
+        "#,
+    )
+    .bind(trade_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(TradeAdjustment::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn trade(match_type: MatchType, side: &str) -> TradeEvent {
+        TradeEvent {
+            symbol: String::new(),
+            market_id: Uuid::new_v4(),
+            outcome_id: Uuid::new_v4(),
+            share_type: ShareType::Yes,
+            match_type,
+            trade_id: Uuid::new_v4(),
+            maker_order_id: Uuid::new_v4(),
+            taker_order_id: Uuid::new_v4(),
+            maker_address: "0xmaker".to_string(),
+            taker_address: "0xtaker".to_string(),
+            side: side.to_string(),
+            price: dec!(0.9),
+            amount: dec!(10),
+            maker_fee: Decimal::ZERO,
+            taker_fee: Decimal::ZERO,
+            timestamp: 0,
+            sequence: 0,
+            is_block_trade: false,
+            is_rfq: false,
+            market_sequence: 0,
+        }
+    }
+
+    #[test]
+    fn test_bust_reverses_normal_trade() {
+        let [taker, maker] = <[Compensation; 2]>::try_from(compensations(&trade(MatchType::Normal, "buy"), None)).unwrap();
+        assert_eq!((taker.role.as_str(), taker.shares, taker.collateral), ("taker", dec!(-10), dec!(9)));
+        assert_eq!((maker.role.as_str(), maker.shares, maker.collateral), ("maker", dec!(10), dec!(-9)));
+    }
+
+    #[test]
+    fn test_bust_mint_refunds_both_buyers() {
+        let comps = compensations(&trade(MatchType::Mint, "buy"), None);
+        assert_eq!(comps[1].share_type, ShareType::No);
+        assert!(comps.iter().all(|c| c.shares == dec!(-10)));
+        // Together the pair cost one unit of collateral each
+        assert_eq!(comps[0].collateral + comps[1].collateral, dec!(10));
+    }
+
+    #[test]
+    fn test_price_adjust_moves_difference_only() {
+        let comps = compensations(&trade(MatchType::Normal, "buy"), Some(dec!(0.6)));
+        assert!(comps.iter().all(|c| c.shares.is_zero()));
+        // Buyer overpaid 0.3 per share
+        assert_eq!((comps[0].collateral, comps[1].collateral), (dec!(3), dec!(-3)));
+
+        let comps = compensations(&trade(MatchType::Mint, "buy"), Some(dec!(0.6)));
+        // Taker pays 0.3 less, maker's complement costs 0.3 more
+        assert_eq!((comps[0].collateral, comps[1].collateral), (dec!(3), dec!(-3)));
+    }
+}
@@ -0,0 +1,632 @@
+//! Trade Persistence Retry Queue
+//!
+//! A matched trade has already happened in the in-memory engine, so a failed
+//! `persist_trade` must not just be logged. Failures are written to
+//! `trade_persist_queue` and retried by a background worker with exponential
+//! backoff; once retries are exhausted the entry is parked as `dead` until an
+//! operator reprocesses it through the admin API.
+//!
+//! If the queue insert fails as well (database down), the trade is held in a
+//! bounded in-memory buffer that the worker flushes into the table once the
+//! database is reachable again.
+//!
+//! Complete sets of categorical markets ([`SetFill`]) go through the same
+//! queue as entries of kind `set_fill`, keyed by their set id.
+//!
+//! With a durable event bus the trades are also consumed from the stream as
+//! group [`TradePersistQueue::GROUP`]. A trade that is neither on record nor
+//! queued a while after it matched - its node crashed between matching and
+//! writing - is written from the stream, so a crash does not lose it.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::services::event_bus::{BusEvent, EventBus};
+use crate::services::matching::{OrderFlowOrchestrator, SetFill, TradeEvent};
+use crate::services::retry::RetryPolicy;
+use crate::services::system_events::{self, SystemEventKind};
+use crate::services::write_batcher;
+
+/// How long after matching a trade is left to its own node to write
+const STREAM_GRACE: Duration = Duration::from_secs(10);
+
+/// Retry worker configuration
+#[derive(Debug, Clone)]
+pub struct TradePersistConfig {
+    /// Attempts before an entry is marked dead, and the delay between them
+    pub retry: RetryPolicy,
+    /// Worker poll interval
+    pub poll_interval_ms: u64,
+    /// Entries retried per poll
+    pub batch_size: i64,
+    /// Trades held in memory while the queue table is unreachable
+    pub buffer_capacity: usize,
+    /// Balance token that trades settle in
+    pub collateral_token: String,
+}
+
+impl Default for TradePersistConfig {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::new(10, 2, 600),
+            poll_interval_ms: 1000,
+            batch_size: 100,
+            buffer_capacity: 10_000,
+            collateral_token: "USDC".to_string(),
+        }
+    }
+}
+
+/// Queue entry as shown to operators
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct QueuedTrade {
+    pub id: Uuid,
+    pub trade_id: Uuid,
+    /// "trade" or "set_fill"
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Result of an operator-triggered reprocess
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ReprocessOutcome {
+    /// Persisted and removed from the queue
+    Persisted,
+    /// Failed again; back in the pending queue with a fresh retry budget
+    Failed { error: String },
+}
+
+/// Entry claimed by the worker
+#[derive(Debug, sqlx::FromRow)]
+struct DueEntry {
+    id: Uuid,
+    trade_id: Uuid,
+    kind: String,
+    payload: serde_json::Value,
+    attempts: i32,
+}
+
+/// What a queue entry writes
+#[derive(Debug, Clone)]
+enum QueuedFill {
+    Trade(TradeEvent),
+    Set(SetFill),
+}
+
+impl QueuedFill {
+    fn from_entry(entry: &DueEntry) -> Result<Self, String> {
+        let invalid = |e: serde_json::Error| format!("invalid payload: {}", e);
+        match entry.kind.as_str() {
+            "set_fill" => serde_json::from_value(entry.payload.clone()).map(Self::Set).map_err(invalid),
+            _ => serde_json::from_value(entry.payload.clone()).map(Self::Trade).map_err(invalid),
+        }
+    }
+
+    /// Trade id, or set id
+    fn id(&self) -> Uuid {
+        match self {
+            Self::Trade(trade) => trade.trade_id,
+            Self::Set(fill) => fill.set_id,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Trade(_) => "trade",
+            Self::Set(_) => "set_fill",
+        }
+    }
+
+    fn payload(&self) -> serde_json::Value {
+        match self {
+            Self::Trade(trade) => serde_json::to_value(trade),
+            Self::Set(fill) => serde_json::to_value(fill),
+        }
+        .unwrap_or_default()
+    }
+
+    async fn write(&self, pool: &PgPool, collateral_token: &str) -> Result<(), sqlx::Error> {
+        match self {
+            Self::Trade(trade) => OrderFlowOrchestrator::persist_trade(pool, trade, collateral_token).await,
+            Self::Set(fill) => write_batcher::write_set_fill(pool, fill, collateral_token).await.map(|_| ()),
+        }
+    }
+}
+
+/// Failed trade persists, retried in the background
+pub struct TradePersistQueue {
+    pool: PgPool,
+    config: TradePersistConfig,
+    /// Fills that could not even be queued
+    buffer: Mutex<VecDeque<(QueuedFill, String)>>,
+}
+
+impl TradePersistQueue {
+    pub fn new(pool: PgPool, config: TradePersistConfig) -> Self {
+        Self {
+            pool,
+            config,
+            buffer: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Persist `trade`, queueing it for retry on failure. Returns whether
+    /// it was persisted immediately.
+    pub async fn persist(&self, trade: &TradeEvent) -> bool {
+        self.persist_fill(QueuedFill::Trade(trade.clone())).await
+    }
+
+    /// Persist a complete set with its legs' maker fills, queueing it for
+    /// retry on failure. Returns whether it was persisted immediately.
+    pub async fn persist_set(&self, fill: &SetFill) -> bool {
+        self.persist_fill(QueuedFill::Set(fill.clone())).await
+    }
+
+    async fn persist_fill(&self, fill: QueuedFill) -> bool {
+        match fill.write(&self.pool, &self.config.collateral_token).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to persist {} {}, queueing for retry: {}", fill.kind(), fill.id(), e);
+                self.enqueue_fill(fill, &e.to_string()).await;
+                false
+            }
+        }
+    }
+
+    /// Queue a trade whose persist failed with `error`
+    pub async fn enqueue(&self, trade: &TradeEvent, error: &str) {
+        self.enqueue_fill(QueuedFill::Trade(trade.clone()), error).await
+    }
+
+    async fn enqueue_fill(&self, fill: QueuedFill, error: &str) {
+        match self.insert(&fill, error).await {
+            Ok(()) => crate::metrics::record_trade_persist("queued"),
+            Err(e) => {
+                tracing::error!("Failed to queue {} {} for retry, buffering in memory: {}", fill.kind(), fill.id(), e);
+                self.buffer_push(fill, error.to_string());
+            }
+        }
+    }
+
+    /// Trades currently held in memory
+    pub fn buffered(&self) -> usize {
+        self.buffer.lock().len()
+    }
+
+    fn buffer_push(&self, fill: QueuedFill, error: String) -> bool {
+        let mut buffer = self.buffer.lock();
+        if buffer.len() >= self.config.buffer_capacity {
+            tracing::error!(
+                "Trade persist buffer full ({}); dropping {} {}: {:?}",
+                self.config.buffer_capacity,
+                fill.kind(),
+                fill.id(),
+                fill
+            );
+            crate::metrics::record_trade_persist("dropped");
+            return false;
+        }
+        buffer.push_back((fill, error));
+        crate::metrics::record_trade_persist("buffered");
+        true
+    }
+
+    async fn insert(&self, fill: &QueuedFill, error: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO trade_persist_queue (trade_id, kind, payload, last_error)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (trade_id) DO NOTHING
+            "#,
+        )
+        .bind(fill.id())
+        .bind(fill.kind())
+        .bind(fill.payload())
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Queue entries, newest first
+    pub async fn list(&self, status: Option<&str>, limit: i64) -> Result<Vec<QueuedTrade>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, trade_id, kind, payload, status, attempts, last_error, next_attempt_at, created_at, updated_at
+            FROM trade_persist_queue
+            WHERE ($1::text IS NULL OR status = $1)
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// (pending, dead) entry counts
+    pub async fn counts(&self) -> Result<(i64, i64), sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FILTER (WHERE status = 'pending'), COUNT(*) FILTER (WHERE status = 'dead')
+            FROM trade_persist_queue
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Retry one entry now, whatever its status. `None` if it does not exist.
+    pub async fn reprocess(&self, id: Uuid) -> Result<Option<ReprocessOutcome>, sqlx::Error> {
+        let entry: Option<DueEntry> =
+            sqlx::query_as("SELECT id, trade_id, kind, payload, attempts FROM trade_persist_queue WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+
+        match self.try_persist(&entry).await {
+            Ok(()) => {
+                self.remove(entry.id).await?;
+                tracing::info!("Reprocessed queued trade {}", entry.trade_id);
+                Ok(Some(ReprocessOutcome::Persisted))
+            }
+            Err(error) => {
+                sqlx::query(
+                    r#"
+                    UPDATE trade_persist_queue
+                    SET status = 'pending', attempts = 0, last_error = $1,
+                        next_attempt_at = NOW() + make_interval(secs => $2), updated_at = NOW()
+                    WHERE id = $3
+                    "#,
+                )
+                .bind(&error)
+                .bind(self.config.retry.backoff(0).as_secs_f64())
+                .bind(entry.id)
+                .execute(&self.pool)
+                .await?;
+                Ok(Some(ReprocessOutcome::Failed { error }))
+            }
+        }
+    }
+
+    /// Move every dead entry back to pending with a fresh retry budget
+    pub async fn requeue_dead(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE trade_persist_queue
+            SET status = 'pending', attempts = 0, next_attempt_at = NOW(), updated_at = NOW()
+            WHERE status = 'dead'
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Consumer group the stream backstop reads the event bus as
+    pub const GROUP: &'static str = "trade_persistence";
+
+    /// Consume trades from the event bus and write the ones their node
+    /// never wrote. Only useful with a durable bus, on worker nodes.
+    pub fn start_consumer(self: Arc<Self>, bus: Arc<EventBus>) {
+        bus.consume(Self::GROUP, move |event| {
+            let queue = self.clone();
+            async move {
+                if let BusEvent::Trade(trade) = event {
+                    queue.backstop(&trade).await.map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+        });
+    }
+
+    /// Write `trade` with its maker fill unless it is on record, queued or
+    /// buffered. Waits out [`STREAM_GRACE`] first so the matching node's
+    /// own write goes first.
+    async fn backstop(&self, trade: &TradeEvent) -> Result<(), sqlx::Error> {
+        let wait_ms = trade.timestamp + STREAM_GRACE.as_millis() as i64 - Utc::now().timestamp_millis();
+        if wait_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(wait_ms as u64)).await;
+        }
+
+        if self.buffer.lock().iter().any(|(buffered, _)| buffered.id() == trade.trade_id) {
+            return Ok(());
+        }
+        let known: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM trades WHERE id = $1)
+                OR EXISTS (SELECT 1 FROM trade_persist_queue WHERE trade_id = $1)
+            "#,
+        )
+        .bind(trade.trade_id)
+        .fetch_one(&self.pool)
+        .await?;
+        if known {
+            return Ok(());
+        }
+
+        // Self-custody trades settle on-chain and are written by their
+        // handler; a taker order that was never written can't be referenced
+        let taker: Option<Option<String>> = sqlx::query_scalar("SELECT token_id FROM orders WHERE id = $1")
+            .bind(trade.taker_order_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        match taker {
+            None => {
+                tracing::error!(
+                    "Trade {} was never written and its taker order {} is missing: {:?}",
+                    trade.trade_id,
+                    trade.taker_order_id,
+                    trade
+                );
+                Ok(())
+            }
+            Some(Some(_)) => {
+                tracing::warn!("Self-custody trade {} was never written; skipping", trade.trade_id);
+                Ok(())
+            }
+            Some(None) => {
+                write_batcher::write_trades(&self.pool, std::slice::from_ref(trade), &self.config.collateral_token)
+                    .await?;
+                tracing::warn!("Wrote trade {} from the event stream", trade.trade_id);
+                crate::metrics::record_trade_persist("recovered");
+                Ok(())
+            }
+        }
+    }
+
+    /// Spawn the background retry worker. Every role runs it: entries are
+    /// claimed with SKIP LOCKED, and the in-memory buffer is per process.
+    pub fn start_worker(self: Arc<Self>) {
+        tokio::spawn(async move {
+            tracing::info!("Trade persist retry worker started");
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.flush_buffer().await {
+                    tracing::warn!("Trade persist buffer still not flushable: {}", e);
+                    continue;
+                }
+                if let Err(e) = self.process_due().await {
+                    tracing::error!("Trade persist retry worker error: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Move buffered trades into the queue table
+    async fn flush_buffer(&self) -> Result<(), sqlx::Error> {
+        loop {
+            let Some((fill, error)) = self.buffer.lock().pop_front() else {
+                return Ok(());
+            };
+            if let Err(e) = self.insert(&fill, &error).await {
+                self.buffer.lock().push_front((fill, error));
+                return Err(e);
+            }
+            crate::metrics::record_trade_persist("queued");
+        }
+    }
+
+    /// Retry all due entries once
+    async fn process_due(&self) -> Result<(), sqlx::Error> {
+        // Claim a batch by pushing next_attempt_at forward; SKIP LOCKED lets
+        // several instances share the queue without double-processing.
+        let due: Vec<DueEntry> = sqlx::query_as(
+            r#"
+            WITH claimed AS (
+                SELECT id
+                FROM trade_persist_queue
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE trade_persist_queue q
+            SET next_attempt_at = NOW() + INTERVAL '1 minute'
+            FROM claimed
+            WHERE q.id = claimed.id
+            RETURNING q.id, q.trade_id, q.kind, q.payload, q.attempts
+            "#,
+        )
+        .bind(self.config.batch_size)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for entry in due {
+            match self.try_persist(&entry).await {
+                Ok(()) => {
+                    self.remove(entry.id).await?;
+                    tracing::info!("Persisted trade {} after {} failed attempt(s)", entry.trade_id, entry.attempts + 1);
+                    crate::metrics::record_trade_persist("persisted");
+                }
+                Err(error) => self.record_failure(&entry, &error).await?,
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn try_persist(&self, entry: &DueEntry) -> Result<(), String> {
+        QueuedFill::from_entry(entry)?
+            .write(&self.pool, &self.config.collateral_token)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn record_failure(&self, entry: &DueEntry, error: &str) -> Result<(), sqlx::Error> {
+        let attempts = entry.attempts + 1;
+        let exhausted = self.config.retry.exhausted(attempts);
+        sqlx::query(
+            r#"
+            UPDATE trade_persist_queue
+            SET status = $1, attempts = $2, last_error = $3,
+                next_attempt_at = NOW() + make_interval(secs => $4), updated_at = NOW()
+            WHERE id = $5
+            "#,
+        )
+        .bind(if exhausted { "dead" } else { "pending" })
+        .bind(attempts)
+        .bind(error)
+        .bind(self.config.retry.backoff(attempts).as_secs_f64())
+        .bind(entry.id)
+        .execute(&self.pool)
+        .await?;
+
+        if exhausted {
+            tracing::error!(
+                "Trade {} could not be persisted after {} attempts; moved to dead letter: {}",
+                entry.trade_id,
+                attempts,
+                error
+            );
+            crate::metrics::record_trade_persist("dead");
+            system_events::record(
+                &self.pool,
+                SystemEventKind::TradePersistDeadLettered,
+                Some(&entry.trade_id.to_string()),
+                format!("Trade could not be persisted after {} attempts", attempts),
+                serde_json::json!({ "queue_id": entry.id, "attempts": attempts, "error": error }),
+            )
+            .await;
+        } else {
+            tracing::warn!("Trade {} persist attempt {} failed: {}", entry.trade_id, attempts, error);
+            crate::metrics::record_trade_persist("retry");
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM trade_persist_queue WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market::ShareType;
+    use crate::models::{OrderSide, OrderStatus};
+    use crate::services::matching::Side;
+    use crate::services::order_gateway::GatewayOrder;
+    use crate::test_support::{gateway_order, TestApp, MAKER, TAKER};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn trade() -> TradeEvent {
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        TradeEvent::new(
+            format!("{}:{}:yes", market_id, outcome_id),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "0xmaker".to_string(),
+            "0xtaker".to_string(),
+            Side::Buy,
+            dec!(0.55),
+            dec!(10),
+            Decimal::ZERO,
+            dec!(0.01),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_buffer_is_bounded_and_payload_round_trips() {
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let config = TradePersistConfig {
+            buffer_capacity: 2,
+            ..Default::default()
+        };
+        let queue = TradePersistQueue::new(pool, config);
+
+        assert!(queue.buffer_push(QueuedFill::Trade(trade()), "e".into()));
+        assert!(queue.buffer_push(QueuedFill::Trade(trade()), "e".into()));
+        assert!(!queue.buffer_push(QueuedFill::Trade(trade()), "e".into()));
+        assert_eq!(queue.buffered(), 2);
+
+        // What the worker reads back must match what was queued
+        let original = trade();
+        let restored: TradeEvent = serde_json::from_value(serde_json::to_value(&original).unwrap()).unwrap();
+        assert_eq!(restored.trade_id, original.trade_id);
+        assert_eq!(restored.symbol, original.symbol);
+        assert_eq!(restored.price, original.price);
+        assert_eq!(restored.taker_fee, original.taker_fee);
+    }
+
+    #[tokio::test]
+    async fn test_stream_backstop_writes_only_lost_trades() {
+        let app = TestApp::builder().build().await;
+        let (market_id, yes, _) = app.create_market().await;
+        app.grant_shares(MAKER, market_id, yes, ShareType::Yes, Decimal::TEN).await;
+        app.deposit(TAKER, Decimal::ONE_HUNDRED).await;
+        let gateway = &app.state.order_gateway;
+        gateway.place(gateway_order(market_id, yes, MAKER, OrderSide::Sell, dec!(0.5))).await.unwrap();
+        let taking = gateway
+            .place(GatewayOrder { amount: dec!(4), ..gateway_order(market_id, yes, TAKER, OrderSide::Buy, dec!(0.6)) })
+            .await
+            .unwrap();
+        assert_eq!(taking.status, OrderStatus::Filled);
+        let mut written = taking.trades[0].clone();
+        // Matched long enough ago that its node had its chance to write it
+        written.timestamp -= 2 * STREAM_GRACE.as_millis() as i64;
+
+        let maker_fill = || async {
+            sqlx::query_as::<_, (Decimal, String)>("SELECT filled_amount, status::text FROM orders WHERE id = $1")
+                .bind(written.maker_order_id)
+                .fetch_one(&app.db.pool)
+                .await
+                .unwrap()
+        };
+        let taker_shares = || async {
+            sqlx::query_scalar::<_, Decimal>(
+                "SELECT amount FROM shares WHERE user_address = $1 AND outcome_id = $2 AND share_type = 'yes'",
+            )
+            .bind(TAKER)
+            .bind(yes)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap()
+        };
+
+        // Written by its node: nothing to do
+        app.state.trade_persist_queue.backstop(&written).await.unwrap();
+        assert_eq!(maker_fill().await, (dec!(4), "partially_filled".to_string()));
+        assert_eq!(taker_shares().await, dec!(4));
+
+        // Lost in a crash between matching and writing: written from the
+        // stream with its maker fill and holdings, once
+        let lost = TradeEvent { trade_id: Uuid::new_v4(), amount: dec!(2), ..written.clone() };
+        app.state.trade_persist_queue.backstop(&lost).await.unwrap();
+        app.state.trade_persist_queue.backstop(&lost).await.unwrap();
+        let recorded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM trades WHERE id = $1")
+            .bind(lost.trade_id)
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(recorded, 1);
+        assert_eq!(maker_fill().await, (dec!(6), "partially_filled".to_string()));
+        assert_eq!(taker_shares().await, dec!(6));
+    }
+}
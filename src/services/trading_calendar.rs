@@ -0,0 +1,305 @@
+//! Market Trading Calendars
+//!
+//! A market without a schedule trades whenever it is active. A schedule
+//! limits trading to weekly sessions in the market's own timezone (say
+//! 09:30-16:00 America/New_York on weekdays, mirroring an underlying
+//! exchange) and can add halts: fixed periods, such as holidays, during
+//! which the market does not trade even inside a session. A schedule with
+//! halts but no sessions trades around the clock outside the halts.
+//!
+//! Outside trading hours new orders are refused and resting orders can
+//! still be cancelled; as matching only happens when an order arrives,
+//! nothing trades until the market reopens.
+//!
+//! Session times are local wall-clock times, so sessions follow daylight
+//! saving changes. A session whose close is not after its open runs past
+//! midnight; one that opens in a skipped hour opens when the clocks allow.
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// How far ahead the next open and close are looked for
+const LOOKAHEAD_DAYS: u64 = 366;
+
+/// A weekly trading session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Days the session opens on
+    pub days: Vec<Weekday>,
+    /// Local open time, `HH:MM` or `HH:MM:SS`
+    pub open: NaiveTime,
+    /// Local close time; not after `open` means the next day
+    pub close: NaiveTime,
+}
+
+/// A period without trading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Halt {
+    /// Start (timestamp in milliseconds)
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub starts_at: DateTime<Utc>,
+    /// End (timestamp in milliseconds), exclusive
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub ends_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// When a market trades
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradingSchedule {
+    pub timezone: Tz,
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+    #[serde(default)]
+    pub halts: Vec<Halt>,
+}
+
+/// Whether a market is trading, and when that changes
+#[derive(Debug, Clone, Serialize)]
+pub struct TradingHours {
+    pub timezone: Tz,
+    pub is_open: bool,
+    /// Next time the market opens (timestamp in milliseconds)
+    pub next_open: Option<i64>,
+    /// Next time the market closes (timestamp in milliseconds); `null` if
+    /// it does not close within a year
+    pub next_close: Option<i64>,
+}
+
+impl TradingSchedule {
+    /// Why the schedule cannot be used, if it cannot
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.sessions.iter().any(|s| s.days.is_empty()) {
+            return Err("every session needs at least one day");
+        }
+        if self.halts.iter().any(|h| h.ends_at <= h.starts_at) {
+            return Err("every halt must end after it starts");
+        }
+        Ok(())
+    }
+
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.intervals(now, 1).first().is_some_and(|(open, _)| *open <= now)
+    }
+
+    /// Start of the next trading period after `now`
+    pub fn next_open(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.intervals(now, LOOKAHEAD_DAYS)
+            .into_iter()
+            .map(|(open, _)| open)
+            .find(|open| *open > now)
+    }
+
+    /// End of the current or next trading period
+    pub fn next_close(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let horizon = now + Days::new(LOOKAHEAD_DAYS);
+        self.intervals(now, LOOKAHEAD_DAYS)
+            .first()
+            .map(|(_, close)| *close)
+            .filter(|close| *close < horizon)
+    }
+
+    pub fn hours(&self, now: DateTime<Utc>) -> TradingHours {
+        TradingHours {
+            timezone: self.timezone,
+            is_open: self.is_open(now),
+            next_open: self.next_open(now).map(|t| t.timestamp_millis()),
+            next_close: self.next_close(now).map(|t| t.timestamp_millis()),
+        }
+    }
+
+    /// Trading periods ending after `from`, up to `days` ahead: sessions
+    /// merged where they touch, less the halts, in order
+    fn intervals(&self, from: DateTime<Utc>, days: u64) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let horizon = from + Days::new(days);
+        let mut periods = if self.sessions.is_empty() {
+            vec![(from, horizon)]
+        } else {
+            // Start a day early to catch a session running past midnight
+            let first = from.with_timezone(&self.timezone).date_naive() - Days::new(1);
+            let mut periods = Vec::new();
+            for date in first.iter_days().take(days as usize + 2) {
+                for session in self.sessions.iter().filter(|s| s.days.contains(&date.weekday())) {
+                    let close_date = if session.close <= session.open { date + Days::new(1) } else { date };
+                    if let (Some(open), Some(close)) = (self.at(date, session.open), self.at(close_date, session.close)) {
+                        if close > from {
+                            periods.push((open, close));
+                        }
+                    }
+                }
+            }
+            periods.sort();
+            merge(periods)
+        };
+
+        for halt in &self.halts {
+            periods = periods
+                .into_iter()
+                .flat_map(|(open, close)| {
+                    if halt.ends_at <= open || halt.starts_at >= close {
+                        return vec![(open, close)];
+                    }
+                    [(open, halt.starts_at), (halt.ends_at, close)]
+                        .into_iter()
+                        .filter(|(open, close)| open < close)
+                        .collect()
+                })
+                .collect();
+        }
+        periods.retain(|(_, close)| *close > from);
+        periods
+    }
+
+    /// Local `date` `time` as UTC; in a skipped hour, an hour later
+    fn at(&self, date: NaiveDate, time: NaiveTime) -> Option<DateTime<Utc>> {
+        let local = date.and_time(time);
+        self.timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .or_else(|| self.timezone.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest())
+            .map(|t| t.with_timezone(&Utc))
+    }
+}
+
+/// Join sorted periods that overlap or touch
+fn merge(periods: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::with_capacity(periods.len());
+    for (open, close) in periods {
+        match merged.last_mut() {
+            Some(last) if open <= last.1 => last.1 = last.1.max(close),
+            _ => merged.push((open, close)),
+        }
+    }
+    merged
+}
+
+#[derive(sqlx::FromRow)]
+struct ScheduleRow {
+    timezone: String,
+    sessions: sqlx::types::Json<Vec<Session>>,
+    halts: sqlx::types::Json<Vec<Halt>>,
+}
+
+/// A market's schedule; `None` if it trades whenever active
+pub async fn load(pool: &PgPool, market_id: Uuid) -> Result<Option<TradingSchedule>, sqlx::Error> {
+    let row: Option<ScheduleRow> =
+        sqlx::query_as("SELECT timezone, sessions, halts FROM market_trading_schedules WHERE market_id = $1")
+            .bind(market_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.and_then(|row| match row.timezone.parse::<Tz>() {
+        Ok(timezone) => Some(TradingSchedule {
+            timezone,
+            sessions: row.sessions.0,
+            halts: row.halts.0,
+        }),
+        Err(_) => {
+            tracing::warn!("Trading schedule of market {} has unknown timezone {}", market_id, row.timezone);
+            None
+        }
+    }))
+}
+
+/// Set a market's schedule; `false` if the market does not exist
+pub async fn save(pool: &PgPool, market_id: Uuid, schedule: &TradingSchedule) -> Result<bool, sqlx::Error> {
+    let saved = sqlx::query(
+        r#"
+        INSERT INTO market_trading_schedules (market_id, timezone, sessions, halts)
+        SELECT id, $2, $3, $4 FROM markets WHERE id = $1
+        ON CONFLICT (market_id) DO UPDATE
+        SET timezone = EXCLUDED.timezone,
+            sessions = EXCLUDED.sessions,
+            halts = EXCLUDED.halts,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(market_id)
+    .bind(schedule.timezone.name())
+    .bind(sqlx::types::Json(&schedule.sessions))
+    .bind(sqlx::types::Json(&schedule.halts))
+    .execute(pool)
+    .await?;
+    Ok(saved.rows_affected() > 0)
+}
+
+/// Let a market trade whenever it is active again
+pub async fn clear(pool: &PgPool, market_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM market_trading_schedules WHERE market_id = $1")
+        .bind(market_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_sessions_follow_local_time() {
+        let schedule: TradingSchedule = serde_json::from_value(serde_json::json!({
+            "timezone": "America/New_York",
+            "sessions": [{ "days": ["Mon", "Tue", "Wed", "Thu", "Fri"], "open": "09:30", "close": "16:00" }],
+            "halts": [{ "starts_at": utc("2026-07-03T00:00:00Z").timestamp_millis(),
+                        "ends_at": utc("2026-07-04T00:00:00Z").timestamp_millis(),
+                        "reason": "Independence Day (observed)" }],
+        }))
+        .unwrap();
+        assert!(schedule.validate().is_ok());
+
+        // Thursday 2 July, 10:00 EDT (UTC-4)
+        let now = utc("2026-07-02T14:00:00Z");
+        assert!(schedule.is_open(now));
+        assert_eq!(schedule.next_close(now), Some(utc("2026-07-02T20:00:00Z")));
+        // Friday is halted, so the next session is Monday's
+        assert_eq!(schedule.next_open(now), Some(utc("2026-07-06T13:30:00Z")));
+
+        // Saturday
+        let weekend = utc("2026-07-04T15:00:00Z");
+        assert!(!schedule.is_open(weekend));
+        assert_eq!(schedule.next_close(weekend), Some(utc("2026-07-06T20:00:00Z")));
+
+        // In winter the same local open is 14:30 UTC (EST, UTC-5)
+        assert_eq!(schedule.next_open(utc("2026-01-05T12:00:00Z")), Some(utc("2026-01-05T14:30:00Z")));
+    }
+
+    #[test]
+    fn test_overnight_sessions_and_halts_only() {
+        let overnight = TradingSchedule {
+            timezone: Tz::UTC,
+            sessions: vec![Session {
+                days: vec![Weekday::Sun],
+                open: "22:00".parse().unwrap(),
+                close: "02:00".parse().unwrap(),
+            }],
+            halts: Vec::new(),
+        };
+        // Monday 01:00 is still Sunday's session
+        assert!(overnight.is_open(utc("2026-03-02T01:00:00Z")));
+        assert!(!overnight.is_open(utc("2026-03-02T03:00:00Z")));
+
+        let around_the_clock = TradingSchedule {
+            timezone: Tz::UTC,
+            sessions: Vec::new(),
+            halts: vec![Halt {
+                starts_at: utc("2026-03-02T00:00:00Z"),
+                ends_at: utc("2026-03-02T06:00:00Z"),
+                reason: None,
+            }],
+        };
+        let before = utc("2026-03-01T12:00:00Z");
+        assert!(around_the_clock.is_open(before));
+        assert_eq!(around_the_clock.next_close(before), Some(utc("2026-03-02T00:00:00Z")));
+        assert!(!around_the_clock.is_open(utc("2026-03-02T03:00:00Z")));
+        // Never closes once the halt is over
+        assert_eq!(around_the_clock.next_close(utc("2026-03-02T07:00:00Z")), None);
+    }
+}
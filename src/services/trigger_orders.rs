@@ -0,0 +1,686 @@
+//! Trigger Orders
+//!
+//! Stop and stop-limit orders: an order held back until the price of its
+//! market key reaches a trigger price, then placed through the order flow
+//! like any other user order - a market order for a stop (its price is the
+//! worst it accepts), a limit order for a stop-limit. The price watched is
+//! either the last trade or the midpoint of the book, including the
+//! mint/merge route (see [`MatchingEngine::quoted_mid`]).
+//!
+//! An `above` trigger fires once the price is at or above its trigger
+//! price, a `below` one once it is at or below, so a trigger already met
+//! when it is created fires on the next evaluation. Triggers are evaluated
+//! on every trade and once a second by each node running a live engine;
+//! a trigger is claimed before its order is placed, so it fires once.
+//!
+//! Nothing is reserved while a trigger waits: the placed order reserves
+//! collateral or shares like any other, and the trigger fails if it
+//! cannot. Owners follow their triggers on the WebSocket `orders` channel.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::market::{DbShareType, ShareType};
+use crate::models::{Address, OrderSide, OrderType, TimeInForce};
+use crate::services::event_bus::{BusEvent, EventBus};
+use crate::services::matching::{
+    MatchingEngine, OrderFlowOrchestrator, OrderIntent, OrderbookSnapshot, SelfTradePrevention,
+};
+use crate::services::order_gateway::OrderSource;
+
+/// How often triggers are evaluated against midpoints and expired
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Active triggers one account may have
+pub const MAX_ACTIVE_PER_ACCOUNT: i64 = 50;
+
+/// Columns of a trigger order, in [`TriggerOrder`] order
+const COLUMNS: &str = "id, user_address, market_id, outcome_id, share_type, side, order_type, \
+     limit_price AS price, size AS amount, trigger_price, trigger_condition, price_source, status, \
+     triggered_at, triggered_price, executed_order_id AS order_id, error_message AS error, expires_at, created_at";
+
+/// Which way the price has to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "trigger_condition")]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerCondition {
+    /// At or above the trigger price
+    #[sqlx(rename = "price_above")]
+    Above,
+    /// At or below the trigger price
+    #[sqlx(rename = "price_below")]
+    Below,
+}
+
+impl TriggerCondition {
+    /// A buy stop fires on a rise, a sell stop on a fall
+    pub fn for_side(side: OrderSide) -> Self {
+        match side {
+            OrderSide::Buy => TriggerCondition::Above,
+            OrderSide::Sell => TriggerCondition::Below,
+        }
+    }
+}
+
+/// Price a trigger watches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "trigger_price_source", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    #[default]
+    LastTrade,
+    Midpoint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "trigger_order_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerOrderStatus {
+    /// Waiting for its price
+    Active,
+    /// Fired and its order placed; the order is followed through `order_id`
+    Triggered,
+    Cancelled,
+    Expired,
+    /// Fired but its order was refused
+    Failed,
+}
+
+/// A stop or stop-limit order
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TriggerOrder {
+    pub id: Uuid,
+    pub user_address: Address,
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    #[sqlx(try_from = "DbShareType")]
+    pub share_type: ShareType,
+    pub side: OrderSide,
+    /// `market` for a stop, `limit` for a stop-limit
+    pub order_type: OrderType,
+    /// Limit price of the placed order; the worst price for a stop
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub trigger_price: Decimal,
+    pub trigger_condition: TriggerCondition,
+    pub price_source: PriceSource,
+    pub status: TriggerOrderStatus,
+    /// When the trigger fired (timestamp in milliseconds)
+    #[serde(with = "chrono::serde::ts_milliseconds_option")]
+    pub triggered_at: Option<DateTime<Utc>>,
+    /// Price that fired it
+    pub triggered_price: Option<Decimal>,
+    /// Order placed when it fired
+    pub order_id: Option<Uuid>,
+    /// Why its order was refused, or why it was cancelled by the exchange
+    pub error: Option<String>,
+    #[serde(with = "chrono::serde::ts_milliseconds_option")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A trigger order changed state; pushed to its owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerOrderUpdateEvent {
+    pub user_address: String,
+    pub trigger: TriggerOrder,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateTriggerOrder {
+    pub market_id: Uuid,
+    pub outcome_id: Uuid,
+    pub share_type: ShareType,
+    pub side: OrderSide,
+    #[serde(default = "default_order_type")]
+    pub order_type: OrderType,
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub trigger_price: Decimal,
+    /// Defaults to `above` for buys and `below` for sells
+    #[serde(default)]
+    pub trigger_condition: Option<TriggerCondition>,
+    #[serde(default)]
+    pub price_source: PriceSource,
+    /// Expiry (timestamp in milliseconds)
+    #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+fn default_order_type() -> OrderType {
+    OrderType::Limit
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TriggerOrderError {
+    #[error("Trigger order not found")]
+    NotFound,
+
+    #[error("Trigger order is {0:?}, not active")]
+    NotActive(TriggerOrderStatus),
+
+    #[error("Market not found")]
+    MarketNotFound,
+
+    #[error("Market is {0}")]
+    MarketNotActive(String),
+
+    #[error("Invalid trigger order: price {0} outside (0, 1)")]
+    InvalidPrice(Decimal),
+
+    #[error("Invalid trigger order: trigger price {0} outside (0, 1)")]
+    InvalidTriggerPrice(Decimal),
+
+    #[error("Invalid trigger order: amount must be positive")]
+    InvalidAmount,
+
+    #[error("Invalid trigger order: already expired")]
+    AlreadyExpired,
+
+    #[error("Too many active trigger orders: cap of {0} reached")]
+    TooManyActive(i64),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl TriggerOrderError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TriggerOrderError::NotFound => "TRIGGER_ORDER_NOT_FOUND",
+            TriggerOrderError::NotActive(_) => "TRIGGER_ORDER_NOT_ACTIVE",
+            TriggerOrderError::MarketNotFound => "MARKET_NOT_FOUND",
+            TriggerOrderError::MarketNotActive(_) => "MARKET_NOT_ACTIVE",
+            TriggerOrderError::InvalidPrice(_) => "INVALID_PRICE",
+            TriggerOrderError::InvalidTriggerPrice(_) => "INVALID_TRIGGER_PRICE",
+            TriggerOrderError::InvalidAmount => "INVALID_AMOUNT",
+            TriggerOrderError::AlreadyExpired => "ALREADY_EXPIRED",
+            TriggerOrderError::TooManyActive(_) => "TOO_MANY_TRIGGER_ORDERS",
+            TriggerOrderError::Database(_) => "DB_ERROR",
+        }
+    }
+}
+
+fn in_unit_interval(price: Decimal) -> bool {
+    price > Decimal::ZERO && price < Decimal::ONE
+}
+
+/// Store a new active trigger for `user_address`
+pub async fn create(
+    pool: &PgPool,
+    user_address: &Address,
+    req: CreateTriggerOrder,
+) -> Result<TriggerOrder, TriggerOrderError> {
+    if !in_unit_interval(req.price) {
+        return Err(TriggerOrderError::InvalidPrice(req.price));
+    }
+    if !in_unit_interval(req.trigger_price) {
+        return Err(TriggerOrderError::InvalidTriggerPrice(req.trigger_price));
+    }
+    if req.amount <= Decimal::ZERO {
+        return Err(TriggerOrderError::InvalidAmount);
+    }
+    if req.expires_at.is_some_and(|at| at <= Utc::now()) {
+        return Err(TriggerOrderError::AlreadyExpired);
+    }
+
+    let status: String = sqlx::query_scalar("SELECT status::text FROM markets WHERE id = $1")
+        .bind(req.market_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(TriggerOrderError::MarketNotFound)?;
+    if status != "active" {
+        return Err(TriggerOrderError::MarketNotActive(status));
+    }
+    let active: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM trigger_orders WHERE user_address = $1 AND status = 'active'")
+            .bind(user_address)
+            .fetch_one(pool)
+            .await?;
+    if active >= MAX_ACTIVE_PER_ACCOUNT {
+        return Err(TriggerOrderError::TooManyActive(MAX_ACTIVE_PER_ACCOUNT));
+    }
+
+    let trigger_type = match req.order_type {
+        OrderType::Market => "stop",
+        OrderType::Limit => "stop_limit",
+    };
+    let order = sqlx::query_as(&format!(
+        r#"
+        INSERT INTO trigger_orders
+            (user_address, market_id, outcome_id, share_type, side, order_type, trigger_type,
+             limit_price, size, trigger_price, trigger_condition, price_source, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7::trigger_order_type, $8, $9, $10, $11, $12, $13)
+        RETURNING {}
+        "#,
+        COLUMNS
+    ))
+    .bind(user_address)
+    .bind(req.market_id)
+    .bind(req.outcome_id)
+    .bind(DbShareType(req.share_type))
+    .bind(req.side)
+    .bind(req.order_type)
+    .bind(trigger_type)
+    .bind(req.price)
+    .bind(req.amount)
+    .bind(req.trigger_price)
+    .bind(req.trigger_condition.unwrap_or(TriggerCondition::for_side(req.side)))
+    .bind(req.price_source)
+    .bind(req.expires_at)
+    .fetch_one(pool)
+    .await?;
+    Ok(order)
+}
+
+/// Triggers of `user_address`, newest first
+pub async fn list(
+    pool: &PgPool,
+    user_address: &Address,
+    market_id: Option<Uuid>,
+    status: Option<TriggerOrderStatus>,
+    limit: i64,
+) -> Result<Vec<TriggerOrder>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        r#"
+        SELECT {} FROM trigger_orders
+        WHERE user_address = $1 AND market_id IS NOT NULL
+          AND ($2::uuid IS NULL OR market_id = $2)
+          AND ($3::trigger_order_status IS NULL OR status = $3)
+        ORDER BY created_at DESC
+        LIMIT $4
+        "#,
+        COLUMNS
+    ))
+    .bind(user_address)
+    .bind(market_id)
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get(pool: &PgPool, user_address: &Address, id: Uuid) -> Result<Option<TriggerOrder>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM trigger_orders WHERE id = $1 AND user_address = $2 AND market_id IS NOT NULL",
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(user_address)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Cancel an active trigger of `user_address`
+pub async fn cancel(pool: &PgPool, user_address: &Address, id: Uuid) -> Result<TriggerOrder, TriggerOrderError> {
+    let cancelled: Option<TriggerOrder> = sqlx::query_as(&format!(
+        r#"
+        UPDATE trigger_orders SET status = 'cancelled', updated_at = NOW()
+        WHERE id = $1 AND user_address = $2 AND status = 'active'
+        RETURNING {}
+        "#,
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(user_address)
+    .fetch_optional(pool)
+    .await?;
+    match cancelled {
+        Some(order) => Ok(order),
+        None => match get(pool, user_address, id).await? {
+            Some(order) => Err(TriggerOrderError::NotActive(order.status)),
+            None => Err(TriggerOrderError::NotFound),
+        },
+    }
+}
+
+/// Cancel every active trigger of `user_address`, returning how many
+pub async fn cancel_all(pool: &PgPool, user_address: &Address) -> Result<u64, sqlx::Error> {
+    let cancelled = sqlx::query(
+        "UPDATE trigger_orders SET status = 'cancelled', updated_at = NOW() WHERE user_address = $1 AND status = 'active'",
+    )
+    .bind(user_address)
+    .execute(pool)
+    .await?;
+    Ok(cancelled.rows_affected())
+}
+
+/// Evaluates triggers against the live engine and places the orders of
+/// those that fire
+pub struct TriggerMonitor {
+    pool: PgPool,
+    engine: Arc<MatchingEngine>,
+    order_flow: Arc<OrderFlowOrchestrator>,
+    event_bus: Arc<EventBus>,
+    /// Last trade price per market key seen by this node
+    last_trades: Mutex<HashMap<String, Decimal>>,
+}
+
+impl TriggerMonitor {
+    pub fn new(
+        pool: PgPool,
+        engine: Arc<MatchingEngine>,
+        order_flow: Arc<OrderFlowOrchestrator>,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        Self {
+            pool,
+            engine,
+            order_flow,
+            event_bus,
+            last_trades: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Spawn the loop evaluating triggers on trades and on a timer
+    pub fn start(self: Arc<Self>) {
+        let mut trades = self.engine.subscribe_trades();
+        tokio::spawn(async move {
+            tracing::info!("Trigger order monitor started");
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                tokio::select! {
+                    trade = trades.recv() => match trade {
+                        Ok(trade) => self.on_trade(&trade.symbol, trade.price).await,
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            tracing::warn!("Trigger order monitor lagged by {} trades", n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = interval.tick() => self.sweep().await,
+                }
+            }
+            tracing::warn!("Trigger order monitor stopped");
+        });
+    }
+
+    /// Record a trade and fire the last-trade triggers it meets
+    pub async fn on_trade(&self, market_key: &str, price: Decimal) {
+        // Sandbox books live in their own namespaces
+        if OrderbookSnapshot::split_namespace(market_key).0.is_some() {
+            return;
+        }
+        self.last_trades.lock().insert(market_key.to_string(), price);
+        if let Err(e) = self.evaluate(market_key, PriceSource::LastTrade, price).await {
+            tracing::error!("Failed to evaluate trigger orders of {}: {}", market_key, e);
+        }
+    }
+
+    /// Expire due triggers, then evaluate every active trigger against its
+    /// key's current midpoint or last trade
+    pub async fn sweep(&self) {
+        if let Err(e) = self.expire().await {
+            tracing::error!("Failed to expire trigger orders: {}", e);
+        }
+        let keys: Vec<(Uuid, Uuid, DbShareType, PriceSource)> = match sqlx::query_as(
+            r#"
+            SELECT DISTINCT market_id, outcome_id, share_type, price_source
+            FROM trigger_orders
+            WHERE status = 'active' AND market_id IS NOT NULL
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        {
+            Ok(keys) => keys,
+            Err(e) => {
+                tracing::error!("Failed to load trigger order keys: {}", e);
+                return;
+            }
+        };
+        for (market_id, outcome_id, DbShareType(share_type), source) in keys {
+            let market_key = format!("{}:{}:{}", market_id, outcome_id, share_type);
+            let price = match source {
+                PriceSource::Midpoint => self.engine.quoted_mid(&market_key),
+                PriceSource::LastTrade => self.last_trades.lock().get(&market_key).copied(),
+            };
+            if let Some(price) = price {
+                if let Err(e) = self.evaluate(&market_key, source, price).await {
+                    tracing::error!("Failed to evaluate trigger orders of {}: {}", market_key, e);
+                }
+            }
+        }
+    }
+
+    /// Fire the active triggers on `market_key` watching `source` that
+    /// `price` meets, oldest first; returns how many fired
+    pub async fn evaluate(&self, market_key: &str, source: PriceSource, price: Decimal) -> Result<usize, sqlx::Error> {
+        let Some((market_id, outcome_id, share_type)) = OrderbookSnapshot::parse_market_key(market_key) else {
+            return Ok(0);
+        };
+        let met: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT id FROM trigger_orders
+            WHERE market_id = $1 AND outcome_id = $2 AND share_type = $3 AND price_source = $4
+              AND status = 'active' AND (expires_at IS NULL OR expires_at > NOW())
+              AND ((trigger_condition = 'price_above' AND $5 >= trigger_price)
+                OR (trigger_condition = 'price_below' AND $5 <= trigger_price))
+            ORDER BY created_at
+            "#,
+        )
+        .bind(market_id)
+        .bind(outcome_id)
+        .bind(DbShareType(share_type))
+        .bind(source)
+        .bind(price)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut fired = 0;
+        for id in met {
+            if self.fire(id, price).await? {
+                fired += 1;
+            }
+        }
+        Ok(fired)
+    }
+
+    /// Claim trigger `id` and place its order; false if another evaluation
+    /// claimed it first
+    async fn fire(&self, id: Uuid, price: Decimal) -> Result<bool, sqlx::Error> {
+        let claimed: Option<TriggerOrder> = sqlx::query_as(&format!(
+            r#"
+            UPDATE trigger_orders
+            SET status = 'triggered', triggered_at = NOW(), triggered_price = $2, updated_at = NOW()
+            WHERE id = $1 AND status = 'active'
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(id)
+        .bind(price)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(trigger) = claimed else {
+            return Ok(false);
+        };
+
+        // Frozen and closing accounts may not open new orders
+        let restricted: bool = sqlx::query_scalar(
+            "SELECT COALESCE(bool_or(frozen_at IS NOT NULL OR closure_requested_at IS NOT NULL), FALSE)
+             FROM users WHERE address = $1",
+        )
+        .bind(&trigger.user_address)
+        .fetch_one(&self.pool)
+        .await?;
+        let placed = if restricted {
+            Err("Account may not place orders".to_string())
+        } else {
+            self.order_flow
+                .place(&OrderIntent {
+                    source: OrderSource::Api,
+                    user_address: trigger.user_address.as_str().to_string(),
+                    market_id: trigger.market_id,
+                    outcome_id: trigger.outcome_id,
+                    share_type: trigger.share_type,
+                    side: trigger.side,
+                    order_type: trigger.order_type,
+                    time_in_force: TimeInForce::Gtc,
+                    post_only: false,
+                    stp: SelfTradePrevention::default(),
+                    price: trigger.price,
+                    amount: trigger.amount,
+                    expires_at: None,
+                    signature: String::new(),
+                })
+                .await
+                .map_err(|e| e.to_string())
+        };
+
+        let updated: TriggerOrder = match placed {
+            Ok(order) => {
+                sqlx::query("UPDATE orders SET trigger_order_id = $1 WHERE id = $2")
+                    .bind(id)
+                    .bind(order.order_id)
+                    .execute(&self.pool)
+                    .await?;
+                sqlx::query_as(&format!(
+                    "UPDATE trigger_orders SET executed_order_id = $2, updated_at = NOW() WHERE id = $1 RETURNING {}",
+                    COLUMNS
+                ))
+                .bind(id)
+                .bind(order.order_id)
+                .fetch_one(&self.pool)
+                .await?
+            }
+            Err(error) => {
+                tracing::info!("Trigger order {} fired but its order was refused: {}", id, error);
+                sqlx::query_as(&format!(
+                    r#"
+                    UPDATE trigger_orders SET status = 'failed', error_message = $2, updated_at = NOW()
+                    WHERE id = $1
+                    RETURNING {}
+                    "#,
+                    COLUMNS
+                ))
+                .bind(id)
+                .bind(error)
+                .fetch_one(&self.pool)
+                .await?
+            }
+        };
+        self.publish(updated).await;
+        Ok(true)
+    }
+
+    /// Expire triggers past their expiry and cancel those whose market
+    /// stopped trading for good
+    async fn expire(&self) -> Result<(), sqlx::Error> {
+        let ended: Vec<TriggerOrder> = sqlx::query_as(&format!(
+            r#"
+            UPDATE trigger_orders
+            SET status = CASE WHEN expires_at <= NOW() THEN 'expired' ELSE 'cancelled' END::trigger_order_status,
+                error_message = CASE WHEN expires_at <= NOW() THEN NULL ELSE 'Market closed' END,
+                updated_at = NOW()
+            WHERE status = 'active' AND market_id IS NOT NULL
+              AND (expires_at <= NOW()
+                OR market_id IN (SELECT id FROM markets WHERE status IN ('resolved', 'cancelled')))
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        for trigger in ended {
+            self.publish(trigger).await;
+        }
+        Ok(())
+    }
+
+    async fn publish(&self, trigger: TriggerOrder) {
+        self.event_bus
+            .publish(BusEvent::TriggerOrderUpdate(TriggerOrderUpdateEvent {
+                user_address: trigger.user_address.as_str().to_string(),
+                trigger,
+            }))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::test_support::TestApp;
+
+    const USER: &str = "0x00000000000000000000000000000000000000a1";
+
+    fn stop(market_id: Uuid, outcome_id: Uuid, side: OrderSide, trigger_price: Decimal) -> CreateTriggerOrder {
+        CreateTriggerOrder {
+            market_id,
+            outcome_id,
+            share_type: ShareType::Yes,
+            side,
+            order_type: OrderType::Limit,
+            price: dec!(0.65),
+            amount: dec!(10),
+            trigger_price,
+            trigger_condition: None,
+            price_source: PriceSource::LastTrade,
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trigger_fires_once_and_places_its_order() {
+        let app = TestApp::builder().build().await;
+        let pool = &app.db.pool;
+        let (market_id, outcome_id, _) = app.create_market().await;
+        app.deposit(USER, dec!(100)).await;
+        let user: Address = USER.parse().unwrap();
+        let state = &app.state;
+        let monitor = TriggerMonitor::new(
+            pool.clone(),
+            state.matching_engine.clone(),
+            state.order_flow.clone(),
+            state.event_bus.clone(),
+        );
+        let mut updates = state.event_bus.subscribe_trigger_updates();
+        let market_key = format!("{}:{}:yes", market_id, outcome_id);
+
+        let buy_stop = create(pool, &user, stop(market_id, outcome_id, OrderSide::Buy, dec!(0.60))).await.unwrap();
+        assert_eq!(buy_stop.trigger_condition, TriggerCondition::Above);
+        // Selling shares the account does not hold is refused when it fires
+        let sell_stop = create(pool, &user, stop(market_id, outcome_id, OrderSide::Sell, dec!(0.40))).await.unwrap();
+
+        assert_eq!(monitor.evaluate(&market_key, PriceSource::LastTrade, dec!(0.55)).await.unwrap(), 0);
+        assert_eq!(monitor.evaluate(&market_key, PriceSource::Midpoint, dec!(0.61)).await.unwrap(), 0);
+        assert_eq!(monitor.evaluate(&market_key, PriceSource::LastTrade, dec!(0.61)).await.unwrap(), 1);
+        assert_eq!(monitor.evaluate(&market_key, PriceSource::LastTrade, dec!(0.62)).await.unwrap(), 0);
+
+        let fired = updates.recv().await.unwrap();
+        assert_eq!((fired.trigger.id, fired.trigger.status), (buy_stop.id, TriggerOrderStatus::Triggered));
+        assert_eq!(fired.trigger.triggered_price, Some(dec!(0.61)));
+        let order_id = fired.trigger.order_id.unwrap();
+        let (linked, status): (Option<Uuid>, String) =
+            sqlx::query_as("SELECT trigger_order_id, status::text FROM orders WHERE id = $1")
+                .bind(order_id)
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        assert_eq!((linked, status.as_str()), (Some(buy_stop.id), "open"));
+
+        assert_eq!(monitor.evaluate(&market_key, PriceSource::LastTrade, dec!(0.40)).await.unwrap(), 1);
+        let failed = get(pool, &user, sell_stop.id).await.unwrap().unwrap();
+        assert_eq!(failed.status, TriggerOrderStatus::Failed);
+        assert!(failed.error.unwrap().contains("Insufficient shares"));
+
+        let pending = create(pool, &user, stop(market_id, outcome_id, OrderSide::Buy, dec!(0.90))).await.unwrap();
+        assert_eq!(cancel(pool, &user, pending.id).await.unwrap().status, TriggerOrderStatus::Cancelled);
+        assert!(matches!(
+            cancel(pool, &user, pending.id).await,
+            Err(TriggerOrderError::NotActive(TriggerOrderStatus::Cancelled))
+        ));
+    }
+}
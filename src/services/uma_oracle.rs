@@ -15,6 +15,7 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::blockchain::contracts::OptimisticOracleV3Contract;
+use crate::services::resolution_evidence;
 
 /// Default identifier for assertions (ASSERT_TRUTH)
 pub const DEFAULT_IDENTIFIER: [u8; 32] = *b"ASSERT_TRUTH\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0";
@@ -100,6 +101,21 @@ pub enum UmaOracleError {
     ContractError(String),
 }
 
+impl UmaOracleError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            UmaOracleError::MarketNotFound(_) => "MARKET_NOT_FOUND",
+            UmaOracleError::PendingAssertionExists => "PENDING_ASSERTION_EXISTS",
+            UmaOracleError::AlreadyResolved => "ALREADY_RESOLVED",
+            UmaOracleError::AssertionNotFound(_) => "ASSERTION_NOT_FOUND",
+            UmaOracleError::NotReadyForSettlement => "NOT_READY_FOR_SETTLEMENT",
+            UmaOracleError::InvalidOutcome(_) => "INVALID_OUTCOME",
+            UmaOracleError::BlockchainError(_) | UmaOracleError::ContractError(_) => "ORACLE_ERROR",
+            UmaOracleError::DatabaseError(_) => "DB_ERROR",
+        }
+    }
+}
+
 /// UMA Oracle client for market resolution
 pub struct UmaOracleClient<M: Middleware> {
     oracle: OptimisticOracleV3Contract<M>,
@@ -340,6 +356,11 @@ impl<M: Middleware + 'static> UmaOracleClient<M> {
                 .execute(&self.pool)
                 .await?;
 
+                let mut conn = self.pool.acquire().await?;
+                if let Some(evidence) = resolution_evidence::uma_evidence(&mut conn, assertion_id_hex).await? {
+                    resolution_evidence::record(&mut conn, record.market_id, &evidence).await?;
+                }
+
                 tracing::info!(
                     market_id = %record.market_id,
                     outcome_id = ?record.outcome_id,
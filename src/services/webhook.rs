@@ -0,0 +1,363 @@
+//! Outbound Webhook Service
+//!
+//! Fans platform events (order filled, withdrawal completed, market resolved)
+//! out to registered webhook subscriptions. Deliveries are persisted in
+//! `webhook_deliveries` and sent by a background worker with exponential
+//! backoff, so a slow or failing endpoint never blocks request handling.
+//!
+//! Each request carries:
+//! - `X-Webhook-Id`: delivery id (stable across retries)
+//! - `X-Webhook-Event`: event type
+//! - `X-Webhook-Timestamp`: unix seconds
+//! - `X-Webhook-Signature`: `sha256=<hex(HMAC-SHA256(secret, "{timestamp}.{body}"))>`
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::services::retry::RetryPolicy;
+
+/// Events that can be delivered to webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    OrderFilled,
+    WithdrawalCompleted,
+    MarketResolved,
+    TradeAdjusted,
+    ResolutionReminder,
+    AdminApproval,
+    MmProtectionTriggered,
+}
+
+impl WebhookEventType {
+    pub const ALL: [WebhookEventType; 7] = [
+        WebhookEventType::OrderFilled,
+        WebhookEventType::WithdrawalCompleted,
+        WebhookEventType::MarketResolved,
+        WebhookEventType::TradeAdjusted,
+        WebhookEventType::ResolutionReminder,
+        WebhookEventType::AdminApproval,
+        WebhookEventType::MmProtectionTriggered,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::OrderFilled => "order.filled",
+            WebhookEventType::WithdrawalCompleted => "withdrawal.completed",
+            WebhookEventType::MarketResolved => "market.resolved",
+            WebhookEventType::TradeAdjusted => "trade.adjusted",
+            WebhookEventType::ResolutionReminder => "market.resolution_reminder",
+            WebhookEventType::AdminApproval => "admin.approval",
+            WebhookEventType::MmProtectionTriggered => "mm.protection_triggered",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+}
+
+impl std::fmt::Display for WebhookEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Webhook delivery worker configuration
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// Delivery attempts before a delivery is marked failed, and the delay
+    /// between them
+    pub retry: RetryPolicy,
+    /// HTTP timeout per delivery attempt
+    pub request_timeout_secs: u64,
+    /// Worker poll interval
+    pub poll_interval_ms: u64,
+    /// Deliveries fetched per poll
+    pub batch_size: i64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::new(8, 10, 3600),
+            request_timeout_secs: 10,
+            poll_interval_ms: 1000,
+            batch_size: 50,
+        }
+    }
+}
+
+/// Sign a webhook body: hex(HMAC-SHA256(secret, "{timestamp}.{body}"))
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Generate a random signing secret
+pub fn generate_secret() -> String {
+    use rand::RngCore;
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+/// Pending delivery loaded by the worker
+#[derive(Debug, sqlx::FromRow)]
+struct DueDelivery {
+    id: Uuid,
+    event_type: String,
+    payload: serde_json::Value,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+/// Webhook dispatcher and delivery worker
+pub struct WebhookService {
+    pool: PgPool,
+    http: reqwest::Client,
+    config: WebhookConfig,
+}
+
+impl WebhookService {
+    pub fn new(pool: PgPool, config: WebhookConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self { pool, http, config }
+    }
+
+    /// Queue an event for every active subscription interested in it.
+    ///
+    /// `user_address` scopes the event to that user's own subscriptions;
+    /// platform-wide (admin) subscriptions always receive it. Public events
+    /// (`None`, e.g. market resolution) go to every interested subscription.
+    /// Failures are logged and swallowed: webhooks must never fail the
+    /// calling request.
+    pub async fn dispatch(
+        &self,
+        event_type: WebhookEventType,
+        user_address: Option<&str>,
+        data: serde_json::Value,
+    ) {
+        let event_id = Uuid::new_v4();
+        let payload = serde_json::json!({
+            "id": event_id,
+            "type": event_type.as_str(),
+            "created_at": Utc::now().timestamp_millis(),
+            "data": data,
+        });
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (subscription_id, event_id, event_type, payload)
+            SELECT id, $1, $2, $3
+            FROM webhook_subscriptions
+            WHERE is_active
+              AND ($4::text IS NULL OR owner_address IS NULL OR owner_address = $4)
+              AND (cardinality(event_types) = 0 OR $2 = ANY(event_types))
+            "#,
+        )
+        .bind(event_id)
+        .bind(event_type.as_str())
+        .bind(&payload)
+        .bind(user_address)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(r) if r.rows_affected() > 0 => {
+                tracing::debug!(
+                    "Queued {} webhook deliveries for event {} ({})",
+                    r.rows_affected(),
+                    event_id,
+                    event_type
+                );
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to queue webhook event {}: {}", event_type, e),
+        }
+    }
+
+    /// Re-queue a past delivery as a new delivery (keeps the original log intact)
+    pub async fn replay(&self, delivery_id: Uuid) -> Result<Uuid, sqlx::Error> {
+        let new_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (id, subscription_id, event_id, event_type, payload, replay_of)
+            SELECT $1, subscription_id, event_id, event_type, payload, id
+            FROM webhook_deliveries
+            WHERE id = $2
+            "#,
+        )
+        .bind(new_id)
+        .bind(delivery_id)
+        .execute(&self.pool)
+        .await
+        .and_then(|r| {
+            if r.rows_affected() == 0 {
+                Err(sqlx::Error::RowNotFound)
+            } else {
+                Ok(new_id)
+            }
+        })
+    }
+
+    /// Spawn the background delivery worker
+    pub fn start_worker(self: Arc<Self>) {
+        tokio::spawn(async move {
+            tracing::info!("Webhook delivery worker started");
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.process_due().await {
+                    tracing::error!("Webhook delivery worker error: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Deliver all due deliveries once
+    async fn process_due(&self) -> Result<(), sqlx::Error> {
+        // Claim a batch by pushing next_attempt_at forward; SKIP LOCKED lets
+        // several instances share the queue without double-sending.
+        let due: Vec<DueDelivery> = sqlx::query_as(
+            r#"
+            WITH claimed AS (
+                SELECT d.id
+                FROM webhook_deliveries d
+                WHERE d.status = 'pending' AND d.next_attempt_at <= NOW()
+                ORDER BY d.next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE webhook_deliveries d
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            FROM claimed, webhook_subscriptions s
+            WHERE d.id = claimed.id AND s.id = d.subscription_id
+            RETURNING d.id, d.event_type, d.payload, d.attempts, s.url, s.secret
+            "#,
+        )
+        .bind(self.config.batch_size)
+        .bind(self.config.request_timeout_secs as f64 * 2.0)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for delivery in due {
+            self.deliver(delivery).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn deliver(&self, delivery: DueDelivery) -> Result<(), sqlx::Error> {
+        let body = delivery.payload.to_string();
+        let timestamp = Utc::now().timestamp();
+        let signature = sign_payload(&delivery.secret, timestamp, &body);
+
+        let result = self
+            .http
+            .post(&delivery.url)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Id", delivery.id.to_string())
+            .header("X-Webhook-Event", &delivery.event_type)
+            .header("X-Webhook-Timestamp", timestamp.to_string())
+            .header("X-Webhook-Signature", format!("sha256={}", signature))
+            .body(body)
+            .send()
+            .await;
+
+        let attempts = delivery.attempts + 1;
+        let (status_code, error) = match result {
+            Ok(resp) if resp.status().is_success() => {
+                sqlx::query(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = 'delivered', attempts = $1, last_status_code = $2,
+                        last_error = NULL, delivered_at = NOW()
+                    WHERE id = $3
+                    "#,
+                )
+                .bind(attempts)
+                .bind(resp.status().as_u16() as i32)
+                .bind(delivery.id)
+                .execute(&self.pool)
+                .await?;
+                crate::metrics::record_webhook_delivery("delivered");
+                return Ok(());
+            }
+            Ok(resp) => (Some(resp.status().as_u16() as i32), format!("HTTP {}", resp.status())),
+            Err(e) => (None, e.to_string()),
+        };
+
+        let exhausted = self.config.retry.exhausted(attempts);
+        let next_attempt = self.config.retry.next_attempt_at(attempts);
+
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $1, attempts = $2, last_status_code = $3, last_error = $4, next_attempt_at = $5
+            WHERE id = $6
+            "#,
+        )
+        .bind(if exhausted { "failed" } else { "pending" })
+        .bind(attempts)
+        .bind(status_code)
+        .bind(&error)
+        .bind(next_attempt)
+        .bind(delivery.id)
+        .execute(&self.pool)
+        .await?;
+
+        if exhausted {
+            tracing::warn!(
+                "Webhook delivery {} to {} failed permanently after {} attempts: {}",
+                delivery.id,
+                delivery.url,
+                attempts,
+                error
+            );
+            crate::metrics::record_webhook_delivery("failed");
+        } else {
+            tracing::debug!("Webhook delivery {} attempt {} failed: {}", delivery.id, attempts, error);
+            crate::metrics::record_webhook_delivery("retry");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_payload_is_deterministic() {
+        let a = sign_payload("secret", 1700000000, r#"{"a":1}"#);
+        let b = sign_payload("secret", 1700000000, r#"{"a":1}"#);
+        let c = sign_payload("secret", 1700000001, r#"{"a":1}"#);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.len(), 64);
+    }
+
+    #[test]
+    fn test_event_type_round_trip() {
+        for t in WebhookEventType::ALL {
+            assert_eq!(WebhookEventType::parse(t.as_str()), Some(t));
+        }
+        assert_eq!(WebhookEventType::parse("unknown"), None);
+    }
+}
@@ -0,0 +1,253 @@
+//! Withdrawal Fee Policy
+//!
+//! Computes the fee, minimum and dust handling for a withdrawal request.
+//! The same quote is returned by `/withdraw/prepare` for disclosure and
+//! re-computed by the withdraw handlers when funds are actually frozen.
+//! The gas price can move in between, so a request may carry the
+//! `max_fee` it was shown; a fee above it is refused rather than charged,
+//! and the user is never charged more than what they saw.
+//!
+//! Fee = flat fee + gas-linked fee, where the gas-linked part is
+//! `gas_units * gas_price * native_token_price` (converted to collateral).
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::config::AppConfig;
+use crate::services::matching::precision::{Collateral, COLLATERAL_DP};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum WithdrawalPolicyError {
+    #[error("Amount must be positive")]
+    InvalidAmount,
+    #[error("Amount supports at most {0} decimals")]
+    TooManyDecimals(u32),
+    #[error("Amount below minimum withdrawal of {0}")]
+    BelowMinimum(Decimal),
+    #[error("Amount does not cover withdrawal fee of {0}")]
+    AmountBelowFee(Decimal),
+    #[error("Insufficient balance: {available} < {requested}")]
+    InsufficientBalance { available: Decimal, requested: Decimal },
+    #[error("Withdrawal fee rose to {fee}, above the accepted {max_fee}")]
+    FeeAboveMax { fee: Decimal, max_fee: Decimal },
+}
+
+/// Configured withdrawal fee/minimum/dust rules
+#[derive(Debug, Clone)]
+pub struct WithdrawalPolicy {
+    /// Minimum gross withdrawal amount
+    pub min_amount: Decimal,
+    /// Flat fee charged on every withdrawal
+    pub flat_fee: Decimal,
+    /// Gas units assumed for the payout transfer
+    pub gas_units: u64,
+    /// Price of the native gas token in collateral units (0 disables gas-linked fee)
+    pub native_token_price: Decimal,
+    /// Cap on the gas-linked portion of the fee (0 = uncapped)
+    pub max_gas_fee: Decimal,
+    /// Remaining balances below this are considered dust
+    pub dust_threshold: Decimal,
+    /// Sweep dust into the withdrawal instead of leaving it behind
+    pub sweep_dust: bool,
+}
+
+/// Fee disclosure for a withdrawal
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct WithdrawalQuote {
+    /// Amount requested by the user
+    pub requested_amount: Decimal,
+    /// Dust added on top of the requested amount
+    pub dust_swept: Decimal,
+    /// Total debited from the available balance (requested + dust)
+    pub gross_amount: Decimal,
+    pub flat_fee: Decimal,
+    pub gas_fee: Decimal,
+    /// flat_fee + gas_fee
+    pub total_fee: Decimal,
+    /// Amount actually paid out on-chain (gross - fee)
+    pub net_amount: Decimal,
+    /// Available balance left after the withdrawal
+    pub remaining_balance: Decimal,
+    pub min_amount: Decimal,
+}
+
+impl WithdrawalPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            min_amount: config.withdraw_min_amount.parse().unwrap_or(Decimal::ZERO),
+            flat_fee: config.withdraw_fee_flat.parse().unwrap_or(Decimal::ZERO),
+            gas_units: config.withdraw_fee_gas_units,
+            native_token_price: config.withdraw_native_token_price.parse().unwrap_or(Decimal::ZERO),
+            max_gas_fee: config.withdraw_max_gas_fee.parse().unwrap_or(Decimal::ZERO),
+            dust_threshold: config.withdraw_dust_threshold.parse().unwrap_or(Decimal::ZERO),
+            sweep_dust: config.withdraw_dust_sweep,
+        }
+    }
+
+    /// Gas-linked fee in collateral units for a gas price given in gwei
+    pub fn gas_fee(&self, gas_price_gwei: Option<Decimal>) -> Decimal {
+        let gas_price_gwei = match gas_price_gwei {
+            Some(p) if p > Decimal::ZERO && self.native_token_price > Decimal::ZERO => p,
+            _ => return Decimal::ZERO,
+        };
+
+        // gwei -> native token: 1e-9
+        let fee = Decimal::from(self.gas_units) * gas_price_gwei * Decimal::new(1, 9) * self.native_token_price;
+        let fee = if self.max_gas_fee > Decimal::ZERO {
+            fee.min(self.max_gas_fee)
+        } else {
+            fee
+        };
+        // Round fees up to collateral precision so we never under-charge
+        fee.round_dp_with_strategy(COLLATERAL_DP, rust_decimal::RoundingStrategy::AwayFromZero)
+    }
+
+    /// Build the quote for withdrawing `amount` out of `available`, refusing
+    /// one whose fee exceeds `max_fee`
+    pub fn binding_quote(
+        &self,
+        amount: Decimal,
+        available: Decimal,
+        gas_price_gwei: Option<Decimal>,
+        max_fee: Option<Decimal>,
+    ) -> Result<WithdrawalQuote, WithdrawalPolicyError> {
+        let quote = self.quote(amount, available, gas_price_gwei)?;
+        match max_fee {
+            Some(max_fee) if quote.total_fee > max_fee => Err(WithdrawalPolicyError::FeeAboveMax {
+                fee: quote.total_fee,
+                max_fee,
+            }),
+            _ => Ok(quote),
+        }
+    }
+
+    /// Build the quote for withdrawing `amount` out of `available`
+    pub fn quote(
+        &self,
+        amount: Decimal,
+        available: Decimal,
+        gas_price_gwei: Option<Decimal>,
+    ) -> Result<WithdrawalQuote, WithdrawalPolicyError> {
+        if amount <= Decimal::ZERO {
+            return Err(WithdrawalPolicyError::InvalidAmount);
+        }
+        if Collateral::exact(amount).is_none() {
+            return Err(WithdrawalPolicyError::TooManyDecimals(COLLATERAL_DP));
+        }
+        if amount > available {
+            return Err(WithdrawalPolicyError::InsufficientBalance {
+                available,
+                requested: amount,
+            });
+        }
+
+        let mut dust_swept = Decimal::ZERO;
+        let leftover = available - amount;
+        if self.sweep_dust && leftover > Decimal::ZERO && leftover < self.dust_threshold {
+            dust_swept = leftover;
+        }
+        let gross_amount = amount + dust_swept;
+
+        if gross_amount < self.min_amount {
+            return Err(WithdrawalPolicyError::BelowMinimum(self.min_amount));
+        }
+
+        let gas_fee = self.gas_fee(gas_price_gwei);
+        let total_fee = self.flat_fee + gas_fee;
+        if gross_amount <= total_fee {
+            return Err(WithdrawalPolicyError::AmountBelowFee(total_fee));
+        }
+
+        Ok(WithdrawalQuote {
+            requested_amount: amount,
+            dust_swept,
+            gross_amount,
+            flat_fee: self.flat_fee,
+            gas_fee,
+            total_fee,
+            net_amount: gross_amount - total_fee,
+            remaining_balance: available - gross_amount,
+            min_amount: self.min_amount,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn policy() -> WithdrawalPolicy {
+        WithdrawalPolicy {
+            min_amount: dec!(10),
+            flat_fee: dec!(1),
+            gas_units: 65_000,
+            native_token_price: dec!(2000),
+            max_gas_fee: dec!(5),
+            dust_threshold: dec!(0.5),
+            sweep_dust: true,
+        }
+    }
+
+    #[test]
+    fn test_quote_flat_fee_only() {
+        let q = policy().quote(dec!(100), dec!(500), None).unwrap();
+        assert_eq!(q.total_fee, dec!(1));
+        assert_eq!(q.net_amount, dec!(99));
+        assert_eq!(q.remaining_balance, dec!(400));
+        assert_eq!(q.dust_swept, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_quote_gas_linked_fee_capped() {
+        let p = policy();
+        // 65000 * 20 gwei = 0.0013 native * 2000 = 2.6
+        assert_eq!(p.gas_fee(Some(dec!(20))), dec!(2.6));
+        // 65000 * 100 gwei = 0.0065 native * 2000 = 13 -> capped at 5
+        assert_eq!(p.gas_fee(Some(dec!(100))), dec!(5));
+    }
+
+    #[test]
+    fn test_quote_sweeps_dust() {
+        let q = policy().quote(dec!(100), dec!(100.3), None).unwrap();
+        assert_eq!(q.dust_swept, dec!(0.3));
+        assert_eq!(q.gross_amount, dec!(100.3));
+        assert_eq!(q.remaining_balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_quote_rejects_below_minimum_and_fee() {
+        let p = policy();
+        assert_eq!(p.quote(dec!(5), dec!(100), None), Err(WithdrawalPolicyError::BelowMinimum(dec!(10))));
+
+        let mut p = policy();
+        p.flat_fee = dec!(20);
+        assert_eq!(p.quote(dec!(15), dec!(100), None), Err(WithdrawalPolicyError::AmountBelowFee(dec!(20))));
+    }
+
+    #[test]
+    fn test_gas_price_rise_after_prepare_is_refused() {
+        let p = policy();
+        // Prepared at 20 gwei: 1 flat + 2.6 gas
+        let shown = p.quote(dec!(100), dec!(500), Some(dec!(20))).unwrap();
+        assert_eq!(shown.total_fee, dec!(3.6));
+
+        // Gas rose to 40 gwei by the time the withdrawal is requested
+        assert_eq!(
+            p.binding_quote(dec!(100), dec!(500), Some(dec!(40)), Some(shown.total_fee)),
+            Err(WithdrawalPolicyError::FeeAboveMax { fee: dec!(6), max_fee: dec!(3.6) })
+        );
+        // A drop is passed on
+        let charged = p.binding_quote(dec!(100), dec!(500), Some(dec!(10)), Some(shown.total_fee)).unwrap();
+        assert_eq!((charged.total_fee, charged.net_amount), (dec!(2.3), dec!(97.7)));
+    }
+
+    #[test]
+    fn test_quote_rejects_sub_unit_amount() {
+        assert_eq!(
+            policy().quote(dec!(50.0000001), dec!(100), None),
+            Err(WithdrawalPolicyError::TooManyDecimals(6))
+        );
+    }
+}
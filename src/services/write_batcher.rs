@@ -0,0 +1,373 @@
+//! Write-Behind Fill Batcher
+//!
+//! Every fill used to cost its own transaction (trade insert plus holdings)
+//! and a separate maker order update. Under load the batcher groups the
+//! fills of all orders submitted within one flush interval: trades go in as
+//! one multi-row `INSERT ... SELECT FROM UNNEST`, the maker order updates as
+//! one multi-row `UPDATE ... FROM UNNEST`, and holdings are applied in the
+//! same transaction.
+//!
+//! Callers wait for the flush that carries their fills (group commit), so a
+//! fill is in the database - or in the trade persist retry queue - before
+//! the order is acknowledged, exactly as with unbatched writes. When a
+//! batch fails, its trades fall back to [`TradePersistQueue::persist`] one
+//! by one, so a single bad trade cannot take the rest of the batch with it.
+//!
+//! Complete sets of categorical markets are rare next to trades and are
+//! written straight away ([`WriteBatcher::persist_set_fills`]), each in its
+//! own transaction with its legs' maker order updates.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use tokio::sync::{oneshot, Notify};
+use uuid::Uuid;
+
+use crate::services::matching::{holdings, SetFill, TradeEvent};
+use crate::services::trade_persistence::TradePersistQueue;
+
+/// Batcher configuration
+#[derive(Debug, Clone)]
+pub struct WriteBatchConfig {
+    /// Longest a fill waits for its batch to be written
+    pub flush_interval_ms: u64,
+    /// Pending trades that trigger a flush before the interval elapses
+    pub max_batch: usize,
+    /// Balance token that trades settle in
+    pub collateral_token: String,
+}
+
+impl Default for WriteBatchConfig {
+    fn default() -> Self {
+        Self {
+            flush_interval_ms: 5,
+            max_batch: 500,
+            collateral_token: "USDC".to_string(),
+        }
+    }
+}
+
+/// Fills of one caller, waiting for the next flush
+struct PendingFills {
+    trades: Vec<TradeEvent>,
+    done: oneshot::Sender<()>,
+}
+
+/// Groups trade inserts and maker order updates into per-interval batches
+pub struct WriteBatcher {
+    pool: PgPool,
+    persist_queue: Arc<TradePersistQueue>,
+    config: WriteBatchConfig,
+    pending: Mutex<Vec<PendingFills>>,
+    full: Notify,
+    running: AtomicBool,
+}
+
+impl WriteBatcher {
+    pub fn new(pool: PgPool, persist_queue: Arc<TradePersistQueue>, config: WriteBatchConfig) -> Self {
+        Self {
+            pool,
+            persist_queue,
+            config,
+            pending: Mutex::new(Vec::new()),
+            full: Notify::new(),
+            running: AtomicBool::new(false),
+        }
+    }
+
+    /// Persist `trades` and their maker order fills, returning once they are
+    /// written or queued for retry. Without a running flush loop the fills
+    /// are written immediately as a batch of their own.
+    pub async fn persist_fills(&self, trades: Vec<TradeEvent>) {
+        if trades.is_empty() {
+            return;
+        }
+        if !self.running.load(Ordering::Acquire) {
+            self.flush(vec![trades]).await;
+            return;
+        }
+
+        let (done, written) = oneshot::channel();
+        let total: usize = {
+            let mut pending = self.pending.lock();
+            pending.push(PendingFills { trades, done });
+            pending.iter().map(|p| p.trades.len()).sum()
+        };
+        if total >= self.config.max_batch {
+            self.full.notify_one();
+        }
+        // The flush loop never drops a sender without flushing its batch
+        let _ = written.await;
+    }
+
+    /// Persist complete sets with their leg makers' fills, queueing the
+    /// ones that fail for retry
+    pub async fn persist_set_fills(&self, fills: Vec<SetFill>) {
+        for fill in &fills {
+            self.persist_queue.persist_set(fill).await;
+        }
+    }
+
+    /// Spawn the flush loop
+    pub fn start(self: Arc<Self>) {
+        self.running.store(true, Ordering::Release);
+        tokio::spawn(async move {
+            tracing::info!(
+                "Write batcher started (flush every {}ms or at {} trades)",
+                self.config.flush_interval_ms,
+                self.config.max_batch
+            );
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.flush_interval_ms.max(1)));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = self.full.notified() => {}
+                }
+                let batch = std::mem::take(&mut *self.pending.lock());
+                if batch.is_empty() {
+                    continue;
+                }
+                let (trades, waiters): (Vec<_>, Vec<_>) = batch.into_iter().map(|p| (p.trades, p.done)).unzip();
+                self.flush(trades).await;
+                for done in waiters {
+                    let _ = done.send(());
+                }
+            }
+        });
+    }
+
+    /// Write one batch, falling back to per-trade persists if it fails
+    async fn flush(&self, batch: Vec<Vec<TradeEvent>>) {
+        let trades: Vec<TradeEvent> = batch.into_iter().flatten().collect();
+        let started = Instant::now();
+        match write_trades(&self.pool, &trades, &self.config.collateral_token).await {
+            Ok(inserted) => {
+                crate::metrics::record_write_batch(trades.len(), "written", started.elapsed().as_secs_f64());
+                tracing::debug!("Wrote batch of {} trades ({} new)", trades.len(), inserted);
+            }
+            Err(e) => {
+                crate::metrics::record_write_batch(trades.len(), "fallback", started.elapsed().as_secs_f64());
+                tracing::warn!("Batched write of {} trades failed, persisting individually: {}", trades.len(), e);
+                for trade in &trades {
+                    self.persist_queue.persist(trade).await;
+                }
+                // Maker fills are independent of the trade rows, as before batching
+                let (ids, amounts) = maker_fills(&trades);
+                let applied = match self.pool.acquire().await {
+                    Ok(mut conn) => apply_maker_fills(&mut conn, &ids, &amounts).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = applied {
+                    tracing::error!("Failed to update {} maker orders: {}", ids.len(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Insert the trades, apply holdings for the new ones and update their
+/// maker orders in one transaction. Returns the number of new trades.
+/// Trades already on record are left alone, so writing a trade twice is
+/// harmless.
+pub async fn write_trades(pool: &PgPool, trades: &[TradeEvent], collateral_token: &str) -> Result<usize, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let inserted: HashSet<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO trades (
+            id, symbol, market_id, outcome_id, share_type, match_type,
+            maker_order_id, taker_order_id, maker_address, taker_address,
+            side, price, amount, maker_fee, taker_fee, created_at,
+            sequence, is_block_trade, is_rfq
+        )
+        SELECT
+            t.id, t.symbol, t.market_id, t.outcome_id, t.share_type::share_type, t.match_type::match_type,
+            t.maker_order_id, t.taker_order_id, t.maker_address, t.taker_address,
+            t.side::order_side, t.price, t.amount, t.maker_fee, t.taker_fee, to_timestamp(t.ts / 1000),
+            t.sequence, t.is_block_trade, t.is_rfq
+        FROM UNNEST(
+            $1::uuid[], $2::text[], $3::uuid[], $4::uuid[], $5::text[], $6::text[],
+            $7::uuid[], $8::uuid[], $9::text[], $10::text[],
+            $11::text[], $12::numeric[], $13::numeric[], $14::numeric[], $15::numeric[], $16::float8[],
+            $17::int8[], $18::bool[], $19::bool[]
+        ) AS t(
+            id, symbol, market_id, outcome_id, share_type, match_type,
+            maker_order_id, taker_order_id, maker_address, taker_address,
+            side, price, amount, maker_fee, taker_fee, ts,
+            sequence, is_block_trade, is_rfq
+        )
+        ON CONFLICT (id) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(trades.iter().map(|t| t.trade_id).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.symbol.clone()).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.market_id).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.outcome_id).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.share_type.to_string()).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.match_type.to_string()).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.maker_order_id).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.taker_order_id).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.maker_address.clone()).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.taker_address.clone()).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.side.clone()).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.price).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.amount).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.maker_fee).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.taker_fee).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.timestamp as f64).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| (t.sequence > 0).then_some(t.sequence as i64)).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.is_block_trade).collect::<Vec<_>>())
+    .bind(trades.iter().map(|t| t.is_rfq).collect::<Vec<_>>())
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .collect();
+
+    // Trades already on record were applied when first written
+    let new_trades: Vec<TradeEvent> = trades.iter().filter(|t| inserted.contains(&t.trade_id)).cloned().collect();
+    for trade in &new_trades {
+        holdings::apply_trade(&mut tx, trade, collateral_token).await?;
+    }
+    let (ids, amounts) = maker_fills(&new_trades);
+    apply_maker_fills(&mut tx, &ids, &amounts).await?;
+
+    tx.commit().await?;
+    Ok(new_trades.len())
+}
+
+/// Insert a set fill with its legs, apply holdings for every party and
+/// update the leg makers' orders in one transaction. Returns whether the
+/// fill was new; one already on record is left alone.
+pub async fn write_set_fill(pool: &PgPool, fill: &SetFill, collateral_token: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO set_fills (
+            id, market_id, match_type, taker_order_id, taker_address, taker_outcome_id,
+            price, amount, taker_fee, created_at
+        )
+        VALUES ($1, $2, $3::match_type, $4, $5, $6, $7, $8, $9, to_timestamp($10::double precision / 1000))
+        ON CONFLICT (id) DO NOTHING
+        "#,
+    )
+    .bind(fill.set_id)
+    .bind(fill.market_id)
+    .bind(fill.match_type.to_string())
+    .bind(fill.taker_order_id)
+    .bind(&fill.taker_address)
+    .bind(fill.taker_outcome_id)
+    .bind(fill.price)
+    .bind(fill.amount)
+    .bind(fill.taker_fee)
+    .bind(fill.timestamp as f64)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if inserted == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO set_fill_legs (set_fill_id, leg_index, outcome_id, maker_order_id, maker_address, price, maker_fee)
+        SELECT $1, l.leg_index, l.outcome_id, l.maker_order_id, l.maker_address, l.price, l.maker_fee
+        FROM UNNEST($2::int2[], $3::uuid[], $4::uuid[], $5::text[], $6::numeric[], $7::numeric[])
+            AS l(leg_index, outcome_id, maker_order_id, maker_address, price, maker_fee)
+        "#,
+    )
+    .bind(fill.set_id)
+    .bind((0..fill.legs.len() as i16).collect::<Vec<_>>())
+    .bind(fill.legs.iter().map(|l| l.outcome_id).collect::<Vec<_>>())
+    .bind(fill.legs.iter().map(|l| l.maker_order_id).collect::<Vec<_>>())
+    .bind(fill.legs.iter().map(|l| l.maker_address.clone()).collect::<Vec<_>>())
+    .bind(fill.legs.iter().map(|l| l.price).collect::<Vec<_>>())
+    .bind(fill.legs.iter().map(|l| l.maker_fee).collect::<Vec<_>>())
+    .execute(&mut *tx)
+    .await?;
+
+    holdings::apply_set_fill(&mut tx, fill, collateral_token).await?;
+    let mut legs: Vec<Uuid> = fill.legs.iter().map(|l| l.maker_order_id).collect();
+    legs.sort_unstable();
+    let amounts = vec![fill.amount; legs.len()];
+    apply_maker_fills(&mut tx, &legs, &amounts).await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Fill amount per maker order, ordered by id so concurrent batches lock
+/// order rows in the same order
+fn maker_fills(trades: &[TradeEvent]) -> (Vec<Uuid>, Vec<Decimal>) {
+    let mut fills: BTreeMap<Uuid, Decimal> = BTreeMap::new();
+    for trade in trades {
+        *fills.entry(trade.maker_order_id).or_default() += trade.amount;
+    }
+    fills.into_iter().unzip()
+}
+
+/// Add each fill to its maker order and move it to (partially) filled
+async fn apply_maker_fills(conn: &mut PgConnection, ids: &[Uuid], amounts: &[Decimal]) -> Result<(), sqlx::Error> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"
+        UPDATE orders o
+        SET filled_amount = o.filled_amount + f.amount,
+            status = CASE
+                WHEN o.filled_amount + f.amount >= o.amount THEN 'filled'::order_status
+                ELSE 'partially_filled'::order_status
+            END,
+            updated_at = NOW()
+        FROM UNNEST($1::uuid[], $2::numeric[]) AS f(id, amount)
+        WHERE o.id = f.id
+        "#,
+    )
+    .bind(ids)
+    .bind(amounts)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::matching::Side;
+    use rust_decimal_macros::dec;
+
+    fn trade(maker_order_id: Uuid, amount: Decimal) -> TradeEvent {
+        TradeEvent::new(
+            format!("{}:{}:yes", Uuid::new_v4(), Uuid::new_v4()),
+            Uuid::new_v4(),
+            maker_order_id,
+            Uuid::new_v4(),
+            "0xmaker".to_string(),
+            "0xtaker".to_string(),
+            Side::Buy,
+            dec!(0.5),
+            amount,
+            Decimal::ZERO,
+            Decimal::ZERO,
+        )
+    }
+
+    #[test]
+    fn test_maker_fills_are_summed_per_order_in_id_order() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let (ids, amounts) = maker_fills(&[trade(a, dec!(3)), trade(b, dec!(1)), trade(a, dec!(2))]);
+
+        let mut expected = [(a, dec!(5)), (b, dec!(1))];
+        expected.sort_by_key(|(id, _)| *id);
+        assert_eq!(ids, expected.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+        assert_eq!(amounts, expected.iter().map(|(_, amount)| *amount).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,183 @@
+//! Scripted settlement chain
+//!
+//! Records every submitted match and No-set conversion and answers with a
+//! scripted outcome instead of sending transactions. A conversion's receipt
+//! answers with the outcome scripted when it is read.
+
+use async_trait::async_trait;
+use ethers::types::{Address, Bytes, H256, U256};
+use parking_lot::Mutex;
+
+use crate::blockchain::SettlementChain;
+use crate::blockchain::types::{ContractAddresses, OnChainOrder, TxResult, TxStatus};
+
+type ChainError = Box<dyn std::error::Error + Send + Sync>;
+
+/// How the chain answers `matchOrders` and `convertPositions`
+#[derive(Debug, Clone)]
+pub enum MatchOutcome {
+    /// Mined and confirmed
+    Confirmed,
+    /// Mined but reverted
+    Reverted,
+    /// Sent but not mined yet
+    Unmined,
+    /// The submission itself fails (RPC error, gas estimation, ...)
+    Error(String),
+}
+
+/// A `matchOrders` call seen by the chain
+#[derive(Debug, Clone)]
+pub struct SubmittedMatch {
+    pub maker_order: OnChainOrder,
+    pub taker_order: OnChainOrder,
+    pub maker_fill_amount: U256,
+    pub taker_fill_amount: U256,
+    pub tx_hash: H256,
+}
+
+/// A `convertPositions` call seen by the chain
+#[derive(Debug, Clone)]
+pub struct SubmittedConversion {
+    pub neg_risk_market_id: [u8; 32],
+    pub index_set: U256,
+    pub amount: U256,
+    pub tx_hash: H256,
+}
+
+pub struct MockChain {
+    addresses: ContractAddresses,
+    outcome: Mutex<MatchOutcome>,
+    submitted: Mutex<Vec<SubmittedMatch>>,
+    conversions: Mutex<Vec<SubmittedConversion>>,
+}
+
+impl MockChain {
+    /// A chain confirming every match
+    pub fn new() -> Self {
+        Self {
+            addresses: ContractAddresses::default(),
+            outcome: Mutex::new(MatchOutcome::Confirmed),
+            submitted: Mutex::new(Vec::new()),
+            conversions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Answer subsequent matches with `outcome`
+    pub fn set_outcome(&self, outcome: MatchOutcome) {
+        *self.outcome.lock() = outcome;
+    }
+
+    /// Matches submitted so far, oldest first
+    pub fn submitted(&self) -> Vec<SubmittedMatch> {
+        self.submitted.lock().clone()
+    }
+
+    /// Conversions submitted so far, oldest first
+    pub fn conversions(&self) -> Vec<SubmittedConversion> {
+        self.conversions.lock().clone()
+    }
+
+    /// Status for the next transaction, or the error submitting it
+    fn scripted_status(&self) -> Result<TxStatus, ChainError> {
+        match self.outcome.lock().clone() {
+            MatchOutcome::Confirmed => Ok(TxStatus::Confirmed),
+            MatchOutcome::Reverted => Ok(TxStatus::Failed),
+            MatchOutcome::Unmined => Ok(TxStatus::Pending),
+            MatchOutcome::Error(message) => Err(message.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl SettlementChain for MockChain {
+    fn addresses(&self) -> &ContractAddresses {
+        &self.addresses
+    }
+
+    async fn match_orders(
+        &self,
+        maker_order: &OnChainOrder,
+        taker_order: &OnChainOrder,
+        _maker_signature: Bytes,
+        _taker_signature: Bytes,
+        maker_fill_amount: U256,
+        taker_fill_amount: U256,
+    ) -> Result<TxResult, ChainError> {
+        let status = self.scripted_status()?;
+
+        let mut submitted = self.submitted.lock();
+        // Transaction hashes and blocks count up, so runs are reproducible
+        let block_number = submitted.len() as u64 + 1;
+        let tx_hash = H256::from_low_u64_be(block_number);
+        submitted.push(SubmittedMatch {
+            maker_order: maker_order.clone(),
+            taker_order: taker_order.clone(),
+            maker_fill_amount,
+            taker_fill_amount,
+            tx_hash,
+        });
+
+        Ok(TxResult {
+            tx_hash,
+            status,
+            block_number: Some(block_number),
+            gas_used: Some(U256::from(150_000)),
+            error: None,
+        })
+    }
+
+    async fn get_collection_id(
+        &self,
+        _parent_collection_id: [u8; 32],
+        condition_id: [u8; 32],
+        index_set: U256,
+    ) -> Result<[u8; 32], ChainError> {
+        let mut collection_id = condition_id;
+        collection_id[31] ^= index_set.low_u32() as u8;
+        Ok(collection_id)
+    }
+
+    async fn get_position_id(&self, _collateral_token: Address, collection_id: [u8; 32]) -> Result<U256, ChainError> {
+        Ok(U256::from_big_endian(&collection_id))
+    }
+
+    async fn get_outcome_slot_count(&self, _condition_id: [u8; 32]) -> Result<U256, ChainError> {
+        Ok(U256::from(2))
+    }
+
+    async fn convert_positions(
+        &self,
+        neg_risk_market_id: [u8; 32],
+        index_set: U256,
+        amount: U256,
+    ) -> Result<H256, ChainError> {
+        self.scripted_status()?;
+
+        let mut conversions = self.conversions.lock();
+        // Counted apart from matches, from 2^32 so the hashes never collide
+        let block_number = (1 << 32) + conversions.len() as u64 + 1;
+        let tx_hash = H256::from_low_u64_be(block_number);
+        conversions.push(SubmittedConversion {
+            neg_risk_market_id,
+            index_set,
+            amount,
+            tx_hash,
+        });
+        Ok(tx_hash)
+    }
+
+    async fn transaction_result(&self, tx_hash: H256) -> Result<Option<TxResult>, ChainError> {
+        let status = self.scripted_status()?;
+        if status == TxStatus::Pending {
+            return Ok(None);
+        }
+        Ok(Some(TxResult {
+            tx_hash,
+            status,
+            block_number: Some(tx_hash.to_low_u64_be()),
+            gas_used: Some(U256::from(120_000)),
+            error: None,
+        }))
+    }
+}
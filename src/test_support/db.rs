@@ -0,0 +1,112 @@
+//! Ephemeral test database
+//!
+//! Every [`TestDatabase`] is a freshly created, fully migrated database on
+//! the Postgres server at `TEST_DATABASE_URL`, dropped again when the
+//! handle goes out of scope. Tests that need one fail when the variable is
+//! unset, rather than passing without having run.
+
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::{Connection, PgConnection};
+use uuid::Uuid;
+
+/// Server URL the test databases are created on
+const TEST_DATABASE_URL: &str = "TEST_DATABASE_URL";
+
+pub struct TestDatabase {
+    pub pool: PgPool,
+    pub url: String,
+    name: String,
+    server_url: String,
+}
+
+impl TestDatabase {
+    /// Create and migrate a new database
+    pub async fn create() -> Self {
+        let Ok(server_url) = std::env::var(TEST_DATABASE_URL) else {
+            panic!("{} must point at a Postgres server to run database tests", TEST_DATABASE_URL);
+        };
+        let name = format!("test_{}", Uuid::new_v4().simple());
+
+        let mut admin = PgConnection::connect(&server_url)
+            .await
+            .expect("connect to TEST_DATABASE_URL");
+        sqlx::query(&format!("CREATE DATABASE {}", name))
+            .execute(&mut admin)
+            .await
+            .expect("create test database");
+        admin.close().await.ok();
+
+        let url = with_database(&server_url, &name);
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&url)
+            .await
+            .expect("connect to test database");
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("migrate test database");
+
+        Self { pool, url, name, server_url }
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        // Drop runs inside the test's runtime, which can't block on its own
+        // futures; the database is dropped from a thread of its own
+        let (server_url, name) = (self.server_url.clone(), self.name.clone());
+        let dropped = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("cleanup runtime")
+                .block_on(async {
+                    let mut admin = PgConnection::connect(&server_url).await?;
+                    sqlx::query(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", name))
+                        .execute(&mut admin)
+                        .await?;
+                    admin.close().await
+                })
+        })
+        .join();
+        if !matches!(dropped, Ok(Ok(()))) {
+            eprintln!("Failed to drop test database {}", self.name);
+        }
+    }
+}
+
+/// `url` with its database path replaced by `database`
+fn with_database(url: &str, database: &str) -> String {
+    let (base, query) = match url.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (url, None),
+    };
+    let authority_end = base.find("://").map(|i| i + 3).unwrap_or(0);
+    let base = match base[authority_end..].find('/') {
+        Some(i) => &base[..authority_end + i],
+        None => base,
+    };
+    match query {
+        Some(query) => format!("{}/{}?{}", base, database, query),
+        None => format!("{}/{}", base, database),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_database() {
+        assert_eq!(
+            with_database("postgres://u:p@localhost:5432/postgres", "test_1"),
+            "postgres://u:p@localhost:5432/test_1"
+        );
+        assert_eq!(with_database("postgres://localhost", "test_1"), "postgres://localhost/test_1");
+        assert_eq!(
+            with_database("postgres://localhost/db?sslmode=disable", "test_1"),
+            "postgres://localhost/test_1?sslmode=disable"
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! Wall clock source
+//!
+//! Services that act on deadlines read the time through [`Clock`] so tests
+//! can drive them with a manually advanced clock.
+
+use chrono::{DateTime, Utc};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
@@ -1,3 +1,4 @@
+pub mod clock;
 pub mod response;
 
 // pub use response::*;
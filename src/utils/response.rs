@@ -1,9 +1,4 @@
 #![allow(dead_code)]
-use axum::{
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    Json,
-};
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -42,44 +37,3 @@ impl<T: Serialize> ApiResponse<T> {
         }
     }
 }
-
-/// Application error type
-#[derive(Debug)]
-pub struct AppError {
-    pub status: StatusCode,
-    pub code: String,
-    pub message: String,
-}
-
-impl AppError {
-    pub fn new(status: StatusCode, code: &str, message: &str) -> Self {
-        Self {
-            status,
-            code: code.to_string(),
-            message: message.to_string(),
-        }
-    }
-
-    pub fn bad_request(message: &str) -> Self {
-        Self::new(StatusCode::BAD_REQUEST, "BAD_REQUEST", message)
-    }
-
-    pub fn unauthorized(message: &str) -> Self {
-        Self::new(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", message)
-    }
-
-    pub fn not_found(message: &str) -> Self {
-        Self::new(StatusCode::NOT_FOUND, "NOT_FOUND", message)
-    }
-
-    pub fn internal(message: &str) -> Self {
-        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", message)
-    }
-}
-
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let body = ApiResponse::<()>::error(&self.code, &self.message);
-        (self.status, Json(body)).into_response()
-    }
-}
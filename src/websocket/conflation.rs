@@ -0,0 +1,284 @@
+//! Trade Conflation
+//!
+//! Busy markets can produce more trades than a client wants to render.
+//! Subscribing to a trade-bearing channel (`trades:{market_id}`, `trades:*`,
+//! `market:{market_id}`, legacy `trades:{symbol}`) with `"conflate": true`
+//! buffers that connection's market trades and flushes them every
+//! [`CONFLATION_WINDOW`] as one `markettradesummary` message per book,
+//! carrying the trade count and VWAP.
+//!
+//! Market data subscribed without real-time entitlement goes through a
+//! [`DelayedFeed`] instead, flushed every [`DELAYED_WINDOW`].
+
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+
+use crate::services::matching::{OrderbookUpdate, TradeEvent};
+
+use super::handler::ServerMessage;
+use super::router::route_orderbook;
+
+/// How long trades are buffered before a summary is sent
+pub const CONFLATION_WINDOW: Duration = Duration::from_millis(100);
+
+/// How long market data of the delayed tier is held back
+pub const DELAYED_WINDOW: Duration = Duration::from_secs(1);
+
+/// Trades of one book within the current window
+struct Bucket {
+    market_id: String,
+    outcome_id: String,
+    share_type: String,
+    count: u32,
+    amount: Decimal,
+    notional: Decimal,
+    high: Decimal,
+    low: Decimal,
+    last_price: Decimal,
+    start_timestamp: i64,
+    timestamp: i64,
+}
+
+/// Per-connection conflation state
+#[derive(Default)]
+pub struct TradeConflator {
+    /// Channels subscribed with conflation
+    channels: HashSet<String>,
+    /// Pending trades keyed by book symbol
+    pending: BTreeMap<String, Bucket>,
+}
+
+impl TradeConflator {
+    /// Conflate trades delivered through `channel`; ignored for channels
+    /// that carry no market trades
+    pub fn enable(&mut self, channel: &str) {
+        if channel.starts_with("trades:") || channel.starts_with("market:") {
+            self.channels.insert(channel.to_string());
+        }
+    }
+
+    pub fn disable(&mut self, channel: &str) {
+        self.channels.remove(channel);
+    }
+
+    /// Buffer `trade` if one of its market channels is conflated. Returns
+    /// whether it was buffered, in which case the individual `markettrade`
+    /// and `trade` messages must not be sent.
+    pub fn offer(&mut self, trade: &TradeEvent) -> bool {
+        if self.channels.is_empty() {
+            return false;
+        }
+        let market_id = trade.market_id.to_string();
+        let conflated = self.channels.contains("trades:*")
+            || self.channels.contains(&format!("trades:{}", market_id))
+            || self.channels.contains(&format!("market:{}", market_id))
+            || self.channels.contains(&format!("trades:{}", trade.symbol));
+        if !conflated {
+            return false;
+        }
+
+        let bucket = self.pending.entry(trade.symbol.clone()).or_insert_with(|| Bucket {
+            market_id,
+            outcome_id: trade.outcome_id.to_string(),
+            share_type: trade.share_type.to_string(),
+            count: 0,
+            amount: Decimal::ZERO,
+            notional: Decimal::ZERO,
+            high: trade.price,
+            low: trade.price,
+            last_price: trade.price,
+            start_timestamp: trade.timestamp,
+            timestamp: trade.timestamp,
+        });
+        bucket.count += 1;
+        bucket.amount += trade.amount;
+        bucket.notional += trade.price * trade.amount;
+        bucket.high = bucket.high.max(trade.price);
+        bucket.low = bucket.low.min(trade.price);
+        bucket.last_price = trade.price;
+        bucket.timestamp = trade.timestamp;
+        true
+    }
+
+    /// Take one summary per book traded since the last flush
+    pub fn flush(&mut self) -> Vec<ServerMessage> {
+        std::mem::take(&mut self.pending)
+            .into_values()
+            .map(|b| {
+                let vwap = if b.amount.is_zero() {
+                    b.last_price
+                } else {
+                    (b.notional / b.amount).round_dp(6)
+                };
+                ServerMessage::MarketTradeSummary {
+                    market_id: b.market_id,
+                    outcome_id: b.outcome_id,
+                    share_type: b.share_type,
+                    count: b.count,
+                    amount: b.amount.normalize().to_string(),
+                    vwap: vwap.normalize().to_string(),
+                    high: b.high.normalize().to_string(),
+                    low: b.low.normalize().to_string(),
+                    last_price: b.last_price.normalize().to_string(),
+                    start_timestamp: b.start_timestamp,
+                    timestamp: b.timestamp,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Market data of the channels a connection subscribed on the delayed
+/// tier: trades are conflated and only the latest update of each orderbook
+/// is kept, both sent every [`DELAYED_WINDOW`]
+#[derive(Default)]
+pub struct DelayedFeed {
+    channels: HashSet<String>,
+    trades: TradeConflator,
+    /// Latest update keyed by book symbol
+    books: BTreeMap<String, OrderbookUpdate>,
+}
+
+impl DelayedFeed {
+    pub fn add(&mut self, channel: &str) {
+        self.channels.insert(channel.to_string());
+        self.trades.enable(channel);
+    }
+
+    /// Stop delaying `channel`; returns whether it was delayed
+    pub fn remove(&mut self, channel: &str) -> bool {
+        self.trades.disable(channel);
+        self.channels.remove(channel)
+    }
+
+    pub fn offer_trade(&mut self, trade: &TradeEvent) {
+        self.trades.offer(trade);
+    }
+
+    pub fn offer_orderbook(&mut self, update: &OrderbookUpdate) {
+        let wants_books = self
+            .channels
+            .iter()
+            .any(|c| c.starts_with("orderbook:") || c.starts_with("market:"));
+        if wants_books {
+            self.books.insert(update.symbol.clone(), update.clone());
+        }
+    }
+
+    /// Take the trade summaries and latest orderbooks held back since the
+    /// last flush
+    pub fn flush(&mut self) -> Vec<ServerMessage> {
+        let mut messages = self.trades.flush();
+        for update in std::mem::take(&mut self.books).into_values() {
+            messages.extend(route_orderbook(&update, &self.channels));
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::matching::Side;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    fn trade(symbol: &str, price: Decimal, amount: Decimal, timestamp: i64) -> TradeEvent {
+        let mut event = TradeEvent::new(
+            symbol.to_string(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "0xmaker".to_string(),
+            "0xtaker".to_string(),
+            Side::Buy,
+            price,
+            amount,
+            Decimal::ZERO,
+            Decimal::ZERO,
+        );
+        event.timestamp = timestamp;
+        event
+    }
+
+    #[test]
+    fn test_conflates_into_vwap_summary() {
+        let market_id = Uuid::new_v4();
+        let symbol = format!("{}:{}:yes", market_id, Uuid::new_v4());
+        let mut conflator = TradeConflator::default();
+
+        // Not conflated until a matching channel opts in
+        assert!(!conflator.offer(&trade(&symbol, dec!(0.5), dec!(10), 1)));
+        conflator.enable(&format!("trades:{}", market_id));
+
+        assert!(conflator.offer(&trade(&symbol, dec!(0.50), dec!(10), 1)));
+        assert!(conflator.offer(&trade(&symbol, dec!(0.60), dec!(30), 2)));
+        assert!(conflator.offer(&trade(&symbol, dec!(0.40), dec!(10), 3)));
+
+        let messages = conflator.flush();
+        assert_eq!(messages.len(), 1);
+        match &messages[0] {
+            ServerMessage::MarketTradeSummary {
+                count,
+                amount,
+                vwap,
+                high,
+                low,
+                last_price,
+                start_timestamp,
+                timestamp,
+                ..
+            } => {
+                assert_eq!(*count, 3);
+                assert_eq!(amount, "50");
+                // (5 + 18 + 4) / 50
+                assert_eq!(vwap, "0.54");
+                assert_eq!((high.as_str(), low.as_str(), last_price.as_str()), ("0.6", "0.4", "0.4"));
+                assert_eq!((*start_timestamp, *timestamp), (1, 3));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+        assert!(conflator.flush().is_empty());
+
+        conflator.disable(&format!("trades:{}", market_id));
+        assert!(!conflator.offer(&trade(&symbol, dec!(0.5), dec!(1), 4)));
+    }
+
+    #[test]
+    fn test_delayed_feed_keeps_latest_orderbook() {
+        let market_id = Uuid::new_v4();
+        let symbol = format!("{}:{}:yes", market_id, Uuid::new_v4());
+        let book = |bid: &str, timestamp| OrderbookUpdate {
+            symbol: symbol.clone(),
+            bids: vec![[bid.to_string(), "10".to_string()]].into(),
+            asks: Vec::new().into(),
+            sequence: timestamp as u64,
+            timestamp,
+        };
+        let mut feed = DelayedFeed::default();
+        feed.add(&format!("market:{}", market_id));
+
+        feed.offer_trade(&trade(&symbol, dec!(0.4), dec!(1), 1));
+        feed.offer_trade(&trade(&symbol, dec!(0.5), dec!(1), 2));
+        feed.offer_orderbook(&book("0.4", 1));
+        feed.offer_orderbook(&book("0.5", 2));
+
+        let messages = feed.flush();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0], ServerMessage::MarketTradeSummary { count: 2, .. }));
+        match &messages[1] {
+            ServerMessage::MarketOrderbook { bids, timestamp, .. } => {
+                assert_eq!((bids[0].price.as_str(), *timestamp), ("0.5", 2));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+        assert!(feed.flush().is_empty());
+
+        assert!(feed.remove(&format!("market:{}", market_id)));
+        feed.offer_trade(&trade(&symbol, dec!(0.5), dec!(1), 3));
+        feed.offer_orderbook(&book("0.5", 3));
+        assert!(feed.flush().is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+//! Orderbook Deltas
+//!
+//! Market makers on the delta tier subscribe to `orderbook-delta:` channels
+//! and receive the matching engine's orderbook deltas: only the price levels
+//! that were added, changed or removed, over the whole depth, numbered per
+//! book. The first message for a book is a snapshot of every level at some
+//! sequence; applying each later delta in sequence order keeps a local copy
+//! exact. Whenever a connection would miss a delta (the receiver lagged) the
+//! book starts over with a fresh snapshot.
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::services::matching::{LevelAction, OrderbookDelta, SequencedDepth, Side};
+
+use super::handler::DeltaLevel;
+
+/// What to do with an engine delta on one connection
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeltaStep {
+    /// Next in the book's sequence: forward it
+    Send,
+    /// Already covered by the snapshot sent
+    Skip,
+    /// No snapshot yet, or deltas were missed: send a snapshot instead
+    Snapshot,
+}
+
+/// Per-connection state: the sequence each book was last sent at
+#[derive(Default)]
+pub struct BookDeltas {
+    sequences: HashMap<String, u64>,
+}
+
+impl BookDeltas {
+    /// Record a snapshot of `symbol` sent at `sequence`
+    pub fn snapshot(&mut self, symbol: &str, sequence: u64) {
+        self.sequences.insert(symbol.to_string(), sequence);
+    }
+
+    /// Whether `delta` continues what was sent for its book
+    pub fn step(&mut self, delta: &OrderbookDelta) -> DeltaStep {
+        match self.sequences.get_mut(&delta.symbol) {
+            Some(sent) if delta.sequence <= *sent => DeltaStep::Skip,
+            Some(sent) if delta.sequence == *sent + 1 => {
+                *sent = delta.sequence;
+                DeltaStep::Send
+            }
+            _ => DeltaStep::Snapshot,
+        }
+    }
+
+    /// Forget what was sent, so every book starts again with a snapshot
+    pub fn reset(&mut self) {
+        self.sequences.clear();
+    }
+}
+
+/// Every level of `depth` as added levels
+pub fn snapshot_levels(depth: &SequencedDepth) -> (Vec<DeltaLevel>, Vec<DeltaLevel>) {
+    let added = |levels: &[(Decimal, Decimal)]| {
+        levels
+            .iter()
+            .map(|(price, size)| DeltaLevel {
+                price: price.to_string(),
+                size: size.to_string(),
+                action: LevelAction::Add,
+            })
+            .collect()
+    };
+    (added(&depth.bids), added(&depth.asks))
+}
+
+/// The changes of `delta`, bids and asks
+pub fn delta_levels(delta: &OrderbookDelta) -> (Vec<DeltaLevel>, Vec<DeltaLevel>) {
+    let (mut bids, mut asks) = (Vec::new(), Vec::new());
+    for change in delta.changes.iter() {
+        let level = DeltaLevel {
+            price: change.price.to_string(),
+            size: change.size.to_string(),
+            action: change.action,
+        };
+        match change.side {
+            Side::Buy => bids.push(level),
+            Side::Sell => asks.push(level),
+        }
+    }
+    (bids, asks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(sequence: u64) -> OrderbookDelta {
+        OrderbookDelta {
+            symbol: "m:o:yes".to_string(),
+            sequence,
+            changes: Vec::new().into(),
+            timestamp: 1,
+        }
+    }
+
+    #[test]
+    fn test_deltas_continue_snapshot_sequence() {
+        let mut deltas = BookDeltas::default();
+        assert_eq!(deltas.step(&delta(3)), DeltaStep::Snapshot);
+
+        deltas.snapshot("m:o:yes", 4);
+        assert_eq!(deltas.step(&delta(4)), DeltaStep::Skip);
+        assert_eq!(deltas.step(&delta(5)), DeltaStep::Send);
+        assert_eq!(deltas.step(&delta(6)), DeltaStep::Send);
+        // 7 was missed
+        assert_eq!(deltas.step(&delta(8)), DeltaStep::Snapshot);
+
+        deltas.reset();
+        assert_eq!(deltas.step(&delta(9)), DeltaStep::Snapshot);
+    }
+}
@@ -10,13 +10,22 @@ use std::collections::HashSet;
 use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
 use tokio::sync::broadcast;
-use uuid::Uuid;
 
 use crate::auth::eip712::{verify_ws_auth_signature, WebSocketAuthMessage};
-use crate::auth::jwt::validate_token;
+use crate::auth::session::validate_session_token;
 use crate::metrics;
+use crate::models::Address;
+use crate::services::market_archive;
+use crate::services::market_data_tier::{self, MarketDataTier};
+use crate::services::matching::recovery;
 #[allow(unused_imports)]
 use crate::services::matching::OrderbookUpdate;
+use crate::services::matching::LevelAction;
+use crate::websocket::conflation::{DelayedFeed, TradeConflator, CONFLATION_WINDOW, DELAYED_WINDOW};
+use crate::websocket::delta::BookDeltas;
+use crate::websocket::router::{
+    check_entitlement, orderbook_delta_snapshot, route_orderbook, route_orderbook_delta, route_trade, to_levels, Delivery,
+};
 use crate::AppState;
 
 /// Global WebSocket connection counter
@@ -72,6 +81,9 @@ pub enum ClientMessage {
         signature: Option<String>,
         #[serde(default)]
         timestamp: Option<u64>,
+        /// Challenge nonce from the server's `challenge` message
+        #[serde(default)]
+        nonce: Option<String>,
         #[serde(default)]
         token: Option<String>,
     },
@@ -83,17 +95,31 @@ pub enum ClientMessage {
         channel: String,
         #[serde(default)]
         token: Option<String>,
+        /// Batch market trades into periodic summaries
+        #[serde(default)]
+        conflate: bool,
     },
     Unsubscribe {
         channel: String,
     },
     Ping,
+    /// Ask for the server clock; `client_time` is echoed back for round-trip
+    /// measurement
+    Time {
+        #[serde(default)]
+        client_time: Option<i64>,
+    },
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Serialize, Clone)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum ServerMessage {
+    /// Nonce to sign for signature auth; valid for one attempt on this
+    /// connection and replaced after every attempt
+    Challenge {
+        nonce: String,
+    },
     AuthResult {
         success: bool,
         message: Option<String>,
@@ -110,12 +136,16 @@ pub enum ServerMessage {
         price: String,
         amount: String,
         side: String,
+        /// Position among the trades of `symbol`
+        market_sequence: u64,
         timestamp: i64,
     },
     Orderbook {
         symbol: String,
         bids: Vec<OrderbookLevel>,
         asks: Vec<OrderbookLevel>,
+        /// Position among the updates of `symbol`
+        sequence: u64,
         timestamp: i64,
     },
     Ticker {
@@ -186,6 +216,14 @@ pub enum ServerMessage {
         message: String,
     },
     Pong,
+    /// Server clock (milliseconds) and matching engine trade sequence
+    /// high-water mark
+    Time {
+        server_time: i64,
+        sequence: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        client_time: Option<i64>,
+    },
     /// K-line update
     Kline {
         channel: String,
@@ -209,6 +247,29 @@ pub enum ServerMessage {
         price: String,
         amount: String,
         side: String,
+        /// Taker side ("buy"/"sell"); same as `side`
+        aggressor_side: String,
+        /// Engine trade sequence number
+        sequence: u64,
+        /// Position among the trades of the book
+        market_sequence: u64,
+        is_block_trade: bool,
+        is_rfq: bool,
+        timestamp: i64,
+    },
+    /// Conflated market trades: every trade on one book within the
+    /// conflation window
+    MarketTradeSummary {
+        market_id: String,
+        outcome_id: String,
+        share_type: String,
+        count: u32,
+        amount: String,
+        vwap: String,
+        high: String,
+        low: String,
+        last_price: String,
+        start_timestamp: i64,
         timestamp: i64,
     },
     /// Orderbook update for prediction markets
@@ -218,6 +279,20 @@ pub enum ServerMessage {
         share_type: String,
         bids: Vec<OrderbookLevel>,
         asks: Vec<OrderbookLevel>,
+        /// Position among the updates of the book
+        sequence: u64,
+        timestamp: i64,
+    },
+    /// Levels of one book that changed in delta `sequence`, a size of "0"
+    /// removing the level; `snapshot` carries the whole book as of `sequence`
+    MarketOrderbookDelta {
+        market_id: String,
+        outcome_id: String,
+        share_type: String,
+        sequence: u64,
+        bids: Vec<DeltaLevel>,
+        asks: Vec<DeltaLevel>,
+        snapshot: bool,
         timestamp: i64,
     },
     /// Market status/probability update
@@ -248,6 +323,14 @@ pub struct OrderbookLevel {
     pub size: String,
 }
 
+/// Changed orderbook level of a delta
+#[derive(Debug, Serialize, Clone)]
+pub struct DeltaLevel {
+    pub price: String,
+    pub size: String,
+    pub action: LevelAction,
+}
+
 /// K-line data for WebSocket
 #[derive(Debug, Serialize, Clone)]
 pub struct KlineData {
@@ -274,6 +357,19 @@ fn validate_timestamp(timestamp: u64) -> bool {
     now.abs_diff(timestamp) <= 300
 }
 
+/// Fresh signature auth challenge
+fn new_auth_nonce() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+/// Consume the connection's challenge; true if `provided` is it
+fn take_auth_nonce(auth_nonce: &mut Option<String>, provided: Option<&str>) -> bool {
+    match (auth_nonce.take(), provided) {
+        (Some(expected), Some(provided)) => expected == provided,
+        _ => false,
+    }
+}
+
 pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     // Track WebSocket connection
     let connection_count = WS_CONNECTION_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
@@ -285,23 +381,44 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     let mut authenticated = false;
     let mut user_address: Option<String> = None;
     let mut subscriptions: HashSet<String> = HashSet::new();
-
-    // Subscribe to trade events from matching engine
-    let mut trade_receiver = state.matching_engine.subscribe_trades();
-    tracing::info!("📡 WebSocket subscribed to trade events from matching engine");
+    let mut conflator = TradeConflator::default();
+    // Market data subscribed without real-time entitlement
+    let mut delayed = DelayedFeed::default();
+    let mut deltas = BookDeltas::default();
+
+    // Signature auth must sign this connection's challenge, so a captured
+    // auth message can't be replayed on another connection
+    let mut auth_nonce = Some(new_auth_nonce());
+    let challenge = ServerMessage::Challenge {
+        nonce: auth_nonce.clone().unwrap_or_default(),
+    };
+    let _ = sender.send(Message::Text(serde_json::to_string(&challenge).unwrap())).await;
+
+    // Subscribe to trade events from the event bus
+    let mut trade_receiver = state.event_bus.subscribe_trades();
+    tracing::info!("📡 WebSocket subscribed to trade events from the event bus");
 
     // Subscribe to orderbook updates from matching engine
     let mut orderbook_receiver = state.matching_engine.subscribe_orderbook();
     tracing::info!("📡 WebSocket subscribed to orderbook events from matching engine");
 
+    // Sequenced orderbook deltas for `orderbook-delta:` channels
+    let mut delta_receiver = state.matching_engine.subscribe_orderbook_deltas();
+
     // Subscribe to order updates for real-time push
-    let mut order_update_receiver = state.order_update_sender.subscribe();
+    let mut order_update_receiver = state.event_bus.subscribe_order_updates();
     tracing::info!("📡 WebSocket subscribed to order update events");
 
+    // Mass cancels arrive as one message instead of one update per order
+    let mut orders_cancelled_receiver = state.event_bus.subscribe_orders_cancelled();
+
     // Subscribe to balance updates for real-time push
-    let mut balance_update_receiver = state.balance_update_sender.subscribe();
+    let mut balance_update_receiver = state.event_bus.subscribe_balance_updates();
     tracing::info!("📡 WebSocket subscribed to balance update events");
 
+    // Stop orders firing, failing or expiring reach their owner's `orders` channel
+    let mut trigger_update_receiver = state.event_bus.subscribe_trigger_updates();
+
     // Ticker update interval (every 2 seconds)
     let mut ticker_interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
 
@@ -311,6 +428,12 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     // Position/balance update interval for authenticated users (every 5 seconds)
     let mut private_interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
 
+    // Conflated trade summaries
+    let mut conflation_interval = tokio::time::interval(CONFLATION_WINDOW);
+
+    // Delayed-tier market data
+    let mut delayed_interval = tokio::time::interval(DELAYED_WINDOW);
+
     loop {
         tokio::select! {
             // Handle incoming client messages
@@ -322,7 +445,11 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                             &text,
                             &mut authenticated,
                             &mut user_address,
+                            &mut auth_nonce,
                             &mut subscriptions,
+                            &mut conflator,
+                            &mut delayed,
+                            &mut deltas,
                             &state,
                             &mut sender,
                         ).await {
@@ -350,52 +477,15 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
             trade = trade_receiver.recv() => {
                 match trade {
                     Ok(trade_event) => {
-                        // Generate unique trade ID
-                        let trade_id = format!("{}-{}", trade_event.timestamp, Uuid::new_v4().to_string().split('-').next().unwrap_or("0"));
-
-                        // Check for prediction market channel subscriptions
-                        // Channels: "trades:{market_id}", "market:{market_id}", "trades:*"
-                        let market_id = trade_event.market_id.to_string();
-                        let market_trade_channel = format!("trades:{}", market_id);
-                        let market_channel = format!("market:{}", market_id);
-
-                        let subscribed_to_market = subscriptions.contains(&market_trade_channel)
-                            || subscriptions.contains(&market_channel)
-                            || subscriptions.contains("trades:*");
-
-                        if subscribed_to_market {
-                            tracing::info!(
-                                "✅ Sending market trade: market_id={}, match_type={}",
-                                market_id, trade_event.match_type
-                            );
-
-                            let msg = ServerMessage::MarketTrade {
-                                id: trade_id.clone(),
-                                market_id: market_id.clone(),
-                                outcome_id: trade_event.outcome_id.to_string(),
-                                share_type: trade_event.share_type.to_string(),
-                                match_type: trade_event.match_type.to_string().to_lowercase(),
-                                price: trade_event.price.to_string(),
-                                amount: trade_event.amount.to_string(),
-                                side: trade_event.side.clone(),
-                                timestamp: trade_event.timestamp,
-                            };
-                            let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
-                        }
-
-                        // Also check legacy symbol-based channel (backwards compatibility)
-                        let symbol_channel = format!("trades:{}", trade_event.symbol);
-                        if subscriptions.contains(&symbol_channel) {
-                            let msg = ServerMessage::Trade {
-                                id: trade_id,
-                                symbol: trade_event.symbol.clone(),
-                                price: trade_event.price.to_string(),
-                                amount: trade_event.amount.to_string(),
-                                side: trade_event.side.clone(),
-                                timestamp: trade_event.timestamp,
-                            };
+                        let conflated = conflator.offer(&trade_event);
+                        for msg in route_trade(&trade_event, &subscriptions) {
+                            // Conflated trades go out with the next summary
+                            if conflated && matches!(msg, ServerMessage::MarketTrade { .. } | ServerMessage::Trade { .. }) {
+                                continue;
+                            }
                             let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
                         }
+                        delayed.offer_trade(&trade_event);
                     }
                     Err(broadcast::error::RecvError::Lagged(n)) => {
                         tracing::warn!("⚠️  Trade receiver lagged by {} messages - some trades may have been missed!", n);
@@ -411,61 +501,10 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
             orderbook = orderbook_receiver.recv() => {
                 match orderbook {
                     Ok(orderbook_update) => {
-                        // Convert to frontend-compatible format
-                        let bids: Vec<OrderbookLevel> = orderbook_update.bids
-                            .iter()
-                            .map(|[price, size]| OrderbookLevel { price: price.clone(), size: size.clone() })
-                            .collect();
-                        let asks: Vec<OrderbookLevel> = orderbook_update.asks
-                            .iter()
-                            .map(|[price, size]| OrderbookLevel { price: price.clone(), size: size.clone() })
-                            .collect();
-
-                        // Symbol format for prediction markets: {market_id}:{outcome_id}:{share_type}
-                        let symbol = &orderbook_update.symbol;
-
-                        // Check for prediction market orderbook channel
-                        // Channels: "orderbook:{market_id}:{outcome_id}:{share_type}", "orderbook:{market_id}", "market:{market_id}"
-                        let parts: Vec<&str> = symbol.split(':').collect();
-                        if parts.len() == 3 {
-                            // Prediction market orderbook
-                            let market_id = parts[0];
-                            let outcome_id = parts[1];
-                            let share_type = parts[2];
-
-                            let specific_channel = format!("orderbook:{}", symbol);
-                            let market_ob_channel = format!("orderbook:{}", market_id);
-                            let market_channel = format!("market:{}", market_id);
-
-                            let subscribed = subscriptions.contains(&specific_channel)
-                                || subscriptions.contains(&market_ob_channel)
-                                || subscriptions.contains(&market_channel)
-                                || subscriptions.contains("orderbook:*");
-
-                            if subscribed {
-                                let msg = ServerMessage::MarketOrderbook {
-                                    market_id: market_id.to_string(),
-                                    outcome_id: outcome_id.to_string(),
-                                    share_type: share_type.to_string(),
-                                    bids: bids.clone(),
-                                    asks: asks.clone(),
-                                    timestamp: orderbook_update.timestamp,
-                                };
-                                let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
-                            }
-                        }
-
-                        // Also check legacy symbol-based channel (backwards compatibility)
-                        let orderbook_channel = format!("orderbook:{}", symbol);
-                        if subscriptions.contains(&orderbook_channel) {
-                            let msg = ServerMessage::Orderbook {
-                                symbol: symbol.clone(),
-                                bids,
-                                asks,
-                                timestamp: orderbook_update.timestamp,
-                            };
+                        for msg in route_orderbook(&orderbook_update, &subscriptions) {
                             let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
                         }
+                        delayed.offer_orderbook(&orderbook_update);
                     }
                     Err(broadcast::error::RecvError::Lagged(n)) => {
                         tracing::warn!("Orderbook receiver lagged by {} messages", n);
@@ -476,6 +515,26 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                 }
             }
 
+            // Handle orderbook deltas from matching engine
+            delta = delta_receiver.recv() => {
+                match delta {
+                    Ok(delta) => {
+                        let depth = |symbol: &str| state.matching_engine.sequenced_depth(symbol).ok();
+                        if let Some(msg) = route_orderbook_delta(&delta, &subscriptions, &mut deltas, depth) {
+                            let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        // Deltas were lost: every book resyncs from a snapshot
+                        tracing::warn!("Orderbook delta receiver lagged by {} messages", n);
+                        deltas.reset();
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        // Continue without orderbook deltas
+                    }
+                }
+            }
+
             // Handle order updates (real-time push when orders are created/updated)
             order_update = order_update_receiver.recv() => {
                 match order_update {
@@ -506,6 +565,34 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                 }
             }
 
+            // Handle mass cancels (POST /orders/cancel-all)
+            orders_cancelled = orders_cancelled_receiver.recv() => {
+                match orders_cancelled {
+                    Ok(event) => {
+                        if authenticated && user_address.as_deref() == Some(event.user_address.as_str())
+                            && subscriptions.contains("orders")
+                        {
+                            let msg = serde_json::json!({
+                                "channel": "orders",
+                                "type": "orders_cancelled",
+                                "data": {
+                                    "order_ids": event.order_ids,
+                                    "count": event.order_ids.len(),
+                                    "timestamp": event.timestamp
+                                }
+                            });
+                            let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Orders cancelled receiver lagged by {} messages", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        // Continue without mass cancel updates
+                    }
+                }
+            }
+
             // Handle balance updates (real-time push for deposits/withdrawals)
             balance_update = balance_update_receiver.recv() => {
                 match balance_update {
@@ -542,6 +629,44 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                 }
             }
 
+            // Handle trigger order updates (stop orders firing, failing or expiring)
+            trigger_update = trigger_update_receiver.recv() => {
+                match trigger_update {
+                    Ok(event) => {
+                        if authenticated && user_address.as_deref() == Some(event.user_address.as_str())
+                            && subscriptions.contains("orders")
+                        {
+                            let msg = serde_json::json!({
+                                "channel": "orders",
+                                "type": "trigger_order_update",
+                                "data": event.trigger
+                            });
+                            let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Trigger order update receiver lagged by {} messages", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        // Continue without trigger order updates
+                    }
+                }
+            }
+
+            // Flush conflated trades
+            _ = conflation_interval.tick() => {
+                for msg in conflator.flush() {
+                    let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
+                }
+            }
+
+            // Flush delayed-tier market data
+            _ = delayed_interval.tick() => {
+                for msg in delayed.flush() {
+                    let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
+                }
+            }
+
             // Ticker updates - simplified for prediction markets
             _ = ticker_interval.tick() => {
                 // TODO: Implement prediction market ticker updates if needed
@@ -555,6 +680,7 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                         if channel.starts_with("orderbook:") {
                             let raw_symbol = channel.strip_prefix("orderbook:").unwrap_or("");
                             let symbol = normalize_symbol(raw_symbol);
+                            let sequence = state.matching_engine.market_sequence(&symbol).orderbook;
                             let cached = orderbook_cache.get_orderbook(&symbol, Some(20)).await;
                             if !cached.bids.is_empty() || !cached.asks.is_empty() {
                                 let bids: Vec<OrderbookLevel> = cached.bids
@@ -575,6 +701,7 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                                     symbol: cached.symbol,
                                     bids,
                                     asks,
+                                    sequence,
                                     timestamp: cached.timestamp,
                                 };
                                 let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
@@ -626,11 +753,16 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     tracing::info!("🔌 WebSocket disconnected (remaining: {}) for {:?}", connection_count, user_address);
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_client_message(
     text: &str,
     authenticated: &mut bool,
     user_address: &mut Option<String>,
+    auth_nonce: &mut Option<String>,
     subscriptions: &mut HashSet<String>,
+    conflator: &mut TradeConflator,
+    delayed: &mut DelayedFeed,
+    deltas: &mut BookDeltas,
     state: &Arc<AppState>,
     sender: &mut futures::stream::SplitSink<WebSocket, Message>,
 ) -> Result<(), ServerMessage> {
@@ -644,11 +776,12 @@ async fn handle_client_message(
             address,
             signature,
             timestamp,
+            nonce,
             token,
         } => {
             // Check if token-based auth (JWT)
             if let Some(jwt_token) = token {
-                match validate_token(&jwt_token, &state.config.jwt_secret) {
+                match validate_session_token(&state.db.pool, &jwt_token, &state.config.jwt_secret).await {
                     Ok(claims) => {
                         *authenticated = true;
                         *user_address = Some(claims.sub.to_lowercase());
@@ -686,6 +819,26 @@ async fn handle_client_message(
                 }
             };
 
+            // The challenge is good for one attempt; hand out the next one
+            // whatever the outcome
+            let nonce_valid = take_auth_nonce(auth_nonce, nonce.as_deref());
+            let signed_nonce = nonce.unwrap_or_default();
+            *auth_nonce = Some(new_auth_nonce());
+            let challenge = ServerMessage::Challenge {
+                nonce: auth_nonce.clone().unwrap_or_default(),
+            };
+            let _ = sender.send(Message::Text(serde_json::to_string(&challenge).unwrap())).await;
+
+            if !nonce_valid {
+                tracing::warn!("WebSocket auth with stale or missing challenge for address: {}", address);
+                let response = ServerMessage::AuthResult {
+                    success: false,
+                    message: Some("Invalid or reused challenge nonce".to_string()),
+                };
+                let _ = sender.send(Message::Text(serde_json::to_string(&response).unwrap())).await;
+                return Ok(());
+            }
+
             // 验证时间戳（5分钟内有效）
             let now = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
@@ -705,6 +858,7 @@ async fn handle_client_message(
             // EIP-712 签名验证
             let ws_auth_msg = WebSocketAuthMessage {
                 wallet: address.to_lowercase(),
+                nonce: signed_nonce,
                 timestamp,
             };
 
@@ -747,7 +901,7 @@ async fn handle_client_message(
 
         ClientMessage::AuthToken { token } => {
             // Validate JWT token
-            match validate_token(&token, &state.config.jwt_secret) {
+            match validate_session_token(&state.db.pool, &token, &state.config.jwt_secret).await {
                 Ok(claims) => {
                     *authenticated = true;
                     *user_address = Some(claims.sub.to_lowercase());
@@ -771,11 +925,11 @@ async fn handle_client_message(
             }
         }
 
-        ClientMessage::Subscribe { channel, token } => {
+        ClientMessage::Subscribe { channel, token, conflate } => {
             // If token is provided with subscribe, try to authenticate first
             if let Some(jwt_token) = token {
                 if !*authenticated {
-                    if let Ok(claims) = validate_token(&jwt_token, &state.config.jwt_secret) {
+                    if let Ok(claims) = validate_session_token(&state.db.pool, &jwt_token, &state.config.jwt_secret).await {
                         *authenticated = true;
                         *user_address = Some(claims.sub.to_lowercase());
                         tracing::info!("WebSocket auto-authenticated via subscribe token: {}", claims.sub);
@@ -795,8 +949,45 @@ async fn handle_client_message(
                 });
             }
 
-            subscriptions.insert(channel.clone());
-            
+            // Resolved/cancelled markets are read-only: no live data
+            if market_archive::live_channel_market(&channel).is_some_and(|id| state.market_archiver.is_archived(id)) {
+                return Err(ServerMessage::Error {
+                    code: "MARKET_ARCHIVED".to_string(),
+                    message: format!("Market is closed; '{}' has no live data, use the REST endpoints", channel),
+                });
+            }
+
+            // Anonymous connections get delayed market data; the delta feed
+            // is for market makers
+            let tier = match user_address.as_deref() {
+                Some(address) if *authenticated => market_data_tier::get(&state.db.pool, address)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Market data tier lookup failed for {}: {}", address, e);
+                        MarketDataTier::RealTime
+                    }),
+                _ => MarketDataTier::Delayed,
+            };
+            let delivery = check_entitlement(tier, &channel).map_err(|reason| ServerMessage::Error {
+                code: "ENTITLEMENT_REQUIRED".to_string(),
+                message: reason.to_string(),
+            })?;
+            match delivery {
+                Delivery::Live => {
+                    delayed.remove(&channel);
+                    subscriptions.insert(channel.clone());
+                    if conflate {
+                        conflator.enable(&channel);
+                    } else {
+                        conflator.disable(&channel);
+                    }
+                }
+                Delivery::Delayed => {
+                    subscriptions.remove(&channel);
+                    conflator.disable(&channel);
+                    delayed.add(&channel);
+                }
+            }
             tracing::info!(
                 "✅ Client subscribed to '{}' (total subscriptions: {})",
                 channel, subscriptions.len()
@@ -810,6 +1001,8 @@ async fn handle_client_message(
             if channel.starts_with("orderbook:") {
                 let raw_symbol = channel.strip_prefix("orderbook:").unwrap_or("");
                 let symbol = normalize_symbol(raw_symbol);
+                // Updates numbered after this continue the snapshot
+                let sequence = state.matching_engine.market_sequence(&symbol).orderbook;
                 // Try Redis cache first, then fallback to matching engine
                 let orderbook_msg = if let Some(orderbook_cache) = state.cache.orderbook_opt() {
                     let cached = orderbook_cache.get_orderbook(&symbol, Some(20)).await;
@@ -832,6 +1025,7 @@ async fn handle_client_message(
                             symbol: cached.symbol,
                             bids,
                             asks,
+                            sequence,
                             timestamp: cached.timestamp,
                         })
                     } else {
@@ -843,31 +1037,32 @@ async fn handle_client_message(
 
                 // Fallback to matching engine if Redis cache is empty
                 let msg = orderbook_msg.unwrap_or_else(|| {
-                    if let Ok(snapshot) = state.matching_engine.get_orderbook(&symbol, 20) {
-                        let bids: Vec<OrderbookLevel> = snapshot.bids
-                            .into_iter()
-                            .map(|[price, size]| OrderbookLevel { price, size })
-                            .collect();
-                        let asks: Vec<OrderbookLevel> = snapshot.asks
-                            .into_iter()
-                            .map(|[price, size]| OrderbookLevel { price, size })
-                            .collect();
+                    if let Ok(depth) = state.matching_engine.get_orderbook_depth(&symbol) {
                         ServerMessage::Orderbook {
-                            symbol: snapshot.symbol,
-                            bids,
-                            asks,
-                            timestamp: snapshot.timestamp,
+                            symbol: symbol.to_string(),
+                            bids: to_levels(&depth.bids),
+                            asks: to_levels(&depth.asks),
+                            sequence,
+                            timestamp: chrono::Utc::now().timestamp_millis(),
                         }
                     } else {
                         ServerMessage::Orderbook {
                             symbol: symbol.to_string(),
                             bids: vec![],
                             asks: vec![],
+                            sequence,
                             timestamp: chrono::Utc::now().timestamp_millis(),
                         }
                     }
                 });
                 let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
+            } else if let Some(symbol) = channel.strip_prefix("orderbook-delta:").filter(|s| s.matches(':').count() == 2) {
+                // Books of market-wide subscriptions get theirs with their first delta
+                if let Ok(depth) = state.matching_engine.sequenced_depth(symbol) {
+                    if let Some(msg) = orderbook_delta_snapshot(symbol, &depth, deltas) {
+                        let _ = sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await;
+                    }
+                }
             } else if channel.starts_with("ticker:") {
                 // TODO: Implement prediction market ticker subscription
                 // For now, just acknowledge the subscription without sending data
@@ -893,12 +1088,21 @@ async fn handle_client_message(
                         let _ = sender.send(Message::Text(serde_json::to_string(&order).unwrap())).await;
                     }
                 }
+                // Orders that filled or failed while the books were rebuilt
+                // after a restart
+                if let Ok(notices) = fetch_recovery_notices(state, &address).await {
+                    for notice in notices {
+                        let _ = sender.send(Message::Text(serde_json::to_string(&notice).unwrap())).await;
+                    }
+                }
             }
             // TODO: Add kline support for prediction markets if needed
         }
 
         ClientMessage::Unsubscribe { channel } => {
             subscriptions.remove(&channel);
+            conflator.disable(&channel);
+            delayed.remove(&channel);
 
             let response = ServerMessage::Unsubscribed { channel };
             let _ = sender.send(Message::Text(serde_json::to_string(&response).unwrap())).await;
@@ -908,6 +1112,15 @@ async fn handle_client_message(
             let response = ServerMessage::Pong;
             let _ = sender.send(Message::Text(serde_json::to_string(&response).unwrap())).await;
         }
+
+        ClientMessage::Time { client_time } => {
+            let response = ServerMessage::Time {
+                server_time: chrono::Utc::now().timestamp_millis(),
+                sequence: state.matching_engine.last_trade_sequence(),
+                client_time,
+            };
+            let _ = sender.send(Message::Text(serde_json::to_string(&response).unwrap())).await;
+        }
     }
 
     Ok(())
@@ -917,7 +1130,7 @@ async fn handle_client_message(
 /// Note: In prediction markets, "positions" are actually share holdings
 async fn fetch_user_positions(state: &Arc<AppState>, address: &str) -> Result<Vec<ServerMessage>, sqlx::Error> {
     // For prediction markets, we don't have traditional positions with leverage
-    // Instead we have share holdings. For now, return empty until we implement share holdings
+    // Instead we have share holdings, maintained by the trade persistence path
     let rows: Vec<(String, String, String, Decimal, Decimal, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
         r#"
         SELECT id::text, market_id::text, share_type, shares, avg_price, updated_at
@@ -928,7 +1141,7 @@ async fn fetch_user_positions(state: &Arc<AppState>, address: &str) -> Result<Ve
     .bind(address)
     .fetch_all(&state.db.pool)
     .await
-    .unwrap_or_default(); // Return empty if table doesn't exist yet
+    .unwrap_or_default();
 
     let mut messages = Vec::new();
     for (id, market_id, share_type, shares, avg_price, updated_at) in rows {
@@ -1017,3 +1230,65 @@ async fn fetch_user_orders(state: &Arc<AppState>, address: &str) -> Result<Vec<S
 
     Ok(messages)
 }
+
+/// Fetch (and mark delivered) the user's order recovery results
+async fn fetch_recovery_notices(state: &Arc<AppState>, address: &str) -> Result<Vec<ServerMessage>, sqlx::Error> {
+    let Ok(address) = address.parse::<Address>() else {
+        return Ok(Vec::new());
+    };
+    let notices = recovery::take_pending_notices(&state.db.pool, &address).await?;
+
+    let messages: Vec<ServerMessage> = notices
+        .into_iter()
+        .map(|notice| {
+            let (status, event) = match notice.outcome.as_str() {
+                "filled" => ("filled", "recovery_fill"),
+                "partially_filled" => ("partially_filled", "recovery_fill"),
+                _ => ("open", "recovery_failed"),
+            };
+            ServerMessage::Order {
+                id: notice.order_id.to_string(),
+                symbol: notice.symbol,
+                side: notice.side,
+                order_type: "limit".to_string(),
+                price: Some(notice.price.to_string()),
+                amount: notice.amount.to_string(),
+                filled_amount: (notice.amount - notice.remaining_after).to_string(),
+                status: status.to_string(),
+                updated_at: notice.recovered_at.timestamp_millis(),
+                event: Some(event.to_string()),
+            }
+        })
+        .collect();
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_nonce_is_single_use() {
+        let nonce = new_auth_nonce();
+        let mut current = Some(nonce.clone());
+        assert!(!take_auth_nonce(&mut Some(nonce.clone()), Some("other")));
+        assert!(take_auth_nonce(&mut current, Some(&nonce)));
+        // Consumed: replaying the same nonce fails
+        assert!(!take_auth_nonce(&mut current, Some(&nonce)));
+        assert_ne!(new_auth_nonce(), nonce);
+    }
+
+    #[test]
+    fn test_time_message_echoes_client_time() {
+        let request: ClientMessage = serde_json::from_str(r#"{"type":"time","client_time":1700000000000}"#).unwrap();
+        assert!(matches!(request, ClientMessage::Time { client_time: Some(1_700_000_000_000) }));
+        assert!(matches!(serde_json::from_str(r#"{"type":"time"}"#).unwrap(), ClientMessage::Time { client_time: None }));
+
+        let reply = ServerMessage::Time { server_time: 1_700_000_000_005, sequence: 42, client_time: None };
+        assert_eq!(
+            serde_json::to_value(&reply).unwrap(),
+            serde_json::json!({"type": "time", "server_time": 1_700_000_000_005_i64, "sequence": 42})
+        );
+    }
+}
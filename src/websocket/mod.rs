@@ -1,6 +1,11 @@
 pub mod routes;
 pub mod handler;
 pub mod channels;
+pub mod conflation;
+pub mod delta;
+pub mod order_events;
+pub mod router;
+pub mod sse;
 // pub mod binance_proxy; // Not needed for prediction markets
 
 // pub use routes::*;
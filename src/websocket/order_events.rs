@@ -0,0 +1,116 @@
+//! Order Update Fan-out
+//!
+//! The matching engine publishes an [`OrderEvent`] for every order state
+//! change, including resting orders filled by someone else's taker order.
+//! This bridge turns them into [`OrderUpdateEvent`]s for the WebSocket
+//! `orders` channel, so owners see every change without handlers having to
+//! remember to send one.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use tokio::sync::broadcast;
+
+use crate::models::order::{OrderResponse, OrderSide, OrderType};
+use crate::models::Address;
+use crate::services::event_bus::{BusEvent, EventBus};
+use crate::services::matching::{self, MatchingEngine, OrderEvent, OrderbookSnapshot};
+use crate::OrderUpdateEvent;
+
+/// Publish engine order events on `bus` until the engine shuts down
+pub fn start(engine: &MatchingEngine, bus: Arc<EventBus>) {
+    let mut receiver = engine.subscribe_orders();
+    tokio::spawn(async move {
+        tracing::info!("Order update forwarder started");
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if let Some(update) = to_order_update(&event) {
+                        bus.publish(BusEvent::OrderUpdate(update)).await;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("Order update forwarder lagged by {} events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// `None` for events on books that are not prediction market keys or whose
+/// owner is not a valid address
+fn to_order_update(event: &OrderEvent) -> Option<OrderUpdateEvent> {
+    let (market_id, outcome_id, share_type) = OrderbookSnapshot::parse_market_key(&event.symbol)?;
+    let user_address: Address = event.user_address.parse().ok()?;
+    let order = OrderResponse {
+        order_id: event.order_id,
+        market_id,
+        outcome_id,
+        share_type,
+        side: match event.side {
+            matching::Side::Buy => OrderSide::Buy,
+            matching::Side::Sell => OrderSide::Sell,
+        },
+        order_type: match event.order_type {
+            matching::OrderType::Limit => OrderType::Limit,
+            matching::OrderType::Market => OrderType::Market,
+        },
+        time_in_force: None,
+        post_only: None,
+        price: event.price.unwrap_or(Decimal::ZERO),
+        amount: event.original_amount,
+        filled_amount: event.filled_amount,
+        remaining_amount: event.remaining_amount,
+        status: event.status.into(),
+        reject_reason: event.reject_reason.map(|reason| reason.code().to_string()),
+        source: None,
+        created_at: DateTime::<Utc>::from_timestamp_millis(event.created_at).unwrap_or_else(Utc::now),
+        expires_at: None,
+        fills: None,
+    };
+
+    Some(OrderUpdateEvent {
+        user_address: user_address.as_str().to_string(),
+        order,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::OrderStatus;
+    use crate::test_support::{MAKER, TAKER};
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_maker_fill_reaches_owner() {
+        let engine = MatchingEngine::new();
+        let bus = Arc::new(EventBus::local());
+        let mut receiver = bus.subscribe_order_updates();
+        start(&engine, bus.clone());
+
+        let symbol = format!("{}:{}:yes", Uuid::new_v4(), Uuid::new_v4());
+        let maker_id = Uuid::new_v4();
+        engine
+            .submit_order(maker_id, &symbol, MAKER, matching::Side::Sell, matching::OrderType::Limit, dec!(10), Some(dec!(0.5)), 1, matching::TimeInForce::GTC)
+            .unwrap();
+        engine
+            .submit_order(Uuid::new_v4(), &symbol, TAKER, matching::Side::Buy, matching::OrderType::Market, dec!(4), None, 1, matching::TimeInForce::GTC)
+            .unwrap();
+
+        let opened = receiver.recv().await.unwrap();
+        assert_eq!((opened.order.order_id, opened.order.status), (maker_id, OrderStatus::Open));
+
+        let fill = receiver.recv().await.unwrap();
+        assert_eq!(fill.user_address, MAKER);
+        assert_eq!(fill.order.order_id, maker_id);
+        assert_eq!(fill.order.status, OrderStatus::PartiallyFilled);
+        assert_eq!((fill.order.filled_amount, fill.order.remaining_amount), (dec!(4), dec!(6)));
+
+        let taker = receiver.recv().await.unwrap();
+        assert_eq!((taker.user_address.as_str(), taker.order.status), (TAKER, OrderStatus::Filled));
+    }
+}
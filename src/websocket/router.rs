@@ -0,0 +1,324 @@
+//! Subscription Router
+//!
+//! Maps matching-engine events to the server messages a client should receive
+//! for its set of channel subscriptions. Shared by the WebSocket handler and
+//! the SSE stream so both transports deliver identical payloads.
+//!
+//! Channels:
+//! - `trades:{market_id}`, `trades:*`, legacy `trades:{symbol}`
+//! - `orderbook:{market_id}:{outcome_id}:{share_type}`, `orderbook:{market_id}`,
+//!   `orderbook:*`, legacy `orderbook:{symbol}`
+//! - `market:{market_id}` (trades + orderbook of every outcome)
+//! - `orderbook-delta:{market_id}:{outcome_id}:{share_type}`,
+//!   `orderbook-delta:{market_id}`, `orderbook-delta:*` (sequenced changed
+//!   levels, see [`super::delta`])
+//!
+//! Entitlements are checked when a channel is subscribed: the delta feed is
+//! reserved for the [`MarketDataTier::Delta`] tier, and market data
+//! subscribed on the delayed tier is conflated (see
+//! [`super::conflation::DelayedFeed`]).
+
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use crate::services::market_data_tier::MarketDataTier;
+use crate::services::matching::{OrderbookDelta, OrderbookUpdate, SequencedDepth, TradeEvent};
+
+use super::delta::{delta_levels, snapshot_levels, BookDeltas, DeltaStep};
+use super::handler::{DeltaLevel, OrderbookLevel, ServerMessage};
+
+/// How a subscription may be served
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// As events happen
+    Live,
+    /// Conflated, through the connection's delayed feed
+    Delayed,
+}
+
+/// Whether `channel` carries public market data
+pub fn is_market_data(channel: &str) -> bool {
+    ["trades:", "orderbook:", "orderbook-delta:", "market:"]
+        .iter()
+        .any(|prefix| channel.starts_with(prefix))
+}
+
+/// Subscribe-time entitlement check of `channel` for a connection on
+/// `tier`; the error is the reason the subscription is refused
+pub fn check_entitlement(tier: MarketDataTier, channel: &str) -> Result<Delivery, &'static str> {
+    if channel.starts_with("orderbook-delta:") && tier < MarketDataTier::Delta {
+        return Err("The orderbook delta feed is reserved for market makers");
+    }
+    if tier == MarketDataTier::Delayed && is_market_data(channel) {
+        return Ok(Delivery::Delayed);
+    }
+    Ok(Delivery::Live)
+}
+
+/// Messages for a trade event
+pub fn route_trade(trade_event: &TradeEvent, subscriptions: &HashSet<String>) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+
+    // Generate unique trade ID
+    let trade_id = format!(
+        "{}-{}",
+        trade_event.timestamp,
+        Uuid::new_v4().to_string().split('-').next().unwrap_or("0")
+    );
+
+    let market_id = trade_event.market_id.to_string();
+    let market_trade_channel = format!("trades:{}", market_id);
+    let market_channel = format!("market:{}", market_id);
+
+    let subscribed_to_market = subscriptions.contains(&market_trade_channel)
+        || subscriptions.contains(&market_channel)
+        || subscriptions.contains("trades:*");
+
+    if subscribed_to_market {
+        messages.push(ServerMessage::MarketTrade {
+            id: trade_id.clone(),
+            market_id,
+            outcome_id: trade_event.outcome_id.to_string(),
+            share_type: trade_event.share_type.to_string(),
+            match_type: trade_event.match_type.to_string().to_lowercase(),
+            price: trade_event.price.to_string(),
+            amount: trade_event.amount.to_string(),
+            side: trade_event.side.clone(),
+            aggressor_side: trade_event.side.clone(),
+            sequence: trade_event.sequence,
+            market_sequence: trade_event.market_sequence,
+            is_block_trade: trade_event.is_block_trade,
+            is_rfq: trade_event.is_rfq,
+            timestamp: trade_event.timestamp,
+        });
+    }
+
+    // Legacy symbol-based channel (backwards compatibility)
+    let symbol_channel = format!("trades:{}", trade_event.symbol);
+    if subscriptions.contains(&symbol_channel) {
+        messages.push(ServerMessage::Trade {
+            id: trade_id,
+            symbol: trade_event.symbol.clone(),
+            price: trade_event.price.to_string(),
+            amount: trade_event.amount.to_string(),
+            side: trade_event.side.clone(),
+            market_sequence: trade_event.market_sequence,
+            timestamp: trade_event.timestamp,
+        });
+    }
+
+    messages
+}
+
+/// Messages for an orderbook update
+pub fn route_orderbook(orderbook_update: &OrderbookUpdate, subscriptions: &HashSet<String>) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+
+    // Convert to frontend-compatible format
+    let bids = to_levels(&orderbook_update.bids);
+    let asks = to_levels(&orderbook_update.asks);
+
+    // Symbol format for prediction markets: {market_id}:{outcome_id}:{share_type}
+    let symbol = &orderbook_update.symbol;
+
+    let parts: Vec<&str> = symbol.split(':').collect();
+    if let [market_id, outcome_id, share_type] = parts.as_slice() {
+        let specific_channel = format!("orderbook:{}", symbol);
+        let market_ob_channel = format!("orderbook:{}", market_id);
+        let market_channel = format!("market:{}", market_id);
+
+        let subscribed = subscriptions.contains(&specific_channel)
+            || subscriptions.contains(&market_ob_channel)
+            || subscriptions.contains(&market_channel)
+            || subscriptions.contains("orderbook:*");
+
+        if subscribed {
+            messages.push(ServerMessage::MarketOrderbook {
+                market_id: market_id.to_string(),
+                outcome_id: outcome_id.to_string(),
+                share_type: share_type.to_string(),
+                bids: bids.clone(),
+                asks: asks.clone(),
+                sequence: orderbook_update.sequence,
+                timestamp: orderbook_update.timestamp,
+            });
+        }
+    }
+
+    // Legacy symbol-based channel (backwards compatibility)
+    let orderbook_channel = format!("orderbook:{}", symbol);
+    if subscriptions.contains(&orderbook_channel) {
+        messages.push(ServerMessage::Orderbook {
+            symbol: symbol.clone(),
+            bids,
+            asks,
+            sequence: orderbook_update.sequence,
+            timestamp: orderbook_update.timestamp,
+        });
+    }
+
+    messages
+}
+
+/// An engine delta for `orderbook-delta:` subscribers. Books the connection
+/// has no snapshot of, or missed deltas of, get a snapshot from `depth`
+/// instead; deltas the snapshot already covers are dropped.
+pub fn route_orderbook_delta(
+    delta: &OrderbookDelta,
+    subscriptions: &HashSet<String>,
+    deltas: &mut BookDeltas,
+    depth: impl FnOnce(&str) -> Option<SequencedDepth>,
+) -> Option<ServerMessage> {
+    if !delta_subscribed(&delta.symbol, subscriptions) {
+        return None;
+    }
+    match deltas.step(delta) {
+        DeltaStep::Send => {
+            let (bids, asks) = delta_levels(delta);
+            orderbook_delta_message(&delta.symbol, delta.sequence, bids, asks, false, delta.timestamp)
+        }
+        DeltaStep::Skip => None,
+        DeltaStep::Snapshot => {
+            let depth = depth(&delta.symbol)?;
+            deltas.snapshot(&delta.symbol, depth.sequence);
+            let (bids, asks) = snapshot_levels(&depth);
+            orderbook_delta_message(&delta.symbol, depth.sequence, bids, asks, true, delta.timestamp)
+        }
+    }
+}
+
+/// Snapshot of `depth` opening the delta feed of `symbol`
+pub fn orderbook_delta_snapshot(symbol: &str, depth: &SequencedDepth, deltas: &mut BookDeltas) -> Option<ServerMessage> {
+    deltas.snapshot(symbol, depth.sequence);
+    let (bids, asks) = snapshot_levels(depth);
+    orderbook_delta_message(symbol, depth.sequence, bids, asks, true, chrono::Utc::now().timestamp_millis())
+}
+
+fn delta_subscribed(symbol: &str, subscriptions: &HashSet<String>) -> bool {
+    let market_id = symbol.split(':').next().unwrap_or_default();
+    subscriptions.contains(&format!("orderbook-delta:{}", symbol))
+        || subscriptions.contains(&format!("orderbook-delta:{}", market_id))
+        || subscriptions.contains("orderbook-delta:*")
+}
+
+fn orderbook_delta_message(
+    symbol: &str,
+    sequence: u64,
+    bids: Vec<DeltaLevel>,
+    asks: Vec<DeltaLevel>,
+    snapshot: bool,
+    timestamp: i64,
+) -> Option<ServerMessage> {
+    let parts: Vec<&str> = symbol.split(':').collect();
+    let [market_id, outcome_id, share_type] = parts.as_slice() else {
+        return None;
+    };
+    Some(ServerMessage::MarketOrderbookDelta {
+        market_id: market_id.to_string(),
+        outcome_id: outcome_id.to_string(),
+        share_type: share_type.to_string(),
+        sequence,
+        bids,
+        asks,
+        snapshot,
+        timestamp,
+    })
+}
+
+/// Convert `[price, size]` pairs to orderbook levels
+pub fn to_levels(levels: &[[String; 2]]) -> Vec<OrderbookLevel> {
+    levels
+        .iter()
+        .map(|[price, size]| OrderbookLevel {
+            price: price.clone(),
+            size: size.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::matching::{LevelAction, LevelChange, Side};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_orderbook_routes_market_channel() {
+        let market_id = Uuid::new_v4();
+        let outcome_id = Uuid::new_v4();
+        let update = OrderbookUpdate {
+            symbol: format!("{}:{}:yes", market_id, outcome_id),
+            bids: vec![["0.4".to_string(), "10".to_string()]].into(),
+            asks: Vec::new().into(),
+            sequence: 7,
+            timestamp: 1,
+        };
+
+        let subs: HashSet<String> = [format!("market:{}", market_id)].into_iter().collect();
+        let messages = route_orderbook(&update, &subs);
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(messages[0], ServerMessage::MarketOrderbook { sequence: 7, .. }));
+
+        let other: HashSet<String> = [format!("market:{}", Uuid::new_v4())].into_iter().collect();
+        assert!(route_orderbook(&update, &other).is_empty());
+    }
+
+    #[test]
+    fn test_entitlements_by_tier() {
+        let market_id = Uuid::new_v4();
+        let trades = format!("trades:{}", market_id);
+        let delta = format!("orderbook-delta:{}", market_id);
+
+        assert_eq!(check_entitlement(MarketDataTier::Delayed, &trades).unwrap(), Delivery::Delayed);
+        assert_eq!(check_entitlement(MarketDataTier::Delayed, "orders").unwrap(), Delivery::Live);
+        assert_eq!(check_entitlement(MarketDataTier::RealTime, &trades).unwrap(), Delivery::Live);
+        assert_eq!(check_entitlement(MarketDataTier::Delta, &delta).unwrap(), Delivery::Live);
+        for tier in [MarketDataTier::Delayed, MarketDataTier::RealTime] {
+            assert!(check_entitlement(tier, &delta).is_err());
+        }
+    }
+
+    #[test]
+    fn test_orderbook_delta_routes_delta_channel() {
+        let market_id = Uuid::new_v4();
+        let symbol = format!("{}:{}:yes", market_id, Uuid::new_v4());
+        let delta = |sequence| OrderbookDelta {
+            symbol: symbol.clone(),
+            sequence,
+            changes: vec![LevelChange {
+                side: Side::Buy,
+                price: dec!(0.4),
+                size: dec!(4),
+                action: LevelAction::Change,
+            }]
+            .into(),
+            timestamp: 1,
+        };
+        let depth = |_: &str| {
+            Some(SequencedDepth {
+                sequence: 7,
+                bids: vec![(dec!(0.4), dec!(10))],
+                asks: Vec::new(),
+            })
+        };
+        let mut deltas = BookDeltas::default();
+
+        let plain: HashSet<String> = [format!("orderbook:{}", market_id)].into_iter().collect();
+        assert!(route_orderbook_delta(&delta(7), &plain, &mut deltas, depth).is_none());
+
+        // First a snapshot, then only deltas past it
+        let subs: HashSet<String> = [format!("orderbook-delta:{}", market_id)].into_iter().collect();
+        assert!(matches!(
+            route_orderbook_delta(&delta(7), &subs, &mut deltas, depth),
+            Some(ServerMessage::MarketOrderbookDelta { snapshot: true, sequence: 7, .. })
+        ));
+        assert!(route_orderbook_delta(&delta(7), &subs, &mut deltas, depth).is_none());
+        match route_orderbook_delta(&delta(8), &subs, &mut deltas, depth) {
+            Some(ServerMessage::MarketOrderbookDelta { bids, snapshot: false, sequence: 8, .. }) => {
+                assert_eq!((bids.len(), bids[0].size.as_str(), bids[0].action), (1, "4", LevelAction::Change));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+}
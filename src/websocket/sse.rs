@@ -0,0 +1,459 @@
+//! Server-Sent Events Market Stream
+//!
+//! Fallback for embedding contexts that can't open WebSockets.
+//! `GET /sse/markets/:market_id` streams the same payloads a WebSocket client
+//! subscribed to `market:{market_id}` receives (routed through
+//! [`super::router`]), plus a periodic ticker:
+//!
+//! - `event: orderbook` — top levels per outcome (`market_orderbook` message)
+//! - `event: trade` — executed trades (`market_trade` message)
+//! - `event: ticker` — status, yes/no price and 24h volume (`market_update` message)
+//!
+//! Orderbook and trade events carry a global, monotonically increasing `id`.
+//! The [`SseHub`] keeps a short per-market replay buffer, so a reconnecting
+//! client sending `Last-Event-ID` (or `?last_event_id=` for polyfills that
+//! can't set headers) receives exactly what it missed. If the buffer no longer
+//! covers the gap, the stream starts with a fresh orderbook snapshot instead.
+
+use std::collections::{HashSet, VecDeque};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use dashmap::DashMap;
+use futures::Stream;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::AppError;
+use crate::api::handlers::replay::ReplayParams;
+use crate::api::validation::ValidQuery;
+use crate::services::market_replay::{self, replay_delay};
+use crate::services::event_bus::EventBus;
+use crate::services::matching::{MatchingEngine, OrderbookUpdate};
+use crate::AppState;
+
+use super::handler::ServerMessage;
+use super::router::{route_orderbook, route_trade, to_levels};
+
+/// Orderbook levels per side pushed over SSE
+const SSE_ORDERBOOK_DEPTH: usize = 10;
+/// Events retained per market for Last-Event-ID resume
+const REPLAY_BUFFER_SIZE: usize = 512;
+/// Ticker push interval
+const TICKER_INTERVAL: Duration = Duration::from_secs(5);
+/// Longest pause between replayed events, whatever the speed
+const MAX_REPLAY_WAIT: Duration = Duration::from_secs(5);
+/// Fastest replay speed accepted
+const MAX_REPLAY_SPEED: f64 = 10_000.0;
+
+pub fn create_router(_state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/markets/:market_id", get(market_stream))
+        .route("/markets/:market_id/replay", get(replay_stream))
+}
+
+// ============================================================================
+// Hub: sequencing and replay buffer
+// ============================================================================
+
+/// A sequenced, serialized market event
+#[derive(Debug, Clone)]
+pub struct SseEvent {
+    pub id: u64,
+    pub market_id: Uuid,
+    pub event: &'static str,
+    pub data: String,
+}
+
+#[derive(Default)]
+struct ReplayBuffer {
+    events: VecDeque<SseEvent>,
+    /// Highest event id dropped from this buffer (0 = nothing dropped)
+    evicted_through: u64,
+}
+
+/// Assigns event ids to matching-engine output and keeps a replay buffer
+/// per market. One hub serves every SSE connection.
+pub struct SseHub {
+    seq: AtomicU64,
+    buffers: DashMap<Uuid, ReplayBuffer>,
+    sender: broadcast::Sender<SseEvent>,
+}
+
+impl Default for SseHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SseHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(4096);
+        Self {
+            seq: AtomicU64::new(0),
+            buffers: DashMap::new(),
+            sender,
+        }
+    }
+
+    /// Id of the most recently published event
+    pub fn current_id(&self) -> u64 {
+        self.seq.load(Ordering::SeqCst)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SseEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Sequence, buffer and broadcast an event
+    pub fn publish(&self, market_id: Uuid, event: &'static str, message: &ServerMessage) {
+        let Ok(data) = serde_json::to_string(message) else {
+            return;
+        };
+
+        // Assign the id while holding the market's buffer entry so buffered
+        // events stay in id order
+        let mut buffer = self.buffers.entry(market_id).or_default();
+        let sse_event = SseEvent {
+            id: self.seq.fetch_add(1, Ordering::SeqCst) + 1,
+            market_id,
+            event,
+            data,
+        };
+        buffer.events.push_back(sse_event.clone());
+        while buffer.events.len() > REPLAY_BUFFER_SIZE {
+            if let Some(dropped) = buffer.events.pop_front() {
+                buffer.evicted_through = dropped.id;
+            }
+        }
+        drop(buffer);
+
+        let _ = self.sender.send(sse_event);
+    }
+
+    /// Buffered events for `market_id` after `last_id`, or `None` if some of
+    /// them have already been evicted
+    pub fn replay_after(&self, market_id: Uuid, last_id: u64) -> Option<Vec<SseEvent>> {
+        let Some(buffer) = self.buffers.get(&market_id) else {
+            return Some(Vec::new());
+        };
+        if last_id < buffer.evicted_through {
+            return None;
+        }
+        Some(buffer.events.iter().filter(|e| e.id > last_id).cloned().collect())
+    }
+
+    /// Feed the hub from the bus's trades and the matching engine's
+    /// orderbook broadcasts
+    pub fn start(self: Arc<Self>, engine: &MatchingEngine, bus: &EventBus) {
+        let mut trade_receiver = bus.subscribe_trades();
+        let mut orderbook_receiver = engine.subscribe_orderbook();
+
+        tokio::spawn(async move {
+            tracing::info!("SSE hub started");
+            loop {
+                tokio::select! {
+                    trade = trade_receiver.recv() => match trade {
+                        Ok(trade_event) => {
+                            let subs = market_subscription(trade_event.market_id);
+                            for msg in route_trade(&trade_event, &subs) {
+                                self.publish(trade_event.market_id, "trade", &msg);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            tracing::warn!("SSE hub trade receiver lagged by {} messages", n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    orderbook = orderbook_receiver.recv() => match orderbook {
+                        Ok(update) => {
+                            let Some(market_id) = update.symbol.split(':').next().and_then(|m| m.parse::<Uuid>().ok()) else {
+                                continue;
+                            };
+                            let top = OrderbookUpdate {
+                                symbol: update.symbol,
+                                bids: update.bids[..update.bids.len().min(SSE_ORDERBOOK_DEPTH)].into(),
+                                asks: update.asks[..update.asks.len().min(SSE_ORDERBOOK_DEPTH)].into(),
+                                sequence: update.sequence,
+                                timestamp: update.timestamp,
+                            };
+                            for msg in route_orderbook(&top, &market_subscription(market_id)) {
+                                self.publish(market_id, "orderbook", &msg);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            tracing::warn!("SSE hub orderbook receiver lagged by {} messages", n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+            tracing::warn!("SSE hub stopped");
+        });
+    }
+}
+
+fn market_subscription(market_id: Uuid) -> HashSet<String> {
+    [format!("market:{}", market_id)].into_iter().collect()
+}
+
+// ============================================================================
+// Handler
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct StreamQuery {
+    /// Resume point for clients that can't send the Last-Event-ID header
+    pub last_event_id: Option<u64>,
+}
+
+/// Per-connection stream state
+struct StreamState {
+    market_id: Uuid,
+    pending: VecDeque<Event>,
+    last_sent: u64,
+    receiver: broadcast::Receiver<SseEvent>,
+    ticker: tokio::time::Interval,
+    app: Arc<AppState>,
+}
+
+/// Stream orderbook, trades and ticker for a market
+/// GET /sse/markets/:market_id
+pub async fn market_stream(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(query): ValidQuery<StreamQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let outcomes: Vec<(Uuid, String)> =
+        sqlx::query_as("SELECT id, share_type::text FROM outcomes WHERE market_id = $1 ORDER BY name")
+            .bind(market_id)
+            .fetch_all(&state.db.pool)
+            .await?;
+
+    if outcomes.is_empty() {
+        return Err(AppError::not_found("MARKET_NOT_FOUND", "Market not found"));
+    }
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .or(query.last_event_id);
+
+    // Subscribe before reading the buffer so nothing falls between the two;
+    // duplicates are filtered by `last_sent`.
+    let receiver = state.sse_hub.subscribe();
+    let mut pending = VecDeque::new();
+
+    let last_sent = match last_event_id.and_then(|id| state.sse_hub.replay_after(market_id, id).map(|events| (id, events))) {
+        Some((id, events)) => {
+            let mut last_sent = id;
+            for e in events {
+                last_sent = e.id;
+                pending.push_back(to_sse(&e));
+            }
+            last_sent
+        }
+        None => {
+            // Fresh connection or unrecoverable gap: start from a snapshot
+            let snapshot_id = state.sse_hub.current_id();
+            for (outcome_id, share_type) in &outcomes {
+                let key = format!("{}:{}:{}", market_id, outcome_id, share_type);
+                let sequence = state.matching_engine.market_sequence(&key).orderbook;
+                let Ok(book) = state.matching_engine.get_orderbook(&key, SSE_ORDERBOOK_DEPTH) else {
+                    continue;
+                };
+                let msg = ServerMessage::MarketOrderbook {
+                    market_id: market_id.to_string(),
+                    outcome_id: outcome_id.to_string(),
+                    share_type: share_type.clone(),
+                    bids: to_levels(&book.bids),
+                    asks: to_levels(&book.asks),
+                    sequence,
+                    timestamp: book.timestamp,
+                };
+                if let Ok(data) = serde_json::to_string(&msg) {
+                    pending.push_back(Event::default().id(snapshot_id.to_string()).event("orderbook").data(data));
+                }
+            }
+            snapshot_id
+        }
+    };
+
+    let stream_state = StreamState {
+        market_id,
+        pending,
+        last_sent,
+        receiver,
+        // First tick fires immediately, so the ticker follows the snapshot
+        ticker: tokio::time::interval(TICKER_INTERVAL),
+        app: state,
+    };
+
+    let stream = futures::stream::unfold(stream_state, |mut st| async move {
+        loop {
+            if let Some(event) = st.pending.pop_front() {
+                return Some((Ok(event), st));
+            }
+
+            tokio::select! {
+                received = st.receiver.recv() => match received {
+                    Ok(e) if e.market_id == st.market_id && e.id > st.last_sent => {
+                        st.last_sent = e.id;
+                        return Some((Ok(to_sse(&e)), st));
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        // Recover missed events from the replay buffer
+                        if let Some(events) = st.app.sse_hub.replay_after(st.market_id, st.last_sent) {
+                            for e in events {
+                                st.last_sent = e.id;
+                                st.pending.push_back(to_sse(&e));
+                            }
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                _ = st.ticker.tick() => {
+                    if let Some(msg) = fetch_ticker(&st.app, st.market_id).await {
+                        if let Ok(data) = serde_json::to_string(&msg) {
+                            return Some((Ok(Event::default().event("ticker").data(data)), st));
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Replay a market's historical events at `speed`x their original pace,
+/// ending with an `end` event carrying `next_from` when the window did not
+/// fit in one page
+/// GET /sse/markets/:market_id/replay?from&to&outcome_id&interval&limit&speed
+pub async fn replay_stream(
+    State(state): State<Arc<AppState>>,
+    Path(market_id): Path<Uuid>,
+    ValidQuery(params): ValidQuery<ReplayParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let speed = params.speed.unwrap_or(1.0);
+    if !(0.0..=MAX_REPLAY_SPEED).contains(&speed) {
+        return Err(AppError::bad_request(
+            "INVALID_SPEED",
+            format!("speed must be between 0 and {}", MAX_REPLAY_SPEED),
+        ));
+    }
+    let query = params.into_query(market_id)?;
+
+    let page = market_replay::load(&state.db.pool, &query).await.map_err(|e| {
+        tracing::error!("Failed to load market replay: {}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "REPLAY_FAILED", "Failed to load replay")
+    })?;
+
+    let end = serde_json::json!({ "next_from": page.next_from }).to_string();
+    let events = page.events.into_iter().map(Some).chain(std::iter::once(None));
+    let stream = futures::stream::unfold(
+        (events, query.from.timestamp_millis()),
+        move |(mut events, previous_ts)| {
+            let end = end.clone();
+            async move {
+                match events.next()? {
+                    Some(event) => {
+                        tokio::time::sleep(replay_delay(event.ts() - previous_ts, speed, MAX_REPLAY_WAIT)).await;
+                        let data = serde_json::to_string(&event).unwrap_or_default();
+                        let sse = Event::default().event(event.kind()).data(data);
+                        Some((Ok(sse), (events, event.ts())))
+                    }
+                    None => Some((Ok(Event::default().event("end").data(end)), (events, previous_ts))),
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn to_sse(e: &SseEvent) -> Event {
+    Event::default().id(e.id.to_string()).event(e.event).data(e.data.clone())
+}
+
+/// (status, volume_24h, yes probability, no probability)
+type TickerRow = (String, Decimal, Option<Decimal>, Option<Decimal>);
+
+async fn fetch_ticker(state: &AppState, market_id: Uuid) -> Option<ServerMessage> {
+    let row: TickerRow = sqlx::query_as(
+        r#"
+        SELECT m.status::text, m.volume_24h,
+               (SELECT probability FROM outcomes WHERE market_id = m.id AND share_type = 'yes' LIMIT 1),
+               (SELECT probability FROM outcomes WHERE market_id = m.id AND share_type = 'no' LIMIT 1)
+        FROM markets m
+        WHERE m.id = $1
+        "#,
+    )
+    .bind(market_id)
+    .fetch_optional(&state.db.pool)
+    .await
+    .map_err(|e| tracing::warn!("Failed to fetch SSE ticker for {}: {}", market_id, e))
+    .ok()??;
+
+    let (status, volume_24h, yes, no) = row;
+    let yes_price = yes.unwrap_or(Decimal::new(5, 1));
+    let no_price = no.unwrap_or(Decimal::ONE - yes_price);
+
+    Some(ServerMessage::MarketUpdate {
+        market_id: market_id.to_string(),
+        status,
+        yes_price: yes_price.to_string(),
+        no_price: no_price.to_string(),
+        volume_24h: volume_24h.to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pong() -> ServerMessage {
+        ServerMessage::Pong
+    }
+
+    #[test]
+    fn test_replay_after_returns_only_newer_events() {
+        let hub = SseHub::new();
+        let market = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        hub.publish(market, "trade", &pong());
+        hub.publish(other, "trade", &pong());
+        hub.publish(market, "trade", &pong());
+
+        let events = hub.replay_after(market, 1).unwrap();
+        assert_eq!(events.iter().map(|e| e.id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(hub.current_id(), 3);
+    }
+
+    #[test]
+    fn test_replay_after_detects_eviction() {
+        let hub = SseHub::new();
+        let market = Uuid::new_v4();
+        for _ in 0..REPLAY_BUFFER_SIZE + 5 {
+            hub.publish(market, "trade", &pong());
+        }
+        assert!(hub.replay_after(market, 1).is_none());
+        assert_eq!(hub.replay_after(market, 5).unwrap().len(), REPLAY_BUFFER_SIZE);
+    }
+}